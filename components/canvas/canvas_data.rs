@@ -2,22 +2,41 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use app_units::Au;
 use azure::azure::AzFloat;
 use azure::azure_hl::{AntialiasMode, CapStyle, CompositionOp, JoinStyle};
 use azure::azure_hl::{BackendType, DrawOptions, DrawTarget, Pattern, StrokeOptions, SurfaceFormat};
 use azure::azure_hl::{Color, ColorPattern, DrawSurfaceOptions, Filter, PathBuilder};
 use azure::azure_hl::{ExtendMode, GradientStop, LinearGradientPattern, RadialGradientPattern};
-use azure::azure_hl::SurfacePattern;
+use azure::azure_hl::{Path, SurfacePattern};
 use canvas_traits::canvas::*;
 use cssparser::RGBA;
 use euclid::{Transform2D, Point2D, Vector2D, Rect, Size2D};
+use gfx::font::{Font, FontDescriptor, FontFamilyDescriptor, FontFamilyName, FontRef};
+use gfx::font::{FontSearchScope, ShapingFlags, ShapingOptions};
+use gfx::font_cache_thread::FontCacheThread;
+use gfx::font_context::FontContext;
+use gfx::font_template::FontTemplateDescriptor;
+use gfx::text::glyph::ByteIndex;
 use ipc_channel::ipc::IpcSender;
 use num_traits::ToPrimitive;
+use ordered_float::NotNan;
+use range::Range;
 use serde_bytes::ByteBuf;
+use servo_atoms::Atom;
+use std::iter;
 use std::mem;
 use std::sync::Arc;
+use style::computed_values::font_variant_caps::T as FontVariantCaps;
+use style::values::computed::font::{FontStretch, FontWeight};
+use style::values::generics::font::FontStyle;
+use unicode_script::Script;
 use webrender_api;
 
+/// The position of the hanging baseline, as a fraction of the ascent, for fonts which do
+/// not provide one.
+const HANGING_BASELINE_RATIO: f64 = 0.8;
+
 pub struct CanvasData<'a> {
     drawtarget: DrawTarget,
     /// TODO(pcwalton): Support multiple paths.
@@ -116,6 +135,53 @@ impl<'a> CanvasData<'a> {
         error!("Unimplemented canvas2d.fillText. Values received: {}, {}, {}, {:?}.", text, x, y, max_width);
     }
 
+    pub fn stroke_text(&self, text: String, x: f64, y: f64, max_width: Option<f64>) {
+        error!("Unimplemented canvas2d.strokeText. Values received: {}, {}, {}, {:?}.", text, x, y, max_width);
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-measuretext
+    pub fn measure_text(
+        &self,
+        font_context: &mut FontContext<FontCacheThread>,
+        text: String,
+        chan: IpcSender<TextMetrics>
+    ) {
+        // https://html.spec.whatwg.org/multipage/#text-preparation-algorithm
+        let text = text.replace(|c: char| c.is_ascii_whitespace(), " ");
+        let metrics = match self.font(font_context) {
+            Some(font) => text_metrics(&mut font.borrow_mut(), &text, self.state.text_align, self.state.text_baseline),
+            None => TextMetrics::default(),
+        };
+        chan.send(metrics).unwrap();
+    }
+
+    /// The first available font of the font family list of the current font, or the default
+    /// font if none of them is.
+    fn font(&self, font_context: &mut FontContext<FontCacheThread>) -> Option<FontRef> {
+        let font = &self.state.font;
+        let descriptor = FontDescriptor {
+            template_descriptor: FontTemplateDescriptor::new(
+                FontWeight(font.weight),
+                FontStretch::hundred(),
+                if font.italic { FontStyle::Italic } else { FontStyle::Normal },
+            ),
+            variant: if font.small_caps { FontVariantCaps::SmallCaps } else { FontVariantCaps::Normal },
+            pt_size: Au::from_f64_px(font.size),
+        };
+        let families = font.families.iter().map(|family| {
+            let name = match *family {
+                CanvasFontFamily::Specific(ref name) => FontFamilyName::Specific(Atom::from(&**name)),
+                CanvasFontFamily::Generic(ref name) => FontFamilyName::Generic(Atom::from(&**name)),
+            };
+            FontFamilyDescriptor::new(name, FontSearchScope::Any)
+        });
+        let default_family = FontFamilyDescriptor::new(FontFamilyName::Generic(Atom::from("serif")),
+                                                       FontSearchScope::Local);
+        families.chain(iter::once(default_family))
+            .filter_map(|family| font_context.font(&descriptor, &family))
+            .next()
+    }
+
     pub fn fill_rect(&self, rect: &Rect<f32>) {
         if is_zero_size_gradient(&self.state.fill_style) {
             return; // Paint nothing if gradient size is zero.
@@ -159,7 +225,7 @@ impl<'a> CanvasData<'a> {
         if self.need_to_draw_shadow() {
             self.draw_with_shadow(&rect, |new_draw_target: &DrawTarget| {
                 new_draw_target.stroke_rect(rect, self.state.stroke_style.to_pattern_ref(),
-                                            &self.state.stroke_opts, &self.state.draw_options);
+                                            &self.stroke_options(), &self.state.draw_options);
             });
         } else if rect.size.width == 0. || rect.size.height == 0. {
             let cap = match self.state.stroke_opts.line_join {
@@ -172,13 +238,13 @@ impl<'a> CanvasData<'a> {
                                    self.state.stroke_opts.line_join,
                                    cap,
                                    self.state.stroke_opts.miter_limit,
-                                   self.state.stroke_opts.mDashPattern);
+                                   &self.state.dash_pattern);
             self.drawtarget.stroke_line(rect.origin, rect.bottom_right(),
                                         self.state.stroke_style.to_pattern_ref(),
                                         &stroke_opts, &self.state.draw_options);
         } else {
             self.drawtarget.stroke_rect(rect, self.state.stroke_style.to_pattern_ref(),
-                                        &self.state.stroke_opts, &self.state.draw_options);
+                                        &self.stroke_options(), &self.state.draw_options);
         }
     }
 
@@ -207,7 +273,7 @@ impl<'a> CanvasData<'a> {
 
         self.drawtarget.stroke(&self.path_builder.finish(),
                                self.state.stroke_style.to_pattern_ref(),
-                               &self.state.stroke_opts,
+                               &self.stroke_options(),
                                &self.state.draw_options);
     }
 
@@ -215,6 +281,73 @@ impl<'a> CanvasData<'a> {
         self.drawtarget.push_clip(&self.path_builder.finish());
     }
 
+    /// Fills the path of a `Path2D`.
+    ///
+    /// Azure paths are always filled with the nonzero winding rule, so the `evenodd` fill rule
+    /// isn't supported, and `_fill_rule` is ignored here as in `clip_path` and the hit tests.
+    pub fn fill_path(&self, path: &[PathSegment], _fill_rule: FillRule) {
+        if is_zero_size_gradient(&self.state.fill_style) {
+            return; // Paint nothing if gradient size is zero.
+        }
+
+        self.drawtarget.fill(&self.path_from_segments(path),
+                             self.state.fill_style.to_pattern_ref(),
+                             &self.state.draw_options);
+    }
+
+    pub fn stroke_path(&self, path: &[PathSegment]) {
+        if is_zero_size_gradient(&self.state.stroke_style) {
+            return; // Paint nothing if gradient size is zero.
+        }
+
+        self.drawtarget.stroke(&self.path_from_segments(path),
+                               self.state.stroke_style.to_pattern_ref(),
+                               &self.stroke_options(),
+                               &self.state.draw_options);
+    }
+
+    /// Clips to the path of a `Path2D`, with the nonzero winding rule.
+    pub fn clip_path(&self, path: &[PathSegment], _fill_rule: FillRule) {
+        self.drawtarget.push_clip(&self.path_from_segments(path));
+    }
+
+    /// Replies whether a point is in the path of a `Path2D`, with the nonzero winding rule.
+    pub fn is_point_in_path_2d(
+        &self,
+        path: &[PathSegment],
+        x: f64,
+        y: f64,
+        _fill_rule: FillRule,
+        chan: IpcSender<bool>
+    ) {
+        let result = self.path_from_segments(path).contains_point(x, y, &self.state.transform);
+        chan.send(result).unwrap();
+    }
+
+    /// Builds the path recorded by a `Path2D` object.
+    fn path_from_segments(&self, segments: &[PathSegment]) -> Path {
+        let path_builder = self.drawtarget.create_path_builder();
+        for segment in segments {
+            match *segment {
+                PathSegment::ClosePath => path_builder.close(),
+                PathSegment::MoveTo(point) => path_builder.move_to(point),
+                PathSegment::LineTo(point) => path_builder.line_to(point),
+                PathSegment::QuadraticCurveTo(ref cp, ref endpoint) => {
+                    path_builder.quadratic_curve_to(cp, endpoint)
+                },
+                PathSegment::BezierCurveTo(ref cp1, ref cp2, ref endpoint) => {
+                    path_builder.bezier_curve_to(cp1, cp2, endpoint)
+                },
+                PathSegment::ArcTo(ref cp1, ref cp2, radius) => arc_to(&path_builder, cp1, cp2, radius),
+                PathSegment::Ellipse(center, radius_x, radius_y, rotation_angle, start_angle, end_angle, ccw) => {
+                    path_builder.ellipse(center, radius_x, radius_y, rotation_angle, start_angle, end_angle, ccw)
+                },
+            }
+        }
+        path_builder.finish()
+    }
+
+    /// Replies whether a point is in the current path, with the nonzero winding rule.
     pub fn is_point_in_path(
         &mut self,
         x: f64,
@@ -279,55 +412,7 @@ impl<'a> CanvasData<'a> {
         cp2: &Point2D<AzFloat>,
         radius: AzFloat
     ) {
-        let cp0 = self.path_builder.get_current_point();
-        let cp1 = *cp1;
-        let cp2 = *cp2;
-
-        if (cp0.x == cp1.x && cp0.y == cp1.y) || cp1 == cp2 || radius == 0.0 {
-            self.line_to(&cp1);
-            return;
-        }
-
-        // if all three control points lie on a single straight line,
-        // connect the first two by a straight line
-        let direction = (cp2.x - cp1.x) * (cp0.y - cp1.y) + (cp2.y - cp1.y) * (cp1.x - cp0.x);
-        if direction == 0.0 {
-            self.line_to(&cp1);
-            return;
-        }
-
-        // otherwise, draw the Arc
-        let a2 = (cp0.x - cp1.x).powi(2) + (cp0.y - cp1.y).powi(2);
-        let b2 = (cp1.x - cp2.x).powi(2) + (cp1.y - cp2.y).powi(2);
-        let d = {
-            let c2 = (cp0.x - cp2.x).powi(2) + (cp0.y - cp2.y).powi(2);
-            let cosx = (a2 + b2 - c2) / (2.0 * (a2 * b2).sqrt());
-            let sinx = (1.0 - cosx.powi(2)).sqrt();
-            radius / ((1.0 - cosx) / sinx)
-        };
-
-        // first tangent point
-        let anx = (cp1.x - cp0.x) / a2.sqrt();
-        let any = (cp1.y - cp0.y) / a2.sqrt();
-        let tp1 = Point2D::new(cp1.x - anx * d, cp1.y - any * d);
-
-        // second tangent point
-        let bnx = (cp1.x - cp2.x) / b2.sqrt();
-        let bny = (cp1.y - cp2.y) / b2.sqrt();
-        let tp2 = Point2D::new(cp1.x - bnx * d, cp1.y - bny * d);
-
-        // arc center and angles
-        let anticlockwise = direction < 0.0;
-        let cx = tp1.x + any * radius * if anticlockwise { 1.0 } else { -1.0 };
-        let cy = tp1.y - anx * radius * if anticlockwise { 1.0 } else { -1.0 };
-        let angle_start = (tp1.y - cy).atan2(tp1.x - cx);
-        let angle_end = (tp2.y - cy).atan2(tp2.x - cx);
-
-        self.line_to(&tp1);
-        if [cx, cy, angle_start, angle_end].iter().all(|x| x.is_finite()) {
-            self.arc(&Point2D::new(cx, cy), radius,
-                     angle_start, angle_end, anticlockwise);
-        }
+        arc_to(&self.path_builder, cp1, cp2, radius)
     }
 
     pub fn ellipse(
@@ -371,6 +456,37 @@ impl<'a> CanvasData<'a> {
         self.state.stroke_opts.miter_limit = limit;
     }
 
+    pub fn set_line_dash(&mut self, segments: &[f32]) {
+        self.state.line_dash = segments.to_vec();
+        self.state.dash_pattern = offset_dash_pattern(&self.state.line_dash, self.state.line_dash_offset);
+    }
+
+    pub fn set_line_dash_offset(&mut self, offset: f32) {
+        self.state.line_dash_offset = offset;
+        self.state.dash_pattern = offset_dash_pattern(&self.state.line_dash, self.state.line_dash_offset);
+    }
+
+    /// The stroke options of the current state, with its dash pattern.
+    fn stroke_options(&self) -> StrokeOptions {
+        StrokeOptions::new(self.state.stroke_opts.line_width,
+                           self.state.stroke_opts.line_join,
+                           self.state.stroke_opts.line_cap,
+                           self.state.stroke_opts.miter_limit,
+                           &self.state.dash_pattern)
+    }
+
+    pub fn set_font(&mut self, font: CanvasFont) {
+        self.state.font = font;
+    }
+
+    pub fn set_text_align(&mut self, align: TextAlign) {
+        self.state.text_align = align;
+    }
+
+    pub fn set_text_baseline(&mut self, baseline: TextBaseline) {
+        self.state.text_baseline = baseline;
+    }
+
     pub fn set_transform(&mut self, transform: &Transform2D<f32>) {
         self.state.transform = transform.clone();
         self.drawtarget.set_transform(transform)
@@ -659,12 +775,20 @@ struct CanvasPaintState<'a> {
     fill_style: Pattern,
     stroke_style: Pattern,
     stroke_opts: StrokeOptions<'a>,
+    /// The dash list set by `setLineDash`.
+    line_dash: Vec<AzFloat>,
+    line_dash_offset: AzFloat,
+    /// The dash list shifted by the dash offset, which Azure does not support.
+    dash_pattern: Vec<AzFloat>,
     /// The current 2D transform matrix.
     transform: Transform2D<f32>,
     shadow_offset_x: f64,
     shadow_offset_y: f64,
     shadow_blur: f64,
     shadow_color: Color,
+    font: CanvasFont,
+    text_align: TextAlign,
+    text_baseline: TextBaseline,
 }
 
 impl<'a> CanvasPaintState<'a> {
@@ -674,15 +798,167 @@ impl<'a> CanvasPaintState<'a> {
             fill_style: Pattern::Color(ColorPattern::new(Color::black())),
             stroke_style: Pattern::Color(ColorPattern::new(Color::black())),
             stroke_opts: StrokeOptions::new(1.0, JoinStyle::MiterOrBevel, CapStyle::Butt, 10.0, &[]),
+            line_dash: vec![],
+            line_dash_offset: 0.0,
+            dash_pattern: vec![],
             transform: Transform2D::identity(),
             shadow_offset_x: 0.0,
             shadow_offset_y: 0.0,
             shadow_blur: 0.0,
             shadow_color: Color::transparent(),
+            font: CanvasFont::default(),
+            text_align: TextAlign::default(),
+            text_baseline: TextBaseline::default(),
         }
     }
 }
 
+/// https://html.spec.whatwg.org/multipage/#dom-context-2d-arcto
+fn arc_to(
+    path_builder: &PathBuilder,
+    cp1: &Point2D<AzFloat>,
+    cp2: &Point2D<AzFloat>,
+    radius: AzFloat
+) {
+    let cp0 = path_builder.get_current_point();
+    let cp1 = *cp1;
+    let cp2 = *cp2;
+
+    if (cp0.x == cp1.x && cp0.y == cp1.y) || cp1 == cp2 || radius == 0.0 {
+        path_builder.line_to(cp1);
+        return;
+    }
+
+    // if all three control points lie on a single straight line,
+    // connect the first two by a straight line
+    let direction = (cp2.x - cp1.x) * (cp0.y - cp1.y) + (cp2.y - cp1.y) * (cp1.x - cp0.x);
+    if direction == 0.0 {
+        path_builder.line_to(cp1);
+        return;
+    }
+
+    // otherwise, draw the Arc
+    let a2 = (cp0.x - cp1.x).powi(2) + (cp0.y - cp1.y).powi(2);
+    let b2 = (cp1.x - cp2.x).powi(2) + (cp1.y - cp2.y).powi(2);
+    let d = {
+        let c2 = (cp0.x - cp2.x).powi(2) + (cp0.y - cp2.y).powi(2);
+        let cosx = (a2 + b2 - c2) / (2.0 * (a2 * b2).sqrt());
+        let sinx = (1.0 - cosx.powi(2)).sqrt();
+        radius / ((1.0 - cosx) / sinx)
+    };
+
+    // first tangent point
+    let anx = (cp1.x - cp0.x) / a2.sqrt();
+    let any = (cp1.y - cp0.y) / a2.sqrt();
+    let tp1 = Point2D::new(cp1.x - anx * d, cp1.y - any * d);
+
+    // second tangent point
+    let bnx = (cp1.x - cp2.x) / b2.sqrt();
+    let bny = (cp1.y - cp2.y) / b2.sqrt();
+    let tp2 = Point2D::new(cp1.x - bnx * d, cp1.y - bny * d);
+
+    // arc center and angles
+    let anticlockwise = direction < 0.0;
+    let cx = tp1.x + any * radius * if anticlockwise { 1.0 } else { -1.0 };
+    let cy = tp1.y - anx * radius * if anticlockwise { 1.0 } else { -1.0 };
+    let angle_start = (tp1.y - cy).atan2(tp1.x - cx);
+    let angle_end = (tp2.y - cy).atan2(tp2.x - cx);
+
+    path_builder.line_to(tp1);
+    if [cx, cy, angle_start, angle_end].iter().all(|x| x.is_finite()) {
+        path_builder.arc(Point2D::new(cx, cy), radius, angle_start, angle_end, anticlockwise);
+    }
+}
+
+/// Shifts a dash pattern so that it starts `offset` into the original pattern. Zero-length
+/// dashes or gaps are added as needed for the result to still start with a dash.
+fn offset_dash_pattern(pattern: &[AzFloat], offset: AzFloat) -> Vec<AzFloat> {
+    let length: AzFloat = pattern.iter().sum();
+    if !(length > 0.0) {
+        return vec![];
+    }
+    let mut offset = offset % length;
+    if offset < 0.0 {
+        offset += length;
+    }
+
+    // Find the dash or gap the offset falls in.
+    let mut index = 0;
+    while index < pattern.len() && offset >= pattern[index] {
+        offset -= pattern[index];
+        index += 1;
+    }
+    if index == pattern.len() {
+        return pattern.to_vec();
+    }
+
+    let mut result = vec![pattern[index] - offset];
+    result.extend_from_slice(&pattern[index + 1..]);
+    result.extend_from_slice(&pattern[..index]);
+    if offset > 0.0 {
+        result.push(offset);
+    }
+    if index % 2 == 1 {
+        result.insert(0, 0.0);
+    }
+    if result.len() % 2 == 1 {
+        result.push(0.0);
+    }
+    result
+}
+
+/// Measures `text` as drawn with `font`, relative to the alignment point and baseline given by
+/// `text_align` and `text_baseline`.
+fn text_metrics(font: &mut Font, text: &str, text_align: TextAlign, text_baseline: TextBaseline) -> TextMetrics {
+    let width = if text.is_empty() {
+        0.0
+    } else {
+        let options = ShapingOptions {
+            letter_spacing: None,
+            word_spacing: (Au(0), NotNan::new(0.0).unwrap()),
+            script: Script::Common,
+            flags: ShapingFlags::empty(),
+        };
+        let glyphs = font.shape_text(text, &options);
+        glyphs.advance_for_byte_range(&Range::new(ByteIndex(0), glyphs.len()), Au(0)).to_f64_px()
+    };
+
+    // We only support left-to-right text, and use the advance box as the bounding box of the text.
+    let anchor = match text_align {
+        TextAlign::Start | TextAlign::Left => 0.0,
+        TextAlign::End | TextAlign::Right => width,
+        TextAlign::Center => width / 2.0,
+    };
+
+    let ascent = font.metrics.ascent.to_f64_px();
+    let descent = font.metrics.descent.to_f64_px();
+    let baseline_height = |baseline: TextBaseline| match baseline {
+        TextBaseline::Top => ascent,
+        TextBaseline::Hanging => ascent * HANGING_BASELINE_RATIO,
+        TextBaseline::Middle => (ascent - descent) / 2.0,
+        TextBaseline::Alphabetic => 0.0,
+        TextBaseline::Ideographic | TextBaseline::Bottom => -descent,
+    };
+    let baseline = baseline_height(text_baseline);
+    let em_size = font.metrics.em_size.to_f64_px();
+    let em_ascent = if ascent + descent > 0.0 { em_size * ascent / (ascent + descent) } else { em_size };
+
+    TextMetrics {
+        width: width,
+        actual_bounding_box_left: anchor,
+        actual_bounding_box_right: width - anchor,
+        font_bounding_box_ascent: ascent - baseline,
+        font_bounding_box_descent: descent + baseline,
+        actual_bounding_box_ascent: ascent - baseline,
+        actual_bounding_box_descent: descent + baseline,
+        em_height_ascent: em_ascent - baseline,
+        em_height_descent: em_size - em_ascent + baseline,
+        hanging_baseline: baseline_height(TextBaseline::Hanging) - baseline,
+        alphabetic_baseline: -baseline,
+        ideographic_baseline: baseline_height(TextBaseline::Ideographic) - baseline,
+    }
+}
+
 fn is_zero_size_gradient(pattern: &Pattern) -> bool {
     if let &Pattern::LinearGradient(ref gradient) = pattern {
         if gradient.is_zero_size() {
@@ -6,6 +6,8 @@ use azure::azure_hl::AntialiasMode;
 use canvas_data::*;
 use canvas_traits::canvas::*;
 use euclid::Size2D;
+use gfx::font_cache_thread::FontCacheThread;
+use gfx::font_context::FontContext;
 use ipc_channel::ipc::{self, IpcSender};
 use std::borrow::ToOwned;
 use std::collections::HashMap;
@@ -15,22 +17,25 @@ use webrender_api;
 pub struct CanvasPaintThread <'a> {
     canvases: HashMap<CanvasId, CanvasData<'a>>,
     next_canvas_id: CanvasId,
+    /// The fonts used to measure the text of every canvas.
+    font_context: FontContext<FontCacheThread>,
 }
 
 impl<'a> CanvasPaintThread <'a> {
-    fn new() -> CanvasPaintThread <'a> {
+    fn new(font_cache_thread: FontCacheThread) -> CanvasPaintThread <'a> {
         CanvasPaintThread {
             canvases: HashMap::new(),
             next_canvas_id: CanvasId(0),
+            font_context: FontContext::new(font_cache_thread),
         }
     }
 
     /// Creates a new `CanvasPaintThread` and returns an `IpcSender` to
     /// communicate with it.
-    pub fn start() -> IpcSender<CanvasMsg> {
+    pub fn start(font_cache_thread: FontCacheThread) -> IpcSender<CanvasMsg> {
         let (sender, receiver) = ipc::channel::<CanvasMsg>().unwrap();
         thread::Builder::new().name("CanvasThread".to_owned()).spawn(move || {
-            let mut canvas_paint_thread = CanvasPaintThread::new();
+            let mut canvas_paint_thread = CanvasPaintThread::new(font_cache_thread);
             loop {
                 match receiver.recv() {
                     Ok(msg) => {
@@ -104,6 +109,13 @@ impl<'a> CanvasPaintThread <'a> {
             Canvas2dMsg::FillText(text, x, y, max_width) => {
                 self.canvas(canvas_id).fill_text(text, x, y, max_width)
             },
+            Canvas2dMsg::StrokeText(text, x, y, max_width) => {
+                self.canvas(canvas_id).stroke_text(text, x, y, max_width)
+            },
+            Canvas2dMsg::MeasureText(text, chan) => {
+                let canvas = self.canvases.get_mut(&canvas_id).expect("Bogus canvas id");
+                canvas.measure_text(&mut self.font_context, text, chan)
+            },
             Canvas2dMsg::FillRect(ref rect) => {
                 self.canvas(canvas_id).fill_rect(rect)
             },
@@ -131,6 +143,18 @@ impl<'a> CanvasPaintThread <'a> {
             Canvas2dMsg::IsPointInPath(x, y, fill_rule, chan) => {
                 self.canvas(canvas_id).is_point_in_path(x, y, fill_rule, chan)
             },
+            Canvas2dMsg::FillPath(ref path, fill_rule) => {
+                self.canvas(canvas_id).fill_path(path, fill_rule)
+            },
+            Canvas2dMsg::StrokePath(ref path) => {
+                self.canvas(canvas_id).stroke_path(path)
+            },
+            Canvas2dMsg::ClipPath(ref path, fill_rule) => {
+                self.canvas(canvas_id).clip_path(path, fill_rule)
+            },
+            Canvas2dMsg::IsPointInPath2D(ref path, x, y, fill_rule, chan) => {
+                self.canvas(canvas_id).is_point_in_path_2d(path, x, y, fill_rule, chan)
+            },
             Canvas2dMsg::DrawImage(
                 imagedata,
                 image_size,
@@ -232,6 +256,12 @@ impl<'a> CanvasPaintThread <'a> {
             Canvas2dMsg::SetLineJoin(join) => {
                 self.canvas(canvas_id).set_line_join(join)
             },
+            Canvas2dMsg::SetLineDash(ref segments) => {
+                self.canvas(canvas_id).set_line_dash(segments)
+            },
+            Canvas2dMsg::SetLineDashOffset(offset) => {
+                self.canvas(canvas_id).set_line_dash_offset(offset)
+            },
             Canvas2dMsg::SetMiterLimit(limit) => {
                 self.canvas(canvas_id).set_miter_limit(limit)
             },
@@ -272,6 +302,15 @@ impl<'a> CanvasPaintThread <'a> {
             Canvas2dMsg::SetShadowColor(ref color) => {
                 self.canvas(canvas_id).set_shadow_color(color.to_azure_style())
             },
+            Canvas2dMsg::SetFont(font) => {
+                self.canvas(canvas_id).set_font(font)
+            },
+            Canvas2dMsg::SetTextAlign(align) => {
+                self.canvas(canvas_id).set_text_align(align)
+            },
+            Canvas2dMsg::SetTextBaseline(baseline) => {
+                self.canvas(canvas_id).set_text_baseline(baseline)
+            },
         }
     }
 
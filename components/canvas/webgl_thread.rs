@@ -1081,6 +1081,151 @@ impl WebGLImpl {
                 }
                 sender.send(value).unwrap();
             }
+            WebGLCommand::TexImage3D {
+                target,
+                level,
+                internal_format,
+                width,
+                height,
+                depth,
+                format,
+                data_type,
+                ref data,
+            } => {
+                ctx.gl().tex_image_3d(target, level, internal_format, width, height, depth,
+                                      /*border*/0, format, data_type, Some(data))
+            }
+            WebGLCommand::TexSubImage3D {
+                target,
+                level,
+                xoffset,
+                yoffset,
+                zoffset,
+                width,
+                height,
+                depth,
+                format,
+                data_type,
+                ref data,
+            } => {
+                ctx.gl().tex_sub_image_3d(target, level, xoffset, yoffset, zoffset,
+                                          width, height, depth, format, data_type, data)
+            }
+            WebGLCommand::TexStorage2D(target, levels, internal_format, width, height) =>
+                ctx.gl().tex_storage_2d(target, levels, internal_format, width, height),
+            WebGLCommand::TexStorage3D(target, levels, internal_format, width, height, depth) =>
+                ctx.gl().tex_storage_3d(target, levels, internal_format, width, height, depth),
+            WebGLCommand::DrawBuffers(ref buffers) =>
+                ctx.gl().draw_buffers(buffers),
+            WebGLCommand::CreateQuery(ref chan) =>
+                Self::create_query(ctx.gl(), chan),
+            WebGLCommand::DeleteQuery(id) =>
+                ctx.gl().delete_queries(&[id.get()]),
+            WebGLCommand::BeginQuery(target, id) =>
+                ctx.gl().begin_query(target, id.get()),
+            WebGLCommand::EndQuery(target) =>
+                ctx.gl().end_query(target),
+            WebGLCommand::GetQueryResult(id, ref sender) => {
+                sender.send(ctx.gl().get_query_object_uiv(id.get(), gl::QUERY_RESULT)).unwrap()
+            }
+            WebGLCommand::GetQueryResultAvailable(id, ref sender) => {
+                let available = ctx.gl().get_query_object_uiv(id.get(), gl::QUERY_RESULT_AVAILABLE);
+                sender.send(available != 0).unwrap()
+            }
+            WebGLCommand::CreateSampler(ref chan) =>
+                Self::create_sampler(ctx.gl(), chan),
+            WebGLCommand::DeleteSampler(id) =>
+                ctx.gl().delete_samplers(&[id.get()]),
+            WebGLCommand::BindSampler(unit, id) =>
+                ctx.gl().bind_sampler(unit, id.map_or(0, WebGLSamplerId::get)),
+            WebGLCommand::SamplerParameteri(id, pname, value) =>
+                ctx.gl().sampler_parameter_i(id.get(), pname, value),
+            WebGLCommand::SamplerParameterf(id, pname, value) =>
+                ctx.gl().sampler_parameter_f(id.get(), pname, value),
+            WebGLCommand::GetSamplerParameterInt(id, pname, ref sender) => {
+                let mut value = [0];
+                unsafe {
+                    ctx.gl().get_sampler_parameter_iv(id.get(), pname, &mut value);
+                }
+                sender.send(value[0]).unwrap()
+            }
+            WebGLCommand::GetSamplerParameterFloat(id, pname, ref sender) => {
+                let mut value = [0.];
+                unsafe {
+                    ctx.gl().get_sampler_parameter_fv(id.get(), pname, &mut value);
+                }
+                sender.send(value[0]).unwrap()
+            }
+            WebGLCommand::FenceSync(ref chan) =>
+                Self::fence_sync(ctx.gl(), chan),
+            WebGLCommand::ClientWaitSync(id, flags, timeout, ref sender) => {
+                let status = ctx.gl().client_wait_sync(id.get() as gl::GLsync, flags, timeout);
+                sender.send(status).unwrap()
+            }
+            WebGLCommand::WaitSync(id) =>
+                ctx.gl().wait_sync(id.get() as gl::GLsync, 0, gl::TIMEOUT_IGNORED),
+            WebGLCommand::DeleteSync(id) =>
+                ctx.gl().delete_sync(id.get() as gl::GLsync),
+            WebGLCommand::CreateTransformFeedback(ref chan) =>
+                Self::create_transform_feedback(ctx.gl(), chan),
+            WebGLCommand::DeleteTransformFeedback(id) =>
+                ctx.gl().delete_transform_feedbacks(&[id.get()]),
+            WebGLCommand::BindTransformFeedback(id) => {
+                let id = id.map_or(0, WebGLTransformFeedbackId::get);
+                ctx.gl().bind_transform_feedback(gl::TRANSFORM_FEEDBACK, id)
+            }
+            WebGLCommand::BeginTransformFeedback(mode) =>
+                ctx.gl().begin_transform_feedback(mode),
+            WebGLCommand::EndTransformFeedback =>
+                ctx.gl().end_transform_feedback(),
+            WebGLCommand::PauseTransformFeedback =>
+                ctx.gl().pause_transform_feedback(),
+            WebGLCommand::ResumeTransformFeedback =>
+                ctx.gl().resume_transform_feedback(),
+            WebGLCommand::TransformFeedbackVaryings(program_id, ref varyings, buffer_mode) => {
+                let varyings = varyings.iter().map(|v| to_name_in_compiled_shader(v)).collect::<Vec<_>>();
+                ctx.gl().transform_feedback_varyings(program_id.get(), &varyings, buffer_mode)
+            }
+            WebGLCommand::GetTransformFeedbackVarying(program_id, index, ref sender) => {
+                let (size, type_, name) = ctx.gl().get_transform_feedback_varying(program_id.get(), index);
+                sender.send((size, type_, from_name_in_compiled_shader(&name))).unwrap()
+            }
+            WebGLCommand::BindBufferBase(target, index, id) =>
+                ctx.gl().bind_buffer_base(target, index, id.map_or(0, WebGLBufferId::get)),
+            WebGLCommand::BindBufferRange(target, index, id, offset, size) => {
+                ctx.gl().bind_buffer_range(target, index, id.map_or(0, WebGLBufferId::get),
+                                           offset as isize, size as isize)
+            }
+            WebGLCommand::GetUniformIndices(program_id, ref names, ref sender) => {
+                let names = names.iter().map(|n| to_name_in_compiled_shader(n)).collect::<Vec<_>>();
+                let names = names.iter().map(|n| &**n).collect::<Vec<_>>();
+                sender.send(ctx.gl().get_uniform_indices(program_id.get(), &names)).unwrap()
+            }
+            WebGLCommand::GetActiveUniforms(program_id, ref indices, pname, ref sender) => {
+                let values = ctx.gl().get_active_uniforms_iv(program_id.get(), indices.clone(), pname);
+                sender.send(values).unwrap()
+            }
+            WebGLCommand::GetUniformBlockIndex(program_id, ref name, ref sender) => {
+                let name = to_name_in_compiled_shader(name);
+                sender.send(ctx.gl().get_uniform_block_index(program_id.get(), &name)).unwrap()
+            }
+            WebGLCommand::GetProgramActiveUniformBlocks(program, ref sender) => {
+                let mut value = [0];
+                unsafe {
+                    ctx.gl().get_program_iv(program.get(), gl::ACTIVE_UNIFORM_BLOCKS, &mut value);
+                }
+                sender.send(value[0]).unwrap()
+            }
+            WebGLCommand::GetActiveUniformBlockParameter(program_id, index, pname, ref sender) => {
+                let values = ctx.gl().get_active_uniform_block_iv(program_id.get(), index, pname);
+                sender.send(values).unwrap()
+            }
+            WebGLCommand::GetActiveUniformBlockName(program_id, index, ref sender) => {
+                let name = ctx.gl().get_active_uniform_block_name(program_id.get(), index);
+                sender.send(from_name_in_compiled_shader(&name)).unwrap()
+            }
+            WebGLCommand::UniformBlockBinding(program_id, index, binding) =>
+                ctx.gl().uniform_block_binding(program_id.get(), index, binding),
         }
 
         // TODO: update test expectations in order to enable debug assertions
@@ -1308,6 +1453,50 @@ impl WebGLImpl {
         chan.send(vao).unwrap();
     }
 
+    #[allow(unsafe_code)]
+    fn create_query(gl: &gl::Gl, chan: &WebGLSender<Option<WebGLQueryId>>) {
+        let query = gl.gen_queries(1)[0];
+        let query = if query == 0 {
+            None
+        } else {
+            Some(unsafe { WebGLQueryId::new(query) })
+        };
+        chan.send(query).unwrap();
+    }
+
+    #[allow(unsafe_code)]
+    fn create_sampler(gl: &gl::Gl, chan: &WebGLSender<Option<WebGLSamplerId>>) {
+        let sampler = gl.gen_samplers(1)[0];
+        let sampler = if sampler == 0 {
+            None
+        } else {
+            Some(unsafe { WebGLSamplerId::new(sampler) })
+        };
+        chan.send(sampler).unwrap();
+    }
+
+    #[allow(unsafe_code)]
+    fn fence_sync(gl: &gl::Gl, chan: &WebGLSender<Option<WebGLSyncId>>) {
+        let sync = gl.fence_sync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+        let sync = if sync.is_null() {
+            None
+        } else {
+            Some(unsafe { WebGLSyncId::new(sync as u64) })
+        };
+        chan.send(sync).unwrap();
+    }
+
+    #[allow(unsafe_code)]
+    fn create_transform_feedback(gl: &gl::Gl, chan: &WebGLSender<Option<WebGLTransformFeedbackId>>) {
+        let tf = gl.gen_transform_feedbacks(1)[0];
+        let tf = if tf == 0 {
+            None
+        } else {
+            Some(unsafe { WebGLTransformFeedbackId::new(tf) })
+        };
+        chan.send(tf).unwrap();
+    }
+
     #[inline]
     fn bind_framebuffer<Native: NativeGLContextMethods>(gl: &gl::Gl,
                                                         target: u32,
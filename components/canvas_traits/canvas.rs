@@ -2,11 +2,12 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use cssparser::RGBA;
+use cssparser::{ParseError, Parser, ParserInput, RGBA, Token, serialize_string};
 use euclid::{Transform2D, Point2D, Vector2D, Rect, Size2D};
 use ipc_channel::ipc::IpcSender;
 use serde_bytes::ByteBuf;
 use std::default::Default;
+use std::fmt;
 use std::str::FromStr;
 use webrender_api;
 
@@ -46,14 +47,18 @@ pub enum Canvas2dMsg {
     BezierCurveTo(Point2D<f32>, Point2D<f32>, Point2D<f32>),
     ClearRect(Rect<f32>),
     Clip,
+    ClipPath(Vec<PathSegment>, FillRule),
     ClosePath,
     Ellipse(Point2D<f32>, f32, f32, f32, f32, f32, bool),
     Fill,
+    FillPath(Vec<PathSegment>, FillRule),
     FillText(String, f64, f64, Option<f64>),
     FillRect(Rect<f32>),
     GetImageData(Rect<i32>, Size2D<f64>, IpcSender<ByteBuf>),
     IsPointInPath(f64, f64, FillRule, IpcSender<bool>),
+    IsPointInPath2D(Vec<PathSegment>, f64, f64, FillRule, IpcSender<bool>),
     LineTo(Point2D<f32>),
+    MeasureText(String, IpcSender<TextMetrics>),
     MoveTo(Point2D<f32>),
     PutImageData(ByteBuf, Vector2D<f64>, Size2D<f64>, Rect<f64>),
     QuadraticCurveTo(Point2D<f32>, Point2D<f32>),
@@ -62,11 +67,15 @@ pub enum Canvas2dMsg {
     SaveContext,
     StrokeRect(Rect<f32>),
     Stroke,
+    StrokePath(Vec<PathSegment>),
+    StrokeText(String, f64, f64, Option<f64>),
     SetFillStyle(FillOrStrokeStyle),
     SetStrokeStyle(FillOrStrokeStyle),
     SetLineWidth(f32),
     SetLineCap(LineCapStyle),
     SetLineJoin(LineJoinStyle),
+    SetLineDash(Vec<f32>),
+    SetLineDashOffset(f32),
     SetMiterLimit(f32),
     SetGlobalAlpha(f32),
     SetGlobalComposition(CompositionOrBlending),
@@ -75,6 +84,39 @@ pub enum Canvas2dMsg {
     SetShadowOffsetY(f64),
     SetShadowBlur(f64),
     SetShadowColor(RGBA),
+    SetFont(CanvasFont),
+    SetTextAlign(TextAlign),
+    SetTextBaseline(TextBaseline),
+}
+
+/// A segment of a path recorded by a `Path2D` object, replayed by the canvas paint thread
+/// when the path is used.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum PathSegment {
+    ClosePath,
+    MoveTo(Point2D<f32>),
+    LineTo(Point2D<f32>),
+    QuadraticCurveTo(Point2D<f32>, Point2D<f32>),
+    BezierCurveTo(Point2D<f32>, Point2D<f32>, Point2D<f32>),
+    ArcTo(Point2D<f32>, Point2D<f32>, f32),
+    Ellipse(Point2D<f32>, f32, f32, f32, f32, f32, bool),
+}
+
+/// https://html.spec.whatwg.org/multipage/#textmetrics
+#[derive(Clone, Debug, Default, Deserialize, MallocSizeOf, Serialize)]
+pub struct TextMetrics {
+    pub width: f64,
+    pub actual_bounding_box_left: f64,
+    pub actual_bounding_box_right: f64,
+    pub font_bounding_box_ascent: f64,
+    pub font_bounding_box_descent: f64,
+    pub actual_bounding_box_ascent: f64,
+    pub actual_bounding_box_descent: f64,
+    pub em_height_ascent: f64,
+    pub em_height_descent: f64,
+    pub hanging_baseline: f64,
+    pub alphabetic_baseline: f64,
+    pub ideographic_baseline: f64,
 }
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -384,6 +426,234 @@ impl FromStr for CompositionOrBlending {
     }
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum TextAlign {
+    Start,
+    End,
+    Left,
+    Right,
+    Center,
+}
+
+impl Default for TextAlign {
+    fn default() -> TextAlign {
+        TextAlign::Start
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum TextBaseline {
+    Top,
+    Hanging,
+    Middle,
+    Alphabetic,
+    Ideographic,
+    Bottom,
+}
+
+impl Default for TextBaseline {
+    fn default() -> TextBaseline {
+        TextBaseline::Alphabetic
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum CanvasFontFamily {
+    /// A family name such as `Times New Roman`.
+    Specific(String),
+    /// A generic family such as `sans-serif`.
+    Generic(String),
+}
+
+/// The font of a 2D context, as set from a CSS `font` shorthand value.
+/// https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub struct CanvasFont {
+    pub italic: bool,
+    pub small_caps: bool,
+    /// The weight, between 1 and 1000.
+    pub weight: f32,
+    /// The size, in CSS pixels.
+    pub size: f64,
+    pub families: Vec<CanvasFontFamily>,
+}
+
+impl Default for CanvasFont {
+    /// The initial font of a context, `10px sans-serif`.
+    fn default() -> CanvasFont {
+        CanvasFont {
+            italic: false,
+            small_caps: false,
+            weight: 400.,
+            size: 10.,
+            families: vec![CanvasFontFamily::Generic("sans-serif".to_owned())],
+        }
+    }
+}
+
+impl CanvasFont {
+    /// Parses a CSS `font` shorthand value. Relative sizes are resolved against `parent_size`,
+    /// the font size of the canvas element, in CSS pixels. System fonts are not supported.
+    pub fn parse(value: &str, parent_size: f64) -> Result<CanvasFont, ()> {
+        let mut input = ParserInput::new(value);
+        let mut parser = Parser::new(&mut input);
+        let font = parse_font(&mut parser, parent_size).map_err(|_| ())?;
+        if !parser.is_exhausted() {
+            return Err(());
+        }
+        Ok(font)
+    }
+}
+
+fn parse_font<'i, 't>(input: &mut Parser<'i, 't>, parent_size: f64) -> Result<CanvasFont, ParseError<'i, ()>> {
+    let mut font = CanvasFont::default();
+
+    // Up to three of font-style, font-variant, font-weight and font-stretch, in any order,
+    // followed by the mandatory font-size.
+    let mut keywords = 0;
+    let size = loop {
+        let token = input.next()?.clone();
+        if let Some(size) = font_size(&token, parent_size) {
+            break size;
+        }
+        if keywords == 4 {
+            return Err(input.new_custom_error(()));
+        }
+        keywords += 1;
+        match token {
+            Token::Number { value, .. } if value >= 1. && value <= 1000. => font.weight = value,
+            Token::Ident(ref ident) => {
+                match_ignore_ascii_case! { &*ident,
+                    "normal" => {},
+                    "italic" | "oblique" => font.italic = true,
+                    "small-caps" => font.small_caps = true,
+                    "bold" | "bolder" => font.weight = 700.,
+                    "lighter" => font.weight = 100.,
+                    // font-stretch has no effect on the selection of canvas fonts yet.
+                    "ultra-condensed" | "extra-condensed" | "condensed" | "semi-condensed" |
+                    "semi-expanded" | "expanded" | "extra-expanded" | "ultra-expanded" => {},
+                    _ => return Err(input.new_custom_error(())),
+                }
+            },
+            _ => return Err(input.new_custom_error(())),
+        }
+    };
+    if size < 0. {
+        return Err(input.new_custom_error(()));
+    }
+    font.size = size;
+
+    // The line height is ignored, as canvas text is drawn on a single line.
+    if input.try(|input| input.expect_delim('/')).is_ok() {
+        input.next()?;
+    }
+
+    font.families = input.parse_comma_separated(|input| parse_font_family(input))?;
+    Ok(font)
+}
+
+fn parse_font_family<'i, 't>(input: &mut Parser<'i, 't>) -> Result<CanvasFontFamily, ParseError<'i, ()>> {
+    if let Ok(name) = input.try(|input| input.expect_string_cloned()) {
+        return Ok(CanvasFontFamily::Specific(name.as_ref().to_owned()));
+    }
+    let mut name = input.expect_ident_cloned()?.as_ref().to_owned();
+    let mut words = 1;
+    while let Ok(word) = input.try(|input| input.expect_ident_cloned()) {
+        name.push(' ');
+        name.push_str(&word);
+        words += 1;
+    }
+    if words == 1 {
+        match_ignore_ascii_case! { &name,
+            "serif" | "sans-serif" | "cursive" | "fantasy" | "monospace" => {
+                return Ok(CanvasFontFamily::Generic(name.to_ascii_lowercase()));
+            },
+            "inherit" | "initial" | "unset" | "default" => return Err(input.new_custom_error(())),
+            _ => {},
+        }
+    }
+    Ok(CanvasFontFamily::Specific(name))
+}
+
+/// The size in CSS pixels of a font-size token, if it is one.
+fn font_size(token: &Token, parent_size: f64) -> Option<f64> {
+    const MEDIUM: f64 = 16.;
+    match *token {
+        Token::Dimension { value, ref unit, .. } => {
+            let value = value as f64;
+            let size = match_ignore_ascii_case! { &*unit,
+                "px" => value,
+                "pt" => value * 4. / 3.,
+                "pc" => value * 16.,
+                "in" => value * 96.,
+                "cm" => value * 96. / 2.54,
+                "mm" => value * 96. / 25.4,
+                "q" => value * 96. / 101.6,
+                "em" | "rem" => value * parent_size,
+                "ex" | "ch" => value * parent_size / 2.,
+                _ => return None,
+            };
+            Some(size)
+        },
+        Token::Percentage { unit_value, .. } => Some(unit_value as f64 * parent_size),
+        Token::Number { value, .. } if value == 0. => Some(0.),
+        Token::Ident(ref ident) => {
+            let size = match_ignore_ascii_case! { &*ident,
+                "xx-small" => MEDIUM * 3. / 5.,
+                "x-small" => MEDIUM * 3. / 4.,
+                "small" => MEDIUM * 8. / 9.,
+                "medium" => MEDIUM,
+                "large" => MEDIUM * 6. / 5.,
+                "x-large" => MEDIUM * 3. / 2.,
+                "xx-large" => MEDIUM * 2.,
+                "larger" => parent_size * 1.2,
+                "smaller" => parent_size / 1.2,
+                _ => return None,
+            };
+            Some(size)
+        },
+        _ => None,
+    }
+}
+
+/// https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+impl fmt::Display for CanvasFont {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.italic {
+            write!(f, "italic ")?;
+        }
+        if self.small_caps {
+            write!(f, "small-caps ")?;
+        }
+        if self.weight == 700. {
+            write!(f, "bold ")?;
+        } else if self.weight != 400. {
+            write!(f, "{} ", self.weight)?;
+        }
+        write!(f, "{}px ", self.size)?;
+        for (i, family) in self.families.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            match *family {
+                CanvasFontFamily::Generic(ref name) => write!(f, "{}", name)?,
+                CanvasFontFamily::Specific(ref name) => {
+                    let is_identifiers = name.split(' ').all(|word| {
+                        !word.is_empty() && !word.starts_with(|c: char| c.is_ascii_digit()) &&
+                        word.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                    });
+                    if is_identifiers {
+                        write!(f, "{}", name)?;
+                    } else {
+                        serialize_string(name, f)?;
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
 // TODO(pcwalton): Speed up with SIMD, or better yet, find some way to not do this.
 pub fn byte_swap(data: &mut [u8]) {
     let length = data.len();
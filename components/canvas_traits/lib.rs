@@ -7,7 +7,7 @@
 
 #![deny(unsafe_code)]
 
-extern crate cssparser;
+#[macro_use] extern crate cssparser;
 extern crate euclid;
 extern crate gleam;
 extern crate ipc_channel;
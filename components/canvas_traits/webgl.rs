@@ -7,7 +7,7 @@ use gleam::gl;
 use offscreen_gl_context::{GLContextAttributes, GLLimits};
 use serde_bytes::ByteBuf;
 use std::borrow::Cow;
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroU64};
 use webrender_api::{DocumentId, ImageKey, PipelineId};
 
 /// Sender type used in WebGLCommands.
@@ -295,22 +295,84 @@ pub enum WebGLCommand {
     GetUniformFloat4(WebGLProgramId, i32, WebGLSender<[f32; 4]>),
     GetUniformFloat9(WebGLProgramId, i32, WebGLSender<[f32; 9]>),
     GetUniformFloat16(WebGLProgramId, i32, WebGLSender<[f32; 16]>),
+    TexImage3D {
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        data: ByteBuf,
+    },
+    TexSubImage3D {
+        target: u32,
+        level: i32,
+        xoffset: i32,
+        yoffset: i32,
+        zoffset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        data: ByteBuf,
+    },
+    TexStorage2D(u32, i32, u32, i32, i32),
+    TexStorage3D(u32, i32, u32, i32, i32, i32),
+    DrawBuffers(Vec<u32>),
+    CreateQuery(WebGLSender<Option<WebGLQueryId>>),
+    DeleteQuery(WebGLQueryId),
+    BeginQuery(u32, WebGLQueryId),
+    EndQuery(u32),
+    GetQueryResult(WebGLQueryId, WebGLSender<u32>),
+    GetQueryResultAvailable(WebGLQueryId, WebGLSender<bool>),
+    CreateSampler(WebGLSender<Option<WebGLSamplerId>>),
+    DeleteSampler(WebGLSamplerId),
+    BindSampler(u32, Option<WebGLSamplerId>),
+    SamplerParameteri(WebGLSamplerId, u32, i32),
+    SamplerParameterf(WebGLSamplerId, u32, f32),
+    GetSamplerParameterInt(WebGLSamplerId, u32, WebGLSender<i32>),
+    GetSamplerParameterFloat(WebGLSamplerId, u32, WebGLSender<f32>),
+    FenceSync(WebGLSender<Option<WebGLSyncId>>),
+    ClientWaitSync(WebGLSyncId, u32, u64, WebGLSender<u32>),
+    WaitSync(WebGLSyncId),
+    DeleteSync(WebGLSyncId),
+    CreateTransformFeedback(WebGLSender<Option<WebGLTransformFeedbackId>>),
+    DeleteTransformFeedback(WebGLTransformFeedbackId),
+    BindTransformFeedback(Option<WebGLTransformFeedbackId>),
+    BeginTransformFeedback(u32),
+    EndTransformFeedback,
+    PauseTransformFeedback,
+    ResumeTransformFeedback,
+    TransformFeedbackVaryings(WebGLProgramId, Vec<String>, u32),
+    GetTransformFeedbackVarying(WebGLProgramId, u32, WebGLSender<(i32, u32, String)>),
+    BindBufferBase(u32, u32, Option<WebGLBufferId>),
+    BindBufferRange(u32, u32, Option<WebGLBufferId>, i64, i64),
+    GetUniformIndices(WebGLProgramId, Vec<String>, WebGLSender<Vec<u32>>),
+    GetActiveUniforms(WebGLProgramId, Vec<u32>, u32, WebGLSender<Vec<i32>>),
+    GetUniformBlockIndex(WebGLProgramId, String, WebGLSender<u32>),
+    GetProgramActiveUniformBlocks(WebGLProgramId, WebGLSender<i32>),
+    GetActiveUniformBlockParameter(WebGLProgramId, u32, u32, WebGLSender<Vec<i32>>),
+    GetActiveUniformBlockName(WebGLProgramId, u32, WebGLSender<String>),
+    UniformBlockBinding(WebGLProgramId, u32, u32),
 }
 
 macro_rules! define_resource_id_struct {
-    ($name:ident) => {
+    ($name:ident, $nonzero:ident, $type:ident) => {
         #[derive(Clone, Copy, Eq, Hash, PartialEq)]
-        pub struct $name(NonZeroU32);
+        pub struct $name($nonzero);
 
         impl $name {
             #[allow(unsafe_code)]
             #[inline]
-            pub unsafe fn new(id: u32) -> Self {
-                $name(NonZeroU32::new_unchecked(id))
+            pub unsafe fn new(id: $type) -> Self {
+                $name($nonzero::new_unchecked(id))
             }
 
             #[inline]
-            pub fn get(self) -> u32 {
+            pub fn get(self) -> $type {
                 self.0.get()
             }
         }
@@ -320,14 +382,17 @@ macro_rules! define_resource_id_struct {
 
 macro_rules! define_resource_id {
     ($name:ident) => {
-        define_resource_id_struct!($name);
+        define_resource_id!($name, NonZeroU32, u32);
+    };
+    ($name:ident, $nonzero:ident, $type:ident) => {
+        define_resource_id_struct!($name, $nonzero, $type);
 
         #[allow(unsafe_code)]
         impl<'de> ::serde::Deserialize<'de> for $name {
             fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
                 where D: ::serde::Deserializer<'de>
             {
-                let id = try!(u32::deserialize(deserializer));
+                let id = try!($type::deserialize(deserializer));
                 if id == 0 {
                     Err(::serde::de::Error::custom("expected a non-zero value"))
                 } else {
@@ -373,6 +438,11 @@ define_resource_id!(WebGLTextureId);
 define_resource_id!(WebGLProgramId);
 define_resource_id!(WebGLShaderId);
 define_resource_id!(WebGLVertexArrayId);
+define_resource_id!(WebGLQueryId);
+define_resource_id!(WebGLSamplerId);
+define_resource_id!(WebGLTransformFeedbackId);
+// Sync objects are opaque pointers rather than GL names.
+define_resource_id!(WebGLSyncId, NonZeroU64, u64);
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, MallocSizeOf, Ord)]
 #[derive(PartialEq, PartialOrd, Serialize)]
@@ -531,6 +601,12 @@ parameters! {
             FrontFace = gl::FRONT_FACE,
             GenerateMipmapHint = gl::GENERATE_MIPMAP_HINT,
             GreenBits = gl::GREEN_BITS,
+            Max3DTextureSize = gl::MAX_3D_TEXTURE_SIZE,
+            MaxArrayTextureLayers = gl::MAX_ARRAY_TEXTURE_LAYERS,
+            MaxColorAttachments = gl::MAX_COLOR_ATTACHMENTS,
+            MaxDrawBuffers = gl::MAX_DRAW_BUFFERS,
+            MaxTransformFeedbackSeparateAttribs = gl::MAX_TRANSFORM_FEEDBACK_SEPARATE_ATTRIBS,
+            MaxUniformBufferBindings = gl::MAX_UNIFORM_BUFFER_BINDINGS,
             PackAlignment = gl::PACK_ALIGNMENT,
             RedBits = gl::RED_BITS,
             SampleBuffers = gl::SAMPLE_BUFFERS,
@@ -552,6 +628,7 @@ parameters! {
             StencilValueMask = gl::STENCIL_VALUE_MASK,
             StencilWritemask = gl::STENCIL_WRITEMASK,
             SubpixelBits = gl::SUBPIXEL_BITS,
+            UniformBufferOffsetAlignment = gl::UNIFORM_BUFFER_OFFSET_ALIGNMENT,
             UnpackAlignment = gl::UNPACK_ALIGNMENT,
         }),
         Int2(ParameterInt2 {
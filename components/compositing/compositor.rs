@@ -497,6 +497,22 @@ impl<Window: WindowMethods, Back: gfx_hal::Backend> IOCompositor<Window, Back> {
                 }
             }
 
+            (Msg::WebDriverMouseButtonEvent(mouse_event_type, mouse_button, x, y),
+             ShutdownState::NotShuttingDown) => {
+                let point = self.webdriver_point_to_device_point(x, y);
+                let mouse_window_event = match mouse_event_type {
+                    MouseEventType::Click => MouseWindowEvent::Click(mouse_button, point),
+                    MouseEventType::MouseDown => MouseWindowEvent::MouseDown(mouse_button, point),
+                    MouseEventType::MouseUp => MouseWindowEvent::MouseUp(mouse_button, point),
+                };
+                self.on_mouse_window_event_class(mouse_window_event);
+            }
+
+            (Msg::WebDriverMouseMoveEvent(x, y), ShutdownState::NotShuttingDown) => {
+                let point = self.webdriver_point_to_device_point(x, y);
+                self.on_mouse_window_move_event_class(point);
+            }
+
             // When we are shutting_down, we need to avoid performing operations
             // such as Paint that may crash because we have begun tearing down
             // the rest of our resources.
@@ -676,6 +692,13 @@ impl<Window: WindowMethods, Back: gfx_hal::Backend> IOCompositor<Window, Back> {
         }
     }
 
+    /// WebDriver input is expressed in CSS pixels relative to the viewport, so scale it
+    /// the same way `hit_test_at_point` will scale it back.
+    fn webdriver_point_to_device_point(&self, x: f32, y: f32) -> DevicePoint {
+        let dppx = self.page_zoom * self.hidpi_factor();
+        TypedPoint2D::new(x, y) * dppx
+    }
+
     fn hit_test_at_point(&self, point: DevicePoint) -> HitTestResult {
         let dppx = self.page_zoom * self.hidpi_factor();
         let scaled_point = (point / dppx).to_untyped();
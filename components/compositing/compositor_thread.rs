@@ -14,7 +14,7 @@ use msg::constellation_msg::{PipelineId, TopLevelBrowsingContextId};
 use net_traits::image::base::Image;
 use profile_traits::mem;
 use profile_traits::time;
-use script_traits::{AnimationState, ConstellationMsg, EventResult};
+use script_traits::{AnimationState, ConstellationMsg, EventResult, MouseButton, MouseEventType};
 use std::fmt::{Debug, Error, Formatter};
 use std::sync::mpsc::{Receiver, Sender};
 use style_traits::viewport::ViewportConstraints;
@@ -115,6 +115,11 @@ pub enum Msg {
     GetScreenSize(IpcSender<DeviceUintSize>),
     /// Get screen available size.
     GetScreenAvailSize(IpcSender<DeviceUintSize>),
+
+    /// WebDriver mouse button event, at a point in CSS pixels relative to the viewport.
+    WebDriverMouseButtonEvent(MouseEventType, MouseButton, f32, f32),
+    /// WebDriver mouse move event, to a point in CSS pixels relative to the viewport.
+    WebDriverMouseMoveEvent(f32, f32),
 }
 
 impl Debug for Msg {
@@ -137,6 +142,8 @@ impl Debug for Msg {
             Msg::GetClientWindow(..) => write!(f, "GetClientWindow"),
             Msg::GetScreenSize(..) => write!(f, "GetScreenSize"),
             Msg::GetScreenAvailSize(..) => write!(f, "GetScreenAvailSize"),
+            Msg::WebDriverMouseButtonEvent(..) => write!(f, "WebDriverMouseButtonEvent"),
+            Msg::WebDriverMouseMoveEvent(..) => write!(f, "WebDriverMouseMoveEvent"),
         }
     }
 }
@@ -5,6 +5,7 @@
 //! Abstract windowing methods. The concrete implementations of these can be found in `platform/`.
 
 use embedder_traits::EventLoopWaker;
+use embedder_traits::request_interception::RequestInterception;
 use euclid::TypedScale;
 //#[cfg(feature = "gleam")]
 //use gleam::gl;
@@ -89,6 +90,8 @@ pub enum WindowEvent {
     ToggleWebRenderDebug(WebRenderDebugOption),
     /// Capture current WebRender
     CaptureWebRender,
+    /// Set how the requests made by the fetch pipeline are intercepted.
+    SetRequestInterception(RequestInterception),
 }
 
 impl Debug for WindowEvent {
@@ -115,6 +118,7 @@ impl Debug for WindowEvent {
             WindowEvent::SelectBrowser(..) => write!(f, "SelectBrowser"),
             WindowEvent::ToggleWebRenderDebug(..) => write!(f, "ToggleWebRenderDebug"),
             WindowEvent::CaptureWebRender => write!(f, "CaptureWebRender"),
+            WindowEvent::SetRequestInterception(..) => write!(f, "SetRequestInterception"),
         }
     }
 }
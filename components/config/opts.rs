@@ -126,6 +126,10 @@ pub struct Opts {
     /// remote Firefox devtools connections.
     pub devtools_port: Option<u16>,
 
+    /// `None` or `Some` with the path of a file to which the network activity of every tab is
+    /// written as an HTTP Archive, even when no devtools server is listening.
+    pub har_output: Option<PathBuf>,
+
     /// `None` to disable WebDriver or `Some` with a port number to start a server to listen to
     /// remote WebDriver commands.
     pub webdriver_port: Option<u16>,
@@ -518,6 +522,7 @@ pub fn default_opts() -> Opts {
         trace_layout: false,
         debugger_port: None,
         devtools_port: None,
+        har_output: None,
         webdriver_port: None,
         initial_window_size: TypedSize2D::new(1024, 740),
         user_agent: default_user_agent_string(DEFAULT_USER_AGENT).into(),
@@ -585,6 +590,7 @@ pub fn from_cmdline_args(args: &[String]) -> ArgumentParsingResult {
     opts.optflag("F", "soft-fail", "Display about:failure on thread failure instead of exiting");
     opts.optflagopt("", "remote-debugging-port", "Start remote debugger server on port", "2794");
     opts.optflagopt("", "devtools", "Start remote devtools server on port", "6000");
+    opts.optopt("", "har-output", "Write the network activity as an HTTP Archive to the given file", "servo.har");
     opts.optflagopt("", "webdriver", "Start remote WebDriver server on port", "7000");
     opts.optopt("", "resolution", "Set window resolution.", "1024x740");
     opts.optopt("u",
@@ -816,6 +822,7 @@ pub fn from_cmdline_args(args: &[String]) -> ArgumentParsingResult {
         trace_layout: debug_options.trace_layout,
         debugger_port: debugger_port,
         devtools_port: devtools_port,
+        har_output: opt_match.opt_str("har-output").map(Into::into),
         webdriver_port: webdriver_port,
         initial_window_size: initial_window_size,
         user_agent: user_agent,
@@ -113,9 +113,11 @@ use ipc_channel::router::ROUTER;
 use layout_traits::LayoutThreadFactory;
 use log::{Log, Level, LevelFilter, Metadata, Record};
 use msg::constellation_msg::{BrowsingContextId, PipelineId, HistoryStateId, TopLevelBrowsingContextId};
-use msg::constellation_msg::{Key, KeyModifiers, KeyState};
+use msg::constellation_msg::{Key, KeyModifiers, KeyState, MessagePortId, MessageRouterId};
 use msg::constellation_msg::{PipelineNamespace, PipelineNamespaceId, TraversalDirection};
 use net_traits::{self, IpcSend, FetchResponseMsg, ResourceThreads};
+use net_traits::cache_storage_thread::CacheStorageThreadMsg;
+use net_traits::indexeddb_thread::IndexedDBThreadMsg;
 use net_traits::pub_domains::reg_host;
 use net_traits::request::RequestInit;
 use net_traits::storage_thread::{StorageThreadMsg, StorageType};
@@ -123,12 +125,12 @@ use network_listener::NetworkListener;
 use pipeline::{InitialPipelineState, Pipeline};
 use profile_traits::mem;
 use profile_traits::time;
-use script_traits::{AnimationState, AnimationTickType, CompositorEvent};
+use script_traits::{AnimationState, AnimationTickType, BroadcastMsg, CompositorEvent};
 use script_traits::{ConstellationControlMsg, ConstellationMsg as FromCompositorMsg, DiscardBrowsingContext};
 use script_traits::{DocumentActivity, DocumentState, LayoutControlMsg, LoadData};
 use script_traits::{IFrameLoadInfo, IFrameLoadInfoWithData, IFrameSandboxState, TimerSchedulerMsg};
 use script_traits::{LayoutMsg as FromLayoutMsg, ScriptMsg as FromScriptMsg, ScriptThreadFactory};
-use script_traits::{LogEntry, ScriptToConstellationChan, ServiceWorkerMsg, webdriver_msg};
+use script_traits::{LogEntry, MessageRouterMsg, ScriptToConstellationChan, ServiceWorkerMsg, webdriver_msg};
 use script_traits::{SWManagerMsg, ScopeThings, UpdatePipelineIdReason, WebDriverCommandMsg};
 use script_traits::{WindowSizeData, WindowSizeType};
 use serde::{Deserialize, Serialize};
@@ -334,6 +336,16 @@ pub struct Constellation<Message, LTF, STF> {
 
     /// A channel through which messages can be sent to the canvas paint thread.
     canvas_chan: IpcSender<CanvasMsg>,
+
+    /// The routers through which messages are delivered to the message ports
+    /// and broadcast channels of globals, with the pipelines of their globals.
+    message_routers: HashMap<MessageRouterId, (PipelineId, IpcSender<MessageRouterMsg>)>,
+
+    /// The message ports that were not closed yet.
+    message_ports: HashMap<MessagePortId, MessagePortInfo>,
+
+    /// The origins and routers of the globals listening to each broadcast channel, by name.
+    broadcast_channels: HashMap<String, Vec<(ImmutableOrigin, MessageRouterId)>>,
 }
 
 /// State needed to construct a constellation.
@@ -400,6 +412,24 @@ impl WebDriverData {
     }
 }
 
+/// What the constellation knows about a message port.
+struct MessagePortInfo {
+    /// Where messages for the port are delivered.
+    state: MessagePortState,
+    /// The port this port is entangled with, if any.
+    entangled_with: Option<MessagePortId>,
+}
+
+/// Where messages for a message port are delivered.
+enum MessagePortState {
+    /// To the global of the given router.
+    Owned(MessageRouterId),
+    /// Nowhere yet: the port is being transferred, and its messages are held until
+    /// its new global claims it. Messages returned by its former global are inserted
+    /// at the given index, after the ones handed over when the port was shipped.
+    Shipped(VecDeque<Vec<u8>>, usize),
+}
+
 /// When we are running reftests, we save an image to compare against a reference.
 /// This enum gives the possible states of preparing such an image.
 #[derive(Debug, PartialEq)]
@@ -585,6 +615,8 @@ where
 
                 PipelineNamespace::install(PipelineNamespaceId(0));
 
+                let canvas_chan = CanvasPaintThread::start(state.font_cache_thread.clone());
+
                 let mut constellation: Constellation<Message, LTF, STF> = Constellation {
                     script_sender: ipc_script_sender,
                     layout_sender: ipc_layout_sender,
@@ -654,7 +686,10 @@ where
                     ),
                     webgl_threads: state.webgl_threads,
                     webvr_chan: state.webvr_chan,
-                    canvas_chan: CanvasPaintThread::start(),
+                    canvas_chan: canvas_chan,
+                    message_routers: HashMap::new(),
+                    message_ports: HashMap::new(),
+                    broadcast_channels: HashMap::new(),
                 };
 
                 constellation.run();
@@ -1078,6 +1113,13 @@ where
                 self.forward_event(destination_pipeline_id, event);
             },
             FromCompositorMsg::SetCursor(cursor) => self.handle_set_cursor_msg(cursor),
+            FromCompositorMsg::SetRequestInterception(interception) => {
+                // The public and private resource threads share their request interceptor.
+                let msg = net_traits::CoreResourceMsg::SetRequestInterception(interception);
+                if let Err(e) = self.public_resource_threads.send(msg) {
+                    warn!("Sending SetRequestInterception to the resource thread failed ({}).", e);
+                }
+            },
         }
     }
 
@@ -1172,6 +1214,58 @@ where
             FromScriptMsg::PostMessage(browsing_context_id, origin, data) => {
                 self.handle_post_message_msg(browsing_context_id, origin, data);
             },
+            FromScriptMsg::NewPipelineNamespace(sender) => {
+                let namespace_id = self.next_pipeline_namespace_id();
+                if let Err(e) = sender.send(namespace_id) {
+                    warn!("Sending pipeline namespace failed ({:?}).", e);
+                }
+            },
+            FromScriptMsg::NewMessageRouter(router_id, sender) => {
+                self.message_routers
+                    .insert(router_id, (source_pipeline_id, sender));
+            },
+            FromScriptMsg::RemoveMessageRouter(router_id) => {
+                self.handle_remove_message_router(router_id);
+            },
+            FromScriptMsg::NewMessagePort(router_id, port_id) => {
+                self.message_ports.insert(
+                    port_id,
+                    MessagePortInfo {
+                        state: MessagePortState::Owned(router_id),
+                        entangled_with: None,
+                    },
+                );
+            },
+            FromScriptMsg::EntanglePorts(port1, port2) => {
+                self.handle_entangle_ports(port1, port2);
+            },
+            FromScriptMsg::PostPortMessage(port_id, data) => {
+                self.handle_post_port_message(port_id, data);
+            },
+            FromScriptMsg::MessagePortShipped(port_id, pending) => {
+                self.handle_message_port_shipped(port_id, pending);
+            },
+            FromScriptMsg::ReturnPortMessage(port_id, data) => {
+                self.handle_return_port_message(port_id, data);
+            },
+            FromScriptMsg::ClaimMessagePort(router_id, port_id) => {
+                self.handle_claim_message_port(router_id, port_id);
+            },
+            FromScriptMsg::CloseMessagePort(port_id) => {
+                self.handle_close_message_port(port_id);
+            },
+            FromScriptMsg::NewBroadcastChannel(router_id, origin, name) => {
+                self.broadcast_channels
+                    .entry(name)
+                    .or_insert_with(Vec::new)
+                    .push((origin, router_id));
+            },
+            FromScriptMsg::RemoveBroadcastChannel(router_id, origin, name) => {
+                self.handle_remove_broadcast_channel(router_id, origin, name);
+            },
+            FromScriptMsg::Broadcast(router_id, msg) => {
+                self.handle_broadcast(router_id, msg);
+            },
             FromScriptMsg::Focus => {
                 self.handle_focus_msg(source_pipeline_id);
             },
@@ -1253,6 +1347,17 @@ where
                     warn!("Sending reply to get parent info failed ({:?}).", e);
                 }
             },
+            FromScriptMsg::GetAncestorUrls(pipeline_id, sender) => {
+                let mut result = vec![];
+                let mut next = Some(pipeline_id);
+                while let Some(pipeline) = next.and_then(|id| self.pipelines.get(&id)) {
+                    result.push(pipeline.url.clone());
+                    next = pipeline.parent_info;
+                }
+                if let Err(e) = sender.send(result) {
+                    warn!("Sending reply to get ancestor urls failed ({:?}).", e);
+                }
+            },
             FromScriptMsg::GetChildBrowsingContextId(browsing_context_id, index, sender) => {
                 let result = self
                     .browsing_contexts
@@ -1418,6 +1523,10 @@ where
         let (core_sender, core_receiver) = ipc::channel().expect("Failed to create IPC channel!");
         let (storage_sender, storage_receiver) =
             ipc::channel().expect("Failed to create IPC channel!");
+        let (indexeddb_sender, indexeddb_receiver) =
+            ipc::channel().expect("Failed to create IPC channel!");
+        let (cache_storage_sender, cache_storage_receiver) =
+            ipc::channel().expect("Failed to create IPC channel!");
 
         debug!("Exiting core resource threads.");
         if let Err(e) = self
@@ -1447,6 +1556,34 @@ where
             warn!("Exit storage thread failed ({})", e);
         }
 
+        debug!("Exiting IndexedDB threads.");
+        if let Err(e) = self
+            .public_resource_threads
+            .send(IndexedDBThreadMsg::Exit(indexeddb_sender.clone()))
+        {
+            warn!("Exit IndexedDB thread failed ({})", e);
+        }
+        if let Err(e) = self
+            .private_resource_threads
+            .send(IndexedDBThreadMsg::Exit(indexeddb_sender))
+        {
+            warn!("Exit private IndexedDB thread failed ({})", e);
+        }
+
+        debug!("Exiting cache storage threads.");
+        if let Err(e) = self
+            .public_resource_threads
+            .send(CacheStorageThreadMsg::Exit(cache_storage_sender.clone()))
+        {
+            warn!("Exit cache storage thread failed ({})", e);
+        }
+        if let Err(e) = self
+            .private_resource_threads
+            .send(CacheStorageThreadMsg::Exit(cache_storage_sender))
+        {
+            warn!("Exit private cache storage thread failed ({})", e);
+        }
+
         debug!("Exiting bluetooth thread.");
         if let Err(e) = self.bluetooth_thread.send(BluetoothRequest::Exit) {
             warn!("Exit bluetooth thread failed ({})", e);
@@ -1488,6 +1625,16 @@ where
         if let Err(e) = storage_receiver.recv() {
             warn!("Exit storage thread failed ({})", e);
         }
+        for _ in 0..2 {
+            if let Err(e) = indexeddb_receiver.recv() {
+                warn!("Exit IndexedDB thread failed ({})", e);
+            }
+        }
+        for _ in 0..2 {
+            if let Err(e) = cache_storage_receiver.recv() {
+                warn!("Exit cache storage thread failed ({})", e);
+            }
+        }
 
         debug!("Asking compositor to complete shutdown.");
         self.compositor_proxy
@@ -1497,6 +1644,17 @@ where
     fn handle_pipeline_exited(&mut self, pipeline_id: PipelineId) {
         debug!("Pipeline {:?} exited.", pipeline_id);
         self.pipelines.remove(&pipeline_id);
+
+        // The globals of the pipeline, including its workers, are gone.
+        let router_ids: Vec<MessageRouterId> = self
+            .message_routers
+            .iter()
+            .filter(|&(_, &(router_pipeline_id, _))| router_pipeline_id == pipeline_id)
+            .map(|(router_id, _)| *router_id)
+            .collect();
+        for router_id in router_ids {
+            self.handle_remove_message_router(router_id);
+        }
     }
 
     fn handle_send_error(&mut self, pipeline_id: PipelineId, err: IpcError) {
@@ -2572,6 +2730,159 @@ where
         }
     }
 
+    fn handle_remove_message_router(&mut self, router_id: MessageRouterId) {
+        self.message_routers.remove(&router_id);
+        let owned_ports: Vec<MessagePortId> = self
+            .message_ports
+            .iter()
+            .filter_map(|(port_id, info)| match info.state {
+                MessagePortState::Owned(owner) if owner == router_id => Some(*port_id),
+                _ => None,
+            })
+            .collect();
+        for port_id in owned_ports {
+            self.handle_close_message_port(port_id);
+        }
+        for listeners in self.broadcast_channels.values_mut() {
+            listeners.retain(|&(_, listener)| listener != router_id);
+        }
+        self.broadcast_channels
+            .retain(|_, listeners| !listeners.is_empty());
+    }
+
+    fn handle_entangle_ports(&mut self, port1: MessagePortId, port2: MessagePortId) {
+        if !self.message_ports.contains_key(&port1) || !self.message_ports.contains_key(&port2) {
+            return warn!("Entangling unknown ports {} and {}.", port1, port2);
+        }
+        if let Some(info) = self.message_ports.get_mut(&port1) {
+            info.entangled_with = Some(port2);
+        }
+        if let Some(info) = self.message_ports.get_mut(&port2) {
+            info.entangled_with = Some(port1);
+        }
+    }
+
+    fn handle_post_port_message(&mut self, port_id: MessagePortId, data: Vec<u8>) {
+        let target = match self
+            .message_ports
+            .get(&port_id)
+            .and_then(|info| info.entangled_with)
+        {
+            Some(target) => target,
+            // Messages posted through a disentangled port go nowhere.
+            None => return,
+        };
+        self.deliver_port_message(target, data);
+    }
+
+    /// Delivers a message to a port, or holds it if the port is being transferred.
+    fn deliver_port_message(&mut self, port_id: MessagePortId, data: Vec<u8>) {
+        let router_id = match self.message_ports.get_mut(&port_id) {
+            None => return warn!("Message for closed port {}.", port_id),
+            Some(info) => match info.state {
+                MessagePortState::Owned(router_id) => router_id,
+                MessagePortState::Shipped(ref mut buffer, _) => return buffer.push_back(data),
+            },
+        };
+        self.send_to_message_router(router_id, MessageRouterMsg::PortMessage(port_id, data));
+    }
+
+    fn send_to_message_router(&self, router_id: MessageRouterId, msg: MessageRouterMsg) {
+        let result = match self.message_routers.get(&router_id) {
+            Some(&(_, ref sender)) => sender.send(msg),
+            None => return warn!("Message for removed router {}.", router_id),
+        };
+        if let Err(e) = result {
+            warn!("Sending to message router {} failed ({:?}).", router_id, e);
+        }
+    }
+
+    fn handle_message_port_shipped(&mut self, port_id: MessagePortId, pending: Vec<Vec<u8>>) {
+        match self.message_ports.get_mut(&port_id) {
+            Some(info) => {
+                let returned_index = pending.len();
+                info.state = MessagePortState::Shipped(pending.into_iter().collect(), returned_index);
+            },
+            None => warn!("Shipping closed port {}.", port_id),
+        }
+    }
+
+    fn handle_return_port_message(&mut self, port_id: MessagePortId, data: Vec<u8>) {
+        let held = match self.message_ports.get_mut(&port_id) {
+            None => return warn!("Returned message for closed port {}.", port_id),
+            Some(info) => match info.state {
+                MessagePortState::Shipped(ref mut buffer, ref mut returned_index) => {
+                    buffer.insert(*returned_index, data);
+                    *returned_index += 1;
+                    return;
+                },
+                // The port was already claimed, so the message can only be delivered late.
+                MessagePortState::Owned(_) => data,
+            },
+        };
+        self.deliver_port_message(port_id, held);
+    }
+
+    fn handle_claim_message_port(&mut self, router_id: MessageRouterId, port_id: MessagePortId) {
+        let held = match self.message_ports.get_mut(&port_id) {
+            None => return warn!("Claiming closed port {}.", port_id),
+            Some(info) => match replace(&mut info.state, MessagePortState::Owned(router_id)) {
+                MessagePortState::Shipped(buffer, _) => buffer,
+                MessagePortState::Owned(_) => {
+                    return warn!("Claiming port {} that was not shipped.", port_id)
+                },
+            },
+        };
+        for data in held {
+            self.send_to_message_router(router_id, MessageRouterMsg::PortMessage(port_id, data));
+        }
+    }
+
+    fn handle_close_message_port(&mut self, port_id: MessagePortId) {
+        let entangled_with = match self.message_ports.remove(&port_id) {
+            Some(info) => info.entangled_with,
+            None => return,
+        };
+        if let Some(other) = entangled_with.and_then(|other| self.message_ports.get_mut(&other)) {
+            other.entangled_with = None;
+        }
+    }
+
+    fn handle_remove_broadcast_channel(
+        &mut self,
+        router_id: MessageRouterId,
+        origin: ImmutableOrigin,
+        name: String,
+    ) {
+        let now_empty = match self.broadcast_channels.get_mut(&name) {
+            Some(listeners) => {
+                if let Some(index) = listeners
+                    .iter()
+                    .position(|&(ref o, r)| r == router_id && *o == origin)
+                {
+                    listeners.remove(index);
+                }
+                listeners.is_empty()
+            },
+            None => return warn!("Removing unknown broadcast channel {}.", name),
+        };
+        if now_empty {
+            self.broadcast_channels.remove(&name);
+        }
+    }
+
+    fn handle_broadcast(&self, router_id: MessageRouterId, msg: BroadcastMsg) {
+        let listeners = match self.broadcast_channels.get(&msg.channel_name) {
+            Some(listeners) => listeners,
+            None => return,
+        };
+        for &(ref origin, listener) in listeners {
+            if listener != router_id && *origin == msg.origin {
+                self.send_to_message_router(listener, MessageRouterMsg::Broadcast(msg.clone()));
+            }
+        }
+    }
+
     fn handle_get_pipeline(
         &mut self,
         browsing_context_id: BrowsingContextId,
@@ -2806,6 +3117,19 @@ where
                 self.compositor_proxy
                     .send(ToCompositorMsg::CreatePng(reply));
             },
+            WebDriverCommandMsg::MouseButtonAction(_, mouse_event_type, mouse_button, x, y) => {
+                // Go through the compositor so that the event is hit tested like real user input.
+                self.compositor_proxy.send(ToCompositorMsg::WebDriverMouseButtonEvent(
+                    mouse_event_type,
+                    mouse_button,
+                    x,
+                    y,
+                ));
+            },
+            WebDriverCommandMsg::MouseMoveAction(_, x, y) => {
+                self.compositor_proxy
+                    .send(ToCompositorMsg::WebDriverMouseMoveEvent(x, y));
+            },
         }
     }
 
@@ -9,7 +9,9 @@
 use actor::{Actor, ActorMessageStatus, ActorRegistry};
 use devtools_traits::HttpRequest as DevtoolsHttpRequest;
 use devtools_traits::HttpResponse as DevtoolsHttpResponse;
-use hyper::header::{ContentType, Cookie};
+use har;
+use hyper::Url;
+use hyper::header::{ContentType, Cookie, Location};
 use hyper::header::Headers;
 use hyper::http::RawStatus;
 use hyper::method::Method;
@@ -466,4 +468,104 @@ impl NetworkEventActor {
     pub fn total_time(&self) -> u64 {
         self.request.connect_time + self.request.send_time
     }
+
+    /// The HAR entry describing this request and its response, on the page `pageref`.
+    pub fn har_entry(&self, pageref: &str) -> har::Entry {
+        let query_string = Url::parse(&self.request.url).ok().map_or(vec![], |url| {
+            url.query_pairs().map(|(name, value)| {
+                har::QueryParameter { name: name.into_owned(), value: value.into_owned() }
+            }).collect()
+        });
+        let request_cookies = self.request.headers.get_raw("cookie").map_or(vec![], |values| {
+            values.iter()
+                .flat_map(|value| String::from_utf8_lossy(value).split(';').map(str::to_owned).collect::<Vec<_>>())
+                .filter_map(|cookie| har_cookie(&cookie))
+                .collect()
+        });
+        let post_data = self.request.body.as_ref().map(|body| {
+            har::PostData {
+                mimeType: mime_type(&self.request.headers),
+                text: String::from_utf8_lossy(body).into_owned(),
+            }
+        });
+        let request = har::Request {
+            method: format!("{}", self.request.method),
+            url: self.request.url.clone(),
+            httpVersion: "HTTP/1.1".to_owned(),
+            cookies: request_cookies,
+            headers: har_headers(&self.request.headers),
+            queryString: query_string,
+            postData: post_data,
+            headersSize: -1,
+            bodySize: self.request.body.as_ref().map_or(0, |body| body.len() as i64),
+        };
+
+        let empty_headers = Headers::new();
+        let response_headers = self.response.headers.as_ref().unwrap_or(&empty_headers);
+        let (status, status_text) = self.response.status.as_ref()
+            .map_or((0, String::new()), |&RawStatus(code, ref text)| (code, text.clone().into_owned()));
+        let response_cookies = response_headers.get_raw("set-cookie").map_or(vec![], |values| {
+            values.iter()
+                .filter_map(|value| har_cookie(String::from_utf8_lossy(value).split(';').next().unwrap_or("")))
+                .collect()
+        });
+        let body_size = self.response.body.as_ref().map_or(-1, |body| body.len() as i64);
+        let response = har::Response {
+            status: status,
+            statusText: status_text,
+            httpVersion: "HTTP/1.1".to_owned(),
+            cookies: response_cookies,
+            headers: har_headers(response_headers),
+            content: har::Content {
+                size: body_size,
+                mimeType: mime_type(response_headers),
+                text: self.response.body.as_ref().and_then(|body| String::from_utf8(body.clone()).ok()),
+            },
+            redirectURL: response_headers.get::<Location>().map_or(String::new(), |location| location.to_string()),
+            headersSize: -1,
+            bodySize: body_size,
+        };
+
+        har::Entry {
+            pageref: pageref.to_owned(),
+            startedDateTime: format!("{}", self.request.startedDateTime.rfc3339()),
+            time: self.total_time(),
+            request: request,
+            response: response,
+            cache: har::Cache {},
+            // TODO: Record the time spent blocked, resolving, waiting and receiving.
+            timings: har::Timings {
+                blocked: -1,
+                dns: -1,
+                connect: self.request.connect_time as i64,
+                send: self.request.send_time,
+                wait: 0,
+                receive: 0,
+                ssl: -1,
+            },
+        }
+    }
+}
+
+fn har_headers(headers: &Headers) -> Vec<har::Header> {
+    headers.iter().map(|header| {
+        har::Header { name: header.name().to_owned(), value: header.value_string() }
+    }).collect()
+}
+
+fn har_cookie(cookie: &str) -> Option<har::Cookie> {
+    let mut parts = cookie.splitn(2, '=');
+    let name = parts.next()?.trim();
+    if name.is_empty() {
+        return None;
+    }
+    let value = parts.next().unwrap_or("").trim();
+    Some(har::Cookie { name: name.to_owned(), value: value.to_owned() })
+}
+
+fn mime_type(headers: &Headers) -> String {
+    match headers.get() {
+        Some(&ContentType(ref mime)) => mime.to_string(),
+        None => "".to_owned(),
+    }
 }
@@ -10,6 +10,7 @@
 use actor::{Actor, ActorMessageStatus, ActorRegistry};
 use actors::console::ConsoleActor;
 use devtools_traits::DevtoolScriptControlMsg::{self, WantsLiveNotifications};
+use har::Har;
 use protocol::JsonPacketStream;
 use serde_json::{Map, Value};
 use std::net::TcpStream;
@@ -54,6 +55,12 @@ struct FrameMsg {
     parentID: u32,
 }
 
+#[derive(Serialize)]
+struct ExportHarReply {
+    from: String,
+    har: Har,
+}
+
 #[derive(Serialize)]
 pub struct TabActorMsg {
     actor: String,
@@ -77,6 +84,8 @@ pub struct TabActor {
     pub profiler: String,
     pub performance: String,
     pub thread: String,
+    /// The names of the network event actors of the requests made by this tab.
+    pub network_events: Vec<String>,
 }
 
 impl Actor for TabActor {
@@ -147,6 +156,15 @@ impl Actor for TabActor {
                 ActorMessageStatus::Processed
             }
 
+            "exportHar" => {
+                let msg = ExportHarReply {
+                    from: self.name(),
+                    har: Har::new(registry, &[self]),
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
             _ => ActorMessageStatus::Ignored
         })
     }
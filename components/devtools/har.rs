@@ -0,0 +1,382 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Export of the network activity recorded by the network event actors as an
+//! [HTTP Archive](http://www.softwareishard.com/blog/har-12-spec/) (HAR 1.2).
+
+use actor::ActorRegistry;
+use actors::network_event::NetworkEventActor;
+use actors::root::RootActor;
+use actors::tab::TabActor;
+use serde_json;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use time;
+
+/// The minimum time between two writes of the archive by a `HarWriter`, in milliseconds.
+const WRITE_INTERVAL_MS: u64 = 1000;
+
+#[derive(Serialize)]
+pub struct Har {
+    log: Log,
+}
+
+#[derive(Serialize)]
+struct Log {
+    version: String,
+    creator: Creator,
+    pages: Vec<Page>,
+    entries: Vec<Entry>,
+}
+
+#[derive(Serialize)]
+struct Creator {
+    name: String,
+    version: String,
+}
+
+#[derive(Serialize)]
+struct Page {
+    startedDateTime: String,
+    id: String,
+    title: String,
+    pageTimings: PageTimings,
+}
+
+#[derive(Serialize)]
+struct PageTimings {
+    onContentLoad: i64,
+    onLoad: i64,
+}
+
+#[derive(Serialize)]
+pub struct Entry {
+    pub pageref: String,
+    pub startedDateTime: String,
+    pub time: u64,
+    pub request: Request,
+    pub response: Response,
+    pub cache: Cache,
+    pub timings: Timings,
+}
+
+#[derive(Serialize)]
+pub struct Request {
+    pub method: String,
+    pub url: String,
+    pub httpVersion: String,
+    pub cookies: Vec<Cookie>,
+    pub headers: Vec<Header>,
+    pub queryString: Vec<QueryParameter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub postData: Option<PostData>,
+    pub headersSize: i64,
+    pub bodySize: i64,
+}
+
+#[derive(Serialize)]
+pub struct Response {
+    pub status: u16,
+    pub statusText: String,
+    pub httpVersion: String,
+    pub cookies: Vec<Cookie>,
+    pub headers: Vec<Header>,
+    pub content: Content,
+    pub redirectURL: String,
+    pub headersSize: i64,
+    pub bodySize: i64,
+}
+
+#[derive(Serialize)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Serialize)]
+pub struct Header {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Serialize)]
+pub struct QueryParameter {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Serialize)]
+pub struct PostData {
+    pub mimeType: String,
+    pub text: String,
+}
+
+#[derive(Serialize)]
+pub struct Content {
+    pub size: i64,
+    pub mimeType: String,
+    /// Only present for bodies which are valid UTF-8, since we have no base64 encoder at hand.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct Cache {}
+
+#[derive(Serialize)]
+pub struct Timings {
+    pub blocked: i64,
+    pub dns: i64,
+    pub connect: i64,
+    pub send: u64,
+    pub wait: u64,
+    pub receive: u64,
+    pub ssl: i64,
+}
+
+impl Har {
+    /// The archive of the network activity of the given tabs, with one page per tab.
+    pub fn new(registry: &ActorRegistry, tabs: &[&TabActor]) -> Har {
+        let mut pages = vec![];
+        let mut entries = vec![];
+        for tab in tabs {
+            let events = tab.network_events.iter().map(|name| registry.find::<NetworkEventActor>(name));
+            let first_entry = entries.len();
+            entries.extend(events.map(|event| event.har_entry(&tab.name)));
+            let started = entries.get(first_entry)
+                .map_or_else(|| time::now().rfc3339().to_string(), |entry: &Entry| entry.startedDateTime.clone());
+            pages.push(Page {
+                startedDateTime: started,
+                id: tab.name.clone(),
+                title: tab.title.clone(),
+                pageTimings: PageTimings {
+                    onContentLoad: -1,
+                    onLoad: -1,
+                },
+            });
+        }
+        Har {
+            log: Log {
+                version: "1.2".to_owned(),
+                creator: Creator {
+                    name: "Servo".to_owned(),
+                    version: env!("CARGO_PKG_VERSION").to_owned(),
+                },
+                pages: pages,
+                entries: entries,
+            },
+        }
+    }
+
+    /// The archive of the network activity of every tab known to the devtools server.
+    pub fn of_all_tabs(registry: &ActorRegistry) -> Har {
+        let root = registry.find::<RootActor>("root");
+        let tabs: Vec<&TabActor> = root.tabs.iter().map(|name| registry.find::<TabActor>(name)).collect();
+        Har::new(registry, &tabs)
+    }
+
+    /// Writes the archive to `path`, through a temporary file which then replaces it,
+    /// so that `path` never holds a partially written archive.
+    pub fn write_to(&self, path: &Path) -> io::Result<()> {
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        let temp_path = PathBuf::from(temp_path);
+        {
+            let mut file = BufWriter::new(File::create(&temp_path)?);
+            serde_json::to_writer_pretty(&mut file, self)
+                .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+            file.flush()?;
+        }
+        fs::rename(&temp_path, path)
+    }
+}
+
+/// Keeps the archive of the network activity of every tab written to a file.
+///
+/// A page can make many requests, so the archive is written at most once every
+/// `WRITE_INTERVAL_MS` milliseconds rather than after every response.
+pub struct HarWriter {
+    path: PathBuf,
+    last_write: Option<Instant>,
+    pending: bool,
+}
+
+impl HarWriter {
+    pub fn new(path: PathBuf) -> HarWriter {
+        HarWriter {
+            path: path,
+            last_write: None,
+            pending: false,
+        }
+    }
+
+    /// Notes that the network activity changed since the archive was last written.
+    pub fn note_change(&mut self) {
+        self.pending = true;
+    }
+
+    /// How long until the changes that weren't written yet are due to be, if there are any.
+    pub fn time_until_write(&self) -> Option<Duration> {
+        if !self.pending {
+            return None;
+        }
+        let interval = Duration::from_millis(WRITE_INTERVAL_MS);
+        Some(self.last_write.map_or(Duration::from_millis(0), |last_write| {
+            interval.checked_sub(last_write.elapsed()).unwrap_or(Duration::from_millis(0))
+        }))
+    }
+
+    /// Writes the archive if there are changes which are due to be written.
+    pub fn write_if_due(&mut self, registry: &ActorRegistry) {
+        if self.time_until_write() == Some(Duration::from_millis(0)) {
+            self.write(registry);
+        }
+    }
+
+    /// Writes the archive now.
+    pub fn write(&mut self, registry: &ActorRegistry) {
+        self.pending = false;
+        self.last_write = Some(Instant::now());
+        if let Err(error) = Har::of_all_tabs(registry).write_to(&self.path) {
+            warn!("Failed to write the HTTP Archive to {:?} ({})", self.path, error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actor::{Actor, ActorRegistry};
+    use actors::network_event::NetworkEventActor;
+    use actors::tab::TabActor;
+    use devtools_traits::{HttpRequest, HttpResponse};
+    use hyper::header::{ContentType, Headers};
+    use hyper::method::Method;
+    use msg::constellation_msg::TEST_PIPELINE_ID;
+    use protocol::JsonPacketStream;
+    use serde_json::{self, Map, Value};
+    use servo_url::ServoUrl;
+    use std::env;
+    use std::fs::{self, File};
+    use std::net::{TcpListener, TcpStream};
+    use super::Har;
+    use time;
+
+    fn tab(name: &str, network_events: Vec<String>) -> TabActor {
+        TabActor {
+            name: name.to_owned(),
+            title: "Title".to_owned(),
+            url: "http://example.com/".to_owned(),
+            console: String::new(),
+            inspector: String::new(),
+            timeline: String::new(),
+            profiler: String::new(),
+            performance: String::new(),
+            thread: String::new(),
+            network_events: network_events,
+        }
+    }
+
+    fn network_event(registry: &mut ActorRegistry, url: &str, body: &[u8]) -> String {
+        let name = registry.new_name("netevent");
+        let mut actor = NetworkEventActor::new(name.clone());
+        actor.add_request(HttpRequest {
+            url: ServoUrl::parse(url).unwrap(),
+            method: Method::Get,
+            headers: Headers::new(),
+            body: None,
+            pipeline_id: TEST_PIPELINE_ID,
+            startedDateTime: time::now(),
+            timeStamp: time::get_time().sec,
+            connect_time: 1,
+            send_time: 2,
+            is_xhr: false,
+        });
+        let mut headers = Headers::new();
+        headers.set(ContentType::plaintext());
+        actor.add_response(HttpResponse {
+            headers: Some(headers),
+            status: Some((200, b"OK".to_vec())),
+            body: Some(body.to_vec()),
+            pipeline_id: TEST_PIPELINE_ID,
+        });
+        registry.register(Box::new(actor));
+        name
+    }
+
+    #[test]
+    fn test_har_has_a_page_per_tab_and_an_entry_per_request() {
+        let mut registry = ActorRegistry::new();
+        let first = network_event(&mut registry, "http://example.com/?a=1", b"hello");
+        let second = network_event(&mut registry, "http://example.com/style.css", b"body {}");
+        let tab_with_events = tab("tab1", vec![first, second]);
+        let tab_without_events = tab("tab2", vec![]);
+
+        let har = Har::new(&registry, &[&tab_with_events, &tab_without_events]);
+        let har = serde_json::to_value(&har).unwrap();
+        let log = &har["log"];
+        assert_eq!(log["version"], "1.2");
+        assert_eq!(log["creator"]["name"], "Servo");
+
+        let pages = log["pages"].as_array().unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0]["id"], "tab1");
+        assert_eq!(pages[0]["title"], "Title");
+        assert_eq!(pages[1]["id"], "tab2");
+
+        let entries = log["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(pages[0]["startedDateTime"], entries[0]["startedDateTime"]);
+        let entry = &entries[0];
+        assert_eq!(entry["pageref"], "tab1");
+        assert_eq!(entry["time"], 3);
+        assert_eq!(entry["request"]["method"], "GET");
+        assert_eq!(entry["request"]["url"], "http://example.com/?a=1");
+        assert_eq!(entry["request"]["queryString"][0]["name"], "a");
+        assert_eq!(entry["request"]["queryString"][0]["value"], "1");
+        assert_eq!(entry["response"]["status"], 200);
+        assert_eq!(entry["response"]["statusText"], "OK");
+        assert_eq!(entry["response"]["content"]["size"], 5);
+        assert_eq!(entry["response"]["content"]["text"], "hello");
+        assert_eq!(entries[1]["request"]["url"], "http://example.com/style.css");
+    }
+
+    #[test]
+    fn test_export_har_replies_with_the_archive_of_the_tab() {
+        let mut registry = ActorRegistry::new();
+        let event = network_event(&mut registry, "http://example.com/", b"hello");
+        let tab = tab("tab1", vec![event]);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+        tab.handle_message(&registry, "exportHar", &Map::new(), &mut server).unwrap();
+
+        let reply: Value = client.read_json_packet().unwrap().unwrap();
+        assert_eq!(reply["from"], "tab1");
+        assert_eq!(reply["har"]["log"]["pages"][0]["id"], "tab1");
+        let entries = reply["har"]["log"]["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["request"]["url"], "http://example.com/");
+    }
+
+    #[test]
+    fn test_har_replaces_the_file_it_is_written_to() {
+        let mut registry = ActorRegistry::new();
+        let event = network_event(&mut registry, "http://example.com/", b"hello");
+        let tab = tab("tab1", vec![event]);
+        let path = env::temp_dir().join(format!("servo-har-test-{}.har", time::precise_time_ns()));
+
+        Har::new(&registry, &[]).write_to(&path).unwrap();
+        Har::new(&registry, &[&tab]).write_to(&path).unwrap();
+
+        let har: Value = serde_json::from_reader(File::open(&path).unwrap()).unwrap();
+        assert_eq!(har["log"]["entries"].as_array().unwrap().len(), 1);
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        assert!(fs::metadata(&temp_path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -21,6 +21,8 @@ extern crate log;
 extern crate msg;
 #[macro_use] extern crate serde;
 extern crate serde_json;
+#[cfg(test)]
+extern crate servo_url;
 extern crate time;
 
 use actor::{Actor, ActorRegistry};
@@ -38,6 +40,7 @@ use actors::worker::WorkerActor;
 use devtools_traits::{ChromeToDevtoolsControlMsg, ConsoleMessage, DevtoolsControlMsg};
 use devtools_traits::{DevtoolScriptControlMsg, DevtoolsPageInfo, LogLevel, NetworkEvent};
 use devtools_traits::{ScriptToDevtoolsControlMsg, WorkerId};
+use har::HarWriter;
 use ipc_channel::ipc::IpcSender;
 use msg::constellation_msg::PipelineId;
 use protocol::JsonPacketStream;
@@ -46,8 +49,9 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use std::sync::mpsc::{Receiver, Sender, channel};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, channel};
 use std::thread;
 use time::precise_time_ns;
 
@@ -68,6 +72,7 @@ mod actors {
     pub mod timeline;
     pub mod worker;
 }
+mod har;
 mod protocol;
 
 #[derive(Serialize)]
@@ -123,13 +128,14 @@ struct ResponseStartUpdateMsg {
     response: ResponseStartMsg,
 }
 
-/// Spin up a devtools server that listens for connections on the specified port.
-pub fn start_server(port: u16) -> Sender<DevtoolsControlMsg> {
+/// Spin up a devtools server that listens for connections on the specified port, if any,
+/// and writes the network activity of every tab as an HTTP Archive to `har_output`, if any.
+pub fn start_server(port: Option<u16>, har_output: Option<PathBuf>) -> Sender<DevtoolsControlMsg> {
     let (sender, receiver) = channel();
     {
         let sender = sender.clone();
         thread::Builder::new().name("Devtools".to_owned()).spawn(move || {
-            run_server(sender, receiver, port)
+            run_server(sender, receiver, port, har_output)
         }).expect("Thread spawning failed");
     }
     sender
@@ -137,9 +143,8 @@ pub fn start_server(port: u16) -> Sender<DevtoolsControlMsg> {
 
 fn run_server(sender: Sender<DevtoolsControlMsg>,
               receiver: Receiver<DevtoolsControlMsg>,
-              port: u16) {
-    let listener = TcpListener::bind(&("127.0.0.1", port)).unwrap();
-
+              port: Option<u16>,
+              har_output: Option<PathBuf>) {
     let mut registry = ActorRegistry::new();
 
     let root = Box::new(RootActor {
@@ -246,6 +251,7 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
                 profiler: profiler.name(),
                 performance: performance.name(),
                 thread: thread.name(),
+                network_events: vec![],
             };
 
             let root = actors.find_mut::<RootActor>("root");
@@ -340,6 +346,14 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
         };
         let netevent_actor_name = find_network_event_actor(actors.clone(), actor_requests, request_id.clone());
         let mut actors = actors.lock().unwrap();
+        if let NetworkEvent::HttpRequest(_) = network_event {
+            if let Some(tab_actor_name) = actor_pipelines.get(&pipeline_id) {
+                let tab_actor = actors.find_mut::<TabActor>(tab_actor_name);
+                if !tab_actor.network_events.contains(&netevent_actor_name) {
+                    tab_actor.network_events.push(netevent_actor_name.clone());
+                }
+            }
+        }
         let actor = actors.find_mut::<NetworkEventActor>(&netevent_actor_name);
 
         match network_event {
@@ -466,17 +480,39 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
         }
     }
 
-    let sender_clone = sender.clone();
-    thread::Builder::new().name("DevtoolsClientAcceptor".to_owned()).spawn(move || {
-        // accept connections and process them, spawning a new thread for each one
-        for stream in listener.incoming() {
-            // connection succeeded
-            sender_clone.send(DevtoolsControlMsg::FromChrome(
-                    ChromeToDevtoolsControlMsg::AddClient(stream.unwrap()))).unwrap();
-        }
-    }).expect("Thread spawning failed");
+    if let Some(port) = port {
+        let listener = TcpListener::bind(&("127.0.0.1", port)).unwrap();
+        let sender_clone = sender.clone();
+        thread::Builder::new().name("DevtoolsClientAcceptor".to_owned()).spawn(move || {
+            // accept connections and process them, spawning a new thread for each one
+            for stream in listener.incoming() {
+                // connection succeeded
+                sender_clone.send(DevtoolsControlMsg::FromChrome(
+                        ChromeToDevtoolsControlMsg::AddClient(stream.unwrap()))).unwrap();
+            }
+        }).expect("Thread spawning failed");
+    }
 
-    while let Ok(msg) = receiver.recv() {
+    let mut har_writer = har_output.map(HarWriter::new);
+    loop {
+        // Wake up in time to write the changes to the archive, if any are waiting.
+        let timeout = har_writer.as_ref().and_then(HarWriter::time_until_write);
+        let msg = match timeout {
+            Some(timeout) => match receiver.recv_timeout(timeout) {
+                Ok(msg) => msg,
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Some(ref mut har_writer) = har_writer {
+                        har_writer.write(&actors.lock().unwrap());
+                    }
+                    continue;
+                },
+                Err(RecvTimeoutError::Disconnected) => break,
+            },
+            None => match receiver.recv() {
+                Ok(msg) => msg,
+                Err(_) => break,
+            },
+        };
         match msg {
             DevtoolsControlMsg::FromChrome(ChromeToDevtoolsControlMsg::AddClient(stream)) => {
                 let actors = actors.clone();
@@ -519,16 +555,28 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
                     connections.push(stream.try_clone().unwrap());
                 }
 
-                let pipeline_id = match network_event {
-                    NetworkEvent::HttpResponse(ref response) => response.pipeline_id,
-                    NetworkEvent::HttpRequest(ref request) => request.pipeline_id,
+                let (pipeline_id, is_response) = match network_event {
+                    NetworkEvent::HttpResponse(ref response) => (response.pipeline_id, true),
+                    NetworkEvent::HttpRequest(ref request) => (request.pipeline_id, false),
                 };
                 handle_network_event(actors.clone(), connections, &actor_pipelines, &mut actor_requests,
                                      &actor_workers, pipeline_id, request_id, network_event);
+
+                // The devtools thread is not waited for on shutdown, so keep the archive up
+                // to date rather than only writing it when exiting.
+                if let Some(ref mut har_writer) = har_writer {
+                    if is_response {
+                        har_writer.note_change();
+                        har_writer.write_if_due(&actors.lock().unwrap());
+                    }
+                }
             },
             DevtoolsControlMsg::FromChrome(ChromeToDevtoolsControlMsg::ServerExitMsg) => break
         }
     }
+    if let Some(ref mut har_writer) = har_writer {
+        har_writer.write(&actors.lock().unwrap());
+    }
     for connection in &mut accepted_connections {
         let _ = connection.shutdown(Shutdown::Both);
     }
@@ -14,10 +14,12 @@ extern crate servo_url;
 extern crate style_traits;
 extern crate webrender_api;
 
+pub mod request_interception;
 pub mod resources;
 
 use ipc_channel::ipc::IpcSender;
 use msg::constellation_msg::{InputMethodType, Key, KeyModifiers, KeyState, TopLevelBrowsingContextId};
+use request_interception::{InterceptedRequest, InterceptionAction};
 use servo_url::ServoUrl;
 use std::fmt::{Debug, Error, Formatter};
 use std::sync::mpsc::{Receiver, Sender};
@@ -114,6 +116,8 @@ pub enum EmbedderMsg {
     ShowIME(InputMethodType),
     /// Request to hide the IME when the editable element is blurred.
     HideIME,
+    /// Ask what to do with a request about to be fetched, when no interception rule matches it.
+    InterceptRequest(InterceptedRequest, IpcSender<InterceptionAction>),
     /// Servo has shut down
     Shutdown,
 }
@@ -142,6 +146,7 @@ impl Debug for EmbedderMsg {
             EmbedderMsg::SelectFiles(..) => write!(f, "SelectFiles"),
             EmbedderMsg::ShowIME(..) => write!(f, "ShowIME"),
             EmbedderMsg::HideIME => write!(f, "HideIME"),
+            EmbedderMsg::InterceptRequest(..) => write!(f, "InterceptRequest"),
             EmbedderMsg::Shutdown => write!(f, "Shutdown"),
         }
     }
@@ -0,0 +1,68 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Types that let an embedder observe and rewrite the requests made by the fetch
+//! pipeline before they go out, e.g. to run pages against a local map of fixtures.
+
+use servo_url::ServoUrl;
+
+/// A header list, as (name, value) pairs.
+pub type InterceptedHeaders = Vec<(String, Vec<u8>)>;
+
+/// A request about to be fetched, as presented to the embedder.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct InterceptedRequest {
+    pub url: ServoUrl,
+    pub method: String,
+    pub headers: InterceptedHeaders,
+}
+
+/// A response supplied in place of the one the network would have provided.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SyntheticResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub headers: InterceptedHeaders,
+    pub body: Vec<u8>,
+}
+
+/// What to do with an intercepted request.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum InterceptionAction {
+    /// Fetch the request as usual.
+    Continue,
+    /// Fail the request with a network error.
+    Block,
+    /// Fetch the given URL instead.
+    Redirect(ServoUrl),
+    /// Do not go to the network, and use the given response instead.
+    Respond(SyntheticResponse),
+    /// Set the given headers on the request, replacing any with the same name, and fetch it.
+    SetHeaders(InterceptedHeaders),
+}
+
+/// Applies `action` to every request whose URL starts with `url_prefix`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct InterceptionRule {
+    pub url_prefix: String,
+    pub action: InterceptionAction,
+}
+
+/// The interception configuration of the fetch pipeline.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct RequestInterception {
+    /// The rules to apply to each request, the first matching one winning.
+    pub rules: Vec<InterceptionRule>,
+    /// Whether to send an `EmbedderMsg::InterceptRequest` for requests that no rule matches.
+    pub ask_embedder: bool,
+}
+
+impl RequestInterception {
+    /// The action of the first rule matching `url`, if any.
+    pub fn matching_action(&self, url: &ServoUrl) -> Option<&InterceptionAction> {
+        self.rules.iter()
+            .find(|rule| url.as_str().starts_with(&rule.url_prefix))
+            .map(|rule| &rule.action)
+    }
+}
@@ -88,14 +88,24 @@ impl FontTemplates {
     }
 
     pub fn add_template(&mut self, identifier: Atom, maybe_data: Option<Vec<u8>>) {
+        self.try_add_template(identifier, maybe_data);
+    }
+
+    /// Adds a template for the given font, returning whether this family now
+    /// has a template for it.
+    pub fn try_add_template(&mut self, identifier: Atom, maybe_data: Option<Vec<u8>>) -> bool {
         for template in &self.templates {
             if *template.identifier() == identifier {
-                return;
+                return true;
             }
         }
 
-        if let Ok(template) = FontTemplate::new(identifier, maybe_data) {
-            self.templates.push(template);
+        match FontTemplate::new(identifier, maybe_data) {
+            Ok(template) => {
+                self.templates.push(template);
+                true
+            }
+            Err(_) => false,
         }
     }
 }
@@ -105,8 +115,9 @@ impl FontTemplates {
 pub enum Command {
     GetFontTemplate(FontTemplateDescriptor, FontFamilyDescriptor, IpcSender<Reply>),
     GetFontInstance(webrender_api::FontKey, Au, IpcSender<webrender_api::FontInstanceKey>),
-    AddWebFont(LowercaseString, EffectiveSources, IpcSender<()>),
-    AddDownloadedWebFont(LowercaseString, ServoUrl, Vec<u8>, IpcSender<()>),
+    AddWebFont(LowercaseString, EffectiveSources, IpcSender<bool>),
+    AddWebFontData(LowercaseString, Vec<u8>, IpcSender<bool>),
+    AddDownloadedWebFont(LowercaseString, ServoUrl, Vec<u8>, EffectiveSources, IpcSender<bool>),
     Exit(IpcSender<()>),
     Ping,
 }
@@ -130,6 +141,9 @@ struct FontCache {
     webrender_api: webrender_api::RenderApi,
     webrender_fonts: HashMap<Atom, webrender_api::FontKey>,
     font_instances: HashMap<(webrender_api::FontKey, Au), webrender_api::FontInstanceKey>,
+    /// The number of web fonts that have been added from raw font data, used
+    /// to give each of them a unique identifier.
+    web_font_data_count: usize,
 }
 
 fn populate_generic_fonts() -> HashMap<FontFamilyName, LowercaseString> {
@@ -193,10 +207,20 @@ impl FontCache {
                 Command::AddWebFont(family_name, sources, result) => {
                     self.handle_add_web_font(family_name, sources, result);
                 }
-                Command::AddDownloadedWebFont(family_name, url, bytes, result) => {
-                    let templates = &mut self.web_families.get_mut(&family_name).unwrap();
-                    templates.add_template(Atom::from(url.to_string()), Some(bytes));
-                    drop(result.send(()));
+                Command::AddWebFontData(family_name, bytes, result) => {
+                    self.handle_add_web_font_data(family_name, bytes, result);
+                }
+                Command::AddDownloadedWebFont(family_name, url, bytes, sources, result) => {
+                    let added = {
+                        let templates = &mut self.web_families.get_mut(&family_name).unwrap();
+                        templates.try_add_template(Atom::from(url.to_string()), Some(bytes))
+                    };
+                    if added {
+                        drop(result.send(true));
+                    } else {
+                        // The font couldn't be used, try the next source.
+                        self.handle_add_web_font(family_name, sources, result);
+                    }
                 }
                 Command::Ping => (),
                 Command::Exit(result) => {
@@ -210,11 +234,11 @@ impl FontCache {
     fn handle_add_web_font(&mut self,
                            family_name: LowercaseString,
                            mut sources: EffectiveSources,
-                           sender: IpcSender<()>) {
+                           sender: IpcSender<bool>) {
         let src = if let Some(src) = sources.next() {
             src
         } else {
-            sender.send(()).unwrap();
+            sender.send(false).unwrap();
             return;
         };
 
@@ -228,7 +252,11 @@ impl FontCache {
                 // https://drafts.csswg.org/css-fonts/#font-fetching-requirements
                 let url = match url_source.url.url() {
                     Some(url) => url.clone(),
-                    None => return,
+                    None => {
+                        let msg = Command::AddWebFont(family_name, sources, sender);
+                        self.channel_to_self.send(msg).unwrap();
+                        return;
+                    }
                 };
 
                 let request = RequestInit {
@@ -246,7 +274,8 @@ impl FontCache {
                 fetch_async(request, &self.core_resource_thread, move |response| {
                     match response {
                         FetchResponseMsg::ProcessRequestBody |
-                        FetchResponseMsg::ProcessRequestEOF => (),
+                        FetchResponseMsg::ProcessRequestEOF |
+                        FetchResponseMsg::ProcessCspViolations(_) => (),
                         FetchResponseMsg::ProcessResponse(meta_result) => {
                             trace!("@font-face {} metadata ok={:?}", family_name, meta_result.is_ok());
                             *response_valid.lock().unwrap() = meta_result.is_ok();
@@ -281,6 +310,7 @@ impl FontCache {
                                 Command::AddDownloadedWebFont(family_name.clone(),
                                                               url.clone(),
                                                               bytes,
+                                                              sources.clone(),
                                                               sender.clone());
                             channel_to_self.send(command).unwrap();
                         }
@@ -296,7 +326,7 @@ impl FontCache {
                     templates.add_template(Atom::from(&*path), None);
                 });
                 if found {
-                    sender.send(()).unwrap();
+                    sender.send(true).unwrap();
                 } else {
                     let msg = Command::AddWebFont(family_name, sources, sender);
                     self.channel_to_self.send(msg).unwrap();
@@ -305,6 +335,25 @@ impl FontCache {
         }
     }
 
+    fn handle_add_web_font_data(&mut self,
+                                family_name: LowercaseString,
+                                bytes: Vec<u8>,
+                                sender: IpcSender<bool>) {
+        let bytes = match fontsan::process(&bytes) {
+            Ok(san) => san,
+            Err(_) => {
+                debug!("Sanitiser rejected web font data: family={}", family_name);
+                drop(sender.send(false));
+                return;
+            }
+        };
+
+        self.web_font_data_count += 1;
+        let identifier = Atom::from(format!("webfont-data:{}:{}", family_name, self.web_font_data_count));
+        let templates = self.web_families.entry(family_name).or_insert_with(FontTemplates::new);
+        drop(sender.send(templates.try_add_template(identifier, Some(bytes))));
+    }
+
     fn refresh_local_families(&mut self) {
         self.local_families.clear();
         for_each_available_family(|family_name| {
@@ -435,6 +484,7 @@ impl FontCacheThread {
                 webrender_api,
                 webrender_fonts: HashMap::new(),
                 font_instances: HashMap::new(),
+                web_font_data_count: 0,
             };
 
             cache.refresh_local_families();
@@ -446,10 +496,16 @@ impl FontCacheThread {
         }
     }
 
-    pub fn add_web_font(&self, family: FamilyName, sources: EffectiveSources, sender: IpcSender<()>) {
+    pub fn add_web_font(&self, family: FamilyName, sources: EffectiveSources, sender: IpcSender<bool>) {
         self.chan.send(Command::AddWebFont(LowercaseString::new(&family.name), sources, sender)).unwrap();
     }
 
+    /// Adds a web font to `family` from the raw data of a font file, as used
+    /// by `FontFace` objects constructed from a buffer.
+    pub fn add_web_font_data(&self, family: FamilyName, data: Vec<u8>, sender: IpcSender<bool>) {
+        self.chan.send(Command::AddWebFontData(LowercaseString::new(&family.name), data, sender)).unwrap();
+    }
+
     pub fn exit(&self) {
         let (response_chan, response_port) = ipc::channel().unwrap();
         self.chan.send(Command::Exit(response_chan)).expect("Couldn't send FontCacheThread exit message");
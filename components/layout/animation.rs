@@ -11,12 +11,16 @@ use fnv::FnvHashMap;
 use ipc_channel::ipc::IpcSender;
 use msg::constellation_msg::PipelineId;
 use opaque_node::OpaqueNodeMethods;
+use script_layout_interface::wrapper_traits::PseudoElementType;
 use script_traits::{AnimationState, ConstellationControlMsg, LayoutMsg as ConstellationMsg};
 use script_traits::UntrustedNodeAddress;
+use servo_arc::Arc as ServoArc;
 use std::sync::mpsc::Receiver;
-use style::animation::{Animation, update_style_for_animation};
+use style::animation::{Animation, ScriptAnimationSample, update_style_for_animation};
+use style::animation::{remove_script_animation_from_style, update_style_for_script_animation};
 use style::dom::TElement;
 use style::font_metrics::ServoMetricsProvider;
+use style::properties::ComputedValues;
 use style::selector_parser::RestyleDamage;
 use style::timer::Timer;
 
@@ -185,3 +189,63 @@ where
         recalc_style_for_animations::<E>(context, kid, animations)
     }
 }
+
+/// The animations created from script that affect a node.
+#[derive(Clone)]
+pub struct ScriptAnimations {
+    /// The style of the node without any of these animations applied.
+    pub base_style: ServoArc<ComputedValues>,
+    /// The samples of the animations, in composite order.
+    pub samples: Vec<ScriptAnimationSample>,
+}
+
+/// Updates the style of the fragments of `flow` for the animations created
+/// from script, undoing the effect of the `previous` ones first.
+pub fn recalc_style_for_script_animations<E>(
+    context: &LayoutContext,
+    flow: &mut Flow,
+    previous: &FnvHashMap<OpaqueNode, ScriptAnimations>,
+    animations: &FnvHashMap<OpaqueNode, ScriptAnimations>,
+)
+where
+    E: TElement,
+{
+    let mut damage = RestyleDamage::empty();
+    flow.mutate_fragments(&mut |fragment| {
+        if fragment.pseudo != PseudoElementType::Normal {
+            return;
+        }
+        let current = animations.get(&fragment.node);
+        let old_style = fragment.style.clone();
+        if let Some(previous) = previous.get(&fragment.node) {
+            let base_style = current.map_or(&previous.base_style, |current| &current.base_style);
+            for sample in &previous.samples {
+                remove_script_animation_from_style(
+                    &context.style_context,
+                    sample,
+                    base_style,
+                    &mut fragment.style,
+                );
+            }
+        }
+        if let Some(current) = current {
+            for sample in &current.samples {
+                update_style_for_script_animation::<E>(
+                    &context.style_context,
+                    sample,
+                    &current.base_style,
+                    &mut fragment.style,
+                    &ServoMetricsProvider,
+                );
+            }
+        }
+        let difference = RestyleDamage::compute_style_difference(&old_style, &fragment.style);
+        damage |= difference.damage;
+    });
+
+    let base = flow.mut_base();
+    base.restyle_damage.insert(damage);
+    for kid in base.children.iter_mut() {
+        recalc_style_for_script_animations::<E>(context, kid, previous, animations)
+    }
+}
@@ -1449,7 +1449,8 @@ impl BlockFlow {
             Display::TableRowGroup |
             Display::Table |
             Display::InlineBlock |
-            Display::Flex => {
+            Display::Flex |
+            Display::Grid => {
                 FormattingContextType::Other
             }
             _ if style.get_box().overflow_x != StyleOverflow::Visible ||
@@ -1568,7 +1569,8 @@ impl BlockFlow {
 
     fn is_inline_block_or_inline_flex(&self) -> bool {
         self.fragment.style().get_box().display == Display::InlineBlock ||
-        self.fragment.style().get_box().display == Display::InlineFlex
+        self.fragment.style().get_box().display == Display::InlineFlex ||
+        self.fragment.style().get_box().display == Display::InlineGrid
     }
 
     /// Computes the content portion (only) of the intrinsic inline sizes of this flow. This is
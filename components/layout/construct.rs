@@ -28,6 +28,7 @@ use fragment::{Fragment, GeneratedContentInfo, IframeFragmentInfo, FragmentFlags
 use fragment::{InlineAbsoluteHypotheticalFragmentInfo, TableColumnFragmentInfo};
 use fragment::{InlineBlockFragmentInfo, SpecificFragmentInfo, UnscannedTextFragmentInfo};
 use fragment::WhitespaceStrippingResult;
+use grid::GridFlow;
 use inline::{InlineFlow, InlineFragmentNodeInfo, InlineFragmentNodeFlags};
 use linked_list::prepend_from;
 use list_item::{ListItemFlow, ListStyleTypeContent};
@@ -982,7 +983,8 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
         ConstructionResult::ConstructionItem(construction_item)
     }
 
-    /// Build the fragment for an inline-block or inline-flex, based on the `display` flag
+    /// Build the fragment for an inline-block, inline-flex or inline-grid, based on the `display`
+    /// flag
     fn build_fragment_for_inline_block_or_inline_flex(
         &mut self,
         node: &ConcreteThreadSafeLayoutNode,
@@ -991,7 +993,8 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
         let block_flow_result = match display {
             Display::InlineBlock => self.build_flow_for_block(node, None),
             Display::InlineFlex => self.build_flow_for_flex(node, None),
-            _ => panic!("The flag should be inline-block or inline-flex")
+            Display::InlineGrid => self.build_flow_for_grid(node, None),
+            _ => panic!("The flag should be inline-block, inline-flex or inline-grid")
         };
         let (block_flow, abs_descendants) = match block_flow_result {
             ConstructionResult::Flow(block_flow, abs_descendants) => (block_flow, abs_descendants),
@@ -1388,6 +1391,16 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
         self.build_flow_for_block_like(flow, node)
     }
 
+    /// Builds a flow for a node with 'display: grid'.
+    fn build_flow_for_grid(&mut self,
+                           node: &ConcreteThreadSafeLayoutNode,
+                           float_kind: Option<FloatKind>)
+                           -> ConstructionResult {
+        let fragment = self.build_fragment_for_block(node);
+        let flow = FlowRef::new(Arc::new(GridFlow::from_fragment(fragment, float_kind)));
+        self.build_flow_for_block_like(flow, node)
+    }
+
     /// Attempts to perform incremental repair to account for recent changes to this node. This
     /// can fail and return false, indicating that flows will need to be reconstructed.
     ///
@@ -1669,6 +1682,19 @@ impl<'a, ConcreteThreadSafeLayoutNode> PostorderNodeMutTraversal<ConcreteThreadS
                 self.set_flow_construction_result(node, construction_result)
             }
 
+            // Grid items contribute grid flow construction results.
+            (Display::Grid, float_value, _) => {
+                let float_kind = FloatKind::from_property(float_value);
+                let construction_result = self.build_flow_for_grid(node, float_kind);
+                self.set_flow_construction_result(node, construction_result)
+            }
+
+            (Display::InlineGrid, _, _) => {
+                let construction_result = self.build_fragment_for_inline_block_or_inline_flex(node,
+                                                                                              Display::InlineGrid);
+                self.set_flow_construction_result(node, construction_result)
+            }
+
             // Block flows that are not floated contribute block flow construction results.
             //
             // TODO(pcwalton): Make this only trigger for blocks and handle the other `display`
@@ -1995,7 +2021,7 @@ impl Legalizer {
     ///
     /// This method attempts to create anonymous blocks in between `parent` and `child` if and only
     /// if those blocks will only ever have `child` as their sole child. At present, this is only
-    /// true for anonymous block children of flex flows.
+    /// true for anonymous block children of flex and grid flows.
     fn try_to_add_child<E>(
         &mut self,
         context: &SharedStyleContext,
@@ -2073,6 +2099,39 @@ impl Legalizer {
                 true
             }
 
+            // Grid items are sized like the children of a column flex container: their grid area
+            // is their containing block.
+            (FlowClass::Grid, FlowClass::Inline) => {
+                FlowRef::deref_mut(child).mut_base().flags.insert(FlowFlags::MARGINS_CANNOT_COLLAPSE);
+                let mut block_wrapper = Legalizer::create_anonymous_flow::<E, _>(
+                    context,
+                    parent,
+                    &[PseudoElement::ServoAnonymousBlock],
+                    SpecificFragmentInfo::Generic,
+                    BlockFlow::from_fragment,
+                );
+
+                {
+                    let block = FlowRef::deref_mut(&mut block_wrapper).as_mut_block();
+                    block.base.flags.insert(FlowFlags::MARGINS_CANNOT_COLLAPSE);
+                    block.fragment.flags.insert(FragmentFlags::IS_BLOCK_FLEX_ITEM);
+                }
+                block_wrapper.add_new_child((*child).clone());
+                block_wrapper.finish();
+                parent.add_new_child(block_wrapper);
+                true
+            }
+
+            (FlowClass::Grid, _) => {
+                {
+                    let block = FlowRef::deref_mut(child).as_mut_block();
+                    block.base.flags.insert(FlowFlags::MARGINS_CANNOT_COLLAPSE);
+                    block.fragment.flags.insert(FragmentFlags::IS_BLOCK_FLEX_ITEM);
+                }
+                parent.add_new_child((*child).clone());
+                true
+            }
+
             _ => {
                 parent.add_new_child((*child).clone());
                 true
@@ -40,6 +40,7 @@ use fragment::SpecificFragmentInfo;
 use gfx::text::TextRun;
 use gfx::text::glyph::ByteIndex;
 use gfx_traits::{combine_id_with_fragment_type, FragmentType, StackingContextId};
+use grid::GridFlow;
 use inline::{InlineFlow, InlineFragmentNodeFlags};
 use ipc_channel::ipc;
 use list_item::ListItemFlow;
@@ -3029,6 +3030,18 @@ impl FlexFlowDisplayListBuilding for FlexFlow {
     }
 }
 
+pub trait GridFlowDisplayListBuilding {
+    fn build_display_list_for_grid(&mut self, state: &mut DisplayListBuildState);
+}
+
+impl GridFlowDisplayListBuilding for GridFlow {
+    fn build_display_list_for_grid(&mut self, state: &mut DisplayListBuildState) {
+        // Draw the rest of the block.
+        self.as_mut_block()
+            .build_display_list_for_block(state, BorderPaintingMode::Separate)
+    }
+}
+
 trait BaseFlowDisplayListBuilding {
     fn build_display_items_for_debugging_tint(
         &self,
@@ -3203,4 +3216,25 @@ impl IndexableText {
                 .range_index_of_advance(&item[0].range, offset.x),
         )
     }
+
+    // Returns the character index within a node's text for a point relative to the
+    // root stacking context, taking into account every line the text was broken into.
+    //
+    // TODO: Map indices through whitespace collapsing back to the node's original text.
+    pub fn text_index_at_point(&self, node: OpaqueNode, point: Point2D<Au>) -> Option<usize> {
+        let items = self.inner.get(&node)?;
+        let first = items.first()?;
+        let item = items
+            .iter()
+            .rev()
+            .find(|item| item.origin.y <= point.y && item.origin.x <= point.x)
+            .unwrap_or(first);
+        let offset = point - item.baseline_origin;
+        let index = item.text_run.range_index_of_advance(&item.range, offset.x);
+        if !Arc::ptr_eq(&item.text_run, &first.text_run) || item.range.begin() < first.range.begin() {
+            return Some(index);
+        }
+        let preceding = &item.text_run.text[first.range.begin().to_usize()..item.range.begin().to_usize()];
+        Some(preceding.chars().count() + index)
+    }
 }
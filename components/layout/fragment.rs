@@ -2556,7 +2556,7 @@ impl Fragment {
         }
 
         match self.style().get_box().display {
-            Display::Flex => self.style().get_position().z_index.integer_or(0),
+            Display::Flex | Display::Grid => self.style().get_position().z_index.integer_or(0),
             _ => 0,
         }
     }
@@ -3111,7 +3111,8 @@ bitflags! {
         // TODO(stshine): find a better name since these flags can also be used for grid item.
         /// Whether this fragment represents a child in a row flex container.
         const IS_INLINE_FLEX_ITEM = 0b0000_0001;
-        /// Whether this fragment represents a child in a column flex container.
+        /// Whether this fragment represents a child in a column flex container or a grid
+        /// container.
         const IS_BLOCK_FLEX_ITEM = 0b0000_0010;
         /// Whether this fragment represents the generated text from a text-overflow clip.
         const IS_ELLIPSIS = 0b0000_0100;
@@ -0,0 +1,1221 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Layout for elements with a CSS `display` property of `grid`.
+
+#![deny(unsafe_code)]
+
+use app_units::{Au, MAX_AU};
+use block::{AbsoluteAssignBSizesTraversal, BlockFlow};
+use context::LayoutContext;
+use display_list::{DisplayListBuildState, GridFlowDisplayListBuilding};
+use display_list::StackingContextCollectionState;
+use euclid::Point2D;
+use floats::FloatKind;
+use flow::{Flow, FlowClass, FlowFlags, GetBaseFlow, OpaqueFlow};
+use fragment::{Fragment, FragmentBorderBoxIterator, Overflow};
+use layout_debug;
+use model::{AdjoiningMargins, CollapsibleMargins, SizeConstraint};
+use std::cmp::{max, min};
+use std::collections::HashMap;
+use std::mem;
+use std::ops::Range;
+use style::computed_values::align_items::T as AlignItems;
+use style::computed_values::align_self::T as AlignSelf;
+use style::logical_geometry::{Direction, LogicalSize};
+use style::properties::ComputedValues;
+use style::servo::restyle_damage::ServoRestyleDamage;
+use style::values::Either;
+use style::values::computed::{GridLine, GridTemplateAreas, GridTemplateComponent, LengthOrPercentage};
+use style::values::computed::{LengthOrPercentageOrAuto, TrackBreadth, TrackSize};
+use style::values::computed::length::NonNegativeLengthOrPercentageOrNormal;
+use style::values::generics::grid::{GridTemplateComponent as GenericGridTemplateComponent, TrackKeyword};
+use style::values::generics::grid::{TrackBreadth as GenericTrackBreadth, TrackListType, TrackListValue};
+use style::values::generics::grid::TrackSize as GenericTrackSize;
+use style::values::specified::position::AutoFlow;
+use traversal::PreorderFlowTraversal;
+
+/// One of the two sizing functions of a grid track, with lengths resolved.
+/// <https://drafts.csswg.org/css-grid/#algo-terms>
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+enum TrackSizingFunction {
+    Fixed(Au),
+    MinContent,
+    MaxContent,
+    Auto,
+    Flex(f32),
+}
+
+impl TrackSizingFunction {
+    /// Resolves a `<track-breadth>`. Percentages that cannot be resolved against `basis` are
+    /// treated as `auto`.
+    fn new(breadth: &TrackBreadth, basis: Option<Au>) -> TrackSizingFunction {
+        match *breadth {
+            GenericTrackBreadth::Breadth(ref length) => {
+                match used_length(length, basis) {
+                    Some(size) => TrackSizingFunction::Fixed(size),
+                    None => TrackSizingFunction::Auto,
+                }
+            }
+            GenericTrackBreadth::Fr(flex) => TrackSizingFunction::Flex(flex),
+            GenericTrackBreadth::Keyword(TrackKeyword::Auto) => TrackSizingFunction::Auto,
+            GenericTrackBreadth::Keyword(TrackKeyword::MinContent) => TrackSizingFunction::MinContent,
+            GenericTrackBreadth::Keyword(TrackKeyword::MaxContent) => TrackSizingFunction::MaxContent,
+        }
+    }
+
+    /// A flexible minimum sizing function is treated as `auto`.
+    fn as_min_sizing_function(self) -> TrackSizingFunction {
+        match self {
+            TrackSizingFunction::Flex(_) => TrackSizingFunction::Auto,
+            other => other,
+        }
+    }
+}
+
+/// A row or column of the grid.
+#[derive(Clone, Debug, Serialize)]
+struct GridTrack {
+    /// The minimum track sizing function.
+    min_sizing: TrackSizingFunction,
+    /// The maximum track sizing function.
+    max_sizing: TrackSizingFunction,
+    /// The argument of a `fit-content()` track size, if any.
+    fit_content_limit: Option<Au>,
+    /// The used size of the track once the track sizing algorithm has run.
+    base_size: Au,
+    /// The size the track may grow to. `MAX_AU` stands for an infinite growth limit.
+    growth_limit: Au,
+}
+
+impl GridTrack {
+    fn new(size: &TrackSize, basis: Option<Au>) -> GridTrack {
+        let (min_sizing, max_sizing, fit_content_limit) = match *size {
+            GenericTrackSize::Breadth(ref breadth) => {
+                let sizing = TrackSizingFunction::new(breadth, basis);
+                (sizing.as_min_sizing_function(), sizing, None)
+            }
+            GenericTrackSize::Minmax(ref min_breadth, ref max_breadth) => {
+                (TrackSizingFunction::new(min_breadth, basis).as_min_sizing_function(),
+                 TrackSizingFunction::new(max_breadth, basis),
+                 None)
+            }
+            GenericTrackSize::FitContent(ref limit) => {
+                (TrackSizingFunction::Auto, TrackSizingFunction::MaxContent, used_length(limit, basis))
+            }
+        };
+        GridTrack {
+            min_sizing: min_sizing,
+            max_sizing: max_sizing,
+            fit_content_limit: fit_content_limit,
+            base_size: Au(0),
+            growth_limit: MAX_AU,
+        }
+    }
+
+    fn flex_factor(&self) -> Option<f32> {
+        match self.max_sizing {
+            TrackSizingFunction::Flex(flex) => Some(flex),
+            _ => None,
+        }
+    }
+
+    fn is_flexible(&self) -> bool {
+        self.flex_factor().is_some()
+    }
+
+    /// Whether the size of this track is known without looking at its contents.
+    fn is_fixed(&self) -> bool {
+        match (self.min_sizing, self.max_sizing) {
+            (TrackSizingFunction::Fixed(_), TrackSizingFunction::Fixed(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// <https://drafts.csswg.org/css-grid/#algo-init>
+    fn initialize(&mut self) {
+        self.base_size = match self.min_sizing {
+            TrackSizingFunction::Fixed(size) => size,
+            _ => Au(0),
+        };
+        self.growth_limit = match self.max_sizing {
+            TrackSizingFunction::Fixed(size) => max(size, self.base_size),
+            TrackSizingFunction::Flex(_) => self.base_size,
+            _ => MAX_AU,
+        };
+    }
+
+    /// The growth limit, with an infinite one standing in for the base size.
+    fn finite_growth_limit(&self) -> Au {
+        if self.growth_limit == MAX_AU {
+            self.base_size
+        } else {
+            self.growth_limit
+        }
+    }
+}
+
+/// The space the track sizing algorithm sizes tracks into.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum AvailableSpace {
+    Definite(Au),
+    MinContent,
+    MaxContent,
+}
+
+/// The outer size contributions of a grid item to the tracks it spans in one axis.
+#[derive(Debug)]
+struct Contribution {
+    span: Range<usize>,
+    min_content: Au,
+    max_content: Au,
+}
+
+/// Represents a child in a grid container, placed in the grid.
+#[derive(Debug, Serialize)]
+struct GridItem {
+    /// The index of the actual flow in our child list.
+    index: usize,
+    /// The rows spanned by the item, as indices into the tracks of the implicit grid.
+    rows: Range<usize>,
+    /// The columns spanned by the item, as indices into the tracks of the implicit grid.
+    columns: Range<usize>,
+}
+
+/// The placement of an item in one axis, before auto-placement.
+#[derive(Clone, Copy, Debug)]
+enum AxisPlacement {
+    /// A definite range of tracks, counted from the start of the explicit grid. Negative indices
+    /// refer to implicit tracks before the explicit grid.
+    Definite(i32, i32),
+    /// An automatic position spanning this many tracks.
+    Auto(i32),
+}
+
+/// The placement of an item along the major axis of `grid-auto-flow` (rows for `row`, columns
+/// for `column`) and the minor axis.
+#[derive(Clone, Copy, Debug)]
+struct Placement {
+    major: AxisPlacement,
+    minor: AxisPlacement,
+}
+
+/// The explicit tracks of one axis of the grid, and the names of the lines between them.
+struct ExplicitTracks {
+    sizes: Vec<TrackSize>,
+    line_names: Vec<Vec<String>>,
+}
+
+impl ExplicitTracks {
+    /// Expands `template` into its tracks, repeating an `<auto-repeat>` `repeat_count` times,
+    /// and adds the implicit `<area>-start` and `<area>-end` lines of `grid-template-areas`.
+    fn new(template: &GridTemplateComponent,
+           areas: &GridTemplateAreas,
+           direction: Direction,
+           repeat_count: u32)
+           -> ExplicitTracks {
+        let mut sizes = vec![];
+        let mut line_names = vec![];
+        if let GenericGridTemplateComponent::TrackList(ref list) = *template {
+            let repeat_index = match list.list_type {
+                TrackListType::Auto(index) => Some(index as usize),
+                _ => None,
+            };
+            let names_at = |index: usize| {
+                list.line_names.get(index)
+                               .into_iter()
+                               .flat_map(|names| names.iter())
+                               .map(|name| name.0.to_string())
+            };
+            let mut pending_names: Vec<String> = vec![];
+            let mut name_index = 0;
+            for index in 0..(list.values.len() + 1) {
+                if repeat_index == Some(index) {
+                    pending_names.extend(names_at(name_index));
+                    name_index += 1;
+                    if let Some(ref repeat) = list.auto_repeat {
+                        for _ in 0..repeat_count {
+                            for (size_index, size) in repeat.track_sizes.iter().enumerate() {
+                                if let Some(names) = repeat.line_names.get(size_index) {
+                                    pending_names.extend(names.iter().map(|name| name.0.to_string()));
+                                }
+                                line_names.push(mem::replace(&mut pending_names, vec![]));
+                                sizes.push(size.clone());
+                            }
+                            if let Some(names) = repeat.line_names.get(repeat.track_sizes.len()) {
+                                pending_names.extend(names.iter().map(|name| name.0.to_string()));
+                            }
+                        }
+                    }
+                }
+                pending_names.extend(names_at(name_index));
+                name_index += 1;
+                match list.values.get(index) {
+                    Some(&TrackListValue::TrackSize(ref size)) => {
+                        line_names.push(mem::replace(&mut pending_names, vec![]));
+                        sizes.push(size.clone());
+                    }
+                    Some(&TrackListValue::TrackRepeat(ref repeat)) => {
+                        for size in &repeat.track_sizes {
+                            line_names.push(mem::replace(&mut pending_names, vec![]));
+                            sizes.push(size.clone());
+                        }
+                    }
+                    None => {}
+                }
+            }
+            line_names.push(pending_names);
+        }
+
+        if let Either::First(ref areas) = *areas {
+            for area in areas.0.areas.iter() {
+                let lines = match direction {
+                    Direction::Inline => &area.columns,
+                    Direction::Block => &area.rows,
+                };
+                let end = lines.end as usize - 1;
+                while line_names.len() <= end {
+                    line_names.push(vec![]);
+                }
+                line_names[lines.start as usize - 1].push(format!("{}-start", area.name));
+                line_names[end].push(format!("{}-end", area.name));
+            }
+        }
+        while line_names.len() <= sizes.len() {
+            line_names.push(vec![]);
+        }
+
+        ExplicitTracks {
+            sizes: sizes,
+            line_names: line_names,
+        }
+    }
+
+    /// The number of tracks in the explicit grid.
+    fn count(&self) -> i32 {
+        self.line_names.len() as i32 - 1
+    }
+
+    fn has_line(&self, name: &str) -> bool {
+        self.line_names.iter().any(|names| names.iter().any(|n| n == name))
+    }
+
+    /// Returns the index of the `nth` line named `name`, counting from the end if `nth` is
+    /// negative. All implicit lines are considered to have every name.
+    /// <https://drafts.csswg.org/css-grid/#line-placement>
+    fn find_line(&self, name: &str, nth: i32) -> i32 {
+        let matching: Vec<i32> = self.line_names
+                                     .iter()
+                                     .enumerate()
+                                     .filter(|&(_, names)| names.iter().any(|n| n == name))
+                                     .map(|(index, _)| index as i32)
+                                     .collect();
+        let count = matching.len() as i32;
+        if nth > 0 {
+            if nth <= count {
+                matching[nth as usize - 1]
+            } else {
+                self.count() + nth - count
+            }
+        } else if -nth <= count {
+            matching[(count + nth) as usize]
+        } else {
+            nth + count
+        }
+    }
+
+    /// Resolves a `<grid-line>` to a line index, or `None` if it is `auto` or a span.
+    fn resolve_line(&self, line: &GridLine, suffix: &str) -> Option<i32> {
+        if line.is_span {
+            return None
+        }
+        match (&line.ident, line.line_num) {
+            (&Some(ref ident), None) => {
+                let implicit_name = format!("{}{}", &*ident.0, suffix);
+                if self.has_line(&implicit_name) {
+                    Some(self.find_line(&implicit_name, 1))
+                } else {
+                    Some(self.find_line(&*ident.0, 1))
+                }
+            }
+            (&Some(ref ident), Some(nth)) => Some(self.find_line(&*ident.0, nth)),
+            (&None, Some(nth)) if nth > 0 => Some(nth - 1),
+            (&None, Some(nth)) => Some(self.count() + 1 + nth),
+            (&None, None) => None,
+        }
+    }
+
+    /// Returns how many tracks a `span` line covers, starting from the definite line `from` and
+    /// going forward or backward.
+    fn span_from(&self, line: &GridLine, from: i32, forward: bool) -> i32 {
+        if !line.is_span {
+            return 1
+        }
+        let nth = max(line.line_num.unwrap_or(1), 1);
+        let name = match line.ident {
+            Some(ref ident) => ident,
+            None => return nth,
+        };
+        let mut found = 0;
+        let mut index = from;
+        while found < nth {
+            index += if forward { 1 } else { -1 };
+            let is_named = index >= 0 && (index as usize) < self.line_names.len() &&
+                self.line_names[index as usize].iter().any(|n| **n == *name.0);
+            let is_implicit = index < 0 || index > self.count();
+            if is_named || is_implicit {
+                found += 1;
+            }
+        }
+        (index - from).abs()
+    }
+
+    /// Resolves the placement of an item from its start and end lines in this axis.
+    /// <https://drafts.csswg.org/css-grid/#line-placement>
+    fn resolve_placement(&self, start: &GridLine, end: &GridLine) -> AxisPlacement {
+        match (self.resolve_line(start, "-start"), self.resolve_line(end, "-end")) {
+            (Some(start), Some(end)) if start < end => AxisPlacement::Definite(start, end),
+            (Some(start), Some(end)) if start > end => AxisPlacement::Definite(end, start),
+            (Some(start), Some(_)) => AxisPlacement::Definite(start, start + 1),
+            (Some(start), None) => AxisPlacement::Definite(start, start + self.span_from(end, start, true)),
+            (None, Some(end)) => AxisPlacement::Definite(end - self.span_from(start, end, false), end),
+            (None, None) => {
+                let span = if start.is_span {
+                    start.line_num.unwrap_or(1)
+                } else if end.is_span {
+                    end.line_num.unwrap_or(1)
+                } else {
+                    1
+                };
+                AxisPlacement::Auto(max(span, 1))
+            }
+        }
+    }
+}
+
+/// Resolves a length against `basis`, if it is known.
+fn used_length(length: &LengthOrPercentage, basis: Option<Au>) -> Option<Au> {
+    match (*length, basis) {
+        (LengthOrPercentage::Length(length), _) => Some(Au::from(length)),
+        (length, Some(basis)) => Some(length.to_used_value(basis)),
+        (_, None) => None,
+    }
+}
+
+/// Returns the used value of `column-gap` or `row-gap`. `normal` is zero in grid containers.
+fn gap_size(gap: &NonNegativeLengthOrPercentageOrNormal, basis: Au) -> Au {
+    match *gap {
+        Either::First(ref length) => length.0.to_used_value(basis),
+        Either::Second(_normal) => Au(0),
+    }
+}
+
+/// Returns the number of times the `<auto-repeat>` of `template` is repeated, which is as many
+/// times as fits in `available`.
+/// <https://drafts.csswg.org/css-grid/#auto-repeat>
+fn auto_repeat_count(template: &GridTemplateComponent, available: Option<Au>, gap: Au) -> u32 {
+    let list = match *template {
+        GenericGridTemplateComponent::TrackList(ref list) => list,
+        _ => return 0,
+    };
+    let repeat = match list.auto_repeat {
+        Some(ref repeat) => repeat,
+        None => return 0,
+    };
+    let available = match available {
+        Some(available) => available,
+        None => return 1,
+    };
+
+    // Only `<fixed-size>` tracks are allowed alongside an `<auto-repeat>`.
+    let fixed_size = |size: &TrackSize| {
+        let track = GridTrack::new(size, Some(available));
+        match (track.min_sizing, track.max_sizing) {
+            (_, TrackSizingFunction::Fixed(size)) |
+            (TrackSizingFunction::Fixed(size), _) => size,
+            _ => Au(0),
+        }
+    };
+    let repeat_size = repeat.track_sizes.iter().fold(Au(0), |sum, size| sum + fixed_size(size) + gap);
+    let other_size = list.values.iter().fold(Au(0), |sum, value| {
+        match *value {
+            TrackListValue::TrackSize(ref size) => sum + fixed_size(size) + gap,
+            TrackListValue::TrackRepeat(_) => sum,
+        }
+    });
+    if repeat_size <= Au(0) {
+        return 1
+    }
+    max(1, (available - other_size + gap).0 / repeat_size.0) as u32
+}
+
+/// Runs the auto-placement algorithm, returning the major and minor tracks of every item.
+/// <https://drafts.csswg.org/css-grid/#auto-placement-algo>
+fn auto_place(placements: &[Placement], explicit_minor_count: i32, dense: bool) -> Vec<(Range<i32>, Range<i32>)> {
+    let overlaps = |occupied: &[(Range<i32>, Range<i32>)], major: &Range<i32>, minor: &Range<i32>| {
+        occupied.iter().any(|&(ref other_major, ref other_minor)| {
+            major.start < other_major.end && other_major.start < major.end &&
+                minor.start < other_minor.end && other_minor.start < minor.end
+        })
+    };
+
+    // The extent of the implicit grid in the minor axis.
+    let mut minor_start = 0;
+    let mut minor_end = explicit_minor_count;
+    let mut major_start = 0;
+    for placement in placements {
+        if let AxisPlacement::Definite(start, end) = placement.minor {
+            minor_start = min(minor_start, start);
+            minor_end = max(minor_end, end);
+        }
+        if let AxisPlacement::Definite(start, _) = placement.major {
+            major_start = min(major_start, start);
+        }
+    }
+    for placement in placements {
+        if let AxisPlacement::Auto(span) = placement.minor {
+            minor_end = max(minor_end, minor_start + span);
+        }
+    }
+
+    let mut areas: Vec<Option<(Range<i32>, Range<i32>)>> = vec![None; placements.len()];
+    let mut occupied = vec![];
+
+    // Step 1: Position anything that's not auto-positioned.
+    for (index, placement) in placements.iter().enumerate() {
+        if let (AxisPlacement::Definite(major, major_end),
+                AxisPlacement::Definite(minor, minor_end)) = (placement.major, placement.minor) {
+            let area = (major..major_end, minor..minor_end);
+            occupied.push(area.clone());
+            areas[index] = Some(area);
+        }
+    }
+
+    // Step 2: Process the items locked to a given major track.
+    let mut cursors = HashMap::new();
+    for (index, placement) in placements.iter().enumerate() {
+        if let (AxisPlacement::Definite(major, major_end), AxisPlacement::Auto(span)) =
+                (placement.major, placement.minor) {
+            let mut minor = if dense {
+                minor_start
+            } else {
+                *cursors.get(&major).unwrap_or(&minor_start)
+            };
+            while overlaps(&occupied, &(major..major_end), &(minor..minor + span)) {
+                minor += 1;
+            }
+            minor_end = max(minor_end, minor + span);
+            cursors.insert(major, minor + span);
+            let area = (major..major_end, minor..minor + span);
+            occupied.push(area.clone());
+            areas[index] = Some(area);
+        }
+    }
+
+    // Step 4: Position the remaining grid items.
+    let (mut cursor_major, mut cursor_minor) = (major_start, minor_start);
+    for (index, placement) in placements.iter().enumerate() {
+        let major_span = match placement.major {
+            AxisPlacement::Auto(span) => span,
+            AxisPlacement::Definite(..) => continue,
+        };
+        if dense {
+            cursor_major = major_start;
+            cursor_minor = minor_start;
+        }
+        let minor = match placement.minor {
+            AxisPlacement::Definite(start, end) => {
+                if start < cursor_minor && !dense {
+                    cursor_major += 1;
+                }
+                cursor_minor = start;
+                while overlaps(&occupied, &(cursor_major..cursor_major + major_span), &(start..end)) {
+                    cursor_major += 1;
+                }
+                start..end
+            }
+            AxisPlacement::Auto(span) => {
+                loop {
+                    if cursor_minor + span > minor_end {
+                        cursor_major += 1;
+                        cursor_minor = minor_start;
+                        continue
+                    }
+                    if !overlaps(&occupied, &(cursor_major..cursor_major + major_span),
+                                 &(cursor_minor..cursor_minor + span)) {
+                        break
+                    }
+                    cursor_minor += 1;
+                }
+                cursor_minor..cursor_minor + span
+            }
+        };
+        let area = (cursor_major..cursor_major + major_span, minor);
+        occupied.push(area.clone());
+        areas[index] = Some(area);
+    }
+
+    areas.into_iter().map(|area| area.unwrap()).collect()
+}
+
+fn total_size(tracks: &[GridTrack], gap: Au) -> Au {
+    if tracks.is_empty() {
+        return Au(0)
+    }
+    tracks.iter().fold(Au(0), |sum, track| sum + track.base_size) + gap * (tracks.len() as i32 - 1)
+}
+
+/// Grows the base sizes of the tracks accepted by `accepts` equally, so that the base sizes
+/// of all `tracks` add up to at least `size`.
+fn grow_base_sizes<F>(tracks: &mut [GridTrack], size: Au, accepts: F) where F: Fn(&GridTrack) -> bool {
+    let current = tracks.iter().fold(Au(0), |sum, track| sum + track.base_size);
+    let count = tracks.iter().filter(|track| accepts(track)).count() as i32;
+    if size <= current || count == 0 {
+        return
+    }
+    let share = (size - current) / count;
+    for track in tracks.iter_mut().filter(|track| accepts(track)) {
+        track.base_size += share;
+        track.growth_limit = max(track.growth_limit, track.base_size);
+    }
+}
+
+/// Like `grow_base_sizes`, but for growth limits.
+fn grow_growth_limits<F>(tracks: &mut [GridTrack], size: Au, accepts: F) where F: Fn(&GridTrack) -> bool {
+    let current = tracks.iter().fold(Au(0), |sum, track| sum + track.finite_growth_limit());
+    let count = tracks.iter().filter(|track| accepts(track)).count() as i32;
+    if count == 0 {
+        return
+    }
+    let share = max(size - current, Au(0)) / count;
+    for track in tracks.iter_mut().filter(|track| accepts(track)) {
+        track.growth_limit = track.finite_growth_limit() + share;
+    }
+}
+
+/// Finds the size of one `fr` so that `tracks` fill `space`.
+/// <https://drafts.csswg.org/css-grid/#algo-find-fr-size>
+fn find_fr_size(tracks: &[GridTrack], space: Au) -> f32 {
+    let mut inflexible: Vec<bool> = tracks.iter().map(|track| !track.is_flexible()).collect();
+    loop {
+        let mut leftover = space;
+        let mut flex_sum = 0.0;
+        for (track, &is_inflexible) in tracks.iter().zip(inflexible.iter()) {
+            match track.flex_factor() {
+                Some(flex) if !is_inflexible => flex_sum += flex,
+                _ => leftover -= track.base_size,
+            }
+        }
+        let fr_size = leftover.to_f32_px() / flex_sum.max(1.0);
+
+        let mut changed = false;
+        for (track, is_inflexible) in tracks.iter().zip(inflexible.iter_mut()) {
+            if let Some(flex) = track.flex_factor() {
+                if !*is_inflexible && fr_size * flex < track.base_size.to_f32_px() {
+                    *is_inflexible = true;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return fr_size.max(0.0)
+        }
+    }
+}
+
+/// Runs the grid track sizing algorithm, leaving the used size of every track in its base size.
+/// <https://drafts.csswg.org/css-grid/#algo-track-sizing>
+fn size_tracks(tracks: &mut [GridTrack], contributions: &[Contribution], gap: Au, available: AvailableSpace) {
+    if tracks.is_empty() {
+        return
+    }
+    let gaps = gap * (tracks.len() as i32 - 1);
+
+    for track in tracks.iter_mut() {
+        track.initialize();
+    }
+
+    // https://drafts.csswg.org/css-grid/#algo-content
+    let (mut inflexible_items, flexible_items): (Vec<&Contribution>, Vec<&Contribution>) =
+        contributions.iter().partition(|contribution| {
+            !tracks[contribution.span.clone()].iter().any(|track| track.is_flexible())
+        });
+    inflexible_items.sort_by_key(|contribution| contribution.span.len());
+    for contribution in inflexible_items {
+        let spanned_gaps = gap * (contribution.span.len() as i32 - 1);
+        let min_content = contribution.min_content - spanned_gaps;
+        let max_content = contribution.max_content - spanned_gaps;
+        let tracks = &mut tracks[contribution.span.clone()];
+        grow_base_sizes(tracks, min_content, |track| {
+            track.min_sizing == TrackSizingFunction::MinContent || track.min_sizing == TrackSizingFunction::Auto
+        });
+        grow_base_sizes(tracks, max_content, |track| track.min_sizing == TrackSizingFunction::MaxContent);
+        grow_growth_limits(tracks, min_content, |track| track.max_sizing == TrackSizingFunction::MinContent);
+        grow_growth_limits(tracks, max_content, |track| {
+            track.max_sizing == TrackSizingFunction::MaxContent || track.max_sizing == TrackSizingFunction::Auto
+        });
+    }
+    for contribution in &flexible_items {
+        let spanned_gaps = gap * (contribution.span.len() as i32 - 1);
+        grow_base_sizes(&mut tracks[contribution.span.clone()], contribution.min_content - spanned_gaps, |track| {
+            track.is_flexible() && track.min_sizing == TrackSizingFunction::Auto
+        });
+    }
+    for track in tracks.iter_mut() {
+        let mut growth_limit = track.finite_growth_limit();
+        if let Some(limit) = track.fit_content_limit {
+            growth_limit = min(growth_limit, max(limit, track.base_size));
+        }
+        track.growth_limit = max(growth_limit, track.base_size);
+    }
+
+    // https://drafts.csswg.org/css-grid/#algo-grow-tracks
+    match available {
+        AvailableSpace::Definite(size) => {
+            let mut free_space = size - gaps - total_size(tracks, Au(0));
+            while free_space > Au(0) {
+                let count = tracks.iter().filter(|track| track.base_size < track.growth_limit).count() as i32;
+                if count == 0 || free_space / count == Au(0) {
+                    break
+                }
+                let share = free_space / count;
+                for track in tracks.iter_mut().filter(|track| track.base_size < track.growth_limit) {
+                    let growth = min(share, track.growth_limit - track.base_size);
+                    track.base_size += growth;
+                    free_space -= growth;
+                }
+            }
+        }
+        AvailableSpace::MaxContent => {
+            for track in tracks.iter_mut() {
+                track.base_size = track.growth_limit;
+            }
+        }
+        AvailableSpace::MinContent => {}
+    }
+
+    // https://drafts.csswg.org/css-grid/#algo-flex-tracks
+    if tracks.iter().any(|track| track.is_flexible()) {
+        let fr_size = match available {
+            AvailableSpace::Definite(size) => find_fr_size(tracks, size - gaps),
+            AvailableSpace::MaxContent => {
+                let mut fr_size: f32 = 0.0;
+                for track in tracks.iter() {
+                    if let Some(flex) = track.flex_factor() {
+                        fr_size = fr_size.max(track.base_size.to_f32_px() / flex.max(1.0));
+                    }
+                }
+                for contribution in &flexible_items {
+                    let spanned_gaps = gap * (contribution.span.len() as i32 - 1);
+                    fr_size = fr_size.max(find_fr_size(&tracks[contribution.span.clone()],
+                                                       contribution.max_content - spanned_gaps));
+                }
+                fr_size
+            }
+            AvailableSpace::MinContent => 0.0,
+        };
+        for track in tracks.iter_mut() {
+            if let Some(flex) = track.flex_factor() {
+                track.base_size = max(track.base_size, Au::from_f32_px(fr_size * flex));
+            }
+        }
+    }
+
+    // https://drafts.csswg.org/css-grid/#algo-stretch
+    // TODO: Honour `justify-content` and `align-content` once Servo supports their `normal`
+    // value; for now auto tracks always stretch.
+    if let AvailableSpace::Definite(size) = available {
+        let free_space = size - gaps - total_size(tracks, Au(0));
+        let count = tracks.iter().filter(|track| track.max_sizing == TrackSizingFunction::Auto).count() as i32;
+        if free_space > Au(0) && count > 0 {
+            let share = free_space / count;
+            for track in tracks.iter_mut().filter(|track| track.max_sizing == TrackSizingFunction::Auto) {
+                track.base_size += share;
+            }
+        }
+    }
+}
+
+/// Returns the offset of the start of each track, counting from `start`.
+fn track_offsets(tracks: &[GridTrack], start: Au, gap: Au) -> Vec<Au> {
+    let mut offset = start;
+    tracks.iter().map(|track| {
+        let track_start = offset;
+        offset += track.base_size + gap;
+        track_start
+    }).collect()
+}
+
+/// Returns the size of the grid area made of the tracks in `range`.
+fn area_size(tracks: &[GridTrack], offsets: &[Au], range: &Range<usize>) -> Au {
+    if range.start >= range.end {
+        return Au(0)
+    }
+    offsets[range.end - 1] + tracks[range.end - 1].base_size - offsets[range.start]
+}
+
+#[allow(unsafe_code)]
+unsafe impl ::flow::HasBaseFlow for GridFlow {}
+
+/// A block with the CSS `display` property equal to `grid`.
+#[derive(Debug, Serialize)]
+#[repr(C)]
+pub struct GridFlow {
+    /// Data common to all block flows.
+    block_flow: BlockFlow,
+    /// The grid items, sorted by the 'order' property.
+    items: Vec<GridItem>,
+    /// The lines of the implicit grid in the inline axis, counted from the start of the
+    /// explicit grid.
+    column_lines: Range<i32>,
+    /// The lines of the implicit grid in the block axis, counted from the start of the
+    /// explicit grid.
+    row_lines: Range<i32>,
+    /// The number of repetitions of the `<auto-repeat>` of `grid-template-columns`.
+    column_repeat_count: u32,
+    /// The number of repetitions of the `<auto-repeat>` of `grid-template-rows`.
+    row_repeat_count: u32,
+    /// The sized columns of the grid.
+    columns: Vec<GridTrack>,
+    /// The sized rows of the grid.
+    rows: Vec<GridTrack>,
+}
+
+impl GridFlow {
+    pub fn from_fragment(fragment: Fragment, flotation: Option<FloatKind>) -> GridFlow {
+        GridFlow {
+            block_flow: BlockFlow::from_fragment_and_float_kind(fragment, flotation),
+            items: Vec::new(),
+            column_lines: 0..0,
+            row_lines: 0..0,
+            column_repeat_count: 0,
+            row_repeat_count: 0,
+            columns: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Places every in-flow child in the grid, first by line-based placement and then by the
+    /// auto-placement algorithm. The available sizes decide how often `<auto-repeat>` tracks
+    /// repeat.
+    /// <https://drafts.csswg.org/css-grid/#placement>
+    fn place_items(&mut self, available_inline_size: Option<Au>, available_block_size: Option<Au>) {
+        let style = self.block_flow.fragment.style.clone();
+        let position = style.get_position();
+        self.column_repeat_count =
+            auto_repeat_count(&position.grid_template_columns,
+                              available_inline_size,
+                              gap_size(&position.column_gap, available_inline_size.unwrap_or(Au(0))));
+        self.row_repeat_count =
+            auto_repeat_count(&position.grid_template_rows,
+                              available_block_size,
+                              gap_size(&position.row_gap, available_block_size.unwrap_or(Au(0))));
+        let columns = ExplicitTracks::new(&position.grid_template_columns,
+                                          &position.grid_template_areas,
+                                          Direction::Inline,
+                                          self.column_repeat_count);
+        let rows = ExplicitTracks::new(&position.grid_template_rows,
+                                       &position.grid_template_areas,
+                                       Direction::Block,
+                                       self.row_repeat_count);
+
+        let mut children: Vec<(usize, i32, AxisPlacement, AxisPlacement)> =
+            self.block_flow
+                .base
+                .children
+                .iter()
+                .enumerate()
+                .filter(|&(_, flow)| {
+                    !flow.as_block().base.flags.contains(FlowFlags::IS_ABSOLUTELY_POSITIONED)
+                })
+                .map(|(index, flow)| {
+                    let kid_position = flow.as_block().fragment.style.get_position();
+                    (index,
+                     kid_position.order,
+                     rows.resolve_placement(&kid_position.grid_row_start, &kid_position.grid_row_end),
+                     columns.resolve_placement(&kid_position.grid_column_start, &kid_position.grid_column_end))
+                })
+                .collect();
+        children.sort_by_key(|&(_, order, _, _)| order);
+
+        let flows_by_column = position.grid_auto_flow.autoflow == AutoFlow::Column;
+        let placements: Vec<Placement> = children.iter().map(|&(_, _, row, column)| {
+            if flows_by_column {
+                Placement { major: column, minor: row }
+            } else {
+                Placement { major: row, minor: column }
+            }
+        }).collect();
+        let explicit_minor_count = if flows_by_column { rows.count() } else { columns.count() };
+        let areas = auto_place(&placements, explicit_minor_count, position.grid_auto_flow.dense);
+
+        let mut row_lines = 0..rows.count();
+        let mut column_lines = 0..columns.count();
+        let areas: Vec<(Range<i32>, Range<i32>)> = areas.into_iter().map(|(major, minor)| {
+            let (row, column) = if flows_by_column { (minor, major) } else { (major, minor) };
+            row_lines = min(row_lines.start, row.start)..max(row_lines.end, row.end);
+            column_lines = min(column_lines.start, column.start)..max(column_lines.end, column.end);
+            (row, column)
+        }).collect();
+
+        self.items = children.iter().zip(areas.into_iter()).map(|(&(index, _, _, _), (row, column))| {
+            GridItem {
+                index: index,
+                rows: ((row.start - row_lines.start) as usize)..((row.end - row_lines.start) as usize),
+                columns: ((column.start - column_lines.start) as usize)..
+                    ((column.end - column_lines.start) as usize),
+            }
+        }).collect();
+        self.row_lines = row_lines;
+        self.column_lines = column_lines;
+    }
+
+    /// Creates the tracks of the implicit grid in the given axis, resolving percentages against
+    /// `basis`. Implicit tracks are sized by `grid-auto-rows` and `grid-auto-columns`.
+    fn build_tracks(&self, direction: Direction, basis: Option<Au>) -> Vec<GridTrack> {
+        let position = self.block_flow.fragment.style.get_position();
+        let (template, auto_size, repeat_count, lines) = match direction {
+            Direction::Inline => (&position.grid_template_columns,
+                                  &position.grid_auto_columns,
+                                  self.column_repeat_count,
+                                  self.column_lines.clone()),
+            Direction::Block => (&position.grid_template_rows,
+                                 &position.grid_auto_rows,
+                                 self.row_repeat_count,
+                                 self.row_lines.clone()),
+        };
+        let explicit = ExplicitTracks::new(template, &position.grid_template_areas, direction, repeat_count);
+        lines.map(|line| {
+            let size = if line >= 0 && (line as usize) < explicit.sizes.len() {
+                &explicit.sizes[line as usize]
+            } else {
+                auto_size
+            };
+            GridTrack::new(size, basis)
+        }).collect()
+    }
+
+    /// Collects the outer inline sizes of the items. Assumes intrinsic inline sizes have been
+    /// computed for every child.
+    fn inline_contributions(&self) -> Vec<Contribution> {
+        let children: Vec<&Flow> = self.block_flow.base.children.iter().collect();
+        self.items.iter().map(|item| {
+            let intrinsic_inline_sizes = &children[item.index].base().intrinsic_inline_sizes;
+            Contribution {
+                span: item.columns.clone(),
+                min_content: intrinsic_inline_sizes.minimum_inline_size,
+                max_content: intrinsic_inline_sizes.preferred_inline_size,
+            }
+        }).collect()
+    }
+
+    /// Collects the outer block sizes of the items. Assumes block sizes have been assigned to
+    /// every child.
+    fn block_contributions(&self) -> Vec<Contribution> {
+        let children: Vec<&Flow> = self.block_flow.base.children.iter().collect();
+        self.items.iter().map(|item| {
+            let fragment = &children[item.index].as_block().fragment;
+            let block_size = fragment.border_box.size.block + fragment.margin.block_start_end();
+            Contribution {
+                span: item.rows.clone(),
+                min_content: block_size,
+                max_content: block_size,
+            }
+        }).collect()
+    }
+
+    /// Returns the content block size given by the style of the container, if any.
+    fn explicit_content_block_size(&self, layout_context: &LayoutContext) -> Option<Au> {
+        let box_border = self.block_flow.fragment.box_sizing_boundary(Direction::Block);
+        let parent_container_size =
+            self.block_flow.explicit_block_containing_size(layout_context.shared_context());
+        // https://drafts.csswg.org/css-ui-3/#box-sizing
+        self.block_flow
+            .explicit_block_size(parent_container_size)
+            .map(|x| max(x - box_border, Au(0)))
+    }
+}
+
+impl Flow for GridFlow {
+    fn class(&self) -> FlowClass {
+        FlowClass::Grid
+    }
+
+    fn as_mut_grid(&mut self) -> &mut GridFlow {
+        self
+    }
+
+    fn as_grid(&self) -> &GridFlow {
+        self
+    }
+
+    fn as_block(&self) -> &BlockFlow {
+        &self.block_flow
+    }
+
+    fn as_mut_block(&mut self) -> &mut BlockFlow {
+        &mut self.block_flow
+    }
+
+    fn mark_as_root(&mut self) {
+        self.block_flow.mark_as_root();
+    }
+
+    fn bubble_inline_sizes(&mut self) {
+        let _scope = layout_debug_scope!("grid::bubble_inline_sizes {:x}",
+                                         self.block_flow.base.debug_id());
+
+        let fixed_width = match self.block_flow.fragment.style().get_position().width {
+            LengthOrPercentageOrAuto::Length(length) => Some(Au::from(length)),
+            _ => None,
+        };
+        self.place_items(fixed_width, None);
+
+        let mut computation = self.block_flow.fragment.compute_intrinsic_inline_sizes();
+        if fixed_width.is_none() {
+            let gap = gap_size(&self.block_flow.fragment.style().get_position().column_gap, Au(0));
+            let contributions = self.inline_contributions();
+            let mut columns = self.build_tracks(Direction::Inline, None);
+
+            size_tracks(&mut columns, &contributions, gap, AvailableSpace::MinContent);
+            computation.content_intrinsic_sizes.minimum_inline_size = total_size(&columns, gap);
+            size_tracks(&mut columns, &contributions, gap, AvailableSpace::MaxContent);
+            computation.content_intrinsic_sizes.preferred_inline_size = total_size(&columns, gap);
+        }
+        self.block_flow.base.intrinsic_inline_sizes = computation.finish();
+    }
+
+    fn assign_inline_sizes(&mut self, layout_context: &LayoutContext) {
+        let _scope = layout_debug_scope!("grid::assign_inline_sizes {:x}", self.block_flow.base.debug_id());
+        debug!("assign_inline_sizes");
+
+        if !self.block_flow.base.restyle_damage.intersects(ServoRestyleDamage::REFLOW_OUT_OF_FLOW |
+                                                           ServoRestyleDamage::REFLOW) {
+            return
+        }
+
+        self.block_flow.initialize_container_size_for_root(layout_context.shared_context());
+
+        // Our inline-size was set to the inline-size of the containing block by the flow's parent.
+        // Now compute the real value.
+        let containing_block_inline_size = self.block_flow.base.block_container_inline_size;
+        self.block_flow.compute_used_inline_size(layout_context.shared_context(),
+                                                 containing_block_inline_size);
+        if self.block_flow.base.flags.is_float() {
+            self.block_flow.float.as_mut().unwrap().containing_inline_size = containing_block_inline_size
+        }
+
+        // Move in from the inline-start border edge.
+        let inline_start_content_edge = self.block_flow.fragment.border_box.start.i +
+            self.block_flow.fragment.border_padding.inline_start;
+        let content_inline_size = self.block_flow.fragment.border_box.size.inline -
+            self.block_flow.fragment.border_padding.inline_start_end();
+        let explicit_content_size = self.explicit_content_block_size(layout_context);
+
+        self.place_items(Some(content_inline_size), explicit_content_size);
+
+        let (column_gap, row_gap) = {
+            let position = self.block_flow.fragment.style().get_position();
+            (gap_size(&position.column_gap, content_inline_size),
+             gap_size(&position.row_gap, explicit_content_size.unwrap_or(Au(0))))
+        };
+        let contributions = self.inline_contributions();
+        let mut columns = self.build_tracks(Direction::Inline, Some(content_inline_size));
+        size_tracks(&mut columns,
+                    &contributions,
+                    column_gap,
+                    AvailableSpace::Definite(content_inline_size));
+        let column_offsets = track_offsets(&columns, inline_start_content_edge, column_gap);
+
+        // Items only spanning rows of fixed size have a definite block size for their contents
+        // to resolve percentages against.
+        let mut rows = self.build_tracks(Direction::Block, explicit_content_size);
+        for track in rows.iter_mut() {
+            track.initialize();
+        }
+        let row_offsets = track_offsets(&rows, Au(0), row_gap);
+
+        // TODO: Handle right-to-left grids.
+        let containing_block_mode = self.block_flow.base.writing_mode;
+        let containing_block_text_align =
+            self.block_flow.fragment.style().get_inherited_text().text_align;
+        let mut children = self.block_flow.base.children.random_access_mut();
+        for item in &self.items {
+            let kid_base = children.get(item.index).mut_base();
+            kid_base.block_container_writing_mode = containing_block_mode;
+            kid_base.block_container_inline_size = area_size(&columns, &column_offsets, &item.columns);
+            kid_base.block_container_explicit_block_size =
+                if rows[item.rows.clone()].iter().all(|track| track.is_fixed()) {
+                    Some(area_size(&rows, &row_offsets, &item.rows))
+                } else {
+                    None
+                };
+            // Per CSS 2.1 § 16.3.1, text alignment propagates to all children in flow.
+            //
+            // TODO(#2265, pcwalton): Do this in the cascade instead.
+            kid_base.flags.set_text_align(containing_block_text_align);
+            kid_base.position.start.i = column_offsets[item.columns.start];
+        }
+        self.columns = columns;
+    }
+
+    fn assign_block_size(&mut self, layout_context: &LayoutContext) {
+        let _scope = layout_debug_scope!("grid::assign_block_size {:x}", self.block_flow.base.debug_id());
+
+        let explicit_content_size = self.explicit_content_block_size(layout_context);
+        let row_gap = gap_size(&self.block_flow.fragment.style().get_position().row_gap,
+                               explicit_content_size.unwrap_or(Au(0)));
+        let available_space = match explicit_content_size {
+            Some(size) => AvailableSpace::Definite(size),
+            None => AvailableSpace::MaxContent,
+        };
+        let contributions = self.block_contributions();
+        let mut rows = self.build_tracks(Direction::Block, explicit_content_size);
+        size_tracks(&mut rows, &contributions, row_gap, available_space);
+
+        let content_block_size = match explicit_content_size {
+            Some(size) => size,
+            None => {
+                let style = &self.block_flow.fragment.style;
+                let parent_container_size =
+                    self.block_flow.explicit_block_containing_size(layout_context.shared_context());
+                let box_border = self.block_flow.fragment.box_sizing_boundary(Direction::Block);
+                SizeConstraint::new(parent_container_size,
+                                    style.min_block_size(),
+                                    style.max_block_size(),
+                                    Some(box_border)).clamp(total_size(&rows, row_gap))
+            }
+        };
+        let block_start_content_edge = self.block_flow.fragment.border_padding.block_start;
+        let row_offsets = track_offsets(&rows, block_start_content_edge, row_gap);
+
+        {
+            let align_items = self.block_flow.fragment.style().get_position().align_items;
+            let mut children = self.block_flow.base.children.random_access_mut();
+            for item in &self.items {
+                let block = children.get(item.index).as_mut_block();
+                let area_block_size = area_size(&rows, &row_offsets, &item.rows);
+                let margin = block.fragment.style().logical_margin();
+                let margin_block_start = block.fragment.margin.block_start;
+                let margin_block_end = block.fragment.margin.block_end;
+                let free_space = area_block_size - block.base.position.size.block -
+                    margin_block_start - margin_block_end;
+
+                let self_align = match block.fragment.style().get_position().align_self {
+                    AlignSelf::Auto => match align_items {
+                        AlignItems::Stretch => AlignSelf::Stretch,
+                        AlignItems::FlexStart => AlignSelf::FlexStart,
+                        AlignItems::FlexEnd => AlignSelf::FlexEnd,
+                        AlignItems::Center => AlignSelf::Center,
+                        AlignItems::Baseline => AlignSelf::Baseline,
+                    },
+                    align_self => align_self,
+                };
+                let has_auto_margin = margin.block_start == LengthOrPercentageOrAuto::Auto ||
+                    margin.block_end == LengthOrPercentageOrAuto::Auto;
+                let offset = if free_space <= Au(0) {
+                    Au(0)
+                } else if has_auto_margin {
+                    if margin.block_start != LengthOrPercentageOrAuto::Auto {
+                        Au(0)
+                    } else if margin.block_end != LengthOrPercentageOrAuto::Auto {
+                        free_space
+                    } else {
+                        free_space / 2
+                    }
+                } else {
+                    match self_align {
+                        AlignSelf::Stretch
+                                if block.fragment.style().content_block_size() == LengthOrPercentageOrAuto::Auto => {
+                            block.base.position.size.block += free_space;
+                            block.fragment.border_box.size.block = block.base.position.size.block;
+                            // FIXME: Like flex items, stretched items should lay out their
+                            // contents against the stretched size.
+                            Au(0)
+                        }
+                        AlignSelf::FlexEnd => free_space,
+                        AlignSelf::Center => free_space / 2,
+                        // TODO: Support baseline alignment.
+                        _ => Au(0),
+                    }
+                };
+                block.base.position.start.b = row_offsets.get(item.rows.start).cloned().unwrap_or(Au(0)) +
+                    margin_block_start + offset;
+            }
+        }
+        self.rows = rows;
+
+        let total_block_size = content_block_size + self.block_flow.fragment.border_padding.block_start_end();
+        self.block_flow.fragment.border_box.size.block = total_block_size;
+        self.block_flow.base.position.size.block = total_block_size;
+
+        let block_start = AdjoiningMargins::from_margin(self.block_flow.fragment.margin.block_start);
+        let block_end = AdjoiningMargins::from_margin(self.block_flow.fragment.margin.block_end);
+        self.block_flow.base.collapsible_margins = CollapsibleMargins::Collapse(block_start, block_end);
+
+        // TODO: Assign proper static positions for absolute descendants.
+        if (&*self as &Flow).contains_roots_of_absolute_flow_tree() {
+            // Assign block-sizes for all flows in this absolute flow tree.
+            // This is preorder because the block-size of an absolute flow may depend on
+            // the block-size of its containing block, which may also be an absolute flow.
+            let assign_abs_b_sizes = AbsoluteAssignBSizesTraversal(layout_context.shared_context());
+            assign_abs_b_sizes.traverse_absolute_flows(&mut *self);
+        }
+    }
+
+    fn compute_stacking_relative_position(&mut self, layout_context: &LayoutContext) {
+        self.block_flow.compute_stacking_relative_position(layout_context)
+    }
+
+    fn place_float_if_applicable<'a>(&mut self) {
+        self.block_flow.place_float_if_applicable()
+    }
+
+    fn update_late_computed_inline_position_if_necessary(&mut self, inline_position: Au) {
+        self.block_flow.update_late_computed_inline_position_if_necessary(inline_position)
+    }
+
+    fn update_late_computed_block_position_if_necessary(&mut self, block_position: Au) {
+        self.block_flow.update_late_computed_block_position_if_necessary(block_position)
+    }
+
+    fn build_display_list(&mut self, state: &mut DisplayListBuildState) {
+        self.build_display_list_for_grid(state);
+    }
+
+    fn collect_stacking_contexts(&mut self, state: &mut StackingContextCollectionState) {
+        self.block_flow.collect_stacking_contexts(state);
+    }
+
+    fn repair_style(&mut self, new_style: &::ServoArc<ComputedValues>) {
+        self.block_flow.repair_style(new_style)
+    }
+
+    fn compute_overflow(&self) -> Overflow {
+        self.block_flow.compute_overflow()
+    }
+
+    fn contains_roots_of_absolute_flow_tree(&self) -> bool {
+        self.block_flow.contains_roots_of_absolute_flow_tree()
+    }
+
+    fn is_absolute_containing_block(&self) -> bool {
+        self.block_flow.is_absolute_containing_block()
+    }
+
+    fn generated_containing_block_size(&self, flow: OpaqueFlow) -> LogicalSize<Au> {
+        self.block_flow.generated_containing_block_size(flow)
+    }
+
+    fn iterate_through_fragment_border_boxes(&self,
+                                             iterator: &mut FragmentBorderBoxIterator,
+                                             level: i32,
+                                             stacking_context_position: &Point2D<Au>) {
+        self.block_flow.iterate_through_fragment_border_boxes(iterator, level, stacking_context_position);
+    }
+
+    fn mutate_fragments(&mut self, mutator: &mut FnMut(&mut Fragment)) {
+        self.block_flow.mutate_fragments(mutator);
+    }
+}
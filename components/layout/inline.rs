@@ -1214,6 +1214,7 @@ impl InlineFlow {
                 (Display::Inline, GenericVerticalAlign::Top) |
                 (Display::Block, GenericVerticalAlign::Top) |
                 (Display::InlineFlex, GenericVerticalAlign::Top) |
+                (Display::InlineGrid, GenericVerticalAlign::Top) |
                 (Display::InlineBlock, GenericVerticalAlign::Top) if
                         inline_metrics.space_above_baseline >= Au(0) => {
                     *largest_block_size_for_top_fragments = max(
@@ -1223,6 +1224,7 @@ impl InlineFlow {
                 (Display::Inline, GenericVerticalAlign::Bottom) |
                 (Display::Block, GenericVerticalAlign::Bottom) |
                 (Display::InlineFlex, GenericVerticalAlign::Bottom) |
+                (Display::InlineGrid, GenericVerticalAlign::Bottom) |
                 (Display::InlineBlock, GenericVerticalAlign::Bottom) if
                         inline_metrics.space_below_baseline >= Au(0) => {
                     *largest_block_size_for_bottom_fragments = max(
@@ -11,6 +11,7 @@ use display_list::IndexableText;
 use display_list::items::{DisplayList, OpaqueNode, ScrollOffsetMap};
 use euclid::{Point2D, Vector2D, Rect, Size2D};
 use flow::{Flow, GetBaseFlow};
+use fnv::FnvHashMap;
 use fragment::{Fragment, FragmentBorderBoxIterator, SpecificFragmentInfo};
 use inline::InlineFragmentNodeFlags;
 use ipc_channel::ipc::IpcSender;
@@ -20,15 +21,18 @@ use script_layout_interface::{LayoutElementType, LayoutNodeType};
 use script_layout_interface::StyleData;
 use script_layout_interface::rpc::{ContentBoxResponse, ContentBoxesResponse, LayoutRPC};
 use script_layout_interface::rpc::{NodeGeometryResponse, NodeScrollIdResponse};
+use script_layout_interface::rpc::{ObservedBoxes, ObservedBoxesResponse, TextIndexResponse};
 use script_layout_interface::rpc::{OffsetParentResponse, ResolvedStyleResponse, StyleResponse};
-use script_layout_interface::rpc::TextIndexResponse;
+use script_layout_interface::rpc::{RunningCSSAnimation, RunningCSSAnimationKind, RunningCSSAnimationsResponse};
 use script_layout_interface::wrapper_traits::{LayoutNode, ThreadSafeLayoutElement, ThreadSafeLayoutNode};
 use script_traits::LayoutMsg as ConstellationMsg;
 use script_traits::UntrustedNodeAddress;
 use sequential;
 use std::cmp::{min, max};
+use std::f64;
 use std::ops::Deref;
 use std::sync::{Arc, Mutex};
+use style::animation::{Animation, KeyframesIterationState, KeyframesRunningState};
 use style::computed_values::display::T as Display;
 use style::computed_values::position::T as Position;
 use style::computed_values::visibility::T as Visibility;
@@ -88,6 +92,12 @@ pub struct LayoutThreadData {
 
     /// A queued response for the inner text of a given element.
     pub element_inner_text_response: String,
+
+    /// A queued response for the border, padding and content boxes of a list of nodes.
+    pub observed_boxes_response: Vec<Option<ObservedBoxes>>,
+
+    /// A queued response for the CSS animations and transitions running in layout.
+    pub running_css_animations_response: Vec<RunningCSSAnimation>,
 }
 
 pub struct LayoutRPCImpl(pub Arc<Mutex<LayoutThreadData>>);
@@ -176,6 +186,18 @@ impl LayoutRPC for LayoutRPCImpl {
         let rw_data = rw_data.lock().unwrap();
         rw_data.element_inner_text_response.clone()
     }
+
+    fn observed_boxes(&self) -> ObservedBoxesResponse {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        ObservedBoxesResponse(rw_data.observed_boxes_response.clone())
+    }
+
+    fn running_css_animations(&self) -> RunningCSSAnimationsResponse {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        RunningCSSAnimationsResponse(rw_data.running_css_animations_response.clone())
+    }
 }
 
 struct UnioningFragmentBorderBoxIterator {
@@ -620,6 +642,52 @@ impl FragmentBorderBoxIterator for ParentOffsetBorderBoxIterator {
 }
 
 
+/// Unions the border, padding and content boxes of the fragments of several nodes
+/// in a single traversal of the flow tree.
+struct ObservedBoxesIterator {
+    node_addresses: Vec<OpaqueNode>,
+    boxes: Vec<Option<ObservedBoxes>>,
+}
+
+impl FragmentBorderBoxIterator for ObservedBoxesIterator {
+    fn process(&mut self, fragment: &Fragment, _: i32, border_box: &Rect<Au>) {
+        let writing_mode = fragment.style.writing_mode;
+        let padding_box = border_box.inner_rect(fragment.style.logical_border_width().to_physical(writing_mode));
+        let content_box = border_box.inner_rect(fragment.border_padding.to_physical(writing_mode));
+        for (node_address, boxes) in self.node_addresses.iter().zip(self.boxes.iter_mut()) {
+            if !fragment.contains_node(*node_address) {
+                continue;
+            }
+            *boxes = Some(match *boxes {
+                Some(ref boxes) => ObservedBoxes {
+                    border_box: boxes.border_box.union(border_box),
+                    padding_box: boxes.padding_box.union(&padding_box),
+                    content_box: boxes.content_box.union(&content_box),
+                },
+                None => ObservedBoxes {
+                    border_box: *border_box,
+                    padding_box: padding_box,
+                    content_box: content_box,
+                },
+            });
+        }
+    }
+
+    fn should_process(&mut self, fragment: &Fragment) -> bool {
+        self.node_addresses.iter().any(|node_address| fragment.contains_node(*node_address))
+    }
+}
+
+pub fn process_observed_boxes_request<N: LayoutNode>(requested_nodes: Vec<N>, layout_root: &mut Flow)
+        -> Vec<Option<ObservedBoxes>> {
+    let mut iterator = ObservedBoxesIterator {
+        boxes: vec![None; requested_nodes.len()],
+        node_addresses: requested_nodes.into_iter().map(|node| node.opaque()).collect(),
+    };
+    sequential::iterate_through_flow_tree_fragment_border_boxes(layout_root, &mut iterator);
+    iterator.boxes
+}
+
 pub fn process_node_geometry_request<N: LayoutNode>(requested_node: N, layout_root: &mut Flow)
         -> Rect<i32> {
     let mut iterator = FragmentLocatingFragmentIterator::new(requested_node.opaque());
@@ -1002,7 +1070,7 @@ fn inner_text_collection_steps<N: LayoutNode>(node: N,
                     "\u{000A}", /* line feed */
                 )));
             },
-            Display::Block | Display::Flex | Display::TableCaption | Display::Table => {
+            Display::Block | Display::Flex | Display::Grid | Display::TableCaption | Display::Table => {
                 // Step 9.
                 items.insert(0, InnerTextItem::RequiredLineBreakCount(1));
                 items.push(InnerTextItem::RequiredLineBreakCount(1));
@@ -1023,3 +1091,46 @@ fn is_last_table_row() -> bool {
     // FIXME(ferjm) Implement this.
     false
 }
+
+/// Returns the CSS animations and transitions running in layout, with their
+/// timing as of `now`.
+pub fn process_running_css_animations_query(running_animations: &FnvHashMap<OpaqueNode, Vec<Animation>>,
+                                            now: f64)
+                                            -> Vec<RunningCSSAnimation> {
+    running_animations.values().flat_map(|animations| animations.iter()).filter_map(|animation| {
+        if animation.is_expired() {
+            return None;
+        }
+        Some(match *animation {
+            Animation::Transition(node, start_time, ref frame, _) => RunningCSSAnimation {
+                node: node.to_untrusted_node_address(),
+                kind: RunningCSSAnimationKind::Transition(frame.property_animation.property_name().to_owned()),
+                duration: frame.duration,
+                delay: 0.,
+                iterations: 1.,
+                elapsed: now - start_time,
+                paused: false,
+            },
+            Animation::Keyframes(node, _, ref name, ref state) => {
+                let iterations = match state.iteration_state {
+                    KeyframesIterationState::Infinite(_) => f64::INFINITY,
+                    KeyframesIterationState::Finite(_, count) => count as f64,
+                };
+                let elapsed_in_iteration = match state.running_state {
+                    KeyframesRunningState::Paused(progress) => state.duration * progress,
+                    KeyframesRunningState::Running => now - state.started_at,
+                };
+                let completed = state.iteration_state.current_iteration() as f64 * state.duration;
+                RunningCSSAnimation {
+                    node: node.to_untrusted_node_address(),
+                    kind: RunningCSSAnimationKind::Animation(name.to_string()),
+                    duration: state.duration,
+                    delay: state.delay,
+                    iterations: iterations,
+                    elapsed: state.delay + completed + elapsed_in_iteration,
+                    paused: animation.is_paused(),
+                }
+            },
+        })
+    }).collect()
+}
@@ -34,7 +34,7 @@ use atomic_refcell::{AtomicRef, AtomicRefMut};
 use data::{LayoutData, LayoutDataFlags, StyleAndLayoutData};
 use script_layout_interface::wrapper_traits::{ThreadSafeLayoutElement, ThreadSafeLayoutNode};
 use script_layout_interface::wrapper_traits::GetLayoutData;
-use style::dom::{NodeInfo, TNode};
+use style::dom::{NodeInfo, TElement, TNode};
 use style::selector_parser::RestyleDamage;
 use style::values::computed::counters::ContentItem;
 use style::values::generics::counters::Content;
@@ -134,8 +134,7 @@ impl<T: ThreadSafeLayoutNode> ThreadSafeLayoutNodeHelpers for T {
         // If this is a text node, use the parent element, since that's what
         // controls our style.
         if node.is_text_node() {
-            node = node.parent_node().unwrap();
-            debug_assert!(node.is_element());
+            node = node.traversal_parent().unwrap().as_node();
         }
 
         let damage = {
@@ -38,9 +38,10 @@ use layout::wrapper::GetRawData;
 use msg::constellation_msg::{BrowsingContextId, PipelineId};
 use range::Range;
 use script::layout_exports::{CharacterDataTypeId, ElementTypeId, HTMLElementTypeId, NodeTypeId};
-use script::layout_exports::{Document, Element, Node, Text};
-use script::layout_exports::{LayoutCharacterDataHelpers, LayoutDocumentHelpers};
+use script::layout_exports::{Document, Element, HTMLSlotElement, Node, ShadowRoot, Text};
+use script::layout_exports::{LayoutCharacterDataHelpers, LayoutDocumentHelpers, LayoutHTMLSlotElementHelpers};
 use script::layout_exports::{LayoutElementHelpers, LayoutNodeHelpers, LayoutDom, RawLayoutElementHelpers};
+use script::layout_exports::LayoutShadowRootHelpers;
 use script::layout_exports::NodeFlags;
 use script::layout_exports::PendingRestyle;
 use script_layout_interface::{HTMLCanvasData, LayoutNodeType, SVGSVGData, TrustedNodeAddress};
@@ -65,14 +66,15 @@ use style::applicable_declarations::ApplicableDeclarationBlock;
 use style::attr::AttrValue;
 use style::context::SharedStyleContext;
 use style::data::ElementData;
-use style::dom::{DomChildren, LayoutIterator, NodeInfo, OpaqueNode};
+use style::dom::{LayoutIterator, NodeInfo, OpaqueNode};
 use style::dom::{TDocument, TElement, TNode, TShadowRoot};
 use style::element_state::*;
 use style::font_metrics::ServoMetricsProvider;
+use style::media_queries::Device;
 use style::properties::{ComputedValues, PropertyDeclarationBlock};
 use style::selector_parser::{AttrValue as SelectorAttrValue, NonTSPseudoClass, PseudoClassStringArg};
 use style::selector_parser::{PseudoElement, SelectorImpl, extended_filtering};
-use style::shared_lock::{SharedRwLock as StyleSharedRwLock, Locked as StyleLocked};
+use style::shared_lock::{SharedRwLock as StyleSharedRwLock, SharedRwLockReadGuard, Locked as StyleLocked};
 use style::str::is_whitespace;
 use style::stylist::CascadeData;
 
@@ -151,35 +153,71 @@ impl<'ln> NodeInfo for ServoLayoutNode<'ln> {
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
-enum Impossible { }
+/// A wrapper around shadow roots that ensures layout can only ever access
+/// safe properties.
+#[derive(Clone, Copy)]
+pub struct ServoShadowRoot<'lr> {
+    /// The wrapped shadow root.
+    shadow_root: LayoutDom<ShadowRoot>,
 
-#[derive(Clone, Copy, PartialEq)]
-pub struct ShadowRoot<'lr>(Impossible, PhantomData<&'lr ()>);
+    /// Being chained to a PhantomData prevents `ShadowRoot`s from escaping.
+    chain: PhantomData<&'lr ()>,
+}
 
-impl<'lr> TShadowRoot for ShadowRoot<'lr> {
+impl<'lr> Debug for ServoShadowRoot<'lr> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_node().fmt(f)
+    }
+}
+
+impl<'lr> PartialEq for ServoShadowRoot<'lr> {
+    #[inline]
+    fn eq(&self, other: &ServoShadowRoot) -> bool {
+        self.shadow_root == other.shadow_root
+    }
+}
+
+impl<'lr> TShadowRoot for ServoShadowRoot<'lr> {
     type ConcreteNode = ServoLayoutNode<'lr>;
 
     fn as_node(&self) -> Self::ConcreteNode {
-        match self.0 { }
+        ServoLayoutNode::from_layout_js(self.shadow_root.upcast())
     }
 
     fn host(&self) -> ServoLayoutElement<'lr> {
-        match self.0 { }
+        ServoLayoutElement::from_layout_js(unsafe { self.shadow_root.get_host_for_layout() })
     }
 
     fn style_data<'a>(&self) -> &'a CascadeData
     where
         Self: 'a,
     {
-        match self.0 { }
+        unsafe { self.shadow_root.get_style_data_for_layout() }
+    }
+}
+
+impl<'lr> ServoShadowRoot<'lr> {
+    fn from_layout_js(shadow_root: LayoutDom<ShadowRoot>) -> ServoShadowRoot<'lr> {
+        ServoShadowRoot {
+            shadow_root,
+            chain: PhantomData,
+        }
+    }
+
+    /// Rebuilds the cascade data of this shadow root if its stylesheets
+    /// changed.
+    pub unsafe fn flush_stylesheets(&self,
+                                    device: &Device,
+                                    quirks_mode: QuirksMode,
+                                    guard: &SharedRwLockReadGuard) {
+        self.shadow_root.flush_stylesheets::<ServoLayoutElement>(device, quirks_mode, guard)
     }
 }
 
 impl<'ln> TNode for ServoLayoutNode<'ln> {
     type ConcreteDocument = ServoLayoutDocument<'ln>;
     type ConcreteElement = ServoLayoutElement<'ln>;
-    type ConcreteShadowRoot = ShadowRoot<'ln>;
+    type ConcreteShadowRoot = ServoShadowRoot<'ln>;
 
     fn parent_node(&self) -> Option<Self> {
         unsafe {
@@ -216,7 +254,14 @@ impl<'ln> TNode for ServoLayoutNode<'ln> {
     }
 
     fn traversal_parent(&self) -> Option<ServoLayoutElement<'ln>> {
-        self.parent_element()
+        if let Some(slot) = unsafe { self.node.assigned_slot_ref() } {
+            return Some(ServoLayoutElement::from_layout_js(slot.upcast()));
+        }
+        let parent = self.parent_node()?;
+        if let Some(shadow_root) = parent.as_shadow_root() {
+            return Some(shadow_root.host());
+        }
+        parent.as_element()
     }
 
     fn opaque(&self) -> OpaqueNode {
@@ -235,8 +280,8 @@ impl<'ln> TNode for ServoLayoutNode<'ln> {
         self.node.downcast().map(ServoLayoutDocument::from_layout_js)
     }
 
-    fn as_shadow_root(&self) -> Option<ShadowRoot<'ln>> {
-        None
+    fn as_shadow_root(&self) -> Option<ServoShadowRoot<'ln>> {
+        self.node.downcast().map(ServoShadowRoot::from_layout_js)
     }
 
     fn is_in_document(&self) -> bool {
@@ -307,6 +352,48 @@ impl<'ln> ServoLayoutNode<'ln> {
     pub unsafe fn get_jsmanaged(&self) -> &LayoutDom<Node> {
         &self.node
     }
+
+    /// Returns the first child of this node in the flat tree, that is, the
+    /// first child of its shadow root if it's a shadow host, or its first
+    /// assigned node if it's a slot with assigned nodes.
+    fn flat_tree_first_child(&self) -> Option<Self> {
+        if let Some(shadow_root) = self.as_element().and_then(|element| element.shadow_root()) {
+            return shadow_root.as_node().first_child();
+        }
+        if let Some(slot) = self.node.downcast::<HTMLSlotElement>() {
+            let assigned_nodes = unsafe { slot.get_assigned_nodes_for_layout() };
+            if let Some(node) = assigned_nodes.first() {
+                return Some(unsafe { self.new_with_this_lifetime(node) });
+            }
+        }
+        self.first_child()
+    }
+
+    /// Returns the next sibling of this node in the flat tree, which is the
+    /// next node assigned to the same slot if this node is assigned to one.
+    fn flat_tree_next_sibling(&self) -> Option<Self> {
+        if let Some(slot) = unsafe { self.node.assigned_slot_ref() } {
+            let assigned_nodes = unsafe { slot.get_assigned_nodes_for_layout() };
+            let index = assigned_nodes.iter().position(|node| *node == self.node)?;
+            return assigned_nodes.get(index + 1).map(|node| unsafe { self.new_with_this_lifetime(node) });
+        }
+        self.next_sibling()
+    }
+}
+
+/// An iterator over the children of a node in the flat tree.
+pub struct FlatTreeChildrenIterator<'ln> {
+    current: Option<ServoLayoutNode<'ln>>,
+}
+
+impl<'ln> Iterator for FlatTreeChildrenIterator<'ln> {
+    type Item = ServoLayoutNode<'ln>;
+
+    fn next(&mut self) -> Option<ServoLayoutNode<'ln>> {
+        let current = self.current?;
+        self.current = current.flat_tree_next_sibling();
+        Some(current)
+    }
 }
 
 // A wrapper around documents that ensures ayout can only ever access safe properties.
@@ -354,6 +441,39 @@ impl<'ld> ServoLayoutDocument<'ld> {
         unsafe { self.document.style_shared_lock() }
     }
 
+    pub fn shadow_roots(&self) -> Vec<ServoShadowRoot<'ld>> {
+        unsafe {
+            self.document.shadow_roots().into_iter().map(ServoShadowRoot::from_layout_js).collect()
+        }
+    }
+
+    pub fn shadow_roots_styles_changed(&self) -> bool {
+        unsafe { self.document.shadow_roots_styles_changed() }
+    }
+
+    /// Tells script whether the stylesheets contain :has() selectors, so that
+    /// DOM mutations only look for their anchors when they may exist.
+    pub fn set_has_relative_selectors(&self, value: bool) {
+        unsafe { self.document.set_has_relative_selectors(value) }
+    }
+
+    /// Rebuilds the cascade data of the shadow roots whose stylesheets
+    /// changed.
+    pub fn flush_shadow_roots_stylesheets(&self,
+                                          device: &Device,
+                                          quirks_mode: QuirksMode,
+                                          guard: &SharedRwLockReadGuard) {
+        unsafe {
+            if !self.document.shadow_roots_styles_changed() {
+                return;
+            }
+            self.document.flush_shadow_roots_stylesheets();
+            for shadow_root in self.shadow_roots() {
+                shadow_root.flush_stylesheets(device, quirks_mode, guard);
+            }
+        }
+    }
+
     pub fn from_layout_js(doc: LayoutDom<Document>) -> ServoLayoutDocument<'ld> {
         ServoLayoutDocument {
             document: doc,
@@ -381,7 +501,7 @@ impl<'le> fmt::Debug for ServoLayoutElement<'le> {
 
 impl<'le> TElement for ServoLayoutElement<'le> {
     type ConcreteNode = ServoLayoutNode<'le>;
-    type TraversalChildrenIterator = DomChildren<Self::ConcreteNode>;
+    type TraversalChildrenIterator = FlatTreeChildrenIterator<'le>;
 
     type FontMetricsProvider = ServoMetricsProvider;
 
@@ -390,7 +510,13 @@ impl<'le> TElement for ServoLayoutElement<'le> {
     }
 
     fn traversal_children(&self) -> LayoutIterator<Self::TraversalChildrenIterator> {
-        LayoutIterator(self.as_node().dom_children())
+        LayoutIterator(FlatTreeChildrenIterator {
+            current: self.as_node().flat_tree_first_child(),
+        })
+    }
+
+    fn inheritance_parent(&self) -> Option<Self> {
+        self.traversal_parent()
     }
 
     fn is_html_element(&self) -> bool {
@@ -575,12 +701,16 @@ impl<'le> TElement for ServoLayoutElement<'le> {
         }
     }
 
-    fn shadow_root(&self) -> Option<ShadowRoot<'le>> {
-        None
+    fn shadow_root(&self) -> Option<ServoShadowRoot<'le>> {
+        unsafe {
+            self.element.get_shadow_root_for_layout().map(ServoShadowRoot::from_layout_js)
+        }
     }
 
-    fn containing_shadow(&self) -> Option<ShadowRoot<'le>> {
-        None
+    fn containing_shadow(&self) -> Option<ServoShadowRoot<'le>> {
+        unsafe {
+            self.as_node().node.containing_shadow_root_ref().map(ServoShadowRoot::from_layout_js)
+        }
     }
 }
 
@@ -635,8 +765,6 @@ impl<'le> ServoLayoutElement<'le> {
     }
 
     pub unsafe fn note_dirty_descendant(&self) {
-        use ::selectors::Element;
-
         let mut current = Some(*self);
         while let Some(el) = current {
             // FIXME(bholley): Ideally we'd have the invariant that any element
@@ -645,7 +773,7 @@ impl<'le> ServoLayoutElement<'le> {
             // we get that wrong.  I have in-flight patches to fix all this
             // stuff up, so we just always propagate this bit for now.
             el.set_dirty_descendants();
-            current = el.parent_element();
+            current = el.traversal_parent();
         }
     }
 }
@@ -668,11 +796,17 @@ impl<'le> ::selectors::Element for ServoLayoutElement<'le> {
     }
 
     fn parent_node_is_shadow_root(&self) -> bool {
-        false
+        self.as_node().parent_node().map_or(false, |parent| parent.as_shadow_root().is_some())
     }
 
     fn containing_shadow_host(&self) -> Option<Self> {
-        None
+        self.containing_shadow().map(|shadow_root| shadow_root.host())
+    }
+
+    fn assigned_slot(&self) -> Option<Self> {
+        unsafe {
+            self.as_node().node.assigned_slot_ref().map(|slot| ServoLayoutElement::from_layout_js(slot.upcast()))
+        }
     }
 
     fn first_child_element(&self) -> Option<ServoLayoutElement<'le>> {
@@ -876,12 +1010,12 @@ impl<'a> PartialEq for ServoThreadSafeLayoutNode<'a> {
 
 impl<'ln> DangerousThreadSafeLayoutNode for ServoThreadSafeLayoutNode<'ln> {
     unsafe fn dangerous_first_child(&self) -> Option<Self> {
-            self.get_jsmanaged().first_child_ref()
-                .map(|node| self.new_with_this_lifetime(&node))
+            self.node.flat_tree_first_child()
+                .map(|node| ServoThreadSafeLayoutNode::new(&node))
     }
     unsafe fn dangerous_next_sibling(&self) -> Option<Self> {
-            self.get_jsmanaged().next_sibling_ref()
-                .map(|node| self.new_with_this_lifetime(&node))
+            self.node.flat_tree_next_sibling()
+                .map(|node| ServoThreadSafeLayoutNode::new(&node))
     }
 }
 
@@ -938,11 +1072,20 @@ impl<'ln> ThreadSafeLayoutNode for ServoThreadSafeLayoutNode<'ln> {
     }
 
     fn parent_style(&self) -> Arc<ComputedValues> {
-        let parent = self.node.parent_node().unwrap().as_element().unwrap();
+        let parent = self.node.traversal_parent().unwrap();
         let parent_data = parent.get_data().unwrap().borrow();
         parent_data.styles.primary().clone()
     }
 
+    fn parent_selected_style(&self) -> Arc<ComputedValues> {
+        let parent = self.node.traversal_parent().unwrap();
+        let parent_data = parent.get_data().unwrap().borrow();
+        parent_data.styles.pseudos
+            .get(&PseudoElement::Selection)
+            .unwrap_or(parent_data.styles.primary())
+            .clone()
+    }
+
     fn debug_id(self) -> usize {
         self.node.debug_id()
     }
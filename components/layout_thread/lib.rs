@@ -67,6 +67,7 @@ use histogram::Histogram;
 use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use ipc_channel::router::ROUTER;
 use layout::animation;
+use layout::animation::ScriptAnimations;
 use layout::construct::ConstructionResult;
 use layout::context::LayoutContext;
 use layout::context::RegisteredPainter;
@@ -82,7 +83,8 @@ use layout::parallel;
 use layout::query::{LayoutRPCImpl, LayoutThreadData, process_content_box_request, process_content_boxes_request};
 use layout::query::{process_element_inner_text_query, process_node_geometry_request};
 use layout::query::{process_node_scroll_area_request, process_node_scroll_id_request};
-use layout::query::{process_offset_parent_query, process_resolved_style_request, process_style_query};
+use layout::query::{process_observed_boxes_request, process_offset_parent_query};
+use layout::query::{process_resolved_style_request, process_running_css_animations_query, process_style_query};
 use layout::sequential;
 use layout::traversal::{ComputeStackingRelativePositions, PreorderFlowTraversal, RecalcStyleAndConstructFlows};
 use layout::wrapper::LayoutNodeLayoutData;
@@ -106,7 +108,6 @@ use script_traits::{ConstellationControlMsg, LayoutControlMsg, LayoutMsg as Cons
 use script_traits::{DrawAPaintImageResult, PaintWorkletError};
 use script_traits::{ScrollState, UntrustedNodeAddress};
 use script_traits::Painter;
-use selectors::Element;
 use servo_arc::Arc as ServoArc;
 use servo_atoms::Atom;
 use servo_config::opts;
@@ -129,6 +130,7 @@ use style::context::{SharedStyleContext, StyleSystemOptions, ThreadLocalStyleCon
 use style::dom::{ShowSubtree, ShowSubtreeDataAndPrimaryValues, TElement, TNode};
 use style::driver;
 use style::error_reporting::RustLogReporter;
+use style::invalidation::element::relative_selectors::invalidate_relative_selector_anchors;
 use style::invalidation::element::restyle_hints::RestyleHint;
 use style::logical_geometry::LogicalPoint;
 use style::media_queries::{Device, MediaList, MediaType};
@@ -170,10 +172,10 @@ pub struct LayoutThread {
     pipeline_port: Receiver<LayoutControlMsg>,
 
     /// The port on which we receive messages from the font cache thread.
-    font_cache_receiver: Receiver<()>,
+    font_cache_receiver: Receiver<bool>,
 
     /// The channel on which the font cache can send messages to us.
-    font_cache_sender: IpcSender<()>,
+    font_cache_sender: IpcSender<bool>,
 
     /// The channel on which messages can be sent to the constellation.
     constellation_chan: IpcSender<ConstellationMsg>,
@@ -228,6 +230,9 @@ pub struct LayoutThread {
     /// The list of animations that have expired since the last style recalculation.
     expired_animations: ServoArc<RwLock<FnvHashMap<OpaqueNode, Vec<Animation>>>>,
 
+    /// The animations created from script that were applied by the last reflow.
+    script_animations: FnvHashMap<OpaqueNode, ScriptAnimations>,
+
     /// A counter for epoch messages
     epoch: Cell<Epoch>,
 
@@ -417,7 +422,7 @@ fn add_font_face_rules(stylesheet: &Stylesheet,
                        guard: &SharedRwLockReadGuard,
                        device: &Device,
                        font_cache_thread: &FontCacheThread,
-                       font_cache_sender: &IpcSender<()>,
+                       font_cache_sender: &IpcSender<bool>,
                        outstanding_web_fonts_counter: &Arc<AtomicUsize>) {
     if opts::get().load_webfonts_synchronously {
         let (sender, receiver) = ipc::channel().unwrap();
@@ -443,6 +448,21 @@ fn add_font_face_rules(stylesheet: &Stylesheet,
     }
 }
 
+/// Returns a sender for the font cache thread to report the load of a web
+/// font requested by script. Successful loads invalidate the font caches
+/// before the result is forwarded to `sender`.
+fn web_font_sender_for_script(sender: IpcSender<bool>) -> IpcSender<bool> {
+    let (font_cache_sender, font_cache_receiver) = ipc::channel().unwrap();
+    ROUTER.add_route(font_cache_receiver.to_opaque(), Box::new(move |message| {
+        let loaded = message.to().unwrap();
+        if loaded {
+            font_context::invalidate_font_caches();
+        }
+        let _ = sender.send(loaded);
+    }));
+    font_cache_sender
+}
+
 impl LayoutThread {
     /// Creates a new `LayoutThread` structure.
     fn new(id: PipelineId,
@@ -519,6 +539,7 @@ impl LayoutThread {
             document_shared_lock: None,
             running_animations: ServoArc::new(RwLock::new(FnvHashMap::default())),
             expired_animations: ServoArc::new(RwLock::new(FnvHashMap::default())),
+            script_animations: FnvHashMap::default(),
             epoch: Cell::new(Epoch(0)),
             viewport_size: Size2D::new(Au(0), Au(0)),
             webrender_api: webrender_api_sender.create_api(),
@@ -541,6 +562,8 @@ impl LayoutThread {
                     text_index_response: TextIndexResponse(None),
                     nodes_from_point_response: vec![],
                     element_inner_text_response: String::new(),
+                    observed_boxes_response: Vec::new(),
+                    running_css_animations_response: Vec::new(),
                 })),
             webrender_image_cache:
                 Arc::new(RwLock::new(FnvHashMap::default())),
@@ -782,6 +805,12 @@ impl LayoutThread {
             Msg::SetNavigationStart(time) => {
                 self.paint_time_metrics.set_navigation_start(time);
             },
+            Msg::AddWebFont(family, sources, sender) => {
+                self.font_cache_thread.add_web_font(family, sources, web_font_sender_for_script(sender));
+            },
+            Msg::AddWebFontData(family, data, sender) => {
+                self.font_cache_thread.add_web_font_data(family, data, web_font_sender_for_script(sender));
+            },
         }
 
         true
@@ -1142,12 +1171,19 @@ impl LayoutThread {
                         &QueryMsg::StyleQuery(_) => {
                             rw_data.style_response = StyleResponse(None);
                         },
-                        &QueryMsg::TextIndexQuery(..) => {
+                        &QueryMsg::TextIndexQuery(..) |
+                        &QueryMsg::TextIndexAtPointQuery(..) => {
                             rw_data.text_index_response = TextIndexResponse(None);
                         }
                         &QueryMsg::ElementInnerTextQuery(_) => {
                             rw_data.element_inner_text_response = String::new();
                         },
+                        &QueryMsg::ObservedBoxesQuery(ref nodes) => {
+                            rw_data.observed_boxes_response = vec![None; nodes.len()];
+                        },
+                        &QueryMsg::RunningCSSAnimationsQuery => {
+                            rw_data.running_css_animations_response = Vec::new();
+                        },
                     },
                     ReflowGoal::Full | ReflowGoal:: TickAnimations => {}
                 }
@@ -1256,7 +1292,7 @@ impl LayoutThread {
             // Propagate the descendant bit up the ancestors. Do this before
             // the restyle calculation so that we can also do it for new
             // unstyled nodes, which the descendants bit helps us find.
-            if let Some(parent) = el.parent_element() {
+            if let Some(parent) = el.traversal_parent() {
                 unsafe { parent.note_dirty_descendant() };
             }
 
@@ -1283,8 +1319,24 @@ impl LayoutThread {
             debug!("Noting restyle for {:?}: {:?}", el, style_data);
         }
 
+        document.flush_shadow_roots_stylesheets(
+            self.stylist.device(),
+            self.stylist.quirks_mode(),
+            guards.author,
+        );
+
         self.stylist.flush(&guards, Some(element), Some(&map));
 
+        // Changes to an element may affect whether its ancestors or previous
+        // siblings match :has() selectors, so restyle those anchors too.
+        let has_relative_selectors = self.stylist.has_relative_selectors();
+        document.set_has_relative_selectors(has_relative_selectors);
+        if has_relative_selectors {
+            for element in &elements_with_snapshot {
+                invalidate_relative_selector_anchors(*element);
+            }
+        }
+
         // Create a layout context for use throughout the following passes.
         let mut layout_context =
             self.build_layout_context(guards.clone(), true, &map);
@@ -1348,6 +1400,28 @@ impl LayoutThread {
         // GC the rule tree if some heuristics are met.
         unsafe { layout_context.style_context.stylist.rule_tree().maybe_gc(); }
 
+        // Apply the animations created from script on top of the styles we
+        // just computed.
+        let script_animations: FnvHashMap<_, _> = data.animations.iter().filter_map(|&(ref node, ref samples)| {
+            let node = unsafe { ServoLayoutNode::new(node) };
+            let element = node.as_element()?;
+            let base_style = element.borrow_data()?.styles.get_primary()?.clone();
+            Some((node.opaque(), ScriptAnimations { base_style, samples: samples.clone() }))
+        }).collect();
+        if let Some(mut root_flow) = self.root_flow.borrow().clone() {
+            if !script_animations.is_empty() || !self.script_animations.is_empty() {
+                animation::recalc_style_for_script_animations::<ServoLayoutElement>(
+                    &layout_context,
+                    FlowRef::deref_mut(&mut root_flow),
+                    &self.script_animations,
+                    &script_animations,
+                );
+            }
+        }
+        self.script_animations = script_animations.into_iter()
+            .filter(|&(_, ref animations)| !animations.samples.is_empty())
+            .collect();
+
         // Perform post-style recalculation layout passes.
         if let Some(mut root_flow) = self.root_flow.borrow().clone() {
             self.perform_post_style_recalc_layout_passes(&mut root_flow,
@@ -1408,6 +1482,14 @@ impl LayoutThread {
                         rw_data.indexable_text.text_index(opaque_node, point_in_node)
                     );
                 },
+                &QueryMsg::TextIndexAtPointQuery(node, point) => {
+                    let node = unsafe { ServoLayoutNode::new(&node) };
+                    let opaque_node = node.opaque();
+                    let point = Point2D::new(Au::from_f32_px(point.x), Au::from_f32_px(point.y));
+                    rw_data.text_index_response = TextIndexResponse(
+                        rw_data.indexable_text.text_index_at_point(opaque_node, point)
+                    );
+                },
                 &QueryMsg::NodeGeometryQuery(node) => {
                     let node = unsafe { ServoLayoutNode::new(&node) };
                     rw_data.client_rect_response = process_node_geometry_request(node, root_flow);
@@ -1464,6 +1546,14 @@ impl LayoutThread {
                     rw_data.element_inner_text_response =
                         process_element_inner_text_query(node, &rw_data.indexable_text);
                 },
+                &QueryMsg::ObservedBoxesQuery(ref nodes) => {
+                    let nodes = nodes.iter().map(|node| unsafe { ServoLayoutNode::new(node) }).collect();
+                    rw_data.observed_boxes_response = process_observed_boxes_request(nodes, root_flow);
+                },
+                &QueryMsg::RunningCSSAnimationsQuery => {
+                    rw_data.running_css_animations_response =
+                        process_running_css_animations_query(&self.running_animations.read(), self.timer.seconds());
+                },
             },
             ReflowGoal::Full | ReflowGoal::TickAnimations => {}
         }
@@ -1530,7 +1620,14 @@ impl LayoutThread {
                                 &layout_context,
                                 FlowRef::deref_mut(&mut root_flow),
                                 &animations,
-                            )
+                            );
+                            // Animations created from script composite on top of CSS animations.
+                            animation::recalc_style_for_script_animations::<ServoLayoutElement>(
+                                &layout_context,
+                                FlowRef::deref_mut(&mut root_flow),
+                                &FnvHashMap::default(),
+                                &self.script_animations,
+                            );
                         });
             }
             self.perform_post_style_recalc_layout_passes(&mut root_flow,
@@ -727,8 +727,11 @@ where
             Component::AttributeOther(ref attr_selector) => {
                 attr_selector.size_of(ops)
             }
-            Component::Negation(ref components) => {
-                components.size_of(ops)
+            Component::Negation(ref list) |
+            Component::Is(ref list) |
+            Component::Where(ref list) |
+            Component::Has(ref list) => {
+                list.size_of(ops)
             }
             Component::NonTSPseudoClass(ref pseudo) => {
                 (*pseudo).size_of(ops)
@@ -764,6 +767,7 @@ where
             Component::FirstOfType |
             Component::LastOfType |
             Component::OnlyOfType |
+            Component::RelativeSelectorAnchor |
             Component::Host(None) => 0,
         }
     }
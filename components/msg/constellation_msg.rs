@@ -195,6 +195,12 @@ impl PipelineNamespace {
         });
     }
 
+    /// Whether a namespace was installed for this thread. Worker threads only
+    /// get one once they need to create ids.
+    pub fn is_installed() -> bool {
+        PIPELINE_NAMESPACE.with(|tls| tls.get().is_some())
+    }
+
     fn next_index(&mut self) -> NonZeroU32 {
         self.index += 1;
         NonZeroU32::new(self.index).expect("pipeline id index wrapped!")
@@ -220,6 +226,20 @@ impl PipelineNamespace {
             index: HistoryStateIndex(self.next_index()),
         }
     }
+
+    fn next_message_port_id(&mut self) -> MessagePortId {
+        MessagePortId {
+            namespace_id: self.id,
+            index: MessagePortIndex(self.next_index()),
+        }
+    }
+
+    fn next_message_router_id(&mut self) -> MessageRouterId {
+        MessageRouterId {
+            namespace_id: self.id,
+            index: MessageRouterIndex(self.next_index()),
+        }
+    }
 }
 
 thread_local!(pub static PIPELINE_NAMESPACE: Cell<Option<PipelineNamespace>> = Cell::new(None));
@@ -387,6 +407,67 @@ impl fmt::Display for HistoryStateId {
     }
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct MessagePortIndex(pub NonZeroU32);
+malloc_size_of_is_0!(MessagePortIndex);
+
+/// Identifies a message port, wherever it was transferred to.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, MallocSizeOf, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct MessagePortId {
+    pub namespace_id: PipelineNamespaceId,
+    pub index: MessagePortIndex,
+}
+
+impl MessagePortId {
+    pub fn new() -> MessagePortId {
+        PIPELINE_NAMESPACE.with(|tls| {
+            let mut namespace = tls.get().expect("No namespace set for this thread!");
+            let next_message_port_id = namespace.next_message_port_id();
+            tls.set(Some(namespace));
+            next_message_port_id
+        })
+    }
+}
+
+impl fmt::Display for MessagePortId {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let PipelineNamespaceId(namespace_id) = self.namespace_id;
+        let MessagePortIndex(index) = self.index;
+        write!(fmt, "({},{})", namespace_id, index.get())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct MessageRouterIndex(pub NonZeroU32);
+malloc_size_of_is_0!(MessageRouterIndex);
+
+/// Identifies the router through which the constellation delivers messages
+/// to the message ports and broadcast channels of a global.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, MallocSizeOf, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct MessageRouterId {
+    pub namespace_id: PipelineNamespaceId,
+    pub index: MessageRouterIndex,
+}
+
+impl MessageRouterId {
+    pub fn new() -> MessageRouterId {
+        PIPELINE_NAMESPACE.with(|tls| {
+            let mut namespace = tls.get().expect("No namespace set for this thread!");
+            let next_message_router_id = namespace.next_message_router_id();
+            tls.set(Some(namespace));
+            next_message_router_id
+        })
+    }
+}
+
+impl fmt::Display for MessageRouterId {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let PipelineNamespaceId(namespace_id) = self.namespace_id;
+        let MessageRouterIndex(index) = self.index;
+        write!(fmt, "({},{})", namespace_id, index.get())
+    }
+}
+
 // We provide ids just for unit testing.
 pub const TEST_NAMESPACE: PipelineNamespaceId = PipelineNamespaceId(1234);
 #[allow(unsafe_code)]
@@ -0,0 +1,405 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! A thread storing the [caches](https://w3c.github.io/ServiceWorker/#cache-objects) of every origin.
+//!
+//! Responses are stored along with their whole body: script is responsible for reading the body
+//! of a response before putting it in a cache.
+
+use disk_cache::write_atomically;
+use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
+use net_traits::cache_storage_thread::{CacheBatchOperation, CacheId, CacheQueryOptions, CacheStorageError};
+use net_traits::cache_storage_thread::{CacheStorageResult, CacheStorageThreadMsg, CachedRequest, CachedResponse};
+use net_traits::cache_storage_thread::combined_header_value;
+use serde_json;
+use std::borrow::ToOwned;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::PathBuf;
+use std::thread;
+use url::Position;
+
+pub trait CacheStorageThreadFactory {
+    fn new(config_dir: Option<PathBuf>, quota: u64) -> Self;
+}
+
+impl CacheStorageThreadFactory for IpcSender<CacheStorageThreadMsg> {
+    /// Create a cache storage thread, allowing each origin to store `quota` bytes.
+    fn new(config_dir: Option<PathBuf>, quota: u64) -> IpcSender<CacheStorageThreadMsg> {
+        let (chan, port) = ipc::channel().unwrap();
+        thread::Builder::new().name("CacheStorageManager".to_owned()).spawn(move || {
+            CacheStorageManager::new(port, config_dir, quota).start();
+        }).expect("Thread spawning failed");
+        chan
+    }
+}
+
+type Entry = (CachedRequest, CachedResponse);
+
+/// <https://w3c.github.io/ServiceWorker/#request-matches-cached-item-algorithm>
+fn request_matches_cached_item(query: &CachedRequest,
+                               request: &CachedRequest,
+                               response: Option<&CachedResponse>,
+                               options: &CacheQueryOptions)
+                               -> bool {
+    // Step 1.
+    if !options.ignore_method && query.method != "GET" {
+        return false;
+    }
+
+    // Steps 2-6.
+    let end = if options.ignore_search { Position::AfterPath } else { Position::AfterQuery };
+    if query.url.as_url()[..end] != request.url.as_url()[..end] {
+        return false;
+    }
+
+    // Step 7.
+    let response = match response {
+        Some(response) if !options.ignore_vary => response,
+        _ => return true,
+    };
+    let vary = match combined_header_value(&response.headers, "vary") {
+        Some(vary) => vary,
+        None => return true,
+    };
+
+    // Steps 8-9.
+    for field in vary.split(|&byte| byte == b',') {
+        let field = String::from_utf8_lossy(field);
+        let field = field.trim();
+        if field.is_empty() {
+            continue;
+        }
+        if field == "*" {
+            return false;
+        }
+        if combined_header_value(&query.headers, field) != combined_header_value(&request.headers, field) {
+            return false;
+        }
+    }
+    true
+}
+
+/// <https://w3c.github.io/ServiceWorker/#query-cache>
+fn query_cache<'a>(entries: &'a [Entry], query: &CachedRequest, options: &CacheQueryOptions) -> Vec<&'a Entry> {
+    entries.iter()
+        .filter(|&&(ref request, ref response)| {
+            request_matches_cached_item(query, request, Some(response), options)
+        })
+        .collect()
+}
+
+fn entry_size(entry: &Entry) -> u64 {
+    (entry.0.size() + entry.1.size()) as u64
+}
+
+struct Cache {
+    origin: String,
+    /// The [request response list](https://w3c.github.io/ServiceWorker/#request-response-list) of the cache.
+    entries: Vec<Entry>,
+}
+
+impl Cache {
+    fn size(&self) -> u64 {
+        self.entries.iter().map(entry_size).sum()
+    }
+
+    fn matching(&self, request: Option<&CachedRequest>, options: &CacheQueryOptions) -> Vec<&Entry> {
+        match request {
+            Some(request) => query_cache(&self.entries, request, options),
+            None => self.entries.iter().collect(),
+        }
+    }
+
+    fn remove_matching(&mut self, request: &CachedRequest, options: &CacheQueryOptions) -> bool {
+        let count = self.entries.len();
+        self.entries.retain(|&(ref cached_request, ref cached_response)| {
+            !request_matches_cached_item(request, cached_request, Some(cached_response), options)
+        });
+        self.entries.len() != count
+    }
+
+    /// <https://w3c.github.io/ServiceWorker/#batch-cache-operations-algorithm>
+    ///
+    /// The caller is responsible for restoring the previous entries when an error is returned.
+    fn batch(&mut self, operations: Vec<CacheBatchOperation>) -> CacheStorageResult<bool> {
+        let mut added: Vec<Entry> = vec![];
+        let mut removed = false;
+
+        // Step 3.
+        for operation in operations {
+            match operation {
+                CacheBatchOperation::Delete(request, options) => {
+                    if !query_cache(&added, &request, &options).is_empty() {
+                        return Err(CacheStorageError::InvalidState);
+                    }
+                    removed |= self.remove_matching(&request, &options);
+                },
+                CacheBatchOperation::Put(request, response) => {
+                    let options = CacheQueryOptions::default();
+                    if !query_cache(&added, &request, &options).is_empty() {
+                        return Err(CacheStorageError::InvalidState);
+                    }
+                    self.remove_matching(&request, &options);
+                    self.entries.push((request.clone(), response.clone()));
+                    added.push((request, response));
+                },
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// The serialized form of a named cache.
+#[derive(Deserialize, Serialize)]
+struct StoredCache {
+    name: String,
+    entries: Vec<Entry>,
+}
+
+/// The serialized form of the caches of an origin.
+#[derive(Deserialize, Serialize)]
+struct StoredOrigin {
+    origin: String,
+    caches: Vec<StoredCache>,
+}
+
+struct CacheStorageManager {
+    port: IpcReceiver<CacheStorageThreadMsg>,
+    config_dir: Option<PathBuf>,
+    /// The number of bytes each origin may store.
+    quota: u64,
+    /// Every cache, including the ones which were deleted while script still used them.
+    /// Those are not persisted, and only live until the end of the session.
+    caches: HashMap<CacheId, Cache>,
+    /// The [name to cache map](https://w3c.github.io/ServiceWorker/#name-to-cache-map) of each origin.
+    origins: HashMap<String, Vec<(String, CacheId)>>,
+    next_id: CacheId,
+}
+
+impl CacheStorageManager {
+    fn new(port: IpcReceiver<CacheStorageThreadMsg>, config_dir: Option<PathBuf>, quota: u64)
+           -> CacheStorageManager {
+        let mut manager = CacheStorageManager {
+            port: port,
+            config_dir: config_dir,
+            quota: quota,
+            caches: HashMap::new(),
+            origins: HashMap::new(),
+            next_id: 1,
+        };
+        manager.load();
+        manager
+    }
+
+    fn start(&mut self) {
+        loop {
+            match self.port.recv().unwrap() {
+                CacheStorageThreadMsg::Open(sender, origin, name) => {
+                    let _ = sender.send(self.open(&origin.ascii_serialization(), name));
+                },
+                CacheStorageThreadMsg::Has(sender, origin, name) => {
+                    let _ = sender.send(self.cache_id(&origin.ascii_serialization(), &name).is_some());
+                },
+                CacheStorageThreadMsg::Delete(sender, origin, name) => {
+                    let _ = sender.send(self.delete(&origin.ascii_serialization(), &name));
+                },
+                CacheStorageThreadMsg::Keys(sender, origin) => {
+                    let names = self.origins.get(&origin.ascii_serialization()).map_or(vec![], |caches| {
+                        caches.iter().map(|&(ref name, _)| name.clone()).collect()
+                    });
+                    let _ = sender.send(names);
+                },
+                CacheStorageThreadMsg::MatchAny(sender, origin, name, request, options) => {
+                    let _ = sender.send(self.match_any(&origin.ascii_serialization(), name, &request, &options));
+                },
+                CacheStorageThreadMsg::MatchAll(sender, id, request, options) => {
+                    let result = self.caches.get(&id).ok_or(CacheStorageError::NotFound).map(|cache| {
+                        cache.matching(request.as_ref(), &options).into_iter()
+                            .map(|&(_, ref response)| response.clone())
+                            .collect()
+                    });
+                    let _ = sender.send(result);
+                },
+                CacheStorageThreadMsg::RequestKeys(sender, id, request, options) => {
+                    let result = self.caches.get(&id).ok_or(CacheStorageError::NotFound).map(|cache| {
+                        cache.matching(request.as_ref(), &options).into_iter()
+                            .map(|&(ref request, _)| request.clone())
+                            .collect()
+                    });
+                    let _ = sender.send(result);
+                },
+                CacheStorageThreadMsg::Batch(sender, id, operations) => {
+                    let _ = sender.send(self.batch(id, operations));
+                },
+                CacheStorageThreadMsg::Usage(sender, origin) => {
+                    let _ = sender.send(self.usage(&origin.ascii_serialization()));
+                },
+                CacheStorageThreadMsg::Exit(sender) => {
+                    // Nothing to do since changes are saved eagerly.
+                    let _ = sender.send(());
+                    break;
+                },
+            }
+        }
+    }
+
+    fn next_id(&mut self) -> CacheId {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn cache_id(&self, origin: &str, name: &str) -> Option<CacheId> {
+        self.origins.get(origin)
+            .and_then(|caches| caches.iter().find(|&&(ref cache_name, _)| cache_name == name))
+            .map(|&(_, id)| id)
+    }
+
+    /// The number of bytes used by the named caches of an origin.
+    fn usage(&self, origin: &str) -> u64 {
+        self.origins.get(origin).map_or(0, |caches| {
+            caches.iter().filter_map(|&(_, id)| self.caches.get(&id)).map(Cache::size).sum()
+        })
+    }
+
+    /// <https://w3c.github.io/ServiceWorker/#dom-cachestorage-open>
+    fn open(&mut self, origin: &str, name: String) -> CacheId {
+        if let Some(id) = self.cache_id(origin, &name) {
+            return id;
+        }
+        let id = self.next_id();
+        self.caches.insert(id, Cache {
+            origin: origin.to_owned(),
+            entries: vec![],
+        });
+        self.origins.entry(origin.to_owned()).or_insert(vec![]).push((name, id));
+        self.save(origin);
+        id
+    }
+
+    /// <https://w3c.github.io/ServiceWorker/#dom-cachestorage-delete>
+    fn delete(&mut self, origin: &str, name: &str) -> bool {
+        let removed = match self.origins.get_mut(origin) {
+            Some(caches) => {
+                let count = caches.len();
+                caches.retain(|&(ref cache_name, _)| cache_name != name);
+                caches.len() != count
+            },
+            None => false,
+        };
+        if removed {
+            self.save(origin);
+        }
+        removed
+    }
+
+    /// <https://w3c.github.io/ServiceWorker/#dom-cachestorage-match>
+    fn match_any(&self,
+                 origin: &str,
+                 name: Option<String>,
+                 request: &CachedRequest,
+                 options: &CacheQueryOptions)
+                 -> Option<CachedResponse> {
+        let ids: Vec<CacheId> = match name {
+            Some(name) => self.cache_id(origin, &name).into_iter().collect(),
+            None => self.origins.get(origin).map_or(vec![], |caches| caches.iter().map(|&(_, id)| id).collect()),
+        };
+        ids.iter()
+            .filter_map(|id| self.caches.get(id))
+            .filter_map(|cache| cache.matching(Some(request), options).into_iter().next())
+            .map(|&(_, ref response)| response)
+            .next()
+            .cloned()
+    }
+
+    fn batch(&mut self, id: CacheId, operations: Vec<CacheBatchOperation>) -> CacheStorageResult<bool> {
+        let (origin, backup, result) = match self.caches.get_mut(&id) {
+            Some(cache) => {
+                let backup = cache.entries.clone();
+                let result = cache.batch(operations);
+                (cache.origin.clone(), backup, result)
+            },
+            None => return Err(CacheStorageError::NotFound),
+        };
+        let result = result.and_then(|removed| {
+            if self.usage(&origin) > self.quota {
+                return Err(CacheStorageError::QuotaExceeded);
+            }
+            Ok(removed)
+        });
+        match result {
+            Ok(_) => self.save(&origin),
+            // Step 4.
+            Err(_) => self.caches.get_mut(&id).unwrap().entries = backup,
+        }
+        result
+    }
+
+    fn storage_dir(&self) -> Option<PathBuf> {
+        self.config_dir.as_ref().map(|config_dir| config_dir.join("caches"))
+    }
+
+    fn load(&mut self) {
+        let dir = match self.storage_dir().and_then(|dir| fs::read_dir(dir).ok()) {
+            Some(dir) => dir,
+            None => return,
+        };
+        for file in dir.filter_map(Result::ok) {
+            let mut json = String::new();
+            if File::open(file.path()).and_then(|mut file| file.read_to_string(&mut json)).is_err() {
+                continue;
+            }
+            match serde_json::from_str::<StoredOrigin>(&json) {
+                Ok(stored) => {
+                    let mut names = vec![];
+                    for cache in stored.caches {
+                        let id = self.next_id();
+                        self.caches.insert(id, Cache {
+                            origin: stored.origin.clone(),
+                            entries: cache.entries,
+                        });
+                        names.push((cache.name, id));
+                    }
+                    self.origins.insert(stored.origin, names);
+                },
+                Err(why) => warn!("Could not decode caches {}: {}", file.path().display(), why),
+            }
+        }
+    }
+
+    fn save(&self, origin: &str) {
+        let dir = match self.storage_dir() {
+            Some(dir) => dir,
+            None => return,
+        };
+        if origin == "null" {
+            // Only the caches of tuple origins outlive the session.
+            return;
+        }
+        let file_name = origin.bytes().map(|byte| format!("{:02x}", byte)).collect::<String>() + ".json";
+        let path = dir.join(file_name);
+        let stored = StoredOrigin {
+            origin: origin.to_owned(),
+            caches: self.origins.get(origin).map_or(vec![], |caches| {
+                caches.iter().filter_map(|&(ref name, id)| self.caches.get(&id).map(|cache| StoredCache {
+                    name: name.clone(),
+                    entries: cache.entries.clone(),
+                })).collect()
+            }),
+        };
+        if stored.caches.is_empty() {
+            let _ = fs::remove_file(&path);
+            return;
+        }
+        let result = fs::create_dir_all(&dir).and_then(|_| {
+            let json = serde_json::to_vec(&stored).expect("Caches are serializable");
+            write_atomically(&path, &json)
+        });
+        if let Err(why) = result {
+            warn!("Could not write caches {}: {}", path.display(), why);
+        }
+    }
+}
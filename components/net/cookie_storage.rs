@@ -7,12 +7,14 @@
 
 use cookie::Cookie;
 use cookie_rs;
-use net_traits::CookieSource;
+use hyper_serde::Serde;
+use net_traits::{CookieJarFormat, CookieSource};
 use net_traits::pub_domains::reg_suffix;
+use serde_json;
 use servo_url::ServoUrl;
 use std::cmp::Ordering;
 use std::collections::HashMap;
-use time::Tm;
+use time::{Timespec, Tm, at_utc, now};
 
 extern crate time;
 
@@ -184,6 +186,191 @@ impl CookieStorage {
             c.cookie.clone()
         })
     }
+
+    /// Remove every cookie whose expiry time has passed.
+    pub fn remove_expired_cookies(&mut self) {
+        for cookies in self.cookies_map.values_mut() {
+            cookies.retain(|c| !is_cookie_expired(c));
+        }
+        self.cookies_map.retain(|_, cookies| !cookies.is_empty());
+    }
+
+    /// Serialize every unexpired cookie in the given format.
+    pub fn export(&self, format: CookieJarFormat) -> String {
+        let mut records: Vec<CookieRecord> = self.cookies_map.values()
+            .flat_map(|cookies| cookies.iter())
+            .filter(|c| !is_cookie_expired(c))
+            .map(CookieRecord::from)
+            .collect();
+        records.sort_by(|a, b| (&a.domain, &a.path, &a.name).cmp(&(&b.domain, &b.path, &b.name)));
+
+        match format {
+            CookieJarFormat::Json => serde_json::to_string_pretty(&records).unwrap_or_default(),
+            CookieJarFormat::Netscape => {
+                let mut result = "# Netscape HTTP Cookie File\n".to_owned();
+                for record in &records {
+                    result.push_str(&record.to_netscape_line());
+                    result.push('\n');
+                }
+                result
+            },
+        }
+    }
+
+    /// Add the cookies serialized in the given format, skipping the ones that have
+    /// already expired. Returns the number of cookies that were added.
+    pub fn import(&mut self, format: CookieJarFormat, data: &str) -> Result<usize, String> {
+        let records = match format {
+            CookieJarFormat::Json => {
+                serde_json::from_str::<Vec<CookieRecord>>(data).map_err(|e| e.to_string())?
+            },
+            CookieJarFormat::Netscape => {
+                let mut records = vec![];
+                for (index, line) in data.lines().enumerate() {
+                    match CookieRecord::from_netscape_line(line) {
+                        Ok(Some(record)) => records.push(record),
+                        Ok(None) => {},
+                        Err(message) => return Err(format!("Line {}: {}", index + 1, message)),
+                    }
+                }
+                records
+            },
+        };
+
+        let mut count = 0;
+        for record in records {
+            let (cookie, url) = record.into_cookie()?;
+            if !is_cookie_expired(&cookie) {
+                self.push(cookie, &url, CookieSource::HTTP);
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+}
+
+/// A cookie as it is exported and imported, with the same field names as WebDriver
+/// cookies in JSON.
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CookieRecord {
+    name: String,
+    value: String,
+    domain: String,
+    #[serde(default = "default_path")]
+    path: String,
+    #[serde(default)]
+    secure: bool,
+    #[serde(default)]
+    http_only: bool,
+    /// Whether the cookie is only sent to its own domain and not to its subdomains.
+    #[serde(default)]
+    host_only: bool,
+    /// The expiry time in seconds since the epoch, or `None` for session cookies.
+    #[serde(default)]
+    expiry: Option<i64>,
+}
+
+fn default_path() -> String {
+    "/".to_owned()
+}
+
+impl<'a> From<&'a Cookie> for CookieRecord {
+    fn from(cookie: &'a Cookie) -> CookieRecord {
+        CookieRecord {
+            name: cookie.cookie.name().to_owned(),
+            value: cookie.cookie.value().to_owned(),
+            domain: cookie.cookie.domain().unwrap_or("").to_owned(),
+            path: cookie.cookie.path().unwrap_or("/").to_owned(),
+            secure: cookie.cookie.secure(),
+            http_only: cookie.cookie.http_only(),
+            host_only: cookie.host_only,
+            expiry: cookie.expiry_time.as_ref().map(|t| t.to_timespec().sec),
+        }
+    }
+}
+
+impl CookieRecord {
+    /// Returns the cookie and a URL it can be set from.
+    fn into_cookie(self) -> Result<(Cookie, ServoUrl), String> {
+        let domain = self.domain.trim_left_matches('.').to_owned();
+        let scheme = if self.secure { "https" } else { "http" };
+        let url = ServoUrl::parse(&format!("{}://{}{}", scheme, domain, self.path))
+            .map_err(|_| format!("Cookie {} has an invalid domain or path", self.name))?;
+
+        let expiry_time = self.expiry.map(|seconds| at_utc(Timespec::new(seconds, 0)));
+        let mut cookie = cookie_rs::Cookie::new(self.name, self.value);
+        cookie.set_domain(domain);
+        cookie.set_path(self.path);
+        cookie.set_secure(self.secure);
+        cookie.set_http_only(self.http_only);
+        if let Some(expiry_time) = expiry_time {
+            cookie.set_expires(expiry_time);
+        }
+
+        let cookie = Cookie {
+            cookie: cookie,
+            host_only: self.host_only,
+            persistent: expiry_time.is_some(),
+            creation_time: now(),
+            last_access: now(),
+            expiry_time: expiry_time.map(Serde),
+        };
+        Ok((cookie, url))
+    }
+
+    /// Each line has tab separated domain, include subdomains, path, secure, expiry,
+    /// name and value fields. HttpOnly cookies have their domain prefixed by
+    /// `#HttpOnly_`, as curl does.
+    fn to_netscape_line(&self) -> String {
+        let domain = if self.host_only { self.domain.clone() } else { format!(".{}", self.domain) };
+        format!("{}{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                if self.http_only { "#HttpOnly_" } else { "" },
+                domain,
+                if self.host_only { "FALSE" } else { "TRUE" },
+                self.path,
+                if self.secure { "TRUE" } else { "FALSE" },
+                self.expiry.unwrap_or(0),
+                self.name,
+                self.value)
+    }
+
+    /// Returns `None` for blank lines and comments.
+    fn from_netscape_line(line: &str) -> Result<Option<CookieRecord>, String> {
+        let (line, http_only) = if line.starts_with("#HttpOnly_") {
+            (&line["#HttpOnly_".len()..], true)
+        } else {
+            (line, false)
+        };
+        if line.trim().is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 7 {
+            return Err(format!("Expected 7 fields, found {}", fields.len()));
+        }
+
+        let parse_flag = |field: &str| match field {
+            "TRUE" => Ok(true),
+            "FALSE" => Ok(false),
+            _ => Err(format!("Invalid flag {}", field)),
+        };
+        let include_subdomains = parse_flag(fields[1])?;
+        let secure = parse_flag(fields[3])?;
+        let expiry = fields[4].parse::<i64>().map_err(|_| format!("Invalid expiry time {}", fields[4]))?;
+
+        Ok(Some(CookieRecord {
+            name: fields[5].to_owned(),
+            value: fields[6].to_owned(),
+            domain: fields[0].trim_left_matches('.').to_owned(),
+            path: fields[2].to_owned(),
+            secure: secure,
+            http_only: http_only,
+            host_only: !include_subdomains,
+            expiry: if expiry == 0 { None } else { Some(expiry) },
+        }))
+    }
 }
 
 fn reg_host<'a>(url: &'a str) -> String {
@@ -0,0 +1,459 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![deny(missing_docs)]
+
+//! A persistent store backing the HTTP cache.
+//!
+//! Each stored resource is kept as a body file inside the cache directory,
+//! while the metadata needed to reconstruct the response lives in a single
+//! `index.json` file. The store is bounded by a byte budget, and the least
+//! recently used resources are evicted once that budget is exceeded.
+//!
+//! Changes to the index are batched, and only written out once enough of
+//! them piled up, once enough time passed since the last write, or when the
+//! store is flushed or dropped.
+
+use hyper::header::Headers;
+use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
+use net_traits::response::HttpsState;
+use serde_json;
+use servo_url::ServoUrl;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use time;
+use uuid::Uuid;
+
+/// The name of the file holding the metadata of every stored resource.
+const INDEX_FILE_NAME: &'static str = "index.json";
+
+/// The extension of the files holding the bodies of stored resources.
+const BODY_FILE_EXTENSION: &'static str = "body";
+
+/// The number of changes to the index after which it is written out.
+const INDEX_FLUSH_BATCH_SIZE: u32 = 32;
+
+/// The time after which pending changes to the index are written out, in nanoseconds.
+const INDEX_FLUSH_INTERVAL: u64 = 5_000_000_000;
+
+/// The metadata of a resource stored on disk.
+#[derive(Clone, Deserialize, MallocSizeOf, Serialize)]
+pub struct DiskCacheEntry {
+    /// The unique identifier of this entry, also used to name its body file.
+    /// Assigned when the entry is inserted.
+    pub id: String,
+    /// The URL of the request this resource was stored for.
+    pub url: ServoUrl,
+    /// The headers of the request this resource was stored for,
+    /// needed to evaluate `Vary`.
+    #[serde(deserialize_with = "::hyper_serde::deserialize",
+            serialize_with = "::hyper_serde::serialize")]
+    #[ignore_malloc_size_of = "Defined in hyper"]
+    pub request_headers: Headers,
+    /// The headers of the stored response.
+    #[serde(deserialize_with = "::hyper_serde::deserialize",
+            serialize_with = "::hyper_serde::serialize")]
+    #[ignore_malloc_size_of = "Defined in hyper"]
+    pub response_headers: Headers,
+    /// Final URL after redirects.
+    pub final_url: ServoUrl,
+    /// Character set.
+    pub charset: Option<String>,
+    /// The location URL of the stored response.
+    pub location_url: Option<Result<ServoUrl, String>>,
+    /// The HTTPS state of the stored response.
+    pub https_state: HttpsState,
+    /// The HTTP status of the stored response.
+    pub raw_status: Option<(u16, Vec<u8>)>,
+    /// The URL list of the stored response.
+    pub url_list: Vec<ServoUrl>,
+    /// The freshness lifetime of the stored response, in seconds.
+    pub expires: i64,
+    /// When the stored response was last validated, in seconds since the epoch.
+    pub last_validated: i64,
+    /// The size of the body file, in bytes. Assigned when the entry is inserted.
+    pub size: u64,
+    /// A logical clock value recording when this entry was last used.
+    /// Assigned when the entry is inserted.
+    pub last_access: u64,
+}
+
+/// The serialized form of the index file.
+#[derive(Deserialize, Serialize)]
+struct DiskCacheIndex {
+    version: u32,
+    clock: u64,
+    entries: Vec<DiskCacheEntry>,
+}
+
+/// A byte-bounded, least-recently-used store of HTTP responses on disk.
+pub struct DiskCache {
+    /// The directory holding the index and body files.
+    root: PathBuf,
+    /// The maximum number of bytes the body files may use.
+    max_size: u64,
+    /// The number of bytes currently used by body files.
+    size: u64,
+    /// A logical clock, advanced every time an entry is used.
+    clock: u64,
+    /// Whether the in-memory index has changed since it was last written.
+    dirty: bool,
+    /// The number of changes to the index since it was last written.
+    pending_changes: u32,
+    /// When the index was last written, in nanoseconds.
+    last_flush: u64,
+    /// The stored entries, keyed by the URL they were requested with.
+    entries: HashMap<ServoUrl, Vec<DiskCacheEntry>>,
+}
+
+impl MallocSizeOf for DiskCache {
+    fn size_of(&self, ops: &mut MallocSizeOfOps) -> usize {
+        self.entries.size_of(ops)
+    }
+}
+
+impl DiskCache {
+    /// Open the store located in `root`, creating it if needed.
+    ///
+    /// Entries whose body file is missing or has been truncated are dropped,
+    /// as are body files which are not referenced by the index.
+    pub fn new(root: PathBuf, max_size: u64) -> DiskCache {
+        if let Err(why) = fs::create_dir_all(&root) {
+            warn!("couldn't create http cache directory {}: {}", root.display(), why);
+        }
+        let mut cache = DiskCache {
+            root: root,
+            max_size: max_size,
+            size: 0,
+            clock: 0,
+            dirty: false,
+            pending_changes: 0,
+            last_flush: time::precise_time_ns(),
+            entries: HashMap::new(),
+        };
+        let index = match cache.read_index() {
+            Some(index) => index,
+            None => DiskCacheIndex { version: 1, clock: 0, entries: vec![] },
+        };
+        cache.clock = index.clock;
+        for entry in index.entries {
+            let valid = fs::metadata(cache.body_path(&entry.id))
+                .map(|metadata| metadata.len() == entry.size)
+                .unwrap_or(false);
+            if !valid {
+                let _ = fs::remove_file(cache.body_path(&entry.id));
+                cache.dirty = true;
+                continue;
+            }
+            cache.size += entry.size;
+            cache.entries.entry(entry.url.clone()).or_insert(vec![]).push(entry);
+        }
+        cache.remove_unreferenced_files();
+        cache.evict(0);
+        cache.flush();
+        cache
+    }
+
+    /// The number of bytes used on disk by the stored bodies.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Whether the entry with the given identifier is stored for `url`.
+    pub fn contains(&self, url: &ServoUrl, id: &str) -> bool {
+        self.entries.get(url).map_or(false, |entries| entries.iter().any(|entry| entry.id == id))
+    }
+
+    /// Prepare reading the bodies of the entries stored for the given URL, except the ones
+    /// for which `skip` returns true. Returns `None` if no entry is stored for `url`.
+    pub fn prepare_read<F>(&self, url: &ServoUrl, skip: F) -> Option<DiskCacheRead>
+        where F: Fn(&DiskCacheEntry) -> bool
+    {
+        let entries = self.entries.get(url)?;
+        Some(DiskCacheRead {
+            entries: entries.iter()
+                .filter(|entry| !skip(entry))
+                .map(|entry| (entry.clone(), self.body_path(&entry.id)))
+                .collect(),
+        })
+    }
+
+    /// Mark every entry stored for the given URL as the most recently used.
+    pub fn touch(&mut self, url: &ServoUrl) {
+        let touched = match self.entries.get_mut(url) {
+            Some(entries) => {
+                self.clock += 1;
+                for entry in entries.iter_mut() {
+                    entry.last_access = self.clock;
+                }
+                true
+            },
+            None => false,
+        };
+        if touched {
+            self.note_change();
+        }
+    }
+
+    /// Prepare storing a new entry along with its body. Writing the body doesn't need access
+    /// to the store, so that it can be performed without holding any lock on it, and the
+    /// written entry is then passed to `insert`.
+    ///
+    /// Returns `None` if the body doesn't fit within the byte budget.
+    pub fn prepare_write(&self, mut entry: DiskCacheEntry, body: Vec<u8>) -> Option<DiskCacheWrite> {
+        let size = body.len() as u64;
+        if size > self.max_size {
+            return None;
+        }
+        entry.id = Uuid::new_v4().simple().to_string();
+        entry.size = size;
+        Some(DiskCacheWrite {
+            path: self.body_path(&entry.id),
+            entry: entry,
+            body: body,
+        })
+    }
+
+    /// Add an entry whose body was written by a `DiskCacheWrite`.
+    ///
+    /// Returns the entries that had to be evicted to stay within the byte budget.
+    pub fn insert(&mut self, mut entry: DiskCacheEntry) -> Vec<DiskCacheEntry> {
+        let evicted = self.evict(entry.size);
+        self.clock += 1;
+        entry.last_access = self.clock;
+        self.size += entry.size;
+        self.entries.entry(entry.url.clone()).or_insert(vec![]).push(entry);
+        self.note_change();
+        evicted
+    }
+
+    /// Remove the body written for an entry that ended up not being inserted.
+    pub fn discard(&self, entry: &DiskCacheEntry) {
+        let _ = fs::remove_file(self.body_path(&entry.id));
+    }
+
+    /// Apply `f` to the entry with the given identifier, stored for `url`.
+    pub fn update<F>(&mut self, url: &ServoUrl, id: &str, f: F)
+        where F: FnOnce(&mut DiskCacheEntry)
+    {
+        let updated = match self.entries.get_mut(url) {
+            Some(entries) => match entries.iter_mut().find(|entry| entry.id == id) {
+                Some(entry) => {
+                    f(entry);
+                    true
+                },
+                None => false,
+            },
+            None => false,
+        };
+        if updated {
+            self.note_change();
+        }
+    }
+
+    /// Require every entry stored for the given URL to be validated on next use.
+    pub fn invalidate(&mut self, url: &ServoUrl) {
+        let invalidated = match self.entries.get_mut(url) {
+            Some(entries) => {
+                for entry in entries.iter_mut() {
+                    entry.expires = 0;
+                }
+                true
+            },
+            None => false,
+        };
+        if invalidated {
+            self.note_change();
+        }
+    }
+
+    /// Remove the entry with the given identifier, stored for `url`.
+    pub fn remove(&mut self, url: &ServoUrl, id: &str) {
+        let removed = match self.entries.get_mut(url) {
+            Some(entries) => match entries.iter().position(|entry| entry.id == id) {
+                Some(position) => Some(entries.remove(position)),
+                None => None,
+            },
+            None => None,
+        };
+        if let Some(entry) = removed {
+            self.remove_body(&entry);
+            if self.entries.get(url).map_or(false, |entries| entries.is_empty()) {
+                self.entries.remove(url);
+            }
+            self.note_change();
+        }
+    }
+
+    /// Write the index to disk, if it changed since it was last written.
+    pub fn flush(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        self.pending_changes = 0;
+        self.last_flush = time::precise_time_ns();
+        let index = DiskCacheIndex {
+            version: 1,
+            clock: self.clock,
+            entries: self.entries.values().flat_map(|entries| entries.iter().cloned()).collect(),
+        };
+        let json = match serde_json::to_vec(&index) {
+            Ok(json) => json,
+            Err(why) => {
+                warn!("couldn't serialize http cache index: {}", why);
+                return;
+            },
+        };
+        match write_atomically(&self.root.join(INDEX_FILE_NAME), &json) {
+            Ok(()) => self.dirty = false,
+            Err(why) => warn!("couldn't write http cache index: {}", why),
+        }
+    }
+
+    /// Record a change to the index, and write it out if enough changes piled up
+    /// or enough time passed since it was last written.
+    fn note_change(&mut self) {
+        self.dirty = true;
+        self.pending_changes += 1;
+        if self.pending_changes >= INDEX_FLUSH_BATCH_SIZE ||
+           time::precise_time_ns() - self.last_flush >= INDEX_FLUSH_INTERVAL {
+            self.flush();
+        }
+    }
+
+    /// Evict the least recently used entries until `incoming` more bytes fit in the budget.
+    fn evict(&mut self, incoming: u64) -> Vec<DiskCacheEntry> {
+        let mut evicted = vec![];
+        while self.size + incoming > self.max_size {
+            let oldest = self.entries.values()
+                .flat_map(|entries| entries.iter())
+                .min_by_key(|entry| entry.last_access)
+                .map(|entry| (entry.url.clone(), entry.id.clone()));
+            let (url, id) = match oldest {
+                Some(oldest) => oldest,
+                None => break,
+            };
+            let entry = {
+                let entries = self.entries.get_mut(&url).unwrap();
+                let position = entries.iter().position(|entry| entry.id == id).unwrap();
+                entries.remove(position)
+            };
+            if self.entries.get(&url).map_or(false, |entries| entries.is_empty()) {
+                self.entries.remove(&url);
+            }
+            self.remove_body(&entry);
+            self.dirty = true;
+            evicted.push(entry);
+        }
+        evicted
+    }
+
+    fn remove_body(&mut self, entry: &DiskCacheEntry) {
+        self.size -= entry.size;
+        if let Err(why) = fs::remove_file(self.body_path(&entry.id)) {
+            warn!("couldn't remove http cache entry for {}: {}", entry.url, why);
+        }
+    }
+
+    fn body_path(&self, id: &str) -> PathBuf {
+        self.root.join(id).with_extension(BODY_FILE_EXTENSION)
+    }
+
+    fn read_index(&self) -> Option<DiskCacheIndex> {
+        let mut json = String::new();
+        let path = self.root.join(INDEX_FILE_NAME);
+        if File::open(&path).and_then(|mut file| file.read_to_string(&mut json)).is_err() {
+            return None;
+        }
+        match serde_json::from_str(&json) {
+            Ok(index) => Some(index),
+            Err(why) => {
+                warn!("couldn't decode http cache index {}: {}", path.display(), why);
+                None
+            },
+        }
+    }
+
+    fn remove_unreferenced_files(&self) {
+        let dir = match fs::read_dir(&self.root) {
+            Ok(dir) => dir,
+            Err(_) => return,
+        };
+        for file in dir.filter_map(Result::ok) {
+            let path = file.path();
+            if path.extension().map_or(false, |extension| extension == "tmp") {
+                // Left behind by an interrupted write.
+                let _ = fs::remove_file(&path);
+                continue;
+            }
+            if path.extension().map_or(true, |extension| extension != BODY_FILE_EXTENSION) {
+                continue;
+            }
+            let referenced = path.file_stem().and_then(|stem| stem.to_str()).map_or(false, |id| {
+                self.entries.values().any(|entries| entries.iter().any(|entry| entry.id == id))
+            });
+            if !referenced {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+}
+
+impl Drop for DiskCache {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// A read of the bodies of stored entries, which doesn't need access to the store,
+/// so that it can be performed without holding any lock on it.
+pub struct DiskCacheRead {
+    entries: Vec<(DiskCacheEntry, PathBuf)>,
+}
+
+impl DiskCacheRead {
+    /// Read the bodies from disk, returning each entry along with its body.
+    pub fn perform(self) -> Vec<(DiskCacheEntry, io::Result<Vec<u8>>)> {
+        self.entries.into_iter().map(|(entry, path)| {
+            let mut body = Vec::with_capacity(entry.size as usize);
+            let result = File::open(&path).and_then(|mut file| file.read_to_end(&mut body)).map(|_| body);
+            (entry, result)
+        }).collect()
+    }
+}
+
+/// A write of the body of a new entry, which doesn't need access to the store,
+/// so that it can be performed without holding any lock on it.
+pub struct DiskCacheWrite {
+    entry: DiskCacheEntry,
+    path: PathBuf,
+    body: Vec<u8>,
+}
+
+impl DiskCacheWrite {
+    /// The identifier assigned to the entry being written.
+    pub fn id(&self) -> &str {
+        &self.entry.id
+    }
+
+    /// Write the body to disk, returning the entry along with the result of the write.
+    pub fn perform(self) -> (DiskCacheEntry, io::Result<()>) {
+        let result = write_atomically(&self.path, &self.body);
+        (self.entry, result)
+    }
+}
+
+/// Write `data` to `path` without ever leaving a partially written file behind,
+/// by writing to a temporary file first and renaming it into place.
+pub fn write_atomically(path: &Path, data: &[u8]) -> io::Result<()> {
+    let temporary_path = path.with_extension("tmp");
+    {
+        let mut file = File::create(&temporary_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+    }
+    fs::rename(&temporary_path, path)
+}
@@ -7,6 +7,7 @@ use data_loader::decode;
 use devtools_traits::DevtoolsControlMsg;
 use fetch::cors_cache::CorsCache;
 use filemanager_thread::FileManager;
+use http_cache::write_to_disk_cache;
 use http_loader::{HttpState, determine_request_referrer, http_fetch};
 use http_loader::{set_default_accept, set_default_accept_language};
 use hyper::{Error, Result as HyperResult};
@@ -18,9 +19,11 @@ use hyper::status::StatusCode;
 use ipc_channel::ipc::IpcReceiver;
 use mime_guess::guess_mime_type;
 use net_traits::{FetchTaskTarget, NetworkError, ReferrerPolicy};
+use net_traits::csp::CheckResult;
 use net_traits::request::{CredentialsMode, Destination, Referrer, Request, RequestMode};
 use net_traits::request::{ResponseTainting, Origin, Window};
 use net_traits::response::{Response, ResponseBody, ResponseType};
+use request_interceptor::RequestInterceptor;
 use servo_url::ServoUrl;
 use std::borrow::Cow;
 use std::fmt;
@@ -46,6 +49,7 @@ pub struct FetchContext {
     pub user_agent: Cow<'static, str>,
     pub devtools_chan: Option<Sender<DevtoolsControlMsg>>,
     pub filemanager: FileManager,
+    pub request_interceptor: Arc<RequestInterceptor>,
     pub cancellation_listener: Arc<Mutex<CancellationListener>>,
 }
 
@@ -144,7 +148,12 @@ pub fn main_fetch(request: &mut Request,
     }
 
     // Step 3.
-    // TODO: handle content security policy violations.
+    let csp_check = request.csp_list.as_ref().map(|csp_list| csp_list.should_request_be_blocked(request));
+    if let Some((_, violations)) = csp_check.as_ref() {
+        if !violations.is_empty() {
+            target.process_csp_violations(request, violations.clone());
+        }
+    }
 
     // Step 4.
     // TODO: handle upgrade to a potentially secure URL.
@@ -154,7 +163,9 @@ pub fn main_fetch(request: &mut Request,
         response = Some(Response::network_error(NetworkError::Internal("Request attempted on bad port".into())));
     }
     // TODO: handle blocking as mixed content.
-    // TODO: handle blocking by content security policy.
+    if let Some((CheckResult::Blocked, _)) = csp_check {
+        response = Some(Response::network_error(NetworkError::Internal("Blocked by Content Security Policy".into())));
+    }
 
     // Step 6
     // TODO: handle request's client's referrer policy.
@@ -196,6 +207,11 @@ pub fn main_fetch(request: &mut Request,
     // Step 11.
     // Not applicable: see fetch_async.
 
+    // Not part of the spec: let the embedder intercept the request before it goes out.
+    if response.is_none() {
+        response = context.request_interceptor.intercept(request);
+    }
+
     // Step 12.
     let mut response = response.unwrap_or_else(|| {
         let current_url = request.current_url();
@@ -412,6 +428,8 @@ pub fn main_fetch(request: &mut Request,
         if let Ok(mut http_cache) = context.state.http_cache.write() {
             http_cache.update_awaiting_consumers(&request, &response);
         }
+        // The resources stored while their body was still being received can now be persisted.
+        write_to_disk_cache(&context.state.http_cache, &request);
     }
 
     // Steps 25-27.
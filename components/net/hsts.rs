@@ -36,7 +36,7 @@ impl HstsEntry {
     pub fn is_expired(&self) -> bool {
         match (self.max_age, self.timestamp) {
             (Some(max_age), Some(timestamp)) => {
-                (time::get_time().sec as u64) - timestamp >= max_age
+                (time::get_time().sec as u64).saturating_sub(timestamp) >= max_age
             }
 
             _ => false
@@ -112,6 +112,14 @@ impl HstsList {
        })
     }
 
+    /// Remove every entry whose max-age has elapsed.
+    pub fn remove_expired_entries(&mut self) {
+        for entries in self.entries_map.values_mut() {
+            entries.retain(|e| !e.is_expired());
+        }
+        self.entries_map.retain(|_, entries| !entries.is_empty());
+    }
+
     pub fn push(&mut self, entry: HstsEntry) {
         let host = entry.host.clone();
         let base_domain = reg_suffix(&host);
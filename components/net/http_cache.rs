@@ -5,8 +5,9 @@
 #![deny(missing_docs)]
 
 //! A memory cache implementing the logic specified in <http://tools.ietf.org/html/rfc7234>
-//! and <http://tools.ietf.org/html/rfc7232>.
+//! and <http://tools.ietf.org/html/rfc7232>, optionally backed by a persistent store on disk.
 
+use disk_cache::{DiskCache, DiskCacheEntry, DiskCacheRead, DiskCacheWrite};
 use fetch::methods::{Data, DoneChannel};
 use hyper::header;
 use hyper::header::ContentType;
@@ -17,18 +18,21 @@ use hyper_serde::Serde;
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps, MallocUnconditionalSizeOf, MallocUnconditionalShallowSizeOf};
 use malloc_size_of::Measurable;
 use net_traits::{Metadata, FetchMetadata};
+use net_traits::csp::CspList;
 use net_traits::request::Request;
 use net_traits::response::{HttpsState, Response, ResponseBody};
 use servo_arc::Arc;
 use servo_config::prefs::PREFS;
 use servo_url::ServoUrl;
 use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
 use std::str;
-use std::sync::Mutex;
+use std::sync::{Mutex, RwLock};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{channel, Sender};
 use time;
-use time::{Duration, Tm};
+use time::{Duration, Timespec, Tm};
 
 
 /// The key used to differentiate requests in the cache.
@@ -76,6 +80,8 @@ struct MeasurableCachedResource {
     url_list: Vec<ServoUrl>,
     expires: Duration,
     last_validated: Tm,
+    /// The identifier of the copy of this resource in the disk cache, if any.
+    disk_id: Option<String>,
 }
 
 impl MallocSizeOf for CachedResource {
@@ -130,6 +136,8 @@ pub struct CachedResponse {
 pub struct HttpCache {
     /// cached responses.
     entries: HashMap<CacheKey, Vec<CachedResource>>,
+    /// The persistent store backing the cached responses, if any.
+    disk_cache: Option<DiskCache>,
 }
 
 
@@ -311,8 +319,10 @@ fn create_cached_response(request: &Request,
     cached_headers: &Headers,
     done_chan: &mut DoneChannel)
     -> CachedResponse {
-    let mut response = Response::new(cached_resource.data.metadata.data.final_url.clone());
+    let final_url = &cached_resource.data.metadata.data.final_url;
+    let mut response = Response::new(final_url.clone());
     response.headers = cached_headers.clone();
+    response.csp_list = CspList::from_headers(cached_headers, final_url);
     response.body = cached_resource.body.clone();
     if let ResponseBody::Receiving(_) = *cached_resource.body.lock().unwrap() {
         let (done_sender, done_receiver) = channel();
@@ -358,10 +368,61 @@ fn create_resource_with_bytes_from_resource(bytes: &[u8], resource: &CachedResou
             url_list: resource.data.url_list.clone(),
             expires: resource.data.expires.clone(),
             last_validated: resource.data.last_validated.clone(),
+            disk_id: None,
         })
     }
 }
 
+/// Create a resource from an entry of the disk cache, and the body stored along with it.
+fn create_resource_from_disk_entry(entry: &DiskCacheEntry, body: Vec<u8>) -> CachedResource {
+    let cacheable_metadata = CachedMetadata {
+        headers: Arc::new(Mutex::new(entry.response_headers.clone())),
+        data: Measurable(MeasurableCachedMetadata {
+            final_url: entry.final_url.clone(),
+            content_type: entry.response_headers.get::<ContentType>().cloned().map(Serde),
+            charset: entry.charset.clone(),
+            status: entry.raw_status.clone()
+        })
+    };
+    CachedResource {
+        request_headers: Arc::new(Mutex::new(entry.request_headers.clone())),
+        body: Arc::new(Mutex::new(ResponseBody::Done(body))),
+        aborted: Arc::new(AtomicBool::new(false)),
+        awaiting_body: Arc::new(Mutex::new(vec![])),
+        data: Measurable(MeasurableCachedResource {
+            metadata: cacheable_metadata,
+            location_url: entry.location_url.clone(),
+            https_state: entry.https_state,
+            status: entry.raw_status.as_ref().map(|&(code, _)| StatusCode::from_u16(code)),
+            raw_status: entry.raw_status.clone(),
+            url_list: entry.url_list.clone(),
+            expires: Duration::seconds(entry.expires),
+            last_validated: time::at(Timespec::new(entry.last_validated, 0)),
+            disk_id: Some(entry.id.clone()),
+        })
+    }
+}
+
+/// Create an entry of the disk cache describing a resource stored for the given key.
+fn create_disk_entry_from_resource(key: &CacheKey, resource: &CachedResource) -> DiskCacheEntry {
+    DiskCacheEntry {
+        id: String::new(),
+        url: key.url(),
+        request_headers: resource.request_headers.lock().unwrap().clone(),
+        response_headers: resource.data.metadata.headers.lock().unwrap().clone(),
+        final_url: resource.data.metadata.data.final_url.clone(),
+        charset: resource.data.metadata.data.charset.clone(),
+        location_url: resource.data.location_url.clone(),
+        https_state: resource.data.https_state,
+        raw_status: resource.data.raw_status.clone(),
+        url_list: resource.data.url_list.clone(),
+        expires: resource.data.expires.num_seconds(),
+        last_validated: resource.data.last_validated.to_timespec().sec,
+        size: 0,
+        last_access: 0,
+    }
+}
+
 /// Support for range requests <https://tools.ietf.org/html/rfc7233>.
 fn handle_range_request(request: &Request,
     candidates: Vec<&CachedResource>,
@@ -519,7 +580,161 @@ impl HttpCache {
     /// Create a new memory cache instance.
     pub fn new() -> HttpCache {
         HttpCache {
-            entries: HashMap::new()
+            entries: HashMap::new(),
+            disk_cache: None,
+        }
+    }
+
+    /// Create a new memory cache instance, backed by a persistent store located in `root`,
+    /// whose stored bodies use at most `max_size` bytes on disk.
+    pub fn new_with_disk_cache(root: PathBuf, max_size: u64) -> HttpCache {
+        HttpCache {
+            entries: HashMap::new(),
+            disk_cache: Some(DiskCache::new(root, max_size)),
+        }
+    }
+
+    /// The number of bytes used on disk by the persistent store.
+    pub fn disk_usage(&self) -> u64 {
+        self.disk_cache.as_ref().map_or(0, |disk_cache| disk_cache.size())
+    }
+
+    /// Write any pending changes to the persistent store.
+    pub fn flush(&mut self) {
+        if let Some(ref mut disk_cache) = self.disk_cache {
+            disk_cache.flush();
+        }
+    }
+
+    /// Prepare reading the resources stored on disk for the request, which are not in memory yet.
+    /// The read itself doesn't need access to the cache, so that it can be performed without
+    /// holding any lock on it, and its result is then passed to `finish_disk_read`.
+    ///
+    /// Returns `None` if nothing is stored on disk for the request.
+    pub fn prepare_disk_read(&self, request: &Request) -> Option<DiskCacheRead> {
+        if request.method != Method::Get {
+            return None;
+        }
+        let disk_cache = self.disk_cache.as_ref()?;
+        let entry_key = CacheKey::new(request.clone());
+        let cached_resources = self.entries.get(&entry_key);
+        disk_cache.prepare_read(&entry_key.url(), |disk_entry| {
+            cached_resources.map_or(false, |resources| {
+                resources.iter().any(|resource| resource.data.disk_id.as_ref() == Some(&disk_entry.id))
+            })
+        })
+    }
+
+    /// Add the resources read from disk for the request to the ones in memory,
+    /// and mark the resources stored for it as the most recently used.
+    pub fn finish_disk_read(&mut self, request: &Request, read: Vec<(DiskCacheEntry, io::Result<Vec<u8>>)>) {
+        let disk_cache = match self.disk_cache {
+            Some(ref mut disk_cache) => disk_cache,
+            None => return,
+        };
+        let entry_key = CacheKey::new(request.clone());
+        let url = entry_key.url();
+        disk_cache.touch(&url);
+        let mut unreadable = vec![];
+        {
+            let cached_resources = self.entries.entry(entry_key.clone()).or_insert(vec![]);
+            for (disk_entry, body) in read {
+                // The entry may have been evicted, or loaded by another fetch, while it was read.
+                let is_loaded = cached_resources.iter().any(|resource| {
+                    resource.data.disk_id.as_ref() == Some(&disk_entry.id)
+                });
+                if is_loaded || !disk_cache.contains(&url, &disk_entry.id) {
+                    continue;
+                }
+                match body {
+                    Ok(body) => cached_resources.push(create_resource_from_disk_entry(&disk_entry, body)),
+                    Err(why) => {
+                        warn!("couldn't read http cache entry for {}: {}", url, why);
+                        unreadable.push(disk_entry.id);
+                    },
+                }
+            }
+        }
+        for id in unreadable {
+            disk_cache.remove(&url, &id);
+        }
+        if self.entries.get(&entry_key).map_or(false, |resources| resources.is_empty()) {
+            self.entries.remove(&entry_key);
+        }
+    }
+
+    /// Prepare writing the completed resources stored for the request, which are not on disk yet,
+    /// to the persistent store. The writes themselves don't need access to the cache, so that
+    /// they can be performed without holding any lock on it, and their results are then passed
+    /// to `finish_disk_writes`.
+    pub fn prepare_disk_writes(&mut self, request: &Request) -> Vec<DiskCacheWrite> {
+        let disk_cache = match self.disk_cache {
+            Some(ref disk_cache) => disk_cache,
+            None => return vec![],
+        };
+        let entry_key = CacheKey::new(request.clone());
+        let cached_resources = match self.entries.get_mut(&entry_key) {
+            Some(cached_resources) => cached_resources,
+            None => return vec![],
+        };
+        let mut writes = vec![];
+        for cached_resource in cached_resources.iter_mut() {
+            if cached_resource.data.disk_id.is_some() || cached_resource.aborted.load(Ordering::Relaxed) {
+                continue;
+            }
+            let disk_entry = create_disk_entry_from_resource(&entry_key, cached_resource);
+            let write = match *cached_resource.body.lock().unwrap() {
+                ResponseBody::Done(ref body) => disk_cache.prepare_write(disk_entry, body.clone()),
+                ResponseBody::Empty | ResponseBody::Receiving(..) => continue,
+            };
+            if let Some(write) = write {
+                // Claim the resource, so that concurrent fetches don't write it as well.
+                cached_resource.data.disk_id = Some(write.id().to_owned());
+                writes.push(write);
+            }
+        }
+        writes
+    }
+
+    /// Add the resources written to disk for the request to the persistent store.
+    pub fn finish_disk_writes(&mut self, request: &Request, written: Vec<(DiskCacheEntry, io::Result<()>)>) {
+        let mut evicted = vec![];
+        {
+            let disk_cache = match self.disk_cache {
+                Some(ref mut disk_cache) => disk_cache,
+                None => return,
+            };
+            let entry_key = CacheKey::new(request.clone());
+            for (disk_entry, result) in written {
+                // The resource may have been evicted or invalidated while it was written.
+                let cached_resource = self.entries.get_mut(&entry_key).and_then(|cached_resources| {
+                    cached_resources.iter_mut().find(|resource| resource.data.disk_id.as_ref() == Some(&disk_entry.id))
+                });
+                match (cached_resource, result) {
+                    (Some(_), Ok(())) => evicted.append(&mut disk_cache.insert(disk_entry)),
+                    (Some(cached_resource), Err(why)) => {
+                        warn!("couldn't write http cache entry for {}: {}", disk_entry.url, why);
+                        cached_resource.data.disk_id = None;
+                    },
+                    (None, Ok(())) => disk_cache.discard(&disk_entry),
+                    (None, Err(_)) => {},
+                }
+            }
+        }
+        // Resources evicted from disk are dropped from memory as well,
+        // so that the copies of the disk cache kept in memory stay within its budget.
+        for disk_entry in evicted {
+            let evicted_key = CacheKey::from_servo_url(&disk_entry.url);
+            let is_empty = match self.entries.get_mut(&evicted_key) {
+                Some(cached_resources) => {
+                    cached_resources.retain(|resource| resource.data.disk_id.as_ref() != Some(&disk_entry.id));
+                    cached_resources.is_empty()
+                },
+                None => false,
+            };
+            if is_empty {
+                self.entries.remove(&evicted_key);
+            }
         }
     }
 
@@ -656,6 +871,16 @@ impl HttpCache {
                 let mut stored_headers = cached_resource.data.metadata.headers.lock().unwrap();
                 stored_headers.extend(response.headers.iter());
                 constructed_response.headers = stored_headers.clone();
+                constructed_response.csp_list = CspList::from_headers(&stored_headers,
+                                                                      &cached_resource.data.metadata.data.final_url);
+                if let (Some(disk_cache), Some(disk_id)) =
+                    (self.disk_cache.as_mut(), cached_resource.data.disk_id.as_ref()) {
+                    let expires = cached_resource.data.expires.num_seconds();
+                    disk_cache.update(&entry_key.url, disk_id, |disk_entry| {
+                        disk_entry.response_headers = stored_headers.clone();
+                        disk_entry.expires = expires;
+                    });
+                }
                 return Some(constructed_response);
             }
         }
@@ -669,6 +894,9 @@ impl HttpCache {
                 cached_resource.data.expires = Duration::seconds(0i64);
             }
         }
+        if let Some(ref mut disk_cache) = self.disk_cache {
+            disk_cache.invalidate(url);
+        }
     }
 
     /// Invalidation.
@@ -734,11 +962,27 @@ impl HttpCache {
                 raw_status: response.raw_status.clone(),
                 url_list: response.url_list.clone(),
                 expires: expiry,
-                last_validated: time::now()
+                last_validated: time::now(),
+                disk_id: None,
             })
         };
-        let entry = self.entries.entry(entry_key).or_insert(vec![]);
-        entry.push(entry_resource);
+        self.entries.entry(entry_key).or_insert(vec![]).push(entry_resource);
     }
 
 }
+
+/// Write the completed resources cached for the request to the persistent store,
+/// without holding the lock on the cache while the disk is written to.
+pub fn write_to_disk_cache(http_cache: &RwLock<HttpCache>, request: &Request) {
+    let writes = match http_cache.write() {
+        Ok(mut http_cache) => http_cache.prepare_disk_writes(request),
+        Err(_) => return,
+    };
+    if writes.is_empty() {
+        return;
+    }
+    let written = writes.into_iter().map(DiskCacheWrite::perform).collect();
+    if let Ok(mut http_cache) = http_cache.write() {
+        http_cache.finish_disk_writes(request, written);
+    }
+}
@@ -13,7 +13,7 @@ use fetch::methods::{Data, DoneChannel, FetchContext, Target};
 use fetch::methods::{is_cors_safelisted_request_header, is_cors_safelisted_method, main_fetch};
 use flate2::read::{DeflateDecoder, GzDecoder};
 use hsts::HstsList;
-use http_cache::HttpCache;
+use http_cache::{HttpCache, write_to_disk_cache};
 use hyper::Error as HttpError;
 use hyper::LanguageTag;
 use hyper::client::{Pool, Request as HyperRequest, Response as HyperResponse};
@@ -34,6 +34,7 @@ use hyper_serde::Serde;
 use log;
 use msg::constellation_msg::{HistoryStateId, PipelineId};
 use net_traits::{CookieSource, FetchMetadata, NetworkError, ReferrerPolicy};
+use net_traits::csp::CspList;
 use net_traits::request::{CacheMode, CredentialsMode, Destination, Origin};
 use net_traits::request::{RedirectMode, Referrer, Request, RequestMode};
 use net_traits::request::{ResponseTainting, ServiceWorkersMode};
@@ -639,6 +640,11 @@ pub fn http_redirect_fetch(request: &mut Request,
         Some(Ok(url)) => url,
     };
 
+    // A fetch that was aborted does not follow its redirects.
+    if context.cancellation_listener.lock().unwrap().cancelled() {
+        return Response::network_error(NetworkError::Internal("Fetch aborted".into()))
+    }
+
     // Step 5
     if request.redirect_count >= 20 {
         return Response::network_error(NetworkError::Internal("Too many redirects".into()));
@@ -873,6 +879,16 @@ fn http_network_or_cache_fetch(request: &mut Request,
     let mut revalidating_flag = false;
 
     // Step 21
+    // The resources of the cache that are only stored on disk are read without holding
+    // its lock, so that other fetches don't wait on that I/O.
+    let disk_read = context.state.http_cache.read().ok()
+        .and_then(|http_cache| http_cache.prepare_disk_read(&http_request));
+    if let Some(disk_read) = disk_read {
+        let read = disk_read.perform();
+        if let Ok(mut http_cache) = context.state.http_cache.write() {
+            http_cache.finish_disk_read(&http_request, read);
+        }
+    }
     if let Ok(http_cache) = context.state.http_cache.read() {
         if let Some(response_from_cache) = http_cache.construct_response(&http_request, done_chan) {
             let response_headers = response_from_cache.response.headers.clone();
@@ -966,6 +982,7 @@ fn http_network_or_cache_fetch(request: &mut Request,
                 if let Ok(mut http_cache) = context.state.http_cache.write() {
                     http_cache.store(&http_request, &forward_response);
                 }
+                write_to_disk_cache(&context.state.http_cache, &http_request);
             }
             // Subsubstep 1
             response = Some(forward_response);
@@ -1054,6 +1071,10 @@ fn http_network_fetch(request: &Request,
     // Step 5
     let url = request.current_url();
 
+    if context.cancellation_listener.lock().unwrap().cancelled() {
+        return Response::network_error(NetworkError::Internal("Fetch aborted".into()))
+    }
+
     let request_id = context.devtools_chan.as_ref().map(|_| {
         uuid::Uuid::new_v4().simple().to_string()
     });
@@ -1192,13 +1213,14 @@ fn http_network_fetch(request: &Request,
     };
 
     // Step 13
-    // TODO this step isn't possible yet (CSP)
+    response.csp_list = CspList::from_headers(&response.headers, &url);
 
     // Step 14
     if !response.is_network_error() && request.cache_mode != CacheMode::NoStore {
         if let Ok(mut http_cache) = context.state.http_cache.write() {
             http_cache.store(&request, &response);
         }
+        write_to_disk_cache(&context.state.http_cache, &request);
     }
 
     // TODO this step isn't possible yet
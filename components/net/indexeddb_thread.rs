@@ -0,0 +1,1180 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! A thread storing the [IndexedDB](https://w3c.github.io/IndexedDB/) databases of every origin,
+//! and scheduling the transactions placed against them.
+//!
+//! Values are stored as opaque structured clone data: script is responsible for extracting
+//! the keys of a value, for its object store and each of its indexes, before storing it.
+
+use disk_cache::write_atomically;
+use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
+use net_traits::indexeddb_thread::{IndexedDBConnectionEvent, IndexedDBConnectionId, IndexedDBCursorDirection};
+use net_traits::indexeddb_thread::{IndexedDBCursorRecord, IndexedDBDatabaseMetadata, IndexedDBDeleteResponse};
+use net_traits::indexeddb_thread::{IndexedDBError, IndexedDBIndexKeys, IndexedDBIndexMetadata, IndexedDBKey};
+use net_traits::indexeddb_thread::{IndexedDBKeyPath, IndexedDBKeyRange, IndexedDBObjectStoreMetadata};
+use net_traits::indexeddb_thread::{IndexedDBOpenResponse, IndexedDBOperation, IndexedDBOperationResult};
+use net_traits::indexeddb_thread::{IndexedDBRecord, IndexedDBResult, IndexedDBThreadMsg};
+use net_traits::indexeddb_thread::{IndexedDBTransactionId, IndexedDBTransactionMode};
+use serde_json;
+use servo_url::ImmutableOrigin;
+use std::borrow::ToOwned;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::collections::Bound::{Excluded, Unbounded};
+use std::fs::{self, File};
+use std::io::Read;
+use std::mem;
+use std::path::PathBuf;
+use std::thread;
+
+/// The largest number a key generator can produce, 2^53.
+const MAX_GENERATED_KEY: u64 = 9007199254740992;
+
+pub trait IndexedDBThreadFactory {
+    fn new(config_dir: Option<PathBuf>, quota: u64) -> Self;
+}
+
+impl IndexedDBThreadFactory for IpcSender<IndexedDBThreadMsg> {
+    /// Create an IndexedDB thread, allowing each origin to store `quota` bytes.
+    fn new(config_dir: Option<PathBuf>, quota: u64) -> IpcSender<IndexedDBThreadMsg> {
+        let (chan, port) = ipc::channel().unwrap();
+        thread::Builder::new().name("IndexedDBManager".to_owned()).spawn(move || {
+            IndexedDBManager::new(port, config_dir, quota).start();
+        }).expect("Thread spawning failed");
+        chan
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#index-construct>
+#[derive(Clone)]
+struct Index {
+    metadata: IndexedDBIndexMetadata,
+    /// The (index key, primary key) pairs of the index, in index order.
+    entries: BTreeSet<(IndexedDBKey, IndexedDBKey)>,
+}
+
+impl Index {
+    fn remove_primary_key(&mut self, primary_key: &IndexedDBKey) -> u64 {
+        let removed: Vec<_> = self.entries.iter()
+            .filter(|&&(_, ref key)| key == primary_key)
+            .cloned()
+            .collect();
+        let mut size = 0;
+        for entry in removed {
+            size += entry_size(&entry);
+            self.entries.remove(&entry);
+        }
+        size
+    }
+
+    /// Whether adding `key` for `primary_key` would violate the uniqueness of this index.
+    fn violates_uniqueness(&self, key: &IndexedDBKey, primary_key: &IndexedDBKey) -> bool {
+        self.metadata.unique &&
+            self.entries.iter().any(|&(ref existing, ref existing_primary_key)| {
+                existing == key && existing_primary_key != primary_key
+            })
+    }
+}
+
+fn entry_size(entry: &(IndexedDBKey, IndexedDBKey)) -> u64 {
+    (entry.0.size() + entry.1.size()) as u64
+}
+
+/// A file name for an arbitrary origin or database name.
+fn hex_encode(name: &str) -> String {
+    name.bytes().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// <https://w3c.github.io/IndexedDB/#object-store-construct>
+#[derive(Clone)]
+struct ObjectStore {
+    name: String,
+    key_path: Option<IndexedDBKeyPath>,
+    auto_increment: bool,
+    /// <https://w3c.github.io/IndexedDB/#key-generator-current-number>
+    key_generator: u64,
+    records: BTreeMap<IndexedDBKey, Vec<u8>>,
+    indexes: BTreeMap<String, Index>,
+    /// The number of bytes used by the records and index entries of this store.
+    size: u64,
+}
+
+impl ObjectStore {
+    fn new(metadata: IndexedDBObjectStoreMetadata) -> ObjectStore {
+        ObjectStore {
+            name: metadata.name,
+            key_path: metadata.key_path,
+            auto_increment: metadata.auto_increment,
+            key_generator: 1,
+            records: BTreeMap::new(),
+            indexes: BTreeMap::new(),
+            size: 0,
+        }
+    }
+
+    fn metadata(&self) -> IndexedDBObjectStoreMetadata {
+        IndexedDBObjectStoreMetadata {
+            name: self.name.clone(),
+            key_path: self.key_path.clone(),
+            auto_increment: self.auto_increment,
+            indexes: self.indexes.values().map(|index| index.metadata.clone()).collect(),
+        }
+    }
+
+    fn index(&self, name: &Option<String>) -> IndexedDBResult<Option<&Index>> {
+        match *name {
+            Some(ref name) => self.indexes.get(name).map(Some).ok_or(IndexedDBError::NotFound),
+            None => Ok(None),
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#generate-a-key>
+    fn generate_key(&mut self) -> IndexedDBResult<IndexedDBKey> {
+        if self.key_generator > MAX_GENERATED_KEY {
+            return Err(IndexedDBError::Constraint);
+        }
+        let key = IndexedDBKey::Number(self.key_generator as f64);
+        self.key_generator += 1;
+        Ok(key)
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#possibly-update-the-key-generator>
+    fn possibly_update_key_generator(&mut self, key: &IndexedDBKey) {
+        if let IndexedDBKey::Number(number) = *key {
+            let value = number.min(MAX_GENERATED_KEY as f64).floor();
+            if value >= self.key_generator as f64 {
+                self.key_generator = value as u64 + 1;
+            }
+        }
+    }
+
+    fn remove_record(&mut self, key: &IndexedDBKey) {
+        if let Some(value) = self.records.remove(key) {
+            self.size -= (key.size() + value.len()) as u64;
+            for index in self.indexes.values_mut() {
+                self.size -= index.remove_primary_key(key);
+            }
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#store-a-record-into-an-object-store>
+    fn put(&mut self,
+           key: Option<IndexedDBKey>,
+           value: Vec<u8>,
+           index_keys: Vec<IndexedDBIndexKeys>,
+           overwrite: bool,
+           available: u64)
+           -> IndexedDBResult<IndexedDBKey> {
+        // Step 1.
+        let key = match key {
+            Some(key) => {
+                if self.auto_increment {
+                    self.possibly_update_key_generator(&key);
+                }
+                key
+            },
+            None if self.auto_increment => self.generate_key()?,
+            None => return Err(IndexedDBError::Data),
+        };
+
+        // Step 2.
+        if !overwrite && self.records.contains_key(&key) {
+            return Err(IndexedDBError::Constraint);
+        }
+
+        // Step 4.
+        for index_keys in &index_keys {
+            let index = self.indexes.get(&index_keys.index).ok_or(IndexedDBError::NotFound)?;
+            if index_keys.keys.iter().any(|index_key| index.violates_uniqueness(index_key, &key)) {
+                return Err(IndexedDBError::Constraint);
+            }
+        }
+
+        let previous_size = self.records.get(&key).map_or(0, |previous| {
+            let index_size: u64 = self.indexes.values().flat_map(|index| index.entries.iter())
+                .filter(|&&(_, ref primary_key)| *primary_key == key)
+                .map(entry_size)
+                .sum();
+            (key.size() + previous.len()) as u64 + index_size
+        });
+        let new_size = (key.size() + value.len()) as u64 + index_keys.iter()
+            .flat_map(|index_keys| index_keys.keys.iter())
+            .map(|index_key| (index_key.size() + key.size()) as u64)
+            .sum::<u64>();
+        if new_size > previous_size + available {
+            return Err(IndexedDBError::QuotaExceeded);
+        }
+
+        // Step 3.
+        self.remove_record(&key);
+        self.records.insert(key.clone(), value);
+        for index_keys in index_keys {
+            let index = self.indexes.get_mut(&index_keys.index).unwrap();
+            for index_key in index_keys.keys {
+                index.entries.insert((index_key, key.clone()));
+            }
+        }
+        self.size += new_size;
+        Ok(key)
+    }
+
+    /// The (key, primary key) pairs of the records in range, in ascending order.
+    fn entries_in_range<'a>(&'a self, index: Option<&'a Index>, range: &'a IndexedDBKeyRange)
+                            -> Box<Iterator<Item=(&'a IndexedDBKey, &'a IndexedDBKey)> + 'a> {
+        match index {
+            Some(index) => Box::new(index.entries.iter()
+                .map(|&(ref key, ref primary_key)| (key, primary_key))
+                .filter(move |&(key, _)| range.contains(key))),
+            None => Box::new(self.records.keys()
+                .map(|key| (key, key))
+                .filter(move |&(key, _)| range.contains(key))),
+        }
+    }
+
+    fn record(&self, primary_key: &IndexedDBKey) -> IndexedDBRecord {
+        IndexedDBRecord {
+            key: primary_key.clone(),
+            value: self.records[primary_key].clone(),
+        }
+    }
+
+    fn execute(&mut self, operation: IndexedDBOperation, available: u64)
+               -> IndexedDBResult<IndexedDBOperationResult> {
+        let limit = |count: Option<u32>| match count {
+            Some(count) if count > 0 => count as usize,
+            _ => usize::max_value(),
+        };
+        match operation {
+            IndexedDBOperation::Put { key, value, index_keys, overwrite } => {
+                self.put(key, value, index_keys, overwrite, available)
+                    .map(|key| IndexedDBOperationResult::Key(Some(key)))
+            },
+            IndexedDBOperation::Delete(range) => {
+                let keys: Vec<_> = self.records.keys().filter(|key| range.contains(key)).cloned().collect();
+                for key in keys {
+                    self.remove_record(&key);
+                }
+                Ok(IndexedDBOperationResult::Done)
+            },
+            IndexedDBOperation::Clear => {
+                self.records.clear();
+                for index in self.indexes.values_mut() {
+                    index.entries.clear();
+                }
+                self.size = 0;
+                Ok(IndexedDBOperationResult::Done)
+            },
+            IndexedDBOperation::Get { index, range } => {
+                let index = self.index(&index)?;
+                let record = self.entries_in_range(index, &range).next()
+                    .map(|(_, primary_key)| self.record(primary_key));
+                Ok(IndexedDBOperationResult::Record(record))
+            },
+            IndexedDBOperation::GetKey { index, range } => {
+                let index = self.index(&index)?;
+                let key = self.entries_in_range(index, &range).next()
+                    .map(|(_, primary_key)| primary_key.clone());
+                Ok(IndexedDBOperationResult::Key(key))
+            },
+            IndexedDBOperation::GetAll { index, range, count } => {
+                let index = self.index(&index)?;
+                let records = self.entries_in_range(index, &range)
+                    .take(limit(count))
+                    .map(|(_, primary_key)| self.record(primary_key))
+                    .collect();
+                Ok(IndexedDBOperationResult::Records(records))
+            },
+            IndexedDBOperation::GetAllKeys { index, range, count } => {
+                let index = self.index(&index)?;
+                let keys = self.entries_in_range(index, &range)
+                    .take(limit(count))
+                    .map(|(_, primary_key)| primary_key.clone())
+                    .collect();
+                Ok(IndexedDBOperationResult::Keys(keys))
+            },
+            IndexedDBOperation::Count { index, range } => {
+                let index = self.index(&index)?;
+                Ok(IndexedDBOperationResult::Count(self.entries_in_range(index, &range).count() as u64))
+            },
+            IndexedDBOperation::Iterate { index, range, direction, position, target, skip, key_only } => {
+                let index = self.index(&index)?;
+                let found = self.iterate(index, &range, direction, position, target, skip);
+                Ok(IndexedDBOperationResult::Cursor(found.map(|(key, primary_key)| {
+                    let value = if key_only {
+                        None
+                    } else {
+                        Some(self.records[&primary_key].clone())
+                    };
+                    IndexedDBCursorRecord { key, primary_key, value }
+                })))
+            },
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#iterate-a-cursor>
+    fn iterate<'a>(&'a self,
+                   index: Option<&'a Index>,
+                   range: &IndexedDBKeyRange,
+                   direction: IndexedDBCursorDirection,
+                   position: Option<(IndexedDBKey, IndexedDBKey)>,
+                   target: Option<IndexedDBKey>,
+                   mut skip: u32)
+                   -> Option<(IndexedDBKey, IndexedDBKey)> {
+        let forward = match direction {
+            IndexedDBCursorDirection::Next | IndexedDBCursorDirection::NextUnique => true,
+            IndexedDBCursorDirection::Prev | IndexedDBCursorDirection::PrevUnique => false,
+        };
+        let unique = match direction {
+            IndexedDBCursorDirection::NextUnique | IndexedDBCursorDirection::PrevUnique => true,
+            IndexedDBCursorDirection::Next | IndexedDBCursorDirection::Prev => false,
+        };
+
+        // Start right after the current position of the cursor.
+        let entries: Box<Iterator<Item=(&'a IndexedDBKey, &'a IndexedDBKey)> + 'a> = match (index, position.clone()) {
+            (Some(index), position) => {
+                let bound = position.map_or(Unbounded, Excluded);
+                let entries = if forward {
+                    index.entries.range((bound, Unbounded))
+                } else {
+                    index.entries.range((Unbounded, bound))
+                };
+                let entries = entries.map(|&(ref key, ref primary_key)| (key, primary_key));
+                if forward { Box::new(entries) } else { Box::new(entries.rev()) }
+            },
+            (None, position) => {
+                let bound = position.map_or(Unbounded, |(key, _)| Excluded(key));
+                let entries = if forward {
+                    self.records.range((bound, Unbounded))
+                } else {
+                    self.records.range((Unbounded, bound))
+                };
+                let entries = entries.map(|(key, _)| (key, key));
+                if forward { Box::new(entries) } else { Box::new(entries.rev()) }
+            },
+        };
+
+        let mut last_key = position.map(|(key, _)| key);
+        let mut entries = entries.peekable();
+        while let Some((key, primary_key)) = entries.next() {
+            let beyond_range = if forward {
+                range.upper.as_ref().map_or(false, |upper| key > upper || (range.upper_open && key == upper))
+            } else {
+                range.lower.as_ref().map_or(false, |lower| key < lower || (range.lower_open && key == lower))
+            };
+            if beyond_range {
+                break;
+            }
+            if !range.contains(key) {
+                continue;
+            }
+            if let Some(ref target) = target {
+                if (forward && key < target) || (!forward && key > target) {
+                    continue;
+                }
+            }
+            if unique && last_key.as_ref() == Some(key) {
+                continue;
+            }
+            last_key = Some(key.clone());
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            let mut primary_key = primary_key;
+            if unique && !forward {
+                // Iterating backwards, the first record with a given key is the last one seen.
+                while entries.peek().map_or(false, |&(next_key, _)| next_key == key) {
+                    primary_key = entries.next().unwrap().1;
+                }
+            }
+            return Some((key.clone(), primary_key.clone()));
+        }
+        None
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#database-construct>
+#[derive(Clone)]
+struct Database {
+    name: String,
+    version: u64,
+    stores: BTreeMap<String, ObjectStore>,
+}
+
+impl Database {
+    fn metadata(&self) -> IndexedDBDatabaseMetadata {
+        IndexedDBDatabaseMetadata {
+            name: self.name.clone(),
+            version: self.version,
+            object_stores: self.stores.values().map(ObjectStore::metadata).collect(),
+        }
+    }
+
+    fn size(&self) -> u64 {
+        self.stores.values().map(|store| store.size).sum()
+    }
+}
+
+/// The serialized form of an index.
+#[derive(Deserialize, Serialize)]
+struct StoredIndex {
+    metadata: IndexedDBIndexMetadata,
+    entries: Vec<(IndexedDBKey, IndexedDBKey)>,
+}
+
+/// The serialized form of an object store.
+#[derive(Deserialize, Serialize)]
+struct StoredObjectStore {
+    name: String,
+    key_path: Option<IndexedDBKeyPath>,
+    auto_increment: bool,
+    key_generator: u64,
+    records: Vec<IndexedDBRecord>,
+    indexes: Vec<StoredIndex>,
+}
+
+/// The serialized form of a database, stored in a file of its own so that committing a
+/// transaction only rewrites the database it was placed against.
+#[derive(Deserialize, Serialize)]
+struct StoredDatabase {
+    origin: String,
+    name: String,
+    version: u64,
+    object_stores: Vec<StoredObjectStore>,
+}
+
+impl StoredDatabase {
+    fn new(origin: &str, database: &Database) -> StoredDatabase {
+        StoredDatabase {
+            origin: origin.to_owned(),
+            name: database.name.clone(),
+            version: database.version,
+            object_stores: database.stores.values().map(|store| StoredObjectStore {
+                name: store.name.clone(),
+                key_path: store.key_path.clone(),
+                auto_increment: store.auto_increment,
+                key_generator: store.key_generator,
+                records: store.records.iter().map(|(key, value)| IndexedDBRecord {
+                    key: key.clone(),
+                    value: value.clone(),
+                }).collect(),
+                indexes: store.indexes.values().map(|index| StoredIndex {
+                    metadata: index.metadata.clone(),
+                    entries: index.entries.iter().cloned().collect(),
+                }).collect(),
+            }).collect(),
+        }
+    }
+}
+
+impl From<StoredDatabase> for Database {
+    fn from(database: StoredDatabase) -> Database {
+        let stores = database.object_stores.into_iter().map(|stored| {
+            let mut store = ObjectStore {
+                name: stored.name,
+                key_path: stored.key_path,
+                auto_increment: stored.auto_increment,
+                key_generator: stored.key_generator,
+                records: BTreeMap::new(),
+                indexes: BTreeMap::new(),
+                size: 0,
+            };
+            for record in stored.records {
+                store.size += (record.key.size() + record.value.len()) as u64;
+                store.records.insert(record.key, record.value);
+            }
+            for index in stored.indexes {
+                store.size += index.entries.iter().map(entry_size).sum::<u64>();
+                store.indexes.insert(index.metadata.name.clone(), Index {
+                    metadata: index.metadata,
+                    entries: index.entries.into_iter().collect(),
+                });
+            }
+            (store.name.clone(), store)
+        }).collect();
+        Database {
+            name: database.name,
+            version: database.version,
+            stores: stores,
+        }
+    }
+}
+
+/// An open connection to a database.
+struct Connection {
+    origin: String,
+    name: String,
+    events: IpcSender<IndexedDBConnectionEvent>,
+}
+
+/// A request placed against a transaction, processed once the transaction has started.
+enum TransactionRequest {
+    Operation(IpcSender<IndexedDBResult<IndexedDBOperationResult>>, String, IndexedDBOperation),
+    Commit(IpcSender<IndexedDBResult<()>>),
+}
+
+/// <https://w3c.github.io/IndexedDB/#transaction-construct>
+struct Transaction {
+    connection: IndexedDBConnectionId,
+    origin: String,
+    name: String,
+    scope: Vec<String>,
+    mode: IndexedDBTransactionMode,
+    started: bool,
+    pending: VecDeque<TransactionRequest>,
+    /// The object stores modified by a read/write transaction, as they were before the transaction.
+    backup: HashMap<String, ObjectStore>,
+    /// The database as it was before a version change transaction, if it existed.
+    snapshot: Option<Database>,
+    /// The open request which started a version change transaction.
+    open_request: Option<IpcSender<IndexedDBOpenResponse>>,
+}
+
+impl Transaction {
+    /// Whether this transaction has to wait for `other` to finish, if `other` was created before it.
+    fn conflicts_with(&self, other: &Transaction) -> bool {
+        if self.origin != other.origin || self.name != other.name {
+            return false;
+        }
+        if self.mode == IndexedDBTransactionMode::ReadOnly && other.mode == IndexedDBTransactionMode::ReadOnly {
+            return false;
+        }
+        if self.mode == IndexedDBTransactionMode::VersionChange ||
+            other.mode == IndexedDBTransactionMode::VersionChange {
+            return true;
+        }
+        self.scope.iter().any(|store| other.scope.contains(store))
+    }
+}
+
+/// A request to open or delete a database, waiting for the previous ones to be processed.
+enum ConnectionRequest {
+    Open {
+        sender: IpcSender<IndexedDBOpenResponse>,
+        events: IpcSender<IndexedDBConnectionEvent>,
+        version: Option<u64>,
+        notified: bool,
+    },
+    Delete {
+        sender: IpcSender<IndexedDBDeleteResponse>,
+        notified: bool,
+    },
+}
+
+struct IndexedDBManager {
+    port: IpcReceiver<IndexedDBThreadMsg>,
+    config_dir: Option<PathBuf>,
+    /// The number of bytes each origin may store.
+    quota: u64,
+    /// The databases of each origin, keyed by name.
+    origins: HashMap<String, HashMap<String, Database>>,
+    connections: HashMap<IndexedDBConnectionId, Connection>,
+    transactions: BTreeMap<IndexedDBTransactionId, Transaction>,
+    /// The open and delete requests of each database, processed in order.
+    connection_requests: HashMap<(String, String), VecDeque<ConnectionRequest>>,
+    next_id: u64,
+}
+
+impl IndexedDBManager {
+    fn new(port: IpcReceiver<IndexedDBThreadMsg>, config_dir: Option<PathBuf>, quota: u64) -> IndexedDBManager {
+        let mut manager = IndexedDBManager {
+            port: port,
+            config_dir: config_dir,
+            quota: quota,
+            origins: HashMap::new(),
+            connections: HashMap::new(),
+            transactions: BTreeMap::new(),
+            connection_requests: HashMap::new(),
+            next_id: 1,
+        };
+        manager.load();
+        manager
+    }
+
+    fn start(&mut self) {
+        loop {
+            match self.port.recv().unwrap() {
+                IndexedDBThreadMsg::Open { sender, events, origin, name, version } => {
+                    let request = ConnectionRequest::Open { sender, events, version, notified: false };
+                    self.queue_connection_request(origin, name, request);
+                },
+                IndexedDBThreadMsg::Close(connection) => self.close(connection),
+                IndexedDBThreadMsg::DeleteDatabase(sender, origin, name) => {
+                    let request = ConnectionRequest::Delete { sender, notified: false };
+                    self.queue_connection_request(origin, name, request);
+                },
+                IndexedDBThreadMsg::Databases(sender, origin) => {
+                    let databases = self.origins.get(&origin.ascii_serialization()).map_or(vec![], |databases| {
+                        databases.values().map(|database| (database.name.clone(), database.version)).collect()
+                    });
+                    let _ = sender.send(databases);
+                },
+                IndexedDBThreadMsg::CreateTransaction(sender, connection, scope, mode) => {
+                    let _ = sender.send(self.create_transaction(connection, scope, mode));
+                },
+                IndexedDBThreadMsg::Operation(sender, transaction, store, operation) => {
+                    let request = TransactionRequest::Operation(sender, store, operation);
+                    self.queue_transaction_request(transaction, request);
+                },
+                IndexedDBThreadMsg::Commit(sender, transaction) => {
+                    self.queue_transaction_request(transaction, TransactionRequest::Commit(sender));
+                },
+                IndexedDBThreadMsg::Abort(sender, transaction) => {
+                    self.abort(transaction);
+                    let _ = sender.send(());
+                },
+                IndexedDBThreadMsg::CreateObjectStore(sender, transaction, metadata) => {
+                    let _ = sender.send(self.create_object_store(transaction, metadata));
+                },
+                IndexedDBThreadMsg::DeleteObjectStore(sender, transaction, name) => {
+                    let _ = sender.send(self.delete_object_store(transaction, name));
+                },
+                IndexedDBThreadMsg::CreateIndex(sender, transaction, store, metadata) => {
+                    let _ = sender.send(self.create_index(transaction, store, metadata));
+                },
+                IndexedDBThreadMsg::SetIndexKeys(sender, transaction, store, index, keys) => {
+                    let _ = sender.send(self.set_index_keys(transaction, store, index, keys));
+                },
+                IndexedDBThreadMsg::DeleteIndex(sender, transaction, store, index) => {
+                    let _ = sender.send(self.delete_index(transaction, store, index));
+                },
+                IndexedDBThreadMsg::Usage(sender, origin) => {
+                    let _ = sender.send(self.usage(&origin.ascii_serialization()));
+                },
+                IndexedDBThreadMsg::Exit(sender) => {
+                    // Nothing to do since committed transactions are saved eagerly.
+                    let _ = sender.send(());
+                    break;
+                },
+            }
+        }
+    }
+
+    fn next_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// The number of bytes used by the databases of an origin.
+    fn usage(&self, origin: &str) -> u64 {
+        self.origins.get(origin).map_or(0, |databases| databases.values().map(Database::size).sum())
+    }
+
+    fn database_mut(&mut self, origin: &str, name: &str) -> Option<&mut Database> {
+        self.origins.get_mut(origin).and_then(|databases| databases.get_mut(name))
+    }
+
+    fn storage_dir(&self) -> Option<PathBuf> {
+        self.config_dir.as_ref().map(|config_dir| config_dir.join("indexeddb"))
+    }
+
+    /// The file storing a database, under a directory holding the databases of its origin.
+    fn database_path(&self, origin: &str, name: &str) -> Option<PathBuf> {
+        self.storage_dir().map(|dir| dir.join(hex_encode(origin)).join(hex_encode(name) + ".json"))
+    }
+
+    fn load(&mut self) {
+        let dir = match self.storage_dir().and_then(|dir| fs::read_dir(dir).ok()) {
+            Some(dir) => dir,
+            None => return,
+        };
+        let files = dir.filter_map(Result::ok)
+            .filter_map(|origin_dir| fs::read_dir(origin_dir.path()).ok())
+            .flat_map(|origin_dir| origin_dir.filter_map(Result::ok));
+        for file in files {
+            let mut json = String::new();
+            if File::open(file.path()).and_then(|mut file| file.read_to_string(&mut json)).is_err() {
+                continue;
+            }
+            match serde_json::from_str::<StoredDatabase>(&json) {
+                Ok(stored) => {
+                    let databases = self.origins.entry(stored.origin.clone()).or_insert(HashMap::new());
+                    databases.insert(stored.name.clone(), Database::from(stored));
+                },
+                Err(why) => warn!("Could not decode IndexedDB database {}: {}", file.path().display(), why),
+            }
+        }
+    }
+
+    /// Write a database out, or remove its file if it was deleted.
+    fn save(&self, origin: &str, name: &str) {
+        if origin == "null" {
+            // Only the databases of tuple origins outlive the session.
+            return;
+        }
+        let path = match self.database_path(origin, name) {
+            Some(path) => path,
+            None => return,
+        };
+        let database = match self.origins.get(origin).and_then(|databases| databases.get(name)) {
+            Some(database) => database,
+            None => {
+                let _ = fs::remove_file(&path);
+                if let Some(origin_dir) = path.parent() {
+                    // This only succeeds once the origin has no databases left.
+                    let _ = fs::remove_dir(origin_dir);
+                }
+                return;
+            },
+        };
+        let result = fs::create_dir_all(path.parent().unwrap()).and_then(|_| {
+            let stored = StoredDatabase::new(origin, database);
+            let json = serde_json::to_vec(&stored).expect("IndexedDB databases are serializable");
+            write_atomically(&path, &json)
+        });
+        if let Err(why) = result {
+            warn!("Could not write IndexedDB database {}: {}", path.display(), why);
+        }
+    }
+
+    fn queue_connection_request(&mut self, origin: ImmutableOrigin, name: String, request: ConnectionRequest) {
+        let key = (origin.ascii_serialization(), name);
+        self.connection_requests.entry(key.clone()).or_insert(VecDeque::new()).push_back(request);
+        self.process_connection_requests(key);
+    }
+
+    /// Whether a version change transaction is running on the given database.
+    fn is_upgrading(&self, origin: &str, name: &str) -> bool {
+        self.transactions.values().any(|transaction| {
+            transaction.origin == origin && transaction.name == name &&
+                transaction.mode == IndexedDBTransactionMode::VersionChange
+        })
+    }
+
+    /// The connections open to the given database.
+    fn open_connections(&self, origin: &str, name: &str) -> Vec<IndexedDBConnectionId> {
+        self.connections.iter()
+            .filter(|&(_, connection)| connection.origin == origin && connection.name == name)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Notify the connections open to the given database that they block a version change.
+    fn notify_version_change(&self, origin: &str, name: &str, old_version: u64, new_version: Option<u64>) {
+        for id in self.open_connections(origin, name) {
+            let event = IndexedDBConnectionEvent::VersionChange { old_version, new_version };
+            let _ = self.connections[&id].events.send(event);
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#open-a-database>
+    /// <https://w3c.github.io/IndexedDB/#delete-a-database>
+    fn process_connection_requests(&mut self, key: (String, String)) {
+        let (ref origin, ref name) = key;
+        loop {
+            // Step 2: wait for the previous requests, and their upgrade transaction, to finish.
+            if self.is_upgrading(origin, name) {
+                return;
+            }
+            let mut request = match self.connection_requests.get_mut(&key).and_then(|queue| queue.pop_front()) {
+                Some(request) => request,
+                None => {
+                    self.connection_requests.remove(&key);
+                    return;
+                },
+            };
+            let current_version = self.origins.get(origin)
+                .and_then(|databases| databases.get(name))
+                .map(|database| database.version);
+            let is_waiting = match request {
+                ConnectionRequest::Open { ref sender, ref events, version, ref mut notified } => {
+                    // Steps 4-5.
+                    let version = version.unwrap_or(current_version.unwrap_or(1));
+                    let old_version = current_version.unwrap_or(0);
+                    if version < old_version {
+                        // Step 6.
+                        let _ = sender.send(IndexedDBOpenResponse::Error(IndexedDBError::Version));
+                        false
+                    } else if version == old_version {
+                        // Step 8.
+                        let connection = self.next_id();
+                        self.connections.insert(connection, Connection {
+                            origin: origin.clone(),
+                            name: name.clone(),
+                            events: events.clone(),
+                        });
+                        let metadata = self.origins[origin][name].metadata();
+                        let _ = sender.send(IndexedDBOpenResponse::Opened { connection, metadata, upgrade: None });
+                        false
+                    } else if !self.open_connections(origin, name).is_empty() {
+                        // Step 10.2-10.4 of "run an upgrade transaction".
+                        if !*notified {
+                            *notified = true;
+                            self.notify_version_change(origin, name, old_version, Some(version));
+                            let _ = sender.send(IndexedDBOpenResponse::Blocked {
+                                old_version: old_version,
+                                new_version: version,
+                            });
+                        }
+                        true
+                    } else {
+                        self.run_upgrade_transaction(origin, name, version, sender.clone(), events.clone());
+                        false
+                    }
+                },
+                ConnectionRequest::Delete { ref sender, ref mut notified } => {
+                    let old_version = current_version.unwrap_or(0);
+                    if current_version.is_some() && !self.open_connections(origin, name).is_empty() {
+                        if !*notified {
+                            *notified = true;
+                            self.notify_version_change(origin, name, old_version, None);
+                            let _ = sender.send(IndexedDBDeleteResponse::Blocked { old_version });
+                        }
+                        true
+                    } else {
+                        if let Some(databases) = self.origins.get_mut(origin) {
+                            databases.remove(name);
+                        }
+                        self.save(origin, name);
+                        let _ = sender.send(IndexedDBDeleteResponse::Deleted(old_version));
+                        false
+                    }
+                },
+            };
+            if is_waiting {
+                self.connection_requests.get_mut(&key).unwrap().push_front(request);
+                return;
+            }
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#run-an-upgrade-transaction>
+    fn run_upgrade_transaction(&mut self,
+                               origin: &str,
+                               name: &str,
+                               version: u64,
+                               sender: IpcSender<IndexedDBOpenResponse>,
+                               events: IpcSender<IndexedDBConnectionEvent>) {
+        let snapshot = self.origins.get(origin).and_then(|databases| databases.get(name)).cloned();
+        let old_version = snapshot.as_ref().map_or(0, |database| database.version);
+        let metadata = {
+            let database = self.origins.entry(origin.to_owned())
+                .or_insert(HashMap::new())
+                .entry(name.to_owned())
+                .or_insert(Database {
+                    name: name.to_owned(),
+                    version: 0,
+                    stores: BTreeMap::new(),
+                });
+            database.version = version;
+            database.metadata()
+        };
+        let connection = self.next_id();
+        self.connections.insert(connection, Connection {
+            origin: origin.to_owned(),
+            name: name.to_owned(),
+            events: events,
+        });
+        let transaction = self.next_id();
+        self.transactions.insert(transaction, Transaction {
+            connection: connection,
+            origin: origin.to_owned(),
+            name: name.to_owned(),
+            scope: vec![],
+            mode: IndexedDBTransactionMode::VersionChange,
+            started: true,
+            pending: VecDeque::new(),
+            backup: HashMap::new(),
+            snapshot: snapshot,
+            open_request: Some(sender.clone()),
+        });
+        let _ = sender.send(IndexedDBOpenResponse::Opened {
+            connection: connection,
+            metadata: metadata,
+            upgrade: Some((transaction, old_version)),
+        });
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#close-a-database-connection>
+    fn close(&mut self, connection: IndexedDBConnectionId) {
+        if let Some(connection) = self.connections.remove(&connection) {
+            self.process_connection_requests((connection.origin, connection.name));
+        }
+    }
+
+    fn create_transaction(&mut self,
+                          connection: IndexedDBConnectionId,
+                          scope: Vec<String>,
+                          mode: IndexedDBTransactionMode)
+                          -> IndexedDBResult<IndexedDBTransactionId> {
+        let (origin, name) = match self.connections.get(&connection) {
+            Some(connection) => (connection.origin.clone(), connection.name.clone()),
+            None => return Err(IndexedDBError::InvalidState),
+        };
+        if mode == IndexedDBTransactionMode::VersionChange {
+            return Err(IndexedDBError::InvalidState);
+        }
+        match self.origins.get(&origin).and_then(|databases| databases.get(&name)) {
+            Some(database) => {
+                if scope.iter().any(|store| !database.stores.contains_key(store)) {
+                    return Err(IndexedDBError::NotFound);
+                }
+            },
+            None => return Err(IndexedDBError::InvalidState),
+        }
+        let id = self.next_id();
+        self.transactions.insert(id, Transaction {
+            connection: connection,
+            origin: origin,
+            name: name,
+            scope: scope,
+            mode: mode,
+            started: false,
+            pending: VecDeque::new(),
+            backup: HashMap::new(),
+            snapshot: None,
+            open_request: None,
+        });
+        self.start_transactions();
+        Ok(id)
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#transaction-scheduling>
+    fn start_transactions(&mut self) {
+        let startable: Vec<IndexedDBTransactionId> = self.transactions.iter()
+            .filter(|&(id, transaction)| {
+                !transaction.started && !self.transactions.range(..*id).any(|(_, earlier)| {
+                    transaction.conflicts_with(earlier)
+                })
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for id in startable {
+            self.transactions.get_mut(&id).unwrap().started = true;
+            self.process_transaction_requests(id);
+        }
+    }
+
+    fn queue_transaction_request(&mut self, id: IndexedDBTransactionId, request: TransactionRequest) {
+        match self.transactions.get_mut(&id) {
+            Some(transaction) => transaction.pending.push_back(request),
+            None => {
+                // The transaction was aborted.
+                match request {
+                    TransactionRequest::Operation(sender, ..) => {
+                        let _ = sender.send(Err(IndexedDBError::Abort));
+                    },
+                    TransactionRequest::Commit(sender) => {
+                        let _ = sender.send(Err(IndexedDBError::Abort));
+                    },
+                }
+                return;
+            },
+        }
+        self.process_transaction_requests(id);
+    }
+
+    fn process_transaction_requests(&mut self, id: IndexedDBTransactionId) {
+        loop {
+            let request = match self.transactions.get_mut(&id) {
+                Some(transaction) => {
+                    if !transaction.started {
+                        return;
+                    }
+                    transaction.pending.pop_front()
+                },
+                None => return,
+            };
+            match request {
+                Some(TransactionRequest::Operation(sender, store, operation)) => {
+                    let _ = sender.send(self.execute(id, store, operation));
+                },
+                Some(TransactionRequest::Commit(sender)) => {
+                    // Reply before committing, so that the "complete" event of a version change
+                    // transaction is fired before the success event of its open request.
+                    let _ = sender.send(Ok(()));
+                    self.commit(id);
+                    return;
+                },
+                None => return,
+            }
+        }
+    }
+
+    fn execute(&mut self, id: IndexedDBTransactionId, store_name: String, operation: IndexedDBOperation)
+               -> IndexedDBResult<IndexedDBOperationResult> {
+        let (origin, name, mode) = {
+            let transaction = &self.transactions[&id];
+            (transaction.origin.clone(), transaction.name.clone(), transaction.mode)
+        };
+        let available = self.quota.saturating_sub(self.usage(&origin));
+        let is_write = match operation {
+            IndexedDBOperation::Put { .. } | IndexedDBOperation::Delete(..) | IndexedDBOperation::Clear => true,
+            _ => false,
+        };
+        if is_write && mode == IndexedDBTransactionMode::ReadOnly {
+            return Err(IndexedDBError::InvalidState);
+        }
+        let store = match self.origins.get_mut(&origin)
+            .and_then(|databases| databases.get_mut(&name))
+            .and_then(|database| database.stores.get_mut(&store_name)) {
+            Some(store) => store,
+            None => return Err(IndexedDBError::NotFound),
+        };
+        if is_write && mode == IndexedDBTransactionMode::ReadWrite {
+            let transaction = self.transactions.get_mut(&id).unwrap();
+            if !transaction.backup.contains_key(&store_name) {
+                transaction.backup.insert(store_name.clone(), store.clone());
+            }
+        }
+        store.execute(operation, available)
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#commit-a-transaction>
+    fn commit(&mut self, id: IndexedDBTransactionId) {
+        let transaction = match self.transactions.remove(&id) {
+            Some(transaction) => transaction,
+            None => return,
+        };
+        if transaction.mode != IndexedDBTransactionMode::ReadOnly {
+            self.save(&transaction.origin, &transaction.name);
+        }
+        if let Some(sender) = transaction.open_request {
+            let _ = sender.send(IndexedDBOpenResponse::UpgradeFinished(Ok(())));
+        }
+        self.finish(transaction.origin, transaction.name);
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#abort-a-transaction>
+    fn abort(&mut self, id: IndexedDBTransactionId) {
+        let mut transaction = match self.transactions.remove(&id) {
+            Some(transaction) => transaction,
+            None => return,
+        };
+        for request in transaction.pending.drain(..) {
+            match request {
+                TransactionRequest::Operation(sender, ..) => {
+                    let _ = sender.send(Err(IndexedDBError::Abort));
+                },
+                TransactionRequest::Commit(sender) => {
+                    let _ = sender.send(Err(IndexedDBError::Abort));
+                },
+            }
+        }
+        if transaction.mode == IndexedDBTransactionMode::VersionChange {
+            // Revert the database to its state before the upgrade, and close the upgrade connection.
+            let databases = self.origins.entry(transaction.origin.clone()).or_insert(HashMap::new());
+            match transaction.snapshot.take() {
+                Some(snapshot) => {
+                    databases.insert(transaction.name.clone(), snapshot);
+                },
+                None => {
+                    databases.remove(&transaction.name);
+                },
+            }
+            self.connections.remove(&transaction.connection);
+        } else if let Some(database) = self.database_mut(&transaction.origin, &transaction.name) {
+            let backup = mem::replace(&mut transaction.backup, HashMap::new());
+            for (store_name, store) in backup {
+                database.stores.insert(store_name, store);
+            }
+        }
+        if let Some(sender) = transaction.open_request {
+            let _ = sender.send(IndexedDBOpenResponse::UpgradeFinished(Err(IndexedDBError::Abort)));
+        }
+        self.finish(transaction.origin, transaction.name);
+    }
+
+    /// Start the transactions and process the connection requests waiting on a finished transaction.
+    fn finish(&mut self, origin: String, name: String) {
+        self.start_transactions();
+        self.process_connection_requests((origin, name));
+    }
+
+    /// The database a version change transaction is running on.
+    fn upgrading_database(&mut self, id: IndexedDBTransactionId) -> IndexedDBResult<&mut Database> {
+        let (origin, name) = match self.transactions.get(&id) {
+            Some(transaction) if transaction.mode == IndexedDBTransactionMode::VersionChange => {
+                (transaction.origin.clone(), transaction.name.clone())
+            },
+            _ => return Err(IndexedDBError::InvalidState),
+        };
+        self.database_mut(&origin, &name).ok_or(IndexedDBError::InvalidState)
+    }
+
+    fn create_object_store(&mut self, id: IndexedDBTransactionId, metadata: IndexedDBObjectStoreMetadata)
+                           -> IndexedDBResult<()> {
+        let database = self.upgrading_database(id)?;
+        if database.stores.contains_key(&metadata.name) {
+            return Err(IndexedDBError::Constraint);
+        }
+        database.stores.insert(metadata.name.clone(), ObjectStore::new(metadata));
+        Ok(())
+    }
+
+    fn delete_object_store(&mut self, id: IndexedDBTransactionId, name: String) -> IndexedDBResult<()> {
+        let database = self.upgrading_database(id)?;
+        database.stores.remove(&name).map(|_| ()).ok_or(IndexedDBError::NotFound)
+    }
+
+    fn create_index(&mut self, id: IndexedDBTransactionId, store: String, metadata: IndexedDBIndexMetadata)
+                    -> IndexedDBResult<Vec<IndexedDBRecord>> {
+        let database = self.upgrading_database(id)?;
+        let store = database.stores.get_mut(&store).ok_or(IndexedDBError::NotFound)?;
+        if store.indexes.contains_key(&metadata.name) {
+            return Err(IndexedDBError::Constraint);
+        }
+        store.indexes.insert(metadata.name.clone(), Index {
+            metadata: metadata,
+            entries: BTreeSet::new(),
+        });
+        Ok(store.records.iter().map(|(key, value)| IndexedDBRecord {
+            key: key.clone(),
+            value: value.clone(),
+        }).collect())
+    }
+
+    fn set_index_keys(&mut self,
+                      id: IndexedDBTransactionId,
+                      store: String,
+                      index: String,
+                      keys: Vec<(IndexedDBKey, Vec<IndexedDBKey>)>)
+                      -> IndexedDBResult<()> {
+        let origin = match self.transactions.get(&id) {
+            Some(transaction) => transaction.origin.clone(),
+            None => return Err(IndexedDBError::InvalidState),
+        };
+        let available = self.quota.saturating_sub(self.usage(&origin));
+        let database = self.upgrading_database(id)?;
+        let store = database.stores.get_mut(&store).ok_or(IndexedDBError::NotFound)?;
+        let index = store.indexes.get_mut(&index).ok_or(IndexedDBError::NotFound)?;
+        // Nothing is inserted until every entry is known to fit, so that a failure leaves the index
+        // and the size of the store consistent.
+        let mut new_entries = BTreeSet::new();
+        let mut new_unique_keys = BTreeMap::new();
+        for (primary_key, index_keys) in keys {
+            for index_key in index_keys {
+                if index.violates_uniqueness(&index_key, &primary_key) {
+                    return Err(IndexedDBError::Constraint);
+                }
+                if index.metadata.unique {
+                    let previous = new_unique_keys.insert(index_key.clone(), primary_key.clone());
+                    if previous.map_or(false, |other_primary_key| other_primary_key != primary_key) {
+                        return Err(IndexedDBError::Constraint);
+                    }
+                }
+                let entry = (index_key, primary_key.clone());
+                if !index.entries.contains(&entry) {
+                    new_entries.insert(entry);
+                }
+            }
+        }
+        let size = new_entries.iter().map(entry_size).sum::<u64>();
+        if size > available {
+            return Err(IndexedDBError::QuotaExceeded);
+        }
+        index.entries.extend(new_entries);
+        store.size += size;
+        Ok(())
+    }
+
+    fn delete_index(&mut self, id: IndexedDBTransactionId, store: String, index: String) -> IndexedDBResult<()> {
+        let database = self.upgrading_database(id)?;
+        let store = database.stores.get_mut(&store).ok_or(IndexedDBError::NotFound)?;
+        let index = store.indexes.remove(&index).ok_or(IndexedDBError::NotFound)?;
+        store.size -= index.entries.iter().map(entry_size).sum::<u64>();
+        Ok(())
+    }
+}
@@ -43,17 +43,21 @@ extern crate webrender_api;
 extern crate websocket;
 
 mod blob_loader;
+pub mod cache_storage_thread;
 pub mod connector;
 pub mod cookie;
 pub mod cookie_storage;
 mod data_loader;
+pub mod disk_cache;
 pub mod filemanager_thread;
 mod hosts;
 pub mod hsts;
 pub mod http_cache;
 pub mod http_loader;
 pub mod image_cache;
+pub mod indexeddb_thread;
 pub mod mime_classifier;
+pub mod request_interceptor;
 pub mod resource_thread;
 mod storage_thread;
 pub mod subresource_integrity;
@@ -0,0 +1,100 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use embedder_traits::{EmbedderMsg, EmbedderProxy};
+use embedder_traits::request_interception::{InterceptedRequest, InterceptionAction};
+use embedder_traits::request_interception::{RequestInterception, SyntheticResponse};
+use hyper::status::StatusCode;
+use ipc_channel::ipc;
+use net_traits::NetworkError;
+use net_traits::request::Request;
+use net_traits::response::{Response, ResponseBody};
+use servo_url::ServoUrl;
+use std::sync::{Mutex, RwLock};
+
+/// Lets the embedder block, redirect, answer or rewrite the requests of every fetch,
+/// according to the rules it has set or by asking it about each request.
+pub struct RequestInterceptor {
+    interception: RwLock<RequestInterception>,
+    embedder_proxy: Mutex<EmbedderProxy>,
+}
+
+impl RequestInterceptor {
+    pub fn new(embedder_proxy: EmbedderProxy) -> RequestInterceptor {
+        RequestInterceptor {
+            interception: RwLock::new(RequestInterception::default()),
+            embedder_proxy: Mutex::new(embedder_proxy),
+        }
+    }
+
+    pub fn set_interception(&self, interception: RequestInterception) {
+        *self.interception.write().unwrap() = interception;
+    }
+
+    /// Apply the interception configuration to `request`, which may modify it. Returns the
+    /// response to use instead of fetching the request, if any.
+    pub fn intercept(&self, request: &mut Request) -> Option<Response> {
+        // Redirections are intercepted in turn, and limited like HTTP redirections.
+        self.intercept_with_redirect_limit(request, 20)
+    }
+
+    fn intercept_with_redirect_limit(&self, request: &mut Request, redirect_limit: u32) -> Option<Response> {
+        let url = request.current_url();
+        let (action, ask_embedder) = {
+            let interception = self.interception.read().unwrap();
+            (interception.matching_action(&url).cloned(), interception.ask_embedder)
+        };
+        let action = match action {
+            Some(action) => action,
+            None if ask_embedder => self.ask_embedder(request),
+            None => return None,
+        };
+
+        match action {
+            InterceptionAction::Continue => None,
+            InterceptionAction::Block => {
+                Some(Response::network_error(NetworkError::Internal("Request blocked by interception".into())))
+            },
+            InterceptionAction::Redirect(url) => {
+                if redirect_limit == 0 {
+                    return Some(Response::network_error(NetworkError::Internal("Too many redirects".into())));
+                }
+                request.url_list.push(url);
+                self.intercept_with_redirect_limit(request, redirect_limit - 1)
+            },
+            InterceptionAction::Respond(response) => Some(synthetic_response(url, response)),
+            InterceptionAction::SetHeaders(headers) => {
+                for (name, value) in headers {
+                    request.headers.set_raw(name, vec![value]);
+                }
+                None
+            },
+        }
+    }
+
+    fn ask_embedder(&self, request: &Request) -> InterceptionAction {
+        let intercepted = InterceptedRequest {
+            url: request.current_url(),
+            method: request.method.as_ref().to_owned(),
+            headers: request.headers.iter().map(|header| {
+                (header.name().to_owned(), header.value_string().into_bytes())
+            }).collect(),
+        };
+        let (sender, receiver) = ipc::channel().unwrap();
+        self.embedder_proxy.lock().unwrap().send((None, EmbedderMsg::InterceptRequest(intercepted, sender)));
+        // Embedders which do not handle the message let the request through.
+        receiver.recv().unwrap_or(InterceptionAction::Continue)
+    }
+}
+
+fn synthetic_response(url: ServoUrl, synthetic: SyntheticResponse) -> Response {
+    let mut response = Response::new(url);
+    response.status = Some(StatusCode::from_u16(synthetic.status));
+    response.raw_status = Some((synthetic.status, synthetic.status_text.into_bytes()));
+    for (name, value) in synthetic.headers {
+        response.headers.append_raw(name, value);
+    }
+    *response.body.lock().unwrap() = ResponseBody::Done(synthetic.body);
+    response
+}
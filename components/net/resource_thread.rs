@@ -3,11 +3,13 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 //! A thread that takes a URL and streams back the binary data.
+use cache_storage_thread::CacheStorageThreadFactory;
 use connector::{create_http_connector, create_ssl_client};
 use cookie;
 use cookie_rs;
 use cookie_storage::CookieStorage;
 use devtools_traits::DevtoolsControlMsg;
+use disk_cache::write_atomically;
 use embedder_traits::EmbedderProxy;
 use embedder_traits::resources::{self, Resource};
 use fetch::cors_cache::CorsCache;
@@ -17,22 +19,27 @@ use hsts::HstsList;
 use http_cache::HttpCache;
 use http_loader::{HttpState, http_redirect_fetch};
 use hyper_serde::Serde;
+use indexeddb_thread::IndexedDBThreadFactory;
 use ipc_channel::ipc::{self, IpcReceiver, IpcReceiverSet, IpcSender};
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
 use net_traits::{CookieSource, CoreResourceThread};
 use net_traits::{CoreResourceMsg, CustomResponseMediator, FetchChannels};
 use net_traits::{FetchResponseMsg, ResourceThreads, WebSocketDomAction};
 use net_traits::WebSocketNetworkEvent;
+use net_traits::cache_storage_thread::CacheStorageThreadMsg;
+use net_traits::indexeddb_thread::IndexedDBThreadMsg;
 use net_traits::request::{Request, RequestInit};
 use net_traits::response::{Response, ResponseInit};
 use net_traits::storage_thread::StorageThreadMsg;
 use profile_traits::mem::{Report, ReportsChan, ReportKind};
 use profile_traits::mem::ProfilerChan as MemProfilerChan;
 use profile_traits::time::ProfilerChan;
+use request_interceptor::RequestInterceptor;
 use serde::{Deserialize, Serialize};
 use serde_json;
 use servo_allocator;
 use servo_config::opts;
+use servo_config::prefs::PREFS;
 use servo_url::ServoUrl;
 use std::borrow::{Cow, ToOwned};
 use std::collections::HashMap;
@@ -42,8 +49,9 @@ use std::io::prelude::*;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex, RwLock};
-use std::sync::mpsc::Sender;
-use std::thread;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 use storage_thread::StorageThreadFactory;
 use websocket_loader;
 
@@ -62,9 +70,20 @@ pub fn new_resource_threads(user_agent: Cow<'static, str>,
         mem_profiler_chan,
         embedder_proxy,
         config_dir.clone());
+    let indexeddb_quota = PREFS.get("dom.indexeddb.quota").as_u64().unwrap_or(0);
+    let indexeddb: IpcSender<IndexedDBThreadMsg> =
+        IndexedDBThreadFactory::new(config_dir.clone(), indexeddb_quota);
+    // Private browsing gets a thread of its own, which never writes its databases to disk.
+    let private_indexeddb: IpcSender<IndexedDBThreadMsg> = IndexedDBThreadFactory::new(None, indexeddb_quota);
+    let cache_storage_quota = PREFS.get("dom.caches.quota").as_u64().unwrap_or(0);
+    let cache_storage: IpcSender<CacheStorageThreadMsg> =
+        CacheStorageThreadFactory::new(config_dir.clone(), cache_storage_quota);
+    // Likewise for the caches of private browsing.
+    let private_cache_storage: IpcSender<CacheStorageThreadMsg> =
+        CacheStorageThreadFactory::new(None, cache_storage_quota);
     let storage: IpcSender<StorageThreadMsg> = StorageThreadFactory::new(config_dir);
-    (ResourceThreads::new(public_core, storage.clone()),
-     ResourceThreads::new(private_core, storage))
+    (ResourceThreads::new(public_core, storage.clone(), indexeddb, cache_storage),
+     ResourceThreads::new(private_core, storage, private_indexeddb, private_cache_storage))
 }
 
 
@@ -88,6 +107,7 @@ pub fn new_core_resource_thread(user_agent: Cow<'static, str>,
         let mut channel_manager = ResourceChannelManager {
             resource_manager: resource_manager,
             config_dir: config_dir,
+            persister: None,
         };
 
         mem_profiler_chan.run_with_memory_reporting(|| (
@@ -107,17 +127,25 @@ pub fn new_core_resource_thread(user_agent: Cow<'static, str>,
 struct ResourceChannelManager {
     resource_manager: CoreResourceManager,
     config_dir: Option<PathBuf>,
+    /// Saves the public profile stores to `config_dir`, if there is one.
+    persister: Option<ProfilePersister>,
 }
 
 fn create_http_states(config_dir: Option<&Path>) -> (Arc<HttpState>, Arc<HttpState>) {
     let mut hsts_list = HstsList::from_servo_preload();
     let mut auth_cache = AuthCache::new();
-    let http_cache = HttpCache::new();
+    let mut http_cache = HttpCache::new();
     let mut cookie_jar = CookieStorage::new(150);
     if let Some(config_dir) = config_dir {
         read_json_from_file(&mut auth_cache, config_dir, "auth_cache.json");
         read_json_from_file(&mut hsts_list, config_dir, "hsts_list.json");
         read_json_from_file(&mut cookie_jar, config_dir, "cookie_jar.json");
+        hsts_list.remove_expired_entries();
+        cookie_jar.remove_expired_cookies();
+        if PREFS.get("network.http-cache.disk.enabled").as_boolean().unwrap_or(false) {
+            let max_size = PREFS.get("network.http-cache.disk.max-size").as_u64().unwrap_or(0);
+            http_cache = HttpCache::new_with_disk_cache(config_dir.join("http_cache"), max_size);
+        }
     }
 
     let certs = match opts::get().certificate_path {
@@ -154,6 +182,12 @@ impl ResourceChannelManager {
              memory_reporter: IpcReceiver<ReportsChan>) {
         let (public_http_state, private_http_state) =
             create_http_states(self.config_dir.as_ref().map(Deref::deref));
+        if let Some(ref config_dir) = self.config_dir {
+            let interval = PREFS.get("network.profile.save-interval-ms").as_u64().unwrap_or(0);
+            self.persister = Some(ProfilePersister::new(config_dir.clone(),
+                                                        public_http_state.clone(),
+                                                        Duration::from_millis(interval)));
+        }
 
         let mut rx_set = IpcReceiverSet::new().unwrap();
         let private_id = rx_set.add(private_receiver).unwrap();
@@ -211,7 +245,19 @@ impl ResourceChannelManager {
             size: private_cache.size_of(&mut ops)
         };
 
-        msg.send(vec!(public_report, private_report));
+        let public_disk_report = Report {
+            path: path!["disk-cache", "public"],
+            kind: ReportKind::NonExplicitSize,
+            size: public_cache.disk_usage() as usize
+        };
+
+        let private_disk_report = Report {
+            path: path!["disk-cache", "private"],
+            kind: ReportKind::NonExplicitSize,
+            size: private_cache.disk_usage() as usize
+        };
+
+        msg.send(vec!(public_report, private_report, public_disk_report, private_disk_report));
     }
 
     /// Returns false if the thread should exit.
@@ -262,24 +308,28 @@ impl ResourceChannelManager {
                     history_states.remove(&history_state);
                 }
             }
+            CoreResourceMsg::ExportCookies(format, sender) => {
+                let cookie_jar = http_state.cookie_jar.read().unwrap();
+                let _ = sender.send(cookie_jar.export(format));
+            }
+            CoreResourceMsg::ImportCookies(format, data, sender) => {
+                let mut cookie_jar = http_state.cookie_jar.write().unwrap();
+                let _ = sender.send(cookie_jar.import(format, &data));
+            }
             CoreResourceMsg::Synchronize(sender) => {
                 let _ = sender.send(());
             }
             CoreResourceMsg::ToFileManager(msg) => self.resource_manager.filemanager.handle(msg),
+            CoreResourceMsg::SetRequestInterception(interception) =>
+                self.resource_manager.request_interceptor.set_interception(interception),
             CoreResourceMsg::Exit(sender) => {
-                if let Some(ref config_dir) = self.config_dir {
-                    match http_state.auth_cache.read() {
-                        Ok(auth_cache) => write_json_to_file(&*auth_cache, config_dir, "auth_cache.json"),
-                        Err(_) => warn!("Error writing auth cache to disk"),
-                    }
-                    match http_state.cookie_jar.read() {
-                        Ok(jar) => write_json_to_file(&*jar, config_dir, "cookie_jar.json"),
-                        Err(_) => warn!("Error writing cookie jar to disk"),
-                    }
-                    match http_state.hsts_list.read() {
-                        Ok(hsts) => write_json_to_file(&*hsts, config_dir, "hsts_list.json"),
-                        Err(_) => warn!("Error writing hsts list to disk"),
-                    }
+                // The persister does a final save of the profile stores before exiting.
+                if let Some(persister) = self.persister.take() {
+                    persister.exit();
+                }
+                match http_state.http_cache.write() {
+                    Ok(mut http_cache) => http_cache.flush(),
+                    Err(_) => warn!("Error writing http cache to disk"),
                 }
                 let _ = sender.send(());
                 return false;
@@ -289,6 +339,85 @@ impl ResourceChannelManager {
     }
 }
 
+/// A thread that periodically saves the cookie jar, HSTS list and authentication
+/// cache of a profile, so that they survive a crash.
+struct ProfilePersister {
+    exit_chan: Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl ProfilePersister {
+    /// Stores are saved every `interval` if they changed, and once more on exit.
+    /// A zero interval only saves on exit.
+    fn new(config_dir: PathBuf, http_state: Arc<HttpState>, interval: Duration) -> ProfilePersister {
+        let (exit_chan, exit_port) = mpsc::channel();
+        let thread = thread::Builder::new().name("ProfilePersister".to_owned()).spawn(move || {
+            let mut saved = HashMap::new();
+            loop {
+                let result = if interval == Duration::from_millis(0) {
+                    exit_port.recv().map_err(|_| RecvTimeoutError::Disconnected)
+                } else {
+                    exit_port.recv_timeout(interval)
+                };
+                save_http_state(&http_state, &config_dir, &mut saved);
+                if result != Err(RecvTimeoutError::Timeout) {
+                    return;
+                }
+            }
+        }).expect("Thread spawning failed");
+
+        ProfilePersister {
+            exit_chan: exit_chan,
+            thread: thread,
+        }
+    }
+
+    /// Save the stores a last time and wait for the thread to finish.
+    fn exit(self) {
+        let _ = self.exit_chan.send(());
+        if self.thread.join().is_err() {
+            warn!("Profile persister panicked");
+        }
+    }
+}
+
+/// Write the stores of `http_state` that changed since they were last saved.
+/// `saved` maps file names to the JSON last written to them.
+fn save_http_state(http_state: &HttpState, config_dir: &Path, saved: &mut HashMap<&'static str, String>) {
+    match http_state.auth_cache.read() {
+        Ok(auth_cache) => save_json_if_changed(&*auth_cache, config_dir, "auth_cache.json", saved),
+        Err(_) => warn!("Error writing auth cache to disk"),
+    }
+    match http_state.cookie_jar.read() {
+        Ok(jar) => save_json_if_changed(&*jar, config_dir, "cookie_jar.json", saved),
+        Err(_) => warn!("Error writing cookie jar to disk"),
+    }
+    match http_state.hsts_list.read() {
+        Ok(hsts) => save_json_if_changed(&*hsts, config_dir, "hsts_list.json", saved),
+        Err(_) => warn!("Error writing hsts list to disk"),
+    }
+}
+
+fn save_json_if_changed<T>(data: &T,
+                           config_dir: &Path,
+                           filename: &'static str,
+                           saved: &mut HashMap<&'static str, String>)
+    where T: Serialize
+{
+    let json_encoded = match serde_json::to_string_pretty(data) {
+        Ok(json_encoded) => json_encoded,
+        Err(_) => return,
+    };
+    if saved.get(filename) == Some(&json_encoded) {
+        return;
+    }
+    if write_string_to_file(&json_encoded, config_dir, filename) {
+        saved.insert(filename, json_encoded);
+    }
+}
+
+/// Reads `data` from a JSON file in `config_dir`. A file that can't be decoded is
+/// moved aside to `<filename>.corrupt`, and `data` is left untouched.
 pub fn read_json_from_file<T>(data: &mut T, config_dir: &Path, filename: &str)
     where T: for<'de> Deserialize<'de>
 {
@@ -306,42 +435,46 @@ pub fn read_json_from_file<T>(data: &mut T, config_dir: &Path, filename: &str)
     let mut string_buffer: String = String::new();
     match file.read_to_string(&mut string_buffer) {
         Err(why) => {
-            panic!("couldn't read from {}: {}", display,
-                                                Error::description(&why))
+            warn!("couldn't read from {}: {}", display, Error::description(&why));
+            return;
         },
-        Ok(_) => println!("successfully read from {}", display),
+        Ok(_) => debug!("successfully read from {}", display),
     }
 
     match serde_json::from_str(&string_buffer) {
         Ok(decoded_buffer) => *data = decoded_buffer,
-        Err(why) => warn!("Could not decode buffer{}", why),
+        Err(why) => {
+            warn!("Could not decode {}: {}", display, why);
+            let corrupt_path = config_dir.join(format!("{}.corrupt", filename));
+            if let Err(why) = fs::rename(&path, &corrupt_path) {
+                warn!("couldn't move {} aside: {}", display, Error::description(&why));
+            }
+        },
     }
 }
 
+/// Writes `data` as JSON to a file in `config_dir`. The file is replaced atomically,
+/// so a crash leaves either the old or the new contents.
 pub fn write_json_to_file<T>(data: &T, config_dir: &Path, filename: &str)
     where T: Serialize
 {
-    let json_encoded: String;
-    match serde_json::to_string_pretty(&data) {
-        Ok(d) => json_encoded = d,
-        Err(_) => return,
+    if let Ok(json_encoded) = serde_json::to_string_pretty(&data) {
+        write_string_to_file(&json_encoded, config_dir, filename);
     }
-    let path = config_dir.join(filename);
-    let display = path.display();
-
-    let mut file = match File::create(&path) {
-        Err(why) => panic!("couldn't create {}: {}",
-                           display,
-                           Error::description(&why)),
-        Ok(file) => file,
-    };
+}
 
-    match file.write_all(json_encoded.as_bytes()) {
+/// Returns whether the file was written.
+fn write_string_to_file(contents: &str, config_dir: &Path, filename: &str) -> bool {
+    let path = config_dir.join(filename);
+    match write_atomically(&path, contents.as_bytes()) {
         Err(why) => {
-            panic!("couldn't write to {}: {}", display,
-                                               Error::description(&why))
+            warn!("couldn't write to {}: {}", path.display(), Error::description(&why));
+            false
+        },
+        Ok(_) => {
+            debug!("successfully wrote to {}", path.display());
+            true
         },
-        Ok(_) => println!("successfully wrote to {}", display),
     }
 }
 
@@ -371,6 +504,7 @@ pub struct CoreResourceManager {
     devtools_chan: Option<Sender<DevtoolsControlMsg>>,
     swmanager_chan: Option<IpcSender<CustomResponseMediator>>,
     filemanager: FileManager,
+    request_interceptor: Arc<RequestInterceptor>,
 }
 
 impl CoreResourceManager {
@@ -382,7 +516,8 @@ impl CoreResourceManager {
             user_agent: user_agent,
             devtools_chan: devtools_channel,
             swmanager_chan: None,
-            filemanager: FileManager::new(embedder_proxy),
+            filemanager: FileManager::new(embedder_proxy.clone()),
+            request_interceptor: Arc::new(RequestInterceptor::new(embedder_proxy)),
         }
     }
 
@@ -406,6 +541,7 @@ impl CoreResourceManager {
         let ua = self.user_agent.clone();
         let dc = self.devtools_chan.clone();
         let filemanager = self.filemanager.clone();
+        let request_interceptor = self.request_interceptor.clone();
 
         thread::Builder::new().name(format!("fetch thread for {}", req_init.url)).spawn(move || {
             let mut request = Request::from_init(req_init);
@@ -418,6 +554,7 @@ impl CoreResourceManager {
                 user_agent: ua,
                 devtools_chan: dc,
                 filemanager: filemanager,
+                request_interceptor: request_interceptor,
                 cancellation_listener: Arc::new(Mutex::new(CancellationListener::new(cancel_chan))),
             };
 
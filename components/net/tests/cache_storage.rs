@@ -0,0 +1,208 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use ipc_channel::ipc::{self, IpcSender};
+use net::cache_storage_thread::CacheStorageThreadFactory;
+use net_traits::cache_storage_thread::{CacheBatchOperation, CacheId, CacheQueryOptions, CacheStorageError};
+use net_traits::cache_storage_thread::{CacheStorageResult, CacheStorageThreadMsg, CachedRequest, CachedResponse};
+use net_traits::response::ResponseType;
+use servo_url::{ImmutableOrigin, ServoUrl};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use time;
+
+fn origin() -> ImmutableOrigin {
+    ServoUrl::parse("https://example.com").unwrap().origin()
+}
+
+fn request(url: &str, headers: &[(&str, &str)]) -> CachedRequest {
+    CachedRequest {
+        url: ServoUrl::parse(url).unwrap(),
+        method: "GET".to_owned(),
+        headers: headers.iter().map(|&(name, value)| (name.to_owned(), value.as_bytes().to_vec())).collect(),
+    }
+}
+
+fn response(body: &str, headers: &[(&str, &str)]) -> CachedResponse {
+    CachedResponse {
+        response_type: ResponseType::Basic,
+        url_list: vec![],
+        status: 200,
+        status_text: b"OK".to_vec(),
+        headers: headers.iter().map(|&(name, value)| (name.to_owned(), value.as_bytes().to_vec())).collect(),
+        body: body.as_bytes().to_vec(),
+    }
+}
+
+fn open(thread: &IpcSender<CacheStorageThreadMsg>, name: &str) -> CacheId {
+    let (sender, receiver) = ipc::channel().unwrap();
+    thread.send(CacheStorageThreadMsg::Open(sender, origin(), name.to_owned())).unwrap();
+    receiver.recv().unwrap()
+}
+
+fn keys(thread: &IpcSender<CacheStorageThreadMsg>) -> Vec<String> {
+    let (sender, receiver) = ipc::channel().unwrap();
+    thread.send(CacheStorageThreadMsg::Keys(sender, origin())).unwrap();
+    receiver.recv().unwrap()
+}
+
+fn batch(thread: &IpcSender<CacheStorageThreadMsg>, id: CacheId, operations: Vec<CacheBatchOperation>)
+         -> CacheStorageResult<bool> {
+    let (sender, receiver) = ipc::channel().unwrap();
+    thread.send(CacheStorageThreadMsg::Batch(sender, id, operations)).unwrap();
+    receiver.recv().unwrap()
+}
+
+fn put(thread: &IpcSender<CacheStorageThreadMsg>, id: CacheId, request: CachedRequest, response: CachedResponse) {
+    assert_eq!(batch(thread, id, vec![CacheBatchOperation::Put(request, response)]), Ok(false));
+}
+
+fn match_all(thread: &IpcSender<CacheStorageThreadMsg>,
+             id: CacheId,
+             request: Option<CachedRequest>,
+             options: CacheQueryOptions)
+             -> Vec<Vec<u8>> {
+    let (sender, receiver) = ipc::channel().unwrap();
+    thread.send(CacheStorageThreadMsg::MatchAll(sender, id, request, options)).unwrap();
+    receiver.recv().unwrap().unwrap().into_iter().map(|response| response.body).collect()
+}
+
+fn match_any(thread: &IpcSender<CacheStorageThreadMsg>, name: Option<&str>, request: CachedRequest) -> Option<Vec<u8>> {
+    let (sender, receiver) = ipc::channel().unwrap();
+    let name = name.map(|name| name.to_owned());
+    thread.send(CacheStorageThreadMsg::MatchAny(sender, origin(), name, request, CacheQueryOptions::default()))
+        .unwrap();
+    receiver.recv().unwrap().map(|response| response.body)
+}
+
+#[test]
+fn test_open_delete_and_keys() {
+    let thread: IpcSender<CacheStorageThreadMsg> = CacheStorageThreadFactory::new(None, 1000);
+    let first = open(&thread, "v1");
+    let second = open(&thread, "v2");
+    assert!(first != second);
+    assert_eq!(open(&thread, "v1"), first);
+    assert_eq!(keys(&thread), vec!["v1".to_owned(), "v2".to_owned()]);
+
+    let (sender, receiver) = ipc::channel().unwrap();
+    thread.send(CacheStorageThreadMsg::Delete(sender, origin(), "v1".to_owned())).unwrap();
+    assert!(receiver.recv().unwrap());
+    assert_eq!(keys(&thread), vec!["v2".to_owned()]);
+
+    // A deleted cache can still be used by the scripts which opened it.
+    put(&thread, first, request("https://example.com/a", &[]), response("a", &[]));
+    assert_eq!(match_all(&thread, first, None, CacheQueryOptions::default()), vec![b"a".to_vec()]);
+    assert_eq!(match_any(&thread, None, request("https://example.com/a", &[])), None);
+}
+
+#[test]
+fn test_put_replaces_matching_entries() {
+    let thread: IpcSender<CacheStorageThreadMsg> = CacheStorageThreadFactory::new(None, 1000);
+    let id = open(&thread, "cache");
+    put(&thread, id, request("https://example.com/a", &[]), response("first", &[]));
+    put(&thread, id, request("https://example.com/b", &[]), response("b", &[]));
+    put(&thread, id, request("https://example.com/a#fragment", &[]), response("second", &[]));
+
+    let options = CacheQueryOptions::default();
+    assert_eq!(match_all(&thread, id, None, options), vec![b"b".to_vec(), b"second".to_vec()]);
+    assert_eq!(match_all(&thread, id, Some(request("https://example.com/a", &[])), options),
+               vec![b"second".to_vec()]);
+
+    let mut post = request("https://example.com/a", &[]);
+    post.method = "POST".to_owned();
+    assert!(match_all(&thread, id, Some(post.clone()), options).is_empty());
+    let ignore_method = CacheQueryOptions { ignore_method: true, ..options };
+    assert_eq!(match_all(&thread, id, Some(post), ignore_method), vec![b"second".to_vec()]);
+}
+
+#[test]
+fn test_ignore_search() {
+    let thread: IpcSender<CacheStorageThreadMsg> = CacheStorageThreadFactory::new(None, 1000);
+    let id = open(&thread, "cache");
+    put(&thread, id, request("https://example.com/a?v=1", &[]), response("a", &[]));
+
+    let options = CacheQueryOptions::default();
+    assert!(match_all(&thread, id, Some(request("https://example.com/a?v=2", &[])), options).is_empty());
+    let ignore_search = CacheQueryOptions { ignore_search: true, ..options };
+    assert_eq!(match_all(&thread, id, Some(request("https://example.com/a?v=2", &[])), ignore_search),
+               vec![b"a".to_vec()]);
+}
+
+#[test]
+fn test_vary() {
+    let thread: IpcSender<CacheStorageThreadMsg> = CacheStorageThreadFactory::new(None, 1000);
+    let id = open(&thread, "cache");
+    let vary = [("vary", "Accept-Language")];
+    put(&thread, id, request("https://example.com/a", &[("accept-language", "en")]), response("en", &vary));
+    put(&thread, id, request("https://example.com/a", &[("accept-language", "fr")]), response("fr", &vary));
+
+    let options = CacheQueryOptions::default();
+    let french = request("https://example.com/a", &[("accept-language", "fr")]);
+    assert_eq!(match_all(&thread, id, Some(french), options), vec![b"fr".to_vec()]);
+    let german = request("https://example.com/a", &[("accept-language", "de")]);
+    assert!(match_all(&thread, id, Some(german.clone()), options).is_empty());
+    let ignore_vary = CacheQueryOptions { ignore_vary: true, ..options };
+    assert_eq!(match_all(&thread, id, Some(german), ignore_vary), vec![b"en".to_vec(), b"fr".to_vec()]);
+}
+
+#[test]
+fn test_batch_is_atomic() {
+    let thread: IpcSender<CacheStorageThreadMsg> = CacheStorageThreadFactory::new(None, 100);
+    let id = open(&thread, "cache");
+    put(&thread, id, request("https://example.com/a", &[]), response("a", &[]));
+
+    // The same request twice in a batch is an error.
+    let duplicates = vec![
+        CacheBatchOperation::Put(request("https://example.com/b", &[]), response("b", &[])),
+        CacheBatchOperation::Put(request("https://example.com/b", &[]), response("b", &[])),
+    ];
+    assert_eq!(batch(&thread, id, duplicates), Err(CacheStorageError::InvalidState));
+
+    let too_large = vec![
+        CacheBatchOperation::Put(request("https://example.com/c", &[]), response("c", &[])),
+        CacheBatchOperation::Put(request("https://example.com/d", &[]), response(&"d".repeat(100), &[])),
+    ];
+    assert_eq!(batch(&thread, id, too_large), Err(CacheStorageError::QuotaExceeded));
+    assert_eq!(match_all(&thread, id, None, CacheQueryOptions::default()), vec![b"a".to_vec()]);
+
+    let delete = vec![CacheBatchOperation::Delete(request("https://example.com/a", &[]), CacheQueryOptions::default())];
+    assert_eq!(batch(&thread, id, delete.clone()), Ok(true));
+    assert_eq!(batch(&thread, id, delete), Ok(false));
+}
+
+#[test]
+fn test_match_any_searches_caches_in_order() {
+    let thread: IpcSender<CacheStorageThreadMsg> = CacheStorageThreadFactory::new(None, 1000);
+    let first = open(&thread, "first");
+    let second = open(&thread, "second");
+    put(&thread, second, request("https://example.com/a", &[]), response("second", &[]));
+    put(&thread, first, request("https://example.com/a", &[]), response("first", &[]));
+
+    assert_eq!(match_any(&thread, None, request("https://example.com/a", &[])), Some(b"first".to_vec()));
+    assert_eq!(match_any(&thread, Some("second"), request("https://example.com/a", &[])), Some(b"second".to_vec()));
+    assert_eq!(match_any(&thread, Some("third"), request("https://example.com/a", &[])), None);
+}
+
+fn create_config_dir() -> PathBuf {
+    let dir = env::temp_dir().join(format!("servo-cache-storage-{}", time::precise_time_ns()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn test_caches_are_persisted() {
+    let dir = create_config_dir();
+    let thread: IpcSender<CacheStorageThreadMsg> = CacheStorageThreadFactory::new(Some(dir.clone()), 1000);
+    let id = open(&thread, "offline");
+    put(&thread, id, request("https://example.com/", &[]), response("shell", &[]));
+    let (sender, receiver) = ipc::channel().unwrap();
+    thread.send(CacheStorageThreadMsg::Exit(sender)).unwrap();
+    receiver.recv().unwrap();
+
+    let thread: IpcSender<CacheStorageThreadMsg> = CacheStorageThreadFactory::new(Some(dir.clone()), 1000);
+    assert_eq!(keys(&thread), vec!["offline".to_owned()]);
+    assert_eq!(match_any(&thread, Some("offline"), request("https://example.com/", &[])), Some(b"shell".to_vec()));
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -6,7 +6,7 @@ use cookie_rs;
 use hyper::header::{Header, SetCookie};
 use net::cookie::Cookie;
 use net::cookie_storage::CookieStorage;
-use net_traits::CookieSource;
+use net_traits::{CookieJarFormat, CookieSource};
 use servo_url::ServoUrl;
 
 #[test]
@@ -411,3 +411,46 @@ fn test_cookie_eviction_all_nonsecure_new_nonsecure() {
                                  &vec, "https://home.example.org:8888/cookie-parser-result?0001");
     assert_eq!(&r, "extra2=bar; extra3=bar; extra4=bar; extra5=bar; foo=bar");
 }
+
+
+fn round_trip_cookies(format: CookieJarFormat) {
+    let url = ServoUrl::parse("https://home.example.org:8888/cookie-parser?0001").unwrap();
+    let mut storage = CookieStorage::new(150);
+    add_cookie_to_storage(&mut storage, &url, "foo=bar; Secure; HttpOnly");
+    add_cookie_to_storage(&mut storage, &url, "foo2=bar; Domain=.example.org; expires=Sun, 18-Apr-2077 21:06:29 GMT");
+    add_cookie_to_storage(&mut storage, &url, "foo3=bar; Path=/foo");
+
+    let exported = storage.export(format);
+    let mut imported = CookieStorage::new(150);
+    assert_eq!(imported.import(format, &exported), Ok(3));
+    assert_eq!(imported.export(format), exported);
+
+    assert_eq!(imported.cookies_for_url(&url, CookieSource::NonHTTP).unwrap(), "foo2=bar");
+    let url = ServoUrl::parse("http://home.example.org/foo/").unwrap();
+    assert_eq!(imported.cookies_for_url(&url, CookieSource::HTTP).unwrap(), "foo3=bar; foo2=bar");
+    let url = ServoUrl::parse("http://www.example.org/foo/").unwrap();
+    assert_eq!(imported.cookies_for_url(&url, CookieSource::HTTP).unwrap(), "foo2=bar");
+}
+
+#[test]
+fn test_cookie_jar_netscape_round_trip() {
+    round_trip_cookies(CookieJarFormat::Netscape);
+}
+
+#[test]
+fn test_cookie_jar_json_round_trip() {
+    round_trip_cookies(CookieJarFormat::Json);
+}
+
+#[test]
+fn test_cookie_jar_netscape_import() {
+    let data = "# Netscape HTTP Cookie File\n\n\
+                .example.org\tTRUE\t/\tFALSE\t0\tfoo\tbar\n\
+                example.org\tFALSE\t/\tFALSE\t1\texpired\tbar\n";
+    let mut storage = CookieStorage::new(150);
+    assert_eq!(storage.import(CookieJarFormat::Netscape, data), Ok(1));
+    let url = ServoUrl::parse("http://www.example.org/").unwrap();
+    assert_eq!(storage.cookies_for_url(&url, CookieSource::HTTP).unwrap(), "foo=bar");
+
+    assert!(storage.import(CookieJarFormat::Netscape, "example.org\tFALSE\t/\tFALSE").is_err());
+}
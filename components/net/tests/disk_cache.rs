@@ -0,0 +1,183 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use hyper::header::{CacheControl, CacheDirective};
+use hyper::method::Method;
+use msg::constellation_msg::TEST_PIPELINE_ID;
+use net::disk_cache::DiskCacheWrite;
+use net::http_cache::{CachedResponse, HttpCache};
+use net_traits::request::{Destination, Request, RequestInit};
+use net_traits::response::{Response, ResponseBody};
+use servo_url::ServoUrl;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use time;
+
+fn create_request(url: &ServoUrl) -> Request {
+    Request::from_init(RequestInit {
+        url: url.clone(),
+        method: Method::Get,
+        destination: Destination::Document,
+        origin: url.clone().origin(),
+        pipeline_id: Some(TEST_PIPELINE_ID),
+        .. RequestInit::default()
+    })
+}
+
+fn create_fresh_response(url: &ServoUrl, body: &[u8]) -> Response {
+    let mut response = Response::new(url.clone());
+    response.headers.set(CacheControl(vec![CacheDirective::MaxAge(3600)]));
+    *response.body.lock().unwrap() = ResponseBody::Done(body.to_vec());
+    response
+}
+
+fn create_cache_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("servo-http-cache-{}-{}", name, time::precise_time_ns()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+/// Store the response in the cache and write it to disk, the way a fetch does.
+fn store(cache: &mut HttpCache, request: &Request, response: &Response) {
+    cache.store(request, response);
+    let written = cache.prepare_disk_writes(request).into_iter().map(DiskCacheWrite::perform).collect();
+    cache.finish_disk_writes(request, written);
+}
+
+/// Look the request up in the cache, the way a fetch does.
+fn construct_response(cache: &mut HttpCache, request: &Request) -> Option<CachedResponse> {
+    if let Some(disk_read) = cache.prepare_disk_read(request) {
+        let read = disk_read.perform();
+        cache.finish_disk_read(request, read);
+    }
+    cache.construct_response(request, &mut None)
+}
+
+fn cached_body(cache: &mut HttpCache, request: &Request) -> Option<Vec<u8>> {
+    let cached_response = construct_response(cache, request)?;
+    let body = cached_response.response.body.lock().unwrap();
+    match *body {
+        ResponseBody::Done(ref bytes) => Some(bytes.clone()),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_disk_cache_survives_restart() {
+    let dir = create_cache_dir("restart");
+    let url = ServoUrl::parse("https://servo.org/style.css").unwrap();
+    let request = create_request(&url);
+    {
+        let mut cache = HttpCache::new_with_disk_cache(dir.clone(), 1024);
+        store(&mut cache, &request, &create_fresh_response(&url, b"body { color: red }"));
+        cache.flush();
+        assert_eq!(cache.disk_usage(), 19);
+    }
+    let mut cache = HttpCache::new_with_disk_cache(dir.clone(), 1024);
+    assert_eq!(cache.disk_usage(), 19);
+    let cached_response = construct_response(&mut cache, &request).unwrap();
+    assert!(!cached_response.needs_validation);
+    assert_eq!(cached_body(&mut cache, &request), Some(b"body { color: red }".to_vec()));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_disk_cache_evicts_least_recently_used() {
+    let dir = create_cache_dir("eviction");
+    let mut cache = HttpCache::new_with_disk_cache(dir.clone(), 15);
+    let urls: Vec<ServoUrl> = ["a", "b", "c"].iter().map(|name| {
+        ServoUrl::parse(&format!("https://servo.org/{}", name)).unwrap()
+    }).collect();
+    store(&mut cache, &create_request(&urls[0]), &create_fresh_response(&urls[0], b"aaaaaa"));
+    store(&mut cache, &create_request(&urls[1]), &create_fresh_response(&urls[1], b"bbbbbb"));
+    // Using the first resource makes the second one the least recently used.
+    assert!(cached_body(&mut cache, &create_request(&urls[0])).is_some());
+    store(&mut cache, &create_request(&urls[2]), &create_fresh_response(&urls[2], b"cccccc"));
+    assert_eq!(cache.disk_usage(), 12);
+    assert!(cached_body(&mut cache, &create_request(&urls[0])).is_some());
+    assert!(cached_body(&mut cache, &create_request(&urls[1])).is_none());
+    assert!(cached_body(&mut cache, &create_request(&urls[2])).is_some());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_disk_cache_skips_entries_evicted_while_read() {
+    let dir = create_cache_dir("evicted-while-read");
+    let urls: Vec<ServoUrl> = ["a", "b", "c"].iter().map(|name| {
+        ServoUrl::parse(&format!("https://servo.org/{}", name)).unwrap()
+    }).collect();
+    {
+        let mut cache = HttpCache::new_with_disk_cache(dir.clone(), 15);
+        store(&mut cache, &create_request(&urls[0]), &create_fresh_response(&urls[0], b"aaaaaa"));
+    }
+    let mut cache = HttpCache::new_with_disk_cache(dir.clone(), 15);
+    let request = create_request(&urls[0]);
+    let disk_read = cache.prepare_disk_read(&request).unwrap();
+    // Evict the first resource before its read completes.
+    store(&mut cache, &create_request(&urls[1]), &create_fresh_response(&urls[1], b"bbbbbb"));
+    store(&mut cache, &create_request(&urls[2]), &create_fresh_response(&urls[2], b"cccccc"));
+    let read = disk_read.perform();
+    cache.finish_disk_read(&request, read);
+    assert!(cache.construct_response(&request, &mut None).is_none());
+    assert_eq!(cache.disk_usage(), 12);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_disk_cache_writes_bodies_outside_of_the_cache() {
+    let dir = create_cache_dir("write");
+    let url = ServoUrl::parse("https://servo.org/style.css").unwrap();
+    let request = create_request(&url);
+    let mut cache = HttpCache::new_with_disk_cache(dir.clone(), 1024);
+    cache.store(&request, &create_fresh_response(&url, b"body { color: red }"));
+    let writes = cache.prepare_disk_writes(&request);
+    assert_eq!(writes.len(), 1);
+    // A concurrent fetch doesn't write the resource again.
+    assert!(cache.prepare_disk_writes(&request).is_empty());
+    assert_eq!(cache.disk_usage(), 0);
+    let written = writes.into_iter().map(DiskCacheWrite::perform).collect();
+    cache.finish_disk_writes(&request, written);
+    assert_eq!(cache.disk_usage(), 19);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_disk_cache_batches_index_writes() {
+    let dir = create_cache_dir("batching");
+    let url = ServoUrl::parse("https://servo.org/script.js").unwrap();
+    let mut cache = HttpCache::new_with_disk_cache(dir.clone(), 1024);
+    store(&mut cache, &create_request(&url), &create_fresh_response(&url, b"alert(1)"));
+    assert!(!dir.join("index.json").exists());
+    cache.flush();
+    assert!(dir.join("index.json").exists());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_disk_cache_skips_resources_over_budget() {
+    let dir = create_cache_dir("over-budget");
+    let url = ServoUrl::parse("https://servo.org/large.png").unwrap();
+    let mut cache = HttpCache::new_with_disk_cache(dir.clone(), 4);
+    store(&mut cache, &create_request(&url), &create_fresh_response(&url, b"too large"));
+    assert_eq!(cache.disk_usage(), 0);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_disk_cache_persists_invalidation() {
+    let dir = create_cache_dir("invalidation");
+    let url = ServoUrl::parse("https://servo.org/index.html").unwrap();
+    let request = create_request(&url);
+    let response = create_fresh_response(&url, b"<p>servo</p>");
+    {
+        let mut cache = HttpCache::new_with_disk_cache(dir.clone(), 1024);
+        store(&mut cache, &request, &response);
+        cache.invalidate(&request, &response);
+    }
+    let mut cache = HttpCache::new_with_disk_cache(dir.clone(), 1024);
+    let cached_response = construct_response(&mut cache, &request).unwrap();
+    assert!(cached_response.needs_validation);
+    let _ = fs::remove_dir_all(&dir);
+}
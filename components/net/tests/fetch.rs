@@ -6,6 +6,8 @@ use {DEFAULT_USER_AGENT, new_fetch_context, create_embedder_proxy, fetch, make_s
 use devtools_traits::DevtoolsControlMsg;
 use devtools_traits::HttpRequest as DevtoolsHttpRequest;
 use devtools_traits::HttpResponse as DevtoolsHttpResponse;
+use embedder_traits::request_interception::{InterceptionAction, InterceptionRule};
+use embedder_traits::request_interception::{RequestInterception, SyntheticResponse};
 use fetch_with_context;
 use fetch_with_cors_cache;
 use http_loader::{expect_devtools_http_request, expect_devtools_http_response};
@@ -28,10 +30,12 @@ use net::fetch::cors_cache::CorsCache;
 use net::fetch::methods::{CancellationListener, FetchContext};
 use net::filemanager_thread::FileManager;
 use net::hsts::HstsEntry;
+use net::request_interceptor::RequestInterceptor;
 use net::test::HttpState;
 use net_traits::IncludeSubdomains;
 use net_traits::NetworkError;
 use net_traits::ReferrerPolicy;
+use net_traits::csp::{CspList, PolicyDisposition, PolicySource};
 use net_traits::request::{Destination, Origin, RedirectMode, Referrer, Request, RequestMode};
 use net_traits::response::{CacheState, Response, ResponseBody, ResponseType};
 use servo_url::{ImmutableOrigin, ServoUrl};
@@ -540,6 +544,7 @@ fn test_fetch_with_hsts() {
         user_agent: DEFAULT_USER_AGENT.into(),
         devtools_chan: None,
         filemanager: FileManager::new(create_embedder_proxy()),
+        request_interceptor: Arc::new(RequestInterceptor::new(create_embedder_proxy())),
         cancellation_listener: Arc::new(Mutex::new(CancellationListener::new(None))),
     };
 
@@ -609,6 +614,72 @@ fn test_fetch_with_sri_sucess() {
     assert_eq!(response_is_done(&response), true);
 }
 
+#[test]
+fn test_fetch_blocked_by_csp() {
+    static MESSAGE: &'static [u8] = b"";
+    let handler = move |_: HyperRequest, response: HyperResponse| {
+        response.send(MESSAGE).unwrap();
+    };
+    let (mut server, url) = make_server(handler);
+
+    let origin = Origin::Origin(url.origin());
+    let mut request = Request::new(url.clone(), Some(origin), None);
+    request.referrer = Referrer::NoReferrer;
+    request.csp_list = Some(CspList::parse("connect-src 'none'",
+                                           PolicySource::Header,
+                                           PolicyDisposition::Enforce,
+                                           &url));
+
+    let response = fetch(&mut request, None);
+
+    let _ = server.close();
+    assert!(response.is_network_error());
+}
+
+#[test]
+fn test_fetch_allowed_by_report_only_csp() {
+    static MESSAGE: &'static [u8] = b"";
+    let handler = move |_: HyperRequest, response: HyperResponse| {
+        response.send(MESSAGE).unwrap();
+    };
+    let (mut server, url) = make_server(handler);
+
+    let origin = Origin::Origin(url.origin());
+    let mut request = Request::new(url.clone(), Some(origin), None);
+    request.referrer = Referrer::NoReferrer;
+    request.csp_list = Some(CspList::parse("connect-src 'none'",
+                                           PolicySource::Header,
+                                           PolicyDisposition::Report,
+                                           &url));
+
+    let response = fetch(&mut request, None);
+
+    let _ = server.close();
+    assert!(!response.is_network_error());
+}
+
+#[test]
+fn test_fetch_response_csp_list_is_parsed_from_headers() {
+    static MESSAGE: &'static [u8] = b"";
+    let handler = move |_: HyperRequest, mut response: HyperResponse| {
+        response.headers_mut().set_raw("Content-Security-Policy", vec![b"script-src 'self'".to_vec()]);
+        response.headers_mut().set_raw("Content-Security-Policy-Report-Only", vec![b"img-src 'none'".to_vec()]);
+        response.send(MESSAGE).unwrap();
+    };
+    let (mut server, url) = make_server(handler);
+
+    let origin = Origin::Origin(url.origin());
+    let mut request = Request::new(url, Some(origin), None);
+    request.referrer = Referrer::NoReferrer;
+    let response = fetch(&mut request, None);
+
+    let _ = server.close();
+    let policies = &response.actual_response().csp_list.0;
+    assert_eq!(policies.len(), 2);
+    assert_eq!(policies[0].disposition, PolicyDisposition::Enforce);
+    assert_eq!(policies[1].disposition, PolicyDisposition::Report);
+}
+
 /// `fetch` should return a network error if there is a header `X-Content-Type-Options: nosniff`
 #[test]
 fn test_fetch_blocked_nosniff() {
@@ -975,3 +1046,90 @@ fn test_fetch_with_devtools() {
     assert_eq!(devhttprequest, httprequest);
     assert_eq!(devhttpresponse, httpresponse);
 }
+
+fn intercepting_context(url_prefix: &str, action: InterceptionAction) -> FetchContext {
+    let context = new_fetch_context(None, None);
+    context.request_interceptor.set_interception(RequestInterception {
+        rules: vec![InterceptionRule { url_prefix: url_prefix.to_owned(), action: action }],
+        ask_embedder: false,
+    });
+    context
+}
+
+#[test]
+fn test_fetch_with_synthetic_response() {
+    let url = ServoUrl::parse("http://fixtures.invalid/data.json").unwrap();
+    let context = intercepting_context("http://fixtures.invalid/", InterceptionAction::Respond(SyntheticResponse {
+        status: 201,
+        status_text: "Created".to_owned(),
+        headers: vec![("content-type".to_owned(), b"application/json".to_vec())],
+        body: b"{}".to_vec(),
+    }));
+    let origin = Origin::Origin(url.origin());
+    let mut request = Request::new(url, Some(origin), None);
+    request.referrer = Referrer::NoReferrer;
+    let fetch_response = fetch_with_context(&mut request, &context);
+
+    assert!(!fetch_response.is_network_error());
+    assert_eq!(fetch_response.raw_status, Some((201, b"Created".to_vec())));
+    assert_eq!(fetch_response.headers.get_raw("content-type").unwrap()[0], b"application/json".to_vec());
+    match *fetch_response.body.lock().unwrap() {
+        ResponseBody::Done(ref body) => assert_eq!(&**body, b"{}"),
+        _ => panic!(),
+    };
+}
+
+#[test]
+fn test_fetch_blocked_by_interception() {
+    static MESSAGE: &'static [u8] = b"should not be fetched";
+    let handler = move |_: HyperRequest, response: HyperResponse| {
+        response.send(MESSAGE).unwrap();
+    };
+    let (mut server, url) = make_server(handler);
+
+    let context = intercepting_context(url.as_str(), InterceptionAction::Block);
+    let origin = Origin::Origin(url.origin());
+    let mut request = Request::new(url, Some(origin), None);
+    request.referrer = Referrer::NoReferrer;
+    let fetch_response = fetch_with_context(&mut request, &context);
+    let _ = server.close();
+
+    assert!(fetch_response.is_network_error());
+}
+
+#[test]
+fn test_fetch_redirected_and_modified_by_interception() {
+    let handler = move |request: HyperRequest, response: HyperResponse| {
+        let value = request.headers.get_raw("x-fixture").map_or(vec![], |values| values[0].clone());
+        response.send(&value).unwrap();
+    };
+    let (mut server, url) = make_server(handler);
+
+    let original = ServoUrl::parse("http://fixtures.invalid/").unwrap();
+    let context = new_fetch_context(None, None);
+    context.request_interceptor.set_interception(RequestInterception {
+        rules: vec![
+            InterceptionRule {
+                url_prefix: original.as_str().to_owned(),
+                action: InterceptionAction::Redirect(url.clone()),
+            },
+            InterceptionRule {
+                url_prefix: url.as_str().to_owned(),
+                action: InterceptionAction::SetHeaders(vec![("x-fixture".to_owned(), b"local".to_vec())]),
+            },
+        ],
+        ask_embedder: false,
+    });
+    let mut request = Request::new(original, None, None);
+    request.referrer = Referrer::NoReferrer;
+    request.mode = RequestMode::NoCors;
+    let fetch_response = fetch_with_context(&mut request, &context);
+    let _ = server.close();
+
+    assert!(!fetch_response.is_network_error());
+    assert_eq!(fetch_response.actual_response().url(), Some(&url));
+    match *fetch_response.body.lock().unwrap() {
+        ResponseBody::Done(ref body) => assert_eq!(&**body, b"local"),
+        _ => panic!(),
+    };
+}
@@ -0,0 +1,317 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
+use net::indexeddb_thread::IndexedDBThreadFactory;
+use net_traits::indexeddb_thread::{IndexedDBConnectionEvent, IndexedDBConnectionId, IndexedDBCursorDirection};
+use net_traits::indexeddb_thread::{IndexedDBDeleteResponse, IndexedDBError, IndexedDBIndexMetadata, IndexedDBKey};
+use net_traits::indexeddb_thread::{IndexedDBKeyPath, IndexedDBKeyRange, IndexedDBObjectStoreMetadata};
+use net_traits::indexeddb_thread::{IndexedDBOpenResponse, IndexedDBOperation, IndexedDBOperationResult};
+use net_traits::indexeddb_thread::{IndexedDBResult, IndexedDBThreadMsg};
+use net_traits::indexeddb_thread::{IndexedDBTransactionId, IndexedDBTransactionMode};
+use servo_url::{ImmutableOrigin, ServoUrl};
+use std::env;
+use std::fs;
+use time;
+
+fn origin() -> ImmutableOrigin {
+    ServoUrl::parse("https://example.com").unwrap().origin()
+}
+
+fn open(thread: &IpcSender<IndexedDBThreadMsg>, name: &str, version: Option<u64>)
+        -> (IpcReceiver<IndexedDBOpenResponse>, IpcReceiver<IndexedDBConnectionEvent>) {
+    let (sender, receiver) = ipc::channel().unwrap();
+    let (events, events_receiver) = ipc::channel().unwrap();
+    thread.send(IndexedDBThreadMsg::Open {
+        sender: sender,
+        events: events,
+        origin: origin(),
+        name: name.to_owned(),
+        version: version,
+    }).unwrap();
+    (receiver, events_receiver)
+}
+
+fn expect_opened(receiver: &IpcReceiver<IndexedDBOpenResponse>)
+                 -> (IndexedDBConnectionId, Option<(IndexedDBTransactionId, u64)>) {
+    match receiver.recv().unwrap() {
+        IndexedDBOpenResponse::Opened { connection, upgrade, .. } => (connection, upgrade),
+        response => panic!("expected an opened connection, got {:?}", response),
+    }
+}
+
+fn commit(thread: &IpcSender<IndexedDBThreadMsg>, transaction: IndexedDBTransactionId) -> IndexedDBResult<()> {
+    let (sender, receiver) = ipc::channel().unwrap();
+    thread.send(IndexedDBThreadMsg::Commit(sender, transaction)).unwrap();
+    receiver.recv().unwrap()
+}
+
+fn execute(thread: &IpcSender<IndexedDBThreadMsg>, transaction: IndexedDBTransactionId, operation: IndexedDBOperation)
+           -> IndexedDBResult<IndexedDBOperationResult> {
+    let (sender, receiver) = ipc::channel().unwrap();
+    thread.send(IndexedDBThreadMsg::Operation(sender, transaction, "store".to_owned(), operation)).unwrap();
+    receiver.recv().unwrap()
+}
+
+fn transaction(thread: &IpcSender<IndexedDBThreadMsg>, connection: IndexedDBConnectionId) -> IndexedDBTransactionId {
+    let (sender, receiver) = ipc::channel().unwrap();
+    let scope = vec!["store".to_owned()];
+    thread.send(IndexedDBThreadMsg::CreateTransaction(sender, connection, scope, IndexedDBTransactionMode::ReadWrite))
+        .unwrap();
+    receiver.recv().unwrap().unwrap()
+}
+
+fn put(key: f64, value: Vec<u8>) -> IndexedDBOperation {
+    IndexedDBOperation::Put {
+        key: Some(IndexedDBKey::Number(key)),
+        value: value,
+        index_keys: vec![],
+        overwrite: true,
+    }
+}
+
+/// Open version 1 of a database holding an object store named "store".
+fn create_database(thread: &IpcSender<IndexedDBThreadMsg>, name: &str, auto_increment: bool)
+                   -> IndexedDBConnectionId {
+    let (receiver, _events) = open(thread, name, Some(1));
+    let (connection, upgrade) = expect_opened(&receiver);
+    let (upgrade, old_version) = upgrade.unwrap();
+    assert_eq!(old_version, 0);
+    let (sender, result) = ipc::channel().unwrap();
+    thread.send(IndexedDBThreadMsg::CreateObjectStore(sender, upgrade, IndexedDBObjectStoreMetadata {
+        name: "store".to_owned(),
+        key_path: None,
+        auto_increment: auto_increment,
+        indexes: vec![],
+    })).unwrap();
+    assert_eq!(result.recv().unwrap(), Ok(()));
+    assert_eq!(commit(thread, upgrade), Ok(()));
+    match receiver.recv().unwrap() {
+        IndexedDBOpenResponse::UpgradeFinished(Ok(())) => {},
+        response => panic!("expected a finished upgrade, got {:?}", response),
+    }
+    connection
+}
+
+fn usage(thread: &IpcSender<IndexedDBThreadMsg>) -> u64 {
+    let (sender, receiver) = ipc::channel().unwrap();
+    thread.send(IndexedDBThreadMsg::Usage(sender, origin())).unwrap();
+    receiver.recv().unwrap()
+}
+
+#[test]
+fn test_put_exceeding_quota_fails() {
+    let thread: IpcSender<IndexedDBThreadMsg> = IndexedDBThreadFactory::new(None, 100);
+    let connection = create_database(&thread, "quota", false);
+    let transaction = transaction(&thread, connection);
+
+    assert!(execute(&thread, transaction, put(1., vec![0; 50])).is_ok());
+    assert_eq!(usage(&thread), 58);
+    assert_eq!(execute(&thread, transaction, put(2., vec![0; 50])), Err(IndexedDBError::QuotaExceeded));
+
+    // Replacing a record only accounts for the difference in size.
+    assert!(execute(&thread, transaction, put(1., vec![0; 90])).is_ok());
+    assert_eq!(usage(&thread), 98);
+    assert_eq!(commit(&thread, transaction), Ok(()));
+}
+
+#[test]
+fn test_aborted_transaction_is_reverted() {
+    let thread: IpcSender<IndexedDBThreadMsg> = IndexedDBThreadFactory::new(None, 1000);
+    let connection = create_database(&thread, "abort", true);
+    let transaction = transaction(&thread, connection);
+    let generated = IndexedDBOperation::Put { key: None, value: vec![1], index_keys: vec![], overwrite: false };
+    assert_eq!(execute(&thread, transaction, generated),
+               Ok(IndexedDBOperationResult::Key(Some(IndexedDBKey::Number(1.)))));
+
+    let (sender, receiver) = ipc::channel().unwrap();
+    thread.send(IndexedDBThreadMsg::Abort(sender, transaction)).unwrap();
+    receiver.recv().unwrap();
+    assert_eq!(commit(&thread, transaction), Err(IndexedDBError::Abort));
+
+    let transaction = self::transaction(&thread, connection);
+    let count = IndexedDBOperation::Count { index: None, range: IndexedDBKeyRange::unbounded() };
+    assert_eq!(execute(&thread, transaction, count), Ok(IndexedDBOperationResult::Count(0)));
+}
+
+#[test]
+fn test_upgrade_waits_for_open_connections() {
+    let thread: IpcSender<IndexedDBThreadMsg> = IndexedDBThreadFactory::new(None, 1000);
+    let (receiver, events) = open(&thread, "upgrade", Some(1));
+    let (first, upgrade) = expect_opened(&receiver);
+    assert_eq!(commit(&thread, upgrade.unwrap().0), Ok(()));
+    receiver.recv().unwrap();
+
+    let (receiver, _events) = open(&thread, "upgrade", Some(2));
+    match events.recv().unwrap() {
+        IndexedDBConnectionEvent::VersionChange { old_version: 1, new_version: Some(2) } => {},
+        event => panic!("expected a version change, got {:?}", event),
+    }
+    match receiver.recv().unwrap() {
+        IndexedDBOpenResponse::Blocked { old_version: 1, new_version: 2 } => {},
+        response => panic!("expected the upgrade to be blocked, got {:?}", response),
+    }
+
+    thread.send(IndexedDBThreadMsg::Close(first)).unwrap();
+    let (_, upgrade) = expect_opened(&receiver);
+    let (upgrade, old_version) = upgrade.unwrap();
+    assert_eq!(old_version, 1);
+
+    // Aborting the upgrade leaves the database at its previous version.
+    let (sender, abort) = ipc::channel().unwrap();
+    thread.send(IndexedDBThreadMsg::Abort(sender, upgrade)).unwrap();
+    abort.recv().unwrap();
+    match receiver.recv().unwrap() {
+        IndexedDBOpenResponse::UpgradeFinished(Err(IndexedDBError::Abort)) => {},
+        response => panic!("expected an aborted upgrade, got {:?}", response),
+    }
+    let (sender, databases) = ipc::channel().unwrap();
+    thread.send(IndexedDBThreadMsg::Databases(sender, origin())).unwrap();
+    assert_eq!(databases.recv().unwrap(), vec![("upgrade".to_owned(), 1)]);
+}
+
+#[test]
+fn test_index_exceeding_quota_is_not_populated() {
+    let thread: IpcSender<IndexedDBThreadMsg> = IndexedDBThreadFactory::new(None, 100);
+    let connection = create_database(&thread, "index", false);
+    let transaction = transaction(&thread, connection);
+    assert!(execute(&thread, transaction, put(1., vec![0; 10])).is_ok());
+    assert_eq!(commit(&thread, transaction), Ok(()));
+    thread.send(IndexedDBThreadMsg::Close(connection)).unwrap();
+    assert_eq!(usage(&thread), 18);
+
+    let (receiver, _events) = open(&thread, "index", Some(2));
+    let (upgrade, _) = expect_opened(&receiver).1.unwrap();
+    let (sender, records) = ipc::channel().unwrap();
+    thread.send(IndexedDBThreadMsg::CreateIndex(sender, upgrade, "store".to_owned(), IndexedDBIndexMetadata {
+        name: "index".to_owned(),
+        key_path: IndexedDBKeyPath::String("key".to_owned()),
+        unique: false,
+        multi_entry: true,
+    })).unwrap();
+    assert_eq!(records.recv().unwrap().unwrap().len(), 1);
+    let index_keys = vec![IndexedDBKey::Binary(vec![0; 40]), IndexedDBKey::Binary(vec![1; 40])];
+    let keys = vec![(IndexedDBKey::Number(1.), index_keys)];
+    let (sender, result) = ipc::channel().unwrap();
+    thread.send(IndexedDBThreadMsg::SetIndexKeys(sender, upgrade, "store".to_owned(), "index".to_owned(), keys))
+        .unwrap();
+    assert_eq!(result.recv().unwrap(), Err(IndexedDBError::QuotaExceeded));
+    assert_eq!(usage(&thread), 18);
+
+    // Deleting the index doesn't free more than it used.
+    let (sender, result) = ipc::channel().unwrap();
+    thread.send(IndexedDBThreadMsg::DeleteIndex(sender, upgrade, "store".to_owned(), "index".to_owned())).unwrap();
+    assert_eq!(result.recv().unwrap(), Ok(()));
+    assert_eq!(usage(&thread), 18);
+}
+
+#[test]
+fn test_open_lower_version_fails() {
+    let thread: IpcSender<IndexedDBThreadMsg> = IndexedDBThreadFactory::new(None, 1000);
+    let connection = create_database(&thread, "version", false);
+    thread.send(IndexedDBThreadMsg::Close(connection)).unwrap();
+    let (receiver, _events) = open(&thread, "version", Some(0));
+    match receiver.recv().unwrap() {
+        IndexedDBOpenResponse::Error(IndexedDBError::Version) => {},
+        response => panic!("expected a version error, got {:?}", response),
+    }
+}
+
+#[test]
+fn test_delete_database() {
+    let thread: IpcSender<IndexedDBThreadMsg> = IndexedDBThreadFactory::new(None, 1000);
+    let connection = create_database(&thread, "delete", false);
+    thread.send(IndexedDBThreadMsg::Close(connection)).unwrap();
+    let (sender, receiver) = ipc::channel().unwrap();
+    thread.send(IndexedDBThreadMsg::DeleteDatabase(sender, origin(), "delete".to_owned())).unwrap();
+    match receiver.recv().unwrap() {
+        IndexedDBDeleteResponse::Deleted(1) => {},
+        response => panic!("expected the database to be deleted, got {:?}", response),
+    }
+    assert_eq!(usage(&thread), 0);
+}
+
+#[test]
+fn test_databases_are_stored_separately() {
+    let config_dir = env::temp_dir().join(format!("servo-indexeddb-{}", time::precise_time_ns()));
+    {
+        let thread: IpcSender<IndexedDBThreadMsg> = IndexedDBThreadFactory::new(Some(config_dir.clone()), 1000);
+        let first = create_database(&thread, "first", false);
+        create_database(&thread, "second", false);
+        let transaction = transaction(&thread, first);
+        assert!(execute(&thread, transaction, put(1., vec![1, 2, 3])).is_ok());
+        assert_eq!(commit(&thread, transaction), Ok(()));
+        let (sender, receiver) = ipc::channel().unwrap();
+        thread.send(IndexedDBThreadMsg::Exit(sender)).unwrap();
+        receiver.recv().unwrap();
+    }
+    let origin_dirs: Vec<_> = fs::read_dir(config_dir.join("indexeddb")).unwrap().collect();
+    assert_eq!(origin_dirs.len(), 1);
+    let origin_dir = origin_dirs[0].as_ref().unwrap().path();
+    assert_eq!(fs::read_dir(&origin_dir).unwrap().count(), 2);
+
+    let thread: IpcSender<IndexedDBThreadMsg> = IndexedDBThreadFactory::new(Some(config_dir.clone()), 1000);
+    assert_eq!(usage(&thread), 11);
+    let _ = fs::remove_dir_all(&config_dir);
+}
+
+#[test]
+fn test_key_ordering() {
+    let mut keys = vec![
+        IndexedDBKey::Array(vec![]),
+        IndexedDBKey::Binary(vec![1]),
+        IndexedDBKey::String("b".to_owned()),
+        IndexedDBKey::String("a".to_owned()),
+        IndexedDBKey::Date(0.),
+        IndexedDBKey::Number(2.),
+        IndexedDBKey::Number(-1.),
+    ];
+    keys.sort();
+    assert_eq!(keys, vec![
+        IndexedDBKey::Number(-1.),
+        IndexedDBKey::Number(2.),
+        IndexedDBKey::Date(0.),
+        IndexedDBKey::String("a".to_owned()),
+        IndexedDBKey::String("b".to_owned()),
+        IndexedDBKey::Binary(vec![1]),
+        IndexedDBKey::Array(vec![]),
+    ]);
+}
+
+#[test]
+fn test_cursor_iteration() {
+    let thread: IpcSender<IndexedDBThreadMsg> = IndexedDBThreadFactory::new(None, 1000);
+    let connection = create_database(&thread, "cursor", false);
+    let transaction = transaction(&thread, connection);
+    for key in 1..6 {
+        assert!(execute(&thread, transaction, put(key as f64, vec![key])).is_ok());
+    }
+
+    let iterate = |direction, position: Option<f64>, skip| {
+        let operation = IndexedDBOperation::Iterate {
+            index: None,
+            range: IndexedDBKeyRange {
+                lower: Some(IndexedDBKey::Number(2.)),
+                upper: Some(IndexedDBKey::Number(5.)),
+                lower_open: false,
+                upper_open: true,
+            },
+            direction: direction,
+            position: position.map(|key| (IndexedDBKey::Number(key), IndexedDBKey::Number(key))),
+            target: None,
+            skip: skip,
+            key_only: false,
+        };
+        match execute(&thread, transaction, operation) {
+            Ok(IndexedDBOperationResult::Cursor(record)) => record.map(|record| (record.key, record.value)),
+            result => panic!("expected a cursor record, got {:?}", result),
+        }
+    };
+
+    assert_eq!(iterate(IndexedDBCursorDirection::Next, None, 0), Some((IndexedDBKey::Number(2.), Some(vec![2]))));
+    assert_eq!(iterate(IndexedDBCursorDirection::Next, Some(2.), 1), Some((IndexedDBKey::Number(4.), Some(vec![4]))));
+    assert_eq!(iterate(IndexedDBCursorDirection::Next, Some(4.), 0), None);
+    assert_eq!(iterate(IndexedDBCursorDirection::Prev, None, 0), Some((IndexedDBKey::Number(4.), Some(vec![4]))));
+    assert_eq!(iterate(IndexedDBCursorDirection::Prev, Some(2.), 0), None);
+}
@@ -21,18 +21,23 @@ extern crate servo_url;
 extern crate time;
 extern crate unicase;
 extern crate url;
+extern crate websocket;
 
+mod cache_storage;
 mod cookie;
 mod cookie_http_state;
 mod data_loader;
+mod disk_cache;
 mod fetch;
 mod file_loader;
 mod filemanager_thread;
 mod hsts;
 mod http_loader;
+mod indexeddb;
 mod mime_classifier;
 mod resource_thread;
 mod subresource_integrity;
+mod websocket_loader;
 
 use devtools_traits::DevtoolsControlMsg;
 use embedder_traits::{EmbedderProxy, EventLoopWaker};
@@ -42,8 +47,10 @@ use net::connector::create_ssl_client;
 use net::fetch::cors_cache::CorsCache;
 use net::fetch::methods::{self, CancellationListener, FetchContext};
 use net::filemanager_thread::FileManager;
+use net::request_interceptor::RequestInterceptor;
 use net::test::HttpState;
 use net_traits::FetchTaskTarget;
+use net_traits::csp::Violation;
 use net_traits::request::Request;
 use net_traits::response::Response;
 use servo_url::ServoUrl;
@@ -89,7 +96,8 @@ fn new_fetch_context(dc: Option<Sender<DevtoolsControlMsg>>, fc: Option<Embedder
         state: Arc::new(HttpState::new(ssl_client)),
         user_agent: DEFAULT_USER_AGENT.into(),
         devtools_chan: dc,
-        filemanager: FileManager::new(sender),
+        filemanager: FileManager::new(sender.clone()),
+        request_interceptor: Arc::new(RequestInterceptor::new(sender)),
         cancellation_listener: Arc::new(Mutex::new(CancellationListener::new(None))),
     }
 }
@@ -102,6 +110,7 @@ impl FetchTaskTarget for FetchResponseCollector {
     fn process_response_eof(&mut self, response: &Response) {
         let _ = self.sender.send(response.clone());
     }
+    fn process_csp_violations(&mut self, _: &Request, _: Vec<Violation>) {}
 }
 
 fn fetch(request: &mut Request, dc: Option<Sender<DevtoolsControlMsg>>) -> Response {
@@ -0,0 +1,308 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use create_embedder_proxy;
+use flate2::Compression;
+use flate2::write::{DeflateDecoder, DeflateEncoder};
+use hyper::buffer::BufReader;
+use hyper::header::{Connection, ConnectionOption, Headers, Protocol, ProtocolName, Upgrade};
+use hyper::http::h1::parse_request;
+use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
+use net::resource_thread::new_core_resource_thread;
+use net_traits::{CoreResourceMsg, FetchChannels, MessageData, WebSocketDomAction, WebSocketNetworkEvent};
+use net_traits::csp::{CspList, PolicyDisposition, PolicySource};
+use net_traits::request::{RequestInit, RequestMode};
+use profile_traits::mem::ProfilerChan as MemProfilerChan;
+use profile_traits::time::ProfilerChan;
+use servo_url::ServoUrl;
+use std::io::Write;
+use std::net::TcpListener;
+use std::thread::{self, JoinHandle};
+use websocket::dataframe::{DataFrame, Opcode};
+use websocket::header::{WebSocketAccept, WebSocketKey, WebSocketProtocol};
+use websocket::ws::dataframe::DataFrame as DataFrameTrait;
+
+const DEFLATE_TRAILER: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// Accepts a single WebSocket connection and echoes every data message back,
+/// replying to the handshake with the given extensions and subprotocol.
+/// Returns whether each received data message was compressed.
+fn echo_server(extensions: Option<&'static str>,
+               protocol: Option<&'static str>)
+               -> (ServoUrl, JoinHandle<Vec<bool>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = ServoUrl::parse(&format!("ws://localhost:{}/", listener.local_addr().unwrap().port())).unwrap();
+
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let request = parse_request(&mut reader).unwrap();
+        assert_eq!(request.headers.get_raw("Sec-WebSocket-Extensions").unwrap(),
+                   &[b"permessage-deflate; client_max_window_bits".to_vec()]);
+
+        let mut headers = Headers::new();
+        headers.set(Upgrade(vec![Protocol::new(ProtocolName::WebSocket, None)]));
+        headers.set(Connection(vec![ConnectionOption::ConnectionHeader("upgrade".into())]));
+        headers.set(WebSocketAccept::new(request.headers.get::<WebSocketKey>().unwrap()));
+        if let Some(extensions) = extensions {
+            headers.set_raw("Sec-WebSocket-Extensions", vec![extensions.as_bytes().to_vec()]);
+        }
+        if let Some(protocol) = protocol {
+            headers.set(WebSocketProtocol(vec![protocol.to_owned()]));
+        }
+        write!(stream, "HTTP/1.1 101 Switching Protocols\r\n{}\r\n", headers).unwrap();
+
+        let deflate = extensions.map_or(false, |extensions| extensions.starts_with("permessage-deflate"));
+        let server_context_takeover = !extensions.map_or(false, |e| e.contains("server_no_context_takeover"));
+        let mut encoder = DeflateEncoder::new(vec![], Compression::default());
+        let mut decoder = DeflateDecoder::new(vec![]);
+        let mut compressed = vec![];
+        loop {
+            let mut frame = match DataFrame::read_dataframe(&mut reader, true) {
+                Ok(frame) => frame,
+                Err(_) => return compressed,
+            };
+            match frame.opcode {
+                Opcode::Text | Opcode::Binary => {},
+                Opcode::Close => {
+                    frame.write_to(&mut stream, false).unwrap();
+                    return compressed;
+                },
+                _ => continue,
+            }
+
+            compressed.push(frame.reserved[0]);
+            if frame.reserved[0] {
+                decoder.write_all(&frame.data).unwrap();
+                decoder.write_all(&DEFLATE_TRAILER).unwrap();
+                decoder.flush().unwrap();
+                frame.data = decoder.get_mut().drain(..).collect();
+                frame.reserved[0] = false;
+            }
+            if deflate {
+                if !server_context_takeover {
+                    encoder = DeflateEncoder::new(vec![], Compression::default());
+                }
+                encoder.write_all(&frame.data).unwrap();
+                encoder.flush().unwrap();
+                let mut data: Vec<u8> = encoder.get_mut().drain(..).collect();
+                assert!(data.ends_with(&DEFLATE_TRAILER));
+                let len = data.len() - DEFLATE_TRAILER.len();
+                data.truncate(len);
+                frame.data = data;
+                frame.reserved[0] = true;
+            }
+            frame.write_to(&mut stream, false).unwrap();
+        }
+    });
+
+    (url, server)
+}
+
+fn connect(url: ServoUrl,
+           protocols: Vec<String>)
+           -> (IpcSender<WebSocketDomAction>, IpcReceiver<WebSocketNetworkEvent>) {
+    connect_with_csp(url, protocols, None)
+}
+
+fn connect_with_csp(url: ServoUrl,
+                    protocols: Vec<String>,
+                    csp_list: Option<CspList>)
+                    -> (IpcSender<WebSocketDomAction>, IpcReceiver<WebSocketNetworkEvent>) {
+    let (tx, _rx) = ipc::channel().unwrap();
+    let (mtx, _mrx) = ipc::channel().unwrap();
+    let (resource_thread, _private_resource_thread) = new_core_resource_thread(
+        "".into(), None, ProfilerChan(tx), MemProfilerChan(mtx), create_embedder_proxy(), None);
+
+    let (action_sender, action_receiver) = ipc::channel().unwrap();
+    let (event_sender, event_receiver) = ipc::channel().unwrap();
+    let request = RequestInit {
+        url: url,
+        mode: RequestMode::WebSocket { protocols },
+        csp_list: csp_list,
+        .. RequestInit::default()
+    };
+    let channels = FetchChannels::WebSocket {
+        event_sender: event_sender,
+        action_receiver: action_receiver,
+    };
+    resource_thread.send(CoreResourceMsg::Fetch(request, channels)).unwrap();
+    (action_sender, event_receiver)
+}
+
+fn expect_established(events: &IpcReceiver<WebSocketNetworkEvent>) -> Option<String> {
+    match events.recv().unwrap() {
+        WebSocketNetworkEvent::ConnectionEstablished { protocol_in_use } => protocol_in_use,
+        _ => panic!("Expected the connection to be established."),
+    }
+}
+
+fn expect_csp_violation(events: &IpcReceiver<WebSocketNetworkEvent>) {
+    match events.recv().unwrap() {
+        WebSocketNetworkEvent::ReportCspViolations(ref violations) => {
+            assert_eq!(violations.len(), 1);
+            assert_eq!(violations[0].effective_directive, "connect-src");
+        },
+        _ => panic!("Expected a CSP violation."),
+    }
+}
+
+fn expect_fail(events: &IpcReceiver<WebSocketNetworkEvent>) {
+    match events.recv().unwrap() {
+        WebSocketNetworkEvent::Fail => {},
+        _ => panic!("Expected the connection to fail."),
+    }
+}
+
+fn echo_text(actions: &IpcSender<WebSocketDomAction>, events: &IpcReceiver<WebSocketNetworkEvent>, text: &str) {
+    actions.send(WebSocketDomAction::SendMessage(MessageData::Text(text.to_owned()))).unwrap();
+    match events.recv().unwrap() {
+        WebSocketNetworkEvent::MessageReceived(MessageData::Text(ref echoed)) => assert_eq!(echoed, text),
+        _ => panic!("Expected a text message."),
+    }
+}
+
+fn echo_binary(actions: &IpcSender<WebSocketDomAction>, events: &IpcReceiver<WebSocketNetworkEvent>, data: &[u8]) {
+    actions.send(WebSocketDomAction::SendMessage(MessageData::Binary(data.to_vec()))).unwrap();
+    match events.recv().unwrap() {
+        WebSocketNetworkEvent::MessageReceived(MessageData::Binary(ref echoed)) => assert_eq!(&**echoed, data),
+        _ => panic!("Expected a binary message."),
+    }
+}
+
+fn close(actions: &IpcSender<WebSocketDomAction>, events: &IpcReceiver<WebSocketNetworkEvent>) {
+    actions.send(WebSocketDomAction::Close(Some(1000), None)).unwrap();
+    match events.recv().unwrap() {
+        WebSocketNetworkEvent::Close(code, _) => assert_eq!(code, Some(1000)),
+        _ => panic!("Expected the connection to be closed."),
+    }
+}
+
+#[test]
+fn test_websocket_without_extensions() {
+    let (url, server) = echo_server(None, None);
+    let (actions, events) = connect(url, vec![]);
+    assert_eq!(expect_established(&events), None);
+
+    echo_text(&actions, &events, "Hello, world!");
+    echo_binary(&actions, &events, &[1, 2, 3, 4]);
+    close(&actions, &events);
+
+    assert_eq!(server.join().unwrap(), vec![false, false]);
+}
+
+#[test]
+fn test_websocket_permessage_deflate() {
+    let (url, server) = echo_server(Some("permessage-deflate"), None);
+    let (actions, events) = connect(url, vec![]);
+    expect_established(&events);
+
+    // Sending the same message twice relies on both sides keeping their
+    // compression context between messages.
+    let text = "Hello, world! Hello, world! Hello, world!";
+    echo_text(&actions, &events, text);
+    echo_text(&actions, &events, text);
+    echo_text(&actions, &events, "");
+    echo_binary(&actions, &events, &[0; 4096]);
+    close(&actions, &events);
+
+    assert_eq!(server.join().unwrap(), vec![true, true, true, true]);
+}
+
+#[test]
+fn test_websocket_permessage_deflate_parameters() {
+    let extensions = "permessage-deflate; server_no_context_takeover; client_no_context_takeover; \
+                      server_max_window_bits=10; client_max_window_bits=\"9\"";
+    let (url, server) = echo_server(Some(extensions), None);
+    let (actions, events) = connect(url, vec![]);
+    expect_established(&events);
+
+    let text = "Hello, world! Hello, world! Hello, world!";
+    echo_text(&actions, &events, text);
+    echo_text(&actions, &events, text);
+    // Messages larger than the client's window are sent uncompressed.
+    echo_binary(&actions, &events, &[0; 1024]);
+    close(&actions, &events);
+
+    assert_eq!(server.join().unwrap(), vec![true, true, false]);
+}
+
+#[test]
+fn test_websocket_permessage_deflate_rejects_too_big_message() {
+    let (url, _server) = echo_server(Some("permessage-deflate"), None);
+    let (actions, events) = connect(url, vec![]);
+    expect_established(&events);
+
+    // A message one byte over the 32MB limit compresses down to a few
+    // kilobytes, but must not be inflated.
+    let data = vec![0; 32 * 1024 * 1024 + 1];
+    actions.send(WebSocketDomAction::SendMessage(MessageData::Binary(data))).unwrap();
+    expect_fail(&events);
+}
+
+#[test]
+fn test_websocket_rejects_invalid_extensions() {
+    let invalid = [
+        "x-webkit-deflate-frame",
+        "permessage-deflate, permessage-deflate",
+        "permessage-deflate; client_max_window_bits=16",
+        "permessage-deflate; server_max_window_bits",
+        "permessage-deflate; server_max_window_bits=010",
+        "permessage-deflate; server_no_context_takeover; server_no_context_takeover",
+        "permessage-deflate; client_no_context_takeover=1",
+        "permessage-deflate; unknown_parameter",
+    ];
+    for extensions in invalid.iter() {
+        let (url, _server) = echo_server(Some(*extensions), None);
+        let (_actions, events) = connect(url, vec![]);
+        expect_fail(&events);
+    }
+}
+
+#[test]
+fn test_websocket_subprotocol() {
+    let (url, server) = echo_server(None, Some("bar"));
+    let (actions, events) = connect(url, vec!["foo".to_owned(), "bar".to_owned()]);
+    assert_eq!(expect_established(&events), Some("bar".to_owned()));
+    close(&actions, &events);
+    server.join().unwrap();
+}
+
+#[test]
+fn test_websocket_rejects_unrequested_subprotocol() {
+    let (url, _server) = echo_server(None, Some("baz"));
+    let (_actions, events) = connect(url, vec!["foo".to_owned(), "bar".to_owned()]);
+    expect_fail(&events);
+
+    let (url, _server) = echo_server(None, Some("foo"));
+    let (_actions, events) = connect(url, vec![]);
+    expect_fail(&events);
+}
+
+#[test]
+fn test_websocket_requires_subprotocol() {
+    let (url, _server) = echo_server(None, None);
+    let (_actions, events) = connect(url, vec!["foo".to_owned()]);
+    expect_fail(&events);
+}
+
+#[test]
+fn test_websocket_blocked_by_csp() {
+    let (url, _server) = echo_server(None, None);
+    let csp_list = CspList::parse("connect-src 'none'", PolicySource::Header, PolicyDisposition::Enforce, &url);
+    let (_actions, events) = connect_with_csp(url, vec![], Some(csp_list));
+    expect_csp_violation(&events);
+    expect_fail(&events);
+}
+
+#[test]
+fn test_websocket_allowed_by_report_only_csp() {
+    let (url, server) = echo_server(None, None);
+    let csp_list = CspList::parse("connect-src 'none'", PolicySource::Header, PolicyDisposition::Report, &url);
+    let (actions, events) = connect_with_csp(url, vec![], Some(csp_list));
+    expect_csp_violation(&events);
+    expect_established(&events);
+    close(&actions, &events);
+    server.join().unwrap();
+}
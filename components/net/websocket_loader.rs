@@ -4,6 +4,7 @@
 
 use cookie::Cookie;
 use fetch::methods::{should_be_blocked_due_to_bad_port, should_be_blocked_due_to_nosniff};
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
 use hosts::replace_host;
 use http_loader::{HttpState, is_redirect_status, set_default_accept};
 use http_loader::{set_default_accept_language, set_request_cookies};
@@ -18,19 +19,25 @@ use hyper::version::HttpVersion;
 use ipc_channel::ipc::{IpcReceiver, IpcSender};
 use net_traits::{CookieSource, MessageData, NetworkError};
 use net_traits::{WebSocketDomAction, WebSocketNetworkEvent};
-use net_traits::request::{Destination, RequestInit, RequestMode};
+use net_traits::csp::{CheckResult, CspList};
+use net_traits::request::{Destination, Request, RequestInit, RequestMode};
 use servo_url::ServoUrl;
 use std::io::{self, Write};
 use std::net::TcpStream;
+use std::str;
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 use url::Position;
 use websocket::Message;
+use websocket::dataframe::{DataFrame as WsDataFrame, Opcode};
 use websocket::header::{Origin, WebSocketAccept, WebSocketKey, WebSocketProtocol, WebSocketVersion};
 use websocket::message::OwnedMessage;
 use websocket::receiver::{Reader as WsReader, Receiver as WsReceiver};
+use websocket::result::{WebSocketError, WebSocketResult};
 use websocket::sender::{Sender as WsSender, Writer as WsWriter};
+use websocket::ws::Message as MessageTrait;
+use websocket::ws::Receiver as ReceiverTrait;
 use websocket::ws::dataframe::DataFrame;
 
 pub fn init(
@@ -40,11 +47,11 @@ pub fn init(
     http_state: Arc<HttpState>
 ) {
     thread::Builder::new().name(format!("WebSocket connection to {}", req_init.url)).spawn(move || {
-        let channel = establish_a_websocket_connection(req_init, &http_state);
-        let (ws_sender, mut receiver) = match channel {
-            Ok((protocol_in_use, sender, receiver)) => {
+        let channel = establish_a_websocket_connection(req_init, &resource_event_sender, &http_state);
+        let (ws_sender, mut receiver, deflate_params) = match channel {
+            Ok((protocol_in_use, sender, receiver, deflate_params)) => {
                 let _ = resource_event_sender.send(WebSocketNetworkEvent::ConnectionEstablished { protocol_in_use });
-                (sender, receiver)
+                (sender, receiver, deflate_params)
             },
             Err(e) => {
                 debug!("Failed to establish a WebSocket connection: {:?}", e);
@@ -54,14 +61,20 @@ pub fn init(
 
         };
 
+        if let Some(ref params) = deflate_params {
+            debug!("Using permessage-deflate: {:?}", params);
+        }
+        let mut deflater = deflate_params.as_ref().map(Deflater::new);
+        let mut inflater = deflate_params.as_ref().map(Inflater::new);
+
         let initiated_close = Arc::new(AtomicBool::new(false));
         let ws_sender = Arc::new(Mutex::new(ws_sender));
 
         let initiated_close_incoming = initiated_close.clone();
         let ws_sender_incoming = ws_sender.clone();
         thread::spawn(move || {
-            for message in receiver.incoming_messages() {
-                let message = match message {
+            loop {
+                let message = match recv_message(&mut receiver, inflater.as_mut()) {
                     Ok(m) => m,
                     Err(e) => {
                         debug!("Error receiving incoming WebSocket message: {:?}", e);
@@ -99,10 +112,10 @@ pub fn init(
         while let Ok(dom_action) = dom_action_receiver.recv() {
             match dom_action {
                 WebSocketDomAction::SendMessage(MessageData::Text(data)) => {
-                    ws_sender.lock().unwrap().send_message(&Message::text(data)).unwrap();
+                    send_data_message(&ws_sender, deflater.as_mut(), Opcode::Text, data.into_bytes());
                 },
                 WebSocketDomAction::SendMessage(MessageData::Binary(data)) => {
-                    ws_sender.lock().unwrap().send_message(&Message::binary(data)).unwrap();
+                    send_data_message(&ws_sender, deflater.as_mut(), Opcode::Binary, data);
                 },
                 WebSocketDomAction::Close(code, reason) => {
                     if !initiated_close.fetch_or(true, Ordering::SeqCst) {
@@ -118,6 +131,259 @@ pub fn init(
     }).expect("Thread spawning failed");
 }
 
+/// Reads the next message from the connection, inflating it if it was
+/// compressed with permessage-deflate.
+fn recv_message(reader: &mut WsReader<HttpStream>, inflater: Option<&mut Inflater>) -> WebSocketResult<OwnedMessage> {
+    let frames = reader.receiver.recv_message_dataframes(&mut reader.stream)?;
+
+    // https://tools.ietf.org/html/rfc7692#section-6
+    // Only the first frame of a compressed message has its RSV1 bit set.
+    if !frames.first().map_or(false, |frame| frame.reserved[0]) {
+        return OwnedMessage::from_dataframes(frames);
+    }
+    let inflater = inflater.ok_or(WebSocketError::ProtocolError("Unexpected compressed message"))?;
+    let opcode = frames[0].opcode;
+    if opcode != Opcode::Text && opcode != Opcode::Binary {
+        return Err(WebSocketError::ProtocolError("Control frames must not be compressed"));
+    }
+    if frames[0].reserved[1..] != [false, false] || frames[1..].iter().any(|frame| frame.reserved != [false; 3]) {
+        return Err(WebSocketError::ProtocolError("Unsupported reserved bits received"));
+    }
+
+    let mut payload = vec![];
+    for frame in frames {
+        payload.extend(frame.data);
+    }
+    let data = inflater.inflate(payload).map_err(WebSocketError::ProtocolError)?;
+    OwnedMessage::from_dataframes(vec![WsDataFrame::new(true, opcode, data)])
+}
+
+/// Sends a text or binary message, compressing it if permessage-deflate is in use.
+fn send_data_message(writer: &Mutex<WsWriter<HttpStream>>,
+                     deflater: Option<&mut Deflater>,
+                     opcode: Opcode,
+                     data: Vec<u8>) {
+    let mut frame = WsDataFrame::new(true, opcode, data);
+    if let Some(deflated) = deflater.and_then(|deflater| deflater.deflate(&frame.data)) {
+        frame.data = deflated;
+        frame.reserved[0] = true;
+    }
+    writer.lock().unwrap().send_dataframe(&frame).unwrap();
+}
+
+/// The permessage-deflate extension offered in the opening handshake.
+/// <https://tools.ietf.org/html/rfc7692#section-7.1>
+const PERMESSAGE_DEFLATE_OFFER: &'static str = "permessage-deflate; client_max_window_bits";
+
+/// The empty stored block ending every flushed DEFLATE stream, which
+/// permessage-deflate strips from compressed payloads.
+const DEFLATE_TRAILER: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// The largest size a compressed message may inflate to, so that a small
+/// message can't make us allocate an unbounded amount of memory.
+const MAX_INFLATED_MESSAGE_SIZE: usize = 32 * 1024 * 1024;
+
+/// The parameters of a negotiated permessage-deflate extension.
+#[derive(Debug)]
+struct DeflateParams {
+    server_no_context_takeover: bool,
+    client_no_context_takeover: bool,
+    server_max_window_bits: u8,
+    client_max_window_bits: u8,
+}
+
+impl Default for DeflateParams {
+    fn default() -> DeflateParams {
+        DeflateParams {
+            server_no_context_takeover: false,
+            client_no_context_takeover: false,
+            server_max_window_bits: 15,
+            client_max_window_bits: 15,
+        }
+    }
+}
+
+/// Validates the server's response to our permessage-deflate offer, returning
+/// `None` if the server didn't accept any extension.
+/// <https://tools.ietf.org/html/rfc7692#section-7.1>
+fn parse_extensions_response(values: &[Vec<u8>]) -> Result<Option<DeflateParams>, NetworkError> {
+    let fail = |reason: &str| {
+        Err(NetworkError::Internal(format!("Response's Sec-WebSocket-Extensions header {}.", reason)))
+    };
+
+    let mut result = None;
+    for value in values {
+        let value = match str::from_utf8(value) {
+            Ok(value) => value,
+            Err(_) => return fail("is not valid UTF-8"),
+        };
+        for extension in value.split(',').map(str::trim).filter(|extension| !extension.is_empty()) {
+            let mut parts = extension.split(';').map(str::trim);
+            if !parts.next().map_or(false, |name| name.eq_ignore_ascii_case("permessage-deflate")) {
+                return fail("included unsupported extensions");
+            }
+            if result.is_some() {
+                return fail("included permessage-deflate more than once");
+            }
+
+            let mut params = DeflateParams::default();
+            let mut seen = vec![];
+            for param in parts {
+                let mut param = param.splitn(2, '=');
+                let name = param.next().unwrap().trim();
+                let value = param.next().map(|value| value.trim().trim_matches('"'));
+                if seen.contains(&name) {
+                    return fail("included a permessage-deflate parameter more than once");
+                }
+                seen.push(name);
+                match (name, value) {
+                    ("server_no_context_takeover", None) => params.server_no_context_takeover = true,
+                    ("client_no_context_takeover", None) => params.client_no_context_takeover = true,
+                    ("server_max_window_bits", Some(value)) => {
+                        match parse_window_bits(value) {
+                            Some(bits) => params.server_max_window_bits = bits,
+                            None => return fail("included an invalid server_max_window_bits value"),
+                        }
+                    },
+                    ("client_max_window_bits", Some(value)) => {
+                        match parse_window_bits(value) {
+                            Some(bits) => params.client_max_window_bits = bits,
+                            None => return fail("included an invalid client_max_window_bits value"),
+                        }
+                    },
+                    _ => return fail("included unsupported permessage-deflate parameters"),
+                }
+            }
+            result = Some(params);
+        }
+    }
+    Ok(result)
+}
+
+/// <https://tools.ietf.org/html/rfc7692#section-7.1.2>
+fn parse_window_bits(value: &str) -> Option<u8> {
+    if value.is_empty() || value.len() > 2 || value.starts_with('0') || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok().filter(|bits| *bits >= 8 && *bits <= 15)
+}
+
+/// Compresses outgoing messages.
+/// <https://tools.ietf.org/html/rfc7692#section-7.2.1>
+struct Deflater {
+    compress: Compress,
+    no_context_takeover: bool,
+    max_message_size: Option<usize>,
+}
+
+impl Deflater {
+    fn new(params: &DeflateParams) -> Deflater {
+        // The DEFLATE implementation always uses a 32K window. A smaller one
+        // can still be honoured by not referring to previous messages and
+        // only compressing messages that are no larger than the window.
+        let limited_window = params.client_max_window_bits < 15;
+        Deflater {
+            compress: Compress::new(Compression::default(), false),
+            no_context_takeover: params.client_no_context_takeover || limited_window,
+            max_message_size: if limited_window { Some(1 << params.client_max_window_bits) } else { None },
+        }
+    }
+
+    /// Returns the compressed payload, or `None` if the message should be
+    /// sent uncompressed.
+    fn deflate(&mut self, data: &[u8]) -> Option<Vec<u8>> {
+        if self.max_message_size.map_or(false, |max| data.len() > max) {
+            return None;
+        }
+
+        let start = self.compress.total_in();
+        let mut output = Vec::with_capacity(data.len() / 2 + 64);
+        loop {
+            let (total_in, total_out) = (self.compress.total_in(), self.compress.total_out());
+            let consumed = (total_in - start) as usize;
+            if self.compress.compress_vec(&data[consumed..], &mut output, FlushCompress::Sync).is_err() {
+                self.compress.reset();
+                return None;
+            }
+            if (self.compress.total_in() - start) as usize == data.len() && output.len() < output.capacity() {
+                break;
+            }
+            if output.len() == output.capacity() {
+                let additional = output.capacity();
+                output.reserve(additional);
+            } else if self.compress.total_in() == total_in && self.compress.total_out() == total_out {
+                self.compress.reset();
+                return None;
+            }
+        }
+
+        if output.ends_with(&DEFLATE_TRAILER) {
+            let len = output.len() - DEFLATE_TRAILER.len();
+            output.truncate(len);
+        }
+        if self.no_context_takeover {
+            self.compress.reset();
+        }
+        Some(output)
+    }
+}
+
+/// Decompresses incoming messages.
+/// <https://tools.ietf.org/html/rfc7692#section-7.2.2>
+struct Inflater {
+    decompress: Decompress,
+    no_context_takeover: bool,
+}
+
+impl Inflater {
+    fn new(params: &DeflateParams) -> Inflater {
+        // The DEFLATE implementation always uses a 32K window, which is
+        // enough for any server_max_window_bits.
+        Inflater {
+            decompress: Decompress::new(false),
+            no_context_takeover: params.server_no_context_takeover,
+        }
+    }
+
+    fn inflate(&mut self, mut payload: Vec<u8>) -> Result<Vec<u8>, &'static str> {
+        payload.extend_from_slice(&DEFLATE_TRAILER);
+
+        let start = self.decompress.total_in();
+        let mut output = Vec::with_capacity(payload.len() * 2);
+        loop {
+            let (total_in, total_out) = (self.decompress.total_in(), self.decompress.total_out());
+            let consumed = (total_in - start) as usize;
+            let status = self.decompress.decompress_vec(&payload[consumed..], &mut output, FlushDecompress::Sync)
+                .map_err(|_| "Compressed message could not be inflated")?;
+            if output.len() > MAX_INFLATED_MESSAGE_SIZE {
+                return Err("Inflated message is too big");
+            }
+            if status == Status::StreamEnd {
+                // The server ended the DEFLATE stream, so the next message
+                // starts a new one.
+                self.decompress.reset(false);
+                return Ok(output);
+            }
+            if (self.decompress.total_in() - start) as usize == payload.len() && output.len() < output.capacity() {
+                break;
+            }
+            if output.len() == output.capacity() {
+                // Leave room for one byte past the limit, to tell apart
+                // messages of exactly the maximum size.
+                let additional = output.capacity().min(MAX_INFLATED_MESSAGE_SIZE + 1 - output.len());
+                output.reserve_exact(additional);
+            } else if self.decompress.total_in() == total_in && self.decompress.total_out() == total_out {
+                return Err("Compressed message could not be inflated");
+            }
+        }
+
+        if self.no_context_takeover {
+            self.decompress.reset(false);
+        }
+        Ok(output)
+    }
+}
+
 type Stream = HttpStream;
 
 // https://fetch.spec.whatwg.org/#concept-websocket-connection-obtain
@@ -152,8 +418,9 @@ fn obtain_a_websocket_connection(url: &ServoUrl) -> Result<Stream, NetworkError>
 // https://fetch.spec.whatwg.org/#concept-websocket-establish
 fn establish_a_websocket_connection(
     req_init: RequestInit,
+    event_sender: &IpcSender<WebSocketNetworkEvent>,
     http_state: &HttpState
-) -> Result<(Option<String>, WsWriter<HttpStream>, WsReader<HttpStream>), NetworkError>
+) -> Result<(Option<String>, WsWriter<HttpStream>, WsReader<HttpStream>, Option<DeflateParams>), NetworkError>
 {
     let protocols = match req_init.mode {
         RequestMode::WebSocket { protocols } => protocols.clone(),
@@ -186,10 +453,15 @@ fn establish_a_websocket_connection(
     }
 
     // Steps 9-10.
-    // TODO: handle permessage-deflate extension.
+    headers.set_raw("Sec-WebSocket-Extensions", vec![PERMESSAGE_DEFLATE_OFFER.as_bytes().to_vec()]);
 
     // Step 11 and network error check from step 12.
-    let response = fetch(req_init.url, req_init.origin.ascii_serialization(), headers, http_state)?;
+    let response = fetch(req_init.url,
+                         req_init.origin.ascii_serialization(),
+                         headers,
+                         req_init.csp_list.as_ref(),
+                         event_sender,
+                         http_state)?;
 
     // Step 12, the status code check.
     if response.status != StatusCode::SwitchingProtocols {
@@ -239,23 +511,25 @@ fn establish_a_websocket_connection(
     }
 
     // Step 14.5.
-    // TODO: handle permessage-deflate extension.
-    // We don't support any extension, so we fail at the mere presence of
-    // a Sec-WebSocket-Extensions header.
-    if response.headers.get_raw("Sec-WebSocket-Extensions").is_some() {
-        return Err(NetworkError::Internal(
-            "Response's Sec-WebSocket-Extensions header value included unsupported extensions.".into()));
-    }
+    // The only extension we offer is permessage-deflate.
+    let deflate_params = match response.headers.get_raw("Sec-WebSocket-Extensions") {
+        Some(values) => parse_extensions_response(values)?,
+        None => None,
+    };
 
     // Step 14.6.
     let protocol_in_use = if let Some(response_protocols) = response.headers.get::<WebSocketProtocol>() {
-        for replied in &**response_protocols {
-            if !protocols.iter().any(|requested| requested.eq_ignore_ascii_case(replied)) {
-                return Err(NetworkError::Internal(
-                    "Response's Sec-WebSocket-Protocols contain values that were not requested.".into()));
-            }
+        // The server must select exactly one of the requested subprotocols.
+        if response_protocols.len() != 1 {
+            return Err(NetworkError::Internal(
+                "Response's Sec-WebSocket-Protocol header should have exactly one value.".into()));
+        }
+        let replied = &response_protocols[0];
+        if !protocols.iter().any(|requested| requested == replied) {
+            return Err(NetworkError::Internal(
+                "Response's Sec-WebSocket-Protocol header contains a value that was not requested.".into()));
         }
-        response_protocols.first().cloned()
+        Some(replied.clone())
     } else {
         None
     };
@@ -272,7 +546,7 @@ fn establish_a_websocket_connection(
         receiver,
     };
 
-    Ok((protocol_in_use, writer, reader))
+    Ok((protocol_in_use, writer, reader, deflate_params))
 }
 
 struct Response {
@@ -286,6 +560,8 @@ struct Response {
 fn fetch(url: ServoUrl,
          origin: String,
          mut headers: Headers,
+         csp_list: Option<&CspList>,
+         event_sender: &IpcSender<WebSocketNetworkEvent>,
          http_state: &HttpState)
          -> Result<Response, NetworkError> {
     // Step 1.
@@ -317,13 +593,15 @@ fn fetch(url: ServoUrl,
     }
 
     // Step 8.
-    main_fetch(url, origin, headers, http_state)
+    main_fetch(url, origin, headers, csp_list, event_sender, http_state)
 }
 
 // https://fetch.spec.whatwg.org/#concept-main-fetch
 fn main_fetch(url: ServoUrl,
               origin: String,
               mut headers: Headers,
+              csp_list: Option<&CspList>,
+              event_sender: &IpcSender<WebSocketNetworkEvent>,
               http_state: &HttpState)
               -> Result<Response, NetworkError> {
     // Step 1.
@@ -333,7 +611,16 @@ fn main_fetch(url: ServoUrl,
     // Not applicable: request’s local-URLs-only flag is unset.
 
     // Step 3.
-    // TODO: handle content security policy violations.
+    // WebSocket requests have an empty destination, so they are checked
+    // against the `connect-src` directives.
+    let csp_check = csp_list.map(|csp_list| {
+        csp_list.should_request_be_blocked(&Request::new(url.clone(), None, None))
+    });
+    if let Some((_, ref violations)) = csp_check {
+        if !violations.is_empty() {
+            let _ = event_sender.send(WebSocketNetworkEvent::ReportCspViolations(violations.clone()));
+        }
+    }
 
     // Step 4.
     // TODO: handle upgrade to a potentially secure URL.
@@ -343,7 +630,9 @@ fn main_fetch(url: ServoUrl,
         response = Some(Err(NetworkError::Internal("Request should be blocked due to bad port.".into())));
     }
     // TODO: handle blocking as mixed content.
-    // TODO: handle blocking by content security policy.
+    if let Some((CheckResult::Blocked, _)) = csp_check {
+        response = Some(Err(NetworkError::Internal("Blocked by Content Security Policy".into())));
+    }
 
     // Steps 6-8.
     // TODO: handle request's referrer policy.
@@ -0,0 +1,123 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The messages exchanged with the thread storing the [caches](https://w3c.github.io/ServiceWorker/#cache-objects)
+//! of every origin, which back the `CacheStorage` and `Cache` interfaces.
+
+use ipc_channel::ipc::IpcSender;
+use response::ResponseType;
+use servo_url::{ImmutableOrigin, ServoUrl};
+
+/// Identifies a cache, which outlives its name when it is deleted while still in use.
+pub type CacheId = u64;
+
+/// The header list of a cached request or response, with lower-case names.
+pub type CacheHeaders = Vec<(String, Vec<u8>)>;
+
+/// The combined values of the header named `name`, as they would be sent on the wire.
+pub fn combined_header_value(headers: &CacheHeaders, name: &str) -> Option<Vec<u8>> {
+    let mut values = headers.iter()
+        .filter(|&&(ref header, _)| header.eq_ignore_ascii_case(name))
+        .map(|&(_, ref value)| value);
+    let mut combined = values.next()?.clone();
+    for value in values {
+        combined.extend_from_slice(b", ");
+        combined.extend_from_slice(value);
+    }
+    Some(combined)
+}
+
+/// The request of a [request response list](https://w3c.github.io/ServiceWorker/#request-response-list) entry.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub struct CachedRequest {
+    pub url: ServoUrl,
+    pub method: String,
+    pub headers: CacheHeaders,
+}
+
+/// The response of a [request response list](https://w3c.github.io/ServiceWorker/#request-response-list) entry,
+/// along with its fully read body.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub struct CachedResponse {
+    pub response_type: ResponseType,
+    pub url_list: Vec<ServoUrl>,
+    pub status: u16,
+    pub status_text: Vec<u8>,
+    pub headers: CacheHeaders,
+    pub body: Vec<u8>,
+}
+
+impl CachedRequest {
+    /// An approximation of the number of bytes this request uses in storage.
+    pub fn size(&self) -> usize {
+        self.url.as_str().len() + self.method.len() + headers_size(&self.headers)
+    }
+}
+
+impl CachedResponse {
+    /// An approximation of the number of bytes this response uses in storage.
+    pub fn size(&self) -> usize {
+        self.url_list.iter().map(|url| url.as_str().len()).sum::<usize>() +
+            self.status_text.len() + headers_size(&self.headers) + self.body.len()
+    }
+}
+
+fn headers_size(headers: &CacheHeaders) -> usize {
+    headers.iter().map(|&(ref name, ref value)| name.len() + value.len()).sum()
+}
+
+/// <https://w3c.github.io/ServiceWorker/#dictdef-cachequeryoptions>
+#[derive(Clone, Copy, Debug, Default, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub struct CacheQueryOptions {
+    pub ignore_search: bool,
+    pub ignore_method: bool,
+    pub ignore_vary: bool,
+}
+
+/// A [cache batch operation](https://w3c.github.io/ServiceWorker/#dfn-cache-batch-operation).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum CacheBatchOperation {
+    /// Replace the entries matching `request` with the given response.
+    Put(CachedRequest, CachedResponse),
+    /// Remove the entries matching `request`.
+    Delete(CachedRequest, CacheQueryOptions),
+}
+
+/// The errors reported by the cache storage thread, named after the matching `DOMException`.
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum CacheStorageError {
+    InvalidState,
+    NotFound,
+    QuotaExceeded,
+}
+
+/// The result type of requests made to the cache storage thread.
+pub type CacheStorageResult<T> = Result<T, CacheStorageError>;
+
+/// Request operations on the caches of an origin.
+#[derive(Deserialize, Serialize)]
+pub enum CacheStorageThreadMsg {
+    /// Get the cache with the given name, creating it if needed.
+    Open(IpcSender<CacheId>, ImmutableOrigin, String),
+    /// Whether a cache with the given name exists.
+    Has(IpcSender<bool>, ImmutableOrigin, String),
+    /// Delete the cache with the given name, replying whether it existed.
+    Delete(IpcSender<bool>, ImmutableOrigin, String),
+    /// Get the names of the caches of an origin, in creation order.
+    Keys(IpcSender<Vec<String>>, ImmutableOrigin),
+    /// Find the first response matching a request in the cache with the given name,
+    /// or in every cache of the origin, in creation order.
+    MatchAny(IpcSender<Option<CachedResponse>>, ImmutableOrigin, Option<String>, CachedRequest, CacheQueryOptions),
+    /// Get the responses of a cache matching a request, or every response when no request is given.
+    MatchAll(IpcSender<CacheStorageResult<Vec<CachedResponse>>>, CacheId, Option<CachedRequest>, CacheQueryOptions),
+    /// Get the requests of a cache matching a request, or every request when no request is given.
+    RequestKeys(IpcSender<CacheStorageResult<Vec<CachedRequest>>>, CacheId, Option<CachedRequest>, CacheQueryOptions),
+    /// Apply operations to a cache atomically, replying whether any entry was removed
+    /// by a delete operation.
+    Batch(IpcSender<CacheStorageResult<bool>>, CacheId, Vec<CacheBatchOperation>),
+    /// Get the number of bytes used by the caches of an origin.
+    Usage(IpcSender<u64>, ImmutableOrigin),
+    /// Send a reply when done cleaning up thread resources and then shut it down.
+    Exit(IpcSender<()>),
+}
@@ -0,0 +1,592 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Parsing and enforcement of [Content Security Policy](https://w3c.github.io/webappsec-csp/).
+
+use base64;
+use hyper::header::Headers;
+use openssl::hash::{MessageDigest, hash2};
+use request::{Destination, Request};
+use servo_url::ServoUrl;
+use url::percent_encoding::percent_decode;
+
+/// <https://w3c.github.io/webappsec-csp/#policy-disposition>
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum PolicyDisposition {
+    Enforce,
+    Report,
+}
+
+/// <https://w3c.github.io/webappsec-csp/#policy-source>
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum PolicySource {
+    Header,
+    Meta,
+}
+
+/// <https://w3c.github.io/webappsec-csp/#directives>
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub struct Directive {
+    /// The name of the directive, in ASCII lowercase.
+    pub name: String,
+    /// The source expressions or other tokens making up its value.
+    pub value: Vec<String>,
+}
+
+/// <https://w3c.github.io/webappsec-csp/#content-security-policy-object>
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct Policy {
+    pub directives: Vec<Directive>,
+    pub disposition: PolicyDisposition,
+    pub source: PolicySource,
+    /// The policy as it was delivered, for violation reports.
+    pub serialized: String,
+    /// The URL of the resource this policy protects, whose origin `'self'`
+    /// refers to.
+    pub self_url: ServoUrl,
+}
+
+/// Whether a check passed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CheckResult {
+    Allowed,
+    Blocked,
+}
+
+/// <https://w3c.github.io/webappsec-csp/#violation-resource>
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum ViolationResource {
+    Inline,
+    Eval,
+    Url(ServoUrl),
+}
+
+/// The parts of a [violation](https://w3c.github.io/webappsec-csp/#violation)
+/// known when a policy is checked. The rest depends on the global object the
+/// violation is reported to.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Violation {
+    pub resource: ViolationResource,
+    /// <https://w3c.github.io/webappsec-csp/#violation-effective-directive>
+    pub effective_directive: String,
+    /// <https://w3c.github.io/webappsec-csp/#violation-policy>
+    pub policy: Policy,
+    /// <https://w3c.github.io/webappsec-csp/#violation-sample>
+    pub sample: String,
+}
+
+/// The kinds of inline content a policy may block.
+///
+/// <https://w3c.github.io/webappsec-csp/#should-block-inline>
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InlineCheckType {
+    Script,
+    ScriptAttribute,
+    Style,
+    StyleAttribute,
+    Navigation,
+}
+
+impl InlineCheckType {
+    fn effective_directive(&self) -> &'static str {
+        match *self {
+            InlineCheckType::Script | InlineCheckType::Navigation => "script-src-elem",
+            InlineCheckType::ScriptAttribute => "script-src-attr",
+            InlineCheckType::Style => "style-src-elem",
+            InlineCheckType::StyleAttribute => "style-src-attr",
+        }
+    }
+
+    fn is_element(&self) -> bool {
+        *self == InlineCheckType::Script || *self == InlineCheckType::Style
+    }
+}
+
+/// A [CSP list](https://w3c.github.io/webappsec-csp/#csp-list).
+#[derive(Clone, Debug, Default, Deserialize, MallocSizeOf, Serialize)]
+pub struct CspList(pub Vec<Policy>);
+
+impl CspList {
+    /// <https://w3c.github.io/webappsec-csp/#parse-serialized-policy-list>
+    pub fn parse(list: &str,
+                 source: PolicySource,
+                 disposition: PolicyDisposition,
+                 self_url: &ServoUrl)
+                 -> CspList {
+        CspList(list.split(',').filter_map(|serialized| {
+            let policy = Policy::parse(serialized, source, disposition, self_url);
+            if policy.directives.is_empty() {
+                None
+            } else {
+                Some(policy)
+            }
+        }).collect())
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#parse-response-csp>
+    pub fn from_headers(headers: &Headers, self_url: &ServoUrl) -> CspList {
+        let mut list = CspList::default();
+        let header_dispositions = [
+            ("Content-Security-Policy", PolicyDisposition::Enforce),
+            ("Content-Security-Policy-Report-Only", PolicyDisposition::Report),
+        ];
+        for &(name, disposition) in header_dispositions.iter() {
+            for value in headers.get_raw(name).into_iter().flat_map(|values| values.iter()) {
+                if let Ok(value) = String::from_utf8(value.clone()) {
+                    list.append(CspList::parse(&value, PolicySource::Header, disposition, self_url));
+                }
+            }
+        }
+        list
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn append(&mut self, mut other: CspList) {
+        self.0.append(&mut other.0)
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#should-block-request>
+    pub fn should_request_be_blocked(&self, request: &Request) -> (CheckResult, Vec<Violation>) {
+        let effective_directive = match effective_directive_for_destination(request.destination) {
+            Some(directive) => directive,
+            None => return (CheckResult::Allowed, vec![]),
+        };
+        let nonce = if is_script_or_style_directive(effective_directive) {
+            Some(&*request.cryptographic_nonce_metadata).filter(|nonce| !nonce.is_empty())
+        } else {
+            None
+        };
+        self.check_each_policy(|policy| {
+            let directive = policy.directive_for(effective_directive)?;
+            if nonce.map_or(false, |nonce| directive.matches_nonce(nonce)) {
+                return None;
+            }
+            if directive.matches_url(&request.current_url(), &policy.self_url, request.redirect_count) {
+                return None;
+            }
+            Some(Violation {
+                resource: ViolationResource::Url(request.url()),
+                effective_directive: effective_directive.to_owned(),
+                policy: policy.clone(),
+                sample: String::new(),
+            })
+        })
+    }
+
+    /// Checks a navigation of a nested browsing context to `url` against the
+    /// `frame-src` directives of this list.
+    ///
+    /// <https://w3c.github.io/webappsec-csp/#should-block-navigation-request>
+    pub fn should_nested_navigation_be_blocked(&self, url: &ServoUrl) -> (CheckResult, Vec<Violation>) {
+        self.check_each_policy(|policy| {
+            let directive = policy.directive_for("frame-src")?;
+            if directive.matches_url(url, &policy.self_url, 0) {
+                return None;
+            }
+            Some(Violation {
+                resource: ViolationResource::Url(url.clone()),
+                effective_directive: "frame-src".to_owned(),
+                policy: policy.clone(),
+                sample: String::new(),
+            })
+        })
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#should-block-inline>
+    pub fn should_inline_be_blocked(&self,
+                                    check_type: InlineCheckType,
+                                    nonce: &str,
+                                    source: &str)
+                                    -> (CheckResult, Vec<Violation>) {
+        let effective_directive = check_type.effective_directive();
+        self.check_each_policy(|policy| {
+            let directive = policy.directive_for(effective_directive)?;
+            if directive.matches_inline(check_type, nonce, source) {
+                return None;
+            }
+            let sample = if directive.contains_keyword("'report-sample'") {
+                source.chars().take(40).collect()
+            } else {
+                String::new()
+            };
+            Some(Violation {
+                resource: ViolationResource::Inline,
+                effective_directive: effective_directive.to_owned(),
+                policy: policy.clone(),
+                sample: sample,
+            })
+        })
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#can-compile-strings>
+    pub fn is_string_compilation_blocked(&self, source: &str) -> (CheckResult, Vec<Violation>) {
+        self.check_each_policy(|policy| {
+            let directive = policy.directive_for("script-src")?;
+            if directive.contains_keyword("'unsafe-eval'") {
+                return None;
+            }
+            let sample = if directive.contains_keyword("'report-sample'") {
+                source.chars().take(40).collect()
+            } else {
+                String::new()
+            };
+            Some(Violation {
+                resource: ViolationResource::Eval,
+                effective_directive: "script-src".to_owned(),
+                policy: policy.clone(),
+                sample: sample,
+            })
+        })
+    }
+
+    /// Checks the `frame-ancestors` directives of this list against the URLs
+    /// of the documents embedding the protected one, innermost first.
+    ///
+    /// <https://w3c.github.io/webappsec-csp/#frame-ancestors-navigation-response>
+    pub fn should_be_blocked_by_frame_ancestors(&self, ancestors: &[ServoUrl]) -> (CheckResult, Vec<Violation>) {
+        self.check_each_policy(|policy| {
+            // The directive is not supported in policies delivered by `<meta>`.
+            if policy.source == PolicySource::Meta {
+                return None;
+            }
+            let directive = policy.directives.iter().find(|directive| directive.name == "frame-ancestors")?;
+            let blocked = ancestors.iter().find(|ancestor| !directive.matches_url(ancestor, &policy.self_url, 0))?;
+            Some(Violation {
+                resource: ViolationResource::Url(blocked.clone()),
+                effective_directive: "frame-ancestors".to_owned(),
+                policy: policy.clone(),
+                sample: String::new(),
+            })
+        })
+    }
+
+    /// Runs `check` on each policy, collecting the violations it returns. The
+    /// result is `Blocked` if any of them is from an enforced policy.
+    fn check_each_policy<F>(&self, mut check: F) -> (CheckResult, Vec<Violation>)
+        where F: FnMut(&Policy) -> Option<Violation>
+    {
+        let violations: Vec<Violation> = self.0.iter().filter_map(|policy| check(policy)).collect();
+        let result = if violations.iter().any(|v| v.policy.disposition == PolicyDisposition::Enforce) {
+            CheckResult::Blocked
+        } else {
+            CheckResult::Allowed
+        };
+        (result, violations)
+    }
+}
+
+impl Policy {
+    /// <https://w3c.github.io/webappsec-csp/#parse-serialized-policy>
+    pub fn parse(serialized: &str,
+                 source: PolicySource,
+                 disposition: PolicyDisposition,
+                 self_url: &ServoUrl)
+                 -> Policy {
+        let mut directives: Vec<Directive> = vec![];
+        // Step 2.
+        for token in serialized.split(';') {
+            // Steps 2.1-2.2.
+            let token = token.trim_matches(is_ascii_whitespace);
+            if token.is_empty() {
+                continue;
+            }
+            // Steps 2.3-2.4.
+            let mut parts = token.split(is_ascii_whitespace).filter(|part| !part.is_empty());
+            let name = match parts.next() {
+                Some(name) => name.to_ascii_lowercase(),
+                None => continue,
+            };
+            if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+                continue;
+            }
+            // Step 2.5.
+            if directives.iter().any(|directive| directive.name == name) {
+                continue;
+            }
+            // Policies delivered by `<meta>` can't use these directives.
+            //
+            // https://html.spec.whatwg.org/multipage/#attr-meta-http-equiv-content-security-policy
+            if source == PolicySource::Meta &&
+               (name == "report-uri" || name == "frame-ancestors" || name == "sandbox") {
+                continue;
+            }
+            // Steps 2.6-2.8.
+            directives.push(Directive {
+                name: name,
+                value: parts.map(|part| part.to_owned()).collect(),
+            });
+        }
+        Policy {
+            directives: directives,
+            disposition: disposition,
+            source: source,
+            serialized: serialized.trim_matches(is_ascii_whitespace).to_owned(),
+            self_url: self_url.clone(),
+        }
+    }
+
+    /// Returns the directive governing `effective_directive` in this policy,
+    /// if any.
+    ///
+    /// <https://w3c.github.io/webappsec-csp/#directive-fallback-list>
+    fn directive_for(&self, effective_directive: &str) -> Option<&Directive> {
+        directive_fallback_list(effective_directive).iter().filter_map(|name| {
+            self.directives.iter().find(|directive| directive.name == *name)
+        }).next()
+    }
+
+    /// The URLs violation reports for this policy are sent to.
+    pub fn report_uris(&self) -> &[String] {
+        self.directives.iter().find(|directive| directive.name == "report-uri")
+            .map_or(&[], |directive| &*directive.value)
+    }
+}
+
+impl Directive {
+    fn contains_keyword(&self, keyword: &str) -> bool {
+        self.value.iter().any(|expression| expression.eq_ignore_ascii_case(keyword))
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#match-nonce-to-source-list>
+    fn matches_nonce(&self, nonce: &str) -> bool {
+        self.value.iter().filter_map(|expression| nonce_source_value(expression)).any(|value| value == nonce)
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#match-url-to-source-list>
+    fn matches_url(&self, url: &ServoUrl, self_url: &ServoUrl, redirect_count: u32) -> bool {
+        self.value.iter().any(|expression| {
+            match_url_to_source_expression(url, expression, self_url, redirect_count)
+        })
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#match-element-to-source-list>
+    fn matches_inline(&self, check_type: InlineCheckType, nonce: &str, source: &str) -> bool {
+        let has_nonce_or_hash = self.value.iter().any(|expression| {
+            nonce_source_value(expression).is_some() || hash_source_value(expression).is_some()
+        });
+        let is_script = check_type != InlineCheckType::Style && check_type != InlineCheckType::StyleAttribute;
+        // Step 1.
+        if self.contains_keyword("'unsafe-inline'") && !has_nonce_or_hash &&
+           !(is_script && self.contains_keyword("'strict-dynamic'")) {
+            return true;
+        }
+        // Step 2.
+        if check_type.is_element() && !nonce.is_empty() && self.matches_nonce(nonce) {
+            return true;
+        }
+        // Steps 3-5.
+        if !check_type.is_element() && !self.contains_keyword("'unsafe-hashes'") {
+            return false;
+        }
+        self.value.iter().filter_map(|expression| hash_source_value(expression)).any(|(digest, expected)| {
+            hash2(digest, source.as_bytes()).ok().map_or(false, |actual| {
+                base64::encode(&*actual) == expected.replace('-', "+").replace('_', "/")
+            })
+        })
+    }
+}
+
+/// <https://w3c.github.io/webappsec-csp/#effective-directive-for-a-request>
+fn effective_directive_for_destination(destination: Destination) -> Option<&'static str> {
+    match destination {
+        Destination::None => Some("connect-src"),
+        Destination::Manifest => Some("manifest-src"),
+        Destination::Audio | Destination::Track | Destination::Video => Some("media-src"),
+        Destination::Font => Some("font-src"),
+        Destination::Image => Some("img-src"),
+        Destination::Style => Some("style-src-elem"),
+        Destination::Script | Destination::Xslt => Some("script-src-elem"),
+        Destination::ServiceWorker | Destination::SharedWorker | Destination::Worker => Some("worker-src"),
+        Destination::Object | Destination::Embed => Some("object-src"),
+        // Navigations are checked against the policies of the navigating
+        // document before they are started, and reports are exempt.
+        Destination::Document | Destination::Report => None,
+    }
+}
+
+/// <https://w3c.github.io/webappsec-csp/#directive-fallback-list>
+fn directive_fallback_list(directive: &str) -> &'static [&'static str] {
+    match directive {
+        "script-src-elem" => &["script-src-elem", "script-src", "default-src"],
+        "script-src-attr" => &["script-src-attr", "script-src", "default-src"],
+        "style-src-elem" => &["style-src-elem", "style-src", "default-src"],
+        "style-src-attr" => &["style-src-attr", "style-src", "default-src"],
+        "worker-src" => &["worker-src", "child-src", "script-src", "default-src"],
+        "frame-src" => &["frame-src", "child-src", "default-src"],
+        "script-src" => &["script-src", "default-src"],
+        "style-src" => &["style-src", "default-src"],
+        "connect-src" => &["connect-src", "default-src"],
+        "manifest-src" => &["manifest-src", "default-src"],
+        "media-src" => &["media-src", "default-src"],
+        "font-src" => &["font-src", "default-src"],
+        "img-src" => &["img-src", "default-src"],
+        "object-src" => &["object-src", "default-src"],
+        _ => &[],
+    }
+}
+
+fn is_script_or_style_directive(directive: &str) -> bool {
+    directive == "script-src-elem" || directive == "style-src-elem"
+}
+
+fn is_ascii_whitespace(c: char) -> bool {
+    match c {
+        '\t' | '\n' | '\x0C' | '\r' | ' ' => true,
+        _ => false,
+    }
+}
+
+/// Returns the nonce of a `nonce-source` expression.
+fn nonce_source_value(expression: &str) -> Option<&str> {
+    let is_nonce_source = expression.get(..7).map_or(false, |prefix| prefix.eq_ignore_ascii_case("'nonce-"));
+    if is_nonce_source && expression.len() > 8 && expression.ends_with('\'') {
+        Some(&expression[7..expression.len() - 1])
+    } else {
+        None
+    }
+}
+
+/// Returns the digest algorithm and base64-encoded value of a `hash-source`
+/// expression.
+fn hash_source_value(expression: &str) -> Option<(MessageDigest, &str)> {
+    if !expression.ends_with('\'') || expression.len() < 10 {
+        return None;
+    }
+    let digest = match &*expression.get(..8)?.to_ascii_lowercase() {
+        "'sha256-" => MessageDigest::sha256(),
+        "'sha384-" => MessageDigest::sha384(),
+        "'sha512-" => MessageDigest::sha512(),
+        _ => return None,
+    };
+    Some((digest, &expression[8..expression.len() - 1]))
+}
+
+/// <https://w3c.github.io/webappsec-csp/#match-schemes>
+fn scheme_part_matches(expression_scheme: &str, url_scheme: &str) -> bool {
+    let expression_scheme = expression_scheme.to_ascii_lowercase();
+    match (&*expression_scheme, url_scheme) {
+        (a, b) if a == b => true,
+        ("http", "https") => true,
+        ("ws", "wss") | ("ws", "http") | ("ws", "https") => true,
+        ("wss", "https") => true,
+        _ => false,
+    }
+}
+
+/// <https://w3c.github.io/webappsec-csp/#match-url-to-source-expression>
+pub fn match_url_to_source_expression(url: &ServoUrl,
+                                      expression: &str,
+                                      self_url: &ServoUrl,
+                                      redirect_count: u32)
+                                      -> bool {
+    // Step 1.
+    if expression == "*" {
+        return is_network_scheme(url.scheme()) || url.scheme() == self_url.scheme();
+    }
+
+    // Step 2.
+    if expression.ends_with(':') && !expression.contains('/') {
+        return scheme_part_matches(&expression[..expression.len() - 1], url.scheme());
+    }
+
+    // Step 4.
+    if expression.eq_ignore_ascii_case("'self'") {
+        if url.origin() == self_url.origin() {
+            return true;
+        }
+        let is_secure_upgrade = match (self_url.scheme(), url.scheme()) {
+            ("http", "https") | ("http", "ws") | ("http", "wss") | ("https", "wss") => true,
+            _ => false,
+        };
+        return is_secure_upgrade && url.host_str() == self_url.host_str() &&
+               (url.port() == self_url.port() ||
+                (url.port().map_or(true, |port| Some(port) == default_port(url.scheme())) &&
+                 self_url.port().map_or(true, |port| Some(port) == default_port(self_url.scheme()))));
+    }
+
+    // Other keyword and nonce or hash sources never match URLs.
+    if expression.starts_with('\'') {
+        return false;
+    }
+
+    // Step 3: this is a host-source.
+    let host = match url.host_str() {
+        Some(host) => host.to_ascii_lowercase(),
+        None => return false,
+    };
+    let (scheme, rest) = match expression.find("://") {
+        Some(index) => (Some(&expression[..index]), &expression[index + 3..]),
+        None => (None, expression),
+    };
+    let matches_scheme = match scheme {
+        Some(scheme) => scheme_part_matches(scheme, url.scheme()),
+        None => scheme_part_matches(self_url.scheme(), url.scheme()),
+    };
+    if !matches_scheme {
+        return false;
+    }
+
+    let (host_and_port, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, ""),
+    };
+    let (host_part, port_part) = match host_and_port.rfind(':') {
+        Some(index) if !host_and_port.ends_with(']') => (&host_and_port[..index], Some(&host_and_port[index + 1..])),
+        _ => (host_and_port, None),
+    };
+
+    // https://w3c.github.io/webappsec-csp/#match-hosts
+    let host_part = host_part.to_ascii_lowercase();
+    let matches_host = if host_part.starts_with("*.") {
+        host.ends_with(&host_part[1..])
+    } else {
+        host_part == host
+    };
+    if !matches_host {
+        return false;
+    }
+
+    // https://w3c.github.io/webappsec-csp/#match-ports
+    let matches_port = match port_part {
+        Some("*") => true,
+        Some(port) => port.parse::<u16>().ok().map_or(false, |port| Some(port) == url.port_or_known_default()),
+        None => url.port().is_none() || url.port() == default_port(url.scheme()),
+    };
+    if !matches_port {
+        return false;
+    }
+
+    // https://w3c.github.io/webappsec-csp/#match-paths
+    // Paths are not compared after redirects, so as not to leak where a
+    // cross-origin resource redirected to.
+    if path.is_empty() || redirect_count > 0 {
+        return true;
+    }
+    let url_path = percent_decode(url.path().as_bytes()).decode_utf8_lossy().into_owned();
+    let path = percent_decode(path.as_bytes()).decode_utf8_lossy().into_owned();
+    if path.ends_with('/') {
+        url_path.starts_with(&*path)
+    } else {
+        url_path == path
+    }
+}
+
+fn is_network_scheme(scheme: &str) -> bool {
+    match scheme {
+        "http" | "https" | "ws" | "wss" => true,
+        _ => false,
+    }
+}
+
+fn default_port(scheme: &str) -> Option<u16> {
+    match scheme {
+        "http" | "ws" => Some(80),
+        "https" | "wss" => Some(443),
+        "ftp" => Some(21),
+        _ => None,
+    }
+}
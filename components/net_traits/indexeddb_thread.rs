@@ -0,0 +1,353 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The messages exchanged with the thread storing the [IndexedDB](https://w3c.github.io/IndexedDB/)
+//! databases of every origin.
+
+use ipc_channel::ipc::IpcSender;
+use servo_url::ImmutableOrigin;
+use std::cmp::Ordering;
+
+/// Identifies a connection to a database.
+pub type IndexedDBConnectionId = u64;
+
+/// Identifies a transaction on a database.
+pub type IndexedDBTransactionId = u64;
+
+/// A [key](https://w3c.github.io/IndexedDB/#key-construct).
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub enum IndexedDBKey {
+    Number(f64),
+    Date(f64),
+    String(String),
+    Binary(Vec<u8>),
+    Array(Vec<IndexedDBKey>),
+}
+
+impl IndexedDBKey {
+    /// The position of the type of this key in the ordering of key types.
+    fn type_rank(&self) -> u8 {
+        match *self {
+            IndexedDBKey::Number(_) => 0,
+            IndexedDBKey::Date(_) => 1,
+            IndexedDBKey::String(_) => 2,
+            IndexedDBKey::Binary(_) => 3,
+            IndexedDBKey::Array(_) => 4,
+        }
+    }
+
+    /// An approximation of the number of bytes this key uses in storage.
+    pub fn size(&self) -> usize {
+        match *self {
+            IndexedDBKey::Number(_) | IndexedDBKey::Date(_) => 8,
+            IndexedDBKey::String(ref string) => string.len(),
+            IndexedDBKey::Binary(ref bytes) => bytes.len(),
+            IndexedDBKey::Array(ref keys) => keys.iter().map(IndexedDBKey::size).sum(),
+        }
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#compare-two-keys>
+impl Ord for IndexedDBKey {
+    fn cmp(&self, other: &IndexedDBKey) -> Ordering {
+        match (self, other) {
+            (&IndexedDBKey::Number(a), &IndexedDBKey::Number(b)) |
+            (&IndexedDBKey::Date(a), &IndexedDBKey::Date(b)) => {
+                // Keys are never NaN, this is enforced when converting from a JS value.
+                a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+            },
+            (&IndexedDBKey::String(ref a), &IndexedDBKey::String(ref b)) => {
+                // Strings are compared by code units.
+                a.encode_utf16().cmp(b.encode_utf16())
+            },
+            (&IndexedDBKey::Binary(ref a), &IndexedDBKey::Binary(ref b)) => a.cmp(b),
+            (&IndexedDBKey::Array(ref a), &IndexedDBKey::Array(ref b)) => a.cmp(b),
+            _ => self.type_rank().cmp(&other.type_rank()),
+        }
+    }
+}
+
+impl PartialOrd for IndexedDBKey {
+    fn partial_cmp(&self, other: &IndexedDBKey) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for IndexedDBKey {
+    fn eq(&self, other: &IndexedDBKey) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for IndexedDBKey {}
+
+/// A [key range](https://w3c.github.io/IndexedDB/#range-construct).
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct IndexedDBKeyRange {
+    pub lower: Option<IndexedDBKey>,
+    pub upper: Option<IndexedDBKey>,
+    pub lower_open: bool,
+    pub upper_open: bool,
+}
+
+impl IndexedDBKeyRange {
+    /// A range containing only `key`.
+    pub fn only(key: IndexedDBKey) -> IndexedDBKeyRange {
+        IndexedDBKeyRange {
+            lower: Some(key.clone()),
+            upper: Some(key),
+            lower_open: false,
+            upper_open: false,
+        }
+    }
+
+    /// A range containing every key.
+    pub fn unbounded() -> IndexedDBKeyRange {
+        IndexedDBKeyRange {
+            lower: None,
+            upper: None,
+            lower_open: false,
+            upper_open: false,
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#in>
+    pub fn contains(&self, key: &IndexedDBKey) -> bool {
+        let above_lower = match self.lower {
+            Some(ref lower) if self.lower_open => key > lower,
+            Some(ref lower) => key >= lower,
+            None => true,
+        };
+        let below_upper = match self.upper {
+            Some(ref upper) if self.upper_open => key < upper,
+            Some(ref upper) => key <= upper,
+            None => true,
+        };
+        above_lower && below_upper
+    }
+}
+
+/// A [key path](https://w3c.github.io/IndexedDB/#key-path-construct).
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum IndexedDBKeyPath {
+    String(String),
+    Sequence(Vec<String>),
+}
+
+/// The [mode](https://w3c.github.io/IndexedDB/#transaction-mode) of a transaction.
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum IndexedDBTransactionMode {
+    ReadOnly,
+    ReadWrite,
+    VersionChange,
+}
+
+/// The [direction](https://w3c.github.io/IndexedDB/#cursor-direction) of a cursor.
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum IndexedDBCursorDirection {
+    Next,
+    NextUnique,
+    Prev,
+    PrevUnique,
+}
+
+/// The metadata of an [index](https://w3c.github.io/IndexedDB/#index-construct).
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct IndexedDBIndexMetadata {
+    pub name: String,
+    pub key_path: IndexedDBKeyPath,
+    pub unique: bool,
+    pub multi_entry: bool,
+}
+
+/// The metadata of an [object store](https://w3c.github.io/IndexedDB/#object-store-construct).
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct IndexedDBObjectStoreMetadata {
+    pub name: String,
+    pub key_path: Option<IndexedDBKeyPath>,
+    pub auto_increment: bool,
+    pub indexes: Vec<IndexedDBIndexMetadata>,
+}
+
+/// The metadata of a [database](https://w3c.github.io/IndexedDB/#database-construct).
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct IndexedDBDatabaseMetadata {
+    pub name: String,
+    pub version: u64,
+    pub object_stores: Vec<IndexedDBObjectStoreMetadata>,
+}
+
+/// The errors reported by the IndexedDB thread, named after the matching `DOMException`.
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum IndexedDBError {
+    Abort,
+    Constraint,
+    Data,
+    InvalidState,
+    NotFound,
+    QuotaExceeded,
+    Version,
+}
+
+/// A record of an object store.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub struct IndexedDBRecord {
+    pub key: IndexedDBKey,
+    /// The value, serialized with the structured clone algorithm.
+    pub value: Vec<u8>,
+}
+
+/// The record a cursor is positioned on.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub struct IndexedDBCursorRecord {
+    /// The key of the record in the source of the cursor.
+    pub key: IndexedDBKey,
+    /// The primary key of the record, which is `key` when iterating over an object store.
+    pub primary_key: IndexedDBKey,
+    /// The value of the record, unless only keys were requested.
+    pub value: Option<Vec<u8>>,
+}
+
+/// The keys of a stored value for one of the indexes of its object store.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct IndexedDBIndexKeys {
+    pub index: String,
+    pub keys: Vec<IndexedDBKey>,
+}
+
+/// A [request](https://w3c.github.io/IndexedDB/#request-construct) placed against an object store,
+/// or one of its indexes when `index` is set.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum IndexedDBOperation {
+    /// Store a value, failing if a record with the same key exists unless `overwrite` is set.
+    /// The key is generated by the object store when `key` is `None`.
+    Put {
+        key: Option<IndexedDBKey>,
+        value: Vec<u8>,
+        index_keys: Vec<IndexedDBIndexKeys>,
+        overwrite: bool,
+    },
+    /// Delete every record in range.
+    Delete(IndexedDBKeyRange),
+    /// Delete every record.
+    Clear,
+    /// Get the first record in range.
+    Get { index: Option<String>, range: IndexedDBKeyRange },
+    /// Get the primary key of the first record in range.
+    GetKey { index: Option<String>, range: IndexedDBKeyRange },
+    /// Get up to `count` records in range.
+    GetAll { index: Option<String>, range: IndexedDBKeyRange, count: Option<u32> },
+    /// Get the primary keys of up to `count` records in range.
+    GetAllKeys { index: Option<String>, range: IndexedDBKeyRange, count: Option<u32> },
+    /// Count the records in range.
+    Count { index: Option<String>, range: IndexedDBKeyRange },
+    /// Find the record following the `position` of a cursor, skipping `skip` records,
+    /// and the records before `target` if set.
+    Iterate {
+        index: Option<String>,
+        range: IndexedDBKeyRange,
+        direction: IndexedDBCursorDirection,
+        position: Option<(IndexedDBKey, IndexedDBKey)>,
+        target: Option<IndexedDBKey>,
+        skip: u32,
+        key_only: bool,
+    },
+}
+
+/// The outcome of a successful `IndexedDBOperation`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum IndexedDBOperationResult {
+    Key(Option<IndexedDBKey>),
+    Record(Option<IndexedDBRecord>),
+    Records(Vec<IndexedDBRecord>),
+    Keys(Vec<IndexedDBKey>),
+    Count(u64),
+    Cursor(Option<IndexedDBCursorRecord>),
+    Done,
+}
+
+/// The events sent to the open connections of a database.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum IndexedDBConnectionEvent {
+    /// Another connection requested a version change, or deletion when `new_version` is `None`.
+    VersionChange { old_version: u64, new_version: Option<u64> },
+    /// The connection was closed by the IndexedDB thread.
+    ForcedClose,
+}
+
+/// The replies to a request to open a database.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum IndexedDBOpenResponse {
+    /// Other connections did not close after being notified of the version change.
+    Blocked { old_version: u64, new_version: u64 },
+    /// A connection was opened. When an upgrade is needed, `upgrade` holds the
+    /// version change transaction along with the previous version of the database.
+    Opened {
+        connection: IndexedDBConnectionId,
+        metadata: IndexedDBDatabaseMetadata,
+        upgrade: Option<(IndexedDBTransactionId, u64)>,
+    },
+    /// The version change transaction finished.
+    UpgradeFinished(Result<(), IndexedDBError>),
+    Error(IndexedDBError),
+}
+
+/// The replies to a request to delete a database.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum IndexedDBDeleteResponse {
+    /// Other connections did not close after being notified of the deletion.
+    Blocked { old_version: u64 },
+    /// The database was deleted, and had the given version.
+    Deleted(u64),
+}
+
+/// The result type of requests made to the IndexedDB thread.
+pub type IndexedDBResult<T> = Result<T, IndexedDBError>;
+
+/// Request operations on the IndexedDB databases of an origin.
+#[derive(Deserialize, Serialize)]
+pub enum IndexedDBThreadMsg {
+    /// Open a connection to a database, creating or upgrading it as needed.
+    Open {
+        sender: IpcSender<IndexedDBOpenResponse>,
+        events: IpcSender<IndexedDBConnectionEvent>,
+        origin: ImmutableOrigin,
+        name: String,
+        version: Option<u64>,
+    },
+    /// Close a connection.
+    Close(IndexedDBConnectionId),
+    /// Delete a database.
+    DeleteDatabase(IpcSender<IndexedDBDeleteResponse>, ImmutableOrigin, String),
+    /// Get the names and versions of the databases of an origin.
+    Databases(IpcSender<Vec<(String, u64)>>, ImmutableOrigin),
+    /// Create a transaction over the given object stores.
+    CreateTransaction(IpcSender<IndexedDBResult<IndexedDBTransactionId>>,
+                      IndexedDBConnectionId, Vec<String>, IndexedDBTransactionMode),
+    /// Place a request against an object store, within a transaction.
+    Operation(IpcSender<IndexedDBResult<IndexedDBOperationResult>>,
+              IndexedDBTransactionId, String, IndexedDBOperation),
+    /// Create an object store, within a version change transaction.
+    CreateObjectStore(IpcSender<IndexedDBResult<()>>, IndexedDBTransactionId, IndexedDBObjectStoreMetadata),
+    /// Delete an object store, within a version change transaction.
+    DeleteObjectStore(IpcSender<IndexedDBResult<()>>, IndexedDBTransactionId, String),
+    /// Create an index on an object store, within a version change transaction.
+    /// Replies with the records already stored, whose index keys need to be computed
+    /// and sent back with `SetIndexKeys`.
+    CreateIndex(IpcSender<IndexedDBResult<Vec<IndexedDBRecord>>>,
+                IndexedDBTransactionId, String, IndexedDBIndexMetadata),
+    /// Populate an index created with `CreateIndex`.
+    SetIndexKeys(IpcSender<IndexedDBResult<()>>, IndexedDBTransactionId, String, String,
+                 Vec<(IndexedDBKey, Vec<IndexedDBKey>)>),
+    /// Delete an index of an object store, within a version change transaction.
+    DeleteIndex(IpcSender<IndexedDBResult<()>>, IndexedDBTransactionId, String, String),
+    /// Commit a transaction, once every request placed against it has been processed.
+    Commit(IpcSender<IndexedDBResult<()>>, IndexedDBTransactionId),
+    /// Abort a transaction, reverting every change made within it.
+    Abort(IpcSender<()>, IndexedDBTransactionId),
+    /// Get the number of bytes used by the databases of an origin.
+    Usage(IpcSender<u64>, ImmutableOrigin),
+    /// Send a reply when done cleaning up thread resources and then shut it down.
+    Exit(IpcSender<()>),
+}
@@ -5,6 +5,7 @@
 
 #![deny(unsafe_code)]
 
+extern crate base64;
 extern crate cookie as cookie_rs;
 extern crate embedder_traits;
 extern crate hyper;
@@ -17,6 +18,7 @@ extern crate ipc_channel;
 #[macro_use] extern crate malloc_size_of_derive;
 extern crate msg;
 extern crate num_traits;
+extern crate openssl;
 #[macro_use] extern crate serde;
 extern crate servo_arc;
 extern crate servo_url;
@@ -24,13 +26,17 @@ extern crate url;
 extern crate uuid;
 extern crate webrender_api;
 
+use cache_storage_thread::CacheStorageThreadMsg;
 use cookie_rs::Cookie;
+use csp::{CspList, Violation};
+use embedder_traits::request_interception::RequestInterception;
 use filemanager_thread::FileManagerThreadMsg;
 use hyper::Error as HyperError;
 use hyper::header::{ContentType, Headers, ReferrerPolicy as ReferrerPolicyHeader};
 use hyper::http::RawStatus;
 use hyper::mime::{Attr, Mime};
 use hyper_serde::Serde;
+use indexeddb_thread::IndexedDBThreadMsg;
 use ipc_channel::Error as IpcError;
 use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use ipc_channel::router::ROUTER;
@@ -42,8 +48,11 @@ use std::error::Error;
 use storage_thread::StorageThreadMsg;
 
 pub mod blob_url_store;
+pub mod cache_storage_thread;
+pub mod csp;
 pub mod filemanager_thread;
 pub mod image_cache;
+pub mod indexeddb_thread;
 pub mod net_error_list;
 pub mod pub_domains;
 pub mod request;
@@ -157,6 +166,7 @@ pub enum FetchResponseMsg {
     ProcessResponse(Result<FetchMetadata, NetworkError>),
     ProcessResponseChunk(Vec<u8>),
     ProcessResponseEOF(Result<(), NetworkError>),
+    ProcessCspViolations(Vec<Violation>),
 }
 
 pub trait FetchTaskTarget {
@@ -182,6 +192,10 @@ pub trait FetchTaskTarget {
     ///
     /// Fired when the response is fully fetched
     fn process_response_eof(&mut self, response: &Response);
+
+    /// Fired when the request violates Content Security Policies, which the
+    /// client reports.
+    fn process_csp_violations(&mut self, request: &Request, violations: Vec<Violation>);
 }
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -207,6 +221,7 @@ pub trait FetchResponseListener {
     fn process_response(&mut self, metadata: Result<FetchMetadata, NetworkError>);
     fn process_response_chunk(&mut self, chunk: Vec<u8>);
     fn process_response_eof(&mut self, response: Result<(), NetworkError>);
+    fn process_csp_violations(&mut self, violations: Vec<Violation>);
 }
 
 impl FetchTaskTarget for IpcSender<FetchResponseMsg> {
@@ -233,6 +248,10 @@ impl FetchTaskTarget for IpcSender<FetchResponseMsg> {
             let _ = self.send(FetchResponseMsg::ProcessResponseEOF(Ok(())));
         }
     }
+
+    fn process_csp_violations(&mut self, _: &Request, violations: Vec<Violation>) {
+        let _ = self.send(FetchResponseMsg::ProcessCspViolations(violations));
+    }
 }
 
 
@@ -249,6 +268,7 @@ impl<T: FetchResponseListener> Action<T> for FetchResponseMsg {
             FetchResponseMsg::ProcessResponse(meta) => listener.process_response(meta),
             FetchResponseMsg::ProcessResponseChunk(data) => listener.process_response_chunk(data),
             FetchResponseMsg::ProcessResponseEOF(data) => listener.process_response_eof(data),
+            FetchResponseMsg::ProcessCspViolations(violations) => listener.process_csp_violations(violations),
         }
     }
 }
@@ -279,13 +299,21 @@ pub trait IpcSend<T>
 pub struct ResourceThreads {
     core_thread: CoreResourceThread,
     storage_thread: IpcSender<StorageThreadMsg>,
+    indexeddb_thread: IpcSender<IndexedDBThreadMsg>,
+    cache_storage_thread: IpcSender<CacheStorageThreadMsg>,
 }
 
 impl ResourceThreads {
-    pub fn new(c: CoreResourceThread, s: IpcSender<StorageThreadMsg>) -> ResourceThreads {
+    pub fn new(c: CoreResourceThread,
+               s: IpcSender<StorageThreadMsg>,
+               i: IpcSender<IndexedDBThreadMsg>,
+               cs: IpcSender<CacheStorageThreadMsg>)
+               -> ResourceThreads {
         ResourceThreads {
             core_thread: c,
             storage_thread: s,
+            indexeddb_thread: i,
+            cache_storage_thread: cs,
         }
     }
 }
@@ -310,6 +338,26 @@ impl IpcSend<StorageThreadMsg> for ResourceThreads {
     }
 }
 
+impl IpcSend<IndexedDBThreadMsg> for ResourceThreads {
+    fn send(&self, msg: IndexedDBThreadMsg) -> IpcSendResult {
+        self.indexeddb_thread.send(msg)
+    }
+
+    fn sender(&self) -> IpcSender<IndexedDBThreadMsg> {
+        self.indexeddb_thread.clone()
+    }
+}
+
+impl IpcSend<CacheStorageThreadMsg> for ResourceThreads {
+    fn send(&self, msg: CacheStorageThreadMsg) -> IpcSendResult {
+        self.cache_storage_thread.send(msg)
+    }
+
+    fn sender(&self) -> IpcSender<CacheStorageThreadMsg> {
+        self.cache_storage_thread.clone()
+    }
+}
+
 // Ignore the sub-fields
 malloc_size_of_is_0!(ResourceThreads);
 
@@ -339,6 +387,8 @@ pub enum WebSocketNetworkEvent {
     MessageReceived(MessageData),
     Close(Option<u16>, String),
     Fail,
+    /// The connection violated the Content Security Policy of its client.
+    ReportCspViolations(Vec<Violation>),
 }
 
 #[derive(Deserialize, Serialize)]
@@ -370,12 +420,19 @@ pub enum CoreResourceMsg {
     SetHistoryState(HistoryStateId, Vec<u8>),
     /// Removes history states for the given ids
     RemoveHistoryStates(Vec<HistoryStateId>),
+    /// Serialize every cookie in the cookie jar in the given format
+    ExportCookies(CookieJarFormat, IpcSender<String>),
+    /// Add the cookies serialized in the given format to the cookie jar, replying with
+    /// the number of cookies that were added
+    ImportCookies(CookieJarFormat, String, IpcSender<Result<usize, String>>),
     /// Synchronization message solely for knowing the state of the ResourceChannelManager loop
     Synchronize(IpcSender<()>),
     /// Send the network sender in constellation to CoreResourceThread
     NetworkMediator(IpcSender<CustomResponseMediator>),
     /// Message forwarded to file manager's handler
     ToFileManager(FileManagerThreadMsg),
+    /// Set how the requests of every fetch are intercepted
+    SetRequestInterception(RequestInterception),
     /// Break the load handler loop, send a reply when done cleaning up local resources
     /// and exit
     Exit(IpcSender<()>),
@@ -431,6 +488,9 @@ pub struct Metadata {
 
     /// Referrer Policy of the Request used to obtain Response
     pub referrer_policy: Option<ReferrerPolicy>,
+
+    /// CSP list of the response, if it has any policy
+    pub csp_list: Option<CspList>,
 }
 
 impl Metadata {
@@ -447,6 +507,7 @@ impl Metadata {
             https_state: HttpsState::None,
             referrer: None,
             referrer_policy: None,
+            csp_list: None,
         }
     }
 
@@ -478,6 +539,15 @@ pub enum CookieSource {
     NonHTTP,
 }
 
+/// The formats the cookie jar can be exported to and imported from
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum CookieJarFormat {
+    /// The tab separated `cookies.txt` format used by Netscape, curl and wget
+    Netscape,
+    /// A JSON array of objects with the same fields as WebDriver cookies
+    Json,
+}
+
 /// Convenience function for synchronously loading a whole resource.
 pub fn load_whole_resource(request: RequestInit,
                            core_resource_thread: &CoreResourceThread)
@@ -491,7 +561,8 @@ pub fn load_whole_resource(request: RequestInit,
     loop {
         match action_receiver.recv().unwrap() {
             FetchResponseMsg::ProcessRequestBody |
-            FetchResponseMsg::ProcessRequestEOF => (),
+            FetchResponseMsg::ProcessRequestEOF |
+            FetchResponseMsg::ProcessCspViolations(_) => (),
             FetchResponseMsg::ProcessResponse(Ok(m)) => {
                 metadata = Some(match m {
                     FetchMetadata::Unfiltered(m) => m,
@@ -3,6 +3,7 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use ReferrerPolicy;
+use csp::CspList;
 use hyper::header::Headers;
 use hyper::method::Method;
 use msg::constellation_msg::PipelineId;
@@ -166,6 +167,8 @@ pub struct RequestInit {
     pub integrity_metadata: String,
     // to keep track of redirects
     pub url_list: Vec<ServoUrl>,
+    pub csp_list: Option<CspList>,
+    pub cryptographic_nonce_metadata: String,
 }
 
 impl Default for RequestInit {
@@ -191,6 +194,8 @@ impl Default for RequestInit {
             redirect_mode: RedirectMode::Follow,
             integrity_metadata: "".to_owned(),
             url_list: vec![],
+            csp_list: None,
+            cryptographic_nonce_metadata: "".to_owned(),
         }
     }
 }
@@ -256,6 +261,11 @@ pub struct Request {
     pub redirect_count: u32,
     /// <https://fetch.spec.whatwg.org/#concept-request-response-tainting>
     pub response_tainting: ResponseTainting,
+    /// The [CSP list](https://w3c.github.io/webappsec-csp/#csp-list) of the
+    /// request's client.
+    pub csp_list: Option<CspList>,
+    /// <https://fetch.spec.whatwg.org/#concept-request-nonce-metadata>
+    pub cryptographic_nonce_metadata: String,
 }
 
 impl Request {
@@ -290,6 +300,8 @@ impl Request {
             url_list: vec![url],
             redirect_count: 0,
             response_tainting: ResponseTainting::Basic,
+            csp_list: None,
+            cryptographic_nonce_metadata: String::new(),
         }
     }
 
@@ -324,6 +336,8 @@ impl Request {
         req.redirect_count = url_list.len() as u32 - 1;
         req.url_list = url_list;
         req.integrity_metadata = init.integrity_metadata;
+        req.csp_list = init.csp_list;
+        req.cryptographic_nonce_metadata = init.cryptographic_nonce_metadata;
         req
     }
 
@@ -5,6 +5,7 @@
 //! The [Response](https://fetch.spec.whatwg.org/#responses) object
 //! resulting from a [fetch operation](https://fetch.spec.whatwg.org/#concept-fetch)
 use {FetchMetadata, FilteredMetadata, Metadata, NetworkError, ReferrerPolicy};
+use csp::CspList;
 use hyper::header::{AccessControlExposeHeaders, ContentType, Headers};
 use hyper::status::StatusCode;
 use hyper_serde::Serde;
@@ -109,6 +110,8 @@ pub struct Response {
     pub cors_exposed_header_name_list: Vec<String>,
     /// [Location URL](https://fetch.spec.whatwg.org/#concept-response-location-url)
     pub location_url: Option<Result<ServoUrl, String>>,
+    /// [CSP list](https://fetch.spec.whatwg.org/#concept-response-csp-list)
+    pub csp_list: CspList,
     /// [Internal response](https://fetch.spec.whatwg.org/#concept-internal-response), only used if the Response
     /// is a filtered response
     pub internal_response: Option<Box<Response>>,
@@ -136,6 +139,7 @@ impl Response {
             referrer_policy: None,
             cors_exposed_header_name_list: vec![],
             location_url: None,
+            csp_list: CspList::default(),
             internal_response: None,
             return_internal: true,
             aborted: Arc::new(AtomicBool::new(false)),
@@ -166,6 +170,7 @@ impl Response {
             referrer_policy: None,
             cors_exposed_header_name_list: vec![],
             location_url: None,
+            csp_list: CspList::default(),
             internal_response: None,
             return_internal: true,
             aborted: Arc::new(AtomicBool::new(false)),
@@ -301,6 +306,9 @@ impl Response {
             metadata.https_state = response.https_state;
             metadata.referrer = response.referrer.clone();
             metadata.referrer_policy = response.referrer_policy.clone();
+            if !response.csp_list.is_empty() {
+                metadata.csp_list = Some(response.csp_list.clone());
+            }
             metadata
         };
 
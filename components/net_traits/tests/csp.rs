@@ -0,0 +1,167 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+extern crate net_traits;
+extern crate servo_url;
+
+use net_traits::csp::{CheckResult, CspList, InlineCheckType, Policy, PolicyDisposition, PolicySource};
+use net_traits::csp::match_url_to_source_expression;
+use servo_url::ServoUrl;
+
+fn url(url: &str) -> ServoUrl {
+    ServoUrl::parse(url).unwrap()
+}
+
+fn enforced(serialized: &str) -> CspList {
+    CspList::parse(serialized, PolicySource::Header, PolicyDisposition::Enforce, &url("https://example.com/"))
+}
+
+#[test]
+fn test_parse_policy() {
+    let policy = Policy::parse(" Script-Src 'self' https://cdn.example ; script-src 'none';; img-src * ",
+                               PolicySource::Header,
+                               PolicyDisposition::Enforce,
+                               &url("https://example.com/"));
+    assert_eq!(policy.directives.len(), 2);
+    assert_eq!(policy.directives[0].name, "script-src");
+    assert_eq!(policy.directives[0].value, vec!["'self'", "https://cdn.example"]);
+    assert_eq!(policy.directives[1].name, "img-src");
+}
+
+#[test]
+fn test_parse_policy_list() {
+    let list = enforced("script-src 'self', img-src 'none', ");
+    assert_eq!(list.0.len(), 2);
+}
+
+#[test]
+fn test_meta_policy_ignores_unsupported_directives() {
+    let policy = Policy::parse("frame-ancestors 'none'; report-uri /csp; sandbox; default-src 'self'",
+                               PolicySource::Meta,
+                               PolicyDisposition::Enforce,
+                               &url("https://example.com/"));
+    assert_eq!(policy.directives.len(), 1);
+    assert_eq!(policy.directives[0].name, "default-src");
+    assert!(policy.report_uris().is_empty());
+}
+
+#[test]
+fn test_match_url_to_source_expression() {
+    let self_url = url("https://example.com/index.html");
+    let matches = |target: &str, expression: &str| {
+        match_url_to_source_expression(&url(target), expression, &self_url, 0)
+    };
+
+    assert!(matches("https://other.org/", "*"));
+    assert!(!matches("data:text/plain,hi", "*"));
+    assert!(matches("data:text/plain,hi", "data:"));
+    assert!(matches("https://example.com/script.js", "'self'"));
+    assert!(!matches("https://other.org/script.js", "'self'"));
+    assert!(matches("https://cdn.example.com/a.js", "*.example.com"));
+    assert!(!matches("https://example.com/a.js", "*.example.com"));
+    assert!(matches("https://example.com/a.js", "http://example.com"));
+    assert!(!matches("http://example.com/a.js", "https://example.com"));
+    assert!(matches("https://example.com:8443/a.js", "example.com:*"));
+    assert!(!matches("https://example.com:8443/a.js", "example.com"));
+    assert!(matches("https://example.com/js/a.js", "example.com/js/"));
+    assert!(!matches("https://example.com/css/a.css", "example.com/js/"));
+    assert!(matches("https://example.com/js/a.js", "example.com/js/a.js"));
+    assert!(!matches("https://example.com/js/b.js", "example.com/js/a.js"));
+}
+
+#[test]
+fn test_paths_are_ignored_after_redirects() {
+    let self_url = url("https://example.com/");
+    let target = url("https://example.com/elsewhere.js");
+    assert!(!match_url_to_source_expression(&target, "example.com/js/", &self_url, 0));
+    assert!(match_url_to_source_expression(&target, "example.com/js/", &self_url, 1));
+}
+
+#[test]
+fn test_inline_script() {
+    let list = enforced("script-src 'self'");
+    let (result, violations) = list.should_inline_be_blocked(InlineCheckType::Script, "", "alert(1)");
+    assert_eq!(result, CheckResult::Blocked);
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].effective_directive, "script-src-elem");
+
+    let list = enforced("script-src 'unsafe-inline'");
+    let (result, _) = list.should_inline_be_blocked(InlineCheckType::Script, "", "alert(1)");
+    assert_eq!(result, CheckResult::Allowed);
+
+    let list = enforced("style-src 'self'");
+    let (result, violations) = list.should_inline_be_blocked(InlineCheckType::Script, "", "alert(1)");
+    assert_eq!(result, CheckResult::Allowed);
+    assert!(violations.is_empty());
+}
+
+#[test]
+fn test_inline_script_nonce() {
+    let list = enforced("script-src 'nonce-abc123' 'unsafe-inline'");
+    let (result, _) = list.should_inline_be_blocked(InlineCheckType::Script, "abc123", "alert(1)");
+    assert_eq!(result, CheckResult::Allowed);
+    // 'unsafe-inline' is ignored in the presence of a nonce.
+    let (result, _) = list.should_inline_be_blocked(InlineCheckType::Script, "wrong", "alert(1)");
+    assert_eq!(result, CheckResult::Blocked);
+    // Nonces don't apply to attributes.
+    let (result, _) = list.should_inline_be_blocked(InlineCheckType::ScriptAttribute, "abc123", "alert(1)");
+    assert_eq!(result, CheckResult::Blocked);
+}
+
+#[test]
+fn test_inline_script_hash() {
+    // echo -n "alert(1)" | openssl dgst -sha256 -binary | openssl base64 -A
+    let list = enforced("script-src 'sha256-bhHHL3z2vDgxUt0W3dWQOrprscmda2Y5pLsLg4GF+pI='");
+    let (result, _) = list.should_inline_be_blocked(InlineCheckType::Script, "", "alert(1)");
+    assert_eq!(result, CheckResult::Allowed);
+    let (result, _) = list.should_inline_be_blocked(InlineCheckType::Script, "", "alert(2)");
+    assert_eq!(result, CheckResult::Blocked);
+    // Hashes only apply to attributes with 'unsafe-hashes'.
+    let (result, _) = list.should_inline_be_blocked(InlineCheckType::ScriptAttribute, "", "alert(1)");
+    assert_eq!(result, CheckResult::Blocked);
+    let list = enforced("script-src 'unsafe-hashes' 'sha256-bhHHL3z2vDgxUt0W3dWQOrprscmda2Y5pLsLg4GF+pI='");
+    let (result, _) = list.should_inline_be_blocked(InlineCheckType::ScriptAttribute, "", "alert(1)");
+    assert_eq!(result, CheckResult::Allowed);
+}
+
+#[test]
+fn test_report_only_policy_does_not_block() {
+    let list = CspList::parse("default-src 'none'",
+                              PolicySource::Header,
+                              PolicyDisposition::Report,
+                              &url("https://example.com/"));
+    let (result, violations) = list.should_inline_be_blocked(InlineCheckType::Style, "", "body {}");
+    assert_eq!(result, CheckResult::Allowed);
+    assert_eq!(violations.len(), 1);
+}
+
+#[test]
+fn test_string_compilation() {
+    let (result, violations) = enforced("script-src 'self'").is_string_compilation_blocked("");
+    assert_eq!(result, CheckResult::Blocked);
+    assert_eq!(violations.len(), 1);
+    let (result, _) = enforced("script-src 'self' 'unsafe-eval'").is_string_compilation_blocked("");
+    assert_eq!(result, CheckResult::Allowed);
+}
+
+#[test]
+fn test_frame_ancestors() {
+    let list = enforced("frame-ancestors 'self'");
+    let (result, _) = list.should_be_blocked_by_frame_ancestors(&[url("https://example.com/parent.html")]);
+    assert_eq!(result, CheckResult::Allowed);
+    let ancestors = [url("https://example.com/parent.html"), url("https://evil.org/")];
+    let (result, violations) = list.should_be_blocked_by_frame_ancestors(&ancestors);
+    assert_eq!(result, CheckResult::Blocked);
+    assert_eq!(violations.len(), 1);
+}
+
+#[test]
+fn test_nested_navigation() {
+    let list = enforced("child-src https://frames.example");
+    let (result, _) = list.should_nested_navigation_be_blocked(&url("https://frames.example/a"));
+    assert_eq!(result, CheckResult::Allowed);
+    let (result, violations) = list.should_nested_navigation_be_blocked(&url("https://other.example/a"));
+    assert_eq!(result, CheckResult::Blocked);
+    assert_eq!(violations[0].effective_directive, "frame-src");
+}
@@ -3,15 +3,18 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::codegen::Bindings::FormDataBinding::FormDataMethods;
+use dom::bindings::codegen::Bindings::ResponseBinding::ResponseBodyInit;
 use dom::bindings::error::{Error, Fallible};
 use dom::bindings::reflector::DomObject;
 use dom::bindings::root::DomRoot;
-use dom::bindings::str::USVString;
+use dom::bindings::str::{DOMString, USVString};
 use dom::bindings::trace::RootedTraceableBox;
 use dom::blob::{Blob, BlobImpl};
 use dom::formdata::FormData;
 use dom::globalscope::GlobalScope;
 use dom::promise::Promise;
+use dom::readablestream::ReadableStream;
+use dom::xmlhttprequest::Extractable;
 use js::jsapi::Heap;
 use js::jsapi::JSContext;
 use js::jsapi::JSObject;
@@ -19,14 +22,16 @@ use js::jsapi::JS_ClearPendingException;
 use js::jsapi::Value as JSValue;
 use js::jsval::JSVal;
 use js::jsval::UndefinedValue;
+use js::rust::HandleValue;
 use js::rust::wrappers::JS_GetPendingException;
 use js::rust::wrappers::JS_ParseJSON;
-use js::typedarray::{ArrayBuffer, CreateWith};
+use js::typedarray::{ArrayBuffer, CreateWith, Uint8Array};
 use mime::{Mime, TopLevel, SubLevel};
 use std::cell::Ref;
 use std::ptr;
 use std::rc::Rc;
 use std::str;
+use streams::upon_settlement;
 use url::form_urlencoded;
 
 #[derive(Clone, Copy, JSTraceable, MallocSizeOf)]
@@ -47,10 +52,36 @@ pub enum FetchedData {
     JSException(RootedTraceableBox<Heap<JSVal>>)
 }
 
+/// The body of a `Request` or `Response` given to its constructor.
+pub enum ExtractedBody {
+    Bytes(Vec<u8>),
+    Stream(DomRoot<ReadableStream>),
+}
+
+// https://fetch.spec.whatwg.org/#concept-bodyinit-extract
+pub fn extract_body(body: &ResponseBodyInit) -> Fallible<(ExtractedBody, Option<DOMString>)> {
+    let (bytes, content_type) = match *body {
+        ResponseBodyInit::ReadableStream(ref stream) => {
+            if stream.is_disturbed() || stream.is_locked() {
+                return Err(Error::Type("The body stream is disturbed or locked".to_owned()));
+            }
+            return Ok((ExtractedBody::Stream(DomRoot::from_ref(&**stream)), None));
+        },
+        ResponseBodyInit::String(ref s) => s.extract(),
+        ResponseBodyInit::URLSearchParams(ref usp) => usp.extract(),
+        ResponseBodyInit::Blob(ref b) => b.extract(),
+        ResponseBodyInit::FormData(ref formdata) => formdata.extract(),
+        ResponseBodyInit::ArrayBuffer(ref typedarray) => (typedarray.to_vec(), None),
+        ResponseBodyInit::ArrayBufferView(ref typedarray) => (typedarray.to_vec(), None),
+    };
+    Ok((ExtractedBody::Bytes(bytes), content_type))
+}
+
 // https://fetch.spec.whatwg.org/#concept-body-consume-body
 #[allow(unrooted_must_root)]
 pub fn consume_body<T: BodyOperations + DomObject>(object: &T, body_type: BodyType) -> Rc<Promise> {
-    let promise = Promise::new(&object.global());
+    let global = object.global();
+    let promise = Promise::new(&global);
 
     // Step 1
     if object.get_body_used() || object.is_locked() {
@@ -60,10 +91,19 @@ pub fn consume_body<T: BodyOperations + DomObject>(object: &T, body_type: BodyTy
         return promise;
     }
 
-    object.set_body_promise(&promise, body_type);
-
     // Steps 2-4
-    // TODO: Body does not yet have a stream.
+    if let Some(stream) = object.body_stream() {
+        let bytes = stream.read_all_bytes();
+        let consumer = StreamConsumer {
+            promise: promise.clone(),
+            body_type: body_type,
+            mime_type: object.get_mime_type().clone(),
+        };
+        upon_settlement(&bytes, consumer, Some(StreamConsumer::bytes_read), Some(StreamConsumer::read_failed));
+        return promise;
+    }
+
+    object.set_body_promise(&promise, body_type);
 
     consume_body_with_promise(object, body_type, &promise);
 
@@ -81,10 +121,41 @@ pub fn consume_body_with_promise<T: BodyOperations + DomObject>(object: &T,
         None => return,
     };
 
-    let pkg_data_results = run_package_data_algorithm(object,
-                                                      body,
-                                                      body_type,
-                                                      object.get_mime_type());
+    resolve_with_package_data(promise, &object.global(), body, body_type, &object.get_mime_type());
+}
+
+/// Packages the bytes read from the stream of a body.
+#[derive(Clone, JSTraceable, MallocSizeOf)]
+struct StreamConsumer {
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+    body_type: BodyType,
+    mime_type: Vec<u8>,
+}
+
+impl StreamConsumer {
+    #[allow(unsafe_code)]
+    fn bytes_read(consumer: &StreamConsumer, cx: *mut JSContext, value: HandleValue) {
+        let bytes = unsafe {
+            typedarray!(in(cx) let array: Uint8Array = value.to_object());
+            array.expect("Reading all bytes produces a Uint8Array").to_vec()
+        };
+        let global = consumer.promise.global();
+        resolve_with_package_data(&consumer.promise, &global, bytes, consumer.body_type, &consumer.mime_type);
+    }
+
+    fn read_failed(consumer: &StreamConsumer, _cx: *mut JSContext, error: HandleValue) {
+        consumer.promise.reject_native(&error);
+    }
+}
+
+// https://fetch.spec.whatwg.org/#concept-body-consume-body
+fn resolve_with_package_data(promise: &Promise,
+                             global: &GlobalScope,
+                             bytes: Vec<u8>,
+                             body_type: BodyType,
+                             mime_type: &[u8]) {
+    let pkg_data_results = run_package_data_algorithm(global, bytes, body_type, mime_type);
 
     match pkg_data_results {
         Ok(results) => {
@@ -103,19 +174,17 @@ pub fn consume_body_with_promise<T: BodyOperations + DomObject>(object: &T,
 
 // https://fetch.spec.whatwg.org/#concept-body-package-data
 #[allow(unsafe_code)]
-fn run_package_data_algorithm<T: BodyOperations + DomObject>(object: &T,
-                                                             bytes: Vec<u8>,
-                                                             body_type: BodyType,
-                                                             mime_type: Ref<Vec<u8>>)
-                                                             -> Fallible<FetchedData> {
-    let global = object.global();
+fn run_package_data_algorithm(global: &GlobalScope,
+                              bytes: Vec<u8>,
+                              body_type: BodyType,
+                              mime: &[u8])
+                              -> Fallible<FetchedData> {
     let cx = global.get_cx();
-    let mime = &*mime_type;
     match body_type {
         BodyType::Text => run_text_data_algorithm(bytes),
         BodyType::Json => run_json_data_algorithm(cx, bytes),
-        BodyType::Blob => run_blob_data_algorithm(&global, bytes, mime),
-        BodyType::FormData => run_form_data_algorithm(&global, bytes, mime),
+        BodyType::Blob => run_blob_data_algorithm(global, bytes, mime),
+        BodyType::FormData => run_form_data_algorithm(global, bytes, mime),
         BodyType::ArrayBuffer => unsafe {
             run_array_buffer_data_algorithm(cx, bytes)
         }
@@ -197,9 +266,12 @@ unsafe fn run_array_buffer_data_algorithm(cx: *mut JSContext, bytes: Vec<u8>) ->
 pub trait BodyOperations {
     fn get_body_used(&self) -> bool;
     fn set_body_promise(&self, p: &Rc<Promise>, body_type: BodyType);
-    /// Returns `Some(_)` if the body is complete, `None` if there is more to
-    /// come.
+    /// Takes the bytes of a body which is not a stream. Returns `Some(_)` if
+    /// the body is complete, `None` if there is more to come.
     fn take_body(&self) -> Option<Vec<u8>>;
+    /// Returns the stream of the body, if it was given as or turned into
+    /// one.
+    fn body_stream(&self) -> Option<DomRoot<ReadableStream>>;
     fn is_locked(&self) -> bool;
     fn get_mime_type(&self) -> Ref<Vec<u8>>;
 }
@@ -0,0 +1,95 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Helpers shared by the [`CacheStorage`](https://w3c.github.io/ServiceWorker/#cachestorage-interface)
+//! and [`Cache`](https://w3c.github.io/ServiceWorker/#cache-interface) interfaces: conversions of
+//! their arguments, and the routing of the replies of the cache storage thread to promises.
+
+use dom::bindings::codegen::Bindings::CacheBinding::CacheQueryOptions;
+use dom::bindings::codegen::Bindings::RequestBinding::{RequestInfo, RequestInit};
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::refcounted::TrustedPromise;
+use dom::bindings::reflector::DomObject;
+use dom::bindings::root::DomRoot;
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::request::Request;
+use ipc_channel::ipc::{self, IpcSender};
+use ipc_channel::router::ROUTER;
+use net_traits::IpcSend;
+use net_traits::cache_storage_thread::{CacheStorageError, CacheStorageThreadMsg};
+use net_traits::cache_storage_thread::CacheQueryOptions as NetTraitsCacheQueryOptions;
+use serde::{Deserialize, Serialize};
+use servo_url::ImmutableOrigin;
+use std::rc::Rc;
+use task_source::{TaskSource, TaskSourceName};
+
+/// Send the message built by `message` to the cache storage thread, and call `handler` with
+/// `promise` and the reply, in a task queued on the networking task source of the global
+/// of `promise`.
+pub fn send_request<T, M, F>(promise: &Rc<Promise>, message: M, handler: F)
+    where T: for<'de> Deserialize<'de> + Serialize + Send + 'static,
+          M: FnOnce(IpcSender<T>) -> CacheStorageThreadMsg,
+          F: FnOnce(&Promise, T) + Send + 'static
+{
+    let global = promise.global();
+    let (sender, receiver) = ipc::channel().unwrap();
+    let thread: IpcSender<CacheStorageThreadMsg> = global.resource_threads().sender();
+    if thread.send(message(sender)).is_err() {
+        promise.reject_error(Error::InvalidState);
+        return;
+    }
+
+    let task_source = global.networking_task_source();
+    let canceller = global.task_canceller(TaskSourceName::Networking);
+    let mut pending = Some((TrustedPromise::new(promise.clone()), handler));
+    ROUTER.add_route(receiver.to_opaque(), Box::new(move |message| {
+        let reply: T = match message.to() {
+            Ok(reply) => reply,
+            Err(_) => return,
+        };
+        let (promise, handler) = match pending.take() {
+            Some(pending) => pending,
+            None => return,
+        };
+        let _ = task_source.queue_with_canceller(task!(cache_storage_reply: move || {
+            handler(&promise.root(), reply);
+        }), &canceller);
+    }));
+}
+
+/// The origin whose caches `global` can access, which needs to be a tuple origin.
+pub fn cache_storage_origin(global: &GlobalScope) -> Fallible<ImmutableOrigin> {
+    let origin = global.origin().immutable().clone();
+    if !origin.is_tuple() {
+        return Err(Error::Security);
+    }
+    Ok(origin)
+}
+
+/// The request described by `info`, parsed relative to the API base URL of `global`.
+pub fn request_from_info(global: &GlobalScope, info: RequestInfo) -> Fallible<DomRoot<Request>> {
+    match info {
+        RequestInfo::Request(request) => Ok(request),
+        info => Request::Constructor(global, info, RequestInit::empty()),
+    }
+}
+
+pub fn query_options(options: &CacheQueryOptions) -> NetTraitsCacheQueryOptions {
+    NetTraitsCacheQueryOptions {
+        ignore_search: options.ignoreSearch,
+        ignore_method: options.ignoreMethod,
+        ignore_vary: options.ignoreVary,
+    }
+}
+
+impl From<CacheStorageError> for Error {
+    fn from(error: CacheStorageError) -> Error {
+        match error {
+            CacheStorageError::InvalidState => Error::InvalidState,
+            CacheStorageError::NotFound => Error::NotFound,
+            CacheStorageError::QuotaExceeded => Error::QuotaExceeded,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::abortsignal::AbortSignal;
+use dom::bindings::codegen::Bindings::AbortControllerBinding;
+use dom::bindings::codegen::Bindings::AbortControllerBinding::AbortControllerMethods;
+use dom::bindings::error::Fallible;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::globalscope::GlobalScope;
+use dom_struct::dom_struct;
+use js::jsapi::JSContext;
+use js::rust::HandleValue;
+
+/// <https://dom.spec.whatwg.org/#interface-abortcontroller>
+#[dom_struct]
+pub struct AbortController {
+    reflector_: Reflector,
+    signal: Dom<AbortSignal>,
+}
+
+impl AbortController {
+    fn new_inherited(signal: &AbortSignal) -> AbortController {
+        AbortController {
+            reflector_: Reflector::new(),
+            signal: Dom::from_ref(signal),
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#dom-abortcontroller-abortcontroller
+    pub fn Constructor(global: &GlobalScope) -> Fallible<DomRoot<AbortController>> {
+        let signal = AbortSignal::new(global);
+        Ok(reflect_dom_object(Box::new(AbortController::new_inherited(&signal)),
+                              global,
+                              AbortControllerBinding::Wrap))
+    }
+}
+
+impl AbortControllerMethods for AbortController {
+    // https://dom.spec.whatwg.org/#dom-abortcontroller-signal
+    fn Signal(&self) -> DomRoot<AbortSignal> {
+        DomRoot::from_ref(&*self.signal)
+    }
+
+    #[allow(unsafe_code)]
+    // https://dom.spec.whatwg.org/#dom-abortcontroller-abort
+    unsafe fn Abort(&self, _cx: *mut JSContext, reason: HandleValue) {
+        self.signal.signal_abort(reason);
+    }
+}
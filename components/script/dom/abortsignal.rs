@@ -0,0 +1,267 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::AbortSignalBinding;
+use dom::bindings::codegen::Bindings::AbortSignalBinding::AbortSignalMethods;
+use dom::bindings::codegen::Bindings::EventListenerBinding::EventListener;
+use dom::bindings::codegen::Bindings::EventTargetBinding::EventListenerOptions;
+use dom::bindings::error::{Error, ErrorResult};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
+use dom::eventtarget::EventTarget;
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::readablestream::PipeState;
+use dom::response::Response;
+use dom_struct::dom_struct;
+use fetch;
+use ipc_channel::ipc::IpcSender;
+use js::jsapi::{Heap, JSAutoCompartment, JSContext};
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::HandleValue;
+use script_traits::MsDuration;
+use std::mem;
+use std::ptr;
+use std::rc::Rc;
+use streams::{error_to_value, throw_value};
+use timers::OneshotTimerCallback;
+
+/// The steps to run when a signal is aborted.
+///
+/// <https://dom.spec.whatwg.org/#abortsignal-abort-algorithms>
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+pub enum AbortAlgorithm {
+    /// Aborts a signal that follows the aborted one.
+    ///
+    /// <https://dom.spec.whatwg.org/#abortsignal-follow>
+    Follow(Dom<AbortSignal>),
+    /// Removes an event listener that was added with the signal.
+    RemoveEventListener(Dom<EventTarget>,
+                        DOMString,
+                        #[ignore_malloc_size_of = "Rc"] Rc<EventListener>,
+                        bool),
+    /// Aborts a fetch, given its promise, its response and the sender
+    /// cancelling the request in the resource thread.
+    Fetch(#[ignore_malloc_size_of = "Rc"] Rc<Promise>,
+          Dom<Response>,
+          #[ignore_malloc_size_of = "Channels are hard"] IpcSender<()>),
+    /// Shuts down a pipe between two streams.
+    StreamPipe(#[ignore_malloc_size_of = "Rc"] Rc<PipeState>),
+}
+
+impl AbortAlgorithm {
+    #[allow(unrooted_must_root)]
+    fn run(self, reason: HandleValue) {
+        match self {
+            AbortAlgorithm::Follow(signal) => signal.signal_abort(reason),
+            AbortAlgorithm::RemoveEventListener(target, ty, listener, capture) => {
+                target.remove_event_listener(ty, Some(listener), EventListenerOptions { capture });
+            },
+            AbortAlgorithm::Fetch(promise, response, cancel_chan) => {
+                fetch::abort_fetch(&promise, Some(&response), reason);
+                // The resource thread may already be done with the request.
+                let _ = cancel_chan.send(());
+            },
+            AbortAlgorithm::StreamPipe(state) => PipeState::abort(&state, reason),
+        }
+    }
+}
+
+/// <https://dom.spec.whatwg.org/#interface-AbortSignal>
+#[dom_struct]
+pub struct AbortSignal {
+    eventtarget: EventTarget,
+    /// <https://dom.spec.whatwg.org/#abortsignal-abort-reason>, which is
+    /// undefined until the signal is aborted.
+    #[ignore_malloc_size_of = "mozjs"]
+    abort_reason: Heap<JSVal>,
+    abort_algorithms: DomRefCell<Vec<AbortAlgorithm>>,
+}
+
+impl AbortSignal {
+    fn new_inherited() -> AbortSignal {
+        AbortSignal {
+            eventtarget: EventTarget::new_inherited(),
+            abort_reason: Heap::default(),
+            abort_algorithms: DomRefCell::new(vec![]),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<AbortSignal> {
+        reflect_dom_object(Box::new(AbortSignal::new_inherited()),
+                           global,
+                           AbortSignalBinding::Wrap)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-abortsignal-abort
+    pub fn Abort(_cx: *mut JSContext, global: &GlobalScope, reason: HandleValue) -> DomRoot<AbortSignal> {
+        let signal = AbortSignal::new(global);
+        signal.signal_abort(reason);
+        signal
+    }
+
+    // https://dom.spec.whatwg.org/#dom-abortsignal-timeout
+    pub fn Timeout(global: &GlobalScope, milliseconds: u64) -> DomRoot<AbortSignal> {
+        let signal = AbortSignal::new(global);
+        let callback = OneshotTimerCallback::AbortSignalTimeout(AbortSignalTimeoutCallback {
+            signal: Trusted::new(&*signal),
+        });
+        global.schedule_callback(callback, MsDuration::new(milliseconds));
+        signal
+    }
+
+    /// <https://dom.spec.whatwg.org/#abortsignal-aborted>
+    pub fn aborted(&self) -> bool {
+        !self.abort_reason.get().is_undefined()
+    }
+
+    /// The abort reason of this signal, which is undefined if it is not aborted.
+    pub fn abort_reason(&self) -> JSVal {
+        self.abort_reason.get()
+    }
+
+    /// <https://dom.spec.whatwg.org/#abortsignal-add>
+    #[allow(unrooted_must_root)]
+    pub fn add_algorithm(&self, algorithm: AbortAlgorithm) {
+        if self.aborted() {
+            return;
+        }
+        self.abort_algorithms.borrow_mut().push(algorithm);
+    }
+
+    /// Removes the algorithm shutting down the pipe of `state`, once the
+    /// pipe finished.
+    ///
+    /// <https://dom.spec.whatwg.org/#abortsignal-remove>
+    pub fn remove_stream_pipe(&self, state: &PipeState) {
+        self.abort_algorithms.borrow_mut().retain(|algorithm| {
+            match *algorithm {
+                AbortAlgorithm::StreamPipe(ref pipe) => !ptr::eq(&**pipe, state),
+                _ => true,
+            }
+        });
+    }
+
+    /// Removes the algorithm aborting the fetch of `response`, once its body
+    /// finished or errored, so that the signal doesn't keep it alive.
+    ///
+    /// <https://dom.spec.whatwg.org/#abortsignal-remove>
+    pub fn remove_fetch(&self, response: &Response) {
+        self.abort_algorithms.borrow_mut().retain(|algorithm| {
+            match *algorithm {
+                AbortAlgorithm::Fetch(_, ref fetch_response, _) => !ptr::eq(&**fetch_response, response),
+                _ => true,
+            }
+        });
+    }
+
+    /// Makes this signal abort when `parent` does.
+    ///
+    /// <https://dom.spec.whatwg.org/#abortsignal-follow>
+    #[allow(unrooted_must_root)]
+    pub fn follow(&self, parent: &AbortSignal) {
+        if self.aborted() {
+            return;
+        }
+        if parent.aborted() {
+            let cx = self.global().get_cx();
+            rooted!(in(cx) let reason = parent.abort_reason.get());
+            return self.signal_abort(reason.handle());
+        }
+        parent.add_algorithm(AbortAlgorithm::Follow(Dom::from_ref(self)));
+    }
+
+    /// Aborts this signal with `reason`, or with an "AbortError"
+    /// DOMException if `reason` is undefined.
+    ///
+    /// <https://dom.spec.whatwg.org/#abortsignal-signal-abort>
+    pub fn signal_abort(&self, reason: HandleValue) {
+        if self.aborted() {
+            return;
+        }
+        if reason.is_undefined() {
+            self.signal_abort_with_error(Error::Abort);
+        } else {
+            self.abort(reason);
+        }
+    }
+
+    /// Aborts this signal with the DOMException of `error`.
+    #[allow(unsafe_code)]
+    pub fn signal_abort_with_error(&self, error: Error) {
+        let global = self.global();
+        let cx = global.get_cx();
+        let _ac = JSAutoCompartment::new(cx, global.reflector().get_jsobject().get());
+        rooted!(in(cx) let mut reason = UndefinedValue());
+        unsafe { error_to_value(cx, &global, error, reason.handle_mut()) };
+        self.abort(reason.handle());
+    }
+
+    #[allow(unrooted_must_root)]
+    fn abort(&self, reason: HandleValue) {
+        // Step 1.
+        if self.aborted() {
+            return;
+        }
+
+        // Step 2.
+        self.abort_reason.set(reason.get());
+
+        // Steps 3-4.
+        let algorithms = mem::replace(&mut *self.abort_algorithms.borrow_mut(), vec![]);
+        for algorithm in algorithms {
+            algorithm.run(reason);
+        }
+
+        // Step 5.
+        self.upcast::<EventTarget>().fire_event(atom!("abort"));
+    }
+}
+
+impl AbortSignalMethods for AbortSignal {
+    // https://dom.spec.whatwg.org/#dom-abortsignal-aborted
+    fn Aborted(&self) -> bool {
+        self.aborted()
+    }
+
+    #[allow(unsafe_code)]
+    // https://dom.spec.whatwg.org/#dom-abortsignal-reason
+    unsafe fn Reason(&self, _cx: *mut JSContext) -> JSVal {
+        self.abort_reason.get()
+    }
+
+    #[allow(unsafe_code)]
+    // https://dom.spec.whatwg.org/#dom-abortsignal-throwifaborted
+    fn ThrowIfAborted(&self) -> ErrorResult {
+        if !self.aborted() {
+            return Ok(());
+        }
+        let cx = self.global().get_cx();
+        rooted!(in(cx) let reason = self.abort_reason.get());
+        Err(unsafe { throw_value(cx, reason.handle()) })
+    }
+
+    // https://dom.spec.whatwg.org/#handler-abortsignal-onabort
+    event_handler!(abort, GetOnabort, SetOnabort);
+}
+
+/// Aborts the signal created by `AbortSignal.timeout()` once its timeout
+/// elapsed.
+#[derive(JSTraceable, MallocSizeOf)]
+pub struct AbortSignalTimeoutCallback {
+    #[ignore_malloc_size_of = "Because it is non-owning"]
+    signal: Trusted<AbortSignal>,
+}
+
+impl AbortSignalTimeoutCallback {
+    // https://dom.spec.whatwg.org/#dom-abortsignal-timeout
+    pub fn invoke(self) {
+        self.signal.root().signal_abort_with_error(Error::Timeout);
+    }
+}
@@ -0,0 +1,229 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::audionode::AudioNode;
+use dom::baseaudiocontext::BaseAudioContext;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::AnalyserNodeBinding::{self, AnalyserNodeMethods, AnalyserOptions};
+use dom::bindings::codegen::Bindings::AudioNodeBinding::{ChannelCountMode, ChannelInterpretation};
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::num::Finite;
+use dom::bindings::refcounted::Trusted;
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::DomRoot;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use ipc_channel::ipc::{self, IpcReceiver};
+use ipc_channel::router::ROUTER;
+use js::rust::CustomAutoRooterGuard;
+use js::typedarray::{Float32Array, Uint8Array};
+use servo_media::audio::analyser_node::AnalysisEngine;
+use servo_media::audio::block::Block;
+use servo_media::audio::node::AudioNodeInit;
+use task_source::{TaskSource, TaskSourceName};
+
+// https://webaudio.github.io/web-audio-api/#dom-analysernode-fftsize
+const MIN_FFT_SIZE: u32 = 32;
+const MAX_FFT_SIZE: u32 = 32768;
+
+#[dom_struct]
+pub struct AnalyserNode {
+    node: AudioNode,
+    /// Performs the analysis of the blocks rendered through this node. It
+    /// lives on the script thread so that the results only depend on the
+    /// blocks received so far.
+    #[ignore_malloc_size_of = "servo_media"]
+    engine: DomRefCell<AnalysisEngine>,
+}
+
+impl AnalyserNode {
+    #[allow(unrooted_must_root)]
+    pub fn new_inherited(
+        context: &BaseAudioContext,
+        options: &AnalyserOptions,
+    ) -> Fallible<(AnalyserNode, IpcReceiver<Block>)> {
+        validate_fft_size(options.fftSize)?;
+        validate_decibels(*options.minDecibels, *options.maxDecibels)?;
+        validate_smoothing_time_constant(*options.smoothingTimeConstant)?;
+
+        let node_options = options.parent.unwrap_or(
+            2,
+            ChannelCountMode::Max,
+            ChannelInterpretation::Speakers,
+        );
+
+        // The rendering thread hands over a copy of every block it renders
+        // through this node.
+        let (sender, receiver) = ipc::channel().unwrap();
+        let callback = move |block| {
+            let _ = sender.send(block);
+        };
+
+        let node = AudioNode::new_inherited(
+            AudioNodeInit::AnalyserNode(Box::new(callback)),
+            None,
+            context,
+            &node_options,
+            1, // inputs
+            1, // outputs
+        );
+        let engine = AnalysisEngine::new(
+            options.fftSize as usize,
+            *options.smoothingTimeConstant,
+            *options.minDecibels,
+            *options.maxDecibels,
+        );
+        Ok((
+            AnalyserNode {
+                node,
+                engine: DomRefCell::new(engine),
+            },
+            receiver,
+        ))
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &AnalyserOptions,
+    ) -> Fallible<DomRoot<AnalyserNode>> {
+        let (node, receiver) = AnalyserNode::new_inherited(context, options)?;
+        let object = reflect_dom_object(Box::new(node), window, AnalyserNodeBinding::Wrap);
+        let task_source = window.dom_manipulation_task_source();
+        let canceller = window.task_canceller(TaskSourceName::DOMManipulation);
+        let this = Trusted::new(&*object);
+
+        ROUTER.add_route(
+            receiver.to_opaque(),
+            Box::new(move |message| {
+                let this = this.clone();
+                let block = message.to().unwrap();
+                let _ = task_source.queue_with_canceller(
+                    task!(append_analysis_block: move || {
+                        this.root().push_block(block);
+                    }),
+                    &canceller,
+                );
+            }),
+        );
+        Ok(object)
+    }
+
+    pub fn Constructor(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &AnalyserOptions,
+    ) -> Fallible<DomRoot<AnalyserNode>> {
+        AnalyserNode::new(window, context, options)
+    }
+
+    fn push_block(&self, block: Block) {
+        self.engine.borrow_mut().push(block);
+    }
+}
+
+fn validate_fft_size(fft_size: u32) -> ErrorResult {
+    if fft_size < MIN_FFT_SIZE || fft_size > MAX_FFT_SIZE || !fft_size.is_power_of_two() {
+        return Err(Error::IndexSize);
+    }
+    Ok(())
+}
+
+fn validate_decibels(min_decibels: f64, max_decibels: f64) -> ErrorResult {
+    if min_decibels >= max_decibels {
+        return Err(Error::IndexSize);
+    }
+    Ok(())
+}
+
+fn validate_smoothing_time_constant(value: f64) -> ErrorResult {
+    if value < 0. || value > 1. {
+        return Err(Error::IndexSize);
+    }
+    Ok(())
+}
+
+impl AnalyserNodeMethods for AnalyserNode {
+    // https://webaudio.github.io/web-audio-api/#dom-analysernode-getfloatfrequencydata
+    #[allow(unsafe_code)]
+    fn GetFloatFrequencyData(&self, mut array: CustomAutoRooterGuard<Float32Array>) {
+        let dest = unsafe { array.as_mut_slice() };
+        self.engine.borrow_mut().fill_frequency_data(dest);
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-analysernode-getbytefrequencydata
+    #[allow(unsafe_code)]
+    fn GetByteFrequencyData(&self, mut array: CustomAutoRooterGuard<Uint8Array>) {
+        let dest = unsafe { array.as_mut_slice() };
+        self.engine.borrow_mut().fill_byte_frequency_data(dest);
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-analysernode-getfloattimedomaindata
+    #[allow(unsafe_code)]
+    fn GetFloatTimeDomainData(&self, mut array: CustomAutoRooterGuard<Float32Array>) {
+        let dest = unsafe { array.as_mut_slice() };
+        self.engine.borrow().fill_time_domain_data(dest);
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-analysernode-getbytetimedomaindata
+    #[allow(unsafe_code)]
+    fn GetByteTimeDomainData(&self, mut array: CustomAutoRooterGuard<Uint8Array>) {
+        let dest = unsafe { array.as_mut_slice() };
+        self.engine.borrow().fill_byte_time_domain_data(dest);
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-analysernode-fftsize
+    fn FftSize(&self) -> u32 {
+        self.engine.borrow().get_fft_size() as u32
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-analysernode-fftsize
+    fn SetFftSize(&self, value: u32) -> ErrorResult {
+        validate_fft_size(value)?;
+        self.engine.borrow_mut().set_fft_size(value as usize);
+        Ok(())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-analysernode-frequencybincount
+    fn FrequencyBinCount(&self) -> u32 {
+        self.FftSize() / 2
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-analysernode-mindecibels
+    fn MinDecibels(&self) -> Finite<f64> {
+        Finite::wrap(self.engine.borrow().get_min_decibels())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-analysernode-mindecibels
+    fn SetMinDecibels(&self, value: Finite<f64>) -> ErrorResult {
+        validate_decibels(*value, self.engine.borrow().get_max_decibels())?;
+        self.engine.borrow_mut().set_min_decibels(*value);
+        Ok(())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-analysernode-maxdecibels
+    fn MaxDecibels(&self) -> Finite<f64> {
+        Finite::wrap(self.engine.borrow().get_max_decibels())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-analysernode-maxdecibels
+    fn SetMaxDecibels(&self, value: Finite<f64>) -> ErrorResult {
+        validate_decibels(self.engine.borrow().get_min_decibels(), *value)?;
+        self.engine.borrow_mut().set_max_decibels(*value);
+        Ok(())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-analysernode-smoothingtimeconstant
+    fn SmoothingTimeConstant(&self) -> Finite<f64> {
+        Finite::wrap(self.engine.borrow().get_smoothing_constant())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-analysernode-smoothingtimeconstant
+    fn SetSmoothingTimeConstant(&self, value: Finite<f64>) -> ErrorResult {
+        validate_smoothing_time_constant(*value)?;
+        self.engine.borrow_mut().set_smoothing_constant(*value);
+        Ok(())
+    }
+}
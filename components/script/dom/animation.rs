@@ -0,0 +1,947 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::animationeffect::{AnimationEffect, Timing};
+use dom::animationplaybackevent::AnimationPlaybackEvent;
+use dom::animationtimeline::AnimationTimeline;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::AnimationBinding::{self, AnimationMethods, AnimationPlayState};
+use dom::bindings::codegen::Bindings::AnimationEffectBinding::FillMode;
+use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
+use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::num::Finite;
+use dom::bindings::refcounted::Trusted;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{DomRoot, MutNullableDom};
+use dom::bindings::str::DOMString;
+use dom::document::Document;
+use dom::element::Element;
+use dom::event::Event;
+use dom::eventtarget::EventTarget;
+use dom::globalscope::GlobalScope;
+use dom::keyframeeffect::KeyframeEffect;
+use dom::promise::Promise;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use microtask::{Microtask, MicrotaskRunnable};
+use script_layout_interface::rpc::RunningCSSAnimation;
+use script_thread::ScriptThread;
+use servo_atoms::Atom;
+use std::cell::Cell;
+use std::f64;
+use std::rc::Rc;
+use style::animation::ScriptAnimationSample;
+use task_source::{TaskSource, TaskSourceName};
+
+/// The task an animation waits to run until it is ready.
+///
+/// <https://drafts.csswg.org/web-animations/#pending-play-task>
+/// <https://drafts.csswg.org/web-animations/#pending-pause-task>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+enum PendingTask {
+    None,
+    Play,
+    Pause,
+}
+
+/// <https://drafts.csswg.org/web-animations/#the-animation-interface>
+#[dom_struct]
+pub struct Animation {
+    eventtarget: EventTarget,
+    id: DomRefCell<DOMString>,
+    effect: MutNullableDom<AnimationEffect>,
+    timeline: MutNullableDom<AnimationTimeline>,
+    /// <https://drafts.csswg.org/web-animations/#animation-start-time>
+    start_time: Cell<Option<f64>>,
+    /// <https://drafts.csswg.org/web-animations/#animation-hold-time>
+    hold_time: Cell<Option<f64>>,
+    /// <https://drafts.csswg.org/web-animations/#previous-current-time>
+    previous_current_time: Cell<Option<f64>>,
+    /// <https://drafts.csswg.org/web-animations/#playback-rate>
+    playback_rate: Cell<f64>,
+    /// <https://drafts.csswg.org/web-animations/#pending-playback-rate>
+    pending_playback_rate: Cell<Option<f64>>,
+    pending_task: Cell<PendingTask>,
+    /// <https://drafts.csswg.org/web-animations/#current-ready-promise>
+    #[ignore_malloc_size_of = "Rc"]
+    ready_promise: DomRefCell<Rc<Promise>>,
+    /// <https://drafts.csswg.org/web-animations/#current-finished-promise>
+    #[ignore_malloc_size_of = "Rc"]
+    finished_promise: DomRefCell<Rc<Promise>>,
+    /// Incremented whenever the pending finish notification microtask is
+    /// canceled, so that a stale one does nothing when it runs.
+    finish_notification_generation: Cell<u32>,
+}
+
+impl Animation {
+    pub fn new_inherited(ready_promise: Rc<Promise>, finished_promise: Rc<Promise>) -> Animation {
+        Animation {
+            eventtarget: EventTarget::new_inherited(),
+            id: DomRefCell::new(DOMString::new()),
+            effect: MutNullableDom::new(None),
+            timeline: MutNullableDom::new(None),
+            start_time: Cell::new(None),
+            hold_time: Cell::new(None),
+            previous_current_time: Cell::new(None),
+            playback_rate: Cell::new(1.),
+            pending_playback_rate: Cell::new(None),
+            pending_task: Cell::new(PendingTask::None),
+            ready_promise: DomRefCell::new(ready_promise),
+            finished_promise: DomRefCell::new(finished_promise),
+            finish_notification_generation: Cell::new(0),
+        }
+    }
+
+    pub fn new(window: &Window,
+               effect: Option<&AnimationEffect>,
+               timeline: Option<&AnimationTimeline>)
+               -> DomRoot<Animation> {
+        let global = window.upcast::<GlobalScope>();
+        let animation = Animation::new_inherited(Promise::new(global), Promise::new(global));
+        let animation = reflect_dom_object(Box::new(animation), window, AnimationBinding::Wrap);
+        animation.init(effect, timeline);
+        animation
+    }
+
+    /// Runs the steps of the `Animation()` constructor that follow the
+    /// creation of `animation`, which is also used for the CSS animations and
+    /// transitions reflected to script.
+    ///
+    /// <https://drafts.csswg.org/web-animations/#dom-animation-animation>
+    pub fn init(&self, effect: Option<&AnimationEffect>, timeline: Option<&AnimationTimeline>) {
+        self.ready_promise.borrow().resolve_native(&DomRoot::from_ref(self));
+        self.set_timeline(timeline);
+        self.set_effect(effect);
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-animation
+    pub fn Constructor(window: &Window,
+                       effect: Option<&AnimationEffect>,
+                       timeline: Option<Option<&AnimationTimeline>>)
+                       -> Fallible<DomRoot<Animation>> {
+        let default_timeline;
+        let timeline = match timeline {
+            Some(timeline) => timeline,
+            None => {
+                default_timeline = window.Document().timeline();
+                Some(default_timeline.upcast::<AnimationTimeline>())
+            },
+        };
+        Ok(Animation::new(window, effect, timeline))
+    }
+
+    fn document(&self) -> DomRoot<Document> {
+        self.global().as_window().Document()
+    }
+
+    pub fn effect(&self) -> Option<DomRoot<AnimationEffect>> {
+        self.effect.get()
+    }
+
+    /// The target of the keyframe effect of this animation, if any.
+    pub fn target(&self) -> Option<DomRoot<Element>> {
+        self.effect.get()
+            .and_then(|effect| effect.downcast::<KeyframeEffect>().and_then(|effect| effect.target()))
+    }
+
+    /// Samples the keyframe effect of this animation, if it is in effect.
+    pub fn sample(&self) -> Option<(DomRoot<Element>, ScriptAnimationSample)> {
+        self.effect.get().and_then(|effect| effect.downcast::<KeyframeEffect>().and_then(|effect| effect.sample()))
+    }
+
+    pub fn playback_rate(&self) -> f64 {
+        self.playback_rate.get()
+    }
+
+    pub fn has_pending_task(&self) -> bool {
+        self.pending_task.get() != PendingTask::None
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#relevant-animation>
+    pub fn is_relevant(&self) -> bool {
+        self.effect.get().map_or(false, |effect| effect.is_current() || effect.is_in_effect())
+    }
+
+    /// Whether this animation needs to be sampled at every animation frame.
+    pub fn is_ticking(&self) -> bool {
+        self.has_pending_task() || self.play_state() == AnimationPlayState::Running
+    }
+
+    /// Updates a CSS animation or transition reflected to script with its
+    /// state in layout, which reports times in seconds.
+    pub fn update_from_layout(&self, running: &RunningCSSAnimation) {
+        if let Some(effect) = self.effect.get() {
+            effect.set_timing(Timing {
+                delay: running.delay * 1000.,
+                fill: FillMode::Both,
+                iterations: running.iterations,
+                duration: Some(running.duration * 1000.),
+                ..Timing::new()
+            });
+        }
+        let current_time = running.elapsed * 1000.;
+        self.pending_task.set(PendingTask::None);
+        match self.timeline_time() {
+            Some(timeline_time) if !running.paused => {
+                self.start_time.set(Some(timeline_time - current_time));
+                self.hold_time.set(None);
+            },
+            _ => {
+                self.start_time.set(None);
+                self.hold_time.set(Some(current_time));
+            },
+        }
+    }
+
+    /// Lets the document know that the state of this animation changed, so
+    /// that its effect gets updated.
+    fn update_rendering(&self) {
+        self.document().register_animation(self);
+    }
+
+    /// Called by the effect of this animation when its timing, target or
+    /// keyframes changed.
+    pub fn effect_changed(&self) {
+        self.update_finished_state(false, false);
+        self.update_rendering();
+    }
+
+    fn timeline_time(&self) -> Option<f64> {
+        self.timeline.get().and_then(|timeline| timeline.current_time())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#associated-effect-end>
+    fn effect_end(&self) -> f64 {
+        self.effect.get().map_or(0., |effect| effect.end_time())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#animation-current-time>
+    pub fn current_time(&self) -> Option<f64> {
+        if let Some(hold_time) = self.hold_time.get() {
+            return Some(hold_time);
+        }
+        let timeline_time = self.timeline_time()?;
+        let start_time = self.start_time.get()?;
+        Some((timeline_time - start_time) * self.playback_rate.get())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#play-states>
+    pub fn play_state(&self) -> AnimationPlayState {
+        let current_time = self.current_time();
+        let pending_task = self.pending_task.get();
+        if current_time.is_none() && self.start_time.get().is_none() && pending_task == PendingTask::None {
+            return AnimationPlayState::Idle;
+        }
+        if pending_task == PendingTask::Pause ||
+            (self.start_time.get().is_none() && pending_task != PendingTask::Play) {
+            return AnimationPlayState::Paused;
+        }
+        if let Some(current_time) = current_time {
+            let playback_rate = self.playback_rate.get();
+            if (playback_rate > 0. && current_time >= self.effect_end()) ||
+                (playback_rate < 0. && current_time <= 0.) {
+                return AnimationPlayState::Finished;
+            }
+        }
+        AnimationPlayState::Running
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#setting-the-timeline>
+    fn set_timeline(&self, new_timeline: Option<&AnimationTimeline>) {
+        // Steps 1-2.
+        let old_timeline = self.timeline.get();
+        if old_timeline.as_ref().map(|timeline| &**timeline) == new_timeline {
+            return;
+        }
+        let previous_play_state = self.play_state();
+        let previous_current_time = self.current_time();
+
+        // Step 3.
+        self.timeline.set(new_timeline);
+
+        // Step 4.
+        if self.start_time.get().is_some() {
+            self.hold_time.set(None);
+        }
+
+        // Step 5.
+        match previous_play_state {
+            AnimationPlayState::Finished | AnimationPlayState::Running => {
+                if let Some(previous_current_time) = previous_current_time {
+                    let _ = self.silently_set_current_time(Some(previous_current_time));
+                }
+            },
+            _ => {},
+        }
+        self.update_finished_state(false, false);
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#setting-the-associated-effect>
+    fn set_effect(&self, new_effect: Option<&AnimationEffect>) {
+        // Steps 1-2.
+        let old_effect = self.effect.get();
+        if old_effect.as_ref().map(|effect| &**effect) == new_effect {
+            return;
+        }
+
+        // Step 3.
+        if let Some(new_effect) = new_effect {
+            if let Some(animation) = new_effect.animation() {
+                animation.set_effect(None);
+            }
+        }
+
+        // Steps 4-5.
+        if let Some(old_effect) = old_effect {
+            old_effect.set_animation(None);
+        }
+        self.effect.set(new_effect);
+        if let Some(new_effect) = new_effect {
+            new_effect.set_animation(Some(self));
+        }
+
+        // Step 6.
+        self.update_finished_state(false, false);
+        self.update_rendering();
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#silently-set-the-current-time>
+    fn silently_set_current_time(&self, seek_time: Option<f64>) -> ErrorResult {
+        // Step 1.
+        let seek_time = match seek_time {
+            Some(seek_time) => seek_time,
+            None => {
+                if self.current_time().is_some() {
+                    return Err(Error::Type("the current time cannot be unset".to_owned()));
+                }
+                return Ok(());
+            },
+        };
+
+        // Step 2.
+        let timeline_time = self.timeline_time();
+        if self.hold_time.get().is_some() || self.start_time.get().is_none() ||
+            timeline_time.is_none() || self.playback_rate.get() == 0. {
+            self.hold_time.set(Some(seek_time));
+        } else {
+            self.start_time.set(Some(timeline_time.unwrap() - seek_time / self.playback_rate.get()));
+        }
+
+        // Step 3.
+        if timeline_time.is_none() {
+            self.start_time.set(None);
+        }
+
+        // Step 4.
+        self.previous_current_time.set(None);
+        Ok(())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#set-the-current-time>
+    fn set_current_time(&self, seek_time: Option<f64>) -> ErrorResult {
+        // Step 1.
+        self.silently_set_current_time(seek_time)?;
+
+        // Step 2.
+        if self.pending_task.get() == PendingTask::Pause {
+            self.hold_time.set(seek_time);
+            self.apply_pending_playback_rate();
+            self.start_time.set(None);
+            self.pending_task.set(PendingTask::None);
+            self.resolve_ready_promise();
+        }
+
+        // Step 3.
+        self.update_finished_state(true, false);
+        self.update_rendering();
+        Ok(())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#set-the-start-time>
+    fn set_start_time(&self, new_start_time: Option<f64>) {
+        // Steps 1-2.
+        let timeline_time = self.timeline_time();
+        if timeline_time.is_none() && new_start_time.is_some() {
+            self.hold_time.set(None);
+        }
+
+        // Step 3.
+        let previous_current_time = self.current_time();
+
+        // Steps 4-5.
+        self.apply_pending_playback_rate();
+        self.start_time.set(new_start_time);
+
+        // Step 6.
+        if new_start_time.is_some() {
+            if self.playback_rate.get() != 0. {
+                self.hold_time.set(None);
+            }
+        } else {
+            self.hold_time.set(previous_current_time);
+        }
+
+        // Step 7.
+        if self.has_pending_task() {
+            self.pending_task.set(PendingTask::None);
+            self.resolve_ready_promise();
+        }
+
+        // Step 8.
+        self.update_finished_state(true, false);
+        self.update_rendering();
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#play-an-animation>
+    fn play(&self, auto_rewind: bool) -> ErrorResult {
+        // Step 1.
+        let aborted_pause = self.pending_task.get() == PendingTask::Pause;
+
+        // Step 2.
+        let mut has_pending_ready_promise = false;
+
+        // Steps 3-4.
+        let mut seek_time = None;
+        let playback_rate = self.effective_playback_rate();
+        let current_time = self.current_time();
+        let end = self.effect_end();
+        if playback_rate > 0. && auto_rewind &&
+            current_time.map_or(true, |time| time < 0. || time >= end) {
+            seek_time = Some(0.);
+        } else if playback_rate < 0. && auto_rewind &&
+            current_time.map_or(true, |time| time <= 0. || time > end) {
+            if end == f64::INFINITY {
+                return Err(Error::InvalidState);
+            }
+            seek_time = Some(end);
+        } else if playback_rate == 0. && current_time.is_none() {
+            seek_time = Some(0.);
+        }
+
+        // Step 5.
+        if seek_time.is_some() {
+            self.hold_time.set(seek_time);
+        }
+
+        // Step 6.
+        if self.hold_time.get().is_some() {
+            self.start_time.set(None);
+        }
+
+        // Step 7.
+        if self.has_pending_task() {
+            self.pending_task.set(PendingTask::None);
+            has_pending_ready_promise = true;
+        }
+
+        // Step 8.
+        if self.hold_time.get().is_none() && seek_time.is_none() && !aborted_pause &&
+            self.pending_playback_rate.get().is_none() {
+            return Ok(());
+        }
+
+        // Step 9.
+        if !has_pending_ready_promise {
+            *self.ready_promise.borrow_mut() = Promise::new(&self.global());
+        }
+
+        // Step 10.
+        self.pending_task.set(PendingTask::Play);
+
+        // Step 11.
+        self.update_finished_state(false, false);
+        self.update_rendering();
+        Ok(())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#pause-an-animation>
+    fn pause(&self) -> ErrorResult {
+        // Steps 1-2.
+        if self.pending_task.get() == PendingTask::Pause || self.play_state() == AnimationPlayState::Paused {
+            return Ok(());
+        }
+
+        // Step 3.
+        if self.current_time().is_none() {
+            if self.playback_rate.get() >= 0. {
+                self.hold_time.set(Some(0.));
+            } else {
+                let end = self.effect_end();
+                if end == f64::INFINITY {
+                    return Err(Error::InvalidState);
+                }
+                self.hold_time.set(Some(end));
+            }
+        }
+
+        // Steps 4-5.
+        let has_pending_ready_promise = self.pending_task.get() == PendingTask::Play;
+
+        // Step 6.
+        if !has_pending_ready_promise {
+            *self.ready_promise.borrow_mut() = Promise::new(&self.global());
+        }
+
+        // Step 7.
+        self.pending_task.set(PendingTask::Pause);
+
+        // Step 8.
+        self.update_finished_state(false, false);
+        self.update_rendering();
+        Ok(())
+    }
+
+    /// Runs the pending play or pause task of this animation, if any, now
+    /// that it is ready, then updates its finished state. Called at every
+    /// animation frame.
+    ///
+    /// <https://drafts.csswg.org/web-animations/#update-animations-and-send-events>
+    pub fn tick(&self) {
+        if let Some(ready_time) = self.timeline_time() {
+            match self.pending_task.get() {
+                PendingTask::Play => self.run_pending_play_task(ready_time),
+                PendingTask::Pause => self.run_pending_pause_task(ready_time),
+                PendingTask::None => {},
+            }
+        }
+        self.update_finished_state(false, false);
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#pending-play-task>
+    fn run_pending_play_task(&self, ready_time: f64) {
+        self.pending_task.set(PendingTask::None);
+
+        // Step 2.
+        if let Some(hold_time) = self.hold_time.get() {
+            self.apply_pending_playback_rate();
+            let playback_rate = self.playback_rate.get();
+            if playback_rate == 0. {
+                self.start_time.set(Some(ready_time));
+            } else {
+                self.start_time.set(Some(ready_time - hold_time / playback_rate));
+                self.hold_time.set(None);
+            }
+        } else if let (Some(start_time), Some(pending_playback_rate)) =
+            (self.start_time.get(), self.pending_playback_rate.get()) {
+            // Step 3.
+            let current_time_to_match = (ready_time - start_time) * self.playback_rate.get();
+            self.playback_rate.set(pending_playback_rate);
+            self.pending_playback_rate.set(None);
+            if pending_playback_rate == 0. {
+                self.hold_time.set(Some(current_time_to_match));
+            } else {
+                self.start_time.set(Some(ready_time - current_time_to_match / pending_playback_rate));
+            }
+        }
+
+        // Steps 4-5.
+        self.resolve_ready_promise();
+        self.update_finished_state(false, false);
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#pending-pause-task>
+    fn run_pending_pause_task(&self, ready_time: f64) {
+        self.pending_task.set(PendingTask::None);
+
+        // Step 2.
+        if let (Some(start_time), None) = (self.start_time.get(), self.hold_time.get()) {
+            self.hold_time.set(Some((ready_time - start_time) * self.playback_rate.get()));
+        }
+
+        // Steps 3-4.
+        self.apply_pending_playback_rate();
+        self.start_time.set(None);
+
+        // Steps 5-6.
+        self.resolve_ready_promise();
+        self.update_finished_state(false, false);
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#effective-playback-rate>
+    fn effective_playback_rate(&self) -> f64 {
+        self.pending_playback_rate.get().unwrap_or(self.playback_rate.get())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#apply-any-pending-playback-rate>
+    fn apply_pending_playback_rate(&self) {
+        if let Some(pending_playback_rate) = self.pending_playback_rate.take() {
+            self.playback_rate.set(pending_playback_rate);
+        }
+    }
+
+    fn resolve_ready_promise(&self) {
+        self.ready_promise.borrow().resolve_native(&DomRoot::from_ref(self));
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#update-an-animations-finished-state>
+    fn update_finished_state(&self, did_seek: bool, synchronously_notify: bool) {
+        // Step 1.
+        let unconstrained_current_time = if did_seek {
+            self.current_time()
+        } else {
+            match (self.timeline_time(), self.start_time.get()) {
+                (Some(timeline_time), Some(start_time)) => {
+                    Some((timeline_time - start_time) * self.playback_rate.get())
+                },
+                _ => None,
+            }
+        };
+
+        // Step 2.
+        if let (Some(unconstrained_current_time), Some(_)) = (unconstrained_current_time, self.start_time.get()) {
+            if !self.has_pending_task() {
+                let playback_rate = self.playback_rate.get();
+                let end = self.effect_end();
+                if playback_rate > 0. && unconstrained_current_time >= end {
+                    if did_seek {
+                        self.hold_time.set(Some(unconstrained_current_time));
+                    } else {
+                        let previous = self.previous_current_time.get().unwrap_or(f64::NEG_INFINITY);
+                        self.hold_time.set(Some(previous.max(end)));
+                    }
+                } else if playback_rate < 0. && unconstrained_current_time <= 0. {
+                    if did_seek {
+                        self.hold_time.set(Some(unconstrained_current_time));
+                    } else {
+                        let previous = self.previous_current_time.get().unwrap_or(f64::INFINITY);
+                        self.hold_time.set(Some(previous.min(0.)));
+                    }
+                } else if playback_rate != 0. {
+                    if let (true, Some(hold_time), Some(timeline_time)) =
+                        (did_seek, self.hold_time.get(), self.timeline_time()) {
+                        self.start_time.set(Some(timeline_time - hold_time / playback_rate));
+                    }
+                    self.hold_time.set(None);
+                }
+            }
+        }
+
+        // Step 3.
+        self.previous_current_time.set(self.current_time());
+
+        // Step 4.
+        let current_finished_state = self.play_state() == AnimationPlayState::Finished;
+        let finished_promise_resolved = self.finished_promise.borrow().is_fulfilled();
+
+        // Step 5.
+        if current_finished_state && !finished_promise_resolved {
+            let generation = self.finish_notification_generation.get() + 1;
+            self.finish_notification_generation.set(generation);
+            if synchronously_notify {
+                self.finish_notification_steps();
+            } else {
+                ScriptThread::enqueue_microtask(Microtask::Animation(AnimationMicrotask::FinishNotificationSteps {
+                    animation: DomRoot::from_ref(self),
+                    generation: generation,
+                }));
+            }
+        }
+
+        // Step 6.
+        if !current_finished_state && finished_promise_resolved {
+            *self.finished_promise.borrow_mut() = Promise::new(&self.global());
+        }
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#finish-notification-steps>
+    fn finish_notification_steps(&self) {
+        // Step 1.
+        if self.play_state() != AnimationPlayState::Finished {
+            return;
+        }
+
+        // Step 2.
+        self.finished_promise.borrow().resolve_native(&DomRoot::from_ref(self));
+
+        // Steps 3-4.
+        self.queue_playback_event(atom!("finish"), self.current_time());
+    }
+
+    fn queue_playback_event(&self, name: Atom, current_time: Option<f64>) {
+        let global = self.global();
+        let window = global.as_window();
+        let event = AnimationPlaybackEvent::new(window, name, false, false, current_time, self.timeline_time());
+        let event = Trusted::new(&*event);
+        let target = Trusted::new(self.upcast::<EventTarget>());
+        let _ = window.dom_manipulation_task_source().queue_with_canceller(
+            task!(fire_animation_playback_event: move || {
+                event.root().upcast::<Event>().fire(&target.root());
+            }),
+            &window.task_canceller(TaskSourceName::DOMManipulation),
+        );
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#reset-an-animations-pending-tasks>
+    fn reset_pending_tasks(&self) {
+        // Step 1.
+        if !self.has_pending_task() {
+            return;
+        }
+
+        // Steps 2-4.
+        self.pending_task.set(PendingTask::None);
+        self.apply_pending_playback_rate();
+
+        // Steps 5-6.
+        self.ready_promise.borrow().reject_error(Error::Abort);
+        let promise = Promise::new(&self.global());
+        promise.resolve_native(&DomRoot::from_ref(self));
+        *self.ready_promise.borrow_mut() = promise;
+    }
+}
+
+impl AnimationMethods for Animation {
+    // https://drafts.csswg.org/web-animations/#dom-animation-id
+    fn Id(&self) -> DOMString {
+        self.id.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-id
+    fn SetId(&self, id: DOMString) {
+        *self.id.borrow_mut() = id;
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-effect
+    fn GetEffect(&self) -> Option<DomRoot<AnimationEffect>> {
+        self.effect.get()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-effect
+    fn SetEffect(&self, effect: Option<&AnimationEffect>) {
+        self.set_effect(effect);
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-timeline
+    fn GetTimeline(&self) -> Option<DomRoot<AnimationTimeline>> {
+        self.timeline.get()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-timeline
+    fn SetTimeline(&self, timeline: Option<&AnimationTimeline>) {
+        self.set_timeline(timeline);
+        self.update_rendering();
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-starttime
+    fn GetStartTime(&self) -> Option<Finite<f64>> {
+        self.start_time.get().map(Finite::wrap)
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-starttime
+    fn SetStartTime(&self, start_time: Option<Finite<f64>>) {
+        self.set_start_time(start_time.map(|time| *time));
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-currenttime
+    fn GetCurrentTime(&self) -> Option<Finite<f64>> {
+        self.current_time().map(Finite::wrap)
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-currenttime
+    fn SetCurrentTime(&self, current_time: Option<Finite<f64>>) -> ErrorResult {
+        self.set_current_time(current_time.map(|time| *time))
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-playbackrate
+    fn PlaybackRate(&self) -> Finite<f64> {
+        Finite::wrap(self.playback_rate.get())
+    }
+
+    // https://drafts.csswg.org/web-animations/#set-the-playback-rate
+    fn SetPlaybackRate(&self, playback_rate: Finite<f64>) {
+        // Step 1.
+        self.pending_playback_rate.set(None);
+
+        // Step 2.
+        let previous_time = self.current_time();
+
+        // Step 3.
+        self.playback_rate.set(*playback_rate);
+
+        // Step 4.
+        if previous_time.is_some() {
+            let _ = self.set_current_time(previous_time);
+        }
+        self.update_rendering();
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-playstate
+    fn PlayState(&self) -> AnimationPlayState {
+        self.play_state()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-pending
+    fn Pending(&self) -> bool {
+        self.has_pending_task()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-ready
+    fn Ready(&self) -> Rc<Promise> {
+        self.ready_promise.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-finished
+    fn Finished(&self) -> Rc<Promise> {
+        self.finished_promise.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-onfinish
+    event_handler!(finish, GetOnfinish, SetOnfinish);
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-oncancel
+    event_handler!(cancel, GetOncancel, SetOncancel);
+
+    // https://drafts.csswg.org/web-animations/#cancel-an-animation
+    fn Cancel(&self) {
+        // Step 1.
+        if self.play_state() != AnimationPlayState::Idle {
+            // Step 1.1.
+            self.reset_pending_tasks();
+
+            // Steps 1.2-1.3.
+            self.finished_promise.borrow().reject_error(Error::Abort);
+            *self.finished_promise.borrow_mut() = Promise::new(&self.global());
+
+            // Steps 1.4-1.6.
+            self.queue_playback_event(atom!("cancel"), None);
+        }
+
+        // Steps 2-3.
+        self.hold_time.set(None);
+        self.start_time.set(None);
+        self.update_rendering();
+    }
+
+    // https://drafts.csswg.org/web-animations/#finish-an-animation
+    fn Finish(&self) -> ErrorResult {
+        // Step 1.
+        let playback_rate = self.effective_playback_rate();
+        let end = self.effect_end();
+        if playback_rate == 0. || (playback_rate > 0. && end == f64::INFINITY) {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 2.
+        self.apply_pending_playback_rate();
+
+        // Steps 3-4.
+        let limit = if playback_rate > 0. { end } else { 0. };
+        self.silently_set_current_time(Some(limit))?;
+
+        // Step 5.
+        if self.start_time.get().is_none() {
+            if let Some(timeline_time) = self.timeline_time() {
+                self.start_time.set(Some(timeline_time - limit / playback_rate));
+            }
+        }
+
+        // Step 6.
+        if self.pending_task.get() == PendingTask::Pause && self.start_time.get().is_some() {
+            self.hold_time.set(None);
+            self.pending_task.set(PendingTask::None);
+            self.resolve_ready_promise();
+        }
+
+        // Step 7.
+        if self.pending_task.get() == PendingTask::Play && self.start_time.get().is_some() {
+            self.pending_task.set(PendingTask::None);
+            self.resolve_ready_promise();
+        }
+
+        // Step 8.
+        self.update_finished_state(true, true);
+        self.update_rendering();
+        Ok(())
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-play
+    fn Play(&self) -> ErrorResult {
+        self.play(true)
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-pause
+    fn Pause(&self) -> ErrorResult {
+        self.pause()
+    }
+
+    // https://drafts.csswg.org/web-animations/#seamlessly-update-the-playback-rate
+    fn UpdatePlaybackRate(&self, playback_rate: Finite<f64>) {
+        // Step 1.
+        let previous_play_state = self.play_state();
+
+        // Step 2.
+        self.pending_playback_rate.set(Some(*playback_rate));
+
+        // Step 3.
+        if self.has_pending_task() {
+            return;
+        }
+        match previous_play_state {
+            AnimationPlayState::Idle | AnimationPlayState::Paused => self.apply_pending_playback_rate(),
+            AnimationPlayState::Finished => {
+                let unconstrained_current_time = match (self.timeline_time(), self.start_time.get()) {
+                    (Some(timeline_time), Some(start_time)) => {
+                        Some((timeline_time - start_time) * self.playback_rate.get())
+                    },
+                    _ => None,
+                };
+                if let Some(timeline_time) = self.timeline_time() {
+                    if *playback_rate == 0. {
+                        self.start_time.set(Some(timeline_time));
+                    } else if let Some(unconstrained_current_time) = unconstrained_current_time {
+                        self.start_time.set(Some(timeline_time - unconstrained_current_time / *playback_rate));
+                    }
+                }
+                self.apply_pending_playback_rate();
+                self.update_finished_state(false, false);
+            },
+            AnimationPlayState::Running => {
+                let _ = self.play(false);
+            },
+        }
+        self.update_rendering();
+    }
+
+    // https://drafts.csswg.org/web-animations/#reverse-an-animation
+    fn Reverse(&self) -> ErrorResult {
+        // Step 1.
+        if self.timeline_time().is_none() {
+            return Err(Error::InvalidState);
+        }
+
+        // Steps 2-3.
+        let original_pending_playback_rate = self.pending_playback_rate.get();
+        self.pending_playback_rate.set(Some(-self.effective_playback_rate()));
+
+        // Step 4.
+        let result = self.play(true);
+        if result.is_err() {
+            self.pending_playback_rate.set(original_pending_playback_rate);
+        }
+        result
+    }
+}
+
+#[derive(JSTraceable, MallocSizeOf)]
+pub enum AnimationMicrotask {
+    FinishNotificationSteps {
+        animation: DomRoot<Animation>,
+        generation: u32,
+    }
+}
+
+impl MicrotaskRunnable for AnimationMicrotask {
+    fn handler(&self) {
+        match self {
+            &AnimationMicrotask::FinishNotificationSteps { ref animation, generation } => {
+                // A newer notification supersedes this one.
+                if animation.finish_notification_generation.get() == generation {
+                    animation.finish_notification_steps();
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,434 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use cssparser::{Parser, ParserInput};
+use dom::animation::Animation;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::AnimationEffectBinding::{AnimationEffectMethods, ComputedEffectTiming};
+use dom::bindings::codegen::Bindings::AnimationEffectBinding::{EffectTiming, FillMode, OptionalEffectTiming};
+use dom::bindings::codegen::Bindings::AnimationEffectBinding::PlaybackDirection;
+use dom::bindings::codegen::UnionTypes::UnrestrictedDoubleOrString;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::num::Finite;
+use dom::bindings::reflector::{DomObject, Reflector};
+use dom::bindings::root::{DomRoot, MutNullableDom};
+use dom::bindings::str::DOMString;
+use dom_struct::dom_struct;
+use servo_url::ServoUrl;
+use std::f64;
+use style::animation::apply_timing_function;
+use style::context::QuirksMode;
+use style::parser::{Parse, ParserContext};
+use style::stylesheets::CssRuleType;
+use style::values::computed::TimingFunction;
+use style::values::generics::transform::TimingKeyword;
+use style::values::specified::TimingFunction as SpecifiedTimingFunction;
+use style_traits::{ParsingMode, ToCss};
+
+/// The timing properties of an animation effect.
+///
+/// <https://drafts.csswg.org/web-animations/#the-effecttiming-dictionaries>
+#[derive(Clone, JSTraceable, MallocSizeOf)]
+pub struct Timing {
+    pub delay: f64,
+    pub end_delay: f64,
+    pub fill: FillMode,
+    pub iteration_start: f64,
+    pub iterations: f64,
+    /// The iteration duration, or `None` for `auto`.
+    pub duration: Option<f64>,
+    pub direction: PlaybackDirection,
+    pub easing: TimingFunction,
+}
+
+impl Timing {
+    pub fn new() -> Timing {
+        Timing {
+            delay: 0.,
+            end_delay: 0.,
+            fill: FillMode::Auto,
+            iteration_start: 0.,
+            iterations: 1.,
+            duration: None,
+            direction: PlaybackDirection::Normal,
+            easing: TimingFunction::Keyword(TimingKeyword::Linear),
+        }
+    }
+
+    /// Creates the timing of an effect from the `duration` passed as the
+    /// options of `KeyframeEffect()` or `animate()`.
+    pub fn from_duration(duration: f64) -> Fallible<Timing> {
+        Ok(Timing {
+            duration: Some(validate_duration(duration)?),
+            ..Timing::new()
+        })
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#update-the-timing-properties-of-an-animation-effect>
+    /// for a complete set of timing properties.
+    pub fn from_dictionary(url: &ServoUrl, timing: &EffectTiming) -> Fallible<Timing> {
+        let mut result = Timing::new();
+        result.update(url, &OptionalEffectTiming {
+            delay: Some(timing.delay),
+            endDelay: Some(timing.endDelay),
+            fill: Some(timing.fill),
+            iterationStart: Some(timing.iterationStart),
+            iterations: Some(timing.iterations),
+            duration: timing.duration.as_ref().map(clone_duration),
+            direction: Some(timing.direction),
+            easing: Some(timing.easing.clone()),
+        })?;
+        Ok(result)
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#update-the-timing-properties-of-an-animation-effect>
+    pub fn update(&mut self, url: &ServoUrl, timing: &OptionalEffectTiming) -> ErrorResult {
+        // Step 1.
+        if let Some(iteration_start) = timing.iterationStart {
+            if *iteration_start < 0. {
+                return Err(Error::Type("iterationStart must not be negative".to_owned()));
+            }
+        }
+        if let Some(iterations) = timing.iterations {
+            if iterations.is_nan() || iterations < 0. {
+                return Err(Error::Type("iterations must be a non-negative number".to_owned()));
+            }
+        }
+        let duration = match timing.duration {
+            Some(UnrestrictedDoubleOrString::UnrestrictedDouble(duration)) => Some(Some(validate_duration(duration)?)),
+            Some(UnrestrictedDoubleOrString::String(ref duration)) if &**duration == "auto" => Some(None),
+            Some(UnrestrictedDoubleOrString::String(_)) => {
+                return Err(Error::Type("duration must be a number or \"auto\"".to_owned()));
+            },
+            None => None,
+        };
+        let easing = match timing.easing {
+            Some(ref easing) => Some(parse_easing(url, easing)?),
+            None => None,
+        };
+
+        // Step 2.
+        if let Some(delay) = timing.delay {
+            self.delay = *delay;
+        }
+        if let Some(end_delay) = timing.endDelay {
+            self.end_delay = *end_delay;
+        }
+        if let Some(fill) = timing.fill {
+            self.fill = fill;
+        }
+        if let Some(iteration_start) = timing.iterationStart {
+            self.iteration_start = *iteration_start;
+        }
+        if let Some(iterations) = timing.iterations {
+            self.iterations = iterations;
+        }
+        if let Some(duration) = duration {
+            self.duration = duration;
+        }
+        if let Some(direction) = timing.direction {
+            self.direction = direction;
+        }
+        if let Some(easing) = easing {
+            self.easing = easing;
+        }
+        Ok(())
+    }
+
+    fn to_dictionary(&self) -> EffectTiming {
+        EffectTiming {
+            delay: Finite::wrap(self.delay),
+            endDelay: Finite::wrap(self.end_delay),
+            fill: self.fill,
+            iterationStart: Finite::wrap(self.iteration_start),
+            iterations: self.iterations,
+            duration: Some(match self.duration {
+                Some(duration) => UnrestrictedDoubleOrString::UnrestrictedDouble(duration),
+                None => UnrestrictedDoubleOrString::String(DOMString::from("auto")),
+            }),
+            direction: self.direction,
+            easing: DOMString::from(self.easing.to_css_string()),
+        }
+    }
+}
+
+fn clone_duration(duration: &UnrestrictedDoubleOrString) -> UnrestrictedDoubleOrString {
+    match *duration {
+        UnrestrictedDoubleOrString::UnrestrictedDouble(duration) => {
+            UnrestrictedDoubleOrString::UnrestrictedDouble(duration)
+        },
+        UnrestrictedDoubleOrString::String(ref duration) => UnrestrictedDoubleOrString::String(duration.clone()),
+    }
+}
+
+fn validate_duration(duration: f64) -> Fallible<f64> {
+    if duration.is_nan() || duration < 0. {
+        return Err(Error::Type("duration must be a non-negative number".to_owned()));
+    }
+    Ok(duration)
+}
+
+/// Parses `easing` as a `<single-timing-function>`.
+///
+/// <https://drafts.csswg.org/web-animations/#dom-effecttiming-easing>
+pub fn parse_easing(url: &ServoUrl, easing: &str) -> Fallible<TimingFunction> {
+    let context = ParserContext::new_for_cssom(
+        url,
+        Some(CssRuleType::Style),
+        ParsingMode::DEFAULT,
+        QuirksMode::NoQuirks,
+        None,
+    );
+    let mut input = ParserInput::new(easing);
+    let mut parser = Parser::new(&mut input);
+    parser.parse_entirely(|input| SpecifiedTimingFunction::parse(&context, input))
+        .map(|timing_function| timing_function.to_computed_value_without_context())
+        .map_err(|_| Error::Type(format!("'{}' is not a valid easing", easing)))
+}
+
+/// <https://drafts.csswg.org/web-animations/#animation-effect-phases-and-states>
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Phase {
+    Before,
+    Active,
+    After,
+    Idle,
+}
+
+/// <https://drafts.csswg.org/web-animations/#the-animationeffect-interface>
+#[dom_struct]
+pub struct AnimationEffect {
+    reflector_: Reflector,
+    timing: DomRefCell<Timing>,
+    /// The animation this effect is associated with, if any.
+    animation: MutNullableDom<Animation>,
+}
+
+impl AnimationEffect {
+    pub fn new_inherited(timing: Timing) -> AnimationEffect {
+        AnimationEffect {
+            reflector_: Reflector::new(),
+            timing: DomRefCell::new(timing),
+            animation: MutNullableDom::new(None),
+        }
+    }
+
+    pub fn timing(&self) -> Timing {
+        self.timing.borrow().clone()
+    }
+
+    pub fn set_timing(&self, timing: Timing) {
+        *self.timing.borrow_mut() = timing;
+    }
+
+    pub fn animation(&self) -> Option<DomRoot<Animation>> {
+        self.animation.get()
+    }
+
+    pub fn set_animation(&self, animation: Option<&Animation>) {
+        self.animation.set(animation);
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#iteration-duration>
+    fn iteration_duration(&self) -> f64 {
+        self.timing.borrow().duration.unwrap_or(0.)
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#active-duration>
+    fn active_duration(&self) -> f64 {
+        let iteration_duration = self.iteration_duration();
+        let iterations = self.timing.borrow().iterations;
+        if iteration_duration == 0. || iterations == 0. {
+            0.
+        } else {
+            iteration_duration * iterations
+        }
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#end-time>
+    pub fn end_time(&self) -> f64 {
+        let timing = self.timing.borrow();
+        (timing.delay + self.active_duration() + timing.end_delay).max(0.)
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#local-time>
+    fn local_time(&self) -> Option<f64> {
+        self.animation.get().and_then(|animation| animation.current_time())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#animation-direction>
+    fn is_backwards(&self) -> bool {
+        self.animation.get().map_or(false, |animation| animation.playback_rate() < 0.)
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#animation-effect-phases-and-states>
+    fn phase(&self) -> Phase {
+        let local_time = match self.local_time() {
+            Some(local_time) => local_time,
+            None => return Phase::Idle,
+        };
+        let delay = self.timing.borrow().delay;
+        let end_time = self.end_time();
+        let before_active_boundary_time = delay.min(end_time).max(0.);
+        let active_after_boundary_time = (delay + self.active_duration()).min(end_time).max(0.);
+        let backwards = self.is_backwards();
+        if local_time < before_active_boundary_time || (backwards && local_time == before_active_boundary_time) {
+            Phase::Before
+        } else if local_time > active_after_boundary_time || (!backwards && local_time == active_after_boundary_time) {
+            Phase::After
+        } else {
+            Phase::Active
+        }
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#current>
+    pub fn is_current(&self) -> bool {
+        match self.phase() {
+            Phase::Before => !self.is_backwards(),
+            Phase::Active => true,
+            Phase::After => self.is_backwards(),
+            Phase::Idle => false,
+        }
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#in-effect>
+    pub fn is_in_effect(&self) -> bool {
+        self.active_time().is_some()
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#calculating-the-active-time>
+    fn active_time(&self) -> Option<f64> {
+        let local_time = self.local_time()?;
+        let timing = self.timing.borrow();
+        match self.phase() {
+            Phase::Before => match timing.fill {
+                FillMode::Backwards | FillMode::Both => Some((local_time - timing.delay).max(0.)),
+                _ => None,
+            },
+            Phase::Active => Some(local_time - timing.delay),
+            Phase::After => match timing.fill {
+                FillMode::Forwards | FillMode::Both => {
+                    Some((local_time - timing.delay).min(self.active_duration()).max(0.))
+                },
+                _ => None,
+            },
+            Phase::Idle => None,
+        }
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#calculating-the-overall-progress>
+    fn overall_progress(&self) -> Option<f64> {
+        let active_time = self.active_time()?;
+        let iteration_duration = self.iteration_duration();
+        let timing = self.timing.borrow();
+        let overall_progress = if iteration_duration == 0. {
+            if self.phase() == Phase::Before { 0. } else { timing.iterations }
+        } else {
+            active_time / iteration_duration
+        };
+        Some(overall_progress + timing.iteration_start)
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#calculating-the-simple-iteration-progress>
+    fn simple_iteration_progress(&self) -> Option<f64> {
+        let overall_progress = self.overall_progress()?;
+        let timing = self.timing.borrow();
+        let mut simple_iteration_progress = if overall_progress.is_infinite() {
+            timing.iteration_start % 1.
+        } else {
+            overall_progress % 1.
+        };
+        let phase = self.phase();
+        if simple_iteration_progress == 0. &&
+            (phase == Phase::Active || phase == Phase::After) &&
+            self.active_time() == Some(self.active_duration()) &&
+            timing.iterations != 0. {
+            simple_iteration_progress = 1.;
+        }
+        Some(simple_iteration_progress)
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#calculating-the-current-iteration>
+    fn current_iteration(&self) -> Option<f64> {
+        self.active_time()?;
+        if self.phase() == Phase::After && self.timing.borrow().iterations.is_infinite() {
+            return Some(f64::INFINITY);
+        }
+        if self.simple_iteration_progress()? == 1. {
+            return Some(self.overall_progress()?.floor() - 1.);
+        }
+        Some(self.overall_progress()?.floor())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#calculating-the-directed-progress>
+    fn directed_progress(&self) -> Option<f64> {
+        let simple_iteration_progress = self.simple_iteration_progress()?;
+        let current_iteration = self.current_iteration()?;
+        let forwards = match self.timing.borrow().direction {
+            PlaybackDirection::Normal => true,
+            PlaybackDirection::Reverse => false,
+            direction => {
+                let mut d = current_iteration;
+                if direction == PlaybackDirection::Alternate_reverse {
+                    d += 1.;
+                }
+                d.is_infinite() || d % 2. == 0.
+            },
+        };
+        if forwards {
+            Some(simple_iteration_progress)
+        } else {
+            Some(1. - simple_iteration_progress)
+        }
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#calculating-the-transformed-progress>
+    pub fn transformed_progress(&self) -> Option<f64> {
+        let directed_progress = self.directed_progress()?;
+        // Solve the timing function with a precision that matches the one
+        // used for CSS animations of the same duration.
+        let epsilon = 1. / (200. * (self.iteration_duration() / 1000.).max(0.001));
+        Some(apply_timing_function(&self.timing.borrow().easing, directed_progress, epsilon))
+    }
+
+    /// Notifies the animation of this effect that its timing, target or
+    /// keyframes changed.
+    pub fn effect_changed(&self) {
+        if let Some(animation) = self.animation.get() {
+            animation.effect_changed();
+        }
+    }
+}
+
+impl AnimationEffectMethods for AnimationEffect {
+    // https://drafts.csswg.org/web-animations/#dom-animationeffect-gettiming
+    fn GetTiming(&self) -> EffectTiming {
+        self.timing.borrow().to_dictionary()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animationeffect-getcomputedtiming
+    fn GetComputedTiming(&self) -> ComputedEffectTiming {
+        let mut timing = self.timing.borrow().to_dictionary();
+        timing.duration = Some(UnrestrictedDoubleOrString::UnrestrictedDouble(self.iteration_duration()));
+        if timing.fill == FillMode::Auto {
+            timing.fill = FillMode::None;
+        }
+        ComputedEffectTiming {
+            parent: timing,
+            endTime: Some(self.end_time()),
+            activeDuration: Some(self.active_duration()),
+            localTime: Some(self.local_time().map(Finite::wrap)),
+            progress: Some(self.transformed_progress().map(Finite::wrap)),
+            currentIteration: Some(self.current_iteration()),
+        }
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animationeffect-updatetiming
+    fn UpdateTiming(&self, timing: &OptionalEffectTiming) -> ErrorResult {
+        let url = self.global().api_base_url();
+        self.timing.borrow_mut().update(&url, timing)?;
+        self.effect_changed();
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::AnimationPlaybackEventBinding;
+use dom::bindings::codegen::Bindings::AnimationPlaybackEventBinding::AnimationPlaybackEventInit;
+use dom::bindings::codegen::Bindings::AnimationPlaybackEventBinding::AnimationPlaybackEventMethods;
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use dom::bindings::error::Fallible;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::num::Finite;
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::event::Event;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use servo_atoms::Atom;
+
+// https://drafts.csswg.org/web-animations/#the-animationplaybackevent-interface
+#[dom_struct]
+pub struct AnimationPlaybackEvent {
+    event: Event,
+    current_time: Option<Finite<f64>>,
+    timeline_time: Option<Finite<f64>>,
+}
+
+impl AnimationPlaybackEvent {
+    fn new_inherited(current_time: Option<Finite<f64>>,
+                     timeline_time: Option<Finite<f64>>)
+                     -> AnimationPlaybackEvent {
+        AnimationPlaybackEvent {
+            event: Event::new_inherited(),
+            current_time: current_time,
+            timeline_time: timeline_time,
+        }
+    }
+
+    pub fn new(window: &Window,
+               type_: Atom,
+               bubbles: bool,
+               cancelable: bool,
+               current_time: Option<f64>,
+               timeline_time: Option<f64>)
+               -> DomRoot<AnimationPlaybackEvent> {
+        let event = AnimationPlaybackEvent::new_inherited(current_time.map(Finite::wrap),
+                                                          timeline_time.map(Finite::wrap));
+        let ev = reflect_dom_object(Box::new(event), window, AnimationPlaybackEventBinding::Wrap);
+        ev.upcast::<Event>().init_event(type_, bubbles, cancelable);
+        ev
+    }
+
+    pub fn Constructor(window: &Window,
+                       type_: DOMString,
+                       init: &AnimationPlaybackEventInit)
+                       -> Fallible<DomRoot<AnimationPlaybackEvent>> {
+        let event = AnimationPlaybackEvent::new_inherited(init.currentTime, init.timelineTime);
+        let ev = reflect_dom_object(Box::new(event), window, AnimationPlaybackEventBinding::Wrap);
+        ev.upcast::<Event>().init_event(Atom::from(type_), init.parent.bubbles, init.parent.cancelable);
+        Ok(ev)
+    }
+}
+
+impl AnimationPlaybackEventMethods for AnimationPlaybackEvent {
+    // https://drafts.csswg.org/web-animations/#dom-animationplaybackevent-currenttime
+    fn GetCurrentTime(&self) -> Option<Finite<f64>> {
+        self.current_time
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animationplaybackevent-timelinetime
+    fn GetTimelineTime(&self) -> Option<Finite<f64>> {
+        self.timeline_time
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-istrusted
+    fn IsTrusted(&self) -> bool {
+        self.upcast::<Event>().IsTrusted()
+    }
+}
@@ -0,0 +1,38 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::AnimationTimelineBinding::AnimationTimelineMethods;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::num::Finite;
+use dom::bindings::reflector::Reflector;
+use dom::documenttimeline::DocumentTimeline;
+use dom_struct::dom_struct;
+
+/// <https://drafts.csswg.org/web-animations/#the-animationtimeline-interface>
+#[dom_struct]
+pub struct AnimationTimeline {
+    reflector_: Reflector,
+}
+
+impl AnimationTimeline {
+    pub fn new_inherited() -> AnimationTimeline {
+        AnimationTimeline {
+            reflector_: Reflector::new(),
+        }
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#timeline-current-time>
+    ///
+    /// Returns `None` while the timeline is inactive.
+    pub fn current_time(&self) -> Option<f64> {
+        self.downcast::<DocumentTimeline>().and_then(|timeline| timeline.current_time())
+    }
+}
+
+impl AnimationTimelineMethods for AnimationTimeline {
+    // https://drafts.csswg.org/web-animations/#dom-animationtimeline-currenttime
+    fn GetCurrentTime(&self) -> Option<Finite<f64>> {
+        self.current_time().map(Finite::wrap)
+    }
+}
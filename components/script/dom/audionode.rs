@@ -204,9 +204,22 @@ impl AudioNodeMethods for AudioNode {
                     return Err(Error::IndexSize);
                 }
             },
-            // XXX We do not support any of the other AudioNodes with
-            // constraints yet. Add more cases here as we add support
-            // for new AudioNodes.
+            EventTargetTypeId::AudioNode(AudioNodeTypeId::ChannelMergerNode) => {
+                if value != 1 {
+                    return Err(Error::InvalidState);
+                }
+            },
+            EventTargetTypeId::AudioNode(AudioNodeTypeId::ChannelSplitterNode) => {
+                if value != self.number_of_outputs {
+                    return Err(Error::InvalidState);
+                }
+            },
+            EventTargetTypeId::AudioNode(AudioNodeTypeId::PannerNode) |
+            EventTargetTypeId::AudioNode(AudioNodeTypeId::StereoPannerNode) => {
+                if value > 2 {
+                    return Err(Error::NotSupported);
+                }
+            },
             _ => (),
         };
 
@@ -237,9 +250,18 @@ impl AudioNodeMethods for AudioNode {
                     return Err(Error::InvalidState);
                 }
             },
-            // XXX We do not support any of the other AudioNodes with
-            // constraints yet. Add more cases here as we add support
-            // for new AudioNodes.
+            EventTargetTypeId::AudioNode(AudioNodeTypeId::ChannelMergerNode) |
+            EventTargetTypeId::AudioNode(AudioNodeTypeId::ChannelSplitterNode) => {
+                if value != ChannelCountMode::Explicit {
+                    return Err(Error::InvalidState);
+                }
+            },
+            EventTargetTypeId::AudioNode(AudioNodeTypeId::PannerNode) |
+            EventTargetTypeId::AudioNode(AudioNodeTypeId::StereoPannerNode) => {
+                if value == ChannelCountMode::Max {
+                    return Err(Error::NotSupported);
+                }
+            },
             _ => (),
         };
 
@@ -254,14 +276,40 @@ impl AudioNodeMethods for AudioNode {
     }
 
     // https://webaudio.github.io/web-audio-api/#dom-audionode-channelinterpretation
-    fn SetChannelInterpretation(&self, value: ChannelInterpretation) {
+    fn SetChannelInterpretation(&self, value: ChannelInterpretation) -> ErrorResult {
         // Channel interpretation mode has no effect for nodes with no inputs.
         if self.number_of_inputs == 0 {
-            return;
+            return Ok(());
+        }
+
+        if let EventTargetTypeId::AudioNode(AudioNodeTypeId::ChannelSplitterNode) =
+            self.upcast::<EventTarget>().type_id()
+        {
+            if value != ChannelInterpretation::Discrete {
+                return Err(Error::InvalidState);
+            }
         }
 
         self.channel_interpretation.set(value);
         self.message(AudioNodeMessage::SetChannelInterpretation(value.into()));
+        Ok(())
+    }
+}
+
+impl AudioNodeOptions {
+    /// Returns a copy of these options where the members which were not
+    /// given to the node constructor take the defaults of that node type.
+    pub fn unwrap_or(
+        &self,
+        count: u32,
+        mode: ChannelCountMode,
+        interpretation: ChannelInterpretation,
+    ) -> AudioNodeOptions {
+        let mut options = AudioNodeOptions::empty();
+        options.channelCount = Some(self.channelCount.unwrap_or(count));
+        options.channelCountMode = Some(self.channelCountMode.unwrap_or(mode));
+        options.channelInterpretation = Some(self.channelInterpretation.unwrap_or(interpretation));
+        options
     }
 }
 
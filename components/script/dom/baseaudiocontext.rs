@@ -2,12 +2,14 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use dom::analysernode::AnalyserNode;
 use dom::audiobuffer::AudioBuffer;
 use dom::audiobuffersourcenode::AudioBufferSourceNode;
 use dom::audiodestinationnode::AudioDestinationNode;
 use dom::audionode::MAX_CHANNEL_COUNT;
 use dom::bindings::callback::ExceptionHandling;
 use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::AnalyserNodeBinding::AnalyserOptions;
 use dom::bindings::codegen::Bindings::AudioBufferSourceNodeBinding::AudioBufferSourceOptions;
 use dom::bindings::codegen::Bindings::AudioNodeBinding::{ChannelCountMode, ChannelInterpretation};
 use dom::bindings::codegen::Bindings::AudioNodeBinding::AudioNodeOptions;
@@ -15,19 +17,33 @@ use dom::bindings::codegen::Bindings::BaseAudioContextBinding::AudioContextState
 use dom::bindings::codegen::Bindings::BaseAudioContextBinding::BaseAudioContextMethods;
 use dom::bindings::codegen::Bindings::BaseAudioContextBinding::DecodeErrorCallback;
 use dom::bindings::codegen::Bindings::BaseAudioContextBinding::DecodeSuccessCallback;
+use dom::bindings::codegen::Bindings::BiquadFilterNodeBinding::BiquadFilterOptions;
+use dom::bindings::codegen::Bindings::ChannelMergerNodeBinding::ChannelMergerOptions;
+use dom::bindings::codegen::Bindings::ChannelSplitterNodeBinding::ChannelSplitterOptions;
+use dom::bindings::codegen::Bindings::ConstantSourceNodeBinding::ConstantSourceOptions;
 use dom::bindings::codegen::Bindings::GainNodeBinding::GainOptions;
 use dom::bindings::codegen::Bindings::OscillatorNodeBinding::OscillatorOptions;
+use dom::bindings::codegen::Bindings::PannerNodeBinding::PannerOptions;
+use dom::bindings::codegen::Bindings::StereoPannerNodeBinding::StereoPannerOptions;
+use dom::bindings::codegen::Bindings::WaveShaperNodeBinding::WaveShaperOptions;
 use dom::bindings::error::{Error, ErrorResult, Fallible};
 use dom::bindings::inheritance::Castable;
 use dom::bindings::num::Finite;
 use dom::bindings::refcounted::Trusted;
 use dom::bindings::reflector::DomObject;
 use dom::bindings::root::{DomRoot, MutNullableDom};
+use dom::biquadfilternode::BiquadFilterNode;
+use dom::channelmergernode::ChannelMergerNode;
+use dom::channelsplitternode::ChannelSplitterNode;
+use dom::constantsourcenode::ConstantSourceNode;
 use dom::domexception::{DOMErrorName, DOMException};
 use dom::eventtarget::EventTarget;
 use dom::gainnode::GainNode;
 use dom::oscillatornode::OscillatorNode;
+use dom::pannernode::PannerNode;
 use dom::promise::Promise;
+use dom::stereopannernode::StereoPannerNode;
+use dom::waveshapernode::WaveShaperNode;
 use dom::window::Window;
 use dom_struct::dom_struct;
 use js::rust::CustomAutoRooterGuard;
@@ -83,14 +99,16 @@ pub struct BaseAudioContext {
     /// been "closed()".
     state: Cell<AudioContextState>,
     channel_count: u32,
+    /// Whether this is the base of an OfflineAudioContext.
+    offline: bool,
 }
 
 impl BaseAudioContext {
     #[allow(unrooted_must_root)]
     pub fn new_inherited(options: BaseAudioContextOptions) -> BaseAudioContext {
-        let (sample_rate, channel_count) = match options {
-            BaseAudioContextOptions::AudioContext(ref opt) => (opt.sample_rate, 2),
-            BaseAudioContextOptions::OfflineAudioContext(ref opt) => (opt.sample_rate, opt.channels),
+        let (sample_rate, channel_count, offline) = match options {
+            BaseAudioContextOptions::AudioContext(ref opt) => (opt.sample_rate, 2, false),
+            BaseAudioContextOptions::OfflineAudioContext(ref opt) => (opt.sample_rate, opt.channels, true),
         };
 
         let context = BaseAudioContext {
@@ -107,6 +125,7 @@ impl BaseAudioContext {
             sample_rate,
             state: Cell::new(AudioContextState::Suspended),
             channel_count: channel_count.into(),
+            offline,
         };
 
         context
@@ -114,7 +133,7 @@ impl BaseAudioContext {
 
     /// Tells whether this is an OfflineAudioContext or not.
     pub fn is_offline(&self) -> bool {
-        false
+        self.offline
     }
 
     pub fn audio_context_impl(&self) -> Rc<AudioContext<Backend>> {
@@ -346,6 +365,62 @@ impl BaseAudioContextMethods for BaseAudioContext {
         )
     }
 
+    // https://webaudio.github.io/web-audio-api/#dom-baseaudiocontext-createconstantsource
+    fn CreateConstantSource(&self) -> DomRoot<ConstantSourceNode> {
+        ConstantSourceNode::new(
+            &self.global().as_window(),
+            &self,
+            &ConstantSourceOptions::empty(),
+        )
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-baseaudiocontext-createanalyser
+    fn CreateAnalyser(&self) -> Fallible<DomRoot<AnalyserNode>> {
+        AnalyserNode::new(&self.global().as_window(), &self, &AnalyserOptions::empty())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-baseaudiocontext-createbiquadfilter
+    fn CreateBiquadFilter(&self) -> DomRoot<BiquadFilterNode> {
+        BiquadFilterNode::new(
+            &self.global().as_window(),
+            &self,
+            &BiquadFilterOptions::empty(),
+        )
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-baseaudiocontext-createwaveshaper
+    fn CreateWaveShaper(&self) -> Fallible<DomRoot<WaveShaperNode>> {
+        WaveShaperNode::new(&self.global().as_window(), &self, &WaveShaperOptions::empty())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-baseaudiocontext-createpanner
+    fn CreatePanner(&self) -> Fallible<DomRoot<PannerNode>> {
+        PannerNode::new(&self.global().as_window(), &self, &PannerOptions::empty())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-baseaudiocontext-createstereopanner
+    fn CreateStereoPanner(&self) -> Fallible<DomRoot<StereoPannerNode>> {
+        StereoPannerNode::new(
+            &self.global().as_window(),
+            &self,
+            &StereoPannerOptions::empty(),
+        )
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-baseaudiocontext-createchannelsplitter
+    fn CreateChannelSplitter(&self, number_of_outputs: u32) -> Fallible<DomRoot<ChannelSplitterNode>> {
+        let mut options = ChannelSplitterOptions::empty();
+        options.numberOfOutputs = number_of_outputs;
+        ChannelSplitterNode::new(&self.global().as_window(), &self, &options)
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-baseaudiocontext-createchannelmerger
+    fn CreateChannelMerger(&self, number_of_inputs: u32) -> Fallible<DomRoot<ChannelMergerNode>> {
+        let mut options = ChannelMergerOptions::empty();
+        options.numberOfInputs = number_of_inputs;
+        ChannelMergerNode::new(&self.global().as_window(), &self, &options)
+    }
+
     // https://webaudio.github.io/web-audio-api/#dom-baseaudiocontext-decodeaudiodata
     #[allow(unrooted_must_root)]
     fn DecodeAudioData(
@@ -51,6 +51,7 @@ use dom::bindings::codegen::Bindings::HTMLProgressElementBinding;
 use dom::bindings::codegen::Bindings::HTMLQuoteElementBinding;
 use dom::bindings::codegen::Bindings::HTMLScriptElementBinding;
 use dom::bindings::codegen::Bindings::HTMLSelectElementBinding;
+use dom::bindings::codegen::Bindings::HTMLSlotElementBinding;
 use dom::bindings::codegen::Bindings::HTMLSourceElementBinding;
 use dom::bindings::codegen::Bindings::HTMLSpanElementBinding;
 use dom::bindings::codegen::Bindings::HTMLStyleElementBinding;
@@ -285,6 +286,7 @@ pub fn get_constructor_object_from_local_name(name: LocalName,
         local_name!("script")     => get_constructor!(HTMLScriptElementBinding),
         local_name!("section")    => get_constructor!(HTMLElementBinding),
         local_name!("select")     => get_constructor!(HTMLSelectElementBinding),
+        local_name!("slot")       => get_constructor!(HTMLSlotElementBinding),
         local_name!("small")      => get_constructor!(HTMLElementBinding),
         local_name!("source")     => get_constructor!(HTMLSourceElementBinding),
         local_name!("span")       => get_constructor!(HTMLSpanElementBinding),
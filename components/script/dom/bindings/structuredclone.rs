@@ -5,12 +5,14 @@
 //! This module implements structured cloning, as defined by [HTML]
 //! (https://html.spec.whatwg.org/multipage/#safe-passing-of-structured-data).
 
-use dom::bindings::conversions::root_from_handleobject;
+use dom::bindings::conversions::{ToJSValConvertible, root_from_handleobject, root_from_object};
 use dom::bindings::error::{Error, Fallible};
 use dom::bindings::reflector::DomObject;
 use dom::bindings::root::DomRoot;
 use dom::blob::{Blob, BlobImpl};
 use dom::globalscope::GlobalScope;
+use dom::imagebitmap::ImageBitmap;
+use dom::messageport::MessagePort;
 use js::jsapi::{JSAutoCompartment, JSContext};
 use js::jsapi::{JSStructuredCloneCallbacks, JSStructuredCloneReader, JSStructuredCloneWriter};
 use js::jsapi::{JS_ClearPendingException, JSObject};
@@ -20,9 +22,12 @@ use js::jsapi::HandleObject as RawHandleObject;
 use js::jsapi::JS_STRUCTURED_CLONE_VERSION;
 use js::jsapi::MutableHandleObject as RawMutableHandleObject;
 use js::jsapi::TransferableOwnership;
+use js::jsval::UndefinedValue;
 use js::rust::{Handle, HandleValue, MutableHandleValue};
 use js::rust::wrappers::{JS_WriteStructuredClone, JS_ReadStructuredClone};
 use libc::size_t;
+use msg::constellation_msg::{MessagePortId, MessagePortIndex, PipelineNamespaceId};
+use std::num::NonZeroU32;
 use std::os::raw;
 use std::ptr;
 use std::slice;
@@ -36,6 +41,8 @@ enum StructuredCloneTags {
     /// To support additional types, add new tags with values incremented from the last one before Max.
     Min = 0xFFFF8000,
     DomBlob = 0xFFFF8001,
+    DomImageBitmap = 0xFFFF8002,
+    MessagePort = 0xFFFF8003,
     Max = 0xFFFFFFFF,
 }
 
@@ -125,6 +132,37 @@ unsafe fn write_blob(blob: DomRoot<Blob>,
     return Ok(())
 }
 
+unsafe fn read_image_bitmap(cx: *mut JSContext,
+                            r: *mut JSStructuredCloneReader)
+                            -> *mut JSObject {
+    let structured_reader = StructuredCloneReader { r: r };
+    let mut width: u32 = 0;
+    let mut height: u32 = 0;
+    assert!(JS_ReadUint32Pair(r, &mut width as *mut u32, &mut height as *mut u32));
+    let bitmap_data = structured_reader.read_bytes();
+    let target_global = GlobalScope::from_context(cx);
+    let bitmap = ImageBitmap::new(&target_global, width, height, bitmap_data, true);
+    return bitmap.reflector().get_jsobject().get()
+}
+
+// https://html.spec.whatwg.org/multipage/#the-imagebitmap-interface:serializable-objects
+unsafe fn write_image_bitmap(bitmap: DomRoot<ImageBitmap>,
+                             w: *mut JSStructuredCloneWriter)
+                             -> Result<(), ()> {
+    // Bitmaps that aren't origin-clean can't be serialized, and neither can
+    // closed ones since they have nothing left to copy.
+    if !bitmap.origin_is_clean() {
+        return Err(())
+    }
+    let bitmap_data = bitmap.bitmap_data().ok_or(())?;
+    let structured_writer = StructuredCloneWriter { w: w };
+    let size = bitmap.get_size();
+    assert!(JS_WriteUint32Pair(w, StructuredCloneTags::DomImageBitmap as u32, 0));
+    assert!(JS_WriteUint32Pair(w, size.width as u32, size.height as u32));
+    structured_writer.write_slice(&bitmap_data);
+    return Ok(())
+}
+
 unsafe extern "C" fn read_callback(cx: *mut JSContext,
                                    r: *mut JSStructuredCloneReader,
                                    tag: u32,
@@ -136,6 +174,9 @@ unsafe extern "C" fn read_callback(cx: *mut JSContext,
     if tag == StructuredCloneTags::DomBlob as u32 {
         return read_blob(cx, r)
     }
+    if tag == StructuredCloneTags::DomImageBitmap as u32 {
+        return read_image_bitmap(cx, r)
+    }
     return ptr::null_mut()
 }
 
@@ -147,29 +188,70 @@ unsafe extern "C" fn write_callback(_cx: *mut JSContext,
     if let Ok(blob) = root_from_handleobject::<Blob>(Handle::from_raw(obj)) {
         return write_blob(blob, w).is_ok()
     }
+    if let Ok(bitmap) = root_from_handleobject::<ImageBitmap>(Handle::from_raw(obj)) {
+        return write_image_bitmap(bitmap, w).is_ok()
+    }
     return false
 }
 
-unsafe extern "C" fn read_transfer_callback(_cx: *mut JSContext,
+/// Packs the id of a transferred port into the extra data of its transfer map entry.
+fn port_id_to_extra_data(port_id: MessagePortId) -> u64 {
+    let PipelineNamespaceId(namespace_id) = port_id.namespace_id;
+    let MessagePortIndex(index) = port_id.index;
+    (u64::from(namespace_id) << 32) | u64::from(index.get())
+}
+
+fn port_id_from_extra_data(extra_data: u64) -> Option<MessagePortId> {
+    NonZeroU32::new(extra_data as u32).map(|index| MessagePortId {
+        namespace_id: PipelineNamespaceId((extra_data >> 32) as u32),
+        index: MessagePortIndex(index),
+    })
+}
+
+// https://html.spec.whatwg.org/multipage/#message-ports:transfer-receiving-steps
+unsafe extern "C" fn read_transfer_callback(cx: *mut JSContext,
                                             _r: *mut JSStructuredCloneReader,
-                                            _tag: u32,
+                                            tag: u32,
                                             _content: *mut raw::c_void,
-                                            _extra_data: u64,
-                                            _closure: *mut raw::c_void,
-                                            _return_object: RawMutableHandleObject)
+                                            extra_data: u64,
+                                            closure: *mut raw::c_void,
+                                            return_object: RawMutableHandleObject)
                                             -> bool {
-    false
+    if tag != StructuredCloneTags::MessagePort as u32 {
+        return false
+    }
+    let port_id = match port_id_from_extra_data(extra_data) {
+        Some(port_id) => port_id,
+        None => return false,
+    };
+    let target_global = GlobalScope::from_context(cx);
+    let port = MessagePort::new_transferred(&target_global, port_id);
+    *return_object.ptr = port.reflector().get_jsobject().get();
+    let ports = &mut *(closure as *mut Vec<DomRoot<MessagePort>>);
+    ports.push(port);
+    true
 }
 
+// https://html.spec.whatwg.org/multipage/#message-ports:transfer-steps
 unsafe extern "C" fn write_transfer_callback(_cx: *mut JSContext,
-                                             _obj: RawHandleObject,
-                                             _closure: *mut raw::c_void,
-                                             _tag: *mut u32,
-                                             _ownership: *mut TransferableOwnership,
+                                             obj: RawHandleObject,
+                                             closure: *mut raw::c_void,
+                                             tag: *mut u32,
+                                             ownership: *mut TransferableOwnership,
                                              _content:  *mut *mut raw::c_void,
-                                             _extra_data: *mut u64)
+                                             extra_data: *mut u64)
                                              -> bool {
-    false
+    let port = match root_from_handleobject::<MessagePort>(Handle::from_raw(obj)) {
+        Ok(port) => port,
+        Err(()) => return false,
+    };
+    *tag = StructuredCloneTags::MessagePort as u32;
+    *ownership = TransferableOwnership::SCTAG_TMO_CUSTOM;
+    *extra_data = port_id_to_extra_data(port.message_port_id());
+    // The port is only shipped once the whole message was written.
+    let ports = &mut *(closure as *mut Vec<DomRoot<MessagePort>>);
+    ports.push(port);
+    true
 }
 
 unsafe extern "C" fn free_transfer_callback(_tag: u32,
@@ -202,6 +284,32 @@ pub enum StructuredCloneData {
 impl StructuredCloneData {
     /// Writes a structured clone. Returns a `DataClone` error if that fails.
     pub fn write(cx: *mut JSContext, message: HandleValue) -> Fallible<StructuredCloneData> {
+        StructuredCloneData::write_with_transfer(cx, message, &[])
+    }
+
+    /// Writes a structured clone, transferring the objects of `transfer`.
+    /// Returns a `DataClone` error if that fails.
+    ///
+    /// The message ports of `transfer` are shipped away from their global
+    /// once the clone was written.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#structuredserializewithtransfer>
+    pub fn write_with_transfer(cx: *mut JSContext,
+                               message: HandleValue,
+                               transfer: &[*mut JSObject])
+                               -> Fallible<StructuredCloneData> {
+        // Ports that were already transferred cannot be transferred again.
+        let detached_port = transfer.iter().any(|obj| {
+            root_from_object::<MessagePort>(*obj).map_or(false, |port| port.is_detached())
+        });
+        if detached_port {
+            return Err(Error::DataClone);
+        }
+        rooted!(in(cx) let mut transfer_list = UndefinedValue());
+        if !transfer.is_empty() {
+            unsafe { transfer.to_jsval(cx, transfer_list.handle_mut()) };
+        }
+        let mut ports: Vec<DomRoot<MessagePort>> = vec![];
         let mut data = ptr::null_mut();
         let mut nbytes = 0;
         let result = unsafe {
@@ -210,8 +318,8 @@ impl StructuredCloneData {
                                     &mut data,
                                     &mut nbytes,
                                     &STRUCTURED_CLONE_CALLBACKS,
-                                    ptr::null_mut(),
-                                    HandleValue::undefined())
+                                    &mut ports as *mut Vec<DomRoot<MessagePort>> as *mut raw::c_void,
+                                    transfer_list.handle())
         };
         if !result {
             unsafe {
@@ -219,6 +327,9 @@ impl StructuredCloneData {
             }
             return Err(Error::DataClone);
         }
+        for port in ports {
+            port.ship();
+        }
         Ok(StructuredCloneData::Struct(data, nbytes))
     }
 
@@ -234,16 +345,18 @@ impl StructuredCloneData {
         }
     }
 
-    /// Reads a structured clone.
+    /// Reads a structured clone, returning the message ports it transferred.
     ///
     /// Panics if `JS_ReadStructuredClone` fails.
     fn read_clone(global: &GlobalScope,
                   data: *mut u64,
                   nbytes: size_t,
-                  rval: MutableHandleValue) {
+                  rval: MutableHandleValue)
+                  -> Vec<DomRoot<MessagePort>> {
         let cx = global.get_cx();
         let globalhandle = global.reflector().get_jsobject();
         let _ac = JSAutoCompartment::new(cx, globalhandle.get());
+        let mut ports: Vec<DomRoot<MessagePort>> = vec![];
         unsafe {
             assert!(JS_ReadStructuredClone(cx,
                                            data,
@@ -251,17 +364,18 @@ impl StructuredCloneData {
                                            JS_STRUCTURED_CLONE_VERSION,
                                            rval,
                                            &STRUCTURED_CLONE_CALLBACKS,
-                                           ptr::null_mut()));
+                                           &mut ports as *mut Vec<DomRoot<MessagePort>> as *mut raw::c_void));
         }
+        ports
     }
 
     /// Thunk for the actual `read_clone` method. Resolves proper variant for read_clone.
-    pub fn read(self, global: &GlobalScope, rval: MutableHandleValue) {
+    pub fn read(self, global: &GlobalScope, rval: MutableHandleValue) -> Vec<DomRoot<MessagePort>> {
         match self {
             StructuredCloneData::Vector(mut vec_msg) => {
                 let nbytes = vec_msg.len();
                 let data = vec_msg.as_mut_ptr() as *mut u64;
-                StructuredCloneData::read_clone(global, data, nbytes, rval);
+                StructuredCloneData::read_clone(global, data, nbytes, rval)
             }
             StructuredCloneData::Struct(data, nbytes) => StructuredCloneData::read_clone(global, data, nbytes, rval)
         }
@@ -0,0 +1,354 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::audionode::AudioNode;
+use dom::audioparam::AudioParam;
+use dom::baseaudiocontext::BaseAudioContext;
+use dom::bindings::codegen::Bindings::AudioNodeBinding::{ChannelCountMode, ChannelInterpretation};
+use dom::bindings::codegen::Bindings::AudioNodeBinding::AudioNodeMethods;
+use dom::bindings::codegen::Bindings::AudioParamBinding::{AudioParamMethods, AutomationRate};
+use dom::bindings::codegen::Bindings::BaseAudioContextBinding::BaseAudioContextMethods;
+use dom::bindings::codegen::Bindings::BiquadFilterNodeBinding::{self, BiquadFilterNodeMethods};
+use dom::bindings::codegen::Bindings::BiquadFilterNodeBinding::{BiquadFilterOptions, BiquadFilterType};
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::{Dom, DomRoot};
+use dom::window::Window;
+use dom_struct::dom_struct;
+use js::rust::CustomAutoRooterGuard;
+use js::typedarray::Float32Array;
+use servo_media::audio::biquad_filter_node::{BiquadFilterNodeMessage, BiquadFilterNodeOptions};
+use servo_media::audio::biquad_filter_node::FilterType;
+use servo_media::audio::node::{AudioNodeInit, AudioNodeMessage};
+use servo_media::audio::param::ParamType;
+use std::cell::Cell;
+use std::f32;
+use std::f64::consts::PI;
+
+#[dom_struct]
+pub struct BiquadFilterNode {
+    node: AudioNode,
+    filter: Cell<BiquadFilterType>,
+    frequency: Dom<AudioParam>,
+    detune: Dom<AudioParam>,
+    q: Dom<AudioParam>,
+    gain: Dom<AudioParam>,
+}
+
+impl BiquadFilterNode {
+    #[allow(unrooted_must_root)]
+    pub fn new_inherited(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &BiquadFilterOptions,
+    ) -> BiquadFilterNode {
+        let node_options = options.parent.unwrap_or(
+            2,
+            ChannelCountMode::Max,
+            ChannelInterpretation::Speakers,
+        );
+        let node = AudioNode::new_inherited(
+            AudioNodeInit::BiquadFilterNode(options.into()),
+            None,
+            context,
+            &node_options,
+            1, // inputs
+            1, // outputs
+        );
+        let nyquist = *context.SampleRate() / 2.;
+        let frequency = AudioParam::new(
+            window,
+            context,
+            node.node_id(),
+            ParamType::Frequency,
+            AutomationRate::A_rate,
+            350.,    // default value
+            0.,      // min value
+            nyquist, // max value
+        );
+        let detune = AudioParam::new(
+            window,
+            context,
+            node.node_id(),
+            ParamType::Detune,
+            AutomationRate::A_rate,
+            0.,       // default value
+            f32::MIN, // min value
+            f32::MAX, // max value
+        );
+        let q = AudioParam::new(
+            window,
+            context,
+            node.node_id(),
+            ParamType::Q,
+            AutomationRate::A_rate,
+            1.,       // default value
+            f32::MIN, // min value
+            f32::MAX, // max value
+        );
+        let gain = AudioParam::new(
+            window,
+            context,
+            node.node_id(),
+            ParamType::Gain,
+            AutomationRate::A_rate,
+            0.,       // default value
+            f32::MIN, // min value
+            f32::MAX, // max value
+        );
+        BiquadFilterNode {
+            node,
+            filter: Cell::new(options.type_),
+            frequency: Dom::from_ref(&frequency),
+            detune: Dom::from_ref(&detune),
+            q: Dom::from_ref(&q),
+            gain: Dom::from_ref(&gain),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &BiquadFilterOptions,
+    ) -> DomRoot<BiquadFilterNode> {
+        let node = BiquadFilterNode::new_inherited(window, context, options);
+        reflect_dom_object(Box::new(node), window, BiquadFilterNodeBinding::Wrap)
+    }
+
+    pub fn Constructor(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &BiquadFilterOptions,
+    ) -> Fallible<DomRoot<BiquadFilterNode>> {
+        Ok(BiquadFilterNode::new(window, context, options))
+    }
+}
+
+impl BiquadFilterNodeMethods for BiquadFilterNode {
+    // https://webaudio.github.io/web-audio-api/#dom-biquadfilternode-type
+    fn Type(&self) -> BiquadFilterType {
+        self.filter.get()
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-biquadfilternode-type
+    fn SetType(&self, filter: BiquadFilterType) {
+        self.filter.set(filter);
+        self.node.message(AudioNodeMessage::BiquadFilterNode(
+            BiquadFilterNodeMessage::SetFilterType(filter.into()),
+        ));
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-biquadfilternode-frequency
+    fn Frequency(&self) -> DomRoot<AudioParam> {
+        DomRoot::from_ref(&self.frequency)
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-biquadfilternode-detune
+    fn Detune(&self) -> DomRoot<AudioParam> {
+        DomRoot::from_ref(&self.detune)
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-biquadfilternode-q
+    fn Q(&self) -> DomRoot<AudioParam> {
+        DomRoot::from_ref(&self.q)
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-biquadfilternode-gain
+    fn Gain(&self) -> DomRoot<AudioParam> {
+        DomRoot::from_ref(&self.gain)
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-biquadfilternode-getfrequencyresponse
+    #[allow(unsafe_code)]
+    fn GetFrequencyResponse(
+        &self,
+        frequency_hz: CustomAutoRooterGuard<Float32Array>,
+        mut mag_response: CustomAutoRooterGuard<Float32Array>,
+        mut phase_response: CustomAutoRooterGuard<Float32Array>,
+    ) -> Fallible<()> {
+        if frequency_hz.len() != mag_response.len() || frequency_hz.len() != phase_response.len() {
+            return Err(Error::InvalidAccess);
+        }
+
+        let nyquist = *self.node.Context().SampleRate() as f64 / 2.;
+        let detune = *self.detune.Value() as f64;
+        let computed_frequency = *self.frequency.Value() as f64 * 2f64.powf(detune / 1200.);
+        let coefficients = Coefficients::new(
+            self.filter.get(),
+            computed_frequency.max(0.).min(nyquist) / nyquist,
+            *self.q.Value() as f64,
+            *self.gain.Value() as f64,
+        );
+
+        let frequencies = unsafe { frequency_hz.as_slice() };
+        let mut magnitudes = Vec::with_capacity(frequencies.len());
+        let mut phases = Vec::with_capacity(frequencies.len());
+        for &frequency in frequencies {
+            let frequency = frequency as f64;
+            if frequency < 0. || frequency > nyquist {
+                magnitudes.push(f32::NAN);
+                phases.push(f32::NAN);
+                continue;
+            }
+            let (magnitude, phase) = coefficients.response(frequency / nyquist);
+            magnitudes.push(magnitude as f32);
+            phases.push(phase as f32);
+        }
+
+        unsafe {
+            mag_response.update(&magnitudes);
+            phase_response.update(&phases);
+        }
+
+        Ok(())
+    }
+}
+
+/// Normalized coefficients of the transfer function of a biquad filter,
+/// computed as described in
+/// https://webaudio.github.io/web-audio-api/#filters-characteristics
+struct Coefficients {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+}
+
+impl Coefficients {
+    /// `frequency` is normalized to the Nyquist frequency.
+    fn new(filter: BiquadFilterType, frequency: f64, q: f64, gain: f64) -> Coefficients {
+        let a = 10f64.powf(gain / 40.);
+        let w0 = PI * frequency;
+        let (sin_w0, cos_w0) = w0.sin_cos();
+        let alpha_q = sin_w0 / (2. * q);
+        let alpha_q_db = sin_w0 / (2. * 10f64.powf(q / 20.));
+        let alpha_s = sin_w0 / 2f64.sqrt();
+        let sqrt_a = a.sqrt();
+
+        let (b0, b1, b2, a0, a1, a2) = match filter {
+            BiquadFilterType::Lowpass => (
+                (1. - cos_w0) / 2.,
+                1. - cos_w0,
+                (1. - cos_w0) / 2.,
+                1. + alpha_q_db,
+                -2. * cos_w0,
+                1. - alpha_q_db,
+            ),
+            BiquadFilterType::Highpass => (
+                (1. + cos_w0) / 2.,
+                -(1. + cos_w0),
+                (1. + cos_w0) / 2.,
+                1. + alpha_q_db,
+                -2. * cos_w0,
+                1. - alpha_q_db,
+            ),
+            BiquadFilterType::Bandpass => (
+                alpha_q,
+                0.,
+                -alpha_q,
+                1. + alpha_q,
+                -2. * cos_w0,
+                1. - alpha_q,
+            ),
+            BiquadFilterType::Notch => (
+                1.,
+                -2. * cos_w0,
+                1.,
+                1. + alpha_q,
+                -2. * cos_w0,
+                1. - alpha_q,
+            ),
+            BiquadFilterType::Allpass => (
+                1. - alpha_q,
+                -2. * cos_w0,
+                1. + alpha_q,
+                1. + alpha_q,
+                -2. * cos_w0,
+                1. - alpha_q,
+            ),
+            BiquadFilterType::Peaking => (
+                1. + alpha_q * a,
+                -2. * cos_w0,
+                1. - alpha_q * a,
+                1. + alpha_q / a,
+                -2. * cos_w0,
+                1. - alpha_q / a,
+            ),
+            BiquadFilterType::Lowshelf => (
+                a * ((a + 1.) - (a - 1.) * cos_w0 + 2. * alpha_s * sqrt_a),
+                2. * a * ((a - 1.) - (a + 1.) * cos_w0),
+                a * ((a + 1.) - (a - 1.) * cos_w0 - 2. * alpha_s * sqrt_a),
+                (a + 1.) + (a - 1.) * cos_w0 + 2. * alpha_s * sqrt_a,
+                -2. * ((a - 1.) + (a + 1.) * cos_w0),
+                (a + 1.) + (a - 1.) * cos_w0 - 2. * alpha_s * sqrt_a,
+            ),
+            BiquadFilterType::Highshelf => (
+                a * ((a + 1.) + (a - 1.) * cos_w0 + 2. * alpha_s * sqrt_a),
+                -2. * a * ((a - 1.) + (a + 1.) * cos_w0),
+                a * ((a + 1.) + (a - 1.) * cos_w0 - 2. * alpha_s * sqrt_a),
+                (a + 1.) - (a - 1.) * cos_w0 + 2. * alpha_s * sqrt_a,
+                2. * ((a - 1.) - (a + 1.) * cos_w0),
+                (a + 1.) - (a - 1.) * cos_w0 - 2. * alpha_s * sqrt_a,
+            ),
+        };
+
+        Coefficients {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+
+    /// Returns the magnitude and phase of the response of the filter at
+    /// `frequency`, normalized to the Nyquist frequency.
+    fn response(&self, frequency: f64) -> (f64, f64) {
+        // Evaluate H(z) at z = e^(jw), with z^-1 = cos(w) - j sin(w) and
+        // z^-2 = cos(2w) - j sin(2w).
+        let w = PI * frequency;
+        let (sin_w, cos_w) = w.sin_cos();
+        let (sin_2w, cos_2w) = (2. * w).sin_cos();
+
+        let num_re = self.b0 + self.b1 * cos_w + self.b2 * cos_2w;
+        let num_im = -(self.b1 * sin_w + self.b2 * sin_2w);
+        let den_re = 1. + self.a1 * cos_w + self.a2 * cos_2w;
+        let den_im = -(self.a1 * sin_w + self.a2 * sin_2w);
+
+        let den_norm = den_re * den_re + den_im * den_im;
+        let re = (num_re * den_re + num_im * den_im) / den_norm;
+        let im = (num_im * den_re - num_re * den_im) / den_norm;
+
+        ((re * re + im * im).sqrt(), im.atan2(re))
+    }
+}
+
+impl<'a> From<&'a BiquadFilterOptions> for BiquadFilterNodeOptions {
+    fn from(options: &'a BiquadFilterOptions) -> Self {
+        Self {
+            gain: *options.gain,
+            q: *options.Q,
+            frequency: *options.frequency,
+            detune: *options.detune,
+            filter: options.type_.into(),
+        }
+    }
+}
+
+impl From<BiquadFilterType> for FilterType {
+    fn from(filter: BiquadFilterType) -> FilterType {
+        match filter {
+            BiquadFilterType::Lowpass => FilterType::LowPass,
+            BiquadFilterType::Highpass => FilterType::HighPass,
+            BiquadFilterType::Bandpass => FilterType::BandPass,
+            BiquadFilterType::Lowshelf => FilterType::LowShelf,
+            BiquadFilterType::Highshelf => FilterType::HighShelf,
+            BiquadFilterType::Peaking => FilterType::Peaking,
+            BiquadFilterType::Allpass => FilterType::AllPass,
+            BiquadFilterType::Notch => FilterType::Notch,
+        }
+    }
+}
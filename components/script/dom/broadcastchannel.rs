@@ -0,0 +1,107 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::BroadcastChannelBinding;
+use dom::bindings::codegen::Bindings::BroadcastChannelBinding::BroadcastChannelMethods;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::bindings::structuredclone::StructuredCloneData;
+use dom::event::Event;
+use dom::eventtarget::EventTarget;
+use dom::globalscope::GlobalScope;
+use dom::messageevent::MessageEvent;
+use dom_struct::dom_struct;
+use js::jsapi::{JSAutoCompartment, JSContext};
+use js::jsval::UndefinedValue;
+use js::rust::HandleValue;
+use std::cell::Cell;
+
+/// <https://html.spec.whatwg.org/multipage/#broadcastchannel>
+#[dom_struct]
+pub struct BroadcastChannel {
+    eventtarget: EventTarget,
+    name: DOMString,
+    /// <https://html.spec.whatwg.org/multipage/#concept-broadcastchannel-closed>
+    closed: Cell<bool>,
+}
+
+impl BroadcastChannel {
+    fn new_inherited(name: DOMString) -> BroadcastChannel {
+        BroadcastChannel {
+            eventtarget: EventTarget::new_inherited(),
+            name: name,
+            closed: Cell::new(false),
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-broadcastchannel
+    pub fn Constructor(global: &GlobalScope, name: DOMString) -> Fallible<DomRoot<BroadcastChannel>> {
+        let channel = reflect_dom_object(Box::new(BroadcastChannel::new_inherited(name)),
+                                         global,
+                                         BroadcastChannelBinding::Wrap);
+        global.track_broadcast_channel(&channel);
+        Ok(channel)
+    }
+
+    /// Fires a message event at this channel for a message broadcast to it.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#dom-broadcastchannel-postmessage>
+    pub fn dispatch_message(&self, data: Vec<u8>) {
+        // Step 10.3.
+        if self.closed.get() {
+            return;
+        }
+        let global = self.global();
+        let cx = global.get_cx();
+        let _ac = JSAutoCompartment::new(cx, self.reflector().get_jsobject().get());
+        rooted!(in(cx) let mut message = UndefinedValue());
+        let ports = StructuredCloneData::Vector(data).read(&global, message.handle_mut());
+        let event = MessageEvent::new(&global,
+                                      atom!("message"),
+                                      false,
+                                      false,
+                                      message.handle(),
+                                      DOMString::from(global.origin().immutable().ascii_serialization()),
+                                      DOMString::new(),
+                                      ports);
+        event.upcast::<Event>().fire(self.upcast());
+    }
+}
+
+impl BroadcastChannelMethods for BroadcastChannel {
+    // https://html.spec.whatwg.org/multipage/#dom-broadcastchannel-name
+    fn Name(&self) -> DOMString {
+        self.name.clone()
+    }
+
+    #[allow(unsafe_code)]
+    // https://html.spec.whatwg.org/multipage/#dom-broadcastchannel-postmessage
+    unsafe fn PostMessage(&self, cx: *mut JSContext, message: HandleValue) -> ErrorResult {
+        // Step 2.
+        if self.closed.get() {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 3.
+        let data = StructuredCloneData::write(cx, message)?;
+
+        // Steps 4-10.
+        self.global().broadcast_message(self, data.move_to_arraybuffer());
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-broadcastchannel-close
+    fn Close(&self) {
+        if self.closed.replace(true) {
+            return;
+        }
+        self.global().untrack_broadcast_channel(self);
+    }
+
+    // https://html.spec.whatwg.org/multipage/#handler-broadcastchannel-onmessage
+    event_handler!(message, GetOnmessage, SetOnmessage);
+}
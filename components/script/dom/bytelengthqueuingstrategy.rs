@@ -0,0 +1,93 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::ByteLengthQueuingStrategyBinding;
+use dom::bindings::codegen::Bindings::ByteLengthQueuingStrategyBinding::ByteLengthQueuingStrategyMethods;
+use dom::bindings::codegen::Bindings::ByteLengthQueuingStrategyBinding::QueuingStrategyInit;
+use dom::bindings::codegen::Bindings::FunctionBinding::Function;
+use dom::bindings::error::Fallible;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::bindings::utils::get_dictionary_property;
+use dom::globalscope::GlobalScope;
+use dom_struct::dom_struct;
+use js::error::throw_type_error;
+use js::jsapi::{CallArgs, Heap, JSContext, JSNative, JSObject, JS_GetFunctionObject, JS_NewFunction};
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::HandleValue;
+use std::rc::Rc;
+
+/// <https://streams.spec.whatwg.org/#blqs-class>
+#[dom_struct]
+pub struct ByteLengthQueuingStrategy {
+    reflector_: Reflector,
+    high_water_mark: f64,
+    #[ignore_malloc_size_of = "mozjs"]
+    size: Heap<*mut JSObject>,
+}
+
+impl ByteLengthQueuingStrategy {
+    fn new_inherited(high_water_mark: f64) -> ByteLengthQueuingStrategy {
+        ByteLengthQueuingStrategy {
+            reflector_: Reflector::new(),
+            high_water_mark: high_water_mark,
+            size: Heap::default(),
+        }
+    }
+
+    #[allow(unsafe_code)]
+    pub fn new(global: &GlobalScope, high_water_mark: f64) -> DomRoot<ByteLengthQueuingStrategy> {
+        let strategy = reflect_dom_object(Box::new(ByteLengthQueuingStrategy::new_inherited(high_water_mark)),
+                                          global,
+                                          ByteLengthQueuingStrategyBinding::Wrap);
+        strategy.size.set(unsafe { new_size_function(global.get_cx(), Some(byte_length_size)) });
+        strategy
+    }
+
+    // https://streams.spec.whatwg.org/#blqs-constructor
+    pub fn Constructor(global: &GlobalScope,
+                       init: &QueuingStrategyInit)
+                       -> Fallible<DomRoot<ByteLengthQueuingStrategy>> {
+        Ok(ByteLengthQueuingStrategy::new(global, init.highWaterMark))
+    }
+}
+
+/// Creates the function object of the size function of a queuing strategy.
+#[allow(unsafe_code)]
+pub unsafe fn new_size_function(cx: *mut JSContext, native: JSNative) -> *mut JSObject {
+    let function = JS_NewFunction(cx, native, /* nargs = */ 1, /* flags = */ 0, b"size\0".as_ptr() as *const _);
+    assert!(!function.is_null());
+    JS_GetFunctionObject(function)
+}
+
+/// <https://streams.spec.whatwg.org/#byte-length-queuing-strategy-size-function>
+#[allow(unsafe_code)]
+unsafe extern fn byte_length_size(cx: *mut JSContext, argc: u32, vp: *mut JSVal) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    let chunk = HandleValue::from_raw(args.get(0));
+    if !chunk.is_object() {
+        throw_type_error(cx, "The chunk has no byteLength");
+        return false;
+    }
+    rooted!(in(cx) let chunk = chunk.to_object());
+    rooted!(in(cx) let mut byte_length = UndefinedValue());
+    if get_dictionary_property(cx, chunk.handle(), "byteLength", byte_length.handle_mut()).is_err() {
+        return false;
+    }
+    *args.rval() = byte_length.get();
+    true
+}
+
+impl ByteLengthQueuingStrategyMethods for ByteLengthQueuingStrategy {
+    // https://streams.spec.whatwg.org/#blqs-high-water-mark
+    fn HighWaterMark(&self) -> f64 {
+        self.high_water_mark
+    }
+
+    #[allow(unsafe_code)]
+    // https://streams.spec.whatwg.org/#blqs-size
+    fn Size(&self) -> Rc<Function> {
+        unsafe { Function::new(self.global().get_cx(), self.size.get()) }
+    }
+}
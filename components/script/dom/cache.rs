@@ -0,0 +1,380 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use body::{BodyType, consume_body};
+use cache_storage::{query_options, request_from_info, send_request};
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::CacheBinding;
+use dom::bindings::codegen::Bindings::CacheBinding::{CacheMethods, CacheQueryOptions};
+use dom::bindings::codegen::Bindings::RequestBinding::{RequestInfo, RequestInit};
+use dom::bindings::codegen::Bindings::ResponseBinding::ResponseBinding::ResponseMethods;
+use dom::bindings::codegen::Bindings::ResponseBinding::ResponseType as DOMResponseType;
+use dom::bindings::conversions::root_from_handlevalue;
+use dom::bindings::error::Error;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::promisenativehandler::{Callback, PromiseNativeHandler};
+use dom::request::Request;
+use dom::response::Response;
+use dom_struct::dom_struct;
+use fetch;
+use ipc_channel::ipc::IpcSender;
+use js::jsapi::JSContext;
+use js::rust::HandleValue;
+use js::typedarray::ArrayBuffer;
+use net_traits::cache_storage_thread::{CacheBatchOperation, CacheId, CacheStorageResult, CacheStorageThreadMsg};
+use net_traits::cache_storage_thread::{CachedRequest, CachedResponse, combined_header_value};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::rc::Rc;
+
+#[dom_struct]
+pub struct Cache {
+    reflector_: Reflector,
+    /// The cache in the cache storage thread.
+    id: CacheId,
+}
+
+impl Cache {
+    fn new_inherited(id: CacheId) -> Cache {
+        Cache {
+            reflector_: Reflector::new(),
+            id: id,
+        }
+    }
+
+    pub fn new(global: &GlobalScope, id: CacheId) -> DomRoot<Cache> {
+        reflect_dom_object(Box::new(Cache::new_inherited(id)),
+                           global,
+                           CacheBinding::Wrap)
+    }
+
+    /// The request described by `info`, which needs to be a GET request to an HTTP(S) URL
+    /// to be stored in a cache.
+    fn request_to_store(&self, info: RequestInfo) -> Result<DomRoot<Request>, Error> {
+        let request = request_from_info(&self.global(), info)?;
+        let cached = request.to_cached_request();
+        match cached.url.scheme() {
+            "http" | "https" => {},
+            _ => return Err(Error::Type("Only HTTP(S) requests can be cached".to_owned())),
+        }
+        if cached.method != "GET" {
+            return Err(Error::Type("Only GET requests can be cached".to_owned()));
+        }
+        Ok(request)
+    }
+
+    /// Send the message built by `message` for the request described by `info`, if any, and call
+    /// `handler` with the promise returned to script and the reply.
+    #[allow(unrooted_must_root)]
+    fn query<T, M, F>(&self, info: Option<RequestInfo>, message: M, handler: F) -> Rc<Promise>
+        where T: for<'de> Deserialize<'de> + Serialize + Send + 'static,
+              M: FnOnce(IpcSender<CacheStorageResult<T>>, Option<CachedRequest>) -> CacheStorageThreadMsg,
+              F: FnOnce(&Promise, T) + Send + 'static
+    {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        let request = match info.map(|info| request_from_info(&global, info)) {
+            Some(Ok(request)) => Some(request.to_cached_request()),
+            Some(Err(error)) => {
+                promise.reject_error(error);
+                return promise;
+            },
+            None => None,
+        };
+        send_request(&promise, |sender| message(sender, request), |promise, result| match result {
+            Ok(reply) => handler(promise, reply),
+            Err(error) => promise.reject_error(Error::from(error)),
+        });
+        promise
+    }
+}
+
+/// Whether the `Vary` header of a response contains `*`, which prevents it from ever matching.
+fn varies_on_everything(response: &CachedResponse) -> bool {
+    combined_header_value(&response.headers, "vary").map_or(false, |vary| {
+        vary.split(|&byte| byte == b',').any(|field| String::from_utf8_lossy(field).trim() == "*")
+    })
+}
+
+/// The entries of a `put`, `add` or `addAll` call, whose responses are read before being stored.
+#[derive(JSTraceable, MallocSizeOf)]
+struct PendingPuts {
+    id: CacheId,
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+    requests: Vec<CachedRequest>,
+    responses: DomRefCell<Vec<Option<CachedResponse>>>,
+    /// The number of responses which still need to be read.
+    remaining: Cell<usize>,
+    /// Whether the promise was rejected already.
+    failed: Cell<bool>,
+}
+
+impl PendingPuts {
+    #[allow(unrooted_must_root)]
+    fn new(id: CacheId, promise: &Rc<Promise>, requests: Vec<CachedRequest>) -> Rc<PendingPuts> {
+        Rc::new(PendingPuts {
+            id: id,
+            promise: promise.clone(),
+            responses: DomRefCell::new(requests.iter().map(|_| None).collect()),
+            remaining: Cell::new(requests.len()),
+            requests: requests,
+            failed: Cell::new(false),
+        })
+    }
+
+    fn fail(&self, error: Error) {
+        if !self.failed.replace(true) {
+            self.promise.reject_error(error);
+        }
+    }
+
+    /// Check that `response` can be stored, then read its body.
+    fn read_response(pending: &Rc<PendingPuts>, index: usize, response: &Response) {
+        let cached = response.to_cached_response(vec![]);
+        if cached.status == 206 {
+            return pending.fail(Error::Type("Partial responses cannot be cached".to_owned()));
+        }
+        if varies_on_everything(&cached) {
+            return pending.fail(Error::Type("Responses varying on every header cannot be cached".to_owned()));
+        }
+        if response.BodyUsed() {
+            return pending.fail(Error::Type("The body of the response was already used".to_owned()));
+        }
+
+        let global = response.global();
+        let body = consume_body(response, BodyType::ArrayBuffer);
+        let read = BodyRead {
+            pending: pending.clone(),
+            index: index,
+            response: DomRefCell::new(Some(cached)),
+        };
+        let failed = PutFailed { pending: pending.clone() };
+        let handler = PromiseNativeHandler::new(&global, Some(Box::new(read)), Some(Box::new(failed)));
+        body.append_native_handler(&handler);
+    }
+
+    /// Store a response once its body was read, then store every entry once all the responses were read.
+    fn response_read(&self, index: usize, response: CachedResponse) {
+        if self.failed.get() {
+            return;
+        }
+        self.responses.borrow_mut()[index] = Some(response);
+        self.remaining.set(self.remaining.get() - 1);
+        if self.remaining.get() > 0 {
+            return;
+        }
+
+        let responses = self.responses.borrow_mut().drain(..).map(Option::unwrap).collect::<Vec<_>>();
+        let operations = self.requests.iter().cloned().zip(responses)
+            .map(|(request, response)| CacheBatchOperation::Put(request, response))
+            .collect();
+        let id = self.id;
+        send_request(&self.promise, |sender| CacheStorageThreadMsg::Batch(sender, id, operations),
+                     |promise, result: CacheStorageResult<bool>| match result {
+            Ok(_) => promise.resolve_native(&()),
+            Err(error) => promise.reject_error(error.into()),
+        });
+    }
+}
+
+/// Checks a response fetched by `addAll`, and reads its body.
+#[derive(JSTraceable, MallocSizeOf)]
+struct ResponseFetched {
+    #[ignore_malloc_size_of = "Rc"]
+    pending: Rc<PendingPuts>,
+    index: usize,
+}
+
+impl Callback for ResponseFetched {
+    fn callback(&self, _cx: *mut JSContext, value: HandleValue) {
+        let response = match root_from_handlevalue::<Response>(value) {
+            Ok(response) => response,
+            Err(()) => return self.pending.fail(Error::Type("Fetching did not produce a response".to_owned())),
+        };
+        if response.Type() == DOMResponseType::Error || !response.Ok() {
+            return self.pending.fail(Error::Type("Only successful responses can be added to a cache".to_owned()));
+        }
+        PendingPuts::read_response(&self.pending, self.index, &response);
+    }
+}
+
+/// Completes a response with the body it was read with.
+#[derive(JSTraceable, MallocSizeOf)]
+struct BodyRead {
+    #[ignore_malloc_size_of = "Rc"]
+    pending: Rc<PendingPuts>,
+    index: usize,
+    response: DomRefCell<Option<CachedResponse>>,
+}
+
+impl Callback for BodyRead {
+    #[allow(unsafe_code)]
+    fn callback(&self, cx: *mut JSContext, value: HandleValue) {
+        if !value.is_object() {
+            return self.pending.fail(Error::Type("The body of the response could not be read".to_owned()));
+        }
+        let body = unsafe {
+            typedarray!(in(cx) let buffer: ArrayBuffer = value.to_object());
+            match buffer {
+                Ok(buffer) => buffer.to_vec(),
+                Err(()) => {
+                    let error = Error::Type("The body of the response could not be read".to_owned());
+                    return self.pending.fail(error);
+                },
+            }
+        };
+        if let Some(mut response) = self.response.borrow_mut().take() {
+            response.body = body;
+            self.pending.response_read(self.index, response);
+        }
+    }
+}
+
+/// Rejects the promise of a `put`, `add` or `addAll` call when fetching or reading a response failed.
+#[derive(JSTraceable, MallocSizeOf)]
+struct PutFailed {
+    #[ignore_malloc_size_of = "Rc"]
+    pending: Rc<PendingPuts>,
+}
+
+impl Callback for PutFailed {
+    #[allow(unsafe_code)]
+    fn callback(&self, cx: *mut JSContext, value: HandleValue) {
+        if !self.pending.failed.replace(true) {
+            unsafe { self.pending.promise.reject(cx, value) };
+        }
+    }
+}
+
+impl CacheMethods for Cache {
+    #[allow(unrooted_must_root)]
+    // https://w3c.github.io/ServiceWorker/#cache-match
+    fn Match(&self, request: RequestInfo, options: &CacheQueryOptions) -> Rc<Promise> {
+        let (id, options) = (self.id, query_options(options));
+        let message = |sender, request| CacheStorageThreadMsg::MatchAll(sender, id, request, options);
+        self.query(Some(request), message, |promise, responses: Vec<CachedResponse>| {
+            match responses.into_iter().next() {
+                Some(response) => promise.resolve_native(&Response::from_cached_response(&promise.global(), response)),
+                None => promise.resolve_native(&()),
+            }
+        })
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://w3c.github.io/ServiceWorker/#cache-matchall
+    fn MatchAll(&self, request: Option<RequestInfo>, options: &CacheQueryOptions) -> Rc<Promise> {
+        let (id, options) = (self.id, query_options(options));
+        let message = |sender, request| CacheStorageThreadMsg::MatchAll(sender, id, request, options);
+        self.query(request, message, |promise, responses: Vec<CachedResponse>| {
+            let global = promise.global();
+            let responses: Vec<_> = responses.into_iter()
+                .map(|response| Response::from_cached_response(&global, response))
+                .collect();
+            promise.resolve_native(&responses);
+        })
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://w3c.github.io/ServiceWorker/#cache-add
+    fn Add(&self, request: RequestInfo) -> Rc<Promise> {
+        self.AddAll(vec![request])
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://w3c.github.io/ServiceWorker/#cache-addAll
+    fn AddAll(&self, requests: Vec<RequestInfo>) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+
+        // Steps 1-3.
+        let mut requests_to_fetch = vec![];
+        for request in requests {
+            match self.request_to_store(request) {
+                Ok(request) => requests_to_fetch.push(request),
+                Err(error) => {
+                    promise.reject_error(error);
+                    return promise;
+                },
+            }
+        }
+        if requests_to_fetch.is_empty() {
+            promise.resolve_native(&());
+            return promise;
+        }
+
+        // Steps 4-7.
+        let cached_requests = requests_to_fetch.iter().map(|request| request.to_cached_request()).collect();
+        let pending = PendingPuts::new(self.id, &promise, cached_requests);
+        for (index, request) in requests_to_fetch.into_iter().enumerate() {
+            let response = fetch::Fetch(&global, RequestInfo::Request(request), RequestInit::empty());
+            let fetched = ResponseFetched {
+                pending: pending.clone(),
+                index: index,
+            };
+            let failed = PutFailed { pending: pending.clone() };
+            let handler = PromiseNativeHandler::new(&global, Some(Box::new(fetched)), Some(Box::new(failed)));
+            response.append_native_handler(&handler);
+        }
+        promise
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://w3c.github.io/ServiceWorker/#cache-put
+    fn Put(&self, request: RequestInfo, response: &Response) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+
+        // Steps 1-4.
+        let request = match self.request_to_store(request) {
+            Ok(request) => request,
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+
+        // Steps 5-14.
+        let pending = PendingPuts::new(self.id, &promise, vec![request.to_cached_request()]);
+        PendingPuts::read_response(&pending, 0, response);
+        promise
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://w3c.github.io/ServiceWorker/#cache-delete
+    fn Delete(&self, request: RequestInfo, options: &CacheQueryOptions) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        let request = match request_from_info(&self.global(), request) {
+            Ok(request) => request.to_cached_request(),
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        let id = self.id;
+        let operations = vec![CacheBatchOperation::Delete(request, query_options(options))];
+        send_request(&promise, |sender| CacheStorageThreadMsg::Batch(sender, id, operations),
+                     |promise, result: CacheStorageResult<bool>| match result {
+            Ok(deleted) => promise.resolve_native(&deleted),
+            Err(error) => promise.reject_error(error.into()),
+        });
+        promise
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://w3c.github.io/ServiceWorker/#cache-keys
+    fn Keys(&self, request: Option<RequestInfo>, options: &CacheQueryOptions) -> Rc<Promise> {
+        let (id, options) = (self.id, query_options(options));
+        let message = |sender, request| CacheStorageThreadMsg::RequestKeys(sender, id, request, options);
+        self.query(request, message, |promise, requests: Vec<CachedRequest>| {
+            let global = promise.global();
+            let requests: Vec<_> = requests.into_iter()
+                .map(|request| Request::from_cached_request(&global, request))
+                .collect();
+            promise.resolve_native(&requests);
+        })
+    }
+}
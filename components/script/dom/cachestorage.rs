@@ -0,0 +1,136 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use cache_storage::{cache_storage_origin, query_options, request_from_info, send_request};
+use dom::bindings::codegen::Bindings::CacheStorageBinding;
+use dom::bindings::codegen::Bindings::CacheStorageBinding::{CacheStorageMethods, MultiCacheQueryOptions};
+use dom::bindings::codegen::Bindings::RequestBinding::RequestInfo;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::cache::Cache;
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::response::Response;
+use dom_struct::dom_struct;
+use net_traits::cache_storage_thread::{CacheId, CacheStorageThreadMsg, CachedResponse};
+use std::rc::Rc;
+
+#[dom_struct]
+pub struct CacheStorage {
+    reflector_: Reflector,
+}
+
+impl CacheStorage {
+    fn new_inherited() -> CacheStorage {
+        CacheStorage {
+            reflector_: Reflector::new(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<CacheStorage> {
+        reflect_dom_object(Box::new(CacheStorage::new_inherited()),
+                           global,
+                           CacheStorageBinding::Wrap)
+    }
+}
+
+impl CacheStorageMethods for CacheStorage {
+    #[allow(unrooted_must_root)]
+    // https://w3c.github.io/ServiceWorker/#cache-storage-match
+    fn Match(&self, request: RequestInfo, options: &MultiCacheQueryOptions) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        let origin = match cache_storage_origin(&global) {
+            Ok(origin) => origin,
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        let request = match request_from_info(&global, request) {
+            Ok(request) => request.to_cached_request(),
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        let name = options.cacheName.as_ref().map(|name| String::from(name.clone()));
+        let options = query_options(&options.parent);
+        send_request(&promise, |sender| CacheStorageThreadMsg::MatchAny(sender, origin, name, request, options),
+                     |promise, response: Option<CachedResponse>| {
+            match response {
+                Some(response) => promise.resolve_native(&Response::from_cached_response(&promise.global(), response)),
+                None => promise.resolve_native(&()),
+            }
+        });
+        promise
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://w3c.github.io/ServiceWorker/#cache-storage-has
+    fn Has(&self, cache_name: DOMString) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        match cache_storage_origin(&global) {
+            Ok(origin) => {
+                let name = String::from(cache_name);
+                send_request(&promise, |sender| CacheStorageThreadMsg::Has(sender, origin, name),
+                             |promise, has: bool| promise.resolve_native(&has));
+            },
+            Err(error) => promise.reject_error(error),
+        }
+        promise
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://w3c.github.io/ServiceWorker/#cache-storage-open
+    fn Open(&self, cache_name: DOMString) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        match cache_storage_origin(&global) {
+            Ok(origin) => {
+                let name = String::from(cache_name);
+                send_request(&promise, |sender| CacheStorageThreadMsg::Open(sender, origin, name),
+                             |promise, id: CacheId| promise.resolve_native(&Cache::new(&promise.global(), id)));
+            },
+            Err(error) => promise.reject_error(error),
+        }
+        promise
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://w3c.github.io/ServiceWorker/#cache-storage-delete
+    fn Delete(&self, cache_name: DOMString) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        match cache_storage_origin(&global) {
+            Ok(origin) => {
+                let name = String::from(cache_name);
+                send_request(&promise, |sender| CacheStorageThreadMsg::Delete(sender, origin, name),
+                             |promise, deleted: bool| promise.resolve_native(&deleted));
+            },
+            Err(error) => promise.reject_error(error),
+        }
+        promise
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://w3c.github.io/ServiceWorker/#cache-storage-keys
+    fn Keys(&self) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        match cache_storage_origin(&global) {
+            Ok(origin) => {
+                send_request(&promise, |sender| CacheStorageThreadMsg::Keys(sender, origin),
+                             |promise, names: Vec<String>| {
+                    let names: Vec<DOMString> = names.into_iter().map(DOMString::from).collect();
+                    promise.resolve_native(&names);
+                });
+            },
+            Err(error) => promise.reject_error(error),
+        }
+        promise
+    }
+}
@@ -2,10 +2,13 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use canvas_traits::canvas::{Canvas2dMsg, CanvasMsg, CanvasId};
+use canvas_traits::canvas::{Canvas2dMsg, CanvasFont, CanvasMsg, CanvasId};
 use canvas_traits::canvas::{CompositionOrBlending, FillOrStrokeStyle, FillRule};
 use canvas_traits::canvas::{LineCapStyle, LineJoinStyle, LinearGradientStyle};
-use canvas_traits::canvas::{RadialGradientStyle, RepetitionStyle, byte_swap_and_premultiply};
+use canvas_traits::canvas::{RadialGradientStyle, RepetitionStyle, TextAlign, TextBaseline};
+use canvas_traits::canvas::TextMetrics as CanvasTextMetrics;
+use canvas_traits::canvas::byte_swap;
+use canvas_traits::canvas::byte_swap_and_premultiply;
 use cssparser::{Parser, ParserInput, RGBA};
 use cssparser::Color as CSSColor;
 use dom::bindings::cell::DomRefCell;
@@ -15,6 +18,8 @@ use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasIma
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasLineCap;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasLineJoin;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasRenderingContext2DMethods;
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasTextAlign;
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasTextBaseline;
 use dom::bindings::codegen::Bindings::ImageDataBinding::ImageDataMethods;
 use dom::bindings::codegen::UnionTypes::StringOrCanvasGradientOrCanvasPattern;
 use dom::bindings::error::{Error, ErrorResult, Fallible};
@@ -25,13 +30,16 @@ use dom::bindings::root::{Dom, DomRoot, LayoutDom};
 use dom::bindings::str::DOMString;
 use dom::canvasgradient::{CanvasGradient, CanvasGradientStyle, ToFillOrStrokeStyle};
 use dom::canvaspattern::CanvasPattern;
+use dom::dommatrix::DOMMatrix;
 use dom::element::Element;
 use dom::globalscope::GlobalScope;
 use dom::htmlcanvaselement::HTMLCanvasElement;
 use dom::imagedata::ImageData;
 use dom::node::{Node, NodeDamage, window_from_node};
+use dom::path2d::Path2D;
+use dom::textmetrics::TextMetrics;
 use dom_struct::dom_struct;
-use euclid::{Transform2D, Point2D, Vector2D, Rect, Size2D, vec2};
+use euclid::{Transform2D, Transform3D, Point2D, Vector2D, Rect, Size2D, vec2};
 use ipc_channel::ipc::IpcSender;
 use net_traits::image::base::PixelFormat;
 use net_traits::image_cache::CanRequestImages;
@@ -66,10 +74,14 @@ pub struct CanvasRenderingContext2D {
     #[ignore_malloc_size_of = "Defined in ipc-channel"]
     ipc_renderer: IpcSender<CanvasMsg>,
     /// For rendering contexts created by an HTML canvas element, this is Some,
-    /// for ones created by a paint worklet, this is None.
+    /// for ones created by a paint worklet or an OffscreenCanvas, this is None.
     canvas: Option<Dom<HTMLCanvasElement>>,
+    /// The dimensions of the output bitmap.
+    size: Cell<Size2D<i32>>,
+    /// The image cache used to look up images drawn onto this context.
+    /// Workers have no image cache, so this is None for contexts created there.
     #[ignore_malloc_size_of = "Arc"]
-    image_cache: Arc<ImageCache>,
+    image_cache: Option<Arc<ImageCache>>,
     /// Any missing image URLs.
     missing_image_urls: DomRefCell<Vec<ServoUrl>>,
     /// The base URL for resolving CSS image URL values.
@@ -98,6 +110,11 @@ struct CanvasContextState {
     shadow_offset_y: f64,
     shadow_blur: f64,
     shadow_color: RGBA,
+    line_dash: Vec<f64>,
+    line_dash_offset: f64,
+    font: CanvasFont,
+    text_align: TextAlign,
+    text_baseline: TextBaseline,
 }
 
 impl CanvasContextState {
@@ -118,6 +135,11 @@ impl CanvasContextState {
             shadow_offset_y: 0.0,
             shadow_blur: 0.0,
             shadow_color: RGBA::transparent(),
+            line_dash: Vec::new(),
+            line_dash_offset: 0.0,
+            font: CanvasFont::default(),
+            text_align: TextAlign::default(),
+            text_baseline: TextBaseline::default(),
         }
     }
 }
@@ -125,7 +147,7 @@ impl CanvasContextState {
 impl CanvasRenderingContext2D {
     pub fn new_inherited(global: &GlobalScope,
                          canvas: Option<&HTMLCanvasElement>,
-                         image_cache: Arc<ImageCache>,
+                         image_cache: Option<Arc<ImageCache>>,
                          base_url: ServoUrl,
                          size: Size2D<i32>)
                          -> CanvasRenderingContext2D {
@@ -140,6 +162,7 @@ impl CanvasRenderingContext2D {
             reflector_: Reflector::new(),
             ipc_renderer: ipc_renderer,
             canvas: canvas.map(Dom::from_ref),
+            size: Cell::new(size),
             image_cache: image_cache,
             missing_image_urls: DomRefCell::new(Vec::new()),
             base_url: base_url,
@@ -158,7 +181,7 @@ impl CanvasRenderingContext2D {
         let image_cache = window.image_cache();
         let base_url = window.get_url();
         let boxed = Box::new(CanvasRenderingContext2D::new_inherited(
-            global, Some(canvas), image_cache, base_url, size
+            global, Some(canvas), Some(image_cache), base_url, size
         ));
         reflect_dom_object(boxed, global, CanvasRenderingContext2DBinding::Wrap)
     }
@@ -166,6 +189,7 @@ impl CanvasRenderingContext2D {
     // https://html.spec.whatwg.org/multipage/#concept-canvas-set-bitmap-dimensions
     pub fn set_bitmap_dimensions(&self, size: Size2D<i32>) {
         self.reset_to_initial_state();
+        self.size.set(size);
         self.ipc_renderer
             .send(CanvasMsg::Recreate(size, self.get_canvas_id()))
             .unwrap();
@@ -248,6 +272,8 @@ impl CanvasRenderingContext2D {
             }
             CanvasImageSource::CanvasRenderingContext2D(image) =>
                 image.origin_is_clean(),
+            CanvasImageSource::ImageBitmap(bitmap) => bitmap.origin_is_clean(),
+            CanvasImageSource::OffscreenCanvas(canvas) => canvas.origin_is_clean(),
             CanvasImageSource::HTMLImageElement(image) => {
                 let image_origin = image.get_origin().expect("Image's origin is missing");
                 image_origin.same_origin(GlobalScope::entry().origin())
@@ -308,6 +334,19 @@ impl CanvasRenderingContext2D {
                                                sx, sy, sw, sh,
                                                dx, dy, dw, dh)
             }
+            CanvasImageSource::ImageBitmap(ref bitmap) => {
+                // https://html.spec.whatwg.org/multipage/#check-the-usability-of-the-image-argument
+                // If the ImageBitmap has its [[Detached]] slot set, throw an InvalidStateError.
+                let mut data = bitmap.bitmap_data().ok_or(Error::InvalidState)?;
+                byte_swap_and_premultiply(&mut data);
+                self.draw_pixels(data, bitmap.get_size(), sx, sy, sw, sh, dx, dy, dw, dh)
+            }
+            CanvasImageSource::OffscreenCanvas(ref canvas) => {
+                let (mut data, size) = canvas.fetch_all_data().ok_or(Error::InvalidState)?;
+                // The pixels are already premultiplied, but come in BGRA order.
+                byte_swap(&mut data);
+                self.draw_pixels(data, size, sx, sy, sw, sh, dx, dy, dw, dh)
+            }
             CanvasImageSource::CSSStyleValue(ref value) => {
                 let url = value.get_url(self.base_url.clone()).ok_or(Error::InvalidState)?;
                 self.fetch_and_draw_image_data(url,
@@ -425,6 +464,34 @@ impl CanvasRenderingContext2D {
                              dx, dy, dw, dh)
     }
 
+    /// Draws premultiplied RGBA pixels that are already available to script,
+    /// such as the contents of an ImageBitmap or an OffscreenCanvas.
+    fn draw_pixels(&self,
+                   data: Vec<u8>,
+                   size: Size2D<i32>,
+                   sx: f64,
+                   sy: f64,
+                   sw: Option<f64>,
+                   sh: Option<f64>,
+                   dx: f64,
+                   dy: f64,
+                   dw: Option<f64>,
+                   dh: Option<f64>)
+                   -> ErrorResult {
+        if size.width == 0 || size.height == 0 {
+            return Err(Error::InvalidState);
+        }
+        let image_size = Size2D::new(size.width as f64, size.height as f64);
+        let dw = dw.unwrap_or(image_size.width);
+        let dh = dh.unwrap_or(image_size.height);
+        let sw = sw.unwrap_or(image_size.width);
+        let sh = sh.unwrap_or(image_size.height);
+        self.draw_image_data(data,
+                             image_size,
+                             sx, sy, sw, sh,
+                             dx, dy, dw, dh)
+    }
+
     fn draw_image_data(&self,
                        image_data: Vec<u8>,
                        image_size: Size2D<f64>,
@@ -487,7 +554,11 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     fn request_image_from_cache(&self, url: ServoUrl) -> ImageResponse {
-        let response = self.image_cache
+        let image_cache = match self.image_cache {
+            Some(ref image_cache) => image_cache,
+            None => return ImageResponse::None,
+        };
+        let response = image_cache
             .find_image_or_metadata(url.clone(),
                                     UsePlaceholder::No,
                                     CanRequestImages::No);
@@ -557,6 +628,21 @@ impl CanvasRenderingContext2D {
         }
     }
 
+    /// The computed font size of the canvas element, against which relative sizes in the
+    /// `font` attribute are resolved. Paint worklets and canvases that aren't being rendered
+    /// use the initial `10px`.
+    fn parent_font_size(&self) -> f64 {
+        let canvas = match self.canvas {
+            None => return 10.0,
+            Some(ref canvas) => canvas.upcast::<Element>(),
+        };
+
+        match canvas.style() {
+            Some(ref s) if canvas.has_css_layout_box() => s.get_font().font_size.size().to_f64_px(),
+            _ => 10.0,
+        }
+    }
+
     pub fn get_canvas_id(&self) -> CanvasId {
         self.canvas_id.clone()
     }
@@ -680,6 +766,15 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
         self.update_transform()
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-gettransform
+    fn GetTransform(&self) -> DomRoot<DOMMatrix> {
+        let transform = self.state.borrow().transform;
+        let matrix = Transform3D::row_major_2d(transform.m11 as f64, transform.m12 as f64,
+                                               transform.m21 as f64, transform.m22 as f64,
+                                               transform.m31 as f64, transform.m32 as f64);
+        DOMMatrix::new(&self.global(), true, matrix)
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-settransform
     fn SetTransform(&self, a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) {
         if !(a.is_finite() && b.is_finite() && c.is_finite() &&
@@ -772,29 +867,51 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
         self.mark_as_dirty();
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-fill
+    fn Fill_(&self, path: &Path2D, fill_rule: CanvasFillRule) {
+        self.send_canvas_2d_msg(Canvas2dMsg::FillPath(path.segments(), to_fill_rule(fill_rule)));
+        self.mark_as_dirty();
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroke
     fn Stroke(&self) {
         self.send_canvas_2d_msg(Canvas2dMsg::Stroke);
         self.mark_as_dirty();
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroke
+    fn Stroke_(&self, path: &Path2D) {
+        self.send_canvas_2d_msg(Canvas2dMsg::StrokePath(path.segments()));
+        self.mark_as_dirty();
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-clip
     fn Clip(&self, _: CanvasFillRule) {
         // TODO: Process fill rule
         self.send_canvas_2d_msg(Canvas2dMsg::Clip);
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-clip
+    fn Clip_(&self, path: &Path2D, fill_rule: CanvasFillRule) {
+        self.send_canvas_2d_msg(Canvas2dMsg::ClipPath(path.segments(), to_fill_rule(fill_rule)));
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinpath
     fn IsPointInPath(&self, x: f64, y: f64, fill_rule: CanvasFillRule) -> bool {
-        let fill_rule = match fill_rule {
-            CanvasFillRule::Nonzero => FillRule::Nonzero,
-            CanvasFillRule::Evenodd => FillRule::Evenodd,
-        };
+        let fill_rule = to_fill_rule(fill_rule);
         let (sender, receiver) = ipc::channel::<bool>(self.global().time_profiler_chan().clone()).unwrap();
         self.send_canvas_2d_msg(Canvas2dMsg::IsPointInPath(x, y, fill_rule, sender));
         receiver.recv().unwrap()
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinpath
+    fn IsPointInPath_(&self, path: &Path2D, x: f64, y: f64, fill_rule: CanvasFillRule) -> bool {
+        let fill_rule = to_fill_rule(fill_rule);
+        let (sender, receiver) = ipc::channel::<bool>(self.global().time_profiler_chan().clone()).unwrap();
+        self.send_canvas_2d_msg(Canvas2dMsg::IsPointInPath2D(path.segments(), x, y, fill_rule, sender));
+        receiver.recv().unwrap()
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-filltext
     fn FillText(&self, text: DOMString, x: f64, y: f64, max_width: Option<f64>) {
         let parsed_text: String = text.into();
@@ -802,6 +919,21 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
         self.mark_as_dirty();
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroketext
+    fn StrokeText(&self, text: DOMString, x: f64, y: f64, max_width: Option<f64>) {
+        let parsed_text: String = text.into();
+        self.send_canvas_2d_msg(Canvas2dMsg::StrokeText(parsed_text, x, y, max_width));
+        self.mark_as_dirty();
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-measuretext
+    fn MeasureText(&self, text: DOMString) -> DomRoot<TextMetrics> {
+        let (sender, receiver) =
+            ipc::channel::<CanvasTextMetrics>(self.global().time_profiler_chan().clone()).unwrap();
+        self.send_canvas_2d_msg(Canvas2dMsg::MeasureText(text.into(), sender));
+        TextMetrics::new(&self.global(), receiver.recv().unwrap())
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-drawimage
     fn DrawImage(&self,
                  image: CanvasImageSource,
@@ -1110,7 +1242,7 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
         let (sender, receiver) = ipc::channel(self.global().time_profiler_chan().clone()).unwrap();
         let dest_rect = Rect::new(Point2D::new(sx.to_i32().unwrap(), sy.to_i32().unwrap()),
                                   Size2D::new(sw as i32, sh as i32));
-        let canvas_size = self.canvas.as_ref().map(|c| c.get_size()).unwrap_or(Size2D::zero());
+        let canvas_size = self.size.get();
         let canvas_size = Size2D::new(canvas_size.width as f64, canvas_size.height as f64);
         self.send_canvas_2d_msg(Canvas2dMsg::GetImageData(dest_rect, canvas_size, sender));
         let mut data = receiver.recv().unwrap();
@@ -1222,6 +1354,13 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
 
                 canvas.fetch_all_data().ok_or(Error::InvalidState)?
             }
+            CanvasImageSource::ImageBitmap(ref bitmap) => {
+                let data = bitmap.bitmap_data().ok_or(Error::InvalidState)?;
+                (data, bitmap.get_size())
+            }
+            CanvasImageSource::OffscreenCanvas(ref canvas) => {
+                canvas.fetch_all_data().ok_or(Error::InvalidState)?
+            }
             CanvasImageSource::CSSStyleValue(ref value) => {
                 value.get_url(self.base_url.clone())
                     .and_then(|url| self.fetch_image_data(url))
@@ -1316,6 +1455,109 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
         self.send_canvas_2d_msg(Canvas2dMsg::SetMiterLimit(limit as f32))
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-setlinedash
+    fn SetLineDash(&self, segments: Vec<f64>) {
+        if segments.iter().any(|segment| !segment.is_finite() || *segment < 0.0) {
+            return;
+        }
+
+        let mut segments = segments;
+        if segments.len() % 2 == 1 {
+            let copy = segments.clone();
+            segments.extend(copy);
+        }
+
+        let dash = segments.iter().map(|segment| *segment as f32).collect();
+        self.state.borrow_mut().line_dash = segments;
+        self.send_canvas_2d_msg(Canvas2dMsg::SetLineDash(dash))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-getlinedash
+    fn GetLineDash(&self) -> Vec<f64> {
+        self.state.borrow().line_dash.clone()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-linedashoffset
+    fn LineDashOffset(&self) -> f64 {
+        self.state.borrow().line_dash_offset
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-linedashoffset
+    fn SetLineDashOffset(&self, offset: f64) {
+        if !offset.is_finite() {
+            return;
+        }
+
+        self.state.borrow_mut().line_dash_offset = offset;
+        self.send_canvas_2d_msg(Canvas2dMsg::SetLineDashOffset(offset as f32))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+    fn Font(&self) -> DOMString {
+        DOMString::from(self.state.borrow().font.to_string())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+    fn SetFont(&self, value: DOMString) {
+        let font = match CanvasFont::parse(&value, self.parent_font_size()) {
+            Ok(font) => font,
+            Err(()) => return,
+        };
+
+        self.state.borrow_mut().font = font.clone();
+        self.send_canvas_2d_msg(Canvas2dMsg::SetFont(font))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textalign
+    fn TextAlign(&self) -> CanvasTextAlign {
+        match self.state.borrow().text_align {
+            TextAlign::Start => CanvasTextAlign::Start,
+            TextAlign::End => CanvasTextAlign::End,
+            TextAlign::Left => CanvasTextAlign::Left,
+            TextAlign::Right => CanvasTextAlign::Right,
+            TextAlign::Center => CanvasTextAlign::Center,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textalign
+    fn SetTextAlign(&self, align: CanvasTextAlign) {
+        let text_align = match align {
+            CanvasTextAlign::Start => TextAlign::Start,
+            CanvasTextAlign::End => TextAlign::End,
+            CanvasTextAlign::Left => TextAlign::Left,
+            CanvasTextAlign::Right => TextAlign::Right,
+            CanvasTextAlign::Center => TextAlign::Center,
+        };
+        self.state.borrow_mut().text_align = text_align;
+        self.send_canvas_2d_msg(Canvas2dMsg::SetTextAlign(text_align));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textbaseline
+    fn TextBaseline(&self) -> CanvasTextBaseline {
+        match self.state.borrow().text_baseline {
+            TextBaseline::Top => CanvasTextBaseline::Top,
+            TextBaseline::Hanging => CanvasTextBaseline::Hanging,
+            TextBaseline::Middle => CanvasTextBaseline::Middle,
+            TextBaseline::Alphabetic => CanvasTextBaseline::Alphabetic,
+            TextBaseline::Ideographic => CanvasTextBaseline::Ideographic,
+            TextBaseline::Bottom => CanvasTextBaseline::Bottom,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textbaseline
+    fn SetTextBaseline(&self, baseline: CanvasTextBaseline) {
+        let text_baseline = match baseline {
+            CanvasTextBaseline::Top => TextBaseline::Top,
+            CanvasTextBaseline::Hanging => TextBaseline::Hanging,
+            CanvasTextBaseline::Middle => TextBaseline::Middle,
+            CanvasTextBaseline::Alphabetic => TextBaseline::Alphabetic,
+            CanvasTextBaseline::Ideographic => TextBaseline::Ideographic,
+            CanvasTextBaseline::Bottom => TextBaseline::Bottom,
+        };
+        self.state.borrow_mut().text_baseline = text_baseline;
+        self.send_canvas_2d_msg(Canvas2dMsg::SetTextBaseline(text_baseline));
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-shadowoffsetx
     fn ShadowOffsetX(&self) -> f64 {
         self.state.borrow().shadow_offset_x
@@ -1399,6 +1641,13 @@ pub fn parse_color(string: &str) -> Result<RGBA, ()> {
 
 // Used by drawImage to determine if a source or destination rectangle is valid
 // Origin coordinates and size cannot be negative. Size has to be greater than zero
+fn to_fill_rule(fill_rule: CanvasFillRule) -> FillRule {
+    match fill_rule {
+        CanvasFillRule::Nonzero => FillRule::Nonzero,
+        CanvasFillRule::Evenodd => FillRule::Evenodd,
+    }
+}
+
 fn is_rect_valid(rect: Rect<f64>) -> bool {
     rect.size.width > 0.0 && rect.size.height > 0.0
 }
@@ -0,0 +1,71 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::audionode::{AudioNode, MAX_CHANNEL_COUNT};
+use dom::baseaudiocontext::BaseAudioContext;
+use dom::bindings::codegen::Bindings::AudioNodeBinding::{ChannelCountMode, ChannelInterpretation};
+use dom::bindings::codegen::Bindings::ChannelMergerNodeBinding::{self, ChannelMergerOptions};
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::DomRoot;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use servo_media::audio::channel_node::ChannelNodeOptions;
+use servo_media::audio::node::AudioNodeInit;
+
+#[dom_struct]
+pub struct ChannelMergerNode {
+    node: AudioNode,
+}
+
+impl ChannelMergerNode {
+    #[allow(unrooted_must_root)]
+    pub fn new_inherited(
+        context: &BaseAudioContext,
+        options: &ChannelMergerOptions,
+    ) -> Fallible<ChannelMergerNode> {
+        if options.numberOfInputs < 1 || options.numberOfInputs > MAX_CHANNEL_COUNT {
+            return Err(Error::IndexSize);
+        }
+        let node_options = options.parent.unwrap_or(
+            1,
+            ChannelCountMode::Explicit,
+            ChannelInterpretation::Speakers,
+        );
+        if node_options.channelCount != Some(1) ||
+            node_options.channelCountMode != Some(ChannelCountMode::Explicit)
+        {
+            return Err(Error::InvalidState);
+        }
+        let node = AudioNode::new_inherited(
+            AudioNodeInit::ChannelMergerNode(ChannelNodeOptions {
+                channels: options.numberOfInputs as u8,
+            }),
+            None,
+            context,
+            &node_options,
+            options.numberOfInputs, // inputs
+            1, // outputs
+        );
+        Ok(ChannelMergerNode { node })
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &ChannelMergerOptions,
+    ) -> Fallible<DomRoot<ChannelMergerNode>> {
+        let node = ChannelMergerNode::new_inherited(context, options)?;
+        Ok(reflect_dom_object(Box::new(node), window, ChannelMergerNodeBinding::Wrap))
+    }
+
+    pub fn Constructor(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &ChannelMergerOptions,
+    ) -> Fallible<DomRoot<ChannelMergerNode>> {
+        ChannelMergerNode::new(window, context, options)
+    }
+}
@@ -0,0 +1,72 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::audionode::{AudioNode, MAX_CHANNEL_COUNT};
+use dom::baseaudiocontext::BaseAudioContext;
+use dom::bindings::codegen::Bindings::AudioNodeBinding::{ChannelCountMode, ChannelInterpretation};
+use dom::bindings::codegen::Bindings::ChannelSplitterNodeBinding::{self, ChannelSplitterOptions};
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::DomRoot;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use servo_media::audio::channel_node::ChannelNodeOptions;
+use servo_media::audio::node::AudioNodeInit;
+
+#[dom_struct]
+pub struct ChannelSplitterNode {
+    node: AudioNode,
+}
+
+impl ChannelSplitterNode {
+    #[allow(unrooted_must_root)]
+    pub fn new_inherited(
+        context: &BaseAudioContext,
+        options: &ChannelSplitterOptions,
+    ) -> Fallible<ChannelSplitterNode> {
+        if options.numberOfOutputs < 1 || options.numberOfOutputs > MAX_CHANNEL_COUNT {
+            return Err(Error::IndexSize);
+        }
+        let node_options = options.parent.unwrap_or(
+            options.numberOfOutputs,
+            ChannelCountMode::Explicit,
+            ChannelInterpretation::Discrete,
+        );
+        if node_options.channelCount != Some(options.numberOfOutputs) ||
+            node_options.channelCountMode != Some(ChannelCountMode::Explicit) ||
+            node_options.channelInterpretation != Some(ChannelInterpretation::Discrete)
+        {
+            return Err(Error::InvalidState);
+        }
+        let node = AudioNode::new_inherited(
+            AudioNodeInit::ChannelSplitterNode(ChannelNodeOptions {
+                channels: options.numberOfOutputs as u8,
+            }),
+            None,
+            context,
+            &node_options,
+            1, // inputs
+            options.numberOfOutputs, // outputs
+        );
+        Ok(ChannelSplitterNode { node })
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &ChannelSplitterOptions,
+    ) -> Fallible<DomRoot<ChannelSplitterNode>> {
+        let node = ChannelSplitterNode::new_inherited(context, options)?;
+        Ok(reflect_dom_object(Box::new(node), window, ChannelSplitterNodeBinding::Wrap))
+    }
+
+    pub fn Constructor(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &ChannelSplitterOptions,
+    ) -> Fallible<DomRoot<ChannelSplitterNode>> {
+        ChannelSplitterNode::new(window, context, options)
+    }
+}
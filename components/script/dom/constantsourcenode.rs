@@ -0,0 +1,95 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::audioparam::AudioParam;
+use dom::audioscheduledsourcenode::AudioScheduledSourceNode;
+use dom::baseaudiocontext::BaseAudioContext;
+use dom::bindings::codegen::Bindings::AudioNodeBinding::{ChannelCountMode, ChannelInterpretation};
+use dom::bindings::codegen::Bindings::AudioNodeBinding::AudioNodeOptions;
+use dom::bindings::codegen::Bindings::AudioParamBinding::AutomationRate;
+use dom::bindings::codegen::Bindings::ConstantSourceNodeBinding::{self, ConstantSourceNodeMethods};
+use dom::bindings::codegen::Bindings::ConstantSourceNodeBinding::ConstantSourceOptions;
+use dom::bindings::error::Fallible;
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::{Dom, DomRoot};
+use dom::window::Window;
+use dom_struct::dom_struct;
+use servo_media::audio::constant_source_node::ConstantSourceNodeOptions;
+use servo_media::audio::node::AudioNodeInit;
+use servo_media::audio::param::ParamType;
+use std::f32;
+
+#[dom_struct]
+pub struct ConstantSourceNode {
+    source_node: AudioScheduledSourceNode,
+    offset: Dom<AudioParam>,
+}
+
+impl ConstantSourceNode {
+    #[allow(unrooted_must_root)]
+    pub fn new_inherited(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &ConstantSourceOptions,
+    ) -> ConstantSourceNode {
+        let mut node_options = AudioNodeOptions::empty();
+        node_options.channelCount = Some(2);
+        node_options.channelCountMode = Some(ChannelCountMode::Max);
+        node_options.channelInterpretation = Some(ChannelInterpretation::Speakers);
+        let source_node = AudioScheduledSourceNode::new_inherited(
+            AudioNodeInit::ConstantSourceNode(options.into()),
+            context,
+            &node_options,
+            0, /* inputs */
+            1, /* outputs */
+        );
+        let offset = AudioParam::new(
+            window,
+            context,
+            source_node.node().node_id(),
+            ParamType::Offset,
+            AutomationRate::A_rate,
+            1.,
+            f32::MIN,
+            f32::MAX,
+        );
+        ConstantSourceNode {
+            source_node,
+            offset: Dom::from_ref(&offset),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &ConstantSourceOptions,
+    ) -> DomRoot<ConstantSourceNode> {
+        let node = ConstantSourceNode::new_inherited(window, context, options);
+        reflect_dom_object(Box::new(node), window, ConstantSourceNodeBinding::Wrap)
+    }
+
+    pub fn Constructor(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &ConstantSourceOptions,
+    ) -> Fallible<DomRoot<ConstantSourceNode>> {
+        Ok(ConstantSourceNode::new(window, context, options))
+    }
+}
+
+impl ConstantSourceNodeMethods for ConstantSourceNode {
+    // https://webaudio.github.io/web-audio-api/#dom-constantsourcenode-offset
+    fn Offset(&self) -> DomRoot<AudioParam> {
+        DomRoot::from_ref(&self.offset)
+    }
+}
+
+impl<'a> From<&'a ConstantSourceOptions> for ConstantSourceNodeOptions {
+    fn from(options: &'a ConstantSourceOptions) -> Self {
+        Self {
+            offset: *options.offset,
+        }
+    }
+}
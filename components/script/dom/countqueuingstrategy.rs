@@ -0,0 +1,73 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::ByteLengthQueuingStrategyBinding::QueuingStrategyInit;
+use dom::bindings::codegen::Bindings::CountQueuingStrategyBinding;
+use dom::bindings::codegen::Bindings::CountQueuingStrategyBinding::CountQueuingStrategyMethods;
+use dom::bindings::codegen::Bindings::FunctionBinding::Function;
+use dom::bindings::error::Fallible;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::bytelengthqueuingstrategy::new_size_function;
+use dom::globalscope::GlobalScope;
+use dom_struct::dom_struct;
+use js::jsapi::{CallArgs, Heap, JSContext, JSObject};
+use js::jsval::{Int32Value, JSVal};
+use std::rc::Rc;
+
+/// <https://streams.spec.whatwg.org/#cqs-class>
+#[dom_struct]
+pub struct CountQueuingStrategy {
+    reflector_: Reflector,
+    high_water_mark: f64,
+    #[ignore_malloc_size_of = "mozjs"]
+    size: Heap<*mut JSObject>,
+}
+
+impl CountQueuingStrategy {
+    fn new_inherited(high_water_mark: f64) -> CountQueuingStrategy {
+        CountQueuingStrategy {
+            reflector_: Reflector::new(),
+            high_water_mark: high_water_mark,
+            size: Heap::default(),
+        }
+    }
+
+    #[allow(unsafe_code)]
+    pub fn new(global: &GlobalScope, high_water_mark: f64) -> DomRoot<CountQueuingStrategy> {
+        let strategy = reflect_dom_object(Box::new(CountQueuingStrategy::new_inherited(high_water_mark)),
+                                          global,
+                                          CountQueuingStrategyBinding::Wrap);
+        strategy.size.set(unsafe { new_size_function(global.get_cx(), Some(count_size)) });
+        strategy
+    }
+
+    // https://streams.spec.whatwg.org/#cqs-constructor
+    pub fn Constructor(global: &GlobalScope,
+                       init: &QueuingStrategyInit)
+                       -> Fallible<DomRoot<CountQueuingStrategy>> {
+        Ok(CountQueuingStrategy::new(global, init.highWaterMark))
+    }
+}
+
+/// <https://streams.spec.whatwg.org/#count-queuing-strategy-size-function>
+#[allow(unsafe_code)]
+unsafe extern fn count_size(_cx: *mut JSContext, argc: u32, vp: *mut JSVal) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    *args.rval() = Int32Value(1);
+    true
+}
+
+impl CountQueuingStrategyMethods for CountQueuingStrategy {
+    // https://streams.spec.whatwg.org/#cqs-high-water-mark
+    fn HighWaterMark(&self) -> f64 {
+        self.high_water_mark
+    }
+
+    #[allow(unsafe_code)]
+    // https://streams.spec.whatwg.org/#cqs-size
+    fn Size(&self) -> Rc<Function> {
+        unsafe { Function::new(self.global().get_cx(), self.size.get()) }
+    }
+}
@@ -60,6 +60,7 @@ use dom::htmlprogresselement::HTMLProgressElement;
 use dom::htmlquoteelement::HTMLQuoteElement;
 use dom::htmlscriptelement::HTMLScriptElement;
 use dom::htmlselectelement::HTMLSelectElement;
+use dom::htmlslotelement::HTMLSlotElement;
 use dom::htmlsourceelement::HTMLSourceElement;
 use dom::htmlspanelement::HTMLSpanElement;
 use dom::htmlstyleelement::HTMLStyleElement;
@@ -323,6 +324,7 @@ pub fn create_native_html_element(
         local_name!("script")     => make!(HTMLScriptElement, creator),
         local_name!("section")    => make!(HTMLElement),
         local_name!("select")     => make!(HTMLSelectElement),
+        local_name!("slot")       => make!(HTMLSlotElement),
         local_name!("small")      => make!(HTMLElement),
         local_name!("source")     => make!(HTMLSourceElement),
         // https://html.spec.whatwg.org/multipage/#other-elements,-attributes-and-apis:spacer
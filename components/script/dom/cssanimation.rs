@@ -0,0 +1,61 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::animation::Animation;
+use dom::animationeffect::Timing;
+use dom::animationtimeline::AnimationTimeline;
+use dom::bindings::codegen::Bindings::CSSAnimationBinding;
+use dom::bindings::codegen::Bindings::CSSAnimationBinding::CSSAnimationMethods;
+use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::element::Element;
+use dom::globalscope::GlobalScope;
+use dom::keyframeeffect::KeyframeEffect;
+use dom::promise::Promise;
+use dom::window::Window;
+use dom_struct::dom_struct;
+
+/// A CSS animation running in layout, reflected to script.
+///
+/// <https://drafts.csswg.org/css-animations-2/#the-CSSAnimation-interface>
+#[dom_struct]
+pub struct CSSAnimation {
+    animation: Animation,
+    animation_name: DOMString,
+}
+
+impl CSSAnimation {
+    fn new_inherited(global: &GlobalScope, animation_name: DOMString) -> CSSAnimation {
+        CSSAnimation {
+            animation: Animation::new_inherited(Promise::new(global), Promise::new(global)),
+            animation_name: animation_name,
+        }
+    }
+
+    pub fn new(window: &Window, target: &Element, animation_name: DOMString) -> DomRoot<CSSAnimation> {
+        let animation = CSSAnimation::new_inherited(window.upcast(), animation_name);
+        let animation = reflect_dom_object(Box::new(animation), window, CSSAnimationBinding::Wrap);
+        // TODO: Reflect the keyframes of the animation, and let script control
+        // its playback in layout.
+        let effect = KeyframeEffect::new_without_keyframes(window, target, Timing::new());
+        let timeline = window.Document().timeline();
+        animation.upcast::<Animation>().init(Some(effect.upcast()), Some(timeline.upcast::<AnimationTimeline>()));
+        animation
+    }
+
+    /// The name of the `@keyframes` rule of this animation.
+    pub fn animation_name(&self) -> &DOMString {
+        &self.animation_name
+    }
+}
+
+impl CSSAnimationMethods for CSSAnimation {
+    // https://drafts.csswg.org/css-animations-2/#dom-cssanimation-animationname
+    fn AnimationName(&self) -> DOMString {
+        self.animation_name.clone()
+    }
+}
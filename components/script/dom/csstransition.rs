@@ -0,0 +1,61 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::animation::Animation;
+use dom::animationeffect::Timing;
+use dom::animationtimeline::AnimationTimeline;
+use dom::bindings::codegen::Bindings::CSSTransitionBinding;
+use dom::bindings::codegen::Bindings::CSSTransitionBinding::CSSTransitionMethods;
+use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::element::Element;
+use dom::globalscope::GlobalScope;
+use dom::keyframeeffect::KeyframeEffect;
+use dom::promise::Promise;
+use dom::window::Window;
+use dom_struct::dom_struct;
+
+/// A CSS transition running in layout, reflected to script.
+///
+/// <https://drafts.csswg.org/css-transitions-2/#the-CSSTransition-interface>
+#[dom_struct]
+pub struct CSSTransition {
+    animation: Animation,
+    transition_property: DOMString,
+}
+
+impl CSSTransition {
+    fn new_inherited(global: &GlobalScope, transition_property: DOMString) -> CSSTransition {
+        CSSTransition {
+            animation: Animation::new_inherited(Promise::new(global), Promise::new(global)),
+            transition_property: transition_property,
+        }
+    }
+
+    pub fn new(window: &Window, target: &Element, transition_property: DOMString) -> DomRoot<CSSTransition> {
+        let transition = CSSTransition::new_inherited(window.upcast(), transition_property);
+        let transition = reflect_dom_object(Box::new(transition), window, CSSTransitionBinding::Wrap);
+        // TODO: Reflect the keyframes of the transition, and let script control
+        // its playback in layout.
+        let effect = KeyframeEffect::new_without_keyframes(window, target, Timing::new());
+        let timeline = window.Document().timeline();
+        transition.upcast::<Animation>().init(Some(effect.upcast()), Some(timeline.upcast::<AnimationTimeline>()));
+        transition
+    }
+
+    /// The property this transition applies to.
+    pub fn transition_property(&self) -> &DOMString {
+        &self.transition_property
+    }
+}
+
+impl CSSTransitionMethods for CSSTransition {
+    // https://drafts.csswg.org/css-transitions-2/#dom-csstransition-transitionproperty
+    fn TransitionProperty(&self) -> DOMString {
+        self.transition_property.clone()
+    }
+}
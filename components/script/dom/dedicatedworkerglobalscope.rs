@@ -25,9 +25,9 @@ use dom::workerglobalscope::WorkerGlobalScope;
 use dom_struct::dom_struct;
 use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use ipc_channel::router::ROUTER;
-use js::jsapi::{JS_SetInterruptCallback, JSAutoCompartment, JSContext};
+use js::jsapi::{JS_SetInterruptCallback, JSAutoCompartment, JSContext, JSObject};
 use js::jsval::UndefinedValue;
-use js::rust::HandleValue;
+use js::rust::{CustomAutoRooterGuard, HandleValue};
 use msg::constellation_msg::TopLevelBrowsingContextId;
 use net_traits::{IpcSend, load_whole_resource};
 use net_traits::request::{CredentialsMode, Destination, RequestInit};
@@ -163,7 +163,9 @@ impl DedicatedWorkerGlobalScope {
         let serialized_worker_url = worker_url.to_string();
         let name = format!("WebWorker for {}", serialized_worker_url);
         let top_level_browsing_context_id = TopLevelBrowsingContextId::installed();
-        let origin = GlobalScope::current().expect("No current global object").origin().immutable().clone();
+        let current_global = GlobalScope::current().expect("No current global object");
+        let origin = current_global.origin().immutable().clone();
+        let csp_list = current_global.get_csp_list();
 
         thread::Builder::new().name(name).spawn(move || {
             thread_state::initialize(ThreadState::SCRIPT | ThreadState::IN_WORKER);
@@ -186,6 +188,7 @@ impl DedicatedWorkerGlobalScope {
                 referrer_url: referrer_url,
                 referrer_policy: referrer_policy,
                 origin,
+                csp_list,
                 .. RequestInit::default()
             };
 
@@ -255,6 +258,7 @@ impl DedicatedWorkerGlobalScope {
                     let _ar = AutoWorkerReset::new(&global, worker.clone());
                     global.upcast::<GlobalScope>().perform_a_microtask_checkpoint();
                 }
+                global.upcast::<GlobalScope>().remove_message_router();
             }, reporter_name, parent_sender, CommonScriptMsg::CollectReports);
         }).expect("Thread spawning failed");
     }
@@ -313,8 +317,8 @@ impl DedicatedWorkerGlobalScope {
                 let _ac = JSAutoCompartment::new(scope.get_cx(),
                                                  scope.reflector().get_jsobject().get());
                 rooted!(in(scope.get_cx()) let mut message = UndefinedValue());
-                data.read(scope.upcast(), message.handle_mut());
-                MessageEvent::dispatch_jsval(target, scope.upcast(), message.handle());
+                let ports = data.read(scope.upcast(), message.handle_mut());
+                MessageEvent::dispatch_jsval(target, scope.upcast(), message.handle(), ports);
             },
             WorkerScriptMsg::Common(msg) => {
                 self.upcast::<WorkerGlobalScope>().process_event(msg);
@@ -402,8 +406,13 @@ unsafe extern "C" fn interrupt_callback(cx: *mut JSContext) -> bool {
 impl DedicatedWorkerGlobalScopeMethods for DedicatedWorkerGlobalScope {
     #[allow(unsafe_code)]
     // https://html.spec.whatwg.org/multipage/#dom-dedicatedworkerglobalscope-postmessage
-    unsafe fn PostMessage(&self, cx: *mut JSContext, message: HandleValue) -> ErrorResult {
-        let data = StructuredCloneData::write(cx, message)?;
+    unsafe fn PostMessage(&self,
+                          cx: *mut JSContext,
+                          message: HandleValue,
+                          transfer: CustomAutoRooterGuard<Option<Vec<*mut JSObject>>>)
+                          -> ErrorResult {
+        let transfer = transfer.as_ref().map(|objects| &objects[..]).unwrap_or(&[]);
+        let data = StructuredCloneData::write_with_transfer(cx, message, transfer)?;
         let worker = self.worker.borrow().as_ref().unwrap().clone();
         let pipeline_id = self.upcast::<GlobalScope>().pipeline_id();
         let task = Box::new(task!(post_worker_message: move || {
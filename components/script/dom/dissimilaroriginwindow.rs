@@ -14,9 +14,9 @@ use dom::globalscope::GlobalScope;
 use dom::windowproxy::WindowProxy;
 use dom_struct::dom_struct;
 use ipc_channel::ipc;
-use js::jsapi::JSContext;
+use js::jsapi::{JSContext, JSObject};
 use js::jsval::{JSVal, UndefinedValue};
-use js::rust::HandleValue;
+use js::rust::{CustomAutoRooterGuard, HandleValue};
 use msg::constellation_msg::PipelineId;
 use script_traits::ScriptMsg;
 use servo_url::ImmutableOrigin;
@@ -142,7 +142,12 @@ impl DissimilarOriginWindowMethods for DissimilarOriginWindow {
 
     #[allow(unsafe_code)]
     // https://html.spec.whatwg.org/multipage/#dom-window-postmessage
-    unsafe fn PostMessage(&self, cx: *mut JSContext, message: HandleValue, origin: DOMString) -> ErrorResult {
+    unsafe fn PostMessage(&self,
+                          cx: *mut JSContext,
+                          message: HandleValue,
+                          origin: DOMString,
+                          transfer: CustomAutoRooterGuard<Option<Vec<*mut JSObject>>>)
+                          -> ErrorResult {
         // Step 3-5.
         let origin = match &origin[..] {
             "*" => None,
@@ -157,8 +162,8 @@ impl DissimilarOriginWindowMethods for DissimilarOriginWindow {
         };
 
         // Step 1-2, 6-8.
-        // TODO(#12717): Should implement the `transfer` argument.
-        let data = StructuredCloneData::write(cx, message)?;
+        let transfer = transfer.as_ref().map(|objects| &objects[..]).unwrap_or(&[]);
+        let data = StructuredCloneData::write_with_transfer(cx, message, transfer)?;
 
         // Step 9.
         self.post_message(origin, data);
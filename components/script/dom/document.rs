@@ -6,6 +6,7 @@ use cookie_rs;
 use devtools_traits::ScriptToDevtoolsControlMsg;
 use document_loader::{DocumentLoader, LoadType};
 use dom::activation::{ActivationSource, synthetic_click_activation};
+use dom::animation::Animation;
 use dom::attr::Attr;
 use dom::beforeunloadevent::BeforeUnloadEvent;
 use dom::bindings::callback::ExceptionHandling;
@@ -18,10 +19,11 @@ use dom::bindings::codegen::Bindings::HTMLIFrameElementBinding::HTMLIFrameElemen
 use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
 use dom::bindings::codegen::Bindings::NodeFilterBinding::NodeFilter;
 use dom::bindings::codegen::Bindings::PerformanceBinding::PerformanceMethods;
+use dom::bindings::codegen::Bindings::SelectionBinding::SelectionMethods;
 use dom::bindings::codegen::Bindings::TouchBinding::TouchMethods;
 use dom::bindings::codegen::Bindings::WindowBinding::{FrameRequestCallback, ScrollBehavior, WindowMethods};
 use dom::bindings::codegen::UnionTypes::NodeOrString;
-use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::error::{Error, ErrorInfo, ErrorResult, Fallible};
 use dom::bindings::inheritance::{Castable, ElementTypeId, HTMLElementTypeId, NodeTypeId};
 use dom::bindings::num::Finite;
 use dom::bindings::refcounted::{Trusted, TrustedPromise};
@@ -30,12 +32,16 @@ use dom::bindings::root::{Dom, DomRoot, LayoutDom, MutNullableDom, RootedReferen
 use dom::bindings::str::{DOMString, USVString};
 use dom::bindings::xmlname::{namespace_from_domstring, validate_and_extract, xml_name_type};
 use dom::bindings::xmlname::XMLName::InvalidXMLName;
+use dom::characterdata::CharacterData;
 use dom::closeevent::CloseEvent;
 use dom::comment::Comment;
+use dom::cssanimation::CSSAnimation;
 use dom::cssstylesheet::CSSStyleSheet;
+use dom::csstransition::CSSTransition;
 use dom::customelementregistry::CustomElementDefinition;
 use dom::customevent::CustomEvent;
 use dom::documentfragment::DocumentFragment;
+use dom::documenttimeline::DocumentTimeline;
 use dom::documenttype::DocumentType;
 use dom::domimplementation::DOMImplementation;
 use dom::element::{Element, ElementCreator, ElementPerformFullscreenEnter, ElementPerformFullscreenExit};
@@ -44,6 +50,7 @@ use dom::errorevent::ErrorEvent;
 use dom::event::{Event, EventBubbles, EventCancelable, EventDefault, EventStatus};
 use dom::eventtarget::EventTarget;
 use dom::focusevent::FocusEvent;
+use dom::fontfaceset::FontFaceSet;
 use dom::globalscope::GlobalScope;
 use dom::hashchangeevent::HashChangeEvent;
 use dom::htmlanchorelement::HTMLAnchorElement;
@@ -58,9 +65,12 @@ use dom::htmlheadelement::HTMLHeadElement;
 use dom::htmlhtmlelement::HTMLHtmlElement;
 use dom::htmliframeelement::HTMLIFrameElement;
 use dom::htmlimageelement::HTMLImageElement;
+use dom::htmlinputelement::HTMLInputElement;
 use dom::htmlmetaelement::HTMLMetaElement;
 use dom::htmlscriptelement::{HTMLScriptElement, ScriptResult};
+use dom::htmltextareaelement::HTMLTextAreaElement;
 use dom::htmltitleelement::HTMLTitleElement;
+use dom::intersectionobserver::IntersectionObserver;
 use dom::keyboardevent::KeyboardEvent;
 use dom::location::Location;
 use dom::messageevent::MessageEvent;
@@ -76,7 +86,10 @@ use dom::processinginstruction::ProcessingInstruction;
 use dom::progressevent::ProgressEvent;
 use dom::promise::Promise;
 use dom::range::Range;
+use dom::resizeobserver::ResizeObserver;
+use dom::selection::Selection;
 use dom::servoparser::ServoParser;
+use dom::shadowroot::ShadowRoot;
 use dom::storageevent::StorageEvent;
 use dom::stylesheetlist::StyleSheetList;
 use dom::text::Text;
@@ -90,6 +103,7 @@ use dom::webglcontextevent::WebGLContextEvent;
 use dom::window::{ReflowReason, Window};
 use dom::windowproxy::WindowProxy;
 use dom_struct::dom_struct;
+use editing::{self, EditingCommand};
 use embedder_traits::EmbedderMsg;
 use encoding_rs::{Encoding, UTF_8};
 use euclid::Point2D;
@@ -100,12 +114,14 @@ use hyper_serde::Serde;
 use ipc_channel::ipc::{self, IpcSender};
 use js::jsapi::{JSContext, JSObject, JSRuntime};
 use js::jsapi::JS_GetRuntime;
+use js::rust::HandleValue;
 use metrics::{InteractiveFlag, InteractiveMetrics, InteractiveWindow, ProfilerMetadataFactory, ProgressiveWebMetric};
 use mime::{Mime, TopLevel, SubLevel};
 use msg::constellation_msg::{BrowsingContextId, Key, KeyModifiers, KeyState};
 use net_traits::{FetchResponseMsg, IpcSend, ReferrerPolicy};
 use net_traits::CookieSource::NonHTTP;
 use net_traits::CoreResourceMsg::{GetCookiesForUrl, SetCookiesForUrl};
+use net_traits::csp::{CheckResult, CspList, InlineCheckType};
 use net_traits::pub_domains::is_pub_domain;
 use net_traits::request::RequestInit;
 use net_traits::response::HttpsState;
@@ -113,7 +129,9 @@ use num_traits::ToPrimitive;
 use profile_traits::ipc as profile_ipc;
 use profile_traits::time::{TimerMetadata, TimerMetadataFrameType, TimerMetadataReflowType};
 use ref_slice::ref_slice;
+use script_layout_interface::TrustedNodeAddress;
 use script_layout_interface::message::{Msg, NodesFromPointQueryType, QueryMsg, ReflowGoal};
+use script_layout_interface::rpc::{ObservedBoxes, RunningCSSAnimationKind, TextIndexResponse};
 use script_runtime::{CommonScriptMsg, ScriptThreadEventCategory};
 use script_thread::{MainThreadScriptMsg, ScriptThread};
 use script_traits::{AnimationState, DocumentActivity, MouseButton, MouseEventType};
@@ -132,15 +150,17 @@ use std::mem;
 use std::ptr::NonNull;
 use std::rc::Rc;
 use std::time::{Duration, Instant};
+use style::animation::ScriptAnimationSample;
 use style::attr::AttrValue;
 use style::context::QuirksMode;
 use style::invalidation::element::restyle_hints::RestyleHint;
+use style::invalidation::media_queries::{MediaListKey, ToMediaListKey};
 use style::media_queries::{Device, MediaList, MediaType};
 use style::selector_parser::{RestyleDamage, Snapshot};
-use style::shared_lock::{SharedRwLock as StyleSharedRwLock, SharedRwLockReadGuard};
+use style::shared_lock::{Locked, SharedRwLock as StyleSharedRwLock, SharedRwLockReadGuard};
 use style::str::{split_html_space_chars, str_join};
 use style::stylesheet_set::DocumentStylesheetSet;
-use style::stylesheets::{CssRule, Stylesheet, Origin, OriginSet};
+use style::stylesheets::{CssRule, FontFaceRule, Stylesheet, StylesheetInDocument, Origin, OriginSet};
 use task_source::{TaskSource, TaskSourceName};
 use time;
 use timers::OneshotTimerCallback;
@@ -208,10 +228,10 @@ impl PendingRestyle {
 
 #[derive(Clone, JSTraceable, MallocSizeOf)]
 #[must_root]
-struct StyleSheetInDocument {
+pub struct StyleSheetInDocument {
     #[ignore_malloc_size_of = "Arc"]
-    sheet: Arc<Stylesheet>,
-    owner: Dom<Element>,
+    pub sheet: Arc<Stylesheet>,
+    pub owner: Dom<Element>,
 }
 
 impl fmt::Debug for StyleSheetInDocument {
@@ -226,7 +246,13 @@ impl PartialEq for StyleSheetInDocument {
     }
 }
 
-impl ::style::stylesheets::StylesheetInDocument for StyleSheetInDocument {
+impl ToMediaListKey for StyleSheetInDocument {
+    fn to_media_list_key(&self) -> MediaListKey {
+        self.sheet.to_media_list_key()
+    }
+}
+
+impl StylesheetInDocument for StyleSheetInDocument {
     fn origin(&self, guard: &SharedRwLockReadGuard) -> Origin {
         self.sheet.origin(guard)
     }
@@ -282,6 +308,32 @@ pub struct Document {
     /// List of stylesheets associated with nodes in this document. |None| if the list needs to be refreshed.
     stylesheets: DomRefCell<DocumentStylesheetSet<StyleSheetInDocument>>,
     stylesheet_list: MutNullableDom<StyleSheetList>,
+    /// <https://drafts.csswg.org/css-font-loading/#font-source>
+    fonts: MutNullableDom<FontFaceSet>,
+    /// <https://drafts.csswg.org/web-animations/#the-documents-default-timeline>
+    timeline: MutNullableDom<DocumentTimeline>,
+    /// The script animations that may still need to be sampled or to fire
+    /// events, in the order they were started.
+    animations: DomRefCell<Vec<Dom<Animation>>>,
+    /// The elements that had script animations applied at the last reflow.
+    animated_targets: DomRefCell<Vec<Dom<Element>>>,
+    /// Whether the script animations need to be sampled by layout again.
+    animations_need_reflow: Cell<bool>,
+    /// The time of the default timeline, updated when animation frames run.
+    animation_timeline_time: Cell<Option<f64>>,
+    /// The CSS animations and transitions running in layout, reflected to
+    /// script the last time they were queried.
+    css_animations: DomRefCell<Vec<Dom<Animation>>>,
+    /// <https://w3c.github.io/selection-api/#dfn-selection>
+    selection: MutNullableDom<Selection>,
+    /// Whether a mouse button is held down after starting a selection.
+    selecting_with_mouse: Cell<bool>,
+    /// The intersection observers observing elements in this document.
+    intersection_observers: DomRefCell<Vec<Dom<IntersectionObserver>>>,
+    /// The resize observers observing elements in this document.
+    resize_observers: DomRefCell<Vec<Dom<ResizeObserver>>>,
+    /// Whether a task to update the intersection and resize observers is queued.
+    layout_observers_update_queued: Cell<bool>,
     ready_state: Cell<DocumentReadyState>,
     /// Whether the DOMContentLoaded event has already been dispatched.
     domcontentloaded_dispatched: Cell<bool>,
@@ -329,6 +381,15 @@ pub struct Document {
     /// Information on elements needing restyle to ship over to the layout thread when the
     /// time comes.
     pending_restyles: DomRefCell<HashMap<Dom<Element>, PendingRestyle>>,
+    /// The shadow roots connected to this document, whose stylesheets are
+    /// flushed by layout.
+    shadow_roots: DomRefCell<Vec<Dom<ShadowRoot>>>,
+    /// Whether the stylesheets of any of the shadow roots changed since the
+    /// last reflow.
+    shadow_roots_styles_changed: Cell<bool>,
+    /// Whether the stylesheets used by the last reflow contain :has()
+    /// selectors, set by layout.
+    has_relative_selectors: Cell<bool>,
     /// This flag will be true if layout suppressed a reflow attempt that was
     /// needed in order for the page to be painted.
     needs_paint: Cell<bool>,
@@ -350,6 +411,8 @@ pub struct Document {
     origin: MutableOrigin,
     ///  https://w3c.github.io/webappsec-referrer-policy/#referrer-policy-states
     referrer_policy: Cell<Option<ReferrerPolicy>>,
+    /// <https://w3c.github.io/webappsec-csp/#concept-document-csp-list>
+    csp_list: DomRefCell<Option<CspList>>,
     /// <https://html.spec.whatwg.org/multipage/#dom-document-referrer>
     referrer: Option<String>,
     /// <https://html.spec.whatwg.org/multipage/#target-element>
@@ -574,11 +637,19 @@ impl Document {
         self.needs_paint.get()
     }
 
+    /// Whether DOM mutations need to look for the anchors of :has()
+    /// selectors.
+    pub fn has_relative_selectors(&self) -> bool {
+        self.has_relative_selectors.get()
+    }
+
     pub fn needs_reflow(&self) -> bool {
         // FIXME: This should check the dirty bit on the document,
         // not the document element. Needs some layout changes to make
         // that workable.
         self.stylesheets.borrow().has_changed() ||
+        self.shadow_roots_styles_changed.get() ||
+        self.animations_need_reflow.get() ||
         self.GetDocumentElement().map_or(false, |root| {
             root.upcast::<Node>().has_dirty_descendants() ||
             !self.pending_restyles.borrow().is_empty() ||
@@ -832,6 +903,20 @@ impl Document {
         self.scripting_enabled
     }
 
+    /// Returns the document's selection, if script or the user has asked for it.
+    pub fn selection(&self) -> Option<DomRoot<Selection>> {
+        self.selection.get()
+    }
+
+    /// Tells layout about any change to the selection before the next reflow.
+    pub fn flush_selection_for_reflow(&self) {
+        if let Some(selection) = self.selection.get() {
+            let dom_changed = self.GetDocumentElement()
+                .map_or(false, |root| root.upcast::<Node>().has_dirty_descendants());
+            selection.update_for_layout(dom_changed);
+        }
+    }
+
     /// Return the element that currently has focus.
     // https://w3c.github.io/uievents/#events-focusevent-doc-focus
     pub fn get_focused_element(&self) -> Option<DomRoot<Element>> {
@@ -934,8 +1019,8 @@ impl Document {
         };
         debug!("{}: at {:?}", mouse_event_type_string, client_point);
 
-        let el = node_address.and_then(|address| {
-            let node = unsafe { node::from_untrusted_node_address(js_runtime, address) };
+        let hit_node = node_address.map(|address| unsafe { node::from_untrusted_node_address(js_runtime, address) });
+        let el = hit_node.as_ref().and_then(|node| {
             node.inclusive_ancestors()
                 .filter_map(DomRoot::downcast::<Element>)
                 .next()
@@ -980,6 +1065,7 @@ impl Document {
             point_in_node,
         );
         let event = event.upcast::<Event>();
+        event.set_composed(true);
 
         // https://w3c.github.io/uievents/#trusted-events
         event.set_trusted(true);
@@ -1005,7 +1091,25 @@ impl Document {
             },
         }
 
+        match mouse_event_type {
+            MouseEventType::MouseDown if !event.DefaultPrevented() => {
+                self.start_mouse_selection(hit_node.as_ref().unwrap(), client_point);
+            },
+            MouseEventType::MouseUp if self.selecting_with_mouse.get() => {
+                self.extend_mouse_selection(hit_node.as_ref().unwrap(), client_point);
+                self.selecting_with_mouse.set(false);
+            },
+            _ => {},
+        }
+
         if let MouseEventType::Click = mouse_event_type {
+            // Clicking into an editing host focuses it, unless a more specific
+            // element (such as a form control) already asked for focus.
+            if self.possibly_focused.get().is_none() {
+                if let Some(host) = node.editing_host() {
+                    self.request_focus(host.upcast());
+                }
+            }
             self.commit_focus_transaction(FocusType::Element);
             self.maybe_fire_dblclick(client_point, node);
         }
@@ -1054,6 +1158,7 @@ impl Document {
                     None,
                     None
                 );
+                event.upcast::<Event>().set_composed(true);
                 event.upcast::<Event>().fire(target.upcast());
 
                 // When a double click occurs, self.last_click_info is left as None so that a
@@ -1090,9 +1195,106 @@ impl Document {
             None
         );
         let event = mouse_event.upcast::<Event>();
+        event.set_composed(true);
         event.fire(target);
     }
 
+    /// Returns the selection, if mouse and keyboard selection is enabled and the
+    /// target is not a text control, which manages its own selection.
+    fn selection_for_user_input(&self, target: &Node) -> Option<DomRoot<Selection>> {
+        if !PREFS.get("dom.selection.enabled").as_boolean().unwrap_or(false) {
+            return None;
+        }
+        if target.inclusive_ancestors().any(|node| node.is::<HTMLInputElement>() || node.is::<HTMLTextAreaElement>()) {
+            return None;
+        }
+        self.GetSelection()
+    }
+
+    /// Returns the boundary point closest to `client_point` within `node`, if any.
+    fn caret_position_at_point(&self, node: &Node, client_point: Point2D<f32>) -> Option<(DomRoot<Node>, u32)> {
+        if let Some(text) = node.downcast::<Text>() {
+            let page_point = Point2D::new(client_point.x + self.window.ScrollX() as f32,
+                                          client_point.y + self.window.ScrollY() as f32);
+            let TextIndexResponse(index) =
+                self.window.text_index_at_point_query(node.to_trusted_node_address(), page_point);
+            let data = text.upcast::<CharacterData>().data();
+            let offset = data.chars().take(index?).map(char::len_utf16).sum::<usize>();
+            return Some((DomRoot::from_ref(node), offset as u32));
+        }
+        if node.editing_host().is_some() && !node.children().any(|child| child.is::<Text>()) {
+            return Some((DomRoot::from_ref(node), 0));
+        }
+        None
+    }
+
+    /// Starts selecting text with the mouse at `client_point`.
+    // https://w3c.github.io/selection-api/#selectstart-event
+    fn start_mouse_selection(&self, node: &Node, client_point: Point2D<f32>) {
+        let selection = match self.selection_for_user_input(node) {
+            Some(selection) => selection,
+            None => return,
+        };
+        let (node, offset) = match self.caret_position_at_point(node, client_point) {
+            Some(position) => position,
+            None => return selection.RemoveAllRanges(),
+        };
+        let event = Event::new(self.window.upcast(),
+                               atom!("selectstart"),
+                               EventBubbles::Bubbles,
+                               EventCancelable::Cancelable);
+        if event.fire(node.upcast()) == EventStatus::Canceled {
+            return;
+        }
+        if selection.Collapse(Some(&node), offset).is_ok() {
+            self.selecting_with_mouse.set(true);
+        }
+    }
+
+    /// Moves the focus of a selection started with the mouse to `client_point`.
+    fn extend_mouse_selection(&self, node: &Node, client_point: Point2D<f32>) {
+        let selection = match self.selection_for_user_input(node) {
+            Some(selection) => selection,
+            None => return,
+        };
+        if let Some((node, offset)) = self.caret_position_at_point(node, client_point) {
+            let _ = selection.Extend(&node, offset);
+        }
+    }
+
+    /// Runs the default action of a key press that moves the selection or edits
+    /// the content of an editing host. Returns whether the key was handled.
+    fn handle_selection_key(&self, target: &EventTarget, ch: Option<char>, key: Key, modifiers: KeyModifiers) -> bool {
+        let target = match target.downcast::<Node>() {
+            Some(target) => target,
+            None => return false,
+        };
+        let selection = match self.selection_for_user_input(target) {
+            Some(selection) => selection,
+            None => return false,
+        };
+        let command_modifier = modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::SUPER);
+        let shift = modifiers.contains(KeyModifiers::SHIFT);
+        let editable = selection.range().map_or(false, |range| range.StartContainer().editing_host().is_some());
+        let command = match key {
+            Key::A if command_modifier => EditingCommand::SelectAll,
+            Key::Left | Key::Right if editable || shift => {
+                selection.modify_by_character(key == Key::Right, shift);
+                return true;
+            },
+            Key::Backspace if editable => EditingCommand::Delete,
+            Key::Delete if editable => EditingCommand::ForwardDelete,
+            Key::Enter if editable => EditingCommand::InsertLineBreak,
+            _ => match ch {
+                Some(ch) if editable && !command_modifier && !ch.is_control() => {
+                    return editing::execute(self, EditingCommand::InsertText, DOMString::from(ch.to_string()));
+                },
+                _ => return false,
+            },
+        };
+        editing::execute(self, command, DOMString::new())
+    }
+
     #[allow(unsafe_code)]
     pub fn handle_mouse_move_event(
         &self,
@@ -1111,8 +1313,8 @@ impl Document {
             Some(client_point) => client_point,
         };
 
-        let maybe_new_target = node_address.and_then(|address| {
-            let node = unsafe { node::from_untrusted_node_address(js_runtime, address) };
+        let hit_node = node_address.map(|address| unsafe { node::from_untrusted_node_address(js_runtime, address) });
+        let maybe_new_target = hit_node.as_ref().and_then(|node| {
             node.inclusive_ancestors()
                 .filter_map(DomRoot::downcast::<Element>)
                 .next()
@@ -1127,6 +1329,10 @@ impl Document {
 
         self.fire_mouse_event(client_point, new_target.upcast(), FireMouseEventType::Move);
 
+        if self.selecting_with_mouse.get() {
+            self.extend_mouse_selection(hit_node.as_ref().unwrap(), client_point);
+        }
+
         // Nothing more to do here, mousemove is sent,
         // and the element under the mouse hasn't changed.
         if maybe_new_target == prev_mouse_over_target.get() {
@@ -1279,6 +1485,7 @@ impl Document {
             false,
         );
         let event = event.upcast::<Event>();
+        event.set_composed(true);
         let result = event.fire(&target);
 
         window.reflow(ReflowGoal::Full, ReflowReason::MouseEvent);
@@ -1339,6 +1546,7 @@ impl Document {
                                           None,
                                           props.key_code);
         let event = keyevent.upcast::<Event>();
+        event.set_composed(true);
         event.fire(target);
         let mut cancel_state = event.get_cancel_state();
 
@@ -1365,10 +1573,16 @@ impl Document {
                                            props.char_code,
                                            0);
             let ev = event.upcast::<Event>();
+            ev.set_composed(true);
             ev.fire(target);
             cancel_state = ev.get_cancel_state();
         }
 
+        if cancel_state == EventDefault::Allowed && state != KeyState::Released &&
+            self.handle_selection_key(target, ch, key, modifiers) {
+            cancel_state = EventDefault::Handled;
+        }
+
         if cancel_state == EventDefault::Allowed {
             let msg = EmbedderMsg::KeyEvent(ch, key, state, modifiers);
             self.send_to_embedder(msg);
@@ -1488,7 +1702,13 @@ impl Document {
         self.animation_frame_list.borrow_mut().push((ident, Some(callback)));
 
         // TODO: Should tick animation only when document is visible
+        self.request_animation_ticks();
+
+        ident
+    }
 
+    /// Asks for the animation frame callbacks to be run at the next frame.
+    fn request_animation_ticks(&self) {
         // If we are running 'fake' animation frames, we unconditionally
         // set up a one-shot timer for script to execute the rAF callbacks.
         if self.is_faking_animation_frames() {
@@ -1508,8 +1728,150 @@ impl Document {
             let event = ScriptMsg::ChangeRunningAnimationsState(AnimationState::AnimationCallbacksPresent);
             self.window().send_to_constellation(event);
         }
+    }
 
-        ident
+    /// <https://drafts.csswg.org/web-animations/#the-documents-default-timeline>
+    pub fn timeline(&self) -> DomRoot<DocumentTimeline> {
+        self.timeline.or_init(|| DocumentTimeline::new(&self.window, 0.))
+    }
+
+    /// The current time of the default timeline, which only advances when
+    /// animation frames run.
+    pub fn animation_timeline_time(&self) -> f64 {
+        if let Some(time) = self.animation_timeline_time.get() {
+            return time;
+        }
+        let time = *self.global().performance().Now();
+        self.animation_timeline_time.set(Some(time));
+        time
+    }
+
+    /// Keeps track of a script animation whose state changed, so that it is
+    /// sampled by layout at the next reflow and ticked at animation frames.
+    ///
+    /// CSS animations and transitions are run by layout and not tracked here.
+    pub fn register_animation(&self, animation: &Animation) {
+        if animation.is::<CSSAnimation>() || animation.is::<CSSTransition>() {
+            return;
+        }
+        {
+            let mut animations = self.animations.borrow_mut();
+            if !animations.iter().any(|a| &**a == animation) {
+                animations.push(Dom::from_ref(animation));
+            }
+        }
+        self.animations_need_reflow.set(true);
+        if animation.is_ticking() && !self.running_animation_callbacks.get() {
+            self.request_animation_ticks();
+        }
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#update-animations-and-send-events>
+    fn update_animations_and_send_events(&self, now: f64) {
+        self.animation_timeline_time.set(Some(now));
+        rooted_vec!(let animations <- self.animations.borrow().iter().map(|a| DomRoot::from_ref(&**a)));
+        for animation in animations.iter() {
+            animation.tick();
+        }
+        self.animations.borrow_mut().retain(|a| a.is_ticking() || a.is_relevant());
+        if !self.animations.borrow().is_empty() {
+            self.animations_need_reflow.set(true);
+        }
+    }
+
+    fn has_active_animations(&self) -> bool {
+        self.animations.borrow().iter().any(|a| a.is_ticking())
+    }
+
+    /// Samples the script animations for layout, grouped by target element.
+    ///
+    /// Elements that were animated at the previous reflow and are not anymore
+    /// get an empty list of samples, so that layout restores their style.
+    pub fn flush_animations_for_reflow(&self) -> Vec<(TrustedNodeAddress, Vec<ScriptAnimationSample>)> {
+        let mut targets: Vec<DomRoot<Element>> = vec![];
+        let mut samples: Vec<Vec<ScriptAnimationSample>> = vec![];
+        for animation in self.animations.borrow().iter() {
+            let (target, sample) = match animation.sample() {
+                Some(sample) => sample,
+                None => continue,
+            };
+            if !target.upcast::<Node>().is_in_doc() {
+                continue;
+            }
+            match targets.iter().position(|t| *t == target) {
+                Some(index) => samples[index].push(sample),
+                None => {
+                    targets.push(target);
+                    samples.push(vec![sample]);
+                },
+            }
+        }
+
+        let mut result: Vec<_> = targets.iter().zip(samples.into_iter()).map(|(target, samples)| {
+            (target.upcast::<Node>().to_trusted_node_address(), samples)
+        }).collect();
+        for target in self.animated_targets.borrow().iter() {
+            if !targets.iter().any(|t| &**t == &**target) {
+                result.push((target.upcast::<Node>().to_trusted_node_address(), vec![]));
+            }
+        }
+
+        *self.animated_targets.borrow_mut() = targets.iter().map(|t| Dom::from_ref(&**t)).collect();
+        self.animations_need_reflow.set(false);
+        result
+    }
+
+    /// Returns the CSS animations and transitions running in layout, reusing
+    /// the objects reflecting them the last time this was called.
+    fn css_animations(&self) -> Vec<DomRoot<Animation>> {
+        let running = self.window.running_css_animations_query();
+        let mut previous: Vec<DomRoot<Animation>> =
+            self.css_animations.borrow().iter().map(|a| DomRoot::from_ref(&**a)).collect();
+        let mut animations = vec![];
+        for (node, running) in running {
+            let target = match DomRoot::downcast::<Element>(node) {
+                Some(target) => target,
+                None => continue,
+            };
+            let existing = previous.iter().position(|animation| {
+                animation.target().map_or(false, |t| t == target) && match running.kind {
+                    RunningCSSAnimationKind::Animation(ref name) => {
+                        animation.downcast::<CSSAnimation>()
+                            .map_or(false, |a| &**a.animation_name() == &**name)
+                    },
+                    RunningCSSAnimationKind::Transition(ref property) => {
+                        animation.downcast::<CSSTransition>()
+                            .map_or(false, |t| &**t.transition_property() == &**property)
+                    },
+                }
+            });
+            let animation = match existing {
+                Some(index) => previous.remove(index),
+                None => match running.kind {
+                    RunningCSSAnimationKind::Animation(ref name) => {
+                        DomRoot::upcast(CSSAnimation::new(&self.window, &target, DOMString::from(&**name)))
+                    },
+                    RunningCSSAnimationKind::Transition(ref property) => {
+                        DomRoot::upcast(CSSTransition::new(&self.window, &target, DOMString::from(&**property)))
+                    },
+                },
+            };
+            animation.update_from_layout(&running);
+            animations.push(animation);
+        }
+        *self.css_animations.borrow_mut() = animations.iter().map(|a| Dom::from_ref(&**a)).collect();
+        animations
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#dom-document-getanimations>
+    pub fn get_animations(&self) -> Vec<DomRoot<Animation>> {
+        let mut animations = self.css_animations();
+        animations.extend(self.animations.borrow().iter().filter(|animation| {
+            animation.is_relevant() && animation.target().map_or(false, |target| {
+                target.upcast::<Node>().is_in_doc() && &*target.owner_document() == self
+            })
+        }).map(|animation| DomRoot::from_ref(&**animation)));
+        animations
     }
 
     /// <https://html.spec.whatwg.org/multipage/#dom-window-cancelanimationframe>
@@ -1531,6 +1893,8 @@ impl Document {
         let was_faking_animation_frames = self.is_faking_animation_frames();
         let timing = self.global().performance().Now();
 
+        self.update_animations_and_send_events(*timing);
+
         for (_, callback) in animation_frame_list.drain(..) {
             if let Some(callback) = callback {
                 callback.call(self, *timing);
@@ -1539,6 +1903,8 @@ impl Document {
 
         self.running_animation_callbacks.set(false);
 
+        self.update_layout_observers();
+
         let spurious = !self.window.reflow(ReflowGoal::Full, ReflowReason::RequestAnimationFrame);
 
         if spurious && !was_faking_animation_frames {
@@ -1565,7 +1931,9 @@ impl Document {
         // animation frame is one in which the callback did not mutate the DOM—that is, an
         // animation frame that wasn't actually used for animation.)
         let is_empty = self.animation_frame_list.borrow().is_empty();
-        if is_empty || (!was_faking_animation_frames && self.is_faking_animation_frames()) {
+        let has_active_animations = self.has_active_animations();
+        if (is_empty && !has_active_animations) ||
+           (!was_faking_animation_frames && self.is_faking_animation_frames()) {
             if is_empty {
                 // If the current animation frame list in the DOM instance is empty,
                 // we can reuse the original `Vec<T>` that we put on the stack to
@@ -1580,6 +1948,12 @@ impl Document {
             self.window().send_to_constellation(event);
         }
 
+        // Keep ticking the running script animations, since the callbacks that
+        // would have requested another animation frame are not there.
+        if is_empty && has_active_animations && self.is_faking_animation_frames() {
+            self.request_animation_ticks();
+        }
+
         // Update the counter of spurious animation frames.
         if spurious {
             if self.spurious_animation_frames.get() < SPURIOUS_ANIMATION_FRAME_THRESHOLD {
@@ -1590,9 +1964,119 @@ impl Document {
         }
     }
 
+    pub fn add_intersection_observer(&self, observer: &IntersectionObserver) {
+        let mut observers = self.intersection_observers.borrow_mut();
+        if !observers.iter().any(|registered| &**registered == observer) {
+            observers.push(Dom::from_ref(observer));
+        }
+    }
+
+    pub fn remove_intersection_observer(&self, observer: &IntersectionObserver) {
+        self.intersection_observers.borrow_mut().retain(|registered| &**registered != observer);
+    }
+
+    pub fn add_resize_observer(&self, observer: &ResizeObserver) {
+        let mut observers = self.resize_observers.borrow_mut();
+        if !observers.iter().any(|registered| &**registered == observer) {
+            observers.push(Dom::from_ref(observer));
+        }
+    }
+
+    pub fn remove_resize_observer(&self, observer: &ResizeObserver) {
+        self.resize_observers.borrow_mut().retain(|registered| &**registered != observer);
+    }
+
+    /// Queues a task to update the intersection and resize observers after layout
+    /// changed outside of an animation frame.
+    pub fn queue_layout_observers_update(&self) {
+        if self.layout_observers_update_queued.get() ||
+            (self.intersection_observers.borrow().is_empty() && self.resize_observers.borrow().is_empty()) {
+            return;
+        }
+        self.layout_observers_update_queued.set(true);
+        let document = Trusted::new(self);
+        let _ = self.window.dom_manipulation_task_source().queue(
+            task!(update_layout_observers: move || {
+                document.root().update_layout_observers();
+            }),
+            self.window.upcast(),
+        );
+    }
+
+    /// Returns the layout boxes of the given elements, from a single layout query.
+    fn observed_boxes(&self, elements: Vec<DomRoot<Element>>) -> Vec<(DomRoot<Element>, Option<ObservedBoxes>)> {
+        let addresses = elements.iter()
+            .map(|element| element.upcast::<Node>().to_trusted_node_address())
+            .collect();
+        let boxes = self.window.observed_boxes_query(addresses);
+        elements.into_iter().zip(boxes).collect()
+    }
+
+    /// Runs the steps of "update the rendering" that report layout changes to script:
+    /// <https://drafts.csswg.org/resize-observer/#html-event-loop> and
+    /// <https://w3c.github.io/IntersectionObserver/#run-the-update-intersection-observations-steps>
+    pub fn update_layout_observers(&self) {
+        self.layout_observers_update_queued.set(false);
+        let lookup = |boxes: &[(DomRoot<Element>, Option<ObservedBoxes>)], element: &Element| {
+            boxes.iter().find(|&&(ref observed, _)| &**observed == element).and_then(|observed| observed.1)
+        };
+
+        let resize_observers: Vec<DomRoot<ResizeObserver>> =
+            self.resize_observers.borrow().iter().map(|observer| DomRoot::from_ref(&**observer)).collect();
+        if !resize_observers.is_empty() {
+            let gather = |depth| {
+                let elements = resize_observers.iter().flat_map(|observer| observer.observed_elements()).collect();
+                let boxes = self.observed_boxes(elements);
+                let boxes = |element: &Element| lookup(&boxes, element);
+                for observer in &resize_observers {
+                    observer.gather_active_observations_at_depth(depth, &boxes);
+                }
+            };
+            gather(0);
+            while resize_observers.iter().any(|observer| observer.has_active_observations()) {
+                let elements = resize_observers.iter().flat_map(|observer| observer.observed_elements()).collect();
+                let boxes = self.observed_boxes(elements);
+                let boxes = |element: &Element| lookup(&boxes, element);
+                let depth = resize_observers.iter()
+                    .map(|observer| observer.broadcast_active_observations(&boxes))
+                    .min()
+                    .unwrap();
+                gather(depth);
+            }
+            if resize_observers.iter().any(|observer| observer.has_skipped_observations()) {
+                let error_info = ErrorInfo {
+                    message: "ResizeObserver loop completed with undelivered notifications.".to_owned(),
+                    filename: String::new(),
+                    lineno: 0,
+                    column: 0,
+                };
+                self.window.upcast::<GlobalScope>().report_an_error(error_info, HandleValue::undefined());
+            }
+        }
+
+        let intersection_observers: Vec<DomRoot<IntersectionObserver>> =
+            self.intersection_observers.borrow().iter().map(|observer| DomRoot::from_ref(&**observer)).collect();
+        if !intersection_observers.is_empty() {
+            let elements = intersection_observers.iter().flat_map(|observer| observer.observed_elements()).collect();
+            let boxes = self.observed_boxes(elements);
+            let boxes = |element: &Element| lookup(&boxes, element);
+            let time = *self.window.Performance().Now();
+            let viewport = self.window.current_viewport();
+            for observer in &intersection_observers {
+                observer.update_intersection_observations(time, viewport, &boxes);
+            }
+            for observer in &intersection_observers {
+                observer.notify();
+            }
+        }
+    }
+
     pub fn fetch_async(&self, load: LoadType,
-                       request: RequestInit,
+                       mut request: RequestInit,
                        fetch_target: IpcSender<FetchResponseMsg>) {
+        if request.csp_list.is_none() {
+            request.csp_list = self.get_csp_list();
+        }
         let mut loader = self.loader.borrow_mut();
         loader.fetch_async(load, request, fetch_target);
     }
@@ -2165,6 +2649,7 @@ impl Document {
                                     0i32,
                                     related_target);
         let event = event.upcast::<Event>();
+        event.set_composed(true);
         event.set_trusted(true);
         let target = node.upcast();
         event.fire(target);
@@ -2237,6 +2722,10 @@ pub trait LayoutDocumentHelpers {
     unsafe fn will_paint(&self);
     unsafe fn quirks_mode(&self) -> QuirksMode;
     unsafe fn style_shared_lock(&self) -> &StyleSharedRwLock;
+    unsafe fn shadow_roots(&self) -> Vec<LayoutDom<ShadowRoot>>;
+    unsafe fn shadow_roots_styles_changed(&self) -> bool;
+    unsafe fn flush_shadow_roots_stylesheets(&self);
+    unsafe fn set_has_relative_selectors(&self, value: bool);
 }
 
 #[allow(unsafe_code)]
@@ -2278,6 +2767,29 @@ impl LayoutDocumentHelpers for LayoutDom<Document> {
     unsafe fn style_shared_lock(&self) -> &StyleSharedRwLock {
         (*self.unsafe_get()).style_shared_lock()
     }
+
+    #[inline]
+    unsafe fn shadow_roots(&self) -> Vec<LayoutDom<ShadowRoot>> {
+        (*self.unsafe_get()).shadow_roots.borrow_for_layout()
+            .iter()
+            .map(|sr| sr.to_layout())
+            .collect()
+    }
+
+    #[inline]
+    unsafe fn shadow_roots_styles_changed(&self) -> bool {
+        (*self.unsafe_get()).shadow_roots_styles_changed.get()
+    }
+
+    #[inline]
+    unsafe fn flush_shadow_roots_stylesheets(&self) {
+        (*self.unsafe_get()).shadow_roots_styles_changed.set(false)
+    }
+
+    #[inline]
+    unsafe fn set_has_relative_selectors(&self, value: bool) {
+        (*self.unsafe_get()).has_relative_selectors.set(value)
+    }
 }
 
 // https://html.spec.whatwg.org/multipage/#is-a-registrable-domain-suffix-of-or-is-equal-to
@@ -2417,6 +2929,18 @@ impl Document {
             },
             stylesheets: DomRefCell::new(DocumentStylesheetSet::new()),
             stylesheet_list: MutNullableDom::new(None),
+            fonts: MutNullableDom::new(None),
+            timeline: MutNullableDom::new(None),
+            animations: DomRefCell::new(vec![]),
+            animated_targets: DomRefCell::new(vec![]),
+            animations_need_reflow: Cell::new(false),
+            animation_timeline_time: Cell::new(None),
+            css_animations: DomRefCell::new(vec![]),
+            selection: MutNullableDom::new(None),
+            selecting_with_mouse: Cell::new(false),
+            intersection_observers: DomRefCell::new(vec![]),
+            resize_observers: DomRefCell::new(vec![]),
+            layout_observers_update_queued: Cell::new(false),
             ready_state: Cell::new(ready_state),
             domcontentloaded_dispatched: Cell::new(domcontentloaded_dispatched),
             possibly_focused: Default::default(),
@@ -2437,6 +2961,9 @@ impl Document {
             base_element: Default::default(),
             appropriate_template_contents_owner_document: Default::default(),
             pending_restyles: DomRefCell::new(HashMap::new()),
+            shadow_roots: DomRefCell::new(Vec::new()),
+            shadow_roots_styles_changed: Cell::new(false),
+            has_relative_selectors: Cell::new(false),
             needs_paint: Cell::new(false),
             active_touch_points: DomRefCell::new(Vec::new()),
             dom_loading: Cell::new(Default::default()),
@@ -2451,6 +2978,7 @@ impl Document {
             origin: origin,
             referrer: referrer,
             referrer_policy: Cell::new(referrer_policy),
+            csp_list: DomRefCell::new(None),
             target_element: MutNullableDom::new(None),
             last_click_info: DomRefCell::new(None),
             ignore_destructive_writes_counter: Default::default(),
@@ -2574,6 +3102,29 @@ impl Document {
         Some(Device::new(MediaType::screen(), viewport_size, device_pixel_ratio))
     }
 
+    /// Returns the `@font-face` rules of the stylesheets of this document that
+    /// apply to the current device, in order, as layout sees them.
+    pub fn font_face_rules(&self) -> Vec<Arc<Locked<FontFaceRule>>> {
+        let device = match self.device() {
+            Some(device) => device,
+            None => return vec![],
+        };
+        let guard = self.style_shared_lock.read();
+        let stylesheets = self.stylesheets.borrow();
+        let mut rules = vec![];
+        for (sheet, _origin) in stylesheets.iter() {
+            if !sheet.enabled() || !sheet.is_effective_for_device(&device, &guard) {
+                continue;
+            }
+            for rule in sheet.effective_rules(&device, &guard) {
+                if let CssRule::FontFace(ref rule) = *rule {
+                    rules.push(rule.clone());
+                }
+            }
+        }
+        rules
+    }
+
     /// Remove a stylesheet owned by `owner` from the list of document sheets.
     #[allow(unrooted_must_root)] // Owner needs to be rooted already necessarily.
     pub fn remove_stylesheet(&self, owner: &Element, s: &Arc<Stylesheet>) {
@@ -2640,6 +3191,32 @@ impl Document {
         }
     }
 
+    /// Registers a shadow root that became connected to this document.
+    #[allow(unrooted_must_root)]
+    pub fn register_shadow_root(&self, shadow_root: &ShadowRoot) {
+        self.shadow_roots.borrow_mut().push(Dom::from_ref(shadow_root));
+        self.invalidate_shadow_roots_stylesheets();
+    }
+
+    /// Unregisters a shadow root that is no longer connected to this document.
+    pub fn unregister_shadow_root(&self, shadow_root: &ShadowRoot) {
+        self.shadow_roots.borrow_mut().retain(|root| &**root != shadow_root);
+    }
+
+    /// Notes that the stylesheets of a shadow root changed, so that layout
+    /// flushes them on the next reflow.
+    pub fn invalidate_shadow_roots_stylesheets(&self) {
+        self.shadow_roots_styles_changed.set(true);
+    }
+
+    /// Lets the font face set of this document know that layout finished
+    /// loading a web font.
+    pub fn web_font_loaded(&self) {
+        if let Some(fonts) = self.fonts.get() {
+            fonts.fulfill_ready_promise_if_needed();
+        }
+    }
+
     /// Returns the number of document stylesheets.
     pub fn stylesheet_count(&self) -> usize {
         self.stylesheets.borrow().len()
@@ -2748,6 +3325,68 @@ impl Document {
         return self.referrer_policy.get();
     }
 
+    pub fn set_csp_list(&self, csp_list: Option<CspList>) {
+        *self.csp_list.borrow_mut() = csp_list;
+    }
+
+    pub fn get_csp_list(&self) -> Option<CspList> {
+        self.csp_list.borrow().clone()
+    }
+
+    /// Policies delivered through `<meta http-equiv="Content-Security-Policy">`.
+    pub fn append_csp_list(&self, csp_list: CspList) {
+        let mut current = self.csp_list.borrow_mut();
+        match *current {
+            Some(ref mut list) => list.append(csp_list),
+            None => *current = Some(csp_list),
+        }
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#should-block-inline>
+    ///
+    /// Returns true if the inline content of `element` must not be applied or run.
+    pub fn should_elements_inline_type_behavior_be_blocked(&self,
+                                                           element: &Element,
+                                                           type_: InlineCheckType,
+                                                           source: &str)
+                                                           -> bool {
+        let (result, violations) = match *self.csp_list.borrow() {
+            Some(ref list) => {
+                list.should_inline_be_blocked(type_, &element.nonce_value(), source)
+            },
+            None => return false,
+        };
+        if !violations.is_empty() {
+            self.global().report_csp_violations(violations, Some(element));
+        }
+        result == CheckResult::Blocked
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#should-block-navigation-response>
+    ///
+    /// Only the `frame-ancestors` directive is checked; it is meaningless for
+    /// top-level documents.
+    pub fn is_blocked_by_frame_ancestors(&self) -> bool {
+        let parent_pipeline_id = match self.window.parent_info() {
+            Some(parent_pipeline_id) => parent_pipeline_id,
+            None => return false,
+        };
+        let (result, violations) = match *self.csp_list.borrow() {
+            Some(ref list) => {
+                let (sender, receiver) = ipc::channel().expect("Failed to create IPC channel!");
+                let msg = ScriptMsg::GetAncestorUrls(parent_pipeline_id, sender);
+                self.window.upcast::<GlobalScope>().script_to_constellation_chan().send(msg).unwrap();
+                let ancestors = receiver.recv().unwrap_or_default();
+                list.should_be_blocked_by_frame_ancestors(&ancestors)
+            },
+            None => return false,
+        };
+        if !violations.is_empty() {
+            self.global().report_csp_violations(violations, None);
+        }
+        result == CheckResult::Blocked
+    }
+
     pub fn set_target_element(&self, node: Option<&Element>) {
         if let Some(ref element) = self.target_element.get() {
             element.set_target_state(false);
@@ -2944,6 +3583,65 @@ impl DocumentMethods for Document {
         self.stylesheet_list.or_init(|| StyleSheetList::new(&self.window, Dom::from_ref(&self)))
     }
 
+    // https://drafts.csswg.org/css-font-loading/#dom-document-fonts
+    fn Fonts(&self) -> DomRoot<FontFaceSet> {
+        self.fonts.or_init(|| FontFaceSet::new(&self.window))
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-document-timeline
+    fn Timeline(&self) -> DomRoot<DocumentTimeline> {
+        self.timeline()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-document-getanimations
+    fn GetAnimations(&self) -> Vec<DomRoot<Animation>> {
+        self.get_animations()
+    }
+
+    // https://w3c.github.io/selection-api/#dom-document-getselection
+    fn GetSelection(&self) -> Option<DomRoot<Selection>> {
+        if self.browsing_context().is_none() {
+            return None;
+        }
+        Some(self.selection.or_init(|| Selection::new(self)))
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#execcommand()
+    fn ExecCommand(&self, command_id: DOMString, _show_ui: bool, value: DOMString) -> bool {
+        match EditingCommand::from_name(&command_id) {
+            Some(command) if editing::is_enabled(self, command) => editing::execute(self, command, value),
+            _ => false,
+        }
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandenabled()
+    fn QueryCommandEnabled(&self, command_id: DOMString) -> bool {
+        EditingCommand::from_name(&command_id).map_or(false, |command| editing::is_enabled(self, command))
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandindeterm()
+    fn QueryCommandIndeterm(&self, _command_id: DOMString) -> bool {
+        // None of the supported commands have an indeterminacy.
+        false
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandstate()
+    fn QueryCommandState(&self, _command_id: DOMString) -> bool {
+        // None of the supported commands have a state.
+        false
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandsupported()
+    fn QueryCommandSupported(&self, command_id: DOMString) -> bool {
+        EditingCommand::from_name(&command_id).is_some()
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandvalue()
+    fn QueryCommandValue(&self, _command_id: DOMString) -> DOMString {
+        // None of the supported commands have a value.
+        DOMString::new()
+    }
+
     // https://dom.spec.whatwg.org/#dom-document-implementation
     fn Implementation(&self) -> DomRoot<DOMImplementation> {
         self.implementation.or_init(|| DOMImplementation::new(self))
@@ -3308,7 +4006,7 @@ impl DocumentMethods for Document {
             "uievent" | "uievents" =>
                 Ok(DomRoot::upcast(UIEvent::new_uninitialized(&self.window))),
             "webglcontextevent" =>
-                Ok(DomRoot::upcast(WebGLContextEvent::new_uninitialized(&self.window))),
+                Ok(DomRoot::upcast(WebGLContextEvent::new_uninitialized(self.window.upcast()))),
             _ =>
                 Err(Error::NotSupported),
         }
@@ -4,6 +4,7 @@
 
 use dom::bindings::codegen::Bindings::DocumentFragmentBinding;
 use dom::bindings::codegen::Bindings::DocumentFragmentBinding::DocumentFragmentMethods;
+use dom::bindings::codegen::Bindings::ShadowRootBinding::ShadowRootMethods;
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use dom::bindings::codegen::UnionTypes::NodeOrString;
 use dom::bindings::error::{ErrorResult, Fallible};
@@ -15,6 +16,7 @@ use dom::element::Element;
 use dom::htmlcollection::HTMLCollection;
 use dom::node::{Node, window_from_node};
 use dom::nodelist::NodeList;
+use dom::shadowroot::ShadowRoot;
 use dom::window::Window;
 use dom_struct::dom_struct;
 use servo_atoms::Atom;
@@ -27,7 +29,7 @@ pub struct DocumentFragment {
 
 impl DocumentFragment {
     /// Creates a new DocumentFragment.
-    fn new_inherited(document: &Document) -> DocumentFragment {
+    pub fn new_inherited(document: &Document) -> DocumentFragment {
         DocumentFragment {
             node: Node::new_inherited(document),
         }
@@ -44,6 +46,11 @@ impl DocumentFragment {
 
         Ok(DocumentFragment::new(&document))
     }
+
+    /// <https://dom.spec.whatwg.org/#concept-documentfragment-host>
+    pub fn host(&self) -> Option<DomRoot<Element>> {
+        self.downcast::<ShadowRoot>().map(|shadow_root| shadow_root.Host())
+    }
 }
 
 impl DocumentFragmentMethods for DocumentFragment {
@@ -0,0 +1,53 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::animationtimeline::AnimationTimeline;
+use dom::bindings::codegen::Bindings::DocumentTimelineBinding;
+use dom::bindings::codegen::Bindings::DocumentTimelineBinding::DocumentTimelineOptions;
+use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use dom::bindings::error::Fallible;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::window::Window;
+use dom_struct::dom_struct;
+
+/// <https://drafts.csswg.org/web-animations/#the-documenttimeline-interface>
+#[dom_struct]
+pub struct DocumentTimeline {
+    timeline: AnimationTimeline,
+    /// The zero time of this timeline, relative to the time origin of the
+    /// document.
+    origin_time: f64,
+}
+
+impl DocumentTimeline {
+    fn new_inherited(origin_time: f64) -> DocumentTimeline {
+        DocumentTimeline {
+            timeline: AnimationTimeline::new_inherited(),
+            origin_time: origin_time,
+        }
+    }
+
+    pub fn new(window: &Window, origin_time: f64) -> DomRoot<DocumentTimeline> {
+        reflect_dom_object(Box::new(DocumentTimeline::new_inherited(origin_time)),
+                           window,
+                           DocumentTimelineBinding::Wrap)
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-documenttimeline-documenttimeline
+    pub fn Constructor(window: &Window, options: &DocumentTimelineOptions) -> Fallible<DomRoot<DocumentTimeline>> {
+        Ok(DocumentTimeline::new(window, *options.originTime))
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#document-timelines>
+    ///
+    /// The timeline is inactive while its document is not fully active.
+    pub fn current_time(&self) -> Option<f64> {
+        let document = self.global().as_window().Document();
+        if !document.is_fully_active() {
+            return None;
+        }
+        Some(document.animation_timeline_time() - self.origin_time)
+    }
+}
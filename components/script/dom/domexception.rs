@@ -36,6 +36,11 @@ pub enum DOMErrorName {
     InvalidNodeTypeError = DOMExceptionConstants::INVALID_NODE_TYPE_ERR,
     DataCloneError = DOMExceptionConstants::DATA_CLONE_ERR,
     NotReadableError = DOMExceptionConstants::NOT_READABLE_ERR,
+    ConstraintError,
+    DataError,
+    TransactionInactiveError,
+    ReadOnlyError,
+    VersionError,
 }
 
 #[dom_struct]
@@ -62,7 +67,15 @@ impl DOMException {
 impl DOMExceptionMethods for DOMException {
     // https://heycam.github.io/webidl/#dfn-DOMException
     fn Code(&self) -> u16 {
-        self.code as u16
+        match self.code {
+            // Error names introduced after the legacy codes were frozen have no code.
+            DOMErrorName::ConstraintError |
+            DOMErrorName::DataError |
+            DOMErrorName::TransactionInactiveError |
+            DOMErrorName::ReadOnlyError |
+            DOMErrorName::VersionError => 0,
+            code => code as u16,
+        }
     }
 
     // https://heycam.github.io/webidl/#idl-DOMException-error-names
@@ -95,7 +108,13 @@ impl DOMExceptionMethods for DOMException {
             DOMErrorName::InvalidNodeTypeError =>
                 "The supplied node is incorrect or has an incorrect ancestor for this operation.",
             DOMErrorName::DataCloneError => "The object can not be cloned.",
-            DOMErrorName::NotReadableError => "The I/O read operation failed."
+            DOMErrorName::NotReadableError => "The I/O read operation failed.",
+            DOMErrorName::ConstraintError => "A mutation operation in a transaction failed due to a constraint.",
+            DOMErrorName::DataError => "The provided data is inadequate.",
+            DOMErrorName::TransactionInactiveError => "The transaction is not active.",
+            DOMErrorName::ReadOnlyError => "A mutating operation was attempted in a read-only transaction.",
+            DOMErrorName::VersionError => "An attempt was made to open a database using a lower version \
+                                           than the existing version."
         };
 
         DOMString::from(message)
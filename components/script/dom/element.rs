@@ -6,8 +6,11 @@
 
 use devtools_traits::AttrInfo;
 use dom::activation::Activatable;
+use dom::animation::Animation;
 use dom::attr::{Attr, AttrHelpersForLayout};
 use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::AnimatableBinding::AnimatableMethods;
+use dom::bindings::codegen::Bindings::AnimationBinding::AnimationMethods;
 use dom::bindings::codegen::Bindings::AttrBinding::AttrMethods;
 use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
 use dom::bindings::codegen::Bindings::ElementBinding;
@@ -16,21 +19,25 @@ use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
 use dom::bindings::codegen::Bindings::FunctionBinding::Function;
 use dom::bindings::codegen::Bindings::HTMLTemplateElementBinding::HTMLTemplateElementMethods;
 use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
+use dom::bindings::codegen::Bindings::ShadowRootBinding::{ShadowRootInit, ShadowRootMethods, ShadowRootMode};
 use dom::bindings::codegen::Bindings::WindowBinding::{ScrollBehavior, ScrollToOptions};
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use dom::bindings::codegen::UnionTypes::NodeOrString;
+use dom::bindings::codegen::UnionTypes::UnrestrictedDoubleOrKeyframeAnimationOptions;
+use dom::bindings::codegen::UnionTypes::UnrestrictedDoubleOrKeyframeEffectOptions;
 use dom::bindings::conversions::DerivedFrom;
 use dom::bindings::error::{Error, ErrorResult, Fallible};
 use dom::bindings::inheritance::{Castable, ElementTypeId, HTMLElementTypeId, NodeTypeId};
 use dom::bindings::refcounted::{Trusted, TrustedPromise};
 use dom::bindings::reflector::DomObject;
-use dom::bindings::root::{Dom, DomRoot, LayoutDom, MutNullableDom, RootedReference};
+use dom::bindings::root::{Dom, DomRoot, LayoutDom, RootedReference};
 use dom::bindings::str::DOMString;
 use dom::bindings::xmlname::{namespace_from_domstring, validate_and_extract, xml_name_type};
 use dom::bindings::xmlname::XMLName::InvalidXMLName;
 use dom::characterdata::CharacterData;
 use dom::create::create_element;
 use dom::customelementregistry::{CallbackReaction, CustomElementDefinition, CustomElementReaction};
+use dom::customelementregistry::is_valid_custom_element_name;
 use dom::document::{Document, LayoutDocumentHelpers};
 use dom::documentfragment::DocumentFragment;
 use dom::domrect::DOMRect;
@@ -56,6 +63,7 @@ use dom::htmllinkelement::HTMLLinkElement;
 use dom::htmlobjectelement::HTMLObjectElement;
 use dom::htmloptgroupelement::HTMLOptGroupElement;
 use dom::htmlselectelement::HTMLSelectElement;
+use dom::htmlslotelement::HTMLSlotElement;
 use dom::htmlstyleelement::HTMLStyleElement;
 use dom::htmltablecellelement::{HTMLTableCellElement, HTMLTableCellElementLayoutHelpers};
 use dom::htmltableelement::{HTMLTableElement, HTMLTableElementLayoutHelpers};
@@ -63,6 +71,7 @@ use dom::htmltablerowelement::{HTMLTableRowElement, HTMLTableRowElementLayoutHel
 use dom::htmltablesectionelement::{HTMLTableSectionElement, HTMLTableSectionElementLayoutHelpers};
 use dom::htmltemplateelement::HTMLTemplateElement;
 use dom::htmltextareaelement::{HTMLTextAreaElement, LayoutHTMLTextAreaElementHelpers};
+use dom::keyframeeffect::KeyframeEffect;
 use dom::mutationobserver::{Mutation, MutationObserver};
 use dom::namednodemap::NamedNodeMap;
 use dom::node::{ChildrenMutation, LayoutNodeHelpers, Node};
@@ -70,7 +79,9 @@ use dom::node::{NodeDamage, NodeFlags, UnbindContext};
 use dom::node::{document_from_node, window_from_node};
 use dom::nodelist::NodeList;
 use dom::promise::Promise;
+use dom::raredata::ElementRareData;
 use dom::servoparser::ServoParser;
+use dom::shadowroot::ShadowRoot;
 use dom::text::Text;
 use dom::validation::Validatable;
 use dom::virtualmethods::{VirtualMethods, vtable_for};
@@ -81,9 +92,10 @@ use html5ever::serialize;
 use html5ever::serialize::SerializeOpts;
 use html5ever::serialize::TraversalScope;
 use html5ever::serialize::TraversalScope::{ChildrenOnly, IncludeNode};
-use js::jsapi::Heap;
+use js::jsapi::{Heap, JSContext, JSObject};
 use js::jsval::JSVal;
 use msg::constellation_msg::InputMethodType;
+use net_traits::csp::InlineCheckType;
 use net_traits::request::CorsSettings;
 use ref_filter_map::ref_filter_map;
 use script_layout_interface::message::ReflowGoal;
@@ -95,7 +107,7 @@ use selectors::sink::Push;
 use servo_arc::Arc;
 use servo_atoms::Atom;
 use std::borrow::Cow;
-use std::cell::{Cell, Ref};
+use std::cell::{Cell, Ref, RefMut};
 use std::default::Default;
 use std::fmt;
 use std::mem;
@@ -116,6 +128,7 @@ use style::rule_tree::CascadeLevel;
 use style::selector_parser::{NonTSPseudoClass, PseudoElement, RestyleDamage, SelectorImpl, SelectorParser};
 use style::selector_parser::extended_filtering;
 use style::shared_lock::{SharedRwLock, Locked};
+use style::stylesheets::Stylesheet;
 use style::thread_state;
 use style::values::{CSSFloat, Either};
 use style::values::{specified, computed};
@@ -143,8 +156,6 @@ pub struct Element {
     is: DomRefCell<Option<LocalName>>,
     #[ignore_malloc_size_of = "Arc"]
     style_attribute: DomRefCell<Option<Arc<Locked<PropertyDeclarationBlock>>>>,
-    attr_list: MutNullableDom<NamedNodeMap>,
-    class_list: MutNullableDom<DOMTokenList>,
     state: Cell<ElementState>,
     /// These flags are set by the style system to indicate the that certain
     /// operations may require restyling this element or its descendants. The
@@ -159,6 +170,8 @@ pub struct Element {
     custom_element_definition: DomRefCell<Option<Rc<CustomElementDefinition>>>,
     /// <https://dom.spec.whatwg.org/#concept-element-custom-element-state>
     custom_element_state: Cell<CustomElementState>,
+    /// Data that only some elements need, allocated on first use.
+    rare_data: DomRefCell<Option<Box<ElementRareData>>>,
 }
 
 impl fmt::Debug for Element {
@@ -267,13 +280,12 @@ impl Element {
             id_attribute: DomRefCell::new(None),
             is: DomRefCell::new(None),
             style_attribute: DomRefCell::new(None),
-            attr_list: Default::default(),
-            class_list: Default::default(),
             state: Cell::new(state),
             selector_flags: Cell::new(ElementSelectorFlags::empty()),
             custom_element_reaction_queue: Default::default(),
             custom_element_definition: Default::default(),
             custom_element_state: Cell::new(CustomElementState::Uncustomized),
+            rare_data: Default::default(),
         }
     }
 
@@ -287,6 +299,36 @@ impl Element {
             ElementBinding::Wrap)
     }
 
+    /// Restyles the elements anchoring :has() selectors that may be affected by
+    /// a change in the children of this element: the children before the
+    /// change, and this element's inclusive ancestors and their previous
+    /// siblings.
+    fn invalidate_relative_selector_anchors(&self, mutation: &ChildrenMutation) {
+        let node = self.upcast::<Node>();
+        let doc = node.owner_doc();
+        if !doc.has_relative_selectors() {
+            return;
+        }
+        let runs = mutation.prev_child()
+            .map(|prev| prev.inclusively_preceding_siblings())
+            .into_iter()
+            .chain(node.inclusive_ancestors().map(|ancestor| ancestor.inclusively_preceding_siblings()));
+        for siblings in runs {
+            let anchor = siblings
+                .filter_map(DomRoot::downcast::<Element>)
+                .find(|element| {
+                    element.selector_flags.get().intersects(ElementSelectorFlags::ANCHORS_RELATIVE_SELECTOR)
+                });
+            // Later siblings and descendants of the anchor may depend on
+            // whether it matches, so restyle the subtree of its parent. This
+            // covers the other anchors among these siblings too.
+            if let Some(anchor) = anchor {
+                let root = anchor.upcast::<Node>().GetParentElement().unwrap_or(anchor);
+                doc.ensure_pending_restyle(&root).hint.insert(RestyleHint::restyle_subtree());
+            }
+        }
+    }
+
     pub fn restyle(&self, damage: NodeDamage) {
         let doc = self.node.owner_doc();
         let mut restyle = doc.ensure_pending_restyle(self);
@@ -485,6 +527,8 @@ pub trait LayoutElementHelpers {
     fn get_state_for_layout(&self) -> ElementState;
     fn insert_selector_flags(&self, flags: ElementSelectorFlags);
     fn has_selector_flags(&self, flags: ElementSelectorFlags) -> bool;
+    #[allow(unsafe_code)]
+    unsafe fn get_shadow_root_for_layout(&self) -> Option<LayoutDom<ShadowRoot>>;
 }
 
 impl LayoutElementHelpers for LayoutDom<Element> {
@@ -914,6 +958,12 @@ impl LayoutElementHelpers for LayoutDom<Element> {
             (*self.unsafe_get()).selector_flags.get().contains(flags)
         }
     }
+
+    #[inline]
+    #[allow(unsafe_code)]
+    unsafe fn get_shadow_root_for_layout(&self) -> Option<LayoutDom<ShadowRoot>> {
+        (*self.unsafe_get()).rare_data.borrow_for_layout().as_ref()?.shadow_root.get_inner_as_layout()
+    }
 }
 
 impl Element {
@@ -1381,6 +1431,11 @@ impl Element {
         }
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#attr-nonce>
+    pub fn nonce_value(&self) -> String {
+        String::from(self.get_string_attribute(&LocalName::from("nonce")))
+    }
+
     pub fn set_string_attribute(&self, local_name: &LocalName, value: DOMString) {
         assert!(*local_name == local_name.to_ascii_lowercase());
         self.set_attribute(local_name, AttrValue::String(value.into()));
@@ -1631,14 +1686,20 @@ impl ElementMethods for Element {
         self.set_tokenlist_attribute(&local_name!("class"), class);
     }
 
+    // https://dom.spec.whatwg.org/#dom-element-slot
+    make_getter!(Slot, "slot");
+
+    // https://dom.spec.whatwg.org/#dom-element-slot
+    make_setter!(SetSlot, "slot");
+
     // https://dom.spec.whatwg.org/#dom-element-classlist
     fn ClassList(&self) -> DomRoot<DOMTokenList> {
-        self.class_list.or_init(|| DOMTokenList::new(self, &local_name!("class")))
+        self.ensure_rare_data().class_list.or_init(|| DOMTokenList::new(self, &local_name!("class")))
     }
 
     // https://dom.spec.whatwg.org/#dom-element-attributes
     fn Attributes(&self) -> DomRoot<NamedNodeMap> {
-        self.attr_list.or_init(|| NamedNodeMap::new(&window_from_node(self), self))
+        self.ensure_rare_data().attr_list.or_init(|| NamedNodeMap::new(&window_from_node(self), self))
     }
 
     // https://dom.spec.whatwg.org/#dom-element-hasattributes
@@ -2213,7 +2274,7 @@ impl ElementMethods for Element {
             NodeTypeId::Document(_) => return Err(Error::NoModificationAllowed),
 
             // Step 4.
-            NodeTypeId::DocumentFragment => {
+            NodeTypeId::DocumentFragment(_) => {
                 let body_elem = Element::create(QualName::new(None, ns!(html), local_name!("body")),
                                                 None,
                                                 &context_document,
@@ -2390,6 +2451,51 @@ impl ElementMethods for Element {
         self.insert_adjacent(position, fragment.upcast()).map(|_| ())
     }
 
+    // https://dom.spec.whatwg.org/#dom-element-attachshadow
+    fn AttachShadow(&self, init: &ShadowRootInit) -> Fallible<DomRoot<ShadowRoot>> {
+        // Step 1.
+        if self.namespace != ns!(html) {
+            return Err(Error::NotSupported);
+        }
+
+        // Step 2.
+        if !self.is_valid_shadow_host_name() {
+            return Err(Error::NotSupported);
+        }
+
+        // Step 3.
+        if self.shadow_root().is_some() {
+            return Err(Error::InvalidState);
+        }
+
+        // Steps 4-6.
+        let doc = document_from_node(self);
+        let shadow_root = ShadowRoot::new(self, &doc, init.mode);
+        self.ensure_rare_data().shadow_root.set(Some(&shadow_root));
+
+        let node = self.upcast::<Node>();
+        if node.is_in_doc() {
+            doc.register_shadow_root(&shadow_root);
+            // The shadow tree is rendered instead of the children of this
+            // element from now on.
+            doc.ensure_pending_restyle(self).hint.insert(RestyleHint::restyle_subtree());
+            node.dirty(NodeDamage::OtherNodeDamage);
+        }
+
+        // Step 7.
+        Ok(shadow_root)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-element-shadowroot
+    fn GetShadowRoot(&self) -> Option<DomRoot<ShadowRoot>> {
+        self.shadow_root().filter(|shadow_root| shadow_root.Mode() == ShadowRootMode::Open)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-slotable-assignedslot
+    fn GetAssignedSlot(&self) -> Option<DomRoot<HTMLSlotElement>> {
+        HTMLSlotElement::find_a_slot(self.upcast(), true)
+    }
+
     // check-tidy: no specs after this line
     fn EnterFormalActivationState(&self) -> ErrorResult {
         match self.as_maybe_activatable() {
@@ -2419,6 +2525,49 @@ impl ElementMethods for Element {
     }
 }
 
+impl AnimatableMethods for Element {
+    #[allow(unsafe_code)]
+    // https://drafts.csswg.org/web-animations/#dom-animatable-animate
+    unsafe fn Animate(&self,
+                      cx: *mut JSContext,
+                      keyframes: *mut JSObject,
+                      options: Option<UnrestrictedDoubleOrKeyframeAnimationOptions>)
+                      -> Fallible<DomRoot<Animation>> {
+        let mut id = DOMString::new();
+        let options = options.map(|options| match options {
+            UnrestrictedDoubleOrKeyframeAnimationOptions::UnrestrictedDouble(duration) => {
+                UnrestrictedDoubleOrKeyframeEffectOptions::UnrestrictedDouble(duration)
+            },
+            UnrestrictedDoubleOrKeyframeAnimationOptions::KeyframeAnimationOptions(options) => {
+                id = options.id;
+                UnrestrictedDoubleOrKeyframeEffectOptions::KeyframeEffectOptions(options.parent)
+            },
+        });
+
+        // Step 1.
+        let window = window_from_node(self);
+        let effect = KeyframeEffect::Constructor(cx, &window, Some(self), keyframes, options)?;
+
+        // Steps 2-4.
+        let timeline = document_from_node(self).timeline();
+        let animation = Animation::new(&window, Some(effect.upcast()), Some(timeline.upcast()));
+        animation.SetId(id);
+
+        // Step 5.
+        animation.Play()?;
+
+        // Step 6.
+        Ok(animation)
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animatable-getanimations
+    fn GetAnimations(&self) -> Vec<DomRoot<Animation>> {
+        document_from_node(self).get_animations().into_iter().filter(|animation| {
+            animation.target().map_or(false, |target| &*target == self)
+        }).collect()
+    }
+}
+
 impl VirtualMethods for Element {
     fn super_type(&self) -> Option<&VirtualMethods> {
         Some(self.upcast::<Node>() as &VirtualMethods)
@@ -2440,8 +2589,18 @@ impl VirtualMethods for Element {
         let doc = node.owner_doc();
         match attr.local_name() {
             &local_name!("style") => {
+                // Declarations set through CSSOM are not subject to CSP.
+                let blocked_by_csp = match mutation {
+                    AttributeMutation::Set(..) => match *attr.value() {
+                        AttrValue::Declaration(..) => false,
+                        ref value => doc.should_elements_inline_type_behavior_be_blocked(
+                            self, InlineCheckType::StyleAttribute, value),
+                    },
+                    AttributeMutation::Removed => false,
+                };
                 // Modifying the `style` attribute might change style.
                 *self.style_attribute.borrow_mut() = match mutation {
+                    AttributeMutation::Set(..) if blocked_by_csp => None,
                     AttributeMutation::Set(..) => {
                         // This is the fast path we use from
                         // CSSStyleDeclaration.
@@ -2489,7 +2648,7 @@ impl VirtualMethods for Element {
                             None
                         }
                     });
-                if node.is_in_doc() {
+                if node.is_in_doc() && !node.is_in_shadow_tree() {
                     let value = attr.value().as_atom().clone();
                     match mutation {
                         AttributeMutation::Set(old_value) => {
@@ -2509,6 +2668,13 @@ impl VirtualMethods for Element {
                     }
                 }
             },
+            &local_name!("slot") if attr.namespace() == &ns!() => {
+                // https://dom.spec.whatwg.org/#shadow-tree-slots
+                if let Some(slot) = node.assigned_slot() {
+                    slot.assign_slottables();
+                }
+                HTMLSlotElement::assign_a_slot(node);
+            },
             _ => {
                 // FIXME(emilio): This is pretty dubious, and should be done in
                 // the relevant super-classes.
@@ -2547,8 +2713,10 @@ impl VirtualMethods for Element {
         }
 
         let doc = document_from_node(self);
-        if let Some(ref value) = *self.id_attribute.borrow() {
-            doc.register_named_element(self, value.clone());
+        if !self.upcast::<Node>().is_in_shadow_tree() {
+            if let Some(ref value) = *self.id_attribute.borrow() {
+                doc.register_named_element(self, value.clone());
+            }
         }
         // This is used for layout optimization.
         doc.increment_dom_count();
@@ -2570,8 +2738,10 @@ impl VirtualMethods for Element {
         if fullscreen.r() == Some(self) {
             doc.exit_fullscreen();
         }
-        if let Some(ref value) = *self.id_attribute.borrow() {
-            doc.unregister_named_element(self, value.clone());
+        if !self.upcast::<Node>().is_in_shadow_tree() {
+            if let Some(ref value) = *self.id_attribute.borrow() {
+                doc.unregister_named_element(self, value.clone());
+            }
         }
         // This is used for layout optimization.
         doc.decrement_dom_count();
@@ -2602,6 +2772,8 @@ impl VirtualMethods for Element {
                 }
             }
         }
+
+        self.invalidate_relative_selector_anchors(mutation);
     }
 
     fn adopting_steps(&self, old_doc: &Document) {
@@ -2625,11 +2797,15 @@ impl<'a> SelectorsElement for DomRoot<Element> {
     }
 
     fn parent_node_is_shadow_root(&self) -> bool {
-        false
+        self.upcast::<Node>().GetParentNode().map_or(false, |parent| parent.is::<ShadowRoot>())
     }
 
     fn containing_shadow_host(&self) -> Option<Self> {
-        None
+        self.upcast::<Node>().containing_shadow_root().map(|shadow_root| shadow_root.Host())
+    }
+
+    fn assigned_slot(&self) -> Option<Self> {
+        self.upcast::<Node>().assigned_slot().map(DomRoot::upcast)
     }
 
     fn match_pseudo_element(
@@ -3047,9 +3223,60 @@ impl Element {
     /// <https://dom.spec.whatwg.org/#connected>
     pub fn is_connected(&self) -> bool {
         let node = self.upcast::<Node>();
-        let root = node.GetRootNode();
+        let root = node.shadow_including_root();
         root.is::<Document>()
     }
+
+    pub fn rare_data(&self) -> Ref<Option<Box<ElementRareData>>> {
+        self.rare_data.borrow()
+    }
+
+    /// Returns the rare data of this element, allocating it if needed.
+    #[allow(unrooted_must_root)]
+    pub fn ensure_rare_data(&self) -> RefMut<Box<ElementRareData>> {
+        let mut rare_data = self.rare_data.borrow_mut();
+        if rare_data.is_none() {
+            *rare_data = Some(Default::default());
+        }
+        RefMut::map(rare_data, |rare_data| rare_data.as_mut().unwrap())
+    }
+
+    /// Returns the shadow root attached to this element, if any, regardless
+    /// of its mode.
+    pub fn shadow_root(&self) -> Option<DomRoot<ShadowRoot>> {
+        self.rare_data().as_ref()?.shadow_root.get()
+    }
+
+    /// <https://dom.spec.whatwg.org/#valid-shadow-host-name>
+    fn is_valid_shadow_host_name(&self) -> bool {
+        match *self.local_name() {
+            local_name!("article") | local_name!("aside") | local_name!("blockquote") |
+            local_name!("body") | local_name!("div") | local_name!("footer") |
+            local_name!("h1") | local_name!("h2") | local_name!("h3") |
+            local_name!("h4") | local_name!("h5") | local_name!("h6") |
+            local_name!("header") | local_name!("main") | local_name!("nav") |
+            local_name!("p") | local_name!("section") | local_name!("span") => true,
+            ref name => is_valid_custom_element_name(name),
+        }
+    }
+
+    /// Adds a stylesheet owned by this element to the shadow root this
+    /// element is in, or to its document otherwise.
+    pub fn add_owned_stylesheet(&self, sheet: Arc<Stylesheet>) {
+        match self.upcast::<Node>().containing_shadow_root() {
+            Some(shadow_root) => shadow_root.add_stylesheet(self, sheet),
+            None => document_from_node(self).add_stylesheet(self, sheet),
+        }
+    }
+
+    /// Removes a stylesheet owned by this element from the shadow root this
+    /// element is in, or from its document otherwise.
+    pub fn remove_owned_stylesheet(&self, sheet: &Arc<Stylesheet>) {
+        match self.upcast::<Node>().containing_shadow_root() {
+            Some(shadow_root) => shadow_root.remove_stylesheet(self, sheet),
+            None => document_from_node(self).remove_stylesheet(self, sheet),
+        }
+    }
 }
 
 impl Element {
@@ -7,16 +7,19 @@ use dom::bindings::callback::ExceptionHandling;
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::EventBinding;
 use dom::bindings::codegen::Bindings::EventBinding::{EventConstants, EventMethods};
+use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
+use dom::bindings::codegen::Bindings::ShadowRootBinding::{ShadowRootMethods, ShadowRootMode};
 use dom::bindings::error::Fallible;
 use dom::bindings::inheritance::Castable;
 use dom::bindings::refcounted::Trusted;
 use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
-use dom::bindings::root::{DomRoot, MutNullableDom, RootedReference};
+use dom::bindings::root::{Dom, DomRoot, MutNullableDom, RootedReference};
 use dom::bindings::str::DOMString;
 use dom::document::Document;
 use dom::eventtarget::{CompiledEventListener, EventTarget, ListenerPhase};
 use dom::globalscope::GlobalScope;
 use dom::node::Node;
+use dom::shadowroot::ShadowRoot;
 use dom::virtualmethods::vtable_for;
 use dom::window::Window;
 use dom_struct::dom_struct;
@@ -38,10 +41,15 @@ pub struct Event {
     stop_immediate: Cell<bool>,
     cancelable: Cell<bool>,
     bubbles: Cell<bool>,
+    composed: Cell<bool>,
     trusted: Cell<bool>,
     dispatching: Cell<bool>,
     initialized: Cell<bool>,
     timestamp: u64,
+    /// The path of the event being dispatched, starting with its target.
+    ///
+    /// <https://dom.spec.whatwg.org/#event-path>
+    path: DomRefCell<Vec<Dom<EventTarget>>>,
 }
 
 impl Event {
@@ -57,10 +65,12 @@ impl Event {
             stop_immediate: Cell::new(false),
             cancelable: Cell::new(false),
             bubbles: Cell::new(false),
+            composed: Cell::new(false),
             trusted: Cell::new(false),
             dispatching: Cell::new(false),
             initialized: Cell::new(false),
             timestamp: time::get_time().sec as u64,
+            path: DomRefCell::new(vec![]),
         }
     }
 
@@ -84,7 +94,9 @@ impl Event {
                        init: &EventBinding::EventInit) -> Fallible<DomRoot<Event>> {
         let bubbles = EventBubbles::from(init.bubbles);
         let cancelable = EventCancelable::from(init.cancelable);
-        Ok(Event::new(global, Atom::from(type_), bubbles, cancelable))
+        let event = Event::new(global, Atom::from(type_), bubbles, cancelable);
+        event.set_composed(init.composed);
+        Ok(event)
     }
 
     pub fn init_event(&self, type_: Atom, bubbles: bool, cancelable: bool) {
@@ -113,14 +125,34 @@ impl Event {
         event_path.iter().any(|target| target.has_listeners_for(type_))
     }
 
+    // https://dom.spec.whatwg.org/#get-the-parent
+    fn get_the_parent(&self, node: &Node, target_root: &Node) -> Option<DomRoot<Node>> {
+        if let Some(slot) = node.assigned_slot() {
+            return Some(DomRoot::upcast(slot));
+        }
+        match node.downcast::<ShadowRoot>() {
+            Some(shadow_root) => {
+                if !self.composed.get() && node == target_root {
+                    None
+                } else {
+                    Some(DomRoot::upcast(shadow_root.Host()))
+                }
+            },
+            None => node.GetParentNode(),
+        }
+    }
+
     // https://dom.spec.whatwg.org/#event-path
     fn construct_event_path(&self, target: &EventTarget) -> Vec<DomRoot<EventTarget>> {
         let mut event_path = vec![];
         // The "invoke" algorithm is only used on `target` separately,
         // so we don't put it in the path.
         if let Some(target_node) = target.downcast::<Node>() {
-            for ancestor in target_node.ancestors() {
-                event_path.push(DomRoot::from_ref(ancestor.upcast::<EventTarget>()));
+            let target_root = target_node.inclusive_ancestors().last().unwrap();
+            let mut parent = self.get_the_parent(target_node, &target_root);
+            while let Some(ancestor) = parent {
+                parent = self.get_the_parent(&ancestor, &target_root);
+                event_path.push(DomRoot::upcast::<EventTarget>(ancestor));
             }
             let top_most_ancestor_or_target =
                 event_path.last().cloned().unwrap_or(DomRoot::from_ref(target));
@@ -147,7 +179,12 @@ impl Event {
         self.dispatching.set(true);
 
         // Step 2.
-        self.target.set(Some(target_override.unwrap_or(target)));
+        let original_target = target_override.unwrap_or(target);
+        self.target.set(Some(original_target));
+
+        // Targets in shadow trees need to be retargeted for the listeners
+        // outside of them, and are not exposed after dispatch.
+        let clear_targets = original_target.downcast::<Node>().map_or(false, |node| node.is_in_shadow_tree());
 
         if self.stop_propagation.get() {
             // If the event's stop propagation flag is set, we can skip everything because
@@ -162,11 +199,18 @@ impl Event {
 
         // Step 3-4.
         let path = self.construct_event_path(&target);
+        {
+            let mut composed_path = self.path.borrow_mut();
+            composed_path.push(Dom::from_ref(target));
+            composed_path.extend(path.iter().map(|target| Dom::from_ref(&**target)));
+        }
         rooted_vec!(let event_path <- path.into_iter());
         // Steps 5-9. In a separate function to short-circuit various things easily.
-        dispatch_to_listeners(self, target, event_path.r());
+        let retarget_from = if clear_targets { Some(original_target) } else { None };
+        dispatch_to_listeners(self, target, retarget_from, event_path.r());
 
         // Default action.
+        self.target.set(Some(original_target));
         if let Some(target) = self.GetTarget() {
             if let Some(node) = target.downcast::<Node>() {
                 let vtable = vtable_for(&node);
@@ -177,6 +221,11 @@ impl Event {
         // Step 10-12.
         self.clear_dispatching_flags();
 
+        // Step 13.
+        if clear_targets {
+            self.target.set(None);
+        }
+
         // Step 14.
         self.status()
     }
@@ -203,6 +252,7 @@ impl Event {
         self.stop_immediate.set(false);
         self.phase.set(EventPhase::None);
         self.current_target.set(None);
+        self.path.borrow_mut().clear();
     }
 
     #[inline]
@@ -229,6 +279,10 @@ impl Event {
         self.trusted.set(trusted);
     }
 
+    pub fn set_composed(&self, composed: bool) {
+        self.composed.set(composed);
+    }
+
     // https://html.spec.whatwg.org/multipage/#fire-a-simple-event
     pub fn fire(&self, target: &EventTarget) -> EventStatus {
         self.set_trusted(true);
@@ -257,6 +311,19 @@ impl EventMethods for Event {
         self.current_target.get()
     }
 
+    // https://dom.spec.whatwg.org/#dom-event-composedpath
+    fn ComposedPath(&self) -> Vec<DomRoot<EventTarget>> {
+        let current_target = match self.current_target.get() {
+            Some(current_target) => current_target,
+            None => return vec![],
+        };
+        self.path.borrow()
+            .iter()
+            .filter(|target| !is_hidden_from(target, &current_target))
+            .map(|target| DomRoot::from_ref(&**target))
+            .collect()
+    }
+
     // https://dom.spec.whatwg.org/#dom-event-defaultprevented
     fn DefaultPrevented(&self) -> bool {
         self.canceled.get() == EventDefault::Prevented
@@ -290,6 +357,11 @@ impl EventMethods for Event {
         self.cancelable.get()
     }
 
+    // https://dom.spec.whatwg.org/#dom-event-composed
+    fn Composed(&self) -> bool {
+        self.composed.get()
+    }
+
     // https://dom.spec.whatwg.org/#dom-event-timestamp
     fn TimeStamp(&self) -> u64 {
         self.timestamp
@@ -425,9 +497,68 @@ impl TaskOnce for SimpleEventTask {
     }
 }
 
+// https://dom.spec.whatwg.org/#retarget
+fn retarget(a: &EventTarget, b: &EventTarget) -> DomRoot<EventTarget> {
+    let mut a = DomRoot::from_ref(a);
+    loop {
+        let host = match a.downcast::<Node>() {
+            Some(node) => {
+                let root = node.inclusive_ancestors().last().unwrap();
+                let is_ancestor_of_b =
+                    b.downcast::<Node>().map_or(false, |b| root.is_shadow_including_inclusive_ancestor_of(b));
+                let host = match root.downcast::<ShadowRoot>() {
+                    Some(shadow_root) if !is_ancestor_of_b => Some(shadow_root.Host()),
+                    _ => None,
+                };
+                host
+            },
+            None => None,
+        };
+        match host {
+            Some(host) => a = DomRoot::upcast(host),
+            None => return a,
+        }
+    }
+}
+
+/// Whether `target` is in a closed shadow tree that `current_target` can't
+/// see into, and thus must not be exposed to it.
+fn is_hidden_from(target: &EventTarget, current_target: &EventTarget) -> bool {
+    let current_target = match current_target.downcast::<Node>() {
+        Some(node) => node,
+        None => return false,
+    };
+    let mut node = match target.downcast::<Node>() {
+        Some(node) => DomRoot::from_ref(node),
+        None => return false,
+    };
+    loop {
+        let host = {
+            let root = node.inclusive_ancestors().last().unwrap();
+            let host = match root.downcast::<ShadowRoot>() {
+                Some(shadow_root) => {
+                    if root.is_shadow_including_inclusive_ancestor_of(current_target) {
+                        return false;
+                    }
+                    if shadow_root.Mode() == ShadowRootMode::Closed {
+                        return true;
+                    }
+                    shadow_root.Host()
+                },
+                None => return false,
+            };
+            host
+        };
+        node = DomRoot::upcast(host);
+    }
+}
+
 // See dispatch_event.
 // https://dom.spec.whatwg.org/#concept-event-dispatch
-fn dispatch_to_listeners(event: &Event, target: &EventTarget, event_path: &[&EventTarget]) {
+fn dispatch_to_listeners(event: &Event,
+                         target: &EventTarget,
+                         retarget_from: Option<&EventTarget>,
+                         event_path: &[&EventTarget]) {
     assert!(!event.stop_propagation.get());
     assert!(!event.stop_immediate.get());
 
@@ -447,6 +578,9 @@ fn dispatch_to_listeners(event: &Event, target: &EventTarget, event_path: &[&Eve
 
     // Step 6.
     for object in event_path.iter().rev() {
+        if let Some(original_target) = retarget_from {
+            event.target.set(Some(&retarget(original_target, object)));
+        }
         invoke(window.r(), object, event, Some(ListenerPhase::Capturing));
         if event.stop_propagation.get() {
             return;
@@ -459,6 +593,9 @@ fn dispatch_to_listeners(event: &Event, target: &EventTarget, event_path: &[&Eve
     event.phase.set(EventPhase::AtTarget);
 
     // Step 8.
+    if let Some(original_target) = retarget_from {
+        event.target.set(Some(&retarget(original_target, target)));
+    }
     invoke(window.r(), target, event, None);
     if event.stop_propagation.get() {
         return;
@@ -475,6 +612,9 @@ fn dispatch_to_listeners(event: &Event, target: &EventTarget, event_path: &[&Eve
 
     // Step 9.2.
     for object in event_path {
+        if let Some(original_target) = retarget_from {
+            event.target.set(Some(&retarget(original_target, object)));
+        }
         invoke(window.r(), object, event, Some(ListenerPhase::Bubbling));
         if event.stop_propagation.get() {
             return;
@@ -543,6 +683,7 @@ impl Default for EventBinding::EventInit {
         EventBinding::EventInit {
             bubbles: false,
             cancelable: false,
+            composed: false,
         }
     }
 }
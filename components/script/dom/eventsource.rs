@@ -26,6 +26,7 @@ use js::jsval::UndefinedValue;
 use mime::{Mime, TopLevel, SubLevel};
 use net_traits::{CoreResourceMsg, FetchChannels, FetchMetadata};
 use net_traits::{FetchResponseMsg, FetchResponseListener, NetworkError};
+use net_traits::csp::Violation;
 use net_traits::request::{CacheMode, CorsSettings, CredentialsMode};
 use net_traits::request::{RequestInit, RequestMode};
 use network_listener::{NetworkListener, PreInvoke};
@@ -221,7 +222,8 @@ impl EventSourceContext {
             unsafe { self.data.to_jsval(event_source.global().get_cx(), data.handle_mut()) };
             MessageEvent::new(&*event_source.global(), type_, false, false, data.handle(),
                               DOMString::from(self.origin.clone()),
-                              event_source.last_event_id.borrow().clone())
+                              event_source.last_event_id.borrow().clone(),
+                              vec![])
         };
         // Step 7
         self.event_type.clear();
@@ -380,6 +382,10 @@ impl FetchResponseListener for EventSourceContext {
         }
         self.reestablish_the_connection();
     }
+
+    fn process_csp_violations(&mut self, violations: Vec<Violation>) {
+        self.event_source.root().global().report_csp_violations(violations, None);
+    }
 }
 
 impl PreInvoke for EventSourceContext {
@@ -472,6 +478,7 @@ impl EventSource {
             } else {
                 CredentialsMode::Include
             },
+            csp_list: global.get_csp_list(),
             ..RequestInit::default()
         };
         // Step 10
@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use dom::abortsignal::AbortAlgorithm;
 use dom::beforeunloadevent::BeforeUnloadEvent;
 use dom::bindings::callback::{CallbackContainer, ExceptionHandling, CallbackFunction};
 use dom::bindings::cell::DomRefCell;
@@ -23,7 +24,7 @@ use dom::bindings::codegen::UnionTypes::EventOrString;
 use dom::bindings::error::{Error, Fallible, report_pending_exception};
 use dom::bindings::inheritance::Castable;
 use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
-use dom::bindings::root::DomRoot;
+use dom::bindings::root::{Dom, DomRoot};
 use dom::bindings::str::DOMString;
 use dom::element::Element;
 use dom::errorevent::ErrorEvent;
@@ -612,18 +613,22 @@ impl EventTarget {
         event
     }
     // https://dom.spec.whatwg.org/#dom-eventtarget-addeventlistener
+    #[allow(unrooted_must_root)]
     pub fn add_event_listener(
         &self,
         ty: DOMString,
         listener: Option<Rc<EventListener>>,
         options: AddEventListenerOptions,
     ) {
+        if options.signal.as_ref().map_or(false, |signal| signal.aborted()) {
+            return;
+        }
         let listener = match listener {
             Some(l) => l,
             None => return,
         };
         let mut handlers = self.handlers.borrow_mut();
-        let entry = match handlers.entry(Atom::from(ty)) {
+        let entry = match handlers.entry(Atom::from(ty.clone())) {
             Occupied(entry) => entry.into_mut(),
             Vacant(entry) => entry.insert(EventListeners(vec!())),
         };
@@ -635,10 +640,21 @@ impl EventTarget {
         };
         let new_entry = EventListenerEntry {
             phase: phase,
-            listener: EventListenerType::Additive(listener)
+            listener: EventListenerType::Additive(listener.clone())
         };
-        if !entry.contains(&new_entry) {
-            entry.push(new_entry);
+        if entry.contains(&new_entry) {
+            return;
+        }
+        entry.push(new_entry);
+
+        // The listener is removed once the signal is aborted.
+        if let Some(ref signal) = options.signal {
+            signal.add_algorithm(AbortAlgorithm::RemoveEventListener(
+                Dom::from_ref(self),
+                ty,
+                listener,
+                options.parent.capture,
+            ));
         }
     }
 
@@ -719,7 +735,7 @@ impl From<AddEventListenerOptionsOrBoolean> for AddEventListenerOptions {
                 options
             },
             AddEventListenerOptionsOrBoolean::Boolean(capture) => {
-                Self { parent: EventListenerOptions { capture } }
+                Self { parent: EventListenerOptions { capture }, signal: None }
             },
         }
     }
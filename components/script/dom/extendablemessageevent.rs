@@ -7,13 +7,15 @@ use dom::bindings::codegen::Bindings::ExtendableMessageEventBinding::ExtendableM
 use dom::bindings::error::Fallible;
 use dom::bindings::inheritance::Castable;
 use dom::bindings::reflector::reflect_dom_object;
-use dom::bindings::root::DomRoot;
+use dom::bindings::root::{Dom, DomRoot};
 use dom::bindings::str::DOMString;
 use dom::bindings::trace::RootedTraceableBox;
 use dom::event::Event;
 use dom::eventtarget::EventTarget;
 use dom::extendableevent::ExtendableEvent;
 use dom::globalscope::GlobalScope;
+use dom::messageevent::frozen_ports_array;
+use dom::messageport::MessagePort;
 use dom::serviceworkerglobalscope::ServiceWorkerGlobalScope;
 use dom_struct::dom_struct;
 use js::jsapi::{Heap, JSContext};
@@ -27,18 +29,24 @@ pub struct ExtendableMessageEvent {
     data: Heap<JSVal>,
     origin: DOMString,
     lastEventId: DOMString,
+    ports: Vec<Dom<MessagePort>>,
+    /// The frozen array returned by the `ports` attribute, once it was created.
+    frozen_ports: Heap<JSVal>,
 }
 
 impl ExtendableMessageEvent {
     pub fn new(global: &GlobalScope, type_: Atom,
                bubbles: bool, cancelable: bool,
-               data: HandleValue, origin: DOMString, lastEventId: DOMString)
+               data: HandleValue, origin: DOMString, lastEventId: DOMString,
+               ports: Vec<DomRoot<MessagePort>>)
                -> DomRoot<ExtendableMessageEvent> {
         let ev = Box::new(ExtendableMessageEvent {
             event: ExtendableEvent::new_inherited(),
             data: Heap::default(),
             origin: origin,
             lastEventId: lastEventId,
+            ports: ports.iter().map(|port| Dom::from_ref(&**port)).collect(),
+            frozen_ports: Heap::default(),
         });
         let ev = reflect_dom_object(ev, global, ExtendableMessageEventBinding::Wrap);
         {
@@ -61,7 +69,8 @@ impl ExtendableMessageEvent {
                                              init.parent.parent.cancelable,
                                              init.data.handle(),
                                              init.origin.clone().unwrap(),
-                                             init.lastEventId.clone().unwrap());
+                                             init.lastEventId.clone().unwrap(),
+                                             init.ports.clone().unwrap_or_default());
         Ok(ev)
     }
 }
@@ -69,10 +78,11 @@ impl ExtendableMessageEvent {
 impl ExtendableMessageEvent {
     pub fn dispatch_jsval(target: &EventTarget,
                           scope: &GlobalScope,
-                          message: HandleValue) {
+                          message: HandleValue,
+                          ports: Vec<DomRoot<MessagePort>>) {
         let Extendablemessageevent = ExtendableMessageEvent::new(
             scope, atom!("message"), false, false, message,
-            DOMString::new(), DOMString::new());
+            DOMString::new(), DOMString::new(), ports);
         Extendablemessageevent.upcast::<Event>().fire(target);
     }
 }
@@ -94,6 +104,12 @@ impl ExtendableMessageEventMethods for ExtendableMessageEvent {
         self.lastEventId.clone()
     }
 
+    #[allow(unsafe_code)]
+    // https://w3c.github.io/ServiceWorker/#extendablemessage-event-ports-attribute
+    unsafe fn Ports(&self, cx: *mut JSContext) -> JSVal {
+        frozen_ports_array(cx, &self.ports, &self.frozen_ports)
+    }
+
     // https://dom.spec.whatwg.org/#dom-event-istrusted
     fn IsTrusted(&self) -> bool {
         self.event.IsTrusted()
@@ -10,7 +10,7 @@ use dom::bindings::inheritance::Castable;
 use dom::bindings::reflector::reflect_dom_object;
 use dom::bindings::root::{DomRoot, MutNullableDom, RootedReference};
 use dom::bindings::str::DOMString;
-use dom::event::{EventBubbles, EventCancelable};
+use dom::event::{Event, EventBubbles, EventCancelable};
 use dom::eventtarget::EventTarget;
 use dom::uievent::UIEvent;
 use dom::window::Window;
@@ -65,6 +65,7 @@ impl FocusEvent {
                                     init.parent.view.r(),
                                     init.parent.detail,
                                     init.relatedTarget.r());
+        event.upcast::<Event>().set_composed(init.parent.parent.composed);
         Ok(event)
     }
 }
@@ -0,0 +1,448 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use cssparser::{Parser, ParserInput, SourceLocation, UnicodeRange, serialize_string};
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::FontFaceBinding::{self, FontFaceDescriptors, FontFaceLoadStatus};
+use dom::bindings::codegen::Bindings::FontFaceBinding::FontFaceMethods;
+use dom::bindings::codegen::Bindings::WindowBinding::WindowBinding::WindowMethods;
+use dom::bindings::codegen::UnionTypes::StringOrBinaryData;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
+use dom::fontfaceset::FontFaceSet;
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::window::{ReflowReason, Window};
+use dom_struct::dom_struct;
+use ipc_channel::ipc;
+use ipc_channel::router::ROUTER;
+use script_layout_interface::message::{Msg, ReflowGoal};
+use servo_arc::Arc;
+use servo_atoms::Atom;
+use servo_url::ServoUrl;
+use std::cell::Cell;
+use std::rc::Rc;
+use style::context::QuirksMode;
+use style::font_face::{EffectiveSources, FontDisplay, FontStretch, FontStyle, FontWeight};
+use style::font_face::parse_font_face_block;
+use style::parser::{Parse, ParserContext};
+use style::shared_lock::Locked;
+use style::stylesheets::{CssRuleType, FontFaceRule};
+use style::values::computed::font::{FamilyName, FamilyNameSyntax};
+use style_traits::{ParseError, ParsingMode};
+use task_source::{TaskSource, TaskSourceName};
+
+/// <https://drafts.csswg.org/css-font-loading/#fontface-interface>
+#[dom_struct]
+pub struct FontFace {
+    reflector_: Reflector,
+    family: DomRefCell<DOMString>,
+    style: DomRefCell<DOMString>,
+    weight: DomRefCell<DOMString>,
+    stretch: DomRefCell<DOMString>,
+    unicode_range: DomRefCell<DOMString>,
+    variant: DomRefCell<DOMString>,
+    feature_settings: DomRefCell<DOMString>,
+    variation_settings: DomRefCell<DOMString>,
+    display: DomRefCell<DOMString>,
+    status: Cell<FontFaceLoadStatus>,
+    /// The value of the `src` descriptor this font face loads from, if it
+    /// was constructed from a string.
+    source: Option<DOMString>,
+    /// <https://drafts.csswg.org/css-font-loading/#css-connected>
+    ///
+    /// The `@font-face` rule this font face represents, if any.
+    #[ignore_malloc_size_of = "Arc"]
+    css_rule: Option<Arc<Locked<FontFaceRule>>>,
+    /// <https://drafts.csswg.org/css-font-loading/#dom-fontface-fontstatuspromise-slot>
+    #[ignore_malloc_size_of = "Rc"]
+    font_status_promise: Rc<Promise>,
+    /// The font face sets this font face belongs to.
+    sets: DomRefCell<Vec<Dom<FontFaceSet>>>,
+}
+
+impl FontFace {
+    fn new_inherited(family: DOMString,
+                     source: Option<DOMString>,
+                     css_rule: Option<Arc<Locked<FontFaceRule>>>,
+                     descriptors: &FontFaceDescriptors,
+                     font_status_promise: Rc<Promise>) -> FontFace {
+        FontFace {
+            reflector_: Reflector::new(),
+            family: DomRefCell::new(family),
+            style: DomRefCell::new(descriptors.style.clone()),
+            weight: DomRefCell::new(descriptors.weight.clone()),
+            stretch: DomRefCell::new(descriptors.stretch.clone()),
+            unicode_range: DomRefCell::new(descriptors.unicodeRange.clone()),
+            variant: DomRefCell::new(descriptors.variant.clone()),
+            feature_settings: DomRefCell::new(descriptors.featureSettings.clone()),
+            variation_settings: DomRefCell::new(descriptors.variationSettings.clone()),
+            display: DomRefCell::new(descriptors.display.clone()),
+            status: Cell::new(FontFaceLoadStatus::Unloaded),
+            source,
+            css_rule,
+            font_status_promise,
+            sets: DomRefCell::new(Vec::new()),
+        }
+    }
+
+    fn new(window: &Window,
+           family: DOMString,
+           source: Option<DOMString>,
+           css_rule: Option<Arc<Locked<FontFaceRule>>>,
+           descriptors: &FontFaceDescriptors) -> DomRoot<FontFace> {
+        let promise = Promise::new(window.upcast::<GlobalScope>());
+        reflect_dom_object(Box::new(FontFace::new_inherited(family, source, css_rule, descriptors, promise)),
+                           window,
+                           FontFaceBinding::Wrap)
+    }
+
+    /// Creates the CSS-connected font face of an `@font-face` rule. Servo
+    /// only supports the `font-family` and `src` descriptors, so the others
+    /// keep their initial values.
+    pub fn new_css_connected(window: &Window, rule: Arc<Locked<FontFaceRule>>) -> DomRoot<FontFace> {
+        let family = {
+            let document = window.Document();
+            let guard = document.style_shared_lock().read();
+            let rule = rule.read_with(&guard);
+            rule.family.as_ref().map_or(DOMString::new(), |family| DOMString::from(&*family.name))
+        };
+        FontFace::new(window, family, None, Some(rule), &FontFaceDescriptors::empty())
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-fontface
+    pub fn Constructor(window: &Window,
+                       family: DOMString,
+                       source: StringOrBinaryData,
+                       descriptors: &FontFaceDescriptors) -> Fallible<DomRoot<FontFace>> {
+        // Step 2.
+        let url = window.Document().base_url();
+        let descriptors_are_valid =
+            parse_descriptor::<FontStyle>(&url, &descriptors.style).is_ok() &&
+            parse_descriptor::<FontWeight>(&url, &descriptors.weight).is_ok() &&
+            parse_descriptor::<FontStretch>(&url, &descriptors.stretch).is_ok() &&
+            parse_unicode_range(&descriptors.unicodeRange).is_ok() &&
+            parse_descriptor::<FontDisplay>(&url, &descriptors.display).is_ok();
+
+        let font_face = match source {
+            // Step 3.
+            StringOrBinaryData::String(source) => {
+                let valid = descriptors_are_valid && parse_sources(&url, &family, &source).is_some();
+                let font_face = FontFace::new(window, family, Some(source), None, descriptors);
+                if !valid {
+                    font_face.fail_to_load(Error::Syntax);
+                }
+                font_face
+            },
+            // Step 4.
+            StringOrBinaryData::ArrayBuffer(ref data) => {
+                FontFace::new_from_data(window, family, descriptors, descriptors_are_valid, data.to_vec())
+            },
+            StringOrBinaryData::ArrayBufferView(ref data) => {
+                FontFace::new_from_data(window, family, descriptors, descriptors_are_valid, data.to_vec())
+            },
+        };
+
+        // Step 5.
+        Ok(font_face)
+    }
+
+    fn new_from_data(window: &Window,
+                     family: DOMString,
+                     descriptors: &FontFaceDescriptors,
+                     descriptors_are_valid: bool,
+                     data: Vec<u8>) -> DomRoot<FontFace> {
+        let font_face = FontFace::new(window, family, None, None, descriptors);
+        if !descriptors_are_valid {
+            font_face.fail_to_load(Error::Syntax);
+            return font_face;
+        }
+
+        // Fonts constructed from binary data start loading right away.
+        font_face.set_status(FontFaceLoadStatus::Loading);
+        let msg = Msg::AddWebFontData(font_face.family_name(), data, font_face.load_result_sender(Error::Syntax));
+        window.layout_chan().send(msg).unwrap();
+        font_face
+    }
+
+    pub fn status(&self) -> FontFaceLoadStatus {
+        self.status.get()
+    }
+
+    pub fn family(&self) -> DOMString {
+        self.family.borrow().clone()
+    }
+
+    /// Whether this font face represents `rule`.
+    pub fn is_connected_to(&self, rule: &Arc<Locked<FontFaceRule>>) -> bool {
+        self.css_rule.as_ref().map_or(false, |css_rule| Arc::ptr_eq(css_rule, rule))
+    }
+
+    pub fn is_css_connected(&self) -> bool {
+        self.css_rule.is_some()
+    }
+
+    /// Returns the sources this font face loads from.
+    fn sources(&self, window: &Window) -> Option<EffectiveSources> {
+        let document = window.Document();
+        if let Some(ref rule) = self.css_rule {
+            let guard = document.style_shared_lock().read();
+            return rule.read_with(&guard).font_face().map(|font_face| font_face.effective_sources());
+        }
+        let source = self.source.as_ref()?;
+        parse_sources(&document.base_url(), &self.family(), source)
+    }
+
+    fn family_name(&self) -> FamilyName {
+        FamilyName {
+            name: Atom::from(&**self.family.borrow()),
+            syntax: FamilyNameSyntax::Quoted,
+        }
+    }
+
+    pub fn add_to_set(&self, set: &FontFaceSet) {
+        self.sets.borrow_mut().push(Dom::from_ref(set));
+    }
+
+    pub fn remove_from_set(&self, set: &FontFaceSet) {
+        self.sets.borrow_mut().retain(|s| &**s != set);
+    }
+
+    fn set_status(&self, status: FontFaceLoadStatus) {
+        self.status.set(status);
+        let sets: Vec<DomRoot<FontFaceSet>> =
+            self.sets.borrow().iter().map(|set| DomRoot::from_ref(&**set)).collect();
+        for set in sets {
+            set.font_face_status_changed(self);
+        }
+    }
+
+    fn fail_to_load(&self, error: Error) {
+        self.font_status_promise.reject_error(error);
+        self.set_status(FontFaceLoadStatus::Error);
+    }
+
+    /// Returns a sender for layout to report whether this font face could be
+    /// loaded. A failed load rejects the status promise with `error`.
+    fn load_result_sender(&self, error: Error) -> ipc::IpcSender<bool> {
+        let (sender, receiver) = ipc::channel().unwrap();
+        let global = self.global();
+        let window = global.as_window();
+        let task_source = window.dom_manipulation_task_source();
+        let canceller = window.task_canceller(TaskSourceName::DOMManipulation);
+        let this = Trusted::new(self);
+        ROUTER.add_route(receiver.to_opaque(), Box::new(move |message| {
+            let this = this.clone();
+            let error = error.clone();
+            let loaded = message.to().unwrap();
+            let _ = task_source.queue_with_canceller(
+                task!(font_face_load_finished: move || {
+                    this.root().finish_load(loaded, error);
+                }),
+                &canceller,
+            );
+        }));
+        sender
+    }
+
+    fn finish_load(&self, loaded: bool, error: Error) {
+        if !loaded {
+            return self.fail_to_load(error);
+        }
+        self.font_status_promise.resolve_native(&DomRoot::from_ref(self));
+        self.set_status(FontFaceLoadStatus::Loaded);
+
+        // Text may now be rendered with this font.
+        let global = self.global();
+        let window = global.as_window();
+        window.Document().dirty_all_nodes();
+        window.reflow(ReflowGoal::Full, ReflowReason::WebFontLoaded);
+    }
+}
+
+impl FontFaceMethods for FontFace {
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-family
+    fn Family(&self) -> DOMString {
+        self.family()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-family
+    fn SetFamily(&self, value: DOMString) {
+        *self.family.borrow_mut() = value;
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-style
+    fn Style(&self) -> DOMString {
+        self.style.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-style
+    fn SetStyle(&self, value: DOMString) -> ErrorResult {
+        parse_descriptor::<FontStyle>(&self.global().api_base_url(), &value)?;
+        *self.style.borrow_mut() = value;
+        Ok(())
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-weight
+    fn Weight(&self) -> DOMString {
+        self.weight.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-weight
+    fn SetWeight(&self, value: DOMString) -> ErrorResult {
+        parse_descriptor::<FontWeight>(&self.global().api_base_url(), &value)?;
+        *self.weight.borrow_mut() = value;
+        Ok(())
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-stretch
+    fn Stretch(&self) -> DOMString {
+        self.stretch.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-stretch
+    fn SetStretch(&self, value: DOMString) -> ErrorResult {
+        parse_descriptor::<FontStretch>(&self.global().api_base_url(), &value)?;
+        *self.stretch.borrow_mut() = value;
+        Ok(())
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-unicoderange
+    fn UnicodeRange(&self) -> DOMString {
+        self.unicode_range.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-unicoderange
+    fn SetUnicodeRange(&self, value: DOMString) -> ErrorResult {
+        parse_unicode_range(&value)?;
+        *self.unicode_range.borrow_mut() = value;
+        Ok(())
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-variant
+    fn Variant(&self) -> DOMString {
+        self.variant.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-variant
+    fn SetVariant(&self, value: DOMString) {
+        *self.variant.borrow_mut() = value;
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-featuresettings
+    fn FeatureSettings(&self) -> DOMString {
+        self.feature_settings.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-featuresettings
+    fn SetFeatureSettings(&self, value: DOMString) {
+        *self.feature_settings.borrow_mut() = value;
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-variationsettings
+    fn VariationSettings(&self) -> DOMString {
+        self.variation_settings.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-variationsettings
+    fn SetVariationSettings(&self, value: DOMString) {
+        *self.variation_settings.borrow_mut() = value;
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-display
+    fn Display(&self) -> DOMString {
+        self.display.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-display
+    fn SetDisplay(&self, value: DOMString) -> ErrorResult {
+        parse_descriptor::<FontDisplay>(&self.global().api_base_url(), &value)?;
+        *self.display.borrow_mut() = value;
+        Ok(())
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-status
+    fn Status(&self) -> FontFaceLoadStatus {
+        self.status.get()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-load
+    fn Load(&self) -> Rc<Promise> {
+        // Step 2. Font faces constructed from binary data are never unloaded.
+        if self.status.get() != FontFaceLoadStatus::Unloaded {
+            return self.font_status_promise.clone();
+        }
+
+        // Step 3.
+        self.set_status(FontFaceLoadStatus::Loading);
+
+        // Step 4.
+        let global = self.global();
+        let window = global.as_window();
+        match self.sources(window) {
+            Some(sources) => {
+                let sender = self.load_result_sender(Error::Network);
+                window.layout_chan().send(Msg::AddWebFont(self.family_name(), sources, sender)).unwrap();
+            },
+            None => self.fail_to_load(Error::Syntax),
+        }
+
+        // Step 5.
+        self.font_status_promise.clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-loaded
+    fn Loaded(&self) -> Rc<Promise> {
+        self.font_status_promise.clone()
+    }
+}
+
+/// Parses `value` entirely as the value of an `@font-face` descriptor.
+fn parse_descriptor<T: Parse>(url: &ServoUrl, value: &str) -> ErrorResult {
+    let context = ParserContext::new_for_cssom(
+        url,
+        Some(CssRuleType::FontFace),
+        ParsingMode::DEFAULT,
+        QuirksMode::NoQuirks,
+        None,
+    );
+    let mut input = ParserInput::new(value);
+    let mut parser = Parser::new(&mut input);
+    parser.parse_entirely(|input| T::parse(&context, input)).map(|_| ()).map_err(|_| Error::Syntax)
+}
+
+/// Parses `value` entirely as the value of the `unicode-range` descriptor.
+fn parse_unicode_range(value: &str) -> ErrorResult {
+    let mut input = ParserInput::new(value);
+    let mut parser = Parser::new(&mut input);
+    let result: Result<_, ParseError> = parser.parse_entirely(|input| {
+        input.parse_comma_separated(|input| UnicodeRange::parse(input).map_err(Into::into))
+    });
+    result.map(|_| ()).map_err(|_| Error::Syntax)
+}
+
+/// Parses `source` as the value of the `src` descriptor of an `@font-face`
+/// rule for `family`, returning the sources the font cache may load from.
+fn parse_sources(url: &ServoUrl, family: &str, source: &str) -> Option<EffectiveSources> {
+    let mut block = String::from("font-family: ");
+    serialize_string(family, &mut block).unwrap();
+    block.push_str("; src: ");
+    block.push_str(source);
+
+    let context = ParserContext::new_for_cssom(
+        url,
+        Some(CssRuleType::FontFace),
+        ParsingMode::DEFAULT,
+        QuirksMode::NoQuirks,
+        None,
+    );
+    let mut input = ParserInput::new(&block);
+    let mut parser = Parser::new(&mut input);
+    let rule = parse_font_face_block(&context, &mut parser, SourceLocation { line: 0, column: 1 });
+    rule.font_face().map(|font_face| font_face.effective_sources())
+}
@@ -0,0 +1,362 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_traits::canvas::{CanvasFont, CanvasFontFamily};
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
+use dom::bindings::codegen::Bindings::FontFaceBinding::{FontFaceLoadStatus, FontFaceMethods};
+use dom::bindings::codegen::Bindings::FontFaceSetBinding::{self, FontFaceSetLoadStatus, FontFaceSetMethods};
+use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
+use dom::event::Event;
+use dom::eventtarget::EventTarget;
+use dom::fontface::FontFace;
+use dom::fontfacesetloadevent::FontFaceSetLoadEvent;
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use ipc_channel::ipc;
+use script_layout_interface::message::Msg;
+use servo_atoms::Atom;
+use std::rc::Rc;
+use task_source::{TaskSource, TaskSourceName};
+
+/// A promise returned by `FontFaceSet.load()`, settled once all the font
+/// faces it is waiting for are done loading.
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+struct PendingFontLoad {
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+    font_faces: Vec<Dom<FontFace>>,
+}
+
+/// <https://drafts.csswg.org/css-font-loading/#FontFaceSet-interface>
+#[dom_struct]
+pub struct FontFaceSet {
+    eventtarget: EventTarget,
+    /// The CSS-connected font faces of the `@font-face` rules of the
+    /// document, which come first in the set, in document order.
+    css_connected_font_faces: DomRefCell<Vec<Dom<FontFace>>>,
+    /// The font faces added to this set, in insertion order.
+    font_faces: DomRefCell<Vec<Dom<FontFace>>>,
+    /// <https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-loadingfonts-slot>
+    loading_fonts: DomRefCell<Vec<Dom<FontFace>>>,
+    /// <https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-loadedfonts-slot>
+    loaded_fonts: DomRefCell<Vec<Dom<FontFace>>>,
+    /// <https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-failedfonts-slot>
+    failed_fonts: DomRefCell<Vec<Dom<FontFace>>>,
+    /// <https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-readypromise-slot>
+    #[ignore_malloc_size_of = "Rc"]
+    ready_promise: DomRefCell<Rc<Promise>>,
+    pending_loads: DomRefCell<Vec<PendingFontLoad>>,
+}
+
+impl FontFaceSet {
+    fn new_inherited(ready_promise: Rc<Promise>) -> FontFaceSet {
+        FontFaceSet {
+            eventtarget: EventTarget::new_inherited(),
+            css_connected_font_faces: DomRefCell::new(Vec::new()),
+            font_faces: DomRefCell::new(Vec::new()),
+            loading_fonts: DomRefCell::new(Vec::new()),
+            loaded_fonts: DomRefCell::new(Vec::new()),
+            failed_fonts: DomRefCell::new(Vec::new()),
+            ready_promise: DomRefCell::new(ready_promise),
+            pending_loads: DomRefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn new(window: &Window) -> DomRoot<FontFaceSet> {
+        let promise = Promise::new(window.upcast::<GlobalScope>());
+        let font_face_set = reflect_dom_object(Box::new(FontFaceSet::new_inherited(promise)),
+                                               window,
+                                               FontFaceSetBinding::Wrap);
+        font_face_set.fulfill_ready_promise_if_needed();
+        font_face_set
+    }
+
+    /// Called whenever the status of one of the font faces of this set
+    /// changes.
+    pub fn font_face_status_changed(&self, font_face: &FontFace) {
+        match font_face.status() {
+            FontFaceLoadStatus::Loading => {
+                let was_loading = !self.loading_fonts.borrow().is_empty();
+                self.loading_fonts.borrow_mut().push(Dom::from_ref(font_face));
+                if !was_loading {
+                    self.switch_to_loading();
+                }
+            },
+            FontFaceLoadStatus::Loaded => {
+                self.loading_fonts.borrow_mut().retain(|f| &**f != font_face);
+                self.loaded_fonts.borrow_mut().push(Dom::from_ref(font_face));
+                self.settle_pending_loads();
+                self.fulfill_ready_promise_if_needed();
+            },
+            FontFaceLoadStatus::Error => {
+                self.loading_fonts.borrow_mut().retain(|f| &**f != font_face);
+                self.failed_fonts.borrow_mut().push(Dom::from_ref(font_face));
+                self.settle_pending_loads();
+                self.fulfill_ready_promise_if_needed();
+            },
+            FontFaceLoadStatus::Unloaded => {},
+        }
+    }
+
+    /// <https://drafts.csswg.org/css-font-loading/#switch-the-fontfaceset-to-loading>
+    fn switch_to_loading(&self) {
+        if self.ready_promise.borrow().is_fulfilled() {
+            *self.ready_promise.borrow_mut() = Promise::new(&self.global());
+        }
+        self.queue_font_load_event(atom!("loading"), vec![]);
+    }
+
+    /// Whether layout still has web fonts from `@font-face` rules to load.
+    fn is_pending_on_the_environment(&self) -> bool {
+        let (sender, receiver) = ipc::channel().unwrap();
+        self.global().as_window().layout_chan().send(Msg::GetWebFontLoadState(sender)).unwrap();
+        receiver.recv().unwrap_or(false)
+    }
+
+    /// <https://drafts.csswg.org/css-font-loading/#switch-the-fontfaceset-to-loaded>
+    ///
+    /// This is also called whenever layout finishes loading a web font, as
+    /// the ready promise waits for those too.
+    pub fn fulfill_ready_promise_if_needed(&self) {
+        if self.ready_promise.borrow().is_fulfilled() ||
+           !self.loading_fonts.borrow().is_empty() ||
+           self.is_pending_on_the_environment() {
+            return;
+        }
+
+        let loaded_fonts = take_fonts(&self.loaded_fonts);
+        let failed_fonts = take_fonts(&self.failed_fonts);
+        let has_failed_fonts = !failed_fonts.is_empty();
+        if !loaded_fonts.is_empty() || has_failed_fonts {
+            self.queue_font_load_event(atom!("loadingdone"), loaded_fonts);
+        }
+        if has_failed_fonts {
+            self.queue_font_load_event(atom!("loadingerror"), failed_fonts);
+        }
+
+        self.ready_promise.borrow().resolve_native(&DomRoot::from_ref(self));
+    }
+
+    /// <https://drafts.csswg.org/css-font-loading/#fire-a-font-load-event>
+    fn queue_font_load_event(&self, name: Atom, font_faces: Vec<DomRoot<FontFace>>) {
+        let global = self.global();
+        let window = global.as_window();
+        let event = FontFaceSetLoadEvent::new(window, name, false, false, font_faces);
+        let event = Trusted::new(&*event);
+        let target = Trusted::new(self.upcast::<EventTarget>());
+        let _ = window.dom_manipulation_task_source().queue_with_canceller(
+            task!(fire_font_load_event: move || {
+                event.root().upcast::<Event>().fire(&target.root());
+            }),
+            &window.task_canceller(TaskSourceName::DOMManipulation),
+        );
+    }
+
+    /// Resolves or rejects the promises returned by `load()` whose font
+    /// faces are done loading.
+    fn settle_pending_loads(&self) {
+        let mut pending_loads = self.pending_loads.borrow_mut();
+        pending_loads.retain(|load| {
+            let statuses = load.font_faces.iter().map(|f| f.status());
+            if statuses.clone().any(|status| status == FontFaceLoadStatus::Error) {
+                load.promise.reject_error(Error::Network);
+                return false;
+            }
+            if statuses.clone().all(|status| status == FontFaceLoadStatus::Loaded) {
+                let font_faces: Vec<DomRoot<FontFace>> =
+                    load.font_faces.iter().map(|f| DomRoot::from_ref(&**f)).collect();
+                load.promise.resolve_native(&font_faces);
+                return false;
+            }
+            true
+        });
+    }
+
+    /// Brings the CSS-connected font faces in line with the `@font-face`
+    /// rules of the document, keeping the font faces of the rules that are
+    /// still there.
+    fn update_css_connected_font_faces(&self) {
+        let global = self.global();
+        let window = global.as_window();
+        let rules = window.Document().font_face_rules();
+        let old_font_faces = take_fonts(&self.css_connected_font_faces);
+        let new_font_faces: Vec<DomRoot<FontFace>> = rules.into_iter().map(|rule| {
+            match old_font_faces.iter().find(|font_face| font_face.is_connected_to(&rule)) {
+                Some(font_face) => font_face.clone(),
+                None => {
+                    let font_face = FontFace::new_css_connected(window, rule);
+                    font_face.add_to_set(self);
+                    font_face
+                },
+            }
+        }).collect();
+        let mut removed_loading_font = false;
+        for font_face in &old_font_faces {
+            if !new_font_faces.contains(font_face) {
+                font_face.remove_from_set(self);
+                removed_loading_font |= font_face.status() == FontFaceLoadStatus::Loading;
+                self.loading_fonts.borrow_mut().retain(|f| &**f != &**font_face);
+            }
+        }
+        *self.css_connected_font_faces.borrow_mut() =
+            new_font_faces.iter().map(|font_face| Dom::from_ref(&**font_face)).collect();
+        if removed_loading_font {
+            self.fulfill_ready_promise_if_needed();
+        }
+    }
+
+    /// The font faces of this set, in order.
+    fn all_font_faces(&self) -> Vec<DomRoot<FontFace>> {
+        self.update_css_connected_font_faces();
+        let css_connected_font_faces = self.css_connected_font_faces.borrow();
+        let font_faces = self.font_faces.borrow();
+        css_connected_font_faces.iter()
+            .chain(font_faces.iter())
+            .map(|font_face| DomRoot::from_ref(&**font_face))
+            .collect()
+    }
+
+    /// <https://drafts.csswg.org/css-font-loading/#find-the-matching-font-faces>
+    fn find_matching_font_faces(&self, font: &str) -> Fallible<Vec<DomRoot<FontFace>>> {
+        // Steps 1-2.
+        let font = CanvasFont::parse(font, 16.).map_err(|_| Error::Syntax)?;
+
+        // Steps 3-4.
+        let matching_font_faces = self.all_font_faces().into_iter().filter(|font_face| {
+            let family = font_face.family();
+            font.families.iter().any(|f| match *f {
+                CanvasFontFamily::Specific(ref name) => name.eq_ignore_ascii_case(&family),
+                CanvasFontFamily::Generic(_) => false,
+            })
+        }).collect();
+        Ok(matching_font_faces)
+    }
+}
+
+impl FontFaceSetMethods for FontFaceSet {
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-size
+    fn Size(&self) -> u32 {
+        self.all_font_faces().len() as u32
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-add
+    fn Add(&self, font: &FontFace) -> DomRoot<FontFaceSet> {
+        if !self.Has(font) {
+            self.font_faces.borrow_mut().push(Dom::from_ref(font));
+            font.add_to_set(self);
+            if font.status() == FontFaceLoadStatus::Loading {
+                self.font_face_status_changed(font);
+            }
+        }
+        DomRoot::from_ref(self)
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-delete
+    fn Delete(&self, font: &FontFace) -> bool {
+        // CSS-connected font faces can only be removed by removing their
+        // @font-face rule.
+        if font.is_css_connected() || !self.Has(font) {
+            return false;
+        }
+        self.font_faces.borrow_mut().retain(|f| &**f != font);
+        font.remove_from_set(self);
+        self.loading_fonts.borrow_mut().retain(|f| &**f != font);
+        self.fulfill_ready_promise_if_needed();
+        true
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-clear
+    fn Clear(&self) {
+        // CSS-connected font faces stay in the set.
+        for font in take_fonts(&self.font_faces) {
+            font.remove_from_set(self);
+            self.loading_fonts.borrow_mut().retain(|f| &**f != &*font);
+        }
+        self.fulfill_ready_promise_if_needed();
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-has
+    fn Has(&self, font: &FontFace) -> bool {
+        self.all_font_faces().iter().any(|f| &**f == font)
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-onloading
+    event_handler!(loading, GetOnloading, SetOnloading);
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-onloadingdone
+    event_handler!(loadingdone, GetOnloadingdone, SetOnloadingdone);
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-onloadingerror
+    event_handler!(loadingerror, GetOnloadingerror, SetOnloadingerror);
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-load
+    #[allow(unrooted_must_root)]
+    fn Load(&self, font: DOMString, _text: DOMString) -> Rc<Promise> {
+        // Step 1.
+        let promise = Promise::new(&self.global());
+
+        // Steps 2-3.
+        let font_faces = match self.find_matching_font_faces(&font) {
+            Ok(font_faces) => font_faces,
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+
+        // Steps 4-5.
+        for font_face in &font_faces {
+            font_face.Load();
+        }
+        self.pending_loads.borrow_mut().push(PendingFontLoad {
+            promise: promise.clone(),
+            font_faces: font_faces.iter().map(|f| Dom::from_ref(&**f)).collect(),
+        });
+        self.settle_pending_loads();
+
+        // Step 6.
+        promise
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-check
+    fn Check(&self, font: DOMString, _text: DOMString) -> Fallible<bool> {
+        let font_faces = self.find_matching_font_faces(&font)?;
+        Ok(font_faces.iter().all(|font_face| font_face.status() == FontFaceLoadStatus::Loaded))
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-ready
+    fn Ready(&self) -> Rc<Promise> {
+        // Web fonts from stylesheets may have started loading since the
+        // ready promise was last fulfilled.
+        if self.ready_promise.borrow().is_fulfilled() && self.is_pending_on_the_environment() {
+            self.switch_to_loading();
+        }
+        self.ready_promise.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-status
+    fn Status(&self) -> FontFaceSetLoadStatus {
+        if self.ready_promise.borrow().is_fulfilled() {
+            FontFaceSetLoadStatus::Loaded
+        } else {
+            FontFaceSetLoadStatus::Loading
+        }
+    }
+}
+
+fn take_fonts(fonts: &DomRefCell<Vec<Dom<FontFace>>>) -> Vec<DomRoot<FontFace>> {
+    let taken = fonts.borrow().iter().map(|f| DomRoot::from_ref(&**f)).collect();
+    fonts.borrow_mut().clear();
+    taken
+}
@@ -2,31 +2,38 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use canvas_traits::webgl::WebGLChan;
 use devtools_traits::{ScriptToDevtoolsControlMsg, WorkerId};
 use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::BroadcastChannelBinding::BroadcastChannelMethods;
 use dom::bindings::codegen::Bindings::EventSourceBinding::EventSourceBinding::EventSourceMethods;
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use dom::bindings::codegen::Bindings::WorkerGlobalScopeBinding::WorkerGlobalScopeMethods;
 use dom::bindings::conversions::root_from_object;
 use dom::bindings::error::{ErrorInfo, report_pending_exception};
 use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
 use dom::bindings::reflector::DomObject;
-use dom::bindings::root::{DomRoot, MutNullableDom};
+use dom::bindings::root::{Dom, DomRoot, MutNullableDom};
 use dom::bindings::settings_stack::{AutoEntryScript, entry_global, incumbent_global};
 use dom::bindings::str::DOMString;
 use dom::bindings::weakref::DOMTracker;
+use dom::broadcastchannel::BroadcastChannel;
 use dom::crypto::Crypto;
 use dom::dedicatedworkerglobalscope::DedicatedWorkerGlobalScope;
+use dom::element::Element;
 use dom::errorevent::ErrorEvent;
 use dom::event::{Event, EventBubbles, EventCancelable, EventStatus};
 use dom::eventsource::EventSource;
 use dom::eventtarget::EventTarget;
+use dom::messageport::MessagePort;
 use dom::performance::Performance;
 use dom::window::Window;
 use dom::workerglobalscope::WorkerGlobalScope;
 use dom::workletglobalscope::WorkletGlobalScope;
 use dom_struct::dom_struct;
-use ipc_channel::ipc::IpcSender;
+use ipc_channel::ipc::{self, IpcSender};
+use ipc_channel::router::ROUTER;
 use js::{JSCLASS_IS_DOMJSCLASS, JSCLASS_IS_GLOBAL};
 use js::glue::{IsWrapper, UnwrapObject};
 use js::jsapi::{CurrentGlobalOrNull, GetGlobalForObjectCrossCompartment};
@@ -39,13 +46,15 @@ use js::rust::{HandleValue, MutableHandleValue};
 use js::rust::wrappers::Evaluate2;
 use libc;
 use microtask::{Microtask, MicrotaskQueue};
-use msg::constellation_msg::PipelineId;
+use msg::constellation_msg::{MessagePortId, MessageRouterId, PipelineId, PipelineNamespace};
 use net_traits::{CoreResourceThread, ResourceThreads, IpcSend};
+use net_traits::csp::{CheckResult, CspList, Violation};
 use profile_traits::{mem, time};
 use script_runtime::{CommonScriptMsg, ScriptChan, ScriptPort};
 use script_thread::{MainThreadScriptChan, ScriptThread};
-use script_traits::{MsDuration, ScriptToConstellationChan, TimerEvent};
+use script_traits::{BroadcastMsg, MessageRouterMsg, MsDuration, ScriptMsg, ScriptToConstellationChan, TimerEvent};
 use script_traits::{TimerEventId, TimerSchedulerMsg, TimerSource};
+use security_manager;
 use servo_url::{MutableOrigin, ServoUrl};
 use std::cell::Cell;
 use std::collections::HashMap;
@@ -55,10 +64,11 @@ use std::rc::Rc;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use task::TaskCanceller;
-use task_source::TaskSourceName;
+use task_source::{TaskSource, TaskSourceName};
 use task_source::file_reading::FileReadingTaskSource;
 use task_source::networking::NetworkingTaskSource;
 use task_source::performance_timeline::PerformanceTimelineTaskSource;
+use task_source::port_message::PortMessageQueue;
 use task_source::remote_event::RemoteEventTaskSource;
 use time::{Timespec, get_time};
 use timers::{IsInterval, OneshotTimerCallback, OneshotTimerHandle};
@@ -137,6 +147,16 @@ pub struct GlobalScope {
 
     /// Vector storing references of all eventsources.
     event_source_tracker: DOMTracker<EventSource>,
+
+    /// The id of the router through which the constellation delivers messages to
+    /// the message ports and broadcast channels of this global, once it has one.
+    message_router_id: Cell<Option<MessageRouterId>>,
+
+    /// The message ports owned by this global, by id.
+    message_ports: DomRefCell<HashMap<MessagePortId, Dom<MessagePort>>>,
+
+    /// The broadcast channels of this global that were not closed, by name.
+    broadcast_channels: DomRefCell<HashMap<DOMString, Vec<Dom<BroadcastChannel>>>>,
 }
 
 impl GlobalScope {
@@ -171,6 +191,9 @@ impl GlobalScope {
             microtask_queue,
             list_auto_close_worker: Default::default(),
             event_source_tracker: DOMTracker::new(),
+            message_router_id: Cell::new(None),
+            message_ports: DomRefCell::new(HashMap::new()),
+            broadcast_channels: DomRefCell::new(HashMap::new()),
         }
     }
 
@@ -182,6 +205,178 @@ impl GlobalScope {
         self.event_source_tracker.track(event_source);
     }
 
+    /// Creates the id of a new message port.
+    pub fn new_message_port_id(&self) -> MessagePortId {
+        self.ensure_pipeline_namespace();
+        MessagePortId::new()
+    }
+
+    /// Worker threads only get a namespace to create ids in once they need one.
+    fn ensure_pipeline_namespace(&self) {
+        if PipelineNamespace::is_installed() {
+            return;
+        }
+        let (sender, receiver) = ipc::channel().expect("ipc channel failure");
+        let _ = self.script_to_constellation_chan().send(ScriptMsg::NewPipelineNamespace(sender));
+        PipelineNamespace::install(receiver.recv().expect("Failed to get a pipeline namespace"));
+    }
+
+    /// Returns the id of the message router of this global, creating the
+    /// router if this global did not have one yet.
+    fn message_router_id(&self) -> MessageRouterId {
+        if let Some(router_id) = self.message_router_id.get() {
+            return router_id;
+        }
+        self.ensure_pipeline_namespace();
+        let router_id = MessageRouterId::new();
+        let (sender, receiver) = ipc::channel().expect("ipc channel failure");
+        let context = Trusted::new(self);
+        let task_source = self.port_message_queue();
+        let canceller = self.task_canceller(TaskSourceName::PortMessage);
+        ROUTER.add_route(receiver.to_opaque(), Box::new(move |message| {
+            let msg: MessageRouterMsg = match message.to() {
+                Ok(msg) => msg,
+                Err(_) => return,
+            };
+            let context = context.clone();
+            // FIXME(nox): Why are errors silenced here?
+            let _ = task_source.queue_with_canceller(
+                task!(route_message: move || {
+                    context.root().route_message(msg);
+                }),
+                &canceller,
+            );
+        }));
+        let _ = self.script_to_constellation_chan().send(ScriptMsg::NewMessageRouter(router_id, sender));
+        self.message_router_id.set(Some(router_id));
+        router_id
+    }
+
+    /// Removes the message router of this global, which closes its message ports
+    /// and broadcast channels.
+    pub fn remove_message_router(&self) {
+        if let Some(router_id) = self.message_router_id.take() {
+            let _ = self.script_to_constellation_chan().send(ScriptMsg::RemoveMessageRouter(router_id));
+        }
+        self.message_ports.borrow_mut().clear();
+        self.broadcast_channels.borrow_mut().clear();
+    }
+
+    /// Handles a message the constellation routed to this global.
+    fn route_message(&self, msg: MessageRouterMsg) {
+        match msg {
+            MessageRouterMsg::PortMessage(port_id, data) => {
+                let port = self.message_ports.borrow().get(&port_id).map(|port| DomRoot::from_ref(&**port));
+                match port {
+                    Some(port) => port.handle_incoming(data),
+                    None => {
+                        // The port was transferred away before the message got here.
+                        let msg = ScriptMsg::ReturnPortMessage(port_id, data);
+                        let _ = self.script_to_constellation_chan().send(msg);
+                    },
+                }
+            },
+            MessageRouterMsg::Broadcast(msg) => {
+                let name = DOMString::from(msg.channel_name);
+                let channels: Vec<DomRoot<BroadcastChannel>> = match self.broadcast_channels.borrow().get(&name) {
+                    Some(channels) => channels.iter().map(|channel| DomRoot::from_ref(&**channel)).collect(),
+                    None => return,
+                };
+                for channel in channels {
+                    channel.dispatch_message(msg.data.clone());
+                }
+            },
+        }
+    }
+
+    /// Starts routing the messages of a port owned by this global. A port
+    /// that was transferred here is claimed from the global it comes from.
+    pub fn track_message_port(&self, port: &MessagePort, transferred: bool) {
+        let router_id = self.message_router_id();
+        let port_id = port.message_port_id();
+        self.message_ports.borrow_mut().insert(port_id, Dom::from_ref(port));
+        let msg = if transferred {
+            ScriptMsg::ClaimMessagePort(router_id, port_id)
+        } else {
+            ScriptMsg::NewMessagePort(router_id, port_id)
+        };
+        let _ = self.script_to_constellation_chan().send(msg);
+    }
+
+    /// Stops routing the messages of a port that was transferred away or closed.
+    pub fn untrack_message_port(&self, port_id: MessagePortId) {
+        self.message_ports.borrow_mut().remove(&port_id);
+    }
+
+    /// Starts listening to the messages broadcast to the channel of `channel`.
+    pub fn track_broadcast_channel(&self, channel: &BroadcastChannel) {
+        let router_id = self.message_router_id();
+        let name = channel.Name();
+        let mut channels = self.broadcast_channels.borrow_mut();
+        let listeners = channels.entry(name.clone()).or_insert_with(Vec::new);
+        if listeners.is_empty() {
+            let origin = self.origin().immutable().clone();
+            let msg = ScriptMsg::NewBroadcastChannel(router_id, origin, String::from(name));
+            let _ = self.script_to_constellation_chan().send(msg);
+        }
+        listeners.push(Dom::from_ref(channel));
+    }
+
+    /// Stops listening to the messages broadcast to `channel`.
+    pub fn untrack_broadcast_channel(&self, channel: &BroadcastChannel) {
+        let name = channel.Name();
+        let mut channels = self.broadcast_channels.borrow_mut();
+        let now_empty = match channels.get_mut(&name) {
+            Some(listeners) => {
+                listeners.retain(|listener| *listener != channel);
+                listeners.is_empty()
+            },
+            None => return,
+        };
+        if now_empty {
+            channels.remove(&name);
+            if let Some(router_id) = self.message_router_id.get() {
+                let origin = self.origin().immutable().clone();
+                let msg = ScriptMsg::RemoveBroadcastChannel(router_id, origin, String::from(name));
+                let _ = self.script_to_constellation_chan().send(msg);
+            }
+        }
+    }
+
+    /// Broadcasts a serialized message to the other channels with the name of
+    /// `source`, in this global and in the other same-origin globals.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#dom-broadcastchannel-postmessage>
+    pub fn broadcast_message(&self, source: &BroadcastChannel, data: Vec<u8>) {
+        let name = source.Name();
+        let destinations: Vec<Trusted<BroadcastChannel>> = match self.broadcast_channels.borrow().get(&name) {
+            Some(channels) => {
+                channels.iter()
+                        .filter(|channel| **channel != source)
+                        .map(|channel| Trusted::new(&**channel))
+                        .collect()
+            },
+            None => vec![],
+        };
+        let task_source = self.port_message_queue();
+        for destination in destinations {
+            let data = data.clone();
+            // FIXME(nox): Why are errors silenced here?
+            let _ = task_source.queue(
+                task!(broadcast_message: move || {
+                    destination.root().dispatch_message(data);
+                }),
+                self,
+            );
+        }
+        let msg = BroadcastMsg {
+            origin: self.origin().immutable().clone(),
+            channel_name: String::from(name),
+            data: data,
+        };
+        let _ = self.script_to_constellation_chan().send(ScriptMsg::Broadcast(self.message_router_id(), msg));
+    }
+
     pub fn close_event_sources(&self) -> bool {
         let mut canceled_any_fetch = false;
         self.event_source_tracker.for_each(|event_source: DomRoot<EventSource>| {
@@ -306,6 +501,17 @@ impl GlobalScope {
         &self.scheduler_chan
     }
 
+    /// The channel to the WebGL thread, if WebGL is enabled and this global can use it.
+    pub fn webgl_chan(&self) -> Option<WebGLChan> {
+        if let Some(window) = self.downcast::<Window>() {
+            return window.webgl_chan();
+        }
+        if let Some(worker) = self.downcast::<WorkerGlobalScope>() {
+            return worker.webgl_chan();
+        }
+        None
+    }
+
     /// Get the `PipelineId` for this global scope.
     pub fn pipeline_id(&self) -> PipelineId {
         self.pipeline_id
@@ -349,6 +555,45 @@ impl GlobalScope {
         unreachable!();
     }
 
+    /// <https://w3c.github.io/webappsec-csp/#concept-global-object-csp-list>
+    pub fn get_csp_list(&self) -> Option<CspList> {
+        if let Some(window) = self.downcast::<Window>() {
+            return window.Document().get_csp_list();
+        }
+        // TODO: workers and worklets should have a CSP list of their own.
+        None
+    }
+
+    /// Queue a task to [report](https://w3c.github.io/webappsec-csp/#report-violation)
+    /// each violation, attributed to `element` if given.
+    pub fn report_csp_violations(&self, violations: Vec<Violation>, element: Option<&Element>) {
+        // Violations are only produced by policies of a Window's document.
+        let window = match self.downcast::<Window>() {
+            Some(window) => window,
+            None => return,
+        };
+        let element = element.map(Trusted::new);
+        let trusted_window = Trusted::new(window);
+        let _ = window.dom_manipulation_task_source().queue(
+            task!(report_csp_violations: move || {
+                security_manager::report_violations(&trusted_window.root(), violations, element);
+            }),
+            self,
+        );
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#can-compile-strings>
+    pub fn is_js_evaluation_allowed(&self, source: &str) -> bool {
+        let (result, violations) = match self.get_csp_list() {
+            Some(csp_list) => csp_list.is_string_compilation_blocked(source),
+            None => return true,
+        };
+        if !violations.is_empty() {
+            self.report_csp_violations(violations, None);
+        }
+        result == CheckResult::Allowed
+    }
+
     /// Extract a `Window`, panic if the global object is not a `Window`.
     pub fn as_window(&self) -> &Window {
         self.downcast::<Window>().expect("expected a Window scope")
@@ -439,6 +684,17 @@ impl GlobalScope {
         unreachable!();
     }
 
+    /// `ScriptChan` to send messages to the port message queue of this global scope.
+    pub fn port_message_queue(&self) -> PortMessageQueue {
+        if let Some(window) = self.downcast::<Window>() {
+            return window.port_message_queue();
+        }
+        if let Some(worker) = self.downcast::<WorkerGlobalScope>() {
+            return worker.port_message_queue();
+        }
+        unreachable!();
+    }
+
     /// Evaluate JS code on this global scope.
     pub fn evaluate_js_on_global_with_result(
             &self, code: &str, rval: MutableHandleValue) -> bool {
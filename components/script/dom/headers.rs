@@ -13,6 +13,7 @@ use dom::globalscope::GlobalScope;
 use dom_struct::dom_struct;
 use hyper::header::Headers as HyperHeaders;
 use mime::{Mime, TopLevel, SubLevel};
+use net_traits::cache_storage_thread::CacheHeaders;
 use std::cell::Cell;
 use std::result::Result;
 use std::str;
@@ -245,6 +246,31 @@ impl Headers {
         self.header_list.borrow().get_raw("content-type").map_or(vec![], |v| v[0].clone())
     }
 
+    /// The header list, in the form it is stored in caches.
+    pub fn cache_headers(&self) -> CacheHeaders {
+        let header_list = self.header_list.borrow();
+        header_list.iter().map(|header| {
+            let name = header.name().to_ascii_lowercase();
+            let mut value = vec![];
+            for line in header_list.get_raw(&name).unwrap_or(&[]) {
+                if !value.is_empty() {
+                    value.extend_from_slice(b", ");
+                }
+                value.extend_from_slice(line);
+            }
+            (name, value)
+        }).collect()
+    }
+
+    /// Replace the header list with one stored in a cache.
+    pub fn set_cache_headers(&self, headers: &CacheHeaders) {
+        let mut header_list = HyperHeaders::new();
+        for &(ref name, ref value) in headers {
+            header_list.set_raw(name.clone(), vec![value.clone()]);
+        }
+        *self.header_list.borrow_mut() = header_list;
+    }
+
     pub fn sort_header_list(&self) -> Vec<(String, String)> {
         let borrowed_header_list = self.header_list.borrow();
         let headers_iter = borrowed_header_list.iter();
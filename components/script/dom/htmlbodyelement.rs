@@ -18,6 +18,7 @@ use dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
 use embedder_traits::EmbedderMsg;
 use html5ever::{LocalName, Prefix};
+use net_traits::csp::InlineCheckType;
 use servo_url::ServoUrl;
 use style::attr::AttrValue;
 use time;
@@ -186,6 +187,10 @@ impl VirtualMethods for HTMLBodyElement {
                     &local_name!("onpopstate") | &local_name!("onstorage") |
                     &local_name!("onresize") | &local_name!("onunload") | &local_name!("onerror")
                       => {
+                          if window.Document().should_elements_inline_type_behavior_be_blocked(
+                              self.upcast(), InlineCheckType::ScriptAttribute, &attr.value()) {
+                              return;
+                          }
                           let evtarget = window.upcast::<EventTarget>(); // forwarded event
                           let source_line = 1; //TODO(#9604) obtain current JS execution line
                           evtarget.set_event_handler_uncompiled(window.get_url(),
@@ -3,26 +3,32 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use base64;
-use canvas_traits::canvas::{CanvasMsg, CanvasId, FromScriptMsg};
+use canvas_traits::canvas::{CanvasMsg, CanvasId, FromScriptMsg, byte_swap};
 use canvas_traits::webgl::WebGLVersion;
 use dom::attr::Attr;
+use dom::bindings::callback::ExceptionHandling;
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasRenderingContext2DMethods;
 use dom::bindings::codegen::Bindings::HTMLCanvasElementBinding;
-use dom::bindings::codegen::Bindings::HTMLCanvasElementBinding::{HTMLCanvasElementMethods, RenderingContext};
+use dom::bindings::codegen::Bindings::HTMLCanvasElementBinding::{BlobCallback, HTMLCanvasElementMethods};
+use dom::bindings::codegen::Bindings::HTMLCanvasElementBinding::RenderingContext;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLContextAttributes;
+use dom::bindings::codegen::UnionTypes::HTMLCanvasElementOrOffscreenCanvas;
 use dom::bindings::conversions::ConversionResult;
-use dom::bindings::error::{Error, Fallible};
+use dom::bindings::error::{Error, ErrorResult, Fallible};
 use dom::bindings::inheritance::Castable;
 use dom::bindings::num::Finite;
+use dom::bindings::refcounted::Trusted;
 use dom::bindings::reflector::DomObject;
-use dom::bindings::root::{Dom, DomRoot, LayoutDom};
+use dom::bindings::root::{Dom, DomRoot, LayoutDom, RootedReference};
 use dom::bindings::str::DOMString;
+use dom::blob::{Blob, BlobImpl};
 use dom::canvasrenderingcontext2d::{CanvasRenderingContext2D, LayoutCanvasRenderingContext2DHelpers};
 use dom::document::Document;
 use dom::element::{AttributeMutation, Element, RawLayoutElementHelpers};
 use dom::globalscope::GlobalScope;
 use dom::htmlelement::HTMLElement;
+use dom::imagebitmap;
 use dom::node::{Node, window_from_node};
 use dom::virtualmethods::VirtualMethods;
 use dom::webgl2renderingcontext::WebGL2RenderingContext;
@@ -30,8 +36,6 @@ use dom::webglrenderingcontext::{LayoutCanvasWebGLRenderingContextHelpers, WebGL
 use dom_struct::dom_struct;
 use euclid::Size2D;
 use html5ever::{LocalName, Prefix};
-use image::ColorType;
-use image::png::PNGEncoder;
 use js::error::throw_type_error;
 use js::jsapi::JSContext;
 use js::rust::HandleValue;
@@ -39,7 +43,10 @@ use offscreen_gl_context::GLContextAttributes;
 use profile_traits::ipc;
 use script_layout_interface::{HTMLCanvasData, HTMLCanvasDataSource};
 use servo_config::prefs::PREFS;
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::iter::repeat;
+use std::rc::Rc;
 use style::attr::{AttrValue, LengthOrPercentageOrAuto};
 
 const DEFAULT_WIDTH: u32 = 300;
@@ -57,6 +64,10 @@ pub enum CanvasContext {
 pub struct HTMLCanvasElement {
     htmlelement: HTMLElement,
     context: DomRefCell<Option<CanvasContext>>,
+    /// Callbacks passed to toBlob() that have not been invoked yet, keyed by request id.
+    #[ignore_malloc_size_of = "Rc"]
+    blob_callbacks: DomRefCell<HashMap<u32, Rc<BlobCallback>>>,
+    next_blob_callback_id: Cell<u32>,
 }
 
 impl HTMLCanvasElement {
@@ -66,6 +77,8 @@ impl HTMLCanvasElement {
         HTMLCanvasElement {
             htmlelement: HTMLElement::new_inherited(local_name, prefix, document),
             context: DomRefCell::new(None),
+            blob_callbacks: DomRefCell::new(HashMap::new()),
+            next_blob_callback_id: Cell::new(0),
         }
     }
 
@@ -197,7 +210,8 @@ impl HTMLCanvasElement {
             let window = window_from_node(self);
             let size = self.get_size();
             let attrs = Self::get_gl_attributes(cx, attrs)?;
-            let maybe_ctx = WebGLRenderingContext::new(&window, self, WebGLVersion::WebGL1, size, attrs);
+            let canvas = HTMLCanvasElementOrOffscreenCanvas::HTMLCanvasElement(DomRoot::from_ref(self));
+            let maybe_ctx = WebGLRenderingContext::new(window.upcast(), &canvas, WebGLVersion::WebGL1, size, attrs);
 
             *self.context.borrow_mut() = maybe_ctx.map( |ctx| CanvasContext::WebGL(Dom::from_ref(&*ctx)));
         }
@@ -221,7 +235,8 @@ impl HTMLCanvasElement {
             let window = window_from_node(self);
             let size = self.get_size();
             let attrs = Self::get_gl_attributes(cx, attrs)?;
-            let maybe_ctx = WebGL2RenderingContext::new(&window, self, size, attrs);
+            let canvas = HTMLCanvasElementOrOffscreenCanvas::HTMLCanvasElement(DomRoot::from_ref(self));
+            let maybe_ctx = WebGL2RenderingContext::new(window.upcast(), &canvas, size, attrs);
 
             *self.context.borrow_mut() = maybe_ctx.map( |ctx| CanvasContext::WebGL2(Dom::from_ref(&*ctx)));
         }
@@ -243,7 +258,7 @@ impl HTMLCanvasElement {
     }
 
     #[allow(unsafe_code)]
-    fn get_gl_attributes(cx: *mut JSContext, attrs: Option<HandleValue>) -> Option<GLContextAttributes> {
+    pub fn get_gl_attributes(cx: *mut JSContext, attrs: Option<HandleValue>) -> Option<GLContextAttributes> {
         let webgl_attributes = match attrs {
             Some(attrs) => attrs,
             None => return Some(GLContextAttributes::default()),
@@ -280,14 +295,8 @@ impl HTMLCanvasElement {
 
                 receiver.recv().unwrap()?.into()
             },
-            Some(&CanvasContext::WebGL(_)) => {
-                // TODO: add a method in WebGLRenderingContext to get the pixels.
-                return None;
-            },
-            Some(&CanvasContext::WebGL2(_)) => {
-                // TODO: add a method in WebGL2RenderingContext to get the pixels.
-                return None;
-            },
+            Some(&CanvasContext::WebGL(ref context)) => context.get_bgra_data(size)?,
+            Some(&CanvasContext::WebGL2(ref context)) => context.base_context().get_bgra_data(size)?,
             None => {
                 repeat(0xffu8).take((size.height as usize) * (size.width as usize) * 4).collect()
             }
@@ -295,6 +304,32 @@ impl HTMLCanvasElement {
 
         Some((data, size))
     }
+
+    /// Reads back the contents of the canvas as unpremultiplied RGBA pixels.
+    /// Returns None if the pixels of the current context can't be read back.
+    fn get_rgba_data(&self) -> Fallible<Option<Vec<u8>>> {
+        let data = match *self.context.borrow() {
+            Some(CanvasContext::Context2d(ref context)) => {
+                let image_data = context.GetImageData(Finite::wrap(0f64), Finite::wrap(0f64),
+                                                      Finite::wrap(self.Width() as f64),
+                                                      Finite::wrap(self.Height() as f64))?;
+                Some(image_data.get_data_array())
+            }
+            Some(CanvasContext::WebGL(_)) | Some(CanvasContext::WebGL2(_)) => {
+                // Unlike readPixels, this reads the drawing buffer top row first.
+                self.fetch_all_data().map(|(mut data, _)| {
+                    imagebitmap::unpremultiply(&mut data);
+                    byte_swap(&mut data);
+                    data
+                })
+            }
+            None => {
+                // Each pixel is fully-transparent black.
+                Some(vec![0; (self.Width() * self.Height() * 4) as usize])
+            }
+        };
+        Ok(data)
+    }
 }
 
 impl HTMLCanvasElementMethods for HTMLCanvasElement {
@@ -353,43 +388,62 @@ impl HTMLCanvasElementMethods for HTMLCanvasElement {
         }
 
         // Step 3.
-        let raw_data = match *self.context.borrow() {
-            Some(CanvasContext::Context2d(ref context)) => {
-                let image_data = context.GetImageData(Finite::wrap(0f64), Finite::wrap(0f64),
-                                                           Finite::wrap(self.Width() as f64),
-                                                           Finite::wrap(self.Height() as f64))?;
-                image_data.get_data_array()
-            }
-            Some(CanvasContext::WebGL(ref context)) => {
-                match context.get_image_data(self.Width(), self.Height()) {
-                    Some(data) => data,
-                    None => return Ok("data:,".into()),
-                }
-            }
-            Some(CanvasContext::WebGL2(ref context)) => {
-                match context.base_context().get_image_data(self.Width(), self.Height()) {
-                    Some(data) => data,
-                    None => return Ok("data:,".into()),
-                }
-            }
-            None => {
-                // Each pixel is fully-transparent black.
-                vec![0; (self.Width() * self.Height() * 4) as usize]
-            }
+        let raw_data = match self.get_rgba_data()? {
+            Some(data) => data,
+            None => return Ok("data:,".into()),
         };
 
         // Only handle image/png for now.
         let mime_type = "image/png";
 
-        let mut encoded = Vec::new();
-        {
-            let encoder: PNGEncoder<&mut Vec<u8>> = PNGEncoder::new(&mut encoded);
-            encoder.encode(&raw_data, self.Width(), self.Height(), ColorType::RGBA(8)).unwrap();
-        }
-
+        let encoded = utils::encode_png(&raw_data, self.Width(), self.Height());
         let encoded = base64::encode(&encoded);
         Ok(DOMString::from(format!("data:{};base64,{}", mime_type, encoded)))
     }
+
+    #[allow(unsafe_code)]
+    // https://html.spec.whatwg.org/multipage/#dom-canvas-toblob
+    unsafe fn ToBlob(&self,
+                     _context: *mut JSContext,
+                     callback: Rc<BlobCallback>,
+                     _mime_type: Option<DOMString>,
+                     _quality: HandleValue) -> ErrorResult {
+        // Step 1.
+        if !self.origin_is_clean() {
+            return Err(Error::Security);
+        }
+
+        // Step 2 and 3. Only image/png is supported for now, so the type and
+        // quality arguments are ignored.
+        let result = if self.Width() == 0 || self.Height() == 0 {
+            None
+        } else {
+            self.get_rgba_data()?.map(|data| utils::encode_png(&data, self.Width(), self.Height()))
+        };
+
+        let id = self.next_blob_callback_id.get();
+        self.next_blob_callback_id.set(id.wrapping_add(1));
+        self.blob_callbacks.borrow_mut().insert(id, callback);
+
+        // Step 4.
+        let this = Trusted::new(self);
+        let window = window_from_node(self);
+        let _ = window.dom_manipulation_task_source().queue(
+            task!(canvas_blob_callback: move || {
+                let this = this.root();
+                let callback = match this.blob_callbacks.borrow_mut().remove(&id) {
+                    Some(callback) => callback,
+                    None => return,
+                };
+                let blob = result.map(|bytes| {
+                    Blob::new(&this.global(), BlobImpl::new_from_bytes(bytes), "image/png".to_owned())
+                });
+                let _ = callback.Call__(blob.r(), ExceptionHandling::Report);
+            }),
+            window.upcast(),
+        );
+        Ok(())
+    }
 }
 
 impl VirtualMethods for HTMLCanvasElement {
@@ -429,6 +483,8 @@ impl<'a> From<&'a WebGLContextAttributes> for GLContextAttributes {
 
 pub mod utils {
     use dom::window::Window;
+    use image::ColorType;
+    use image::png::PNGEncoder;
     use net_traits::image_cache::{ImageResponse, UsePlaceholder, ImageOrMetadataAvailable};
     use net_traits::image_cache::CanRequestImages;
     use servo_url::ServoUrl;
@@ -445,4 +501,14 @@ pub mod utils {
             _ => ImageResponse::None,
         }
     }
+
+    /// Encodes unpremultiplied RGBA pixels as a PNG image.
+    pub fn encode_png(data: &[u8], width: u32, height: u32) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        {
+            let encoder: PNGEncoder<&mut Vec<u8>> = PNGEncoder::new(&mut encoded);
+            encoder.encode(data, width, height, ColorType::RGBA(8)).unwrap();
+        }
+        encoded
+    }
 }
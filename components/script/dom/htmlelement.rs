@@ -34,6 +34,7 @@ use dom::text::Text;
 use dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
 use html5ever::{LocalName, Prefix};
+use net_traits::csp::InlineCheckType;
 use script_layout_interface::message::QueryMsg;
 use std::collections::HashSet;
 use std::default::Default;
@@ -96,17 +97,14 @@ impl HTMLElement {
                     }
                 },
                 _ => {
-                    if let Some(attr) = element.get_attribute(&ns!(), &local_name!("draggable")) {
-                        let value = attr.value();
-                        let is_true = match *value {
+                    let is_draggable = match element.get_attribute(&ns!(), &local_name!("draggable")) {
+                        Some(attr) => match *attr.value() {
                             AttrValue::String(ref string) => string == "true",
                             _ => false,
-                        };
-                        node.set_flag(NodeFlags::SEQUENTIALLY_FOCUSABLE, is_true);
-                    } else {
-                        node.set_flag(NodeFlags::SEQUENTIALLY_FOCUSABLE, false);
-                    }
-                    //TODO set SEQUENTIALLY_FOCUSABLE flag if editing host
+                        },
+                        None => false,
+                    };
+                    node.set_flag(NodeFlags::SEQUENTIALLY_FOCUSABLE, is_draggable || self.is_editing_host());
                     //TODO set SEQUENTIALLY_FOCUSABLE flag if "sorting interface th elements"
                 },
             }
@@ -422,6 +420,34 @@ impl HTMLElementMethods for HTMLElement {
         DOMString::from(window.layout().element_inner_text())
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-contenteditable
+    fn ContentEditable(&self) -> DOMString {
+        DOMString::from(match self.content_editable_state() {
+            Some(true) => "true",
+            Some(false) => "false",
+            None => "inherit",
+        })
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-contenteditable
+    fn SetContentEditable(&self, value: DOMString) -> ErrorResult {
+        let element = self.upcast::<Element>();
+        if value.eq_ignore_ascii_case("inherit") {
+            element.remove_attribute(&ns!(), &local_name!("contenteditable"));
+        } else if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+            element.set_string_attribute(&local_name!("contenteditable"),
+                                         DOMString::from(value.to_ascii_lowercase()));
+        } else {
+            return Err(Error::Syntax);
+        }
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-iscontenteditable
+    fn IsContentEditable(&self) -> bool {
+        self.upcast::<Node>().editing_host().is_some()
+    }
+
     // https://html.spec.whatwg.org/multipage/#the-innertext-idl-attribute
     fn SetInnerText(&self, input: DOMString) {
         // Step 1.
@@ -568,6 +594,28 @@ impl HTMLElement {
         self.upcast::<Element>().remove_attribute(&ns!(), &local_name);
     }
 
+    /// Returns `Some(true)` for the true state of the `contenteditable` attribute,
+    /// `Some(false)` for the false state and `None` for the inherit state.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#attr-contenteditable>
+    pub fn content_editable_state(&self) -> Option<bool> {
+        let element = self.upcast::<Element>();
+        let attr = element.get_attribute(&ns!(), &local_name!("contenteditable"))?;
+        let value = attr.value();
+        if value.is_empty() || value.eq_ignore_ascii_case("true") {
+            Some(true)
+        } else if value.eq_ignore_ascii_case("false") {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#editing-host
+    pub fn is_editing_host(&self) -> bool {
+        self.upcast::<Node>().editing_host().map_or(false, |host| &*host == self)
+    }
+
     // https://html.spec.whatwg.org/multipage/#category-label
     pub fn is_labelable_element(&self) -> bool {
         // Note: HTMLKeygenElement is omitted because Servo doesn't currently implement it
@@ -669,6 +717,10 @@ impl VirtualMethods for HTMLElement {
         self.super_type().unwrap().attribute_mutated(attr, mutation);
         match (attr.local_name(), mutation) {
             (name, AttributeMutation::Set(_)) if name.starts_with("on") => {
+                if document_from_node(self).should_elements_inline_type_behavior_be_blocked(
+                    self.upcast(), InlineCheckType::ScriptAttribute, &attr.value()) {
+                    return;
+                }
                 let evtarget = self.upcast::<EventTarget>();
                 let source_line = 1; //TODO(#9604) get current JS execution line
                 evtarget.set_event_handler_uncompiled(window_from_node(self).get_url(),
@@ -677,6 +729,9 @@ impl VirtualMethods for HTMLElement {
                                                       // FIXME(ajeffrey): Convert directly from AttrValue to DOMString
                                                       DOMString::from(&**attr.value()));
             },
+            (&local_name!("contenteditable"), _) => {
+                self.update_sequentially_focusable_status();
+            },
             _ => {}
         }
     }
@@ -27,6 +27,7 @@ use dom_struct::dom_struct;
 use html5ever::{LocalName, Prefix};
 use ipc_channel::ipc;
 use msg::constellation_msg::{BrowsingContextId, PipelineId, TopLevelBrowsingContextId};
+use net_traits::csp::CheckResult;
 use profile_traits::ipc as ProfiledIpc;
 use script_layout_interface::message::ReflowGoal;
 use script_thread::ScriptThread;
@@ -117,23 +118,26 @@ impl HTMLIFrameElement {
             Some(id) => id,
         };
 
-        let document = document_from_node(self);
-
-        let mut load_blocker = self.load_blocker.borrow_mut();
-        // Any oustanding load is finished from the point of view of the blocked
-        // document; the new navigation will continue blocking it.
-        LoadBlocker::terminate(&mut load_blocker);
-
         if let Some(ref mut load_data) = load_data {
             let is_javascript = load_data.url.scheme() == "javascript";
             if is_javascript {
                 let window_proxy = self.GetContentWindow();
                 if let Some(window_proxy) = window_proxy {
-                    ScriptThread::eval_js_url(&window_proxy.global(), load_data);
+                    // A navigation blocked by CSP leaves the current document in place.
+                    if !ScriptThread::eval_js_url(&window_proxy.global(), load_data) {
+                        return;
+                    }
                 }
             }
         }
 
+        let document = document_from_node(self);
+
+        let mut load_blocker = self.load_blocker.borrow_mut();
+        // Any oustanding load is finished from the point of view of the blocked
+        // document; the new navigation will continue blocking it.
+        LoadBlocker::terminate(&mut load_blocker);
+
         //TODO(#9592): Deal with the case where an iframe is being reloaded so url is None.
         //      The iframe should always have access to the nested context's active
         //      document URL through the browsing context.
@@ -224,6 +228,11 @@ impl HTMLIFrameElement {
 
         let url = self.get_url();
 
+        // https://w3c.github.io/webappsec-csp/#should-block-navigation-request
+        if url.as_str() != "about:blank" && self.is_navigation_blocked_by_csp(&url) {
+            return;
+        }
+
         // TODO: check ancestor browsing contexts for same URL
 
         let creator_pipeline_id = if url.as_str() == "about:blank" {
@@ -241,6 +250,18 @@ impl HTMLIFrameElement {
         self.navigate_or_reload_child_browsing_context(Some(load_data), NavigationType::Regular, replace);
     }
 
+    fn is_navigation_blocked_by_csp(&self, url: &ServoUrl) -> bool {
+        let window = window_from_node(self);
+        let (result, violations) = match window.upcast::<GlobalScope>().get_csp_list() {
+            Some(csp_list) => csp_list.should_nested_navigation_be_blocked(url),
+            None => return false,
+        };
+        if !violations.is_empty() {
+            window.upcast::<GlobalScope>().report_csp_violations(violations, Some(self.upcast()));
+        }
+        result == CheckResult::Blocked
+    }
+
     fn create_nested_browsing_context(&self) {
         // Synchronously create a new context and navigate it to about:blank.
         let url = ServoUrl::parse("about:blank").unwrap();
@@ -26,6 +26,7 @@ use dom::element::{AttributeMutation, Element, RawLayoutElementHelpers};
 use dom::element::{reflect_cross_origin_attribute, set_cross_origin_attribute};
 use dom::event::{Event, EventBubbles, EventCancelable};
 use dom::eventtarget::EventTarget;
+use dom::globalscope::GlobalScope;
 use dom::htmlareaelement::HTMLAreaElement;
 use dom::htmlelement::HTMLElement;
 use dom::htmlformelement::{FormControl, HTMLFormElement};
@@ -46,11 +47,12 @@ use ipc_channel::router::ROUTER;
 use microtask::{Microtask, MicrotaskRunnable};
 use mime::{Mime, TopLevel};
 use net_traits::{FetchResponseListener, FetchMetadata, NetworkError, FetchResponseMsg};
+use net_traits::csp::Violation;
 use net_traits::image::base::{Image, ImageMetadata};
 use net_traits::image_cache::{CanRequestImages, ImageCache, ImageOrMetadataAvailable};
 use net_traits::image_cache::{ImageResponder, ImageResponse, ImageState, PendingImageId};
 use net_traits::image_cache::UsePlaceholder;
-use net_traits::request::RequestInit;
+use net_traits::request::{Destination, RequestInit};
 use network_listener::{NetworkListener, PreInvoke};
 use num_traits::ToPrimitive;
 use script_thread::ScriptThread;
@@ -160,6 +162,8 @@ struct ImageContext {
     status: Result<(), NetworkError>,
     /// The cache ID for this request.
     id: PendingImageId,
+    /// The element that initiated the request.
+    elem: Trusted<HTMLImageElement>,
 }
 
 impl FetchResponseListener for ImageContext {
@@ -202,6 +206,12 @@ impl FetchResponseListener for ImageContext {
             self.id,
             FetchResponseMsg::ProcessResponseEOF(response));
     }
+
+    fn process_csp_violations(&mut self, violations: Vec<Violation>) {
+        let elem = self.elem.root();
+        let global = window_from_node(&*elem);
+        global.upcast::<GlobalScope>().report_csp_violations(violations, Some(elem.upcast()));
+    }
 }
 
 impl PreInvoke for ImageContext {}
@@ -279,6 +289,7 @@ impl HTMLImageElement {
             image_cache: window.image_cache(),
             status: Ok(()),
             id: id,
+            elem: Trusted::new(self),
         }));
 
         let (action_sender, action_receiver) = ipc::channel().unwrap();
@@ -293,6 +304,7 @@ impl HTMLImageElement {
 
         let request = RequestInit {
             url: img_url.clone(),
+            destination: Destination::Image,
             origin: document.origin().immutable().clone(),
             pipeline_id: Some(document.global().pipeline_id()),
             .. RequestInit::default()
@@ -96,13 +96,13 @@ impl HTMLLinkElement {
     // FIXME(emilio): These methods are duplicated with
     // HTMLStyleElement::set_stylesheet.
     pub fn set_stylesheet(&self, s: Arc<Stylesheet>) {
-        let doc = document_from_node(self);
+        let element = self.upcast::<Element>();
         if let Some(ref s) = *self.stylesheet.borrow() {
-            doc.remove_stylesheet(self.upcast(), s)
+            element.remove_owned_stylesheet(s)
         }
         *self.stylesheet.borrow_mut() = Some(s.clone());
         self.cssom_stylesheet.set(None);
-        doc.add_stylesheet(self.upcast(), s);
+        element.add_owned_stylesheet(s);
     }
 
     pub fn get_stylesheet(&self) -> Option<Arc<Stylesheet>> {
@@ -234,7 +234,7 @@ impl VirtualMethods for HTMLLinkElement {
         }
 
         if let Some(s) = self.stylesheet.borrow_mut().take() {
-            document_from_node(self).remove_stylesheet(self.upcast(), &s);
+            self.upcast::<Element>().remove_owned_stylesheet(&s);
         }
     }
 }
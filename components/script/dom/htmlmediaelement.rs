@@ -37,6 +37,7 @@ use ipc_channel::router::ROUTER;
 use microtask::{Microtask, MicrotaskRunnable};
 use mime::{Mime, SubLevel, TopLevel};
 use net_traits::{FetchResponseListener, FetchMetadata, Metadata, NetworkError};
+use net_traits::csp::Violation;
 use net_traits::request::{CredentialsMode, Destination, RequestInit};
 use network_listener::{NetworkListener, PreInvoke};
 use script_thread::ScriptThread;
@@ -1090,6 +1091,11 @@ impl FetchResponseListener for HTMLMediaElementContext {
             elem.queue_dedicated_media_source_failure_steps();
         }
     }
+
+    fn process_csp_violations(&mut self, violations: Vec<Violation>) {
+        let elem = self.elem.root();
+        elem.global().report_csp_violations(violations, Some(elem.upcast()));
+    }
 }
 
 impl PreInvoke for HTMLMediaElementContext {
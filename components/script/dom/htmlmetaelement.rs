@@ -19,6 +19,7 @@ use dom::node::{Node, UnbindContext, document_from_node, window_from_node};
 use dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
 use html5ever::{LocalName, Prefix};
+use net_traits::csp::{CspList, Policy, PolicyDisposition, PolicySource};
 use parking_lot::RwLock;
 use servo_arc::Arc;
 use servo_config::prefs::PREFS;
@@ -87,6 +88,39 @@ impl HTMLMetaElement {
                 self.apply_referrer();
             }
         }
+
+        if let Some(http_equiv) = element.get_attribute(&ns!(), &local_name!("http-equiv")).r() {
+            if http_equiv.value().trim_matches(HTML_SPACE_CHARACTERS)
+                                 .eq_ignore_ascii_case("content-security-policy") {
+                self.apply_content_security_policy();
+            }
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#attr-meta-http-equiv-content-security-policy>
+    fn apply_content_security_policy(&self) {
+        // Step 1.
+        let parent = self.upcast::<Node>().GetParentElement();
+        if !parent.map_or(false, |parent| parent.is::<HTMLHeadElement>()) {
+            return;
+        }
+
+        // Step 2.
+        let element = self.upcast::<Element>();
+        let content = element.get_string_attribute(&local_name!("content"));
+        if content.is_empty() {
+            return;
+        }
+
+        // Steps 3-5.
+        let document = document_from_node(self);
+        let policy = Policy::parse(&content, PolicySource::Meta, PolicyDisposition::Enforce, &document.url());
+        if policy.directives.is_empty() {
+            return;
+        }
+
+        // Step 6.
+        document.append_csp_list(CspList(vec![policy]));
     }
 
     fn apply_viewport(&self) {
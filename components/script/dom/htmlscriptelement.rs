@@ -29,6 +29,7 @@ use ipc_channel::ipc;
 use ipc_channel::router::ROUTER;
 use js::jsval::UndefinedValue;
 use net_traits::{FetchMetadata, FetchResponseListener, Metadata, NetworkError};
+use net_traits::csp::{InlineCheckType, Violation};
 use net_traits::request::{CorsSettings, CredentialsMode, Destination, RequestInit, RequestMode};
 use network_listener::{NetworkListener, PreInvoke};
 use servo_atoms::Atom;
@@ -221,6 +222,12 @@ impl FetchResponseListener for ScriptContext {
 
         document.finish_load(LoadType::Script(self.url.clone()));
     }
+
+    fn process_csp_violations(&mut self, violations: Vec<Violation>) {
+        let elem = self.elem.root();
+        let global = elem.global();
+        global.report_csp_violations(violations, Some(elem.upcast()));
+    }
 }
 
 impl PreInvoke for ScriptContext {}
@@ -255,6 +262,7 @@ fn fetch_a_classic_script(script: &HTMLScriptElement,
         referrer_url: Some(doc.url()),
         referrer_policy: doc.get_referrer_policy(),
         integrity_metadata: integrity_metadata,
+        cryptographic_nonce_metadata: script.upcast::<Element>().nonce_value(),
         .. RequestInit::default()
     };
 
@@ -341,7 +349,11 @@ impl HTMLScriptElement {
 
         // TODO: Step 11: nomodule content attribute
 
-        // TODO(#4577): Step 12: CSP.
+        // Step 12.
+        if !element.has_attribute(&local_name!("src")) &&
+           doc.should_elements_inline_type_behavior_be_blocked(element, InlineCheckType::Script, &text) {
+            return;
+        }
 
         // Step 13.
         let for_attribute = element.get_attribute(&ns!(), &local_name!("for"));
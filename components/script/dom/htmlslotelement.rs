@@ -0,0 +1,265 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::attr::Attr;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::HTMLSlotElementBinding;
+use dom::bindings::codegen::Bindings::HTMLSlotElementBinding::{AssignedNodesOptions, HTMLSlotElementMethods};
+use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
+use dom::bindings::codegen::Bindings::ShadowRootBinding::ShadowRootMethods;
+use dom::bindings::codegen::Bindings::ShadowRootBinding::ShadowRootMode;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::root::{Dom, DomRoot, LayoutDom};
+use dom::bindings::str::DOMString;
+use dom::document::Document;
+use dom::element::{AttributeMutation, Element};
+use dom::htmlelement::HTMLElement;
+use dom::mutationobserver::MutationObserver;
+use dom::node::{Node, NodeDamage, document_from_node};
+use dom::shadowroot::ShadowRoot;
+use dom::text::Text;
+use dom::virtualmethods::VirtualMethods;
+use dom_struct::dom_struct;
+use html5ever::{LocalName, Prefix};
+use script_thread::ScriptThread;
+use style::invalidation::element::restyle_hints::RestyleHint;
+
+/// <https://html.spec.whatwg.org/multipage/#the-slot-element>
+#[dom_struct]
+pub struct HTMLSlotElement {
+    htmlelement: HTMLElement,
+    /// <https://dom.spec.whatwg.org/#slot-assigned-nodes>
+    assigned_nodes: DomRefCell<Vec<Dom<Node>>>,
+}
+
+impl HTMLSlotElement {
+    fn new_inherited(local_name: LocalName,
+                     prefix: Option<Prefix>,
+                     document: &Document) -> HTMLSlotElement {
+        HTMLSlotElement {
+            htmlelement: HTMLElement::new_inherited(local_name, prefix, document),
+            assigned_nodes: DomRefCell::new(Vec::new()),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(local_name: LocalName,
+               prefix: Option<Prefix>,
+               document: &Document) -> DomRoot<HTMLSlotElement> {
+        Node::reflect_node(Box::new(HTMLSlotElement::new_inherited(local_name, prefix, document)),
+                           document,
+                           HTMLSlotElementBinding::Wrap)
+    }
+
+    /// <https://dom.spec.whatwg.org/#slot-name>
+    fn name(&self) -> DOMString {
+        self.upcast::<Element>().get_string_attribute(&local_name!("name"))
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-slotable>
+    pub fn is_slottable(node: &Node) -> bool {
+        node.is::<Element>() || node.is::<Text>()
+    }
+
+    /// <https://dom.spec.whatwg.org/#slotable-name>
+    fn slottable_name(slottable: &Node) -> DOMString {
+        match slottable.downcast::<Element>() {
+            Some(element) => element.get_string_attribute(&local_name!("slot")),
+            None => DOMString::new(),
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#find-a-slot>
+    pub fn find_a_slot(slottable: &Node, open: bool) -> Option<DomRoot<HTMLSlotElement>> {
+        // Step 1.
+        let parent = slottable.GetParentNode()?;
+
+        // Step 2.
+        let shadow_root = parent.downcast::<Element>()?.shadow_root()?;
+
+        // Step 3.
+        if open && shadow_root.Mode() != ShadowRootMode::Open {
+            return None;
+        }
+
+        // Step 4.
+        let name = HTMLSlotElement::slottable_name(slottable);
+        shadow_root.upcast::<Node>()
+            .traverse_preorder()
+            .filter_map(DomRoot::downcast::<HTMLSlotElement>)
+            .find(|slot| slot.name() == name)
+    }
+
+    /// <https://dom.spec.whatwg.org/#find-slotables>
+    fn find_slottables(&self) -> Vec<DomRoot<Node>> {
+        // Steps 1-2.
+        let root = self.upcast::<Node>().inclusive_ancestors().last().unwrap();
+        let shadow_root = match root.downcast::<ShadowRoot>() {
+            Some(shadow_root) => shadow_root,
+            None => return vec![],
+        };
+
+        // Steps 3-4.
+        let host = shadow_root.Host();
+        host.upcast::<Node>()
+            .children()
+            .filter(|child| HTMLSlotElement::is_slottable(child))
+            .filter(|child| {
+                HTMLSlotElement::find_a_slot(child, false).map_or(false, |slot| &*slot == self)
+            })
+            .collect()
+    }
+
+    /// <https://dom.spec.whatwg.org/#find-flattened-slotables>
+    fn find_flattened_slottables(&self) -> Vec<DomRoot<Node>> {
+        // Steps 1-2.
+        let mut result = vec![];
+        if !self.upcast::<Node>().is_in_shadow_tree() {
+            return result;
+        }
+
+        // Steps 3-4.
+        let mut slottables = self.assigned_nodes();
+        if slottables.is_empty() {
+            slottables = self.upcast::<Node>()
+                .children()
+                .filter(|child| HTMLSlotElement::is_slottable(child))
+                .collect();
+        }
+
+        // Step 5.
+        for node in slottables {
+            match node.downcast::<HTMLSlotElement>() {
+                Some(slot) if node.is_in_shadow_tree() => {
+                    result.extend(slot.find_flattened_slottables());
+                },
+                _ => result.push(node.clone()),
+            }
+        }
+        result
+    }
+
+    /// Returns the nodes assigned to this slot.
+    pub fn assigned_nodes(&self) -> Vec<DomRoot<Node>> {
+        self.assigned_nodes.borrow().iter().map(|node| DomRoot::from_ref(&**node)).collect()
+    }
+
+    /// <https://dom.spec.whatwg.org/#assign-slotables>
+    pub fn assign_slottables(&self) {
+        // Step 1.
+        let slottables = self.find_slottables();
+
+        // Step 2.
+        let changed = {
+            let assigned_nodes = self.assigned_nodes.borrow();
+            assigned_nodes.len() != slottables.len() ||
+            assigned_nodes.iter().zip(slottables.iter()).any(|(old, new)| &**old != &**new)
+        };
+        if !changed {
+            return;
+        }
+        self.signal_a_slot_change();
+
+        // Steps 3-4.
+        for node in self.assigned_nodes.borrow().iter() {
+            if node.assigned_slot().map_or(false, |slot| &*slot == self) {
+                node.set_assigned_slot(None);
+            }
+        }
+        *self.assigned_nodes.borrow_mut() = slottables.iter().map(|node| Dom::from_ref(&**node)).collect();
+        for node in &slottables {
+            node.set_assigned_slot(Some(self));
+        }
+
+        // The children of this slot in the flat tree changed, so it needs to
+        // be laid out again.
+        let node = self.upcast::<Node>();
+        if node.is_in_doc() {
+            let doc = document_from_node(self);
+            doc.ensure_pending_restyle(self.upcast()).hint.insert(RestyleHint::restyle_subtree());
+            node.dirty(NodeDamage::OtherNodeDamage);
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#assign-slotables-for-a-tree>
+    pub fn assign_slottables_for_a_tree(root: &Node) {
+        for slot in root.traverse_preorder().filter_map(DomRoot::downcast::<HTMLSlotElement>) {
+            slot.assign_slottables();
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#assign-a-slot>
+    pub fn assign_a_slot(slottable: &Node) {
+        if let Some(slot) = HTMLSlotElement::find_a_slot(slottable, false) {
+            slot.assign_slottables();
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#signal-a-slot-change>
+    pub fn signal_a_slot_change(&self) {
+        ScriptThread::add_signal_slot(self);
+        MutationObserver::queue_mutation_observer_compound_microtask();
+    }
+
+    pub fn has_assigned_nodes(&self) -> bool {
+        !self.assigned_nodes.borrow().is_empty()
+    }
+}
+
+pub trait LayoutHTMLSlotElementHelpers {
+    #[allow(unsafe_code)]
+    unsafe fn get_assigned_nodes_for_layout(&self) -> Vec<LayoutDom<Node>>;
+}
+
+impl LayoutHTMLSlotElementHelpers for LayoutDom<HTMLSlotElement> {
+    #[allow(unsafe_code)]
+    #[allow(unrooted_must_root)]
+    unsafe fn get_assigned_nodes_for_layout(&self) -> Vec<LayoutDom<Node>> {
+        (*self.unsafe_get()).assigned_nodes
+            .borrow_for_layout()
+            .iter()
+            .map(|node| node.to_layout())
+            .collect()
+    }
+}
+
+impl HTMLSlotElementMethods for HTMLSlotElement {
+    // https://html.spec.whatwg.org/multipage/#dom-slot-name
+    make_getter!(Name, "name");
+
+    // https://html.spec.whatwg.org/multipage/#dom-slot-name
+    make_setter!(SetName, "name");
+
+    // https://html.spec.whatwg.org/multipage/#dom-slot-assignednodes
+    fn AssignedNodes(&self, options: &AssignedNodesOptions) -> Vec<DomRoot<Node>> {
+        if options.flatten {
+            self.find_flattened_slottables()
+        } else {
+            self.assigned_nodes()
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-slot-assignedelements
+    fn AssignedElements(&self, options: &AssignedNodesOptions) -> Vec<DomRoot<Element>> {
+        self.AssignedNodes(options).into_iter().filter_map(DomRoot::downcast::<Element>).collect()
+    }
+}
+
+impl VirtualMethods for HTMLSlotElement {
+    fn super_type(&self) -> Option<&VirtualMethods> {
+        Some(self.upcast::<HTMLElement>() as &VirtualMethods)
+    }
+
+    fn attribute_mutated(&self, attr: &Attr, mutation: AttributeMutation) {
+        self.super_type().unwrap().attribute_mutated(attr, mutation);
+
+        // https://dom.spec.whatwg.org/#shadow-tree-slots
+        if attr.local_name() == &local_name!("name") && attr.namespace() == &ns!() {
+            let node = self.upcast::<Node>();
+            if node.is_in_shadow_tree() {
+                HTMLSlotElement::assign_slottables_for_a_tree(&node.inclusive_ancestors().last().unwrap());
+            }
+        }
+    }
+}
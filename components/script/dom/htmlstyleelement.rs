@@ -19,6 +19,7 @@ use dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
 use html5ever::{LocalName, Prefix};
 use net_traits::ReferrerPolicy;
+use net_traits::csp::InlineCheckType;
 use servo_arc::Arc;
 use std::cell::Cell;
 use style::media_queries::MediaList;
@@ -83,6 +84,13 @@ impl HTMLStyleElement {
         };
 
         let data = node.GetTextContent().expect("Element.textContent must be a string");
+        if doc.should_elements_inline_type_behavior_be_blocked(element, InlineCheckType::Style, &data) {
+            if let Some(sheet) = self.stylesheet.borrow_mut().take() {
+                element.remove_owned_stylesheet(&sheet);
+            }
+            self.cssom_stylesheet.set(None);
+            return;
+        }
         let url = window.get_url();
         let css_error_reporter = window.css_error_reporter();
         let context = CssParserContext::new_for_cssom(
@@ -124,13 +132,13 @@ impl HTMLStyleElement {
 
     // FIXME(emilio): This is duplicated with HTMLLinkElement::set_stylesheet.
     pub fn set_stylesheet(&self, s: Arc<Stylesheet>) {
-        let doc = document_from_node(self);
+        let element = self.upcast::<Element>();
         if let Some(ref s) = *self.stylesheet.borrow() {
-            doc.remove_stylesheet(self.upcast(), s)
+            element.remove_owned_stylesheet(s)
         }
         *self.stylesheet.borrow_mut() = Some(s.clone());
         self.cssom_stylesheet.set(None);
-        doc.add_stylesheet(self.upcast(), s);
+        element.add_owned_stylesheet(s);
     }
 
     pub fn get_stylesheet(&self) -> Option<Arc<Stylesheet>> {
@@ -200,7 +208,7 @@ impl VirtualMethods for HTMLStyleElement {
 
         if context.tree_in_doc {
             if let Some(s) = self.stylesheet.borrow_mut().take() {
-                document_from_node(self).remove_stylesheet(self.upcast(), &s)
+                self.upcast::<Element>().remove_owned_stylesheet(&s)
             }
         }
     }
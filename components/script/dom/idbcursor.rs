@@ -0,0 +1,333 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::IDBCursorBinding;
+use dom::bindings::codegen::Bindings::IDBCursorBinding::{IDBCursorDirection, IDBCursorMethods};
+use dom::bindings::codegen::Bindings::IDBTransactionBinding::IDBTransactionMode;
+use dom::bindings::codegen::UnionTypes::IDBObjectStoreOrIDBIndex;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::structuredclone::StructuredCloneData;
+use dom::globalscope::GlobalScope;
+use dom::idbcursorwithvalue::IDBCursorWithValue;
+use dom::idbindex::IDBIndex;
+use dom::idbobjectstore::IDBObjectStore;
+use dom::idbrequest::IDBRequest;
+use dom::idbtransaction::IDBTransaction;
+use dom_struct::dom_struct;
+use indexed_db::{convert_value_to_key, extract_index_keys, extract_key, key_to_jsval, read_value};
+use js::jsapi::{Heap, JSContext};
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::HandleValue;
+use net_traits::indexeddb_thread::{IndexedDBCursorDirection, IndexedDBCursorRecord, IndexedDBIndexKeys};
+use net_traits::indexeddb_thread::{IndexedDBKey, IndexedDBKeyRange, IndexedDBOperation};
+use std::cell::Cell;
+
+#[dom_struct]
+pub struct IDBCursor {
+    reflector_: Reflector,
+    /// The object store iterated by this cursor, directly or through `index`.
+    store: Dom<IDBObjectStore>,
+    index: Option<Dom<IDBIndex>>,
+    transaction: Dom<IDBTransaction>,
+    direction: IDBCursorDirection,
+    range: IndexedDBKeyRange,
+    key_only: bool,
+    request: Dom<IDBRequest>,
+    /// The key and primary key of the record this cursor is positioned on.
+    position: DomRefCell<Option<(IndexedDBKey, IndexedDBKey)>>,
+    #[ignore_malloc_size_of = "Defined in rust-mozjs"]
+    key: Heap<JSVal>,
+    #[ignore_malloc_size_of = "Defined in rust-mozjs"]
+    primary_key: Heap<JSVal>,
+    #[ignore_malloc_size_of = "Defined in rust-mozjs"]
+    value: Heap<JSVal>,
+    /// <https://w3c.github.io/IndexedDB/#cursor-got-value-flag>
+    got_value: Cell<bool>,
+}
+
+impl IDBCursor {
+    pub fn new_inherited(store: &IDBObjectStore,
+                         index: Option<&IDBIndex>,
+                         direction: IDBCursorDirection,
+                         range: IndexedDBKeyRange,
+                         key_only: bool,
+                         request: &IDBRequest)
+                         -> IDBCursor {
+        IDBCursor {
+            reflector_: Reflector::new(),
+            store: Dom::from_ref(store),
+            index: index.map(Dom::from_ref),
+            transaction: Dom::from_ref(&*store.transaction()),
+            direction: direction,
+            range: range,
+            key_only: key_only,
+            request: Dom::from_ref(request),
+            position: DomRefCell::new(None),
+            key: Heap::default(),
+            primary_key: Heap::default(),
+            value: Heap::default(),
+            got_value: Cell::new(false),
+        }
+    }
+
+    fn new(global: &GlobalScope,
+           store: &IDBObjectStore,
+           index: Option<&IDBIndex>,
+           direction: IDBCursorDirection,
+           range: IndexedDBKeyRange,
+           request: &IDBRequest)
+           -> DomRoot<IDBCursor> {
+        reflect_dom_object(Box::new(IDBCursor::new_inherited(store, index, direction, range, true, request)),
+                           global,
+                           IDBCursorBinding::Wrap)
+    }
+
+    /// Open a cursor over `store`, or `index` if set, returning the request it is iterated with.
+    /// <https://w3c.github.io/IndexedDB/#dom-idbobjectstore-opencursor> steps 7-10
+    pub fn open(global: &GlobalScope,
+                store: &IDBObjectStore,
+                index: Option<&IDBIndex>,
+                range: IndexedDBKeyRange,
+                direction: IDBCursorDirection,
+                key_only: bool)
+                -> DomRoot<IDBRequest> {
+        let request = IDBRequest::new(global);
+        match index {
+            Some(index) => request.set_source_index(index),
+            None => request.set_source_store(store),
+        }
+        let cursor = if key_only {
+            IDBCursor::new(global, store, index, direction, range, &request)
+        } else {
+            DomRoot::upcast(IDBCursorWithValue::new(global, store, index, direction, range, &request))
+        };
+        request.set_cursor(&cursor);
+        cursor.iterate(None, 0);
+        request
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#iterate-a-cursor>
+    fn iterate(&self, target: Option<IndexedDBKey>, skip: u32) {
+        self.got_value.set(false);
+        let operation = IndexedDBOperation::Iterate {
+            index: self.index.as_ref().map(|index| String::from(index.name())),
+            range: self.range.clone(),
+            direction: self.direction.into(),
+            position: self.position.borrow().clone(),
+            target: target,
+            skip: skip,
+            key_only: self.key_only,
+        };
+        self.transaction.execute(&self.request, self.store.name(), operation);
+    }
+
+    /// Move this cursor to the record found by the last iteration.
+    #[allow(unsafe_code)]
+    pub fn set_record(&self, record: IndexedDBCursorRecord) {
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(cx) let mut value = UndefinedValue());
+        unsafe {
+            key_to_jsval(cx, &record.key, value.handle_mut());
+            self.key.set(value.get());
+            key_to_jsval(cx, &record.primary_key, value.handle_mut());
+            self.primary_key.set(value.get());
+            value.set(UndefinedValue());
+            if let Some(data) = record.value {
+                let key_path = self.store.generated_key_path();
+                let generated_key = key_path.as_ref().map(|path| (&record.primary_key, &**path));
+                read_value(&global, data, generated_key, value.handle_mut());
+            }
+            self.value.set(value.get());
+        }
+        *self.position.borrow_mut() = Some((record.key, record.primary_key));
+        self.got_value.set(true);
+    }
+
+    pub fn value(&self) -> JSVal {
+        self.value.get()
+    }
+
+    /// Whether the source of this cursor, or its object store, was deleted.
+    fn source_deleted(&self) -> bool {
+        let store = match self.store.metadata() {
+            Some(store) => store,
+            None => return true,
+        };
+        match self.index {
+            Some(ref index) => !store.indexes.iter().any(|metadata| metadata.name == *index.name()),
+            None => false,
+        }
+    }
+
+    /// The checks shared by the methods iterating this cursor.
+    fn check_can_iterate(&self) -> ErrorResult {
+        if !self.transaction.is_active() {
+            return Err(Error::TransactionInactive);
+        }
+        if self.source_deleted() || !self.got_value.get() {
+            return Err(Error::InvalidState);
+        }
+        Ok(())
+    }
+
+    /// The checks shared by `update()` and `delete()`, returning the current primary key.
+    fn check_can_write(&self) -> Fallible<IndexedDBKey> {
+        if !self.transaction.is_active() {
+            return Err(Error::TransactionInactive);
+        }
+        if self.transaction.mode() == IDBTransactionMode::Readonly {
+            return Err(Error::ReadOnly);
+        }
+        if self.source_deleted() || !self.got_value.get() || self.key_only {
+            return Err(Error::InvalidState);
+        }
+        let position = self.position.borrow();
+        Ok(position.as_ref().expect("A cursor with a value has a position").1.clone())
+    }
+
+    /// Place a request against the object store of this cursor, with this cursor as its source.
+    fn execute(&self, operation: IndexedDBOperation) -> DomRoot<IDBRequest> {
+        let request = IDBRequest::new(&self.global());
+        request.set_source_cursor(self);
+        self.transaction.execute(&request, self.store.name(), operation);
+        request
+    }
+}
+
+impl IDBCursorMethods for IDBCursor {
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-source
+    fn Source(&self) -> IDBObjectStoreOrIDBIndex {
+        match self.index {
+            Some(ref index) => IDBObjectStoreOrIDBIndex::IDBIndex(DomRoot::from_ref(&**index)),
+            None => IDBObjectStoreOrIDBIndex::IDBObjectStore(DomRoot::from_ref(&*self.store)),
+        }
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-direction
+    fn Direction(&self) -> IDBCursorDirection {
+        self.direction
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-key
+    #[allow(unsafe_code)]
+    unsafe fn Key(&self, _cx: *mut JSContext) -> JSVal {
+        self.key.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-primarykey
+    #[allow(unsafe_code)]
+    unsafe fn PrimaryKey(&self, _cx: *mut JSContext) -> JSVal {
+        self.primary_key.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-request
+    fn Request(&self) -> DomRoot<IDBRequest> {
+        DomRoot::from_ref(&*self.request)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-advance
+    fn Advance(&self, count: u32) -> ErrorResult {
+        // Step 1.
+        if count == 0 {
+            return Err(Error::Type("The count must be greater than zero".to_owned()));
+        }
+
+        // Steps 2-4.
+        self.check_can_iterate()?;
+
+        // Steps 5-7.
+        self.request.reset();
+        self.iterate(None, count - 1);
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-continue
+    #[allow(unsafe_code)]
+    unsafe fn Continue(&self, cx: *mut JSContext, key: HandleValue) -> ErrorResult {
+        // Steps 1-3.
+        self.check_can_iterate()?;
+
+        // Step 4.
+        let target = if key.is_undefined() {
+            None
+        } else {
+            let key = convert_value_to_key(cx, key)?;
+            let forward = match self.direction {
+                IDBCursorDirection::Next | IDBCursorDirection::Nextunique => true,
+                IDBCursorDirection::Prev | IDBCursorDirection::Prevunique => false,
+            };
+            let out_of_order = match *self.position.borrow() {
+                Some((ref position, _)) => if forward { key <= *position } else { key >= *position },
+                None => false,
+            };
+            if out_of_order {
+                return Err(Error::Data);
+            }
+            Some(key)
+        };
+
+        // Steps 5-7.
+        self.request.reset();
+        self.iterate(target, 0);
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-update
+    #[allow(unsafe_code)]
+    unsafe fn Update(&self, cx: *mut JSContext, value: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-5.
+        let primary_key = self.check_can_write()?;
+
+        // Step 6.
+        let data = StructuredCloneData::write(cx, value)?;
+
+        // Step 7.
+        let metadata = self.store.metadata().ok_or(Error::InvalidState)?;
+        if let Some(ref key_path) = metadata.key_path {
+            if extract_key(cx, value, key_path)? != Some(primary_key.clone()) {
+                return Err(Error::Data);
+            }
+        }
+
+        let mut index_keys = vec![];
+        for index in &metadata.indexes {
+            index_keys.push(IndexedDBIndexKeys {
+                index: index.name.clone(),
+                keys: extract_index_keys(cx, value, index)?,
+            });
+        }
+
+        // Steps 8-9.
+        Ok(self.execute(IndexedDBOperation::Put {
+            key: Some(primary_key),
+            value: data.move_to_arraybuffer(),
+            index_keys: index_keys,
+            overwrite: true,
+        }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-delete
+    fn Delete(&self) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-5.
+        let primary_key = self.check_can_write()?;
+
+        // Steps 6-7.
+        Ok(self.execute(IndexedDBOperation::Delete(IndexedDBKeyRange::only(primary_key))))
+    }
+}
+
+impl From<IDBCursorDirection> for IndexedDBCursorDirection {
+    fn from(direction: IDBCursorDirection) -> IndexedDBCursorDirection {
+        match direction {
+            IDBCursorDirection::Next => IndexedDBCursorDirection::Next,
+            IDBCursorDirection::Nextunique => IndexedDBCursorDirection::NextUnique,
+            IDBCursorDirection::Prev => IndexedDBCursorDirection::Prev,
+            IDBCursorDirection::Prevunique => IndexedDBCursorDirection::PrevUnique,
+        }
+    }
+}
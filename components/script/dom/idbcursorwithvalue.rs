@@ -0,0 +1,56 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::IDBCursorBinding::IDBCursorDirection;
+use dom::bindings::codegen::Bindings::IDBCursorWithValueBinding;
+use dom::bindings::codegen::Bindings::IDBCursorWithValueBinding::IDBCursorWithValueMethods;
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::DomRoot;
+use dom::globalscope::GlobalScope;
+use dom::idbcursor::IDBCursor;
+use dom::idbindex::IDBIndex;
+use dom::idbobjectstore::IDBObjectStore;
+use dom::idbrequest::IDBRequest;
+use dom_struct::dom_struct;
+use js::jsapi::JSContext;
+use js::jsval::JSVal;
+use net_traits::indexeddb_thread::IndexedDBKeyRange;
+
+#[dom_struct]
+pub struct IDBCursorWithValue {
+    cursor: IDBCursor,
+}
+
+impl IDBCursorWithValue {
+    fn new_inherited(store: &IDBObjectStore,
+                     index: Option<&IDBIndex>,
+                     direction: IDBCursorDirection,
+                     range: IndexedDBKeyRange,
+                     request: &IDBRequest)
+                     -> IDBCursorWithValue {
+        IDBCursorWithValue {
+            cursor: IDBCursor::new_inherited(store, index, direction, range, false, request),
+        }
+    }
+
+    pub fn new(global: &GlobalScope,
+               store: &IDBObjectStore,
+               index: Option<&IDBIndex>,
+               direction: IDBCursorDirection,
+               range: IndexedDBKeyRange,
+               request: &IDBRequest)
+               -> DomRoot<IDBCursorWithValue> {
+        reflect_dom_object(Box::new(IDBCursorWithValue::new_inherited(store, index, direction, range, request)),
+                           global,
+                           IDBCursorWithValueBinding::Wrap)
+    }
+}
+
+impl IDBCursorWithValueMethods for IDBCursorWithValue {
+    // https://w3c.github.io/IndexedDB/#dom-idbcursorwithvalue-value
+    #[allow(unsafe_code)]
+    unsafe fn Value(&self, _cx: *mut JSContext) -> JSVal {
+        self.cursor.value()
+    }
+}
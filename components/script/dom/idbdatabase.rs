@@ -0,0 +1,302 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::IDBDatabaseBinding;
+use dom::bindings::codegen::Bindings::IDBDatabaseBinding::{IDBDatabaseMethods, IDBObjectStoreParameters};
+use dom::bindings::codegen::Bindings::IDBTransactionBinding::IDBTransactionMode;
+use dom::bindings::codegen::UnionTypes::StringOrStringSequence;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{DomRoot, MutNullableDom};
+use dom::bindings::str::DOMString;
+use dom::domstringlist::DOMStringList;
+use dom::eventtarget::EventTarget;
+use dom::globalscope::GlobalScope;
+use dom::idbobjectstore::IDBObjectStore;
+use dom::idbtransaction::IDBTransaction;
+use dom::idbversionchangeevent::IDBVersionChangeEvent;
+use dom_struct::dom_struct;
+use indexed_db::{is_valid_key_path, request_sync};
+use ipc_channel::ipc::IpcSender;
+use net_traits::IpcSend;
+use net_traits::indexeddb_thread::{IndexedDBConnectionEvent, IndexedDBConnectionId, IndexedDBDatabaseMetadata};
+use net_traits::indexeddb_thread::{IndexedDBKeyPath, IndexedDBObjectStoreMetadata, IndexedDBThreadMsg};
+use std::cell::Cell;
+
+#[dom_struct]
+pub struct IDBDatabase {
+    eventtarget: EventTarget,
+    connection: IndexedDBConnectionId,
+    /// The metadata of the database, as seen by this connection.
+    metadata: DomRefCell<IndexedDBDatabaseMetadata>,
+    /// The metadata of the database before the running upgrade transaction, if any.
+    previous_metadata: DomRefCell<Option<IndexedDBDatabaseMetadata>>,
+    /// <https://w3c.github.io/IndexedDB/#connection-close-pending-flag>
+    close_pending: Cell<bool>,
+    upgrade_transaction: MutNullableDom<IDBTransaction>,
+}
+
+impl IDBDatabase {
+    fn new_inherited(connection: IndexedDBConnectionId, metadata: IndexedDBDatabaseMetadata) -> IDBDatabase {
+        IDBDatabase {
+            eventtarget: EventTarget::new_inherited(),
+            connection: connection,
+            metadata: DomRefCell::new(metadata),
+            previous_metadata: DomRefCell::new(None),
+            close_pending: Cell::new(false),
+            upgrade_transaction: Default::default(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope,
+               connection: IndexedDBConnectionId,
+               metadata: IndexedDBDatabaseMetadata)
+               -> DomRoot<IDBDatabase> {
+        reflect_dom_object(Box::new(IDBDatabase::new_inherited(connection, metadata)),
+                           global,
+                           IDBDatabaseBinding::Wrap)
+    }
+
+    fn sender(&self) -> IpcSender<IndexedDBThreadMsg> {
+        self.global().resource_threads().sender()
+    }
+
+    pub fn has_object_store(&self, name: &str) -> bool {
+        self.metadata.borrow().object_stores.iter().any(|store| store.name == name)
+    }
+
+    pub fn object_store_metadata(&self, name: &str) -> Option<IndexedDBObjectStoreMetadata> {
+        self.metadata.borrow().object_stores.iter().find(|store| store.name == name).cloned()
+    }
+
+    /// Replace the metadata of an object store, after one of its indexes was created or deleted.
+    pub fn set_object_store_metadata(&self, metadata: IndexedDBObjectStoreMetadata) {
+        let mut database = self.metadata.borrow_mut();
+        if let Some(store) = database.object_stores.iter_mut().find(|store| store.name == metadata.name) {
+            *store = metadata;
+        }
+    }
+
+    /// Run the upgrade transaction of this connection, which upgraded the database from `old_version`.
+    pub fn start_upgrade(&self, transaction: &IDBTransaction, old_version: u64) {
+        let mut previous = self.metadata.borrow().clone();
+        previous.version = old_version;
+        if old_version == 0 {
+            previous.object_stores.clear();
+        }
+        *self.previous_metadata.borrow_mut() = Some(previous);
+        self.upgrade_transaction.set(Some(transaction));
+    }
+
+    /// Revert the metadata of this connection when its upgrade transaction is aborted.
+    /// <https://w3c.github.io/IndexedDB/#abort-an-upgrade-transaction>
+    pub fn revert_upgrade(&self) {
+        if let Some(previous) = self.previous_metadata.borrow_mut().take() {
+            *self.metadata.borrow_mut() = previous;
+        }
+    }
+
+    pub fn transaction_finished(&self, transaction: &IDBTransaction) {
+        let is_upgrade = self.upgrade_transaction.get().map_or(false, |upgrade| upgrade.id() == transaction.id());
+        if is_upgrade {
+            self.upgrade_transaction.set(None);
+            *self.previous_metadata.borrow_mut() = None;
+        }
+    }
+
+    /// The running upgrade transaction, which object stores are created and deleted within.
+    fn active_upgrade_transaction(&self) -> Fallible<DomRoot<IDBTransaction>> {
+        let transaction = match self.upgrade_transaction.get() {
+            Some(ref transaction) if !transaction.is_finished() => DomRoot::from_ref(&**transaction),
+            _ => return Err(Error::InvalidState),
+        };
+        if !transaction.is_active() {
+            return Err(Error::TransactionInactive);
+        }
+        Ok(transaction)
+    }
+
+    /// Handle an event sent by the IndexedDB thread to this connection.
+    pub fn handle_event(&self, event: IndexedDBConnectionEvent) {
+        match event {
+            IndexedDBConnectionEvent::VersionChange { old_version, new_version } => {
+                // https://w3c.github.io/IndexedDB/#opening step 10.2
+                if !self.close_pending.get() {
+                    IDBVersionChangeEvent::fire(self.upcast(), atom!("versionchange"), old_version, new_version);
+                }
+            },
+            IndexedDBConnectionEvent::ForcedClose => {
+                // https://w3c.github.io/IndexedDB/#close-a-database-connection
+                if !self.close_pending.get() {
+                    self.close_pending.set(true);
+                    self.upcast::<EventTarget>().fire_event(atom!("close"));
+                }
+            },
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#close-a-database-connection>
+    pub fn close_connection(&self) {
+        if self.close_pending.get() {
+            return;
+        }
+        self.close_pending.set(true);
+        if self.sender().send(IndexedDBThreadMsg::Close(self.connection)).is_err() {
+            warn!("Could not close an IndexedDB connection.");
+        }
+    }
+}
+
+impl IDBDatabaseMethods for IDBDatabase {
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-name
+    fn Name(&self) -> DOMString {
+        DOMString::from(self.metadata.borrow().name.clone())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-version
+    fn Version(&self) -> u64 {
+        self.metadata.borrow().version
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-objectstorenames
+    fn ObjectStoreNames(&self) -> DomRoot<DOMStringList> {
+        let mut names: Vec<DOMString> = self.metadata.borrow().object_stores.iter()
+            .map(|store| DOMString::from(store.name.clone()))
+            .collect();
+        names.sort();
+        DOMStringList::new(&self.global(), names)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-transaction
+    fn Transaction(&self, store_names: StringOrStringSequence, mode: IDBTransactionMode)
+                   -> Fallible<DomRoot<IDBTransaction>> {
+        // Step 1.
+        if self.upgrade_transaction.get().map_or(false, |transaction| !transaction.is_finished()) {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 2.
+        if self.close_pending.get() {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 3.
+        let mut scope = match store_names {
+            StringOrStringSequence::String(name) => vec![name],
+            StringOrStringSequence::StringSequence(names) => names,
+        };
+        scope.sort();
+        scope.dedup();
+
+        // Step 4.
+        if scope.iter().any(|name| !self.has_object_store(name)) {
+            return Err(Error::NotFound);
+        }
+
+        // Step 5.
+        if scope.is_empty() {
+            return Err(Error::InvalidAccess);
+        }
+
+        // Step 6.
+        if mode == IDBTransactionMode::Versionchange {
+            return Err(Error::Type("Transactions cannot be created in versionchange mode".to_owned()));
+        }
+
+        // Steps 7-9.
+        let global = self.global();
+        let names = scope.iter().cloned().map(String::from).collect();
+        let id = request_sync(&global, |sender| {
+            IndexedDBThreadMsg::CreateTransaction(sender, self.connection, names, mode.into())
+        })?;
+        Ok(IDBTransaction::new(&global, self, id, mode, scope))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-close
+    fn Close(&self) {
+        self.close_connection();
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-createobjectstore
+    fn CreateObjectStore(&self, name: DOMString, options: &IDBObjectStoreParameters)
+                         -> Fallible<DomRoot<IDBObjectStore>> {
+        // Steps 1-4.
+        let transaction = self.active_upgrade_transaction()?;
+
+        // Steps 5-6.
+        let key_path = options.keyPath.as_ref().map(IndexedDBKeyPath::from);
+        if let Some(ref key_path) = key_path {
+            if !is_valid_key_path(key_path) {
+                return Err(Error::Syntax);
+            }
+        }
+
+        // Step 7.
+        if self.has_object_store(&name) {
+            return Err(Error::Constraint);
+        }
+
+        // Step 8.
+        let auto_increment = options.autoIncrement;
+        match key_path {
+            Some(IndexedDBKeyPath::String(ref path)) if auto_increment && path.is_empty() => {
+                return Err(Error::InvalidAccess);
+            },
+            Some(IndexedDBKeyPath::Sequence(_)) if auto_increment => return Err(Error::InvalidAccess),
+            _ => {},
+        }
+
+        // Steps 9-10.
+        let metadata = IndexedDBObjectStoreMetadata {
+            name: String::from(name.clone()),
+            key_path: key_path,
+            auto_increment: auto_increment,
+            indexes: vec![],
+        };
+        let global = self.global();
+        request_sync(&global, |sender| {
+            IndexedDBThreadMsg::CreateObjectStore(sender, transaction.id(), metadata.clone())
+        })?;
+        self.metadata.borrow_mut().object_stores.push(metadata);
+
+        // Step 11.
+        let store = IDBObjectStore::new(&global, &transaction, name);
+        transaction.add_store_handle(&store);
+        Ok(store)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-deleteobjectstore
+    fn DeleteObjectStore(&self, name: DOMString) -> ErrorResult {
+        // Steps 1-4.
+        let transaction = self.active_upgrade_transaction()?;
+
+        // Step 5.
+        if !self.has_object_store(&name) {
+            return Err(Error::NotFound);
+        }
+
+        // Step 6.
+        let global = self.global();
+        let store_name = String::from(name);
+        request_sync(&global, |sender| {
+            IndexedDBThreadMsg::DeleteObjectStore(sender, transaction.id(), store_name.clone())
+        })?;
+        self.metadata.borrow_mut().object_stores.retain(|store| store.name != store_name);
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-onabort
+    event_handler!(abort, GetOnabort, SetOnabort);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-onclose
+    event_handler!(close, GetOnclose, SetOnclose);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-onerror
+    event_handler!(error, GetOnerror, SetOnerror);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-onversionchange
+    event_handler!(versionchange, GetOnversionchange, SetOnversionchange);
+}
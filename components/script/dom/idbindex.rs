@@ -0,0 +1,179 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::IDBCursorBinding::IDBCursorDirection;
+use dom::bindings::codegen::Bindings::IDBIndexBinding;
+use dom::bindings::codegen::Bindings::IDBIndexBinding::IDBIndexMethods;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
+use dom::globalscope::GlobalScope;
+use dom::idbcursor::IDBCursor;
+use dom::idbkeyrange::convert_value_to_key_range;
+use dom::idbobjectstore::IDBObjectStore;
+use dom::idbrequest::IDBRequest;
+use dom_struct::dom_struct;
+use indexed_db::key_path_to_jsval;
+use js::jsapi::JSContext;
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::HandleValue;
+use net_traits::indexeddb_thread::{IndexedDBIndexMetadata, IndexedDBOperation};
+
+#[dom_struct]
+pub struct IDBIndex {
+    reflector_: Reflector,
+    name: DOMString,
+    object_store: Dom<IDBObjectStore>,
+}
+
+impl IDBIndex {
+    fn new_inherited(object_store: &IDBObjectStore, name: DOMString) -> IDBIndex {
+        IDBIndex {
+            reflector_: Reflector::new(),
+            name: name,
+            object_store: Dom::from_ref(object_store),
+        }
+    }
+
+    pub fn new(global: &GlobalScope, object_store: &IDBObjectStore, name: DOMString) -> DomRoot<IDBIndex> {
+        reflect_dom_object(Box::new(IDBIndex::new_inherited(object_store, name)),
+                           global,
+                           IDBIndexBinding::Wrap)
+    }
+
+    pub fn name(&self) -> DOMString {
+        self.name.clone()
+    }
+
+    pub fn object_store(&self) -> DomRoot<IDBObjectStore> {
+        DomRoot::from_ref(&*self.object_store)
+    }
+
+    /// The metadata of this index, or `None` if it or its object store was deleted.
+    fn metadata(&self) -> Option<IndexedDBIndexMetadata> {
+        self.object_store.metadata()
+            .and_then(|store| store.indexes.into_iter().find(|index| index.name == *self.name))
+    }
+
+    /// Check that a request can be placed against this index.
+    fn check_usable(&self) -> Fallible<()> {
+        self.object_store.check_usable(false)?;
+        if self.metadata().is_none() {
+            return Err(Error::InvalidState);
+        }
+        Ok(())
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#asynchronously-execute-a-request>
+    fn execute(&self, operation: IndexedDBOperation) -> DomRoot<IDBRequest> {
+        let request = IDBRequest::new(&self.global());
+        request.set_source_index(self);
+        self.object_store.transaction().execute(&request, self.object_store.name(), operation);
+        request
+    }
+
+    fn index_name(&self) -> Option<String> {
+        Some(String::from(self.name.clone()))
+    }
+}
+
+impl IDBIndexMethods for IDBIndex {
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-name
+    fn Name(&self) -> DOMString {
+        self.name.clone()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-objectstore
+    fn ObjectStore(&self) -> DomRoot<IDBObjectStore> {
+        self.object_store()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-keypath
+    #[allow(unsafe_code)]
+    unsafe fn KeyPath(&self, cx: *mut JSContext) -> JSVal {
+        let key_path = self.metadata().map(|metadata| metadata.key_path);
+        rooted!(in(cx) let mut value = UndefinedValue());
+        key_path_to_jsval(cx, key_path.as_ref(), value.handle_mut());
+        value.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-multientry
+    fn MultiEntry(&self) -> bool {
+        self.metadata().map_or(false, |metadata| metadata.multi_entry)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-unique
+    fn Unique(&self) -> bool {
+        self.metadata().map_or(false, |metadata| metadata.unique)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-get
+    #[allow(unsafe_code)]
+    unsafe fn Get(&self, cx: *mut JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_usable()?;
+        let range = convert_value_to_key_range(cx, query, true)?;
+        Ok(self.execute(IndexedDBOperation::Get { index: self.index_name(), range: range }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-getkey
+    #[allow(unsafe_code)]
+    unsafe fn GetKey(&self, cx: *mut JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_usable()?;
+        let range = convert_value_to_key_range(cx, query, true)?;
+        Ok(self.execute(IndexedDBOperation::GetKey { index: self.index_name(), range: range }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-getall
+    #[allow(unsafe_code)]
+    unsafe fn GetAll(&self, cx: *mut JSContext, query: HandleValue, count: Option<u32>)
+                     -> Fallible<DomRoot<IDBRequest>> {
+        self.check_usable()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        Ok(self.execute(IndexedDBOperation::GetAll {
+            index: self.index_name(),
+            range: range,
+            count: count.and_then(|count| if count == 0 { None } else { Some(count) }),
+        }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-getallkeys
+    #[allow(unsafe_code)]
+    unsafe fn GetAllKeys(&self, cx: *mut JSContext, query: HandleValue, count: Option<u32>)
+                         -> Fallible<DomRoot<IDBRequest>> {
+        self.check_usable()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        Ok(self.execute(IndexedDBOperation::GetAllKeys {
+            index: self.index_name(),
+            range: range,
+            count: count.and_then(|count| if count == 0 { None } else { Some(count) }),
+        }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-count
+    #[allow(unsafe_code)]
+    unsafe fn Count(&self, cx: *mut JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_usable()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        Ok(self.execute(IndexedDBOperation::Count { index: self.index_name(), range: range }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-opencursor
+    #[allow(unsafe_code)]
+    unsafe fn OpenCursor(&self, cx: *mut JSContext, query: HandleValue, direction: IDBCursorDirection)
+                         -> Fallible<DomRoot<IDBRequest>> {
+        self.check_usable()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        Ok(IDBCursor::open(&self.global(), &self.object_store, Some(self), range, direction, false))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-openkeycursor
+    #[allow(unsafe_code)]
+    unsafe fn OpenKeyCursor(&self, cx: *mut JSContext, query: HandleValue, direction: IDBCursorDirection)
+                            -> Fallible<DomRoot<IDBRequest>> {
+        self.check_usable()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        Ok(IDBCursor::open(&self.global(), &self.object_store, Some(self), range, direction, true))
+    }
+}
@@ -0,0 +1,166 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::IDBKeyRangeBinding;
+use dom::bindings::codegen::Bindings::IDBKeyRangeBinding::IDBKeyRangeMethods;
+use dom::bindings::conversions::root_from_handlevalue;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::globalscope::GlobalScope;
+use dom_struct::dom_struct;
+use indexed_db::{convert_value_to_key, key_to_jsval};
+use js::jsapi::JSContext;
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::HandleValue;
+use net_traits::indexeddb_thread::{IndexedDBKey, IndexedDBKeyRange};
+
+#[dom_struct]
+pub struct IDBKeyRange {
+    reflector_: Reflector,
+    range: IndexedDBKeyRange,
+}
+
+impl IDBKeyRange {
+    fn new_inherited(range: IndexedDBKeyRange) -> IDBKeyRange {
+        IDBKeyRange {
+            reflector_: Reflector::new(),
+            range: range,
+        }
+    }
+
+    pub fn new(global: &GlobalScope, range: IndexedDBKeyRange) -> DomRoot<IDBKeyRange> {
+        reflect_dom_object(Box::new(IDBKeyRange::new_inherited(range)),
+                           global,
+                           IDBKeyRangeBinding::Wrap)
+    }
+
+    pub fn range(&self) -> &IndexedDBKeyRange {
+        &self.range
+    }
+
+    #[allow(unsafe_code)]
+    unsafe fn bound_to_jsval(&self, bound: &Option<IndexedDBKey>) -> JSVal {
+        let cx = self.global().get_cx();
+        rooted!(in(cx) let mut value = UndefinedValue());
+        if let Some(ref key) = *bound {
+            key_to_jsval(cx, key, value.handle_mut());
+        }
+        value.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-only
+    #[allow(unsafe_code)]
+    pub unsafe fn Only(global: &GlobalScope, cx: *mut JSContext, value: HandleValue)
+                       -> Fallible<DomRoot<IDBKeyRange>> {
+        let key = convert_value_to_key(cx, value)?;
+        Ok(IDBKeyRange::new(global, IndexedDBKeyRange::only(key)))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-lowerbound
+    #[allow(unsafe_code)]
+    pub unsafe fn LowerBound(global: &GlobalScope, cx: *mut JSContext, lower: HandleValue, open: bool)
+                             -> Fallible<DomRoot<IDBKeyRange>> {
+        let lower = convert_value_to_key(cx, lower)?;
+        Ok(IDBKeyRange::new(global, IndexedDBKeyRange {
+            lower: Some(lower),
+            upper: None,
+            lower_open: open,
+            upper_open: true,
+        }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-upperbound
+    #[allow(unsafe_code)]
+    pub unsafe fn UpperBound(global: &GlobalScope, cx: *mut JSContext, upper: HandleValue, open: bool)
+                             -> Fallible<DomRoot<IDBKeyRange>> {
+        let upper = convert_value_to_key(cx, upper)?;
+        Ok(IDBKeyRange::new(global, IndexedDBKeyRange {
+            lower: None,
+            upper: Some(upper),
+            lower_open: true,
+            upper_open: open,
+        }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-bound
+    #[allow(unsafe_code)]
+    pub unsafe fn Bound(global: &GlobalScope,
+                        cx: *mut JSContext,
+                        lower: HandleValue,
+                        upper: HandleValue,
+                        lower_open: bool,
+                        upper_open: bool)
+                        -> Fallible<DomRoot<IDBKeyRange>> {
+        // Steps 1-4.
+        let lower = convert_value_to_key(cx, lower)?;
+        let upper = convert_value_to_key(cx, upper)?;
+
+        // Step 5.
+        if lower > upper || (lower == upper && (lower_open || upper_open)) {
+            return Err(Error::Data);
+        }
+
+        // Steps 6-7.
+        Ok(IDBKeyRange::new(global, IndexedDBKeyRange {
+            lower: Some(lower),
+            upper: Some(upper),
+            lower_open: lower_open,
+            upper_open: upper_open,
+        }))
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#convert-a-value-to-a-key-range>
+#[allow(unsafe_code)]
+pub unsafe fn convert_value_to_key_range(cx: *mut JSContext, value: HandleValue, null_disallowed: bool)
+                                         -> Fallible<IndexedDBKeyRange> {
+    // Step 1.
+    if let Ok(range) = root_from_handlevalue::<IDBKeyRange>(value) {
+        return Ok(range.range.clone());
+    }
+
+    // Step 2.
+    if value.is_undefined() || value.is_null() {
+        if null_disallowed {
+            return Err(Error::Data);
+        }
+        return Ok(IndexedDBKeyRange::unbounded());
+    }
+
+    // Steps 3-5.
+    let key = convert_value_to_key(cx, value)?;
+    Ok(IndexedDBKeyRange::only(key))
+}
+
+impl IDBKeyRangeMethods for IDBKeyRange {
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-lower
+    #[allow(unsafe_code)]
+    unsafe fn Lower(&self, _cx: *mut JSContext) -> JSVal {
+        self.bound_to_jsval(&self.range.lower)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-upper
+    #[allow(unsafe_code)]
+    unsafe fn Upper(&self, _cx: *mut JSContext) -> JSVal {
+        self.bound_to_jsval(&self.range.upper)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-loweropen
+    fn LowerOpen(&self) -> bool {
+        self.range.lower_open
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-upperopen
+    fn UpperOpen(&self) -> bool {
+        self.range.upper_open
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-includes
+    #[allow(unsafe_code)]
+    unsafe fn Includes(&self, cx: *mut JSContext, key: HandleValue) -> Fallible<bool> {
+        let key = convert_value_to_key(cx, key)?;
+        Ok(self.range.contains(&key))
+    }
+}
@@ -0,0 +1,439 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::IDBCursorBinding::IDBCursorDirection;
+use dom::bindings::codegen::Bindings::IDBObjectStoreBinding;
+use dom::bindings::codegen::Bindings::IDBObjectStoreBinding::{IDBIndexParameters, IDBObjectStoreMethods};
+use dom::bindings::codegen::Bindings::IDBTransactionBinding::IDBTransactionMode;
+use dom::bindings::codegen::UnionTypes::StringOrStringSequence;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
+use dom::bindings::structuredclone::StructuredCloneData;
+use dom::domexception::{DOMErrorName, DOMException};
+use dom::domstringlist::DOMStringList;
+use dom::globalscope::GlobalScope;
+use dom::idbcursor::IDBCursor;
+use dom::idbindex::IDBIndex;
+use dom::idbkeyrange::convert_value_to_key_range;
+use dom::idbrequest::IDBRequest;
+use dom::idbtransaction::IDBTransaction;
+use dom_struct::dom_struct;
+use indexed_db::{can_inject_key, convert_value_to_key, extract_index_keys, extract_key};
+use indexed_db::{is_valid_key_path, key_path_to_jsval, read_value, request_sync};
+use js::jsapi::JSContext;
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::HandleValue;
+use net_traits::indexeddb_thread::{IndexedDBIndexKeys, IndexedDBIndexMetadata, IndexedDBKeyPath};
+use net_traits::indexeddb_thread::{IndexedDBObjectStoreMetadata, IndexedDBOperation, IndexedDBThreadMsg};
+
+#[dom_struct]
+pub struct IDBObjectStore {
+    reflector_: Reflector,
+    name: DOMString,
+    transaction: Dom<IDBTransaction>,
+    /// The index handles created for this object store.
+    indexes: DomRefCell<Vec<Dom<IDBIndex>>>,
+}
+
+impl IDBObjectStore {
+    fn new_inherited(transaction: &IDBTransaction, name: DOMString) -> IDBObjectStore {
+        IDBObjectStore {
+            reflector_: Reflector::new(),
+            name: name,
+            transaction: Dom::from_ref(transaction),
+            indexes: DomRefCell::new(vec![]),
+        }
+    }
+
+    pub fn new(global: &GlobalScope, transaction: &IDBTransaction, name: DOMString) -> DomRoot<IDBObjectStore> {
+        reflect_dom_object(Box::new(IDBObjectStore::new_inherited(transaction, name)),
+                           global,
+                           IDBObjectStoreBinding::Wrap)
+    }
+
+    pub fn name(&self) -> DOMString {
+        self.name.clone()
+    }
+
+    pub fn transaction(&self) -> DomRoot<IDBTransaction> {
+        DomRoot::from_ref(&*self.transaction)
+    }
+
+    /// The metadata of this object store, or `None` if it was deleted.
+    pub fn metadata(&self) -> Option<IndexedDBObjectStoreMetadata> {
+        self.transaction.db().object_store_metadata(&self.name)
+    }
+
+    /// The key path generated keys are injected at, when values of this object store are read.
+    pub fn generated_key_path(&self) -> Option<String> {
+        match self.metadata() {
+            Some(IndexedDBObjectStoreMetadata { key_path: Some(IndexedDBKeyPath::String(path)),
+                                                auto_increment: true, .. }) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Check that a request can be placed against this object store, returning its metadata.
+    /// These are the common first steps of the request methods of `IDBObjectStore` and `IDBIndex`.
+    pub fn check_usable(&self, write: bool) -> Fallible<IndexedDBObjectStoreMetadata> {
+        let metadata = self.metadata().ok_or(Error::InvalidState)?;
+        if !self.transaction.is_active() {
+            return Err(Error::TransactionInactive);
+        }
+        if write && self.transaction.mode() == IDBTransactionMode::Readonly {
+            return Err(Error::ReadOnly);
+        }
+        Ok(metadata)
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#asynchronously-execute-a-request>
+    fn execute(&self, operation: IndexedDBOperation) -> DomRoot<IDBRequest> {
+        let request = IDBRequest::new(&self.global());
+        request.set_source_store(self);
+        self.transaction.execute(&request, self.name.clone(), operation);
+        request
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#add-or-put>
+    #[allow(unsafe_code)]
+    pub unsafe fn put(&self, cx: *mut JSContext, value: HandleValue, key: HandleValue, overwrite: bool)
+                      -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-5.
+        let metadata = self.check_usable(true)?;
+        let key_given = !key.is_undefined();
+
+        // Step 6.
+        if metadata.key_path.is_some() && key_given {
+            return Err(Error::Data);
+        }
+
+        // Step 7.
+        if metadata.key_path.is_none() && !metadata.auto_increment && !key_given {
+            return Err(Error::Data);
+        }
+
+        // Step 8.
+        let mut key = if key_given { Some(convert_value_to_key(cx, key)?) } else { None };
+
+        // Step 9.
+        let data = StructuredCloneData::write(cx, value)?;
+
+        // Step 10.
+        if let Some(ref key_path) = metadata.key_path {
+            key = extract_key(cx, value, key_path)?;
+            if key.is_none() {
+                match *key_path {
+                    IndexedDBKeyPath::String(ref path) if metadata.auto_increment => {
+                        if !can_inject_key(cx, value, path)? {
+                            return Err(Error::Data);
+                        }
+                    },
+                    _ => return Err(Error::Data),
+                }
+            }
+        }
+
+        // https://w3c.github.io/IndexedDB/#store-a-record-into-an-object-store step 5
+        let mut index_keys = vec![];
+        for index in &metadata.indexes {
+            index_keys.push(IndexedDBIndexKeys {
+                index: index.name.clone(),
+                keys: extract_index_keys(cx, value, index)?,
+            });
+        }
+
+        // Step 11.
+        Ok(self.execute(IndexedDBOperation::Put {
+            key: key,
+            value: data.move_to_arraybuffer(),
+            index_keys: index_keys,
+            overwrite: overwrite,
+        }))
+    }
+}
+
+impl IDBObjectStoreMethods for IDBObjectStore {
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-name
+    fn Name(&self) -> DOMString {
+        self.name.clone()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-keypath
+    #[allow(unsafe_code)]
+    unsafe fn KeyPath(&self, cx: *mut JSContext) -> JSVal {
+        let key_path = self.metadata().and_then(|metadata| metadata.key_path);
+        rooted!(in(cx) let mut value = UndefinedValue());
+        key_path_to_jsval(cx, key_path.as_ref(), value.handle_mut());
+        value.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-indexnames
+    fn IndexNames(&self) -> DomRoot<DOMStringList> {
+        let mut names: Vec<DOMString> = self.metadata().map_or(vec![], |metadata| {
+            metadata.indexes.into_iter().map(|index| DOMString::from(index.name)).collect()
+        });
+        names.sort();
+        DOMStringList::new(&self.global(), names)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-transaction
+    fn Transaction(&self) -> DomRoot<IDBTransaction> {
+        self.transaction()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-autoincrement
+    fn AutoIncrement(&self) -> bool {
+        self.metadata().map_or(false, |metadata| metadata.auto_increment)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-put
+    #[allow(unsafe_code)]
+    unsafe fn Put(&self, cx: *mut JSContext, value: HandleValue, key: HandleValue)
+                  -> Fallible<DomRoot<IDBRequest>> {
+        self.put(cx, value, key, true)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-add
+    #[allow(unsafe_code)]
+    unsafe fn Add(&self, cx: *mut JSContext, value: HandleValue, key: HandleValue)
+                  -> Fallible<DomRoot<IDBRequest>> {
+        self.put(cx, value, key, false)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-delete
+    #[allow(unsafe_code)]
+    unsafe fn Delete(&self, cx: *mut JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-5.
+        self.check_usable(true)?;
+
+        // Step 6.
+        let range = convert_value_to_key_range(cx, query, true)?;
+
+        // Step 7.
+        Ok(self.execute(IndexedDBOperation::Delete(range)))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-clear
+    fn Clear(&self) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_usable(true)?;
+        Ok(self.execute(IndexedDBOperation::Clear))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-get
+    #[allow(unsafe_code)]
+    unsafe fn Get(&self, cx: *mut JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_usable(false)?;
+        let range = convert_value_to_key_range(cx, query, true)?;
+        Ok(self.execute(IndexedDBOperation::Get { index: None, range: range }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-getkey
+    #[allow(unsafe_code)]
+    unsafe fn GetKey(&self, cx: *mut JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_usable(false)?;
+        let range = convert_value_to_key_range(cx, query, true)?;
+        Ok(self.execute(IndexedDBOperation::GetKey { index: None, range: range }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-getall
+    #[allow(unsafe_code)]
+    unsafe fn GetAll(&self, cx: *mut JSContext, query: HandleValue, count: Option<u32>)
+                     -> Fallible<DomRoot<IDBRequest>> {
+        self.check_usable(false)?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        Ok(self.execute(IndexedDBOperation::GetAll {
+            index: None,
+            range: range,
+            count: count.and_then(|count| if count == 0 { None } else { Some(count) }),
+        }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-getallkeys
+    #[allow(unsafe_code)]
+    unsafe fn GetAllKeys(&self, cx: *mut JSContext, query: HandleValue, count: Option<u32>)
+                         -> Fallible<DomRoot<IDBRequest>> {
+        self.check_usable(false)?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        Ok(self.execute(IndexedDBOperation::GetAllKeys {
+            index: None,
+            range: range,
+            count: count.and_then(|count| if count == 0 { None } else { Some(count) }),
+        }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-count
+    #[allow(unsafe_code)]
+    unsafe fn Count(&self, cx: *mut JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_usable(false)?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        Ok(self.execute(IndexedDBOperation::Count { index: None, range: range }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-opencursor
+    #[allow(unsafe_code)]
+    unsafe fn OpenCursor(&self, cx: *mut JSContext, query: HandleValue, direction: IDBCursorDirection)
+                         -> Fallible<DomRoot<IDBRequest>> {
+        self.check_usable(false)?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        Ok(IDBCursor::open(&self.global(), self, None, range, direction, false))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-openkeycursor
+    #[allow(unsafe_code)]
+    unsafe fn OpenKeyCursor(&self, cx: *mut JSContext, query: HandleValue, direction: IDBCursorDirection)
+                            -> Fallible<DomRoot<IDBRequest>> {
+        self.check_usable(false)?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        Ok(IDBCursor::open(&self.global(), self, None, range, direction, true))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-index
+    fn Index(&self, name: DOMString) -> Fallible<DomRoot<IDBIndex>> {
+        // Steps 1-4.
+        let metadata = self.metadata().ok_or(Error::InvalidState)?;
+        if self.transaction.is_finished() {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 5.
+        if !metadata.indexes.iter().any(|index| index.name == *name) {
+            return Err(Error::NotFound);
+        }
+
+        // Step 6.
+        if let Some(index) = self.indexes.borrow().iter().find(|index| index.name() == name) {
+            return Ok(DomRoot::from_ref(&**index));
+        }
+        let index = IDBIndex::new(&self.global(), self, name);
+        self.indexes.borrow_mut().push(Dom::from_ref(&*index));
+        Ok(index)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-createindex
+    #[allow(unsafe_code)]
+    fn CreateIndex(&self,
+                   name: DOMString,
+                   key_path: StringOrStringSequence,
+                   options: &IDBIndexParameters)
+                   -> Fallible<DomRoot<IDBIndex>> {
+        // Steps 1-3.
+        if self.transaction.mode() != IDBTransactionMode::Versionchange {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 4.
+        let mut metadata = self.metadata().ok_or(Error::InvalidState)?;
+
+        // Step 5.
+        if !self.transaction.is_active() {
+            return Err(Error::TransactionInactive);
+        }
+
+        // Step 6.
+        if metadata.indexes.iter().any(|index| index.name == *name) {
+            return Err(Error::Constraint);
+        }
+
+        // Step 7.
+        let key_path = IndexedDBKeyPath::from(&key_path);
+        if !is_valid_key_path(&key_path) {
+            return Err(Error::Syntax);
+        }
+
+        // Step 10.
+        if let IndexedDBKeyPath::Sequence(_) = key_path {
+            if options.multiEntry {
+                return Err(Error::InvalidAccess);
+            }
+        }
+
+        // Step 11.
+        let index_metadata = IndexedDBIndexMetadata {
+            name: String::from(name.clone()),
+            key_path: key_path,
+            unique: options.unique,
+            multi_entry: options.multiEntry,
+        };
+        let global = self.global();
+        let transaction_id = self.transaction.id();
+        let store_name = String::from(self.name.clone());
+        let records = request_sync(&global, |sender| {
+            IndexedDBThreadMsg::CreateIndex(sender, transaction_id, store_name.clone(), index_metadata.clone())
+        })?;
+
+        // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-createindex step 12:
+        // populate the index with the records already stored.
+        let generated_key_path = self.generated_key_path();
+        let cx = global.get_cx();
+        let mut entries = vec![];
+        for record in records {
+            rooted!(in(cx) let mut value = UndefinedValue());
+            let keys = unsafe {
+                let generated_key = generated_key_path.as_ref().map(|path| (&record.key, &**path));
+                read_value(&global, record.value, generated_key, value.handle_mut());
+                extract_index_keys(cx, value.handle(), &index_metadata)?
+            };
+            entries.push((record.key, keys));
+        }
+        let result = request_sync(&global, |sender| {
+            IndexedDBThreadMsg::SetIndexKeys(sender, transaction_id, store_name, index_metadata.name.clone(), entries)
+        });
+        match result {
+            Ok(()) => {},
+            Err(Error::Constraint) => {
+                // The existing records violate the uniqueness of the index.
+                let error = DOMException::new(&global, DOMErrorName::ConstraintError);
+                self.transaction.abort(Some(error));
+            },
+            Err(Error::QuotaExceeded) => {
+                // The index of the existing records doesn't fit in the quota of the origin.
+                let error = DOMException::new(&global, DOMErrorName::QuotaExceededError);
+                self.transaction.abort(Some(error));
+            },
+            Err(error) => return Err(error),
+        }
+
+        metadata.indexes.push(index_metadata);
+        self.transaction.db().set_object_store_metadata(metadata);
+        let index = IDBIndex::new(&global, self, name);
+        self.indexes.borrow_mut().push(Dom::from_ref(&*index));
+        Ok(index)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-deleteindex
+    fn DeleteIndex(&self, name: DOMString) -> ErrorResult {
+        // Steps 1-3.
+        if self.transaction.mode() != IDBTransactionMode::Versionchange {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 4.
+        let mut metadata = self.metadata().ok_or(Error::InvalidState)?;
+
+        // Step 5.
+        if !self.transaction.is_active() {
+            return Err(Error::TransactionInactive);
+        }
+
+        // Step 6.
+        if !metadata.indexes.iter().any(|index| index.name == *name) {
+            return Err(Error::NotFound);
+        }
+
+        // Steps 7-8.
+        let transaction_id = self.transaction.id();
+        let store_name = String::from(self.name.clone());
+        let index_name = String::from(name.clone());
+        request_sync(&self.global(), |sender| {
+            IndexedDBThreadMsg::DeleteIndex(sender, transaction_id, store_name, index_name)
+        })?;
+        metadata.indexes.retain(|index| index.name != *name);
+        self.transaction.db().set_object_store_metadata(metadata);
+        self.indexes.borrow_mut().retain(|index| index.name() != name);
+        Ok(())
+    }
+}
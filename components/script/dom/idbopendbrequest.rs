@@ -0,0 +1,191 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::IDBDatabaseBinding::IDBDatabaseMethods;
+use dom::bindings::codegen::Bindings::IDBOpenDBRequestBinding;
+use dom::bindings::codegen::Bindings::IDBOpenDBRequestBinding::IDBOpenDBRequestMethods;
+use dom::bindings::codegen::Bindings::IDBTransactionBinding::IDBTransactionMode;
+use dom::bindings::conversions::ToJSValConvertible;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{DomRoot, MutNullableDom};
+use dom::domexception::DOMErrorName;
+use dom::globalscope::GlobalScope;
+use dom::idbdatabase::IDBDatabase;
+use dom::idbrequest::{IDBRequest, dom_error_name};
+use dom::idbtransaction::IDBTransaction;
+use dom::idbversionchangeevent::IDBVersionChangeEvent;
+use dom_struct::dom_struct;
+use indexed_db::route_replies;
+use ipc_channel::ipc::{self, IpcSender};
+use js::jsval::UndefinedValue;
+use net_traits::IpcSend;
+use net_traits::indexeddb_thread::{IndexedDBConnectionEvent, IndexedDBDeleteResponse};
+use net_traits::indexeddb_thread::{IndexedDBOpenResponse, IndexedDBThreadMsg, IndexedDBTransactionId};
+
+#[dom_struct]
+pub struct IDBOpenDBRequest {
+    request: IDBRequest,
+    /// The connection opened by this request.
+    database: MutNullableDom<IDBDatabase>,
+}
+
+impl IDBOpenDBRequest {
+    fn new_inherited() -> IDBOpenDBRequest {
+        IDBOpenDBRequest {
+            request: IDBRequest::new_inherited(),
+            database: Default::default(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<IDBOpenDBRequest> {
+        reflect_dom_object(Box::new(IDBOpenDBRequest::new_inherited()),
+                           global,
+                           IDBOpenDBRequestBinding::Wrap)
+    }
+
+    fn sender(global: &GlobalScope) -> IpcSender<IndexedDBThreadMsg> {
+        global.resource_threads().sender()
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#dom-idbfactory-open> steps 5-6
+    pub fn open(global: &GlobalScope, name: String, version: Option<u64>) -> DomRoot<IDBOpenDBRequest> {
+        let request = IDBOpenDBRequest::new(global);
+
+        let (sender, receiver) = ipc::channel().unwrap();
+        let this = Trusted::new(&*request);
+        route_replies(global, receiver, move |response| {
+            this.root().handle_open_response(response);
+        });
+
+        let (events_sender, events_receiver) = ipc::channel().unwrap();
+        let this = Trusted::new(&*request);
+        route_replies(global, events_receiver, move |event| {
+            this.root().handle_connection_event(event);
+        });
+
+        let message = IndexedDBThreadMsg::Open {
+            sender: sender,
+            events: events_sender,
+            origin: global.origin().immutable().clone(),
+            name: name,
+            version: version,
+        };
+        if IDBOpenDBRequest::sender(global).send(message).is_err() {
+            warn!("Could not open an IndexedDB database.");
+        }
+        request
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#dom-idbfactory-deletedatabase> steps 4-5
+    pub fn delete_database(global: &GlobalScope, name: String) -> DomRoot<IDBOpenDBRequest> {
+        let request = IDBOpenDBRequest::new(global);
+
+        let (sender, receiver) = ipc::channel().unwrap();
+        let this = Trusted::new(&*request);
+        route_replies(global, receiver, move |response| {
+            this.root().handle_delete_response(response);
+        });
+
+        let message = IndexedDBThreadMsg::DeleteDatabase(sender, global.origin().immutable().clone(), name);
+        if IDBOpenDBRequest::sender(global).send(message).is_err() {
+            warn!("Could not delete an IndexedDB database.");
+        }
+        request
+    }
+
+    #[allow(unsafe_code)]
+    fn set_database_result(&self, database: &IDBDatabase) {
+        let cx = self.global().get_cx();
+        rooted!(in(cx) let mut value = UndefinedValue());
+        unsafe { database.to_jsval(cx, value.handle_mut()) };
+        self.request.set_done(value.handle());
+    }
+
+    fn handle_open_response(&self, response: IndexedDBOpenResponse) {
+        match response {
+            IndexedDBOpenResponse::Blocked { old_version, new_version } => {
+                // https://w3c.github.io/IndexedDB/#opening step 10.4
+                IDBVersionChangeEvent::fire(self.upcast(), atom!("blocked"), old_version, Some(new_version));
+            },
+            IndexedDBOpenResponse::Opened { connection, metadata, upgrade } => {
+                let global = self.global();
+                let database = IDBDatabase::new(&global, connection, metadata);
+                self.database.set(Some(&database));
+                self.set_database_result(&database);
+                match upgrade {
+                    None => self.request.fire_success_event(),
+                    Some((transaction, old_version)) => self.run_upgrade(&database, transaction, old_version),
+                }
+            },
+            IndexedDBOpenResponse::UpgradeFinished(result) => {
+                // https://w3c.github.io/IndexedDB/#run-an-upgrade-transaction step 10
+                self.request.set_transaction(None);
+                match result {
+                    Ok(()) => self.request.fire_success_event(),
+                    Err(_) => {
+                        if let Some(database) = self.database.get() {
+                            database.close_connection();
+                        }
+                        self.request.set_failed(DOMErrorName::AbortError);
+                        self.request.fire_error_event();
+                    },
+                }
+            },
+            IndexedDBOpenResponse::Error(error) => {
+                self.request.set_failed(dom_error_name(error));
+                self.request.fire_error_event();
+            },
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#run-an-upgrade-transaction>
+    fn run_upgrade(&self, database: &IDBDatabase, id: IndexedDBTransactionId, old_version: u64) {
+        // Steps 1-6.
+        let global = self.global();
+        let transaction = IDBTransaction::new(&global, database, id, IDBTransactionMode::Versionchange, vec![]);
+        database.start_upgrade(&transaction, old_version);
+        self.request.set_transaction(Some(&transaction));
+
+        // Steps 7-8.
+        IDBVersionChangeEvent::fire(self.upcast(),
+                                    atom!("upgradeneeded"),
+                                    old_version,
+                                    Some(database.Version()));
+
+        // Step 9.
+        transaction.set_active(false);
+        transaction.maybe_commit();
+    }
+
+    fn handle_delete_response(&self, response: IndexedDBDeleteResponse) {
+        match response {
+            IndexedDBDeleteResponse::Blocked { old_version } => {
+                // https://w3c.github.io/IndexedDB/#deleting-a-database step 6.3
+                IDBVersionChangeEvent::fire(self.upcast(), atom!("blocked"), old_version, None);
+            },
+            IndexedDBDeleteResponse::Deleted(old_version) => {
+                // https://w3c.github.io/IndexedDB/#dom-idbfactory-deletedatabase step 5.3
+                rooted!(in(self.global().get_cx()) let value = UndefinedValue());
+                self.request.set_done(value.handle());
+                IDBVersionChangeEvent::fire(self.upcast(), atom!("success"), old_version, None);
+            },
+        }
+    }
+
+    fn handle_connection_event(&self, event: IndexedDBConnectionEvent) {
+        if let Some(database) = self.database.get() {
+            database.handle_event(event);
+        }
+    }
+}
+
+impl IDBOpenDBRequestMethods for IDBOpenDBRequest {
+    // https://w3c.github.io/IndexedDB/#dom-idbopendbrequest-onblocked
+    event_handler!(blocked, GetOnblocked, SetOnblocked);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbopendbrequest-onupgradeneeded
+    event_handler!(upgradeneeded, GetOnupgradeneeded, SetOnupgradeneeded);
+}
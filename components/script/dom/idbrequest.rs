@@ -0,0 +1,288 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::IDBRequestBinding;
+use dom::bindings::codegen::Bindings::IDBRequestBinding::{IDBRequestMethods, IDBRequestReadyState};
+use dom::bindings::codegen::UnionTypes::IDBObjectStoreOrIDBIndexOrIDBCursor;
+use dom::bindings::conversions::ToJSValConvertible;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{DomRoot, MutNullableDom};
+use dom::domexception::{DOMErrorName, DOMException};
+use dom::event::{Event, EventBubbles, EventCancelable, EventStatus};
+use dom::eventtarget::EventTarget;
+use dom::globalscope::GlobalScope;
+use dom::idbcursor::IDBCursor;
+use dom::idbindex::IDBIndex;
+use dom::idbobjectstore::IDBObjectStore;
+use dom::idbtransaction::IDBTransaction;
+use dom_struct::dom_struct;
+use indexed_db::{key_to_jsval, read_value};
+use js::jsapi::{Heap, JSContext, JS_NewArrayObject1};
+use js::jsapi::JSPROP_ENUMERATE;
+use js::jsval::{DoubleValue, JSVal, NullValue, ObjectValue, UndefinedValue};
+use js::rust::{HandleValue, MutableHandleValue};
+use js::rust::wrappers::JS_DefineElement;
+use net_traits::indexeddb_thread::{IndexedDBError, IndexedDBOperationResult, IndexedDBResult};
+use std::cell::Cell;
+
+/// The `DOMException` matching an error reported by the IndexedDB thread.
+pub fn dom_error_name(error: IndexedDBError) -> DOMErrorName {
+    match error {
+        IndexedDBError::Abort => DOMErrorName::AbortError,
+        IndexedDBError::Constraint => DOMErrorName::ConstraintError,
+        IndexedDBError::Data => DOMErrorName::DataError,
+        IndexedDBError::InvalidState => DOMErrorName::InvalidStateError,
+        IndexedDBError::NotFound => DOMErrorName::NotFoundError,
+        IndexedDBError::QuotaExceeded => DOMErrorName::QuotaExceededError,
+        IndexedDBError::Version => DOMErrorName::VersionError,
+    }
+}
+
+#[dom_struct]
+pub struct IDBRequest {
+    eventtarget: EventTarget,
+    #[ignore_malloc_size_of = "Defined in rust-mozjs"]
+    result: Heap<JSVal>,
+    error: MutNullableDom<DOMException>,
+    source_store: MutNullableDom<IDBObjectStore>,
+    source_index: MutNullableDom<IDBIndex>,
+    source_cursor: MutNullableDom<IDBCursor>,
+    /// The cursor iterated by this request, if it was opened by `openCursor` or `openKeyCursor`.
+    cursor: MutNullableDom<IDBCursor>,
+    transaction: MutNullableDom<IDBTransaction>,
+    ready_state: Cell<IDBRequestReadyState>,
+}
+
+impl IDBRequest {
+    pub fn new_inherited() -> IDBRequest {
+        IDBRequest {
+            eventtarget: EventTarget::new_inherited(),
+            result: Heap::default(),
+            error: Default::default(),
+            source_store: Default::default(),
+            source_index: Default::default(),
+            source_cursor: Default::default(),
+            cursor: Default::default(),
+            transaction: Default::default(),
+            ready_state: Cell::new(IDBRequestReadyState::Pending),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<IDBRequest> {
+        reflect_dom_object(Box::new(IDBRequest::new_inherited()),
+                           global,
+                           IDBRequestBinding::Wrap)
+    }
+
+    pub fn set_source_store(&self, store: &IDBObjectStore) {
+        self.source_store.set(Some(store));
+    }
+
+    pub fn set_source_index(&self, index: &IDBIndex) {
+        self.source_index.set(Some(index));
+    }
+
+    pub fn set_source_cursor(&self, cursor: &IDBCursor) {
+        self.source_cursor.set(Some(cursor));
+    }
+
+    pub fn set_cursor(&self, cursor: &IDBCursor) {
+        self.cursor.set(Some(cursor));
+    }
+
+    pub fn set_transaction(&self, transaction: Option<&IDBTransaction>) {
+        self.transaction.set(transaction);
+    }
+
+    pub fn set_result(&self, result: HandleValue) {
+        self.result.set(result.get());
+    }
+
+    pub fn set_error(&self, error: Option<&DOMException>) {
+        self.error.set(error);
+    }
+
+    /// Mark this request as done, with the given result.
+    pub fn set_done(&self, result: HandleValue) {
+        self.set_result(result);
+        self.error.set(None);
+        self.ready_state.set(IDBRequestReadyState::Done);
+    }
+
+    /// Mark this request as done, failed with the given error.
+    pub fn set_failed(&self, error: DOMErrorName) {
+        self.result.set(UndefinedValue());
+        self.error.set(Some(&DOMException::new(&self.global(), error)));
+        self.ready_state.set(IDBRequestReadyState::Done);
+    }
+
+    /// Mark this request as pending again, when its cursor is iterated.
+    pub fn reset(&self) {
+        self.result.set(UndefinedValue());
+        self.error.set(None);
+        self.ready_state.set(IDBRequestReadyState::Pending);
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#fire-a-success-event>
+    pub fn fire_success_event(&self) {
+        let event = Event::new(&self.global(),
+                               atom!("success"),
+                               EventBubbles::DoesNotBubble,
+                               EventCancelable::NotCancelable);
+        let transaction = self.transaction.get();
+        if let Some(ref transaction) = transaction {
+            transaction.set_active(true);
+        }
+        event.fire(self.upcast());
+        if let Some(ref transaction) = transaction {
+            transaction.set_active(false);
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#fire-an-error-event>
+    pub fn fire_error_event(&self) -> EventStatus {
+        let event = Event::new(&self.global(),
+                               atom!("error"),
+                               EventBubbles::Bubbles,
+                               EventCancelable::Cancelable);
+        let transaction = self.transaction.get();
+        if let Some(ref transaction) = transaction {
+            transaction.set_active(true);
+        }
+        let status = event.fire(self.upcast());
+        if let Some(ref transaction) = transaction {
+            transaction.set_active(false);
+        }
+        status
+    }
+
+    /// Process the outcome of the operation this request was placed for.
+    /// <https://w3c.github.io/IndexedDB/#asynchronously-execute-a-request> steps 5-6
+    pub fn handle_result(&self, result: IndexedDBResult<IndexedDBOperationResult>) {
+        let transaction = self.transaction.get().expect("IndexedDB requests belong to a transaction");
+        transaction.request_finished();
+
+        // Requests which completed before their transaction was aborted are failed too.
+        let result = if transaction.is_aborted() { Err(IndexedDBError::Abort) } else { result };
+        match result {
+            Ok(result) => {
+                let cx = self.global().get_cx();
+                rooted!(in(cx) let mut value = UndefinedValue());
+                self.result_to_jsval(cx, result, value.handle_mut());
+                self.set_done(value.handle());
+                self.fire_success_event();
+            },
+            Err(error) => {
+                self.set_failed(dom_error_name(error));
+                let status = self.fire_error_event();
+                if status == EventStatus::NotCanceled && !transaction.is_finished() {
+                    transaction.abort(self.error.get());
+                }
+            },
+        }
+        transaction.maybe_commit();
+    }
+
+    /// The key path a generated primary key has to be injected at, in the values read by this request.
+    fn generated_key_path(&self) -> Option<String> {
+        let store = self.source_store.get().or_else(|| self.source_index.get().map(|index| index.object_store()));
+        store.and_then(|store| store.generated_key_path())
+    }
+
+    #[allow(unsafe_code)]
+    fn result_to_jsval(&self, cx: *mut JSContext, result: IndexedDBOperationResult, mut rval: MutableHandleValue) {
+        let global = self.global();
+        let key_path = self.generated_key_path();
+        unsafe {
+            match result {
+                IndexedDBOperationResult::Key(Some(key)) => key_to_jsval(cx, &key, rval),
+                IndexedDBOperationResult::Record(Some(record)) => {
+                    let key_path = key_path.as_ref().map(|key_path| (&record.key, &**key_path));
+                    read_value(&global, record.value, key_path, rval);
+                },
+                IndexedDBOperationResult::Records(records) => {
+                    rooted!(in(cx) let array = JS_NewArrayObject1(cx, records.len()));
+                    for (index, record) in records.into_iter().enumerate() {
+                        rooted!(in(cx) let mut value = UndefinedValue());
+                        let key_path = key_path.as_ref().map(|key_path| (&record.key, &**key_path));
+                        read_value(&global, record.value, key_path, value.handle_mut());
+                        JS_DefineElement(cx, array.handle(), index as u32, value.handle(), JSPROP_ENUMERATE);
+                    }
+                    rval.set(ObjectValue(array.get()));
+                },
+                IndexedDBOperationResult::Keys(keys) => {
+                    rooted!(in(cx) let array = JS_NewArrayObject1(cx, keys.len()));
+                    for (index, key) in keys.iter().enumerate() {
+                        rooted!(in(cx) let mut value = UndefinedValue());
+                        key_to_jsval(cx, key, value.handle_mut());
+                        JS_DefineElement(cx, array.handle(), index as u32, value.handle(), JSPROP_ENUMERATE);
+                    }
+                    rval.set(ObjectValue(array.get()));
+                },
+                IndexedDBOperationResult::Count(count) => rval.set(DoubleValue(count as f64)),
+                IndexedDBOperationResult::Cursor(record) => {
+                    let cursor = self.cursor.get().expect("Cursor results are only sent to cursor requests");
+                    match record {
+                        Some(record) => {
+                            cursor.set_record(record);
+                            cursor.to_jsval(cx, rval);
+                        },
+                        None => rval.set(NullValue()),
+                    }
+                },
+                IndexedDBOperationResult::Key(None) |
+                IndexedDBOperationResult::Record(None) |
+                IndexedDBOperationResult::Done => rval.set(UndefinedValue()),
+            }
+        }
+    }
+}
+
+impl IDBRequestMethods for IDBRequest {
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-result
+    #[allow(unsafe_code)]
+    unsafe fn GetResult(&self, _cx: *mut JSContext) -> Fallible<JSVal> {
+        if self.ready_state.get() == IDBRequestReadyState::Pending {
+            return Err(Error::InvalidState);
+        }
+        Ok(self.result.get())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-error
+    fn GetError(&self) -> Fallible<Option<DomRoot<DOMException>>> {
+        if self.ready_state.get() == IDBRequestReadyState::Pending {
+            return Err(Error::InvalidState);
+        }
+        Ok(self.error.get())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-source
+    fn GetSource(&self) -> Option<IDBObjectStoreOrIDBIndexOrIDBCursor> {
+        if let Some(cursor) = self.source_cursor.get() {
+            return Some(IDBObjectStoreOrIDBIndexOrIDBCursor::IDBCursor(cursor));
+        }
+        if let Some(index) = self.source_index.get() {
+            return Some(IDBObjectStoreOrIDBIndexOrIDBCursor::IDBIndex(index));
+        }
+        self.source_store.get().map(IDBObjectStoreOrIDBIndexOrIDBCursor::IDBObjectStore)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-transaction
+    fn GetTransaction(&self) -> Option<DomRoot<IDBTransaction>> {
+        self.transaction.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-readystate
+    fn ReadyState(&self) -> IDBRequestReadyState {
+        self.ready_state.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-onsuccess
+    event_handler!(success, GetOnsuccess, SetOnsuccess);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-onerror
+    event_handler!(error, GetOnerror, SetOnerror);
+}
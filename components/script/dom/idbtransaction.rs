@@ -0,0 +1,297 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::IDBTransactionBinding;
+use dom::bindings::codegen::Bindings::IDBTransactionBinding::{IDBTransactionMethods, IDBTransactionMode};
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot, MutNullableDom, RootedReference};
+use dom::bindings::str::DOMString;
+use dom::domexception::DOMException;
+use dom::domstringlist::DOMStringList;
+use dom::eventtarget::EventTarget;
+use dom::globalscope::GlobalScope;
+use dom::idbdatabase::IDBDatabase;
+use dom::idbobjectstore::IDBObjectStore;
+use dom::idbrequest::IDBRequest;
+use dom_struct::dom_struct;
+use indexed_db::route_replies;
+use ipc_channel::ipc;
+use net_traits::IpcSend;
+use net_traits::indexeddb_thread::{IndexedDBOperation, IndexedDBResult, IndexedDBThreadMsg};
+use net_traits::indexeddb_thread::{IndexedDBTransactionId, IndexedDBTransactionMode};
+use profile_traits::ipc as ProfiledIpc;
+use std::cell::Cell;
+use task_source::{TaskSource, TaskSourceName};
+
+#[dom_struct]
+pub struct IDBTransaction {
+    eventtarget: EventTarget,
+    id: IndexedDBTransactionId,
+    db: Dom<IDBDatabase>,
+    mode: IDBTransactionMode,
+    /// The names of the object stores in the scope of this transaction.
+    /// Version change transactions are scoped to every object store of their database.
+    scope: Vec<DOMString>,
+    error: MutNullableDom<DOMException>,
+    /// <https://w3c.github.io/IndexedDB/#transaction-active>
+    active: Cell<bool>,
+    /// Whether this transaction committed or was aborted.
+    finished: Cell<bool>,
+    aborted: Cell<bool>,
+    commit_requested: Cell<bool>,
+    pending_requests: Cell<u32>,
+    /// The object store handles created for this transaction.
+    stores: DomRefCell<Vec<Dom<IDBObjectStore>>>,
+}
+
+impl IDBTransaction {
+    fn new_inherited(db: &IDBDatabase,
+                     id: IndexedDBTransactionId,
+                     mode: IDBTransactionMode,
+                     scope: Vec<DOMString>)
+                     -> IDBTransaction {
+        IDBTransaction {
+            eventtarget: EventTarget::new_inherited(),
+            id: id,
+            db: Dom::from_ref(db),
+            mode: mode,
+            scope: scope,
+            error: Default::default(),
+            active: Cell::new(true),
+            finished: Cell::new(false),
+            aborted: Cell::new(false),
+            commit_requested: Cell::new(false),
+            pending_requests: Cell::new(0),
+            stores: DomRefCell::new(vec![]),
+        }
+    }
+
+    pub fn new(global: &GlobalScope,
+               db: &IDBDatabase,
+               id: IndexedDBTransactionId,
+               mode: IDBTransactionMode,
+               scope: Vec<DOMString>)
+               -> DomRoot<IDBTransaction> {
+        let transaction = reflect_dom_object(Box::new(IDBTransaction::new_inherited(db, id, mode, scope)),
+                                             global,
+                                             IDBTransactionBinding::Wrap);
+        if mode != IDBTransactionMode::Versionchange {
+            // https://w3c.github.io/IndexedDB/#dom-idbdatabase-transaction step 8:
+            // the transaction is deactivated when control returns to the event loop.
+            let this = Trusted::new(&*transaction);
+            let _ = global.networking_task_source().queue_with_canceller(
+                task!(deactivate_transaction: move || {
+                    let transaction = this.root();
+                    transaction.set_active(false);
+                    transaction.maybe_commit();
+                }),
+                &global.task_canceller(TaskSourceName::Networking),
+            );
+        }
+        transaction
+    }
+
+    pub fn id(&self) -> IndexedDBTransactionId {
+        self.id
+    }
+
+    pub fn db(&self) -> DomRoot<IDBDatabase> {
+        DomRoot::from_ref(&*self.db)
+    }
+
+    pub fn mode(&self) -> IDBTransactionMode {
+        self.mode
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.get() && !self.finished.get()
+    }
+
+    pub fn set_active(&self, active: bool) {
+        self.active.set(active);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished.get()
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.aborted.get()
+    }
+
+    /// Whether the given object store can be used within this transaction.
+    fn is_in_scope(&self, name: &DOMString) -> bool {
+        self.mode == IDBTransactionMode::Versionchange || self.scope.contains(name)
+    }
+
+    fn sender(&self) -> ipc::IpcSender<IndexedDBThreadMsg> {
+        self.global().resource_threads().sender()
+    }
+
+    /// Keep track of a handle created by `IDBDatabase.createObjectStore`.
+    pub fn add_store_handle(&self, store: &IDBObjectStore) {
+        self.stores.borrow_mut().push(Dom::from_ref(store));
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#asynchronously-execute-a-request>
+    pub fn execute(&self, request: &IDBRequest, store: DOMString, operation: IndexedDBOperation) {
+        self.pending_requests.set(self.pending_requests.get() + 1);
+        request.set_transaction(Some(self));
+
+        let (sender, receiver) = ipc::channel().unwrap();
+        let request = Trusted::new(request);
+        route_replies(&self.global(), receiver, move |result| {
+            request.root().handle_result(result);
+        });
+        let message = IndexedDBThreadMsg::Operation(sender, self.id, String::from(store), operation);
+        if self.sender().send(message).is_err() {
+            warn!("Could not send an IndexedDB request.");
+        }
+    }
+
+    pub fn request_finished(&self) {
+        self.pending_requests.set(self.pending_requests.get() - 1);
+    }
+
+    /// Commit this transaction once it is inactive and none of its requests is pending.
+    /// <https://w3c.github.io/IndexedDB/#transaction-lifetime-concept> step 5
+    pub fn maybe_commit(&self) {
+        if self.active.get() || self.finished.get() || self.commit_requested.get() ||
+            self.pending_requests.get() > 0 {
+            return;
+        }
+        self.commit_requested.set(true);
+
+        let (sender, receiver) = ipc::channel().unwrap();
+        let this = Trusted::new(self);
+        route_replies(&self.global(), receiver, move |result: IndexedDBResult<()>| {
+            let transaction = this.root();
+            if result.is_ok() && !transaction.finished.get() {
+                // https://w3c.github.io/IndexedDB/#commit-a-transaction step 5
+                transaction.finished.set(true);
+                transaction.upcast::<EventTarget>().fire_event(atom!("complete"));
+                transaction.db.transaction_finished(&transaction);
+            }
+        });
+        if self.sender().send(IndexedDBThreadMsg::Commit(sender, self.id)).is_err() {
+            warn!("Could not commit an IndexedDB transaction.");
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#abort-a-transaction>
+    pub fn abort(&self, error: Option<DomRoot<DOMException>>) {
+        if self.finished.get() {
+            return;
+        }
+
+        // Step 1.
+        let (sender, receiver) = ProfiledIpc::channel(self.global().time_profiler_chan().clone()).unwrap();
+        if self.sender().send(IndexedDBThreadMsg::Abort(sender, self.id)).is_ok() {
+            let _ = receiver.recv();
+        }
+
+        // Steps 2-3.
+        if self.mode == IDBTransactionMode::Versionchange {
+            self.db.revert_upgrade();
+        }
+
+        // Step 4.
+        self.finished.set(true);
+        self.aborted.set(true);
+        self.error.set(error.r());
+
+        // Step 6.
+        let this = Trusted::new(self);
+        let global = self.global();
+        let _ = global.networking_task_source().queue_with_canceller(
+            task!(fire_transaction_abort: move || {
+                let transaction = this.root();
+                transaction.upcast::<EventTarget>().fire_bubbling_event(atom!("abort"));
+                transaction.db.transaction_finished(&transaction);
+            }),
+            &global.task_canceller(TaskSourceName::Networking),
+        );
+    }
+}
+
+impl IDBTransactionMethods for IDBTransaction {
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-objectstorenames
+    fn ObjectStoreNames(&self) -> DomRoot<DOMStringList> {
+        if self.mode == IDBTransactionMode::Versionchange {
+            return self.db.ObjectStoreNames();
+        }
+        DOMStringList::new(&self.global(), self.scope.clone())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-mode
+    fn Mode(&self) -> IDBTransactionMode {
+        self.mode
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-db
+    fn Db(&self) -> DomRoot<IDBDatabase> {
+        self.db()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-error
+    fn GetError(&self) -> Option<DomRoot<DOMException>> {
+        self.error.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-objectstore
+    fn ObjectStore(&self, name: DOMString) -> Fallible<DomRoot<IDBObjectStore>> {
+        // Step 1.
+        if self.finished.get() {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 2.
+        if !self.is_in_scope(&name) || !self.db.has_object_store(&name) {
+            return Err(Error::NotFound);
+        }
+
+        // Step 3.
+        if let Some(store) = self.stores.borrow().iter().find(|store| store.name() == name) {
+            return Ok(DomRoot::from_ref(&**store));
+        }
+        let store = IDBObjectStore::new(&self.global(), self, name);
+        self.stores.borrow_mut().push(Dom::from_ref(&*store));
+        Ok(store)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-abort
+    fn Abort(&self) -> ErrorResult {
+        // Step 1.
+        if self.finished.get() || self.commit_requested.get() {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 2.
+        self.abort(None);
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-onabort
+    event_handler!(abort, GetOnabort, SetOnabort);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-oncomplete
+    event_handler!(complete, GetOncomplete, SetOncomplete);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-onerror
+    event_handler!(error, GetOnerror, SetOnerror);
+}
+
+impl From<IDBTransactionMode> for IndexedDBTransactionMode {
+    fn from(mode: IDBTransactionMode) -> IndexedDBTransactionMode {
+        match mode {
+            IDBTransactionMode::Readonly => IndexedDBTransactionMode::ReadOnly,
+            IDBTransactionMode::Readwrite => IndexedDBTransactionMode::ReadWrite,
+            IDBTransactionMode::Versionchange => IndexedDBTransactionMode::VersionChange,
+        }
+    }
+}
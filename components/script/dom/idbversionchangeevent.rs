@@ -0,0 +1,92 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use dom::bindings::codegen::Bindings::IDBVersionChangeEventBinding;
+use dom::bindings::codegen::Bindings::IDBVersionChangeEventBinding::IDBVersionChangeEventMethods;
+use dom::bindings::error::Fallible;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::event::{Event, EventBubbles, EventCancelable};
+use dom::eventtarget::EventTarget;
+use dom::globalscope::GlobalScope;
+use dom_struct::dom_struct;
+use servo_atoms::Atom;
+
+#[dom_struct]
+pub struct IDBVersionChangeEvent {
+    event: Event,
+    old_version: u64,
+    new_version: Option<u64>,
+}
+
+impl IDBVersionChangeEvent {
+    fn new_inherited(old_version: u64, new_version: Option<u64>) -> IDBVersionChangeEvent {
+        IDBVersionChangeEvent {
+            event: Event::new_inherited(),
+            old_version: old_version,
+            new_version: new_version,
+        }
+    }
+
+    pub fn new(global: &GlobalScope,
+               type_: Atom,
+               bubbles: EventBubbles,
+               cancelable: EventCancelable,
+               old_version: u64,
+               new_version: Option<u64>)
+               -> DomRoot<IDBVersionChangeEvent> {
+        let ev = reflect_dom_object(Box::new(IDBVersionChangeEvent::new_inherited(old_version, new_version)),
+                                    global,
+                                    IDBVersionChangeEventBinding::Wrap);
+        {
+            let event = ev.upcast::<Event>();
+            event.init_event(type_, bool::from(bubbles), bool::from(cancelable));
+        }
+        ev
+    }
+
+    pub fn Constructor(global: &GlobalScope,
+                       type_: DOMString,
+                       init: &IDBVersionChangeEventBinding::IDBVersionChangeEventInit)
+                       -> Fallible<DomRoot<IDBVersionChangeEvent>> {
+        Ok(IDBVersionChangeEvent::new(global,
+                                      Atom::from(type_),
+                                      EventBubbles::from(init.parent.bubbles),
+                                      EventCancelable::from(init.parent.cancelable),
+                                      init.oldVersion,
+                                      init.newVersion))
+    }
+
+    /// Fire a version change event of the given type at `target`.
+    pub fn fire(target: &EventTarget, type_: Atom, old_version: u64, new_version: Option<u64>) {
+        let global = target.global();
+        let event = IDBVersionChangeEvent::new(&global,
+                                               type_,
+                                               EventBubbles::DoesNotBubble,
+                                               EventCancelable::NotCancelable,
+                                               old_version,
+                                               new_version);
+        event.upcast::<Event>().fire(target);
+    }
+}
+
+impl IDBVersionChangeEventMethods for IDBVersionChangeEvent {
+    // https://w3c.github.io/IndexedDB/#dom-idbversionchangeevent-oldversion
+    fn OldVersion(&self) -> u64 {
+        self.old_version
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbversionchangeevent-newversion
+    fn GetNewVersion(&self) -> Option<u64> {
+        self.new_version
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-istrusted
+    fn IsTrusted(&self) -> bool {
+        self.event.IsTrusted()
+    }
+}
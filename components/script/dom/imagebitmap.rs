@@ -0,0 +1,312 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_traits::canvas::byte_swap;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasRenderingContext2DMethods;
+use dom::bindings::codegen::Bindings::ImageBitmapBinding;
+use dom::bindings::codegen::Bindings::ImageBitmapBinding::{ImageBitmapMethods, ImageBitmapOptions};
+use dom::bindings::codegen::Bindings::ImageBitmapBinding::{ImageBitmapSource, ImageOrientation, ResizeQuality};
+use dom::bindings::codegen::Bindings::ImageDataBinding::ImageDataMethods;
+use dom::bindings::error::Error;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::globalscope::GlobalScope;
+use dom::htmlcanvaselement::utils as canvas_utils;
+use dom::node::window_from_node;
+use dom::promise::Promise;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use euclid::{Point2D, Rect, Size2D};
+use image::{FilterType, ImageBuffer, Rgba};
+use image::imageops;
+use net_traits::image::base::{Image, PixelFormat, load_from_memory};
+use net_traits::image_cache::ImageResponse;
+use servo_url::ServoUrl;
+use std::rc::Rc;
+use unpremultiplytable::UNPREMULTIPLY_TABLE;
+
+// https://html.spec.whatwg.org/multipage/#imagebitmap
+#[dom_struct]
+pub struct ImageBitmap {
+    reflector_: Reflector,
+    width: u32,
+    height: u32,
+    /// The bitmap data, as unpremultiplied BGRA8 pixels like the ones in the
+    /// image cache. This is None once the bitmap has been closed.
+    bitmap_data: DomRefCell<Option<Vec<u8>>>,
+    origin_clean: bool,
+}
+
+impl ImageBitmap {
+    fn new_inherited(width: u32, height: u32, bitmap_data: Vec<u8>, origin_clean: bool) -> ImageBitmap {
+        ImageBitmap {
+            reflector_: Reflector::new(),
+            width: width,
+            height: height,
+            bitmap_data: DomRefCell::new(Some(bitmap_data)),
+            origin_clean: origin_clean,
+        }
+    }
+
+    pub fn new(global: &GlobalScope,
+               width: u32,
+               height: u32,
+               bitmap_data: Vec<u8>,
+               origin_clean: bool)
+               -> DomRoot<ImageBitmap> {
+        reflect_dom_object(Box::new(ImageBitmap::new_inherited(width, height, bitmap_data, origin_clean)),
+                           global,
+                           ImageBitmapBinding::Wrap)
+    }
+
+    /// Returns a copy of the bitmap data, or None if the bitmap has been closed.
+    pub fn bitmap_data(&self) -> Option<Vec<u8>> {
+        self.bitmap_data.borrow().clone()
+    }
+
+    pub fn get_size(&self) -> Size2D<i32> {
+        Size2D::new(self.Width() as i32, self.Height() as i32)
+    }
+
+    pub fn origin_is_clean(&self) -> bool {
+        self.origin_clean
+    }
+}
+
+impl ImageBitmapMethods for ImageBitmap {
+    // https://html.spec.whatwg.org/multipage/#dom-imagebitmap-width
+    fn Width(&self) -> u32 {
+        if self.bitmap_data.borrow().is_none() {
+            return 0;
+        }
+        self.width
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-imagebitmap-height
+    fn Height(&self) -> u32 {
+        if self.bitmap_data.borrow().is_none() {
+            return 0;
+        }
+        self.height
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-imagebitmap-close
+    fn Close(&self) {
+        *self.bitmap_data.borrow_mut() = None;
+    }
+}
+
+/// Decoded source pixels, as unpremultiplied BGRA8.
+struct SourceBitmap {
+    data: Vec<u8>,
+    size: Size2D<i32>,
+    origin_clean: bool,
+}
+
+fn image_to_source_bitmap(image: &Image, origin_clean: bool) -> Result<SourceBitmap, Error> {
+    match image.format {
+        PixelFormat::BGRA8 => Ok(SourceBitmap {
+            data: image.bytes.to_vec(),
+            size: Size2D::new(image.width as i32, image.height as i32),
+            origin_clean: origin_clean,
+        }),
+        _ => Err(Error::InvalidState),
+    }
+}
+
+fn source_bitmap_from_cache(window: &Window, url: ServoUrl, origin_clean: bool) -> Result<SourceBitmap, Error> {
+    match canvas_utils::request_image_from_cache(window, url) {
+        ImageResponse::Loaded(image, _) => image_to_source_bitmap(&image, origin_clean),
+        _ => Err(Error::InvalidState),
+    }
+}
+
+pub fn unpremultiply(data: &mut [u8]) {
+    for chunk in data.chunks_mut(4) {
+        let alpha = chunk[3] as usize;
+        chunk[0] = UNPREMULTIPLY_TABLE[256 * alpha + chunk[0] as usize];
+        chunk[1] = UNPREMULTIPLY_TABLE[256 * alpha + chunk[1] as usize];
+        chunk[2] = UNPREMULTIPLY_TABLE[256 * alpha + chunk[2] as usize];
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/#check-the-usability-of-the-image-argument
+fn get_source_bitmap(global: &GlobalScope, image: &ImageBitmapSource) -> Result<SourceBitmap, Error> {
+    match *image {
+        ImageBitmapSource::HTMLImageElement(ref image) => {
+            let url = image.get_url().ok_or(Error::InvalidState)?;
+            let origin_clean = image.get_origin()
+                .map_or(false, |origin| origin.same_origin(global.origin()));
+            source_bitmap_from_cache(&window_from_node(&**image), url, origin_clean)
+        },
+        ImageBitmapSource::HTMLCanvasElement(ref canvas) => {
+            if !canvas.is_valid() {
+                return Err(Error::InvalidState);
+            }
+            let (mut data, size) = canvas.fetch_all_data().ok_or(Error::InvalidState)?;
+            unpremultiply(&mut data);
+            Ok(SourceBitmap { data: data, size: size, origin_clean: canvas.origin_is_clean() })
+        },
+        ImageBitmapSource::CanvasRenderingContext2D(ref context) => {
+            let canvas = context.Canvas();
+            let (mut data, size) = canvas.fetch_all_data().ok_or(Error::InvalidState)?;
+            unpremultiply(&mut data);
+            Ok(SourceBitmap { data: data, size: size, origin_clean: canvas.origin_is_clean() })
+        },
+        ImageBitmapSource::ImageBitmap(ref bitmap) => {
+            let data = bitmap.bitmap_data().ok_or(Error::InvalidState)?;
+            Ok(SourceBitmap { data: data, size: bitmap.get_size(), origin_clean: bitmap.origin_is_clean() })
+        },
+        ImageBitmapSource::OffscreenCanvas(ref canvas) => {
+            let (mut data, size) = canvas.fetch_all_data().ok_or(Error::InvalidState)?;
+            unpremultiply(&mut data);
+            Ok(SourceBitmap { data: data, size: size, origin_clean: canvas.origin_is_clean() })
+        },
+        ImageBitmapSource::CSSStyleValue(ref value) => {
+            let window = global.downcast::<Window>().ok_or(Error::InvalidState)?;
+            let url = value.get_url(global.api_base_url()).ok_or(Error::InvalidState)?;
+            source_bitmap_from_cache(window, url, true)
+        },
+        ImageBitmapSource::Blob(ref blob) => {
+            let bytes = blob.get_bytes().map_err(|_| Error::InvalidState)?;
+            let image = load_from_memory(&bytes).ok_or(Error::InvalidState)?;
+            image_to_source_bitmap(&image, true)
+        },
+        ImageBitmapSource::ImageData(ref image_data) => {
+            let mut data = image_data.get_data_array();
+            byte_swap(&mut data);
+            let size = Size2D::new(image_data.Width() as i32, image_data.Height() as i32);
+            Ok(SourceBitmap { data: data, size: size, origin_clean: true })
+        },
+    }
+}
+
+/// Copies the pixels of `rect` out of `data`, leaving the parts of the
+/// rectangle that lie outside of the source transparent black.
+fn crop(data: &[u8], size: Size2D<i32>, rect: Rect<i32>) -> Vec<u8> {
+    let mut cropped = vec![0; rect.size.width as usize * rect.size.height as usize * 4];
+    let source_rect = Rect::new(Point2D::zero(), size);
+    if let Some(intersection) = rect.intersection(&source_rect) {
+        let row_length = intersection.size.width as usize * 4;
+        for y in intersection.origin.y..intersection.max_y() {
+            let source_start = ((y * size.width + intersection.origin.x) * 4) as usize;
+            let dest_start = (((y - rect.origin.y) * rect.size.width +
+                               intersection.origin.x - rect.origin.x) * 4) as usize;
+            cropped[dest_start..dest_start + row_length]
+                .copy_from_slice(&data[source_start..source_start + row_length]);
+        }
+    }
+    cropped
+}
+
+fn flip_y(data: &[u8], size: Size2D<u32>) -> Vec<u8> {
+    let row_length = size.width as usize * 4;
+    data.chunks(row_length).rev().flat_map(|row| row.iter().cloned()).collect()
+}
+
+// https://html.spec.whatwg.org/multipage/#cropped-to-the-source-rectangle-with-formatting
+fn crop_and_format(source: SourceBitmap,
+                   crop_rect: Option<Rect<i32>>,
+                   options: &ImageBitmapOptions)
+                   -> Result<(Vec<u8>, Size2D<u32>), Error> {
+    // Steps 1-3.
+    let rect = crop_rect.unwrap_or(Rect::new(Point2D::zero(), source.size));
+    let data = if rect == Rect::new(Point2D::zero(), source.size) {
+        source.data
+    } else {
+        crop(&source.data, source.size, rect)
+    };
+    let size = Size2D::new(rect.size.width as u32, rect.size.height as u32);
+
+    // Steps 4-6.
+    let output_size = match (options.resizeWidth, options.resizeHeight) {
+        (Some(width), Some(height)) => Size2D::new(width, height),
+        (Some(width), None) => {
+            Size2D::new(width, (width as f64 * size.height as f64 / size.width as f64).ceil() as u32)
+        },
+        (None, Some(height)) => {
+            Size2D::new((height as f64 * size.width as f64 / size.height as f64).ceil() as u32, height)
+        },
+        (None, None) => size,
+    };
+    if output_size.width == 0 || output_size.height == 0 {
+        return Err(Error::InvalidState);
+    }
+
+    // Step 7.
+    let data = if output_size != size {
+        let filter = match options.resizeQuality {
+            ResizeQuality::Pixelated => FilterType::Nearest,
+            ResizeQuality::Low => FilterType::Triangle,
+            ResizeQuality::Medium => FilterType::CatmullRom,
+            ResizeQuality::High => FilterType::Lanczos3,
+        };
+        // The channel order doesn't matter for resampling, so BGRA pixels are
+        // handled as if they were RGBA.
+        let buffer = ImageBuffer::<Rgba<u8>, Vec<u8>>::from_raw(size.width, size.height, data)
+            .ok_or(Error::InvalidState)?;
+        imageops::resize(&buffer, output_size.width, output_size.height, filter).into_raw()
+    } else {
+        data
+    };
+
+    // Step 8.
+    let data = match options.imageOrientation {
+        ImageOrientation::FlipY => flip_y(&data, output_size),
+        ImageOrientation::None => data,
+    };
+
+    // TODO: premultiplyAlpha and colorSpaceConversion. Bitmaps are always
+    // stored unpremultiplied and no color space conversion is done.
+    Ok((data, output_size))
+}
+
+// https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
+#[allow(unrooted_must_root)]
+pub fn create_image_bitmap(global: &GlobalScope,
+                           image: ImageBitmapSource,
+                           crop_rect: Option<Rect<i32>>,
+                           options: &ImageBitmapOptions)
+                           -> Rc<Promise> {
+    let promise = Promise::new(global);
+
+    // Step 1.
+    if let Some(rect) = crop_rect {
+        if rect.size.width == 0 || rect.size.height == 0 {
+            promise.reject_error(Error::Range("The crop rectangle must not be empty".to_owned()));
+            return promise;
+        }
+    }
+
+    // Step 2.
+    if options.resizeWidth == Some(0) || options.resizeHeight == Some(0) {
+        promise.reject_error(Error::InvalidState);
+        return promise;
+    }
+
+    // Steps 3-6. The sources are all available synchronously, so the bitmap
+    // is created right away rather than in parallel.
+    let result = get_source_bitmap(global, &image).and_then(|source| {
+        let origin_clean = source.origin_clean;
+        crop_and_format(source, crop_rect, options).map(|(data, size)| (data, size, origin_clean))
+    });
+    match result {
+        Ok((data, size, origin_clean)) => {
+            let bitmap = ImageBitmap::new(global, size.width, size.height, data, origin_clean);
+            promise.resolve_native(&bitmap);
+        },
+        Err(error) => promise.reject_error(error),
+    }
+    promise
+}
+
+/// Builds the crop rectangle for the `createImageBitmap(image, sx, sy, sw, sh)`
+/// overload, normalizing negative widths and heights.
+pub fn normalize_crop_rect(sx: i32, sy: i32, sw: i32, sh: i32) -> Rect<i32> {
+    let (x, width) = if sw < 0 { (sx + sw, -sw) } else { (sx, sw) };
+    let (y, height) = if sh < 0 { (sy + sh, -sh) } else { (sy, sh) };
+    Rect::new(Point2D::new(x, y), Size2D::new(width, height))
+}
@@ -0,0 +1,316 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use app_units::Au;
+use dom::bindings::callback::ExceptionHandling;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::IntersectionObserverBinding;
+use dom::bindings::codegen::Bindings::IntersectionObserverBinding::IntersectionObserverCallback;
+use dom::bindings::codegen::Bindings::IntersectionObserverBinding::IntersectionObserverInit;
+use dom::bindings::codegen::Bindings::IntersectionObserverBinding::IntersectionObserverMethods;
+use dom::bindings::codegen::UnionTypes::DoubleOrDoubleSequence;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
+use dom::element::Element;
+use dom::intersectionobserverentry::IntersectionObserverEntry;
+use dom::node::Node;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use euclid::{Point2D, Rect, SideOffsets2D, Size2D};
+use script_layout_interface::rpc::ObservedBoxes;
+use std::cell::Cell;
+use std::cmp::{max, min};
+use std::rc::Rc;
+
+/// One side of the root margin, either in pixels or as a percentage of the
+/// size of the root intersection rectangle.
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf, PartialEq)]
+enum RootMarginValue {
+    Px(f64),
+    Percent(f64),
+}
+
+impl RootMarginValue {
+    fn parse(value: &str) -> Option<RootMarginValue> {
+        if value.ends_with("px") {
+            value[..value.len() - 2].parse().ok().map(RootMarginValue::Px)
+        } else if value.ends_with('%') {
+            value[..value.len() - 1].parse().ok().map(RootMarginValue::Percent)
+        } else {
+            None
+        }
+    }
+
+    fn resolve(&self, basis: Au) -> Au {
+        match *self {
+            RootMarginValue::Px(px) => Au::from_f64_px(px),
+            RootMarginValue::Percent(percent) => basis.scale_by((percent / 100.) as f32),
+        }
+    }
+
+    fn serialize(&self) -> String {
+        match *self {
+            RootMarginValue::Px(px) => format!("{}px", px),
+            RootMarginValue::Percent(percent) => format!("{}%", percent),
+        }
+    }
+}
+
+/// <https://w3c.github.io/IntersectionObserver/#intersectionobserverregistration>
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+struct IntersectionObserverRegistration {
+    target: Dom<Element>,
+    previous_threshold_index: Cell<i32>,
+    previous_is_intersecting: Cell<bool>,
+}
+
+// https://w3c.github.io/IntersectionObserver/#intersection-observer-interface
+#[dom_struct]
+pub struct IntersectionObserver {
+    reflector_: Reflector,
+    #[ignore_malloc_size_of = "can't measure Rc values"]
+    callback: Rc<IntersectionObserverCallback>,
+    root: Option<Dom<Element>>,
+    /// The top, right, bottom and left root margins.
+    root_margin: Vec<RootMarginValue>,
+    thresholds: Vec<f64>,
+    observation_targets: DomRefCell<Vec<IntersectionObserverRegistration>>,
+    queued_entries: DomRefCell<Vec<Dom<IntersectionObserverEntry>>>,
+}
+
+impl IntersectionObserver {
+    fn new_inherited(callback: Rc<IntersectionObserverCallback>,
+                     root: Option<&Element>,
+                     root_margin: Vec<RootMarginValue>,
+                     thresholds: Vec<f64>)
+                     -> IntersectionObserver {
+        IntersectionObserver {
+            reflector_: Reflector::new(),
+            callback: callback,
+            root: root.map(Dom::from_ref),
+            root_margin: root_margin,
+            thresholds: thresholds,
+            observation_targets: DomRefCell::new(vec![]),
+            queued_entries: DomRefCell::new(vec![]),
+        }
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#initialize-a-new-intersectionobserver
+    pub fn Constructor(window: &Window,
+                       callback: Rc<IntersectionObserverCallback>,
+                       options: &IntersectionObserverInit)
+                       -> Fallible<DomRoot<IntersectionObserver>> {
+        // Steps 3-4.
+        let root_margin = parse_root_margin(&options.rootMargin).ok_or(Error::Syntax)?;
+
+        // Steps 5-7.
+        let mut thresholds = match options.threshold {
+            Some(DoubleOrDoubleSequence::Double(threshold)) => vec![*threshold],
+            Some(DoubleOrDoubleSequence::DoubleSequence(ref thresholds)) => {
+                thresholds.iter().map(|threshold| **threshold).collect()
+            },
+            None => vec![],
+        };
+        if thresholds.iter().any(|&threshold| threshold < 0. || threshold > 1.) {
+            return Err(Error::Range("Thresholds must be between 0 and 1".to_owned()));
+        }
+        thresholds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        if thresholds.is_empty() {
+            thresholds.push(0.);
+        }
+
+        let observer = IntersectionObserver::new_inherited(
+            callback,
+            options.root.as_ref().map(|root| &**root),
+            root_margin,
+            thresholds,
+        );
+        Ok(reflect_dom_object(Box::new(observer), window, IntersectionObserverBinding::Wrap))
+    }
+
+    /// Returns the targets this observer is observing, along with the root it
+    /// computes their intersection with.
+    pub fn observed_elements(&self) -> Vec<DomRoot<Element>> {
+        let targets = self.observation_targets.borrow();
+        self.root.iter()
+            .map(|root| DomRoot::from_ref(&**root))
+            .chain(targets.iter().map(|registration| DomRoot::from_ref(&*registration.target)))
+            .collect()
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#update-intersection-observations-algo>
+    ///
+    /// `boxes` returns the layout boxes of the root and targets, and `viewport` is
+    /// the visual viewport of the document, in the same coordinate space. The
+    /// rectangles in the entries are relative to the viewport.
+    pub fn update_intersection_observations<F>(&self, time: f64, viewport: Rect<Au>, boxes: F)
+        where F: Fn(&Element) -> Option<ObservedBoxes>
+    {
+        // Step 2.1.
+        let root_bounds = match self.root {
+            Some(ref root) => boxes(&**root).map(|boxes| boxes.padding_box),
+            None => Some(viewport),
+        };
+        let root_bounds = root_bounds.map(|bounds| self.apply_root_margin(bounds));
+        let to_client = |rect: Rect<Au>| rect.translate(&-viewport.origin.to_vector());
+
+        let window = self.global();
+        let window = window.as_window();
+        for registration in self.observation_targets.borrow().iter() {
+            let target = &*registration.target;
+
+            // Steps 2.2.2-2.2.5.
+            let target_boxes = boxes(target);
+            let target_rect = target_boxes.map_or(Rect::zero(), |boxes| boxes.border_box);
+            let in_root = target_boxes.is_some() && self.root.as_ref().map_or(true, |root| {
+                root.upcast::<Node>().is_ancestor_of(target.upcast::<Node>())
+            });
+            let intersection = match root_bounds {
+                Some(ref root_bounds) if in_root => edge_inclusive_intersection(&target_rect, root_bounds),
+                _ => None,
+            };
+
+            // Steps 2.2.6-2.2.9.
+            let is_intersecting = intersection.is_some();
+            let intersection_rect = intersection.unwrap_or(Rect::zero());
+            let target_area = area(&target_rect);
+            let intersection_ratio = if target_area > 0. {
+                area(&intersection_rect) / target_area
+            } else if is_intersecting {
+                1.
+            } else {
+                0.
+            };
+
+            // Step 2.2.10.
+            let threshold_index =
+                self.thresholds.iter().take_while(|&&threshold| threshold <= intersection_ratio).count() as i32;
+
+            // Steps 2.2.11-2.2.13.
+            if threshold_index == registration.previous_threshold_index.get() &&
+                is_intersecting == registration.previous_is_intersecting.get() {
+                continue;
+            }
+            let entry = IntersectionObserverEntry::new(
+                window,
+                time,
+                root_bounds.map(&to_client),
+                to_client(target_rect),
+                to_client(intersection_rect),
+                is_intersecting,
+                intersection_ratio,
+                target,
+            );
+            self.queued_entries.borrow_mut().push(Dom::from_ref(&*entry));
+            registration.previous_threshold_index.set(threshold_index);
+            registration.previous_is_intersecting.set(is_intersecting);
+        }
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#notify-intersection-observers-algo>
+    pub fn notify(&self) {
+        let entries = self.TakeRecords();
+        if entries.is_empty() {
+            return;
+        }
+        let _ = self.callback.Call_(self, entries, self, ExceptionHandling::Report);
+    }
+
+    fn apply_root_margin(&self, rect: Rect<Au>) -> Rect<Au> {
+        let (width, height) = (rect.size.width, rect.size.height);
+        let margin = &self.root_margin;
+        let margin = SideOffsets2D::new(margin[0].resolve(height),
+                                        margin[1].resolve(width),
+                                        margin[2].resolve(height),
+                                        margin[3].resolve(width));
+        rect.outer_rect(margin)
+    }
+}
+
+impl IntersectionObserverMethods for IntersectionObserver {
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-root
+    fn GetRoot(&self) -> Option<DomRoot<Element>> {
+        self.root.as_ref().map(|root| DomRoot::from_ref(&**root))
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-rootmargin
+    fn RootMargin(&self) -> DOMString {
+        let margins: Vec<String> = self.root_margin.iter().map(RootMarginValue::serialize).collect();
+        DOMString::from(margins.join(" "))
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-observe
+    fn Observe(&self, target: &Element) {
+        // Step 1.
+        if self.observation_targets.borrow().iter().any(|registration| &*registration.target == target) {
+            return;
+        }
+        // Steps 2-4.
+        self.observation_targets.borrow_mut().push(IntersectionObserverRegistration {
+            target: Dom::from_ref(target),
+            previous_threshold_index: Cell::new(-1),
+            previous_is_intersecting: Cell::new(false),
+        });
+        let document = target.upcast::<Node>().owner_doc();
+        document.add_intersection_observer(self);
+        // The initial intersection is reported even if layout doesn't change.
+        document.queue_layout_observers_update();
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-unobserve
+    fn Unobserve(&self, target: &Element) {
+        self.observation_targets.borrow_mut().retain(|registration| &*registration.target != target);
+        if self.observation_targets.borrow().is_empty() {
+            target.upcast::<Node>().owner_doc().remove_intersection_observer(self);
+        }
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-disconnect
+    fn Disconnect(&self) {
+        let targets: Vec<_> = self.observation_targets.borrow_mut().drain(..)
+            .map(|registration| DomRoot::from_ref(&*registration.target))
+            .collect();
+        for target in targets {
+            target.upcast::<Node>().owner_doc().remove_intersection_observer(self);
+        }
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-takerecords
+    fn TakeRecords(&self) -> Vec<DomRoot<IntersectionObserverEntry>> {
+        self.queued_entries.borrow_mut().drain(..).map(|entry| DomRoot::from_ref(&*entry)).collect()
+    }
+}
+
+/// <https://w3c.github.io/IntersectionObserver/#parse-a-root-margin>
+fn parse_root_margin(value: &str) -> Option<Vec<RootMarginValue>> {
+    let tokens = value.split_whitespace()
+        .map(RootMarginValue::parse)
+        .collect::<Option<Vec<_>>>()?;
+    match tokens.len() {
+        1 => Some(vec![tokens[0], tokens[0], tokens[0], tokens[0]]),
+        2 => Some(vec![tokens[0], tokens[1], tokens[0], tokens[1]]),
+        3 => Some(vec![tokens[0], tokens[1], tokens[2], tokens[1]]),
+        4 => Some(tokens),
+        _ => None,
+    }
+}
+
+/// Like `Rect::intersection`, but edge-adjacent rectangles intersect with a
+/// zero area rectangle, as the spec requires.
+fn edge_inclusive_intersection(a: &Rect<Au>, b: &Rect<Au>) -> Option<Rect<Au>> {
+    let origin = Point2D::new(max(a.origin.x, b.origin.x), max(a.origin.y, b.origin.y));
+    let lower_right = Point2D::new(min(a.max_x(), b.max_x()), min(a.max_y(), b.max_y()));
+    if origin.x > lower_right.x || origin.y > lower_right.y {
+        return None;
+    }
+    Some(Rect::new(origin, Size2D::new(lower_right.x - origin.x, lower_right.y - origin.y)))
+}
+
+fn area(rect: &Rect<Au>) -> f64 {
+    rect.size.width.to_f64_px() * rect.size.height.to_f64_px()
+}
@@ -0,0 +1,120 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use app_units::Au;
+use dom::bindings::codegen::Bindings::IntersectionObserverEntryBinding;
+use dom::bindings::codegen::Bindings::IntersectionObserverEntryBinding::IntersectionObserverEntryMethods;
+use dom::bindings::codegen::Bindings::PerformanceBinding::DOMHighResTimeStamp;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::num::Finite;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::domrectreadonly::DOMRectReadOnly;
+use dom::element::Element;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use euclid::Rect;
+
+// https://w3c.github.io/IntersectionObserver/#intersection-observer-entry
+#[dom_struct]
+pub struct IntersectionObserverEntry {
+    reflector_: Reflector,
+    time: f64,
+    root_bounds: Option<Dom<DOMRectReadOnly>>,
+    bounding_client_rect: Dom<DOMRectReadOnly>,
+    intersection_rect: Dom<DOMRectReadOnly>,
+    is_intersecting: bool,
+    intersection_ratio: f64,
+    target: Dom<Element>,
+}
+
+impl IntersectionObserverEntry {
+    fn new_inherited(time: f64,
+                     root_bounds: Option<&DOMRectReadOnly>,
+                     bounding_client_rect: &DOMRectReadOnly,
+                     intersection_rect: &DOMRectReadOnly,
+                     is_intersecting: bool,
+                     intersection_ratio: f64,
+                     target: &Element)
+                     -> IntersectionObserverEntry {
+        IntersectionObserverEntry {
+            reflector_: Reflector::new(),
+            time: time,
+            root_bounds: root_bounds.map(Dom::from_ref),
+            bounding_client_rect: Dom::from_ref(bounding_client_rect),
+            intersection_rect: Dom::from_ref(intersection_rect),
+            is_intersecting: is_intersecting,
+            intersection_ratio: intersection_ratio,
+            target: Dom::from_ref(target),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(window: &Window,
+               time: f64,
+               root_bounds: Option<Rect<Au>>,
+               bounding_client_rect: Rect<Au>,
+               intersection_rect: Rect<Au>,
+               is_intersecting: bool,
+               intersection_ratio: f64,
+               target: &Element)
+               -> DomRoot<IntersectionObserverEntry> {
+        let root_bounds = root_bounds.map(|rect| rect_to_dom(window, rect));
+        let entry = IntersectionObserverEntry::new_inherited(
+            time,
+            root_bounds.as_ref().map(|rect| &**rect),
+            &rect_to_dom(window, bounding_client_rect),
+            &rect_to_dom(window, intersection_rect),
+            is_intersecting,
+            intersection_ratio,
+            target,
+        );
+        reflect_dom_object(Box::new(entry), window, IntersectionObserverEntryBinding::Wrap)
+    }
+}
+
+fn rect_to_dom(window: &Window, rect: Rect<Au>) -> DomRoot<DOMRectReadOnly> {
+    DOMRectReadOnly::new(window.upcast(),
+                         rect.origin.x.to_f64_px(),
+                         rect.origin.y.to_f64_px(),
+                         rect.size.width.to_f64_px(),
+                         rect.size.height.to_f64_px())
+}
+
+impl IntersectionObserverEntryMethods for IntersectionObserverEntry {
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserverentry-time
+    fn Time(&self) -> DOMHighResTimeStamp {
+        Finite::wrap(self.time)
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserverentry-rootbounds
+    fn GetRootBounds(&self) -> Option<DomRoot<DOMRectReadOnly>> {
+        self.root_bounds.as_ref().map(|rect| DomRoot::from_ref(&**rect))
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserverentry-boundingclientrect
+    fn BoundingClientRect(&self) -> DomRoot<DOMRectReadOnly> {
+        DomRoot::from_ref(&*self.bounding_client_rect)
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserverentry-intersectionrect
+    fn IntersectionRect(&self) -> DomRoot<DOMRectReadOnly> {
+        DomRoot::from_ref(&*self.intersection_rect)
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserverentry-isintersecting
+    fn IsIntersecting(&self) -> bool {
+        self.is_intersecting
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserverentry-intersectionratio
+    fn IntersectionRatio(&self) -> Finite<f64> {
+        Finite::wrap(self.intersection_ratio)
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserverentry-target
+    fn Target(&self) -> DomRoot<Element> {
+        DomRoot::from_ref(&*self.target)
+    }
+}
@@ -115,6 +115,7 @@ impl KeyboardEvent {
                                        init.repeat, init.isComposing, init.parent.ctrlKey,
                                        init.parent.altKey, init.parent.shiftKey, init.parent.metaKey,
                                        None, 0);
+        event.upcast::<Event>().set_composed(init.parent.parent.parent.composed);
         Ok(event)
     }
 
@@ -0,0 +1,554 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::animationeffect::{AnimationEffect, Timing, parse_easing};
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::KeyframeEffectBinding;
+use dom::bindings::codegen::Bindings::KeyframeEffectBinding::KeyframeEffectMethods;
+use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use dom::bindings::codegen::UnionTypes::UnrestrictedDoubleOrKeyframeEffectOptions;
+use dom::bindings::conversions::{ConversionResult, FromJSValConvertible, StringificationBehavior};
+use dom::bindings::conversions::{ToJSValConvertible, jsid_to_string};
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{DomRoot, MutNullableDom};
+use dom::bindings::str::DOMString;
+use dom::bindings::utils::set_dictionary_property;
+use dom::element::Element;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use js::jsapi::{JSContext, JSITER_OWNONLY, JSObject, JSPROP_ENUMERATE};
+use js::jsapi::{JS_NewArrayObject1, JS_NewPlainObject};
+use js::jsval::{DoubleValue, NullValue, ObjectValue, UndefinedValue};
+use js::rust::{HandleObject, HandleValue, IdVector};
+use js::rust::wrappers::{GetArrayLength, GetPropertyKeys, JS_DefineElement, JS_GetElement};
+use js::rust::wrappers::{JS_GetPropertyById, JS_IsArrayObject};
+use servo_arc::Arc;
+use std::ptr::NonNull;
+use style::animation::{ScriptAnimationSample, ScriptKeyframe};
+use style::properties::{Importance, PropertyDeclarationBlock, PropertyId};
+use style::properties::{SourcePropertyDeclaration, parse_one_declaration_into};
+use style::shared_lock::Locked;
+use style::values::computed::TimingFunction;
+use style_traits::{ParsingMode, ToCss};
+
+/// <https://drafts.csswg.org/web-animations/#keyframe>
+#[derive(Clone, JSTraceable, MallocSizeOf)]
+struct Keyframe {
+    /// The offset specified for this keyframe, if any.
+    offset: Option<f64>,
+    /// <https://drafts.csswg.org/web-animations/#computed-keyframe-offset>
+    computed_offset: f64,
+    easing: TimingFunction,
+    /// The names of the properties specified for this keyframe, as CSS
+    /// property names.
+    properties: Vec<String>,
+    /// The declarations of this keyframe, with the invalid values left out.
+    #[ignore_malloc_size_of = "Arc"]
+    block: Arc<Locked<PropertyDeclarationBlock>>,
+}
+
+/// A keyframe read from a keyframes argument, before its values are parsed.
+struct ParsedKeyframe {
+    offset: Option<f64>,
+    computed_offset: Option<f64>,
+    easing: DOMString,
+    declarations: Vec<(PropertyId, DOMString)>,
+}
+
+/// <https://drafts.csswg.org/web-animations/#the-keyframeeffect-interface>
+#[dom_struct]
+pub struct KeyframeEffect {
+    effect: AnimationEffect,
+    target: MutNullableDom<Element>,
+    keyframes: DomRefCell<Vec<Keyframe>>,
+}
+
+impl KeyframeEffect {
+    fn new_inherited(target: Option<&Element>, timing: Timing, keyframes: Vec<Keyframe>) -> KeyframeEffect {
+        KeyframeEffect {
+            effect: AnimationEffect::new_inherited(timing),
+            target: MutNullableDom::new(target),
+            keyframes: DomRefCell::new(keyframes),
+        }
+    }
+
+    fn new(window: &Window,
+           target: Option<&Element>,
+           timing: Timing,
+           keyframes: Vec<Keyframe>)
+           -> DomRoot<KeyframeEffect> {
+        reflect_dom_object(Box::new(KeyframeEffect::new_inherited(target, timing, keyframes)),
+                           window,
+                           KeyframeEffectBinding::Wrap)
+    }
+
+    /// Creates an effect with no keyframes, used to reflect CSS animations
+    /// and transitions.
+    pub fn new_without_keyframes(window: &Window, target: &Element, timing: Timing) -> DomRoot<KeyframeEffect> {
+        KeyframeEffect::new(window, Some(target), timing, vec![])
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-keyframeeffect-keyframeeffect
+    #[allow(unsafe_code)]
+    pub unsafe fn Constructor(cx: *mut JSContext,
+                              window: &Window,
+                              target: Option<&Element>,
+                              keyframes: *mut JSObject,
+                              options: Option<UnrestrictedDoubleOrKeyframeEffectOptions>)
+                              -> Fallible<DomRoot<KeyframeEffect>> {
+        let timing = match options {
+            Some(UnrestrictedDoubleOrKeyframeEffectOptions::UnrestrictedDouble(duration)) => {
+                Timing::from_duration(duration)?
+            },
+            Some(UnrestrictedDoubleOrKeyframeEffectOptions::KeyframeEffectOptions(ref options)) => {
+                Timing::from_dictionary(&window.get_url(), &options.parent)?
+            },
+            None => Timing::new(),
+        };
+        let keyframes = process_keyframes(cx, window, keyframes)?;
+        Ok(KeyframeEffect::new(window, target, timing, keyframes))
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-keyframeeffect-keyframeeffect-source
+    pub fn Constructor_(window: &Window, source: &KeyframeEffect) -> Fallible<DomRoot<KeyframeEffect>> {
+        Ok(KeyframeEffect::new(window,
+                               source.target.get().as_ref().map(|target| &**target),
+                               source.upcast::<AnimationEffect>().timing(),
+                               source.keyframes.borrow().clone()))
+    }
+
+    pub fn target(&self) -> Option<DomRoot<Element>> {
+        self.target.get()
+    }
+
+    /// Samples this effect at the current time of its animation, returning
+    /// its target and what layout needs to apply it, if the effect is in
+    /// effect.
+    pub fn sample(&self) -> Option<(DomRoot<Element>, ScriptAnimationSample)> {
+        let target = self.target.get()?;
+        let keyframes = self.keyframes.borrow();
+        if keyframes.is_empty() {
+            return None;
+        }
+        let iteration_progress = self.upcast::<AnimationEffect>().transformed_progress()?;
+        let sample = ScriptAnimationSample {
+            keyframes: keyframes.iter().map(|keyframe| {
+                ScriptKeyframe {
+                    offset: keyframe.computed_offset,
+                    timing_function: keyframe.easing,
+                    block: keyframe.block.clone(),
+                }
+            }).collect(),
+            iteration_progress: iteration_progress,
+        };
+        Some((target, sample))
+    }
+}
+
+impl KeyframeEffectMethods for KeyframeEffect {
+    // https://drafts.csswg.org/web-animations/#dom-keyframeeffect-target
+    fn GetTarget(&self) -> Option<DomRoot<Element>> {
+        self.target.get()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-keyframeeffect-target
+    fn SetTarget(&self, target: Option<&Element>) {
+        self.target.set(target);
+        self.upcast::<AnimationEffect>().effect_changed();
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-keyframeeffect-getkeyframes
+    #[allow(unsafe_code)]
+    unsafe fn GetKeyframes(&self, cx: *mut JSContext) -> Fallible<NonNull<JSObject>> {
+        let window = self.global();
+        let document = window.as_window().Document();
+        let guard = document.style_shared_lock().read();
+        let keyframes = self.keyframes.borrow();
+
+        rooted!(in(cx) let array = JS_NewArrayObject1(cx, keyframes.len()));
+        if array.is_null() {
+            return Err(Error::JSFailed);
+        }
+        for (index, keyframe) in keyframes.iter().enumerate() {
+            rooted!(in(cx) let object = JS_NewPlainObject(cx));
+            if object.is_null() {
+                return Err(Error::JSFailed);
+            }
+            rooted!(in(cx) let mut value = UndefinedValue());
+            match keyframe.offset {
+                Some(offset) => value.set(DoubleValue(offset)),
+                None => value.set(NullValue()),
+            }
+            set_property(cx, object.handle(), "offset", value.handle())?;
+            value.set(DoubleValue(keyframe.computed_offset));
+            set_property(cx, object.handle(), "computedOffset", value.handle())?;
+            DOMString::from(keyframe.easing.to_css_string()).to_jsval(cx, value.handle_mut());
+            set_property(cx, object.handle(), "easing", value.handle())?;
+
+            let block = keyframe.block.read_with(&guard);
+            for property in &keyframe.properties {
+                let id = match PropertyId::parse_enabled_for_all_content(property) {
+                    Ok(id) => id,
+                    Err(..) => continue,
+                };
+                let mut string = String::new();
+                block.property_value_to_css(&id, &mut string).unwrap();
+                if string.is_empty() {
+                    continue;
+                }
+                DOMString::from(string).to_jsval(cx, value.handle_mut());
+                set_property(cx, object.handle(), &property_to_idl_attribute(property), value.handle())?;
+            }
+
+            value.set(ObjectValue(object.get()));
+            if !JS_DefineElement(cx, array.handle(), index as u32, value.handle(), JSPROP_ENUMERATE) {
+                return Err(Error::JSFailed);
+            }
+        }
+        Ok(NonNull::new_unchecked(array.get()))
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-keyframeeffect-setkeyframes
+    #[allow(unsafe_code)]
+    unsafe fn SetKeyframes(&self, cx: *mut JSContext, keyframes: *mut JSObject) -> ErrorResult {
+        let window = self.global();
+        let keyframes = process_keyframes(cx, window.as_window(), keyframes)?;
+        *self.keyframes.borrow_mut() = keyframes;
+        self.upcast::<AnimationEffect>().effect_changed();
+        Ok(())
+    }
+}
+
+#[allow(unsafe_code)]
+unsafe fn set_property(cx: *mut JSContext, object: HandleObject, name: &str, value: HandleValue) -> ErrorResult {
+    set_dictionary_property(cx, object, name, value).map_err(|_| Error::JSFailed)
+}
+
+#[allow(unsafe_code)]
+unsafe fn convert<T>(cx: *mut JSContext, value: HandleValue, config: T::Config) -> Fallible<T>
+    where T: FromJSValConvertible
+{
+    match T::from_jsval(cx, value, config) {
+        Ok(ConversionResult::Success(value)) => Ok(value),
+        Ok(ConversionResult::Failure(message)) => Err(Error::Type(message.into_owned())),
+        Err(()) => Err(Error::JSFailed),
+    }
+}
+
+/// Converts a keyframe offset, which is either `null` or a finite number.
+#[allow(unsafe_code)]
+unsafe fn convert_offset(cx: *mut JSContext, value: HandleValue) -> Fallible<Option<f64>> {
+    if value.is_null_or_undefined() {
+        return Ok(None);
+    }
+    let offset = convert::<f64>(cx, value, ())?;
+    if !offset.is_finite() {
+        return Err(Error::Type("keyframe offsets must be finite numbers".to_owned()));
+    }
+    Ok(Some(offset))
+}
+
+#[allow(unsafe_code)]
+unsafe fn is_array(cx: *mut JSContext, value: HandleValue) -> Fallible<bool> {
+    let mut is_array = false;
+    if !JS_IsArrayObject(cx, value, &mut is_array) {
+        return Err(Error::JSFailed);
+    }
+    Ok(is_array)
+}
+
+/// Calls `f` with the name and the value of each own property of `object`,
+/// skipping symbols.
+#[allow(unsafe_code)]
+unsafe fn for_each_property<F>(cx: *mut JSContext, object: HandleObject, mut f: F) -> ErrorResult
+    where F: FnMut(DOMString, HandleValue) -> ErrorResult
+{
+    let ids = IdVector::new(cx);
+    if !GetPropertyKeys(cx, object, JSITER_OWNONLY, ids.get()) {
+        return Err(Error::JSFailed);
+    }
+    for id in &*ids {
+        rooted!(in(cx) let id = *id);
+        let name = match jsid_to_string(cx, id.handle()) {
+            Some(name) => name,
+            None => continue,
+        };
+        rooted!(in(cx) let mut value = UndefinedValue());
+        if !JS_GetPropertyById(cx, object, id.handle(), value.handle_mut()) {
+            return Err(Error::JSFailed);
+        }
+        f(name, value.handle())?;
+    }
+    Ok(())
+}
+
+/// <https://drafts.csswg.org/web-animations/#processing-a-keyframes-argument>
+#[allow(unsafe_code)]
+unsafe fn process_keyframes(cx: *mut JSContext, window: &Window, object: *mut JSObject) -> Fallible<Vec<Keyframe>> {
+    // Step 1.
+    if object.is_null() {
+        return Ok(vec![]);
+    }
+    rooted!(in(cx) let object = object);
+    rooted!(in(cx) let value = ObjectValue(object.get()));
+
+    let mut keyframes = if is_array(cx, value.handle())? {
+        process_keyframe_list(cx, object.handle())?
+    } else {
+        process_property_indexed_keyframes(cx, object.handle())?
+    };
+
+    // Steps 6-7.
+    let mut previous_offset = 0.;
+    for offset in keyframes.iter().filter_map(|keyframe| keyframe.offset) {
+        if offset < 0. || offset > 1. {
+            return Err(Error::Type("keyframe offsets must be between 0 and 1".to_owned()));
+        }
+        if offset < previous_offset {
+            return Err(Error::Type("keyframe offsets must be loosely sorted".to_owned()));
+        }
+        previous_offset = offset;
+    }
+    compute_missing_keyframe_offsets(&mut keyframes);
+
+    // Steps 8-9.
+    let document = window.Document();
+    let url = window.get_url();
+    keyframes.into_iter().map(|keyframe| {
+        let easing = parse_easing(&url, &keyframe.easing)?;
+        let mut block = PropertyDeclarationBlock::new();
+        let mut properties = vec![];
+        for (id, value) in keyframe.declarations {
+            properties.push(id.name().into_owned());
+            let mut declarations = SourcePropertyDeclaration::new();
+            let result = parse_one_declaration_into(
+                &mut declarations,
+                id,
+                &value,
+                &url,
+                window.css_error_reporter(),
+                ParsingMode::DEFAULT,
+                document.quirks_mode(),
+            );
+            // Invalid property values are ignored.
+            if result.is_ok() {
+                block.extend(declarations.drain(), Importance::Normal);
+            }
+        }
+        Ok(Keyframe {
+            offset: keyframe.offset,
+            computed_offset: keyframe.computed_offset.unwrap(),
+            easing: easing,
+            properties: properties,
+            block: Arc::new(document.style_shared_lock().wrap(block)),
+        })
+    }).collect()
+}
+
+/// Processes a keyframes argument that is an array of keyframe objects.
+#[allow(unsafe_code)]
+unsafe fn process_keyframe_list(cx: *mut JSContext, list: HandleObject) -> Fallible<Vec<ParsedKeyframe>> {
+    let mut length = 0;
+    if !GetArrayLength(cx, list, &mut length) {
+        return Err(Error::JSFailed);
+    }
+    let mut keyframes = Vec::with_capacity(length as usize);
+    for index in 0..length {
+        rooted!(in(cx) let mut entry = UndefinedValue());
+        if !JS_GetElement(cx, list, index, entry.handle_mut()) {
+            return Err(Error::JSFailed);
+        }
+        let mut keyframe = ParsedKeyframe {
+            offset: None,
+            computed_offset: None,
+            easing: DOMString::from("linear"),
+            declarations: vec![],
+        };
+        if entry.is_null_or_undefined() {
+            keyframes.push(keyframe);
+            continue;
+        }
+        if !entry.is_object() {
+            return Err(Error::Type("keyframes must be objects".to_owned()));
+        }
+        rooted!(in(cx) let object = entry.to_object());
+        for_each_property(cx, object.handle(), |name, value| {
+            match &*name {
+                "offset" => keyframe.offset = convert_offset(cx, value)?,
+                "easing" => keyframe.easing = convert(cx, value, StringificationBehavior::Default)?,
+                "composite" => {},
+                _ => if let Some(id) = idl_attribute_to_property(&name) {
+                    let value = convert(cx, value, StringificationBehavior::Default)?;
+                    keyframe.declarations.push((id, value));
+                },
+            }
+            Ok(())
+        })?;
+        keyframe.computed_offset = keyframe.offset;
+        keyframes.push(keyframe);
+    }
+    Ok(keyframes)
+}
+
+/// Processes a keyframes argument that maps each property to its values.
+#[allow(unsafe_code)]
+unsafe fn process_property_indexed_keyframes(cx: *mut JSContext,
+                                             object: HandleObject)
+                                             -> Fallible<Vec<ParsedKeyframe>> {
+    let mut offsets: Vec<Option<f64>> = vec![];
+    let mut easings: Vec<DOMString> = vec![];
+    let mut properties = vec![];
+    for_each_property(cx, object, |name, value| {
+        match &*name {
+            "offset" => if is_array(cx, value)? {
+                let values: Vec<Option<f64>> = convert(cx, value, ())?;
+                for offset in values {
+                    if offset.map_or(false, |offset| !offset.is_finite()) {
+                        return Err(Error::Type("keyframe offsets must be finite numbers".to_owned()));
+                    }
+                    offsets.push(offset);
+                }
+            } else {
+                offsets.push(convert_offset(cx, value)?);
+            },
+            "easing" => if is_array(cx, value)? {
+                easings = convert(cx, value, StringificationBehavior::Default)?;
+            } else {
+                easings.push(convert(cx, value, StringificationBehavior::Default)?);
+            },
+            "composite" => {},
+            _ => if let Some(id) = idl_attribute_to_property(&name) {
+                let values: Vec<DOMString> = if is_array(cx, value)? {
+                    convert(cx, value, StringificationBehavior::Default)?
+                } else {
+                    vec![convert(cx, value, StringificationBehavior::Default)?]
+                };
+                properties.push((name, id, values));
+            },
+        }
+        Ok(())
+    })?;
+
+    // The properties are processed in ascending Unicode order of their names.
+    properties.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // Spread the values of each property evenly, and merge the keyframes
+    // that end up at the same offset.
+    let mut keyframes: Vec<ParsedKeyframe> = vec![];
+    for (_, id, values) in properties {
+        let count = values.len();
+        for (index, value) in values.into_iter().enumerate() {
+            let offset = if count == 1 { 1. } else { index as f64 / (count - 1) as f64 };
+            let position = keyframes.iter().position(|keyframe| keyframe.computed_offset.unwrap() >= offset);
+            match position {
+                Some(position) if keyframes[position].computed_offset == Some(offset) => {
+                    keyframes[position].declarations.push((id.clone(), value));
+                },
+                position => {
+                    let keyframe = ParsedKeyframe {
+                        offset: None,
+                        computed_offset: Some(offset),
+                        easing: DOMString::from("linear"),
+                        declarations: vec![(id.clone(), value)],
+                    };
+                    keyframes.insert(position.unwrap_or(keyframes.len()), keyframe);
+                },
+            }
+        }
+    }
+
+    for (keyframe, offset) in keyframes.iter_mut().zip(offsets) {
+        keyframe.offset = offset;
+        if offset.is_some() {
+            keyframe.computed_offset = offset;
+        }
+    }
+    if !easings.is_empty() {
+        for (keyframe, easing) in keyframes.iter_mut().zip(easings.iter().cycle()) {
+            keyframe.easing = easing.clone();
+        }
+    }
+    Ok(keyframes)
+}
+
+/// <https://drafts.csswg.org/web-animations/#compute-missing-keyframe-offsets>
+fn compute_missing_keyframe_offsets(keyframes: &mut [ParsedKeyframe]) {
+    let count = keyframes.len();
+    if count == 0 {
+        return;
+    }
+    if count > 1 && keyframes[0].computed_offset.is_none() {
+        keyframes[0].computed_offset = Some(0.);
+    }
+    if keyframes[count - 1].computed_offset.is_none() {
+        keyframes[count - 1].computed_offset = Some(1.);
+    }
+    let mut start = 0;
+    for index in 1..count {
+        if let Some(end_offset) = keyframes[index].computed_offset {
+            let start_offset = keyframes[start].computed_offset.unwrap();
+            let steps = (index - start) as f64;
+            for (step, keyframe) in keyframes[start + 1..index].iter_mut().enumerate() {
+                let step = (step + 1) as f64;
+                keyframe.computed_offset = Some(start_offset + (end_offset - start_offset) * step / steps);
+            }
+            start = index;
+        }
+    }
+}
+
+/// <https://drafts.csswg.org/web-animations/#idl-attribute-name-to-animation-property-name>
+fn idl_attribute_to_property(name: &str) -> Option<PropertyId> {
+    if name.starts_with("--") {
+        return PropertyId::parse_enabled_for_all_content(name).ok();
+    }
+    let property = match name {
+        "cssFloat" => "float".to_owned(),
+        "cssOffset" => "offset".to_owned(),
+        "float" | "offset" => return None,
+        _ => {
+            if name.contains('-') {
+                return None;
+            }
+            let mut property = String::with_capacity(name.len());
+            for c in name.chars() {
+                if c.is_ascii_uppercase() {
+                    property.push('-');
+                    property.push(c.to_ascii_lowercase());
+                } else {
+                    property.push(c);
+                }
+            }
+            property
+        },
+    };
+    PropertyId::parse_enabled_for_all_content(&property).ok()
+}
+
+/// <https://drafts.csswg.org/web-animations/#animation-property-name-to-idl-attribute-name>
+fn property_to_idl_attribute(property: &str) -> String {
+    if property.starts_with("--") {
+        return property.to_owned();
+    }
+    match property {
+        "float" => return "cssFloat".to_owned(),
+        "offset" => return "cssOffset".to_owned(),
+        _ => {},
+    }
+    let mut attribute = String::with_capacity(property.len());
+    let mut uppercase_next = false;
+    for c in property.chars() {
+        if c == '-' {
+            uppercase_next = true;
+        } else if uppercase_next {
+            attribute.push(c.to_ascii_uppercase());
+            uppercase_next = false;
+        } else {
+            attribute.push(c);
+        }
+    }
+    attribute
+}
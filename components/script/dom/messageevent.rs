@@ -5,18 +5,20 @@
 use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
 use dom::bindings::codegen::Bindings::MessageEventBinding;
 use dom::bindings::codegen::Bindings::MessageEventBinding::MessageEventMethods;
+use dom::bindings::conversions::ToJSValConvertible;
 use dom::bindings::error::Fallible;
 use dom::bindings::inheritance::Castable;
 use dom::bindings::reflector::reflect_dom_object;
-use dom::bindings::root::DomRoot;
+use dom::bindings::root::{Dom, DomRoot};
 use dom::bindings::str::DOMString;
 use dom::bindings::trace::RootedTraceableBox;
 use dom::event::Event;
 use dom::eventtarget::EventTarget;
 use dom::globalscope::GlobalScope;
+use dom::messageport::MessagePort;
 use dom_struct::dom_struct;
-use js::jsapi::{Heap, JSContext};
-use js::jsval::JSVal;
+use js::jsapi::{Heap, JSContext, JS_FreezeObject};
+use js::jsval::{JSVal, UndefinedValue};
 use js::rust::HandleValue;
 use servo_atoms::Atom;
 
@@ -26,6 +28,9 @@ pub struct MessageEvent {
     data: Heap<JSVal>,
     origin: DOMString,
     lastEventId: DOMString,
+    ports: Vec<Dom<MessagePort>>,
+    /// The frozen array returned by the `ports` attribute, once it was created.
+    frozen_ports: Heap<JSVal>,
 }
 
 impl MessageEvent {
@@ -33,18 +38,22 @@ impl MessageEvent {
         MessageEvent::new_initialized(global,
                                       HandleValue::undefined(),
                                       DOMString::new(),
-                                      DOMString::new())
+                                      DOMString::new(),
+                                      vec![])
     }
 
     pub fn new_initialized(global: &GlobalScope,
                            data: HandleValue,
                            origin: DOMString,
-                           lastEventId: DOMString) -> DomRoot<MessageEvent> {
+                           lastEventId: DOMString,
+                           ports: Vec<DomRoot<MessagePort>>) -> DomRoot<MessageEvent> {
         let ev = Box::new(MessageEvent {
             event: Event::new_inherited(),
             data: Heap::default(),
             origin: origin,
             lastEventId: lastEventId,
+            ports: ports.iter().map(|port| Dom::from_ref(&**port)).collect(),
+            frozen_ports: Heap::default(),
         });
         let ev = reflect_dom_object(ev, global, MessageEventBinding::Wrap);
         ev.data.set(data.get());
@@ -54,9 +63,10 @@ impl MessageEvent {
 
     pub fn new(global: &GlobalScope, type_: Atom,
                bubbles: bool, cancelable: bool,
-               data: HandleValue, origin: DOMString, lastEventId: DOMString)
+               data: HandleValue, origin: DOMString, lastEventId: DOMString,
+               ports: Vec<DomRoot<MessagePort>>)
                -> DomRoot<MessageEvent> {
-        let ev = MessageEvent::new_initialized(global, data, origin, lastEventId);
+        let ev = MessageEvent::new_initialized(global, data, origin, lastEventId, ports);
         {
             let event = ev.upcast::<Event>();
             event.init_event(type_, bubbles, cancelable);
@@ -74,7 +84,8 @@ impl MessageEvent {
                                    init.parent.cancelable,
                                    init.data.handle(),
                                    init.origin.clone(),
-                                   init.lastEventId.clone());
+                                   init.lastEventId.clone(),
+                                   init.ports.clone().unwrap_or_default());
         Ok(ev)
     }
 }
@@ -82,7 +93,8 @@ impl MessageEvent {
 impl MessageEvent {
     pub fn dispatch_jsval(target: &EventTarget,
                           scope: &GlobalScope,
-                          message: HandleValue) {
+                          message: HandleValue,
+                          ports: Vec<DomRoot<MessagePort>>) {
         let messageevent = MessageEvent::new(
             scope,
             atom!("message"),
@@ -90,11 +102,30 @@ impl MessageEvent {
             false,
             message,
             DOMString::new(),
-            DOMString::new());
+            DOMString::new(),
+            ports);
         messageevent.upcast::<Event>().fire(target);
     }
 }
 
+/// Returns the frozen array of `ports` cached in `frozen_ports`, creating it
+/// the first time.
+#[allow(unsafe_code)]
+pub unsafe fn frozen_ports_array(cx: *mut JSContext,
+                                 ports: &[Dom<MessagePort>],
+                                 frozen_ports: &Heap<JSVal>)
+                                 -> JSVal {
+    if frozen_ports.get().is_undefined() {
+        let ports: Vec<DomRoot<MessagePort>> = ports.iter().map(|port| DomRoot::from_ref(&**port)).collect();
+        rooted!(in(cx) let mut array = UndefinedValue());
+        ports.to_jsval(cx, array.handle_mut());
+        rooted!(in(cx) let object = array.to_object());
+        assert!(JS_FreezeObject(cx, object.handle().into()));
+        frozen_ports.set(array.get());
+    }
+    frozen_ports.get()
+}
+
 impl MessageEventMethods for MessageEvent {
     #[allow(unsafe_code)]
     // https://html.spec.whatwg.org/multipage/#dom-messageevent-data
@@ -112,6 +143,12 @@ impl MessageEventMethods for MessageEvent {
         self.lastEventId.clone()
     }
 
+    #[allow(unsafe_code)]
+    // https://html.spec.whatwg.org/multipage/#dom-messageevent-ports
+    unsafe fn Ports(&self, cx: *mut JSContext) -> JSVal {
+        frozen_ports_array(cx, &self.ports, &self.frozen_ports)
+    }
+
     // https://dom.spec.whatwg.org/#dom-event-istrusted
     fn IsTrusted(&self) -> bool {
         self.event.IsTrusted()
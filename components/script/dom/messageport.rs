@@ -0,0 +1,231 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
+use dom::bindings::codegen::Bindings::MessagePortBinding;
+use dom::bindings::codegen::Bindings::MessagePortBinding::MessagePortMethods;
+use dom::bindings::conversions::root_from_object;
+use dom::bindings::error::{Error, ErrorResult};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::bindings::structuredclone::StructuredCloneData;
+use dom::eventtarget::EventTarget;
+use dom::globalscope::GlobalScope;
+use dom::messageevent::MessageEvent;
+use dom_struct::dom_struct;
+use js::jsapi::{JSAutoCompartment, JSContext, JSObject};
+use js::jsval::UndefinedValue;
+use js::rust::{CustomAutoRooterGuard, HandleValue};
+use msg::constellation_msg::MessagePortId;
+use script_traits::ScriptMsg;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use task_source::TaskSource;
+
+/// <https://html.spec.whatwg.org/multipage/#messageport>
+#[dom_struct]
+pub struct MessagePort {
+    eventtarget: EventTarget,
+    message_port_id: MessagePortId,
+    /// The port this port is entangled with, as far as this global knows.
+    entangled_port: Cell<Option<MessagePortId>>,
+    /// Whether the port was transferred to another global.
+    detached: Cell<bool>,
+    closed: Cell<bool>,
+    /// <https://html.spec.whatwg.org/multipage/#port-message-queue>
+    message_queue_enabled: Cell<bool>,
+    message_queue: DomRefCell<VecDeque<Vec<u8>>>,
+}
+
+impl MessagePort {
+    fn new_inherited(message_port_id: MessagePortId) -> MessagePort {
+        MessagePort {
+            eventtarget: EventTarget::new_inherited(),
+            message_port_id: message_port_id,
+            entangled_port: Cell::new(None),
+            detached: Cell::new(false),
+            closed: Cell::new(false),
+            message_queue_enabled: Cell::new(false),
+            message_queue: DomRefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#create-a-new-messageport-object>
+    pub fn new(owner: &GlobalScope) -> DomRoot<MessagePort> {
+        let port = reflect_dom_object(Box::new(MessagePort::new_inherited(owner.new_message_port_id())),
+                                      owner,
+                                      MessagePortBinding::Wrap);
+        owner.track_message_port(&port, false);
+        port
+    }
+
+    /// Creates the port `message_port_id` in `owner` when it was transferred there.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#messageport-transfer-receiving-steps>
+    pub fn new_transferred(owner: &GlobalScope, message_port_id: MessagePortId) -> DomRoot<MessagePort> {
+        let port = reflect_dom_object(Box::new(MessagePort::new_inherited(message_port_id)),
+                                      owner,
+                                      MessagePortBinding::Wrap);
+        owner.track_message_port(&port, true);
+        port
+    }
+
+    pub fn message_port_id(&self) -> MessagePortId {
+        self.message_port_id
+    }
+
+    pub fn is_detached(&self) -> bool {
+        self.detached.get()
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#entangle>
+    pub fn entangle(&self, other: &MessagePort) {
+        self.entangled_port.set(Some(other.message_port_id));
+        other.entangled_port.set(Some(self.message_port_id));
+        let msg = ScriptMsg::EntanglePorts(self.message_port_id, other.message_port_id);
+        let _ = self.global().script_to_constellation_chan().send(msg);
+    }
+
+    /// Detaches the port once it was transferred, handing the messages it
+    /// did not dispatch yet back to the constellation for its new owner.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#messageport-transfer-steps>
+    pub fn ship(&self) {
+        self.detached.set(true);
+        let global = self.global();
+        global.untrack_message_port(self.message_port_id);
+        let pending: Vec<Vec<u8>> = self.message_queue.borrow_mut().drain(..).collect();
+        let msg = ScriptMsg::MessagePortShipped(self.message_port_id, pending);
+        let _ = global.script_to_constellation_chan().send(msg);
+    }
+
+    /// Adds a message received from the entangled port to the port message queue.
+    pub fn handle_incoming(&self, data: Vec<u8>) {
+        if self.detached.get() {
+            return;
+        }
+        if self.message_queue_enabled.get() && self.message_queue.borrow().is_empty() {
+            return self.dispatch_message(data);
+        }
+        self.message_queue.borrow_mut().push_back(data);
+        if self.message_queue_enabled.get() {
+            self.queue_dispatch();
+        }
+    }
+
+    /// Queues a task dispatching the first message of the port message queue.
+    fn queue_dispatch(&self) {
+        let global = self.global();
+        let port = Trusted::new(self);
+        // FIXME(nox): Why are errors silenced here?
+        let _ = global.port_message_queue().queue(
+            task!(dispatch_port_message: move || {
+                port.root().dispatch_next_message();
+            }),
+            &global,
+        );
+    }
+
+    fn dispatch_next_message(&self) {
+        if self.detached.get() {
+            return;
+        }
+        let data = self.message_queue.borrow_mut().pop_front();
+        if let Some(data) = data {
+            self.dispatch_message(data);
+        }
+    }
+
+    fn dispatch_message(&self, data: Vec<u8>) {
+        let global = self.global();
+        let cx = global.get_cx();
+        let _ac = JSAutoCompartment::new(cx, self.reflector().get_jsobject().get());
+        rooted!(in(cx) let mut message = UndefinedValue());
+        let ports = StructuredCloneData::Vector(data).read(&global, message.handle_mut());
+        MessageEvent::dispatch_jsval(self.upcast(), &global, message.handle(), ports);
+    }
+}
+
+impl MessagePortMethods for MessagePort {
+    #[allow(unsafe_code)]
+    // https://html.spec.whatwg.org/multipage/#dom-messageport-postmessage
+    unsafe fn PostMessage(&self,
+                          cx: *mut JSContext,
+                          message: HandleValue,
+                          transfer: CustomAutoRooterGuard<Option<Vec<*mut JSObject>>>)
+                          -> ErrorResult {
+        if self.detached.get() {
+            return Ok(());
+        }
+        let transfer = transfer.as_ref().map(|objects| &objects[..]).unwrap_or(&[]);
+
+        // Step 2.
+        let mut target_in_transfer = false;
+        for object in transfer {
+            if let Ok(port) = root_from_object::<MessagePort>(*object) {
+                if port.message_port_id == self.message_port_id {
+                    return Err(Error::DataClone);
+                }
+                if Some(port.message_port_id) == self.entangled_port.get() {
+                    target_in_transfer = true;
+                }
+            }
+        }
+
+        // Steps 3-5.
+        let data = StructuredCloneData::write_with_transfer(cx, message, transfer)?;
+
+        // Step 6.
+        if self.closed.get() || target_in_transfer {
+            return Ok(());
+        }
+
+        // Steps 7-8.
+        let msg = ScriptMsg::PostPortMessage(self.message_port_id, data.move_to_arraybuffer());
+        let _ = self.global().script_to_constellation_chan().send(msg);
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-messageport-start
+    fn Start(&self) {
+        if self.message_queue_enabled.get() {
+            return;
+        }
+        self.message_queue_enabled.set(true);
+        let pending = self.message_queue.borrow().len();
+        for _ in 0..pending {
+            self.queue_dispatch();
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-messageport-close
+    fn Close(&self) {
+        if self.closed.get() {
+            return;
+        }
+        self.closed.set(true);
+        if self.detached.get() {
+            return;
+        }
+        let global = self.global();
+        global.untrack_message_port(self.message_port_id);
+        let _ = global.script_to_constellation_chan().send(ScriptMsg::CloseMessagePort(self.message_port_id));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#handler-messageport-onmessage
+    fn GetOnmessage(&self) -> Option<Rc<EventHandlerNonNull>> {
+        self.upcast::<EventTarget>().get_event_handler_common("message")
+    }
+
+    // https://html.spec.whatwg.org/multipage/#handler-messageport-onmessage
+    fn SetOnmessage(&self, listener: Option<Rc<EventHandlerNonNull>>) {
+        self.upcast::<EventTarget>().set_event_handler_common("message", listener);
+        // Setting the handler implicitly enables the port message queue.
+        self.Start();
+    }
+}
@@ -212,9 +212,16 @@ pub mod types {
     include!(concat!(env!("OUT_DIR"), "/build/InterfaceTypes.rs"));
 }
 
+pub mod abortcontroller;
+pub mod abortsignal;
 pub mod abstractworker;
 pub mod abstractworkerglobalscope;
 pub mod activation;
+pub mod analysernode;
+pub mod animation;
+pub mod animationeffect;
+pub mod animationplaybackevent;
+pub mod animationtimeline;
 pub mod attr;
 pub mod audiobuffer;
 pub mod audiobuffersourcenode;
@@ -226,6 +233,7 @@ pub mod audioscheduledsourcenode;
 pub mod baseaudiocontext;
 pub mod beforeunloadevent;
 pub mod bindings;
+pub mod biquadfilternode;
 pub mod blob;
 pub mod bluetooth;
 pub mod bluetoothadvertisingevent;
@@ -237,18 +245,27 @@ pub mod bluetoothremotegattdescriptor;
 pub mod bluetoothremotegattserver;
 pub mod bluetoothremotegattservice;
 pub mod bluetoothuuid;
+pub mod broadcastchannel;
+pub mod bytelengthqueuingstrategy;
+pub mod cache;
+pub mod cachestorage;
 pub mod canvasgradient;
 pub mod canvaspattern;
 pub mod canvasrenderingcontext2d;
+pub mod channelmergernode;
+pub mod channelsplitternode;
 pub mod characterdata;
 pub mod client;
 pub mod closeevent;
 pub mod comment;
 pub mod compositionevent;
 pub mod console;
+pub mod constantsourcenode;
+pub mod countqueuingstrategy;
 mod create;
 pub mod crypto;
 pub mod css;
+pub mod cssanimation;
 pub mod cssconditionrule;
 pub mod cssfontfacerule;
 pub mod cssgroupingrule;
@@ -264,6 +281,7 @@ pub mod cssstylerule;
 pub mod cssstylesheet;
 pub mod cssstylevalue;
 pub mod csssupportsrule;
+pub mod csstransition;
 pub mod cssviewportrule;
 pub mod customelementregistry;
 pub mod customevent;
@@ -272,6 +290,7 @@ pub mod dissimilaroriginlocation;
 pub mod dissimilaroriginwindow;
 pub mod document;
 pub mod documentfragment;
+pub mod documenttimeline;
 pub mod documenttype;
 pub mod domexception;
 pub mod domimplementation;
@@ -283,6 +302,7 @@ pub mod dompointreadonly;
 pub mod domquad;
 pub mod domrect;
 pub mod domrectreadonly;
+pub mod domstringlist;
 pub mod domstringmap;
 pub mod domtokenlist;
 pub mod element;
@@ -297,6 +317,9 @@ pub mod filelist;
 pub mod filereader;
 pub mod filereadersync;
 pub mod focusevent;
+pub mod fontface;
+pub mod fontfaceset;
+pub mod fontfacesetloadevent;
 pub mod formdata;
 pub mod gainnode;
 pub mod gamepad;
@@ -362,6 +385,7 @@ pub mod htmlprogresselement;
 pub mod htmlquoteelement;
 pub mod htmlscriptelement;
 pub mod htmlselectelement;
+pub mod htmlslotelement;
 pub mod htmlsourceelement;
 pub mod htmlspanelement;
 pub mod htmlstyleelement;
@@ -381,15 +405,32 @@ pub mod htmltrackelement;
 pub mod htmlulistelement;
 pub mod htmlunknownelement;
 pub mod htmlvideoelement;
+pub mod idbcursor;
+pub mod idbcursorwithvalue;
+pub mod idbdatabase;
+pub mod idbfactory;
+pub mod idbindex;
+pub mod idbkeyrange;
+pub mod idbobjectstore;
+pub mod idbopendbrequest;
+pub mod idbrequest;
+pub mod idbtransaction;
+pub mod idbversionchangeevent;
+pub mod imagebitmap;
 pub mod imagedata;
 pub mod inputevent;
+pub mod intersectionobserver;
+pub mod intersectionobserverentry;
 pub mod keyboardevent;
+pub mod keyframeeffect;
 pub mod location;
 pub mod mediaerror;
 pub mod medialist;
 pub mod mediaquerylist;
 pub mod mediaquerylistevent;
+pub mod messagechannel;
 pub mod messageevent;
+pub mod messageport;
 pub mod mimetype;
 pub mod mimetypearray;
 pub mod mouseevent;
@@ -403,11 +444,15 @@ pub mod nodeiterator;
 pub mod nodelist;
 pub mod offlineaudiocompletionevent;
 pub mod offlineaudiocontext;
+pub mod offscreencanvas;
+pub mod offscreencanvasrenderingcontext2d;
 pub mod oscillatornode;
 pub mod pagetransitionevent;
 pub mod paintrenderingcontext2d;
 pub mod paintsize;
 pub mod paintworkletglobalscope;
+pub mod pannernode;
+pub mod path2d;
 pub mod performance;
 pub mod performanceentry;
 pub mod performancemark;
@@ -427,14 +472,27 @@ pub mod promise;
 pub mod promisenativehandler;
 pub mod radionodelist;
 pub mod range;
+pub mod raredata;
+pub mod readablebytestreamcontroller;
+pub mod readablestream;
+pub mod readablestreambyobreader;
+pub mod readablestreambyobrequest;
+pub mod readablestreamdefaultcontroller;
+pub mod readablestreamdefaultreader;
 pub mod request;
+pub mod resizeobserver;
+pub mod resizeobserverentry;
 pub mod response;
 pub mod screen;
+pub mod securitypolicyviolationevent;
+pub mod selection;
 pub mod serviceworker;
 pub mod serviceworkercontainer;
 pub mod serviceworkerglobalscope;
 pub mod serviceworkerregistration;
 pub mod servoparser;
+pub mod shadowroot;
+pub mod stereopannernode;
 pub mod storage;
 pub mod storageevent;
 pub mod stylepropertymapreadonly;
@@ -454,9 +512,12 @@ pub mod text;
 pub mod textcontrol;
 pub mod textdecoder;
 pub mod textencoder;
+pub mod textmetrics;
 pub mod touch;
 pub mod touchevent;
 pub mod touchlist;
+pub mod transformstream;
+pub mod transformstreamdefaultcontroller;
 pub mod transitionevent;
 pub mod treewalker;
 pub mod uievent;
@@ -477,6 +538,7 @@ pub mod vrfieldofview;
 pub mod vrframedata;
 pub mod vrpose;
 pub mod vrstageparameters;
+pub mod waveshapernode;
 pub mod webgl_extensions;
 pub use self::webgl_extensions::ext::*;
 pub mod webgl2renderingcontext;
@@ -487,11 +549,15 @@ pub mod webglcontextevent;
 pub mod webglframebuffer;
 pub mod webglobject;
 pub mod webglprogram;
+pub mod webglquery;
 pub mod webglrenderbuffer;
 pub mod webglrenderingcontext;
+pub mod webglsampler;
 pub mod webglshader;
 pub mod webglshaderprecisionformat;
+pub mod webglsync;
 pub mod webgltexture;
+pub mod webgltransformfeedback;
 pub mod webgluniformlocation;
 pub mod webglvertexarrayobjectoes;
 pub mod websocket;
@@ -503,6 +569,9 @@ pub mod workerlocation;
 pub mod workernavigator;
 pub mod worklet;
 pub mod workletglobalscope;
+pub mod writablestream;
+pub mod writablestreamdefaultcontroller;
+pub mod writablestreamdefaultwriter;
 pub mod xmldocument;
 pub mod xmlhttprequest;
 pub mod xmlhttprequesteventtarget;
@@ -12,14 +12,15 @@ use dom::bindings::codegen::Bindings::CharacterDataBinding::CharacterDataMethods
 use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
 use dom::bindings::codegen::Bindings::ElementBinding::ElementMethods;
 use dom::bindings::codegen::Bindings::HTMLCollectionBinding::HTMLCollectionMethods;
-use dom::bindings::codegen::Bindings::NodeBinding::{NodeConstants, NodeMethods};
+use dom::bindings::codegen::Bindings::NodeBinding::{GetRootNodeOptions, NodeConstants, NodeMethods};
 use dom::bindings::codegen::Bindings::NodeListBinding::NodeListMethods;
 use dom::bindings::codegen::Bindings::ProcessingInstructionBinding::ProcessingInstructionMethods;
+use dom::bindings::codegen::Bindings::ShadowRootBinding::ShadowRootMethods;
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use dom::bindings::codegen::UnionTypes::NodeOrString;
 use dom::bindings::conversions::{self, DerivedFrom};
 use dom::bindings::error::{Error, ErrorResult, Fallible};
-use dom::bindings::inheritance::{Castable, CharacterDataTypeId, ElementTypeId};
+use dom::bindings::inheritance::{Castable, CharacterDataTypeId, DocumentFragmentTypeId, ElementTypeId};
 use dom::bindings::inheritance::{EventTargetTypeId, HTMLElementTypeId, NodeTypeId};
 use dom::bindings::inheritance::{SVGElementTypeId, SVGGraphicsElementTypeId};
 use dom::bindings::reflector::{DomObject, reflect_dom_object};
@@ -44,14 +45,17 @@ use dom::htmlimageelement::{HTMLImageElement, LayoutHTMLImageElementHelpers};
 use dom::htmlinputelement::{HTMLInputElement, LayoutHTMLInputElementHelpers};
 use dom::htmllinkelement::HTMLLinkElement;
 use dom::htmlmetaelement::HTMLMetaElement;
+use dom::htmlslotelement::HTMLSlotElement;
 use dom::htmlstyleelement::HTMLStyleElement;
 use dom::htmltextareaelement::{HTMLTextAreaElement, LayoutHTMLTextAreaElementHelpers};
 use dom::mutationobserver::{Mutation, MutationObserver, RegisteredObserver};
 use dom::nodelist::NodeList;
 use dom::processinginstruction::ProcessingInstruction;
 use dom::range::WeakRangeVec;
+use dom::raredata::NodeRareData;
+use dom::shadowroot::ShadowRoot;
 use dom::svgsvgelement::{SVGSVGElement, LayoutSVGSVGElementHelpers};
-use dom::text::Text;
+use dom::text::{LayoutTextHelpers, Text};
 use dom::virtualmethods::{VirtualMethods, vtable_for};
 use dom::window::Window;
 use dom_struct::dom_struct;
@@ -74,7 +78,7 @@ use servo_arc::Arc;
 use servo_url::ServoUrl;
 use smallvec::SmallVec;
 use std::borrow::ToOwned;
-use std::cell::{Cell, UnsafeCell, RefMut};
+use std::cell::{Cell, Ref, RefMut, UnsafeCell};
 use std::cmp;
 use std::default::Default;
 use std::iter;
@@ -115,8 +119,8 @@ pub struct Node {
     /// The document that this node belongs to.
     owner_doc: MutNullableDom<Document>,
 
-    /// The live list of children return by .childNodes.
-    child_list: MutNullableDom<NodeList>,
+    /// Data that only some nodes need, allocated on first use.
+    rare_data: DomRefCell<Option<Box<NodeRareData>>>,
 
     /// The live count of children of this node.
     children_count: Cell<u32>,
@@ -141,8 +145,6 @@ pub struct Node {
 
     /// Registered observers for this node.
     mutation_observers: DomRefCell<Vec<RegisteredObserver>>,
-
-    unique_id: UniqueId,
 }
 
 bitflags! {
@@ -164,7 +166,10 @@ bitflags! {
                  to be reachable with using sequential focus navigation."]
         const SEQUENTIALLY_FOCUSABLE = 1 << 3;
 
-        // There are two free bits here.
+        #[doc = "Specifies whether this node is in a shadow tree."]
+        const IS_IN_SHADOW_TREE = 1 << 4;
+
+        // There is one free bit here.
 
         #[doc = "Specifies whether the parser has set an associated form owner for \
                  this element. Only applicable for form-associatable elements."]
@@ -257,7 +262,15 @@ impl Node {
         self.children_count.set(self.children_count.get() + 1);
 
         let parent_in_doc = self.is_in_doc();
+        let parent_in_shadow_tree = self.is_in_shadow_tree();
+        let containing_shadow_root = self.containing_shadow_root();
         for node in new_child.traverse_preorder() {
+            if parent_in_shadow_tree {
+                node.set_containing_shadow_root(containing_shadow_root.r());
+            }
+            node.set_flag(NodeFlags::IS_IN_SHADOW_TREE, parent_in_shadow_tree);
+        }
+        for node in new_child.traverse_preorder_shadow_including() {
             node.set_flag(NodeFlags::IS_IN_DOC, parent_in_doc);
             // Out-of-document elements never have the descendants flag set.
             debug_assert!(!node.get_flag(NodeFlags::HAS_DIRTY_DESCENDANTS));
@@ -296,13 +309,13 @@ impl Node {
         child.parent_node.set(None);
         self.children_count.set(self.children_count.get() - 1);
 
-        for node in child.traverse_preorder() {
+        for node in child.traverse_preorder_shadow_including() {
             // Out-of-document elements never have the descendants flag set.
             node.set_flag(NodeFlags::IS_IN_DOC | NodeFlags::HAS_DIRTY_DESCENDANTS |
                           NodeFlags::HAS_SNAPSHOT | NodeFlags::HANDLED_SNAPSHOT,
                           false);
         }
-        for node in child.traverse_preorder() {
+        for node in child.traverse_preorder_shadow_including() {
             // This needs to be in its own loop, because unbind_from_tree may
             // rely on the state of IS_IN_DOC of the context node's descendants,
             // e.g. when removing a <form>.
@@ -313,6 +326,14 @@ impl Node {
                 ScriptThread::enqueue_callback_reaction(&*element, CallbackReaction::Disconnected, None);
             }
         }
+        // The nodes of the removed subtree, unlike the shadow trees they
+        // host, are no longer in the shadow tree of this node, if any.
+        if self.is_in_shadow_tree() {
+            for node in child.traverse_preorder() {
+                node.set_containing_shadow_root(None);
+                node.set_flag(NodeFlags::IS_IN_SHADOW_TREE, false);
+            }
+        }
     }
 
     pub fn to_untrusted_node_address(&self) -> UntrustedNodeAddress {
@@ -429,6 +450,99 @@ impl Node {
         self.flags.get().contains(NodeFlags::IS_IN_DOC)
     }
 
+    pub fn is_in_shadow_tree(&self) -> bool {
+        self.flags.get().contains(NodeFlags::IS_IN_SHADOW_TREE)
+    }
+
+    pub fn rare_data(&self) -> Ref<Option<Box<NodeRareData>>> {
+        self.rare_data.borrow()
+    }
+
+    /// Returns the rare data of this node, allocating it if needed.
+    #[allow(unrooted_must_root)]
+    pub fn ensure_rare_data(&self) -> RefMut<Box<NodeRareData>> {
+        let mut rare_data = self.rare_data.borrow_mut();
+        if rare_data.is_none() {
+            *rare_data = Some(Default::default());
+        }
+        RefMut::map(rare_data, |rare_data| rare_data.as_mut().unwrap())
+    }
+
+    /// Returns the shadow root this node is in, if it's in a shadow tree.
+    pub fn containing_shadow_root(&self) -> Option<DomRoot<ShadowRoot>> {
+        self.rare_data().as_ref()?.containing_shadow_root.get()
+    }
+
+    pub fn set_containing_shadow_root(&self, shadow_root: Option<&ShadowRoot>) {
+        if shadow_root.is_none() && self.rare_data().is_none() {
+            return;
+        }
+        self.ensure_rare_data().containing_shadow_root.set(shadow_root);
+    }
+
+    /// <https://dom.spec.whatwg.org/#slotable-assigned-slot>
+    pub fn assigned_slot(&self) -> Option<DomRoot<HTMLSlotElement>> {
+        self.rare_data().as_ref()?.assigned_slot.get()
+    }
+
+    pub fn set_assigned_slot(&self, slot: Option<&HTMLSlotElement>) {
+        if slot.is_none() && self.rare_data().is_none() {
+            return;
+        }
+        self.ensure_rare_data().assigned_slot.set(slot);
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-shadow-including-root>
+    pub fn shadow_including_root(&self) -> DomRoot<Node> {
+        let root = self.inclusive_ancestors().last().unwrap();
+        match root.downcast::<ShadowRoot>() {
+            Some(shadow_root) => shadow_root.Host().upcast::<Node>().shadow_including_root(),
+            None => root,
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-shadow-including-inclusive-ancestor>
+    pub fn is_shadow_including_inclusive_ancestor_of(&self, node: &Node) -> bool {
+        let mut current = Some(DomRoot::from_ref(node));
+        while let Some(node) = current {
+            if self.is_inclusive_ancestor_of(&node) {
+                return true;
+            }
+            current = node.containing_shadow_root().map(|root| DomRoot::upcast(root.Host()));
+        }
+        false
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-tree-host-including-inclusive-ancestor>
+    pub fn is_host_including_inclusive_ancestor_of(&self, node: &Node) -> bool {
+        let mut current = Some(DomRoot::from_ref(node));
+        while let Some(node) = current {
+            if self.is_inclusive_ancestor_of(&node) {
+                return true;
+            }
+            let root = node.inclusive_ancestors().last().unwrap();
+            current = match root.downcast::<DocumentFragment>() {
+                Some(fragment) => fragment.host().map(DomRoot::upcast),
+                None => None,
+            };
+        }
+        false
+    }
+
+    /// Returns the parent of this node in the flat tree, that is, the slot it
+    /// is assigned to, or the host of its parent if its parent is a shadow
+    /// root.
+    pub fn flat_tree_parent(&self) -> Option<DomRoot<Node>> {
+        if let Some(slot) = self.assigned_slot() {
+            return Some(DomRoot::upcast(slot));
+        }
+        let parent = self.GetParentNode()?;
+        match parent.downcast::<ShadowRoot>() {
+            Some(shadow_root) => Some(DomRoot::upcast(shadow_root.Host())),
+            None => Some(parent),
+        }
+    }
+
     /// Returns the type ID of this node.
     pub fn type_id(&self) -> NodeTypeId {
         match *self.eventtarget.type_id() {
@@ -491,12 +605,16 @@ impl Node {
     pub fn note_dirty_descendants(&self) {
         debug_assert!(self.is_in_doc());
 
-        for ancestor in self.inclusive_ancestors() {
+        // Layout traverses the flat tree, so that's what the flag needs to be
+        // propagated through.
+        let mut current = Some(DomRoot::from_ref(self));
+        while let Some(ancestor) = current {
             if ancestor.get_flag(NodeFlags::HAS_DIRTY_DESCENDANTS) {
                 return;
             }
 
             ancestor.set_flag(NodeFlags::HAS_DIRTY_DESCENDANTS, true);
+            current = ancestor.flat_tree_parent();
         }
     }
 
@@ -524,10 +642,20 @@ impl Node {
         }
 
         match self.type_id() {
-            NodeTypeId::CharacterData(CharacterDataTypeId::Text) =>
-                self.parent_node.get().unwrap().downcast::<Element>().unwrap().restyle(damage),
+            NodeTypeId::CharacterData(CharacterDataTypeId::Text) => {
+                // Text nodes are styled as part of the element they're laid
+                // out in, which is the host if they're children of a shadow
+                // root.
+                if let Some(parent) = self.flat_tree_parent() {
+                    if let Some(parent) = parent.downcast::<Element>() {
+                        parent.restyle(damage);
+                    }
+                }
+            },
             NodeTypeId::Element(_) =>
                 self.downcast::<Element>().unwrap().restyle(damage),
+            NodeTypeId::DocumentFragment(DocumentFragmentTypeId::ShadowRoot) =>
+                self.downcast::<ShadowRoot>().unwrap().Host().restyle(damage),
             _ => {},
         };
     }
@@ -542,6 +670,14 @@ impl Node {
         TreeIterator::new(self)
     }
 
+    /// Iterates over this node and all its shadow-including descendants, in
+    /// shadow-including preorder.
+    pub fn traverse_preorder_shadow_including(&self) -> ShadowIncludingTreeIterator {
+        ShadowIncludingTreeIterator {
+            stack: vec![TreeIterator::new(self)],
+        }
+    }
+
     pub fn inclusively_following_siblings(&self) -> impl Iterator<Item=DomRoot<Node>> {
         SimpleNodeIterator {
             current: Some(DomRoot::from_ref(self)),
@@ -564,6 +700,23 @@ impl Node {
         parent.ancestors().any(|ancestor| &*ancestor == self)
     }
 
+    /// Returns the editing host this node is an inclusive descendant of, if any.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#editing-host>
+    pub fn editing_host(&self) -> Option<DomRoot<HTMLElement>> {
+        let mut host = None;
+        for ancestor in self.inclusive_ancestors() {
+            if let Some(element) = ancestor.downcast::<HTMLElement>() {
+                match element.content_editable_state() {
+                    Some(true) => host = Some(DomRoot::from_ref(element)),
+                    Some(false) => break,
+                    None => {},
+                }
+            }
+        }
+        host
+    }
+
     pub fn following_siblings(&self) -> impl Iterator<Item=DomRoot<Node>> {
         SimpleNodeIterator {
             current: self.GetNextSibling(),
@@ -857,7 +1010,7 @@ impl Node {
     }
 
     pub fn unique_id(&self) -> String {
-        self.unique_id.borrow().simple().to_string()
+        self.ensure_rare_data().unique_id.borrow().simple().to_string()
     }
 
     pub fn summarize(&self) -> NodeInfo {
@@ -1015,6 +1168,9 @@ pub trait LayoutNodeHelpers {
     unsafe fn prev_sibling_ref(&self) -> Option<LayoutDom<Node>>;
     unsafe fn next_sibling_ref(&self) -> Option<LayoutDom<Node>>;
 
+    unsafe fn containing_shadow_root_ref(&self) -> Option<LayoutDom<ShadowRoot>>;
+    unsafe fn assigned_slot_ref(&self) -> Option<LayoutDom<HTMLSlotElement>>;
+
     unsafe fn owner_doc_for_layout(&self) -> LayoutDom<Document>;
 
     unsafe fn is_element_for_layout(&self) -> bool;
@@ -1080,6 +1236,18 @@ impl LayoutNodeHelpers for LayoutDom<Node> {
         (*self.unsafe_get()).next_sibling.get_inner_as_layout()
     }
 
+    #[inline]
+    #[allow(unsafe_code)]
+    unsafe fn containing_shadow_root_ref(&self) -> Option<LayoutDom<ShadowRoot>> {
+        (*self.unsafe_get()).rare_data.borrow_for_layout().as_ref()?.containing_shadow_root.get_inner_as_layout()
+    }
+
+    #[inline]
+    #[allow(unsafe_code)]
+    unsafe fn assigned_slot_ref(&self) -> Option<LayoutDom<HTMLSlotElement>> {
+        (*self.unsafe_get()).rare_data.borrow_for_layout().as_ref()?.assigned_slot.get_inner_as_layout()
+    }
+
     #[inline]
     #[allow(unsafe_code)]
     unsafe fn owner_doc_for_layout(&self) -> LayoutDom<Document> {
@@ -1153,6 +1321,10 @@ impl LayoutNodeHelpers for LayoutDom<Node> {
 
     #[allow(unsafe_code)]
     fn selection(&self) -> Option<Range<usize>> {
+        if let Some(text) = self.downcast::<Text>() {
+            return unsafe { text.selection_for_layout() };
+        }
+
         if let Some(area) = self.downcast::<HTMLTextAreaElement>() {
             return unsafe { area.selection_for_layout() };
         }
@@ -1361,6 +1533,39 @@ impl Iterator for TreeIterator {
     }
 }
 
+/// An iterator over the shadow-including descendants of a node.
+///
+/// <https://dom.spec.whatwg.org/#concept-shadow-including-preorder-depth-first-traversal>
+pub struct ShadowIncludingTreeIterator {
+    /// The iterators over the trees being traversed, the innermost shadow
+    /// tree last.
+    stack: Vec<TreeIterator>,
+}
+
+impl Iterator for ShadowIncludingTreeIterator {
+    type Item = DomRoot<Node>;
+
+    fn next(&mut self) -> Option<DomRoot<Node>> {
+        loop {
+            let next = match self.stack.last_mut() {
+                Some(iterator) => iterator.next(),
+                None => return None,
+            };
+            match next {
+                Some(node) => {
+                    if let Some(shadow_root) = node.downcast::<Element>().and_then(|e| e.shadow_root()) {
+                        self.stack.push(TreeIterator::new(shadow_root.upcast()));
+                    }
+                    return Some(node);
+                },
+                None => {
+                    self.stack.pop();
+                },
+            }
+        }
+    }
+}
+
 /// Specifies whether children must be recursively cloned or not.
 #[derive(Clone, Copy, MallocSizeOf, PartialEq)]
 pub enum CloneChildrenFlag {
@@ -1402,7 +1607,7 @@ impl Node {
             next_sibling: Default::default(),
             prev_sibling: Default::default(),
             owner_doc: MutNullableDom::new(doc),
-            child_list: Default::default(),
+            rare_data: Default::default(),
             children_count: Cell::new(0u32),
             flags: Cell::new(flags),
             inclusive_descendants_version: Cell::new(0),
@@ -1411,8 +1616,6 @@ impl Node {
             style_and_layout_data: Cell::new(None),
 
             mutation_observers: Default::default(),
-
-            unique_id: UniqueId::new(),
         }
     }
 
@@ -1447,13 +1650,13 @@ impl Node {
         // Step 1.
         match parent.type_id() {
             NodeTypeId::Document(_) |
-            NodeTypeId::DocumentFragment |
+            NodeTypeId::DocumentFragment(_) |
             NodeTypeId::Element(..) => (),
             _ => return Err(Error::HierarchyRequest)
         }
 
         // Step 2.
-        if node.is_inclusive_ancestor_of(parent) {
+        if node.is_host_including_inclusive_ancestor_of(parent) {
             return Err(Error::HierarchyRequest);
         }
 
@@ -1476,7 +1679,7 @@ impl Node {
                     return Err(Error::HierarchyRequest);
                 }
             },
-            NodeTypeId::DocumentFragment |
+            NodeTypeId::DocumentFragment(_) |
             NodeTypeId::Element(_) |
             NodeTypeId::CharacterData(CharacterDataTypeId::ProcessingInstruction) |
             NodeTypeId::CharacterData(CharacterDataTypeId::Comment) => (),
@@ -1487,7 +1690,7 @@ impl Node {
         if parent.is::<Document>() {
             match node.type_id() {
                 // Step 6.1
-                NodeTypeId::DocumentFragment => {
+                NodeTypeId::DocumentFragment(_) => {
                     // Step 6.1.1(b)
                     if node.children()
                            .any(|c| c.is::<Text>())
@@ -1604,7 +1807,7 @@ impl Node {
             }
         }
         rooted_vec!(let mut new_nodes);
-        let new_nodes = if let NodeTypeId::DocumentFragment = node.type_id() {
+        let new_nodes = if let NodeTypeId::DocumentFragment(_) = node.type_id() {
             // Step 3.
             new_nodes.extend(node.children().map(|kid| Dom::from_ref(&*kid)));
             // Step 4.
@@ -1641,6 +1844,24 @@ impl Node {
         for kid in new_nodes {
             // Step 7.1.
             parent.add_child(*kid, child);
+            // Step 7.2.
+            let parent_is_shadow_host =
+                parent.downcast::<Element>().map_or(false, |parent| parent.shadow_root().is_some());
+            if parent_is_shadow_host && HTMLSlotElement::is_slottable(kid) {
+                HTMLSlotElement::assign_a_slot(kid);
+            }
+            // Step 7.3.
+            if parent.is_in_shadow_tree() {
+                if let Some(slot) = parent.downcast::<HTMLSlotElement>() {
+                    if !slot.has_assigned_nodes() {
+                        slot.signal_a_slot_change();
+                    }
+                }
+            }
+            // Step 7.4.
+            if kid.is_in_shadow_tree() && kid.traverse_preorder().any(|node| node.is::<HTMLSlotElement>()) {
+                HTMLSlotElement::assign_slottables_for_a_tree(&kid.inclusive_ancestors().last().unwrap());
+            }
             // Step 7.7.
             for descendant in kid.traverse_preorder().filter_map(DomRoot::downcast::<Element>) {
                 // Step 7.7.2.
@@ -1680,7 +1901,7 @@ impl Node {
         // Step 3.
         rooted_vec!(let mut added_nodes);
         let added_nodes = if let Some(node) = node.as_ref() {
-            if let NodeTypeId::DocumentFragment = node.type_id() {
+            if let NodeTypeId::DocumentFragment(_) = node.type_id() {
                 added_nodes.extend(node.children().map(|child| Dom::from_ref(&*child)));
                 added_nodes.r()
             } else {
@@ -1752,7 +1973,23 @@ impl Node {
         // Step 8.
         let old_next_sibling = node.GetNextSibling();
         // Steps 9-10 are handled in unbind_from_tree.
+        let assigned_slot = node.assigned_slot();
         parent.remove_child(node, cached_index);
+        // Update the slot assignments the removal affects.
+        if let Some(slot) = assigned_slot {
+            slot.assign_slottables();
+        }
+        if parent.is_in_shadow_tree() {
+            if let Some(slot) = parent.downcast::<HTMLSlotElement>() {
+                if !slot.has_assigned_nodes() {
+                    slot.signal_a_slot_change();
+                }
+            }
+        }
+        if node.traverse_preorder().any(|node| node.is::<HTMLSlotElement>()) {
+            HTMLSlotElement::assign_slottables_for_a_tree(&parent.inclusive_ancestors().last().unwrap());
+            HTMLSlotElement::assign_slottables_for_a_tree(node);
+        }
         // Step 11. transient registered observers
         // Step 12.
         if let SuppressObserver::Unsuppressed = suppress_observers {
@@ -1792,7 +2029,7 @@ impl Node {
                                                 &document);
                 DomRoot::upcast::<Node>(doctype)
             },
-            NodeTypeId::DocumentFragment => {
+            NodeTypeId::DocumentFragment(_) => {
                 let doc_fragment = DocumentFragment::new(&document);
                 DomRoot::upcast::<Node>(doc_fragment)
             },
@@ -1915,7 +2152,7 @@ impl Node {
                     .GetDocumentElement().as_ref()
                     .map_or(ns!(), |elem| elem.locate_namespace(prefix))
             },
-            NodeTypeId::DocumentType | NodeTypeId::DocumentFragment => ns!(),
+            NodeTypeId::DocumentType | NodeTypeId::DocumentFragment(_) => ns!(),
             _ => {
                 node.GetParentElement().as_ref()
                     .map_or(ns!(), |elem| elem.locate_namespace(prefix))
@@ -1938,7 +2175,7 @@ impl NodeMethods for Node {
                 NodeConstants::DOCUMENT_NODE,
             NodeTypeId::DocumentType =>
                 NodeConstants::DOCUMENT_TYPE_NODE,
-            NodeTypeId::DocumentFragment =>
+            NodeTypeId::DocumentFragment(_) =>
                 NodeConstants::DOCUMENT_FRAGMENT_NODE,
             NodeTypeId::Element(_) =>
                 NodeConstants::ELEMENT_NODE,
@@ -1959,7 +2196,7 @@ impl NodeMethods for Node {
             NodeTypeId::DocumentType => {
                 self.downcast::<DocumentType>().unwrap().name().clone()
             },
-            NodeTypeId::DocumentFragment => DOMString::from("#document-fragment"),
+            NodeTypeId::DocumentFragment(_) => DOMString::from("#document-fragment"),
             NodeTypeId::Document(_) => DOMString::from("#document")
         }
     }
@@ -1975,14 +2212,18 @@ impl NodeMethods for Node {
             NodeTypeId::CharacterData(..) |
             NodeTypeId::Element(..) |
             NodeTypeId::DocumentType |
-            NodeTypeId::DocumentFragment => Some(self.owner_doc()),
+            NodeTypeId::DocumentFragment(_) => Some(self.owner_doc()),
             NodeTypeId::Document(_) => None
         }
     }
 
     // https://dom.spec.whatwg.org/#dom-node-getrootnode
-    fn GetRootNode(&self) -> DomRoot<Node> {
-        self.inclusive_ancestors().last().unwrap()
+    fn GetRootNode(&self, options: &GetRootNodeOptions) -> DomRoot<Node> {
+        if options.composed {
+            self.shadow_including_root()
+        } else {
+            self.inclusive_ancestors().last().unwrap()
+        }
     }
 
     // https://dom.spec.whatwg.org/#dom-node-parentnode
@@ -2002,7 +2243,7 @@ impl NodeMethods for Node {
 
     // https://dom.spec.whatwg.org/#dom-node-childnodes
     fn ChildNodes(&self) -> DomRoot<NodeList> {
-        self.child_list.or_init(|| {
+        self.ensure_rare_data().child_list.or_init(|| {
             let doc = self.owner_doc();
             let window = doc.window();
             NodeList::new_child_list(window, self)
@@ -2044,7 +2285,7 @@ impl NodeMethods for Node {
     // https://dom.spec.whatwg.org/#dom-node-textcontent
     fn GetTextContent(&self) -> Option<DOMString> {
         match self.type_id() {
-            NodeTypeId::DocumentFragment |
+            NodeTypeId::DocumentFragment(_) |
             NodeTypeId::Element(..) => {
                 let content = Node::collect_text_contents(self.traverse_preorder());
                 Some(content)
@@ -2064,7 +2305,7 @@ impl NodeMethods for Node {
     fn SetTextContent(&self, value: Option<DOMString>) {
         let value = value.unwrap_or_default();
         match self.type_id() {
-            NodeTypeId::DocumentFragment |
+            NodeTypeId::DocumentFragment(_) |
             NodeTypeId::Element(..) => {
                 // Step 1-2.
                 let node = if value.is_empty() {
@@ -2100,7 +2341,7 @@ impl NodeMethods for Node {
         // Step 1.
         match self.type_id() {
             NodeTypeId::Document(_) |
-            NodeTypeId::DocumentFragment |
+            NodeTypeId::DocumentFragment(_) |
             NodeTypeId::Element(..) => (),
             _ => return Err(Error::HierarchyRequest)
         }
@@ -2128,7 +2369,7 @@ impl NodeMethods for Node {
         if self.is::<Document>() {
             match node.type_id() {
                 // Step 6.1
-                NodeTypeId::DocumentFragment => {
+                NodeTypeId::DocumentFragment(_) => {
                     // Step 6.1.1(b)
                     if node.children()
                            .any(|c| c.is::<Text>())
@@ -2209,7 +2450,7 @@ impl NodeMethods for Node {
 
         // Step 12.
         rooted_vec!(let mut nodes);
-        let nodes = if node.type_id() == NodeTypeId::DocumentFragment {
+        let nodes = if node.is::<DocumentFragment>() {
             nodes.extend(node.children().map(|node| Dom::from_ref(&*node)));
             nodes.r()
         } else {
@@ -2270,12 +2511,18 @@ impl NodeMethods for Node {
     }
 
     // https://dom.spec.whatwg.org/#dom-node-clonenode
-    fn CloneNode(&self, deep: bool) -> DomRoot<Node> {
-        Node::clone(self, None, if deep {
+    fn CloneNode(&self, deep: bool) -> Fallible<DomRoot<Node>> {
+        // Step 1.
+        if self.is::<ShadowRoot>() {
+            return Err(Error::NotSupported);
+        }
+
+        // Step 2.
+        Ok(Node::clone(self, None, if deep {
             CloneChildrenFlag::CloneChildren
         } else {
             CloneChildrenFlag::DoNotCloneChildren
-        })
+        }))
     }
 
     // https://dom.spec.whatwg.org/#dom-node-isequalnode
@@ -2463,7 +2710,7 @@ impl NodeMethods for Node {
                     element.lookup_prefix(namespace)
                 })
             },
-            NodeTypeId::DocumentType | NodeTypeId::DocumentFragment => None,
+            NodeTypeId::DocumentType | NodeTypeId::DocumentFragment(_) => None,
             _ => {
                 self.GetParentElement().and_then(|element| {
                     element.lookup_prefix(namespace)
@@ -2511,7 +2758,8 @@ impl VirtualMethods for Node {
         if let Some(ref s) = self.super_type() {
             s.children_changed(mutation);
         }
-        if let Some(list) = self.child_list.get() {
+        let child_list = self.rare_data().as_ref().and_then(|rare_data| rare_data.child_list.get());
+        if let Some(list) = child_list {
             list.as_children_list().children_changed(mutation);
         }
         self.owner_doc().content_and_heritage_changed(self);
@@ -2604,6 +2852,17 @@ impl<'a> ChildrenMutation<'a> {
     /// Currently only used when this mutation might force us to
     /// restyle later children (see HAS_SLOW_SELECTOR_LATER_SIBLINGS and
     /// Element's implementation of VirtualMethods::children_changed).
+    pub fn prev_child(&self) -> Option<&Node> {
+        match *self {
+            ChildrenMutation::Append { prev, .. } => Some(prev),
+            ChildrenMutation::Insert { prev, .. } => Some(prev),
+            ChildrenMutation::Prepend { .. } => None,
+            ChildrenMutation::Replace { prev, .. } => prev,
+            ChildrenMutation::ReplaceAll { .. } => None,
+            ChildrenMutation::ChangeText => None,
+        }
+    }
+
     pub fn next_child(&self) -> Option<&Node> {
         match *self {
             ChildrenMutation::Append { .. } => None,
@@ -2693,7 +2952,9 @@ impl<'a> UnbindContext<'a> {
 }
 
 /// A node's unique ID, for devtools.
-struct UniqueId {
+/// A unique ID, whose underlying `Uuid` is lazily created.
+#[derive(Default)]
+pub struct UniqueId {
     cell: UnsafeCell<Option<Box<Uuid>>>,
 }
 
@@ -2711,11 +2972,6 @@ impl MallocSizeOf for UniqueId {
 }
 
 impl UniqueId {
-    /// Create a new `UniqueId` value. The underlying `Uuid` is lazily created.
-    fn new() -> UniqueId {
-        UniqueId { cell: UnsafeCell::new(None) }
-    }
-
     /// The Uuid of that unique ID.
     #[allow(unsafe_code)]
     fn borrow(&self) -> &Uuid {
@@ -0,0 +1,320 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_traits::canvas::{CanvasMsg, FromScriptMsg, byte_swap};
+use canvas_traits::webgl::WebGLVersion;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::ImageDataBinding::ImageDataMethods;
+use dom::bindings::codegen::Bindings::OffscreenCanvasBinding;
+use dom::bindings::codegen::Bindings::OffscreenCanvasBinding::{ImageEncodeOptions, OffscreenCanvasMethods};
+use dom::bindings::codegen::Bindings::OffscreenCanvasBinding::OffscreenRenderingContext;
+use dom::bindings::codegen::Bindings::OffscreenCanvasRenderingContext2DBinding::
+    OffscreenCanvasRenderingContext2DMethods;
+use dom::bindings::codegen::UnionTypes::HTMLCanvasElementOrOffscreenCanvas;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::num::Finite;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
+use dom::blob::{Blob, BlobImpl};
+use dom::eventtarget::EventTarget;
+use dom::globalscope::GlobalScope;
+use dom::htmlcanvaselement::HTMLCanvasElement;
+use dom::htmlcanvaselement::utils as canvas_utils;
+use dom::imagebitmap::{self, ImageBitmap};
+use dom::offscreencanvasrenderingcontext2d::OffscreenCanvasRenderingContext2D;
+use dom::promise::Promise;
+use dom::webgl2renderingcontext::WebGL2RenderingContext;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use euclid::Size2D;
+use js::jsapi::JSContext;
+use js::rust::HandleValue;
+use profile_traits::ipc;
+use servo_config::prefs::PREFS;
+use std::cell::Cell;
+use std::cmp;
+use std::i32;
+use std::rc::Rc;
+
+#[must_root]
+#[derive(Clone, JSTraceable, MallocSizeOf)]
+pub enum OffscreenCanvasContext {
+    OffscreenContext2d(Dom<OffscreenCanvasRenderingContext2D>),
+    WebGL(Dom<WebGLRenderingContext>),
+    WebGL2(Dom<WebGL2RenderingContext>),
+}
+
+// https://html.spec.whatwg.org/multipage/#the-offscreencanvas-interface
+#[dom_struct]
+pub struct OffscreenCanvas {
+    eventtarget: EventTarget,
+    width: Cell<u64>,
+    height: Cell<u64>,
+    context: DomRefCell<Option<OffscreenCanvasContext>>,
+}
+
+impl OffscreenCanvas {
+    fn new_inherited(width: u64, height: u64) -> OffscreenCanvas {
+        OffscreenCanvas {
+            eventtarget: EventTarget::new_inherited(),
+            width: Cell::new(width),
+            height: Cell::new(height),
+            context: DomRefCell::new(None),
+        }
+    }
+
+    pub fn new(global: &GlobalScope, width: u64, height: u64) -> DomRoot<OffscreenCanvas> {
+        reflect_dom_object(Box::new(OffscreenCanvas::new_inherited(width, height)),
+                           global,
+                           OffscreenCanvasBinding::Wrap)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas
+    pub fn Constructor(global: &GlobalScope, width: u64, height: u64) -> Fallible<DomRoot<OffscreenCanvas>> {
+        Ok(OffscreenCanvas::new(global, width, height))
+    }
+
+    pub fn get_size(&self) -> Size2D<i32> {
+        let clamp = |value: u64| cmp::min(value, i32::MAX as u64) as i32;
+        Size2D::new(clamp(self.width.get()), clamp(self.height.get()))
+    }
+
+    pub fn origin_is_clean(&self) -> bool {
+        match *self.context.borrow() {
+            Some(OffscreenCanvasContext::OffscreenContext2d(ref context)) => context.origin_is_clean(),
+            _ => true,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#offscreencanvas-set-bitmap-dimensions
+    fn recreate_contexts(&self) {
+        let size = self.get_size();
+        if let Some(ref context) = *self.context.borrow() {
+            match *context {
+                OffscreenCanvasContext::OffscreenContext2d(ref context) => context.set_bitmap_dimensions(size),
+                OffscreenCanvasContext::WebGL(ref context) => context.recreate(size),
+                OffscreenCanvasContext::WebGL2(ref context) => context.recreate(size),
+            }
+        }
+    }
+
+    /// Returns the bitmap of the canvas as premultiplied BGRA pixels, the same
+    /// format as `HTMLCanvasElement::fetch_all_data`.
+    pub fn fetch_all_data(&self) -> Option<(Vec<u8>, Size2D<i32>)> {
+        let size = self.get_size();
+
+        if size.width == 0 || size.height == 0 {
+            return None
+        }
+
+        let data = match self.context.borrow().as_ref() {
+            Some(&OffscreenCanvasContext::OffscreenContext2d(ref context)) => {
+                let (sender, receiver) = ipc::channel(self.global().time_profiler_chan().clone()).unwrap();
+                let msg = CanvasMsg::FromScript(FromScriptMsg::SendPixels(sender), context.get_canvas_id());
+                context.get_ipc_renderer().send(msg).unwrap();
+
+                receiver.recv().unwrap()?.into()
+            },
+            Some(&OffscreenCanvasContext::WebGL(ref context)) => context.get_bgra_data(size)?,
+            Some(&OffscreenCanvasContext::WebGL2(ref context)) => context.base_context().get_bgra_data(size)?,
+            None => {
+                // Each pixel is fully-transparent black.
+                vec![0; (size.height as usize) * (size.width as usize) * 4]
+            }
+        };
+
+        Some((data, size))
+    }
+
+    /// Reads back the contents of the canvas as unpremultiplied RGBA pixels.
+    fn get_rgba_data(&self) -> Fallible<Option<Vec<u8>>> {
+        let size = self.get_size();
+        let data = match *self.context.borrow() {
+            Some(OffscreenCanvasContext::OffscreenContext2d(ref context)) => {
+                let image_data = context.GetImageData(Finite::wrap(0f64), Finite::wrap(0f64),
+                                                      Finite::wrap(size.width as f64),
+                                                      Finite::wrap(size.height as f64))?;
+                Some(image_data.get_data_array())
+            }
+            Some(OffscreenCanvasContext::WebGL(_)) | Some(OffscreenCanvasContext::WebGL2(_)) => {
+                // Unlike readPixels, this reads the drawing buffer top row first.
+                self.fetch_all_data().map(|(mut data, _)| {
+                    imagebitmap::unpremultiply(&mut data);
+                    byte_swap(&mut data);
+                    data
+                })
+            }
+            None => Some(vec![0; (size.width * size.height * 4) as usize]),
+        };
+        Ok(data)
+    }
+
+    fn get_or_init_2d_context(&self) -> Option<DomRoot<OffscreenCanvasRenderingContext2D>> {
+        if self.context.borrow().is_none() {
+            let context = OffscreenCanvasRenderingContext2D::new(&self.global(), self, self.get_size());
+            *self.context.borrow_mut() = Some(OffscreenCanvasContext::OffscreenContext2d(Dom::from_ref(&*context)));
+        }
+
+        match *self.context.borrow() {
+            Some(OffscreenCanvasContext::OffscreenContext2d(ref context)) => Some(DomRoot::from_ref(&*context)),
+            _ => None,
+        }
+    }
+
+    fn get_or_init_webgl_context(
+        &self,
+        cx: *mut JSContext,
+        options: HandleValue,
+    ) -> Option<DomRoot<WebGLRenderingContext>> {
+        if self.context.borrow().is_none() {
+            let attrs = HTMLCanvasElement::get_gl_attributes(cx, Some(options))?;
+            let canvas = HTMLCanvasElementOrOffscreenCanvas::OffscreenCanvas(DomRoot::from_ref(self));
+            let maybe_ctx = WebGLRenderingContext::new(
+                &self.global(), &canvas, WebGLVersion::WebGL1, self.get_size(), attrs
+            );
+
+            *self.context.borrow_mut() = maybe_ctx.map(|ctx| OffscreenCanvasContext::WebGL(Dom::from_ref(&*ctx)));
+        }
+
+        if let Some(OffscreenCanvasContext::WebGL(ref context)) = *self.context.borrow() {
+            Some(DomRoot::from_ref(&*context))
+        } else {
+            None
+        }
+    }
+
+    fn get_or_init_webgl2_context(
+        &self,
+        cx: *mut JSContext,
+        options: HandleValue,
+    ) -> Option<DomRoot<WebGL2RenderingContext>> {
+        if !PREFS.is_webgl2_enabled() {
+            return None
+        }
+        if self.context.borrow().is_none() {
+            let attrs = HTMLCanvasElement::get_gl_attributes(cx, Some(options))?;
+            let canvas = HTMLCanvasElementOrOffscreenCanvas::OffscreenCanvas(DomRoot::from_ref(self));
+            let maybe_ctx = WebGL2RenderingContext::new(&self.global(), &canvas, self.get_size(), attrs);
+
+            *self.context.borrow_mut() = maybe_ctx.map(|ctx| OffscreenCanvasContext::WebGL2(Dom::from_ref(&*ctx)));
+        }
+
+        if let Some(OffscreenCanvasContext::WebGL2(ref context)) = *self.context.borrow() {
+            Some(DomRoot::from_ref(&*context))
+        } else {
+            None
+        }
+    }
+}
+
+impl OffscreenCanvasMethods for OffscreenCanvas {
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-width
+    fn Width(&self) -> u64 {
+        self.width.get()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-width
+    fn SetWidth(&self, value: u64) {
+        self.width.set(value);
+        self.recreate_contexts();
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-height
+    fn Height(&self) -> u64 {
+        self.height.get()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-height
+    fn SetHeight(&self, value: u64) {
+        self.height.set(value);
+        self.recreate_contexts();
+    }
+
+    #[allow(unsafe_code)]
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-getcontext
+    unsafe fn GetContext(&self,
+                         cx: *mut JSContext,
+                         id: DOMString,
+                         options: HandleValue)
+                         -> Option<OffscreenRenderingContext> {
+        match &*id {
+            "2d" => {
+                self.get_or_init_2d_context()
+                    .map(OffscreenRenderingContext::OffscreenCanvasRenderingContext2D)
+            }
+            "webgl" => {
+                self.get_or_init_webgl_context(cx, options)
+                    .map(OffscreenRenderingContext::WebGLRenderingContext)
+            }
+            "webgl2" => {
+                self.get_or_init_webgl2_context(cx, options)
+                    .map(OffscreenRenderingContext::WebGL2RenderingContext)
+            }
+            // TODO: ImageBitmapRenderingContext isn't supported yet.
+            _ => None,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-transfertoimagebitmap
+    fn TransferToImageBitmap(&self) -> Fallible<DomRoot<ImageBitmap>> {
+        // Step 2.
+        if self.context.borrow().is_none() {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 3.
+        let size = self.get_size();
+        let data = if size.width == 0 || size.height == 0 {
+            vec![]
+        } else {
+            let (mut data, _) = self.fetch_all_data().ok_or(Error::InvalidState)?;
+            imagebitmap::unpremultiply(&mut data);
+            data
+        };
+        let bitmap = ImageBitmap::new(&self.global(),
+                                      size.width as u32,
+                                      size.height as u32,
+                                      data,
+                                      self.origin_is_clean());
+
+        // Step 4. Start over with a transparent black bitmap of the same size.
+        self.recreate_contexts();
+
+        Ok(bitmap)
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-converttoblob
+    fn ConvertToBlob(&self, _options: &ImageEncodeOptions) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+
+        // Step 2.
+        if !self.origin_is_clean() {
+            promise.reject_error(Error::Security);
+            return promise;
+        }
+
+        // Step 3.
+        let size = self.get_size();
+        if size.width == 0 || size.height == 0 {
+            promise.reject_error(Error::IndexSize);
+            return promise;
+        }
+
+        // Steps 4-6. Only image/png is supported for now, so the options are
+        // ignored, and the encoding is done synchronously.
+        match self.get_rgba_data() {
+            Ok(Some(data)) => {
+                let encoded = canvas_utils::encode_png(&data, size.width as u32, size.height as u32);
+                let blob = Blob::new(&global, BlobImpl::new_from_bytes(encoded), "image/png".to_owned());
+                promise.resolve_native(&blob);
+            },
+            Ok(None) => promise.reject_error(Error::InvalidState),
+            Err(error) => promise.reject_error(error),
+        }
+        promise
+    }
+}
@@ -0,0 +1,496 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_traits::canvas::{CanvasId, CanvasMsg};
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasFillRule;
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasImageSource;
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasLineCap;
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasLineJoin;
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasRenderingContext2DMethods;
+use dom::bindings::codegen::Bindings::OffscreenCanvasRenderingContext2DBinding;
+use dom::bindings::codegen::Bindings::OffscreenCanvasRenderingContext2DBinding::
+    OffscreenCanvasRenderingContext2DMethods;
+use dom::bindings::codegen::UnionTypes::StringOrCanvasGradientOrCanvasPattern;
+use dom::bindings::error::ErrorResult;
+use dom::bindings::error::Fallible;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::num::Finite;
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
+use dom::canvasgradient::CanvasGradient;
+use dom::canvaspattern::CanvasPattern;
+use dom::canvasrenderingcontext2d::CanvasRenderingContext2D;
+use dom::dommatrix::DOMMatrix;
+use dom::globalscope::GlobalScope;
+use dom::imagedata::ImageData;
+use dom::offscreencanvas::OffscreenCanvas;
+use dom::path2d::Path2D;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use euclid::Size2D;
+use ipc_channel::ipc::IpcSender;
+
+// https://html.spec.whatwg.org/multipage/#offscreencanvasrenderingcontext2d
+#[dom_struct]
+pub struct OffscreenCanvasRenderingContext2D {
+    context: CanvasRenderingContext2D,
+    canvas: Dom<OffscreenCanvas>,
+}
+
+impl OffscreenCanvasRenderingContext2D {
+    fn new_inherited(global: &GlobalScope,
+                     canvas: &OffscreenCanvas,
+                     size: Size2D<i32>)
+                     -> OffscreenCanvasRenderingContext2D {
+        // Only windows have an image cache; workers can't draw images that
+        // would need one anyway.
+        let image_cache = global.downcast::<Window>().map(|window| window.image_cache());
+        let base_url = global.api_base_url();
+        OffscreenCanvasRenderingContext2D {
+            context: CanvasRenderingContext2D::new_inherited(global, None, image_cache, base_url, size),
+            canvas: Dom::from_ref(canvas),
+        }
+    }
+
+    pub fn new(global: &GlobalScope,
+               canvas: &OffscreenCanvas,
+               size: Size2D<i32>)
+               -> DomRoot<OffscreenCanvasRenderingContext2D> {
+        reflect_dom_object(Box::new(OffscreenCanvasRenderingContext2D::new_inherited(global, canvas, size)),
+                           global,
+                           OffscreenCanvasRenderingContext2DBinding::Wrap)
+    }
+
+    pub fn set_bitmap_dimensions(&self, size: Size2D<i32>) {
+        self.context.set_bitmap_dimensions(size)
+    }
+
+    pub fn get_canvas_id(&self) -> CanvasId {
+        self.context.get_canvas_id()
+    }
+
+    pub fn get_ipc_renderer(&self) -> IpcSender<CanvasMsg> {
+        self.context.get_ipc_renderer()
+    }
+
+    pub fn origin_is_clean(&self) -> bool {
+        self.context.origin_is_clean()
+    }
+}
+
+impl OffscreenCanvasRenderingContext2DMethods for OffscreenCanvasRenderingContext2D {
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvasrenderingcontext2d-commit
+    fn Commit(&self) {
+        // There is no placeholder canvas element to push the bitmap to, since
+        // transferControlToOffscreen() isn't supported yet.
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvasrenderingcontext2d-canvas
+    fn Canvas(&self) -> DomRoot<OffscreenCanvas> {
+        DomRoot::from_ref(&*self.canvas)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-save
+    fn Save(&self) {
+        self.context.Save()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-restore
+    fn Restore(&self) {
+        self.context.Restore()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-scale
+    fn Scale(&self, x: f64, y: f64) {
+        self.context.Scale(x, y)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-rotate
+    fn Rotate(&self, angle: f64) {
+        self.context.Rotate(angle)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-translate
+    fn Translate(&self, x: f64, y: f64) {
+        self.context.Translate(x, y)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-transform
+    fn Transform(&self, a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) {
+        self.context.Transform(a, b, c, d, e, f)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-gettransform
+    fn GetTransform(&self) -> DomRoot<DOMMatrix> {
+        self.context.GetTransform()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-settransform
+    fn SetTransform(&self, a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) {
+        self.context.SetTransform(a, b, c, d, e, f)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-resettransform
+    fn ResetTransform(&self) {
+        self.context.ResetTransform()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-globalalpha
+    fn GlobalAlpha(&self) -> f64 {
+        self.context.GlobalAlpha()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-globalalpha
+    fn SetGlobalAlpha(&self, alpha: f64) {
+        self.context.SetGlobalAlpha(alpha)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-globalcompositeoperation
+    fn GlobalCompositeOperation(&self) -> DOMString {
+        self.context.GlobalCompositeOperation()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-globalcompositeoperation
+    fn SetGlobalCompositeOperation(&self, op_str: DOMString) {
+        self.context.SetGlobalCompositeOperation(op_str)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-fillrect
+    fn FillRect(&self, x: f64, y: f64, width: f64, height: f64) {
+        self.context.FillRect(x, y, width, height)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-clearrect
+    fn ClearRect(&self, x: f64, y: f64, width: f64, height: f64) {
+        self.context.ClearRect(x, y, width, height)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-strokerect
+    fn StrokeRect(&self, x: f64, y: f64, width: f64, height: f64) {
+        self.context.StrokeRect(x, y, width, height)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-beginpath
+    fn BeginPath(&self) {
+        self.context.BeginPath()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-closepath
+    fn ClosePath(&self) {
+        self.context.ClosePath()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-fill
+    fn Fill(&self, fill_rule: CanvasFillRule) {
+        self.context.Fill(fill_rule)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-fill
+    fn Fill_(&self, path: &Path2D, fill_rule: CanvasFillRule) {
+        self.context.Fill_(path, fill_rule)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroke
+    fn Stroke(&self) {
+        self.context.Stroke()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroke
+    fn Stroke_(&self, path: &Path2D) {
+        self.context.Stroke_(path)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-clip
+    fn Clip(&self, fill_rule: CanvasFillRule) {
+        self.context.Clip(fill_rule)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-clip
+    fn Clip_(&self, path: &Path2D, fill_rule: CanvasFillRule) {
+        self.context.Clip_(path, fill_rule)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinpath
+    fn IsPointInPath(&self, x: f64, y: f64, fill_rule: CanvasFillRule) -> bool {
+        self.context.IsPointInPath(x, y, fill_rule)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinpath
+    fn IsPointInPath_(&self, path: &Path2D, x: f64, y: f64, fill_rule: CanvasFillRule) -> bool {
+        self.context.IsPointInPath_(path, x, y, fill_rule)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-drawimage
+    fn DrawImage(&self,
+                 image: CanvasImageSource,
+                 dx: f64,
+                 dy: f64)
+                 -> ErrorResult {
+        self.context.DrawImage(image, dx, dy)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-drawimage
+    fn DrawImage_(&self,
+                  image: CanvasImageSource,
+                  dx: f64,
+                  dy: f64,
+                  dw: f64,
+                  dh: f64)
+                  -> ErrorResult {
+        self.context.DrawImage_(image, dx, dy, dw, dh)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-drawimage
+    fn DrawImage__(&self,
+                   image: CanvasImageSource,
+                   sx: f64,
+                   sy: f64,
+                   sw: f64,
+                   sh: f64,
+                   dx: f64,
+                   dy: f64,
+                   dw: f64,
+                   dh: f64)
+                   -> ErrorResult {
+        self.context.DrawImage__(image, sx, sy, sw, sh, dx, dy, dw, dh)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-createimagedata
+    fn CreateImageData(&self, sw: Finite<f64>, sh: Finite<f64>) -> Fallible<DomRoot<ImageData>> {
+        self.context.CreateImageData(sw, sh)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-createimagedata
+    fn CreateImageData_(&self, imagedata: &ImageData) -> Fallible<DomRoot<ImageData>> {
+        self.context.CreateImageData_(imagedata)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-getimagedata
+    fn GetImageData(&self,
+                    sx: Finite<f64>,
+                    sy: Finite<f64>,
+                    sw: Finite<f64>,
+                    sh: Finite<f64>)
+                    -> Fallible<DomRoot<ImageData>> {
+        self.context.GetImageData(sx, sy, sw, sh)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-putimagedata
+    fn PutImageData(&self, imagedata: &ImageData, dx: Finite<f64>, dy: Finite<f64>) {
+        self.context.PutImageData(imagedata, dx, dy)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-putimagedata
+    fn PutImageData_(&self,
+                     imagedata: &ImageData,
+                     dx: Finite<f64>,
+                     dy: Finite<f64>,
+                     dirty_x: Finite<f64>,
+                     dirty_y: Finite<f64>,
+                     dirty_width: Finite<f64>,
+                     dirty_height: Finite<f64>) {
+        self.context.PutImageData_(imagedata, dx, dy, dirty_x, dirty_y, dirty_width, dirty_height)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-moveto
+    fn MoveTo(&self, x: f64, y: f64) {
+        self.context.MoveTo(x, y)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-lineto
+    fn LineTo(&self, x: f64, y: f64) {
+        self.context.LineTo(x, y)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-rect
+    fn Rect(&self, x: f64, y: f64, width: f64, height: f64) {
+        self.context.Rect(x, y, width, height)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-quadraticcurveto
+    fn QuadraticCurveTo(&self, cpx: f64, cpy: f64, x: f64, y: f64) {
+        self.context.QuadraticCurveTo(cpx, cpy, x, y)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-beziercurveto
+    fn BezierCurveTo(&self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, x: f64, y: f64) {
+        self.context.BezierCurveTo(cp1x, cp1y, cp2x, cp2y, x, y)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-arc
+    fn Arc(&self, x: f64, y: f64, r: f64, start: f64, end: f64, ccw: bool) -> ErrorResult {
+        self.context.Arc(x, y, r, start, end, ccw)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-arcto
+    fn ArcTo(&self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, r: f64) -> ErrorResult {
+        self.context.ArcTo(cp1x, cp1y, cp2x, cp2y, r)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ellipse
+    fn Ellipse(&self, x: f64, y: f64, rx: f64, ry: f64, rotation: f64, start: f64, end: f64, ccw: bool) -> ErrorResult {
+        self.context.Ellipse(x, y, rx, ry, rotation, start, end, ccw)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-imagesmoothingenabled
+    fn ImageSmoothingEnabled(&self) -> bool {
+        self.context.ImageSmoothingEnabled()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-imagesmoothingenabled
+    fn SetImageSmoothingEnabled(&self, value: bool) {
+        self.context.SetImageSmoothingEnabled(value)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-strokestyle
+    fn StrokeStyle(&self) -> StringOrCanvasGradientOrCanvasPattern {
+        self.context.StrokeStyle()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-strokestyle
+    fn SetStrokeStyle(&self, value: StringOrCanvasGradientOrCanvasPattern) {
+        self.context.SetStrokeStyle(value)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-strokestyle
+    fn FillStyle(&self) -> StringOrCanvasGradientOrCanvasPattern {
+        self.context.FillStyle()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-strokestyle
+    fn SetFillStyle(&self, value: StringOrCanvasGradientOrCanvasPattern) {
+        self.context.SetFillStyle(value)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-createlineargradient
+    fn CreateLinearGradient(&self,
+                            x0: Finite<f64>,
+                            y0: Finite<f64>,
+                            x1: Finite<f64>,
+                            y1: Finite<f64>)
+                            -> DomRoot<CanvasGradient> {
+        self.context.CreateLinearGradient(x0, y0, x1, y1)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-createradialgradient
+    fn CreateRadialGradient(&self,
+                            x0: Finite<f64>,
+                            y0: Finite<f64>,
+                            r0: Finite<f64>,
+                            x1: Finite<f64>,
+                            y1: Finite<f64>,
+                            r1: Finite<f64>)
+                            -> Fallible<DomRoot<CanvasGradient>> {
+        self.context.CreateRadialGradient(x0, y0, r0, x1, y1, r1)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-createpattern
+    fn CreatePattern(&self,
+                     image: CanvasImageSource,
+                     repetition: DOMString)
+                     -> Fallible<DomRoot<CanvasPattern>> {
+        self.context.CreatePattern(image, repetition)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-linewidth
+    fn LineWidth(&self) -> f64 {
+        self.context.LineWidth()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-linewidth
+    fn SetLineWidth(&self, width: f64) {
+        self.context.SetLineWidth(width)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-linecap
+    fn LineCap(&self) -> CanvasLineCap {
+        self.context.LineCap()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-linecap
+    fn SetLineCap(&self, cap: CanvasLineCap) {
+        self.context.SetLineCap(cap)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-linejoin
+    fn LineJoin(&self) -> CanvasLineJoin {
+        self.context.LineJoin()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-linejoin
+    fn SetLineJoin(&self, join: CanvasLineJoin) {
+        self.context.SetLineJoin(join)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-miterlimit
+    fn MiterLimit(&self) -> f64 {
+        self.context.MiterLimit()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-miterlimit
+    fn SetMiterLimit(&self, limit: f64) {
+        self.context.SetMiterLimit(limit)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-setlinedash
+    fn SetLineDash(&self, segments: Vec<f64>) {
+        self.context.SetLineDash(segments)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-getlinedash
+    fn GetLineDash(&self) -> Vec<f64> {
+        self.context.GetLineDash()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-linedashoffset
+    fn LineDashOffset(&self) -> f64 {
+        self.context.LineDashOffset()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-linedashoffset
+    fn SetLineDashOffset(&self, offset: f64) {
+        self.context.SetLineDashOffset(offset)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-shadowoffsetx
+    fn ShadowOffsetX(&self) -> f64 {
+        self.context.ShadowOffsetX()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-shadowoffsetx
+    fn SetShadowOffsetX(&self, value: f64) {
+        self.context.SetShadowOffsetX(value)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-shadowoffsety
+    fn ShadowOffsetY(&self) -> f64 {
+        self.context.ShadowOffsetY()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-shadowoffsety
+    fn SetShadowOffsetY(&self, value: f64) {
+        self.context.SetShadowOffsetY(value)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-shadowblur
+    fn ShadowBlur(&self) -> f64 {
+        self.context.ShadowBlur()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-shadowblur
+    fn SetShadowBlur(&self, value: f64) {
+        self.context.SetShadowBlur(value)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-shadowcolor
+    fn ShadowColor(&self) -> DOMString {
+        self.context.ShadowColor()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-shadowcolor
+    fn SetShadowColor(&self, value: DOMString) {
+        self.context.SetShadowColor(value)
+    }
+}
@@ -6,24 +6,27 @@ use canvas_traits::canvas::CanvasImageData;
 use canvas_traits::canvas::CanvasMsg;
 use canvas_traits::canvas::FromLayoutMsg;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasFillRule;
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasImageSource;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasLineCap;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasLineJoin;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasRenderingContext2DMethods;
 use dom::bindings::codegen::Bindings::PaintRenderingContext2DBinding;
 use dom::bindings::codegen::Bindings::PaintRenderingContext2DBinding::PaintRenderingContext2DMethods;
-use dom::bindings::codegen::UnionTypes::HTMLImageElementOrHTMLCanvasElementOrCanvasRenderingContext2DOrCSSStyleValue;
 use dom::bindings::codegen::UnionTypes::StringOrCanvasGradientOrCanvasPattern;
 use dom::bindings::error::ErrorResult;
 use dom::bindings::error::Fallible;
 use dom::bindings::inheritance::Castable;
 use dom::bindings::num::Finite;
-use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
 use dom::bindings::root::DomRoot;
 use dom::bindings::str::DOMString;
 use dom::canvasgradient::CanvasGradient;
 use dom::canvaspattern::CanvasPattern;
 use dom::canvasrenderingcontext2d::CanvasRenderingContext2D;
+use dom::dommatrix::DOMMatrix;
+use dom::dommatrixreadonly::DOMMatrixReadOnly;
 use dom::paintworkletglobalscope::PaintWorkletGlobalScope;
+use dom::path2d::Path2D;
 use dom::workletglobalscope::WorkletGlobalScope;
 use dom_struct::dom_struct;
 use euclid::Size2D;
@@ -47,7 +50,7 @@ impl PaintRenderingContext2D {
         let image_cache = global.image_cache();
         let base_url = global.upcast::<WorkletGlobalScope>().base_url();
         PaintRenderingContext2D {
-            context: CanvasRenderingContext2D::new_inherited(global.upcast(), None, image_cache, base_url, size),
+            context: CanvasRenderingContext2D::new_inherited(global.upcast(), None, Some(image_cache), base_url, size),
             device_pixel_ratio: Cell::new(TypedScale::new(1.0)),
         }
     }
@@ -116,6 +119,16 @@ impl PaintRenderingContext2DMethods for PaintRenderingContext2D {
         self.context.Transform(a, b, c, d, e, f)
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-gettransform
+    fn GetTransform(&self) -> DomRoot<DOMMatrix> {
+        // The scale by the device pixel ratio isn't visible to the worklet.
+        let device_pixel_ratio = self.device_pixel_ratio.get().get() as f64;
+        let transform = self.context.GetTransform();
+        let matrix = transform.upcast::<DOMMatrixReadOnly>().matrix()
+            .pre_scale(1.0 / device_pixel_ratio, 1.0 / device_pixel_ratio, 1.0);
+        DOMMatrix::new(&self.global(), true, matrix)
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-settransform
     fn SetTransform(&self, a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) {
         self.context.SetTransform(a, b, c, d, e, f);
@@ -178,24 +191,44 @@ impl PaintRenderingContext2DMethods for PaintRenderingContext2D {
         self.context.Fill(fill_rule)
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-fill
+    fn Fill_(&self, path: &Path2D, fill_rule: CanvasFillRule) {
+        self.context.Fill_(path, fill_rule)
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroke
     fn Stroke(&self) {
         self.context.Stroke()
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroke
+    fn Stroke_(&self, path: &Path2D) {
+        self.context.Stroke_(path)
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-clip
     fn Clip(&self, fill_rule: CanvasFillRule) {
         self.context.Clip(fill_rule)
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-clip
+    fn Clip_(&self, path: &Path2D, fill_rule: CanvasFillRule) {
+        self.context.Clip_(path, fill_rule)
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinpath
     fn IsPointInPath(&self, x: f64, y: f64, fill_rule: CanvasFillRule) -> bool {
         self.context.IsPointInPath(x, y, fill_rule)
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinpath
+    fn IsPointInPath_(&self, path: &Path2D, x: f64, y: f64, fill_rule: CanvasFillRule) -> bool {
+        self.context.IsPointInPath_(path, x, y, fill_rule)
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-drawimage
     fn DrawImage(&self,
-                 image: HTMLImageElementOrHTMLCanvasElementOrCanvasRenderingContext2DOrCSSStyleValue,
+                 image: CanvasImageSource,
                  dx: f64,
                  dy: f64)
                  -> ErrorResult {
@@ -204,7 +237,7 @@ impl PaintRenderingContext2DMethods for PaintRenderingContext2D {
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-drawimage
     fn DrawImage_(&self,
-                  image: HTMLImageElementOrHTMLCanvasElementOrCanvasRenderingContext2DOrCSSStyleValue,
+                  image: CanvasImageSource,
                   dx: f64,
                   dy: f64,
                   dw: f64,
@@ -215,7 +248,7 @@ impl PaintRenderingContext2DMethods for PaintRenderingContext2D {
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-drawimage
     fn DrawImage__(&self,
-                   image: HTMLImageElementOrHTMLCanvasElementOrCanvasRenderingContext2DOrCSSStyleValue,
+                   image: CanvasImageSource,
                    sx: f64,
                    sy: f64,
                    sw: f64,
@@ -322,7 +355,7 @@ impl PaintRenderingContext2DMethods for PaintRenderingContext2D {
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-createpattern
     fn CreatePattern(&self,
-                     image: HTMLImageElementOrHTMLCanvasElementOrCanvasRenderingContext2DOrCSSStyleValue,
+                     image: CanvasImageSource,
                      repetition: DOMString)
                      -> Fallible<DomRoot<CanvasPattern>> {
         self.context.CreatePattern(image, repetition)
@@ -368,6 +401,26 @@ impl PaintRenderingContext2DMethods for PaintRenderingContext2D {
         self.context.SetMiterLimit(limit)
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-setlinedash
+    fn SetLineDash(&self, segments: Vec<f64>) {
+        self.context.SetLineDash(segments)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-getlinedash
+    fn GetLineDash(&self) -> Vec<f64> {
+        self.context.GetLineDash()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-linedashoffset
+    fn LineDashOffset(&self) -> f64 {
+        self.context.LineDashOffset()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-linedashoffset
+    fn SetLineDashOffset(&self, offset: f64) {
+        self.context.SetLineDashOffset(offset)
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-shadowoffsetx
     fn ShadowOffsetX(&self) -> f64 {
         self.context.ShadowOffsetX()
@@ -0,0 +1,343 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::audionode::AudioNode;
+use dom::audioparam::AudioParam;
+use dom::baseaudiocontext::BaseAudioContext;
+use dom::bindings::codegen::Bindings::AudioNodeBinding::{ChannelCountMode, ChannelInterpretation};
+use dom::bindings::codegen::Bindings::AudioParamBinding::{AudioParamMethods, AutomationRate};
+use dom::bindings::codegen::Bindings::PannerNodeBinding::{self, PannerNodeMethods, PannerOptions};
+use dom::bindings::codegen::Bindings::PannerNodeBinding::{DistanceModelType, PanningModelType};
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::num::Finite;
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::{Dom, DomRoot};
+use dom::window::Window;
+use dom_struct::dom_struct;
+use servo_media::audio::node::{AudioNodeInit, AudioNodeMessage};
+use servo_media::audio::panner_node::{DistanceModel, PannerNodeMessage, PannerNodeOptions, PanningModel};
+use servo_media::audio::param::{ParamDir, ParamType};
+use std::cell::Cell;
+use std::f32;
+
+#[dom_struct]
+pub struct PannerNode {
+    node: AudioNode,
+    position_x: Dom<AudioParam>,
+    position_y: Dom<AudioParam>,
+    position_z: Dom<AudioParam>,
+    orientation_x: Dom<AudioParam>,
+    orientation_y: Dom<AudioParam>,
+    orientation_z: Dom<AudioParam>,
+    panning_model: Cell<PanningModelType>,
+    distance_model: Cell<DistanceModelType>,
+    ref_distance: Cell<f64>,
+    max_distance: Cell<f64>,
+    rolloff_factor: Cell<f64>,
+    cone_inner_angle: Cell<f64>,
+    cone_outer_angle: Cell<f64>,
+    cone_outer_gain: Cell<f64>,
+}
+
+impl PannerNode {
+    #[allow(unrooted_must_root)]
+    pub fn new_inherited(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &PannerOptions,
+    ) -> Fallible<PannerNode> {
+        let node_options = options.parent.unwrap_or(
+            2,
+            ChannelCountMode::Clamped_max,
+            ChannelInterpretation::Speakers,
+        );
+        if node_options.channelCountMode == Some(ChannelCountMode::Max) {
+            return Err(Error::NotSupported);
+        }
+        if node_options.channelCount.unwrap() > 2 {
+            return Err(Error::NotSupported);
+        }
+        validate_ref_distance(*options.refDistance)?;
+        validate_max_distance(*options.maxDistance)?;
+        validate_rolloff_factor(*options.rolloffFactor)?;
+        validate_cone_outer_gain(*options.coneOuterGain)?;
+
+        let node = AudioNode::new_inherited(
+            AudioNodeInit::PannerNode(options.into()),
+            None,
+            context,
+            &node_options,
+            1, // inputs
+            1, // outputs
+        );
+        let node_id = node.node_id();
+        let param = |param_type, default_value| {
+            AudioParam::new(
+                window,
+                context,
+                node_id,
+                param_type,
+                AutomationRate::A_rate,
+                default_value,
+                f32::MIN,
+                f32::MAX,
+            )
+        };
+        let position_x = param(ParamType::Position(ParamDir::X), *options.positionX);
+        let position_y = param(ParamType::Position(ParamDir::Y), *options.positionY);
+        let position_z = param(ParamType::Position(ParamDir::Z), *options.positionZ);
+        let orientation_x = param(ParamType::Orientation(ParamDir::X), *options.orientationX);
+        let orientation_y = param(ParamType::Orientation(ParamDir::Y), *options.orientationY);
+        let orientation_z = param(ParamType::Orientation(ParamDir::Z), *options.orientationZ);
+        Ok(PannerNode {
+            node,
+            position_x: Dom::from_ref(&position_x),
+            position_y: Dom::from_ref(&position_y),
+            position_z: Dom::from_ref(&position_z),
+            orientation_x: Dom::from_ref(&orientation_x),
+            orientation_y: Dom::from_ref(&orientation_y),
+            orientation_z: Dom::from_ref(&orientation_z),
+            panning_model: Cell::new(options.panningModel),
+            distance_model: Cell::new(options.distanceModel),
+            ref_distance: Cell::new(*options.refDistance),
+            max_distance: Cell::new(*options.maxDistance),
+            rolloff_factor: Cell::new(*options.rolloffFactor),
+            cone_inner_angle: Cell::new(*options.coneInnerAngle),
+            cone_outer_angle: Cell::new(*options.coneOuterAngle),
+            cone_outer_gain: Cell::new(*options.coneOuterGain),
+        })
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &PannerOptions,
+    ) -> Fallible<DomRoot<PannerNode>> {
+        let node = PannerNode::new_inherited(window, context, options)?;
+        Ok(reflect_dom_object(Box::new(node), window, PannerNodeBinding::Wrap))
+    }
+
+    pub fn Constructor(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &PannerOptions,
+    ) -> Fallible<DomRoot<PannerNode>> {
+        PannerNode::new(window, context, options)
+    }
+
+    fn message(&self, message: PannerNodeMessage) {
+        self.node.message(AudioNodeMessage::PannerNode(message));
+    }
+}
+
+fn validate_ref_distance(value: f64) -> ErrorResult {
+    if value < 0. {
+        return Err(Error::Range("refDistance should be positive".into()));
+    }
+    Ok(())
+}
+
+fn validate_max_distance(value: f64) -> ErrorResult {
+    if value <= 0. {
+        return Err(Error::Range("maxDistance should be strictly positive".into()));
+    }
+    Ok(())
+}
+
+fn validate_rolloff_factor(value: f64) -> ErrorResult {
+    if value < 0. {
+        return Err(Error::Range("rolloffFactor should be positive".into()));
+    }
+    Ok(())
+}
+
+fn validate_cone_outer_gain(value: f64) -> ErrorResult {
+    if value < 0. || value > 1. {
+        return Err(Error::InvalidState);
+    }
+    Ok(())
+}
+
+impl PannerNodeMethods for PannerNode {
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-positionx
+    fn PositionX(&self) -> DomRoot<AudioParam> {
+        DomRoot::from_ref(&self.position_x)
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-positiony
+    fn PositionY(&self) -> DomRoot<AudioParam> {
+        DomRoot::from_ref(&self.position_y)
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-positionz
+    fn PositionZ(&self) -> DomRoot<AudioParam> {
+        DomRoot::from_ref(&self.position_z)
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-orientationx
+    fn OrientationX(&self) -> DomRoot<AudioParam> {
+        DomRoot::from_ref(&self.orientation_x)
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-orientationy
+    fn OrientationY(&self) -> DomRoot<AudioParam> {
+        DomRoot::from_ref(&self.orientation_y)
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-orientationz
+    fn OrientationZ(&self) -> DomRoot<AudioParam> {
+        DomRoot::from_ref(&self.orientation_z)
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-panningmodel
+    fn PanningModel(&self) -> PanningModelType {
+        self.panning_model.get()
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-panningmodel
+    fn SetPanningModel(&self, model: PanningModelType) {
+        self.panning_model.set(model);
+        self.message(PannerNodeMessage::SetPanningModel(model.into()));
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-distancemodel
+    fn DistanceModel(&self) -> DistanceModelType {
+        self.distance_model.get()
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-distancemodel
+    fn SetDistanceModel(&self, model: DistanceModelType) {
+        self.distance_model.set(model);
+        self.message(PannerNodeMessage::SetDistanceModel(model.into()));
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-refdistance
+    fn RefDistance(&self) -> Finite<f64> {
+        Finite::wrap(self.ref_distance.get())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-refdistance
+    fn SetRefDistance(&self, value: Finite<f64>) -> ErrorResult {
+        validate_ref_distance(*value)?;
+        self.ref_distance.set(*value);
+        self.message(PannerNodeMessage::SetRefDistance(*value));
+        Ok(())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-maxdistance
+    fn MaxDistance(&self) -> Finite<f64> {
+        Finite::wrap(self.max_distance.get())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-maxdistance
+    fn SetMaxDistance(&self, value: Finite<f64>) -> ErrorResult {
+        validate_max_distance(*value)?;
+        self.max_distance.set(*value);
+        self.message(PannerNodeMessage::SetMaxDistance(*value));
+        Ok(())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-rollofffactor
+    fn RolloffFactor(&self) -> Finite<f64> {
+        Finite::wrap(self.rolloff_factor.get())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-rollofffactor
+    fn SetRolloffFactor(&self, value: Finite<f64>) -> ErrorResult {
+        validate_rolloff_factor(*value)?;
+        self.rolloff_factor.set(*value);
+        self.message(PannerNodeMessage::SetRolloff(*value));
+        Ok(())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-coneinnerangle
+    fn ConeInnerAngle(&self) -> Finite<f64> {
+        Finite::wrap(self.cone_inner_angle.get())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-coneinnerangle
+    fn SetConeInnerAngle(&self, value: Finite<f64>) {
+        self.cone_inner_angle.set(*value);
+        self.message(PannerNodeMessage::SetConeInner(*value));
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-coneouterangle
+    fn ConeOuterAngle(&self) -> Finite<f64> {
+        Finite::wrap(self.cone_outer_angle.get())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-coneouterangle
+    fn SetConeOuterAngle(&self, value: Finite<f64>) {
+        self.cone_outer_angle.set(*value);
+        self.message(PannerNodeMessage::SetConeOuter(*value));
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-coneoutergain
+    fn ConeOuterGain(&self) -> Finite<f64> {
+        Finite::wrap(self.cone_outer_gain.get())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-coneoutergain
+    fn SetConeOuterGain(&self, value: Finite<f64>) -> ErrorResult {
+        validate_cone_outer_gain(*value)?;
+        self.cone_outer_gain.set(*value);
+        self.message(PannerNodeMessage::SetConeGain(*value));
+        Ok(())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-setposition
+    fn SetPosition(&self, x: Finite<f32>, y: Finite<f32>, z: Finite<f32>) {
+        self.position_x.SetValue(x);
+        self.position_y.SetValue(y);
+        self.position_z.SetValue(z);
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-pannernode-setorientation
+    fn SetOrientation(&self, x: Finite<f32>, y: Finite<f32>, z: Finite<f32>) {
+        self.orientation_x.SetValue(x);
+        self.orientation_y.SetValue(y);
+        self.orientation_z.SetValue(z);
+    }
+}
+
+impl<'a> From<&'a PannerOptions> for PannerNodeOptions {
+    fn from(options: &'a PannerOptions) -> Self {
+        Self {
+            panning_model: options.panningModel.into(),
+            distance_model: options.distanceModel.into(),
+            position_x: *options.positionX,
+            position_y: *options.positionY,
+            position_z: *options.positionZ,
+            orientation_x: *options.orientationX,
+            orientation_y: *options.orientationY,
+            orientation_z: *options.orientationZ,
+            ref_distance: *options.refDistance,
+            max_distance: *options.maxDistance,
+            rolloff_factor: *options.rolloffFactor,
+            cone_inner_angle: *options.coneInnerAngle,
+            cone_outer_angle: *options.coneOuterAngle,
+            cone_outer_gain: *options.coneOuterGain,
+        }
+    }
+}
+
+impl From<DistanceModelType> for DistanceModel {
+    fn from(model: DistanceModelType) -> Self {
+        match model {
+            DistanceModelType::Linear => DistanceModel::Linear,
+            DistanceModelType::Inverse => DistanceModel::Inverse,
+            DistanceModelType::Exponential => DistanceModel::Exponential,
+        }
+    }
+}
+
+impl From<PanningModelType> for PanningModel {
+    fn from(model: PanningModelType) -> Self {
+        match model {
+            PanningModelType::Equalpower => PanningModel::EqualPower,
+            PanningModelType::HRTF => PanningModel::HRTF,
+        }
+    }
+}
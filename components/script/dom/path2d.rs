@@ -0,0 +1,459 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_traits::canvas::PathSegment;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::Path2DBinding;
+use dom::bindings::codegen::Bindings::Path2DBinding::Path2DMethods;
+use dom::bindings::codegen::UnionTypes::Path2DOrString;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::globalscope::GlobalScope;
+use dom_struct::dom_struct;
+use euclid::{Point2D, Vector2D};
+use std::f32::consts::PI;
+use std::str;
+
+// https://html.spec.whatwg.org/multipage/#path2d
+#[dom_struct]
+pub struct Path2D {
+    reflector_: Reflector,
+    segments: DomRefCell<Vec<PathSegment>>,
+}
+
+impl Path2D {
+    fn new_inherited(segments: Vec<PathSegment>) -> Path2D {
+        Path2D {
+            reflector_: Reflector::new(),
+            segments: DomRefCell::new(segments),
+        }
+    }
+
+    pub fn new(global: &GlobalScope, segments: Vec<PathSegment>) -> DomRoot<Path2D> {
+        reflect_dom_object(Box::new(Path2D::new_inherited(segments)),
+                           global,
+                           Path2DBinding::Wrap)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-path2d
+    pub fn Constructor(global: &GlobalScope, path: Option<Path2DOrString>) -> Fallible<DomRoot<Path2D>> {
+        let segments = match path {
+            None => Vec::new(),
+            Some(Path2DOrString::Path2D(path)) => path.segments(),
+            Some(Path2DOrString::String(data)) => parse_path_data(&data),
+        };
+        Ok(Path2D::new(global, segments))
+    }
+
+    /// The segments making up this path, to be replayed by the canvas paint thread.
+    pub fn segments(&self) -> Vec<PathSegment> {
+        self.segments.borrow().clone()
+    }
+
+    fn push(&self, segment: PathSegment) {
+        self.segments.borrow_mut().push(segment);
+    }
+}
+
+impl Path2DMethods for Path2D {
+    // https://html.spec.whatwg.org/multipage/#dom-path2d-addpath
+    fn AddPath(&self, path: &Path2D) {
+        // Adding a path to itself must not loop, so copy the segments out first.
+        let segments = path.segments();
+        self.segments.borrow_mut().extend(segments);
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-closepath
+    fn ClosePath(&self) {
+        self.push(PathSegment::ClosePath);
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-moveto
+    fn MoveTo(&self, x: f64, y: f64) {
+        if !(x.is_finite() && y.is_finite()) {
+            return;
+        }
+        self.push(PathSegment::MoveTo(Point2D::new(x as f32, y as f32)));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-lineto
+    fn LineTo(&self, x: f64, y: f64) {
+        if !(x.is_finite() && y.is_finite()) {
+            return;
+        }
+        self.push(PathSegment::LineTo(Point2D::new(x as f32, y as f32)));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-quadraticcurveto
+    fn QuadraticCurveTo(&self, cpx: f64, cpy: f64, x: f64, y: f64) {
+        if !(cpx.is_finite() && cpy.is_finite() && x.is_finite() && y.is_finite()) {
+            return;
+        }
+        self.push(PathSegment::QuadraticCurveTo(Point2D::new(cpx as f32, cpy as f32),
+                                                Point2D::new(x as f32, y as f32)));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-beziercurveto
+    fn BezierCurveTo(&self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, x: f64, y: f64) {
+        if !(cp1x.is_finite() && cp1y.is_finite() && cp2x.is_finite() && cp2y.is_finite() &&
+             x.is_finite() && y.is_finite()) {
+            return;
+        }
+        self.push(PathSegment::BezierCurveTo(Point2D::new(cp1x as f32, cp1y as f32),
+                                             Point2D::new(cp2x as f32, cp2y as f32),
+                                             Point2D::new(x as f32, y as f32)));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-arcto
+    fn ArcTo(&self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, r: f64) -> ErrorResult {
+        if !([cp1x, cp1y, cp2x, cp2y, r].iter().all(|x| x.is_finite())) {
+            return Ok(());
+        }
+        if r < 0.0 {
+            return Err(Error::IndexSize);
+        }
+
+        self.push(PathSegment::ArcTo(Point2D::new(cp1x as f32, cp1y as f32),
+                                     Point2D::new(cp2x as f32, cp2y as f32),
+                                     r as f32));
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-rect
+    fn Rect(&self, x: f64, y: f64, width: f64, height: f64) {
+        if !([x, y, width, height].iter().all(|val| val.is_finite())) {
+            return;
+        }
+
+        let (x, y, width, height) = (x as f32, y as f32, width as f32, height as f32);
+        let mut segments = self.segments.borrow_mut();
+        segments.push(PathSegment::MoveTo(Point2D::new(x, y)));
+        segments.push(PathSegment::LineTo(Point2D::new(x + width, y)));
+        segments.push(PathSegment::LineTo(Point2D::new(x + width, y + height)));
+        segments.push(PathSegment::LineTo(Point2D::new(x, y + height)));
+        segments.push(PathSegment::ClosePath);
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-arc
+    fn Arc(&self, x: f64, y: f64, r: f64, start: f64, end: f64, ccw: bool) -> ErrorResult {
+        self.Ellipse(x, y, r, r, 0.0, start, end, ccw)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ellipse
+    fn Ellipse(&self, x: f64, y: f64, rx: f64, ry: f64, rotation: f64, start: f64, end: f64, ccw: bool) -> ErrorResult {
+        if !([x, y, rx, ry, rotation, start, end].iter().all(|x| x.is_finite())) {
+            return Ok(());
+        }
+        if rx < 0.0 || ry < 0.0 {
+            return Err(Error::IndexSize);
+        }
+
+        self.push(PathSegment::Ellipse(Point2D::new(x as f32, y as f32),
+                                       rx as f32,
+                                       ry as f32,
+                                       rotation as f32,
+                                       start as f32,
+                                       end as f32,
+                                       ccw));
+        Ok(())
+    }
+}
+
+/// Parses SVG path data, as used by the `Path2D(DOMString)` constructor. On a syntax error,
+/// the segments parsed up to that point are kept, as SVG requires.
+///
+/// https://www.w3.org/TR/SVG11/paths.html#PathDataBNF
+fn parse_path_data(data: &str) -> Vec<PathSegment> {
+    let mut parser = PathDataParser {
+        data: data.as_bytes(),
+        position: 0,
+        segments: Vec::new(),
+        current: Point2D::zero(),
+        subpath_start: Point2D::zero(),
+        last_cubic_control: None,
+        last_quadratic_control: None,
+    };
+    parser.parse();
+    parser.segments
+}
+
+struct PathDataParser<'a> {
+    data: &'a [u8],
+    position: usize,
+    segments: Vec<PathSegment>,
+    current: Point2D<f32>,
+    subpath_start: Point2D<f32>,
+    /// The second control point of the previous segment, if it was a cubic Bézier curve.
+    last_cubic_control: Option<Point2D<f32>>,
+    /// The control point of the previous segment, if it was a quadratic Bézier curve.
+    last_quadratic_control: Option<Point2D<f32>>,
+}
+
+impl<'a> PathDataParser<'a> {
+    fn parse(&mut self) {
+        // Path data must start with a moveto.
+        let mut command = match self.command() {
+            Some(command) if command == b'M' || command == b'm' => command,
+            _ => return,
+        };
+
+        loop {
+            if self.segment(command).is_none() {
+                return;
+            }
+
+            if self.at_number() {
+                // Further coordinates repeat the command, except that a moveto is followed
+                // by implicit linetos.
+                command = match command {
+                    b'M' => b'L',
+                    b'm' => b'l',
+                    b'Z' | b'z' => return,
+                    command => command,
+                };
+                continue;
+            }
+
+            command = match self.command() {
+                Some(command) => command,
+                None => return,
+            };
+        }
+    }
+
+    fn segment(&mut self, command: u8) -> Option<()> {
+        let origin = if command.is_ascii_lowercase() {
+            self.current.to_vector()
+        } else {
+            Vector2D::zero()
+        };
+
+        let mut cubic_control = None;
+        let mut quadratic_control = None;
+        match command.to_ascii_uppercase() {
+            b'M' => {
+                let point = self.point(origin)?;
+                self.segments.push(PathSegment::MoveTo(point));
+                self.subpath_start = point;
+                self.current = point;
+            },
+            b'L' => {
+                let point = self.point(origin)?;
+                self.line_to(point);
+            },
+            b'H' => {
+                let x = self.number()? + origin.x;
+                let point = Point2D::new(x, self.current.y);
+                self.line_to(point);
+            },
+            b'V' => {
+                let y = self.number()? + origin.y;
+                let point = Point2D::new(self.current.x, y);
+                self.line_to(point);
+            },
+            b'C' => {
+                let cp1 = self.point(origin)?;
+                let cp2 = self.point(origin)?;
+                let point = self.point(origin)?;
+                self.curve_to(cp1, cp2, point);
+                cubic_control = Some(cp2);
+            },
+            b'S' => {
+                let cp1 = self.reflect(self.last_cubic_control);
+                let cp2 = self.point(origin)?;
+                let point = self.point(origin)?;
+                self.curve_to(cp1, cp2, point);
+                cubic_control = Some(cp2);
+            },
+            b'Q' => {
+                let cp = self.point(origin)?;
+                let point = self.point(origin)?;
+                self.quadratic_curve_to(cp, point);
+                quadratic_control = Some(cp);
+            },
+            b'T' => {
+                let cp = self.reflect(self.last_quadratic_control);
+                let point = self.point(origin)?;
+                self.quadratic_curve_to(cp, point);
+                quadratic_control = Some(cp);
+            },
+            b'A' => {
+                let rx = self.number()?;
+                let ry = self.number()?;
+                let rotation = self.number()?;
+                let large_arc = self.flag()?;
+                let sweep = self.flag()?;
+                let point = self.point(origin)?;
+                self.arc_to(rx, ry, rotation, large_arc, sweep, point);
+            },
+            b'Z' => {
+                self.segments.push(PathSegment::ClosePath);
+                self.current = self.subpath_start;
+            },
+            _ => return None,
+        }
+
+        self.last_cubic_control = cubic_control;
+        self.last_quadratic_control = quadratic_control;
+        Some(())
+    }
+
+    fn line_to(&mut self, point: Point2D<f32>) {
+        self.segments.push(PathSegment::LineTo(point));
+        self.current = point;
+    }
+
+    fn curve_to(&mut self, cp1: Point2D<f32>, cp2: Point2D<f32>, point: Point2D<f32>) {
+        self.segments.push(PathSegment::BezierCurveTo(cp1, cp2, point));
+        self.current = point;
+    }
+
+    fn quadratic_curve_to(&mut self, cp: Point2D<f32>, point: Point2D<f32>) {
+        self.segments.push(PathSegment::QuadraticCurveTo(cp, point));
+        self.current = point;
+    }
+
+    /// The first control point of a smooth curve: the reflection of the previous curve's
+    /// control point about the current point, or the current point itself.
+    fn reflect(&self, control: Option<Point2D<f32>>) -> Point2D<f32> {
+        match control {
+            Some(control) => self.current + (self.current - control),
+            None => self.current,
+        }
+    }
+
+    /// Converts an endpoint-parameterized elliptical arc to a center-parameterized one.
+    ///
+    /// https://www.w3.org/TR/SVG11/implnote.html#ArcConversionEndpointToCenter
+    fn arc_to(&mut self, rx: f32, ry: f32, rotation: f32, large_arc: bool, sweep: bool, point: Point2D<f32>) {
+        if point == self.current {
+            return;
+        }
+
+        let (mut rx, mut ry) = (rx.abs(), ry.abs());
+        if rx == 0.0 || ry == 0.0 {
+            return self.line_to(point);
+        }
+
+        let phi = (rotation % 360.0) * PI / 180.0;
+        let (sin, cos) = phi.sin_cos();
+        let half = (self.current - point) / 2.0;
+        let x1 = cos * half.x + sin * half.y;
+        let y1 = -sin * half.x + cos * half.y;
+
+        let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+        if lambda > 1.0 {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+
+        let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+        let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+        let mut coefficient = (numerator / denominator).max(0.0).sqrt();
+        if large_arc == sweep {
+            coefficient = -coefficient;
+        }
+        let cx1 = coefficient * rx * y1 / ry;
+        let cy1 = -coefficient * ry * x1 / rx;
+
+        let mid = self.current.lerp(point, 0.5);
+        let center = Point2D::new(cos * cx1 - sin * cy1 + mid.x, sin * cx1 + cos * cy1 + mid.y);
+        let start = ((y1 - cy1) / ry).atan2((x1 - cx1) / rx);
+        let end = ((-y1 - cy1) / ry).atan2((-x1 - cx1) / rx);
+
+        // A positive sweep goes in the direction of increasing angles, which is clockwise on
+        // a canvas.
+        self.segments.push(PathSegment::Ellipse(center, rx, ry, phi, start, end, !sweep));
+        self.current = point;
+    }
+
+    fn skip_separators(&mut self) {
+        while let Some(&byte) = self.data.get(self.position) {
+            match byte {
+                b' ' | b'\t' | b'\n' | b'\r' | b'\x0C' | b',' => self.position += 1,
+                _ => return,
+            }
+        }
+    }
+
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        let byte = *self.data.get(self.position)?;
+        if !byte.is_ascii_alphabetic() {
+            return None;
+        }
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn at_number(&mut self) -> bool {
+        self.skip_separators();
+        match self.data.get(self.position) {
+            Some(&byte) => byte.is_ascii_digit() || byte == b'.' || byte == b'-' || byte == b'+',
+            None => false,
+        }
+    }
+
+    fn point(&mut self, origin: Vector2D<f32>) -> Option<Point2D<f32>> {
+        let x = self.number()?;
+        let y = self.number()?;
+        Some(Point2D::new(x, y) + origin)
+    }
+
+    fn flag(&mut self) -> Option<bool> {
+        self.skip_separators();
+        let flag = match *self.data.get(self.position)? {
+            b'0' => false,
+            b'1' => true,
+            _ => return None,
+        };
+        self.position += 1;
+        Some(flag)
+    }
+
+    fn number(&mut self) -> Option<f32> {
+        self.skip_separators();
+        let start = self.position;
+        if let Some(&b'-') = self.data.get(self.position) {
+            self.position += 1;
+        } else if let Some(&b'+') = self.data.get(self.position) {
+            self.position += 1;
+        }
+        self.skip_digits();
+        if let Some(&b'.') = self.data.get(self.position) {
+            self.position += 1;
+            self.skip_digits();
+        }
+        if let Some(&byte) = self.data.get(self.position) {
+            if byte == b'e' || byte == b'E' {
+                // Only consume the exponent if it has digits, so that it isn't confused with
+                // a following command.
+                let mantissa_end = self.position;
+                self.position += 1;
+                if let Some(&sign) = self.data.get(self.position) {
+                    if sign == b'-' || sign == b'+' {
+                        self.position += 1;
+                    }
+                }
+                let exponent_start = self.position;
+                self.skip_digits();
+                if self.position == exponent_start {
+                    self.position = mantissa_end;
+                }
+            }
+        }
+
+        str::from_utf8(&self.data[start..self.position]).ok()?.parse().ok()
+    }
+
+    fn skip_digits(&mut self) {
+        while let Some(&byte) = self.data.get(self.position) {
+            if !byte.is_ascii_digit() {
+                return;
+            }
+            self.position += 1;
+        }
+    }
+}
@@ -14,7 +14,7 @@ use dom::bindings::error::{Error, ErrorResult, Fallible};
 use dom::bindings::inheritance::{CharacterDataTypeId, NodeTypeId};
 use dom::bindings::inheritance::Castable;
 use dom::bindings::reflector::{Reflector, reflect_dom_object};
-use dom::bindings::root::{Dom, DomRoot, MutDom, RootedReference};
+use dom::bindings::root::{Dom, DomRoot, MutDom, MutNullableDom, RootedReference};
 use dom::bindings::str::DOMString;
 use dom::bindings::trace::JSTraceable;
 use dom::bindings::weakref::{WeakRef, WeakRefVec};
@@ -24,6 +24,7 @@ use dom::documentfragment::DocumentFragment;
 use dom::element::Element;
 use dom::htmlscriptelement::HTMLScriptElement;
 use dom::node::{Node, UnbindContext};
+use dom::selection::Selection;
 use dom::text::Text;
 use dom::window::Window;
 use dom_struct::dom_struct;
@@ -37,6 +38,8 @@ pub struct Range {
     reflector_: Reflector,
     start: BoundaryPoint,
     end: BoundaryPoint,
+    /// <https://w3c.github.io/selection-api/#dfn-associated>
+    associated_selection: MutNullableDom<Selection>,
 }
 
 impl Range {
@@ -46,6 +49,7 @@ impl Range {
             reflector_: Reflector::new(),
             start: BoundaryPoint::new(start_container, start_offset),
             end: BoundaryPoint::new(end_container, end_offset),
+            associated_selection: Default::default(),
         }
     }
 
@@ -146,6 +150,7 @@ impl Range {
             }
         }
         self.start.set(node, offset);
+        self.boundary_point_changed();
     }
 
     // https://dom.spec.whatwg.org/#concept-range-bp-set
@@ -160,6 +165,21 @@ impl Range {
             }
         }
         self.end.set(node, offset);
+        self.boundary_point_changed();
+    }
+
+    fn boundary_point_changed(&self) {
+        if let Some(selection) = self.associated_selection.get() {
+            selection.queue_selectionchange();
+        }
+    }
+
+    pub fn associated_selection(&self) -> Option<DomRoot<Selection>> {
+        self.associated_selection.get()
+    }
+
+    pub fn set_associated_selection(&self, selection: Option<&Selection>) {
+        self.associated_selection.set(selection);
     }
 
     // https://dom.spec.whatwg.org/#dom-range-comparepointnode-offset
@@ -470,7 +490,7 @@ impl RangeMethods for Range {
                 fragment.upcast::<Node>().AppendChild(&clone)?;
             } else {
                 // Step 14.1.
-                let clone = child.CloneNode(false);
+                let clone = child.CloneNode(false)?;
                 // Step 14.2.
                 fragment.upcast::<Node>().AppendChild(&clone)?;
                 // Step 14.3.
@@ -489,7 +509,7 @@ impl RangeMethods for Range {
         // Step 15.
         for child in contained_children {
             // Step 15.1.
-            let clone = child.CloneNode(true);
+            let clone = child.CloneNode(true)?;
             // Step 15.2.
             fragment.upcast::<Node>().AppendChild(&clone)?;
         }
@@ -505,7 +525,7 @@ impl RangeMethods for Range {
                 fragment.upcast::<Node>().AppendChild(&clone)?;
             } else {
                 // Step 17.1.
-                let clone = child.CloneNode(false);
+                let clone = child.CloneNode(false)?;
                 // Step 17.2.
                 fragment.upcast::<Node>().AppendChild(&clone)?;
                 // Step 17.3.
@@ -545,7 +565,7 @@ impl RangeMethods for Range {
         if end_node == start_node {
             if let Some(end_data) = end_node.downcast::<CharacterData>() {
                 // Step 4.1.
-                let clone = end_node.CloneNode(true);
+                let clone = end_node.CloneNode(true)?;
                 // Step 4.2.
                 let text = end_data.SubstringData(start_offset, end_offset - start_offset);
                 clone.downcast::<CharacterData>().unwrap().SetData(text.unwrap());
@@ -581,7 +601,7 @@ impl RangeMethods for Range {
             if let Some(start_data) = child.downcast::<CharacterData>() {
                 assert!(child == start_node);
                 // Step 15.1.
-                let clone = start_node.CloneNode(true);
+                let clone = start_node.CloneNode(true)?;
                 // Step 15.2.
                 let text = start_data.SubstringData(start_offset,
                                                     start_node.len() - start_offset);
@@ -594,7 +614,7 @@ impl RangeMethods for Range {
                                             DOMString::new())?;
             } else {
                 // Step 16.1.
-                let clone = child.CloneNode(false);
+                let clone = child.CloneNode(false)?;
                 // Step 16.2.
                 fragment.upcast::<Node>().AppendChild(&clone)?;
                 // Step 16.3.
@@ -619,7 +639,7 @@ impl RangeMethods for Range {
             if let Some(end_data) = child.downcast::<CharacterData>() {
                 assert!(child == end_node);
                 // Step 18.1.
-                let clone = end_node.CloneNode(true);
+                let clone = end_node.CloneNode(true)?;
                 // Step 18.2.
                 let text = end_data.SubstringData(0, end_offset);
                 clone.downcast::<CharacterData>().unwrap().SetData(text.unwrap());
@@ -629,7 +649,7 @@ impl RangeMethods for Range {
                 end_data.ReplaceData(0, end_offset, DOMString::new())?;
             } else {
                 // Step 19.1.
-                let clone = child.CloneNode(false);
+                let clone = child.CloneNode(false)?;
                 // Step 19.2.
                 fragment.upcast::<Node>().AppendChild(&clone)?;
                 // Step 19.3.
@@ -725,7 +745,7 @@ impl RangeMethods for Range {
             reference_node.r().map_or(parent.len(), |node| node.index());
 
         // Step 11
-        let new_offset = new_offset + if node.type_id() == NodeTypeId::DocumentFragment {
+        let new_offset = new_offset + if node.is::<DocumentFragment>() {
             node.len()
         } else {
             1
@@ -837,7 +857,7 @@ impl RangeMethods for Range {
         match new_parent.type_id() {
             NodeTypeId::Document(_) |
             NodeTypeId::DocumentType |
-            NodeTypeId::DocumentFragment => return Err(Error::InvalidNodeType),
+            NodeTypeId::DocumentFragment(_) => return Err(Error::InvalidNodeType),
             _ => ()
         }
 
@@ -906,7 +926,7 @@ impl RangeMethods for Range {
         let node = self.StartContainer();
         let owner_doc = node.owner_doc();
         let element = match node.type_id() {
-            NodeTypeId::Document(_) | NodeTypeId::DocumentFragment => None,
+            NodeTypeId::Document(_) | NodeTypeId::DocumentFragment(_) => None,
             NodeTypeId::Element(_) => Some(DomRoot::downcast::<Element>(node).unwrap()),
             NodeTypeId::CharacterData(CharacterDataTypeId::Comment) |
             NodeTypeId::CharacterData(CharacterDataTypeId::Text) => node.GetParentElement(),
@@ -977,9 +997,9 @@ impl PartialEq for BoundaryPoint {
 }
 
 // https://dom.spec.whatwg.org/#concept-range-bp-position
-fn bp_position(a_node: &Node, a_offset: u32,
-               b_node: &Node, b_offset: u32)
-               -> Option<Ordering> {
+pub fn bp_position(a_node: &Node, a_offset: u32,
+                   b_node: &Node, b_offset: u32)
+                   -> Option<Ordering> {
     if a_node as *const Node == b_node as *const Node {
         // Step 1.
         return Some(a_offset.cmp(&b_offset));
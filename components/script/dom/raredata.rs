@@ -0,0 +1,39 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::root::MutNullableDom;
+use dom::domtokenlist::DOMTokenList;
+use dom::htmlslotelement::HTMLSlotElement;
+use dom::namednodemap::NamedNodeMap;
+use dom::node::UniqueId;
+use dom::nodelist::NodeList;
+use dom::shadowroot::ShadowRoot;
+
+// Data that only a small share of nodes ever need is kept here, and only
+// allocated for the nodes that do, so that it doesn't make every node in the
+// DOM bigger.
+
+#[derive(Default, JSTraceable, MallocSizeOf)]
+#[must_root]
+pub struct NodeRareData {
+    /// The shadow root this node is in, if it's in a shadow tree.
+    pub containing_shadow_root: MutNullableDom<ShadowRoot>,
+    /// The slot this node is assigned to, if any.
+    pub assigned_slot: MutNullableDom<HTMLSlotElement>,
+    /// The live list of children return by .childNodes.
+    pub child_list: MutNullableDom<NodeList>,
+    /// The unique ID of this node, used by devtools and webdriver.
+    pub unique_id: UniqueId,
+}
+
+#[derive(Default, JSTraceable, MallocSizeOf)]
+#[must_root]
+pub struct ElementRareData {
+    /// <https://dom.spec.whatwg.org/#concept-element-shadow-root>
+    pub shadow_root: MutNullableDom<ShadowRoot>,
+    /// The live map of attributes returned by .attributes.
+    pub attr_list: MutNullableDom<NamedNodeMap>,
+    /// The live token list returned by .classList.
+    pub class_list: MutNullableDom<DOMTokenList>,
+}
@@ -0,0 +1,763 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::ReadableByteStreamControllerBinding;
+use dom::bindings::codegen::Bindings::ReadableByteStreamControllerBinding::ReadableByteStreamControllerMethods;
+use dom::bindings::conversions::ConversionBehavior;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::readablestream::{ReadableStream, ReadableStreamState, UnderlyingSource};
+use dom::readablestreambyobrequest::ReadableStreamBYOBRequest;
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSContext, JSObject, JS_GetArrayBufferByteLength, JS_GetArrayBufferViewBuffer};
+use js::jsapi::{JS_GetArrayBufferViewByteLength, JS_GetArrayBufferViewByteOffset, JS_GetArrayBufferViewType};
+use js::jsapi::{JS_NewDataView, JS_NewFloat32ArrayWithBuffer, JS_NewFloat64ArrayWithBuffer};
+use js::jsapi::{JS_NewInt16ArrayWithBuffer, JS_NewInt32ArrayWithBuffer, JS_NewInt8ArrayWithBuffer};
+use js::jsapi::{JS_NewUint16ArrayWithBuffer, JS_NewUint32ArrayWithBuffer, JS_NewUint8ArrayWithBuffer};
+use js::jsapi::{JS_NewUint8ClampedArrayWithBuffer, Type};
+use js::jsval::{ObjectValue, UndefinedValue};
+use js::rust::{CustomAutoRooterGuard, HandleObject, HandleValue};
+use js::typedarray::{ArrayBufferView, CreateWith, Uint8Array};
+use std::cell::Cell;
+use std::cmp;
+use std::collections::VecDeque;
+use std::ptr;
+use std::rc::Rc;
+use streams::{convert, error_to_value, fulfill_read_request, get_property, throw_value, upon_settlement};
+
+/// Which kind of read a pull-into descriptor fulfills.
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf, PartialEq)]
+enum ReaderType {
+    /// A read of a default reader, into an automatically allocated buffer.
+    Default,
+    Byob,
+    /// A read whose reader was released before the source responded to it.
+    None,
+}
+
+/// <https://streams.spec.whatwg.org/#pull-into-descriptor>
+#[derive(JSTraceable, MallocSizeOf)]
+struct PullIntoDescriptor {
+    /// The view to read into. Unlike the specification, the buffer is not
+    /// transferred, so the view keeps working in script.
+    #[ignore_malloc_size_of = "mozjs"]
+    view: Box<Heap<*mut JSObject>>,
+    byte_length: usize,
+    bytes_filled: usize,
+    element_size: usize,
+    reader_type: ReaderType,
+}
+
+/// <https://streams.spec.whatwg.org/#rbs-controller-class>
+#[dom_struct]
+pub struct ReadableByteStreamController {
+    reflector_: Reflector,
+    stream: Dom<ReadableStream>,
+    source: DomRefCell<UnderlyingSource>,
+    /// The underlying source object of `UnderlyingSource::Script`.
+    #[ignore_malloc_size_of = "mozjs"]
+    underlying_source: Heap<*mut JSObject>,
+    /// The bytes enqueued and not read yet, one entry per chunk.
+    queue: DomRefCell<VecDeque<Vec<u8>>>,
+    pending_pull_intos: DomRefCell<VecDeque<PullIntoDescriptor>>,
+    byob_request: MutNullableDom<ReadableStreamBYOBRequest>,
+    auto_allocate_chunk_size: Option<usize>,
+    started: Cell<bool>,
+    close_requested: Cell<bool>,
+    pull_again: Cell<bool>,
+    pulling: Cell<bool>,
+    strategy_hwm: Cell<f64>,
+}
+
+impl ReadableByteStreamController {
+    #[allow(unrooted_must_root)]
+    fn new_inherited(stream: &ReadableStream,
+                     source: UnderlyingSource,
+                     high_water_mark: f64,
+                     auto_allocate_chunk_size: Option<usize>)
+                     -> ReadableByteStreamController {
+        ReadableByteStreamController {
+            reflector_: Reflector::new(),
+            stream: Dom::from_ref(stream),
+            source: DomRefCell::new(source),
+            underlying_source: Heap::default(),
+            queue: DomRefCell::new(VecDeque::new()),
+            pending_pull_intos: DomRefCell::new(VecDeque::new()),
+            byob_request: Default::default(),
+            auto_allocate_chunk_size: auto_allocate_chunk_size,
+            started: Cell::new(false),
+            close_requested: Cell::new(false),
+            pull_again: Cell::new(false),
+            pulling: Cell::new(false),
+            strategy_hwm: Cell::new(high_water_mark),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    fn new(global: &GlobalScope,
+           stream: &ReadableStream,
+           source: UnderlyingSource,
+           high_water_mark: f64,
+           auto_allocate_chunk_size: Option<usize>)
+           -> DomRoot<ReadableByteStreamController> {
+        reflect_dom_object(
+            Box::new(ReadableByteStreamController::new_inherited(
+                stream, source, high_water_mark, auto_allocate_chunk_size)),
+            global,
+            ReadableByteStreamControllerBinding::Wrap,
+        )
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-readable-byte-stream-controller>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn set_up(cx: *mut JSContext,
+                         stream: &ReadableStream,
+                         source: UnderlyingSource,
+                         underlying_source: HandleObject,
+                         high_water_mark: f64,
+                         auto_allocate_chunk_size: Option<usize>)
+                         -> Fallible<DomRoot<ReadableByteStreamController>> {
+        let global = stream.global();
+        let controller = ReadableByteStreamController::new(
+            &global, stream, source, high_water_mark, auto_allocate_chunk_size);
+        controller.underlying_source.set(underlying_source.get());
+        stream.set_byte_controller(&controller);
+
+        rooted!(in(cx) let controller_value = ObjectValue(controller.reflector().get_jsobject().get()));
+        let source = controller.source.borrow().clone();
+        let start = source.start(cx, &global, underlying_source, controller_value.handle())?;
+        upon_settlement(&start, Dom::from_ref(&*controller),
+                        Some(ReadableByteStreamController::started),
+                        Some(ReadableByteStreamController::start_failed));
+        Ok(controller)
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-readable-byte-stream-controller-from-underlying-source>
+    #[allow(unsafe_code)]
+    pub unsafe fn set_up_from_underlying_source(cx: *mut JSContext,
+                                                stream: &ReadableStream,
+                                                underlying_source: HandleObject,
+                                                high_water_mark: f64)
+                                                -> ErrorResult {
+        rooted!(in(cx) let mut value = UndefinedValue());
+        let auto_allocate_chunk_size = if get_property(cx, underlying_source, "autoAllocateChunkSize",
+                                                       value.handle_mut())? {
+            let size = convert::<u64>(cx, value.handle(), ConversionBehavior::EnforceRange)?;
+            if size == 0 {
+                return Err(Error::Type("autoAllocateChunkSize must be greater than 0".to_owned()));
+            }
+            Some(size as usize)
+        } else {
+            None
+        };
+        ReadableByteStreamController::set_up(cx, stream, UnderlyingSource::Script, underlying_source,
+                                             high_water_mark, auto_allocate_chunk_size)
+            .map(|_| ())
+    }
+
+    #[allow(unrooted_must_root)]
+    fn started(controller: &Dom<ReadableByteStreamController>, _cx: *mut JSContext, _value: HandleValue) {
+        controller.started.set(true);
+        controller.call_pull_if_needed();
+    }
+
+    #[allow(unrooted_must_root)]
+    fn start_failed(controller: &Dom<ReadableByteStreamController>, _cx: *mut JSContext, error: HandleValue) {
+        controller.error(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-call-pull-if-needed>
+    #[allow(unrooted_must_root, unsafe_code)]
+    fn call_pull_if_needed(&self) {
+        if !self.should_call_pull() {
+            return;
+        }
+        if self.pulling.get() {
+            self.pull_again.set(true);
+            return;
+        }
+        self.pulling.set(true);
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(cx) let underlying_source = self.underlying_source.get());
+        rooted!(in(cx) let controller = ObjectValue(self.reflector().get_jsobject().get()));
+        let source = self.source.borrow().clone();
+        let pull = unsafe { source.pull(cx, &global, underlying_source.handle(), controller.handle()) };
+        upon_settlement(&pull, Dom::from_ref(self),
+                        Some(ReadableByteStreamController::pulled),
+                        Some(ReadableByteStreamController::pull_failed));
+    }
+
+    #[allow(unrooted_must_root)]
+    fn pulled(controller: &Dom<ReadableByteStreamController>, _cx: *mut JSContext, _value: HandleValue) {
+        controller.pulling.set(false);
+        if controller.pull_again.get() {
+            controller.pull_again.set(false);
+            controller.call_pull_if_needed();
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    fn pull_failed(controller: &Dom<ReadableByteStreamController>, _cx: *mut JSContext, error: HandleValue) {
+        controller.error(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-should-call-pull>
+    fn should_call_pull(&self) -> bool {
+        if !self.can_close_or_enqueue() || !self.started.get() {
+            return false;
+        }
+        if self.stream.has_default_reader() && self.stream.get_num_read_requests() > 0 {
+            return true;
+        }
+        if self.stream.has_byob_reader() && self.stream.get_num_read_into_requests() > 0 {
+            return true;
+        }
+        self.desired_size().map_or(false, |desired_size| desired_size > 0.)
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-clear-algorithms>
+    fn clear_algorithms(&self) {
+        *self.source.borrow_mut() = UnderlyingSource::Native;
+        self.underlying_source.set(ptr::null_mut());
+    }
+
+    fn queue_total_size(&self) -> usize {
+        self.queue.borrow().iter().map(Vec::len).sum()
+    }
+
+    /// Whether the stream is readable and not asked to close yet.
+    pub fn can_close_or_enqueue(&self) -> bool {
+        !self.close_requested.get() && self.stream.is_readable()
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-get-desired-size>
+    pub fn desired_size(&self) -> Option<f64> {
+        match self.stream.state() {
+            ReadableStreamState::Errored => None,
+            ReadableStreamState::Closed => Some(0.),
+            ReadableStreamState::Readable => Some(self.strategy_hwm.get() - self.queue_total_size() as f64),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-invalidate-byob-request>
+    fn invalidate_byob_request(&self) {
+        if let Some(request) = self.byob_request.get() {
+            request.invalidate();
+        }
+        self.byob_request.set(None);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-clear-pending-pull-intos>
+    fn clear_pending_pull_intos(&self) {
+        self.invalidate_byob_request();
+        self.pending_pull_intos.borrow_mut().clear();
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-handle-queue-drain>
+    fn handle_queue_drain(&self) {
+        if self.queue_total_size() == 0 && self.close_requested.get() {
+            self.clear_algorithms();
+            self.stream.close();
+        } else {
+            self.call_pull_if_needed();
+        }
+    }
+
+    /// Takes the first `length` bytes of the queue.
+    fn dequeue_bytes(&self, length: usize) -> Vec<u8> {
+        let mut queue = self.queue.borrow_mut();
+        let mut bytes = Vec::with_capacity(length);
+        while bytes.len() < length {
+            let remaining = length - bytes.len();
+            let exhausted = {
+                let chunk = queue.front_mut().expect("The queue has enough bytes");
+                if chunk.len() <= remaining {
+                    bytes.extend_from_slice(chunk);
+                    true
+                } else {
+                    bytes.extend(chunk.drain(..remaining));
+                    false
+                }
+            };
+            if exhausted {
+                queue.pop_front();
+            }
+        }
+        bytes
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-fill-pull-into-descriptor-from-queue>
+    #[allow(unsafe_code)]
+    unsafe fn fill_pull_into_descriptor_from_queue(&self, cx: *mut JSContext, descriptor: &mut PullIntoDescriptor)
+                                                   -> bool {
+        let max_bytes_to_copy = cmp::min(self.queue_total_size(), descriptor.byte_length - descriptor.bytes_filled);
+        let max_bytes_filled = descriptor.bytes_filled + max_bytes_to_copy;
+        let max_aligned_bytes = max_bytes_filled - max_bytes_filled % descriptor.element_size;
+        let mut total_bytes_to_copy = max_bytes_to_copy;
+        let mut ready = false;
+        if max_aligned_bytes > descriptor.bytes_filled {
+            total_bytes_to_copy = max_aligned_bytes - descriptor.bytes_filled;
+            ready = true;
+        }
+        let bytes = self.dequeue_bytes(total_bytes_to_copy);
+        typedarray!(in(cx) let view: ArrayBufferView = descriptor.view.get());
+        if let Ok(mut view) = view {
+            let start = descriptor.bytes_filled;
+            view.as_mut_slice()[start..start + bytes.len()].copy_from_slice(&bytes);
+        }
+        descriptor.bytes_filled += total_bytes_to_copy;
+        ready
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-commit-pull-into-descriptor>
+    #[allow(unsafe_code)]
+    unsafe fn commit_pull_into_descriptor(&self, cx: *mut JSContext, descriptor: PullIntoDescriptor) {
+        let done = self.stream.state() == ReadableStreamState::Closed;
+        rooted!(in(cx) let view = descriptor.view.get());
+        rooted!(in(cx) let filled_view = ObjectValue(
+            filled_view(cx, view.handle(), descriptor.bytes_filled, descriptor.element_size)));
+        match descriptor.reader_type {
+            ReaderType::Default => self.stream.fulfill_read_request(filled_view.handle(), done),
+            ReaderType::Byob => self.stream.fulfill_read_into_request(filled_view.handle(), done),
+            ReaderType::None => {},
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-process-pull-into-descriptors-using-queue>
+    #[allow(unsafe_code)]
+    unsafe fn process_pull_into_descriptors_using_queue(&self, cx: *mut JSContext) {
+        while self.queue_total_size() > 0 {
+            let descriptor = self.pending_pull_intos.borrow_mut().pop_front();
+            let mut descriptor = match descriptor {
+                Some(descriptor) => descriptor,
+                None => return,
+            };
+            if self.fill_pull_into_descriptor_from_queue(cx, &mut descriptor) {
+                self.commit_pull_into_descriptor(cx, descriptor);
+            } else {
+                self.pending_pull_intos.borrow_mut().push_front(descriptor);
+            }
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-enqueue>
+    #[allow(unsafe_code)]
+    pub unsafe fn enqueue(&self, cx: *mut JSContext, bytes: Vec<u8>) {
+        if !self.can_close_or_enqueue() {
+            return;
+        }
+        self.invalidate_byob_request();
+        if self.stream.has_default_reader() && self.stream.get_num_read_requests() > 0 {
+            // The automatically allocated buffer of a pending read is not
+            // needed anymore.
+            let default_pull_into = self.pending_pull_intos.borrow().front()
+                .map_or(false, |descriptor| descriptor.reader_type == ReaderType::Default);
+            if default_pull_into {
+                self.pending_pull_intos.borrow_mut().pop_front();
+            }
+            rooted!(in(cx) let mut array = ptr::null_mut::<JSObject>());
+            assert!(Uint8Array::create(cx, CreateWith::Slice(&bytes), array.handle_mut()).is_ok());
+            rooted!(in(cx) let chunk = ObjectValue(array.get()));
+            self.stream.fulfill_read_request(chunk.handle(), false);
+        } else {
+            self.queue.borrow_mut().push_back(bytes);
+            if self.stream.has_byob_reader() {
+                self.process_pull_into_descriptors_using_queue(cx);
+            }
+        }
+        self.call_pull_if_needed();
+    }
+
+    /// Enqueues the bytes of `chunk`, which must be an `ArrayBufferView`.
+    #[allow(unsafe_code)]
+    pub unsafe fn enqueue_value(&self, cx: *mut JSContext, chunk: HandleValue) -> ErrorResult {
+        if !chunk.is_object() {
+            return Err(Error::Type("Chunks of readable byte streams must be ArrayBufferViews".to_owned()));
+        }
+        typedarray!(in(cx) let view: ArrayBufferView = chunk.to_object());
+        match view {
+            Ok(view) => {
+                let bytes = view.to_vec();
+                self.enqueue(cx, bytes);
+                Ok(())
+            },
+            Err(()) => Err(Error::Type("Chunks of readable byte streams must be ArrayBufferViews".to_owned())),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-close>
+    #[allow(unsafe_code)]
+    pub unsafe fn close(&self, cx: *mut JSContext) -> ErrorResult {
+        if !self.can_close_or_enqueue() {
+            return Ok(());
+        }
+        if self.queue_total_size() > 0 {
+            self.close_requested.set(true);
+            return Ok(());
+        }
+        let partial_element = self.pending_pull_intos.borrow().front()
+            .map_or(false, |descriptor| descriptor.bytes_filled % descriptor.element_size != 0);
+        if partial_element {
+            rooted!(in(cx) let mut error = UndefinedValue());
+            let global = self.global();
+            error_to_value(cx, &global, Error::Type("The stream closed within an element".to_owned()),
+                           error.handle_mut());
+            self.error(error.handle());
+            return Err(throw_value(cx, error.handle()));
+        }
+        self.close_requested.set(true);
+        self.clear_algorithms();
+        self.stream.close();
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-error>
+    pub fn error(&self, error: HandleValue) {
+        if !self.stream.is_readable() {
+            return;
+        }
+        self.clear_pending_pull_intos();
+        self.queue.borrow_mut().clear();
+        self.clear_algorithms();
+        self.stream.error(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#rbs-controller-private-cancel>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn cancel_steps(&self, cx: *mut JSContext, reason: HandleValue) -> Rc<Promise> {
+        self.clear_pending_pull_intos();
+        self.queue.borrow_mut().clear();
+        rooted!(in(cx) let underlying_source = self.underlying_source.get());
+        let source = self.source.borrow().clone();
+        let result = source.cancel(cx, &self.global(), underlying_source.handle(), reason);
+        self.clear_algorithms();
+        result
+    }
+
+    /// <https://streams.spec.whatwg.org/#rbs-controller-private-pull>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn pull_steps(&self, cx: *mut JSContext, promise: Rc<Promise>) {
+        let queue_total_size = self.queue_total_size();
+        if queue_total_size > 0 {
+            let bytes = self.queue.borrow_mut().pop_front().expect("The queue is not empty");
+            rooted!(in(cx) let mut array = ptr::null_mut::<JSObject>());
+            assert!(Uint8Array::create(cx, CreateWith::Slice(&bytes), array.handle_mut()).is_ok());
+            rooted!(in(cx) let chunk = ObjectValue(array.get()));
+            self.handle_queue_drain();
+            return fulfill_read_request(&promise, chunk.handle(), false);
+        }
+        if let Some(size) = self.auto_allocate_chunk_size {
+            rooted!(in(cx) let mut array = ptr::null_mut::<JSObject>());
+            if Uint8Array::create(cx, CreateWith::Length(size as u32), array.handle_mut()).is_err() {
+                return promise.reject_error(Error::Range("Could not allocate a chunk".to_owned()));
+            }
+            self.pending_pull_intos.borrow_mut().push_back(PullIntoDescriptor {
+                view: Heap::boxed(array.get()),
+                byte_length: size,
+                bytes_filled: 0,
+                element_size: 1,
+                reader_type: ReaderType::Default,
+            });
+        }
+        self.stream.add_read_request(promise);
+        self.call_pull_if_needed();
+    }
+
+    /// <https://streams.spec.whatwg.org/#abstract-opdef-readablebytestreamcontroller-releasesteps>
+    pub fn release_steps(&self) {
+        let mut pending_pull_intos = self.pending_pull_intos.borrow_mut();
+        if let Some(mut descriptor) = pending_pull_intos.pop_front() {
+            descriptor.reader_type = ReaderType::None;
+            pending_pull_intos.clear();
+            pending_pull_intos.push_back(descriptor);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-pull-into>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn pull_into(&self, cx: *mut JSContext, view: HandleObject, promise: Rc<Promise>) {
+        let mut descriptor = PullIntoDescriptor {
+            view: Heap::boxed(view.get()),
+            byte_length: JS_GetArrayBufferViewByteLength(view.get()) as usize,
+            bytes_filled: 0,
+            element_size: element_size(JS_GetArrayBufferViewType(view.get())),
+            reader_type: ReaderType::Byob,
+        };
+        if !self.pending_pull_intos.borrow().is_empty() {
+            self.pending_pull_intos.borrow_mut().push_back(descriptor);
+            return self.stream.add_read_into_request(promise);
+        }
+        if self.stream.state() == ReadableStreamState::Closed {
+            rooted!(in(cx) let empty_view = ObjectValue(filled_view(cx, view, 0, descriptor.element_size)));
+            return fulfill_read_request(&promise, empty_view.handle(), true);
+        }
+        if self.queue_total_size() > 0 {
+            if self.fill_pull_into_descriptor_from_queue(cx, &mut descriptor) {
+                rooted!(in(cx) let filled = ObjectValue(
+                    filled_view(cx, view, descriptor.bytes_filled, descriptor.element_size)));
+                self.handle_queue_drain();
+                return fulfill_read_request(&promise, filled.handle(), false);
+            }
+            if self.close_requested.get() {
+                rooted!(in(cx) let mut error = UndefinedValue());
+                let global = self.global();
+                error_to_value(cx, &global, Error::Type("The stream closed within an element".to_owned()),
+                               error.handle_mut());
+                self.error(error.handle());
+                return promise.reject_native(&error.handle());
+            }
+        }
+        self.pending_pull_intos.borrow_mut().push_back(descriptor);
+        self.stream.add_read_into_request(promise);
+        self.call_pull_if_needed();
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-respond>
+    #[allow(unsafe_code)]
+    pub unsafe fn respond(&self, cx: *mut JSContext, bytes_written: u64) -> ErrorResult {
+        let (bytes_filled, byte_length) = match self.pending_pull_intos.borrow().front() {
+            Some(descriptor) => (descriptor.bytes_filled, descriptor.byte_length),
+            None => return Err(Error::Type("There is no pending read to respond to".to_owned())),
+        };
+        if self.stream.state() == ReadableStreamState::Closed {
+            if bytes_written != 0 {
+                return Err(Error::Type("A closed stream can only respond with 0 bytes".to_owned()));
+            }
+        } else {
+            if bytes_written == 0 {
+                return Err(Error::Type("A readable stream cannot respond with 0 bytes".to_owned()));
+            }
+            if bytes_filled as u64 + bytes_written > byte_length as u64 {
+                return Err(Error::Range("More bytes were written than the view can hold".to_owned()));
+            }
+        }
+        self.invalidate_byob_request();
+        if self.stream.state() == ReadableStreamState::Closed {
+            self.respond_in_closed_state(cx);
+        } else {
+            self.respond_in_readable_state(cx, bytes_written as usize);
+        }
+        self.call_pull_if_needed();
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-respond-with-new-view>
+    #[allow(unsafe_code)]
+    pub unsafe fn respond_with_new_view(&self, cx: *mut JSContext, view: HandleObject) -> ErrorResult {
+        let byte_length = JS_GetArrayBufferViewByteLength(view.get()) as usize;
+        {
+            let pending_pull_intos = self.pending_pull_intos.borrow();
+            let descriptor = match pending_pull_intos.front() {
+                Some(descriptor) => descriptor,
+                None => return Err(Error::Type("There is no pending read to respond to".to_owned())),
+            };
+            if self.stream.state() == ReadableStreamState::Closed {
+                if byte_length != 0 {
+                    return Err(Error::Type("A closed stream can only respond with an empty view".to_owned()));
+                }
+            } else {
+                if byte_length == 0 {
+                    return Err(Error::Type("A readable stream cannot respond with an empty view".to_owned()));
+                }
+                if descriptor.bytes_filled + byte_length > descriptor.byte_length {
+                    return Err(Error::Range("The view is larger than the pending read".to_owned()));
+                }
+            }
+            // The new view replaces the buffer of the pending read, whose
+            // filled bytes it starts with, so copy what it holds.
+            typedarray!(in(cx) let source: ArrayBufferView = view.get());
+            typedarray!(in(cx) let dest: ArrayBufferView = descriptor.view.get());
+            if let (Ok(source), Ok(mut dest)) = (source, dest) {
+                let start = descriptor.bytes_filled;
+                dest.as_mut_slice()[start..start + byte_length].copy_from_slice(source.as_slice());
+            }
+        }
+        self.respond(cx, byte_length as u64)
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-respond-in-closed-state>
+    #[allow(unsafe_code)]
+    unsafe fn respond_in_closed_state(&self, cx: *mut JSContext) {
+        let released = self.pending_pull_intos.borrow().front()
+            .map_or(false, |descriptor| descriptor.reader_type == ReaderType::None);
+        if released {
+            self.pending_pull_intos.borrow_mut().pop_front();
+        }
+        if !self.stream.has_byob_reader() {
+            return;
+        }
+        while self.stream.get_num_read_into_requests() > 0 {
+            let descriptor = self.pending_pull_intos.borrow_mut().pop_front();
+            match descriptor {
+                Some(descriptor) => self.commit_pull_into_descriptor(cx, descriptor),
+                None => break,
+            }
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-respond-in-readable-state>
+    #[allow(unsafe_code)]
+    unsafe fn respond_in_readable_state(&self, cx: *mut JSContext, bytes_written: usize) {
+        let mut descriptor = self.pending_pull_intos.borrow_mut().pop_front().expect("A read is pending");
+        descriptor.bytes_filled += bytes_written;
+        if descriptor.reader_type == ReaderType::None {
+            // The reader is gone, so the filled bytes go back to the queue.
+            if let Some(bytes) = view_bytes(cx, &descriptor, 0, descriptor.bytes_filled) {
+                self.queue.borrow_mut().push_back(bytes);
+            }
+            return self.process_pull_into_descriptors_using_queue(cx);
+        }
+        if descriptor.bytes_filled < descriptor.element_size {
+            return self.pending_pull_intos.borrow_mut().push_front(descriptor);
+        }
+        let remainder = descriptor.bytes_filled % descriptor.element_size;
+        if remainder > 0 {
+            let end = descriptor.bytes_filled;
+            if let Some(bytes) = view_bytes(cx, &descriptor, end - remainder, end) {
+                self.queue.borrow_mut().push_back(bytes);
+            }
+        }
+        descriptor.bytes_filled -= remainder;
+        self.commit_pull_into_descriptor(cx, descriptor);
+        self.process_pull_into_descriptors_using_queue(cx);
+    }
+
+    /// Creates the view of the pending read that the source writes into.
+    ///
+    /// <https://streams.spec.whatwg.org/#abstract-opdef-readablebytestreamcontrollergetbyobrequest>
+    #[allow(unsafe_code)]
+    fn get_byob_request(&self) -> Option<DomRoot<ReadableStreamBYOBRequest>> {
+        if let Some(request) = self.byob_request.get() {
+            return Some(request);
+        }
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(cx) let mut view = ptr::null_mut::<JSObject>());
+        {
+            let pending_pull_intos = self.pending_pull_intos.borrow();
+            let descriptor = pending_pull_intos.front()?;
+            rooted!(in(cx) let descriptor_view = descriptor.view.get());
+            unsafe {
+                let mut is_shared = false;
+                rooted!(in(cx) let buffer =
+                    JS_GetArrayBufferViewBuffer(cx, descriptor_view.handle().into(), &mut is_shared));
+                let byte_offset = JS_GetArrayBufferViewByteOffset(descriptor_view.get()) as usize;
+                view.set(JS_NewUint8ArrayWithBuffer(cx, buffer.handle().into(),
+                                                    (byte_offset + descriptor.bytes_filled) as u32,
+                                                    (descriptor.byte_length - descriptor.bytes_filled) as i32));
+            }
+        }
+        let request = ReadableStreamBYOBRequest::new(&global, self, view.handle());
+        self.byob_request.set(Some(&request));
+        Some(request)
+    }
+}
+
+/// The size in bytes of the elements of views of type `type_`.
+fn element_size(type_: Type) -> usize {
+    match type_ {
+        Type::Int16 | Type::Uint16 => 2,
+        Type::Int32 | Type::Uint32 | Type::Float32 => 4,
+        Type::Float64 => 8,
+        _ => 1,
+    }
+}
+
+/// Creates a view of the same type as `view` over its first `bytes_filled`
+/// bytes.
+#[allow(unsafe_code)]
+unsafe fn filled_view(cx: *mut JSContext, view: HandleObject, bytes_filled: usize, element_size: usize)
+                      -> *mut JSObject {
+    if bytes_filled == JS_GetArrayBufferViewByteLength(view.get()) as usize {
+        return view.get();
+    }
+    let mut is_shared = false;
+    rooted!(in(cx) let buffer = JS_GetArrayBufferViewBuffer(cx, view.into(), &mut is_shared));
+    let buffer = buffer.handle().into();
+    let byte_offset = JS_GetArrayBufferViewByteOffset(view.get());
+    let length = (bytes_filled / element_size) as i32;
+    match JS_GetArrayBufferViewType(view.get()) {
+        Type::Int8 => JS_NewInt8ArrayWithBuffer(cx, buffer, byte_offset, length),
+        Type::Uint8 => JS_NewUint8ArrayWithBuffer(cx, buffer, byte_offset, length),
+        Type::Uint8Clamped => JS_NewUint8ClampedArrayWithBuffer(cx, buffer, byte_offset, length),
+        Type::Int16 => JS_NewInt16ArrayWithBuffer(cx, buffer, byte_offset, length),
+        Type::Uint16 => JS_NewUint16ArrayWithBuffer(cx, buffer, byte_offset, length),
+        Type::Int32 => JS_NewInt32ArrayWithBuffer(cx, buffer, byte_offset, length),
+        Type::Uint32 => JS_NewUint32ArrayWithBuffer(cx, buffer, byte_offset, length),
+        Type::Float32 => JS_NewFloat32ArrayWithBuffer(cx, buffer, byte_offset, length),
+        Type::Float64 => JS_NewFloat64ArrayWithBuffer(cx, buffer, byte_offset, length),
+        _ => JS_NewDataView(cx, buffer, byte_offset, bytes_filled as i32),
+    }
+}
+
+/// Copies the bytes `start..end` of the view of `descriptor`.
+#[allow(unsafe_code)]
+unsafe fn view_bytes(cx: *mut JSContext, descriptor: &PullIntoDescriptor, start: usize, end: usize)
+                     -> Option<Vec<u8>> {
+    typedarray!(in(cx) let view: ArrayBufferView = descriptor.view.get());
+    view.ok().map(|view| view.as_slice()[start..end].to_vec())
+}
+
+/// The byte length of `view` and of its buffer.
+#[allow(unsafe_code)]
+pub unsafe fn view_byte_lengths(cx: *mut JSContext, view: HandleObject) -> (usize, usize) {
+    let mut is_shared = false;
+    rooted!(in(cx) let buffer = JS_GetArrayBufferViewBuffer(cx, view.into(), &mut is_shared));
+    (JS_GetArrayBufferViewByteLength(view.get()) as usize, JS_GetArrayBufferByteLength(buffer.get()) as usize)
+}
+
+impl ReadableByteStreamControllerMethods for ReadableByteStreamController {
+    // https://streams.spec.whatwg.org/#rbs-controller-byob-request
+    fn GetByobRequest(&self) -> Option<DomRoot<ReadableStreamBYOBRequest>> {
+        self.get_byob_request()
+    }
+
+    // https://streams.spec.whatwg.org/#rbs-controller-desired-size
+    fn GetDesiredSize(&self) -> Option<f64> {
+        self.desired_size()
+    }
+
+    #[allow(unsafe_code)]
+    // https://streams.spec.whatwg.org/#rbs-controller-close
+    fn Close(&self) -> ErrorResult {
+        if !self.can_close_or_enqueue() {
+            return Err(Error::Type("The stream cannot be closed".to_owned()));
+        }
+        unsafe { self.close(self.global().get_cx()) }
+    }
+
+    #[allow(unsafe_code)]
+    // https://streams.spec.whatwg.org/#rbs-controller-enqueue
+    fn Enqueue(&self, chunk: CustomAutoRooterGuard<ArrayBufferView>) -> ErrorResult {
+        let cx = self.global().get_cx();
+        rooted!(in(cx) let view = *chunk.underlying_object());
+        let (byte_length, buffer_byte_length) = unsafe { view_byte_lengths(cx, view.handle()) };
+        if byte_length == 0 {
+            return Err(Error::Type("The chunk is empty".to_owned()));
+        }
+        if buffer_byte_length == 0 {
+            return Err(Error::Type("The buffer of the chunk is empty".to_owned()));
+        }
+        if !self.can_close_or_enqueue() {
+            return Err(Error::Type("The stream cannot be enqueued to".to_owned()));
+        }
+        unsafe {
+            let bytes = chunk.to_vec();
+            self.enqueue(cx, bytes);
+        }
+        Ok(())
+    }
+
+    #[allow(unsafe_code)]
+    // https://streams.spec.whatwg.org/#rbs-controller-error
+    unsafe fn Error(&self, _cx: *mut JSContext, error: HandleValue) {
+        self.error(error);
+    }
+}
+
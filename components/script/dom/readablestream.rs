@@ -0,0 +1,1051 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::abortsignal::{AbortAlgorithm, AbortSignal};
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::ReadableStreamBinding::{self, ReadableStreamGetReaderOptions};
+use dom::bindings::codegen::Bindings::ReadableStreamBinding::{ReadableStreamMethods, ReadableStreamReaderMode};
+use dom::bindings::codegen::Bindings::ReadableStreamBinding::{ReadableWritablePair, StreamPipeOptions};
+use dom::bindings::codegen::UnionTypes::ReadableStreamDefaultReaderOrReadableStreamBYOBReader as ReadableStreamReader;
+use dom::bindings::conversions::StringificationBehavior;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use dom::bindings::str::DOMString;
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::readablebytestreamcontroller::ReadableByteStreamController;
+use dom::readablestreambyobreader::ReadableStreamBYOBReader;
+use dom::readablestreamdefaultcontroller::ReadableStreamDefaultController;
+use dom::readablestreamdefaultreader::ReadableStreamDefaultReader;
+use dom::transformstream::TransformStream;
+use dom::writablestream::{WritableStream, WritableStreamState};
+use dom::writablestreamdefaultwriter::WritableStreamDefaultWriter;
+use dom_struct::dom_struct;
+use js::jsapi::{HandleValueArray, Heap, JSAutoCompartment, JSContext, JSObject, JS_NewArrayObject};
+use js::jsval::{JSVal, ObjectValue, UndefinedValue};
+use js::rust::{HandleObject, HandleValue, MutableHandleValue};
+use js::typedarray::{CreateWith, Uint8Array};
+use std::cell::Cell;
+use std::ptr;
+use std::rc::Rc;
+use streams::{convert, error_to_value, extract_high_water_mark, extract_size_algorithm, get_property};
+use streams::{invoke_or_noop, object_or_null, promise_invoke_or_noop, promise_rejected_with};
+use streams::{promise_rejected_with_error, promise_resolved_with, read_read_result, resolved_promise};
+use streams::{transform_to_undefined, upon_settlement};
+
+/// <https://streams.spec.whatwg.org/#readablestream-state>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum ReadableStreamState {
+    Readable,
+    Closed,
+    Errored,
+}
+
+/// Where the controller of a readable stream pulls its chunks from.
+#[derive(Clone, JSTraceable, MallocSizeOf)]
+#[must_root]
+pub enum UnderlyingSource {
+    /// The underlying source object given to the constructor, which the
+    /// controller keeps.
+    Script,
+    /// Chunks enqueued by the user agent, like the body of a fetch response.
+    Native,
+    /// A branch of a teed stream.
+    Tee {
+        #[ignore_malloc_size_of = "Rc"]
+        state: Rc<TeeState>,
+        branch: TeeBranch,
+    },
+    /// The readable side of a transform stream.
+    Transform(Dom<TransformStream>),
+}
+
+impl UnderlyingSource {
+    /// Runs the start algorithm, returning a promise settled once the source
+    /// has started.
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn start(&self,
+                        cx: *mut JSContext,
+                        global: &GlobalScope,
+                        source: HandleObject,
+                        controller: HandleValue)
+                        -> Fallible<Rc<Promise>> {
+        match *self {
+            UnderlyingSource::Script => {
+                rooted!(in(cx) let mut result = UndefinedValue());
+                invoke_or_noop(cx, source, "start", &[controller.get()], result.handle_mut())?;
+                Ok(promise_resolved_with(global, cx, result.handle()))
+            },
+            UnderlyingSource::Transform(ref stream) => Ok(stream.start_promise()),
+            UnderlyingSource::Native | UnderlyingSource::Tee { .. } => Ok(resolved_promise(global)),
+        }
+    }
+
+    /// Runs the pull algorithm.
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn pull(&self,
+                       cx: *mut JSContext,
+                       global: &GlobalScope,
+                       source: HandleObject,
+                       controller: HandleValue)
+                       -> Rc<Promise> {
+        match *self {
+            UnderlyingSource::Script => promise_invoke_or_noop(global, cx, source, "pull", &[controller.get()]),
+            UnderlyingSource::Tee { ref state, .. } => TeeState::pull(state),
+            UnderlyingSource::Transform(ref stream) => stream.source_pull(),
+            UnderlyingSource::Native => resolved_promise(global),
+        }
+    }
+
+    /// Runs the cancel algorithm.
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn cancel(&self,
+                         cx: *mut JSContext,
+                         global: &GlobalScope,
+                         source: HandleObject,
+                         reason: HandleValue)
+                         -> Rc<Promise> {
+        match *self {
+            UnderlyingSource::Script => promise_invoke_or_noop(global, cx, source, "cancel", &[reason.get()]),
+            UnderlyingSource::Tee { ref state, branch } => TeeState::cancel(state, cx, branch, reason),
+            UnderlyingSource::Transform(ref stream) => stream.source_cancel(cx, reason),
+            UnderlyingSource::Native => resolved_promise(global),
+        }
+    }
+}
+
+/// <https://streams.spec.whatwg.org/#rs-class>
+#[dom_struct]
+pub struct ReadableStream {
+    reflector_: Reflector,
+    state: Cell<ReadableStreamState>,
+    disturbed: Cell<bool>,
+    #[ignore_malloc_size_of = "mozjs"]
+    stored_error: Heap<JSVal>,
+    default_controller: MutNullableDom<ReadableStreamDefaultController>,
+    byte_controller: MutNullableDom<ReadableByteStreamController>,
+    default_reader: MutNullableDom<ReadableStreamDefaultReader>,
+    byob_reader: MutNullableDom<ReadableStreamBYOBReader>,
+}
+
+impl ReadableStream {
+    fn new_inherited() -> ReadableStream {
+        ReadableStream {
+            reflector_: Reflector::new(),
+            state: Cell::new(ReadableStreamState::Readable),
+            disturbed: Cell::new(false),
+            stored_error: Heap::default(),
+            default_controller: Default::default(),
+            byte_controller: Default::default(),
+            default_reader: Default::default(),
+            byob_reader: Default::default(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<ReadableStream> {
+        reflect_dom_object(Box::new(ReadableStream::new_inherited()), global, ReadableStreamBinding::Wrap)
+    }
+
+    /// Creates a stream pulling from a source that is not given by script,
+    /// and so never fails to start.
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub fn new_with_source(global: &GlobalScope,
+                           source: UnderlyingSource,
+                           bytes: bool,
+                           high_water_mark: f64)
+                           -> DomRoot<ReadableStream> {
+        let cx = global.get_cx();
+        let stream = ReadableStream::new(global);
+        unsafe {
+            let result = if bytes {
+                ReadableByteStreamController::set_up(cx, &stream, source, HandleObject::null(), high_water_mark, None)
+                    .map(|_| ())
+            } else {
+                rooted!(in(cx) let size = UndefinedValue());
+                ReadableStreamDefaultController::set_up(cx, &stream, source, HandleObject::null(),
+                                                        high_water_mark, size.handle())
+                    .map(|_| ())
+            };
+            result.expect("Only sources given by script can fail to start");
+        }
+        stream
+    }
+
+    /// Creates a stream of the `Uint8Array` chunks given to `enqueue_native`.
+    pub fn new_with_native_source(global: &GlobalScope) -> DomRoot<ReadableStream> {
+        ReadableStream::new_with_source(global, UnderlyingSource::Native, false, 1.)
+    }
+
+    // https://streams.spec.whatwg.org/#rs-constructor
+    #[allow(unsafe_code)]
+    pub unsafe fn Constructor(cx: *mut JSContext,
+                              global: &GlobalScope,
+                              underlying_source: Option<*mut JSObject>,
+                              strategy: Option<*mut JSObject>)
+                              -> Fallible<DomRoot<ReadableStream>> {
+        rooted!(in(cx) let underlying_source = object_or_null(underlying_source));
+        rooted!(in(cx) let strategy = object_or_null(strategy));
+        let stream = ReadableStream::new(global);
+
+        rooted!(in(cx) let mut type_ = UndefinedValue());
+        if get_property(cx, underlying_source.handle(), "type", type_.handle_mut())? {
+            let type_ = convert::<DOMString>(cx, type_.handle(), StringificationBehavior::Default)?;
+            if &*type_ != "bytes" {
+                return Err(Error::Range(format!("\"{}\" is not a valid type of readable stream", type_)));
+            }
+            rooted!(in(cx) let mut size = UndefinedValue());
+            if get_property(cx, strategy.handle(), "size", size.handle_mut())? {
+                return Err(Error::Range("Readable byte streams cannot have a size function".to_owned()));
+            }
+            let high_water_mark = extract_high_water_mark(cx, strategy.handle(), 0.)?;
+            ReadableByteStreamController::set_up_from_underlying_source(
+                cx, &stream, underlying_source.handle(), high_water_mark)?;
+        } else {
+            rooted!(in(cx) let mut size = UndefinedValue());
+            extract_size_algorithm(cx, strategy.handle(), size.handle_mut())?;
+            let high_water_mark = extract_high_water_mark(cx, strategy.handle(), 1.)?;
+            ReadableStreamDefaultController::set_up(cx, &stream, UnderlyingSource::Script,
+                                                    underlying_source.handle(), high_water_mark, size.handle())?;
+        }
+        Ok(stream)
+    }
+
+    pub fn state(&self) -> ReadableStreamState {
+        self.state.get()
+    }
+
+    pub fn is_readable(&self) -> bool {
+        self.state.get() == ReadableStreamState::Readable
+    }
+
+    /// <https://streams.spec.whatwg.org/#is-readable-stream-locked>
+    pub fn is_locked(&self) -> bool {
+        self.default_reader.get().is_some() || self.byob_reader.get().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#is-readable-stream-disturbed>
+    pub fn is_disturbed(&self) -> bool {
+        self.disturbed.get()
+    }
+
+    pub fn set_disturbed(&self) {
+        self.disturbed.set(true);
+    }
+
+    pub fn get_stored_error(&self, mut rval: MutableHandleValue) {
+        rval.set(self.stored_error.get());
+    }
+
+    pub fn is_byte_stream(&self) -> bool {
+        self.byte_controller.get().is_some()
+    }
+
+    pub fn default_controller(&self) -> Option<DomRoot<ReadableStreamDefaultController>> {
+        self.default_controller.get()
+    }
+
+    pub fn byte_controller(&self) -> Option<DomRoot<ReadableByteStreamController>> {
+        self.byte_controller.get()
+    }
+
+    pub fn set_default_controller(&self, controller: &ReadableStreamDefaultController) {
+        self.default_controller.set(Some(controller));
+    }
+
+    pub fn set_byte_controller(&self, controller: &ReadableByteStreamController) {
+        self.byte_controller.set(Some(controller));
+    }
+
+    pub fn set_default_reader(&self, reader: Option<&ReadableStreamDefaultReader>) {
+        self.default_reader.set(reader);
+    }
+
+    pub fn set_byob_reader(&self, reader: Option<&ReadableStreamBYOBReader>) {
+        self.byob_reader.set(reader);
+    }
+
+    pub fn has_default_reader(&self) -> bool {
+        self.default_reader.get().is_some()
+    }
+
+    pub fn has_byob_reader(&self) -> bool {
+        self.byob_reader.get().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-get-num-read-requests>
+    pub fn get_num_read_requests(&self) -> usize {
+        self.default_reader.get().map_or(0, |reader| reader.num_read_requests())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-get-num-read-into-requests>
+    pub fn get_num_read_into_requests(&self) -> usize {
+        self.byob_reader.get().map_or(0, |reader| reader.num_read_into_requests())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-add-read-request>
+    pub fn add_read_request(&self, promise: Rc<Promise>) {
+        self.default_reader.get().expect("Read requests need a default reader").add_read_request(promise);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-add-read-into-request>
+    pub fn add_read_into_request(&self, promise: Rc<Promise>) {
+        self.byob_reader.get().expect("Read-into requests need a BYOB reader").add_read_into_request(promise);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-fulfill-read-request>
+    pub fn fulfill_read_request(&self, chunk: HandleValue, done: bool) {
+        let reader = self.default_reader.get().expect("Read requests need a default reader");
+        reader.fulfill_read_request(chunk, done);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-fulfill-read-into-request>
+    pub fn fulfill_read_into_request(&self, chunk: HandleValue, done: bool) {
+        let reader = self.byob_reader.get().expect("Read-into requests need a BYOB reader");
+        reader.fulfill_read_into_request(chunk, done);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-close>
+    pub fn close(&self) {
+        assert!(self.is_readable());
+        self.state.set(ReadableStreamState::Closed);
+        if let Some(reader) = self.default_reader.get() {
+            reader.close();
+        }
+        if let Some(reader) = self.byob_reader.get() {
+            reader.close();
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-error>
+    pub fn error(&self, error: HandleValue) {
+        assert!(self.is_readable());
+        self.state.set(ReadableStreamState::Errored);
+        self.stored_error.set(error.get());
+        if let Some(reader) = self.default_reader.get() {
+            reader.error(error);
+        }
+        if let Some(reader) = self.byob_reader.get() {
+            reader.error(error);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-cancel>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn cancel(&self, cx: *mut JSContext, reason: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        self.disturbed.set(true);
+        match self.state.get() {
+            ReadableStreamState::Closed => return resolved_promise(&global),
+            ReadableStreamState::Errored => {
+                rooted!(in(cx) let mut error = UndefinedValue());
+                self.get_stored_error(error.handle_mut());
+                return promise_rejected_with(&global, error.handle());
+            },
+            ReadableStreamState::Readable => {},
+        }
+        self.close();
+        if let Some(reader) = self.byob_reader.get() {
+            reader.close_read_into_requests();
+        }
+        let source_cancel = match self.default_controller.get() {
+            Some(controller) => controller.cancel_steps(cx, reason),
+            None => self.byte_controller.get().expect("A stream has a controller").cancel_steps(cx, reason),
+        };
+        transform_to_undefined(&global, &source_cancel)
+    }
+
+    /// Runs the pull steps of the controller for a new read request.
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub fn pull(&self, promise: Rc<Promise>) {
+        let cx = self.global().get_cx();
+        match self.default_controller.get() {
+            Some(controller) => unsafe { controller.pull_steps(cx, promise) },
+            None => unsafe {
+                self.byte_controller.get().expect("A stream has a controller").pull_steps(cx, promise)
+            },
+        }
+    }
+
+    /// Runs the release steps of the controller once a reader is released.
+    pub fn release_controller(&self) {
+        if let Some(controller) = self.byte_controller.get() {
+            controller.release_steps();
+        }
+    }
+
+    /// Enqueues `chunk` into the controller, which must be able to enqueue.
+    #[allow(unsafe_code)]
+    pub unsafe fn enqueue(&self, cx: *mut JSContext, chunk: HandleValue) -> ErrorResult {
+        match self.default_controller.get() {
+            Some(controller) => controller.enqueue(cx, chunk),
+            None => {
+                let controller = self.byte_controller.get().expect("A stream has a controller");
+                controller.enqueue_value(cx, chunk)
+            },
+        }
+    }
+
+    /// Closes the controller, if it can still be closed.
+    #[allow(unsafe_code)]
+    pub fn close_controller(&self) {
+        if let Some(controller) = self.default_controller.get() {
+            if controller.can_close_or_enqueue() {
+                controller.close();
+            }
+        }
+        if let Some(controller) = self.byte_controller.get() {
+            if controller.can_close_or_enqueue() {
+                let cx = self.global().get_cx();
+                if let Err(error) = unsafe { controller.close(cx) } {
+                    debug!("Closing a readable byte stream failed: {:?}", error);
+                }
+            }
+        }
+    }
+
+    /// Errors the controller with `error`.
+    pub fn error_controller(&self, error: HandleValue) {
+        if let Some(controller) = self.default_controller.get() {
+            controller.error(error);
+        }
+        if let Some(controller) = self.byte_controller.get() {
+            controller.error(error);
+        }
+    }
+
+    /// Enqueues `bytes` as a `Uint8Array`, if the native source of this
+    /// stream has not been closed, errored or canceled.
+    #[allow(unsafe_code)]
+    pub fn enqueue_native(&self, bytes: Vec<u8>) {
+        let controller = self.default_controller.get().expect("Native sources use a default controller");
+        if !controller.can_close_or_enqueue() {
+            return;
+        }
+        let global = self.global();
+        let cx = global.get_cx();
+        let _ac = JSAutoCompartment::new(cx, global.reflector().get_jsobject().get());
+        unsafe {
+            rooted!(in(cx) let mut array = ptr::null_mut::<JSObject>());
+            assert!(Uint8Array::create(cx, CreateWith::Slice(&bytes), array.handle_mut()).is_ok());
+            rooted!(in(cx) let chunk = ObjectValue(array.get()));
+            if let Err(error) = controller.enqueue(cx, chunk.handle()) {
+                debug!("Enqueuing a native chunk failed: {:?}", error);
+            }
+        }
+    }
+
+    /// Closes the native source of this stream.
+    pub fn close_native(&self) {
+        self.close_controller();
+    }
+
+    /// Errors the native source of this stream with `error`.
+    #[allow(unsafe_code)]
+    pub fn error_native(&self, error: Error) {
+        let global = self.global();
+        let cx = global.get_cx();
+        let _ac = JSAutoCompartment::new(cx, global.reflector().get_jsobject().get());
+        rooted!(in(cx) let mut error_value = UndefinedValue());
+        unsafe { error_to_value(cx, &global, error, error_value.handle_mut()) };
+        self.error_controller(error_value.handle());
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-tee>
+    #[allow(unrooted_must_root)]
+    pub fn tee(&self, clone_for_branch_2: bool) -> Fallible<(DomRoot<ReadableStream>, DomRoot<ReadableStream>)> {
+        let global = self.global();
+        let reader = ReadableStreamDefaultReader::set_up(&global, self)?;
+        let state = Rc::new(TeeState {
+            stream: Dom::from_ref(self),
+            reader: Dom::from_ref(&*reader),
+            clone_for_branch_2: clone_for_branch_2,
+            reading: Cell::new(false),
+            read_again: Cell::new(false),
+            canceled_1: Cell::new(false),
+            canceled_2: Cell::new(false),
+            reason_1: Heap::default(),
+            reason_2: Heap::default(),
+            branch_1: Default::default(),
+            branch_2: Default::default(),
+            cancel_promise: Promise::new(&global),
+        });
+        let bytes = self.is_byte_stream();
+        let high_water_mark = if bytes { 0. } else { 1. };
+        let branch_1 = ReadableStream::new_with_source(
+            &global, UnderlyingSource::Tee { state: state.clone(), branch: TeeBranch::First }, bytes, high_water_mark);
+        let branch_2 = ReadableStream::new_with_source(
+            &global, UnderlyingSource::Tee { state: state.clone(), branch: TeeBranch::Second }, bytes, high_water_mark);
+        state.branch_1.set(Some(&branch_1));
+        state.branch_2.set(Some(&branch_2));
+        upon_settlement(&reader.closed_promise(), state, None, Some(TeeState::source_errored));
+        Ok((branch_1, branch_2))
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-pipe-to>
+    #[allow(unrooted_must_root)]
+    pub fn pipe_to(&self, dest: &WritableStream, options: &StreamPipeOptions) -> Rc<Promise> {
+        let global = self.global();
+        let reader = match ReadableStreamDefaultReader::set_up(&global, self) {
+            Ok(reader) => reader,
+            Err(error) => return promise_rejected_with_error(&global, error),
+        };
+        let writer = match WritableStreamDefaultWriter::set_up(&global, dest) {
+            Ok(writer) => writer,
+            Err(error) => {
+                reader.release();
+                return promise_rejected_with_error(&global, error);
+            },
+        };
+        self.disturbed.set(true);
+        let state = Rc::new(PipeState {
+            source: Dom::from_ref(self),
+            dest: Dom::from_ref(dest),
+            reader: Dom::from_ref(&*reader),
+            writer: Dom::from_ref(&*writer),
+            prevent_close: options.preventClose,
+            prevent_abort: options.preventAbort,
+            prevent_cancel: options.preventCancel,
+            reading: Cell::new(false),
+            shutting_down: Cell::new(false),
+            action: Cell::new(PipeAction::None),
+            has_error: Cell::new(false),
+            error: Heap::default(),
+            pending_write: DomRefCell::new(None),
+            pending_action: DomRefCell::new(None),
+            action_failed: Cell::new(false),
+            signal: options.signal.as_ref().map(|signal| Dom::from_ref(&**signal)),
+            promise: Promise::new(&global),
+        });
+        if let Some(ref signal) = options.signal {
+            if signal.aborted() {
+                rooted!(in(global.get_cx()) let reason = signal.abort_reason());
+                PipeState::abort(&state, reason.handle());
+                return state.promise.clone();
+            }
+            signal.add_algorithm(AbortAlgorithm::StreamPipe(state.clone()));
+        }
+        PipeState::start(&state);
+        state.promise.clone()
+    }
+
+    /// Reads every chunk of this stream, returning a promise fulfilled with a
+    /// `Uint8Array` of the concatenated chunks.
+    ///
+    /// <https://streams.spec.whatwg.org/#readablestreamdefaultreader-read-all-bytes>
+    #[allow(unrooted_must_root)]
+    pub fn read_all_bytes(&self) -> Rc<Promise> {
+        let global = self.global();
+        let reader = match ReadableStreamDefaultReader::set_up(&global, self) {
+            Ok(reader) => reader,
+            Err(error) => return promise_rejected_with_error(&global, error),
+        };
+        let state = Rc::new(AllBytesReader {
+            reader: Dom::from_ref(&*reader),
+            bytes: DomRefCell::new(vec![]),
+            promise: Promise::new(&global),
+        });
+        AllBytesReader::read(&state);
+        state.promise.clone()
+    }
+}
+
+impl ReadableStreamMethods for ReadableStream {
+    // https://streams.spec.whatwg.org/#rs-locked
+    fn Locked(&self) -> bool {
+        self.is_locked()
+    }
+
+    #[allow(unrooted_must_root, unsafe_code)]
+    // https://streams.spec.whatwg.org/#rs-cancel
+    unsafe fn Cancel(&self, cx: *mut JSContext, reason: HandleValue) -> Rc<Promise> {
+        if self.is_locked() {
+            return promise_rejected_with_error(&self.global(), Error::Type("The stream is locked".to_owned()));
+        }
+        self.cancel(cx, reason)
+    }
+
+    // https://streams.spec.whatwg.org/#rs-get-reader
+    fn GetReader(&self, options: &ReadableStreamGetReaderOptions) -> Fallible<ReadableStreamReader> {
+        let global = self.global();
+        Ok(match options.mode {
+            None => ReadableStreamReader::ReadableStreamDefaultReader(
+                ReadableStreamDefaultReader::set_up(&global, self)?),
+            Some(ReadableStreamReaderMode::Byob) => ReadableStreamReader::ReadableStreamBYOBReader(
+                ReadableStreamBYOBReader::set_up(&global, self)?),
+        })
+    }
+
+    // https://streams.spec.whatwg.org/#rs-pipe-through
+    fn PipeThrough(&self,
+                   transform: &ReadableWritablePair,
+                   options: &StreamPipeOptions)
+                   -> Fallible<DomRoot<ReadableStream>> {
+        if self.is_locked() {
+            return Err(Error::Type("The stream is locked".to_owned()));
+        }
+        if transform.writable.is_locked() {
+            return Err(Error::Type("The writable side of the transform is locked".to_owned()));
+        }
+        self.pipe_to(&transform.writable, options);
+        Ok(DomRoot::from_ref(&*transform.readable))
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://streams.spec.whatwg.org/#rs-pipe-to
+    fn PipeTo(&self, destination: &WritableStream, options: &StreamPipeOptions) -> Rc<Promise> {
+        if self.is_locked() {
+            return promise_rejected_with_error(&self.global(), Error::Type("The stream is locked".to_owned()));
+        }
+        if destination.is_locked() {
+            let error = Error::Type("The destination stream is locked".to_owned());
+            return promise_rejected_with_error(&self.global(), error);
+        }
+        self.pipe_to(destination, options)
+    }
+
+    // https://streams.spec.whatwg.org/#rs-tee
+    fn Tee(&self) -> Fallible<Vec<DomRoot<ReadableStream>>> {
+        let (branch_1, branch_2) = self.tee(false)?;
+        Ok(vec![branch_1, branch_2])
+    }
+}
+
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum TeeBranch {
+    First,
+    Second,
+}
+
+/// The state shared by the two branches of a teed stream.
+///
+/// <https://streams.spec.whatwg.org/#abstract-opdef-readablestreamdefaulttee>
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+pub struct TeeState {
+    stream: Dom<ReadableStream>,
+    reader: Dom<ReadableStreamDefaultReader>,
+    /// Whether the second branch gets copies of the chunks, so that the
+    /// branches do not share buffers.
+    clone_for_branch_2: bool,
+    reading: Cell<bool>,
+    read_again: Cell<bool>,
+    canceled_1: Cell<bool>,
+    canceled_2: Cell<bool>,
+    #[ignore_malloc_size_of = "mozjs"]
+    reason_1: Heap<JSVal>,
+    #[ignore_malloc_size_of = "mozjs"]
+    reason_2: Heap<JSVal>,
+    branch_1: MutNullableDom<ReadableStream>,
+    branch_2: MutNullableDom<ReadableStream>,
+    #[ignore_malloc_size_of = "Rc"]
+    cancel_promise: Rc<Promise>,
+}
+
+impl TeeState {
+    #[allow(unrooted_must_root)]
+    fn pull(state: &Rc<TeeState>) -> Rc<Promise> {
+        let global = state.stream.global();
+        if state.reading.get() {
+            state.read_again.set(true);
+            return resolved_promise(&global);
+        }
+        state.reading.set(true);
+        let read = state.reader.read();
+        upon_settlement(&read, state.clone(), Some(TeeState::chunk_read), Some(TeeState::read_failed));
+        resolved_promise(&global)
+    }
+
+    #[allow(unrooted_must_root, unsafe_code)]
+    fn chunk_read(state: &Rc<TeeState>, cx: *mut JSContext, result: HandleValue) {
+        rooted!(in(cx) let mut chunk = UndefinedValue());
+        if unsafe { read_read_result(cx, result, chunk.handle_mut()) } {
+            return state.closed();
+        }
+        state.read_again.set(false);
+        if !state.canceled_1.get() {
+            if let Some(branch) = state.branch_1.get() {
+                let _ = unsafe { branch.enqueue(cx, chunk.handle()) };
+            }
+        }
+        if !state.canceled_2.get() {
+            if let Some(branch) = state.branch_2.get() {
+                rooted!(in(cx) let mut chunk_2 = chunk.get());
+                if state.clone_for_branch_2 {
+                    unsafe { clone_chunk(cx, chunk.handle(), chunk_2.handle_mut()) };
+                }
+                let _ = unsafe { branch.enqueue(cx, chunk_2.handle()) };
+            }
+        }
+        state.reading.set(false);
+        if state.read_again.get() {
+            TeeState::pull(state);
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    fn read_failed(state: &Rc<TeeState>, _cx: *mut JSContext, _error: HandleValue) {
+        state.reading.set(false);
+    }
+
+    fn closed(&self) {
+        self.reading.set(false);
+        if !self.canceled_1.get() {
+            if let Some(branch) = self.branch_1.get() {
+                branch.close_controller();
+            }
+        }
+        if !self.canceled_2.get() {
+            if let Some(branch) = self.branch_2.get() {
+                branch.close_controller();
+            }
+        }
+        if !self.canceled_1.get() || !self.canceled_2.get() {
+            self.cancel_promise.resolve_native(&());
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    fn source_errored(state: &Rc<TeeState>, _cx: *mut JSContext, error: HandleValue) {
+        if let Some(branch) = state.branch_1.get() {
+            branch.error_controller(error);
+        }
+        if let Some(branch) = state.branch_2.get() {
+            branch.error_controller(error);
+        }
+        if !state.canceled_1.get() || !state.canceled_2.get() {
+            state.cancel_promise.resolve_native(&());
+        }
+    }
+
+    #[allow(unrooted_must_root, unsafe_code)]
+    fn cancel(state: &Rc<TeeState>, cx: *mut JSContext, branch: TeeBranch, reason: HandleValue) -> Rc<Promise> {
+        match branch {
+            TeeBranch::First => {
+                state.canceled_1.set(true);
+                state.reason_1.set(reason.get());
+            },
+            TeeBranch::Second => {
+                state.canceled_2.set(true);
+                state.reason_2.set(reason.get());
+            },
+        }
+        if state.canceled_1.get() && state.canceled_2.get() {
+            unsafe {
+                let reasons = [state.reason_1.get(), state.reason_2.get()];
+                let reasons = HandleValueArray::from_rooted_slice(&reasons);
+                rooted!(in(cx) let composite_reason = JS_NewArrayObject(cx, &reasons));
+                rooted!(in(cx) let composite_reason = ObjectValue(composite_reason.get()));
+                let cancel_result = state.stream.cancel(cx, composite_reason.handle());
+                rooted!(in(cx) let cancel_result = ObjectValue(cancel_result.reflector().get_jsobject().get()));
+                state.cancel_promise.resolve_native(&cancel_result.handle());
+            }
+        }
+        state.cancel_promise.clone()
+    }
+}
+
+/// Copies `chunk` if it is a `Uint8Array`, which the chunks of bodies are.
+#[allow(unsafe_code)]
+unsafe fn clone_chunk(cx: *mut JSContext, chunk: HandleValue, mut rval: MutableHandleValue) {
+    if !chunk.is_object() {
+        return;
+    }
+    typedarray!(in(cx) let array: Uint8Array = chunk.to_object());
+    if let Ok(array) = array {
+        rooted!(in(cx) let mut copy = ptr::null_mut::<JSObject>());
+        if Uint8Array::create(cx, CreateWith::Slice(array.as_slice()), copy.handle_mut()).is_ok() {
+            rval.set(ObjectValue(copy.get()));
+        }
+    }
+}
+
+/// What to do with the other stream once a pipe shuts down.
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf, PartialEq)]
+enum PipeAction {
+    None,
+    AbortDest,
+    CloseDest,
+    CancelSource,
+    AbortDestAndCancelSource,
+}
+
+/// The state of a pipe from a readable stream to a writable stream.
+///
+/// <https://streams.spec.whatwg.org/#readable-stream-pipe-to>
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+pub struct PipeState {
+    source: Dom<ReadableStream>,
+    dest: Dom<WritableStream>,
+    reader: Dom<ReadableStreamDefaultReader>,
+    writer: Dom<WritableStreamDefaultWriter>,
+    prevent_close: bool,
+    prevent_abort: bool,
+    prevent_cancel: bool,
+    reading: Cell<bool>,
+    shutting_down: Cell<bool>,
+    action: Cell<PipeAction>,
+    has_error: Cell<bool>,
+    #[ignore_malloc_size_of = "mozjs"]
+    error: Heap<JSVal>,
+    /// The promise of the last write, which settles after every earlier one.
+    #[ignore_malloc_size_of = "Rc"]
+    pending_write: DomRefCell<Option<Rc<Promise>>>,
+    /// The promise of the second action of a pipe that was aborted, which is
+    /// waited for after the first one.
+    #[ignore_malloc_size_of = "Rc"]
+    pending_action: DomRefCell<Option<Rc<Promise>>>,
+    action_failed: Cell<bool>,
+    signal: Option<Dom<AbortSignal>>,
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+}
+
+impl PipeState {
+    #[allow(unrooted_must_root, unsafe_code)]
+    fn start(state: &Rc<PipeState>) {
+        upon_settlement(&state.reader.closed_promise(), state.clone(),
+                        Some(PipeState::source_closed), Some(PipeState::source_errored));
+        upon_settlement(&state.writer.closed_promise(), state.clone(), None, Some(PipeState::dest_errored));
+        if state.dest.close_queued_or_in_flight() || state.dest.state() == WritableStreamState::Closed {
+            let global = state.source.global();
+            let cx = global.get_cx();
+            rooted!(in(cx) let mut error = UndefinedValue());
+            unsafe {
+                let _ac = JSAutoCompartment::new(cx, global.reflector().get_jsobject().get());
+                error_to_value(cx, &global, Error::Type("The destination stream is closed".to_owned()),
+                               error.handle_mut());
+            }
+            let action = if state.prevent_cancel { PipeAction::None } else { PipeAction::CancelSource };
+            return PipeState::shutdown(state, action, Some(error.handle()));
+        }
+        PipeState::wait_for_ready(state);
+    }
+
+    /// Waits until the destination can take another chunk without
+    /// exceeding its high water mark.
+    #[allow(unrooted_must_root)]
+    fn wait_for_ready(state: &Rc<PipeState>) {
+        if state.shutting_down.get() {
+            return;
+        }
+        upon_settlement(&state.writer.ready_promise(), state.clone(), Some(PipeState::ready), None);
+    }
+
+    #[allow(unrooted_must_root)]
+    fn ready(state: &Rc<PipeState>, _cx: *mut JSContext, _value: HandleValue) {
+        if state.shutting_down.get() {
+            return;
+        }
+        state.reading.set(true);
+        let read = state.reader.read();
+        upon_settlement(&read, state.clone(), Some(PipeState::chunk_read), Some(PipeState::read_failed));
+    }
+
+    #[allow(unrooted_must_root, unsafe_code)]
+    fn chunk_read(state: &Rc<PipeState>, cx: *mut JSContext, result: HandleValue) {
+        state.reading.set(false);
+        rooted!(in(cx) let mut chunk = UndefinedValue());
+        if unsafe { read_read_result(cx, result, chunk.handle_mut()) } {
+            return PipeState::source_closed_steps(state);
+        }
+        if state.shutting_down.get() {
+            return;
+        }
+        let write = unsafe { state.writer.write(cx, chunk.handle()) };
+        *state.pending_write.borrow_mut() = Some(write);
+        PipeState::wait_for_ready(state);
+    }
+
+    #[allow(unrooted_must_root)]
+    fn read_failed(state: &Rc<PipeState>, _cx: *mut JSContext, _error: HandleValue) {
+        state.reading.set(false);
+    }
+
+    #[allow(unrooted_must_root)]
+    fn source_closed(state: &Rc<PipeState>, _cx: *mut JSContext, _value: HandleValue) {
+        // A pending read sees the end of the stream once it has written the
+        // chunks that were read before.
+        if !state.reading.get() {
+            PipeState::source_closed_steps(state);
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    fn source_closed_steps(state: &Rc<PipeState>) {
+        let action = if state.prevent_close { PipeAction::None } else { PipeAction::CloseDest };
+        PipeState::shutdown(state, action, None);
+    }
+
+    #[allow(unrooted_must_root)]
+    fn source_errored(state: &Rc<PipeState>, _cx: *mut JSContext, error: HandleValue) {
+        let action = if state.prevent_abort { PipeAction::None } else { PipeAction::AbortDest };
+        PipeState::shutdown(state, action, Some(error));
+    }
+
+    #[allow(unrooted_must_root)]
+    fn dest_errored(state: &Rc<PipeState>, _cx: *mut JSContext, error: HandleValue) {
+        let action = if state.prevent_cancel { PipeAction::None } else { PipeAction::CancelSource };
+        PipeState::shutdown(state, action, Some(error));
+    }
+
+    /// Shuts down the pipe once its signal was aborted with `reason`.
+    ///
+    /// <https://streams.spec.whatwg.org/#readable-stream-pipe-to>
+    #[allow(unrooted_must_root)]
+    pub fn abort(state: &Rc<PipeState>, reason: HandleValue) {
+        let abort_dest = !state.prevent_abort && state.dest.state() == WritableStreamState::Writable;
+        let cancel_source = !state.prevent_cancel && state.source.is_readable();
+        let action = match (abort_dest, cancel_source) {
+            (false, false) => PipeAction::None,
+            (true, false) => PipeAction::AbortDest,
+            (false, true) => PipeAction::CancelSource,
+            (true, true) => PipeAction::AbortDestAndCancelSource,
+        };
+        PipeState::shutdown(state, action, Some(reason));
+    }
+
+    /// <https://streams.spec.whatwg.org/#rs-pipeTo-shutdown-with-action>
+    #[allow(unrooted_must_root)]
+    fn shutdown(state: &Rc<PipeState>, action: PipeAction, error: Option<HandleValue>) {
+        if state.shutting_down.get() {
+            return;
+        }
+        state.shutting_down.set(true);
+        state.action.set(action);
+        if let Some(error) = error {
+            state.has_error.set(true);
+            state.error.set(error.get());
+        }
+        // Chunks that were read are written before acting on the shutdown.
+        let pending_write = state.pending_write.borrow_mut().take();
+        match pending_write {
+            Some(ref write) if state.dest.state() == WritableStreamState::Writable &&
+                               !state.dest.close_queued_or_in_flight() => {
+                upon_settlement(write, state.clone(),
+                                Some(PipeState::writes_finished), Some(PipeState::writes_finished));
+            },
+            _ => PipeState::perform_action(state),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    fn writes_finished(state: &Rc<PipeState>, _cx: *mut JSContext, _value: HandleValue) {
+        PipeState::perform_action(state);
+    }
+
+    #[allow(unrooted_must_root, unsafe_code)]
+    fn perform_action(state: &Rc<PipeState>) {
+        let global = state.source.global();
+        let cx = global.get_cx();
+        rooted!(in(cx) let mut error = UndefinedValue());
+        if state.has_error.get() {
+            error.set(state.error.get());
+        }
+        let promise = match state.action.get() {
+            PipeAction::None => return state.finalize(),
+            PipeAction::AbortDest => unsafe { state.dest.abort(cx, error.handle()) },
+            PipeAction::CloseDest => state.writer.close_with_error_propagation(),
+            PipeAction::CancelSource => unsafe { state.source.cancel(cx, error.handle()) },
+            PipeAction::AbortDestAndCancelSource => unsafe {
+                let abort = state.dest.abort(cx, error.handle());
+                *state.pending_action.borrow_mut() = Some(state.source.cancel(cx, error.handle()));
+                abort
+            },
+        };
+        upon_settlement(&promise, state.clone(), Some(PipeState::action_fulfilled), Some(PipeState::action_rejected));
+    }
+
+    #[allow(unrooted_must_root)]
+    fn action_fulfilled(state: &Rc<PipeState>, _cx: *mut JSContext, _value: HandleValue) {
+        PipeState::action_settled(state);
+    }
+
+    #[allow(unrooted_must_root)]
+    fn action_rejected(state: &Rc<PipeState>, _cx: *mut JSContext, error: HandleValue) {
+        // The pipe finalizes with the first error of its actions.
+        if !state.action_failed.get() {
+            state.action_failed.set(true);
+            state.has_error.set(true);
+            state.error.set(error.get());
+        }
+        PipeState::action_settled(state);
+    }
+
+    #[allow(unrooted_must_root)]
+    fn action_settled(state: &Rc<PipeState>) {
+        let pending_action = state.pending_action.borrow_mut().take();
+        match pending_action {
+            Some(ref promise) => {
+                upon_settlement(promise, state.clone(),
+                                Some(PipeState::action_fulfilled), Some(PipeState::action_rejected));
+            },
+            None => state.finalize(),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#rs-pipeTo-finalize>
+    fn finalize(&self) {
+        if let Some(ref signal) = self.signal {
+            signal.remove_stream_pipe(self);
+        }
+        self.writer.release();
+        self.reader.release();
+        if self.has_error.get() {
+            let cx = self.source.global().get_cx();
+            rooted!(in(cx) let error = self.error.get());
+            self.promise.reject_native(&error.handle());
+        } else {
+            self.promise.resolve_native(&());
+        }
+    }
+}
+
+/// The state of reading every chunk of a stream.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct AllBytesReader {
+    reader: Dom<ReadableStreamDefaultReader>,
+    bytes: DomRefCell<Vec<u8>>,
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+}
+
+impl AllBytesReader {
+    #[allow(unrooted_must_root)]
+    fn read(state: &Rc<AllBytesReader>) {
+        let read = state.reader.read();
+        upon_settlement(&read, state.clone(), Some(AllBytesReader::chunk_read), Some(AllBytesReader::read_failed));
+    }
+
+    #[allow(unrooted_must_root, unsafe_code)]
+    fn chunk_read(state: &Rc<AllBytesReader>, cx: *mut JSContext, result: HandleValue) {
+        rooted!(in(cx) let mut chunk = UndefinedValue());
+        unsafe {
+            if read_read_result(cx, result, chunk.handle_mut()) {
+                let bytes = state.bytes.borrow();
+                rooted!(in(cx) let mut array = ptr::null_mut::<JSObject>());
+                assert!(Uint8Array::create(cx, CreateWith::Slice(&bytes), array.handle_mut()).is_ok());
+                rooted!(in(cx) let value = ObjectValue(array.get()));
+                return state.promise.resolve(cx, value.handle());
+            }
+            let not_bytes = || Error::Type("The chunk is not a Uint8Array".to_owned());
+            if !chunk.is_object() {
+                return state.promise.reject_error(not_bytes());
+            }
+            typedarray!(in(cx) let array: Uint8Array = chunk.to_object());
+            match array {
+                Ok(array) => state.bytes.borrow_mut().extend_from_slice(array.as_slice()),
+                Err(()) => return state.promise.reject_error(not_bytes()),
+            }
+        }
+        AllBytesReader::read(state);
+    }
+
+    #[allow(unrooted_must_root)]
+    fn read_failed(state: &Rc<AllBytesReader>, _cx: *mut JSContext, error: HandleValue) {
+        state.promise.reject_native(&error);
+    }
+}
@@ -0,0 +1,189 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::ReadableStreamBYOBReaderBinding;
+use dom::bindings::codegen::Bindings::ReadableStreamBYOBReaderBinding::ReadableStreamBYOBReaderMethods;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{DomRoot, MutNullableDom};
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::readablebytestreamcontroller::view_byte_lengths;
+use dom::readablestream::{ReadableStream, ReadableStreamState};
+use dom::readablestreamdefaultreader::{initialize_closed_promise, release_closed_promise};
+use dom_struct::dom_struct;
+use js::jsapi::JSContext;
+use js::jsval::UndefinedValue;
+use js::rust::{CustomAutoRooterGuard, HandleObject, HandleValue};
+use js::typedarray::ArrayBufferView;
+use std::collections::VecDeque;
+use std::mem;
+use std::rc::Rc;
+use streams::{fulfill_read_request, promise_rejected_with, promise_rejected_with_error};
+
+/// <https://streams.spec.whatwg.org/#byob-reader-class>
+#[dom_struct]
+pub struct ReadableStreamBYOBReader {
+    reflector_: Reflector,
+    stream: MutNullableDom<ReadableStream>,
+    #[ignore_malloc_size_of = "Rc"]
+    closed_promise: DomRefCell<Rc<Promise>>,
+    #[ignore_malloc_size_of = "Rc"]
+    read_into_requests: DomRefCell<VecDeque<Rc<Promise>>>,
+}
+
+impl ReadableStreamBYOBReader {
+    #[allow(unrooted_must_root)]
+    fn new_inherited(closed_promise: Rc<Promise>) -> ReadableStreamBYOBReader {
+        ReadableStreamBYOBReader {
+            reflector_: Reflector::new(),
+            stream: Default::default(),
+            closed_promise: DomRefCell::new(closed_promise),
+            read_into_requests: DomRefCell::new(VecDeque::new()),
+        }
+    }
+
+    fn new(global: &GlobalScope) -> DomRoot<ReadableStreamBYOBReader> {
+        reflect_dom_object(Box::new(ReadableStreamBYOBReader::new_inherited(Promise::new(global))),
+                           global,
+                           ReadableStreamBYOBReaderBinding::Wrap)
+    }
+
+    // https://streams.spec.whatwg.org/#byob-reader-constructor
+    pub fn Constructor(global: &GlobalScope, stream: &ReadableStream) -> Fallible<DomRoot<ReadableStreamBYOBReader>> {
+        ReadableStreamBYOBReader::set_up(global, stream)
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-readable-stream-byob-reader>
+    pub fn set_up(global: &GlobalScope, stream: &ReadableStream) -> Fallible<DomRoot<ReadableStreamBYOBReader>> {
+        if stream.is_locked() {
+            return Err(Error::Type("The stream is locked".to_owned()));
+        }
+        if !stream.is_byte_stream() {
+            return Err(Error::Type("BYOB readers can only read from readable byte streams".to_owned()));
+        }
+        let reader = ReadableStreamBYOBReader::new(global);
+        reader.stream.set(Some(stream));
+        stream.set_byob_reader(Some(&reader));
+        initialize_closed_promise(stream, &reader.closed_promise.borrow());
+        Ok(reader)
+    }
+
+    pub fn num_read_into_requests(&self) -> usize {
+        self.read_into_requests.borrow().len()
+    }
+
+    pub fn add_read_into_request(&self, promise: Rc<Promise>) {
+        self.read_into_requests.borrow_mut().push_back(promise);
+    }
+
+    pub fn fulfill_read_into_request(&self, chunk: HandleValue, done: bool) {
+        let promise = self.read_into_requests.borrow_mut().pop_front();
+        if let Some(promise) = promise {
+            fulfill_read_request(&promise, chunk, done);
+        }
+    }
+
+    /// Resolves the closed promise. Pending read-into requests are fulfilled
+    /// once the controller responds to them.
+    pub fn close(&self) {
+        self.closed_promise.borrow().resolve_native(&());
+    }
+
+    /// Runs the close steps of every read-into request, when the stream is
+    /// canceled.
+    pub fn close_read_into_requests(&self) {
+        let cx = self.global().get_cx();
+        rooted!(in(cx) let undefined = UndefinedValue());
+        let read_into_requests = mem::replace(&mut *self.read_into_requests.borrow_mut(), VecDeque::new());
+        for promise in read_into_requests {
+            fulfill_read_request(&promise, undefined.handle(), true);
+        }
+    }
+
+    /// Rejects every read-into request and the closed promise with `error`.
+    pub fn error(&self, error: HandleValue) {
+        let read_into_requests = mem::replace(&mut *self.read_into_requests.borrow_mut(), VecDeque::new());
+        for promise in read_into_requests {
+            promise.reject_native(&error);
+        }
+        self.closed_promise.borrow().reject_native(&error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-byob-reader-read>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn read(&self, cx: *mut JSContext, view: HandleObject) -> Rc<Promise> {
+        let stream = self.stream.get().expect("The reader is not released");
+        let global = self.global();
+        stream.set_disturbed();
+        if stream.state() == ReadableStreamState::Errored {
+            rooted!(in(cx) let mut error = UndefinedValue());
+            stream.get_stored_error(error.handle_mut());
+            return promise_rejected_with(&global, error.handle());
+        }
+        let promise = Promise::new(&global);
+        let controller = stream.byte_controller().expect("BYOB readers read from byte streams");
+        controller.pull_into(cx, view, promise.clone());
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#abstract-opdef-readablestreambyobreaderrelease>
+    pub fn release(&self) {
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => return,
+        };
+        let error = Error::Type("The reader was released".to_owned());
+        release_closed_promise(&stream, &self.closed_promise, error.clone());
+        stream.release_controller();
+        stream.set_byob_reader(None);
+        self.stream.set(None);
+        let read_into_requests = mem::replace(&mut *self.read_into_requests.borrow_mut(), VecDeque::new());
+        for promise in read_into_requests {
+            promise.reject_error(error.clone());
+        }
+    }
+}
+
+impl ReadableStreamBYOBReaderMethods for ReadableStreamBYOBReader {
+    #[allow(unrooted_must_root, unsafe_code)]
+    // https://streams.spec.whatwg.org/#byob-reader-read
+    fn Read(&self, view: CustomAutoRooterGuard<ArrayBufferView>) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(cx) let view = *view.underlying_object());
+        let (byte_length, buffer_byte_length) = unsafe { view_byte_lengths(cx, view.handle()) };
+        if byte_length == 0 {
+            return promise_rejected_with_error(&global, Error::Type("The view is empty".to_owned()));
+        }
+        if buffer_byte_length == 0 {
+            return promise_rejected_with_error(&global, Error::Type("The buffer of the view is empty".to_owned()));
+        }
+        if self.stream.get().is_none() {
+            return promise_rejected_with_error(&global, Error::Type("The reader was released".to_owned()));
+        }
+        unsafe { self.read(cx, view.handle()) }
+    }
+
+    // https://streams.spec.whatwg.org/#byob-reader-release-lock
+    fn ReleaseLock(&self) {
+        self.release();
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://streams.spec.whatwg.org/#generic-reader-closed
+    fn Closed(&self) -> Rc<Promise> {
+        self.closed_promise.borrow().clone()
+    }
+
+    #[allow(unrooted_must_root, unsafe_code)]
+    // https://streams.spec.whatwg.org/#generic-reader-cancel
+    unsafe fn Cancel(&self, cx: *mut JSContext, reason: HandleValue) -> Rc<Promise> {
+        match self.stream.get() {
+            Some(stream) => stream.cancel(cx, reason),
+            None => promise_rejected_with_error(&self.global(), Error::Type("The reader was released".to_owned())),
+        }
+    }
+}
@@ -0,0 +1,89 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::ReadableStreamBYOBRequestBinding;
+use dom::bindings::codegen::Bindings::ReadableStreamBYOBRequestBinding::ReadableStreamBYOBRequestMethods;
+use dom::bindings::error::{Error, ErrorResult};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{DomRoot, MutNullableDom};
+use dom::globalscope::GlobalScope;
+use dom::readablebytestreamcontroller::{ReadableByteStreamController, view_byte_lengths};
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSContext, JSObject};
+use js::rust::{CustomAutoRooterGuard, HandleObject};
+use js::typedarray::ArrayBufferView;
+use std::ptr::{self, NonNull};
+
+/// <https://streams.spec.whatwg.org/#rs-byob-request-class>
+#[dom_struct]
+pub struct ReadableStreamBYOBRequest {
+    reflector_: Reflector,
+    controller: MutNullableDom<ReadableByteStreamController>,
+    #[ignore_malloc_size_of = "mozjs"]
+    view: Heap<*mut JSObject>,
+}
+
+impl ReadableStreamBYOBRequest {
+    fn new_inherited(controller: &ReadableByteStreamController) -> ReadableStreamBYOBRequest {
+        ReadableStreamBYOBRequest {
+            reflector_: Reflector::new(),
+            controller: MutNullableDom::new(Some(controller)),
+            view: Heap::default(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope,
+               controller: &ReadableByteStreamController,
+               view: HandleObject)
+               -> DomRoot<ReadableStreamBYOBRequest> {
+        let request = reflect_dom_object(Box::new(ReadableStreamBYOBRequest::new_inherited(controller)),
+                                         global,
+                                         ReadableStreamBYOBRequestBinding::Wrap);
+        request.view.set(view.get());
+        request
+    }
+
+    /// Detaches the request from its controller, once the controller was
+    /// responded to or the pending read is gone.
+    pub fn invalidate(&self) {
+        self.controller.set(None);
+        self.view.set(ptr::null_mut());
+    }
+}
+
+impl ReadableStreamBYOBRequestMethods for ReadableStreamBYOBRequest {
+    #[allow(unsafe_code)]
+    // https://streams.spec.whatwg.org/#rs-byob-request-view
+    unsafe fn GetView(&self, _cx: *mut JSContext) -> Option<NonNull<JSObject>> {
+        NonNull::new(self.view.get())
+    }
+
+    #[allow(unsafe_code)]
+    // https://streams.spec.whatwg.org/#rs-byob-request-respond
+    fn Respond(&self, bytes_written: u64) -> ErrorResult {
+        let controller = match self.controller.get() {
+            Some(controller) => controller,
+            None => return Err(Error::Type("The request was already responded to".to_owned())),
+        };
+        let cx = self.global().get_cx();
+        rooted!(in(cx) let view = self.view.get());
+        let (_, buffer_byte_length) = unsafe { view_byte_lengths(cx, view.handle()) };
+        if buffer_byte_length == 0 {
+            return Err(Error::Type("The buffer of the view is empty".to_owned()));
+        }
+        unsafe { controller.respond(cx, bytes_written) }
+    }
+
+    #[allow(unsafe_code)]
+    // https://streams.spec.whatwg.org/#rs-byob-request-respond-with-new-view
+    fn RespondWithNewView(&self, view: CustomAutoRooterGuard<ArrayBufferView>) -> ErrorResult {
+        let controller = match self.controller.get() {
+            Some(controller) => controller,
+            None => return Err(Error::Type("The request was already responded to".to_owned())),
+        };
+        let cx = self.global().get_cx();
+        rooted!(in(cx) let view = *view.underlying_object());
+        unsafe { controller.respond_with_new_view(cx, view.handle()) }
+    }
+}
@@ -0,0 +1,289 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::ReadableStreamDefaultControllerBinding;
+use dom::bindings::codegen::Bindings::ReadableStreamDefaultControllerBinding::ReadableStreamDefaultControllerMethods;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::readablestream::{ReadableStream, ReadableStreamState, UnderlyingSource};
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSContext, JSObject};
+use js::jsval::{JSVal, ObjectValue, UndefinedValue};
+use js::rust::{HandleObject, HandleValue};
+use std::cell::Cell;
+use std::ptr;
+use std::rc::Rc;
+use streams::{QueueWithSizes, call_size_algorithm, error_to_value, fulfill_read_request, throw_value};
+use streams::upon_settlement;
+
+/// <https://streams.spec.whatwg.org/#rs-default-controller-class>
+#[dom_struct]
+pub struct ReadableStreamDefaultController {
+    reflector_: Reflector,
+    stream: Dom<ReadableStream>,
+    source: DomRefCell<UnderlyingSource>,
+    /// The underlying source object of `UnderlyingSource::Script`.
+    #[ignore_malloc_size_of = "mozjs"]
+    underlying_source: Heap<*mut JSObject>,
+    queue: DomRefCell<QueueWithSizes>,
+    started: Cell<bool>,
+    close_requested: Cell<bool>,
+    pull_again: Cell<bool>,
+    pulling: Cell<bool>,
+    strategy_hwm: Cell<f64>,
+    #[ignore_malloc_size_of = "mozjs"]
+    strategy_size: Heap<JSVal>,
+}
+
+impl ReadableStreamDefaultController {
+    #[allow(unrooted_must_root)]
+    fn new_inherited(stream: &ReadableStream,
+                     source: UnderlyingSource,
+                     high_water_mark: f64)
+                     -> ReadableStreamDefaultController {
+        ReadableStreamDefaultController {
+            reflector_: Reflector::new(),
+            stream: Dom::from_ref(stream),
+            source: DomRefCell::new(source),
+            underlying_source: Heap::default(),
+            queue: Default::default(),
+            started: Cell::new(false),
+            close_requested: Cell::new(false),
+            pull_again: Cell::new(false),
+            pulling: Cell::new(false),
+            strategy_hwm: Cell::new(high_water_mark),
+            strategy_size: Heap::default(),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    fn new(global: &GlobalScope,
+           stream: &ReadableStream,
+           source: UnderlyingSource,
+           high_water_mark: f64)
+           -> DomRoot<ReadableStreamDefaultController> {
+        reflect_dom_object(
+            Box::new(ReadableStreamDefaultController::new_inherited(stream, source, high_water_mark)),
+            global,
+            ReadableStreamDefaultControllerBinding::Wrap,
+        )
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-readable-stream-default-controller>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn set_up(cx: *mut JSContext,
+                         stream: &ReadableStream,
+                         source: UnderlyingSource,
+                         underlying_source: HandleObject,
+                         high_water_mark: f64,
+                         size: HandleValue)
+                         -> Fallible<DomRoot<ReadableStreamDefaultController>> {
+        let global = stream.global();
+        let controller = ReadableStreamDefaultController::new(&global, stream, source, high_water_mark);
+        controller.underlying_source.set(underlying_source.get());
+        controller.strategy_size.set(size.get());
+        stream.set_default_controller(&controller);
+
+        rooted!(in(cx) let controller_value = ObjectValue(controller.reflector().get_jsobject().get()));
+        let source = controller.source.borrow().clone();
+        let start = source.start(cx, &global, underlying_source, controller_value.handle())?;
+        upon_settlement(&start, Dom::from_ref(&*controller),
+                        Some(ReadableStreamDefaultController::started),
+                        Some(ReadableStreamDefaultController::start_failed));
+        Ok(controller)
+    }
+
+    #[allow(unrooted_must_root)]
+    fn started(controller: &Dom<ReadableStreamDefaultController>, _cx: *mut JSContext, _value: HandleValue) {
+        controller.started.set(true);
+        controller.call_pull_if_needed();
+    }
+
+    #[allow(unrooted_must_root)]
+    fn start_failed(controller: &Dom<ReadableStreamDefaultController>, _cx: *mut JSContext, error: HandleValue) {
+        controller.error(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-call-pull-if-needed>
+    #[allow(unrooted_must_root, unsafe_code)]
+    fn call_pull_if_needed(&self) {
+        if !self.should_call_pull() {
+            return;
+        }
+        if self.pulling.get() {
+            self.pull_again.set(true);
+            return;
+        }
+        self.pulling.set(true);
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(cx) let underlying_source = self.underlying_source.get());
+        rooted!(in(cx) let controller = ObjectValue(self.reflector().get_jsobject().get()));
+        let source = self.source.borrow().clone();
+        let pull = unsafe { source.pull(cx, &global, underlying_source.handle(), controller.handle()) };
+        upon_settlement(&pull, Dom::from_ref(self),
+                        Some(ReadableStreamDefaultController::pulled),
+                        Some(ReadableStreamDefaultController::pull_failed));
+    }
+
+    #[allow(unrooted_must_root)]
+    fn pulled(controller: &Dom<ReadableStreamDefaultController>, _cx: *mut JSContext, _value: HandleValue) {
+        controller.pulling.set(false);
+        if controller.pull_again.get() {
+            controller.pull_again.set(false);
+            controller.call_pull_if_needed();
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    fn pull_failed(controller: &Dom<ReadableStreamDefaultController>, _cx: *mut JSContext, error: HandleValue) {
+        controller.error(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-should-call-pull>
+    fn should_call_pull(&self) -> bool {
+        if !self.can_close_or_enqueue() || !self.started.get() {
+            return false;
+        }
+        if self.stream.is_locked() && self.stream.get_num_read_requests() > 0 {
+            return true;
+        }
+        self.desired_size().map_or(false, |desired_size| desired_size > 0.)
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-clear-algorithms>
+    fn clear_algorithms(&self) {
+        *self.source.borrow_mut() = UnderlyingSource::Native;
+        self.underlying_source.set(ptr::null_mut());
+        self.strategy_size.set(UndefinedValue());
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-can-close-or-enqueue>
+    pub fn can_close_or_enqueue(&self) -> bool {
+        !self.close_requested.get() && self.stream.is_readable()
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-get-desired-size>
+    pub fn desired_size(&self) -> Option<f64> {
+        match self.stream.state() {
+            ReadableStreamState::Errored => None,
+            ReadableStreamState::Closed => Some(0.),
+            ReadableStreamState::Readable => Some(self.strategy_hwm.get() - self.queue.borrow().total_size()),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-has-backpressure>
+    pub fn has_backpressure(&self) -> bool {
+        !self.should_call_pull()
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-close>
+    pub fn close(&self) {
+        if !self.can_close_or_enqueue() {
+            return;
+        }
+        self.close_requested.set(true);
+        if self.queue.borrow().is_empty() {
+            self.clear_algorithms();
+            self.stream.close();
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-enqueue>
+    #[allow(unsafe_code)]
+    pub unsafe fn enqueue(&self, cx: *mut JSContext, chunk: HandleValue) -> ErrorResult {
+        if !self.can_close_or_enqueue() {
+            return Ok(());
+        }
+        if self.stream.is_locked() && self.stream.get_num_read_requests() > 0 {
+            self.stream.fulfill_read_request(chunk, false);
+        } else {
+            rooted!(in(cx) let size = self.strategy_size.get());
+            let result = call_size_algorithm(cx, size.handle(), chunk)
+                .and_then(|size| self.queue.borrow_mut().enqueue_value_with_size(chunk, size));
+            if let Err(error) = result {
+                rooted!(in(cx) let mut error_value = UndefinedValue());
+                error_to_value(cx, &self.global(), error, error_value.handle_mut());
+                self.error(error_value.handle());
+                return Err(throw_value(cx, error_value.handle()));
+            }
+        }
+        self.call_pull_if_needed();
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-error>
+    pub fn error(&self, error: HandleValue) {
+        if !self.stream.is_readable() {
+            return;
+        }
+        self.queue.borrow_mut().reset();
+        self.clear_algorithms();
+        self.stream.error(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#rs-default-controller-private-cancel>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn cancel_steps(&self, cx: *mut JSContext, reason: HandleValue) -> Rc<Promise> {
+        self.queue.borrow_mut().reset();
+        rooted!(in(cx) let underlying_source = self.underlying_source.get());
+        let source = self.source.borrow().clone();
+        let result = source.cancel(cx, &self.global(), underlying_source.handle(), reason);
+        self.clear_algorithms();
+        result
+    }
+
+    /// <https://streams.spec.whatwg.org/#rs-default-controller-private-pull>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn pull_steps(&self, cx: *mut JSContext, promise: Rc<Promise>) {
+        if self.queue.borrow().is_empty() {
+            self.stream.add_read_request(promise);
+            return self.call_pull_if_needed();
+        }
+        rooted!(in(cx) let mut chunk = UndefinedValue());
+        self.queue.borrow_mut().dequeue_value(chunk.handle_mut());
+        if self.close_requested.get() && self.queue.borrow().is_empty() {
+            self.clear_algorithms();
+            self.stream.close();
+        } else {
+            self.call_pull_if_needed();
+        }
+        fulfill_read_request(&promise, chunk.handle(), false);
+    }
+}
+
+impl ReadableStreamDefaultControllerMethods for ReadableStreamDefaultController {
+    // https://streams.spec.whatwg.org/#rs-default-controller-desired-size
+    fn GetDesiredSize(&self) -> Option<f64> {
+        self.desired_size()
+    }
+
+    // https://streams.spec.whatwg.org/#rs-default-controller-close
+    fn Close(&self) -> ErrorResult {
+        if !self.can_close_or_enqueue() {
+            return Err(Error::Type("The stream cannot be closed".to_owned()));
+        }
+        self.close();
+        Ok(())
+    }
+
+    #[allow(unsafe_code)]
+    // https://streams.spec.whatwg.org/#rs-default-controller-enqueue
+    unsafe fn Enqueue(&self, cx: *mut JSContext, chunk: HandleValue) -> ErrorResult {
+        if !self.can_close_or_enqueue() {
+            return Err(Error::Type("The stream cannot be enqueued to".to_owned()));
+        }
+        self.enqueue(cx, chunk)
+    }
+
+    #[allow(unsafe_code)]
+    // https://streams.spec.whatwg.org/#rs-default-controller-error
+    unsafe fn Error(&self, _cx: *mut JSContext, error: HandleValue) {
+        self.error(error);
+    }
+}
@@ -0,0 +1,215 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::ReadableStreamDefaultReaderBinding;
+use dom::bindings::codegen::Bindings::ReadableStreamDefaultReaderBinding::ReadableStreamDefaultReaderMethods;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{DomRoot, MutNullableDom};
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::readablestream::{ReadableStream, ReadableStreamState};
+use dom_struct::dom_struct;
+use js::jsapi::JSContext;
+use js::jsval::UndefinedValue;
+use js::rust::HandleValue;
+use std::collections::VecDeque;
+use std::mem;
+use std::rc::Rc;
+use streams::{fulfill_read_request, promise_rejected_with, promise_rejected_with_error};
+
+/// <https://streams.spec.whatwg.org/#default-reader-class>
+#[dom_struct]
+pub struct ReadableStreamDefaultReader {
+    reflector_: Reflector,
+    stream: MutNullableDom<ReadableStream>,
+    #[ignore_malloc_size_of = "Rc"]
+    closed_promise: DomRefCell<Rc<Promise>>,
+    #[ignore_malloc_size_of = "Rc"]
+    read_requests: DomRefCell<VecDeque<Rc<Promise>>>,
+}
+
+impl ReadableStreamDefaultReader {
+    #[allow(unrooted_must_root)]
+    fn new_inherited(closed_promise: Rc<Promise>) -> ReadableStreamDefaultReader {
+        ReadableStreamDefaultReader {
+            reflector_: Reflector::new(),
+            stream: Default::default(),
+            closed_promise: DomRefCell::new(closed_promise),
+            read_requests: DomRefCell::new(VecDeque::new()),
+        }
+    }
+
+    fn new(global: &GlobalScope) -> DomRoot<ReadableStreamDefaultReader> {
+        reflect_dom_object(Box::new(ReadableStreamDefaultReader::new_inherited(Promise::new(global))),
+                           global,
+                           ReadableStreamDefaultReaderBinding::Wrap)
+    }
+
+    // https://streams.spec.whatwg.org/#default-reader-constructor
+    pub fn Constructor(global: &GlobalScope,
+                       stream: &ReadableStream)
+                       -> Fallible<DomRoot<ReadableStreamDefaultReader>> {
+        ReadableStreamDefaultReader::set_up(global, stream)
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-readable-stream-default-reader>
+    pub fn set_up(global: &GlobalScope, stream: &ReadableStream) -> Fallible<DomRoot<ReadableStreamDefaultReader>> {
+        if stream.is_locked() {
+            return Err(Error::Type("The stream is locked".to_owned()));
+        }
+        let reader = ReadableStreamDefaultReader::new(global);
+        reader.stream.set(Some(stream));
+        stream.set_default_reader(Some(&reader));
+        initialize_closed_promise(stream, &reader.closed_promise.borrow());
+        Ok(reader)
+    }
+
+    /// The promise resolved once the stream is closed, or rejected if it is
+    /// errored or the reader is released.
+    #[allow(unrooted_must_root)]
+    pub fn closed_promise(&self) -> Rc<Promise> {
+        self.closed_promise.borrow().clone()
+    }
+
+    pub fn num_read_requests(&self) -> usize {
+        self.read_requests.borrow().len()
+    }
+
+    pub fn add_read_request(&self, promise: Rc<Promise>) {
+        self.read_requests.borrow_mut().push_back(promise);
+    }
+
+    pub fn fulfill_read_request(&self, chunk: HandleValue, done: bool) {
+        let promise = self.read_requests.borrow_mut().pop_front();
+        if let Some(promise) = promise {
+            fulfill_read_request(&promise, chunk, done);
+        }
+    }
+
+    /// Runs the close steps of every read request, and resolves the closed
+    /// promise.
+    pub fn close(&self) {
+        let cx = self.global().get_cx();
+        rooted!(in(cx) let undefined = UndefinedValue());
+        let read_requests = mem::replace(&mut *self.read_requests.borrow_mut(), VecDeque::new());
+        for promise in read_requests {
+            fulfill_read_request(&promise, undefined.handle(), true);
+        }
+        self.closed_promise.borrow().resolve_native(&());
+    }
+
+    /// Rejects every read request and the closed promise with `error`.
+    pub fn error(&self, error: HandleValue) {
+        let read_requests = mem::replace(&mut *self.read_requests.borrow_mut(), VecDeque::new());
+        for promise in read_requests {
+            promise.reject_native(&error);
+        }
+        self.closed_promise.borrow().reject_native(&error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-reader-read>
+    #[allow(unrooted_must_root)]
+    pub fn read(&self) -> Rc<Promise> {
+        let stream = self.stream.get().expect("The reader is not released");
+        let global = self.global();
+        stream.set_disturbed();
+        match stream.state() {
+            ReadableStreamState::Closed => {
+                let promise = Promise::new(&global);
+                rooted!(in(global.get_cx()) let undefined = UndefinedValue());
+                fulfill_read_request(&promise, undefined.handle(), true);
+                promise
+            },
+            ReadableStreamState::Errored => {
+                rooted!(in(global.get_cx()) let mut error = UndefinedValue());
+                stream.get_stored_error(error.handle_mut());
+                promise_rejected_with(&global, error.handle())
+            },
+            ReadableStreamState::Readable => {
+                let promise = Promise::new(&global);
+                stream.pull(promise.clone());
+                promise
+            },
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#abstract-opdef-readablestreamdefaultreaderrelease>
+    pub fn release(&self) {
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => return,
+        };
+        let error = Error::Type("The reader was released".to_owned());
+        release_closed_promise(&stream, &self.closed_promise, error.clone());
+        stream.release_controller();
+        stream.set_default_reader(None);
+        self.stream.set(None);
+        let read_requests = mem::replace(&mut *self.read_requests.borrow_mut(), VecDeque::new());
+        for promise in read_requests {
+            promise.reject_error(error.clone());
+        }
+    }
+}
+
+/// Settles the closed promise of a new reader according to the state of
+/// its stream.
+///
+/// <https://streams.spec.whatwg.org/#readable-stream-reader-generic-initialize>
+pub fn initialize_closed_promise(stream: &ReadableStream, closed_promise: &Promise) {
+    match stream.state() {
+        ReadableStreamState::Readable => {},
+        ReadableStreamState::Closed => closed_promise.resolve_native(&()),
+        ReadableStreamState::Errored => {
+            rooted!(in(stream.global().get_cx()) let mut error = UndefinedValue());
+            stream.get_stored_error(error.handle_mut());
+            closed_promise.reject_native(&error.handle());
+        },
+    }
+}
+
+/// Rejects the closed promise of a reader that is released, replacing it if
+/// it is already settled.
+///
+/// <https://streams.spec.whatwg.org/#readable-stream-reader-generic-release>
+#[allow(unrooted_must_root)]
+pub fn release_closed_promise(stream: &ReadableStream, closed_promise: &DomRefCell<Rc<Promise>>, error: Error) {
+    if stream.is_readable() {
+        closed_promise.borrow().reject_error(error);
+    } else {
+        *closed_promise.borrow_mut() = promise_rejected_with_error(&stream.global(), error);
+    }
+}
+
+impl ReadableStreamDefaultReaderMethods for ReadableStreamDefaultReader {
+    #[allow(unrooted_must_root)]
+    // https://streams.spec.whatwg.org/#default-reader-read
+    fn Read(&self) -> Rc<Promise> {
+        if self.stream.get().is_none() {
+            return promise_rejected_with_error(&self.global(), Error::Type("The reader was released".to_owned()));
+        }
+        self.read()
+    }
+
+    // https://streams.spec.whatwg.org/#default-reader-release-lock
+    fn ReleaseLock(&self) {
+        self.release();
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://streams.spec.whatwg.org/#generic-reader-closed
+    fn Closed(&self) -> Rc<Promise> {
+        self.closed_promise()
+    }
+
+    #[allow(unrooted_must_root, unsafe_code)]
+    // https://streams.spec.whatwg.org/#generic-reader-cancel
+    unsafe fn Cancel(&self, cx: *mut JSContext, reason: HandleValue) -> Rc<Promise> {
+        match self.stream.get() {
+            Some(stream) => stream.cancel(cx, reason),
+            None => promise_rejected_with_error(&self.global(), Error::Type("The reader was released".to_owned())),
+        }
+    }
+}
@@ -2,7 +2,8 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use body::{BodyOperations, BodyType, consume_body};
+use body::{BodyOperations, BodyType, ExtractedBody, consume_body, extract_body};
+use dom::abortsignal::AbortSignal;
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::HeadersBinding::{HeadersInit, HeadersMethods};
 use dom::bindings::codegen::Bindings::RequestBinding;
@@ -23,10 +24,11 @@ use dom::bindings::trace::RootedTraceableBox;
 use dom::globalscope::GlobalScope;
 use dom::headers::{Guard, Headers};
 use dom::promise::Promise;
-use dom::xmlhttprequest::Extractable;
+use dom::readablestream::ReadableStream;
 use dom_struct::dom_struct;
 use hyper::method::Method as HttpMethod;
 use net_traits::ReferrerPolicy as MsgReferrerPolicy;
+use net_traits::cache_storage_thread::CachedRequest;
 use net_traits::request::{Origin, Window};
 use net_traits::request::CacheMode as NetTraitsRequestCache;
 use net_traits::request::CredentialsMode as NetTraitsRequestCredentials;
@@ -46,8 +48,11 @@ pub struct Request {
     body_used: Cell<bool>,
     headers: MutNullableDom<Headers>,
     mime_type: DomRefCell<Vec<u8>>,
-    #[ignore_malloc_size_of = "Rc"]
-    body_promise: DomRefCell<Option<(Rc<Promise>, BodyType)>>,
+    /// The stream of a body given as a stream.
+    stream: MutNullableDom<ReadableStream>,
+    /// The signal of this request, which is only created once it is needed
+    /// since it can only be aborted through a signal it follows.
+    signal: MutNullableDom<AbortSignal>,
 }
 
 impl Request {
@@ -60,7 +65,8 @@ impl Request {
             body_used: Cell::new(false),
             headers: Default::default(),
             mime_type: DomRefCell::new("".to_string().into_bytes()),
-            body_promise: DomRefCell::new(None),
+            stream: Default::default(),
+            signal: Default::default(),
         }
     }
 
@@ -167,6 +173,7 @@ impl Request {
             init.redirect.is_some() ||
             init.referrer.is_some() ||
             init.referrerPolicy.is_some() ||
+            init.signal.is_some() ||
             !init.window.handle().is_undefined() {
                 // Step 13.1
                 if request.mode == NetTraitsRequestMode::Navigate {
@@ -283,6 +290,19 @@ impl Request {
         let r = Request::from_net_request(global, request);
         r.headers.or_init(|| Headers::for_request(&r.global()));
 
+        // The signal of the request follows the one given in `init`, or
+        // else the one of the input request.
+        let signal = match init.signal {
+            Some(ref signal) => signal.clone(),
+            None => match input {
+                RequestInfo::Request(ref input_request) => input_request.signal.get(),
+                RequestInfo::USVString(_) => None,
+            },
+        };
+        if let Some(signal) = signal {
+            r.Signal().follow(&signal);
+        }
+
         // Step 27
         let mut headers_copy = r.Headers();
 
@@ -343,16 +363,15 @@ impl Request {
         r.request.borrow_mut().headers = r.Headers().get_headers_list();
 
         // Step 32
-        let mut input_body = if let RequestInfo::Request(ref input_request) = input {
-            let input_request_request = input_request.request.borrow();
-            input_request_request.body.clone()
+        let (mut input_body, mut input_stream) = if let RequestInfo::Request(ref input_request) = input {
+            (input_request.request.borrow().body.clone(), input_request.stream.get())
         } else {
-            None
+            (None, None)
         };
 
         // Step 33
         if let Some(init_body_option) = init.body.as_ref() {
-            if init_body_option.is_some() || input_body.is_some() {
+            if init_body_option.is_some() || input_body.is_some() || input_stream.is_some() {
                 let req = r.request.borrow();
                 let req_method = &req.method;
                 match *req_method {
@@ -368,9 +387,17 @@ impl Request {
         // Step 34
         if let Some(Some(ref init_body)) = init.body {
             // Step 34.2
-            let extracted_body_tmp = init_body.extract();
-            input_body = Some(extracted_body_tmp.0);
-            let content_type = extracted_body_tmp.1;
+            let (extracted_body, content_type) = extract_body(init_body)?;
+            match extracted_body {
+                ExtractedBody::Bytes(bytes) => {
+                    input_body = Some(bytes);
+                    input_stream = None;
+                },
+                ExtractedBody::Stream(stream) => {
+                    input_body = None;
+                    input_stream = Some(stream);
+                },
+            }
 
             // Step 34.3
             if let Some(contents) = content_type {
@@ -389,7 +416,13 @@ impl Request {
         *r.mime_type.borrow_mut() = extracted_mime_type;
 
         // Step 37
-        // TODO: `ReadableStream` object is not implemented in Servo yet.
+        if let RequestInfo::Request(ref input_request) = input {
+            if input_stream.is_some() && input_request.stream.get() == input_stream {
+                // The stream now belongs to the new request.
+                input_request.body_used.set(true);
+            }
+        }
+        r.stream.set(input_stream.as_ref().map(|stream| &**stream));
 
         // Step 38
         Ok(r)
@@ -397,9 +430,7 @@ impl Request {
 
     // https://fetch.spec.whatwg.org/#concept-body-locked
     fn locked(&self) -> bool {
-        // TODO: ReadableStream is unimplemented. Just return false
-        // for now.
-        false
+        self.stream.get().map_or(false, |stream| stream.is_locked())
     }
 }
 
@@ -427,14 +458,47 @@ impl Request {
         *r_clone.request.borrow_mut() = req.clone();
         r_clone.body_used.set(body_used);
         *r_clone.mime_type.borrow_mut() = mime_type;
+        if let Some(stream) = r.stream.get() {
+            let (branch_1, branch_2) = stream.tee(true)?;
+            r.stream.set(Some(&branch_1));
+            r_clone.stream.set(Some(&branch_2));
+        }
         r_clone.Headers().fill(Some(HeadersInit::Headers(r.Headers())))?;
         r_clone.Headers().set_guard(headers_guard);
+        if let Some(signal) = r.signal.get() {
+            r_clone.Signal().follow(&signal);
+        }
         Ok(r_clone)
     }
 
     pub fn get_request(&self) -> NetTraitsRequest {
         self.request.borrow().clone()
     }
+
+    /// The signal of this request, if it was created.
+    pub fn abort_signal(&self) -> Option<DomRoot<AbortSignal>> {
+        self.signal.get()
+    }
+
+    /// The state of this request to store in a cache.
+    pub fn to_cached_request(&self) -> CachedRequest {
+        let request = self.request.borrow();
+        CachedRequest {
+            url: request.url(),
+            method: request.method.as_ref().to_owned(),
+            headers: self.Headers().cache_headers(),
+        }
+    }
+
+    /// Create a request from one stored in a cache.
+    pub fn from_cached_request(global: &GlobalScope, cached: CachedRequest) -> DomRoot<Request> {
+        let request = Request::new(global, cached.url);
+        request.request.borrow_mut().method = normalize_method(&cached.method);
+        request.Headers().set_cache_headers(&cached.headers);
+        request.Headers().set_guard(Guard::Immutable);
+        request.request.borrow_mut().headers = request.Headers().get_headers_list();
+        request
+    }
 }
 
 fn net_request_from_global(global: &GlobalScope,
@@ -487,16 +551,14 @@ fn includes_credentials(input: &ServoUrl) -> bool {
     !input.username().is_empty() || input.password().is_some()
 }
 
-// TODO: `Readable Stream` object is not implemented in Servo yet.
 // https://fetch.spec.whatwg.org/#concept-body-disturbed
-fn request_is_disturbed(_input: &Request) -> bool {
-    false
+fn request_is_disturbed(input: &Request) -> bool {
+    input.BodyUsed()
 }
 
-// TODO: `Readable Stream` object is not implemented in Servo yet.
 // https://fetch.spec.whatwg.org/#concept-body-locked
-fn request_is_locked(_input: &Request) -> bool {
-    false
+fn request_is_locked(input: &Request) -> bool {
+    input.locked()
 }
 
 impl RequestMethods for Request {
@@ -569,9 +631,14 @@ impl RequestMethods for Request {
         DOMString::from_string(r.integrity_metadata.clone())
     }
 
+    // https://fetch.spec.whatwg.org/#dom-request-signal
+    fn Signal(&self) -> DomRoot<AbortSignal> {
+        self.signal.or_init(|| AbortSignal::new(&self.global()))
+    }
+
     // https://fetch.spec.whatwg.org/#dom-body-bodyused
     fn BodyUsed(&self) -> bool {
-        self.body_used.get()
+        self.body_used.get() || self.stream.get().map_or(false, |stream| stream.is_disturbed())
     }
 
     // https://fetch.spec.whatwg.org/#dom-request-clone
@@ -624,10 +691,9 @@ impl BodyOperations for Request {
         self.BodyUsed()
     }
 
-    fn set_body_promise(&self, p: &Rc<Promise>, body_type: BodyType) {
-        assert!(self.body_promise.borrow().is_none());
+    fn set_body_promise(&self, _p: &Rc<Promise>, _body_type: BodyType) {
+        // The body of a request is always complete.
         self.body_used.set(true);
-        *self.body_promise.borrow_mut() = Some((p.clone(), body_type));
     }
 
     fn is_locked(&self) -> bool {
@@ -640,6 +706,10 @@ impl BodyOperations for Request {
         Some(body.unwrap_or(vec![]))
     }
 
+    fn body_stream(&self) -> Option<DomRoot<ReadableStream>> {
+        self.stream.get()
+    }
+
     fn get_mime_type(&self) -> Ref<Vec<u8>> {
         self.mime_type.borrow()
     }
@@ -0,0 +1,181 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::callback::ExceptionHandling;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::ResizeObserverBinding;
+use dom::bindings::codegen::Bindings::ResizeObserverBinding::{ResizeObserverBoxOptions, ResizeObserverCallback};
+use dom::bindings::codegen::Bindings::ResizeObserverBinding::{ResizeObserverMethods, ResizeObserverOptions};
+use dom::bindings::error::Fallible;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::domrectreadonly::DOMRectReadOnly;
+use dom::element::Element;
+use dom::node::Node;
+use dom::resizeobserverentry::ResizeObserverEntry;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use script_layout_interface::rpc::ObservedBoxes;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::usize;
+
+/// <https://drafts.csswg.org/resize-observer/#resize-observation-interface>
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+struct ResizeObservation {
+    target: Dom<Element>,
+    observed_box: ResizeObserverBoxOptions,
+    /// The last reported width and height of the observed box, in CSS pixels.
+    last_reported_size: Cell<(f64, f64)>,
+    /// Whether the size of the observed box changed since it was last reported.
+    active: Cell<bool>,
+}
+
+impl ResizeObservation {
+    fn size(&self, boxes: Option<ObservedBoxes>) -> (f64, f64) {
+        let rect = match (boxes, self.observed_box) {
+            (Some(boxes), ResizeObserverBoxOptions::Content_box) => boxes.content_box,
+            (Some(boxes), ResizeObserverBoxOptions::Border_box) => boxes.border_box,
+            (None, _) => return (0., 0.),
+        };
+        (rect.size.width.to_f64_px(), rect.size.height.to_f64_px())
+    }
+}
+
+// https://drafts.csswg.org/resize-observer/#resize-observer-interface
+#[dom_struct]
+pub struct ResizeObserver {
+    reflector_: Reflector,
+    #[ignore_malloc_size_of = "can't measure Rc values"]
+    callback: Rc<ResizeObserverCallback>,
+    observation_targets: DomRefCell<Vec<ResizeObservation>>,
+    /// Whether any changed observation was skipped because it was not deeper in
+    /// the tree than the targets notified before it.
+    skipped_targets: Cell<bool>,
+}
+
+impl ResizeObserver {
+    fn new_inherited(callback: Rc<ResizeObserverCallback>) -> ResizeObserver {
+        ResizeObserver {
+            reflector_: Reflector::new(),
+            callback: callback,
+            observation_targets: DomRefCell::new(vec![]),
+            skipped_targets: Cell::new(false),
+        }
+    }
+
+    pub fn Constructor(window: &Window, callback: Rc<ResizeObserverCallback>) -> Fallible<DomRoot<ResizeObserver>> {
+        let observer = Box::new(ResizeObserver::new_inherited(callback));
+        Ok(reflect_dom_object(observer, window, ResizeObserverBinding::Wrap))
+    }
+
+    /// Returns the elements this observer is observing.
+    pub fn observed_elements(&self) -> Vec<DomRoot<Element>> {
+        self.observation_targets.borrow().iter()
+            .map(|observation| DomRoot::from_ref(&*observation.target))
+            .collect()
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#gather-active-observations-h>
+    pub fn gather_active_observations_at_depth<F>(&self, depth: usize, boxes: &F)
+        where F: Fn(&Element) -> Option<ObservedBoxes>
+    {
+        self.skipped_targets.set(false);
+        for observation in self.observation_targets.borrow().iter() {
+            let size = observation.size(boxes(&*observation.target));
+            let is_active = size != observation.last_reported_size.get();
+            let deep_enough = depth_of(&observation.target) > depth;
+            observation.active.set(is_active && deep_enough);
+            if is_active && !deep_enough {
+                self.skipped_targets.set(true);
+            }
+        }
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#has-active-observations-h>
+    pub fn has_active_observations(&self) -> bool {
+        self.observation_targets.borrow().iter().any(|observation| observation.active.get())
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#has-skipped-observations-h>
+    pub fn has_skipped_observations(&self) -> bool {
+        self.skipped_targets.get()
+    }
+
+    /// Runs the steps of <https://drafts.csswg.org/resize-observer/#broadcast-resize-notifications-h>
+    /// for this observer, and returns the depth of its shallowest notified target.
+    pub fn broadcast_active_observations<F>(&self, boxes: &F) -> usize
+        where F: Fn(&Element) -> Option<ObservedBoxes>
+    {
+        let window = self.global();
+        let window = window.as_window();
+        let mut shallowest_target_depth = usize::MAX;
+        let mut entries = vec![];
+        for observation in self.observation_targets.borrow().iter().filter(|observation| observation.active.get()) {
+            let target_boxes = boxes(&*observation.target);
+            let content_rect = match target_boxes {
+                Some(boxes) => DOMRectReadOnly::new(
+                    window.upcast(),
+                    (boxes.content_box.origin.x - boxes.padding_box.origin.x).to_f64_px(),
+                    (boxes.content_box.origin.y - boxes.padding_box.origin.y).to_f64_px(),
+                    boxes.content_box.size.width.to_f64_px(),
+                    boxes.content_box.size.height.to_f64_px(),
+                ),
+                None => DOMRectReadOnly::new(window.upcast(), 0., 0., 0., 0.),
+            };
+            entries.push(ResizeObserverEntry::new(window, &observation.target, &content_rect));
+            observation.last_reported_size.set(observation.size(target_boxes));
+            observation.active.set(false);
+            shallowest_target_depth = shallowest_target_depth.min(depth_of(&observation.target));
+        }
+        if !entries.is_empty() {
+            let _ = self.callback.Call_(self, entries, self, ExceptionHandling::Report);
+        }
+        shallowest_target_depth
+    }
+}
+
+impl ResizeObserverMethods for ResizeObserver {
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserver-observe
+    fn Observe(&self, target: &Element, options: &ResizeObserverOptions) {
+        // Step 1.
+        self.Unobserve(target);
+        // Steps 2-3.
+        self.observation_targets.borrow_mut().push(ResizeObservation {
+            target: Dom::from_ref(target),
+            observed_box: options.box_,
+            last_reported_size: Cell::new((0., 0.)),
+            active: Cell::new(false),
+        });
+        let document = target.upcast::<Node>().owner_doc();
+        document.add_resize_observer(self);
+        // The initial size is reported even if layout doesn't change.
+        document.queue_layout_observers_update();
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserver-unobserve
+    fn Unobserve(&self, target: &Element) {
+        self.observation_targets.borrow_mut().retain(|observation| &*observation.target != target);
+        if self.observation_targets.borrow().is_empty() {
+            target.upcast::<Node>().owner_doc().remove_resize_observer(self);
+        }
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserver-disconnect
+    fn Disconnect(&self) {
+        let targets: Vec<_> = self.observation_targets.borrow_mut().drain(..)
+            .map(|observation| DomRoot::from_ref(&*observation.target))
+            .collect();
+        for target in targets {
+            target.upcast::<Node>().owner_doc().remove_resize_observer(self);
+        }
+    }
+}
+
+/// <https://drafts.csswg.org/resize-observer/#calculate-depth-for-node-h>
+fn depth_of(element: &Element) -> usize {
+    element.upcast::<Node>().ancestors().count()
+}
@@ -0,0 +1,48 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::ResizeObserverEntryBinding;
+use dom::bindings::codegen::Bindings::ResizeObserverEntryBinding::ResizeObserverEntryMethods;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::domrectreadonly::DOMRectReadOnly;
+use dom::element::Element;
+use dom::window::Window;
+use dom_struct::dom_struct;
+
+// https://drafts.csswg.org/resize-observer/#resize-observer-entry-interface
+#[dom_struct]
+pub struct ResizeObserverEntry {
+    reflector_: Reflector,
+    target: Dom<Element>,
+    content_rect: Dom<DOMRectReadOnly>,
+}
+
+impl ResizeObserverEntry {
+    fn new_inherited(target: &Element, content_rect: &DOMRectReadOnly) -> ResizeObserverEntry {
+        ResizeObserverEntry {
+            reflector_: Reflector::new(),
+            target: Dom::from_ref(target),
+            content_rect: Dom::from_ref(content_rect),
+        }
+    }
+
+    pub fn new(window: &Window, target: &Element, content_rect: &DOMRectReadOnly) -> DomRoot<ResizeObserverEntry> {
+        reflect_dom_object(Box::new(ResizeObserverEntry::new_inherited(target, content_rect)),
+                           window,
+                           ResizeObserverEntryBinding::Wrap)
+    }
+}
+
+impl ResizeObserverEntryMethods for ResizeObserverEntry {
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserverentry-target
+    fn Target(&self) -> DomRoot<Element> {
+        DomRoot::from_ref(&*self.target)
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserverentry-contentrect
+    fn ContentRect(&self) -> DomRoot<DOMRectReadOnly> {
+        DomRoot::from_ref(&*self.content_rect)
+    }
+}
@@ -2,12 +2,12 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use body::{BodyOperations, BodyType, consume_body, consume_body_with_promise};
+use body::{BodyOperations, BodyType, ExtractedBody, consume_body, consume_body_with_promise, extract_body};
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::HeadersBinding::{HeadersInit, HeadersMethods};
 use dom::bindings::codegen::Bindings::ResponseBinding;
-use dom::bindings::codegen::Bindings::ResponseBinding::{ResponseMethods, ResponseType as DOMResponseType};
-use dom::bindings::codegen::Bindings::XMLHttpRequestBinding::BodyInit;
+use dom::bindings::codegen::Bindings::ResponseBinding::{ResponseBodyInit, ResponseMethods};
+use dom::bindings::codegen::Bindings::ResponseBinding::ResponseType as DOMResponseType;
 use dom::bindings::error::{Error, Fallible};
 use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
 use dom::bindings::root::{DomRoot, MutNullableDom};
@@ -16,12 +16,16 @@ use dom::globalscope::GlobalScope;
 use dom::headers::{Headers, Guard};
 use dom::headers::{is_vchar, is_obs_text};
 use dom::promise::Promise;
-use dom::xmlhttprequest::Extractable;
+use dom::readablestream::ReadableStream;
 use dom_struct::dom_struct;
 use hyper::header::Headers as HyperHeaders;
 use hyper::status::StatusCode;
 use hyper_serde::Serde;
-use net_traits::response::{ResponseBody as NetTraitsResponseBody};
+use js::rust::HandleValue;
+use net_traits::NetworkError;
+use net_traits::cache_storage_thread::CachedResponse;
+use net_traits::response::{ResponseBody as NetTraitsResponseBody, ResponseType as NetTraitsResponseType};
+use servo_config::prefs::PREFS;
 use servo_url::ServoUrl;
 use std::cell::{Cell, Ref};
 use std::mem;
@@ -42,10 +46,14 @@ pub struct Response {
     response_type: DomRefCell<DOMResponseType>,
     url: DomRefCell<Option<ServoUrl>>,
     url_list: DomRefCell<Vec<ServoUrl>>,
-    // For now use the existing NetTraitsResponseBody enum
+    /// The bytes of a body which is not a stream.
     body: DomRefCell<NetTraitsResponseBody>,
+    /// A body consumed while it is still being received without streams.
     #[ignore_malloc_size_of = "Rc"]
     body_promise: DomRefCell<Option<(Rc<Promise>, BodyType)>>,
+    /// The stream of a body which is being fetched, was given as a stream,
+    /// or was accessed through the `body` attribute.
+    stream: MutNullableDom<ReadableStream>,
 }
 
 impl Response {
@@ -62,6 +70,7 @@ impl Response {
             url_list: DomRefCell::new(vec![]),
             body: DomRefCell::new(NetTraitsResponseBody::Empty),
             body_promise: DomRefCell::new(None),
+            stream: Default::default(),
         }
     }
 
@@ -70,7 +79,7 @@ impl Response {
         reflect_dom_object(Box::new(Response::new_inherited()), global, ResponseBinding::Wrap)
     }
 
-    pub fn Constructor(global: &GlobalScope, body: Option<BodyInit>, init: &ResponseBinding::ResponseInit)
+    pub fn Constructor(global: &GlobalScope, body: Option<ResponseBodyInit>, init: &ResponseBinding::ResponseInit)
                        -> Fallible<DomRoot<Response>> {
         // Step 1
         if init.status < 200 || init.status > 599 {
@@ -112,8 +121,11 @@ impl Response {
             };
 
             // Step 7.3
-            let (extracted_body, content_type) = body.extract();
-            *r.body.borrow_mut() = NetTraitsResponseBody::Done(extracted_body);
+            let (extracted_body, content_type) = extract_body(body)?;
+            match extracted_body {
+                ExtractedBody::Bytes(bytes) => *r.body.borrow_mut() = NetTraitsResponseBody::Done(bytes),
+                ExtractedBody::Stream(stream) => r.stream.set(Some(&stream)),
+            }
 
             // Step 7.4
             if let Some(content_type_contents) = content_type {
@@ -185,9 +197,7 @@ impl Response {
 
     // https://fetch.spec.whatwg.org/#concept-body-locked
     fn locked(&self) -> bool {
-        // TODO: ReadableStream is unimplemented. Just return false
-        // for now.
-        false
+        self.stream.get().map_or(false, |stream| stream.is_locked())
     }
 }
 
@@ -212,6 +222,7 @@ impl BodyOperations for Response {
             NetTraitsResponseBody::Done(bytes) => {
                 Some(bytes)
             },
+            NetTraitsResponseBody::Empty => Some(vec![]),
             body => {
                 mem::replace(&mut *self.body.borrow_mut(), body);
                 None
@@ -219,6 +230,10 @@ impl BodyOperations for Response {
         }
     }
 
+    fn body_stream(&self) -> Option<DomRoot<ReadableStream>> {
+        self.stream.get()
+    }
+
     fn get_mime_type(&self) -> Ref<Vec<u8>> {
         self.mime_type.borrow()
     }
@@ -297,7 +312,7 @@ impl ResponseMethods for Response {
     // https://fetch.spec.whatwg.org/#dom-response-clone
     fn Clone(&self) -> Fallible<DomRoot<Response>> {
         // Step 1
-        if self.is_locked() || self.body_used.get() {
+        if self.is_locked() || self.BodyUsed() {
             return Err(Error::Type("cannot clone a disturbed response".to_string()));
         }
 
@@ -318,6 +333,11 @@ impl ResponseMethods for Response {
         if *self.body.borrow() != NetTraitsResponseBody::Empty {
             *new_response.body.borrow_mut() = self.body.borrow().clone();
         }
+        if let Some(stream) = self.stream.get() {
+            let (branch_1, branch_2) = stream.tee(true)?;
+            self.stream.set(Some(&branch_1));
+            new_response.stream.set(Some(&branch_2));
+        }
 
         // Step 3
         // TODO: This step relies on promises, which are still unimplemented.
@@ -326,9 +346,28 @@ impl ResponseMethods for Response {
         Ok(new_response)
     }
 
+    // https://fetch.spec.whatwg.org/#dom-body-body
+    fn GetBody(&self) -> Option<DomRoot<ReadableStream>> {
+        if let Some(stream) = self.stream.get() {
+            return Some(stream);
+        }
+        let bytes = match mem::replace(&mut *self.body.borrow_mut(), NetTraitsResponseBody::Empty) {
+            NetTraitsResponseBody::Done(bytes) => bytes,
+            NetTraitsResponseBody::Empty => return None,
+            NetTraitsResponseBody::Receiving(_) => unreachable!("Fetched bodies are streams when streams are enabled"),
+        };
+        let stream = ReadableStream::new_with_native_source(&self.global());
+        if !bytes.is_empty() {
+            stream.enqueue_native(bytes);
+        }
+        stream.close_native();
+        self.stream.set(Some(&stream));
+        Some(stream)
+    }
+
     // https://fetch.spec.whatwg.org/#dom-body-bodyused
     fn BodyUsed(&self) -> bool {
-        self.body_used.get()
+        self.body_used.get() || self.stream.get().map_or(false, |stream| stream.is_disturbed())
     }
 
     #[allow(unrooted_must_root)]
@@ -386,11 +425,115 @@ impl Response {
         *self.url.borrow_mut() = Some(final_url);
     }
 
+    /// Mark the body as being received, until `finish` is called. With
+    /// streams enabled, the chunks given to `receive_chunk` feed the stream
+    /// of the body, otherwise they are buffered.
+    pub fn start_receiving_body(&self) {
+        if PREFS.get("dom.streams.enabled").as_boolean().unwrap_or(false) {
+            self.stream.set(Some(&ReadableStream::new_with_native_source(&self.global())));
+        } else {
+            *self.body.borrow_mut() = NetTraitsResponseBody::Receiving(vec![]);
+        }
+    }
+
+    /// Enqueues or buffers a chunk of the body being received.
+    pub fn receive_chunk(&self, mut chunk: Vec<u8>) {
+        if let Some(stream) = self.stream.get() {
+            stream.enqueue_native(chunk);
+        } else if let NetTraitsResponseBody::Receiving(ref mut body) = *self.body.borrow_mut() {
+            body.append(&mut chunk);
+        }
+    }
+
+    /// The state of this response to store in a cache, with the given body.
+    pub fn to_cached_response(&self, body: Vec<u8>) -> CachedResponse {
+        let response_type = match *self.response_type.borrow() {
+            DOMResponseType::Basic => NetTraitsResponseType::Basic,
+            DOMResponseType::Cors => NetTraitsResponseType::Cors,
+            DOMResponseType::Default => NetTraitsResponseType::Default,
+            DOMResponseType::Error => NetTraitsResponseType::Error(NetworkError::Internal("Cached error".to_owned())),
+            DOMResponseType::Opaque => NetTraitsResponseType::Opaque,
+            DOMResponseType::Opaqueredirect => NetTraitsResponseType::OpaqueRedirect,
+        };
+        let url_list = match *self.url_list.borrow() {
+            ref url_list if !url_list.is_empty() => url_list.clone(),
+            _ => self.url.borrow().iter().cloned().collect(),
+        };
+        let (status, status_text) = self.raw_status.borrow().clone().unwrap_or((0, vec![]));
+        CachedResponse {
+            response_type: response_type,
+            url_list: url_list,
+            status: status,
+            status_text: status_text,
+            headers: self.Headers().cache_headers(),
+            body: body,
+        }
+    }
+
+    /// Create a response from one stored in a cache.
+    pub fn from_cached_response(global: &GlobalScope, cached: CachedResponse) -> DomRoot<Response> {
+        let response = Response::new(global);
+        *response.response_type.borrow_mut() = match cached.response_type {
+            NetTraitsResponseType::Basic => DOMResponseType::Basic,
+            NetTraitsResponseType::Cors => DOMResponseType::Cors,
+            NetTraitsResponseType::Default => DOMResponseType::Default,
+            NetTraitsResponseType::Error(_) => DOMResponseType::Error,
+            NetTraitsResponseType::Opaque => DOMResponseType::Opaque,
+            NetTraitsResponseType::OpaqueRedirect => DOMResponseType::Opaqueredirect,
+        };
+        *response.status.borrow_mut() = Some(StatusCode::from_u16(cached.status));
+        *response.raw_status.borrow_mut() = Some((cached.status, cached.status_text));
+        *response.url.borrow_mut() = cached.url_list.last().cloned();
+        *response.url_list.borrow_mut() = cached.url_list;
+        response.Headers().set_cache_headers(&cached.headers);
+        response.Headers().set_guard(Guard::Immutable);
+        *response.mime_type.borrow_mut() = response.Headers().extract_mime_type();
+        *response.body.borrow_mut() = NetTraitsResponseBody::Done(cached.body);
+        response
+    }
+
+    /// Errors the stream of the body being received with `reason`, once the
+    /// fetch was aborted.
+    pub fn abort_body(&self, reason: HandleValue) {
+        if let Some(stream) = self.stream.get() {
+            if stream.is_readable() {
+                stream.error_controller(reason);
+            }
+            return;
+        }
+        let body_promise = self.body_promise.borrow_mut().take();
+        if let Some((promise, _)) = body_promise {
+            promise.reject_native(&reason);
+        }
+    }
+
+    /// Closes the stream of the body being received, or errors it if the
+    /// fetch failed. Without a stream, the buffered body is done and given
+    /// to the pending body promise, if any.
     #[allow(unrooted_must_root)]
-    pub fn finish(&self, body: Vec<u8>) {
+    pub fn finish(&self, result: Result<(), NetworkError>) {
+        if let Some(stream) = self.stream.get() {
+            match result {
+                Ok(()) => stream.close_native(),
+                Err(_) => stream.error_native(Error::Type("Network error occurred".to_owned())),
+            }
+            return;
+        }
+        let body = mem::replace(&mut *self.body.borrow_mut(), NetTraitsResponseBody::Empty);
+        let body = match body {
+            NetTraitsResponseBody::Receiving(body) => body,
+            body => {
+                *self.body.borrow_mut() = body;
+                return;
+            },
+        };
         *self.body.borrow_mut() = NetTraitsResponseBody::Done(body);
-        if let Some((p, body_type)) = self.body_promise.borrow_mut().take() {
-            consume_body_with_promise(self, body_type, &p);
+        let body_promise = self.body_promise.borrow_mut().take();
+        if let Some((promise, body_type)) = body_promise {
+            match result {
+                Ok(()) => consume_body_with_promise(self, body_type, &promise),
+                Err(_) => promise.reject_error(Error::Type("Network error occurred".to_owned())),
+            }
         }
     }
 }
@@ -0,0 +1,500 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::RangeBinding::RangeMethods;
+use dom::bindings::codegen::Bindings::SelectionBinding;
+use dom::bindings::codegen::Bindings::SelectionBinding::SelectionMethods;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use dom::bindings::str::DOMString;
+use dom::characterdata::CharacterData;
+use dom::document::Document;
+use dom::eventtarget::EventTarget;
+use dom::node::Node;
+use dom::range::{Range, bp_position};
+use dom::text::Text;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::ops;
+use task_source::TaskSource;
+
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf, PartialEq)]
+enum Direction {
+    Forwards,
+    Backwards,
+    Directionless,
+}
+
+// https://w3c.github.io/selection-api/#selection-interface
+#[dom_struct]
+pub struct Selection {
+    reflector_: Reflector,
+    document: Dom<Document>,
+    range: MutNullableDom<Range>,
+    direction: Cell<Direction>,
+    /// Whether a task to fire `selectionchange` is already queued.
+    selectionchange_queued: Cell<bool>,
+    /// Whether the selection changed since layout was last told about it.
+    needs_layout_update: Cell<bool>,
+    /// The text nodes layout was last told to paint as selected.
+    painted_text_nodes: DomRefCell<Vec<Dom<Text>>>,
+}
+
+impl Selection {
+    fn new_inherited(document: &Document) -> Selection {
+        Selection {
+            reflector_: Reflector::new(),
+            document: Dom::from_ref(document),
+            range: Default::default(),
+            direction: Cell::new(Direction::Directionless),
+            selectionchange_queued: Cell::new(false),
+            needs_layout_update: Cell::new(false),
+            painted_text_nodes: DomRefCell::new(vec![]),
+        }
+    }
+
+    pub fn new(document: &Document) -> DomRoot<Selection> {
+        reflect_dom_object(Box::new(Selection::new_inherited(document)),
+                           document.window(),
+                           SelectionBinding::Wrap)
+    }
+
+    pub fn range(&self) -> Option<DomRoot<Range>> {
+        self.range.get()
+    }
+
+    fn set_range(&self, range: Option<&Range>, direction: Direction) {
+        if let Some(old_range) = self.range.get() {
+            old_range.set_associated_selection(None);
+        }
+        if let Some(range) = range {
+            range.set_associated_selection(Some(self));
+        }
+        self.range.set(range);
+        self.direction.set(direction);
+        self.queue_selectionchange();
+    }
+
+    fn anchor(&self) -> Option<(DomRoot<Node>, u32)> {
+        self.range.get().map(|range| match self.direction.get() {
+            Direction::Backwards => (range.EndContainer(), range.EndOffset()),
+            _ => (range.StartContainer(), range.StartOffset()),
+        })
+    }
+
+    fn focus(&self) -> Option<(DomRoot<Node>, u32)> {
+        self.range.get().map(|range| match self.direction.get() {
+            Direction::Backwards => (range.StartContainer(), range.StartOffset()),
+            _ => (range.EndContainer(), range.EndOffset()),
+        })
+    }
+
+    /// Whether `node`'s root is the document this selection belongs to.
+    fn is_in_document_tree(&self, node: &Node) -> bool {
+        node.inclusive_ancestors().last().map_or(false, |root| &*root == self.document.upcast::<Node>())
+    }
+
+    // https://w3c.github.io/selection-api/#selectionchange-event
+    pub fn queue_selectionchange(&self) {
+        self.needs_layout_update.set(true);
+        if self.selectionchange_queued.get() {
+            return;
+        }
+        self.selectionchange_queued.set(true);
+        let window = self.document.window();
+        let this = Trusted::new(self);
+        let _ = window.user_interaction_task_source().queue(
+            task!(fire_selectionchange: move || {
+                let this = this.root();
+                this.selectionchange_queued.set(false);
+                this.document.upcast::<EventTarget>().fire_event(atom!("selectionchange"));
+            }),
+            window.upcast(),
+        );
+    }
+
+    /// Collapses the selection, or extends it if `extend` is true, one character
+    /// forwards or backwards through the text of the document.
+    pub fn modify_by_character(&self, forward: bool, extend: bool) {
+        let range = match self.range.get() {
+            Some(range) => range,
+            None => return,
+        };
+        if !extend && !range.Collapsed() {
+            let _ = if forward { self.CollapseToEnd() } else { self.CollapseToStart() };
+            return;
+        }
+        let (node, offset) = self.focus().unwrap();
+        let (node, offset) = match adjacent_caret_position(&node, offset, forward) {
+            Some(position) => position,
+            None => return,
+        };
+        let _ = if extend { self.Extend(&node, offset) } else { self.Collapse(Some(&node), offset) };
+    }
+
+    /// Tells layout which text is covered by the selection. A collapsed selection
+    /// inside an editing host is painted as a caret. `dom_changed` forces an update
+    /// when the document was mutated, as that can move the selected text around.
+    #[allow(unrooted_must_root)]
+    pub fn update_for_layout(&self, dom_changed: bool) {
+        if !self.needs_layout_update.get() && !dom_changed {
+            return;
+        }
+        self.needs_layout_update.set(false);
+        let selected = match self.range.get() {
+            Some(range) => selected_text(&range),
+            None => vec![],
+        };
+        for text in self.painted_text_nodes.borrow().iter() {
+            if !selected.iter().any(|&(ref selected_text, _)| &**selected_text == &**text) {
+                text.set_selected_range(None);
+            }
+        }
+        for &(ref text, ref range) in &selected {
+            text.set_selected_range(Some(range.clone()));
+        }
+        *self.painted_text_nodes.borrow_mut() =
+            selected.iter().map(|&(ref text, _)| Dom::from_ref(&**text)).collect();
+    }
+}
+
+impl SelectionMethods for Selection {
+    // https://w3c.github.io/selection-api/#dom-selection-anchornode
+    fn GetAnchorNode(&self) -> Option<DomRoot<Node>> {
+        self.anchor().map(|(node, _)| node)
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-anchoroffset
+    fn AnchorOffset(&self) -> u32 {
+        self.anchor().map_or(0, |(_, offset)| offset)
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-focusnode
+    fn GetFocusNode(&self) -> Option<DomRoot<Node>> {
+        self.focus().map(|(node, _)| node)
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-focusoffset
+    fn FocusOffset(&self) -> u32 {
+        self.focus().map_or(0, |(_, offset)| offset)
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-iscollapsed
+    fn IsCollapsed(&self) -> bool {
+        self.range.get().map_or(true, |range| range.Collapsed())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-rangecount
+    fn RangeCount(&self) -> u32 {
+        if self.range.get().is_some() { 1 } else { 0 }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-type
+    fn Type(&self) -> DOMString {
+        DOMString::from(match self.range.get() {
+            None => "None",
+            Some(ref range) if range.Collapsed() => "Caret",
+            Some(_) => "Range",
+        })
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-getrangeat
+    fn GetRangeAt(&self, index: u32) -> Fallible<DomRoot<Range>> {
+        match self.range.get() {
+            Some(range) if index == 0 => Ok(range),
+            _ => Err(Error::IndexSize),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-addrange
+    fn AddRange(&self, range: &Range) {
+        // Step 1.
+        if !self.is_in_document_tree(&range.StartContainer()) {
+            return;
+        }
+        // Step 2.
+        if self.range.get().is_some() {
+            return;
+        }
+        // Step 3.
+        self.set_range(Some(range), Direction::Directionless);
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-removerange
+    fn RemoveRange(&self, range: &Range) -> ErrorResult {
+        match self.range.get() {
+            Some(ref current) if &**current == range => {
+                self.set_range(None, Direction::Directionless);
+                Ok(())
+            },
+            _ => Err(Error::NotFound),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-removeallranges
+    fn RemoveAllRanges(&self) {
+        if self.range.get().is_some() {
+            self.set_range(None, Direction::Directionless);
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-empty
+    fn Empty(&self) {
+        self.RemoveAllRanges()
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-collapse
+    fn Collapse(&self, node: Option<&Node>, offset: u32) -> ErrorResult {
+        // Step 1.
+        let node = match node {
+            Some(node) => node,
+            None => {
+                self.RemoveAllRanges();
+                return Ok(());
+            },
+        };
+        // Step 2.
+        if node.is_doctype() {
+            return Err(Error::InvalidNodeType);
+        }
+        // Step 3.
+        if offset > node.len() {
+            return Err(Error::IndexSize);
+        }
+        // Step 4.
+        if !self.is_in_document_tree(node) {
+            return Ok(());
+        }
+        // Steps 5-7.
+        let range = Range::new(&self.document, node, offset, node, offset);
+        self.set_range(Some(&range), Direction::Directionless);
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-setposition
+    fn SetPosition(&self, node: Option<&Node>, offset: u32) -> ErrorResult {
+        self.Collapse(node, offset)
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-collapsetostart
+    fn CollapseToStart(&self) -> ErrorResult {
+        let range = self.range.get().ok_or(Error::InvalidState)?;
+        let (node, offset) = (range.StartContainer(), range.StartOffset());
+        let range = Range::new(&self.document, &node, offset, &node, offset);
+        self.set_range(Some(&range), Direction::Directionless);
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-collapsetoend
+    fn CollapseToEnd(&self) -> ErrorResult {
+        let range = self.range.get().ok_or(Error::InvalidState)?;
+        let (node, offset) = (range.EndContainer(), range.EndOffset());
+        let range = Range::new(&self.document, &node, offset, &node, offset);
+        self.set_range(Some(&range), Direction::Directionless);
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-extend
+    fn Extend(&self, node: &Node, offset: u32) -> ErrorResult {
+        // Step 1.
+        if !self.is_in_document_tree(node) {
+            return Ok(());
+        }
+        // Step 2.
+        let (anchor_node, anchor_offset) = self.anchor().ok_or(Error::InvalidState)?;
+        if node.is_doctype() {
+            return Err(Error::InvalidNodeType);
+        }
+        if offset > node.len() {
+            return Err(Error::IndexSize);
+        }
+        // Steps 3-8.
+        let (range, direction) = match bp_position(node, offset, &anchor_node, anchor_offset) {
+            None => (Range::new(&self.document, node, offset, node, offset), Direction::Forwards),
+            Some(Ordering::Less) => {
+                (Range::new(&self.document, node, offset, &anchor_node, anchor_offset), Direction::Backwards)
+            },
+            Some(_) => {
+                (Range::new(&self.document, &anchor_node, anchor_offset, node, offset), Direction::Forwards)
+            },
+        };
+        self.set_range(Some(&range), direction);
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-setbaseandextent
+    fn SetBaseAndExtent(&self,
+                        anchor_node: &Node,
+                        anchor_offset: u32,
+                        focus_node: &Node,
+                        focus_offset: u32)
+                        -> ErrorResult {
+        // Step 1.
+        if anchor_offset > anchor_node.len() || focus_offset > focus_node.len() {
+            return Err(Error::IndexSize);
+        }
+        // Step 2.
+        if !self.is_in_document_tree(anchor_node) || !self.is_in_document_tree(focus_node) {
+            return Ok(());
+        }
+        // Steps 3-7.
+        let (range, direction) = match bp_position(focus_node, focus_offset, anchor_node, anchor_offset) {
+            Some(Ordering::Less) => {
+                let range = Range::new(&self.document, focus_node, focus_offset, anchor_node, anchor_offset);
+                (range, Direction::Backwards)
+            },
+            _ => {
+                let range = Range::new(&self.document, anchor_node, anchor_offset, focus_node, focus_offset);
+                (range, Direction::Forwards)
+            },
+        };
+        self.set_range(Some(&range), direction);
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-selectallchildren
+    fn SelectAllChildren(&self, node: &Node) -> ErrorResult {
+        // Step 1.
+        if node.is_doctype() {
+            return Err(Error::InvalidNodeType);
+        }
+        // Step 2.
+        if !self.is_in_document_tree(node) {
+            return Ok(());
+        }
+        // Steps 3-6.
+        let range = Range::new(&self.document, node, 0, node, node.children_count());
+        self.set_range(Some(&range), Direction::Forwards);
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-deletefromdocument
+    fn DeleteFromDocument(&self) -> ErrorResult {
+        match self.range.get() {
+            Some(range) => range.DeleteContents(),
+            None => Ok(()),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-containsnode
+    fn ContainsNode(&self, node: &Node, allow_partial_containment: bool) -> bool {
+        if !self.is_in_document_tree(node) {
+            return false;
+        }
+        let range = match self.range.get() {
+            Some(range) => range,
+            None => return false,
+        };
+        let (start_node, start_offset) = (range.StartContainer(), range.StartOffset());
+        let (end_node, end_offset) = (range.EndContainer(), range.EndOffset());
+        let (start, end) = if allow_partial_containment {
+            (bp_position(&start_node, start_offset, node, node.len()),
+             bp_position(&end_node, end_offset, node, 0))
+        } else {
+            (bp_position(&start_node, start_offset, node, 0),
+             bp_position(&end_node, end_offset, node, node.len()))
+        };
+        match (start, end) {
+            (Some(start), Some(end)) => start != Ordering::Greater && end != Ordering::Less,
+            _ => false,
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-stringifier
+    fn Stringifier(&self) -> DOMString {
+        self.range.get().map_or(DOMString::new(), |range| range.Stringifier())
+    }
+}
+
+/// Returns the text nodes covered by `range`, along with the byte range of
+/// their data that is selected.
+fn selected_text(range: &Range) -> Vec<(DomRoot<Text>, ops::Range<usize>)> {
+    let (start_node, start_offset) = (range.StartContainer(), range.StartOffset());
+    let (end_node, end_offset) = (range.EndContainer(), range.EndOffset());
+
+    if range.Collapsed() {
+        return match start_node.downcast::<Text>() {
+            Some(text) if start_node.editing_host().is_some() => {
+                let index = utf16_offset_to_byte_index(&text.upcast::<CharacterData>().data(), start_offset);
+                vec![(DomRoot::from_ref(text), index..index)]
+            },
+            _ => vec![],
+        };
+    }
+
+    let mut selected = vec![];
+    for node in range.CommonAncestorContainer().traverse_preorder() {
+        if bp_position(&node, 0, &end_node, end_offset) != Some(Ordering::Less) {
+            break;
+        }
+        let text = match node.downcast::<Text>() {
+            Some(text) => text,
+            None => continue,
+        };
+        let start = if node == start_node {
+            start_offset
+        } else if bp_position(&node, node.len(), &start_node, start_offset) != Some(Ordering::Greater) {
+            continue;
+        } else {
+            0
+        };
+        let end = if node == end_node { end_offset } else { node.len() };
+        if start >= end {
+            continue;
+        }
+        let data = text.upcast::<CharacterData>().data();
+        let byte_range = utf16_offset_to_byte_index(&data, start)..utf16_offset_to_byte_index(&data, end);
+        selected.push((DomRoot::from_ref(text), byte_range));
+    }
+    selected
+}
+
+/// Returns the caret position one character away from the given boundary point,
+/// staying within its editing host if it has one.
+fn adjacent_caret_position(node: &Node, offset: u32, forward: bool) -> Option<(DomRoot<Node>, u32)> {
+    let root = match node.editing_host() {
+        Some(host) => DomRoot::upcast::<Node>(host),
+        None => node.inclusive_ancestors().last().unwrap(),
+    };
+    let mut texts = root.traverse_preorder().filter(|candidate| candidate.is::<Text>() && candidate.len() > 0);
+    let text = if forward {
+        texts.find(|text| bp_position(text, text.len(), node, offset) == Some(Ordering::Greater))?
+    } else {
+        texts.filter(|text| bp_position(text, 0, node, offset) == Some(Ordering::Less)).last()?
+    };
+    let data = text.downcast::<CharacterData>().unwrap().data();
+    let current = if &*text == node {
+        offset
+    } else if forward {
+        0
+    } else {
+        text.len()
+    };
+    let byte_index = utf16_offset_to_byte_index(&data, current);
+    let units = if forward {
+        data[byte_index..].chars().next().map_or(0, |c| c.len_utf16())
+    } else {
+        data[..byte_index].chars().next_back().map_or(0, |c| c.len_utf16())
+    };
+    let new_offset = if forward { current + units as u32 } else { current - units as u32 };
+    Some((text, new_offset))
+}
+
+/// Converts an offset in UTF-16 code units into a byte index into `text`,
+/// clamped to the length of `text`.
+pub fn utf16_offset_to_byte_index(text: &str, offset: u32) -> usize {
+    let mut code_units = 0;
+    for (index, c) in text.char_indices() {
+        if code_units >= offset as usize {
+            return index;
+        }
+        code_units += c.len_utf16();
+    }
+    text.len()
+}
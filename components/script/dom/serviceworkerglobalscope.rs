@@ -221,6 +221,7 @@ impl ServiceWorkerGlobalScope {
                     // Step 6
                     global.upcast::<GlobalScope>().perform_a_microtask_checkpoint();
                 }
+                global.upcast::<GlobalScope>().remove_message_router();
             }, reporter_name, scope.script_chan(), CommonScriptMsg::CollectReports);
         }).expect("Thread spawning failed");
     }
@@ -259,8 +260,8 @@ impl ServiceWorkerGlobalScope {
                 let target = self.upcast();
                 let _ac = JSAutoCompartment::new(scope.get_cx(), scope.reflector().get_jsobject().get());
                 rooted!(in(scope.get_cx()) let mut message = UndefinedValue());
-                data.read(scope.upcast(), message.handle_mut());
-                ExtendableMessageEvent::dispatch_jsval(target, scope.upcast(), message.handle());
+                let ports = data.read(scope.upcast(), message.handle_mut());
+                ExtendableMessageEvent::dispatch_jsval(target, scope.upcast(), message.handle(), ports);
             },
             CommonWorker(WorkerScriptMsg::Common(msg)) => {
                 self.upcast::<WorkerGlobalScope>().process_event(msg);
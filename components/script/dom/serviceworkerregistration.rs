@@ -68,7 +68,10 @@ impl ServiceWorkerRegistration {
 
         let worker_id = global.get_next_worker_id();
         let devtools_chan = global.devtools_chan().cloned();
-        let init = prepare_workerscope_init(&global, None);
+        let mut init = prepare_workerscope_init(&global, None);
+        // The init is sent over IPC to the service worker manager, which
+        // in-process WebGL channels do not support.
+        init.webgl_chan = None;
         ScopeThings {
             script_url: script_url,
             init: init,
@@ -40,6 +40,7 @@ use hyper::mime::{Mime, SubLevel, TopLevel};
 use hyper_serde::Serde;
 use msg::constellation_msg::PipelineId;
 use net_traits::{FetchMetadata, FetchResponseListener, Metadata, NetworkError};
+use net_traits::csp::Violation;
 use network_listener::PreInvoke;
 use profile_traits::time::{TimerMetadata, TimerMetadataFrameType};
 use profile_traits::time::{TimerMetadataReflowType, ProfilerCategory, profile};
@@ -654,6 +655,15 @@ impl FetchResponseListener for ParserContext {
 
         self.parser = Some(Trusted::new(&*parser));
 
+        if parser.document.is_blocked_by_frame_ancestors() {
+            self.is_synthesized_document = true;
+            let page = resources::read_string(Resource::NetErrorHTML);
+            let page = page.replace("${reason}", "Blocked by Content Security Policy");
+            parser.push_string_input_chunk(page);
+            parser.parse_sync();
+            return;
+        }
+
         match content_type {
             Some(ContentType(Mime(TopLevel::Image, _, _))) => {
                 self.is_synthesized_document = true;
@@ -745,6 +755,10 @@ impl FetchResponseListener for ParserContext {
             parser.parse_sync();
         }
     }
+
+    // Navigation requests carry no CSP list; nested navigations are checked
+    // against the parent's policies before they start.
+    fn process_csp_violations(&mut self, _violations: Vec<Violation>) {}
 }
 
 impl PreInvoke for ParserContext {}
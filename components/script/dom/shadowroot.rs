@@ -0,0 +1,198 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::ShadowRootBinding;
+use dom::bindings::codegen::Bindings::ShadowRootBinding::{ShadowRootMethods, ShadowRootMode};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::root::{Dom, DomRoot, LayoutDom};
+use dom::document::{Document, StyleSheetInDocument};
+use dom::documentfragment::DocumentFragment;
+use dom::element::Element;
+use dom::node::{Node, NodeFlags, UnbindContext};
+use dom::virtualmethods::VirtualMethods;
+use dom_struct::dom_struct;
+use servo_arc::Arc;
+use style::author_styles::AuthorStyles;
+use style::context::QuirksMode;
+use style::dom::TElement;
+use style::invalidation::element::restyle_hints::RestyleHint;
+use style::media_queries::Device;
+use style::shared_lock::SharedRwLockReadGuard;
+use style::stylesheets::Stylesheet;
+use style::stylist::CascadeData;
+
+/// <https://dom.spec.whatwg.org/#interface-shadowroot>
+#[dom_struct]
+pub struct ShadowRoot {
+    document_fragment: DocumentFragment,
+    document: Dom<Document>,
+    host: Dom<Element>,
+    mode: ShadowRootMode,
+    /// The stylesheets of this shadow tree, and the cascade data computed
+    /// from them.
+    #[ignore_malloc_size_of = "Defined in style"]
+    author_styles: DomRefCell<AuthorStyles<StyleSheetInDocument>>,
+}
+
+impl ShadowRoot {
+    #[allow(unrooted_must_root)]
+    fn new_inherited(host: &Element, document: &Document, mode: ShadowRootMode) -> ShadowRoot {
+        let document_fragment = DocumentFragment::new_inherited(document);
+        {
+            let node = document_fragment.upcast::<Node>();
+            node.set_flag(NodeFlags::IS_IN_SHADOW_TREE, true);
+            node.set_flag(NodeFlags::IS_IN_DOC, host.upcast::<Node>().is_in_doc());
+        }
+        ShadowRoot {
+            document_fragment,
+            document: Dom::from_ref(document),
+            host: Dom::from_ref(host),
+            mode,
+            author_styles: DomRefCell::new(AuthorStyles::new()),
+        }
+    }
+
+    pub fn new(host: &Element, document: &Document, mode: ShadowRootMode) -> DomRoot<ShadowRoot> {
+        let shadow_root = Node::reflect_node(Box::new(ShadowRoot::new_inherited(host, document, mode)),
+                                             document,
+                                             ShadowRootBinding::Wrap);
+        shadow_root.upcast::<Node>().set_containing_shadow_root(Some(&shadow_root));
+        shadow_root
+    }
+
+    /// Add a stylesheet owned by `owner` to the list of shadow root sheets, in
+    /// the correct tree position.
+    #[allow(unrooted_must_root)] // Owner needs to be rooted already necessarily.
+    pub fn add_stylesheet(&self, owner: &Element, sheet: Arc<Stylesheet>) {
+        {
+            let mut author_styles = self.author_styles.borrow_mut();
+            let insertion_point = author_styles.stylesheets
+                .iter()
+                .find(|sheet_in_shadow| owner.upcast::<Node>().is_before(sheet_in_shadow.owner.upcast()))
+                .cloned();
+
+            let sheet = StyleSheetInDocument {
+                sheet,
+                owner: Dom::from_ref(owner),
+            };
+
+            let lock = self.document.style_shared_lock();
+            let guard = lock.read();
+
+            match insertion_point {
+                Some(ip) => {
+                    author_styles.stylesheets.insert_stylesheet_before(None, sheet, ip, &guard);
+                }
+                None => {
+                    author_styles.stylesheets.append_stylesheet(None, sheet, &guard);
+                }
+            }
+        }
+        self.invalidate_stylesheets();
+    }
+
+    /// Remove a stylesheet owned by `owner` from the list of shadow root
+    /// sheets.
+    #[allow(unrooted_must_root)] // Owner needs to be rooted already necessarily.
+    pub fn remove_stylesheet(&self, owner: &Element, s: &Arc<Stylesheet>) {
+        {
+            let guard = s.shared_lock.read();
+            self.author_styles.borrow_mut().stylesheets.remove_stylesheet(
+                None,
+                StyleSheetInDocument {
+                    sheet: s.clone(),
+                    owner: Dom::from_ref(owner),
+                },
+                &guard,
+            );
+        }
+        self.invalidate_stylesheets();
+    }
+
+    /// Marks the stylesheets of this shadow root as dirty, so that layout
+    /// flushes them and restyles the shadow tree on the next reflow.
+    pub fn invalidate_stylesheets(&self) {
+        self.author_styles.borrow_mut().stylesheets.force_dirty();
+        if !self.upcast::<Node>().is_in_doc() {
+            return;
+        }
+        self.document.invalidate_shadow_roots_stylesheets();
+        self.document.ensure_pending_restyle(&self.host).hint.insert(RestyleHint::restyle_subtree());
+    }
+}
+
+impl ShadowRootMethods for ShadowRoot {
+    // https://dom.spec.whatwg.org/#dom-shadowroot-mode
+    fn Mode(&self) -> ShadowRootMode {
+        self.mode
+    }
+
+    // https://dom.spec.whatwg.org/#dom-shadowroot-host
+    fn Host(&self) -> DomRoot<Element> {
+        DomRoot::from_ref(&self.host)
+    }
+}
+
+impl VirtualMethods for ShadowRoot {
+    fn super_type(&self) -> Option<&VirtualMethods> {
+        Some(self.upcast::<Node>() as &VirtualMethods)
+    }
+
+    fn bind_to_tree(&self, tree_in_doc: bool) {
+        if let Some(ref s) = self.super_type() {
+            s.bind_to_tree(tree_in_doc);
+        }
+
+        if tree_in_doc {
+            self.document.register_shadow_root(self);
+        }
+    }
+
+    fn unbind_from_tree(&self, context: &UnbindContext) {
+        if let Some(ref s) = self.super_type() {
+            s.unbind_from_tree(context);
+        }
+
+        if context.tree_in_doc {
+            self.document.unregister_shadow_root(self);
+        }
+    }
+}
+
+#[allow(unsafe_code)]
+pub trait LayoutShadowRootHelpers {
+    unsafe fn get_host_for_layout(&self) -> LayoutDom<Element>;
+    unsafe fn get_style_data_for_layout<'a>(&self) -> &'a CascadeData;
+    unsafe fn flush_stylesheets<E: TElement>(&self,
+                                             device: &Device,
+                                             quirks_mode: QuirksMode,
+                                             guard: &SharedRwLockReadGuard);
+}
+
+impl LayoutShadowRootHelpers for LayoutDom<ShadowRoot> {
+    #[inline]
+    #[allow(unsafe_code)]
+    unsafe fn get_host_for_layout(&self) -> LayoutDom<Element> {
+        (*self.unsafe_get()).host.to_layout()
+    }
+
+    #[inline]
+    #[allow(unsafe_code)]
+    unsafe fn get_style_data_for_layout<'a>(&self) -> &'a CascadeData {
+        &(*self.unsafe_get()).author_styles.borrow_for_layout().data
+    }
+
+    #[inline]
+    #[allow(unsafe_code)]
+    unsafe fn flush_stylesheets<E: TElement>(&self,
+                                             device: &Device,
+                                             quirks_mode: QuirksMode,
+                                             guard: &SharedRwLockReadGuard) {
+        let mut author_styles = (*self.unsafe_get()).author_styles.borrow_mut_for_layout();
+        if author_styles.stylesheets.dirty() {
+            author_styles.flush::<E>(device, quirks_mode, guard);
+        }
+    }
+}
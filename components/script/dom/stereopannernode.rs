@@ -0,0 +1,99 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::audionode::AudioNode;
+use dom::audioparam::AudioParam;
+use dom::baseaudiocontext::BaseAudioContext;
+use dom::bindings::codegen::Bindings::AudioNodeBinding::{ChannelCountMode, ChannelInterpretation};
+use dom::bindings::codegen::Bindings::AudioParamBinding::AutomationRate;
+use dom::bindings::codegen::Bindings::StereoPannerNodeBinding::{self, StereoPannerNodeMethods};
+use dom::bindings::codegen::Bindings::StereoPannerNodeBinding::StereoPannerOptions;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::{Dom, DomRoot};
+use dom::window::Window;
+use dom_struct::dom_struct;
+use servo_media::audio::node::AudioNodeInit;
+use servo_media::audio::param::ParamType;
+use servo_media::audio::stereo_panner::StereoPannerOptions as ServoMediaStereoPannerOptions;
+
+#[dom_struct]
+pub struct StereoPannerNode {
+    node: AudioNode,
+    pan: Dom<AudioParam>,
+}
+
+impl StereoPannerNode {
+    #[allow(unrooted_must_root)]
+    pub fn new_inherited(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &StereoPannerOptions,
+    ) -> Fallible<StereoPannerNode> {
+        let node_options = options.parent.unwrap_or(
+            2,
+            ChannelCountMode::Clamped_max,
+            ChannelInterpretation::Speakers,
+        );
+        if node_options.channelCountMode == Some(ChannelCountMode::Max) {
+            return Err(Error::NotSupported);
+        }
+        if node_options.channelCount.unwrap() > 2 {
+            return Err(Error::NotSupported);
+        }
+        let node = AudioNode::new_inherited(
+            AudioNodeInit::StereoPannerNode(options.into()),
+            None,
+            context,
+            &node_options,
+            1, // inputs
+            1, // outputs
+        );
+        let pan = AudioParam::new(
+            window,
+            context,
+            node.node_id(),
+            ParamType::Pan,
+            AutomationRate::A_rate,
+            0.,  // default value
+            -1., // min value
+            1.,  // max value
+        );
+        Ok(StereoPannerNode {
+            node,
+            pan: Dom::from_ref(&pan),
+        })
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &StereoPannerOptions,
+    ) -> Fallible<DomRoot<StereoPannerNode>> {
+        let node = StereoPannerNode::new_inherited(window, context, options)?;
+        Ok(reflect_dom_object(Box::new(node), window, StereoPannerNodeBinding::Wrap))
+    }
+
+    pub fn Constructor(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &StereoPannerOptions,
+    ) -> Fallible<DomRoot<StereoPannerNode>> {
+        StereoPannerNode::new(window, context, options)
+    }
+}
+
+impl StereoPannerNodeMethods for StereoPannerNode {
+    // https://webaudio.github.io/web-audio-api/#dom-stereopannernode-pan
+    fn Pan(&self) -> DomRoot<AudioParam> {
+        DomRoot::from_ref(&self.pan)
+    }
+}
+
+impl<'a> From<&'a StereoPannerOptions> for ServoMediaStereoPannerOptions {
+    fn from(options: &'a StereoPannerOptions) -> Self {
+        Self { pan: *options.pan }
+    }
+}
@@ -9,24 +9,31 @@ use dom::bindings::codegen::Bindings::TextBinding::{self, TextMethods};
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use dom::bindings::error::{Error, Fallible};
 use dom::bindings::inheritance::Castable;
-use dom::bindings::root::{DomRoot, RootedReference};
+use dom::bindings::root::{DomRoot, LayoutDom, RootedReference};
 use dom::bindings::str::DOMString;
 use dom::characterdata::CharacterData;
 use dom::document::Document;
-use dom::node::Node;
+use dom::htmlslotelement::HTMLSlotElement;
+use dom::node::{Node, NodeDamage};
 use dom::window::Window;
 use dom_struct::dom_struct;
+use std::cell::Cell;
+use std::ops::Range;
 
 /// An HTML text node.
 #[dom_struct]
 pub struct Text {
     characterdata: CharacterData,
+    /// The byte range of this node's data covered by the document's selection,
+    /// as painted by layout. An empty range is painted as a caret.
+    selected_range: Cell<Option<(usize, usize)>>,
 }
 
 impl Text {
     fn new_inherited(text: DOMString, document: &Document) -> Text {
         Text {
-            characterdata: CharacterData::new_inherited(text, document)
+            characterdata: CharacterData::new_inherited(text, document),
+            selected_range: Cell::new(None),
         }
     }
 
@@ -39,6 +46,26 @@ impl Text {
         let document = window.Document();
         Ok(Text::new(text, &document))
     }
+
+    pub fn set_selected_range(&self, range: Option<Range<usize>>) {
+        let range = range.map(|range| (range.start, range.end));
+        if self.selected_range.get() != range {
+            self.selected_range.set(range);
+            self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        }
+    }
+}
+
+pub trait LayoutTextHelpers {
+    #[allow(unsafe_code)]
+    unsafe fn selection_for_layout(self) -> Option<Range<usize>>;
+}
+
+impl LayoutTextHelpers for LayoutDom<Text> {
+    #[allow(unsafe_code)]
+    unsafe fn selection_for_layout(self) -> Option<Range<usize>> {
+        (*self.unsafe_get()).selected_range.get().map(|(start, end)| start..end)
+    }
 }
 
 impl TextMethods for Text {
@@ -90,4 +117,9 @@ impl TextMethods for Text {
         }
         DOMString::from(text)
     }
+
+    // https://dom.spec.whatwg.org/#dom-slotable-assignedslot
+    fn GetAssignedSlot(&self) -> Option<DomRoot<HTMLSlotElement>> {
+        HTMLSlotElement::find_a_slot(self.upcast(), true)
+    }
 }
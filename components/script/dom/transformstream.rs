@@ -0,0 +1,315 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::TransformStreamBinding::{self, TransformStreamMethods};
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::readablestream::{ReadableStream, ReadableStreamState, UnderlyingSource};
+use dom::readablestreamdefaultcontroller::ReadableStreamDefaultController;
+use dom::transformstreamdefaultcontroller::TransformStreamDefaultController;
+use dom::writablestream::{UnderlyingSink, WritableStream, WritableStreamState};
+use dom::writablestreamdefaultcontroller::WritableStreamDefaultController;
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSContext, JSObject};
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::{HandleObject, HandleValue};
+use std::cell::Cell;
+use std::rc::Rc;
+use streams::{extract_high_water_mark, extract_size_algorithm, get_property, object_or_null};
+use streams::{resolved_promise, upon_settlement};
+
+/// <https://streams.spec.whatwg.org/#ts-class>
+#[dom_struct]
+pub struct TransformStream {
+    reflector_: Reflector,
+    readable: MutNullableDom<ReadableStream>,
+    writable: MutNullableDom<WritableStream>,
+    controller: MutNullableDom<TransformStreamDefaultController>,
+    backpressure: Cell<bool>,
+    /// Resolved when the backpressure changes, which is when the readable
+    /// side is pulled from.
+    #[ignore_malloc_size_of = "Rc"]
+    backpressure_change_promise: DomRefCell<Rc<Promise>>,
+    /// Resolved once the transformer has started.
+    #[ignore_malloc_size_of = "Rc"]
+    start_promise: Rc<Promise>,
+}
+
+impl TransformStream {
+    #[allow(unrooted_must_root)]
+    fn new_inherited(backpressure_change_promise: Rc<Promise>, start_promise: Rc<Promise>) -> TransformStream {
+        TransformStream {
+            reflector_: Reflector::new(),
+            readable: Default::default(),
+            writable: Default::default(),
+            controller: Default::default(),
+            backpressure: Cell::new(false),
+            backpressure_change_promise: DomRefCell::new(backpressure_change_promise),
+            start_promise: start_promise,
+        }
+    }
+
+    fn new(global: &GlobalScope) -> DomRoot<TransformStream> {
+        reflect_dom_object(
+            Box::new(TransformStream::new_inherited(Promise::new(global), Promise::new(global))),
+            global,
+            TransformStreamBinding::Wrap,
+        )
+    }
+
+    // https://streams.spec.whatwg.org/#ts-constructor
+    #[allow(unsafe_code)]
+    pub unsafe fn Constructor(cx: *mut JSContext,
+                              global: &GlobalScope,
+                              transformer: Option<*mut JSObject>,
+                              writable_strategy: Option<*mut JSObject>,
+                              readable_strategy: Option<*mut JSObject>)
+                              -> Fallible<DomRoot<TransformStream>> {
+        rooted!(in(cx) let transformer = object_or_null(transformer));
+        rooted!(in(cx) let writable_strategy = object_or_null(writable_strategy));
+        rooted!(in(cx) let readable_strategy = object_or_null(readable_strategy));
+
+        rooted!(in(cx) let mut type_ = UndefinedValue());
+        if get_property(cx, transformer.handle(), "readableType", type_.handle_mut())? {
+            return Err(Error::Range("Transformers cannot have a readable type".to_owned()));
+        }
+        if get_property(cx, transformer.handle(), "writableType", type_.handle_mut())? {
+            return Err(Error::Range("Transformers cannot have a writable type".to_owned()));
+        }
+        rooted!(in(cx) let mut readable_size = UndefinedValue());
+        extract_size_algorithm(cx, readable_strategy.handle(), readable_size.handle_mut())?;
+        let readable_high_water_mark = extract_high_water_mark(cx, readable_strategy.handle(), 0.)?;
+        rooted!(in(cx) let mut writable_size = UndefinedValue());
+        extract_size_algorithm(cx, writable_strategy.handle(), writable_size.handle_mut())?;
+        let writable_high_water_mark = extract_high_water_mark(cx, writable_strategy.handle(), 1.)?;
+
+        // https://streams.spec.whatwg.org/#initialize-transform-stream
+        let stream = TransformStream::new(global);
+        let writable = WritableStream::new(global);
+        WritableStreamDefaultController::set_up(
+            cx, &writable, UnderlyingSink::Transform(Dom::from_ref(&*stream)), HandleObject::null(),
+            writable_high_water_mark, writable_size.handle())?;
+        stream.writable.set(Some(&writable));
+        let readable = ReadableStream::new(global);
+        ReadableStreamDefaultController::set_up(
+            cx, &readable, UnderlyingSource::Transform(Dom::from_ref(&*stream)), HandleObject::null(),
+            readable_high_water_mark, readable_size.handle())?;
+        stream.readable.set(Some(&readable));
+        stream.set_backpressure(true);
+
+        let controller = TransformStreamDefaultController::set_up(cx, &stream, transformer.handle())?;
+        stream.controller.set(Some(&controller));
+        Ok(stream)
+    }
+
+    fn readable(&self) -> DomRoot<ReadableStream> {
+        self.readable.get().expect("The readable side is set up")
+    }
+
+    fn writable(&self) -> DomRoot<WritableStream> {
+        self.writable.get().expect("The writable side is set up")
+    }
+
+    fn controller(&self) -> DomRoot<TransformStreamDefaultController> {
+        self.controller.get().expect("The controller is set up")
+    }
+
+    pub fn readable_controller(&self) -> DomRoot<ReadableStreamDefaultController> {
+        self.readable().default_controller().expect("The readable side has a default controller")
+    }
+
+    pub fn has_backpressure(&self) -> bool {
+        self.backpressure.get()
+    }
+
+    /// The promise the start algorithms of both sides wait for.
+    #[allow(unrooted_must_root)]
+    pub fn start_promise(&self) -> Rc<Promise> {
+        self.start_promise.clone()
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-error>
+    pub fn error(&self, error: HandleValue) {
+        self.readable().error_controller(error);
+        self.error_writable_and_unblock_write(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-error-writable-and-unblock-write>
+    pub fn error_writable_and_unblock_write(&self, error: HandleValue) {
+        if let Some(controller) = self.controller.get() {
+            controller.clear_algorithms();
+        }
+        self.writable().controller().error_if_needed(error);
+        if self.backpressure.get() {
+            self.set_backpressure(false);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-set-backpressure>
+    pub fn set_backpressure(&self, backpressure: bool) {
+        self.backpressure_change_promise.borrow().resolve_native(&());
+        *self.backpressure_change_promise.borrow_mut() = Promise::new(&self.global());
+        self.backpressure.set(backpressure);
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-sink-write-algorithm>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn sink_write(&self, cx: *mut JSContext, chunk: HandleValue) -> Rc<Promise> {
+        if !self.backpressure.get() {
+            return self.perform_transform(cx, chunk);
+        }
+        let pending = PendingTransform {
+            stream: Dom::from_ref(self),
+            chunk: Rc::new(Heap::default()),
+            promise: Promise::new(&self.global()),
+        };
+        pending.chunk.set(chunk.get());
+        let backpressure_change_promise = self.backpressure_change_promise.borrow().clone();
+        upon_settlement(&backpressure_change_promise, pending.clone(),
+                        Some(PendingTransform::backpressure_changed), None);
+        pending.promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-controller-perform-transform>
+    #[allow(unrooted_must_root, unsafe_code)]
+    unsafe fn perform_transform(&self, cx: *mut JSContext, chunk: HandleValue) -> Rc<Promise> {
+        let transform = self.controller().transform(cx, chunk);
+        let settlement = Settlement {
+            stream: Dom::from_ref(self),
+            promise: Promise::new(&self.global()),
+        };
+        upon_settlement(&transform, settlement.clone(), Some(Settlement::resolve), Some(Settlement::transform_failed));
+        settlement.promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-sink-abort-algorithm>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn sink_abort(&self, _cx: *mut JSContext, reason: HandleValue) -> Rc<Promise> {
+        self.error(reason);
+        resolved_promise(&self.global())
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-sink-close-algorithm>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn sink_close(&self, cx: *mut JSContext) -> Rc<Promise> {
+        let controller = self.controller();
+        let flush = controller.flush(cx);
+        controller.clear_algorithms();
+        let settlement = Settlement {
+            stream: Dom::from_ref(self),
+            promise: Promise::new(&self.global()),
+        };
+        upon_settlement(&flush, settlement.clone(), Some(Settlement::flushed), Some(Settlement::flush_failed));
+        settlement.promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-source-pull>
+    #[allow(unrooted_must_root)]
+    pub fn source_pull(&self) -> Rc<Promise> {
+        self.set_backpressure(false);
+        self.backpressure_change_promise.borrow().clone()
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-source-cancel>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn source_cancel(&self, _cx: *mut JSContext, reason: HandleValue) -> Rc<Promise> {
+        self.error_writable_and_unblock_write(reason);
+        resolved_promise(&self.global())
+    }
+}
+
+/// A chunk written while the stream had backpressure, transformed once the
+/// readable side is pulled from.
+#[derive(Clone, JSTraceable, MallocSizeOf)]
+#[must_root]
+struct PendingTransform {
+    stream: Dom<TransformStream>,
+    #[ignore_malloc_size_of = "mozjs"]
+    chunk: Rc<Heap<JSVal>>,
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+}
+
+impl PendingTransform {
+    #[allow(unrooted_must_root, unsafe_code)]
+    fn backpressure_changed(pending: &PendingTransform, cx: *mut JSContext, _value: HandleValue) {
+        let writable = pending.stream.writable();
+        if writable.state() == WritableStreamState::Erroring {
+            rooted!(in(cx) let mut error = UndefinedValue());
+            writable.get_stored_error(error.handle_mut());
+            return pending.promise.reject_native(&error.handle());
+        }
+        rooted!(in(cx) let chunk = pending.chunk.get());
+        let transform = unsafe { pending.stream.perform_transform(cx, chunk.handle()) };
+        let settlement = Settlement {
+            stream: pending.stream.clone(),
+            promise: pending.promise.clone(),
+        };
+        upon_settlement(&transform, settlement, Some(Settlement::resolve), Some(Settlement::reject));
+    }
+}
+
+/// The promise of a sink algorithm, settled once the transformer is done.
+#[derive(Clone, JSTraceable, MallocSizeOf)]
+#[must_root]
+struct Settlement {
+    stream: Dom<TransformStream>,
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+}
+
+impl Settlement {
+    #[allow(unrooted_must_root)]
+    fn resolve(settlement: &Settlement, _cx: *mut JSContext, _value: HandleValue) {
+        settlement.promise.resolve_native(&());
+    }
+
+    #[allow(unrooted_must_root)]
+    fn reject(settlement: &Settlement, _cx: *mut JSContext, reason: HandleValue) {
+        settlement.promise.reject_native(&reason);
+    }
+
+    #[allow(unrooted_must_root)]
+    fn transform_failed(settlement: &Settlement, _cx: *mut JSContext, reason: HandleValue) {
+        settlement.stream.error(reason);
+        settlement.promise.reject_native(&reason);
+    }
+
+    #[allow(unrooted_must_root)]
+    fn flushed(settlement: &Settlement, cx: *mut JSContext, _value: HandleValue) {
+        let readable = settlement.stream.readable();
+        if readable.state() == ReadableStreamState::Errored {
+            rooted!(in(cx) let mut error = UndefinedValue());
+            readable.get_stored_error(error.handle_mut());
+            return settlement.promise.reject_native(&error.handle());
+        }
+        readable.close_controller();
+        settlement.promise.resolve_native(&());
+    }
+
+    #[allow(unrooted_must_root)]
+    fn flush_failed(settlement: &Settlement, cx: *mut JSContext, reason: HandleValue) {
+        settlement.stream.error(reason);
+        let readable = settlement.stream.readable();
+        rooted!(in(cx) let mut error = UndefinedValue());
+        readable.get_stored_error(error.handle_mut());
+        settlement.promise.reject_native(&error.handle());
+    }
+}
+
+impl TransformStreamMethods for TransformStream {
+    // https://streams.spec.whatwg.org/#ts-readable
+    fn Readable(&self) -> DomRoot<ReadableStream> {
+        self.readable()
+    }
+
+    // https://streams.spec.whatwg.org/#ts-writable
+    fn Writable(&self) -> DomRoot<WritableStream> {
+        self.writable()
+    }
+}
@@ -0,0 +1,163 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::TransformStreamDefaultControllerBinding;
+use dom::bindings::codegen::Bindings::TransformStreamDefaultControllerBinding::TransformStreamDefaultControllerMethods;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::transformstream::TransformStream;
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSAutoCompartment, JSContext, JSObject};
+use js::jsval::{ObjectValue, UndefinedValue};
+use js::rust::{HandleObject, HandleValue};
+use std::ptr;
+use std::rc::Rc;
+use streams::{error_to_value, get_method, invoke_or_noop, promise_invoke_or_noop, promise_rejected_with};
+use streams::{promise_rejected_with_error, resolved_promise, throw_value};
+
+/// <https://streams.spec.whatwg.org/#ts-default-controller-class>
+#[dom_struct]
+pub struct TransformStreamDefaultController {
+    reflector_: Reflector,
+    stream: Dom<TransformStream>,
+    /// The transformer given to the constructor of the stream, or null once
+    /// the algorithms are cleared.
+    #[ignore_malloc_size_of = "mozjs"]
+    transformer: Heap<*mut JSObject>,
+}
+
+impl TransformStreamDefaultController {
+    fn new_inherited(stream: &TransformStream) -> TransformStreamDefaultController {
+        TransformStreamDefaultController {
+            reflector_: Reflector::new(),
+            stream: Dom::from_ref(stream),
+            transformer: Heap::default(),
+        }
+    }
+
+    fn new(global: &GlobalScope, stream: &TransformStream) -> DomRoot<TransformStreamDefaultController> {
+        reflect_dom_object(Box::new(TransformStreamDefaultController::new_inherited(stream)),
+                           global,
+                           TransformStreamDefaultControllerBinding::Wrap)
+    }
+
+    /// Sets up the controller and runs the start method of `transformer`.
+    ///
+    /// <https://streams.spec.whatwg.org/#set-up-transform-stream-default-controller-from-transformer>
+    #[allow(unsafe_code)]
+    pub unsafe fn set_up(cx: *mut JSContext,
+                         stream: &TransformStream,
+                         transformer: HandleObject)
+                         -> Fallible<DomRoot<TransformStreamDefaultController>> {
+        let controller = TransformStreamDefaultController::new(&stream.global(), stream);
+        controller.transformer.set(transformer.get());
+        rooted!(in(cx) let controller_value = ObjectValue(controller.reflector().get_jsobject().get()));
+        rooted!(in(cx) let mut result = UndefinedValue());
+        invoke_or_noop(cx, transformer, "start", &[controller_value.get()], result.handle_mut())?;
+        stream.start_promise().resolve(cx, result.handle());
+        Ok(controller)
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-controller-clear-algorithms>
+    pub fn clear_algorithms(&self) {
+        self.transformer.set(ptr::null_mut());
+    }
+
+    /// Runs the transform algorithm, which enqueues `chunk` unchanged if the
+    /// transformer has no transform method.
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn transform(&self, cx: *mut JSContext, chunk: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        rooted!(in(cx) let transformer = self.transformer.get());
+        rooted!(in(cx) let mut method = UndefinedValue());
+        match get_method(cx, transformer.handle(), "transform", method.handle_mut()) {
+            Ok(true) => {
+                rooted!(in(cx) let controller = ObjectValue(self.reflector().get_jsobject().get()));
+                return promise_invoke_or_noop(&global, cx, transformer.handle(), "transform",
+                                              &[chunk.get(), controller.get()]);
+            },
+            Ok(false) => {},
+            Err(error) => return promise_rejected_with_error(&global, error),
+        }
+        match self.enqueue(cx, chunk) {
+            Ok(()) => resolved_promise(&global),
+            Err(error) => {
+                let _ac = JSAutoCompartment::new(cx, global.reflector().get_jsobject().get());
+                rooted!(in(cx) let mut reason = UndefinedValue());
+                error_to_value(cx, &global, error, reason.handle_mut());
+                promise_rejected_with(&global, reason.handle())
+            },
+        }
+    }
+
+    /// Runs the flush algorithm.
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn flush(&self, cx: *mut JSContext) -> Rc<Promise> {
+        rooted!(in(cx) let transformer = self.transformer.get());
+        rooted!(in(cx) let controller = ObjectValue(self.reflector().get_jsobject().get()));
+        promise_invoke_or_noop(&self.global(), cx, transformer.handle(), "flush", &[controller.get()])
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-controller-enqueue>
+    #[allow(unsafe_code)]
+    pub unsafe fn enqueue(&self, cx: *mut JSContext, chunk: HandleValue) -> ErrorResult {
+        let readable_controller = self.stream.readable_controller();
+        if !readable_controller.can_close_or_enqueue() {
+            return Err(Error::Type("The readable side cannot be enqueued to".to_owned()));
+        }
+        if let Err(error) = readable_controller.enqueue(cx, chunk) {
+            rooted!(in(cx) let mut error_value = UndefinedValue());
+            error_to_value(cx, &self.global(), error, error_value.handle_mut());
+            self.stream.error_writable_and_unblock_write(error_value.handle());
+            return Err(throw_value(cx, error_value.handle()));
+        }
+        let backpressure = readable_controller.has_backpressure();
+        if backpressure != self.stream.has_backpressure() {
+            assert!(backpressure);
+            self.stream.set_backpressure(true);
+        }
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-controller-terminate>
+    #[allow(unsafe_code)]
+    fn terminate(&self) {
+        self.stream.readable_controller().close();
+        let global = self.global();
+        let cx = global.get_cx();
+        let _ac = JSAutoCompartment::new(cx, global.reflector().get_jsobject().get());
+        rooted!(in(cx) let mut error = UndefinedValue());
+        unsafe {
+            error_to_value(cx, &global, Error::Type("The stream was terminated".to_owned()), error.handle_mut());
+        }
+        self.stream.error_writable_and_unblock_write(error.handle());
+    }
+}
+
+impl TransformStreamDefaultControllerMethods for TransformStreamDefaultController {
+    // https://streams.spec.whatwg.org/#ts-default-controller-desired-size
+    fn GetDesiredSize(&self) -> Option<f64> {
+        self.stream.readable_controller().desired_size()
+    }
+
+    #[allow(unsafe_code)]
+    // https://streams.spec.whatwg.org/#ts-default-controller-enqueue
+    unsafe fn Enqueue(&self, cx: *mut JSContext, chunk: HandleValue) -> ErrorResult {
+        self.enqueue(cx, chunk)
+    }
+
+    #[allow(unsafe_code)]
+    // https://streams.spec.whatwg.org/#ts-default-controller-error
+    unsafe fn Error(&self, _cx: *mut JSContext, reason: HandleValue) {
+        self.stream.error(reason);
+    }
+
+    // https://streams.spec.whatwg.org/#ts-default-controller-terminate
+    fn Terminate(&self) {
+        self.terminate();
+    }
+}
@@ -60,6 +60,7 @@ impl UIEvent {
                                  type_,
                                  bubbles, cancelable,
                                  init.view.r(), init.detail);
+        event.upcast::<Event>().set_composed(init.parent.composed);
         Ok(event)
     }
 }
@@ -0,0 +1,146 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::audionode::AudioNode;
+use dom::baseaudiocontext::BaseAudioContext;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::AudioNodeBinding::{ChannelCountMode, ChannelInterpretation};
+use dom::bindings::codegen::Bindings::WaveShaperNodeBinding::{self, OverSampleType, WaveShaperNodeMethods};
+use dom::bindings::codegen::Bindings::WaveShaperNodeBinding::WaveShaperOptions;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::DomRoot;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use js::jsapi::{JSContext, JSObject};
+use js::rust::CustomAutoRooterGuard;
+use js::typedarray::{CreateWith, Float32Array};
+use servo_media::audio::node::{AudioNodeInit, AudioNodeMessage};
+use servo_media::audio::wave_shaper_node::{OverSampleType as ServoMediaOverSampleType, WaveShaperNodeMessage};
+use servo_media::audio::wave_shaper_node::WaveShaperNodeOptions;
+use std::cell::Cell;
+use std::ptr::{self, NonNull};
+
+#[dom_struct]
+pub struct WaveShaperNode {
+    node: AudioNode,
+    /// https://webaudio.github.io/web-audio-api/#dom-waveshapernode-curve-set
+    curve_set: Cell<bool>,
+    curve: DomRefCell<Option<Vec<f32>>>,
+    oversample: Cell<OverSampleType>,
+}
+
+impl WaveShaperNode {
+    #[allow(unrooted_must_root)]
+    pub fn new_inherited(
+        context: &BaseAudioContext,
+        options: &WaveShaperOptions,
+    ) -> Fallible<WaveShaperNode> {
+        let node_options = options.parent.unwrap_or(
+            2,
+            ChannelCountMode::Max,
+            ChannelInterpretation::Speakers,
+        );
+        let curve = match options.curve {
+            Some(ref curve) => {
+                if curve.len() < 2 {
+                    return Err(Error::InvalidState);
+                }
+                Some(curve.iter().map(|value| **value).collect::<Vec<f32>>())
+            },
+            None => None,
+        };
+        let node = AudioNode::new_inherited(
+            AudioNodeInit::WaveShaperNode(WaveShaperNodeOptions {
+                curve: curve.clone(),
+                oversample: options.oversample.into(),
+            }),
+            None,
+            context,
+            &node_options,
+            1, // inputs
+            1, // outputs
+        );
+        Ok(WaveShaperNode {
+            node,
+            curve_set: Cell::new(curve.is_some()),
+            curve: DomRefCell::new(curve),
+            oversample: Cell::new(options.oversample),
+        })
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &WaveShaperOptions,
+    ) -> Fallible<DomRoot<WaveShaperNode>> {
+        let node = WaveShaperNode::new_inherited(context, options)?;
+        Ok(reflect_dom_object(Box::new(node), window, WaveShaperNodeBinding::Wrap))
+    }
+
+    pub fn Constructor(
+        window: &Window,
+        context: &BaseAudioContext,
+        options: &WaveShaperOptions,
+    ) -> Fallible<DomRoot<WaveShaperNode>> {
+        WaveShaperNode::new(window, context, options)
+    }
+}
+
+impl WaveShaperNodeMethods for WaveShaperNode {
+    // https://webaudio.github.io/web-audio-api/#dom-waveshapernode-curve
+    #[allow(unsafe_code)]
+    unsafe fn GetCurve(&self, cx: *mut JSContext) -> Option<NonNull<JSObject>> {
+        let curve = self.curve.borrow();
+        let curve = curve.as_ref()?;
+        rooted!(in(cx) let mut array = ptr::null_mut::<JSObject>());
+        assert!(Float32Array::create(cx, CreateWith::Slice(curve), array.handle_mut()).is_ok());
+        Some(NonNull::new_unchecked(array.get()))
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-waveshapernode-curve
+    #[allow(unsafe_code)]
+    fn SetCurve(&self, new_curve: Option<CustomAutoRooterGuard<Float32Array>>) -> ErrorResult {
+        let curve = match new_curve {
+            Some(new_curve) => {
+                if new_curve.len() < 2 || self.curve_set.get() {
+                    return Err(Error::InvalidState);
+                }
+                self.curve_set.set(true);
+                Some(unsafe { new_curve.as_slice().to_vec() })
+            },
+            None => None,
+        };
+
+        *self.curve.borrow_mut() = curve.clone();
+        self.node.message(AudioNodeMessage::WaveShaperNode(
+            WaveShaperNodeMessage::SetCurve(curve),
+        ));
+        Ok(())
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-waveshapernode-oversample
+    fn Oversample(&self) -> OverSampleType {
+        self.oversample.get()
+    }
+
+    // https://webaudio.github.io/web-audio-api/#dom-waveshapernode-oversample
+    fn SetOversample(&self, oversample: OverSampleType) {
+        self.oversample.set(oversample);
+        self.node.message(AudioNodeMessage::WaveShaperNode(
+            WaveShaperNodeMessage::SetOverSample(oversample.into()),
+        ));
+    }
+}
+
+impl From<OverSampleType> for ServoMediaOverSampleType {
+    fn from(oversample: OverSampleType) -> Self {
+        match oversample {
+            OverSampleType::None => ServoMediaOverSampleType::None,
+            OverSampleType::_2x => ServoMediaOverSampleType::Double,
+            OverSampleType::_4x => ServoMediaOverSampleType::Quadruple,
+        }
+    }
+}
@@ -3,71 +3,231 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 /// https://www.khronos.org/registry/webgl/specs/latest/2.0/webgl.idl
-use canvas_traits::webgl::WebGLVersion;
+use canvas_traits::webgl::{ParameterInt, WebGLCommand, WebGLResult, WebGLVersion};
+use canvas_traits::webgl::WebGLError::*;
+use canvas_traits::webgl::webgl_channel;
 use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding;
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as constants2;
 use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextMethods;
-use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLContextAttributes;
+use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::{TexImageSource, WebGLContextAttributes};
+use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextMethods;
 use dom::bindings::codegen::UnionTypes::ArrayBufferViewOrArrayBuffer;
 use dom::bindings::codegen::UnionTypes::Float32ArrayOrUnrestrictedFloatSequence;
-use dom::bindings::codegen::UnionTypes::ImageDataOrHTMLImageElementOrHTMLCanvasElementOrHTMLVideoElement;
+use dom::bindings::codegen::UnionTypes::HTMLCanvasElementOrOffscreenCanvas;
 use dom::bindings::codegen::UnionTypes::Int32ArrayOrLongSequence;
+use dom::bindings::conversions::ToJSValConvertible;
 use dom::bindings::error::{ErrorResult, Fallible};
 use dom::bindings::reflector::{reflect_dom_object, Reflector};
-use dom::bindings::root::{Dom, DomRoot, LayoutDom};
+use dom::bindings::root::{Dom, DomRoot, LayoutDom, MutNullableDom};
 use dom::bindings::str::DOMString;
-use dom::htmlcanvaselement::HTMLCanvasElement;
+use dom::globalscope::GlobalScope;
 use dom::htmliframeelement::HTMLIFrameElement;
+use dom::webgl_validations::WebGLValidator;
+use dom::webgl_validations::tex_image_3d::{TexImage3DValidator, TexImage3DValidatorResult};
+use dom::webgl_validations::types::{TexDataType, TexFormat, TexImage3DTarget, sized_internal_format_info};
 use dom::webglactiveinfo::WebGLActiveInfo;
 use dom::webglbuffer::WebGLBuffer;
 use dom::webglframebuffer::WebGLFramebuffer;
 use dom::webglprogram::WebGLProgram;
+use dom::webglquery::{QueryParameterValue, WebGLQuery};
 use dom::webglrenderbuffer::WebGLRenderbuffer;
 use dom::webglrenderingcontext::{LayoutCanvasWebGLRenderingContextHelpers, WebGLRenderingContext};
+use dom::webglsampler::WebGLSampler;
 use dom::webglshader::WebGLShader;
 use dom::webglshaderprecisionformat::WebGLShaderPrecisionFormat;
-use dom::webgltexture::WebGLTexture;
+use dom::webglsync::WebGLSync;
+use dom::webgltexture::{TexParameterValue, WebGLTexture};
+use dom::webgltransformfeedback::WebGLTransformFeedback;
 use dom::webgluniformlocation::WebGLUniformLocation;
-use dom::window::Window;
 use dom_struct::dom_struct;
 use euclid::Size2D;
 use js::jsapi::{JSContext, JSObject};
-use js::jsval::JSVal;
+use js::jsval::{BooleanValue, DoubleValue, Int32Value, JSVal, NullValue, ObjectValue, UInt32Value};
 use js::rust::CustomAutoRooterGuard;
-use js::typedarray::ArrayBufferView;
+use js::typedarray::{ArrayBufferView, CreateWith, Uint32Array};
 use offscreen_gl_context::GLContextAttributes;
 use script_layout_interface::HTMLCanvasDataSource;
-use std::ptr::NonNull;
+use std::cell::Cell;
+use std::cmp;
+use std::ptr::{self, NonNull};
 
 #[dom_struct]
 pub struct WebGL2RenderingContext {
     reflector_: Reflector,
     base: Dom<WebGLRenderingContext>,
+    limits: WebGL2Limits,
+    /// The active ANY_SAMPLES_PASSED or ANY_SAMPLES_PASSED_CONSERVATIVE query.
+    occlusion_query: MutNullableDom<WebGLQuery>,
+    /// The active TRANSFORM_FEEDBACK_PRIMITIVES_WRITTEN query.
+    primitives_query: MutNullableDom<WebGLQuery>,
+    /// The sampler bound to each texture unit.
+    samplers: Box<[MutNullableDom<WebGLSampler>]>,
+    uniform_buffer_bindings: Box<[IndexedBinding]>,
+    default_transform_feedback: Dom<WebGLTransformFeedback>,
+    current_transform_feedback: MutNullableDom<WebGLTransformFeedback>,
+}
+
+/// The implementation limits that only matter to WebGL 2, queried once at
+/// context creation.
+#[derive(JSTraceable, MallocSizeOf)]
+struct WebGL2Limits {
+    max_3d_texture_size: u32,
+    max_array_texture_layers: u32,
+    max_color_attachments: u32,
+    max_draw_buffers: u32,
+    max_transform_feedback_separate_attribs: u32,
+    max_uniform_buffer_bindings: u32,
+    uniform_buffer_offset_alignment: u32,
+}
+
+impl WebGL2Limits {
+    fn new(context: &WebGLRenderingContext) -> Self {
+        let get = |parameter| {
+            let (sender, receiver) = webgl_channel().unwrap();
+            context.send_command(WebGLCommand::GetParameterInt(parameter, sender));
+            receiver.recv().unwrap() as u32
+        };
+        WebGL2Limits {
+            max_3d_texture_size: get(ParameterInt::Max3DTextureSize),
+            max_array_texture_layers: get(ParameterInt::MaxArrayTextureLayers),
+            max_color_attachments: get(ParameterInt::MaxColorAttachments),
+            max_draw_buffers: get(ParameterInt::MaxDrawBuffers),
+            max_transform_feedback_separate_attribs: get(ParameterInt::MaxTransformFeedbackSeparateAttribs),
+            max_uniform_buffer_bindings: get(ParameterInt::MaxUniformBufferBindings),
+            uniform_buffer_offset_alignment: get(ParameterInt::UniformBufferOffsetAlignment),
+        }
+    }
+}
+
+/// A buffer bound to an indexed binding point by bindBufferBase or
+/// bindBufferRange. A size of 0 means the whole buffer is bound.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+pub struct IndexedBinding {
+    buffer: MutNullableDom<WebGLBuffer>,
+    start: Cell<i64>,
+    size: Cell<i64>,
+}
+
+impl IndexedBinding {
+    #[allow(unrooted_must_root)]
+    pub fn new() -> Self {
+        IndexedBinding {
+            buffer: MutNullableDom::new(None),
+            start: Cell::new(0),
+            size: Cell::new(0),
+        }
+    }
+
+    pub fn buffer(&self) -> Option<DomRoot<WebGLBuffer>> {
+        self.buffer.get()
+    }
+
+    pub fn start(&self) -> i64 {
+        self.start.get()
+    }
+
+    pub fn size(&self) -> i64 {
+        self.size.get()
+    }
+
+    /// The number of bytes of the buffer available through this binding.
+    pub fn available_bytes(&self) -> u64 {
+        let buffer = match self.buffer.get() {
+            Some(buffer) => buffer,
+            None => return 0,
+        };
+        let available = (buffer.capacity() as u64).saturating_sub(self.start.get() as u64);
+        if self.size.get() > 0 {
+            cmp::min(available, self.size.get() as u64)
+        } else {
+            available
+        }
+    }
+
+    pub fn set(&self, buffer: Option<&WebGLBuffer>, start: i64, size: i64) {
+        if let Some(buffer) = buffer {
+            buffer.increment_attached_counter();
+        }
+        if let Some(old) = self.buffer.get() {
+            old.decrement_attached_counter();
+        }
+        self.buffer.set(buffer);
+        self.start.set(start);
+        self.size.set(size);
+    }
+
+    pub fn unbind(&self, buffer: &WebGLBuffer) {
+        if self.buffer.get().map_or(false, |b| buffer == &*b) {
+            self.clear();
+        }
+    }
+
+    pub fn clear(&self) {
+        self.set(None, 0, 0);
+    }
+}
+
+/// The size in bytes of a transform feedback varying of the given type.
+fn transform_feedback_type_size(type_: u32) -> u64 {
+    match type_ {
+        constants::FLOAT | constants::INT | constants2::UNSIGNED_INT => 4,
+        constants::FLOAT_VEC2 | constants::INT_VEC2 | constants2::UNSIGNED_INT_VEC2 => 8,
+        constants::FLOAT_VEC3 | constants::INT_VEC3 | constants2::UNSIGNED_INT_VEC3 => 12,
+        constants::FLOAT_VEC4 | constants::INT_VEC4 | constants2::UNSIGNED_INT_VEC4 |
+        constants::FLOAT_MAT2 => 16,
+        constants2::FLOAT_MAT2x3 | constants2::FLOAT_MAT3x2 => 24,
+        constants2::FLOAT_MAT2x4 | constants2::FLOAT_MAT4x2 => 32,
+        constants::FLOAT_MAT3 => 36,
+        constants2::FLOAT_MAT3x4 | constants2::FLOAT_MAT4x3 => 48,
+        constants::FLOAT_MAT4 => 64,
+        _ => 0,
+    }
+}
+
+/// The number of levels of a full mipmap chain for the given size.
+fn max_level_count(size: u32) -> u32 {
+    32 - size.leading_zeros()
 }
 
 impl WebGL2RenderingContext {
+    #[allow(unrooted_must_root)]
     fn new_inherited(
-        window: &Window,
-        canvas: &HTMLCanvasElement,
+        global: &GlobalScope,
+        canvas: &HTMLCanvasElementOrOffscreenCanvas,
         size: Size2D<i32>,
         attrs: GLContextAttributes
     ) -> Option<WebGL2RenderingContext> {
-        let base = WebGLRenderingContext::new(window, canvas, WebGLVersion::WebGL2, size, attrs)?;
+        let base = WebGLRenderingContext::new(global, canvas, WebGLVersion::WebGL2, size, attrs)?;
+        let limits = WebGL2Limits::new(&base);
+        let texture_unit_count = base.limits().max_combined_texture_image_units;
+        let default_transform_feedback =
+            WebGLTransformFeedback::new(&base, None, limits.max_transform_feedback_separate_attribs);
         Some(WebGL2RenderingContext {
             reflector_: Reflector::new(),
             base: Dom::from_ref(&*base),
+            occlusion_query: MutNullableDom::new(None),
+            primitives_query: MutNullableDom::new(None),
+            samplers: (0..texture_unit_count).map(|_| MutNullableDom::new(None)).collect(),
+            uniform_buffer_bindings: (0..limits.max_uniform_buffer_bindings)
+                .map(|_| IndexedBinding::new())
+                .collect(),
+            default_transform_feedback: Dom::from_ref(&*default_transform_feedback),
+            current_transform_feedback: MutNullableDom::new(None),
+            limits,
         })
     }
 
     #[allow(unrooted_must_root)]
     pub fn new(
-        window: &Window,
-        canvas: &HTMLCanvasElement,
+        global: &GlobalScope,
+        canvas: &HTMLCanvasElementOrOffscreenCanvas,
         size: Size2D<i32>,
         attrs: GLContextAttributes
     ) -> Option<DomRoot<WebGL2RenderingContext>> {
-        WebGL2RenderingContext::new_inherited(window, canvas, size, attrs).map(|ctx| {
-            reflect_dom_object(Box::new(ctx), window, WebGL2RenderingContextBinding::Wrap)
+        WebGL2RenderingContext::new_inherited(global, canvas, size, attrs).map(|ctx| {
+            reflect_dom_object(Box::new(ctx), global, WebGL2RenderingContextBinding::Wrap)
         })
     }
 }
@@ -80,11 +240,167 @@ impl WebGL2RenderingContext {
     pub fn base_context(&self) -> DomRoot<WebGLRenderingContext> {
         DomRoot::from_ref(&*self.base)
     }
+
+    fn current_transform_feedback(&self) -> DomRoot<WebGLTransformFeedback> {
+        self.current_transform_feedback.or_init(|| DomRoot::from_ref(&*self.default_transform_feedback))
+    }
+
+    /// The slot holding the active query for `target`, ANY_SAMPLES_PASSED and
+    /// ANY_SAMPLES_PASSED_CONSERVATIVE share the same one.
+    fn query_slot(&self, target: u32) -> WebGLResult<&MutNullableDom<WebGLQuery>> {
+        match target {
+            constants2::ANY_SAMPLES_PASSED |
+            constants2::ANY_SAMPLES_PASSED_CONSERVATIVE => Ok(&self.occlusion_query),
+            constants2::TRANSFORM_FEEDBACK_PRIMITIVES_WRITTEN => Ok(&self.primitives_query),
+            _ => Err(InvalidEnum),
+        }
+    }
+
+    /// Checks that a draw call is compatible with the active transform
+    /// feedback, and accounts for the vertices it will capture.
+    fn validate_transform_feedback_draw(&self, mode: u32, count: i32, instance_count: i32) -> WebGLResult<()> {
+        let transform_feedback = self.current_transform_feedback();
+        if !transform_feedback.is_capturing() {
+            return Ok(());
+        }
+        if transform_feedback.active_mode() != Some(mode) {
+            return Err(InvalidOperation);
+        }
+        if count < 0 || instance_count < 0 {
+            // The draw call itself reports the error.
+            return Ok(());
+        }
+        let vertices_per_primitive = match mode {
+            constants::POINTS => 1,
+            constants::LINES => 2,
+            _ => 3,
+        };
+        let vertices = (count as u64 / vertices_per_primitive) * vertices_per_primitive;
+        transform_feedback.record_vertices(vertices * instance_count as u64)
+    }
+
+    /// The number of vertices that the buffers bound to `transform_feedback`
+    /// can hold with the varyings of `program`.
+    fn transform_feedback_capacity(
+        &self,
+        transform_feedback: &WebGLTransformFeedback,
+        program: &WebGLProgram,
+    ) -> WebGLResult<u64> {
+        let (varying_count, buffer_mode) = program.linked_transform_feedback_varyings();
+        if varying_count == 0 {
+            return Err(InvalidOperation);
+        }
+        let separate = buffer_mode == constants2::SEPARATE_ATTRIBS;
+        let buffer_count = if separate { varying_count } else { 1 };
+        if !transform_feedback.has_buffers_bound(buffer_count) {
+            return Err(InvalidOperation);
+        }
+        let mut strides = vec![0; buffer_count];
+        for index in 0..varying_count {
+            let (sender, receiver) = webgl_channel().unwrap();
+            self.base.send_command(
+                WebGLCommand::GetTransformFeedbackVarying(program.id(), index as u32, sender),
+            );
+            let (size, type_, _) = receiver.recv().unwrap();
+            let stride = &mut strides[if separate { index } else { 0 }];
+            *stride += size as u64 * transform_feedback_type_size(type_);
+        }
+        Ok(strides.iter().enumerate().map(|(index, &stride)| {
+            let binding = transform_feedback.buffer_binding(index as u32).unwrap();
+            if stride == 0 { 0 } else { binding.available_bytes() / stride }
+        }).min().unwrap_or(0))
+    }
+
+    /// glBindBufferBase and glBindBufferRange, `range` is `None` for the
+    /// former.
+    fn bind_indexed_buffer(
+        &self,
+        target: u32,
+        index: u32,
+        buffer: Option<&WebGLBuffer>,
+        range: Option<(i64, i64)>,
+    ) -> WebGLResult<()> {
+        if let Some(buffer) = buffer {
+            self.base.validate_ownership(buffer)?;
+            if buffer.is_marked_for_deletion() {
+                return Err(InvalidOperation);
+            }
+        }
+        let transform_feedback = self.current_transform_feedback();
+        let (binding, alignment) = match target {
+            constants2::UNIFORM_BUFFER => (
+                self.uniform_buffer_bindings.get(index as usize),
+                self.limits.uniform_buffer_offset_alignment as i64,
+            ),
+            constants2::TRANSFORM_FEEDBACK_BUFFER => {
+                if transform_feedback.is_active() {
+                    return Err(InvalidOperation);
+                }
+                (transform_feedback.buffer_binding(index), 4)
+            },
+            _ => return Err(InvalidEnum),
+        };
+        let binding = binding.ok_or(InvalidValue)?;
+        let (start, size) = range.unwrap_or((0, 0));
+        if range.is_some() && buffer.is_some() {
+            if start < 0 || size <= 0 {
+                return Err(InvalidValue);
+            }
+            // https://www.khronos.org/registry/webgl/specs/latest/2.0/#BUFFER_OBJECT_BINDING
+            if start % alignment != 0 {
+                return Err(InvalidValue);
+            }
+            if target == constants2::TRANSFORM_FEEDBACK_BUFFER && size % 4 != 0 {
+                return Err(InvalidValue);
+            }
+        }
+        if let Some(buffer) = buffer {
+            buffer.set_target(target)?;
+        }
+
+        let id = buffer.map(|b| b.id());
+        self.base.send_command(match range {
+            Some((start, size)) => WebGLCommand::BindBufferRange(target, index, id, start, size),
+            None => WebGLCommand::BindBufferBase(target, index, id),
+        });
+        binding.set(buffer, start, size);
+        self.base.set_generic_buffer_binding(target, buffer);
+        Ok(())
+    }
+
+    /// Validates the parameters common to texStorage2D and texStorage3D,
+    /// returning the bound texture along with the format and type the
+    /// internal format maps to.
+    fn validate_tex_storage(
+        &self,
+        target: u32,
+        levels: i32,
+        internal_format: u32,
+        size: &[i32],
+        max_size: u32,
+    ) -> WebGLResult<(DomRoot<WebGLTexture>, TexFormat, TexDataType)> {
+        let texture = self.base.bound_texture(target).ok_or(InvalidOperation)?;
+        if texture.is_immutable() {
+            return Err(InvalidOperation);
+        }
+        let (format, data_type) = sized_internal_format_info(internal_format).ok_or(InvalidEnum)?;
+        if levels < 1 || size.iter().any(|&dimension| dimension < 1) {
+            return Err(InvalidValue);
+        }
+        if size.iter().any(|&dimension| dimension as u32 > max_size) {
+            return Err(InvalidValue);
+        }
+        let largest = size.iter().cloned().max().unwrap() as u32;
+        if levels as u32 > max_level_count(largest) {
+            return Err(InvalidOperation);
+        }
+        Ok((texture, format, data_type))
+    }
 }
 
 impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.1
-    fn Canvas(&self) -> DomRoot<HTMLCanvasElement> {
+    fn Canvas(&self) -> HTMLCanvasElementOrOffscreenCanvas {
         self.base.Canvas()
     }
 
@@ -117,7 +433,44 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
     #[allow(unsafe_code)]
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
     unsafe fn GetParameter(&self, cx: *mut JSContext, parameter: u32) -> JSVal {
-        self.base.GetParameter(cx, parameter)
+        rooted!(in(cx) let mut rval = NullValue());
+        match parameter {
+            constants2::TEXTURE_BINDING_3D => {
+                self.base.bound_texture(constants2::TEXTURE_3D).to_jsval(cx, rval.handle_mut());
+            },
+            constants2::TEXTURE_BINDING_2D_ARRAY => {
+                self.base.bound_texture(constants2::TEXTURE_2D_ARRAY).to_jsval(cx, rval.handle_mut());
+            },
+            constants2::UNIFORM_BUFFER_BINDING => {
+                self.base.bound_buffer(constants2::UNIFORM_BUFFER).unwrap().to_jsval(cx, rval.handle_mut());
+            },
+            constants2::TRANSFORM_FEEDBACK_BUFFER_BINDING => {
+                self.base
+                    .bound_buffer(constants2::TRANSFORM_FEEDBACK_BUFFER)
+                    .unwrap()
+                    .to_jsval(cx, rval.handle_mut());
+            },
+            constants2::TRANSFORM_FEEDBACK_BINDING => {
+                let transform_feedback = self.current_transform_feedback();
+                if transform_feedback.id().is_some() {
+                    transform_feedback.to_jsval(cx, rval.handle_mut());
+                }
+            },
+            constants2::TRANSFORM_FEEDBACK_ACTIVE => {
+                return BooleanValue(self.current_transform_feedback().is_active());
+            },
+            constants2::TRANSFORM_FEEDBACK_PAUSED => {
+                return BooleanValue(self.current_transform_feedback().is_paused());
+            },
+            constants2::SAMPLER_BINDING => {
+                let unit = self.base.active_texture_unit_index() as usize;
+                self.samplers[unit].get().to_jsval(cx, rval.handle_mut());
+            },
+            // Waiting on the client is never allowed.
+            constants2::MAX_CLIENT_WAIT_TIMEOUT_WEBGL => return DoubleValue(0.),
+            _ => return self.base.GetParameter(cx, parameter),
+        }
+        rval.get()
     }
 
     #[allow(unsafe_code)]
@@ -378,6 +731,15 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
     fn DeleteBuffer(&self, buffer: Option<&WebGLBuffer>) {
+        if let Some(buffer) = buffer {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(buffer), return);
+            if !buffer.is_marked_for_deletion() {
+                for binding in self.uniform_buffer_bindings.iter() {
+                    binding.unbind(buffer);
+                }
+                self.current_transform_feedback().unbind_buffer(buffer);
+            }
+        }
         self.base.DeleteBuffer(buffer)
     }
 
@@ -408,11 +770,15 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.11
     fn DrawArrays(&self, mode: u32, first: i32, count: i32) {
+        handle_potential_webgl_error!(self.base, self.validate_transform_feedback_draw(mode, count, 1), return);
         self.base.DrawArrays(mode, first, count)
     }
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.11
     fn DrawElements(&self, mode: u32, count: i32, type_: u32, offset: i64) {
+        if self.current_transform_feedback().is_capturing() {
+            return self.base.webgl_error(InvalidOperation);
+        }
         self.base.DrawElements(mode, count, type_, offset)
     }
 
@@ -449,7 +815,27 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
     #[allow(unsafe_code)]
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
     unsafe fn GetProgramParameter(&self, cx: *mut JSContext, program: &WebGLProgram, param_id: u32) -> JSVal {
-        self.base.GetProgramParameter(cx, program, param_id)
+        match param_id {
+            constants2::TRANSFORM_FEEDBACK_BUFFER_MODE |
+            constants2::TRANSFORM_FEEDBACK_VARYINGS |
+            constants2::ACTIVE_UNIFORM_BLOCKS => {},
+            _ => return self.base.GetProgramParameter(cx, program, param_id),
+        }
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(program), return NullValue());
+        if program.is_deleted() {
+            self.base.webgl_error(InvalidOperation);
+            return NullValue();
+        }
+        let (varying_count, buffer_mode) = program.linked_transform_feedback_varyings();
+        match param_id {
+            constants2::TRANSFORM_FEEDBACK_BUFFER_MODE => UInt32Value(buffer_mode),
+            constants2::TRANSFORM_FEEDBACK_VARYINGS => Int32Value(varying_count as i32),
+            _ => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.base.send_command(WebGLCommand::GetProgramActiveUniformBlocks(program.id(), sender));
+                Int32Value(receiver.recv().unwrap())
+            },
+        }
     }
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
@@ -595,6 +981,10 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
     fn LinkProgram(&self, program: &WebGLProgram) {
+        let transform_feedback = self.current_transform_feedback();
+        if transform_feedback.program().map_or(false, |p| &*p == program) {
+            return self.base.webgl_error(InvalidOperation);
+        }
         self.base.LinkProgram(program)
     }
 
@@ -779,6 +1169,9 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
     fn UseProgram(&self, program: Option<&WebGLProgram>) {
+        if self.current_transform_feedback().is_capturing() {
+            return self.base.webgl_error(InvalidOperation);
+        }
         self.base.UseProgram(program)
     }
 
@@ -860,7 +1253,7 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
         internal_format: u32,
         format: u32,
         data_type: u32,
-        source: ImageDataOrHTMLImageElementOrHTMLCanvasElementOrHTMLVideoElement,
+        source: TexImageSource,
     ) -> ErrorResult {
         self.base.TexImage2D_(target, level, internal_format, format, data_type, source)
     }
@@ -901,7 +1294,7 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
         yoffset: i32,
         format: u32,
         data_type: u32,
-        source: ImageDataOrHTMLImageElementOrHTMLCanvasElementOrHTMLVideoElement,
+        source: TexImageSource,
     ) -> ErrorResult {
         self.base.TexSubImage2D_(target, level, xoffset, yoffset, format, data_type, source)
     }
@@ -957,6 +1350,11 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
         count: i32,
         primcount: i32,
     ) {
+        handle_potential_webgl_error!(
+            self.base,
+            self.validate_transform_feedback_draw(mode, count, primcount),
+            return
+        );
         self.base.draw_arrays_instanced(mode, first, count, primcount);
     }
 
@@ -969,6 +1367,9 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
         offset: i64,
         primcount: i32,
     ) {
+        if self.current_transform_feedback().is_capturing() {
+            return self.base.webgl_error(InvalidOperation);
+        }
         self.base.draw_elements_instanced(mode, count, type_, offset, primcount);
     }
 
@@ -976,6 +1377,777 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
     fn VertexAttribDivisor(&self, index: u32, divisor: u32) {
         self.base.vertex_attrib_divisor(index, divisor);
     }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.6
+    fn TexStorage2D(&self, target: u32, levels: i32, internal_format: u32, width: i32, height: i32) {
+        let max_size = match target {
+            constants::TEXTURE_2D => self.base.limits().max_tex_size,
+            constants::TEXTURE_CUBE_MAP => {
+                if width != height {
+                    return self.base.webgl_error(InvalidValue);
+                }
+                self.base.limits().max_cube_map_tex_size
+            },
+            _ => return self.base.webgl_error(InvalidEnum),
+        };
+        let (texture, format, data_type) = handle_potential_webgl_error!(
+            self.base,
+            self.validate_tex_storage(target, levels, internal_format, &[width, height], max_size),
+            return
+        );
+        self.base.send_command(WebGLCommand::TexStorage2D(target, levels, internal_format, width, height));
+        texture.initialize_storage(levels as u32, width as u32, height as u32, 1, format, data_type);
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.6
+    fn TexStorage3D(
+        &self,
+        target: u32,
+        levels: i32,
+        internal_format: u32,
+        width: i32,
+        height: i32,
+        depth: i32,
+    ) {
+        let validated = match TexImage3DTarget::from_gl_constant(target) {
+            Some(TexImage3DTarget::Texture3D) => {
+                let max_size = self.limits.max_3d_texture_size;
+                self.validate_tex_storage(target, levels, internal_format, &[width, height, depth], max_size)
+            },
+            Some(TexImage3DTarget::Texture2DArray) => {
+                // The layers of an array texture are not part of the mipmap chain.
+                if depth < 1 {
+                    return self.base.webgl_error(InvalidValue);
+                }
+                if depth as u32 > self.limits.max_array_texture_layers {
+                    return self.base.webgl_error(InvalidValue);
+                }
+                let max_size = self.base.limits().max_tex_size;
+                self.validate_tex_storage(target, levels, internal_format, &[width, height], max_size)
+            },
+            None => return self.base.webgl_error(InvalidEnum),
+        };
+        let (texture, format, data_type) = handle_potential_webgl_error!(self.base, validated, return);
+        self.base.send_command(
+            WebGLCommand::TexStorage3D(target, levels, internal_format, width, height, depth),
+        );
+        texture.initialize_storage(levels as u32, width as u32, height as u32, depth as u32, format, data_type);
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.6
+    fn TexImage3D(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        border: i32,
+        format: u32,
+        data_type: u32,
+        mut src_data: CustomAutoRooterGuard<Option<ArrayBufferView>>,
+    ) {
+        let validator = TexImage3DValidator::new(
+            &self.base,
+            self.limits.max_3d_texture_size,
+            self.limits.max_array_texture_layers,
+            target,
+            level,
+            internal_format as u32,
+            width,
+            height,
+            depth,
+            border,
+            format,
+            data_type,
+        );
+        let TexImage3DValidatorResult {
+            width,
+            height,
+            depth,
+            level,
+            texture,
+            target,
+            format,
+            data_type,
+        } = match validator.validate() {
+            Ok(result) => result,
+            Err(_) => return, // NB: The validator sets the correct error for us.
+        };
+
+        // https://www.khronos.org/registry/webgl/specs/latest/2.0/#PIXEL_STORE_PARAMETERS
+        if src_data.is_some() && self.base.has_unpacking_transforms() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+
+        // The images of a 3D texture are stored one after the other, so the
+        // data is laid out as a single 2D image of height * depth rows.
+        let unpacking_alignment = self.base.texture_unpacking_alignment();
+        let expected_byte_length = match self.base.validate_tex_image_2d_data(
+            width,
+            height * depth,
+            format,
+            data_type,
+            unpacking_alignment,
+            &*src_data,
+        ) {
+            Ok(byte_length) => byte_length,
+            Err(()) => return,
+        };
+
+        // If data is null, a buffer of sufficient size initialized to 0 is
+        // passed.
+        let buff = match *src_data {
+            None => vec![0u8; expected_byte_length as usize],
+            Some(ref mut data) => data.to_vec(),
+        };
+        if buff.len() < expected_byte_length as usize {
+            return self.base.webgl_error(InvalidOperation);
+        }
+
+        self.base.send_command(WebGLCommand::PixelStorei(constants::UNPACK_ALIGNMENT, unpacking_alignment as i32));
+        self.base.send_command(WebGLCommand::TexImage3D {
+            target: target.as_gl_constant(),
+            level: level as i32,
+            internal_format,
+            width: width as i32,
+            height: height as i32,
+            depth: depth as i32,
+            format: format.as_gl_constant(),
+            data_type: data_type.as_gl_constant(),
+            data: buff.into(),
+        });
+        texture.initialize_3d(width, height, depth, format, level, Some(data_type));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.6
+    fn TexSubImage3D(
+        &self,
+        target: u32,
+        level: i32,
+        xoffset: i32,
+        yoffset: i32,
+        zoffset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        mut src_data: CustomAutoRooterGuard<Option<ArrayBufferView>>,
+        src_offset: u32,
+    ) {
+        if TexImage3DTarget::from_gl_constant(target).is_none() {
+            return self.base.webgl_error(InvalidEnum);
+        }
+        let texture = match self.base.bound_texture(target) {
+            Some(texture) => texture,
+            None => return self.base.webgl_error(InvalidOperation),
+        };
+        let format = TexFormat::from_gl_constant(format);
+        let data_type = TexDataType::from_gl_constant(data_type);
+        let (format, data_type) = match (format, data_type) {
+            (Some(format), Some(data_type)) if data_type != TexDataType::HalfFloat => (format, data_type),
+            _ => return self.base.webgl_error(InvalidEnum),
+        };
+        if level < 0 || xoffset < 0 || yoffset < 0 || zoffset < 0 || width < 0 || height < 0 || depth < 0 {
+            return self.base.webgl_error(InvalidValue);
+        }
+        let image_info = texture.image_info_at_face(0, level as u32);
+        if !image_info.is_initialized() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        if xoffset as u32 + width as u32 > image_info.width() ||
+           yoffset as u32 + height as u32 > image_info.height() ||
+           zoffset as u32 + depth as u32 > image_info.depth() {
+            return self.base.webgl_error(InvalidValue);
+        }
+        // Packed sized formats may also be updated from bytes.
+        let type_matches = match (image_info.data_type(), data_type) {
+            (Some(TexDataType::UnsignedShort4444), TexDataType::UnsignedByte) |
+            (Some(TexDataType::UnsignedShort5551), TexDataType::UnsignedByte) |
+            (Some(TexDataType::UnsignedShort565), TexDataType::UnsignedByte) => true,
+            (stored, data_type) => stored == Some(data_type),
+        };
+        if image_info.internal_format() != Some(format) || !type_matches {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        if src_data.is_none() {
+            return self.base.webgl_error(InvalidValue);
+        }
+        if self.base.has_unpacking_transforms() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+
+        let unpacking_alignment = self.base.texture_unpacking_alignment();
+        let expected_byte_length = match self.base.validate_tex_image_2d_data(
+            width as u32,
+            (height * depth) as u32,
+            format,
+            data_type,
+            unpacking_alignment,
+            &*src_data,
+        ) {
+            Ok(byte_length) => byte_length,
+            Err(()) => return,
+        };
+
+        // srcOffset is expressed in elements of the source view.
+        let src_byte_offset = src_offset as usize * data_type.element_size() as usize;
+        let buff = match *src_data {
+            Some(ref mut data) => data.to_vec(),
+            None => unreachable!(),
+        };
+        if src_byte_offset > buff.len() {
+            return self.base.webgl_error(InvalidValue);
+        }
+        if buff.len() - src_byte_offset < expected_byte_length as usize {
+            return self.base.webgl_error(InvalidOperation);
+        }
+
+        self.base.send_command(WebGLCommand::PixelStorei(constants::UNPACK_ALIGNMENT, unpacking_alignment as i32));
+        self.base.send_command(WebGLCommand::TexSubImage3D {
+            target,
+            level,
+            xoffset,
+            yoffset,
+            zoffset,
+            width,
+            height,
+            depth,
+            format: format.as_gl_constant(),
+            data_type: data_type.as_gl_constant(),
+            data: buff[src_byte_offset..].to_vec().into(),
+        });
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.11
+    fn DrawBuffers(&self, buffers: Vec<u32>) {
+        if self.base.bound_framebuffer().is_none() {
+            // The default framebuffer only has a back buffer.
+            if buffers.len() != 1 {
+                return self.base.webgl_error(InvalidOperation);
+            }
+            let buffer = match buffers[0] {
+                constants::BACK => constants::COLOR_ATTACHMENT0,
+                constants::NONE => constants::NONE,
+                _ => return self.base.webgl_error(InvalidOperation),
+            };
+            // Our default framebuffer is backed by an offscreen framebuffer
+            // object, whose only color attachment is the first one.
+            return self.base.send_command(WebGLCommand::DrawBuffers(vec![buffer]));
+        }
+
+        if buffers.len() > self.limits.max_draw_buffers as usize {
+            return self.base.webgl_error(InvalidValue);
+        }
+        for (index, &buffer) in buffers.iter().enumerate() {
+            if buffer == constants::NONE {
+                continue;
+            }
+            if buffer != constants::COLOR_ATTACHMENT0 + index as u32 ||
+               index as u32 >= self.limits.max_color_attachments {
+                return self.base.webgl_error(InvalidOperation);
+            }
+        }
+        self.base.send_command(WebGLCommand::DrawBuffers(buffers));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.12
+    fn CreateQuery(&self) -> Option<DomRoot<WebGLQuery>> {
+        WebGLQuery::maybe_new(&self.base)
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.12
+    fn DeleteQuery(&self, query: Option<&WebGLQuery>) {
+        if let Some(query) = query {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(query), return);
+            for slot in &[&self.occlusion_query, &self.primitives_query] {
+                if slot.get().map_or(false, |q| query == &*q) {
+                    slot.set(None);
+                }
+            }
+            query.delete();
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.12
+    fn IsQuery(&self, query: Option<&WebGLQuery>) -> bool {
+        query.map_or(false, |query| {
+            self.base.validate_ownership(query).is_ok() && !query.is_deleted() && query.target().is_some()
+        })
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.12
+    fn BeginQuery(&self, target: u32, query: &WebGLQuery) {
+        let slot = handle_potential_webgl_error!(self.base, self.query_slot(target), return);
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(query), return);
+        if slot.get().is_some() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        handle_potential_webgl_error!(self.base, query.begin(target), return);
+        slot.set(Some(query));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.12
+    fn EndQuery(&self, target: u32) {
+        let slot = handle_potential_webgl_error!(self.base, self.query_slot(target), return);
+        let query = match slot.get() {
+            Some(ref query) if query.target() == Some(target) => query.clone(),
+            _ => return self.base.webgl_error(InvalidOperation),
+        };
+        query.end();
+        slot.set(None);
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.12
+    fn GetQuery(&self, target: u32, pname: u32) -> Option<DomRoot<WebGLQuery>> {
+        let slot = handle_potential_webgl_error!(self.base, self.query_slot(target), return None);
+        if pname != constants2::CURRENT_QUERY {
+            self.base.webgl_error(InvalidEnum);
+            return None;
+        }
+        slot.get().and_then(|query| if query.target() == Some(target) { Some(query) } else { None })
+    }
+
+    #[allow(unsafe_code)]
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.12
+    unsafe fn GetQueryParameter(&self, _cx: *mut JSContext, query: &WebGLQuery, pname: u32) -> JSVal {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(query), return NullValue());
+        match handle_potential_webgl_error!(self.base, query.get_parameter(pname), return NullValue()) {
+            QueryParameterValue::Result(value) => UInt32Value(value),
+            QueryParameterValue::Available(value) => BooleanValue(value),
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.13
+    fn CreateSampler(&self) -> Option<DomRoot<WebGLSampler>> {
+        WebGLSampler::maybe_new(&self.base)
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.13
+    fn DeleteSampler(&self, sampler: Option<&WebGLSampler>) {
+        if let Some(sampler) = sampler {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(sampler), return);
+            // Deleting a sampler unbinds it from all the texture units.
+            for slot in self.samplers.iter() {
+                if slot.get().map_or(false, |s| sampler == &*s) {
+                    slot.set(None);
+                }
+            }
+            sampler.delete();
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.13
+    fn IsSampler(&self, sampler: Option<&WebGLSampler>) -> bool {
+        sampler.map_or(false, |sampler| {
+            self.base.validate_ownership(sampler).is_ok() && !sampler.is_deleted()
+        })
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.13
+    fn BindSampler(&self, unit: u32, sampler: Option<&WebGLSampler>) {
+        if let Some(sampler) = sampler {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(sampler), return);
+            if sampler.is_deleted() {
+                return self.base.webgl_error(InvalidOperation);
+            }
+        }
+        let slot = match self.samplers.get(unit as usize) {
+            Some(slot) => slot,
+            None => return self.base.webgl_error(InvalidValue),
+        };
+        self.base.send_command(WebGLCommand::BindSampler(unit, sampler.map(|s| s.id())));
+        slot.set(sampler);
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.13
+    fn SamplerParameteri(&self, sampler: &WebGLSampler, pname: u32, param: i32) {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(sampler), return);
+        handle_potential_webgl_error!(self.base, sampler.set_parameter(pname, TexParameterValue::Int(param)));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.13
+    fn SamplerParameterf(&self, sampler: &WebGLSampler, pname: u32, param: f32) {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(sampler), return);
+        handle_potential_webgl_error!(self.base, sampler.set_parameter(pname, TexParameterValue::Float(param)));
+    }
+
+    #[allow(unsafe_code)]
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.13
+    unsafe fn GetSamplerParameter(&self, _cx: *mut JSContext, sampler: &WebGLSampler, pname: u32) -> JSVal {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(sampler), return NullValue());
+        match handle_potential_webgl_error!(self.base, sampler.get_parameter(pname), return NullValue()) {
+            TexParameterValue::Int(value) => UInt32Value(value as u32),
+            TexParameterValue::Float(value) => DoubleValue(value as f64),
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.14
+    fn FenceSync(&self, condition: u32, flags: u32) -> Option<DomRoot<WebGLSync>> {
+        if condition != constants2::SYNC_GPU_COMMANDS_COMPLETE {
+            self.base.webgl_error(InvalidEnum);
+            return None;
+        }
+        if flags != 0 {
+            self.base.webgl_error(InvalidValue);
+            return None;
+        }
+        WebGLSync::maybe_fence(&self.base)
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.14
+    fn IsSync(&self, sync: Option<&WebGLSync>) -> bool {
+        sync.map_or(false, |sync| {
+            self.base.validate_ownership(sync).is_ok() && !sync.is_deleted()
+        })
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.14
+    fn DeleteSync(&self, sync: Option<&WebGLSync>) {
+        if let Some(sync) = sync {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(sync), return);
+            sync.delete();
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.14
+    fn ClientWaitSync(&self, sync: &WebGLSync, flags: u32, timeout: u64) -> u32 {
+        handle_potential_webgl_error!(
+            self.base,
+            self.base.validate_ownership(sync),
+            return constants2::WAIT_FAILED
+        );
+        if sync.is_deleted() {
+            self.base.webgl_error(InvalidOperation);
+            return constants2::WAIT_FAILED;
+        }
+        if flags != 0 && flags != constants2::SYNC_FLUSH_COMMANDS_BIT {
+            self.base.webgl_error(InvalidValue);
+            return constants2::WAIT_FAILED;
+        }
+        // Blocking the script thread is not allowed, the timeout must not
+        // exceed MAX_CLIENT_WAIT_TIMEOUT_WEBGL.
+        if timeout > 0 {
+            self.base.webgl_error(InvalidOperation);
+            return constants2::WAIT_FAILED;
+        }
+        sync.client_wait(flags, timeout)
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.14
+    fn WaitSync(&self, sync: &WebGLSync, flags: u32, timeout: i64) {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(sync), return);
+        if sync.is_deleted() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        if flags != 0 || timeout != constants2::TIMEOUT_IGNORED {
+            return self.base.webgl_error(InvalidValue);
+        }
+        sync.wait();
+    }
+
+    #[allow(unsafe_code)]
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.14
+    unsafe fn GetSyncParameter(&self, _cx: *mut JSContext, sync: &WebGLSync, pname: u32) -> JSVal {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(sync), return NullValue());
+        if sync.is_deleted() {
+            self.base.webgl_error(InvalidOperation);
+            return NullValue();
+        }
+        match pname {
+            constants2::OBJECT_TYPE => UInt32Value(constants2::SYNC_FENCE),
+            constants2::SYNC_CONDITION => UInt32Value(constants2::SYNC_GPU_COMMANDS_COMPLETE),
+            constants2::SYNC_FLAGS => UInt32Value(0),
+            constants2::SYNC_STATUS => {
+                match sync.client_wait(0, 0) {
+                    constants2::ALREADY_SIGNALED |
+                    constants2::CONDITION_SATISFIED => UInt32Value(constants2::SIGNALED),
+                    _ => UInt32Value(constants2::UNSIGNALED),
+                }
+            },
+            _ => {
+                self.base.webgl_error(InvalidEnum);
+                NullValue()
+            },
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn CreateTransformFeedback(&self) -> Option<DomRoot<WebGLTransformFeedback>> {
+        WebGLTransformFeedback::maybe_new(&self.base, self.limits.max_transform_feedback_separate_attribs)
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn DeleteTransformFeedback(&self, transform_feedback: Option<&WebGLTransformFeedback>) {
+        if let Some(transform_feedback) = transform_feedback {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(transform_feedback), return);
+            // The default transform feedback object has no id and should never
+            // be passed around.
+            assert!(transform_feedback.id().is_some());
+            if transform_feedback.is_deleted() {
+                return;
+            }
+            if transform_feedback.is_active() {
+                return self.base.webgl_error(InvalidOperation);
+            }
+            if transform_feedback == &*self.current_transform_feedback() {
+                // Setting it to None will make current_transform_feedback()
+                // reset it to the default one next time it is called.
+                self.current_transform_feedback.set(None);
+                self.base.send_command(WebGLCommand::BindTransformFeedback(None));
+            }
+            transform_feedback.delete();
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn IsTransformFeedback(&self, transform_feedback: Option<&WebGLTransformFeedback>) -> bool {
+        transform_feedback.map_or(false, |transform_feedback| {
+            self.base.validate_ownership(transform_feedback).is_ok() &&
+                transform_feedback.ever_bound() &&
+                !transform_feedback.is_deleted()
+        })
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn BindTransformFeedback(&self, target: u32, transform_feedback: Option<&WebGLTransformFeedback>) {
+        if target != constants2::TRANSFORM_FEEDBACK {
+            return self.base.webgl_error(InvalidEnum);
+        }
+        if let Some(transform_feedback) = transform_feedback {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(transform_feedback), return);
+            if transform_feedback.is_deleted() {
+                return self.base.webgl_error(InvalidOperation);
+            }
+        }
+        if self.current_transform_feedback().is_capturing() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        match transform_feedback {
+            Some(transform_feedback) => {
+                transform_feedback.set_ever_bound();
+                self.base.send_command(WebGLCommand::BindTransformFeedback(transform_feedback.id()));
+                self.current_transform_feedback.set(Some(transform_feedback));
+            },
+            None => {
+                self.base.send_command(WebGLCommand::BindTransformFeedback(None));
+                self.current_transform_feedback.set(None);
+            },
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn BeginTransformFeedback(&self, primitive_mode: u32) {
+        match primitive_mode {
+            constants::POINTS | constants::LINES | constants::TRIANGLES => {},
+            _ => return self.base.webgl_error(InvalidEnum),
+        }
+        let program = match self.base.current_program() {
+            Some(program) => program,
+            None => return self.base.webgl_error(InvalidOperation),
+        };
+        let transform_feedback = self.current_transform_feedback();
+        if transform_feedback.is_active() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        let max_vertices = handle_potential_webgl_error!(
+            self.base,
+            self.transform_feedback_capacity(&transform_feedback, &program),
+            return
+        );
+        handle_potential_webgl_error!(self.base, transform_feedback.begin(primitive_mode, &program, max_vertices));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn EndTransformFeedback(&self) {
+        handle_potential_webgl_error!(self.base, self.current_transform_feedback().end());
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn PauseTransformFeedback(&self) {
+        handle_potential_webgl_error!(self.base, self.current_transform_feedback().pause());
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn ResumeTransformFeedback(&self) {
+        let transform_feedback = self.current_transform_feedback();
+        // The program used when transform feedback began must still be current.
+        if transform_feedback.program() != self.base.current_program() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        handle_potential_webgl_error!(self.base, transform_feedback.resume());
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn TransformFeedbackVaryings(&self, program: &WebGLProgram, varyings: Vec<DOMString>, buffer_mode: u32) {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(program), return);
+        if program.is_deleted() {
+            return self.base.webgl_error(InvalidValue);
+        }
+        match buffer_mode {
+            constants2::INTERLEAVED_ATTRIBS => {},
+            constants2::SEPARATE_ATTRIBS => {
+                if varyings.len() > self.limits.max_transform_feedback_separate_attribs as usize {
+                    return self.base.webgl_error(InvalidValue);
+                }
+            },
+            _ => return self.base.webgl_error(InvalidEnum),
+        }
+        program.set_transform_feedback_varyings(varyings.into_iter().map(String::from).collect(), buffer_mode);
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn GetTransformFeedbackVarying(&self, program: &WebGLProgram, index: u32) -> Option<DomRoot<WebGLActiveInfo>> {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(program), return None);
+        handle_potential_webgl_error!(self.base, program.get_transform_feedback_varying(index).map(Some), None)
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.16
+    fn BindBufferBase(&self, target: u32, index: u32, buffer: Option<&WebGLBuffer>) {
+        handle_potential_webgl_error!(self.base, self.bind_indexed_buffer(target, index, buffer, None));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.16
+    fn BindBufferRange(&self, target: u32, index: u32, buffer: Option<&WebGLBuffer>, offset: i64, size: i64) {
+        handle_potential_webgl_error!(
+            self.base,
+            self.bind_indexed_buffer(target, index, buffer, Some((offset, size)))
+        );
+    }
+
+    #[allow(unsafe_code)]
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.16
+    unsafe fn GetIndexedParameter(&self, cx: *mut JSContext, target: u32, index: u32) -> JSVal {
+        let transform_feedback = self.current_transform_feedback();
+        let binding = match target {
+            constants2::UNIFORM_BUFFER_BINDING |
+            constants2::UNIFORM_BUFFER_START |
+            constants2::UNIFORM_BUFFER_SIZE => self.uniform_buffer_bindings.get(index as usize),
+            constants2::TRANSFORM_FEEDBACK_BUFFER_BINDING |
+            constants2::TRANSFORM_FEEDBACK_BUFFER_START |
+            constants2::TRANSFORM_FEEDBACK_BUFFER_SIZE => transform_feedback.buffer_binding(index),
+            _ => {
+                self.base.webgl_error(InvalidEnum);
+                return NullValue();
+            },
+        };
+        let binding = match binding {
+            Some(binding) => binding,
+            None => {
+                self.base.webgl_error(InvalidValue);
+                return NullValue();
+            },
+        };
+        match target {
+            constants2::UNIFORM_BUFFER_START |
+            constants2::TRANSFORM_FEEDBACK_BUFFER_START => DoubleValue(binding.start() as f64),
+            constants2::UNIFORM_BUFFER_SIZE |
+            constants2::TRANSFORM_FEEDBACK_BUFFER_SIZE => DoubleValue(binding.size() as f64),
+            _ => {
+                rooted!(in(cx) let mut rval = NullValue());
+                binding.buffer().to_jsval(cx, rval.handle_mut());
+                rval.get()
+            },
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.16
+    fn GetUniformIndices(&self, program: &WebGLProgram, names: Vec<DOMString>) -> Option<Vec<u32>> {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(program), return None);
+        let names = names.into_iter().map(String::from).collect();
+        handle_potential_webgl_error!(self.base, program.get_uniform_indices(names).map(Some), None)
+    }
+
+    #[allow(unsafe_code)]
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.16
+    unsafe fn GetActiveUniforms(
+        &self,
+        cx: *mut JSContext,
+        program: &WebGLProgram,
+        indices: Vec<u32>,
+        pname: u32,
+    ) -> JSVal {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(program), return NullValue());
+        let values = handle_potential_webgl_error!(
+            self.base,
+            program.get_active_uniforms(indices, pname),
+            return NullValue()
+        );
+        rooted!(in(cx) let mut rval = NullValue());
+        match pname {
+            constants2::UNIFORM_TYPE | constants2::UNIFORM_SIZE => {
+                values.into_iter().map(|value| value as u32).collect::<Vec<_>>().to_jsval(cx, rval.handle_mut())
+            },
+            constants2::UNIFORM_IS_ROW_MAJOR => {
+                values.into_iter().map(|value| value != 0).collect::<Vec<_>>().to_jsval(cx, rval.handle_mut())
+            },
+            _ => values.to_jsval(cx, rval.handle_mut()),
+        }
+        rval.get()
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.16
+    fn GetUniformBlockIndex(&self, program: &WebGLProgram, name: DOMString) -> u32 {
+        handle_potential_webgl_error!(
+            self.base,
+            self.base.validate_ownership(program),
+            return constants2::INVALID_INDEX
+        );
+        handle_potential_webgl_error!(
+            self.base,
+            program.get_uniform_block_index(name.into()),
+            constants2::INVALID_INDEX
+        )
+    }
+
+    #[allow(unsafe_code)]
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.16
+    unsafe fn GetActiveUniformBlockParameter(
+        &self,
+        cx: *mut JSContext,
+        program: &WebGLProgram,
+        index: u32,
+        pname: u32,
+    ) -> JSVal {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(program), return NullValue());
+        let values = handle_potential_webgl_error!(
+            self.base,
+            program.get_active_uniform_block_parameter(index, pname),
+            return NullValue()
+        );
+        match pname {
+            constants2::UNIFORM_BLOCK_REFERENCED_BY_VERTEX_SHADER |
+            constants2::UNIFORM_BLOCK_REFERENCED_BY_FRAGMENT_SHADER => BooleanValue(values[0] != 0),
+            constants2::UNIFORM_BLOCK_ACTIVE_UNIFORM_INDICES => {
+                let indices = values.into_iter().map(|value| value as u32).collect::<Vec<_>>();
+                rooted!(in(cx) let mut rval = ptr::null_mut::<JSObject>());
+                let _ = Uint32Array::create(cx, CreateWith::Slice(&indices), rval.handle_mut()).unwrap();
+                ObjectValue(rval.get())
+            },
+            _ => UInt32Value(values[0] as u32),
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.16
+    fn GetActiveUniformBlockName(&self, program: &WebGLProgram, index: u32) -> Option<DOMString> {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(program), return None);
+        handle_potential_webgl_error!(
+            self.base,
+            program.get_active_uniform_block_name(index).map(|name| Some(name.into())),
+            None
+        )
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.16
+    fn UniformBlockBinding(&self, program: &WebGLProgram, index: u32, binding: u32) {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(program), return);
+        if binding >= self.limits.max_uniform_buffer_bindings {
+            return self.base.webgl_error(InvalidValue);
+        }
+        handle_potential_webgl_error!(self.base, program.uniform_block_binding(index, binding));
+    }
 }
 
 
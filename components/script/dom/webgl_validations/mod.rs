@@ -10,4 +10,5 @@ pub trait WebGLValidator {
 }
 
 pub mod tex_image_2d;
+pub mod tex_image_3d;
 pub mod types;
@@ -40,6 +40,8 @@ pub enum TexImageValidationError {
     InvalidBorder,
     /// Expected a power of two texture.
     NonPotTexture,
+    /// The texture storage was made immutable by texStorage*.
+    TextureImmutable,
 }
 
 impl std::error::Error for TexImageValidationError {
@@ -72,6 +74,8 @@ impl std::error::Error for TexImageValidationError {
                 => "Invalid border",
             NonPotTexture
                 => "Expected a power of two texture",
+            TextureImmutable
+                => "The texture is immutable",
         }
     }
 }
@@ -0,0 +1,225 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_traits::webgl::WebGLError::*;
+use dom::bindings::root::DomRoot;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom::webgltexture::WebGLTexture;
+use super::WebGLValidator;
+use super::tex_image_2d::TexImageValidationError;
+use super::types::{TexDataType, TexFormat, TexImage3DTarget, sized_internal_format_info};
+
+fn log2(n: u32) -> u32 {
+    31 - n.leading_zeros()
+}
+
+pub struct TexImage3DValidator<'a> {
+    context: &'a WebGLRenderingContext,
+    max_3d_texture_size: u32,
+    max_array_texture_layers: u32,
+    target: u32,
+    level: i32,
+    internal_format: u32,
+    width: i32,
+    height: i32,
+    depth: i32,
+    border: i32,
+    format: u32,
+    data_type: u32,
+}
+
+impl<'a> TexImage3DValidator<'a> {
+    pub fn new(context: &'a WebGLRenderingContext,
+               max_3d_texture_size: u32,
+               max_array_texture_layers: u32,
+               target: u32,
+               level: i32,
+               internal_format: u32,
+               width: i32,
+               height: i32,
+               depth: i32,
+               border: i32,
+               format: u32,
+               data_type: u32) -> Self {
+        TexImage3DValidator {
+            context: context,
+            max_3d_texture_size: max_3d_texture_size,
+            max_array_texture_layers: max_array_texture_layers,
+            target: target,
+            level: level,
+            internal_format: internal_format,
+            width: width,
+            height: height,
+            depth: depth,
+            border: border,
+            format: format,
+            data_type: data_type,
+        }
+    }
+}
+
+/// The validated result of a TexImage3DValidator-validated call.
+pub struct TexImage3DValidatorResult {
+    /// NB: width, height, depth and level are already unsigned after validation.
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    pub level: u32,
+    pub texture: DomRoot<WebGLTexture>,
+    pub target: TexImage3DTarget,
+    pub format: TexFormat,
+    pub data_type: TexDataType,
+}
+
+/// TexImage3D validator as per
+/// <https://www.khronos.org/registry/OpenGL-Refpages/es3.0/html/glTexImage3D.xhtml>
+impl<'a> WebGLValidator for TexImage3DValidator<'a> {
+    type ValidatedOutput = TexImage3DValidatorResult;
+    type Error = TexImageValidationError;
+
+    fn validate(self) -> Result<Self::ValidatedOutput, TexImageValidationError> {
+        let context = self.context;
+
+        // GL_INVALID_ENUM is generated if target is not GL_TEXTURE_3D or
+        // GL_TEXTURE_2D_ARRAY.
+        let target = match TexImage3DTarget::from_gl_constant(self.target) {
+            Some(target) => target,
+            None => {
+                context.webgl_error(InvalidEnum);
+                return Err(TexImageValidationError::InvalidTextureTarget(self.target));
+            }
+        };
+
+        let texture = match context.bound_texture(self.target) {
+            Some(texture) => texture,
+            None => {
+                context.webgl_error(InvalidOperation);
+                return Err(TexImageValidationError::TextureTargetNotBound(self.target));
+            }
+        };
+
+        // GL_INVALID_OPERATION is generated if the texture was made
+        // immutable by a call to texStorage3D.
+        if texture.is_immutable() {
+            context.webgl_error(InvalidOperation);
+            return Err(TexImageValidationError::TextureImmutable);
+        }
+
+        // GL_INVALID_ENUM is generated if internal_format is not an accepted
+        // format. Sized formats also mandate a specific data type.
+        let (internal_format, sized_data_type) = match TexFormat::from_gl_constant(self.internal_format) {
+            Some(format) => (format, None),
+            None => match sized_internal_format_info(self.internal_format) {
+                Some((format, data_type)) => (format, Some(data_type)),
+                None => {
+                    context.webgl_error(InvalidEnum);
+                    return Err(TexImageValidationError::InvalidTextureFormat);
+                }
+            },
+        };
+
+        // GL_INVALID_VALUE is generated if level is less than 0.
+        if self.level < 0 {
+            context.webgl_error(InvalidValue);
+            return Err(TexImageValidationError::NegativeLevel);
+        }
+
+        // GL_INVALID_VALUE is generated if width, height or depth is less
+        // than 0.
+        if self.width < 0 || self.height < 0 || self.depth < 0 {
+            context.webgl_error(InvalidValue);
+            return Err(TexImageValidationError::NegativeDimension);
+        }
+
+        let width = self.width as u32;
+        let height = self.height as u32;
+        let depth = self.depth as u32;
+        let level = self.level as u32;
+
+        // GL_INVALID_VALUE is generated if width, height or depth is greater
+        // than GL_MAX_3D_TEXTURE_SIZE for GL_TEXTURE_3D. For
+        // GL_TEXTURE_2D_ARRAY, width and height are limited by
+        // GL_MAX_TEXTURE_SIZE and depth by GL_MAX_ARRAY_TEXTURE_LAYERS.
+        let max_size = match target {
+            TexImage3DTarget::Texture3D => self.max_3d_texture_size,
+            TexImage3DTarget::Texture2DArray => context.limits().max_tex_size,
+        };
+        let max_depth = match target {
+            TexImage3DTarget::Texture3D => max_size >> level,
+            TexImage3DTarget::Texture2DArray => self.max_array_texture_layers,
+        };
+        if width > max_size >> level || height > max_size >> level || depth > max_depth {
+            context.webgl_error(InvalidValue);
+            return Err(TexImageValidationError::TextureTooBig);
+        }
+
+        // GL_INVALID_VALUE may be generated if level is greater than
+        // log_2(max), where max is the size limit for the given target.
+        if level > log2(max_size) {
+            context.webgl_error(InvalidValue);
+            return Err(TexImageValidationError::LevelTooHigh);
+        }
+
+        // GL_INVALID_VALUE is generated if border is not 0.
+        if self.border != 0 {
+            context.webgl_error(InvalidValue);
+            return Err(TexImageValidationError::InvalidBorder);
+        }
+
+        // GL_INVALID_ENUM is generated if format or data_type is not an
+        // accepted value. The OES_texture_half_float constant is not valid in
+        // WebGL 2.
+        let data_type = match TexDataType::from_gl_constant(self.data_type) {
+            Some(TexDataType::HalfFloat) | None => {
+                context.webgl_error(InvalidEnum);
+                return Err(TexImageValidationError::InvalidDataType);
+            },
+            Some(data_type) => data_type,
+        };
+
+        let format = match TexFormat::from_gl_constant(self.format) {
+            Some(format) => format,
+            None => {
+                context.webgl_error(InvalidEnum);
+                return Err(TexImageValidationError::InvalidTextureFormat);
+            }
+        };
+
+        // GL_INVALID_OPERATION is generated if the combination of
+        // internal_format, format and type is not one of the valid
+        // combinations listed in table 3.2 of the OpenGL ES 3.0 spec.
+        if format != internal_format || format == TexFormat::DepthComponent {
+            context.webgl_error(InvalidOperation);
+            return Err(TexImageValidationError::TextureFormatMismatch);
+        }
+        let valid_type = match (sized_data_type, data_type) {
+            (_, TexDataType::UnsignedShort4444) |
+            (_, TexDataType::UnsignedShort5551) if format != TexFormat::RGBA => false,
+            (_, TexDataType::UnsignedShort565) if format != TexFormat::RGB => false,
+            // Unsized formats can't hold floating point data.
+            (None, TexDataType::Float) => false,
+            (None, _) => true,
+            // Packed sized formats may also be uploaded from bytes.
+            (Some(TexDataType::UnsignedShort4444), TexDataType::UnsignedByte) |
+            (Some(TexDataType::UnsignedShort5551), TexDataType::UnsignedByte) |
+            (Some(TexDataType::UnsignedShort565), TexDataType::UnsignedByte) => true,
+            (Some(sized_data_type), data_type) => sized_data_type == data_type,
+        };
+        if !valid_type {
+            context.webgl_error(InvalidOperation);
+            return Err(TexImageValidationError::InvalidTypeForFormat);
+        }
+
+        Ok(TexImage3DValidatorResult {
+            width: width,
+            height: height,
+            depth: depth,
+            level: level,
+            texture: texture,
+            target: target,
+            format: format,
+            data_type: data_type,
+        })
+    }
+}
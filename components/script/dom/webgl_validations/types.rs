@@ -3,6 +3,7 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::codegen::Bindings::OESTextureHalfFloatBinding::OESTextureHalfFloatConstants;
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as constants2;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
 
 /// This macro creates type-safe wrappers for WebGL types, associating variants
@@ -52,6 +53,11 @@ impl TexImageTarget {
     }
 }
 
+type_safe_wrapper! { TexImage3DTarget,
+    Texture3D => constants2::TEXTURE_3D,
+    Texture2DArray => constants2::TEXTURE_2D_ARRAY,
+}
+
 type_safe_wrapper! { TexDataType,
     UnsignedByte => constants::UNSIGNED_BYTE,
     UnsignedShort4444 => constants::UNSIGNED_SHORT_4_4_4_4,
@@ -114,3 +120,21 @@ impl TexFormat {
         }
     }
 }
+
+/// Returns the unsized format and the data type matching a sized internal
+/// format, as used by `texStorage*` and the WebGL 2 `texImage*` entry points.
+///
+/// Only the sized formats that have an unsized WebGL 1 equivalent are
+/// supported for now.
+pub fn sized_internal_format_info(internal_format: u32) -> Option<(TexFormat, TexDataType)> {
+    Some(match internal_format {
+        constants2::RGBA8 => (TexFormat::RGBA, TexDataType::UnsignedByte),
+        constants2::RGB8 => (TexFormat::RGB, TexDataType::UnsignedByte),
+        constants::RGBA4 => (TexFormat::RGBA, TexDataType::UnsignedShort4444),
+        constants::RGB5_A1 => (TexFormat::RGBA, TexDataType::UnsignedShort5551),
+        constants::RGB565 => (TexFormat::RGB, TexDataType::UnsignedShort565),
+        constants2::RGBA32F => (TexFormat::RGBA, TexDataType::Float),
+        constants2::RGB32F => (TexFormat::RGB, TexDataType::Float),
+        _ => return None,
+    })
+}
@@ -12,7 +12,7 @@ use dom::bindings::reflector::reflect_dom_object;
 use dom::bindings::root::DomRoot;
 use dom::bindings::str::DOMString;
 use dom::event::{Event, EventBubbles, EventCancelable};
-use dom::window::Window;
+use dom::globalscope::GlobalScope;
 use dom_struct::dom_struct;
 use servo_atoms::Atom;
 
@@ -42,25 +42,25 @@ impl WebGLContextEvent {
         }
     }
 
-    pub fn new_uninitialized(window: &Window) -> DomRoot<WebGLContextEvent> {
+    pub fn new_uninitialized(global: &GlobalScope) -> DomRoot<WebGLContextEvent> {
         // according to https://www.khronos.org/registry/webgl/specs/1.0/#5.15 this is
         // additional information or the empty string if no additional information is
         // available.
         let status_message = DOMString::new();
         reflect_dom_object(
                         Box::new(WebGLContextEvent::new_inherited(status_message)),
-                        window,
+                        global,
                         WebGLContextEventBinding::Wrap)
     }
 
-    pub fn new(window: &Window,
+    pub fn new(global: &GlobalScope,
                type_: Atom,
                bubbles: EventBubbles,
                cancelable: EventCancelable,
                status_message: DOMString) -> DomRoot<WebGLContextEvent> {
         let event = reflect_dom_object(
                         Box::new(WebGLContextEvent::new_inherited(status_message)),
-                        window,
+                        global,
                         WebGLContextEventBinding::Wrap);
 
         {
@@ -71,7 +71,7 @@ impl WebGLContextEvent {
         event
     }
 
-    pub fn Constructor(window: &Window,
+    pub fn Constructor(global: &GlobalScope,
                        type_: DOMString,
                        init: &WebGLContextEventInit) -> Fallible<DomRoot<WebGLContextEvent>> {
         let status_message = match init.statusMessage.as_ref() {
@@ -83,7 +83,7 @@ impl WebGLContextEvent {
 
         let cancelable = EventCancelable::from(init.parent.cancelable);
 
-        Ok(WebGLContextEvent::new(window,
+        Ok(WebGLContextEvent::new(global,
                                   Atom::from(type_),
                                   bubbles,
                                   cancelable,
@@ -6,6 +6,7 @@
 use canvas_traits::webgl::{ActiveAttribInfo, ActiveUniformInfo, WebGLCommand, WebGLError};
 use canvas_traits::webgl::{WebGLProgramId, WebGLResult, webgl_channel};
 use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as constants2;
 use dom::bindings::codegen::Bindings::WebGLProgramBinding;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
 use dom::bindings::inheritance::Castable;
@@ -34,6 +35,10 @@ pub struct WebGLProgram {
     vertex_shader: MutNullableDom<WebGLShader>,
     active_attribs: DomRefCell<Box<[ActiveAttribInfo]>>,
     active_uniforms: DomRefCell<Box<[ActiveUniformInfo]>>,
+    /// The varying count and buffer mode passed to transformFeedbackVaryings.
+    transform_feedback_varyings: Cell<(usize, u32)>,
+    /// The varying count and buffer mode in use since the last successful link.
+    linked_transform_feedback_varyings: Cell<(usize, u32)>,
 }
 
 impl WebGLProgram {
@@ -50,6 +55,8 @@ impl WebGLProgram {
             vertex_shader: Default::default(),
             active_attribs: DomRefCell::new(vec![].into()),
             active_uniforms: DomRefCell::new(vec![].into()),
+            transform_feedback_varyings: Cell::new((0, constants2::INTERLEAVED_ATTRIBS)),
+            linked_transform_feedback_varyings: Cell::new((0, constants2::INTERLEAVED_ATTRIBS)),
         }
     }
 
@@ -179,6 +186,9 @@ impl WebGLProgram {
         }
 
         self.linked.set(link_info.linked);
+        if link_info.linked {
+            self.linked_transform_feedback_varyings.set(self.transform_feedback_varyings.get());
+        }
         *self.active_attribs.borrow_mut() = link_info.active_attribs;
         *self.active_uniforms.borrow_mut() = link_info.active_uniforms;
         Ok(())
@@ -188,6 +198,20 @@ impl WebGLProgram {
         Ref::map(self.active_attribs.borrow(), |attribs| &**attribs)
     }
 
+    /// glTransformFeedbackVaryings
+    pub fn set_transform_feedback_varyings(&self, varyings: Vec<String>, buffer_mode: u32) {
+        self.transform_feedback_varyings.set((varyings.len(), buffer_mode));
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::TransformFeedbackVaryings(self.id, varyings, buffer_mode));
+    }
+
+    /// The number of transform feedback varyings and their buffer mode, as of
+    /// the last successful link.
+    pub fn linked_transform_feedback_varyings(&self) -> (usize, u32) {
+        self.linked_transform_feedback_varyings.get()
+    }
+
     pub fn active_uniforms(&self) -> Ref<[ActiveUniformInfo]> {
         Ref::map(self.active_uniforms.borrow(), |uniforms| &**uniforms)
     }
@@ -287,7 +311,7 @@ impl WebGLProgram {
         let uniforms = self.active_uniforms.borrow();
         let data = uniforms.get(index as usize).ok_or(WebGLError::InvalidValue)?;
         Ok(WebGLActiveInfo::new(
-            self.global().as_window(),
+            &self.global(),
             data.size.unwrap_or(1),
             data.type_,
             data.name().into(),
@@ -302,7 +326,7 @@ impl WebGLProgram {
         let attribs = self.active_attribs.borrow();
         let data = attribs.get(index as usize).ok_or(WebGLError::InvalidValue)?;
         Ok(WebGLActiveInfo::new(
-            self.global().as_window(),
+            &self.global(),
             data.size,
             data.type_,
             data.name.clone().into(),
@@ -380,7 +404,7 @@ impl WebGLProgram {
         let location = receiver.recv().unwrap();
 
         Ok(Some(WebGLUniformLocation::new(
-            self.global().as_window(),
+            &self.global(),
             location,
             self.id,
             self.link_generation.get(),
@@ -426,6 +450,135 @@ impl WebGLProgram {
     pub fn link_generation(&self) -> u64 {
         self.link_generation.get()
     }
+
+    /// glGetTransformFeedbackVarying
+    pub fn get_transform_feedback_varying(&self, index: u32) -> WebGLResult<DomRoot<WebGLActiveInfo>> {
+        if self.is_deleted() || !self.is_linked() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        if index as usize >= self.linked_transform_feedback_varyings.get().0 {
+            return Err(WebGLError::InvalidValue);
+        }
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::GetTransformFeedbackVarying(self.id, index, sender));
+        let (size, type_, name) = receiver.recv().unwrap();
+        Ok(WebGLActiveInfo::new(&self.global(), size, type_, name.into()))
+    }
+
+    /// glGetUniformIndices
+    pub fn get_uniform_indices(&self, names: Vec<String>) -> WebGLResult<Vec<u32>> {
+        if self.is_deleted() {
+            return Err(WebGLError::InvalidValue);
+        }
+        if !self.is_linked() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::GetUniformIndices(self.id, names, sender));
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// glGetActiveUniformsiv
+    pub fn get_active_uniforms(&self, indices: Vec<u32>, pname: u32) -> WebGLResult<Vec<i32>> {
+        if self.is_deleted() {
+            return Err(WebGLError::InvalidValue);
+        }
+        match pname {
+            constants2::UNIFORM_TYPE |
+            constants2::UNIFORM_SIZE |
+            constants2::UNIFORM_BLOCK_INDEX |
+            constants2::UNIFORM_OFFSET |
+            constants2::UNIFORM_ARRAY_STRIDE |
+            constants2::UNIFORM_MATRIX_STRIDE |
+            constants2::UNIFORM_IS_ROW_MAJOR => {},
+            _ => return Err(WebGLError::InvalidEnum),
+        }
+        let uniform_count = self.active_uniforms.borrow().len();
+        if indices.iter().any(|&index| index as usize >= uniform_count) {
+            return Err(WebGLError::InvalidValue);
+        }
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::GetActiveUniforms(self.id, indices, pname, sender));
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// glGetUniformBlockIndex
+    pub fn get_uniform_block_index(&self, name: String) -> WebGLResult<u32> {
+        if self.is_deleted() {
+            return Err(WebGLError::InvalidValue);
+        }
+        if !self.is_linked() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::GetUniformBlockIndex(self.id, name, sender));
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Checks that `index` names an active uniform block of this program.
+    fn validate_uniform_block_index(&self, index: u32) -> WebGLResult<()> {
+        if self.is_deleted() {
+            return Err(WebGLError::InvalidValue);
+        }
+        if !self.is_linked() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::GetProgramActiveUniformBlocks(self.id, sender));
+        if index as i32 >= receiver.recv().unwrap() {
+            return Err(WebGLError::InvalidValue);
+        }
+        Ok(())
+    }
+
+    /// glGetActiveUniformBlockiv
+    pub fn get_active_uniform_block_parameter(&self, index: u32, pname: u32) -> WebGLResult<Vec<i32>> {
+        self.validate_uniform_block_index(index)?;
+        match pname {
+            constants2::UNIFORM_BLOCK_BINDING |
+            constants2::UNIFORM_BLOCK_DATA_SIZE |
+            constants2::UNIFORM_BLOCK_ACTIVE_UNIFORMS |
+            constants2::UNIFORM_BLOCK_ACTIVE_UNIFORM_INDICES |
+            constants2::UNIFORM_BLOCK_REFERENCED_BY_VERTEX_SHADER |
+            constants2::UNIFORM_BLOCK_REFERENCED_BY_FRAGMENT_SHADER => {},
+            _ => return Err(WebGLError::InvalidEnum),
+        }
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::GetActiveUniformBlockParameter(self.id, index, pname, sender));
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// glGetActiveUniformBlockName
+    pub fn get_active_uniform_block_name(&self, index: u32) -> WebGLResult<String> {
+        self.validate_uniform_block_index(index)?;
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::GetActiveUniformBlockName(self.id, index, sender));
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// glUniformBlockBinding, the binding point has already been validated
+    /// by the caller.
+    pub fn uniform_block_binding(&self, index: u32, binding: u32) -> WebGLResult<()> {
+        self.validate_uniform_block_index(index)?;
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::UniformBlockBinding(self.id, index, binding));
+        Ok(())
+    }
 }
 
 impl Drop for WebGLProgram {
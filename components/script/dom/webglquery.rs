@@ -0,0 +1,140 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// https://www.khronos.org/registry/webgl/specs/latest/2.0/webgl2.idl
+use canvas_traits::webgl::{WebGLCommand, WebGLError, WebGLQueryId, WebGLResult, webgl_channel};
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as constants;
+use dom::bindings::codegen::Bindings::WebGLQueryBinding;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::webglobject::WebGLObject;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+
+#[dom_struct]
+pub struct WebGLQuery {
+    webgl_object: WebGLObject,
+    id: WebGLQueryId,
+    /// The target with which this query was first begun
+    target: Cell<Option<u32>>,
+    is_active: Cell<bool>,
+    is_deleted: Cell<bool>,
+}
+
+/// The value of a QUERY_RESULT or QUERY_RESULT_AVAILABLE query.
+pub enum QueryParameterValue {
+    Result(u32),
+    Available(bool),
+}
+
+impl WebGLQuery {
+    fn new_inherited(context: &WebGLRenderingContext, id: WebGLQueryId) -> Self {
+        Self {
+            webgl_object: WebGLObject::new_inherited(context),
+            id,
+            target: Cell::new(None),
+            is_active: Cell::new(false),
+            is_deleted: Cell::new(false),
+        }
+    }
+
+    pub fn maybe_new(context: &WebGLRenderingContext) -> Option<DomRoot<Self>> {
+        let (sender, receiver) = webgl_channel().unwrap();
+        context.send_command(WebGLCommand::CreateQuery(sender));
+        receiver.recv().unwrap().map(|id| WebGLQuery::new(context, id))
+    }
+
+    pub fn new(context: &WebGLRenderingContext, id: WebGLQueryId) -> DomRoot<Self> {
+        reflect_dom_object(
+            Box::new(WebGLQuery::new_inherited(context, id)),
+            &*context.global(),
+            WebGLQueryBinding::Wrap,
+        )
+    }
+}
+
+
+impl WebGLQuery {
+    pub fn id(&self) -> WebGLQueryId {
+        self.id
+    }
+
+    pub fn target(&self) -> Option<u32> {
+        self.target.get()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.is_active.get()
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.is_deleted.get()
+    }
+
+    /// glBeginQuery, the target has already been validated by the caller.
+    pub fn begin(&self, target: u32) -> WebGLResult<()> {
+        if self.is_deleted.get() || self.is_active.get() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        if self.target.get().map_or(false, |t| t != target) {
+            return Err(WebGLError::InvalidOperation);
+        }
+        self.target.set(Some(target));
+        self.is_active.set(true);
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::BeginQuery(target, self.id));
+        Ok(())
+    }
+
+    /// glEndQuery, the caller must have checked that this query is active.
+    pub fn end(&self) {
+        let target = self.target.get().unwrap();
+        assert!(self.is_active.get());
+        self.is_active.set(false);
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::EndQuery(target));
+    }
+
+    pub fn delete(&self) {
+        if !self.is_deleted.get() {
+            if self.is_active.get() {
+                self.end();
+            }
+            self.is_deleted.set(true);
+            self.upcast::<WebGLObject>()
+                .context()
+                .send_command(WebGLCommand::DeleteQuery(self.id));
+        }
+    }
+
+    pub fn get_parameter(&self, pname: u32) -> WebGLResult<QueryParameterValue> {
+        if self.is_deleted.get() || self.is_active.get() || self.target.get().is_none() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        let context = self.upcast::<WebGLObject>().context();
+        match pname {
+            constants::QUERY_RESULT => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                context.send_command(WebGLCommand::GetQueryResult(self.id, sender));
+                Ok(QueryParameterValue::Result(receiver.recv().unwrap()))
+            },
+            constants::QUERY_RESULT_AVAILABLE => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                context.send_command(WebGLCommand::GetQueryResultAvailable(self.id, sender));
+                Ok(QueryParameterValue::Available(receiver.recv().unwrap()))
+            },
+            _ => Err(WebGLError::InvalidEnum),
+        }
+    }
+}
+
+impl Drop for WebGLQuery {
+    fn drop(&mut self) {
+        self.delete();
+    }
+}
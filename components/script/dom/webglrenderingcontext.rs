@@ -3,7 +3,7 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use byteorder::{NativeEndian, ReadBytesExt, WriteBytesExt};
-use canvas_traits::canvas::{byte_swap, multiply_u8_pixel};
+use canvas_traits::canvas::{byte_swap, byte_swap_and_premultiply, multiply_u8_pixel};
 use canvas_traits::webgl::{DOMToTextureCommand, Parameter};
 use canvas_traits::webgl::{TexParameter, WebGLCommand, WebGLContextShareMode, WebGLError};
 use canvas_traits::webgl::{WebGLFramebufferBindingRequest, WebGLMsg, WebGLMsgSender};
@@ -14,12 +14,13 @@ use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::ANGLEInstancedArraysBinding::ANGLEInstancedArraysConstants;
 use dom::bindings::codegen::Bindings::EXTBlendMinmaxBinding::EXTBlendMinmaxConstants;
 use dom::bindings::codegen::Bindings::OESVertexArrayObjectBinding::OESVertexArrayObjectConstants;
-use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::{self, WebGLContextAttributes};
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as constants2;
+use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::{self, TexImageSource, WebGLContextAttributes};
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextMethods;
 use dom::bindings::codegen::UnionTypes::ArrayBufferViewOrArrayBuffer;
 use dom::bindings::codegen::UnionTypes::Float32ArrayOrUnrestrictedFloatSequence;
-use dom::bindings::codegen::UnionTypes::ImageDataOrHTMLImageElementOrHTMLCanvasElementOrHTMLVideoElement;
+use dom::bindings::codegen::UnionTypes::HTMLCanvasElementOrOffscreenCanvas;
 use dom::bindings::codegen::UnionTypes::Int32ArrayOrLongSequence;
 use dom::bindings::conversions::{DerivedFrom, ToJSValConvertible};
 use dom::bindings::error::{Error, ErrorResult};
@@ -28,10 +29,12 @@ use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
 use dom::bindings::root::{Dom, DomOnceCell, DomRoot, LayoutDom, MutNullableDom};
 use dom::bindings::str::DOMString;
 use dom::event::{Event, EventBubbles, EventCancelable};
+use dom::globalscope::GlobalScope;
 use dom::htmlcanvaselement::HTMLCanvasElement;
 use dom::htmlcanvaselement::utils as canvas_utils;
 use dom::htmliframeelement::HTMLIFrameElement;
 use dom::node::{Node, NodeDamage, window_from_node};
+use dom::offscreencanvas::OffscreenCanvas;
 use dom::webgl_extensions::WebGLExtensions;
 use dom::webgl_validations::WebGLValidator;
 use dom::webgl_validations::tex_image_2d::{CommonTexImage2DValidator, CommonTexImage2DValidatorResult};
@@ -49,7 +52,6 @@ use dom::webglshaderprecisionformat::WebGLShaderPrecisionFormat;
 use dom::webgltexture::{TexParameterValue, WebGLTexture};
 use dom::webgluniformlocation::WebGLUniformLocation;
 use dom::webglvertexarrayobjectoes::WebGLVertexArrayObjectOES;
-use dom::window::Window;
 use dom_struct::dom_struct;
 use euclid::Size2D;
 use fnv::FnvHashMap;
@@ -141,6 +143,8 @@ bitflags! {
 struct TextureUnitBindings {
     bound_texture_2d: MutNullableDom<WebGLTexture>,
     bound_texture_cube_map: MutNullableDom<WebGLTexture>,
+    bound_texture_3d: MutNullableDom<WebGLTexture>,
+    bound_texture_2d_array: MutNullableDom<WebGLTexture>,
 }
 
 impl TextureUnitBindings {
@@ -148,6 +152,8 @@ impl TextureUnitBindings {
         Self {
             bound_texture_2d: MutNullableDom::new(None),
             bound_texture_cube_map: MutNullableDom::new(None),
+            bound_texture_3d: MutNullableDom::new(None),
+            bound_texture_2d_array: MutNullableDom::new(None),
         }
     }
 
@@ -155,7 +161,9 @@ impl TextureUnitBindings {
     /// Returns the GL target of the cleared slot, if any.
     fn clear_slot(&self, texture: &WebGLTexture) -> Option<u32> {
         let fields = [(&self.bound_texture_2d, constants::TEXTURE_2D),
-                      (&self.bound_texture_cube_map, constants::TEXTURE_CUBE_MAP)];
+                      (&self.bound_texture_cube_map, constants::TEXTURE_CUBE_MAP),
+                      (&self.bound_texture_3d, constants2::TEXTURE_3D),
+                      (&self.bound_texture_2d_array, constants2::TEXTURE_2D_ARRAY)];
 
         fields.iter().find(|field| {
             match field.0.get() {
@@ -182,7 +190,7 @@ pub struct WebGLRenderingContext {
     glsl_version: WebGLSLVersion,
     #[ignore_malloc_size_of = "Defined in offscreen_gl_context"]
     limits: GLLimits,
-    canvas: Dom<HTMLCanvasElement>,
+    canvas: WebGLCanvas,
     #[ignore_malloc_size_of = "Defined in canvas_traits"]
     last_error: Cell<Option<WebGLError>>,
     texture_unpacking_settings: Cell<TextureUnpacking>,
@@ -192,6 +200,10 @@ pub struct WebGLRenderingContext {
     bound_textures: DomRefCell<FnvHashMap<u32, TextureUnitBindings>>,
     bound_texture_unit: Cell<u32>,
     bound_buffer_array: MutNullableDom<WebGLBuffer>,
+    /// The generic UNIFORM_BUFFER binding point, WebGL 2 only.
+    bound_buffer_uniform: MutNullableDom<WebGLBuffer>,
+    /// The generic TRANSFORM_FEEDBACK_BUFFER binding point, WebGL 2 only.
+    bound_buffer_transform_feedback: MutNullableDom<WebGLBuffer>,
     current_program: MutNullableDom<WebGLProgram>,
     /// https://www.khronos.org/webgl/wiki/WebGL_and_OpenGL_Differences#Vertex_Attribute_0
     #[ignore_malloc_size_of = "Because it's small"]
@@ -206,10 +218,43 @@ pub struct WebGLRenderingContext {
     current_vao: MutNullableDom<WebGLVertexArrayObjectOES>,
 }
 
+/// The canvas a WebGL context renders to, either a `<canvas>` element or an `OffscreenCanvas`.
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+pub enum WebGLCanvas {
+    Element(Dom<HTMLCanvasElement>),
+    Offscreen(Dom<OffscreenCanvas>),
+}
+
+impl WebGLCanvas {
+    fn from_union(canvas: &HTMLCanvasElementOrOffscreenCanvas) -> WebGLCanvas {
+        match *canvas {
+            HTMLCanvasElementOrOffscreenCanvas::HTMLCanvasElement(ref canvas) => {
+                WebGLCanvas::Element(Dom::from_ref(&**canvas))
+            },
+            HTMLCanvasElementOrOffscreenCanvas::OffscreenCanvas(ref canvas) => {
+                WebGLCanvas::Offscreen(Dom::from_ref(&**canvas))
+            },
+        }
+    }
+
+    fn to_union(&self) -> HTMLCanvasElementOrOffscreenCanvas {
+        match *self {
+            WebGLCanvas::Element(ref canvas) => {
+                HTMLCanvasElementOrOffscreenCanvas::HTMLCanvasElement(DomRoot::from_ref(&**canvas))
+            },
+            WebGLCanvas::Offscreen(ref canvas) => {
+                HTMLCanvasElementOrOffscreenCanvas::OffscreenCanvas(DomRoot::from_ref(&**canvas))
+            },
+        }
+    }
+}
+
 impl WebGLRenderingContext {
+    #[allow(unrooted_must_root)]
     pub fn new_inherited(
-        window: &Window,
-        canvas: &HTMLCanvasElement,
+        global: &GlobalScope,
+        canvas: &HTMLCanvasElementOrOffscreenCanvas,
         webgl_version: WebGLVersion,
         size: Size2D<i32>,
         attrs: GLContextAttributes
@@ -218,7 +263,7 @@ impl WebGLRenderingContext {
             return Err("WebGL context creation error forced by pref `webgl.testing.context_creation_error`".into());
         }
 
-        let webgl_chan = match window.webgl_chan() {
+        let webgl_chan = match global.webgl_chan() {
             Some(chan) => chan,
             None => return Err("WebGL initialization failed early on".into()),
         };
@@ -237,7 +282,7 @@ impl WebGLRenderingContext {
                 webgl_version,
                 glsl_version: ctx_data.glsl_version,
                 limits: ctx_data.limits,
-                canvas: Dom::from_ref(canvas),
+                canvas: WebGLCanvas::from_union(canvas),
                 last_error: Cell::new(None),
                 texture_unpacking_settings: Cell::new(TextureUnpacking::CONVERT_COLORSPACE),
                 texture_unpacking_alignment: Cell::new(4),
@@ -245,6 +290,8 @@ impl WebGLRenderingContext {
                 bound_textures: DomRefCell::new(Default::default()),
                 bound_texture_unit: Cell::new(constants::TEXTURE0),
                 bound_buffer_array: MutNullableDom::new(None),
+                bound_buffer_uniform: MutNullableDom::new(None),
+                bound_buffer_transform_feedback: MutNullableDom::new(None),
                 bound_renderbuffer: MutNullableDom::new(None),
                 current_program: MutNullableDom::new(None),
                 current_vertex_attrib_0: Cell::new((0f32, 0f32, 0f32, 1f32)),
@@ -260,22 +307,29 @@ impl WebGLRenderingContext {
 
     #[allow(unrooted_must_root)]
     pub fn new(
-        window: &Window,
-        canvas: &HTMLCanvasElement,
+        global: &GlobalScope,
+        canvas: &HTMLCanvasElementOrOffscreenCanvas,
         webgl_version: WebGLVersion,
         size: Size2D<i32>,
         attrs: GLContextAttributes
     ) -> Option<DomRoot<WebGLRenderingContext>> {
-        match WebGLRenderingContext::new_inherited(window, canvas, webgl_version, size, attrs) {
-            Ok(ctx) => Some(reflect_dom_object(Box::new(ctx), window, WebGLRenderingContextBinding::Wrap)),
+        match WebGLRenderingContext::new_inherited(global, canvas, webgl_version, size, attrs) {
+            Ok(ctx) => Some(reflect_dom_object(Box::new(ctx), global, WebGLRenderingContextBinding::Wrap)),
             Err(msg) => {
                 error!("Couldn't create WebGLRenderingContext: {}", msg);
-                let event = WebGLContextEvent::new(window,
+                let event = WebGLContextEvent::new(global,
                                                    atom!("webglcontextcreationerror"),
                                                    EventBubbles::DoesNotBubble,
                                                    EventCancelable::Cancelable,
                                                    DOMString::from(msg));
-                event.upcast::<Event>().fire(canvas.upcast());
+                match *canvas {
+                    HTMLCanvasElementOrOffscreenCanvas::HTMLCanvasElement(ref canvas) => {
+                        event.upcast::<Event>().fire(canvas.upcast());
+                    },
+                    HTMLCanvasElementOrOffscreenCanvas::OffscreenCanvas(ref canvas) => {
+                        event.upcast::<Event>().fire(canvas.upcast());
+                    },
+                }
                 None
             }
         }
@@ -293,7 +347,11 @@ impl WebGLRenderingContext {
         })
     }
 
-    fn bound_texture(&self, target: u32) -> Option<DomRoot<WebGLTexture>> {
+    pub fn webgl_version(&self) -> WebGLVersion {
+        self.webgl_version
+    }
+
+    pub fn bound_texture(&self, target: u32) -> Option<DomRoot<WebGLTexture>> {
         match target {
             constants::TEXTURE_2D => {
                 self.bound_textures.borrow().get(&self.bound_texture_unit.get()).and_then(|t| {
@@ -305,6 +363,16 @@ impl WebGLRenderingContext {
                     t.bound_texture_cube_map.get()
                 })
             },
+            constants2::TEXTURE_3D => {
+                self.bound_textures.borrow().get(&self.bound_texture_unit.get()).and_then(|t| {
+                    t.bound_texture_3d.get()
+                })
+            },
+            constants2::TEXTURE_2D_ARRAY => {
+                self.bound_textures.borrow().get(&self.bound_texture_unit.get()).and_then(|t| {
+                    t.bound_texture_2d_array.get()
+                })
+            },
             _ => None,
         }
     }
@@ -418,7 +486,7 @@ impl WebGLRenderingContext {
         }
     }
 
-    fn validate_ownership<T>(&self, object: &T) -> WebGLResult<()>
+    pub fn validate_ownership<T>(&self, object: &T) -> WebGLResult<()>
     where
         T: DerivedFrom<WebGLObject>,
     {
@@ -450,6 +518,10 @@ impl WebGLRenderingContext {
         let texture = match target {
             constants::TEXTURE_2D |
             constants::TEXTURE_CUBE_MAP => self.bound_texture(target),
+            constants2::TEXTURE_3D |
+            constants2::TEXTURE_2D_ARRAY if self.webgl_version == WebGLVersion::WebGL2 => {
+                self.bound_texture(target)
+            },
             _ => return self.webgl_error(InvalidEnum),
         };
 
@@ -485,7 +557,10 @@ impl WebGLRenderingContext {
     }
 
     fn mark_as_dirty(&self) {
-        self.canvas.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        // An offscreen canvas has nothing to repaint until its contents are read back.
+        if let WebGLCanvas::Element(ref canvas) = self.canvas {
+            canvas.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        }
     }
 
     fn vertex_attrib(&self, indx: u32, x: f32, y: f32, z: f32, w: f32) {
@@ -751,7 +826,7 @@ impl WebGLRenderingContext {
 
     fn get_image_pixels(
         &self,
-        source: ImageDataOrHTMLImageElementOrHTMLCanvasElementOrHTMLVideoElement,
+        source: TexImageSource,
     ) -> ImagePixelResult {
         // NOTE: Getting the pixels probably can be short-circuited if some
         // parameter is invalid.
@@ -759,16 +834,25 @@ impl WebGLRenderingContext {
         // Nontheless, since it's the error case, I'm not totally sure the
         // complexity is worth it.
         let (pixels, size, premultiplied) = match source {
-            ImageDataOrHTMLImageElementOrHTMLCanvasElementOrHTMLVideoElement::ImageData(image_data) => {
+            TexImageSource::ImageBitmap(bitmap) => {
+                // Image bitmaps are stored like decoded images, in unpremultiplied BGRA8.
+                let mut data = match bitmap.bitmap_data() {
+                    Some(data) => data,
+                    None => return Err(()),
+                };
+                byte_swap(&mut data);
+                (data, bitmap.get_size(), false)
+            },
+            TexImageSource::ImageData(image_data) => {
                 (image_data.get_data_array(), image_data.get_size(), false)
             },
-            ImageDataOrHTMLImageElementOrHTMLCanvasElementOrHTMLVideoElement::HTMLImageElement(image) => {
+            TexImageSource::HTMLImageElement(image) => {
                 let img_url = match image.get_url() {
                     Some(url) => url,
                     None => return Err(()),
                 };
 
-                let window = window_from_node(&*self.canvas);
+                let window = window_from_node(&*image);
 
                 let img = match canvas_utils::request_image_from_cache(&window, img_url) {
                     ImageResponse::Loaded(img, _) => img,
@@ -792,7 +876,7 @@ impl WebGLRenderingContext {
             // TODO(emilio): Getting canvas data is implemented in CanvasRenderingContext2D,
             // but we need to refactor it moving it to `HTMLCanvasElement` and support
             // WebGLContext (probably via GetPixels()).
-            ImageDataOrHTMLImageElementOrHTMLCanvasElementOrHTMLVideoElement::HTMLCanvasElement(canvas) => {
+            TexImageSource::HTMLCanvasElement(canvas) => {
                 if let Some((mut data, size)) = canvas.fetch_all_data() {
                     // Pixels got from Canvas have already alpha premultiplied
                     byte_swap(&mut data);
@@ -801,15 +885,22 @@ impl WebGLRenderingContext {
                     return Err(());
                 }
             },
-            ImageDataOrHTMLImageElementOrHTMLCanvasElementOrHTMLVideoElement::HTMLVideoElement(_rooted_video)
-                => unimplemented!(),
+            TexImageSource::HTMLVideoElement(_rooted_video) => unimplemented!(),
+            TexImageSource::OffscreenCanvas(canvas) => {
+                if let Some((mut data, size)) = canvas.fetch_all_data() {
+                    byte_swap(&mut data);
+                    (data, size, true)
+                } else {
+                    return Err(());
+                }
+            },
         };
 
         return Ok((pixels, size, premultiplied));
     }
 
     // TODO(emilio): Move this logic to a validator.
-    fn validate_tex_image_2d_data(&self,
+    pub fn validate_tex_image_2d_data(&self,
                                   width: u32,
                                   height: u32,
                                   format: TexFormat,
@@ -1013,6 +1104,9 @@ impl WebGLRenderingContext {
                     _border: u32,
                     unpacking_alignment: u32,
                     pixels: Vec<u8>) { // NB: pixels should NOT be premultipied
+        if texture.is_immutable() {
+            return self.webgl_error(InvalidOperation);
+        }
 
         // TexImage2D depth is always equal to 1
         handle_potential_webgl_error!(self, texture.initialize(target,
@@ -1306,6 +1400,26 @@ impl WebGLRenderingContext {
         Some(receiver.recv().unwrap().into())
     }
 
+    /// Reads back the drawing buffer as premultiplied BGRA pixels, top row first, the
+    /// format of `HTMLCanvasElement::fetch_all_data`.
+    pub fn get_bgra_data(&self, size: Size2D<i32>) -> Option<Vec<u8>> {
+        let pixels = self.get_image_data(size.width as u32, size.height as u32)?;
+        let row_length = size.width as usize * 4;
+        if pixels.len() != row_length * size.height as usize {
+            return None;
+        }
+
+        // The rows are read back from the bottom of the framebuffer up.
+        let mut data: Vec<u8> = pixels.chunks(row_length).rev().flat_map(|row| row.iter().cloned()).collect();
+        let premultiplied = self.GetContextAttributes().map_or(true, |attrs| attrs.premultipliedAlpha);
+        if premultiplied {
+            byte_swap(&mut data);
+        } else {
+            byte_swap_and_premultiply(&mut data);
+        }
+        Some(data)
+    }
+
     pub fn array_buffer(&self) -> Option<DomRoot<WebGLBuffer>> {
         self.bound_buffer_array.get()
     }
@@ -1314,10 +1428,58 @@ impl WebGLRenderingContext {
         match target {
             constants::ARRAY_BUFFER => Ok(self.bound_buffer_array.get()),
             constants::ELEMENT_ARRAY_BUFFER => Ok(self.current_vao().element_array_buffer().get()),
+            constants2::UNIFORM_BUFFER if self.webgl_version == WebGLVersion::WebGL2 => {
+                Ok(self.bound_buffer_uniform.get())
+            },
+            constants2::TRANSFORM_FEEDBACK_BUFFER if self.webgl_version == WebGLVersion::WebGL2 => {
+                Ok(self.bound_buffer_transform_feedback.get())
+            },
             _ => Err(WebGLError::InvalidEnum),
         }
     }
 
+    /// Updates the generic binding point of an indexed buffer target, as done
+    /// by bindBufferBase and bindBufferRange. The caller is responsible for
+    /// validating the buffer and sending the GL command.
+    pub fn set_generic_buffer_binding(&self, target: u32, buffer: Option<&WebGLBuffer>) {
+        let slot = match target {
+            constants2::UNIFORM_BUFFER => &self.bound_buffer_uniform,
+            constants2::TRANSFORM_FEEDBACK_BUFFER => &self.bound_buffer_transform_feedback,
+            _ => unreachable!(),
+        };
+        if let Some(buffer) = buffer {
+            buffer.increment_attached_counter();
+        }
+        if let Some(old) = slot.get() {
+            old.decrement_attached_counter();
+        }
+        slot.set(buffer);
+    }
+
+    pub fn bound_framebuffer(&self) -> Option<DomRoot<WebGLFramebuffer>> {
+        self.bound_framebuffer.get()
+    }
+
+    pub fn current_program(&self) -> Option<DomRoot<WebGLProgram>> {
+        self.current_program.get()
+    }
+
+    /// The index of the active texture unit, starting from 0.
+    pub fn active_texture_unit_index(&self) -> u32 {
+        self.bound_texture_unit.get() - constants::TEXTURE0
+    }
+
+    pub fn texture_unpacking_alignment(&self) -> u32 {
+        self.texture_unpacking_alignment.get()
+    }
+
+    /// Whether UNPACK_FLIP_Y_WEBGL or UNPACK_PREMULTIPLY_ALPHA_WEBGL is set.
+    pub fn has_unpacking_transforms(&self) -> bool {
+        self.texture_unpacking_settings.get().intersects(
+            TextureUnpacking::FLIP_Y_AXIS | TextureUnpacking::PREMULTIPLY_ALPHA,
+        )
+    }
+
     pub fn create_vertex_array(&self) -> Option<DomRoot<WebGLVertexArrayObjectOES>> {
         let (sender, receiver) = webgl_channel().unwrap();
         self.send_command(WebGLCommand::CreateVertexArray(sender));
@@ -1403,8 +1565,8 @@ unsafe fn fallible_array_buffer_view_to_vec(
 
 impl WebGLRenderingContextMethods for WebGLRenderingContext {
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.1
-    fn Canvas(&self) -> DomRoot<HTMLCanvasElement> {
-        DomRoot::from_ref(&*self.canvas)
+    fn Canvas(&self) -> HTMLCanvasElementOrOffscreenCanvas {
+        self.canvas.to_union()
     }
 
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.11
@@ -1578,6 +1740,21 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
             return UInt32Value(limit);
         }
 
+        // These are only exposed by WebGL2RenderingContext.
+        match parameter {
+            constants2::MAX_3D_TEXTURE_SIZE |
+            constants2::MAX_ARRAY_TEXTURE_LAYERS |
+            constants2::MAX_COLOR_ATTACHMENTS |
+            constants2::MAX_DRAW_BUFFERS |
+            constants2::MAX_TRANSFORM_FEEDBACK_SEPARATE_ATTRIBS |
+            constants2::MAX_UNIFORM_BUFFER_BINDINGS |
+            constants2::UNIFORM_BUFFER_OFFSET_ALIGNMENT if self.webgl_version == WebGLVersion::WebGL1 => {
+                self.webgl_error(InvalidEnum);
+                return NullValue();
+            }
+            _ => {}
+        }
+
         if let Ok(value) = self.capabilities.is_enabled(parameter) {
             return BooleanValue(value);
         }
@@ -1850,6 +2027,12 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
                 current_vao = self.current_vao();
                 current_vao.element_array_buffer()
             }
+            constants2::UNIFORM_BUFFER if self.webgl_version == WebGLVersion::WebGL2 => {
+                &self.bound_buffer_uniform
+            }
+            constants2::TRANSFORM_FEEDBACK_BUFFER if self.webgl_version == WebGLVersion::WebGL2 => {
+                &self.bound_buffer_transform_feedback
+            }
             _ => return self.webgl_error(InvalidEnum),
         };
 
@@ -1935,6 +2118,12 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
         let slot = match target {
             constants::TEXTURE_2D => &binding.bound_texture_2d,
             constants::TEXTURE_CUBE_MAP => &binding.bound_texture_cube_map,
+            constants2::TEXTURE_3D if self.webgl_version == WebGLVersion::WebGL2 => {
+                &binding.bound_texture_3d
+            },
+            constants2::TEXTURE_2D_ARRAY if self.webgl_version == WebGLVersion::WebGL2 => {
+                &binding.bound_texture_2d_array
+            },
             _ => return self.webgl_error(InvalidEnum),
         };
 
@@ -2081,6 +2270,10 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
             Err(_) => return,
         };
 
+        if texture.is_immutable() {
+            return self.webgl_error(InvalidOperation);
+        }
+
         let image_info = texture.image_info_for_target(&target, level);
 
         // The color buffer components can be dropped during the conversion to
@@ -2312,9 +2505,13 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
             return;
         }
         self.current_vao().unbind_buffer(buffer);
-        if self.bound_buffer_array.get().map_or(false, |b| buffer == &*b) {
-            self.bound_buffer_array.set(None);
-            buffer.decrement_attached_counter();
+        for slot in &[&self.bound_buffer_array,
+                      &self.bound_buffer_uniform,
+                      &self.bound_buffer_transform_feedback] {
+            if slot.get().map_or(false, |b| buffer == &*b) {
+                slot.set(None);
+                buffer.decrement_attached_counter();
+            }
         }
         buffer.mark_for_deletion();
     }
@@ -2723,7 +2920,7 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
                                                                  sender));
 
         let (range_min, range_max, precision) = receiver.recv().unwrap();
-        Some(WebGLShaderPrecisionFormat::new(self.global().as_window(), range_min, range_max, precision))
+        Some(WebGLShaderPrecisionFormat::new(&self.global(), range_min, range_max, precision))
     }
 
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
@@ -3883,7 +4080,7 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
         internal_format: u32,
         format: u32,
         data_type: u32,
-        source: ImageDataOrHTMLImageElementOrHTMLCanvasElementOrHTMLVideoElement,
+        source: TexImageSource,
     ) -> ErrorResult {
         if !self.extension_manager.is_tex_type_enabled(data_type) {
             return Ok(self.webgl_error(InvalidEnum));
@@ -4041,7 +4238,7 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
         yoffset: i32,
         format: u32,
         data_type: u32,
-        source: ImageDataOrHTMLImageElementOrHTMLCanvasElementOrHTMLVideoElement,
+        source: TexImageSource,
     ) -> ErrorResult {
         let (pixels, size, premultiplied) = match self.get_image_pixels(source) {
             Ok((pixels, size, premultiplied)) => (pixels, size, premultiplied),
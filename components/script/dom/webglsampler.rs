@@ -0,0 +1,175 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// https://www.khronos.org/registry/webgl/specs/latest/2.0/webgl2.idl
+use canvas_traits::webgl::{WebGLCommand, WebGLError, WebGLResult, WebGLSamplerId, webgl_channel};
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as constants2;
+use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
+use dom::bindings::codegen::Bindings::WebGLSamplerBinding;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::webglobject::WebGLObject;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom::webgltexture::TexParameterValue;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+
+#[dom_struct]
+pub struct WebGLSampler {
+    webgl_object: WebGLObject,
+    id: WebGLSamplerId,
+    is_deleted: Cell<bool>,
+}
+
+impl WebGLSampler {
+    fn new_inherited(context: &WebGLRenderingContext, id: WebGLSamplerId) -> Self {
+        Self {
+            webgl_object: WebGLObject::new_inherited(context),
+            id,
+            is_deleted: Cell::new(false),
+        }
+    }
+
+    pub fn maybe_new(context: &WebGLRenderingContext) -> Option<DomRoot<Self>> {
+        let (sender, receiver) = webgl_channel().unwrap();
+        context.send_command(WebGLCommand::CreateSampler(sender));
+        receiver.recv().unwrap().map(|id| WebGLSampler::new(context, id))
+    }
+
+    pub fn new(context: &WebGLRenderingContext, id: WebGLSamplerId) -> DomRoot<Self> {
+        reflect_dom_object(
+            Box::new(WebGLSampler::new_inherited(context, id)),
+            &*context.global(),
+            WebGLSamplerBinding::Wrap,
+        )
+    }
+}
+
+/// Whether `pname` is one of the sampler parameters holding a float.
+fn is_float_parameter(pname: u32) -> bool {
+    match pname {
+        constants2::TEXTURE_MIN_LOD | constants2::TEXTURE_MAX_LOD => true,
+        _ => false,
+    }
+}
+
+/// Validates an enum valued sampler parameter, as per
+/// https://www.khronos.org/registry/OpenGL-Refpages/es3.0/html/glSamplerParameter.xhtml
+fn validate_enum_parameter(pname: u32, value: u32) -> WebGLResult<()> {
+    let valid = match pname {
+        constants::TEXTURE_MIN_FILTER => match value {
+            constants::NEAREST |
+            constants::LINEAR |
+            constants::NEAREST_MIPMAP_NEAREST |
+            constants::LINEAR_MIPMAP_NEAREST |
+            constants::NEAREST_MIPMAP_LINEAR |
+            constants::LINEAR_MIPMAP_LINEAR => true,
+            _ => false,
+        },
+        constants::TEXTURE_MAG_FILTER => match value {
+            constants::NEAREST | constants::LINEAR => true,
+            _ => false,
+        },
+        constants::TEXTURE_WRAP_S |
+        constants::TEXTURE_WRAP_T |
+        constants2::TEXTURE_WRAP_R => match value {
+            constants::REPEAT | constants::CLAMP_TO_EDGE | constants::MIRRORED_REPEAT => true,
+            _ => false,
+        },
+        constants2::TEXTURE_COMPARE_MODE => match value {
+            constants::NONE | constants2::COMPARE_REF_TO_TEXTURE => true,
+            _ => false,
+        },
+        constants2::TEXTURE_COMPARE_FUNC => match value {
+            constants::LEQUAL |
+            constants::GEQUAL |
+            constants::LESS |
+            constants::GREATER |
+            constants::EQUAL |
+            constants::NOTEQUAL |
+            constants::ALWAYS |
+            constants::NEVER => true,
+            _ => false,
+        },
+        _ => return Err(WebGLError::InvalidEnum),
+    };
+    if !valid {
+        return Err(WebGLError::InvalidEnum);
+    }
+    Ok(())
+}
+
+
+impl WebGLSampler {
+    pub fn id(&self) -> WebGLSamplerId {
+        self.id
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.is_deleted.get()
+    }
+
+    pub fn delete(&self) {
+        if !self.is_deleted.get() {
+            self.is_deleted.set(true);
+            self.upcast::<WebGLObject>()
+                .context()
+                .send_command(WebGLCommand::DeleteSampler(self.id));
+        }
+    }
+
+    /// glSamplerParameter{i,f}
+    pub fn set_parameter(&self, pname: u32, value: TexParameterValue) -> WebGLResult<()> {
+        if self.is_deleted.get() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        let command = if is_float_parameter(pname) {
+            let value = match value {
+                TexParameterValue::Float(value) => value,
+                TexParameterValue::Int(value) => value as f32,
+            };
+            WebGLCommand::SamplerParameterf(self.id, pname, value)
+        } else {
+            let value = match value {
+                TexParameterValue::Float(value) => value as i32,
+                TexParameterValue::Int(value) => value,
+            };
+            validate_enum_parameter(pname, value as u32)?;
+            WebGLCommand::SamplerParameteri(self.id, pname, value)
+        };
+        self.upcast::<WebGLObject>().context().send_command(command);
+        Ok(())
+    }
+
+    /// glGetSamplerParameter{iv,fv}
+    pub fn get_parameter(&self, pname: u32) -> WebGLResult<TexParameterValue> {
+        if self.is_deleted.get() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        let context = self.upcast::<WebGLObject>().context();
+        if is_float_parameter(pname) {
+            let (sender, receiver) = webgl_channel().unwrap();
+            context.send_command(WebGLCommand::GetSamplerParameterFloat(self.id, pname, sender));
+            return Ok(TexParameterValue::Float(receiver.recv().unwrap()));
+        }
+        // Check that pname is a valid parameter, any valid value will do.
+        let default_value = match pname {
+            constants2::TEXTURE_COMPARE_MODE => constants::NONE,
+            constants2::TEXTURE_COMPARE_FUNC => constants::LEQUAL,
+            constants::TEXTURE_MIN_FILTER | constants::TEXTURE_MAG_FILTER => constants::LINEAR,
+            _ => constants::REPEAT,
+        };
+        validate_enum_parameter(pname, default_value)?;
+        let (sender, receiver) = webgl_channel().unwrap();
+        context.send_command(WebGLCommand::GetSamplerParameterInt(self.id, pname, sender));
+        Ok(TexParameterValue::Int(receiver.recv().unwrap()))
+    }
+}
+
+impl Drop for WebGLSampler {
+    fn drop(&mut self) {
+        self.delete();
+    }
+}
@@ -0,0 +1,90 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// https://www.khronos.org/registry/webgl/specs/latest/2.0/webgl2.idl
+use canvas_traits::webgl::{WebGLCommand, WebGLSyncId, webgl_channel};
+use dom::bindings::codegen::Bindings::WebGLSyncBinding;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::webglobject::WebGLObject;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+
+#[dom_struct]
+pub struct WebGLSync {
+    webgl_object: WebGLObject,
+    id: WebGLSyncId,
+    is_deleted: Cell<bool>,
+}
+
+impl WebGLSync {
+    fn new_inherited(context: &WebGLRenderingContext, id: WebGLSyncId) -> Self {
+        Self {
+            webgl_object: WebGLObject::new_inherited(context),
+            id,
+            is_deleted: Cell::new(false),
+        }
+    }
+
+    /// glFenceSync, the condition and flags have already been validated by
+    /// the caller. Returns None if the driver couldn't create the sync object.
+    pub fn maybe_fence(context: &WebGLRenderingContext) -> Option<DomRoot<Self>> {
+        let (sender, receiver) = webgl_channel().unwrap();
+        context.send_command(WebGLCommand::FenceSync(sender));
+        receiver.recv().unwrap().map(|id| WebGLSync::new(context, id))
+    }
+
+    pub fn new(context: &WebGLRenderingContext, id: WebGLSyncId) -> DomRoot<Self> {
+        reflect_dom_object(
+            Box::new(WebGLSync::new_inherited(context, id)),
+            &*context.global(),
+            WebGLSyncBinding::Wrap,
+        )
+    }
+}
+
+
+impl WebGLSync {
+    pub fn id(&self) -> WebGLSyncId {
+        self.id
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.is_deleted.get()
+    }
+
+    /// glClientWaitSync, returns one of ALREADY_SIGNALED, TIMEOUT_EXPIRED,
+    /// CONDITION_SATISFIED or WAIT_FAILED.
+    pub fn client_wait(&self, flags: u32, timeout: u64) -> u32 {
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::ClientWaitSync(self.id, flags, timeout, sender));
+        receiver.recv().unwrap()
+    }
+
+    /// glWaitSync
+    pub fn wait(&self) {
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::WaitSync(self.id));
+    }
+
+    pub fn delete(&self) {
+        if !self.is_deleted.get() {
+            self.is_deleted.set(true);
+            self.upcast::<WebGLObject>()
+                .context()
+                .send_command(WebGLCommand::DeleteSync(self.id));
+        }
+    }
+}
+
+impl Drop for WebGLSync {
+    fn drop(&mut self) {
+        self.delete();
+    }
+}
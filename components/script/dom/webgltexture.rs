@@ -8,6 +8,7 @@ use canvas_traits::webgl::{DOMToTextureCommand, WebGLCommand, WebGLError};
 use canvas_traits::webgl::{WebGLResult, WebGLTextureId, webgl_channel};
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::EXTTextureFilterAnisotropicBinding::EXTTextureFilterAnisotropicConstants;
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as constants2;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
 use dom::bindings::codegen::Bindings::WebGLTextureBinding;
 use dom::bindings::inheritance::Castable;
@@ -48,6 +49,8 @@ pub struct WebGLTexture {
     mag_filter: Cell<u32>,
     /// True if this texture is used for the DOMToTexture feature.
     attached_to_dom: Cell<bool>,
+    /// The number of levels allocated by texStorage*, if any.
+    immutable_levels: Cell<Option<u32>>,
 }
 
 impl WebGLTexture {
@@ -63,6 +66,7 @@ impl WebGLTexture {
             mag_filter: Cell::new(constants::LINEAR),
             image_info_array: DomRefCell::new([ImageInfo::new(); MAX_LEVEL_COUNT * MAX_FACE_COUNT]),
             attached_to_dom: Cell::new(false),
+            immutable_levels: Cell::new(None),
         }
     }
 
@@ -100,7 +104,9 @@ impl WebGLTexture {
         } else {
             // This is the first time binding
             let face_count = match target {
-                constants::TEXTURE_2D => 1,
+                constants::TEXTURE_2D |
+                constants2::TEXTURE_3D |
+                constants2::TEXTURE_2D_ARRAY => 1,
                 constants::TEXTURE_CUBE_MAP => 6,
                 _ => return Err(WebGLError::InvalidEnum)
             };
@@ -137,6 +143,60 @@ impl WebGLTexture {
         Ok(())
     }
 
+    /// Initializes a level of a TEXTURE_3D or TEXTURE_2D_ARRAY texture.
+    pub fn initialize_3d(&self,
+                         width: u32,
+                         height: u32,
+                         depth: u32,
+                         internal_format: TexFormat,
+                         level: u32,
+                         data_type: Option<TexDataType>) {
+        let image_info = ImageInfo {
+            width: width,
+            height: height,
+            depth: depth,
+            internal_format: Some(internal_format),
+            is_initialized: true,
+            data_type: data_type,
+        };
+        self.set_image_infos_at_level(level, image_info);
+    }
+
+    pub fn is_immutable(&self) -> bool {
+        self.immutable_levels.get().is_some()
+    }
+
+    /// Allocates all the levels of the texture as done by texStorage2D and
+    /// texStorage3D, making it immutable. The depth of a TEXTURE_2D_ARRAY
+    /// texture is not halved from one level to the next.
+    pub fn initialize_storage(&self,
+                              levels: u32,
+                              width: u32,
+                              height: u32,
+                              depth: u32,
+                              internal_format: TexFormat,
+                              data_type: TexDataType) {
+        let halve_depth = self.target.get() == Some(constants2::TEXTURE_3D);
+        let (mut width, mut height, mut depth) = (width, height, depth);
+        for level in 0..levels {
+            let image_info = ImageInfo {
+                width: width,
+                height: height,
+                depth: depth,
+                internal_format: Some(internal_format),
+                is_initialized: true,
+                data_type: Some(data_type),
+            };
+            self.set_image_infos_at_level(level, image_info);
+            width = cmp::max(1, width / 2);
+            height = cmp::max(1, height / 2);
+            if halve_depth {
+                depth = cmp::max(1, depth / 2);
+            }
+        }
+        self.immutable_levels.set(Some(levels));
+    }
+
     pub fn generate_mipmap(&self) -> WebGLResult<()> {
         let target = match self.target.get() {
             Some(target) => target,
@@ -436,6 +496,10 @@ impl ImageInfo {
         self.height
     }
 
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
     pub fn internal_format(&self) -> Option<TexFormat> {
         self.internal_format
     }
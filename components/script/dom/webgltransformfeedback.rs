@@ -0,0 +1,221 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// https://www.khronos.org/registry/webgl/specs/latest/2.0/webgl2.idl
+use canvas_traits::webgl::{WebGLCommand, WebGLError, WebGLResult, WebGLTransformFeedbackId, webgl_channel};
+use dom::bindings::codegen::Bindings::WebGLTransformFeedbackBinding;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{DomRoot, MutNullableDom};
+use dom::webgl2renderingcontext::IndexedBinding;
+use dom::webglbuffer::WebGLBuffer;
+use dom::webglobject::WebGLObject;
+use dom::webglprogram::WebGLProgram;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+
+#[dom_struct]
+pub struct WebGLTransformFeedback {
+    webgl_object: WebGLObject,
+    /// `None` for the default transform feedback object of the context.
+    id: Option<WebGLTransformFeedbackId>,
+    ever_bound: Cell<bool>,
+    is_deleted: Cell<bool>,
+    /// The primitive mode given to beginTransformFeedback, if active.
+    active_mode: Cell<Option<u32>>,
+    is_paused: Cell<bool>,
+    /// The program in use when this object was made active.
+    program: MutNullableDom<WebGLProgram>,
+    /// How many more vertices fit in the bound buffers while active.
+    remaining_vertices: Cell<u64>,
+    buffer_bindings: Box<[IndexedBinding]>,
+}
+
+impl WebGLTransformFeedback {
+    #[allow(unrooted_must_root)]
+    fn new_inherited(
+        context: &WebGLRenderingContext,
+        id: Option<WebGLTransformFeedbackId>,
+        max_separate_attribs: u32,
+    ) -> Self {
+        Self {
+            webgl_object: WebGLObject::new_inherited(context),
+            id,
+            ever_bound: Default::default(),
+            is_deleted: Default::default(),
+            active_mode: Default::default(),
+            is_paused: Default::default(),
+            program: Default::default(),
+            remaining_vertices: Default::default(),
+            buffer_bindings: (0..max_separate_attribs).map(|_| IndexedBinding::new()).collect(),
+        }
+    }
+
+    pub fn maybe_new(context: &WebGLRenderingContext, max_separate_attribs: u32) -> Option<DomRoot<Self>> {
+        let (sender, receiver) = webgl_channel().unwrap();
+        context.send_command(WebGLCommand::CreateTransformFeedback(sender));
+        receiver
+            .recv()
+            .unwrap()
+            .map(|id| WebGLTransformFeedback::new(context, Some(id), max_separate_attribs))
+    }
+
+    pub fn new(
+        context: &WebGLRenderingContext,
+        id: Option<WebGLTransformFeedbackId>,
+        max_separate_attribs: u32,
+    ) -> DomRoot<Self> {
+        reflect_dom_object(
+            Box::new(WebGLTransformFeedback::new_inherited(context, id, max_separate_attribs)),
+            &*context.global(),
+            WebGLTransformFeedbackBinding::Wrap,
+        )
+    }
+}
+
+
+impl WebGLTransformFeedback {
+    pub fn id(&self) -> Option<WebGLTransformFeedbackId> {
+        self.id
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.is_deleted.get()
+    }
+
+    pub fn ever_bound(&self) -> bool {
+        self.ever_bound.get()
+    }
+
+    pub fn set_ever_bound(&self) {
+        self.ever_bound.set(true);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active_mode.get().is_some()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.is_paused.get()
+    }
+
+    /// Whether transform feedback is currently capturing vertices.
+    pub fn is_capturing(&self) -> bool {
+        self.is_active() && !self.is_paused()
+    }
+
+    pub fn active_mode(&self) -> Option<u32> {
+        self.active_mode.get()
+    }
+
+    pub fn program(&self) -> Option<DomRoot<WebGLProgram>> {
+        self.program.get()
+    }
+
+    pub fn buffer_binding(&self, index: u32) -> Option<&IndexedBinding> {
+        self.buffer_bindings.get(index as usize)
+    }
+
+    /// Whether the first `count` binding points all have a buffer bound.
+    pub fn has_buffers_bound(&self, count: usize) -> bool {
+        self.buffer_bindings.len() >= count &&
+            self.buffer_bindings[..count].iter().all(|binding| binding.buffer().is_some())
+    }
+
+    /// Unbinds `buffer` from all the binding points of this object.
+    pub fn unbind_buffer(&self, buffer: &WebGLBuffer) {
+        for binding in self.buffer_bindings.iter() {
+            binding.unbind(buffer);
+        }
+    }
+
+    /// glBeginTransformFeedback, `mode` has already been validated.
+    /// `max_vertices` is the number of vertices that can be captured before
+    /// the bound buffers overflow.
+    pub fn begin(&self, mode: u32, program: &WebGLProgram, max_vertices: u64) -> WebGLResult<()> {
+        if self.is_active() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        self.active_mode.set(Some(mode));
+        self.program.set(Some(program));
+        self.remaining_vertices.set(max_vertices);
+        self.is_paused.set(false);
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::BeginTransformFeedback(mode));
+        Ok(())
+    }
+
+    /// Accounts for `vertices` being captured by a draw call, failing if
+    /// the bound buffers don't have enough room left for them.
+    pub fn record_vertices(&self, vertices: u64) -> WebGLResult<()> {
+        let remaining = self.remaining_vertices.get();
+        if vertices > remaining {
+            return Err(WebGLError::InvalidOperation);
+        }
+        self.remaining_vertices.set(remaining - vertices);
+        Ok(())
+    }
+
+    /// glEndTransformFeedback
+    pub fn end(&self) -> WebGLResult<()> {
+        if !self.is_active() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        self.active_mode.set(None);
+        self.program.set(None);
+        self.is_paused.set(false);
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::EndTransformFeedback);
+        Ok(())
+    }
+
+    /// glPauseTransformFeedback
+    pub fn pause(&self) -> WebGLResult<()> {
+        if !self.is_capturing() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        self.is_paused.set(true);
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::PauseTransformFeedback);
+        Ok(())
+    }
+
+    /// glResumeTransformFeedback
+    pub fn resume(&self) -> WebGLResult<()> {
+        if !self.is_active() || !self.is_paused() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        self.is_paused.set(false);
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::ResumeTransformFeedback);
+        Ok(())
+    }
+
+    pub fn delete(&self) {
+        assert!(self.id.is_some());
+        if self.is_deleted.get() {
+            return;
+        }
+        self.is_deleted.set(true);
+        self.upcast::<WebGLObject>()
+            .context()
+            .send_command(WebGLCommand::DeleteTransformFeedback(self.id.unwrap()));
+        for binding in self.buffer_bindings.iter() {
+            binding.clear();
+        }
+    }
+}
+
+impl Drop for WebGLTransformFeedback {
+    fn drop(&mut self) {
+        if self.id.is_some() {
+            self.delete();
+        }
+    }
+}
@@ -191,6 +191,7 @@ impl WebSocket {
             url: url_record,
             origin: global.origin().immutable().clone(),
             mode: RequestMode::WebSocket { protocols },
+            csp_list: global.get_csp_list(),
             ..RequestInit::default()
         };
         let channels = FetchChannels::WebSocket {
@@ -229,6 +230,13 @@ impl WebSocket {
                         close_the_websocket_connection(address.clone(),
                             &task_source, &canceller, code, reason);
                     },
+                    WebSocketNetworkEvent::ReportCspViolations(violations) => {
+                        let address = address.clone();
+                        let task = task!(report_websocket_csp_violations: move || {
+                            address.root().global().report_csp_violations(violations, None);
+                        });
+                        task_source.queue_with_canceller(task, &canceller).unwrap();
+                    },
                 }
             }
         });
@@ -549,7 +557,7 @@ impl TaskOnce for MessageReceivedTask {
                     }
                 },
             }
-            MessageEvent::dispatch_jsval(ws.upcast(), &global, message.handle());
+            MessageEvent::dispatch_jsval(ws.upcast(), &global, message.handle(), vec![]);
         }
     }
 }
@@ -12,6 +12,7 @@ use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::DocumentBinding::{DocumentMethods, DocumentReadyState};
 use dom::bindings::codegen::Bindings::FunctionBinding::Function;
 use dom::bindings::codegen::Bindings::HistoryBinding::HistoryBinding::HistoryMethods;
+use dom::bindings::codegen::Bindings::ImageBitmapBinding::{ImageBitmapOptions, ImageBitmapSource};
 use dom::bindings::codegen::Bindings::MediaQueryListBinding::MediaQueryListBinding::MediaQueryListMethods;
 use dom::bindings::codegen::Bindings::PermissionStatusBinding::PermissionState;
 use dom::bindings::codegen::Bindings::RequestBinding::RequestInit;
@@ -30,6 +31,7 @@ use dom::bindings::trace::RootedTraceableBox;
 use dom::bindings::utils::{GlobalStaticData, WindowProxyHandler};
 use dom::bindings::weakref::DOMTracker;
 use dom::bluetooth::BluetoothExtraPermissionData;
+use dom::cachestorage::CacheStorage;
 use dom::crypto::Crypto;
 use dom::cssstyledeclaration::{CSSModificationAccess, CSSStyleDeclaration, CSSStyleOwner};
 use dom::customelementregistry::CustomElementRegistry;
@@ -40,6 +42,8 @@ use dom::eventtarget::EventTarget;
 use dom::globalscope::GlobalScope;
 use dom::hashchangeevent::HashChangeEvent;
 use dom::history::History;
+use dom::idbfactory::IDBFactory;
+use dom::imagebitmap;
 use dom::location::Location;
 use dom::mediaquerylist::{MediaQueryList, MediaQueryListMatchState};
 use dom::mediaquerylistevent::MediaQueryListEvent;
@@ -49,6 +53,7 @@ use dom::node::{Node, NodeDamage, document_from_node, from_untrusted_node_addres
 use dom::performance::Performance;
 use dom::promise::Promise;
 use dom::screen::Screen;
+use dom::selection::Selection;
 use dom::storage::Storage;
 use dom::testrunner::TestRunner;
 use dom::windowproxy::WindowProxy;
@@ -60,10 +65,10 @@ use euclid::{Point2D, Vector2D, Rect, Size2D, TypedPoint2D, TypedScale, TypedSiz
 use fetch;
 use ipc_channel::ipc::IpcSender;
 use ipc_channel::router::ROUTER;
-use js::jsapi::{JSAutoCompartment, JSContext};
+use js::jsapi::{JSAutoCompartment, JSContext, JSObject};
 use js::jsapi::{JS_GC, JS_GetRuntime};
 use js::jsval::UndefinedValue;
-use js::rust::HandleValue;
+use js::rust::{CustomAutoRooterGuard, HandleValue};
 use layout_image::fetch_image_for_layout;
 use microtask::MicrotaskQueue;
 use msg::constellation_msg::PipelineId;
@@ -79,7 +84,9 @@ use script_layout_interface::{TrustedNodeAddress, PendingImageState};
 use script_layout_interface::message::{Msg, Reflow, QueryMsg, ReflowGoal, ScriptReflow};
 use script_layout_interface::reporter::CSSErrorReporter;
 use script_layout_interface::rpc::{ContentBoxResponse, ContentBoxesResponse, LayoutRPC};
-use script_layout_interface::rpc::{NodeScrollIdResponse, ResolvedStyleResponse, TextIndexResponse};
+use script_layout_interface::rpc::{NodeScrollIdResponse, ObservedBoxes, ObservedBoxesResponse};
+use script_layout_interface::rpc::{ResolvedStyleResponse, RunningCSSAnimation, RunningCSSAnimationsResponse};
+use script_layout_interface::rpc::TextIndexResponse;
 use script_runtime::{CommonScriptMsg, ScriptChan, ScriptPort, ScriptThreadEventCategory, Runtime};
 use script_thread::{ImageCacheMsg, MainThreadScriptChan, MainThreadScriptMsg};
 use script_thread::{ScriptThread, SendableMainThreadScriptChan};
@@ -121,6 +128,7 @@ use task_source::file_reading::FileReadingTaskSource;
 use task_source::history_traversal::HistoryTraversalTaskSource;
 use task_source::networking::NetworkingTaskSource;
 use task_source::performance_timeline::PerformanceTimelineTaskSource;
+use task_source::port_message::PortMessageQueue;
 use task_source::remote_event::RemoteEventTaskSource;
 use task_source::user_interaction::UserInteractionTaskSource;
 use time;
@@ -181,6 +189,8 @@ pub struct Window {
     performance_timeline_task_source: PerformanceTimelineTaskSource,
     #[ignore_malloc_size_of = "task sources are hard"]
     remote_event_task_source: RemoteEventTaskSource,
+    #[ignore_malloc_size_of = "task sources are hard"]
+    port_message_queue: PortMessageQueue,
     navigator: MutNullableDom<Navigator>,
     #[ignore_malloc_size_of = "Arc"]
     image_cache: Arc<ImageCache>,
@@ -192,6 +202,8 @@ pub struct Window {
     history: MutNullableDom<History>,
     custom_element_registry: MutNullableDom<CustomElementRegistry>,
     performance: MutNullableDom<Performance>,
+    indexed_db: MutNullableDom<IDBFactory>,
+    caches: MutNullableDom<CacheStorage>,
     navigation_start: Cell<u64>,
     navigation_start_precise: Cell<u64>,
     screen: MutNullableDom<Screen>,
@@ -371,6 +383,10 @@ impl Window {
         self.remote_event_task_source.clone()
     }
 
+    pub fn port_message_queue(&self) -> PortMessageQueue {
+        self.port_message_queue.clone()
+    }
+
     pub fn main_thread_script_chan(&self) -> &Sender<MainThreadScriptMsg> {
         &self.script_chan.0
     }
@@ -800,7 +816,8 @@ impl WindowMethods for Window {
     unsafe fn PostMessage(&self,
                    cx: *mut JSContext,
                    message: HandleValue,
-                   origin: DOMString)
+                   origin: DOMString,
+                   transfer: CustomAutoRooterGuard<Option<Vec<*mut JSObject>>>)
                    -> ErrorResult {
         // Step 3-5.
         let origin = match &origin[..] {
@@ -817,8 +834,8 @@ impl WindowMethods for Window {
         };
 
         // Step 1-2, 6-8.
-        // TODO(#12717): Should implement the `transfer` argument.
-        let data = StructuredCloneData::write(cx, message)?;
+        let transfer = transfer.as_ref().map(|objects| &objects[..]).unwrap_or(&[]);
+        let data = StructuredCloneData::write_with_transfer(cx, message, transfer)?;
 
         // Step 9.
         self.post_message(origin, data);
@@ -869,6 +886,11 @@ impl WindowMethods for Window {
         }
     }
 
+    // https://w3c.github.io/selection-api/#dom-window-getselection
+    fn GetSelection(&self) -> Option<DomRoot<Selection>> {
+        self.Document().GetSelection()
+    }
+
     // https://drafts.csswg.org/cssom/#dom-window-getcomputedstyle
     fn GetComputedStyle(&self,
                         element: &Element,
@@ -1068,6 +1090,36 @@ impl WindowMethods for Window {
         fetch::Fetch(&self.upcast(), input, init)
     }
 
+    #[allow(unrooted_must_root)]
+    // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
+    fn CreateImageBitmap(&self, image: ImageBitmapSource, options: &ImageBitmapOptions) -> Rc<Promise> {
+        imagebitmap::create_image_bitmap(self.upcast(), image, None, options)
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
+    fn CreateImageBitmap_(&self,
+                          image: ImageBitmapSource,
+                          sx: i32,
+                          sy: i32,
+                          sw: i32,
+                          sh: i32,
+                          options: &ImageBitmapOptions)
+                          -> Rc<Promise> {
+        let crop_rect = imagebitmap::normalize_crop_rect(sx, sy, sw, sh);
+        imagebitmap::create_image_bitmap(self.upcast(), image, Some(crop_rect), options)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-windoworworkerglobalscope-indexeddb
+    fn IndexedDB(&self) -> DomRoot<IDBFactory> {
+        self.indexed_db.or_init(|| IDBFactory::new(self.upcast()))
+    }
+
+    // https://w3c.github.io/ServiceWorker/#self-caches
+    fn Caches(&self) -> DomRoot<CacheStorage> {
+        self.caches.or_init(|| CacheStorage::new(self.upcast()))
+    }
+
     fn TestRunner(&self) -> DomRoot<TestRunner> {
         self.test_runner.or_init(|| TestRunner::new(self.upcast()))
     }
@@ -1159,6 +1211,8 @@ impl Window {
         // script.
         // TODO: ensure that this doesn't happen!
 
+        self.upcast::<GlobalScope>().remove_message_router();
+
         self.current_state.set(WindowState::Zombie);
         *self.js_runtime.borrow_mut() = None;
         self.window_proxy.set(None);
@@ -1326,6 +1380,7 @@ impl Window {
             reflow_goal,
             script_join_chan: join_chan,
             dom_count: self.Document().dom_count(),
+            animations: document.flush_animations_for_reflow(),
         };
 
         self.layout_chan.send(Msg::Reflow(reflow)).unwrap();
@@ -1403,9 +1458,22 @@ impl Window {
     pub fn reflow(&self, reflow_goal: ReflowGoal, reason: ReflowReason) -> bool {
         let for_display = reflow_goal == ReflowGoal::Full;
 
+        // Selection changes dirty the selected text nodes, so they must be
+        // flushed before checking whether the document needs a reflow.
+        self.Document().flush_selection_for_reflow();
+
         let mut issued_reflow = false;
         if !for_display || self.Document().needs_reflow() {
+            // Observers are updated right after animation frame callbacks run, so only
+            // other layout changes need a separate update.
+            let update_layout_observers = for_display && match reason {
+                ReflowReason::RequestAnimationFrame => false,
+                _ => true,
+            };
             issued_reflow = self.force_reflow(reflow_goal, reason);
+            if issued_reflow && update_layout_observers {
+                self.Document().queue_layout_observers_update();
+            }
 
             // If window_size is `None`, we don't reflow, so the document stays
             // dirty. Otherwise, we shouldn't need a reflow immediately after a
@@ -1474,6 +1542,35 @@ impl Window {
         rects
     }
 
+    /// Returns the boxes of each of the given nodes, or `None` for nodes that aren't rendered.
+    pub fn observed_boxes_query(&self, nodes: Vec<TrustedNodeAddress>) -> Vec<Option<ObservedBoxes>> {
+        let count = nodes.len();
+        if !self.layout_reflow(QueryMsg::ObservedBoxesQuery(nodes)) {
+            return vec![None; count];
+        }
+        let ObservedBoxesResponse(boxes) = self.layout_rpc.observed_boxes();
+        if boxes.len() != count {
+            return vec![None; count];
+        }
+        boxes
+    }
+
+    /// Returns the CSS animations and transitions that layout is currently
+    /// running, along with the node each of them applies to.
+    #[allow(unsafe_code)]
+    pub fn running_css_animations_query(&self) -> Vec<(DomRoot<Node>, RunningCSSAnimation)> {
+        if !self.layout_reflow(QueryMsg::RunningCSSAnimationsQuery) {
+            return vec![];
+        }
+        let RunningCSSAnimationsResponse(animations) = self.layout_rpc.running_css_animations();
+        let js_runtime = self.js_runtime.borrow();
+        let js_runtime = js_runtime.as_ref().unwrap();
+        animations.into_iter().map(|animation| {
+            let node = unsafe { from_untrusted_node_address(js_runtime.rt(), animation.node) };
+            (node, animation)
+        }).collect()
+    }
+
     pub fn client_rect_query(&self, node_geometry_request: TrustedNodeAddress) -> Rect<i32> {
         if !self.layout_reflow(QueryMsg::NodeGeometryQuery(node_geometry_request)) {
             return Rect::zero();
@@ -1570,6 +1667,17 @@ impl Window {
         self.layout_rpc.text_index()
     }
 
+    pub fn text_index_at_point_query(
+        &self,
+        node: TrustedNodeAddress,
+        point: Point2D<f32>
+    ) -> TextIndexResponse {
+        if !self.layout_reflow(QueryMsg::TextIndexAtPointQuery(node, point)) {
+            return TextIndexResponse(None);
+        }
+        self.layout_rpc.text_index()
+    }
+
     #[allow(unsafe_code)]
     pub fn init_window_proxy(&self, window_proxy: &WindowProxy) {
         assert!(self.window_proxy.get().is_none());
@@ -1866,6 +1974,7 @@ impl Window {
         file_reading_task_source: FileReadingTaskSource,
         performance_timeline_task_source: PerformanceTimelineTaskSource,
         remote_event_task_source: RemoteEventTaskSource,
+        port_message_queue: PortMessageQueue,
         image_cache_chan: Sender<ImageCacheMsg>,
         image_cache: Arc<ImageCache>,
         resource_threads: ResourceThreads,
@@ -1919,6 +2028,7 @@ impl Window {
             file_reading_task_source,
             performance_timeline_task_source,
             remote_event_task_source,
+            port_message_queue,
             image_cache_chan,
             image_cache,
             navigator: Default::default(),
@@ -1928,6 +2038,8 @@ impl Window {
             window_proxy: Default::default(),
             document: Default::default(),
             performance: Default::default(),
+            indexed_db: Default::default(),
+            caches: Default::default(),
             navigation_start: Cell::new(navigation_start),
             navigation_start_precise: Cell::new(navigation_start_precise),
             screen: Default::default(),
@@ -2011,7 +2123,10 @@ fn debug_reflow_events(id: PipelineId, reflow_goal: &ReflowGoal, reason: &Reflow
             &QueryMsg::OffsetParentQuery(_n) => "\tOffsetParentQuery",
             &QueryMsg::StyleQuery(_n) => "\tStyleQuery",
             &QueryMsg::TextIndexQuery(..) => "\tTextIndexQuery",
+            &QueryMsg::TextIndexAtPointQuery(..) => "\tTextIndexAtPointQuery",
             &QueryMsg::ElementInnerTextQuery(_) => "\tElementInnerTextQuery",
+            &QueryMsg::ObservedBoxesQuery(_) => "\tObservedBoxesQuery",
+            &QueryMsg::RunningCSSAnimationsQuery => "\tRunningCSSAnimationsQuery",
         },
     });
 
@@ -2064,20 +2179,19 @@ impl Window {
             let obj = this.reflector().get_jsobject();
             let _ac = JSAutoCompartment::new(cx, obj.get());
             rooted!(in(cx) let mut message_clone = UndefinedValue());
-            serialize_with_transfer_result.read(
+            // Step 7.6.
+            let new_ports = serialize_with_transfer_result.read(
                 this.upcast(),
                 message_clone.handle_mut(),
             );
 
-            // Step 7.6.
-            // TODO: MessagePort array.
-
             // Step 7.7.
             // TODO(#12719): Set the other attributes.
             MessageEvent::dispatch_jsval(
                 this.upcast(),
                 this.upcast(),
                 message_clone.handle(),
+                new_ports,
             );
         });
         // FIXME(nox): Why are errors silenced here?
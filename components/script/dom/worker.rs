@@ -21,9 +21,10 @@ use dom::messageevent::MessageEvent;
 use dom::workerglobalscope::prepare_workerscope_init;
 use dom_struct::dom_struct;
 use ipc_channel::ipc;
-use js::jsapi::{JSAutoCompartment, JSContext};
+use js::jsapi::{JSAutoCompartment, JSContext, JSObject};
 use js::jsval::UndefinedValue;
-use js::rust::HandleValue;
+use js::rust::{CustomAutoRooterGuard, HandleValue};
+use net_traits::request::{Destination, Request};
 use script_traits::WorkerScriptLoadOrigin;
 use std::cell::Cell;
 use std::sync::{Arc, Mutex};
@@ -77,6 +78,17 @@ impl Worker {
             Err(_) => return Err(Error::Syntax),
         };
 
+        // The worker thread loads its script with the CSP list of `global`,
+        // but can't report the violations of that load itself.
+        if let Some(csp_list) = global.get_csp_list() {
+            let mut request = Request::new(worker_url.clone(), None, None);
+            request.destination = Destination::Worker;
+            let (_, violations) = csp_list.should_request_be_blocked(&request);
+            if !violations.is_empty() {
+                global.report_csp_violations(violations, None);
+            }
+        }
+
         let (sender, receiver) = channel();
         let closing = Arc::new(AtomicBool::new(false));
         let worker = Worker::new(global, sender.clone(), closing.clone());
@@ -132,8 +144,8 @@ impl Worker {
         let target = worker.upcast();
         let _ac = JSAutoCompartment::new(global.get_cx(), target.reflector().get_jsobject().get());
         rooted!(in(global.get_cx()) let mut message = UndefinedValue());
-        data.read(&global, message.handle_mut());
-        MessageEvent::dispatch_jsval(target, &global, message.handle());
+        let ports = data.read(&global, message.handle_mut());
+        MessageEvent::dispatch_jsval(target, &global, message.handle(), ports);
     }
 
     pub fn dispatch_simple_error(address: TrustedWorkerAddress) {
@@ -145,8 +157,13 @@ impl Worker {
 impl WorkerMethods for Worker {
     #[allow(unsafe_code)]
     // https://html.spec.whatwg.org/multipage/#dom-worker-postmessage
-    unsafe fn PostMessage(&self, cx: *mut JSContext, message: HandleValue) -> ErrorResult {
-        let data = StructuredCloneData::write(cx, message)?;
+    unsafe fn PostMessage(&self,
+                          cx: *mut JSContext,
+                          message: HandleValue,
+                          transfer: CustomAutoRooterGuard<Option<Vec<*mut JSObject>>>)
+                          -> ErrorResult {
+        let transfer = transfer.as_ref().map(|objects| &objects[..]).unwrap_or(&[]);
+        let data = StructuredCloneData::write_with_transfer(cx, message, transfer)?;
         let address = Trusted::new(self);
 
         // NOTE: step 9 of https://html.spec.whatwg.org/multipage/#dom-messageport-postmessage
@@ -2,8 +2,10 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use canvas_traits::webgl::WebGLChan;
 use devtools_traits::{DevtoolScriptControlMsg, WorkerId};
 use dom::bindings::codegen::Bindings::FunctionBinding::Function;
+use dom::bindings::codegen::Bindings::ImageBitmapBinding::{ImageBitmapOptions, ImageBitmapSource};
 use dom::bindings::codegen::Bindings::RequestBinding::RequestInit;
 use dom::bindings::codegen::Bindings::WorkerGlobalScopeBinding::WorkerGlobalScopeMethods;
 use dom::bindings::codegen::UnionTypes::RequestOrUSVString;
@@ -14,9 +16,12 @@ use dom::bindings::root::{DomRoot, MutNullableDom};
 use dom::bindings::settings_stack::AutoEntryScript;
 use dom::bindings::str::{DOMString, USVString};
 use dom::bindings::trace::RootedTraceableBox;
+use dom::cachestorage::CacheStorage;
 use dom::crypto::Crypto;
 use dom::dedicatedworkerglobalscope::DedicatedWorkerGlobalScope;
 use dom::globalscope::GlobalScope;
+use dom::idbfactory::IDBFactory;
+use dom::imagebitmap;
 use dom::performance::Performance;
 use dom::promise::Promise;
 use dom::serviceworkerglobalscope::ServiceWorkerGlobalScope;
@@ -46,6 +51,7 @@ use task::TaskCanceller;
 use task_source::file_reading::FileReadingTaskSource;
 use task_source::networking::NetworkingTaskSource;
 use task_source::performance_timeline::PerformanceTimelineTaskSource;
+use task_source::port_message::PortMessageQueue;
 use task_source::remote_event::RemoteEventTaskSource;
 use time::precise_time_ns;
 use timers::{IsInterval, TimerCallback};
@@ -63,6 +69,7 @@ pub fn prepare_workerscope_init(global: &GlobalScope,
             worker_id: global.get_next_worker_id(),
             pipeline_id: global.pipeline_id(),
             origin: global.origin().immutable().clone(),
+            webgl_chan: global.webgl_chan(),
         };
 
     init
@@ -94,6 +101,12 @@ pub struct WorkerGlobalScope {
 
     navigation_start_precise: u64,
     performance: MutNullableDom<Performance>,
+    indexed_db: MutNullableDom<IDBFactory>,
+    caches: MutNullableDom<CacheStorage>,
+
+    #[ignore_malloc_size_of = "Channels are hard"]
+    /// A channel to the WebGL thread, for contexts of `OffscreenCanvas` objects.
+    webgl_chan: Option<WebGLChan>,
 }
 
 impl WorkerGlobalScope {
@@ -128,9 +141,16 @@ impl WorkerGlobalScope {
             from_devtools_receiver,
             navigation_start_precise: precise_time_ns(),
             performance: Default::default(),
+            indexed_db: Default::default(),
+            caches: Default::default(),
+            webgl_chan: init.webgl_chan,
         }
     }
 
+    pub fn webgl_chan(&self) -> Option<WebGLChan> {
+        self.webgl_chan.clone()
+    }
+
     pub fn from_devtools_sender(&self) -> Option<IpcSender<DevtoolScriptControlMsg>> {
         self.from_devtools_sender.clone()
     }
@@ -320,6 +340,36 @@ impl WorkerGlobalScopeMethods for WorkerGlobalScope {
         fetch::Fetch(self.upcast(), input, init)
     }
 
+    #[allow(unrooted_must_root)]
+    // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
+    fn CreateImageBitmap(&self, image: ImageBitmapSource, options: &ImageBitmapOptions) -> Rc<Promise> {
+        imagebitmap::create_image_bitmap(self.upcast(), image, None, options)
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
+    fn CreateImageBitmap_(&self,
+                          image: ImageBitmapSource,
+                          sx: i32,
+                          sy: i32,
+                          sw: i32,
+                          sh: i32,
+                          options: &ImageBitmapOptions)
+                          -> Rc<Promise> {
+        let crop_rect = imagebitmap::normalize_crop_rect(sx, sy, sw, sh);
+        imagebitmap::create_image_bitmap(self.upcast(), image, Some(crop_rect), options)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-windoworworkerglobalscope-indexeddb
+    fn IndexedDB(&self) -> DomRoot<IDBFactory> {
+        self.indexed_db.or_init(|| IDBFactory::new(self.upcast()))
+    }
+
+    // https://w3c.github.io/ServiceWorker/#self-caches
+    fn Caches(&self) -> DomRoot<CacheStorage> {
+        self.caches.or_init(|| CacheStorage::new(self.upcast()))
+    }
+
     // https://w3c.github.io/hr-time/#the-performance-attribute
     fn Performance(&self) -> DomRoot<Performance> {
         self.performance.or_init(|| {
@@ -390,6 +440,10 @@ impl WorkerGlobalScope {
         RemoteEventTaskSource(self.script_chan(), self.pipeline_id())
     }
 
+    pub fn port_message_queue(&self) -> PortMessageQueue {
+        PortMessageQueue(self.script_chan(), self.pipeline_id())
+    }
+
     pub fn new_script_pair(&self) -> (Box<ScriptChan + Send>, Box<ScriptPort + Send>) {
         let dedicated = self.downcast::<DedicatedWorkerGlobalScope>();
         if let Some(dedicated) = dedicated {
@@ -0,0 +1,494 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::WritableStreamBinding::{self, WritableStreamMethods};
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::transformstream::TransformStream;
+use dom::writablestreamdefaultcontroller::WritableStreamDefaultController;
+use dom::writablestreamdefaultwriter::WritableStreamDefaultWriter;
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSContext, JSObject};
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::{HandleObject, HandleValue, MutableHandleValue};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::mem;
+use std::rc::Rc;
+use streams::{extract_high_water_mark, extract_size_algorithm, get_property, invoke_or_noop, object_or_null};
+use streams::{promise_invoke_or_noop, promise_rejected_with_error, promise_resolved_with, resolved_promise};
+use streams::upon_settlement;
+
+/// <https://streams.spec.whatwg.org/#writablestream-state>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum WritableStreamState {
+    Writable,
+    Closed,
+    Erroring,
+    Errored,
+}
+
+/// Where the controller of a writable stream writes its chunks to.
+#[derive(Clone, JSTraceable, MallocSizeOf)]
+#[must_root]
+pub enum UnderlyingSink {
+    /// The underlying sink object given to the constructor, which the
+    /// controller keeps.
+    Script,
+    /// A sink that was closed, errored or aborted, and drops its chunks.
+    Cleared,
+    /// The writable side of a transform stream.
+    Transform(Dom<TransformStream>),
+}
+
+impl UnderlyingSink {
+    /// Runs the start algorithm, returning a promise settled once the sink
+    /// has started.
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn start(&self,
+                        cx: *mut JSContext,
+                        global: &GlobalScope,
+                        sink: HandleObject,
+                        controller: HandleValue)
+                        -> Fallible<Rc<Promise>> {
+        match *self {
+            UnderlyingSink::Script => {
+                rooted!(in(cx) let mut result = UndefinedValue());
+                invoke_or_noop(cx, sink, "start", &[controller.get()], result.handle_mut())?;
+                Ok(promise_resolved_with(global, cx, result.handle()))
+            },
+            UnderlyingSink::Transform(ref stream) => Ok(stream.start_promise()),
+            UnderlyingSink::Cleared => Ok(resolved_promise(global)),
+        }
+    }
+
+    /// Runs the write algorithm.
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn write(&self,
+                        cx: *mut JSContext,
+                        global: &GlobalScope,
+                        sink: HandleObject,
+                        chunk: HandleValue,
+                        controller: HandleValue)
+                        -> Rc<Promise> {
+        match *self {
+            UnderlyingSink::Script => {
+                promise_invoke_or_noop(global, cx, sink, "write", &[chunk.get(), controller.get()])
+            },
+            UnderlyingSink::Transform(ref stream) => stream.sink_write(cx, chunk),
+            UnderlyingSink::Cleared => resolved_promise(global),
+        }
+    }
+
+    /// Runs the close algorithm.
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn close(&self, cx: *mut JSContext, global: &GlobalScope, sink: HandleObject) -> Rc<Promise> {
+        match *self {
+            UnderlyingSink::Script => promise_invoke_or_noop(global, cx, sink, "close", &[]),
+            UnderlyingSink::Transform(ref stream) => stream.sink_close(cx),
+            UnderlyingSink::Cleared => resolved_promise(global),
+        }
+    }
+
+    /// Runs the abort algorithm.
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn abort(&self,
+                        cx: *mut JSContext,
+                        global: &GlobalScope,
+                        sink: HandleObject,
+                        reason: HandleValue)
+                        -> Rc<Promise> {
+        match *self {
+            UnderlyingSink::Script => promise_invoke_or_noop(global, cx, sink, "abort", &[reason.get()]),
+            UnderlyingSink::Transform(ref stream) => stream.sink_abort(cx, reason),
+            UnderlyingSink::Cleared => resolved_promise(global),
+        }
+    }
+}
+
+/// <https://streams.spec.whatwg.org/#pending-abort-request>
+#[derive(JSTraceable, MallocSizeOf)]
+struct PendingAbortRequest {
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+    #[ignore_malloc_size_of = "mozjs"]
+    reason: Box<Heap<JSVal>>,
+    was_already_erroring: bool,
+}
+
+/// <https://streams.spec.whatwg.org/#ws-class>
+#[dom_struct]
+pub struct WritableStream {
+    reflector_: Reflector,
+    state: Cell<WritableStreamState>,
+    #[ignore_malloc_size_of = "mozjs"]
+    stored_error: Heap<JSVal>,
+    backpressure: Cell<bool>,
+    controller: MutNullableDom<WritableStreamDefaultController>,
+    writer: MutNullableDom<WritableStreamDefaultWriter>,
+    #[ignore_malloc_size_of = "Rc"]
+    write_requests: DomRefCell<VecDeque<Rc<Promise>>>,
+    #[ignore_malloc_size_of = "Rc"]
+    in_flight_write_request: DomRefCell<Option<Rc<Promise>>>,
+    #[ignore_malloc_size_of = "Rc"]
+    close_request: DomRefCell<Option<Rc<Promise>>>,
+    #[ignore_malloc_size_of = "Rc"]
+    in_flight_close_request: DomRefCell<Option<Rc<Promise>>>,
+    pending_abort_request: DomRefCell<Option<PendingAbortRequest>>,
+}
+
+impl WritableStream {
+    fn new_inherited() -> WritableStream {
+        WritableStream {
+            reflector_: Reflector::new(),
+            state: Cell::new(WritableStreamState::Writable),
+            stored_error: Heap::default(),
+            backpressure: Cell::new(false),
+            controller: Default::default(),
+            writer: Default::default(),
+            write_requests: DomRefCell::new(VecDeque::new()),
+            in_flight_write_request: DomRefCell::new(None),
+            close_request: DomRefCell::new(None),
+            in_flight_close_request: DomRefCell::new(None),
+            pending_abort_request: DomRefCell::new(None),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<WritableStream> {
+        reflect_dom_object(Box::new(WritableStream::new_inherited()), global, WritableStreamBinding::Wrap)
+    }
+
+    /// Creates a stream writing to a sink that is not given by script, and
+    /// so never fails to start.
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub fn new_with_sink(global: &GlobalScope,
+                         sink: UnderlyingSink,
+                         high_water_mark: f64)
+                         -> DomRoot<WritableStream> {
+        let cx = global.get_cx();
+        let stream = WritableStream::new(global);
+        rooted!(in(cx) let size = UndefinedValue());
+        unsafe {
+            WritableStreamDefaultController::set_up(cx, &stream, sink, HandleObject::null(),
+                                                    high_water_mark, size.handle())
+                .expect("Only sinks given by script can fail to start");
+        }
+        stream
+    }
+
+    // https://streams.spec.whatwg.org/#ws-constructor
+    #[allow(unsafe_code)]
+    pub unsafe fn Constructor(cx: *mut JSContext,
+                              global: &GlobalScope,
+                              underlying_sink: Option<*mut JSObject>,
+                              strategy: Option<*mut JSObject>)
+                              -> Fallible<DomRoot<WritableStream>> {
+        rooted!(in(cx) let underlying_sink = object_or_null(underlying_sink));
+        rooted!(in(cx) let strategy = object_or_null(strategy));
+        let stream = WritableStream::new(global);
+
+        rooted!(in(cx) let mut type_ = UndefinedValue());
+        if get_property(cx, underlying_sink.handle(), "type", type_.handle_mut())? {
+            return Err(Error::Range("Writable streams do not have types".to_owned()));
+        }
+        rooted!(in(cx) let mut size = UndefinedValue());
+        extract_size_algorithm(cx, strategy.handle(), size.handle_mut())?;
+        let high_water_mark = extract_high_water_mark(cx, strategy.handle(), 1.)?;
+        WritableStreamDefaultController::set_up(cx, &stream, UnderlyingSink::Script, underlying_sink.handle(),
+                                                high_water_mark, size.handle())?;
+        Ok(stream)
+    }
+
+    pub fn state(&self) -> WritableStreamState {
+        self.state.get()
+    }
+
+    /// <https://streams.spec.whatwg.org/#is-writable-stream-locked>
+    pub fn is_locked(&self) -> bool {
+        self.writer.get().is_some()
+    }
+
+    pub fn get_stored_error(&self, mut rval: MutableHandleValue) {
+        rval.set(self.stored_error.get());
+    }
+
+    pub fn has_backpressure(&self) -> bool {
+        self.backpressure.get()
+    }
+
+    pub fn controller(&self) -> DomRoot<WritableStreamDefaultController> {
+        self.controller.get().expect("A stream has a controller")
+    }
+
+    pub fn set_controller(&self, controller: &WritableStreamDefaultController) {
+        self.controller.set(Some(controller));
+    }
+
+    pub fn set_writer(&self, writer: Option<&WritableStreamDefaultWriter>) {
+        self.writer.set(writer);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-close-queued-or-in-flight>
+    pub fn close_queued_or_in_flight(&self) -> bool {
+        self.close_request.borrow().is_some() || self.in_flight_close_request.borrow().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-has-operation-marked-in-flight>
+    pub fn has_operation_marked_in_flight(&self) -> bool {
+        self.in_flight_write_request.borrow().is_some() || self.in_flight_close_request.borrow().is_some()
+    }
+
+    pub fn has_in_flight_write_request(&self) -> bool {
+        self.in_flight_write_request.borrow().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-abort>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn abort(&self, cx: *mut JSContext, reason: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        match self.state.get() {
+            WritableStreamState::Closed | WritableStreamState::Errored => return resolved_promise(&global),
+            WritableStreamState::Writable | WritableStreamState::Erroring => {},
+        }
+        if let Some(ref request) = *self.pending_abort_request.borrow() {
+            return request.promise.clone();
+        }
+        let was_already_erroring = self.state.get() == WritableStreamState::Erroring;
+        rooted!(in(cx) let mut reason = reason.get());
+        if was_already_erroring {
+            reason.set(UndefinedValue());
+        }
+        let promise = Promise::new(&global);
+        *self.pending_abort_request.borrow_mut() = Some(PendingAbortRequest {
+            promise: promise.clone(),
+            reason: Heap::boxed(reason.get()),
+            was_already_erroring: was_already_erroring,
+        });
+        if !was_already_erroring {
+            self.start_erroring(reason.handle());
+        }
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-close>
+    #[allow(unrooted_must_root)]
+    pub fn close(&self) -> Rc<Promise> {
+        let global = self.global();
+        match self.state.get() {
+            WritableStreamState::Closed | WritableStreamState::Errored => {
+                return promise_rejected_with_error(&global, Error::Type("The stream is closed".to_owned()));
+            },
+            WritableStreamState::Writable | WritableStreamState::Erroring => {},
+        }
+        let promise = Promise::new(&global);
+        *self.close_request.borrow_mut() = Some(promise.clone());
+        if let Some(writer) = self.writer.get() {
+            if self.backpressure.get() && self.state.get() == WritableStreamState::Writable {
+                writer.resolve_ready_promise();
+            }
+        }
+        self.controller().close();
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-add-write-request>
+    #[allow(unrooted_must_root)]
+    pub fn add_write_request(&self) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        self.write_requests.borrow_mut().push_back(promise.clone());
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-deal-with-rejection>
+    pub fn deal_with_rejection(&self, error: HandleValue) {
+        if self.state.get() == WritableStreamState::Writable {
+            return self.start_erroring(error);
+        }
+        self.finish_erroring();
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-start-erroring>
+    pub fn start_erroring(&self, reason: HandleValue) {
+        assert_eq!(self.state.get(), WritableStreamState::Writable);
+        let controller = self.controller();
+        self.state.set(WritableStreamState::Erroring);
+        self.stored_error.set(reason.get());
+        if let Some(writer) = self.writer.get() {
+            writer.ensure_ready_promise_rejected(reason);
+        }
+        if !self.has_operation_marked_in_flight() && controller.is_started() {
+            self.finish_erroring();
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-erroring>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub fn finish_erroring(&self) {
+        assert_eq!(self.state.get(), WritableStreamState::Erroring);
+        assert!(!self.has_operation_marked_in_flight());
+        self.state.set(WritableStreamState::Errored);
+        let controller = self.controller();
+        controller.error_steps();
+        let cx = self.global().get_cx();
+        rooted!(in(cx) let stored_error = self.stored_error.get());
+        let write_requests = mem::replace(&mut *self.write_requests.borrow_mut(), VecDeque::new());
+        for promise in write_requests {
+            promise.reject_native(&stored_error.handle());
+        }
+        let abort_request = self.pending_abort_request.borrow_mut().take();
+        let abort_request = match abort_request {
+            Some(abort_request) => abort_request,
+            None => return self.reject_close_and_closed_promise_if_needed(),
+        };
+        if abort_request.was_already_erroring {
+            abort_request.promise.reject_native(&stored_error.handle());
+            return self.reject_close_and_closed_promise_if_needed();
+        }
+        rooted!(in(cx) let reason = abort_request.reason.get());
+        let promise = unsafe { controller.abort_steps(cx, reason.handle()) };
+        let holder = AbortHolder {
+            stream: Dom::from_ref(self),
+            promise: abort_request.promise,
+        };
+        upon_settlement(&promise, holder, Some(AbortHolder::aborted), Some(AbortHolder::abort_failed));
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-in-flight-write>
+    pub fn finish_in_flight_write(&self) {
+        let request = self.in_flight_write_request.borrow_mut().take();
+        request.expect("A write is in flight").resolve_native(&());
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-in-flight-write-with-error>
+    pub fn finish_in_flight_write_with_error(&self, error: HandleValue) {
+        let request = self.in_flight_write_request.borrow_mut().take();
+        request.expect("A write is in flight").reject_native(&error);
+        self.deal_with_rejection(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-in-flight-close>
+    pub fn finish_in_flight_close(&self) {
+        let request = self.in_flight_close_request.borrow_mut().take();
+        request.expect("A close is in flight").resolve_native(&());
+        if self.state.get() == WritableStreamState::Erroring {
+            self.stored_error.set(UndefinedValue());
+            let abort_request = self.pending_abort_request.borrow_mut().take();
+            if let Some(abort_request) = abort_request {
+                abort_request.promise.resolve_native(&());
+            }
+        }
+        self.state.set(WritableStreamState::Closed);
+        if let Some(writer) = self.writer.get() {
+            writer.resolve_closed_promise();
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-in-flight-close-with-error>
+    pub fn finish_in_flight_close_with_error(&self, error: HandleValue) {
+        let request = self.in_flight_close_request.borrow_mut().take();
+        request.expect("A close is in flight").reject_native(&error);
+        let abort_request = self.pending_abort_request.borrow_mut().take();
+        if let Some(abort_request) = abort_request {
+            abort_request.promise.reject_native(&error);
+        }
+        self.deal_with_rejection(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-mark-close-request-in-flight>
+    pub fn mark_close_request_in_flight(&self) {
+        let request = self.close_request.borrow_mut().take();
+        *self.in_flight_close_request.borrow_mut() = request;
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-mark-first-write-request-in-flight>
+    pub fn mark_first_write_request_in_flight(&self) {
+        let request = self.write_requests.borrow_mut().pop_front();
+        *self.in_flight_write_request.borrow_mut() = request;
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-reject-close-and-closed-promise-if-needed>
+    fn reject_close_and_closed_promise_if_needed(&self) {
+        let cx = self.global().get_cx();
+        rooted!(in(cx) let stored_error = self.stored_error.get());
+        let request = self.close_request.borrow_mut().take();
+        if let Some(request) = request {
+            request.reject_native(&stored_error.handle());
+        }
+        if let Some(writer) = self.writer.get() {
+            writer.reject_closed_promise(stored_error.handle());
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-update-backpressure>
+    pub fn update_backpressure(&self, backpressure: bool) {
+        if let Some(writer) = self.writer.get() {
+            if backpressure != self.backpressure.get() {
+                if backpressure {
+                    writer.reset_ready_promise();
+                } else {
+                    writer.resolve_ready_promise();
+                }
+            }
+        }
+        self.backpressure.set(backpressure);
+    }
+}
+
+/// The stream of an abort request waiting for the abort algorithm.
+#[derive(Clone, JSTraceable, MallocSizeOf)]
+#[must_root]
+struct AbortHolder {
+    stream: Dom<WritableStream>,
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+}
+
+impl AbortHolder {
+    fn aborted(holder: &AbortHolder, _cx: *mut JSContext, _value: HandleValue) {
+        holder.promise.resolve_native(&());
+        holder.stream.reject_close_and_closed_promise_if_needed();
+    }
+
+    fn abort_failed(holder: &AbortHolder, _cx: *mut JSContext, reason: HandleValue) {
+        holder.promise.reject_native(&reason);
+        holder.stream.reject_close_and_closed_promise_if_needed();
+    }
+}
+
+impl WritableStreamMethods for WritableStream {
+    // https://streams.spec.whatwg.org/#ws-locked
+    fn Locked(&self) -> bool {
+        self.is_locked()
+    }
+
+    #[allow(unrooted_must_root, unsafe_code)]
+    // https://streams.spec.whatwg.org/#ws-abort
+    unsafe fn Abort(&self, cx: *mut JSContext, reason: HandleValue) -> Rc<Promise> {
+        if self.is_locked() {
+            return promise_rejected_with_error(&self.global(), Error::Type("The stream is locked".to_owned()));
+        }
+        self.abort(cx, reason)
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://streams.spec.whatwg.org/#ws-close
+    fn Close(&self) -> Rc<Promise> {
+        if self.is_locked() {
+            return promise_rejected_with_error(&self.global(), Error::Type("The stream is locked".to_owned()));
+        }
+        if self.close_queued_or_in_flight() {
+            return promise_rejected_with_error(&self.global(), Error::Type("The stream is closing".to_owned()));
+        }
+        self.close()
+    }
+
+    // https://streams.spec.whatwg.org/#ws-get-writer
+    fn GetWriter(&self) -> Fallible<DomRoot<WritableStreamDefaultWriter>> {
+        WritableStreamDefaultWriter::set_up(&self.global(), self)
+    }
+}
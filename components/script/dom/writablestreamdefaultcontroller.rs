@@ -0,0 +1,287 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::WritableStreamDefaultControllerBinding;
+use dom::bindings::codegen::Bindings::WritableStreamDefaultControllerBinding::WritableStreamDefaultControllerMethods;
+use dom::bindings::error::Fallible;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::writablestream::{UnderlyingSink, WritableStream, WritableStreamState};
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSContext, JSObject};
+use js::jsval::{JSVal, ObjectValue, UndefinedValue};
+use js::rust::{HandleObject, HandleValue};
+use std::cell::Cell;
+use std::ptr;
+use std::rc::Rc;
+use streams::{QueueWithSizes, call_size_algorithm, error_to_value, upon_settlement};
+
+/// <https://streams.spec.whatwg.org/#ws-default-controller-class>
+#[dom_struct]
+pub struct WritableStreamDefaultController {
+    reflector_: Reflector,
+    stream: Dom<WritableStream>,
+    sink: DomRefCell<UnderlyingSink>,
+    /// The underlying sink object of `UnderlyingSink::Script`.
+    #[ignore_malloc_size_of = "mozjs"]
+    underlying_sink: Heap<*mut JSObject>,
+    queue: DomRefCell<QueueWithSizes>,
+    started: Cell<bool>,
+    strategy_hwm: Cell<f64>,
+    #[ignore_malloc_size_of = "mozjs"]
+    strategy_size: Heap<JSVal>,
+}
+
+impl WritableStreamDefaultController {
+    #[allow(unrooted_must_root)]
+    fn new_inherited(stream: &WritableStream,
+                     sink: UnderlyingSink,
+                     high_water_mark: f64)
+                     -> WritableStreamDefaultController {
+        WritableStreamDefaultController {
+            reflector_: Reflector::new(),
+            stream: Dom::from_ref(stream),
+            sink: DomRefCell::new(sink),
+            underlying_sink: Heap::default(),
+            queue: Default::default(),
+            started: Cell::new(false),
+            strategy_hwm: Cell::new(high_water_mark),
+            strategy_size: Heap::default(),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    fn new(global: &GlobalScope,
+           stream: &WritableStream,
+           sink: UnderlyingSink,
+           high_water_mark: f64)
+           -> DomRoot<WritableStreamDefaultController> {
+        reflect_dom_object(
+            Box::new(WritableStreamDefaultController::new_inherited(stream, sink, high_water_mark)),
+            global,
+            WritableStreamDefaultControllerBinding::Wrap,
+        )
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-writable-stream-default-controller>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn set_up(cx: *mut JSContext,
+                         stream: &WritableStream,
+                         sink: UnderlyingSink,
+                         underlying_sink: HandleObject,
+                         high_water_mark: f64,
+                         size: HandleValue)
+                         -> Fallible<DomRoot<WritableStreamDefaultController>> {
+        let global = stream.global();
+        let controller = WritableStreamDefaultController::new(&global, stream, sink, high_water_mark);
+        controller.underlying_sink.set(underlying_sink.get());
+        controller.strategy_size.set(size.get());
+        stream.set_controller(&controller);
+        stream.update_backpressure(controller.get_backpressure());
+
+        rooted!(in(cx) let controller_value = ObjectValue(controller.reflector().get_jsobject().get()));
+        let sink = controller.sink.borrow().clone();
+        let start = sink.start(cx, &global, underlying_sink, controller_value.handle())?;
+        upon_settlement(&start, Dom::from_ref(&*controller),
+                        Some(WritableStreamDefaultController::started),
+                        Some(WritableStreamDefaultController::start_failed));
+        Ok(controller)
+    }
+
+    #[allow(unrooted_must_root)]
+    fn started(controller: &Dom<WritableStreamDefaultController>, _cx: *mut JSContext, _value: HandleValue) {
+        controller.started.set(true);
+        controller.advance_queue_if_needed();
+    }
+
+    #[allow(unrooted_must_root)]
+    fn start_failed(controller: &Dom<WritableStreamDefaultController>, _cx: *mut JSContext, error: HandleValue) {
+        controller.started.set(true);
+        controller.stream.deal_with_rejection(error);
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.started.get()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-clear-algorithms>
+    fn clear_algorithms(&self) {
+        *self.sink.borrow_mut() = UnderlyingSink::Cleared;
+        self.underlying_sink.set(ptr::null_mut());
+        self.strategy_size.set(UndefinedValue());
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-get-desired-size>
+    pub fn desired_size(&self) -> f64 {
+        self.strategy_hwm.get() - self.queue.borrow().total_size()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-get-backpressure>
+    fn get_backpressure(&self) -> bool {
+        self.desired_size() <= 0.
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-close>
+    pub fn close(&self) {
+        self.queue.borrow_mut().enqueue_close();
+        self.advance_queue_if_needed();
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-get-chunk-size>
+    #[allow(unsafe_code)]
+    pub unsafe fn get_chunk_size(&self, cx: *mut JSContext, chunk: HandleValue) -> f64 {
+        rooted!(in(cx) let size = self.strategy_size.get());
+        match call_size_algorithm(cx, size.handle(), chunk) {
+            Ok(size) => size,
+            Err(error) => {
+                rooted!(in(cx) let mut error_value = UndefinedValue());
+                error_to_value(cx, &self.global(), error, error_value.handle_mut());
+                self.error_if_needed(error_value.handle());
+                1.
+            },
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-write>
+    #[allow(unsafe_code)]
+    pub unsafe fn write(&self, cx: *mut JSContext, chunk: HandleValue, chunk_size: f64) {
+        if let Err(error) = self.queue.borrow_mut().enqueue_value_with_size(chunk, chunk_size) {
+            rooted!(in(cx) let mut error_value = UndefinedValue());
+            error_to_value(cx, &self.global(), error, error_value.handle_mut());
+            return self.error_if_needed(error_value.handle());
+        }
+        if !self.stream.close_queued_or_in_flight() && self.stream.state() == WritableStreamState::Writable {
+            self.stream.update_backpressure(self.get_backpressure());
+        }
+        self.advance_queue_if_needed();
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-advance-queue-if-needed>
+    fn advance_queue_if_needed(&self) {
+        if !self.started.get() || self.stream.has_in_flight_write_request() {
+            return;
+        }
+        match self.stream.state() {
+            WritableStreamState::Erroring => return self.stream.finish_erroring(),
+            WritableStreamState::Writable => {},
+            WritableStreamState::Closed | WritableStreamState::Errored => return,
+        }
+        if self.queue.borrow().is_empty() {
+            return;
+        }
+        if self.queue.borrow().peek_is_close() {
+            self.process_close();
+        } else {
+            self.process_write();
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-error-if-needed>
+    pub fn error_if_needed(&self, error: HandleValue) {
+        if self.stream.state() == WritableStreamState::Writable {
+            self.error(error);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-process-close>
+    #[allow(unrooted_must_root, unsafe_code)]
+    fn process_close(&self) {
+        self.stream.mark_close_request_in_flight();
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(cx) let mut close_sentinel = UndefinedValue());
+        self.queue.borrow_mut().dequeue_value(close_sentinel.handle_mut());
+        rooted!(in(cx) let underlying_sink = self.underlying_sink.get());
+        let sink = self.sink.borrow().clone();
+        let close = unsafe { sink.close(cx, &global, underlying_sink.handle()) };
+        self.clear_algorithms();
+        upon_settlement(&close, Dom::from_ref(self),
+                        Some(WritableStreamDefaultController::closed),
+                        Some(WritableStreamDefaultController::close_failed));
+    }
+
+    #[allow(unrooted_must_root)]
+    fn closed(controller: &Dom<WritableStreamDefaultController>, _cx: *mut JSContext, _value: HandleValue) {
+        controller.stream.finish_in_flight_close();
+    }
+
+    #[allow(unrooted_must_root)]
+    fn close_failed(controller: &Dom<WritableStreamDefaultController>, _cx: *mut JSContext, error: HandleValue) {
+        controller.stream.finish_in_flight_close_with_error(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-process-write>
+    #[allow(unrooted_must_root, unsafe_code)]
+    fn process_write(&self) {
+        self.stream.mark_first_write_request_in_flight();
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(cx) let mut chunk = UndefinedValue());
+        self.queue.borrow().peek_value(chunk.handle_mut());
+        rooted!(in(cx) let underlying_sink = self.underlying_sink.get());
+        rooted!(in(cx) let controller = ObjectValue(self.reflector().get_jsobject().get()));
+        let sink = self.sink.borrow().clone();
+        let write = unsafe {
+            sink.write(cx, &global, underlying_sink.handle(), chunk.handle(), controller.handle())
+        };
+        upon_settlement(&write, Dom::from_ref(self),
+                        Some(WritableStreamDefaultController::written),
+                        Some(WritableStreamDefaultController::write_failed));
+    }
+
+    #[allow(unrooted_must_root)]
+    fn written(controller: &Dom<WritableStreamDefaultController>, cx: *mut JSContext, _value: HandleValue) {
+        let stream = &controller.stream;
+        stream.finish_in_flight_write();
+        rooted!(in(cx) let mut chunk = UndefinedValue());
+        controller.queue.borrow_mut().dequeue_value(chunk.handle_mut());
+        if !stream.close_queued_or_in_flight() && stream.state() == WritableStreamState::Writable {
+            stream.update_backpressure(controller.get_backpressure());
+        }
+        controller.advance_queue_if_needed();
+    }
+
+    #[allow(unrooted_must_root)]
+    fn write_failed(controller: &Dom<WritableStreamDefaultController>, _cx: *mut JSContext, error: HandleValue) {
+        if controller.stream.state() == WritableStreamState::Writable {
+            controller.clear_algorithms();
+        }
+        controller.stream.finish_in_flight_write_with_error(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-error>
+    pub fn error(&self, error: HandleValue) {
+        self.clear_algorithms();
+        self.stream.start_erroring(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#ws-default-controller-private-abort>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn abort_steps(&self, cx: *mut JSContext, reason: HandleValue) -> Rc<Promise> {
+        rooted!(in(cx) let underlying_sink = self.underlying_sink.get());
+        let sink = self.sink.borrow().clone();
+        let result = sink.abort(cx, &self.global(), underlying_sink.handle(), reason);
+        self.clear_algorithms();
+        result
+    }
+
+    /// <https://streams.spec.whatwg.org/#ws-default-controller-private-error>
+    pub fn error_steps(&self) {
+        self.queue.borrow_mut().reset();
+    }
+}
+
+impl WritableStreamDefaultControllerMethods for WritableStreamDefaultController {
+    #[allow(unsafe_code)]
+    // https://streams.spec.whatwg.org/#ws-default-controller-error
+    unsafe fn Error(&self, _cx: *mut JSContext, error: HandleValue) {
+        if self.stream.state() != WritableStreamState::Writable {
+            return;
+        }
+        self.error(error);
+    }
+}
@@ -0,0 +1,268 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::WritableStreamDefaultWriterBinding;
+use dom::bindings::codegen::Bindings::WritableStreamDefaultWriterBinding::WritableStreamDefaultWriterMethods;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{DomRoot, MutNullableDom};
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::writablestream::{WritableStream, WritableStreamState};
+use dom_struct::dom_struct;
+use js::jsapi::{JSAutoCompartment, JSContext};
+use js::jsval::UndefinedValue;
+use js::rust::HandleValue;
+use std::rc::Rc;
+use streams::{error_to_value, promise_rejected_with, promise_rejected_with_error, resolved_promise};
+
+/// <https://streams.spec.whatwg.org/#default-writer-class>
+#[dom_struct]
+pub struct WritableStreamDefaultWriter {
+    reflector_: Reflector,
+    stream: MutNullableDom<WritableStream>,
+    #[ignore_malloc_size_of = "Rc"]
+    closed_promise: DomRefCell<Rc<Promise>>,
+    #[ignore_malloc_size_of = "Rc"]
+    ready_promise: DomRefCell<Rc<Promise>>,
+}
+
+impl WritableStreamDefaultWriter {
+    #[allow(unrooted_must_root)]
+    fn new_inherited(closed_promise: Rc<Promise>, ready_promise: Rc<Promise>) -> WritableStreamDefaultWriter {
+        WritableStreamDefaultWriter {
+            reflector_: Reflector::new(),
+            stream: Default::default(),
+            closed_promise: DomRefCell::new(closed_promise),
+            ready_promise: DomRefCell::new(ready_promise),
+        }
+    }
+
+    fn new(global: &GlobalScope) -> DomRoot<WritableStreamDefaultWriter> {
+        reflect_dom_object(
+            Box::new(WritableStreamDefaultWriter::new_inherited(Promise::new(global), Promise::new(global))),
+            global,
+            WritableStreamDefaultWriterBinding::Wrap,
+        )
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-constructor
+    pub fn Constructor(global: &GlobalScope,
+                       stream: &WritableStream)
+                       -> Fallible<DomRoot<WritableStreamDefaultWriter>> {
+        WritableStreamDefaultWriter::set_up(global, stream)
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-writable-stream-default-writer>
+    pub fn set_up(global: &GlobalScope, stream: &WritableStream) -> Fallible<DomRoot<WritableStreamDefaultWriter>> {
+        if stream.is_locked() {
+            return Err(Error::Type("The stream is locked".to_owned()));
+        }
+        let writer = WritableStreamDefaultWriter::new(global);
+        writer.stream.set(Some(stream));
+        stream.set_writer(Some(&writer));
+        let cx = global.get_cx();
+        rooted!(in(cx) let mut stored_error = UndefinedValue());
+        stream.get_stored_error(stored_error.handle_mut());
+        match stream.state() {
+            WritableStreamState::Writable => {
+                if stream.close_queued_or_in_flight() || !stream.has_backpressure() {
+                    writer.resolve_ready_promise();
+                }
+            },
+            WritableStreamState::Erroring => writer.ready_promise.borrow().reject_native(&stored_error.handle()),
+            WritableStreamState::Closed => {
+                writer.resolve_ready_promise();
+                writer.resolve_closed_promise();
+            },
+            WritableStreamState::Errored => {
+                writer.ready_promise.borrow().reject_native(&stored_error.handle());
+                writer.reject_closed_promise(stored_error.handle());
+            },
+        }
+        Ok(writer)
+    }
+
+    /// The promise resolved once the stream is closed, or rejected if it is
+    /// errored or the writer is released.
+    #[allow(unrooted_must_root)]
+    pub fn closed_promise(&self) -> Rc<Promise> {
+        self.closed_promise.borrow().clone()
+    }
+
+    /// The promise resolved once the stream has no backpressure.
+    #[allow(unrooted_must_root)]
+    pub fn ready_promise(&self) -> Rc<Promise> {
+        self.ready_promise.borrow().clone()
+    }
+
+    pub fn resolve_closed_promise(&self) {
+        self.closed_promise.borrow().resolve_native(&());
+    }
+
+    pub fn reject_closed_promise(&self, error: HandleValue) {
+        self.closed_promise.borrow().reject_native(&error);
+    }
+
+    pub fn resolve_ready_promise(&self) {
+        self.ready_promise.borrow().resolve_native(&());
+    }
+
+    /// Replaces the ready promise with a pending one, once the stream has
+    /// backpressure.
+    pub fn reset_ready_promise(&self) {
+        *self.ready_promise.borrow_mut() = Promise::new(&self.global());
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-ensure-ready-promise-rejected>
+    pub fn ensure_ready_promise_rejected(&self, error: HandleValue) {
+        if self.ready_promise.borrow().is_fulfilled() {
+            *self.ready_promise.borrow_mut() = promise_rejected_with(&self.global(), error);
+        } else {
+            self.ready_promise.borrow().reject_native(&error);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-ensure-closed-promise-rejected>
+    fn ensure_closed_promise_rejected(&self, error: HandleValue) {
+        if self.closed_promise.borrow().is_fulfilled() {
+            *self.closed_promise.borrow_mut() = promise_rejected_with(&self.global(), error);
+        } else {
+            self.closed_promise.borrow().reject_native(&error);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-get-desired-size>
+    fn get_desired_size(&self, stream: &WritableStream) -> Option<f64> {
+        match stream.state() {
+            WritableStreamState::Errored | WritableStreamState::Erroring => None,
+            WritableStreamState::Closed => Some(0.),
+            WritableStreamState::Writable => Some(stream.controller().desired_size()),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-write>
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub unsafe fn write(&self, cx: *mut JSContext, chunk: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let stream = self.stream.get().expect("The writer is not released");
+        let controller = stream.controller();
+        let chunk_size = controller.get_chunk_size(cx, chunk);
+        // The size algorithm may have released the writer.
+        if self.stream.get().is_none() {
+            return promise_rejected_with_error(&global, Error::Type("The writer was released".to_owned()));
+        }
+        rooted!(in(cx) let mut stored_error = UndefinedValue());
+        stream.get_stored_error(stored_error.handle_mut());
+        match stream.state() {
+            WritableStreamState::Errored | WritableStreamState::Erroring => {
+                return promise_rejected_with(&global, stored_error.handle());
+            },
+            WritableStreamState::Closed => {
+                return promise_rejected_with_error(&global, Error::Type("The stream is closed".to_owned()));
+            },
+            WritableStreamState::Writable => {},
+        }
+        if stream.close_queued_or_in_flight() {
+            return promise_rejected_with_error(&global, Error::Type("The stream is closing".to_owned()));
+        }
+        let promise = stream.add_write_request();
+        controller.write(cx, chunk, chunk_size);
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-close-with-error-propagation>
+    #[allow(unrooted_must_root)]
+    pub fn close_with_error_propagation(&self) -> Rc<Promise> {
+        let global = self.global();
+        let stream = self.stream.get().expect("The writer is not released");
+        if stream.close_queued_or_in_flight() || stream.state() == WritableStreamState::Closed {
+            return resolved_promise(&global);
+        }
+        if stream.state() == WritableStreamState::Errored {
+            rooted!(in(global.get_cx()) let mut stored_error = UndefinedValue());
+            stream.get_stored_error(stored_error.handle_mut());
+            return promise_rejected_with(&global, stored_error.handle());
+        }
+        stream.close()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-release>
+    #[allow(unsafe_code)]
+    pub fn release(&self) {
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => return,
+        };
+        let global = self.global();
+        let cx = global.get_cx();
+        let _ac = JSAutoCompartment::new(cx, global.reflector().get_jsobject().get());
+        rooted!(in(cx) let mut error = UndefinedValue());
+        unsafe { error_to_value(cx, &global, Error::Type("The writer was released".to_owned()), error.handle_mut()) };
+        self.ensure_ready_promise_rejected(error.handle());
+        self.ensure_closed_promise_rejected(error.handle());
+        stream.set_writer(None);
+        self.stream.set(None);
+    }
+}
+
+impl WritableStreamDefaultWriterMethods for WritableStreamDefaultWriter {
+    #[allow(unrooted_must_root)]
+    // https://streams.spec.whatwg.org/#default-writer-closed
+    fn Closed(&self) -> Rc<Promise> {
+        self.closed_promise()
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-desired-size
+    fn GetDesiredSize(&self) -> Fallible<Option<f64>> {
+        match self.stream.get() {
+            Some(stream) => Ok(self.get_desired_size(&stream)),
+            None => Err(Error::Type("The writer was released".to_owned())),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://streams.spec.whatwg.org/#default-writer-ready
+    fn Ready(&self) -> Rc<Promise> {
+        self.ready_promise()
+    }
+
+    #[allow(unrooted_must_root, unsafe_code)]
+    // https://streams.spec.whatwg.org/#default-writer-abort
+    unsafe fn Abort(&self, cx: *mut JSContext, reason: HandleValue) -> Rc<Promise> {
+        match self.stream.get() {
+            Some(stream) => stream.abort(cx, reason),
+            None => promise_rejected_with_error(&self.global(), Error::Type("The writer was released".to_owned())),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://streams.spec.whatwg.org/#default-writer-close
+    fn Close(&self) -> Rc<Promise> {
+        let global = self.global();
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => return promise_rejected_with_error(&global, Error::Type("The writer was released".to_owned())),
+        };
+        if stream.close_queued_or_in_flight() {
+            return promise_rejected_with_error(&global, Error::Type("The stream is closing".to_owned()));
+        }
+        stream.close()
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-release-lock
+    fn ReleaseLock(&self) {
+        self.release();
+    }
+
+    #[allow(unrooted_must_root, unsafe_code)]
+    // https://streams.spec.whatwg.org/#default-writer-write
+    unsafe fn Write(&self, cx: *mut JSContext, chunk: HandleValue) -> Rc<Promise> {
+        if self.stream.get().is_none() {
+            return promise_rejected_with_error(&self.global(), Error::Type("The writer was released".to_owned()));
+        }
+        self.write(cx, chunk)
+    }
+}
@@ -56,6 +56,7 @@ use js::typedarray::{ArrayBuffer, CreateWith};
 use net_traits::{FetchChannels, FetchMetadata, FilteredMetadata};
 use net_traits::{FetchResponseListener, NetworkError, ReferrerPolicy};
 use net_traits::CoreResourceMsg::Fetch;
+use net_traits::csp::Violation;
 use net_traits::request::{CredentialsMode, Destination, RequestInit, RequestMode};
 use net_traits::trim_http_whitespace;
 use network_listener::{NetworkListener, PreInvoke};
@@ -256,6 +257,10 @@ impl XMLHttpRequest {
                 let rv = self.xhr.root().process_response_complete(self.gen_id, response);
                 *self.sync_status.borrow_mut() = Some(rv);
             }
+
+            fn process_csp_violations(&mut self, violations: Vec<Violation>) {
+                self.xhr.root().global().report_csp_violations(violations, None);
+            }
         }
 
         impl PreInvoke for XHRContext {
@@ -594,6 +599,7 @@ impl XMLHttpRequestMethods for XMLHttpRequest {
             referrer_url: self.referrer_url.clone(),
             referrer_policy: self.referrer_policy.clone(),
             pipeline_id: Some(self.global().pipeline_id()),
+            csp_list: self.global().get_csp_list(),
             .. RequestInit::default()
         };
 
@@ -0,0 +1,205 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Editing commands run by `document.execCommand` and by key presses in editing hosts.
+//!
+//! <https://w3c.github.io/editing/docs/execCommand/>
+
+use dom::bindings::codegen::Bindings::CharacterDataBinding::CharacterDataMethods;
+use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
+use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
+use dom::bindings::codegen::Bindings::RangeBinding::RangeMethods;
+use dom::bindings::codegen::Bindings::SelectionBinding::SelectionMethods;
+use dom::bindings::codegen::Bindings::TextBinding::TextMethods;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::characterdata::CharacterData;
+use dom::document::Document;
+use dom::element::Element;
+use dom::eventtarget::EventTarget;
+use dom::htmlbrelement::HTMLBRElement;
+use dom::htmlelement::HTMLElement;
+use dom::node::Node;
+use dom::range::Range;
+use dom::selection::Selection;
+use dom::text::Text;
+
+/// The commands supported by `document.execCommand`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EditingCommand {
+    /// <https://w3c.github.io/editing/docs/execCommand/#the-delete-command>
+    Delete,
+    /// <https://w3c.github.io/editing/docs/execCommand/#the-forwarddelete-command>
+    ForwardDelete,
+    /// <https://w3c.github.io/editing/docs/execCommand/#the-insertlinebreak-command>
+    InsertLineBreak,
+    /// <https://w3c.github.io/editing/docs/execCommand/#the-inserttext-command>
+    InsertText,
+    /// <https://w3c.github.io/editing/docs/execCommand/#the-selectall-command>
+    SelectAll,
+}
+
+impl EditingCommand {
+    /// Command names are matched ASCII case-insensitively.
+    pub fn from_name(name: &str) -> Option<EditingCommand> {
+        match &*name.to_ascii_lowercase() {
+            "delete" => Some(EditingCommand::Delete),
+            "forwarddelete" => Some(EditingCommand::ForwardDelete),
+            "insertlinebreak" => Some(EditingCommand::InsertLineBreak),
+            "inserttext" => Some(EditingCommand::InsertText),
+            "selectall" => Some(EditingCommand::SelectAll),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the document's selection and its range, along with the editing host
+/// containing it, if the whole selection is editable.
+fn editable_selection(document: &Document)
+                      -> Option<(DomRoot<Selection>, DomRoot<Range>, DomRoot<HTMLElement>)> {
+    let selection = document.selection()?;
+    let range = selection.range()?;
+    let host = range.StartContainer().editing_host()?;
+    if range.EndContainer().editing_host().as_ref() != Some(&host) {
+        return None;
+    }
+    Some((selection, range, host))
+}
+
+// https://w3c.github.io/editing/docs/execCommand/#enabled
+pub fn is_enabled(document: &Document, command: EditingCommand) -> bool {
+    match command {
+        EditingCommand::SelectAll => true,
+        _ => editable_selection(document).is_some(),
+    }
+}
+
+// https://w3c.github.io/editing/docs/execCommand/#execcommand()
+pub fn execute(document: &Document, command: EditingCommand, value: DOMString) -> bool {
+    if command == EditingCommand::SelectAll {
+        return select_all(document);
+    }
+    let (selection, range, host) = match editable_selection(document) {
+        Some(editable) => editable,
+        None => return false,
+    };
+    match command {
+        EditingCommand::Delete => delete(&selection, &range, false),
+        EditingCommand::ForwardDelete => delete(&selection, &range, true),
+        EditingCommand::InsertLineBreak => insert_line_break(document, &selection, &range),
+        EditingCommand::InsertText => insert_text(document, &selection, &range, value),
+        EditingCommand::SelectAll => unreachable!(),
+    }
+    host.upcast::<EventTarget>().fire_bubbling_event(atom!("input"));
+    true
+}
+
+// https://w3c.github.io/editing/docs/execCommand/#the-selectall-command
+fn select_all(document: &Document) -> bool {
+    let selection = match document.GetSelection() {
+        Some(selection) => selection,
+        None => return false,
+    };
+    let target = selection.range()
+        .and_then(|range| range.StartContainer().editing_host())
+        .map(DomRoot::upcast::<Element>)
+        .or_else(|| document.GetBody().map(DomRoot::upcast::<Element>))
+        .or_else(|| document.GetDocumentElement());
+    if let Some(target) = target {
+        let _ = selection.SelectAllChildren(target.upcast());
+    }
+    true
+}
+
+fn delete(selection: &Selection, range: &Range, forward: bool) {
+    if !range.Collapsed() {
+        let _ = range.DeleteContents();
+        return;
+    }
+    let (node, offset) = (range.StartContainer(), range.StartOffset());
+    if let Some(line_break) = node_next_to_caret(&node, offset, forward).filter(|node| node.is::<HTMLBRElement>()) {
+        line_break.remove_self();
+        return;
+    }
+    selection.modify_by_character(forward, true);
+    if let Some(range) = selection.range() {
+        if !range.Collapsed() {
+            let _ = range.DeleteContents();
+        }
+    }
+}
+
+fn insert_line_break(document: &Document, selection: &Selection, range: &Range) {
+    if !range.Collapsed() {
+        let _ = range.DeleteContents();
+    }
+    let (node, offset) = (range.StartContainer(), range.StartOffset());
+    let line_break = HTMLBRElement::new(local_name!("br"), None, document);
+    let (parent, index) = match node.downcast::<Text>() {
+        Some(text) => {
+            let parent = match node.GetParentNode() {
+                Some(parent) => parent,
+                None => return,
+            };
+            let after = match text.SplitText(offset) {
+                Ok(after) => after,
+                Err(_) => return,
+            };
+            if parent.InsertBefore(line_break.upcast(), Some(after.upcast())).is_err() {
+                return;
+            }
+            (parent, line_break.upcast::<Node>().index())
+        },
+        None => {
+            if range.InsertNode(line_break.upcast()).is_err() {
+                return;
+            }
+            (node, offset)
+        },
+    };
+    let _ = selection.Collapse(Some(&parent), index + 1);
+}
+
+fn insert_text(document: &Document, selection: &Selection, range: &Range, value: DOMString) {
+    if !range.Collapsed() {
+        let _ = range.DeleteContents();
+    }
+    if value.is_empty() {
+        return;
+    }
+    let (node, offset) = (range.StartContainer(), range.StartOffset());
+    let length = value.encode_utf16().count() as u32;
+    match node.downcast::<CharacterData>() {
+        Some(data) if node.is::<Text>() => {
+            if data.InsertData(offset, value).is_ok() {
+                let _ = selection.Collapse(Some(&node), offset + length);
+            }
+        },
+        _ => {
+            let text = document.CreateTextNode(value);
+            if range.InsertNode(text.upcast()).is_ok() {
+                let _ = selection.Collapse(Some(text.upcast()), length);
+            }
+        },
+    }
+}
+
+/// Returns the node right before, or right after if `forward` is set, a caret
+/// at the given boundary point.
+fn node_next_to_caret(node: &Node, offset: u32, forward: bool) -> Option<DomRoot<Node>> {
+    if node.is::<CharacterData>() {
+        if forward && offset == node.len() {
+            node.GetNextSibling()
+        } else if !forward && offset == 0 {
+            node.GetPreviousSibling()
+        } else {
+            None
+        }
+    } else if forward {
+        node.children().nth(offset as usize)
+    } else {
+        node.children().nth(offset.checked_sub(1)? as usize)
+    }
+}
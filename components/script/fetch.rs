@@ -2,6 +2,9 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use body::BodyOperations;
+use dom::abortsignal::{AbortAlgorithm, AbortSignal};
+use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::RequestBinding::RequestInfo;
 use dom::bindings::codegen::Bindings::RequestBinding::RequestInit;
 use dom::bindings::codegen::Bindings::ResponseBinding::ResponseBinding::ResponseMethods;
@@ -10,7 +13,7 @@ use dom::bindings::error::Error;
 use dom::bindings::inheritance::Castable;
 use dom::bindings::refcounted::{Trusted, TrustedPromise};
 use dom::bindings::reflector::DomObject;
-use dom::bindings::root::DomRoot;
+use dom::bindings::root::{Dom, DomRoot};
 use dom::bindings::trace::RootedTraceableBox;
 use dom::globalscope::GlobalScope;
 use dom::headers::Guard;
@@ -20,23 +23,41 @@ use dom::response::Response;
 use dom::serviceworkerglobalscope::ServiceWorkerGlobalScope;
 use ipc_channel::ipc;
 use ipc_channel::router::ROUTER;
-use js::jsapi::JSAutoCompartment;
+use js::jsapi::{JSAutoCompartment, JSContext};
+use js::rust::HandleValue;
+use js::typedarray::Uint8Array;
 use net_traits::{FetchChannels, FetchResponseListener, NetworkError};
 use net_traits::{FilteredMetadata, FetchMetadata, Metadata};
 use net_traits::CoreResourceMsg::Fetch as NetTraitsFetch;
+use net_traits::csp::Violation;
 use net_traits::request::{Request as NetTraitsRequest, ServiceWorkersMode};
 use net_traits::request::RequestInit as NetTraitsRequestInit;
 use network_listener::{NetworkListener, PreInvoke};
 use servo_url::ServoUrl;
-use std::mem;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
+use streams::upon_settlement;
 use task_source::TaskSourceName;
 
 struct FetchContext {
     fetch_promise: Option<TrustedPromise>,
     response_object: Trusted<Response>,
-    body: Vec<u8>,
+    /// The signal of the request, whose abortion makes the rest of the
+    /// response be ignored.
+    signal: Option<Trusted<AbortSignal>>,
+}
+
+impl FetchContext {
+    fn aborted(&self) -> bool {
+        self.signal.as_ref().map_or(false, |signal| signal.root().aborted())
+    }
+
+    /// Stops the signal from aborting this fetch, which is done.
+    fn finish(&mut self) {
+        if let Some(signal) = self.signal.take() {
+            signal.root().remove_fetch(&self.response_object.root());
+        }
+    }
 }
 
 /// RAII fetch canceller object. By default initialized to not having a canceller
@@ -121,21 +142,30 @@ fn request_init_from_request(request: NetTraitsRequest) -> NetTraitsRequestInit
 // https://fetch.spec.whatwg.org/#fetch-method
 #[allow(unrooted_must_root)]
 pub fn Fetch(global: &GlobalScope, input: RequestInfo, init: RootedTraceableBox<RequestInit>) -> Rc<Promise> {
-    let core_resource_thread = global.core_resource_thread();
-
     // Step 1
     let promise = Promise::new(global);
     let response = Response::new(global);
 
     // Step 2
-    let request = match Request::Constructor(global, input, init) {
+    let (request, body_stream, signal) = match Request::Constructor(global, input, init) {
         Err(e) => {
             promise.reject_error(e);
             return promise;
         },
-        Ok(r) => r.get_request(),
+        Ok(r) => (r.get_request(), r.body_stream(), r.abort_signal()),
     };
+
+    // A fetch whose signal is already aborted is not even started.
+    if let Some(ref signal) = signal {
+        if signal.aborted() {
+            rooted!(in(global.get_cx()) let reason = signal.abort_reason());
+            abort_fetch(&promise, None, reason.handle());
+            return promise;
+        }
+    }
+
     let mut request_init = request_init_from_request(request);
+    request_init.csp_list = global.get_csp_list();
 
     // Step 3
     if global.downcast::<ServiceWorkerGlobalScope>().is_some() {
@@ -144,13 +174,51 @@ pub fn Fetch(global: &GlobalScope, input: RequestInfo, init: RootedTraceableBox<
 
     // Step 4
     response.Headers().set_guard(Guard::Immutable);
+    response.start_receiving_body();
+
+    // Aborting the signal rejects the promise and cancels the request.
+    let cancel_chan = match signal {
+        Some(ref signal) => {
+            let (cancel_sender, cancel_receiver) = ipc::channel().unwrap();
+            signal.add_algorithm(AbortAlgorithm::Fetch(promise.clone(), Dom::from_ref(&*response), cancel_sender));
+            Some(cancel_receiver)
+        },
+        None => None,
+    };
+
+    // A body given as a stream is read entirely before the request is sent.
+    if let Some(stream) = body_stream {
+        let body = stream.read_all_bytes();
+        let pending = Rc::new(PendingFetch {
+            promise: promise.clone(),
+            response: Dom::from_ref(&*response),
+            request_init: DomRefCell::new(Some(request_init)),
+            signal: signal.as_ref().map(|signal| Dom::from_ref(&**signal)),
+            cancel_chan: DomRefCell::new(cancel_chan),
+        });
+        upon_settlement(&body, pending, Some(PendingFetch::body_read), Some(PendingFetch::body_read_failed));
+        return promise;
+    }
+
+    send_fetch(global, request_init, &promise, &response, signal.as_ref().map(|signal| &**signal), cancel_chan);
+    promise
+}
+
+// https://fetch.spec.whatwg.org/#fetch-method
+fn send_fetch(global: &GlobalScope,
+              request_init: NetTraitsRequestInit,
+              promise: &Rc<Promise>,
+              response: &Response,
+              signal: Option<&AbortSignal>,
+              cancel_chan: Option<ipc::IpcReceiver<()>>) {
+    let core_resource_thread = global.core_resource_thread();
 
     // Step 5
     let (action_sender, action_receiver) = ipc::channel().unwrap();
     let fetch_context = Arc::new(Mutex::new(FetchContext {
         fetch_promise: Some(TrustedPromise::new(promise.clone())),
-        response_object: Trusted::new(&*response),
-        body: vec![],
+        response_object: Trusted::new(response),
+        signal: signal.map(Trusted::new),
     }));
     let listener = NetworkListener {
         context: fetch_context,
@@ -162,9 +230,61 @@ pub fn Fetch(global: &GlobalScope, input: RequestInfo, init: RootedTraceableBox<
         listener.notify_fetch(message.to().unwrap());
     }));
     core_resource_thread.send(
-        NetTraitsFetch(request_init, FetchChannels::ResponseMsg(action_sender, None))).unwrap();
+        NetTraitsFetch(request_init, FetchChannels::ResponseMsg(action_sender, cancel_chan))).unwrap();
+}
 
-    promise
+/// Rejects the promise of an aborted fetch and errors the body of its
+/// response, if it has one.
+///
+/// <https://fetch.spec.whatwg.org/#abort-fetch>
+pub fn abort_fetch(promise: &Promise, response: Option<&Response>, reason: HandleValue) {
+    // Step 1
+    promise.reject_native(&reason);
+
+    // Steps 3-5
+    if let Some(response) = response {
+        response.abort_body(reason);
+    }
+}
+
+/// A fetch waiting for the stream of its request body to be read.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct PendingFetch {
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+    response: Dom<Response>,
+    request_init: DomRefCell<Option<NetTraitsRequestInit>>,
+    signal: Option<Dom<AbortSignal>>,
+    #[ignore_malloc_size_of = "Channels are hard"]
+    cancel_chan: DomRefCell<Option<ipc::IpcReceiver<()>>>,
+}
+
+impl PendingFetch {
+    #[allow(unrooted_must_root, unsafe_code)]
+    fn body_read(pending: &Rc<PendingFetch>, cx: *mut JSContext, value: HandleValue) {
+        let body = unsafe {
+            typedarray!(in(cx) let array: Uint8Array = value.to_object());
+            array.expect("Reading all bytes produces a Uint8Array").to_vec()
+        };
+        // The fetch was aborted while its body was read.
+        if pending.signal.as_ref().map_or(false, |signal| signal.aborted()) {
+            return;
+        }
+        let mut request_init = pending.request_init.borrow_mut().take().expect("The request was already sent");
+        request_init.body = Some(body);
+        let cancel_chan = pending.cancel_chan.borrow_mut().take();
+        send_fetch(&pending.response.global(), request_init, &pending.promise, &pending.response,
+                   pending.signal.as_ref().map(|signal| &**signal), cancel_chan);
+    }
+
+    #[allow(unrooted_must_root)]
+    fn body_read_failed(pending: &Rc<PendingFetch>, _cx: *mut JSContext, error: HandleValue) {
+        pending.promise.reject_native(&error);
+        if let Some(ref signal) = pending.signal {
+            signal.remove_fetch(&pending.response);
+        }
+    }
 }
 
 impl PreInvoke for FetchContext {}
@@ -180,6 +300,10 @@ impl FetchResponseListener for FetchContext {
 
     #[allow(unrooted_must_root)]
     fn process_response(&mut self, fetch_metadata: Result<FetchMetadata, NetworkError>) {
+        // The promise was rejected when the fetch was aborted.
+        if self.aborted() {
+            return;
+        }
         let promise = self.fetch_promise.take().expect("fetch promise is missing").root();
 
         // JSAutoCompartment needs to be manually made.
@@ -192,6 +316,7 @@ impl FetchResponseListener for FetchContext {
                 promise.reject_error(Error::Type("Network error occurred".to_string()));
                 self.fetch_promise = Some(TrustedPromise::new(promise));
                 self.response_object.root().set_type(DOMResponseType::Error);
+                self.finish();
                 return;
             },
             // Step 4.2
@@ -223,19 +348,30 @@ impl FetchResponseListener for FetchContext {
         self.fetch_promise = Some(TrustedPromise::new(promise));
     }
 
-    fn process_response_chunk(&mut self, mut chunk: Vec<u8>) {
-        self.body.append(&mut chunk);
+    fn process_response_chunk(&mut self, chunk: Vec<u8>) {
+        if self.aborted() {
+            return;
+        }
+        self.response_object.root().receive_chunk(chunk);
     }
 
-    fn process_response_eof(&mut self, _response: Result<(), NetworkError>) {
-        let response = self.response_object.root();
-        let global = response.global();
+    fn process_response_eof(&mut self, response: Result<(), NetworkError>) {
+        if self.aborted() {
+            return;
+        }
+        let response_object = self.response_object.root();
+        let global = response_object.global();
         let cx = global.get_cx();
         let _ac = JSAutoCompartment::new(cx, global.reflector().get_jsobject().get());
-        response.finish(mem::replace(&mut self.body, vec![]));
+        response_object.finish(response);
+        self.finish();
         // TODO
         // ... trailerObject is not supported in Servo yet.
     }
+
+    fn process_csp_violations(&mut self, violations: Vec<Violation>) {
+        self.response_object.root().global().report_csp_violations(violations, None);
+    }
 }
 
 fn fill_headers_with_metadata(r: DomRoot<Response>, m: Metadata) {
@@ -0,0 +1,404 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Helpers shared by the [IndexedDB](https://w3c.github.io/IndexedDB/) interfaces: conversions
+//! between JavaScript values and keys, the evaluation of key paths on values, and the routing of
+//! the replies of the IndexedDB thread.
+
+use dom::bindings::codegen::UnionTypes::StringOrStringSequence;
+use dom::bindings::conversions::{ToJSValConvertible, get_property_jsval, jsstring_to_str};
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::str::DOMString;
+use dom::bindings::structuredclone::StructuredCloneData;
+use dom::globalscope::GlobalScope;
+use ipc_channel::ipc::{IpcReceiver, IpcSender};
+use ipc_channel::router::ROUTER;
+use js::jsapi::{ClippedTime, JSContext, JSObject, JS_NewArrayObject1, JS_NewPlainObject, NewDateObject};
+use js::jsapi::JSPROP_ENUMERATE;
+use js::jsval::{DoubleValue, NullValue, ObjectValue, UndefinedValue};
+use js::rust::{HandleValue, MutableHandleValue};
+use js::rust::wrappers::{DateGetMsecSinceEpoch, GetArrayLength, JS_DefineElement, JS_DefineProperty};
+use js::rust::wrappers::{JS_GetElement, JS_IsArrayObject, ObjectIsDate};
+use js::typedarray::{ArrayBuffer, ArrayBufferView, CreateWith};
+use net_traits::IpcSend;
+use net_traits::indexeddb_thread::{IndexedDBError, IndexedDBIndexMetadata, IndexedDBKey, IndexedDBKeyPath};
+use net_traits::indexeddb_thread::{IndexedDBResult, IndexedDBThreadMsg};
+use profile_traits::ipc as ProfiledIpc;
+use serde::{Deserialize, Serialize};
+use std::ffi::CString;
+use std::ptr;
+use task_source::{TaskSource, TaskSourceName};
+
+/// Call `handler` with every reply received on `receiver`, in a task queued on the networking
+/// task source of `global`.
+pub fn route_replies<T, F>(global: &GlobalScope, receiver: IpcReceiver<T>, handler: F)
+    where T: for<'de> Deserialize<'de> + Serialize + Send + 'static,
+          F: Fn(T) + Clone + Send + 'static
+{
+    let task_source = global.networking_task_source();
+    let canceller = global.task_canceller(TaskSourceName::Networking);
+    ROUTER.add_route(receiver.to_opaque(), Box::new(move |message| {
+        let reply: T = match message.to() {
+            Ok(reply) => reply,
+            Err(_) => return,
+        };
+        let handler = handler.clone();
+        let _ = task_source.queue_with_canceller(task!(indexeddb_reply: move || {
+            handler(reply);
+        }), &canceller);
+    }));
+}
+
+/// Send the message built by `message` to the IndexedDB thread, and wait for its reply.
+pub fn request_sync<T, F>(global: &GlobalScope, message: F) -> Fallible<T>
+    where T: for<'de> Deserialize<'de> + Serialize,
+          F: FnOnce(IpcSender<IndexedDBResult<T>>) -> IndexedDBThreadMsg
+{
+    let (sender, receiver) = ProfiledIpc::channel(global.time_profiler_chan().clone()).unwrap();
+    let thread: IpcSender<IndexedDBThreadMsg> = global.resource_threads().sender();
+    if thread.send(message(sender)).is_err() {
+        return Err(Error::InvalidState);
+    }
+    match receiver.recv() {
+        Ok(result) => result.map_err(Error::from),
+        Err(_) => Err(Error::InvalidState),
+    }
+}
+
+impl From<IndexedDBError> for Error {
+    fn from(error: IndexedDBError) -> Error {
+        match error {
+            IndexedDBError::Abort => Error::Abort,
+            IndexedDBError::Constraint => Error::Constraint,
+            IndexedDBError::Data => Error::Data,
+            IndexedDBError::InvalidState => Error::InvalidState,
+            IndexedDBError::NotFound => Error::NotFound,
+            IndexedDBError::QuotaExceeded => Error::QuotaExceeded,
+            IndexedDBError::Version => Error::Version,
+        }
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#convert-a-value-to-a-key>
+#[allow(unsafe_code)]
+pub unsafe fn convert_value_to_key(cx: *mut JSContext, input: HandleValue) -> Fallible<IndexedDBKey> {
+    convert_value_to_key_with_seen(cx, input, &mut vec![])
+}
+
+#[allow(unsafe_code)]
+unsafe fn convert_value_to_key_with_seen(cx: *mut JSContext,
+                                         input: HandleValue,
+                                         seen: &mut Vec<*mut JSObject>)
+                                         -> Fallible<IndexedDBKey> {
+    if input.is_number() {
+        let number = input.to_number();
+        if number.is_nan() {
+            return Err(Error::Data);
+        }
+        return Ok(IndexedDBKey::Number(number));
+    }
+    if input.is_string() {
+        let string = jsstring_to_str(cx, input.to_string());
+        return Ok(IndexedDBKey::String(String::from(string)));
+    }
+    if !input.is_object() {
+        return Err(Error::Data);
+    }
+
+    rooted!(in(cx) let object = input.to_object());
+    if seen.contains(&object.get()) {
+        return Err(Error::Data);
+    }
+
+    let mut is_date = false;
+    if !ObjectIsDate(cx, object.handle(), &mut is_date) {
+        return Err(Error::JSFailed);
+    }
+    if is_date {
+        let mut time = 0.;
+        if !DateGetMsecSinceEpoch(cx, object.handle(), &mut time) {
+            return Err(Error::JSFailed);
+        }
+        if time.is_nan() {
+            return Err(Error::Data);
+        }
+        return Ok(IndexedDBKey::Date(time));
+    }
+
+    if let Ok(buffer) = ArrayBuffer::from(object.get()) {
+        return Ok(IndexedDBKey::Binary(buffer.to_vec()));
+    }
+    if let Ok(view) = ArrayBufferView::from(object.get()) {
+        return Ok(IndexedDBKey::Binary(view.to_vec()));
+    }
+
+    let mut is_array = false;
+    if !JS_IsArrayObject(cx, input, &mut is_array) {
+        return Err(Error::JSFailed);
+    }
+    if !is_array {
+        return Err(Error::Data);
+    }
+    let mut length = 0;
+    if !GetArrayLength(cx, object.handle(), &mut length) {
+        return Err(Error::JSFailed);
+    }
+    seen.push(object.get());
+    let mut keys = Vec::with_capacity(length as usize);
+    for index in 0..length {
+        rooted!(in(cx) let mut entry = UndefinedValue());
+        if !JS_GetElement(cx, object.handle(), index, entry.handle_mut()) {
+            return Err(Error::JSFailed);
+        }
+        keys.push(convert_value_to_key_with_seen(cx, entry.handle(), seen)?);
+    }
+    seen.pop();
+    Ok(IndexedDBKey::Array(keys))
+}
+
+/// <https://w3c.github.io/IndexedDB/#convert-a-key-to-a-value>
+#[allow(unsafe_code)]
+pub unsafe fn key_to_jsval(cx: *mut JSContext, key: &IndexedDBKey, mut rval: MutableHandleValue) {
+    match *key {
+        IndexedDBKey::Number(number) => rval.set(DoubleValue(number)),
+        IndexedDBKey::Date(time) => {
+            rooted!(in(cx) let date = NewDateObject(cx, ClippedTime { t: time }));
+            rval.set(ObjectValue(date.get()));
+        },
+        IndexedDBKey::String(ref string) => DOMString::from(string.clone()).to_jsval(cx, rval),
+        IndexedDBKey::Binary(ref bytes) => {
+            rooted!(in(cx) let mut buffer = ptr::null_mut::<JSObject>());
+            assert!(ArrayBuffer::create(cx, CreateWith::Slice(bytes), buffer.handle_mut()).is_ok());
+            rval.set(ObjectValue(buffer.get()));
+        },
+        IndexedDBKey::Array(ref keys) => {
+            rooted!(in(cx) let array = JS_NewArrayObject1(cx, keys.len()));
+            for (index, key) in keys.iter().enumerate() {
+                rooted!(in(cx) let mut value = UndefinedValue());
+                key_to_jsval(cx, key, value.handle_mut());
+                JS_DefineElement(cx, array.handle(), index as u32, value.handle(), JSPROP_ENUMERATE);
+            }
+            rval.set(ObjectValue(array.get()));
+        },
+    }
+}
+
+impl<'a> From<&'a StringOrStringSequence> for IndexedDBKeyPath {
+    fn from(key_path: &'a StringOrStringSequence) -> IndexedDBKeyPath {
+        match *key_path {
+            StringOrStringSequence::String(ref path) => IndexedDBKeyPath::String(path.to_string()),
+            StringOrStringSequence::StringSequence(ref paths) => {
+                IndexedDBKeyPath::Sequence(paths.iter().map(|path| path.to_string()).collect())
+            },
+        }
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#valid-key-path>
+pub fn is_valid_key_path(key_path: &IndexedDBKeyPath) -> bool {
+    fn is_identifier(identifier: &str) -> bool {
+        let mut chars = identifier.chars();
+        match chars.next() {
+            Some(first) if first.is_alphabetic() || first == '$' || first == '_' => {},
+            _ => return false,
+        }
+        chars.all(|c| c.is_alphanumeric() || c == '$' || c == '_')
+    }
+    fn is_valid_path(path: &str) -> bool {
+        path.is_empty() || path.split('.').all(is_identifier)
+    }
+    match *key_path {
+        IndexedDBKeyPath::String(ref path) => is_valid_path(path),
+        IndexedDBKeyPath::Sequence(ref paths) => {
+            !paths.is_empty() && paths.iter().all(|path| !path.is_empty() && is_valid_path(path))
+        },
+    }
+}
+
+/// The value of the `keyPath` attribute of object stores and indexes.
+#[allow(unsafe_code)]
+pub unsafe fn key_path_to_jsval(cx: *mut JSContext, key_path: Option<&IndexedDBKeyPath>, mut rval: MutableHandleValue) {
+    match key_path {
+        None => rval.set(NullValue()),
+        Some(&IndexedDBKeyPath::String(ref path)) => DOMString::from(path.clone()).to_jsval(cx, rval),
+        Some(&IndexedDBKeyPath::Sequence(ref paths)) => {
+            let paths: Vec<DOMString> = paths.iter().cloned().map(DOMString::from).collect();
+            paths.to_jsval(cx, rval);
+        },
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#evaluate-a-key-path-on-a-value>
+///
+/// Returns whether the key path resolved to a value, stored in `rval`.
+#[allow(unsafe_code)]
+pub unsafe fn evaluate_key_path_on_value(cx: *mut JSContext,
+                                         value: HandleValue,
+                                         key_path: &IndexedDBKeyPath,
+                                         mut rval: MutableHandleValue)
+                                         -> Fallible<bool> {
+    match *key_path {
+        IndexedDBKeyPath::String(ref path) => evaluate_key_path_string(cx, value, path, rval),
+        IndexedDBKeyPath::Sequence(ref paths) => {
+            rooted!(in(cx) let array = JS_NewArrayObject1(cx, paths.len()));
+            for (index, path) in paths.iter().enumerate() {
+                rooted!(in(cx) let mut key = UndefinedValue());
+                if !evaluate_key_path_string(cx, value, path, key.handle_mut())? {
+                    return Ok(false);
+                }
+                JS_DefineElement(cx, array.handle(), index as u32, key.handle(), JSPROP_ENUMERATE);
+            }
+            rval.set(ObjectValue(array.get()));
+            Ok(true)
+        },
+    }
+}
+
+#[allow(unsafe_code)]
+unsafe fn evaluate_key_path_string(cx: *mut JSContext,
+                                   value: HandleValue,
+                                   path: &str,
+                                   mut rval: MutableHandleValue)
+                                   -> Fallible<bool> {
+    rooted!(in(cx) let mut current = value.get());
+    if !path.is_empty() {
+        for identifier in path.split('.') {
+            if current.is_string() && identifier == "length" {
+                let length = jsstring_to_str(cx, current.to_string()).encode_utf16().count();
+                current.set(DoubleValue(length as f64));
+                continue;
+            }
+            if !current.is_object() {
+                return Ok(false);
+            }
+            rooted!(in(cx) let object = current.to_object());
+            get_property_jsval(cx, object.handle(), identifier, current.handle_mut())?;
+            if current.is_undefined() {
+                return Ok(false);
+            }
+        }
+    }
+    rval.set(current.get());
+    Ok(true)
+}
+
+/// <https://w3c.github.io/IndexedDB/#extract-a-key-from-a-value-using-a-key-path>
+///
+/// Returns `None` when the key path does not resolve to a value.
+#[allow(unsafe_code)]
+pub unsafe fn extract_key(cx: *mut JSContext, value: HandleValue, key_path: &IndexedDBKeyPath)
+                          -> Fallible<Option<IndexedDBKey>> {
+    rooted!(in(cx) let mut key = UndefinedValue());
+    if !evaluate_key_path_on_value(cx, value, key_path, key.handle_mut())? {
+        return Ok(None);
+    }
+    convert_value_to_key(cx, key.handle()).map(Some)
+}
+
+/// The keys a value is indexed under in the given index.
+/// <https://w3c.github.io/IndexedDB/#store-a-record-into-an-object-store> step 5
+#[allow(unsafe_code)]
+pub unsafe fn extract_index_keys(cx: *mut JSContext, value: HandleValue, index: &IndexedDBIndexMetadata)
+                                 -> Fallible<Vec<IndexedDBKey>> {
+    rooted!(in(cx) let mut key = UndefinedValue());
+    if !evaluate_key_path_on_value(cx, value, &index.key_path, key.handle_mut())? {
+        return Ok(vec![]);
+    }
+    let mut is_array = false;
+    if index.multi_entry && key.is_object() && JS_IsArrayObject(cx, key.handle(), &mut is_array) && is_array {
+        // https://w3c.github.io/IndexedDB/#convert-a-value-to-a-multientry-key
+        rooted!(in(cx) let array = key.to_object());
+        let mut length = 0;
+        if !GetArrayLength(cx, array.handle(), &mut length) {
+            return Err(Error::JSFailed);
+        }
+        let mut keys: Vec<IndexedDBKey> = vec![];
+        for index in 0..length {
+            rooted!(in(cx) let mut entry = UndefinedValue());
+            if !JS_GetElement(cx, array.handle(), index, entry.handle_mut()) {
+                return Err(Error::JSFailed);
+            }
+            if let Ok(key) = convert_value_to_key(cx, entry.handle()) {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+        return Ok(keys);
+    }
+    Ok(convert_value_to_key(cx, key.handle()).ok().into_iter().collect())
+}
+
+/// <https://w3c.github.io/IndexedDB/#check-that-a-key-could-be-injected-into-a-value>
+#[allow(unsafe_code)]
+pub unsafe fn can_inject_key(cx: *mut JSContext, value: HandleValue, key_path: &str) -> Fallible<bool> {
+    let identifiers: Vec<&str> = key_path.split('.').collect();
+    rooted!(in(cx) let mut current = value.get());
+    for identifier in &identifiers[..identifiers.len() - 1] {
+        if !current.is_object() {
+            return Ok(false);
+        }
+        rooted!(in(cx) let object = current.to_object());
+        get_property_jsval(cx, object.handle(), identifier, current.handle_mut())?;
+        if current.is_undefined() {
+            return Ok(true);
+        }
+    }
+    Ok(current.is_object())
+}
+
+/// <https://w3c.github.io/IndexedDB/#inject-a-key-into-a-value-using-a-key-path>
+#[allow(unsafe_code)]
+pub unsafe fn inject_key(cx: *mut JSContext, value: HandleValue, key: &IndexedDBKey, key_path: &str)
+                         -> Fallible<()> {
+    let identifiers: Vec<&str> = key_path.split('.').collect();
+    let (last, parents) = identifiers.split_last().unwrap();
+    rooted!(in(cx) let mut current = value.get());
+    for identifier in parents {
+        if !current.is_object() {
+            return Ok(());
+        }
+        rooted!(in(cx) let object = current.to_object());
+        get_property_jsval(cx, object.handle(), identifier, current.handle_mut())?;
+        if current.is_undefined() {
+            rooted!(in(cx) let created = JS_NewPlainObject(cx));
+            current.set(ObjectValue(created.get()));
+            define_property(cx, object.get(), identifier, current.handle())?;
+        }
+    }
+    if !current.is_object() {
+        return Ok(());
+    }
+    rooted!(in(cx) let mut key_value = UndefinedValue());
+    key_to_jsval(cx, key, key_value.handle_mut());
+    define_property(cx, current.to_object(), last, key_value.handle())
+}
+
+#[allow(unsafe_code)]
+unsafe fn define_property(cx: *mut JSContext, object: *mut JSObject, name: &str, value: HandleValue)
+                          -> Fallible<()> {
+    rooted!(in(cx) let object = object);
+    let name = CString::new(name).map_err(|_| Error::Data)?;
+    if !JS_DefineProperty(cx, object.handle(), name.as_ptr(), value, JSPROP_ENUMERATE) {
+        return Err(Error::JSFailed);
+    }
+    Ok(())
+}
+
+/// Deserialize a stored value, injecting its primary key when it was generated by the object store.
+#[allow(unsafe_code)]
+pub unsafe fn read_value(global: &GlobalScope,
+                         data: Vec<u8>,
+                         generated_key: Option<(&IndexedDBKey, &str)>,
+                         mut rval: MutableHandleValue) {
+    let cx = global.get_cx();
+    rooted!(in(cx) let mut value = UndefinedValue());
+    StructuredCloneData::Vector(data).read(global, value.handle_mut());
+    if let Some((key, key_path)) = generated_key {
+        if inject_key(cx, value.handle(), key, key_path).is_err() {
+            warn!("Could not inject a generated key into an IndexedDB value.");
+        }
+    }
+    rval.set(value.get());
+}
@@ -7,11 +7,14 @@
 //! no guarantee that the responsible nodes will still exist in the future if the
 //! layout thread holds on to them during asynchronous operations.
 
+use dom::bindings::refcounted::Trusted;
 use dom::bindings::reflector::DomObject;
+use dom::document::Document;
 use dom::node::{Node, document_from_node};
 use ipc_channel::ipc;
 use ipc_channel::router::ROUTER;
 use net_traits::{FetchResponseMsg, FetchResponseListener, FetchMetadata, NetworkError};
+use net_traits::csp::Violation;
 use net_traits::image_cache::{ImageCache, PendingImageId};
 use net_traits::request::{Destination, RequestInit as FetchRequestInit};
 use network_listener::{NetworkListener, PreInvoke};
@@ -22,6 +25,7 @@ use task_source::TaskSourceName;
 struct LayoutImageContext {
     id: PendingImageId,
     cache: Arc<ImageCache>,
+    doc: Trusted<Document>,
 }
 
 impl FetchResponseListener for LayoutImageContext {
@@ -43,6 +47,10 @@ impl FetchResponseListener for LayoutImageContext {
         self.cache.notify_pending_response(self.id,
                                            FetchResponseMsg::ProcessResponseEOF(response));
     }
+
+    fn process_csp_violations(&mut self, violations: Vec<Violation>) {
+        self.doc.root().global().report_csp_violations(violations, None);
+    }
 }
 
 impl PreInvoke for LayoutImageContext {}
@@ -51,12 +59,13 @@ pub fn fetch_image_for_layout(url: ServoUrl,
                               node: &Node,
                               id: PendingImageId,
                               cache: Arc<ImageCache>) {
+    let document = document_from_node(node);
     let context = Arc::new(Mutex::new(LayoutImageContext {
         id: id,
         cache: cache,
+        doc: Trusted::new(&*document),
     }));
 
-    let document = document_from_node(node);
     let window = document.window();
 
     let (action_sender, action_receiver) = ipc::channel().unwrap();
@@ -74,6 +83,7 @@ pub fn fetch_image_for_layout(url: ServoUrl,
         origin: document.origin().immutable().clone(),
         destination: Destination::Image,
         pipeline_id: Some(document.global().pipeline_id()),
+        csp_list: document.get_csp_list(),
         .. FetchRequestInit::default()
     };
 
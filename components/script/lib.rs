@@ -108,12 +108,15 @@ extern crate xml5ever;
 #[macro_use]
 mod task;
 mod body;
+mod cache_storage;
 pub mod clipboard_provider;
 mod devtools;
 pub mod document_loader;
 #[macro_use]
 mod dom;
+mod editing;
 pub mod fetch;
+mod indexed_db;
 mod layout_image;
 mod mem;
 mod microtask;
@@ -121,8 +124,10 @@ mod network_listener;
 pub mod script_runtime;
 #[allow(unsafe_code)]
 pub mod script_thread;
+mod security_manager;
 mod serviceworker_manager;
 mod serviceworkerjob;
+mod streams;
 mod stylesheet_loader;
 mod task_source;
 pub mod test;
@@ -143,8 +148,10 @@ pub mod layout_exports {
     pub use dom::characterdata::LayoutCharacterDataHelpers;
     pub use dom::document::{Document, LayoutDocumentHelpers, PendingRestyle};
     pub use dom::element::{Element, LayoutElementHelpers, RawLayoutElementHelpers};
+    pub use dom::htmlslotelement::{HTMLSlotElement, LayoutHTMLSlotElementHelpers};
     pub use dom::node::NodeFlags;
     pub use dom::node::{LayoutNodeHelpers, Node};
+    pub use dom::shadowroot::{LayoutShadowRootHelpers, ShadowRoot};
     pub use dom::text::Text;
 }
 
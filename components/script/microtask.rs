@@ -6,6 +6,7 @@
 //! microtask queues. It is up to implementations of event loops to store a queue and
 //! perform checkpoints at appropriate times, as well as enqueue microtasks as required.
 
+use dom::animation::AnimationMicrotask;
 use dom::bindings::callback::ExceptionHandling;
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::PromiseBinding::PromiseJobCallback;
@@ -34,6 +35,7 @@ pub enum Microtask {
     Promise(EnqueuedPromiseCallback),
     MediaElement(MediaElementMicrotask),
     ImageElement(ImageElementMicrotask),
+    Animation(AnimationMicrotask),
     CustomElementReaction,
     NotifyMutationObservers,
 }
@@ -89,6 +91,9 @@ impl MicrotaskQueue {
                     Microtask::ImageElement(ref task) => {
                         task.handler();
                     },
+                    Microtask::Animation(ref task) => {
+                        task.handler();
+                    },
                     Microtask::CustomElementReaction => {
                         ScriptThread::invoke_backup_element_queue();
                     },
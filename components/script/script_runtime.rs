@@ -21,6 +21,7 @@ use js::jsapi::{JSGCInvocationKind, JSGCStatus, JS_AddExtraGCRootsTracer, JS_Set
 use js::jsapi::{JSGCMode, JSGCParamKey, JS_SetGCParameter, JS_SetGlobalJitCompilerOption};
 use js::jsapi::{JSJitCompilerOption, JS_SetOffthreadIonCompilationEnabled, JS_SetParallelParsingEnabled};
 use js::jsapi::{JSObject, RuntimeOptionsRef, SetPreserveWrapperCallback, SetEnqueuePromiseJobCallback};
+use js::jsapi::{JSSecurityCallbacks, JS_SetSecurityCallbacks};
 use js::panic::wrap_panic;
 use js::rust::Runtime as RustRuntime;
 use malloc_size_of::MallocSizeOfOps;
@@ -125,6 +126,20 @@ unsafe extern "C" fn enqueue_job(cx: *mut JSContext,
     }), false)
 }
 
+/// SM callback consulted before `eval()` and `Function()` compile a string.
+#[allow(unsafe_code)]
+unsafe extern "C" fn content_security_policy_allows(cx: *mut JSContext) -> bool {
+    wrap_panic(AssertUnwindSafe(|| {
+        // SpiderMonkey does not give us the source, so violations carry no sample.
+        GlobalScope::from_context(cx).is_js_evaluation_allowed("")
+    }), false)
+}
+
+static SECURITY_CALLBACKS: JSSecurityCallbacks = JSSecurityCallbacks {
+    contentSecurityPolicyAllows: Some(content_security_policy_allows),
+    subsumes: None,
+};
+
 #[derive(JSTraceable)]
 pub struct Runtime(RustRuntime);
 
@@ -165,6 +180,8 @@ pub unsafe fn new_rt_and_cx() -> Runtime {
 
     SetEnqueuePromiseJobCallback(runtime.rt(), Some(enqueue_job), ptr::null_mut());
 
+    JS_SetSecurityCallbacks(runtime.rt(), &SECURITY_CALLBACKS);
+
     set_gc_zeal_options(runtime.rt());
 
     // Enable or disable the JITs.
@@ -48,6 +48,7 @@ use dom::event::{Event, EventBubbles, EventCancelable};
 use dom::globalscope::GlobalScope;
 use dom::htmlanchorelement::HTMLAnchorElement;
 use dom::htmliframeelement::{HTMLIFrameElement, NavigationType};
+use dom::htmlslotelement::HTMLSlotElement;
 use dom::mutationobserver::MutationObserver;
 use dom::node::{Node, NodeDamage, window_from_node, from_untrusted_node_address};
 use dom::performanceentry::PerformanceEntry;
@@ -81,6 +82,7 @@ use msg::constellation_msg::{BrowsingContextId, HistoryStateId, PipelineId};
 use msg::constellation_msg::{PipelineNamespace, TopLevelBrowsingContextId};
 use net_traits::{FetchMetadata, FetchResponseListener, FetchResponseMsg};
 use net_traits::{Metadata, NetworkError, ReferrerPolicy, ResourceThreads};
+use net_traits::csp::{CheckResult, InlineCheckType};
 use net_traits::image_cache::{ImageCache, PendingImageResponse};
 use net_traits::request::{CredentialsMode, Destination, RedirectMode, RequestInit};
 use net_traits::storage_thread::StorageType;
@@ -121,6 +123,7 @@ use task_source::file_reading::FileReadingTaskSource;
 use task_source::history_traversal::HistoryTraversalTaskSource;
 use task_source::networking::NetworkingTaskSource;
 use task_source::performance_timeline::PerformanceTimelineTaskSource;
+use task_source::port_message::PortMessageQueue;
 use task_source::remote_event::RemoteEventTaskSource;
 use task_source::user_interaction::UserInteractionTaskSource;
 use time::{get_time, precise_time_ns, Tm};
@@ -439,6 +442,8 @@ pub struct ScriptThread {
 
     remote_event_task_sender: Box<ScriptChan>,
 
+    port_message_sender: Box<ScriptChan>,
+
     /// A channel to hand out to threads that need to respond to a message from the script thread.
     control_chan: IpcSender<ConstellationControlMsg>,
 
@@ -494,6 +499,9 @@ pub struct ScriptThread {
     /// The unit of related similar-origin browsing contexts' list of MutationObserver objects
     mutation_observers: DomRefCell<Vec<Dom<MutationObserver>>>,
 
+    /// <https://dom.spec.whatwg.org/#signal-slot-list>
+    signal_slots: DomRefCell<Vec<Dom<HTMLSlotElement>>>,
+
     /// A handle to the WebGL thread
     webgl_chan: Option<WebGLPipeline>,
 
@@ -642,6 +650,25 @@ impl ScriptThread {
         })
     }
 
+    /// Appends a slot to the signal slots, if it's not already there.
+    pub fn add_signal_slot(slot: &HTMLSlotElement) {
+        SCRIPT_THREAD_ROOT.with(|root| {
+            let script_thread = unsafe { &*root.get().unwrap() };
+            let mut signal_slots = script_thread.signal_slots.borrow_mut();
+            if !signal_slots.iter().any(|s| &**s == slot) {
+                signal_slots.push(Dom::from_ref(slot));
+            }
+        })
+    }
+
+    /// Empties the signal slots, returning their previous contents.
+    pub fn take_signal_slots() -> Vec<DomRoot<HTMLSlotElement>> {
+        SCRIPT_THREAD_ROOT.with(|root| {
+            let script_thread = unsafe { &*root.get().unwrap() };
+            script_thread.signal_slots.borrow_mut().drain(..).map(|s| DomRoot::from_ref(&*s)).collect()
+        })
+    }
+
     pub fn mark_document_with_no_blocked_loads(doc: &Document) {
         SCRIPT_THREAD_ROOT.with(|root| {
             let script_thread = unsafe { &*root.get().unwrap() };
@@ -854,6 +881,7 @@ impl ScriptThread {
             file_reading_task_sender: boxed_script_sender.clone(),
             performance_timeline_task_sender: boxed_script_sender.clone(),
             remote_event_task_sender: boxed_script_sender.clone(),
+            port_message_sender: boxed_script_sender.clone(),
 
             history_traversal_task_source: HistoryTraversalTaskSource(chan),
 
@@ -883,6 +911,8 @@ impl ScriptThread {
 
             mutation_observers: Default::default(),
 
+            signal_slots: Default::default(),
+
             layout_to_constellation_chan: state.layout_to_constellation_chan,
 
             webgl_chan: state.webgl_chan,
@@ -1448,6 +1478,8 @@ impl ScriptThread {
         match msg {
             WebDriverScriptCommand::AddCookie(params, reply) =>
                 webdriver_handlers::handle_add_cookie(&*documents, pipeline_id, params, reply),
+            WebDriverScriptCommand::ElementClear(element_id, reply) =>
+                webdriver_handlers::handle_element_clear(&*documents, pipeline_id, element_id, reply),
             WebDriverScriptCommand::ExecuteScript(script, reply) =>
                 webdriver_handlers::handle_execute_script(&*documents, pipeline_id, script, reply),
             WebDriverScriptCommand::FindElementCSS(selector, reply) =>
@@ -1468,12 +1500,20 @@ impl ScriptThread {
                 webdriver_handlers::handle_get_attribute(&*documents, pipeline_id, node_id, name, reply),
             WebDriverScriptCommand::GetElementCSS(node_id, name, reply) =>
                 webdriver_handlers::handle_get_css(&*documents, pipeline_id, node_id, name, reply),
+            WebDriverScriptCommand::GetElementInViewCenterPoint(node_id, reply) =>
+                webdriver_handlers::handle_get_in_view_center_point(&*documents, pipeline_id, node_id, reply),
             WebDriverScriptCommand::GetElementRect(node_id, reply) =>
                 webdriver_handlers::handle_get_rect(&*documents, pipeline_id, node_id, reply),
             WebDriverScriptCommand::GetElementText(node_id, reply) =>
                 webdriver_handlers::handle_get_text(&*documents, pipeline_id, node_id, reply),
+            WebDriverScriptCommand::GetFrameOffset(browsing_context_id, reply) =>
+                webdriver_handlers::handle_get_frame_offset(&*documents, pipeline_id, browsing_context_id, reply),
             WebDriverScriptCommand::GetBrowsingContextId(webdriver_frame_id, reply) =>
                 webdriver_handlers::handle_get_browsing_context_id(&*documents, pipeline_id, webdriver_frame_id, reply),
+            WebDriverScriptCommand::GetScrollOffset(reply) =>
+                webdriver_handlers::handle_get_scroll_offset(&*documents, pipeline_id, reply),
+            WebDriverScriptCommand::GetSelection(reply) =>
+                webdriver_handlers::handle_get_selection(&*documents, pipeline_id, reply),
             WebDriverScriptCommand::GetUrl(reply) =>
                 webdriver_handlers::handle_get_url(&*documents, pipeline_id, reply),
             WebDriverScriptCommand::IsEnabled(element_id, reply) =>
@@ -1813,6 +1853,10 @@ impl ScriptThread {
         RemoteEventTaskSource(self.remote_event_task_sender.clone(), pipeline_id)
     }
 
+    pub fn port_message_queue(&self, pipeline_id: PipelineId) -> PortMessageQueue {
+        PortMessageQueue(self.port_message_sender.clone(), pipeline_id)
+    }
+
     /// Handles a request for the window title.
     fn handle_get_title_msg(&self, pipeline_id: PipelineId) {
         let document = match { self.documents.borrow().find_document(pipeline_id) } {
@@ -1943,6 +1987,7 @@ impl ScriptThread {
             parent: EventInit {
                 bubbles: true,
                 cancelable: false,
+                composed: false,
             },
             propertyName: DOMString::from(name),
             elapsedTime: Finite::new(duration as f32).unwrap(),
@@ -1960,6 +2005,7 @@ impl ScriptThread {
         let document = self.documents.borrow().find_document(pipeline_id);
         if let Some(document) = document {
             self.rebuild_and_force_reflow(&document, ReflowReason::WebFontLoaded);
+            document.web_font_loaded();
         }
     }
 
@@ -2122,6 +2168,7 @@ impl ScriptThread {
             self.file_reading_task_source(incomplete.pipeline_id),
             self.performance_timeline_task_source(incomplete.pipeline_id).clone(),
             self.remote_event_task_source(incomplete.pipeline_id),
+            self.port_message_queue(incomplete.pipeline_id),
             self.image_cache_channel.clone(),
             self.image_cache.clone(),
             self.resource_threads.clone(),
@@ -2216,6 +2263,8 @@ impl ScriptThread {
         document.set_https_state(metadata.https_state);
         document.set_navigation_start(incomplete.navigation_start_precise);
 
+        document.set_csp_list(metadata.csp_list.clone());
+
         if is_html_document == IsHTMLDocument::NonHTMLDocument {
             ServoParser::parse_xml_document(&document, parse_input, final_url);
         } else {
@@ -2422,7 +2471,9 @@ impl ScriptThread {
         if is_javascript {
             let window = self.documents.borrow().find_window(parent_pipeline_id);
             if let Some(window) = window {
-                ScriptThread::eval_js_url(window.upcast::<GlobalScope>(), &mut load_data);
+                if !ScriptThread::eval_js_url(window.upcast::<GlobalScope>(), &mut load_data) {
+                    return;
+                }
             }
         }
 
@@ -2441,7 +2492,9 @@ impl ScriptThread {
         }
     }
 
-    pub fn eval_js_url(global_scope: &GlobalScope, load_data: &mut LoadData) {
+    /// Evaluates the `javascript:` URL of `load_data`, returning false if
+    /// the navigation was blocked by CSP and must be aborted.
+    pub fn eval_js_url(global_scope: &GlobalScope, load_data: &mut LoadData) -> bool {
         // Turn javascript: URL into JS code to eval, according to the steps in
         // https://html.spec.whatwg.org/multipage/#javascript-protocol
 
@@ -2451,6 +2504,18 @@ impl ScriptThread {
         // append number sign and fragment component if any.
         let encoded = &load_data.url.clone()[Position::BeforePath..];
 
+        // https://w3c.github.io/webappsec-csp/#should-block-navigation-request
+        if let Some(csp_list) = global_scope.get_csp_list() {
+            let (result, violations) =
+                csp_list.should_inline_be_blocked(InlineCheckType::Navigation, "", load_data.url.as_str());
+            if !violations.is_empty() {
+                global_scope.report_csp_violations(violations, None);
+            }
+            if result == CheckResult::Blocked {
+                return false;
+            }
+        }
+
         // Percent-decode (8.) and UTF-8 decode (9.)
         let script_source = percent_decode(encoded.as_bytes()).decode_utf8_lossy();
 
@@ -2476,6 +2541,7 @@ impl ScriptThread {
         };
 
         load_data.url = ServoUrl::parse("about:blank").unwrap();
+        true
     }
 
     fn handle_resize_event(&self, pipeline_id: PipelineId, new_size: WindowSizeData, size_type: WindowSizeType) {
@@ -0,0 +1,204 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Reporting of [Content Security Policy](https://w3c.github.io/webappsec-csp/)
+//! violations.
+
+use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
+use dom::bindings::codegen::Bindings::EventBinding::EventInit;
+use dom::bindings::codegen::Bindings::SecurityPolicyViolationEventBinding::SecurityPolicyViolationEventDisposition;
+use dom::bindings::codegen::Bindings::SecurityPolicyViolationEventBinding::SecurityPolicyViolationEventInit;
+use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::{DOMString, USVString};
+use dom::element::Element;
+use dom::event::{Event, EventBubbles, EventCancelable};
+use dom::eventtarget::EventTarget;
+use dom::globalscope::GlobalScope;
+use dom::node::Node;
+use dom::securitypolicyviolationevent::SecurityPolicyViolationEvent;
+use dom::window::Window;
+use hyper::header::ContentType;
+use hyper::method::Method;
+use ipc_channel::ipc;
+use net_traits::{CoreResourceMsg, FetchChannels, IpcSend};
+use net_traits::csp::{PolicyDisposition, Violation, ViolationResource};
+use net_traits::request::{CredentialsMode, Destination, RedirectMode, RequestInit, RequestMode};
+use servo_url::ServoUrl;
+
+/// A violation together with the global-dependent parts of the report.
+struct ViolationReport {
+    document_uri: String,
+    referrer: String,
+    blocked_uri: String,
+    effective_directive: String,
+    original_policy: String,
+    disposition: PolicyDisposition,
+    status_code: u16,
+    sample: String,
+}
+
+impl ViolationReport {
+    fn new(window: &Window, violation: &Violation) -> ViolationReport {
+        let document = window.Document();
+        let url = document.url();
+        ViolationReport {
+            document_uri: strip_url_for_use_in_reports(&url),
+            referrer: String::from(document.Referrer()),
+            blocked_uri: match violation.resource {
+                ViolationResource::Inline => "inline".to_owned(),
+                ViolationResource::Eval => "eval".to_owned(),
+                ViolationResource::Url(ref url) => strip_url_for_use_in_reports(url),
+            },
+            effective_directive: violation.effective_directive.clone(),
+            original_policy: violation.policy.serialized.clone(),
+            disposition: violation.policy.disposition,
+            // TODO: the status code of the response the document was loaded from.
+            status_code: if url.scheme() == "http" || url.scheme() == "https" { 200 } else { 0 },
+            sample: violation.sample.clone(),
+        }
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#deprecated-serialize-violation>
+    fn serialize(&self) -> Vec<u8> {
+        let disposition = match self.disposition {
+            PolicyDisposition::Enforce => "enforce",
+            PolicyDisposition::Report => "report",
+        };
+        let fields = [
+            ("document-uri", json_string(&self.document_uri)),
+            ("referrer", json_string(&self.referrer)),
+            ("blocked-uri", json_string(&self.blocked_uri)),
+            ("effective-directive", json_string(&self.effective_directive)),
+            ("violated-directive", json_string(&self.effective_directive)),
+            ("original-policy", json_string(&self.original_policy)),
+            ("disposition", json_string(disposition)),
+            ("status-code", self.status_code.to_string()),
+            ("script-sample", json_string(&self.sample)),
+        ];
+        let body = fields.iter()
+                         .map(|&(name, ref value)| format!("\"{}\":{}", name, value))
+                         .collect::<Vec<_>>()
+                         .join(",");
+        format!("{{\"csp-report\":{{{}}}}}", body).into_bytes()
+    }
+}
+
+/// <https://w3c.github.io/webappsec-csp/#report-violation>
+///
+/// Must be called from a task; `element` is the element the violation is
+/// attributed to, if any.
+pub fn report_violations(window: &Window, violations: Vec<Violation>, element: Option<Trusted<Element>>) {
+    let document = window.Document();
+    // Step 2: the target is the element if it is still connected to the
+    // document, otherwise the document itself.
+    let element = element.map(|element| element.root());
+    let target = match element {
+        Some(ref element) if element.is_connected() && *element.upcast::<Node>().owner_doc() == *document => {
+            DomRoot::from_ref(element.upcast::<EventTarget>())
+        },
+        _ => DomRoot::from_ref(document.upcast::<EventTarget>()),
+    };
+
+    for violation in &violations {
+        let report = ViolationReport::new(window, violation);
+
+        // Step 3.1.
+        let init = SecurityPolicyViolationEventInit {
+            parent: EventInit {
+                bubbles: true,
+                cancelable: false,
+                composed: true,
+            },
+            documentURI: USVString(report.document_uri.clone()),
+            referrer: USVString(report.referrer.clone()),
+            blockedURI: USVString(report.blocked_uri.clone()),
+            effectiveDirective: DOMString::from(report.effective_directive.clone()),
+            violatedDirective: DOMString::from(report.effective_directive.clone()),
+            originalPolicy: DOMString::from(report.original_policy.clone()),
+            sourceFile: USVString(String::new()),
+            sample: DOMString::from(report.sample.clone()),
+            disposition: match report.disposition {
+                PolicyDisposition::Enforce => SecurityPolicyViolationEventDisposition::Enforce,
+                PolicyDisposition::Report => SecurityPolicyViolationEventDisposition::Report,
+            },
+            statusCode: report.status_code,
+            lineNumber: 0,
+            columnNumber: 0,
+        };
+        let event = SecurityPolicyViolationEvent::new(window.upcast(),
+                                                      atom!("securitypolicyviolation"),
+                                                      EventBubbles::Bubbles,
+                                                      EventCancelable::NotCancelable,
+                                                      &init);
+        event.upcast::<Event>().fire(&target);
+
+        // Step 3.2.
+        for report_uri in violation.policy.report_uris() {
+            let endpoint = match document.url().join(report_uri) {
+                Ok(url) => url,
+                Err(_) => continue,
+            };
+            send_report(window, endpoint, report.serialize());
+        }
+    }
+}
+
+/// <https://w3c.github.io/webappsec-csp/#report-violation> step 3.2.3.
+fn send_report(window: &Window, endpoint: ServoUrl, body: Vec<u8>) {
+    let global = window.upcast::<GlobalScope>();
+    let mut request = RequestInit {
+        method: Method::Post,
+        url: endpoint,
+        body: Some(body),
+        destination: Destination::Report,
+        mode: RequestMode::NoCors,
+        credentials_mode: CredentialsMode::CredentialsSameOrigin,
+        redirect_mode: RedirectMode::Error,
+        origin: global.origin().immutable().clone(),
+        pipeline_id: Some(global.pipeline_id()),
+        .. RequestInit::default()
+    };
+    request.headers.set(ContentType("application/csp-report".parse().unwrap()));
+
+    // Nobody is interested in the response.
+    let (sender, _receiver) = match ipc::channel() {
+        Ok(channel) => channel,
+        Err(_) => return,
+    };
+    let channels = FetchChannels::ResponseMsg(sender, None);
+    let _ = global.core_resource_thread().send(CoreResourceMsg::Fetch(request, channels));
+}
+
+/// <https://w3c.github.io/webappsec-csp/#strip-url-for-use-in-reports>
+fn strip_url_for_use_in_reports(url: &ServoUrl) -> String {
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return url.scheme().to_owned();
+    }
+    let mut url = url.as_url().clone();
+    url.set_fragment(None);
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    url.into_string()
+}
+
+fn json_string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
@@ -0,0 +1,413 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Abstract operations shared by the readable, writable and transform
+//! streams: promise reactions running native steps, queues with sizes and
+//! calls into the underlying sources, sinks and transformers given by script.
+//!
+//! <https://streams.spec.whatwg.org/>
+
+use dom::bindings::conversions::{ConversionResult, FromJSValConvertible};
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::reflector::DomObject;
+use dom::bindings::trace::JSTraceable;
+use dom::bindings::utils::get_dictionary_property;
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::promisenativehandler::{Callback, PromiseNativeHandler};
+use js::jsapi::{HandleValueArray, Heap, IsCallable, JSAutoCompartment, JSContext, JSObject, JSTracer};
+use js::jsapi::{JSPROP_ENUMERATE, JS_ClearPendingException, JS_NewPlainObject};
+use js::jsval::{BooleanValue, JSVal, ObjectValue, UndefinedValue};
+use js::rust::{HandleObject, HandleValue, MutableHandleValue, maybe_wrap_value};
+use js::rust::wrappers::{Call, JS_DefineProperty, JS_GetPendingException, JS_SetPendingException};
+use libc;
+use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
+use std::collections::VecDeque;
+use std::ptr;
+use std::rc::Rc;
+
+/// Native steps run when a promise settles, given the state they were
+/// registered with and the value or reason of the promise.
+pub type ReactionSteps<T> = fn(&T, *mut JSContext, HandleValue);
+
+/// A promise reaction running native steps.
+struct Reaction<T: JSTraceable + 'static> {
+    holder: T,
+    steps: ReactionSteps<T>,
+}
+
+#[allow(unsafe_code)]
+unsafe impl<T: JSTraceable + 'static> JSTraceable for Reaction<T> {
+    unsafe fn trace(&self, trc: *mut JSTracer) {
+        self.holder.trace(trc);
+    }
+}
+
+impl<T: JSTraceable + 'static> MallocSizeOf for Reaction<T> {
+    fn size_of(&self, _ops: &mut MallocSizeOfOps) -> usize {
+        0
+    }
+}
+
+impl<T: JSTraceable + 'static> Callback for Reaction<T> {
+    fn callback(&self, cx: *mut JSContext, value: HandleValue) {
+        (self.steps)(&self.holder, cx, value)
+    }
+}
+
+/// Runs `on_fulfilled` or `on_rejected` with `holder` once `promise` settles.
+///
+/// <https://heycam.github.io/webidl/#upon-fulfillment>
+pub fn upon_settlement<T>(promise: &Promise,
+                          holder: T,
+                          on_fulfilled: Option<ReactionSteps<T>>,
+                          on_rejected: Option<ReactionSteps<T>>)
+    where T: JSTraceable + Clone + 'static
+{
+    let resolve = on_fulfilled.map(|steps| {
+        Box::new(Reaction { holder: holder.clone(), steps: steps }) as Box<Callback>
+    });
+    let reject = on_rejected.map(|steps| Box::new(Reaction { holder: holder, steps: steps }) as Box<Callback>);
+    let handler = PromiseNativeHandler::new(&promise.global(), resolve, reject);
+    promise.append_native_handler(&handler);
+}
+
+/// A promise resolved with `undefined`.
+pub fn resolved_promise(global: &GlobalScope) -> Rc<Promise> {
+    let promise = Promise::new(global);
+    promise.resolve_native(&());
+    promise
+}
+
+/// A promise resolved with `value`, adopting its state if it is a thenable.
+///
+/// <https://heycam.github.io/webidl/#a-promise-resolved-with>
+#[allow(unsafe_code)]
+pub unsafe fn promise_resolved_with(global: &GlobalScope, cx: *mut JSContext, value: HandleValue) -> Rc<Promise> {
+    match Promise::new_resolved(global, cx, value) {
+        Ok(promise) => promise,
+        Err(error) => promise_rejected_with_error(global, error),
+    }
+}
+
+/// A promise rejected with `reason`.
+///
+/// <https://heycam.github.io/webidl/#a-promise-rejected-with>
+pub fn promise_rejected_with(global: &GlobalScope, reason: HandleValue) -> Rc<Promise> {
+    let promise = Promise::new(global);
+    promise.reject_native(&reason);
+    promise
+}
+
+/// A promise rejected with the value `error` is thrown as.
+#[allow(unsafe_code)]
+pub fn promise_rejected_with_error(global: &GlobalScope, error: Error) -> Rc<Promise> {
+    let cx = global.get_cx();
+    let _ac = JSAutoCompartment::new(cx, global.reflector().get_jsobject().get());
+    rooted!(in(cx) let mut reason = UndefinedValue());
+    unsafe { error_to_value(cx, global, error, reason.handle_mut()) };
+    promise_rejected_with(global, reason.handle())
+}
+
+/// A promise resolved with `undefined` once `promise` is fulfilled, or
+/// rejected with the same reason.
+pub fn transform_to_undefined(global: &GlobalScope, promise: &Promise) -> Rc<Promise> {
+    let result = Promise::new(global);
+    upon_settlement(promise, result.clone(), Some(resolve_with_undefined), Some(reject_with_reason));
+    result
+}
+
+fn resolve_with_undefined(promise: &Rc<Promise>, _cx: *mut JSContext, _value: HandleValue) {
+    promise.resolve_native(&());
+}
+
+fn reject_with_reason(promise: &Rc<Promise>, _cx: *mut JSContext, reason: HandleValue) {
+    promise.reject_native(&reason);
+}
+
+/// Takes the pending exception of `cx`.
+#[allow(unsafe_code)]
+pub unsafe fn take_pending_exception(cx: *mut JSContext, rval: MutableHandleValue) {
+    if !JS_GetPendingException(cx, rval) {
+        error!("Could not get the pending exception");
+    }
+    JS_ClearPendingException(cx);
+}
+
+/// Converts `error` to the value it is thrown as, taking the pending
+/// exception for `Error::JSFailed`.
+#[allow(unsafe_code)]
+pub unsafe fn error_to_value(cx: *mut JSContext, global: &GlobalScope, error: Error, rval: MutableHandleValue) {
+    match error {
+        Error::JSFailed => take_pending_exception(cx, rval),
+        error => error.to_jsval(cx, global, rval),
+    }
+}
+
+/// Throws `value`, returning the error to report it with.
+#[allow(unsafe_code)]
+pub unsafe fn throw_value(cx: *mut JSContext, value: HandleValue) -> Error {
+    JS_SetPendingException(cx, value);
+    Error::JSFailed
+}
+
+#[allow(unsafe_code)]
+pub unsafe fn convert<T>(cx: *mut JSContext, value: HandleValue, config: T::Config) -> Fallible<T>
+    where T: FromJSValConvertible
+{
+    match T::from_jsval(cx, value, config) {
+        Ok(ConversionResult::Success(value)) => Ok(value),
+        Ok(ConversionResult::Failure(message)) => Err(Error::Type(message.into_owned())),
+        Err(()) => Err(Error::JSFailed),
+    }
+}
+
+/// Gets the property `name` of `object` into `rval`, returning whether it
+/// is defined.
+#[allow(unsafe_code)]
+pub unsafe fn get_property(cx: *mut JSContext,
+                           object: HandleObject,
+                           name: &str,
+                           mut rval: MutableHandleValue)
+                           -> Fallible<bool> {
+    rooted!(in(cx) let mut value = UndefinedValue());
+    if !get_dictionary_property(cx, object, name, value.handle_mut()).map_err(|()| Error::JSFailed)? {
+        return Ok(false);
+    }
+    let defined = !value.is_undefined();
+    rval.set(value.get());
+    Ok(defined)
+}
+
+/// Gets the method `name` of `object` into `rval`, returning whether it has
+/// one.
+///
+/// <https://tc39.github.io/ecma262/#sec-getmethod>
+#[allow(unsafe_code)]
+pub unsafe fn get_method(cx: *mut JSContext,
+                         object: HandleObject,
+                         name: &str,
+                         mut rval: MutableHandleValue)
+                         -> Fallible<bool> {
+    rooted!(in(cx) let mut method = UndefinedValue());
+    if !get_property(cx, object, name, method.handle_mut())? || method.is_null() {
+        return Ok(false);
+    }
+    if !method.is_object() || !IsCallable(method.to_object()) {
+        return Err(Error::Type(format!("{} is not a function", name)));
+    }
+    rval.set(method.get());
+    Ok(true)
+}
+
+/// Calls the method `name` of `object` with `args`, if it has one.
+///
+/// <https://streams.spec.whatwg.org/#invoke-or-noop>
+#[allow(unsafe_code)]
+pub unsafe fn invoke_or_noop(cx: *mut JSContext,
+                             object: HandleObject,
+                             name: &str,
+                             args: &[JSVal],
+                             mut rval: MutableHandleValue)
+                             -> Fallible<()> {
+    rooted!(in(cx) let mut method = UndefinedValue());
+    if !get_method(cx, object, name, method.handle_mut())? {
+        rval.set(UndefinedValue());
+        return Ok(());
+    }
+    rooted!(in(cx) let this = ObjectValue(object.get()));
+    let args = HandleValueArray::from_rooted_slice(args);
+    if !Call(cx, this.handle(), method.handle(), &args, rval) {
+        return Err(Error::JSFailed);
+    }
+    Ok(())
+}
+
+/// Calls the method `name` of `object` with `args`, if it has one, and
+/// returns a promise for its result.
+///
+/// <https://streams.spec.whatwg.org/#promise-invoke-or-noop>
+#[allow(unsafe_code)]
+pub unsafe fn promise_invoke_or_noop(global: &GlobalScope,
+                                     cx: *mut JSContext,
+                                     object: HandleObject,
+                                     name: &str,
+                                     args: &[JSVal])
+                                     -> Rc<Promise> {
+    rooted!(in(cx) let mut result = UndefinedValue());
+    match invoke_or_noop(cx, object, name, args, result.handle_mut()) {
+        Ok(()) => promise_resolved_with(global, cx, result.handle()),
+        Err(error) => promise_rejected_with_error(global, error),
+    }
+}
+
+/// Gets the high water mark of a queuing strategy, or `default` if it has none.
+///
+/// <https://streams.spec.whatwg.org/#validate-and-normalize-high-water-mark>
+#[allow(unsafe_code)]
+pub unsafe fn extract_high_water_mark(cx: *mut JSContext, strategy: HandleObject, default: f64) -> Fallible<f64> {
+    rooted!(in(cx) let mut value = UndefinedValue());
+    if !get_property(cx, strategy, "highWaterMark", value.handle_mut())? {
+        return Ok(default);
+    }
+    let high_water_mark = convert::<f64>(cx, value.handle(), ())?;
+    if high_water_mark.is_nan() || high_water_mark < 0. {
+        return Err(Error::Range("The high water mark must be a non-negative number".to_owned()));
+    }
+    Ok(high_water_mark)
+}
+
+/// Gets the size function of a queuing strategy into `rval`, leaving it
+/// undefined if chunks all have a size of 1.
+///
+/// <https://streams.spec.whatwg.org/#make-size-algorithm-from-size-function>
+#[allow(unsafe_code)]
+pub unsafe fn extract_size_algorithm(cx: *mut JSContext,
+                                     strategy: HandleObject,
+                                     rval: MutableHandleValue)
+                                     -> Fallible<()> {
+    get_method(cx, strategy, "size", rval).map(|_| ())
+}
+
+/// Calls the size function of a queuing strategy on `chunk`.
+#[allow(unsafe_code)]
+pub unsafe fn call_size_algorithm(cx: *mut JSContext, size: HandleValue, chunk: HandleValue) -> Fallible<f64> {
+    if size.is_undefined() {
+        return Ok(1.);
+    }
+    rooted!(in(cx) let this = UndefinedValue());
+    rooted!(in(cx) let mut result = UndefinedValue());
+    let args = [chunk.get()];
+    let args = HandleValueArray::from_rooted_slice(&args);
+    if !Call(cx, this.handle(), size, &args, result.handle_mut()) {
+        return Err(Error::JSFailed);
+    }
+    convert::<f64>(cx, result.handle(), ())
+}
+
+/// Creates the `{ value, done }` object a read is fulfilled with.
+///
+/// <https://streams.spec.whatwg.org/#readable-stream-create-read-result>
+#[allow(unsafe_code)]
+pub unsafe fn create_read_result(cx: *mut JSContext, value: HandleValue, done: bool, mut rval: MutableHandleValue) {
+    rooted!(in(cx) let object = JS_NewPlainObject(cx));
+    assert!(!object.is_null());
+    rooted!(in(cx) let done = BooleanValue(done));
+    assert!(JS_DefineProperty(cx, object.handle(), b"value\0".as_ptr() as *const libc::c_char,
+                              value, JSPROP_ENUMERATE, None, None));
+    assert!(JS_DefineProperty(cx, object.handle(), b"done\0".as_ptr() as *const libc::c_char,
+                              done.handle(), JSPROP_ENUMERATE, None, None));
+    rval.set(ObjectValue(object.get()));
+}
+
+/// Gets the value of a `{ value, done }` object a read was fulfilled with,
+/// returning whether the stream is done.
+#[allow(unsafe_code)]
+pub unsafe fn read_read_result(cx: *mut JSContext, result: HandleValue, rval: MutableHandleValue) -> bool {
+    assert!(result.is_object());
+    rooted!(in(cx) let object = result.to_object());
+    rooted!(in(cx) let mut done = UndefinedValue());
+    if get_dictionary_property(cx, object.handle(), "done", done.handle_mut()).is_err() ||
+       get_dictionary_property(cx, object.handle(), "value", rval).is_err() {
+        JS_ClearPendingException(cx);
+        return true;
+    }
+    done.is_boolean() && done.to_boolean()
+}
+
+/// Fulfills the promise of a read request with `chunk`.
+#[allow(unsafe_code)]
+pub fn fulfill_read_request(promise: &Promise, chunk: HandleValue, done: bool) {
+    let cx = promise.global().get_cx();
+    let _ac = JSAutoCompartment::new(cx, promise.reflector().get_jsobject().get());
+    rooted!(in(cx) let mut chunk = chunk.get());
+    rooted!(in(cx) let mut result = UndefinedValue());
+    unsafe {
+        maybe_wrap_value(cx, chunk.handle_mut());
+        create_read_result(cx, chunk.handle(), done, result.handle_mut());
+        promise.resolve(cx, result.handle());
+    }
+}
+
+/// A chunk of a queue with sizes, or the close sentinel of a writable stream.
+#[derive(JSTraceable, MallocSizeOf)]
+enum QueueEntry {
+    Chunk {
+        #[ignore_malloc_size_of = "mozjs"]
+        value: Box<Heap<JSVal>>,
+        size: f64,
+    },
+    Close,
+}
+
+/// <https://streams.spec.whatwg.org/#queue-with-sizes>
+#[derive(Default, JSTraceable, MallocSizeOf)]
+pub struct QueueWithSizes {
+    queue: VecDeque<QueueEntry>,
+    total_size: f64,
+}
+
+impl QueueWithSizes {
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn total_size(&self) -> f64 {
+        self.total_size
+    }
+
+    /// <https://streams.spec.whatwg.org/#enqueue-value-with-size>
+    pub fn enqueue_value_with_size(&mut self, value: HandleValue, size: f64) -> Fallible<()> {
+        if !size.is_finite() || size < 0. {
+            return Err(Error::Range("The size of a chunk must be a finite, non-negative number".to_owned()));
+        }
+        self.queue.push_back(QueueEntry::Chunk { value: Heap::boxed(value.get()), size: size });
+        self.total_size += size;
+        Ok(())
+    }
+
+    /// Enqueues the close sentinel, which has a size of 0.
+    pub fn enqueue_close(&mut self) {
+        self.queue.push_back(QueueEntry::Close);
+    }
+
+    /// <https://streams.spec.whatwg.org/#dequeue-value>
+    pub fn dequeue_value(&mut self, mut rval: MutableHandleValue) {
+        match self.queue.pop_front() {
+            Some(QueueEntry::Chunk { value, size }) => {
+                // Rounding errors may make the total size slightly negative.
+                self.total_size = (self.total_size - size).max(0.);
+                rval.set(value.get());
+            },
+            Some(QueueEntry::Close) | None => rval.set(UndefinedValue()),
+        }
+    }
+
+    /// Whether the next entry is the close sentinel.
+    pub fn peek_is_close(&self) -> bool {
+        match self.queue.front() {
+            Some(&QueueEntry::Close) => true,
+            _ => false,
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#peek-queue-value>
+    pub fn peek_value(&self, mut rval: MutableHandleValue) {
+        match self.queue.front() {
+            Some(&QueueEntry::Chunk { ref value, .. }) => rval.set(value.get()),
+            _ => rval.set(UndefinedValue()),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#reset-queue>
+    pub fn reset(&mut self) {
+        self.queue.clear();
+        self.total_size = 0.;
+    }
+}
+
+/// Gets the object of an optional `object` argument, or null.
+pub fn object_or_null(object: Option<*mut JSObject>) -> *mut JSObject {
+    object.unwrap_or(ptr::null_mut())
+}
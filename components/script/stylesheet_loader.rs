@@ -20,6 +20,7 @@ use hyper_serde::Serde;
 use ipc_channel::ipc;
 use ipc_channel::router::ROUTER;
 use net_traits::{FetchResponseListener, FetchMetadata, FilteredMetadata, Metadata, NetworkError, ReferrerPolicy};
+use net_traits::csp::Violation;
 use net_traits::request::{CorsSettings, CredentialsMode, Destination, RequestInit, RequestMode};
 use network_listener::{NetworkListener, PreInvoke};
 use parking_lot::RwLock;
@@ -193,6 +194,11 @@ impl FetchResponseListener for StylesheetContext {
             elem.upcast::<EventTarget>().fire_event(event);
         }
     }
+
+    fn process_csp_violations(&mut self, violations: Vec<Violation>) {
+        let elem = self.elem.root();
+        self.document.root().global().report_csp_violations(violations, Some(elem.upcast()));
+    }
 }
 
 pub struct StylesheetLoader<'a> {
@@ -265,6 +271,7 @@ impl<'a> StylesheetLoader<'a> {
             referrer_url: Some(document.url()),
             referrer_policy: referrer_policy,
             integrity_metadata: integrity_metadata,
+            cryptographic_nonce_metadata: self.elem.upcast::<Element>().nonce_value(),
             .. RequestInit::default()
         };
 
@@ -8,6 +8,7 @@ pub mod file_reading;
 pub mod history_traversal;
 pub mod networking;
 pub mod performance_timeline;
+pub mod port_message;
 pub mod remote_event;
 pub mod user_interaction;
 
@@ -27,6 +28,7 @@ pub enum TaskSourceName {
     HistoryTraversal,
     Networking,
     PerformanceTimeline,
+    PortMessage,
     UserInteraction,
     RemoteEvent
 }
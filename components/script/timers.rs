@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use dom::abortsignal::AbortSignalTimeoutCallback;
 use dom::bindings::callback::ExceptionHandling::Report;
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::FunctionBinding::Function;
@@ -72,6 +73,7 @@ pub enum OneshotTimerCallback {
     JsTimer(JsTimerTask),
     TestBindingCallback(TestBindingCallback),
     FakeRequestAnimationFrame(FakeRequestAnimationFrameCallback),
+    AbortSignalTimeout(AbortSignalTimeoutCallback),
 }
 
 impl OneshotTimerCallback {
@@ -82,6 +84,7 @@ impl OneshotTimerCallback {
             OneshotTimerCallback::JsTimer(task) => task.invoke(this, js_timers),
             OneshotTimerCallback::TestBindingCallback(callback) => callback.invoke(),
             OneshotTimerCallback::FakeRequestAnimationFrame(callback) => callback.invoke(),
+            OneshotTimerCallback::AbortSignalTimeout(callback) => callback.invoke(),
         }
     }
 }
@@ -494,11 +497,13 @@ impl JsTimerTask {
         match self.callback {
             InternalTimerCallback::StringTimerCallback(ref code_str) => {
                 let global = this.global();
-                let cx = global.get_cx();
-                rooted!(in(cx) let mut rval = UndefinedValue());
+                if global.is_js_evaluation_allowed(code_str) {
+                    let cx = global.get_cx();
+                    rooted!(in(cx) let mut rval = UndefinedValue());
 
-                global.evaluate_js_on_global_with_result(
-                    code_str, rval.handle_mut());
+                    global.evaluate_js_on_global_with_result(
+                        code_str, rval.handle_mut());
+                }
             },
             InternalTimerCallback::FunctionTimerCallback(ref function, ref arguments) => {
                 let arguments = self.collect_heap_args(arguments);
@@ -9,34 +9,45 @@ use dom::bindings::codegen::Bindings::ElementBinding::ElementMethods;
 use dom::bindings::codegen::Bindings::HTMLElementBinding::HTMLElementMethods;
 use dom::bindings::codegen::Bindings::HTMLInputElementBinding::HTMLInputElementMethods;
 use dom::bindings::codegen::Bindings::HTMLOptionElementBinding::HTMLOptionElementMethods;
+use dom::bindings::codegen::Bindings::HTMLTextAreaElementBinding::HTMLTextAreaElementMethods;
 use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
+use dom::bindings::codegen::Bindings::SelectionBinding::SelectionMethods;
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
-use dom::bindings::conversions::{ConversionResult, FromJSValConvertible, StringificationBehavior};
+use dom::bindings::conversions::{ConversionBehavior, ConversionResult, FromJSValConvertible};
+use dom::bindings::conversions::{StringificationBehavior, get_property, get_property_jsval};
+use dom::bindings::conversions::{is_array_like, jsid_to_string, root_from_object};
 use dom::bindings::inheritance::Castable;
 use dom::bindings::root::DomRoot;
 use dom::bindings::str::DOMString;
+use dom::bindings::trace::RootedVec;
 use dom::element::Element;
+use dom::eventtarget::EventTarget;
 use dom::globalscope::GlobalScope;
+use dom::htmlcollection::HTMLCollection;
 use dom::htmlelement::HTMLElement;
 use dom::htmliframeelement::HTMLIFrameElement;
 use dom::htmlinputelement::HTMLInputElement;
 use dom::htmloptionelement::HTMLOptionElement;
+use dom::htmltextareaelement::HTMLTextAreaElement;
 use dom::node::{Node, window_from_node};
+use dom::nodelist::NodeList;
 use euclid::{Point2D, Rect, Size2D};
 use hyper_serde::Serde;
 use ipc_channel::ipc::{self, IpcSender};
-use js::jsapi::JSContext;
+use js::jsapi::{Heap, JSContext, JSITER_OWNONLY, JSObject, JS_ClearPendingException};
 use js::jsval::UndefinedValue;
-use js::rust::HandleValue;
+use js::rust::{HandleObject, HandleValue, IdVector};
+use js::rust::wrappers::{GetPropertyKeys, JS_GetPropertyById};
 use msg::constellation_msg::BrowsingContextId;
 use msg::constellation_msg::PipelineId;
 use net_traits::CookieSource::{HTTP, NonHTTP};
 use net_traits::CoreResourceMsg::{GetCookiesDataForUrl, SetCookieForUrl};
 use net_traits::IpcSend;
 use script_thread::Documents;
-use script_traits::webdriver_msg::{WebDriverFrameId, WebDriverJSError, WebDriverJSResult, WebDriverJSValue};
-use script_traits::webdriver_msg::WebDriverCookieError;
+use script_traits::webdriver_msg::{WebDriverCookieError, WebDriverElementError, WebDriverFrameId};
+use script_traits::webdriver_msg::{WebDriverJSError, WebDriverJSResult, WebDriverJSValue, WebDriverSelection};
 use servo_url::ServoUrl;
+use std::collections::BTreeMap;
 
 fn find_node_by_unique_id(documents: &Documents,
                           pipeline: PipelineId,
@@ -49,6 +60,16 @@ fn find_node_by_unique_id(documents: &Documents,
 
 #[allow(unsafe_code)]
 pub unsafe fn jsval_to_webdriver(cx: *mut JSContext, val: HandleValue) -> WebDriverJSResult {
+    rooted_vec!(let mut seen);
+    internal_json_clone(cx, val, &mut seen)
+}
+
+// https://w3c.github.io/webdriver/webdriver-spec.html#dfn-internal-json-clone-algorithm
+#[allow(unsafe_code)]
+unsafe fn internal_json_clone(cx: *mut JSContext,
+                              val: HandleValue,
+                              seen: &mut RootedVec<Box<Heap<*mut JSObject>>>)
+                              -> WebDriverJSResult {
     if val.get().is_undefined() {
         Ok(WebDriverJSValue::Undefined)
     } else if val.get().is_boolean() {
@@ -68,11 +89,87 @@ pub unsafe fn jsval_to_webdriver(cx: *mut JSContext, val: HandleValue) -> WebDri
         Ok(WebDriverJSValue::String(String::from(string)))
     } else if val.get().is_null() {
         Ok(WebDriverJSValue::Null)
+    } else if val.get().is_object() {
+        rooted!(in(cx) let object = val.get().to_object());
+        if let Ok(element) = root_from_object::<Element>(object.get()) {
+            return Ok(WebDriverJSValue::Element(element.upcast::<Node>().unique_id()));
+        }
+
+        // Only the objects currently being cloned are remembered, so the same
+        // object may appear more than once as long as it doesn't contain itself.
+        if seen.iter().any(|seen_object| seen_object.get() == object.get()) {
+            return Err(WebDriverJSError::JSError);
+        }
+        seen.push(Heap::boxed(object.get()));
+        let result = if is_array_like(cx, val) ||
+                        root_from_object::<NodeList>(object.get()).is_ok() ||
+                        root_from_object::<HTMLCollection>(object.get()).is_ok() {
+            clone_collection(cx, object.handle(), seen)
+        } else {
+            clone_object(cx, object.handle(), seen)
+        };
+        seen.pop();
+        result
     } else {
         Err(WebDriverJSError::UnknownType)
     }
 }
 
+#[allow(unsafe_code)]
+unsafe fn clone_collection(cx: *mut JSContext,
+                           object: HandleObject,
+                           seen: &mut RootedVec<Box<Heap<*mut JSObject>>>)
+                           -> WebDriverJSResult {
+    let length = match get_property::<u32>(cx, object, "length", ConversionBehavior::Default) {
+        Ok(Some(length)) => length,
+        Ok(None) => 0,
+        Err(_) => {
+            JS_ClearPendingException(cx);
+            return Err(WebDriverJSError::JSError);
+        },
+    };
+
+    let mut result = Vec::with_capacity(length as usize);
+    for index in 0..length {
+        rooted!(in(cx) let mut item = UndefinedValue());
+        if get_property_jsval(cx, object, &index.to_string(), item.handle_mut()).is_err() {
+            JS_ClearPendingException(cx);
+            return Err(WebDriverJSError::JSError);
+        }
+        result.push(internal_json_clone(cx, item.handle(), seen)?);
+    }
+    Ok(WebDriverJSValue::ArrayLike(result))
+}
+
+#[allow(unsafe_code)]
+unsafe fn clone_object(cx: *mut JSContext,
+                       object: HandleObject,
+                       seen: &mut RootedVec<Box<Heap<*mut JSObject>>>)
+                       -> WebDriverJSResult {
+    let ids = IdVector::new(cx);
+    if !GetPropertyKeys(cx, object, JSITER_OWNONLY, ids.get()) {
+        JS_ClearPendingException(cx);
+        return Err(WebDriverJSError::JSError);
+    }
+
+    let mut result = BTreeMap::new();
+    for id in &*ids {
+        rooted!(in(cx) let id = *id);
+        let name = match jsid_to_string(cx, id.handle()) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        rooted!(in(cx) let mut property = UndefinedValue());
+        if !JS_GetPropertyById(cx, object, id.handle(), property.handle_mut()) {
+            JS_ClearPendingException(cx);
+            return Err(WebDriverJSError::JSError);
+        }
+        result.insert(String::from(name), internal_json_clone(cx, property.handle(), seen)?);
+    }
+    Ok(WebDriverJSValue::Object(result))
+}
+
 #[allow(unsafe_code)]
 pub fn handle_execute_script(documents: &Documents,
                              pipeline: PipelineId,
@@ -172,6 +269,60 @@ pub fn handle_focus_element(documents: &Documents,
     }).unwrap();
 }
 
+pub fn handle_element_clear(documents: &Documents,
+                            pipeline: PipelineId,
+                            element_id: String,
+                            reply: IpcSender<Result<(), WebDriverElementError>>) {
+    reply.send(match find_node_by_unique_id(documents, pipeline, element_id) {
+        Some(ref node) => clear_element(node),
+        None => Err(WebDriverElementError::NotFound)
+    }).unwrap();
+}
+
+// https://w3c.github.io/webdriver/webdriver-spec.html#dfn-clear-a-resettable-element
+fn clear_element(node: &Node) -> Result<(), WebDriverElementError> {
+    // TODO: content editable elements can't be cleared yet.
+    let html_element = match node.downcast::<HTMLElement>() {
+        Some(html_element) => html_element,
+        None => return Err(WebDriverElementError::InvalidState)
+    };
+    let disabled = node.downcast::<Element>().unwrap().disabled_state();
+
+    // Step 1
+    let was_empty = if let Some(input) = node.downcast::<HTMLInputElement>() {
+        if disabled || input.ReadOnly() {
+            return Err(WebDriverElementError::InvalidState);
+        }
+        input.Value().is_empty()
+    } else if let Some(textarea) = node.downcast::<HTMLTextAreaElement>() {
+        if disabled || textarea.ReadOnly() {
+            return Err(WebDriverElementError::InvalidState);
+        }
+        textarea.Value().is_empty()
+    } else {
+        return Err(WebDriverElementError::InvalidState);
+    };
+
+    // Step 2
+    html_element.Focus();
+
+    // Step 3
+    if let Some(input) = node.downcast::<HTMLInputElement>() {
+        input.SetValue(DOMString::new()).map_err(|_| WebDriverElementError::InvalidState)?;
+    } else if let Some(textarea) = node.downcast::<HTMLTextAreaElement>() {
+        textarea.SetValue(DOMString::new());
+    }
+
+    // Step 4
+    if !was_empty {
+        node.upcast::<EventTarget>().fire_bubbling_event(atom!("change"));
+    }
+
+    // Step 5
+    html_element.Blur();
+    Ok(())
+}
+
 pub fn handle_get_active_element(documents: &Documents,
                                  pipeline: PipelineId,
                                  reply: IpcSender<Option<String>>) {
@@ -256,6 +407,26 @@ pub fn handle_add_cookie(documents: &Documents,
     }).unwrap();
 }
 
+pub fn handle_get_selection(documents: &Documents,
+                            pipeline: PipelineId,
+                            reply: IpcSender<Option<WebDriverSelection>>) {
+    fn containing_element_id(node: Option<DomRoot<Node>>) -> Option<String> {
+        node?.inclusive_ancestors().find(|node| node.is::<Element>()).map(|element| element.unique_id())
+    }
+
+    let selection = documents.find_document(pipeline)
+        .and_then(|doc| doc.GetSelection())
+        .map(|selection| WebDriverSelection {
+            text: String::from(selection.Stringifier()),
+            selection_type: String::from(selection.Type()),
+            anchor_element: containing_element_id(selection.GetAnchorNode()),
+            anchor_offset: selection.AnchorOffset(),
+            focus_element: containing_element_id(selection.GetFocusNode()),
+            focus_offset: selection.FocusOffset(),
+        });
+    reply.send(selection).unwrap();
+}
+
 pub fn handle_get_title(documents: &Documents, pipeline: PipelineId, reply: IpcSender<String>) {
     // TODO: Return an error if the pipeline doesn't exist.
     let title = documents.find_document(pipeline)
@@ -302,6 +473,77 @@ pub fn handle_get_rect(documents: &Documents,
     }).unwrap();
 }
 
+pub fn handle_get_in_view_center_point(documents: &Documents,
+                                       pipeline: PipelineId,
+                                       element_id: String,
+                                       reply: IpcSender<Result<Option<(i64, i64)>, ()>>) {
+    reply.send(match find_node_by_unique_id(documents, pipeline, element_id) {
+        Some(ref node) if node.is::<Element>() => {
+            // https://w3c.github.io/webdriver/webdriver-spec.html#dfn-scrolls-into-view
+            if in_view_center_point(node).is_none() {
+                if let Some(rect) = node.content_boxes().first() {
+                    window_from_node(&**node).ScrollTo_(rect.origin.x.to_f64_px(), rect.origin.y.to_f64_px());
+                }
+            }
+            Ok(in_view_center_point(node))
+        },
+        _ => Err(())
+    }).unwrap();
+}
+
+// https://w3c.github.io/webdriver/webdriver-spec.html#dfn-in-view-center-point
+fn in_view_center_point(node: &Node) -> Option<(i64, i64)> {
+    let window = window_from_node(node);
+
+    // Step 1
+    let rect = match node.content_boxes().first() {
+        Some(rect) => *rect,
+        None => return None
+    };
+
+    // Layout reports boxes relative to the document, not to the viewport.
+    let scroll_x = window.ScrollX() as f64;
+    let scroll_y = window.ScrollY() as f64;
+    let x = rect.origin.x.to_f64_px() - scroll_x;
+    let y = rect.origin.y.to_f64_px() - scroll_y;
+
+    // Steps 2-5
+    let left = x.max(0.);
+    let right = (x + rect.size.width.to_f64_px()).min(window.InnerWidth() as f64);
+    let top = y.max(0.);
+    let bottom = (y + rect.size.height.to_f64_px()).min(window.InnerHeight() as f64);
+    if left > right || top > bottom {
+        return None;
+    }
+
+    // Steps 6-8
+    Some((((left + right) / 2.).floor() as i64, ((top + bottom) / 2.).floor() as i64))
+}
+
+pub fn handle_get_frame_offset(documents: &Documents,
+                               pipeline: PipelineId,
+                               browsing_context_id: BrowsingContextId,
+                               reply: IpcSender<Result<(i64, i64), ()>>) {
+    reply.send(match documents.find_iframe(pipeline, browsing_context_id) {
+        Some(iframe) => {
+            let node = iframe.upcast::<Node>();
+            match node.content_boxes().first() {
+                Some(rect) => {
+                    // The nested document starts inside the border of the
+                    // iframe, whose box layout reports relative to the document.
+                    let window = window_from_node(node);
+                    let element = iframe.upcast::<Element>();
+                    let x = rect.origin.x.to_f64_px() - window.ScrollX() as f64 + element.ClientLeft() as f64;
+                    let y = rect.origin.y.to_f64_px() - window.ScrollY() as f64 + element.ClientTop() as f64;
+                    Ok((x.floor() as i64, y.floor() as i64))
+                },
+                None => Err(()),
+            }
+        },
+        None => Err(())
+    }).unwrap();
+}
+
 pub fn handle_get_text(documents: &Documents,
                        pipeline: PipelineId,
                        node_id: String,
@@ -356,6 +598,16 @@ pub fn handle_get_css(documents: &Documents,
     }).unwrap();
 }
 
+pub fn handle_get_scroll_offset(documents: &Documents,
+                                pipeline: PipelineId,
+                                reply: IpcSender<(f64, f64)>) {
+    // TODO: Return an error if the pipeline doesn't exist.
+    let offset = documents.find_window(pipeline)
+        .map(|window| (window.ScrollX() as f64, window.ScrollY() as f64))
+        .unwrap_or((0., 0.));
+    reply.send(offset).unwrap();
+}
+
 pub fn handle_get_url(documents: &Documents,
                       pipeline: PipelineId,
                       reply: IpcSender<ServoUrl>) {
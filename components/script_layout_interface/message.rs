@@ -20,10 +20,13 @@ use servo_atoms::Atom;
 use servo_url::ServoUrl;
 use std::sync::Arc;
 use std::sync::mpsc::{Receiver, Sender};
+use style::animation::ScriptAnimationSample;
 use style::context::QuirksMode;
+use style::font_face::EffectiveSources;
 use style::properties::PropertyId;
 use style::selector_parser::PseudoElement;
 use style::stylesheets::Stylesheet;
+use style::values::computed::font::FamilyName;
 
 /// Asynchronous messages that script can send to layout.
 pub enum Msg {
@@ -98,6 +101,14 @@ pub enum Msg {
 
     /// Send to layout the precise time when the navigation started.
     SetNavigationStart(u64),
+
+    /// Asks layout to load a web font for the given family from the given
+    /// sources, replying whether the font could be loaded.
+    AddWebFont(FamilyName, EffectiveSources, IpcSender<bool>),
+
+    /// Asks layout to add a web font for the given family from the raw data
+    /// of a font file, replying whether the font could be loaded.
+    AddWebFontData(FamilyName, Vec<u8>, IpcSender<bool>),
 }
 
 #[derive(Debug, PartialEq)]
@@ -117,8 +128,11 @@ pub enum QueryMsg {
     OffsetParentQuery(TrustedNodeAddress),
     StyleQuery(TrustedNodeAddress),
     TextIndexQuery(TrustedNodeAddress, Point2D<f32>),
+    TextIndexAtPointQuery(TrustedNodeAddress, Point2D<f32>),
     NodesFromPointQuery(Point2D<f32>, NodesFromPointQueryType),
     ElementInnerTextQuery(TrustedNodeAddress),
+    ObservedBoxesQuery(Vec<TrustedNodeAddress>),
+    RunningCSSAnimationsQuery,
 }
 
 /// Any query to perform with this reflow.
@@ -138,6 +152,7 @@ impl ReflowGoal {
             ReflowGoal::LayoutQuery(ref querymsg, _) => match querymsg {
                 &QueryMsg::NodesFromPointQuery(..) |
                 &QueryMsg::TextIndexQuery(..) |
+                &QueryMsg::TextIndexAtPointQuery(..) |
                 &QueryMsg::ElementInnerTextQuery(_) => true,
                 &QueryMsg::ContentBoxQuery(_) |
                 &QueryMsg::ContentBoxesQuery(_) |
@@ -146,7 +161,9 @@ impl ReflowGoal {
                 &QueryMsg::NodeScrollIdQuery(_) |
                 &QueryMsg::ResolvedStyleQuery(..) |
                 &QueryMsg::OffsetParentQuery(_) |
-                &QueryMsg::StyleQuery(_) => false,
+                &QueryMsg::StyleQuery(_) |
+                &QueryMsg::ObservedBoxesQuery(_) |
+                &QueryMsg::RunningCSSAnimationsQuery => false,
             },
         }
     }
@@ -159,6 +176,7 @@ impl ReflowGoal {
             ReflowGoal::LayoutQuery(ref querymsg, _) => match querymsg {
                 &QueryMsg::NodesFromPointQuery(..) |
                 &QueryMsg::TextIndexQuery(..) |
+                &QueryMsg::TextIndexAtPointQuery(..) |
                 &QueryMsg::ElementInnerTextQuery(_) => true,
                 &QueryMsg::ContentBoxQuery(_) |
                 &QueryMsg::ContentBoxesQuery(_) |
@@ -167,7 +185,9 @@ impl ReflowGoal {
                 &QueryMsg::NodeScrollIdQuery(_) |
                 &QueryMsg::ResolvedStyleQuery(..) |
                 &QueryMsg::OffsetParentQuery(_) |
-                &QueryMsg::StyleQuery(_) => false,
+                &QueryMsg::StyleQuery(_) |
+                &QueryMsg::ObservedBoxesQuery(_) |
+                &QueryMsg::RunningCSSAnimationsQuery => false,
             },
         }
     }
@@ -204,6 +224,9 @@ pub struct ScriptReflow {
     pub reflow_goal: ReflowGoal,
     /// The number of objects in the dom #10110
     pub dom_count: u32,
+    /// The animations created from script that currently affect each node, in
+    /// composite order.
+    pub animations: Vec<(TrustedNodeAddress, Vec<ScriptAnimationSample>)>,
 }
 
 pub struct NewLayoutThreadInfo {
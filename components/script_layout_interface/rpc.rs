@@ -40,6 +40,12 @@ pub trait LayoutRPC {
     fn nodes_from_point_response(&self) -> Vec<UntrustedNodeAddress>;
     /// Query layout to get the inner text for a given element.
     fn element_inner_text(&self) -> String;
+    /// Requests the border, padding and content boxes of several nodes at once, as used by
+    /// `IntersectionObserver` and `ResizeObserver`.
+    fn observed_boxes(&self) -> ObservedBoxesResponse;
+    /// Requests the CSS animations and transitions currently running in layout, as used by
+    /// `getAnimations()`.
+    fn running_css_animations(&self) -> RunningCSSAnimationsResponse;
 }
 
 pub struct ContentBoxResponse(pub Option<Rect<Au>>);
@@ -76,3 +82,39 @@ pub struct StyleResponse(pub Option<Arc<ComputedValues>>);
 
 #[derive(Clone)]
 pub struct TextIndexResponse(pub Option<usize>);
+
+/// The border, padding and content boxes of a node, relative to the initial containing block.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ObservedBoxes {
+    pub border_box: Rect<Au>,
+    pub padding_box: Rect<Au>,
+    pub content_box: Rect<Au>,
+}
+
+/// The boxes of each requested node, in order, or `None` for nodes without any fragments.
+pub struct ObservedBoxesResponse(pub Vec<Option<ObservedBoxes>>);
+
+/// The kind of a CSS-driven animation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RunningCSSAnimationKind {
+    /// A CSS animation, with its `animation-name`.
+    Animation(String),
+    /// A CSS transition, with its transitioned property.
+    Transition(String),
+}
+
+/// A CSS animation or transition running in layout. Times are in seconds.
+#[derive(Clone, Debug)]
+pub struct RunningCSSAnimation {
+    pub node: UntrustedNodeAddress,
+    pub kind: RunningCSSAnimationKind,
+    pub duration: f64,
+    pub delay: f64,
+    /// The number of iterations, which may be infinite.
+    pub iterations: f64,
+    /// The time elapsed since the animation started, including its delay.
+    pub elapsed: f64,
+    pub paused: bool,
+}
+
+pub struct RunningCSSAnimationsResponse(pub Vec<RunningCSSAnimation>);
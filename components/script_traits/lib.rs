@@ -40,8 +40,9 @@ mod script_msg;
 pub mod webdriver_msg;
 
 use bluetooth_traits::BluetoothRequest;
-use canvas_traits::webgl::WebGLPipeline;
+use canvas_traits::webgl::{WebGLChan, WebGLPipeline};
 use devtools_traits::{DevtoolScriptControlMsg, ScriptToDevtoolsControlMsg, WorkerId};
+use embedder_traits::request_interception::RequestInterception;
 use euclid::{Length, Point2D, Vector2D, Rect, TypedSize2D, TypedScale};
 use gfx_traits::Epoch;
 use hyper::header::Headers;
@@ -75,6 +76,7 @@ use webvr_traits::{WebVREvent, WebVRMsg};
 
 pub use script_msg::{LayoutMsg, ScriptMsg, EventResult, LogEntry};
 pub use script_msg::{ServiceWorkerMsg, ScopeThings, SWManagerMsg, SWManagerSenders, DOMMessage};
+pub use script_msg::{BroadcastMsg, MessageRouterMsg};
 
 /// The address of a node. Layout sends these back. They must be validated via
 /// `from_untrusted_node_address` before they can be used, because we do not trust layout.
@@ -652,6 +654,12 @@ pub enum WebDriverCommandMsg {
     GetWindowSize(TopLevelBrowsingContextId, IpcSender<WindowSizeData>),
     /// Load a URL in the top-level browsing context with the given ID.
     LoadUrl(TopLevelBrowsingContextId, LoadData, IpcSender<LoadStatus>),
+    /// Act as if a mouse button was pressed or released at the given point, in CSS pixels
+    /// relative to the viewport of the top-level browsing context with the given ID.
+    MouseButtonAction(TopLevelBrowsingContextId, MouseEventType, MouseButton, f32, f32),
+    /// Act as if the mouse was moved to the given point, in CSS pixels relative to the
+    /// viewport of the top-level browsing context with the given ID.
+    MouseMoveAction(TopLevelBrowsingContextId, f32, f32),
     /// Refresh the top-level browsing context with the given ID.
     Refresh(TopLevelBrowsingContextId, IpcSender<LoadStatus>),
     /// Pass a webdriver command to the script thread of the current pipeline
@@ -711,6 +719,8 @@ pub enum ConstellationMsg {
     ForwardEvent(PipelineId, CompositorEvent),
     /// Requesting a change to the onscreen cursor.
     SetCursor(CursorKind),
+    /// Set how the requests made by the fetch pipeline are intercepted.
+    SetRequestInterception(RequestInterception),
 }
 
 impl fmt::Debug for ConstellationMsg {
@@ -737,6 +747,7 @@ impl fmt::Debug for ConstellationMsg {
             SelectBrowser(..) => "SelectBrowser",
             ForwardEvent(..) => "ForwardEvent",
             SetCursor(..) => "SetCursor",
+            SetRequestInterception(..) => "SetRequestInterception",
         };
         write!(formatter, "ConstellationMsg::{}", variant)
     }
@@ -765,6 +776,8 @@ pub struct WorkerGlobalScopeInit {
     pub pipeline_id: PipelineId,
     /// The origin
     pub origin: ImmutableOrigin,
+    /// A channel to the WebGL thread, if WebGL is enabled
+    pub webgl_chan: Option<WebGLChan>,
 }
 
 /// Common entities representing a network load origin
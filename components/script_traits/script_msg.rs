@@ -16,7 +16,8 @@ use embedder_traits::EmbedderMsg;
 use euclid::{Size2D, TypedSize2D};
 use gfx_traits::Epoch;
 use ipc_channel::ipc::{IpcReceiver, IpcSender};
-use msg::constellation_msg::{BrowsingContextId, HistoryStateId, PipelineId, TraversalDirection};
+use msg::constellation_msg::{BrowsingContextId, HistoryStateId, MessagePortId, MessageRouterId};
+use msg::constellation_msg::{PipelineId, PipelineNamespaceId, TraversalDirection};
 use net_traits::CoreResourceMsg;
 use net_traits::request::RequestInit;
 use net_traits::storage_thread::StorageType;
@@ -104,6 +105,8 @@ pub enum ScriptMsg {
     GetBrowsingContextId(PipelineId, IpcSender<Option<BrowsingContextId>>),
     /// Get the parent info for a given pipeline.
     GetParentInfo(PipelineId, IpcSender<Option<PipelineId>>),
+    /// Get the URLs of a given pipeline and of all its ancestors, innermost first.
+    GetAncestorUrls(PipelineId, IpcSender<Vec<ServoUrl>>),
     /// Get the nth child browsing context ID for a given browsing context, sorted in tree order.
     GetChildBrowsingContextId(BrowsingContextId, usize, IpcSender<Option<BrowsingContextId>>),
     /// All pending loads are complete, and the `load` event for this pipeline
@@ -116,6 +119,36 @@ pub enum ScriptMsg {
     AbortLoadUrl,
     /// Post a message to the currently active window of a given browsing context.
     PostMessage(BrowsingContextId, Option<ImmutableOrigin>, Vec<u8>),
+    /// Get a new namespace, for a worker thread that needs to create ids.
+    NewPipelineNamespace(IpcSender<PipelineNamespaceId>),
+    /// Register the router through which the message ports and broadcast channels
+    /// of a global receive their messages.
+    NewMessageRouter(MessageRouterId, IpcSender<MessageRouterMsg>),
+    /// Remove a router whose global went away, along with its ports and broadcast channels.
+    RemoveMessageRouter(MessageRouterId),
+    /// A new message port was created by the global of the given router.
+    NewMessagePort(MessageRouterId, MessagePortId),
+    /// Entangle two message ports.
+    EntanglePorts(MessagePortId, MessagePortId),
+    /// Post a serialized message through a port to the port it is entangled with.
+    PostPortMessage(MessagePortId, Vec<u8>),
+    /// A port was transferred away from its global, with the messages its global had not
+    /// dispatched yet. Messages for the port are held until its new global claims it.
+    MessagePortShipped(MessagePortId, Vec<Vec<u8>>),
+    /// A message for a port that was transferred away before the message could be dispatched.
+    ReturnPortMessage(MessagePortId, Vec<u8>),
+    /// A transferred port was received by the global of the given router.
+    ClaimMessagePort(MessageRouterId, MessagePortId),
+    /// A port was closed, which disentangles it.
+    CloseMessagePort(MessagePortId),
+    /// The global of the given router listens to the broadcast channel with the given
+    /// origin and name.
+    NewBroadcastChannel(MessageRouterId, ImmutableOrigin, String),
+    /// The global of the given router no longer listens to the broadcast channel with
+    /// the given origin and name.
+    RemoveBroadcastChannel(MessageRouterId, ImmutableOrigin, String),
+    /// Broadcast a message to the other globals listening to a broadcast channel.
+    Broadcast(MessageRouterId, BroadcastMsg),
     /// Inform the constellation that a fragment was navigated to and whether or not it was a replacement navigation.
     NavigatedToFragment(ServoUrl, bool),
     /// HTMLIFrameElement Forward or Back traversal.
@@ -181,11 +214,25 @@ impl fmt::Debug for ScriptMsg {
             GetClipboardContents(..) => "GetClipboardContents",
             GetBrowsingContextId(..) => "GetBrowsingContextId",
             GetParentInfo(..) => "GetParentInfo",
+            GetAncestorUrls(..) => "GetAncestorUrls",
             GetChildBrowsingContextId(..) => "GetChildBrowsingContextId",
             LoadComplete => "LoadComplete",
             LoadUrl(..) => "LoadUrl",
             AbortLoadUrl => "AbortLoadUrl",
             PostMessage(..) => "PostMessage",
+            NewPipelineNamespace(..) => "NewPipelineNamespace",
+            NewMessageRouter(..) => "NewMessageRouter",
+            RemoveMessageRouter(..) => "RemoveMessageRouter",
+            NewMessagePort(..) => "NewMessagePort",
+            EntanglePorts(..) => "EntanglePorts",
+            PostPortMessage(..) => "PostPortMessage",
+            MessagePortShipped(..) => "MessagePortShipped",
+            ReturnPortMessage(..) => "ReturnPortMessage",
+            ClaimMessagePort(..) => "ClaimMessagePort",
+            CloseMessagePort(..) => "CloseMessagePort",
+            NewBroadcastChannel(..) => "NewBroadcastChannel",
+            RemoveBroadcastChannel(..) => "RemoveBroadcastChannel",
+            Broadcast(..) => "Broadcast",
             NavigatedToFragment(..) => "NavigatedToFragment",
             TraverseHistory(..) => "TraverseHistory",
             PushHistoryState(..) => "PushHistoryState",
@@ -230,6 +277,26 @@ pub struct ScopeThings {
     pub worker_id: WorkerId,
 }
 
+/// A message routed by the constellation to the global of a message router.
+#[derive(Deserialize, Serialize)]
+pub enum MessageRouterMsg {
+    /// A serialized message for a port owned by the global.
+    PortMessage(MessagePortId, Vec<u8>),
+    /// A message for the broadcast channels of the global.
+    Broadcast(BroadcastMsg),
+}
+
+/// A serialized message posted to a broadcast channel.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BroadcastMsg {
+    /// The origin of the channel.
+    pub origin: ImmutableOrigin,
+    /// The name of the channel.
+    pub channel_name: String,
+    /// The serialized message.
+    pub data: Vec<u8>,
+}
+
 /// Message that gets passed to service worker scope on postMessage
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct DOMMessage(pub Vec<u8>);
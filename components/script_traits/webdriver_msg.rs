@@ -11,6 +11,12 @@ use ipc_channel::ipc::IpcSender;
 use msg::constellation_msg::BrowsingContextId;
 use rustc_serialize::json::{Json, ToJson};
 use servo_url::ServoUrl;
+use std::collections::BTreeMap;
+
+/// The key under which a web element reference is serialized.
+///
+/// <https://w3c.github.io/webdriver/webdriver-spec.html#dfn-web-element-identifier>
+const ELEMENT_KEY: &'static str = "element-6066-11e4-a52e-4f735466cecf";
 
 #[derive(Deserialize, Serialize)]
 pub enum WebDriverScriptCommand {
@@ -18,6 +24,7 @@ pub enum WebDriverScriptCommand {
                 serialize_with = "::hyper_serde::serialize")]
               Cookie<'static>,
               IpcSender<Result<(), WebDriverCookieError>>),
+    ElementClear(String, IpcSender<Result<(), WebDriverElementError>>),
     ExecuteScript(String, IpcSender<WebDriverJSResult>),
     ExecuteAsyncScript(String, IpcSender<WebDriverJSResult>),
     FindElementCSS(String, IpcSender<Result<Option<String>, ()>>),
@@ -28,9 +35,15 @@ pub enum WebDriverScriptCommand {
     GetCookies(IpcSender<Vec<Serde<Cookie<'static>>>>),
     GetElementAttribute(String, String, IpcSender<Result<Option<String>, ()>>),
     GetElementCSS(String, String, IpcSender<Result<String, ()>>),
+    GetElementInViewCenterPoint(String, IpcSender<Result<Option<(i64, i64)>, ()>>),
     GetElementRect(String, IpcSender<Result<Rect<f64>, ()>>),
     GetElementTagName(String, IpcSender<Result<String, ()>>),
     GetElementText(String, IpcSender<Result<String, ()>>),
+    /// The position of the document of the given child browsing context in
+    /// the viewport of this one.
+    GetFrameOffset(BrowsingContextId, IpcSender<Result<(i64, i64), ()>>),
+    GetScrollOffset(IpcSender<(f64, f64)>),
+    GetSelection(IpcSender<Option<WebDriverSelection>>),
     GetBrowsingContextId(WebDriverFrameId, IpcSender<Result<BrowsingContextId, ()>>),
     GetUrl(IpcSender<ServoUrl>),
     IsEnabled(String, IpcSender<Result<bool, ()>>),
@@ -38,25 +51,51 @@ pub enum WebDriverScriptCommand {
     GetTitle(IpcSender<String>),
 }
 
+/// The state of a document's selection.
+#[derive(Deserialize, Serialize)]
+pub struct WebDriverSelection {
+    pub text: String,
+    pub selection_type: String,
+    /// The id of the element containing the anchor node, if any.
+    pub anchor_element: Option<String>,
+    pub anchor_offset: u32,
+    /// The id of the element containing the focus node, if any.
+    pub focus_element: Option<String>,
+    pub focus_offset: u32,
+}
+
 #[derive(Deserialize, Serialize)]
 pub enum WebDriverCookieError {
     InvalidDomain,
     UnableToSetCookie,
 }
 
+#[derive(Deserialize, Serialize)]
+pub enum WebDriverElementError {
+    NotFound,
+    InvalidState,
+}
+
 #[derive(Deserialize, Serialize)]
 pub enum WebDriverJSValue {
     Undefined,
     Null,
     Boolean(bool),
     Number(f64),
-    String(String), // TODO: Object and WebElement
+    String(String),
+    /// A reference to an element, identified by its node's unique id.
+    Element(String),
+    ArrayLike(Vec<WebDriverJSValue>),
+    Object(BTreeMap<String, WebDriverJSValue>),
 }
 
 #[derive(Deserialize, Serialize)]
 pub enum WebDriverJSError {
     Timeout,
     UnknownType,
+    /// Occurs when the result contains a cycle, or when a property getter throws
+    /// while the result is being serialized.
+    JSError,
     /// Occurs when handler received an event message for a layout channel that is not
     /// associated with the current script thread
     BrowsingContextNotFound,
@@ -79,6 +118,13 @@ impl ToJson for WebDriverJSValue {
             WebDriverJSValue::Boolean(ref x) => x.to_json(),
             WebDriverJSValue::Number(ref x) => x.to_json(),
             WebDriverJSValue::String(ref x) => x.to_json(),
+            WebDriverJSValue::Element(ref x) => {
+                let mut object = BTreeMap::new();
+                object.insert(ELEMENT_KEY.to_owned(), x.to_json());
+                Json::Object(object)
+            },
+            WebDriverJSValue::ArrayLike(ref x) => x.to_json(),
+            WebDriverJSValue::Object(ref x) => x.to_json(),
         }
     }
 }
@@ -360,10 +360,19 @@ where
             Component::Namespace(..) => {
                 // Does not affect specificity
             },
-            Component::Negation(ref negated) => {
-                for ss in negated.iter() {
-                    simple_selector_specificity(builder, &ss, specificity);
-                }
+            Component::RelativeSelectorAnchor |
+            Component::Where(..) => {
+                // Does not affect specificity
+            },
+            Component::Negation(ref list) |
+            Component::Is(ref list) |
+            Component::Has(ref list) => {
+                // The specificity of these pseudo-classes is the one of the
+                // most specific selector in their argument.
+                //
+                // https://drafts.csswg.org/selectors-4/#specificity-rules
+                let max = list.iter().map(|s| s.specificity()).max().unwrap_or(0);
+                *specificity += Specificity::from(max);
             },
         }
     }
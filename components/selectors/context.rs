@@ -132,6 +132,10 @@ where
     /// Whether we're inside a negation or not.
     in_negation: bool,
 
+    /// The element that is anchoring the relative selector we're currently
+    /// matching, if any, for `:has()`.
+    relative_selector_anchor: Option<OpaqueElement>,
+
     /// An optional hook function for checking whether a pseudo-element
     /// should match when matching_mode is ForStatelessPseudoElement.
     pub pseudo_element_matching_fn: Option<&'a Fn(&Impl::PseudoElement) -> bool>,
@@ -183,6 +187,7 @@ where
             current_host: None,
             nesting_level: 0,
             in_negation: false,
+            relative_selector_anchor: None,
             pseudo_element_matching_fn: None,
             extra_data: Default::default(),
             _impl: ::std::marker::PhantomData,
@@ -247,13 +252,34 @@ where
     where
         F: FnOnce(&mut Self) -> R,
     {
-        debug_assert!(!self.in_negation, "Someone messed up parsing?");
+        let original_in_negation = self.in_negation;
         self.in_negation = true;
         let result = self.nest(f);
-        self.in_negation = false;
+        self.in_negation = original_in_negation;
+        result
+    }
+
+    /// Runs F with a deeper nesting level, and with `anchor` as the element
+    /// anchoring the relative selectors of a `:has()` selector.
+    #[inline]
+    pub fn nest_for_relative_selector<F, R>(&mut self, anchor: OpaqueElement, f: F) -> R
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        let original_anchor = self.relative_selector_anchor.take();
+        self.relative_selector_anchor = Some(anchor);
+        let result = self.nest(f);
+        self.relative_selector_anchor = original_anchor;
         result
     }
 
+    /// Returns the element anchoring the relative selector we're matching, if
+    /// any.
+    #[inline]
+    pub fn relative_selector_anchor(&self) -> Option<OpaqueElement> {
+        self.relative_selector_anchor.clone()
+    }
+
     #[inline]
     pub fn visited_handling(&self) -> VisitedHandlingMode {
         self.visited_handling
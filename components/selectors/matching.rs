@@ -40,13 +40,19 @@ bitflags! {
         /// The element has an empty selector, so when a child is appended we
         /// might need to restyle the parent completely.
         const HAS_EMPTY_SELECTOR = 1 << 3;
+
+        /// The element is the anchor of a `:has()` selector, so when its
+        /// descendants or later siblings change we might need to restyle it
+        /// and its siblings.
+        const ANCHORS_RELATIVE_SELECTOR = 1 << 4;
     }
 }
 
 impl ElementSelectorFlags {
     /// Returns the subset of flags that apply to the element.
     pub fn for_self(self) -> ElementSelectorFlags {
-        self & (ElementSelectorFlags::HAS_EMPTY_SELECTOR)
+        self & (ElementSelectorFlags::HAS_EMPTY_SELECTOR |
+            ElementSelectorFlags::ANCHORS_RELATIVE_SELECTOR)
     }
 
     /// Returns the subset of flags that apply to the parent.
@@ -378,6 +384,9 @@ fn matches_hover_and_active_quirk<Impl: SelectorImpl>(
         Component::Class(_) |
         Component::PseudoElement(_) |
         Component::Negation(_) |
+        Component::Is(_) |
+        Component::Where(_) |
+        Component::Has(_) |
         Component::FirstChild |
         Component::LastChild |
         Component::OnlyChild |
@@ -798,15 +807,109 @@ where
             matches_generic_nth_child(element, context, 0, 1, true, false, flags_setter) &&
                 matches_generic_nth_child(element, context, 0, 1, true, true, flags_setter)
         },
-        Component::Negation(ref negated) => context.shared.nest_for_negation(|context| {
-            let mut local_context = LocalMatchingContext {
-                matches_hover_and_active_quirk: MatchesHoverAndActiveQuirk::No,
-                shared: context,
-            };
-            !negated
-                .iter()
-                .all(|ss| matches_simple_selector(ss, element, &mut local_context, flags_setter))
+        Component::Negation(ref list) => context.shared.nest_for_negation(|context| {
+            !list.iter().any(|selector| {
+                matches_complex_selector(selector.iter(), element, context, flags_setter)
+            })
+        }),
+        Component::Is(ref list) | Component::Where(ref list) => context.shared.nest(|context| {
+            list.iter().any(|selector| {
+                matches_complex_selector(selector.iter(), element, context, flags_setter)
+            })
         }),
+        Component::Has(ref list) => {
+            flags_setter(element, ElementSelectorFlags::ANCHORS_RELATIVE_SELECTOR);
+            context
+                .shared
+                .nest_for_relative_selector(element.opaque(), |context| {
+                    list.iter().any(|selector| {
+                        matches_relative_selector(selector, element, context, flags_setter)
+                    })
+                })
+        },
+        Component::RelativeSelectorAnchor => {
+            context.shared.relative_selector_anchor() == Some(element.opaque())
+        },
+    }
+}
+
+/// Determines whether the relative selector `selector` matches, anchored at
+/// `anchor`.
+///
+/// This tries to match the selector against all the elements that could be
+/// its subject, which are the children or the later siblings of the anchor,
+/// depending on the leading combinator, plus their descendants if the
+/// selector can reach them.
+fn matches_relative_selector<E, F>(
+    selector: &Selector<E::Impl>,
+    anchor: &E,
+    context: &mut MatchingContext<E::Impl>,
+    flags_setter: &mut F,
+) -> bool
+where
+    E: Element,
+    F: FnMut(&E, ElementSelectorFlags),
+{
+    // Relative selectors look like `<anchor> <combinator> <complex selector>`,
+    // see parse_relative_selector.
+    let leading_combinator = selector.combinator_at_parse_order(1);
+    let reaches_descendants = leading_combinator == Combinator::Descendant ||
+        selector.iter_raw_parse_order_from(2).any(|component| match *component {
+            Component::Combinator(Combinator::Child) |
+            Component::Combinator(Combinator::Descendant) => true,
+            _ => false,
+        });
+
+    let mut candidate = match leading_combinator {
+        Combinator::Child | Combinator::Descendant => anchor.first_child_element(),
+        Combinator::NextSibling | Combinator::LaterSibling => anchor.next_sibling_element(),
+        Combinator::SlotAssignment | Combinator::PseudoElement => {
+            unreachable!("Someone messed up relative selector parsing")
+        },
+    };
+
+    while let Some(root) = candidate {
+        let mut current = Some(root.clone());
+        while let Some(element) = current {
+            if matches_complex_selector(selector.iter(), &element, context, flags_setter) {
+                return true;
+            }
+            current = if reaches_descendants {
+                next_element_in_subtree(&element, &root)
+            } else {
+                None
+            };
+        }
+
+        if leading_combinator == Combinator::NextSibling && !reaches_descendants {
+            return false;
+        }
+        candidate = root.next_sibling_element();
+    }
+
+    false
+}
+
+/// Returns the next element after `element` in a pre-order traversal of the
+/// subtree rooted at `root`.
+fn next_element_in_subtree<E>(element: &E, root: &E) -> Option<E>
+where
+    E: Element,
+{
+    if let Some(child) = element.first_child_element() {
+        return Some(child);
+    }
+
+    let root = root.opaque();
+    let mut current = element.clone();
+    loop {
+        if current.opaque() == root {
+            return None;
+        }
+        if let Some(sibling) = current.next_sibling_element() {
+            return Some(sibling);
+        }
+        current = current.parent_element()?;
     }
 }
 
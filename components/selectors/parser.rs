@@ -19,7 +19,7 @@ use std::borrow::{Borrow, Cow};
 use std::fmt::{self, Debug, Display, Write};
 use std::iter::Rev;
 use std::slice;
-pub use visitor::{SelectorVisitor, Visit};
+pub use visitor::{SelectorListKind, SelectorVisitor, Visit};
 
 /// A trait that represents a pseudo-element.
 pub trait PseudoElement: Sized + ToCss {
@@ -55,6 +55,21 @@ fn to_ascii_lowercase(s: &str) -> Cow<str> {
     }
 }
 
+bitflags! {
+    /// Flags that indicate at which point of parsing a selector are we.
+    struct SelectorParsingState: u8 {
+        /// Whether we should avoid adding default namespaces to compound
+        /// selectors that don't have a type selector, like inside `:is()`,
+        /// `:where()`, `:not()` and `:has()`.
+        const SKIP_DEFAULT_NAMESPACE = 1 << 0;
+        /// Whether pseudo-elements are disallowed, like in nested selectors.
+        const DISALLOW_PSEUDOS = 1 << 1;
+        /// Whether `:has()` is disallowed, since relative selectors can't be
+        /// nested.
+        const DISALLOW_RELATIVE_SELECTOR = 1 << 2;
+    }
+}
+
 pub type SelectorParseError<'i> = ParseError<'i, SelectorParseErrorKind<'i>>;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -63,8 +78,8 @@ pub enum SelectorParseErrorKind<'i> {
     NoQualifiedNameInAttributeSelector(Token<'i>),
     EmptySelector,
     DanglingCombinator,
-    NonSimpleSelectorInNegation,
     NonCompoundSelector,
+    PseudoElementInNestedSelector,
     UnexpectedTokenInAttributeSelector(Token<'i>),
     PseudoElementExpectedColon(Token<'i>),
     PseudoElementExpectedIdent(Token<'i>),
@@ -227,9 +242,9 @@ impl<Impl: SelectorImpl> SelectorList<Impl> {
     {
         let mut values = SmallVec::new();
         loop {
-            values
-                .push(input
-                    .parse_until_before(Delimiter::Comma, |input| parse_selector(parser, input))?);
+            values.push(input.parse_until_before(Delimiter::Comma, |input| {
+                parse_selector(parser, input, SelectorParsingState::empty())
+            })?);
             match input.next() {
                 Err(_) => return Ok(SelectorList(values)),
                 Ok(&Token::Comma) => continue,
@@ -406,11 +421,24 @@ where
                     return false;
                 }
             },
-            Negation(ref negated) => {
-                for component in negated.iter() {
-                    if !component.visit(visitor) {
-                        return false;
-                    }
+            Negation(ref list) => {
+                if !visitor.visit_selector_list(SelectorListKind::Negation, list) {
+                    return false;
+                }
+            },
+            Is(ref list) => {
+                if !visitor.visit_selector_list(SelectorListKind::Is, list) {
+                    return false;
+                }
+            },
+            Where(ref list) => {
+                if !visitor.visit_selector_list(SelectorListKind::Where, list) {
+                    return false;
+                }
+            },
+            Has(ref list) => {
+                if !visitor.visit_selector_list(SelectorListKind::Has, list) {
+                    return false;
                 }
             },
 
@@ -828,15 +856,29 @@ pub enum Component<Impl: SelectorImpl> {
 
     /// Pseudo-classes
     ///
-    /// CSS3 Negation only takes a simple simple selector, but we still need to
-    /// treat it as a compound selector because it might be a type selector
-    /// which we represent as a namespace and a localname.
+    /// The `:not()` pseudo-class, which takes a selector list:
+    ///
+    /// https://drafts.csswg.org/selectors-4/#negation
+    Negation(Box<[Selector<Impl>]>),
+    /// The `:is()` pseudo-class (also known as `:matches()`):
+    ///
+    /// https://drafts.csswg.org/selectors-4/#matches
+    Is(Box<[Selector<Impl>]>),
+    /// The `:where()` pseudo-class, which behaves like `:is()` but doesn't
+    /// contribute to the specificity of the selector:
+    ///
+    /// https://drafts.csswg.org/selectors-4/#zero-matches
+    Where(Box<[Selector<Impl>]>),
+    /// The `:has()` pseudo-class:
     ///
-    /// Note: if/when we upgrade this to CSS4, which supports combinators, we
-    /// need to think about how this should interact with
-    /// visit_complex_selector, and what the consumers of those APIs should do
-    /// about the presence of combinators in negation.
-    Negation(Box<[Component<Impl>]>),
+    /// https://drafts.csswg.org/selectors-4/#relational
+    ///
+    /// Each relative selector is stored as a complex selector whose leftmost
+    /// compound selector is a `RelativeSelectorAnchor`.
+    Has(Box<[Selector<Impl>]>),
+    /// The element a relative selector inside `:has()` is anchored at. This
+    /// can't be written by authors.
+    RelativeSelectorAnchor,
     FirstChild,
     LastChild,
     OnlyChild,
@@ -964,18 +1006,26 @@ impl<Impl: SelectorImpl> ToCss for SelectorList<Impl> {
     where
         W: fmt::Write,
     {
-        let mut iter = self.0.iter();
-        let first = iter.next()
-            .expect("Empty SelectorList, should contain at least one selector");
-        first.to_css(dest)?;
-        for selector in iter {
-            dest.write_str(", ")?;
-            selector.to_css(dest)?;
-        }
-        Ok(())
+        serialize_selector_list(self.0.iter(), dest)
     }
 }
 
+fn serialize_selector_list<'a, Impl, I, W>(mut iter: I, dest: &mut W) -> fmt::Result
+where
+    Impl: SelectorImpl,
+    I: Iterator<Item = &'a Selector<Impl>>,
+    W: fmt::Write,
+{
+    let first = iter.next()
+        .expect("Empty SelectorList, should contain at least one selector");
+    first.to_css(dest)?;
+    for selector in iter {
+        dest.write_str(", ")?;
+        selector.to_css(dest)?;
+    }
+    Ok(())
+}
+
 impl<Impl: SelectorImpl> ToCss for Selector<Impl> {
     fn to_css<W>(&self, dest: &mut W) -> fmt::Result
     where
@@ -1196,13 +1246,31 @@ impl<Impl: SelectorImpl> ToCss for Component<Impl> {
             AttributeOther(ref attr_selector) => attr_selector.to_css(dest),
 
             // Pseudo-classes
-            Negation(ref arg) => {
-                dest.write_str(":not(")?;
-                for component in arg.iter() {
-                    component.to_css(dest)?;
+            Negation(ref list) | Is(ref list) | Where(ref list) => {
+                match *self {
+                    Negation(..) => dest.write_str(":not(")?,
+                    Is(..) => dest.write_str(":is(")?,
+                    Where(..) => dest.write_str(":where(")?,
+                    _ => unreachable!(),
                 }
-                dest.write_str(")")
+                serialize_selector_list(list.iter(), dest)?;
+                dest.write_char(')')
             },
+            Has(ref list) => {
+                dest.write_str(":has(")?;
+                let mut first = true;
+                for selector in list.iter() {
+                    if !first {
+                        dest.write_str(", ")?;
+                    }
+                    first = false;
+                    // The anchor serializes as the empty string, so we need to
+                    // get rid of the whitespace around the leading combinator.
+                    dest.write_str(selector.to_css_string().trim_left())?;
+                }
+                dest.write_char(')')
+            },
+            RelativeSelectorAnchor => Ok(()),
 
             FirstChild => dest.write_str(":first-child"),
             LastChild => dest.write_str(":last-child"),
@@ -1309,18 +1377,33 @@ fn display_to_css_identifier<T: Display, W: fmt::Write>(x: &T, dest: &mut W) ->
 fn parse_selector<'i, 't, P, Impl>(
     parser: &P,
     input: &mut CssParser<'i, 't>,
+    state: SelectorParsingState,
 ) -> Result<Selector<Impl>, ParseError<'i, P::Error>>
 where
     P: Parser<'i, Impl = Impl>,
     Impl: SelectorImpl,
 {
     let mut builder = SelectorBuilder::default();
+    parse_complex_selector(parser, input, state, &mut builder)
+}
 
+/// Parses the rest of a complex selector into `builder`, which may already
+/// contain the leftmost part of it, like the anchor of a relative selector.
+fn parse_complex_selector<'i, 't, P, Impl>(
+    parser: &P,
+    input: &mut CssParser<'i, 't>,
+    state: SelectorParsingState,
+    builder: &mut SelectorBuilder<Impl>,
+) -> Result<Selector<Impl>, ParseError<'i, P::Error>>
+where
+    P: Parser<'i, Impl = Impl>,
+    Impl: SelectorImpl,
+{
     let mut has_pseudo_element;
     let mut slotted;
     'outer_loop: loop {
         // Parse a sequence of simple selectors.
-        match parse_compound_selector(parser, input, &mut builder)? {
+        match parse_compound_selector(parser, input, state, builder)? {
             Some((has_pseudo, slot)) => {
                 has_pseudo_element = has_pseudo;
                 slotted = slot;
@@ -1384,7 +1467,7 @@ impl<Impl: SelectorImpl> Selector<Impl> {
     where
         P: Parser<'i, Impl = Impl>,
     {
-        let selector = parse_selector(parser, input)?;
+        let selector = parse_selector(parser, input, SelectorParsingState::empty())?;
         if selector.has_pseudo_element() {
             let e = SelectorParseErrorKind::PseudoElementInComplexSelector;
             return Err(input.new_custom_error(e));
@@ -1743,49 +1826,97 @@ fn parse_attribute_flags<'i, 't>(
     }
 }
 
-/// Level 3: Parse **one** simple_selector.  (Though we might insert a second
-/// implied "<defaultns>|*" type selector.)
+/// Parses the comma-separated selector list argument of `:is()`, `:where()` or
+/// `:not()`.
+///
+/// https://drafts.csswg.org/selectors-4/#matches
+fn parse_nested_selector_list<'i, 't, P, Impl>(
+    parser: &P,
+    input: &mut CssParser<'i, 't>,
+    state: SelectorParsingState,
+) -> Result<Box<[Selector<Impl>]>, ParseError<'i, P::Error>>
+where
+    P: Parser<'i, Impl = Impl>,
+    Impl: SelectorImpl,
+{
+    let state = state | SelectorParsingState::SKIP_DEFAULT_NAMESPACE |
+        SelectorParsingState::DISALLOW_PSEUDOS;
+    input
+        .parse_comma_separated(|input| parse_selector(parser, input, state))
+        .map(|selectors| selectors.into_boxed_slice())
+}
+
+/// Parses the argument of `:not()`, which is a selector list as of Selectors
+/// Level 4.
 fn parse_negation<'i, 't, P, Impl>(
     parser: &P,
     input: &mut CssParser<'i, 't>,
+    state: SelectorParsingState,
 ) -> Result<Component<Impl>, ParseError<'i, P::Error>>
 where
     P: Parser<'i, Impl = Impl>,
     Impl: SelectorImpl,
 {
-    // We use a sequence because a type selector may be represented as two Components.
-    let mut sequence = SmallVec::<[Component<Impl>; 2]>::new();
-
     input.skip_whitespace();
+    if input.is_exhausted() {
+        return Err(input.new_custom_error(SelectorParseErrorKind::EmptyNegation));
+    }
 
-    // Get exactly one simple selector. The parse logic in the caller will verify
-    // that there are no trailing tokens after we're done.
-    let is_type_sel = match parse_type_selector(parser, input, &mut sequence) {
-        Ok(result) => result,
-        Err(ParseError {
-            kind: ParseErrorKind::Basic(BasicParseErrorKind::EndOfInput),
-            ..
-        }) => return Err(input.new_custom_error(SelectorParseErrorKind::EmptyNegation)),
-        Err(e) => return Err(e.into()),
+    Ok(Component::Negation(parse_nested_selector_list(parser, input, state)?))
+}
+
+/// Parses the comma-separated relative selector list argument of `:has()`.
+///
+/// https://drafts.csswg.org/selectors-4/#relational
+fn parse_relative_selector_list<'i, 't, P, Impl>(
+    parser: &P,
+    input: &mut CssParser<'i, 't>,
+    state: SelectorParsingState,
+) -> Result<Box<[Selector<Impl>]>, ParseError<'i, P::Error>>
+where
+    P: Parser<'i, Impl = Impl>,
+    Impl: SelectorImpl,
+{
+    let state = state | SelectorParsingState::SKIP_DEFAULT_NAMESPACE |
+        SelectorParsingState::DISALLOW_PSEUDOS |
+        SelectorParsingState::DISALLOW_RELATIVE_SELECTOR;
+    input
+        .parse_comma_separated(|input| parse_relative_selector(parser, input, state))
+        .map(|selectors| selectors.into_boxed_slice())
+}
+
+/// Parses a relative selector, like `> .foo` or `+ .bar`.
+///
+/// We store it as a complex selector whose leftmost compound selector is a
+/// `Component::RelativeSelectorAnchor`, followed by the leading combinator of
+/// the relative selector (an implied descendant combinator if there's none).
+fn parse_relative_selector<'i, 't, P, Impl>(
+    parser: &P,
+    input: &mut CssParser<'i, 't>,
+    state: SelectorParsingState,
+) -> Result<Selector<Impl>, ParseError<'i, P::Error>>
+where
+    P: Parser<'i, Impl = Impl>,
+    Impl: SelectorImpl,
+{
+    let mut builder = SelectorBuilder::default();
+    builder.push_simple_selector(Component::RelativeSelectorAnchor);
+
+    input.skip_whitespace();
+    let start = input.state();
+    // FIXME: remove clone() when lifetimes are non-lexical
+    let combinator = match input.next().map(|t| t.clone()) {
+        Ok(Token::Delim('>')) => Combinator::Child,
+        Ok(Token::Delim('+')) => Combinator::NextSibling,
+        Ok(Token::Delim('~')) => Combinator::LaterSibling,
+        _ => {
+            input.reset(&start);
+            Combinator::Descendant
+        },
     };
-    if !is_type_sel {
-        match parse_one_simple_selector(parser, input, /* inside_negation = */ true)? {
-            Some(SimpleSelectorParseResult::SimpleSelector(s)) => {
-                sequence.push(s);
-            },
-            None => {
-                return Err(input.new_custom_error(SelectorParseErrorKind::EmptyNegation));
-            },
-            Some(SimpleSelectorParseResult::PseudoElement(_)) |
-            Some(SimpleSelectorParseResult::SlottedPseudo(_)) => {
-                let e = SelectorParseErrorKind::NonSimpleSelectorInNegation;
-                return Err(input.new_custom_error(e));
-            },
-        }
-    }
+    builder.push_combinator(combinator);
 
-    // Success.
-    Ok(Component::Negation(sequence.into_vec().into_boxed_slice()))
+    parse_complex_selector(parser, input, state, &mut builder)
 }
 
 /// simple_selector_sequence
@@ -1800,6 +1931,7 @@ where
 fn parse_compound_selector<'i, 't, P, Impl>(
     parser: &P,
     input: &mut CssParser<'i, 't>,
+    state: SelectorParsingState,
     builder: &mut SelectorBuilder<Impl>,
 ) -> Result<Option<(bool, bool)>, ParseError<'i, P::Error>>
 where
@@ -1814,8 +1946,10 @@ where
         if let Some(url) = parser.default_namespace() {
             // If there was no explicit type selector, but there is a
             // default namespace, there is an implicit "<defaultns>|*" type
-            // selector.
-            builder.push_simple_selector(Component::DefaultNamespace(url))
+            // selector, except in nested selectors, where it's ignored.
+            if !state.contains(SelectorParsingState::SKIP_DEFAULT_NAMESPACE) {
+                builder.push_simple_selector(Component::DefaultNamespace(url))
+            }
         }
     } else {
         empty = false;
@@ -1823,11 +1957,10 @@ where
 
     let mut pseudo = false;
     loop {
-        let parse_result =
-            match parse_one_simple_selector(parser, input, /* inside_negation = */ false)? {
-                None => break,
-                Some(result) => result,
-            };
+        let parse_result = match parse_one_simple_selector(parser, input, state)? {
+            None => break,
+            Some(result) => result,
+        };
 
         match parse_result {
             SimpleSelectorParseResult::SimpleSelector(s) => {
@@ -1911,7 +2044,7 @@ fn parse_functional_pseudo_class<'i, 't, P, Impl>(
     parser: &P,
     input: &mut CssParser<'i, 't>,
     name: CowRcStr<'i>,
-    inside_negation: bool,
+    state: SelectorParsingState,
 ) -> Result<Component<Impl>, ParseError<'i, P::Error>>
 where
     P: Parser<'i, Impl = Impl>,
@@ -1923,13 +2056,18 @@ where
         "nth-last-child" => return Ok(parse_nth_pseudo_class(input, Component::NthLastChild)?),
         "nth-last-of-type" => return Ok(parse_nth_pseudo_class(input, Component::NthLastOfType)?),
         "host" => return Ok(Component::Host(Some(parse_inner_compound_selector(parser, input)?))),
-        "not" => {
-            if inside_negation {
+        "not" => return parse_negation(parser, input, state),
+        "is" | "matches" => {
+            return Ok(Component::Is(parse_nested_selector_list(parser, input, state)?))
+        },
+        "where" => return Ok(Component::Where(parse_nested_selector_list(parser, input, state)?)),
+        "has" => {
+            if state.contains(SelectorParsingState::DISALLOW_RELATIVE_SELECTOR) {
                 return Err(input.new_custom_error(
-                    SelectorParseErrorKind::UnexpectedIdent("not".into())
+                    SelectorParseErrorKind::UnexpectedIdent("has".into())
                 ));
             }
-            return parse_negation(parser, input)
+            return Ok(Component::Has(parse_relative_selector_list(parser, input, state)?))
         },
         _ => {}
     }
@@ -1967,7 +2105,7 @@ pub fn is_css2_pseudo_element(name: &str) -> bool {
 fn parse_one_simple_selector<'i, 't, P, Impl>(
     parser: &P,
     input: &mut CssParser<'i, 't>,
-    inside_negation: bool,
+    state: SelectorParsingState,
 ) -> Result<Option<SimpleSelectorParseResult<Impl>>, ParseError<'i, P::Error>>
 where
     P: Parser<'i, Impl = Impl>,
@@ -2014,6 +2152,10 @@ where
             let is_pseudo_element =
                 !is_single_colon || P::pseudo_element_allows_single_colon(&name);
             if is_pseudo_element {
+                if state.contains(SelectorParsingState::DISALLOW_PSEUDOS) {
+                    let e = SelectorParseErrorKind::PseudoElementInNestedSelector;
+                    return Err(input.new_custom_error(e));
+                }
                 let parse_result = if is_functional {
                     if P::parse_slotted(parser) && name.eq_ignore_ascii_case("slotted") {
                         let selector = input.parse_nested_block(|input| {
@@ -2037,7 +2179,7 @@ where
             } else {
                 let pseudo_class = if is_functional {
                     input.parse_nested_block(|input| {
-                        parse_functional_pseudo_class(parser, input, name, inside_negation)
+                        parse_functional_pseudo_class(parser, input, name, state)
                     })?
                 } else {
                     parse_simple_pseudo_class(parser, location, name)?
@@ -2625,7 +2767,10 @@ pub mod tests {
                     vec![
                         Component::DefaultNamespace(MATHML.into()),
                         Component::Negation(
-                            vec![Component::Class(DummyAtom::from("cl"))].into_boxed_slice(),
+                            vec![Selector::from_vec(
+                                vec![Component::Class(DummyAtom::from("cl"))],
+                                specificity(0, 1, 0),
+                            )].into_boxed_slice(),
                         ),
                     ],
                     specificity(0, 1, 0),
@@ -2639,10 +2784,13 @@ pub mod tests {
                     vec![
                         Component::DefaultNamespace(MATHML.into()),
                         Component::Negation(
-                            vec![
-                                Component::DefaultNamespace(MATHML.into()),
-                                Component::ExplicitUniversalType,
-                            ].into_boxed_slice(),
+                            vec![Selector::from_vec(
+                                vec![
+                                    Component::DefaultNamespace(MATHML.into()),
+                                    Component::ExplicitUniversalType,
+                                ],
+                                specificity(0, 0, 0),
+                            )].into_boxed_slice(),
                         ),
                     ],
                     specificity(0, 0, 0),
@@ -2656,13 +2804,16 @@ pub mod tests {
                     vec![
                         Component::DefaultNamespace(MATHML.into()),
                         Component::Negation(
-                            vec![
-                                Component::DefaultNamespace(MATHML.into()),
-                                Component::LocalName(LocalName {
-                                    name: DummyAtom::from("e"),
-                                    lower_name: DummyAtom::from("e"),
-                                }),
-                            ].into_boxed_slice(),
+                            vec![Selector::from_vec(
+                                vec![
+                                    Component::DefaultNamespace(MATHML.into()),
+                                    Component::LocalName(LocalName {
+                                        name: DummyAtom::from("e"),
+                                        lower_name: DummyAtom::from("e"),
+                                    }),
+                                ],
+                                specificity(0, 0, 1),
+                            )].into_boxed_slice(),
                         ),
                     ],
                     specificity(0, 0, 1),
@@ -2761,8 +2912,11 @@ pub mod tests {
             ]))
         );
         parser.default_ns = None;
-        assert!(parse(":not(#provel.old)").is_err());
-        assert!(parse(":not(#provel > old)").is_err());
+        assert!(parse(":not(#provel.old)").is_ok());
+        assert!(parse(":not(#provel > old)").is_ok());
+        assert!(parse(":not(#provel, .old)").is_ok());
+        assert!(parse(":not()").is_err());
+        assert!(parse(":not(::before)").is_err());
         assert!(parse("table[rules]:not([rules=\"none\"]):not([rules=\"\"])").is_ok());
         assert_eq!(
             parse(":not(#provel)"),
@@ -2770,7 +2924,10 @@ pub mod tests {
                 Selector::from_vec(
                     vec![
                         Component::Negation(
-                            vec![Component::ID(DummyAtom::from("provel"))].into_boxed_slice(),
+                            vec![Selector::from_vec(
+                                vec![Component::ID(DummyAtom::from("provel"))],
+                                specificity(1, 0, 0),
+                            )].into_boxed_slice(),
                         ),
                     ],
                     specificity(1, 0, 0),
@@ -2783,13 +2940,16 @@ pub mod tests {
                 Selector::from_vec(
                     vec![
                         Component::Negation(
-                            vec![
-                                Component::Namespace(DummyAtom("svg".into()), SVG.into()),
-                                Component::LocalName(LocalName {
-                                    name: DummyAtom::from("circle"),
-                                    lower_name: DummyAtom::from("circle"),
-                                }),
-                            ].into_boxed_slice(),
+                            vec![Selector::from_vec(
+                                vec![
+                                    Component::Namespace(DummyAtom("svg".into()), SVG.into()),
+                                    Component::LocalName(LocalName {
+                                        name: DummyAtom::from("circle"),
+                                        lower_name: DummyAtom::from("circle"),
+                                    }),
+                                ],
+                                specificity(0, 0, 1),
+                            )].into_boxed_slice(),
                         ),
                     ],
                     specificity(0, 0, 1),
@@ -2803,7 +2963,10 @@ pub mod tests {
                 Selector::from_vec(
                     vec![
                         Component::Negation(
-                            vec![Component::ExplicitUniversalType].into_boxed_slice(),
+                            vec![Selector::from_vec(
+                                vec![Component::ExplicitUniversalType],
+                                specificity(0, 0, 0),
+                            )].into_boxed_slice(),
                         ),
                     ],
                     specificity(0, 0, 0),
@@ -2816,10 +2979,13 @@ pub mod tests {
                 Selector::from_vec(
                     vec![
                         Component::Negation(
-                            vec![
-                                Component::ExplicitNoNamespace,
-                                Component::ExplicitUniversalType,
-                            ].into_boxed_slice(),
+                            vec![Selector::from_vec(
+                                vec![
+                                    Component::ExplicitNoNamespace,
+                                    Component::ExplicitUniversalType,
+                                ],
+                                specificity(0, 0, 0),
+                            )].into_boxed_slice(),
                         ),
                     ],
                     specificity(0, 0, 0),
@@ -2834,7 +3000,10 @@ pub mod tests {
                 Selector::from_vec(
                     vec![
                         Component::Negation(
-                            vec![Component::ExplicitUniversalType].into_boxed_slice(),
+                            vec![Selector::from_vec(
+                                vec![Component::ExplicitUniversalType],
+                                specificity(0, 0, 0),
+                            )].into_boxed_slice(),
                         ),
                     ],
                     specificity(0, 0, 0),
@@ -2847,10 +3016,13 @@ pub mod tests {
                 Selector::from_vec(
                     vec![
                         Component::Negation(
-                            vec![
-                                Component::Namespace(DummyAtom("svg".into()), SVG.into()),
-                                Component::ExplicitUniversalType,
-                            ].into_boxed_slice(),
+                            vec![Selector::from_vec(
+                                vec![
+                                    Component::Namespace(DummyAtom("svg".into()), SVG.into()),
+                                    Component::ExplicitUniversalType,
+                                ],
+                                specificity(0, 0, 0),
+                            )].into_boxed_slice(),
                         ),
                     ],
                     specificity(0, 0, 0),
@@ -2858,6 +3030,27 @@ pub mod tests {
             ]))
         );
 
+        assert!(parse(":is(.a, #b > c)").is_ok());
+        assert!(parse_expected(":matches(.a)", Some(":is(.a)")).is_ok());
+        assert!(parse(":where(.a, #b)").is_ok());
+        assert!(parse(":is()").is_err());
+        assert!(parse(":is(::before)").is_err());
+        assert!(parse(":where(.a::before)").is_err());
+        assert!(parse(":is(:not(.a, .b), :where(.c))").is_ok());
+        assert_eq!(parse(":is(.a, #b)").unwrap().0[0].specificity(), specificity(1, 0, 0));
+        assert_eq!(parse(":where(.a, #b)").unwrap().0[0].specificity(), specificity(0, 0, 0));
+        assert_eq!(parse("div:where(.a) .b").unwrap().0[0].specificity(), specificity(0, 1, 1));
+        assert_eq!(parse(":not(.a, #b .c)").unwrap().0[0].specificity(), specificity(1, 1, 0));
+
+        assert!(parse(":has(.a)").is_ok());
+        assert!(parse(":has(> .a + .b)").is_ok());
+        assert!(parse(":has(+ .a, ~ .b)").is_ok());
+        assert!(parse(":has()").is_err());
+        assert!(parse(":has(::before)").is_err());
+        assert!(parse(":has(:has(.a))").is_err());
+        assert!(parse(":has(:is(:has(.a)))").is_err());
+        assert_eq!(parse(":has(.a, > #b)").unwrap().0[0].specificity(), specificity(1, 0, 0));
+
         assert!(parse("::slotted()").is_err());
         assert!(parse("::slotted(div)").is_ok());
         assert!(parse("::slotted(div).foo").is_err());
@@ -7,13 +7,26 @@
 #![deny(missing_docs)]
 
 use attr::NamespaceConstraint;
-use parser::{Combinator, Component, SelectorImpl};
+use parser::{Combinator, Component, Selector, SelectorImpl};
+
+/// The kind of nested selector list being visited.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SelectorListKind {
+    /// The argument of `:not()`.
+    Negation,
+    /// The argument of `:is()`.
+    Is,
+    /// The argument of `:where()`.
+    Where,
+    /// The relative selectors in the argument of `:has()`.
+    Has,
+}
 
 /// A trait to visit selector properties.
 ///
 /// All the `visit_foo` methods return a boolean indicating whether the
 /// traversal should continue or not.
-pub trait SelectorVisitor {
+pub trait SelectorVisitor: Sized {
     /// The selector implementation this visitor wants to visit.
     type Impl: SelectorImpl;
 
@@ -41,6 +54,27 @@ pub trait SelectorVisitor {
     fn visit_complex_selector(&mut self, _combinator_to_right: Option<Combinator>) -> bool {
         true
     }
+
+    /// Visits a nested selector list, like the argument of `:is()`, `:where()`,
+    /// `:not()` or `:has()`.
+    ///
+    /// By default this visits all the selectors in the list, which means that
+    /// `visit_complex_selector` will be called for them too.
+    fn visit_selector_list(
+        &mut self,
+        _list_kind: SelectorListKind,
+        list: &[Selector<Self::Impl>],
+    ) -> bool
+    where
+        <Self::Impl as SelectorImpl>::NonTSPseudoClass: Visit<Impl = Self::Impl>,
+    {
+        for selector in list {
+            if !selector.visit(self) {
+                return false;
+            }
+        }
+        true
+    }
 }
 
 /// Enables traversing selector components stored in various types
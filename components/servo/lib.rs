@@ -151,9 +151,11 @@ impl<Window, Back> Servo<Window, Back> where Window: WindowMethods + 'static, Ba
         let debugger_chan = opts.debugger_port.map(|port| {
             debugger::start_server(port)
         });
-        let devtools_chan = opts.devtools_port.map(|port| {
-            devtools::start_server(port)
-        });
+        let devtools_chan = if opts.devtools_port.is_some() || opts.har_output.is_some() {
+            Some(devtools::start_server(opts.devtools_port, opts.har_output.clone()))
+        } else {
+            None
+        };
 
         let coordinates = window.get_coordinates();
 
@@ -340,6 +342,13 @@ impl<Window, Back> Servo<Window, Back> where Window: WindowMethods + 'static, Ba
                 }
             }
 
+            WindowEvent::SetRequestInterception(interception) => {
+                let msg = ConstellationMsg::SetRequestInterception(interception);
+                if let Err(e) = self.constellation_chan.send(msg) {
+                    warn!("Sending SetRequestInterception message to constellation failed ({}).", e);
+                }
+            }
+
             WindowEvent::SelectBrowser(ctx) => {
                 let msg = ConstellationMsg::SelectBrowser(ctx);
                 if let Err(e) = self.constellation_chan.send(msg) {
@@ -9,12 +9,14 @@ use bezier::Bezier;
 use context::SharedStyleContext;
 use dom::{OpaqueNode, TElement};
 use font_metrics::FontMetricsProvider;
-use properties::{self, CascadeMode, ComputedValues, LonghandId};
+use properties::{self, CascadeMode, ComputedValues, LonghandId, LonghandIdSet};
+use properties::{PropertyDeclarationBlock, PropertyDeclarationId};
 use properties::animated_properties::AnimatedProperty;
 use properties::longhands::animation_direction::computed_value::single_value::T as AnimationDirection;
 use properties::longhands::animation_play_state::computed_value::single_value::T as AnimationPlayState;
 use rule_tree::CascadeLevel;
 use servo_arc::Arc;
+use shared_lock::{Locked, SharedRwLockReadGuard};
 use std::fmt;
 use std::sync::mpsc::Sender;
 use stylesheets::keyframes_rule::{KeyframesAnimation, KeyframesStep, KeyframesStepValue};
@@ -23,20 +25,31 @@ use values::computed::Time;
 use values::computed::box_::TransitionProperty;
 use values::computed::transform::TimingFunction;
 use values::generics::box_::AnimationIterationCount;
-use values::generics::transform::{StepPosition, TimingFunction as GenericTimingFunction};
+use values::generics::transform::{StepPosition, TimingFunction as GenericTimingFunction, TimingKeyword};
 
 /// This structure represents a keyframes animation current iteration state.
 ///
-/// If the iteration count is infinite, there's no other state, otherwise we
-/// have to keep track the current iteration and the max iteration count.
+/// If the iteration count is infinite, we only keep track of the current
+/// iteration, otherwise we have to keep track the current iteration and the max
+/// iteration count.
 #[derive(Clone, Debug)]
 pub enum KeyframesIterationState {
-    /// Infinite iterations, so no need to track a state.
-    Infinite,
+    /// Infinite iterations, with the current iteration.
+    Infinite(f32),
     /// Current and max iterations.
     Finite(f32, f32),
 }
 
+impl KeyframesIterationState {
+    /// The number of iterations this animation has completed.
+    pub fn current_iteration(&self) -> f32 {
+        match *self {
+            KeyframesIterationState::Infinite(current) |
+            KeyframesIterationState::Finite(current, _) => current,
+        }
+    }
+}
+
 /// This structure represents wether an animation is actually running.
 ///
 /// An animation can be running, or paused at a given time.
@@ -93,13 +106,16 @@ impl KeyframesAnimationState {
             KeyframesRunningState::Running => {},
         }
 
-        if let KeyframesIterationState::Finite(ref mut current, ref max) = self.iteration_state {
-            *current += 1.0;
-            // NB: This prevent us from updating the direction, which might be
-            // needed for the correct handling of animation-fill-mode.
-            if *current >= *max {
-                return false;
-            }
+        match self.iteration_state {
+            KeyframesIterationState::Infinite(ref mut current) => *current += 1.0,
+            KeyframesIterationState::Finite(ref mut current, ref max) => {
+                *current += 1.0;
+                // NB: This prevent us from updating the direction, which might be
+                // needed for the correct handling of animation-fill-mode.
+                if *current >= *max {
+                    return false;
+                }
+            },
         }
 
         // Update the next iteration direction if applicable.
@@ -167,6 +183,10 @@ impl KeyframesAnimationState {
             (
                 &mut KeyframesIterationState::Finite(ref mut iters, _),
                 KeyframesIterationState::Finite(old_iters, _),
+            ) |
+            (
+                &mut KeyframesIterationState::Infinite(ref mut iters),
+                KeyframesIterationState::Infinite(old_iters),
             ) => *iters = old_iters,
             _ => {},
         }
@@ -274,6 +294,45 @@ pub struct AnimationFrame {
     pub duration: f64,
 }
 
+/// A keyframe of an animation created from script through the Web Animations
+/// API.
+#[derive(Clone, Debug)]
+pub struct ScriptKeyframe {
+    /// The computed offset of this keyframe, usually between 0 and 1.
+    pub offset: f64,
+    /// The timing function used from this keyframe to the next one.
+    pub timing_function: TimingFunction,
+    /// The declarations of this keyframe.
+    pub block: Arc<Locked<PropertyDeclarationBlock>>,
+}
+
+/// An animation created from script, sampled by the Web Animations timing
+/// model at the current time of its timeline.
+///
+/// Script resolves all the timing of the animation, so layout only needs to
+/// interpolate between its keyframes.
+#[derive(Clone, Debug)]
+pub struct ScriptAnimationSample {
+    /// The keyframes of the animation, sorted by offset.
+    pub keyframes: Vec<ScriptKeyframe>,
+    /// The iteration progress of the animation, once its timing function has
+    /// been applied.
+    pub iteration_progress: f64,
+}
+
+impl ScriptAnimationSample {
+    /// The longhands animated by this animation.
+    pub fn animated_properties(&self, guard: &SharedRwLockReadGuard) -> LonghandIdSet {
+        let mut properties = LonghandIdSet::new();
+        for keyframe in &self.keyframes {
+            for property in animated_longhands(&keyframe.block.read_with(guard)).iter() {
+                properties.insert(property);
+            }
+        }
+        properties
+    }
+}
+
 /// Represents an animation for a given property.
 #[derive(Clone, Debug)]
 pub struct PropertyAnimation {
@@ -360,38 +419,7 @@ impl PropertyAnimation {
     /// Update the given animation at a given point of progress.
     pub fn update(&self, style: &mut ComputedValues, time: f64) {
         let epsilon = 1. / (200. * (self.duration.seconds() as f64));
-        let progress = match self.timing_function {
-            GenericTimingFunction::CubicBezier { x1, y1, x2, y2 } => {
-                Bezier::new(x1, y1, x2, y2).solve(time, epsilon)
-            },
-            GenericTimingFunction::Steps(steps, StepPosition::Start) => {
-                (time * (steps as f64)).ceil() / (steps as f64)
-            },
-            GenericTimingFunction::Steps(steps, StepPosition::End) => {
-                (time * (steps as f64)).floor() / (steps as f64)
-            },
-            GenericTimingFunction::Frames(frames) => {
-                // https://drafts.csswg.org/css-timing/#frames-timing-functions
-                let mut out = (time * (frames as f64)).floor() / ((frames - 1) as f64);
-                if out > 1.0 {
-                    // FIXME: Basically, during the animation sampling process, the input progress
-                    // should be in the range of [0, 1]. However, |time| is not accurate enough
-                    // here, which means |time| could be larger than 1.0 in the last animation
-                    // frame. (It should be equal to 1.0 exactly.) This makes the output of frames
-                    // timing function jumps to the next frame/level.
-                    // However, this solution is still not correct because |time| is possible
-                    // outside the range of [0, 1] after introducing Web Animations. We should fix
-                    // this problem when implementing web animations.
-                    out = 1.0;
-                }
-                out
-            },
-            GenericTimingFunction::Keyword(keyword) => {
-                let (x1, x2, y1, y2) = keyword.to_bezier();
-                Bezier::new(x1, x2, y1, y2).solve(time, epsilon)
-            },
-        };
-
+        let progress = apply_timing_function(&self.timing_function, time, epsilon);
         self.property.update(style, progress);
     }
 
@@ -407,6 +435,42 @@ impl PropertyAnimation {
     }
 }
 
+/// Returns the output progress of `timing_function` for the input progress
+/// `time`, solving cubic Bézier curves with the given precision.
+pub fn apply_timing_function(timing_function: &TimingFunction, time: f64, epsilon: f64) -> f64 {
+    match *timing_function {
+        GenericTimingFunction::CubicBezier { x1, y1, x2, y2 } => {
+            Bezier::new(x1, y1, x2, y2).solve(time, epsilon)
+        },
+        GenericTimingFunction::Steps(steps, StepPosition::Start) => {
+            (time * (steps as f64)).ceil() / (steps as f64)
+        },
+        GenericTimingFunction::Steps(steps, StepPosition::End) => {
+            (time * (steps as f64)).floor() / (steps as f64)
+        },
+        GenericTimingFunction::Frames(frames) => {
+            // https://drafts.csswg.org/css-timing/#frames-timing-functions
+            let mut out = (time * (frames as f64)).floor() / ((frames - 1) as f64);
+            if out > 1.0 {
+                // FIXME: Basically, during the animation sampling process, the input progress
+                // should be in the range of [0, 1]. However, |time| is not accurate enough
+                // here, which means |time| could be larger than 1.0 in the last animation
+                // frame. (It should be equal to 1.0 exactly.) This makes the output of frames
+                // timing function jumps to the next frame/level.
+                // However, this solution is still not correct because |time| is possible
+                // outside the range of [0, 1] after introducing Web Animations. We should fix
+                // this problem when implementing web animations.
+                out = 1.0;
+            }
+            out
+        },
+        GenericTimingFunction::Keyword(keyword) => {
+            let (x1, x2, y1, y2) = keyword.to_bezier();
+            Bezier::new(x1, x2, y1, y2).solve(time, epsilon)
+        },
+    }
+}
+
 /// Inserts transitions into the queue of running animations as applicable for
 /// the given style difference. This is called from the layout worker threads.
 /// Returns true if any animations were kicked off and false otherwise.
@@ -479,43 +543,57 @@ where
         KeyframesStepValue::ComputedValues => style_from_cascade.clone(),
         KeyframesStepValue::Declarations {
             block: ref declarations,
-        } => {
-            let guard = declarations.read_with(context.guards.author);
-
-            let iter = || {
-                // It's possible to have !important properties in keyframes
-                // so we have to filter them out.
-                // See the spec issue https://github.com/w3c/csswg-drafts/issues/1824
-                // Also we filter our non-animatable properties.
-                guard
-                    .normal_declaration_iter()
-                    .filter(|declaration| declaration.is_animatable())
-                    .map(|decl| (decl, CascadeLevel::Animations))
-            };
-
-            // This currently ignores visited styles, which seems acceptable,
-            // as existing browsers don't appear to animate visited styles.
-            let computed = properties::apply_declarations::<E, _, _>(
-                context.stylist.device(),
-                /* pseudo = */ None,
-                previous_style.rules(),
-                &context.guards,
-                iter,
-                Some(previous_style),
-                Some(previous_style),
-                Some(previous_style),
-                font_metrics_provider,
-                CascadeMode::Unvisited { visited_rules: None },
-                context.quirks_mode(),
-                /* rule_cache = */ None,
-                &mut Default::default(),
-                /* element = */ None,
-            );
-            computed
-        },
+        } => compute_style_for_declarations::<E>(
+            context,
+            declarations,
+            previous_style,
+            font_metrics_provider,
+        ),
     }
 }
 
+fn compute_style_for_declarations<E>(
+    context: &SharedStyleContext,
+    declarations: &Arc<Locked<PropertyDeclarationBlock>>,
+    previous_style: &ComputedValues,
+    font_metrics_provider: &FontMetricsProvider,
+) -> Arc<ComputedValues>
+where
+    E: TElement,
+{
+    let guard = declarations.read_with(context.guards.author);
+
+    let iter = || {
+        // It's possible to have !important properties in keyframes
+        // so we have to filter them out.
+        // See the spec issue https://github.com/w3c/csswg-drafts/issues/1824
+        // Also we filter our non-animatable properties.
+        guard
+            .normal_declaration_iter()
+            .filter(|declaration| declaration.is_animatable())
+            .map(|decl| (decl, CascadeLevel::Animations))
+    };
+
+    // This currently ignores visited styles, which seems acceptable,
+    // as existing browsers don't appear to animate visited styles.
+    properties::apply_declarations::<E, _, _>(
+        context.stylist.device(),
+        /* pseudo = */ None,
+        previous_style.rules(),
+        &context.guards,
+        iter,
+        Some(previous_style),
+        Some(previous_style),
+        Some(previous_style),
+        font_metrics_provider,
+        CascadeMode::Unvisited { visited_rules: None },
+        context.quirks_mode(),
+        /* rule_cache = */ None,
+        &mut Default::default(),
+        /* element = */ None,
+    )
+}
+
 /// Triggers animations for a given node looking at the animation property
 /// values.
 pub fn maybe_start_animations<E>(
@@ -560,7 +638,7 @@ where
             let animation_start = now + delay as f64;
             let duration = box_style.animation_duration_mod(i).seconds();
             let iteration_state = match box_style.animation_iteration_count_mod(i) {
-                AnimationIterationCount::Infinite => KeyframesIterationState::Infinite,
+                AnimationIterationCount::Infinite => KeyframesIterationState::Infinite(0.0),
                 AnimationIterationCount::Number(n) => KeyframesIterationState::Finite(0.0, n),
             };
 
@@ -868,3 +946,116 @@ pub fn complete_expired_transitions(
 
     had_animations_to_expire
 }
+
+/// The animatable longhands declared in a keyframe.
+fn animated_longhands(block: &PropertyDeclarationBlock) -> LonghandIdSet {
+    let mut properties = LonghandIdSet::new();
+    for declaration in block.normal_declaration_iter() {
+        if !declaration.is_animatable() {
+            continue;
+        }
+        if let PropertyDeclarationId::Longhand(longhand) = declaration.id() {
+            properties.insert(longhand);
+        }
+    }
+    properties
+}
+
+/// Updates a style with the values of an animation created from script.
+///
+/// `base_style` is the style of the element without any animation applied,
+/// which is used for the keyframes that don't specify a value for an animated
+/// property.
+///
+/// <https://drafts.csswg.org/web-animations/#the-effect-value-of-a-keyframe-animation-effect>
+pub fn update_style_for_script_animation<E>(
+    context: &SharedStyleContext,
+    sample: &ScriptAnimationSample,
+    base_style: &Arc<ComputedValues>,
+    style: &mut Arc<ComputedValues>,
+    font_metrics_provider: &FontMetricsProvider,
+) where
+    E: TElement,
+{
+    let keyframes: Vec<_> = sample
+        .keyframes
+        .iter()
+        .map(|keyframe| {
+            let properties = animated_longhands(&keyframe.block.read_with(context.guards.author));
+            let computed = compute_style_for_declarations::<E>(
+                context,
+                &keyframe.block,
+                base_style,
+                font_metrics_provider,
+            );
+            (keyframe, properties, computed)
+        })
+        .collect();
+
+    let progress = sample.iteration_progress;
+    let linear = GenericTimingFunction::Keyword(TimingKeyword::Linear);
+    for property in sample.animated_properties(context.guards.author).iter() {
+        // Step 3. Compute the property-specific keyframes, adding neutral
+        // keyframes at offsets 0 and 1 if needed.
+        let mut property_keyframes: Vec<_> = keyframes
+            .iter()
+            .filter(|&&(_, ref properties, _)| properties.contains(property))
+            .map(|&(keyframe, _, ref computed)| (keyframe.offset, keyframe.timing_function, computed))
+            .collect();
+        if property_keyframes.first().map_or(true, |keyframe| keyframe.0 != 0.) {
+            property_keyframes.insert(0, (0., linear, base_style));
+        }
+        if property_keyframes.last().map_or(true, |keyframe| keyframe.0 != 1.) {
+            property_keyframes.push((1., linear, base_style));
+        }
+
+        // Steps 4-7. Find the interval endpoints for the current progress.
+        let keyframes_at = |offset| property_keyframes.iter().filter(|keyframe| keyframe.0 == offset).count();
+        let last = property_keyframes.len() - 1;
+        let (start, end) = if progress < 0. && keyframes_at(0.) > 1 {
+            (0, 0)
+        } else if progress >= 1. && keyframes_at(1.) > 1 {
+            (last, last)
+        } else if progress >= 1. {
+            (last - 1, last)
+        } else {
+            let start = property_keyframes
+                .iter()
+                .rposition(|keyframe| keyframe.0 <= progress)
+                .unwrap_or(0);
+            (start, start + 1)
+        };
+
+        // Steps 9-14. Interpolate between the endpoints.
+        let (start_offset, timing_function, from) = property_keyframes[start];
+        let (end_offset, _, to) = property_keyframes[end];
+        let distance = if end_offset == start_offset {
+            0.
+        } else {
+            (progress - start_offset) / (end_offset - start_offset)
+        };
+        if let Some(property) = AnimatedProperty::from_longhand(property, from, to) {
+            let animation = PropertyAnimation {
+                property: property,
+                timing_function: timing_function,
+                duration: Time::from_seconds(1.),
+            };
+            animation.update(Arc::make_mut(style), distance);
+        }
+    }
+}
+
+/// Restores the properties animated by an animation created from script to
+/// their values in `base_style`.
+pub fn remove_script_animation_from_style(
+    context: &SharedStyleContext,
+    sample: &ScriptAnimationSample,
+    base_style: &ComputedValues,
+    style: &mut Arc<ComputedValues>,
+) {
+    for property in sample.animated_properties(context.guards.author).iter() {
+        if let Some(property) = AnimatedProperty::from_longhand(property, base_style, base_style) {
+            property.update(Arc::make_mut(style), 0.);
+        }
+    }
+}
@@ -16,7 +16,7 @@ use selector_parser::SelectorImpl;
 use selectors::attr::NamespaceConstraint;
 use selectors::parser::{Combinator, Component};
 use selectors::parser::{Selector, SelectorIter, Visit};
-use selectors::visitor::SelectorVisitor;
+use selectors::visitor::{SelectorListKind, SelectorVisitor};
 use smallvec::SmallVec;
 
 #[cfg(feature = "gecko")]
@@ -61,6 +61,14 @@ pub struct Dependency {
 
     /// The offset into the selector that we should match on.
     pub selector_offset: usize,
+
+    /// The dependency of the compound selector containing the selector list
+    /// this dependency's selector is nested in (like `:is()` or `:not()`), if
+    /// any.
+    ///
+    /// When the nested selector matches, the invalidation should continue
+    /// from the parent compound selector instead.
+    pub parent: Option<Box<Dependency>>,
 }
 
 /// The kind of elements down the tree this dependency may affect.
@@ -101,7 +109,10 @@ impl Dependency {
     /// The kind of invalidation that this would generate.
     pub fn invalidation_kind(&self) -> DependencyInvalidationKind {
         match self.combinator() {
-            None => DependencyInvalidationKind::Element,
+            None => match self.parent {
+                Some(ref parent) => parent.invalidation_kind(),
+                None => DependencyInvalidationKind::Element,
+            },
             Some(Combinator::Child) | Some(Combinator::Descendant) => {
                 DependencyInvalidationKind::Descendants
             },
@@ -183,6 +194,9 @@ pub struct InvalidationMap {
     /// `other_attribute_affecting_selectors` too even if only the `id` has
     /// changed.
     pub has_id_attribute_selectors: bool,
+    /// Whether there are `:has()` selectors, which need to invalidate their
+    /// anchors when their descendants or siblings change.
+    pub has_relative_selectors: bool,
 }
 
 impl InvalidationMap {
@@ -196,6 +210,7 @@ impl InvalidationMap {
             other_attribute_affecting_selectors: SelectorMap::new(),
             has_class_attribute_selectors: false,
             has_id_attribute_selectors: false,
+            has_relative_selectors: false,
         }
     }
 
@@ -220,6 +235,7 @@ impl InvalidationMap {
         self.other_attribute_affecting_selectors.clear();
         self.has_id_attribute_selectors = false;
         self.has_class_attribute_selectors = false;
+        self.has_relative_selectors = false;
     }
 
     /// Adds a selector to this `InvalidationMap`.  Returns Err(..) to
@@ -231,12 +247,36 @@ impl InvalidationMap {
     ) -> Result<(), FailedAllocationError> {
         debug!("InvalidationMap::note_selector({:?})", selector);
 
+        let mut document_state = DocumentState::empty();
+        self.note_selector_internal(selector, None, &mut document_state, quirks_mode)?;
+
+        if !document_state.is_empty() {
+            self.document_state_selectors
+                .try_push(DocumentStateDependency {
+                    state: document_state,
+                    selector: selector.clone(),
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds the dependencies of a selector, which may be nested in a selector
+    /// list of the compound selector represented by `parent`.
+    ///
+    /// Document state dependencies are accumulated in `document_state`, since
+    /// they're tracked for the outermost selector.
+    fn note_selector_internal(
+        &mut self,
+        selector: &Selector<SelectorImpl>,
+        parent: Option<&Dependency>,
+        document_state: &mut DocumentState,
+        quirks_mode: QuirksMode,
+    ) -> Result<(), FailedAllocationError> {
         let mut iter = selector.iter();
         let mut combinator;
         let mut index = 0;
 
-        let mut document_state = DocumentState::empty();
-
         loop {
             let sequence_start = index;
 
@@ -244,20 +284,19 @@ impl InvalidationMap {
                 classes: SmallVec::new(),
                 ids: SmallVec::new(),
                 state: ElementState::empty(),
-                document_state: &mut document_state,
+                document_state: DocumentState::empty(),
                 other_attributes: false,
                 has_id_attribute_selectors: false,
                 has_class_attribute_selectors: false,
+                has_relative_selectors: false,
+                nested_selectors: SmallVec::new(),
             };
 
             // Visit all the simple selectors in this sequence.
             //
-            // Note that this works because we can't have combinators nested
-            // inside simple selectors (i.e. in :not() or :-moz-any()).
-            //
-            // If we ever support that we'll need to visit nested complex
-            // selectors as well, in order to mark them as affecting descendants
-            // at least.
+            // Complex selectors nested inside simple selectors (i.e. in :is()
+            // or :not()) are not visited, but collected so that we can note
+            // them afterwards, with this compound selector as their parent.
             for ss in &mut iter {
                 ss.visit(&mut compound_visitor);
                 index += 1; // Account for the simple selector.
@@ -265,34 +304,33 @@ impl InvalidationMap {
 
             self.has_id_attribute_selectors |= compound_visitor.has_id_attribute_selectors;
             self.has_class_attribute_selectors |= compound_visitor.has_class_attribute_selectors;
+            self.has_relative_selectors |= compound_visitor.has_relative_selectors;
+            *document_state |= compound_visitor.document_state;
+
+            let dependency = Dependency {
+                selector: selector.clone(),
+                selector_offset: sequence_start,
+                parent: parent.map(|p| Box::new(p.clone())),
+            };
 
             for class in compound_visitor.classes {
                 self.class_to_selector
                     .try_entry(class, quirks_mode)?
                     .or_insert_with(SmallVec::new)
-                    .try_push(Dependency {
-                        selector: selector.clone(),
-                        selector_offset: sequence_start,
-                    })?;
+                    .try_push(dependency.clone())?;
             }
 
             for id in compound_visitor.ids {
                 self.id_to_selector
                     .try_entry(id, quirks_mode)?
                     .or_insert_with(SmallVec::new)
-                    .try_push(Dependency {
-                        selector: selector.clone(),
-                        selector_offset: sequence_start,
-                    })?;
+                    .try_push(dependency.clone())?;
             }
 
             if !compound_visitor.state.is_empty() {
                 self.state_affecting_selectors.insert(
                     StateDependency {
-                        dep: Dependency {
-                            selector: selector.clone(),
-                            selector_offset: sequence_start,
-                        },
+                        dep: dependency.clone(),
                         state: compound_visitor.state,
                     },
                     quirks_mode,
@@ -300,13 +338,12 @@ impl InvalidationMap {
             }
 
             if compound_visitor.other_attributes {
-                self.other_attribute_affecting_selectors.insert(
-                    Dependency {
-                        selector: selector.clone(),
-                        selector_offset: sequence_start,
-                    },
-                    quirks_mode,
-                )?;
+                self.other_attribute_affecting_selectors
+                    .insert(dependency.clone(), quirks_mode)?;
+            }
+
+            for nested in &compound_visitor.nested_selectors {
+                self.note_selector_internal(nested, Some(&dependency), document_state, quirks_mode)?;
             }
 
             combinator = iter.next_sequence();
@@ -317,28 +354,20 @@ impl InvalidationMap {
             index += 1; // Account for the combinator.
         }
 
-        if !document_state.is_empty() {
-            self.document_state_selectors
-                .try_push(DocumentStateDependency {
-                    state: document_state,
-                    selector: selector.clone(),
-                })?;
-        }
-
         Ok(())
     }
 }
 
 /// A struct that collects invalidations for a given compound selector.
-struct CompoundSelectorDependencyCollector<'a> {
+struct CompoundSelectorDependencyCollector {
     /// The state this compound selector is affected by.
     state: ElementState,
 
-    /// The document this _complex_ selector is affected by.
+    /// The document state this compound selector is affected by.
     ///
-    /// We don't need to track state per compound selector, since it's global
-    /// state and it changes for everything.
-    document_state: &'a mut DocumentState,
+    /// This gets accumulated for the whole complex selector, since it's
+    /// global state and it changes for everything.
+    document_state: DocumentState,
 
     /// The classes this compound selector is affected by.
     ///
@@ -362,11 +391,35 @@ struct CompoundSelectorDependencyCollector<'a> {
 
     /// Whether there were attribute selectors with the class attribute.
     has_class_attribute_selectors: bool,
+
+    /// Whether there were `:has()` selectors.
+    has_relative_selectors: bool,
+
+    /// The complex selectors nested in this compound selector, in `:is()`,
+    /// `:where()` or `:not()`.
+    nested_selectors: SmallVec<[Selector<SelectorImpl>; 1]>,
 }
 
-impl<'a> SelectorVisitor for CompoundSelectorDependencyCollector<'a> {
+impl SelectorVisitor for CompoundSelectorDependencyCollector {
     type Impl = SelectorImpl;
 
+    fn visit_selector_list(
+        &mut self,
+        list_kind: SelectorListKind,
+        list: &[Selector<SelectorImpl>],
+    ) -> bool {
+        match list_kind {
+            // Changes that affect relative selectors are handled by
+            // invalidating the anchors of the `:has()` selectors, see
+            // invalidation::element::relative_selectors.
+            SelectorListKind::Has => self.has_relative_selectors = true,
+            SelectorListKind::Negation | SelectorListKind::Is | SelectorListKind::Where => {
+                self.nested_selectors.extend(list.iter().cloned());
+            },
+        }
+        true
+    }
+
     fn visit_simple_selector(&mut self, s: &Component<SelectorImpl>) -> bool {
         #[cfg(feature = "gecko")]
         use selector_parser::NonTSPseudoClass;
@@ -385,7 +438,7 @@ impl<'a> SelectorVisitor for CompoundSelectorDependencyCollector<'a> {
                     NonTSPseudoClass::Dir(ref dir) => dir_selector_to_state(dir),
                     _ => pc.state_flag(),
                 };
-                *self.document_state |= pc.document_state_flag();
+                self.document_state |= pc.document_state_flag();
             },
             _ => {},
         }
@@ -7,6 +7,7 @@
 
 use context::StackLimitChecker;
 use dom::{TElement, TNode, TShadowRoot};
+use invalidation::element::invalidation_map::{Dependency, DependencyInvalidationKind};
 use selector_parser::SelectorImpl;
 use selectors::matching::{CompoundSelectorMatchingResult, MatchingContext};
 use selectors::matching::matches_compound_selector_from;
@@ -133,6 +134,12 @@ pub struct Invalidation<'a> {
     /// this one if the generated invalidation is effective for all the siblings
     /// or descendants after us.
     matched_by_any_previous: bool,
+    /// The dependency of the compound selector this invalidation's selector is
+    /// nested in, if any.
+    ///
+    /// When the selector fully matches, we need to keep invalidating from the
+    /// parent compound selector instead of invalidating the element.
+    parent: Option<&'a Dependency>,
 }
 
 impl<'a> Invalidation<'a> {
@@ -142,6 +149,7 @@ impl<'a> Invalidation<'a> {
             selector,
             offset,
             matched_by_any_previous: false,
+            parent: None,
         }
     }
 
@@ -194,6 +202,62 @@ impl<'a> fmt::Debug for Invalidation<'a> {
     }
 }
 
+/// Pushes the invalidations needed when the compound selector represented by
+/// `dependency` starts or stops matching an element.
+///
+/// Returns whether the element itself needs to be invalidated.
+pub fn note_dependency<'a>(
+    dependency: &'a Dependency,
+    descendant_invalidations: &mut DescendantInvalidationLists<'a>,
+    sibling_invalidations: &mut InvalidationVector<'a>,
+) -> bool {
+    if dependency.selector_offset == 0 {
+        // This is the rightmost compound selector, so either the element
+        // itself is affected, or the compound selector our selector is nested
+        // in.
+        return match dependency.parent {
+            Some(ref parent) => {
+                note_dependency(parent, descendant_invalidations, sibling_invalidations)
+            },
+            None => true,
+        };
+    }
+
+    debug_assert_ne!(dependency.selector_offset, dependency.selector.len());
+
+    let invalidation = Invalidation {
+        selector: &dependency.selector,
+        offset: dependency.selector.len() - dependency.selector_offset + 1,
+        matched_by_any_previous: false,
+        parent: dependency.parent.as_ref().map(|p| &**p),
+    };
+
+    match dependency.invalidation_kind() {
+        DependencyInvalidationKind::Element => unreachable!(),
+        DependencyInvalidationKind::ElementAndDescendants => {
+            descendant_invalidations
+                .dom_descendants
+                .push(invalidation);
+            return true;
+        },
+        DependencyInvalidationKind::Descendants => {
+            descendant_invalidations
+                .dom_descendants
+                .push(invalidation);
+        },
+        DependencyInvalidationKind::Siblings => {
+            sibling_invalidations.push(invalidation);
+        },
+        DependencyInvalidationKind::SlottedElements => {
+            descendant_invalidations
+                .slotted_descendants
+                .push(invalidation);
+        },
+    }
+
+    false
+}
+
 /// The result of processing a single invalidation for a given element.
 struct SingleInvalidationResult {
     /// Whether the element itself was invalidated.
@@ -693,7 +757,14 @@ where
             CompoundSelectorMatchingResult::FullyMatched => {
                 debug!(" > Invalidation matched completely");
                 matched = true;
-                invalidated_self = true;
+                invalidated_self = match invalidation.parent {
+                    // The nested selector matched, so the compound selector
+                    // it's nested in may have changed for this element.
+                    Some(parent) => {
+                        note_dependency(parent, descendant_invalidations, sibling_invalidations)
+                    },
+                    None => true,
+                };
             },
             CompoundSelectorMatchingResult::Matched {
                 next_combinator_offset,
@@ -746,6 +817,7 @@ where
                     selector: invalidation.selector,
                     offset: next_combinator_offset + 1,
                     matched_by_any_previous: false,
+                    parent: invalidation.parent,
                 };
 
                 debug!(
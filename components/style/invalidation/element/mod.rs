@@ -8,5 +8,6 @@ pub mod document_state;
 pub mod element_wrapper;
 pub mod invalidation_map;
 pub mod invalidator;
+pub mod relative_selectors;
 pub mod restyle_hints;
 pub mod state_and_attributes;
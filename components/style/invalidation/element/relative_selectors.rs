@@ -0,0 +1,69 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Invalidation of the anchors of relative selectors (`:has()`).
+//!
+//! Unlike the rest of the invalidations, which go down the tree, a change in
+//! an element may affect whether an ancestor or a previous sibling matches a
+//! `:has()` selector. These elements are flagged with
+//! `ANCHORS_RELATIVE_SELECTOR` during selector matching, so we look for them
+//! and restyle them together with everything they may affect.
+
+use dom::TElement;
+use invalidation::element::restyle_hints::RestyleHint;
+use selectors::matching::ElementSelectorFlags;
+
+/// Restyles the anchors of `:has()` selectors that may be affected by a change
+/// in `element`, that is, its ancestors, its previous siblings, and the
+/// previous siblings of its ancestors.
+pub fn invalidate_relative_selector_anchors<E>(element: E)
+where
+    E: TElement,
+{
+    let mut current = Some(element);
+    while let Some(el) = current {
+        let mut sibling = el.prev_sibling_element();
+        while let Some(s) = sibling {
+            invalidate_anchor(s);
+            sibling = s.prev_sibling_element();
+        }
+
+        current = el.parent_element();
+        if let Some(parent) = current {
+            invalidate_anchor(parent);
+        }
+    }
+}
+
+/// Restyles `anchor` if it anchors a relative selector.
+///
+/// Since the anchor's later siblings and its descendants may depend on
+/// whether it matches, we restyle the subtree of its parent.
+fn invalidate_anchor<E>(anchor: E)
+where
+    E: TElement,
+{
+    if !anchor.has_selector_flags(ElementSelectorFlags::ANCHORS_RELATIVE_SELECTOR) {
+        return;
+    }
+
+    let root = anchor.traversal_parent().unwrap_or(anchor);
+    let mut data = match root.mutate_data() {
+        Some(data) => data,
+        None => return,
+    };
+
+    if data.hint.contains(RestyleHint::restyle_subtree()) {
+        return;
+    }
+
+    debug!("Invalidating relative selector anchor {:?}", anchor);
+    data.hint.insert(RestyleHint::restyle_subtree());
+
+    let mut current = root.traversal_parent();
+    while let Some(parent) = current {
+        unsafe { parent.set_dirty_descendants() };
+        current = parent.traversal_parent();
+    }
+}
@@ -13,7 +13,7 @@ use element_state::ElementState;
 use invalidation::element::element_wrapper::{ElementSnapshot, ElementWrapper};
 use invalidation::element::invalidation_map::*;
 use invalidation::element::invalidator::{DescendantInvalidationLists, InvalidationVector};
-use invalidation::element::invalidator::{Invalidation, InvalidationProcessor};
+use invalidation::element::invalidator::{InvalidationProcessor, note_dependency};
 use invalidation::element::restyle_hints::RestyleHint;
 use selector_map::SelectorMap;
 use selector_parser::Snapshot;
@@ -495,42 +495,12 @@ where
     fn note_dependency(&mut self, dependency: &'selectors Dependency) {
         debug_assert!(self.dependency_may_be_relevant(dependency));
 
-        let invalidation_kind = dependency.invalidation_kind();
-        if matches!(invalidation_kind, DependencyInvalidationKind::Element) {
-            self.invalidates_self = true;
-            return;
-        }
-
-        debug_assert_ne!(dependency.selector_offset, 0);
-        debug_assert_ne!(dependency.selector_offset, dependency.selector.len());
-
-        let invalidation = Invalidation::new(
-            &dependency.selector,
-            dependency.selector.len() - dependency.selector_offset + 1,
+        let invalidates_self = note_dependency(
+            dependency,
+            self.descendant_invalidations,
+            self.sibling_invalidations,
         );
-
-        match invalidation_kind {
-            DependencyInvalidationKind::Element => unreachable!(),
-            DependencyInvalidationKind::ElementAndDescendants => {
-                self.invalidates_self = true;
-                self.descendant_invalidations
-                    .dom_descendants
-                    .push(invalidation);
-            },
-            DependencyInvalidationKind::Descendants => {
-                self.descendant_invalidations
-                    .dom_descendants
-                    .push(invalidation);
-            },
-            DependencyInvalidationKind::Siblings => {
-                self.sibling_invalidations.push(invalidation);
-            },
-            DependencyInvalidationKind::SlottedElements => {
-                self.descendant_invalidations
-                    .slotted_descendants
-                    .push(invalidation);
-            },
-        }
+        self.invalidates_self |= invalidates_self;
     }
 
     /// Returns whether `dependency` may cause us to invalidate the style of
@@ -323,7 +323,8 @@ ${helpers.predefined_type("object-position",
                                   "Default::default()",
                                   animation_value_type="discrete",
                                   spec="https://drafts.csswg.org/css-grid/#propdef-grid-%s-%s" % (kind, range),
-                                  products="gecko",
+                                  servo_pref="layout.grid.enabled",
+                                  servo_restyle_damage="reflow",
                                   boxed=True)}
     % endfor
 
@@ -334,14 +335,16 @@ ${helpers.predefined_type("object-position",
                               "Default::default()",
                               animation_value_type="discrete",
                               spec="https://drafts.csswg.org/css-grid/#propdef-grid-auto-%ss" % kind,
-                              products="gecko",
+                              servo_pref="layout.grid.enabled",
+                              servo_restyle_damage="reflow",
                               boxed=True)}
 
     ${helpers.predefined_type(
         "grid-template-%ss" % kind,
         "GridTemplateComponent",
         "specified::GenericGridTemplateComponent::None",
-        products="gecko",
+        servo_pref="layout.grid.enabled",
+        servo_restyle_damage="reflow",
         spec="https://drafts.csswg.org/css-grid/#propdef-grid-template-%ss" % kind,
         boxed=True,
         flags="GETCS_NEEDS_LAYOUT_FLUSH",
@@ -353,23 +356,26 @@ ${helpers.predefined_type("object-position",
 ${helpers.predefined_type("grid-auto-flow",
                           "GridAutoFlow",
                           initial_value="computed::GridAutoFlow::row()",
-                          products="gecko",
+                          servo_pref="layout.grid.enabled",
+                          servo_restyle_damage="reflow",
                           animation_value_type="discrete",
                           spec="https://drafts.csswg.org/css-grid/#propdef-grid-auto-flow")}
 
 ${helpers.predefined_type("grid-template-areas",
                           "GridTemplateAreas",
                           initial_value="computed::GridTemplateAreas::none()",
-                          products="gecko",
+                          servo_pref="layout.grid.enabled",
+                          servo_restyle_damage="reflow",
                           animation_value_type="discrete",
                           spec="https://drafts.csswg.org/css-grid/#propdef-grid-template-areas")}
 
 ${helpers.predefined_type("column-gap",
                           "length::NonNegativeLengthOrPercentageOrNormal",
                           "Either::Second(Normal)",
-                          alias="grid-column-gap" if product == "gecko" else "",
+                          alias="grid-column-gap",
                           extra_prefixes="moz",
                           servo_pref="layout.columns.enabled",
+                          servo_alias_pref="layout.grid.enabled",
                           spec="https://drafts.csswg.org/css-align-3/#propdef-column-gap",
                           animation_value_type="NonNegativeLengthOrPercentageOrNormal",
                           servo_restyle_damage = "reflow")}
@@ -379,7 +385,7 @@ ${helpers.predefined_type("row-gap",
                           "length::NonNegativeLengthOrPercentageOrNormal",
                           "Either::Second(Normal)",
                           alias="grid-row-gap",
-                          products="gecko",
+                          servo_pref="layout.grid.enabled",
                           spec="https://drafts.csswg.org/css-align-3/#propdef-row-gap",
                           animation_value_type="NonNegativeLengthOrPercentageOrNormal",
                           servo_restyle_damage = "reflow")}
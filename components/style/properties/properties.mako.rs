@@ -448,8 +448,10 @@ impl NonCustomPropertyId {
 
         let passes_pref_check = || {
             % if product == "servo":
-                static PREF_NAME: [Option< &str>; ${len(data.longhands) + len(data.shorthands)}] = [
-                    % for property in data.longhands + data.shorthands:
+                static PREF_NAME: [Option< &str>; ${
+                    len(data.longhands) + len(data.shorthands) + len(data.all_aliases())
+                }] = [
+                    % for property in data.longhands + data.shorthands + data.all_aliases():
                         % if property.servo_pref:
                             Some("${property.servo_pref}"),
                         % else:
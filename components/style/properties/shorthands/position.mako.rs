@@ -110,7 +110,7 @@
 
 <%helpers:shorthand name="gap" alias="grid-gap" sub_properties="row-gap column-gap"
                     spec="https://drafts.csswg.org/css-align-3/#gap-shorthand"
-                    products="gecko">
+                    servo_pref="layout.grid.enabled">
   use properties::longhands::{row_gap, column_gap};
 
   pub fn parse_value<'i, 't>(context: &ParserContext, input: &mut Parser<'i, 't>)
@@ -141,7 +141,7 @@
 % for kind in ["row", "column"]:
 <%helpers:shorthand name="grid-${kind}" sub_properties="grid-${kind}-start grid-${kind}-end"
                     spec="https://drafts.csswg.org/css-grid/#propdef-grid-${kind}"
-                    products="gecko">
+                    servo_pref="layout.grid.enabled">
     use values::specified::GridLine;
     use parser::Parse;
 
@@ -183,7 +183,7 @@
 <%helpers:shorthand name="grid-area"
                     sub_properties="grid-row-start grid-row-end grid-column-start grid-column-end"
                     spec="https://drafts.csswg.org/css-grid/#propdef-grid-area"
-                    products="gecko">
+                    servo_pref="layout.grid.enabled">
     use values::specified::GridLine;
     use parser::Parse;
 
@@ -250,7 +250,7 @@
 <%helpers:shorthand name="grid-template"
                     sub_properties="grid-template-rows grid-template-columns grid-template-areas"
                     spec="https://drafts.csswg.org/css-grid/#propdef-grid-template"
-                    products="gecko">
+                    servo_pref="layout.grid.enabled">
     use parser::Parse;
     use servo_arc::Arc;
     use values::{Either, None_};
@@ -483,7 +483,7 @@
                     sub_properties="grid-template-rows grid-template-columns grid-template-areas
                                     grid-auto-rows grid-auto-columns grid-auto-flow"
                     spec="https://drafts.csswg.org/css-grid/#propdef-grid"
-                    products="gecko">
+                    servo_pref="layout.grid.enabled">
     use parser::Parse;
     use properties::longhands::{grid_auto_columns, grid_auto_rows, grid_auto_flow};
     use values::{Either, None_};
@@ -6,15 +6,17 @@
 
 use app_units::Au;
 use context::QuirksMode;
-use cssparser::{Parser, RGBA};
+use cssparser::{CowRcStr, Parser, RGBA, Token};
 use euclid::{Size2D, TypedScale, TypedSize2D};
 use media_queries::MediaType;
-use parser::ParserContext;
+use parser::{Parse, ParserContext};
 use properties::ComputedValues;
-use selectors::parser::SelectorParseErrorKind;
+use servo_config::prefs::PREFS;
+use std::cmp;
 use std::fmt::{self, Write};
 use std::sync::atomic::{AtomicBool, AtomicIsize, Ordering};
-use style_traits::{CSSPixel, CssWriter, DevicePixel, ParseError, ToCss};
+use str::starts_with_ignore_ascii_case;
+use style_traits::{CSSPixel, CssWriter, DevicePixel, ParseError, StyleParseErrorKind, ToCss};
 use style_traits::viewport::ViewportConstraints;
 use values::{specified, KeyframesName};
 use values::computed::{self, ToComputedValue};
@@ -50,10 +52,17 @@ pub struct Device {
     /// Whether any styles computed in the document relied on the viewport size.
     #[ignore_malloc_size_of = "Pure stack type"]
     used_viewport_units: AtomicBool,
+    /// The user preferences and pointing device capabilities media queries
+    /// are evaluated against.
+    media_preferences: MediaPreferences,
 }
 
 impl Device {
-    /// Trivially construct a new `Device`.
+    /// Construct a new `Device`, whose user preferences are read from the
+    /// `layout.media-queries.*` prefs.
+    ///
+    /// Layout creates a new device for every reflow and script for every media
+    /// query evaluation, so a change to the prefs applies from the next one.
     pub fn new(
         media_type: MediaType,
         viewport_size: TypedSize2D<f32, CSSPixel>,
@@ -67,6 +76,7 @@ impl Device {
             root_font_size: AtomicIsize::new(FontSize::medium().size().0 as isize),
             used_root_font_size: AtomicBool::new(false),
             used_viewport_units: AtomicBool::new(false),
+            media_preferences: MediaPreferences::from_prefs(),
         }
     }
 
@@ -153,16 +163,186 @@ impl Device {
     pub fn default_background_color(&self) -> RGBA {
         RGBA::new(255, 255, 255, 255)
     }
+
+    /// Returns the user preferences and pointing device capabilities of this
+    /// device.
+    pub fn media_preferences(&self) -> MediaPreferences {
+        self.media_preferences
+    }
+
+    /// Overrides the user preferences and pointing device capabilities this
+    /// device read from the prefs.
+    ///
+    /// This is only used to evaluate media queries against given preferences
+    /// in tests; the devices of layout and script always use the prefs.
+    pub fn set_media_preferences(&mut self, media_preferences: MediaPreferences) {
+        self.media_preferences = media_preferences;
+    }
+
+    /// Returns the color scheme the user asked for.
+    pub fn prefers_color_scheme(&self) -> PrefersColorScheme {
+        self.media_preferences.prefers_color_scheme
+    }
+
+    /// Returns whether the user asked for less motion.
+    pub fn prefers_reduced_motion(&self) -> PrefersReducedMotion {
+        self.media_preferences.prefers_reduced_motion
+    }
+
+    /// Returns whether the primary pointing device can hover.
+    pub fn hover(&self) -> Hover {
+        self.media_preferences.hover
+    }
+
+    /// Returns the accuracy of the primary pointing device.
+    pub fn pointer(&self) -> Pointer {
+        self.media_preferences.pointer
+    }
+}
+
+/// The user preferences and pointing device capabilities that media queries
+/// can test.
+///
+/// These are read from the prefs when a `Device` is created. Nothing observes
+/// the prefs, so changing them doesn't restyle the document or fire `change`
+/// events on `MediaQueryList`s by itself: the new values are only used from
+/// the next reflow or media query evaluation.
+#[derive(Clone, Copy, Debug, MallocSizeOf, PartialEq)]
+pub struct MediaPreferences {
+    /// The color scheme the user asked for.
+    pub prefers_color_scheme: PrefersColorScheme,
+    /// Whether the user asked for less motion.
+    pub prefers_reduced_motion: PrefersReducedMotion,
+    /// Whether the primary pointing device can hover.
+    pub hover: Hover,
+    /// The accuracy of the primary pointing device.
+    pub pointer: Pointer,
+}
+
+impl MediaPreferences {
+    /// Reads the preferences from the `layout.media-queries.*` prefs.
+    pub fn from_prefs() -> Self {
+        MediaPreferences {
+            prefers_color_scheme:
+                keyword_pref("layout.media-queries.prefers-color-scheme", PrefersColorScheme::from_ident)
+                    .unwrap_or(PrefersColorScheme::NoPreference),
+            prefers_reduced_motion:
+                keyword_pref("layout.media-queries.prefers-reduced-motion", PrefersReducedMotion::from_ident)
+                    .unwrap_or(PrefersReducedMotion::NoPreference),
+            hover: keyword_pref("layout.media-queries.hover", Hover::from_ident).unwrap_or(Hover::Hover),
+            pointer: keyword_pref("layout.media-queries.pointer", Pointer::from_ident).unwrap_or(Pointer::Fine),
+        }
+    }
+}
+
+fn keyword_pref<T>(name: &str, from_ident: fn(&str) -> Result<T, ()>) -> Option<T> {
+    PREFS.get(name).as_string().and_then(|value| from_ident(value).ok())
+}
+
+/// <https://drafts.csswg.org/mediaqueries-4/#orientation>
+#[derive(Clone, Copy, Debug, Eq, Parse, PartialEq, ToCss)]
+#[cfg_attr(feature = "servo", derive(MallocSizeOf))]
+#[allow(missing_docs)]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+/// <https://drafts.csswg.org/mediaqueries-5/#prefers-color-scheme>
+#[derive(Clone, Copy, Debug, Eq, Parse, PartialEq, ToCss)]
+#[cfg_attr(feature = "servo", derive(MallocSizeOf))]
+#[allow(missing_docs)]
+pub enum PrefersColorScheme {
+    NoPreference,
+    Light,
+    Dark,
+}
+
+/// <https://drafts.csswg.org/mediaqueries-5/#prefers-reduced-motion>
+#[derive(Clone, Copy, Debug, Eq, Parse, PartialEq, ToCss)]
+#[cfg_attr(feature = "servo", derive(MallocSizeOf))]
+#[allow(missing_docs)]
+pub enum PrefersReducedMotion {
+    NoPreference,
+    Reduce,
+}
+
+/// <https://drafts.csswg.org/mediaqueries-4/#hover>
+#[derive(Clone, Copy, Debug, Eq, Parse, PartialEq, ToCss)]
+#[cfg_attr(feature = "servo", derive(MallocSizeOf))]
+#[allow(missing_docs)]
+pub enum Hover {
+    None,
+    Hover,
+}
+
+/// <https://drafts.csswg.org/mediaqueries-4/#pointer>
+#[derive(Clone, Copy, Debug, Eq, Parse, PartialEq, ToCss)]
+#[cfg_attr(feature = "servo", derive(MallocSizeOf))]
+#[allow(missing_docs)]
+pub enum Pointer {
+    None,
+    Coarse,
+    Fine,
+}
+
+/// A `<ratio>` value, as used by the `aspect-ratio` media feature.
+///
+/// <https://drafts.csswg.org/mediaqueries-4/#values>
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "servo", derive(MallocSizeOf))]
+pub struct AspectRatio(pub u32, pub u32);
+
+impl AspectRatio {
+    fn parse<'i, 't>(
+        context: &ParserContext,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i>> {
+        let a = specified::Integer::parse_positive(context, input)?;
+        input.expect_delim('/')?;
+        let b = specified::Integer::parse_positive(context, input)?;
+        Ok(AspectRatio(a.value() as u32, b.value() as u32))
+    }
+}
+
+impl ToCss for AspectRatio {
+    fn to_css<W>(&self, dest: &mut CssWriter<W>) -> fmt::Result
+    where
+        W: Write,
+    {
+        self.0.to_css(dest)?;
+        dest.write_char('/')?;
+        self.1.to_css(dest)
+    }
 }
 
 /// A expression kind servo understands and parses.
 ///
+/// Features that are evaluated in a boolean context, like `(hover)`, have no
+/// value.
+///
 /// Only `pub` for unit testing, please don't use it directly!
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "servo", derive(MallocSizeOf))]
 pub enum ExpressionKind {
-    /// <http://dev.w3.org/csswg/mediaqueries-3/#width>
-    Width(Range<specified::Length>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#width>
+    Width(Option<Range<specified::Length>>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#height>
+    Height(Option<Range<specified::Length>>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#aspect-ratio>
+    AspectRatio(Option<Range<AspectRatio>>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#orientation>
+    Orientation(Option<Orientation>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#resolution>
+    Resolution(Option<Range<specified::Resolution>>),
+    /// <https://drafts.csswg.org/mediaqueries-5/#prefers-color-scheme>
+    PrefersColorScheme(Option<PrefersColorScheme>),
+    /// <https://drafts.csswg.org/mediaqueries-5/#prefers-reduced-motion>
+    PrefersReducedMotion(Option<PrefersReducedMotion>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#hover>
+    Hover(Option<Hover>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#pointer>
+    Pointer(Option<Pointer>),
 }
 
 /// A single expression a per:
@@ -172,6 +352,13 @@ pub enum ExpressionKind {
 #[cfg_attr(feature = "servo", derive(MallocSizeOf))]
 pub struct MediaFeatureExpression(pub ExpressionKind);
 
+/// The `min-` or `max-` prefix of a feature name.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Prefix {
+    Min,
+    Max,
+}
+
 impl MediaFeatureExpression {
     /// The kind of expression we're, just for unit testing.
     ///
@@ -186,7 +373,7 @@ impl MediaFeatureExpression {
     /// media-feature: media-value
     /// ```
     ///
-    /// Only supports width ranges for now.
+    /// or any of the forms of the range syntax of Media Queries Level 4.
     pub fn parse<'i, 't>(
         context: &ParserContext,
         input: &mut Parser<'i, 't>,
@@ -203,37 +390,200 @@ impl MediaFeatureExpression {
         context: &ParserContext,
         input: &mut Parser<'i, 't>,
     ) -> Result<Self, ParseError<'i>> {
-        let name = input.expect_ident_cloned()?;
-        input.expect_colon()?;
-        // TODO: Handle other media features
-        Ok(MediaFeatureExpression(match_ignore_ascii_case! { &name,
-            "min-width" => {
-                ExpressionKind::Width(Range::Min(specified::Length::parse_non_negative(context, input)?))
-            },
-            "max-width" => {
-                ExpressionKind::Width(Range::Max(specified::Length::parse_non_negative(context, input)?))
-            },
-            "width" => {
-                ExpressionKind::Width(Range::Eq(specified::Length::parse_non_negative(context, input)?))
-            },
-            _ => return Err(input.new_custom_error(SelectorParseErrorKind::UnexpectedIdent(name.clone())))
+        let location = input.current_source_location();
+        let (ident, value_first) = match input.try(|i| i.expect_ident_cloned()) {
+            Ok(ident) => (ident, false),
+            Err(..) => (Self::find_feature_name(input)?, true),
+        };
+
+        let (name, prefix) = if starts_with_ignore_ascii_case(&ident, "min-") {
+            (&ident[4..], Some(Prefix::Min))
+        } else if starts_with_ignore_ascii_case(&ident, "max-") {
+            (&ident[4..], Some(Prefix::Max))
+        } else {
+            (&*ident, None)
+        };
+
+        let discrete = prefix.is_none() && !value_first;
+        let unknown_feature = || -> ParseError<'i> {
+            location.new_custom_error(StyleParseErrorKind::MediaQueryExpectedFeatureName(ident.clone()))
+        };
+
+        Ok(MediaFeatureExpression(match_ignore_ascii_case! { name,
+            "width" => ExpressionKind::Width(
+                parse_range(context, input, &ident, prefix, value_first, specified::Length::parse_non_negative)?
+            ),
+            "height" => ExpressionKind::Height(
+                parse_range(context, input, &ident, prefix, value_first, specified::Length::parse_non_negative)?
+            ),
+            "aspect-ratio" => ExpressionKind::AspectRatio(
+                parse_range(context, input, &ident, prefix, value_first, AspectRatio::parse)?
+            ),
+            "resolution" => ExpressionKind::Resolution(
+                parse_range(context, input, &ident, prefix, value_first, specified::Resolution::parse)?
+            ),
+            "orientation" if discrete => ExpressionKind::Orientation(
+                parse_keyword(input, Orientation::from_ident)?
+            ),
+            "prefers-color-scheme" if discrete => ExpressionKind::PrefersColorScheme(
+                parse_keyword(input, PrefersColorScheme::from_ident)?
+            ),
+            "prefers-reduced-motion" if discrete => ExpressionKind::PrefersReducedMotion(
+                parse_keyword(input, PrefersReducedMotion::from_ident)?
+            ),
+            "hover" if discrete => ExpressionKind::Hover(parse_keyword(input, Hover::from_ident)?),
+            "pointer" if discrete => ExpressionKind::Pointer(parse_keyword(input, Pointer::from_ident)?),
+            _ => return Err(unknown_feature()),
         }))
     }
 
+    /// Returns the name of the feature of an expression like
+    /// `400px <= width`, leaving the parser where it was.
+    fn find_feature_name<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<CowRcStr<'i>, ParseError<'i>> {
+        let start = input.state();
+        let name = loop {
+            let name = match *input.next()? {
+                Token::Ident(ref name) => name.clone(),
+                _ => continue,
+            };
+            break name;
+        };
+        input.reset(&start);
+        Ok(name)
+    }
+
     /// Evaluate this expression and return whether it matches the current
     /// device.
     pub fn matches(&self, device: &Device, quirks_mode: QuirksMode) -> bool {
         let viewport_size = device.au_viewport_size();
-        let value = viewport_size.width;
         match self.0 {
             ExpressionKind::Width(ref range) => {
-                match range.to_computed_range(device, quirks_mode) {
-                    Range::Min(ref width) => value >= *width,
-                    Range::Max(ref width) => value <= *width,
-                    Range::Eq(ref width) => value == *width,
-                }
+                matches_length_range(range.as_ref(), viewport_size.width, device, quirks_mode)
+            },
+            ExpressionKind::Height(ref range) => {
+                matches_length_range(range.as_ref(), viewport_size.height, device, quirks_mode)
+            },
+            ExpressionKind::AspectRatio(ref range) => {
+                let ratio = viewport_size.width.0 as f64 / viewport_size.height.0 as f64;
+                range.as_ref().map_or(true, |range| {
+                    range.map(|r| r.0 as f64 / r.1 as f64).matches(ratio)
+                })
+            },
+            ExpressionKind::Resolution(ref range) => {
+                let dppx = device.device_pixel_ratio().get();
+                range.as_ref().map_or(true, |range| range.map(|r| r.to_dppx()).matches(dppx))
             },
+            ExpressionKind::Orientation(ref value) => {
+                let orientation = if viewport_size.height >= viewport_size.width {
+                    Orientation::Portrait
+                } else {
+                    Orientation::Landscape
+                };
+                value.map_or(true, |value| value == orientation)
+            },
+            ExpressionKind::PrefersColorScheme(ref value) => {
+                let scheme = device.prefers_color_scheme();
+                value.map_or(scheme != PrefersColorScheme::NoPreference, |value| value == scheme)
+            },
+            ExpressionKind::PrefersReducedMotion(ref value) => {
+                let motion = device.prefers_reduced_motion();
+                value.map_or(motion != PrefersReducedMotion::NoPreference, |value| value == motion)
+            },
+            ExpressionKind::Hover(ref value) => {
+                let hover = device.hover();
+                value.map_or(hover != Hover::None, |value| value == hover)
+            },
+            ExpressionKind::Pointer(ref value) => {
+                let pointer = device.pointer();
+                value.map_or(pointer != Pointer::None, |value| value == pointer)
+            },
+        }
+    }
+}
+
+/// Parses whatever follows the name of a discrete feature, that is, either
+/// nothing or a colon and a keyword.
+fn parse_keyword<'i, 't, T>(
+    input: &mut Parser<'i, 't>,
+    from_ident: fn(&str) -> Result<T, ()>,
+) -> Result<Option<T>, ParseError<'i>> {
+    if input.try(|i| i.expect_colon()).is_err() {
+        return Ok(None);
+    }
+    let location = input.current_source_location();
+    let ident = input.expect_ident()?;
+    from_ident(ident).map(Some).map_err(|()| {
+        location.new_custom_error(StyleParseErrorKind::MediaQueryExpectedFeatureValue)
+    })
+}
+
+/// Parses a range feature, either with the legacy `min-` and `max-` prefixes
+/// or with the range syntax. When `value_first` is true, the input starts
+/// with a value rather than with the feature name `ident`.
+fn parse_range<'i, 't, T>(
+    context: &ParserContext,
+    input: &mut Parser<'i, 't>,
+    ident: &str,
+    prefix: Option<Prefix>,
+    value_first: bool,
+    parse_value: fn(&ParserContext, &mut Parser<'i, 't>) -> Result<T, ParseError<'i>>,
+) -> Result<Option<Range<T>>, ParseError<'i>> {
+    if value_first {
+        if prefix.is_some() {
+            return Err(input.new_custom_error(StyleParseErrorKind::MediaQueryUnexpectedOperator));
         }
+        let first = parse_value(context, input)?;
+        let first_operator = Operator::parse(input)?;
+        input.expect_ident_matching(ident)?;
+        let second_operator = match input.try(Operator::parse) {
+            Ok(operator) => operator,
+            Err(..) => return Ok(Some(Range::Operator(first_operator.reverse(), first))),
+        };
+        if !first_operator.is_less_than() && !first_operator.is_greater_than() ||
+            first_operator.is_less_than() != second_operator.is_less_than() ||
+            first_operator.is_greater_than() != second_operator.is_greater_than()
+        {
+            return Err(input.new_custom_error(StyleParseErrorKind::MediaQueryUnexpectedOperator));
+        }
+        let second = parse_value(context, input)?;
+        return Ok(Some(Range::Interval(first, first_operator, second_operator, second)));
+    }
+
+    if input.try(|i| i.expect_colon()).is_ok() {
+        let value = parse_value(context, input)?;
+        return Ok(Some(match prefix {
+            Some(Prefix::Min) => Range::Min(value),
+            Some(Prefix::Max) => Range::Max(value),
+            None => Range::Eq(value),
+        }));
+    }
+
+    let operator = match input.try(Operator::parse) {
+        Ok(operator) => operator,
+        Err(..) => {
+            if prefix.is_some() {
+                return Err(input.new_custom_error(StyleParseErrorKind::RangedExpressionWithNoValue));
+            }
+            return Ok(None);
+        },
+    };
+    if prefix.is_some() {
+        return Err(input.new_custom_error(StyleParseErrorKind::MediaQueryUnexpectedOperator));
+    }
+    Ok(Some(Range::Operator(operator, parse_value(context, input)?)))
+}
+
+fn matches_length_range(
+    range: Option<&Range<specified::Length>>,
+    value: Au,
+    device: &Device,
+    quirks_mode: QuirksMode,
+) -> bool {
+    match range {
+        Some(range) => range.to_computed_range(device, quirks_mode).matches(value),
+        None => value != Au(0),
     }
 }
 
@@ -242,17 +592,170 @@ impl ToCss for MediaFeatureExpression {
     where
         W: Write,
     {
-        let (s, l) = match self.0 {
-            ExpressionKind::Width(Range::Min(ref l)) => ("(min-width: ", l),
-            ExpressionKind::Width(Range::Max(ref l)) => ("(max-width: ", l),
-            ExpressionKind::Width(Range::Eq(ref l)) => ("(width: ", l),
-        };
-        dest.write_str(s)?;
-        l.to_css(dest)?;
+        dest.write_char('(')?;
+        match self.0 {
+            ExpressionKind::Width(ref range) => serialize_range("width", range.as_ref(), dest)?,
+            ExpressionKind::Height(ref range) => serialize_range("height", range.as_ref(), dest)?,
+            ExpressionKind::AspectRatio(ref range) => serialize_range("aspect-ratio", range.as_ref(), dest)?,
+            ExpressionKind::Resolution(ref range) => serialize_range("resolution", range.as_ref(), dest)?,
+            ExpressionKind::Orientation(ref value) => serialize_keyword("orientation", value.as_ref(), dest)?,
+            ExpressionKind::PrefersColorScheme(ref value) => {
+                serialize_keyword("prefers-color-scheme", value.as_ref(), dest)?
+            },
+            ExpressionKind::PrefersReducedMotion(ref value) => {
+                serialize_keyword("prefers-reduced-motion", value.as_ref(), dest)?
+            },
+            ExpressionKind::Hover(ref value) => serialize_keyword("hover", value.as_ref(), dest)?,
+            ExpressionKind::Pointer(ref value) => serialize_keyword("pointer", value.as_ref(), dest)?,
+        }
         dest.write_char(')')
     }
 }
 
+fn serialize_keyword<T, W>(name: &str, value: Option<&T>, dest: &mut CssWriter<W>) -> fmt::Result
+where
+    T: ToCss,
+    W: Write,
+{
+    dest.write_str(name)?;
+    if let Some(value) = value {
+        dest.write_str(": ")?;
+        value.to_css(dest)?;
+    }
+    Ok(())
+}
+
+fn serialize_range<T, W>(name: &str, range: Option<&Range<T>>, dest: &mut CssWriter<W>) -> fmt::Result
+where
+    T: ToCss,
+    W: Write,
+{
+    let range = match range {
+        Some(range) => range,
+        None => return dest.write_str(name),
+    };
+    match *range {
+        Range::Min(ref value) => {
+            dest.write_str("min-")?;
+            serialize_keyword(name, Some(value), dest)
+        },
+        Range::Max(ref value) => {
+            dest.write_str("max-")?;
+            serialize_keyword(name, Some(value), dest)
+        },
+        Range::Eq(ref value) => serialize_keyword(name, Some(value), dest),
+        Range::Operator(operator, ref value) => {
+            dest.write_str(name)?;
+            dest.write_char(' ')?;
+            operator.to_css(dest)?;
+            dest.write_char(' ')?;
+            value.to_css(dest)
+        },
+        Range::Interval(ref first, first_operator, second_operator, ref second) => {
+            first.to_css(dest)?;
+            dest.write_char(' ')?;
+            first_operator.to_css(dest)?;
+            dest.write_char(' ')?;
+            dest.write_str(name)?;
+            dest.write_char(' ')?;
+            second_operator.to_css(dest)?;
+            dest.write_char(' ')?;
+            second.to_css(dest)
+        },
+    }
+}
+
+/// A comparison operator of the range syntax.
+///
+/// <https://drafts.csswg.org/mediaqueries-4/#mq-range-context>
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "servo", derive(MallocSizeOf))]
+#[allow(missing_docs)]
+pub enum Operator {
+    Equal,
+    GreaterThan,
+    GreaterThanEqual,
+    LessThan,
+    LessThanEqual,
+}
+
+impl Operator {
+    fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, ParseError<'i>> {
+        let location = input.current_source_location();
+        let first_delim = match *input.next()? {
+            Token::Delim(delim) => delim,
+            ref t => return Err(location.new_unexpected_token_error(t.clone())),
+        };
+        Ok(match first_delim {
+            '=' => Operator::Equal,
+            '>' => {
+                if input.try(|i| i.expect_delim('=')).is_ok() {
+                    Operator::GreaterThanEqual
+                } else {
+                    Operator::GreaterThan
+                }
+            },
+            '<' => {
+                if input.try(|i| i.expect_delim('=')).is_ok() {
+                    Operator::LessThanEqual
+                } else {
+                    Operator::LessThan
+                }
+            },
+            _ => return Err(location.new_custom_error(StyleParseErrorKind::MediaQueryUnexpectedOperator)),
+        })
+    }
+
+    /// The operator to use when swapping its operands.
+    fn reverse(self) -> Self {
+        match self {
+            Operator::Equal => Operator::Equal,
+            Operator::GreaterThan => Operator::LessThan,
+            Operator::GreaterThanEqual => Operator::LessThanEqual,
+            Operator::LessThan => Operator::GreaterThan,
+            Operator::LessThanEqual => Operator::GreaterThanEqual,
+        }
+    }
+
+    fn is_less_than(self) -> bool {
+        self == Operator::LessThan || self == Operator::LessThanEqual
+    }
+
+    fn is_greater_than(self) -> bool {
+        self == Operator::GreaterThan || self == Operator::GreaterThanEqual
+    }
+
+    /// Returns whether `one <operator> other` holds.
+    fn evaluate<T: PartialOrd>(self, one: &T, other: &T) -> bool {
+        let cmp = match one.partial_cmp(other) {
+            Some(cmp) => cmp,
+            None => return false,
+        };
+        match self {
+            Operator::Equal => cmp == cmp::Ordering::Equal,
+            Operator::GreaterThan => cmp == cmp::Ordering::Greater,
+            Operator::GreaterThanEqual => cmp != cmp::Ordering::Less,
+            Operator::LessThan => cmp == cmp::Ordering::Less,
+            Operator::LessThanEqual => cmp != cmp::Ordering::Greater,
+        }
+    }
+}
+
+impl ToCss for Operator {
+    fn to_css<W>(&self, dest: &mut CssWriter<W>) -> fmt::Result
+    where
+        W: Write,
+    {
+        dest.write_str(match *self {
+            Operator::Equal => "=",
+            Operator::LessThan => "<",
+            Operator::LessThanEqual => "<=",
+            Operator::GreaterThan => ">",
+            Operator::GreaterThanEqual => ">=",
+        })
+    }
+}
+
 /// An enumeration that represents a ranged value.
 ///
 /// Only public for testing, implementation details of `MediaFeatureExpression`
@@ -266,14 +769,51 @@ pub enum Range<T> {
     Max(T),
     /// Exactly the inner value.
     Eq(T),
+    /// A comparison of the feature with the inner value, like
+    /// `width > 400px`.
+    Operator(Operator, T),
+    /// A comparison of the feature with two values, like
+    /// `400px <= width < 700px`. The operators either both are `<` or `<=`,
+    /// or both are `>` or `>=`.
+    Interval(T, Operator, Operator, T),
+}
+
+impl<T> Range<T> {
+    fn map<U, F>(&self, f: F) -> Range<U>
+    where
+        F: Fn(&T) -> U,
+    {
+        match *self {
+            Range::Min(ref value) => Range::Min(f(value)),
+            Range::Max(ref value) => Range::Max(f(value)),
+            Range::Eq(ref value) => Range::Eq(f(value)),
+            Range::Operator(operator, ref value) => Range::Operator(operator, f(value)),
+            Range::Interval(ref first, first_operator, second_operator, ref second) => {
+                Range::Interval(f(first), first_operator, second_operator, f(second))
+            },
+        }
+    }
+}
+
+impl<T: PartialOrd> Range<T> {
+    /// Returns whether the value of the feature is in this range.
+    fn matches(&self, actual: T) -> bool {
+        match *self {
+            Range::Min(ref value) => actual >= *value,
+            Range::Max(ref value) => actual <= *value,
+            Range::Eq(ref value) => actual == *value,
+            Range::Operator(operator, ref value) => operator.evaluate(&actual, value),
+            Range::Interval(ref first, first_operator, second_operator, ref second) => {
+                first_operator.evaluate(first, &actual) && second_operator.evaluate(&actual, second)
+            },
+        }
+    }
 }
 
 impl Range<specified::Length> {
     fn to_computed_range(&self, device: &Device, quirks_mode: QuirksMode) -> Range<Au> {
-        computed::Context::for_media_query_evaluation(device, quirks_mode, |context| match *self {
-            Range::Min(ref width) => Range::Min(Au::from(width.to_computed_value(&context))),
-            Range::Max(ref width) => Range::Max(Au::from(width.to_computed_value(&context))),
-            Range::Eq(ref width) => Range::Eq(Au::from(width.to_computed_value(&context))),
+        computed::Context::for_media_query_evaluation(device, quirks_mode, |context| {
+            self.map(|length| Au::from(length.to_computed_value(&context)))
         })
     }
 }
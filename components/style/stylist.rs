@@ -33,7 +33,7 @@ use selectors::matching::{matches_selector, ElementSelectorFlags, MatchingContex
 use selectors::matching::VisitedHandlingMode;
 use selectors::parser::{AncestorHashes, Combinator, Component, Selector};
 use selectors::parser::{SelectorIter, Visit};
-use selectors::visitor::SelectorVisitor;
+use selectors::visitor::{SelectorListKind, SelectorVisitor};
 use servo_arc::{Arc, ArcBorrow};
 use shared_lock::{Locked, SharedRwLockReadGuard, StylesheetGuards};
 use smallbitvec::SmallBitVec;
@@ -476,6 +476,15 @@ impl Stylist {
             .any(|(d, _)| d.document_state_dependencies.intersects(state))
     }
 
+    /// Returns whether there are `:has()` selectors in any rule, which means
+    /// that changes to an element may affect its ancestors and previous
+    /// siblings.
+    pub fn has_relative_selectors(&self) -> bool {
+        self.cascade_data
+            .iter_origins()
+            .any(|(d, _)| d.invalidation_map.has_relative_selectors)
+    }
+
     /// Flush the list of stylesheets if they changed, ensuring the stylist is
     /// up-to-date.
     pub fn flush<E>(
@@ -1791,7 +1800,8 @@ fn component_needs_revalidation(
         Component::NthLastOfType(..) |
         Component::FirstOfType |
         Component::LastOfType |
-        Component::OnlyOfType => true,
+        Component::OnlyOfType |
+        Component::Has(..) => true,
         Component::NonTSPseudoClass(ref p) => p.needs_cache_revalidation(),
         _ => false,
     }
@@ -1804,9 +1814,9 @@ impl<'a> SelectorVisitor for StylistSelectorVisitor<'a> {
         self.needs_revalidation =
             self.needs_revalidation || combinator.map_or(false, |c| c.is_sibling());
 
-        // NOTE(emilio): This works properly right now because we can't store
-        // complex selectors in nested selectors, otherwise we may need to
-        // rethink this.
+        // NOTE(emilio): This works properly even with complex selectors nested
+        // in :is() or :not(), since visit_selector_list restores the state
+        // after visiting them.
         //
         // Also, note that this call happens before we visit any of the simple
         // selectors in the next ComplexSelector, so we can use this to skip
@@ -1817,6 +1827,25 @@ impl<'a> SelectorVisitor for StylistSelectorVisitor<'a> {
         true
     }
 
+    fn visit_selector_list(
+        &mut self,
+        list_kind: SelectorListKind,
+        list: &[Selector<SelectorImpl>],
+    ) -> bool {
+        // The nested selectors start at the compound selector they're nested
+        // in, so they shouldn't affect what we consider the rightmost selector
+        // once we're done with them.
+        //
+        // Relative selectors never match the element itself though.
+        for selector in list {
+            let passed_rightmost_selector = self.passed_rightmost_selector;
+            self.passed_rightmost_selector |= list_kind == SelectorListKind::Has;
+            selector.visit(self);
+            self.passed_rightmost_selector = passed_rightmost_selector;
+        }
+        true
+    }
+
     fn visit_attribute_selector(
         &mut self,
         _ns: &NamespaceConstraint<&Namespace>,
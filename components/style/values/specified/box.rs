@@ -19,6 +19,17 @@ use values::generics::box_::VerticalAlign as GenericVerticalAlign;
 use values::specified::{AllowQuirks, Number};
 use values::specified::length::{LengthOrPercentage, NonNegativeLength};
 
+#[cfg(feature = "gecko")]
+fn grid_enabled(_context: &ParserContext) -> bool {
+    true
+}
+
+#[cfg(feature = "servo")]
+fn grid_enabled(_context: &ParserContext) -> bool {
+    use servo_config::prefs::PREFS;
+    PREFS.get("layout.grid.enabled").as_boolean().unwrap_or(false)
+}
+
 #[cfg(feature = "gecko")]
 fn moz_display_values_enabled(context: &ParserContext) -> bool {
     use gecko_bindings::structs;
@@ -68,9 +79,9 @@ pub enum Display {
     Flex,
     #[parse(aliases = "-webkit-inline-flex")]
     InlineFlex,
-    #[cfg(feature = "gecko")]
+    #[parse(condition = "grid_enabled")]
     Grid,
-    #[cfg(feature = "gecko")]
+    #[parse(condition = "grid_enabled")]
     InlineGrid,
     #[cfg(feature = "gecko")]
     Ruby,
@@ -139,7 +150,6 @@ impl Display {
     pub fn is_item_container(&self) -> bool {
         match *self {
             Display::Flex | Display::InlineFlex => true,
-            #[cfg(feature = "gecko")]
             Display::Grid | Display::InlineGrid => true,
             _ => false,
         }
@@ -209,9 +219,8 @@ impl Display {
             // Values that have a corresponding block-outside version.
             Display::InlineTable => Display::Table,
             Display::InlineFlex => Display::Flex,
-
-            #[cfg(feature = "gecko")]
             Display::InlineGrid => Display::Grid,
+
             #[cfg(feature = "gecko")]
             Display::WebkitInlineBox => Display::WebkitBox,
 
@@ -224,12 +233,11 @@ impl Display {
             },
 
             // These are not changed by blockification.
-            Display::None | Display::Block | Display::Flex | Display::ListItem | Display::Table => {
-                *self
-            },
+            Display::None | Display::Block | Display::Flex | Display::Grid | Display::ListItem |
+            Display::Table => *self,
 
             #[cfg(feature = "gecko")]
-            Display::Contents | Display::FlowRoot | Display::Grid | Display::WebkitBox => *self,
+            Display::Contents | Display::FlowRoot | Display::WebkitBox => *self,
 
             // Everything else becomes block.
             _ => Display::Block,
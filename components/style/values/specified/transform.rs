@@ -422,6 +422,30 @@ impl Parse for TimingFunction {
     }
 }
 
+impl TimingFunction {
+    /// Computes this timing function, which doesn't depend on any context,
+    /// since none of its components can contain relative values.
+    pub fn to_computed_value_without_context(&self) -> ComputedTimingFunction {
+        match *self {
+            generic::TimingFunction::Keyword(keyword) => generic::TimingFunction::Keyword(keyword),
+            generic::TimingFunction::CubicBezier { x1, y1, x2, y2 } => {
+                generic::TimingFunction::CubicBezier {
+                    x1: x1.get(),
+                    y1: y1.get(),
+                    x2: x2.get(),
+                    y2: y2.get(),
+                }
+            },
+            generic::TimingFunction::Steps(steps, position) => {
+                generic::TimingFunction::Steps(steps.value() as u32, position)
+            },
+            generic::TimingFunction::Frames(frames) => {
+                generic::TimingFunction::Frames(frames.value() as u32)
+            },
+        }
+    }
+}
+
 impl ToComputedValue for TimingFunction {
     type ComputedValue = ComputedTimingFunction;
 
@@ -0,0 +1,689 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use Handler;
+use ipc_channel::ipc;
+use keys::{key_from_char, key_modifier};
+use msg::constellation_msg::{BrowsingContextId, Key, KeyModifiers, KeyState};
+use script_traits::{ConstellationMsg, MouseButton, MouseEventType, WebDriverCommandMsg};
+use script_traits::webdriver_msg::{WebDriverFrameId, WebDriverScriptCommand};
+use std::collections::HashSet;
+use std::mem;
+use std::thread;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use webdriver::actions::{ActionSequence, ActionsType, GeneralAction, KeyAction, KeyActionItem};
+use webdriver::actions::{NullActionItem, PointerAction, PointerActionItem, PointerMoveAction};
+use webdriver::actions::{PointerOrigin, PointerType};
+use webdriver::common::{Nullable, WebElement};
+use webdriver::error::{ErrorStatus, WebDriverError, WebDriverResult};
+
+/// How often, in milliseconds, the pointer position is updated while a pointer move
+/// with a duration is in progress.
+const POINTER_MOVE_INTERVAL: u64 = 17;
+
+// https://w3c.github.io/webdriver/webdriver-spec.html#dfn-input-source-state
+pub enum InputSourceState {
+    Null,
+    Key(KeyInputState),
+    Pointer(PointerInputState),
+}
+
+impl InputSourceState {
+    fn has_same_type(&self, other: &InputSourceState) -> bool {
+        match (self, other) {
+            (&InputSourceState::Null, &InputSourceState::Null) |
+            (&InputSourceState::Key(_), &InputSourceState::Key(_)) |
+            (&InputSourceState::Pointer(_), &InputSourceState::Pointer(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+// https://w3c.github.io/webdriver/webdriver-spec.html#dfn-key-input-state
+pub struct KeyInputState {
+    pressed: HashSet<char>,
+}
+
+impl KeyInputState {
+    fn new() -> KeyInputState {
+        KeyInputState {
+            pressed: HashSet::new(),
+        }
+    }
+
+    /// The modifiers of the pressed keys, so that a modifier stays active
+    /// while either of its left and right keys is held.
+    fn modifiers(&self) -> KeyModifiers {
+        self.pressed.iter()
+            .filter_map(key_from_char)
+            .fold(KeyModifiers::empty(), |modifiers, (key, _)| modifiers | key_modifier(key))
+    }
+}
+
+// https://w3c.github.io/webdriver/webdriver-spec.html#dfn-pointer-input-state
+pub struct PointerInputState {
+    pressed: HashSet<u64>,
+    x: i64,
+    y: i64,
+    /// The last button that was pressed and where, used to synthesize a click when
+    /// it is released without the pointer having moved, like the embedder does.
+    click_origin: Option<(u64, i64, i64)>,
+}
+
+impl PointerInputState {
+    fn new() -> PointerInputState {
+        PointerInputState {
+            pressed: HashSet::new(),
+            x: 0,
+            y: 0,
+            click_origin: None,
+        }
+    }
+}
+
+/// A pointer move being performed during a tick, possibly alongside those of
+/// other input sources.
+///
+/// <https://w3c.github.io/webdriver/webdriver-spec.html#dfn-perform-a-pointer-move>
+struct PointerMove {
+    id: String,
+    start: (i64, i64),
+    target: (i64, i64),
+    duration: Duration,
+}
+
+impl PointerMove {
+    /// The position of the pointer once `elapsed` passed since the start of the tick.
+    fn position_at(&self, elapsed: Duration) -> (i64, i64) {
+        if elapsed >= self.duration {
+            return self.target;
+        }
+        let ratio = millis(elapsed) / millis(self.duration);
+        let (start_x, start_y) = self.start;
+        let (x, y) = self.target;
+        (start_x + ((x - start_x) as f64 * ratio) as i64, start_y + ((y - start_y) as f64 * ratio) as i64)
+    }
+}
+
+/// An action that undoes the effect of a dispatched action once the session's
+/// actions are released.
+///
+/// <https://w3c.github.io/webdriver/webdriver-spec.html#dfn-input-cancel-list>
+pub enum CancelAction {
+    KeyUp(char),
+    PointerUp(u64),
+}
+
+fn input_source_id(sequence: &ActionSequence) -> WebDriverResult<&str> {
+    match sequence.id {
+        Some(ref id) => Ok(id),
+        None => Err(WebDriverError::new(ErrorStatus::InvalidArgument,
+                                        "Action sequence has no input source id")),
+    }
+}
+
+fn action_count(sequence: &ActionSequence) -> usize {
+    match sequence.actions {
+        ActionsType::Null(ref items) => items.len(),
+        ActionsType::Key(ref items) => items.len(),
+        ActionsType::Pointer(_, ref items) => items.len(),
+    }
+}
+
+// https://w3c.github.io/webdriver/webdriver-spec.html#dfn-computing-the-tick-duration
+fn tick_duration(sequence: &ActionSequence, tick: usize) -> u64 {
+    match sequence.actions {
+        ActionsType::Null(ref items) => match items.get(tick) {
+            Some(&NullActionItem::General(GeneralAction::Pause(ref pause))) => pause.duration,
+            _ => 0,
+        },
+        ActionsType::Key(ref items) => match items.get(tick) {
+            Some(&KeyActionItem::General(GeneralAction::Pause(ref pause))) => pause.duration,
+            _ => 0,
+        },
+        ActionsType::Pointer(_, ref items) => match items.get(tick) {
+            Some(&PointerActionItem::General(GeneralAction::Pause(ref pause))) => pause.duration,
+            Some(&PointerActionItem::Pointer(PointerAction::Move(ref action))) => match action.duration {
+                Nullable::Value(duration) => duration,
+                Nullable::Null => 0,
+            },
+            _ => 0,
+        },
+    }
+}
+
+fn mouse_button(button: u64) -> WebDriverResult<MouseButton> {
+    match button {
+        0 => Ok(MouseButton::Left),
+        1 => Ok(MouseButton::Middle),
+        2 => Ok(MouseButton::Right),
+        _ => Err(WebDriverError::new(ErrorStatus::UnsupportedOperation,
+                                     "Only the left, middle and right mouse buttons are supported")),
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs() as f64 * 1000. + duration.subsec_nanos() as f64 / 1_000_000.
+}
+
+impl Handler {
+    // https://w3c.github.io/webdriver/webdriver-spec.html#dfn-dispatch-actions
+    pub fn dispatch_actions(&mut self, actions: &[ActionSequence]) -> WebDriverResult<()> {
+        // Check every input source before dispatching anything, so that a bad
+        // sequence doesn't cause only some of the actions to be performed.
+        self.check_input_sources(actions)?;
+
+        let tick_count = actions.iter().map(action_count).max().unwrap_or(0);
+        for tick in 0..tick_count {
+            let tick_start = Instant::now();
+
+            // Step 1
+            let duration = actions.iter()
+                .map(|sequence| tick_duration(sequence, tick))
+                .max()
+                .unwrap_or(0);
+
+            // Step 2
+            // The actions of a tick are dispatched concurrently, so the pointer
+            // moves that take time are performed together.
+            let mut moves = vec![];
+            for sequence in actions {
+                if let Some(pointer_move) = self.dispatch_tick_action(sequence, tick, duration)? {
+                    moves.push(pointer_move);
+                }
+            }
+            self.perform_pointer_moves(&moves, tick_start)?;
+
+            // Step 3
+            let duration = Duration::from_millis(duration);
+            let elapsed = tick_start.elapsed();
+            if elapsed < duration {
+                thread::sleep(duration - elapsed);
+            }
+        }
+        Ok(())
+    }
+
+    // https://w3c.github.io/webdriver/webdriver-spec.html#dfn-get-an-input-source
+    fn check_input_sources(&mut self, actions: &[ActionSequence]) -> WebDriverResult<()> {
+        let session = self.session_mut()?;
+        for sequence in actions {
+            let id = input_source_id(sequence)?;
+            let source = match sequence.actions {
+                ActionsType::Null(_) => InputSourceState::Null,
+                ActionsType::Key(_) => InputSourceState::Key(KeyInputState::new()),
+                ActionsType::Pointer(ref parameters, _) => {
+                    match parameters.pointer_type {
+                        PointerType::Mouse => {},
+                        _ => return Err(WebDriverError::new(ErrorStatus::UnsupportedOperation,
+                                                            "Only mouse pointers are supported")),
+                    }
+                    InputSourceState::Pointer(PointerInputState::new())
+                },
+            };
+
+            let same_type = session.input_state_table.get(id).map(|existing| existing.has_same_type(&source));
+            match same_type {
+                Some(true) => {},
+                Some(false) => return Err(WebDriverError::new(ErrorStatus::InvalidArgument,
+                                                              "Input source already has a different type")),
+                None => {
+                    session.input_state_table.insert(id.to_owned(), source);
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Dispatches the action of `sequence` for the given tick, returning the
+    /// pointer move to perform if it is a pointer move.
+    fn dispatch_tick_action(&mut self,
+                            sequence: &ActionSequence,
+                            tick: usize,
+                            tick_duration: u64)
+                            -> WebDriverResult<Option<PointerMove>> {
+        let id = input_source_id(sequence)?;
+        match sequence.actions {
+            // Null input sources can only pause.
+            ActionsType::Null(_) => Ok(None),
+            ActionsType::Key(ref items) => match items.get(tick) {
+                Some(&KeyActionItem::Key(KeyAction::Down(ref action))) =>
+                    self.dispatch_keydown_action(id, action.value).map(|_| None),
+                Some(&KeyActionItem::Key(KeyAction::Up(ref action))) =>
+                    self.dispatch_keyup_action(id, action.value).map(|_| None),
+                _ => Ok(None),
+            },
+            ActionsType::Pointer(_, ref items) => match items.get(tick) {
+                Some(&PointerActionItem::Pointer(PointerAction::Down(ref action))) =>
+                    self.dispatch_pointerdown_action(id, action.button).map(|_| None),
+                Some(&PointerActionItem::Pointer(PointerAction::Up(ref action))) =>
+                    self.dispatch_pointerup_action(id, action.button).map(|_| None),
+                Some(&PointerActionItem::Pointer(PointerAction::Move(ref action))) =>
+                    self.dispatch_pointermove_action(id, action, tick_duration).map(Some),
+                // Mice never cancel their pointer.
+                _ => Ok(None),
+            },
+        }
+    }
+
+    fn key_input_state(&mut self, id: &str) -> WebDriverResult<&mut KeyInputState> {
+        match self.session_mut()?.input_state_table.get_mut(id) {
+            Some(&mut InputSourceState::Key(ref mut state)) => Ok(state),
+            _ => Err(WebDriverError::new(ErrorStatus::InvalidArgument, "Not a key input source")),
+        }
+    }
+
+    fn pointer_input_state(&mut self, id: &str) -> WebDriverResult<&mut PointerInputState> {
+        match self.session_mut()?.input_state_table.get_mut(id) {
+            Some(&mut InputSourceState::Pointer(ref mut state)) => Ok(state),
+            _ => Err(WebDriverError::new(ErrorStatus::InvalidArgument, "Not a pointer input source")),
+        }
+    }
+
+    // https://w3c.github.io/webdriver/webdriver-spec.html#dfn-dispatch-a-keydown-action
+    fn dispatch_keydown_action(&mut self, id: &str, value: char) -> WebDriverResult<()> {
+        let (key, with_shift) = key_from_char(&value).ok_or_else(||
+            WebDriverError::new(ErrorStatus::UnsupportedOperation, format!("Unsupported key {:?}", value)))?;
+
+        let (modifiers, repeated) = {
+            let state = self.key_input_state(id)?;
+            let repeated = !state.pressed.insert(value);
+            (state.modifiers(), repeated)
+        };
+        if !repeated {
+            self.session_mut()?.input_cancel_list.push((id.to_owned(), CancelAction::KeyUp(value)));
+        }
+
+        let modifiers = if with_shift { modifiers | KeyModifiers::SHIFT } else { modifiers };
+        let state = if repeated { KeyState::Repeated } else { KeyState::Pressed };
+        self.send_key_event(key, modifiers, state)
+    }
+
+    // https://w3c.github.io/webdriver/webdriver-spec.html#dfn-dispatch-a-keyup-action
+    fn dispatch_keyup_action(&mut self, id: &str, value: char) -> WebDriverResult<()> {
+        let (key, with_shift) = key_from_char(&value).ok_or_else(||
+            WebDriverError::new(ErrorStatus::UnsupportedOperation, format!("Unsupported key {:?}", value)))?;
+
+        let modifiers = {
+            let state = self.key_input_state(id)?;
+            if !state.pressed.remove(&value) {
+                return Ok(());
+            }
+            state.modifiers()
+        };
+
+        let modifiers = if with_shift { modifiers | KeyModifiers::SHIFT } else { modifiers };
+        self.send_key_event(key, modifiers, KeyState::Released)
+    }
+
+    fn send_key_event(&self, key: Key, modifiers: KeyModifiers, state: KeyState) -> WebDriverResult<()> {
+        let browsing_context_id = self.session()?.browsing_context_id;
+        let cmd_msg = WebDriverCommandMsg::SendKeys(browsing_context_id, vec![(key, modifiers, state)]);
+        self.constellation_chan.send(ConstellationMsg::WebDriverCommand(cmd_msg)).unwrap();
+        Ok(())
+    }
+
+    // https://w3c.github.io/webdriver/webdriver-spec.html#dfn-dispatch-a-pointerdown-action
+    fn dispatch_pointerdown_action(&mut self, id: &str, button: u64) -> WebDriverResult<()> {
+        let mouse_button = mouse_button(button)?;
+
+        let (x, y) = {
+            let state = self.pointer_input_state(id)?;
+            if !state.pressed.insert(button) {
+                return Ok(());
+            }
+            state.click_origin = Some((button, state.x, state.y));
+            (state.x, state.y)
+        };
+        self.session_mut()?.input_cancel_list.push((id.to_owned(), CancelAction::PointerUp(button)));
+
+        self.send_mouse_button_event(MouseEventType::MouseDown, mouse_button, x, y)
+    }
+
+    // https://w3c.github.io/webdriver/webdriver-spec.html#dfn-dispatch-a-pointerup-action
+    fn dispatch_pointerup_action(&mut self, id: &str, button: u64) -> WebDriverResult<()> {
+        let mouse_button = mouse_button(button)?;
+
+        let (x, y, click) = {
+            let state = self.pointer_input_state(id)?;
+            if !state.pressed.remove(&button) {
+                return Ok(());
+            }
+            let click = state.click_origin.take() == Some((button, state.x, state.y));
+            (state.x, state.y, click)
+        };
+
+        self.send_mouse_button_event(MouseEventType::MouseUp, mouse_button, x, y)?;
+        if click {
+            self.send_mouse_button_event(MouseEventType::Click, mouse_button, x, y)?;
+        }
+        Ok(())
+    }
+
+    /// Computes where and for how long the pointer moves, leaving the move itself
+    /// to `perform_pointer_moves`.
+    ///
+    /// <https://w3c.github.io/webdriver/webdriver-spec.html#dfn-dispatch-a-pointermove-action>
+    fn dispatch_pointermove_action(&mut self,
+                                   id: &str,
+                                   action: &PointerMoveAction,
+                                   tick_duration: u64)
+                                   -> WebDriverResult<PointerMove> {
+        // Steps 1-2
+        let x_offset = match action.x {
+            Nullable::Value(x) => x,
+            Nullable::Null => 0,
+        };
+        let y_offset = match action.y {
+            Nullable::Value(y) => y,
+            Nullable::Null => 0,
+        };
+
+        // Steps 3-4
+        let (start_x, start_y) = {
+            let state = self.pointer_input_state(id)?;
+            (state.x, state.y)
+        };
+
+        // Steps 5-8
+        let (x, y) = match action.origin {
+            PointerOrigin::Viewport => (x_offset, y_offset),
+            PointerOrigin::Pointer => (start_x + x_offset, start_y + y_offset),
+            PointerOrigin::Element(ref element) => {
+                match self.element_in_view_center_point(element)? {
+                    Some((x, y)) => (x + x_offset, y + y_offset),
+                    None => return Err(WebDriverError::new(ErrorStatus::MoveTargetOutOfBounds,
+                                                           "Element is not in view")),
+                }
+            },
+        };
+
+        // Step 9
+        let (width, height) = self.viewport_size()?;
+        if x < 0 || y < 0 || x > width || y > height {
+            return Err(WebDriverError::new(ErrorStatus::MoveTargetOutOfBounds,
+                                           "Move target is outside of the viewport"));
+        }
+
+        // Step 10
+        let duration = match action.duration {
+            Nullable::Value(duration) => duration,
+            Nullable::Null => tick_duration,
+        };
+
+        Ok(PointerMove {
+            id: id.to_owned(),
+            start: (start_x, start_y),
+            target: (x, y),
+            duration: Duration::from_millis(duration),
+        })
+    }
+
+    /// Performs the pointer moves of a tick that started at `tick_start`, until the
+    /// longest of them is over.
+    ///
+    /// <https://w3c.github.io/webdriver/webdriver-spec.html#dfn-perform-a-pointer-move>
+    fn perform_pointer_moves(&mut self, moves: &[PointerMove], tick_start: Instant) -> WebDriverResult<()> {
+        let longest = match moves.iter().map(|pointer_move| pointer_move.duration).max() {
+            Some(duration) => duration,
+            None => return Ok(()),
+        };
+        loop {
+            let elapsed = tick_start.elapsed();
+            for pointer_move in moves {
+                let (x, y) = pointer_move.position_at(elapsed);
+                self.perform_pointer_move(&pointer_move.id, x, y)?;
+            }
+            if elapsed >= longest {
+                return Ok(());
+            }
+            thread::sleep((longest - elapsed).min(Duration::from_millis(POINTER_MOVE_INTERVAL)));
+        }
+    }
+
+    fn perform_pointer_move(&mut self, id: &str, x: i64, y: i64) -> WebDriverResult<()> {
+        {
+            let state = self.pointer_input_state(id)?;
+            if (state.x, state.y) == (x, y) {
+                return Ok(());
+            }
+            state.x = x;
+            state.y = y;
+        }
+
+        let top_level_browsing_context_id = self.session()?.top_level_browsing_context_id;
+        let cmd_msg = WebDriverCommandMsg::MouseMoveAction(top_level_browsing_context_id, x as f32, y as f32);
+        self.constellation_chan.send(ConstellationMsg::WebDriverCommand(cmd_msg)).unwrap();
+        Ok(())
+    }
+
+    fn send_mouse_button_event(&self,
+                               event_type: MouseEventType,
+                               button: MouseButton,
+                               x: i64,
+                               y: i64)
+                               -> WebDriverResult<()> {
+        let top_level_browsing_context_id = self.session()?.top_level_browsing_context_id;
+        let cmd_msg = WebDriverCommandMsg::MouseButtonAction(top_level_browsing_context_id,
+                                                             event_type,
+                                                             button,
+                                                             x as f32,
+                                                             y as f32);
+        self.constellation_chan.send(ConstellationMsg::WebDriverCommand(cmd_msg)).unwrap();
+        Ok(())
+    }
+
+    fn viewport_size(&self) -> WebDriverResult<(i64, i64)> {
+        let (sender, receiver) = ipc::channel().unwrap();
+        let top_level_browsing_context_id = self.session()?.top_level_browsing_context_id;
+        let cmd_msg = WebDriverCommandMsg::GetWindowSize(top_level_browsing_context_id, sender);
+        self.constellation_chan.send(ConstellationMsg::WebDriverCommand(cmd_msg)).unwrap();
+
+        let viewport = receiver.recv().unwrap().initial_viewport;
+        Ok((viewport.width as i64, viewport.height as i64))
+    }
+
+    /// Scrolls the element into view and returns its in-view center point in the
+    /// top-level viewport, or `None` if it still isn't in view.
+    pub fn element_in_view_center_point(&self, element: &WebElement) -> WebDriverResult<Option<(i64, i64)>> {
+        let (sender, receiver) = ipc::channel().unwrap();
+        let cmd = WebDriverScriptCommand::GetElementInViewCenterPoint(element.id.clone(), sender);
+        self.browsing_context_script_command(cmd)?;
+        let point = receiver.recv().unwrap().or_else(|_| Err(WebDriverError::new(
+            ErrorStatus::StaleElementReference, "Unable to find element in document")))?;
+        match point {
+            Some((x, y)) => self.to_top_level_point(x, y).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Converts a point in the viewport of the current browsing context to one in
+    /// the viewport of the top-level browsing context, to which input events are sent.
+    fn to_top_level_point(&self, x: i64, y: i64) -> WebDriverResult<(i64, i64)> {
+        let (offset_x, offset_y) = self.top_level_frame_offset()?;
+        Ok((x + offset_x, y + offset_y))
+    }
+
+    /// Returns the offset of the viewport of the current browsing context in the
+    /// viewport of the top-level browsing context.
+    pub fn top_level_frame_offset(&self) -> WebDriverResult<(i64, i64)> {
+        let (mut x, mut y) = (0, 0);
+        let session = self.session()?;
+        let top_level_browsing_context_id = BrowsingContextId::from(session.top_level_browsing_context_id);
+        let mut browsing_context_id = session.browsing_context_id;
+        while browsing_context_id != top_level_browsing_context_id {
+            let (sender, receiver) = ipc::channel().unwrap();
+            let cmd = WebDriverScriptCommand::GetBrowsingContextId(WebDriverFrameId::Parent, sender);
+            self.script_command(browsing_context_id, cmd);
+            let parent_id = receiver.recv().unwrap().or_else(|_| Err(WebDriverError::new(
+                ErrorStatus::NoSuchFrame, "Frame does not exist")))?;
+
+            let (sender, receiver) = ipc::channel().unwrap();
+            self.script_command(parent_id, WebDriverScriptCommand::GetFrameOffset(browsing_context_id, sender));
+            let (offset_x, offset_y) = receiver.recv().unwrap().or_else(|_| Err(WebDriverError::new(
+                ErrorStatus::NoSuchFrame, "Frame is not rendered")))?;
+
+            x += offset_x;
+            y += offset_y;
+            browsing_context_id = parent_id;
+        }
+        Ok((x, y))
+    }
+
+    fn script_command(&self, browsing_context_id: BrowsingContextId, cmd_msg: WebDriverScriptCommand) {
+        let msg = ConstellationMsg::WebDriverCommand(WebDriverCommandMsg::ScriptCommand(browsing_context_id, cmd_msg));
+        self.constellation_chan.send(msg).unwrap();
+    }
+
+    // https://w3c.github.io/webdriver/webdriver-spec.html#release-actions
+    pub fn release_actions(&mut self) -> WebDriverResult<()> {
+        // Step 1
+        let cancel_list = mem::replace(&mut self.session_mut()?.input_cancel_list, vec![]);
+        for (id, action) in cancel_list.into_iter().rev() {
+            match action {
+                CancelAction::KeyUp(value) => self.dispatch_keyup_action(&id, value)?,
+                CancelAction::PointerUp(button) => self.dispatch_pointerup_action(&id, button)?,
+            }
+        }
+
+        // Step 2
+        self.session_mut()?.input_state_table.clear();
+        Ok(())
+    }
+
+    /// Clicks at the given point with a mouse that only exists for the duration of the click.
+    ///
+    /// <https://w3c.github.io/webdriver/webdriver-spec.html#element-click> steps 8-11
+    pub fn dispatch_click(&mut self, x: i64, y: i64) -> WebDriverResult<()> {
+        let id = Uuid::new_v4().to_string();
+        self.session_mut()?.input_state_table
+            .insert(id.clone(), InputSourceState::Pointer(PointerInputState::new()));
+
+        let result = self.click(&id, x, y);
+
+        let session = self.session_mut()?;
+        session.input_state_table.remove(&id);
+        session.input_cancel_list.retain(|&(ref source, _)| *source != id);
+        result
+    }
+
+    fn click(&mut self, id: &str, x: i64, y: i64) -> WebDriverResult<()> {
+        self.perform_pointer_move(id, x, y)?;
+        self.dispatch_pointerdown_action(id, 0)?;
+        self.dispatch_pointerup_action(id, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use msg::constellation_msg::KeyModifiers;
+    use std::time::Duration;
+    use super::{KeyInputState, PointerMove, action_count, tick_duration};
+    use webdriver::actions::{ActionSequence, ActionsType, GeneralAction, KeyAction, KeyActionItem};
+    use webdriver::actions::{KeyDownAction, NullActionItem, PauseAction, PointerAction};
+    use webdriver::actions::{PointerActionItem, PointerActionParameters, PointerMoveAction};
+    use webdriver::actions::{PointerOrigin, PointerType};
+    use webdriver::common::Nullable;
+
+    fn pause(duration: u64) -> GeneralAction {
+        GeneralAction::Pause(PauseAction { duration: duration })
+    }
+
+    fn pointer_move(duration: Nullable<u64>) -> PointerActionItem {
+        PointerActionItem::Pointer(PointerAction::Move(PointerMoveAction {
+            duration: duration,
+            origin: PointerOrigin::Viewport,
+            x: Nullable::Value(10),
+            y: Nullable::Value(10),
+        }))
+    }
+
+    fn sequences() -> Vec<ActionSequence> {
+        vec![
+            ActionSequence {
+                id: Some("null".to_owned()),
+                actions: ActionsType::Null(vec![
+                    NullActionItem::General(pause(100)),
+                ]),
+            },
+            ActionSequence {
+                id: Some("keyboard".to_owned()),
+                actions: ActionsType::Key(vec![
+                    KeyActionItem::Key(KeyAction::Down(KeyDownAction { value: 'a' })),
+                    KeyActionItem::General(pause(30)),
+                    KeyActionItem::Key(KeyAction::Down(KeyDownAction { value: 'b' })),
+                ]),
+            },
+            ActionSequence {
+                id: Some("mouse".to_owned()),
+                actions: ActionsType::Pointer(PointerActionParameters { pointer_type: PointerType::Mouse }, vec![
+                    pointer_move(Nullable::Value(250)),
+                    pointer_move(Nullable::Null),
+                ]),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_action_count() {
+        let counts: Vec<usize> = sequences().iter().map(action_count).collect();
+        assert_eq!(counts, vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn test_tick_duration() {
+        let sequences = sequences();
+        let durations = |tick| -> Vec<u64> {
+            sequences.iter().map(|sequence| tick_duration(sequence, tick)).collect()
+        };
+        assert_eq!(durations(0), vec![100, 0, 250]);
+        // A move without a duration does not lengthen the tick.
+        assert_eq!(durations(1), vec![0, 30, 0]);
+        // Sequences that are over don't lengthen the tick.
+        assert_eq!(durations(2), vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn test_modifiers_follow_pressed_keys() {
+        let left_shift = '\u{E008}';
+        let right_shift = '\u{E050}';
+        let left_control = '\u{E009}';
+
+        let mut state = KeyInputState::new();
+        state.pressed.insert('a');
+        assert_eq!(state.modifiers(), KeyModifiers::empty());
+
+        state.pressed.insert(left_shift);
+        state.pressed.insert(right_shift);
+        state.pressed.insert(left_control);
+        assert_eq!(state.modifiers(), KeyModifiers::SHIFT | KeyModifiers::CONTROL);
+
+        // Shift stays active while the other Shift key is held.
+        state.pressed.remove(&left_shift);
+        assert_eq!(state.modifiers(), KeyModifiers::SHIFT | KeyModifiers::CONTROL);
+
+        state.pressed.remove(&right_shift);
+        state.pressed.remove(&left_control);
+        assert_eq!(state.modifiers(), KeyModifiers::empty());
+    }
+
+    #[test]
+    fn test_pointer_move_position() {
+        let pointer_move = PointerMove {
+            id: "mouse".to_owned(),
+            start: (0, 100),
+            target: (100, 50),
+            duration: Duration::from_millis(100),
+        };
+        assert_eq!(pointer_move.position_at(Duration::from_millis(0)), (0, 100));
+        assert_eq!(pointer_move.position_at(Duration::from_millis(50)), (50, 75));
+        assert_eq!(pointer_move.position_at(Duration::from_millis(100)), (100, 50));
+        // A move that is over stays at its target while the tick goes on.
+        assert_eq!(pointer_move.position_at(Duration::from_millis(400)), (100, 50));
+
+        let instant_move = PointerMove { duration: Duration::from_millis(0), ..pointer_move };
+        assert_eq!(instant_move.position_at(Duration::from_millis(0)), (100, 50));
+    }
+}
@@ -0,0 +1,161 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The parameters of the `servo/intercept` extension command, which sets how the
+//! requests made by the fetch pipeline are intercepted. The body looks like:
+//!
+//! ```json
+//! {
+//!     "rules": [
+//!         {"url": "https://example.com/tracker", "action": "block"},
+//!         {"url": "https://example.com/api/", "action": {"respond": {"status": 200, "body": "{}"}}},
+//!         {"url": "https://cdn.example.com/", "action": {"redirect": "http://localhost:8000/cdn/"}},
+//!         {"url": "https://example.com/", "action": {"headers": {"X-Test": "1"}}}
+//!     ],
+//!     "askEmbedder": false
+//! }
+//! ```
+
+use embedder_traits::request_interception::{InterceptedHeaders, InterceptionAction, InterceptionRule};
+use embedder_traits::request_interception::{RequestInterception, SyntheticResponse};
+use rustc_serialize::json::{Json, ToJson};
+use servo_url::ServoUrl;
+use webdriver::command::Parameters;
+use webdriver::error::{ErrorStatus, WebDriverError, WebDriverResult};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestInterceptionParameters {
+    pub interception: RequestInterception,
+    json: Json,
+}
+
+fn invalid_argument(message: &str) -> WebDriverError {
+    WebDriverError::new(ErrorStatus::InvalidArgument, message.to_owned())
+}
+
+fn parse_headers(json: &Json) -> WebDriverResult<InterceptedHeaders> {
+    let headers = json.as_object().ok_or(invalid_argument("headers was not an object"))?;
+    headers.iter().map(|(name, value)| {
+        let value = value.as_string().ok_or(invalid_argument("Header value is not a string"))?;
+        Ok((name.to_lowercase(), value.as_bytes().to_vec()))
+    }).collect()
+}
+
+fn parse_response(json: &Json) -> WebDriverResult<SyntheticResponse> {
+    let response = json.as_object().ok_or(invalid_argument("respond was not an object"))?;
+    let status = match response.get("status") {
+        Some(status) => match status.as_u64() {
+            // https://fetch.spec.whatwg.org/#dom-response, step 1
+            Some(status) if status >= 200 && status <= 599 => status as u16,
+            _ => return Err(invalid_argument("status is not an integer between 200 and 599")),
+        },
+        None => 200,
+    };
+    let status_text = match response.get("statusText") {
+        Some(text) => text.as_string().ok_or(invalid_argument("statusText is not a string"))?.to_owned(),
+        None => "OK".to_owned(),
+    };
+    let headers = match response.get("headers") {
+        Some(headers) => parse_headers(headers)?,
+        None => vec![],
+    };
+    let body = match response.get("body") {
+        Some(body) => body.as_string().ok_or(invalid_argument("body is not a string"))?.as_bytes().to_vec(),
+        None => vec![],
+    };
+    Ok(SyntheticResponse {
+        status: status,
+        status_text: status_text,
+        headers: headers,
+        body: body,
+    })
+}
+
+fn parse_action(json: &Json) -> WebDriverResult<InterceptionAction> {
+    if let Some(action) = json.as_string() {
+        return match action {
+            "continue" => Ok(InterceptionAction::Continue),
+            "block" => Ok(InterceptionAction::Block),
+            _ => Err(invalid_argument("Unknown interception action")),
+        };
+    }
+    let action = json.as_object().ok_or(invalid_argument("action was not a string or an object"))?;
+    if let Some(url) = action.get("redirect") {
+        let url = url.as_string().ok_or(invalid_argument("redirect is not a string"))?;
+        let url = ServoUrl::parse(url).or(Err(invalid_argument("redirect is not a valid URL")))?;
+        return Ok(InterceptionAction::Redirect(url));
+    }
+    if let Some(response) = action.get("respond") {
+        return Ok(InterceptionAction::Respond(parse_response(response)?));
+    }
+    if let Some(headers) = action.get("headers") {
+        return Ok(InterceptionAction::SetHeaders(parse_headers(headers)?));
+    }
+    Err(invalid_argument("Unknown interception action"))
+}
+
+impl Parameters for RequestInterceptionParameters {
+    fn from_json(body: &Json) -> WebDriverResult<RequestInterceptionParameters> {
+        let data = body.as_object().ok_or(invalid_argument("Message body was not an object"))?;
+        let rules = match data.get("rules") {
+            Some(rules) => rules.as_array().ok_or(invalid_argument("rules was not an array"))?,
+            None => return Err(invalid_argument("Missing rules key")),
+        };
+        let rules = rules.iter().map(|rule| {
+            let rule = rule.as_object().ok_or(invalid_argument("Rule was not an object"))?;
+            let url_prefix = rule.get("url").and_then(|url| url.as_string())
+                .ok_or(invalid_argument("Rule url is missing or not a string"))?;
+            let action = rule.get("action").ok_or(invalid_argument("Missing rule action"))?;
+            Ok(InterceptionRule {
+                url_prefix: url_prefix.to_owned(),
+                action: parse_action(action)?,
+            })
+        }).collect::<WebDriverResult<Vec<_>>>()?;
+        let ask_embedder = match data.get("askEmbedder") {
+            Some(ask) => ask.as_boolean().ok_or(invalid_argument("askEmbedder is not a boolean"))?,
+            None => false,
+        };
+        Ok(RequestInterceptionParameters {
+            interception: RequestInterception {
+                rules: rules,
+                ask_embedder: ask_embedder,
+            },
+            json: body.clone(),
+        })
+    }
+}
+
+impl ToJson for RequestInterceptionParameters {
+    fn to_json(&self) -> Json {
+        self.json.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedder_traits::request_interception::InterceptionAction;
+    use rustc_serialize::json::Json;
+    use super::RequestInterceptionParameters;
+    use webdriver::command::Parameters;
+    use webdriver::error::ErrorStatus;
+
+    fn respond_with_status(status: &str) -> Result<InterceptionAction, ErrorStatus> {
+        let action = format!(r#"{{"respond": {{"status": {}}}}}"#, status);
+        let body = format!(r#"{{"rules": [{{"url": "https://example.com/", "action": {}}}]}}"#, action);
+        RequestInterceptionParameters::from_json(&Json::from_str(&body).unwrap())
+            .map(|parameters| parameters.interception.rules[0].action.clone())
+            .map_err(|error| error.error)
+    }
+
+    #[test]
+    fn test_respond_status_is_checked() {
+        match respond_with_status("404") {
+            Ok(InterceptionAction::Respond(response)) => assert_eq!(response.status, 404),
+            action => panic!("Unexpected action {:?}", action),
+        }
+        for status in &["0", "100", "199", "600", "65736", "-1", "200.5", "\"200\""] {
+            assert_eq!(respond_with_status(status), Err(ErrorStatus::InvalidArgument));
+        }
+    }
+}
@@ -11,7 +11,7 @@ use msg::constellation_msg::{Key, KeyState, KeyModifiers};
 /// entirely clear on how to deal with characters outside this
 /// range. Returns None if no key corresponding to the character is
 /// matched.
-fn key_from_char(key_string: &char) -> Option<(Key, bool)> {
+pub fn key_from_char(key_string: &char) -> Option<(Key, bool)> {
     match *key_string {
         ' ' => Some((Key::Space, false)),
         '\'' => Some((Key::Apostrophe, true)),
@@ -117,7 +117,7 @@ fn key_from_char(key_string: &char) -> Option<(Key, bool)> {
         '\u{E006}' => Some((Key::Enter, false)), // This is supposed to be the Return key
         '\u{E007}' => Some((Key::Enter, false)),
         '\u{E008}' => Some((Key::LeftShift, false)),
-        '\u{E009}' => Some((Key::LeftShift, false)),
+        '\u{E009}' => Some((Key::LeftControl, false)),
         '\u{E00A}' => Some((Key::LeftAlt, false)),
         '\u{E00B}' => Some((Key::Pause, false)),
         '\u{E00C}' => Some((Key::Escape, false)),
@@ -162,12 +162,27 @@ fn key_from_char(key_string: &char) -> Option<(Key, bool)> {
         '\u{E03A}' => Some((Key::F10, false)),
         '\u{E03B}' => Some((Key::F11, false)),
         '\u{E03C}' => Some((Key::F12, false)),
-        '\u{E03D}' => None,
+        '\u{E03D}' => Some((Key::LeftSuper, false)),
         '\u{E040}' => None,
+        '\u{E050}' => Some((Key::RightShift, false)),
+        '\u{E051}' => Some((Key::RightControl, false)),
+        '\u{E052}' => Some((Key::RightAlt, false)),
+        '\u{E053}' => Some((Key::RightSuper, false)),
         _ => None
     }
 }
 
+/// Returns the modifier that is set while the given key is held down, if any.
+pub fn key_modifier(key: Key) -> KeyModifiers {
+    match key {
+        Key::LeftShift | Key::RightShift => KeyModifiers::SHIFT,
+        Key::LeftControl | Key::RightControl => KeyModifiers::CONTROL,
+        Key::LeftAlt | Key::RightAlt => KeyModifiers::ALT,
+        Key::LeftSuper | Key::RightSuper => KeyModifiers::SUPER,
+        _ => KeyModifiers::empty(),
+    }
+}
+
 pub fn keycodes_to_keys(key_codes: &str) -> Result<Vec<(Key, KeyModifiers, KeyState)>, String> {
     let mut rv = vec![];
 
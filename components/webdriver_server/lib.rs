@@ -9,6 +9,7 @@
 
 extern crate base64;
 extern crate cookie as cookie_rs;
+extern crate embedder_traits;
 extern crate euclid;
 extern crate hyper;
 extern crate image;
@@ -25,11 +26,15 @@ extern crate servo_url;
 extern crate uuid;
 extern crate webdriver;
 
+mod actions;
+mod interception;
 mod keys;
 
-use euclid::TypedSize2D;
-use hyper::method::Method::{self, Post};
+use actions::{CancelAction, InputSourceState};
+use euclid::{Point2D, Rect, Size2D, TypedSize2D, Vector2D};
+use hyper::method::Method::{self, Get, Post};
 use image::{DynamicImage, ImageFormat, RgbImage};
+use interception::RequestInterceptionParameters;
 use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use keys::keycodes_to_keys;
 use msg::constellation_msg::{BrowsingContextId, TopLevelBrowsingContextId, TraversalDirection};
@@ -37,18 +42,18 @@ use net_traits::image::base::PixelFormat;
 use regex::Captures;
 use rustc_serialize::json::{Json, ToJson};
 use script_traits::{ConstellationMsg, LoadData, WebDriverCommandMsg};
-use script_traits::webdriver_msg::{LoadStatus, WebDriverCookieError, WebDriverFrameId};
+use script_traits::webdriver_msg::{LoadStatus, WebDriverCookieError, WebDriverElementError, WebDriverFrameId};
 use script_traits::webdriver_msg::{WebDriverJSError, WebDriverJSResult, WebDriverScriptCommand};
 use servo_config::prefs::{PREFS, PrefValue};
 use servo_url::ServoUrl;
 use std::borrow::ToOwned;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::net::{SocketAddr, SocketAddrV4};
 use std::sync::mpsc::Sender;
 use std::thread;
 use std::time::Duration;
 use uuid::Uuid;
-use webdriver::command::{AddCookieParameters, GetParameters, JavascriptCommandParameters};
+use webdriver::command::{ActionsParameters, AddCookieParameters, GetParameters, JavascriptCommandParameters};
 use webdriver::command::{LocatorParameters, Parameters};
 use webdriver::command::{SendKeysParameters, SwitchToFrameParameters, TimeoutsParameters};
 use webdriver::command::{WebDriverCommand, WebDriverExtensionCommand, WebDriverMessage};
@@ -64,7 +69,9 @@ use webdriver::server::{self, Session, WebDriverHandler};
 fn extension_routes() -> Vec<(Method, &'static str, ServoExtensionRoute)> {
     return vec![(Post, "/session/{sessionId}/servo/prefs/get", ServoExtensionRoute::GetPrefs),
                 (Post, "/session/{sessionId}/servo/prefs/set", ServoExtensionRoute::SetPrefs),
-                (Post, "/session/{sessionId}/servo/prefs/reset", ServoExtensionRoute::ResetPrefs)]
+                (Post, "/session/{sessionId}/servo/prefs/reset", ServoExtensionRoute::ResetPrefs),
+                (Post, "/session/{sessionId}/servo/intercept", ServoExtensionRoute::SetRequestInterception),
+                (Get, "/session/{sessionId}/servo/selection", ServoExtensionRoute::GetSelection)]
 }
 
 fn cookie_msg_to_cookie(cookie: cookie_rs::Cookie) -> Cookie {
@@ -115,6 +122,14 @@ struct WebDriverSession {
     /// Time to wait for the element location strategy when retrieving elements, and when
     /// waiting for an element to become interactable.
     implicit_wait_timeout: u64,
+
+    /// The state of each input source used by the actions of this session, keyed by
+    /// input source id.
+    input_state_table: HashMap<String, InputSourceState>,
+
+    /// The actions that undo the pressed keys and buttons once the actions of this
+    /// session are released, in dispatch order.
+    input_cancel_list: Vec<(String, CancelAction)>,
 }
 
 impl WebDriverSession {
@@ -130,6 +145,9 @@ impl WebDriverSession {
             script_timeout: 30_000,
             load_timeout: 300_000,
             implicit_wait_timeout: 0,
+
+            input_state_table: HashMap::new(),
+            input_cancel_list: Vec::new(),
         }
     }
 }
@@ -145,6 +163,8 @@ enum ServoExtensionRoute {
     GetPrefs,
     SetPrefs,
     ResetPrefs,
+    SetRequestInterception,
+    GetSelection,
 }
 
 impl WebDriverExtensionRoute for ServoExtensionRoute {
@@ -166,6 +186,11 @@ impl WebDriverExtensionRoute for ServoExtensionRoute {
                 let parameters: GetPrefsParameters = Parameters::from_json(&body_data)?;
                 ServoExtensionCommand::ResetPrefs(parameters)
             }
+            ServoExtensionRoute::SetRequestInterception => {
+                let parameters: RequestInterceptionParameters = Parameters::from_json(&body_data)?;
+                ServoExtensionCommand::SetRequestInterception(parameters)
+            }
+            ServoExtensionRoute::GetSelection => ServoExtensionCommand::GetSelection,
         };
         Ok(WebDriverCommand::Extension(command))
     }
@@ -176,6 +201,8 @@ enum ServoExtensionCommand {
     GetPrefs(GetPrefsParameters),
     SetPrefs(SetPrefsParameters),
     ResetPrefs(GetPrefsParameters),
+    SetRequestInterception(RequestInterceptionParameters),
+    GetSelection,
 }
 
 impl WebDriverExtensionCommand for ServoExtensionCommand {
@@ -184,6 +211,8 @@ impl WebDriverExtensionCommand for ServoExtensionCommand {
             ServoExtensionCommand::GetPrefs(ref x) => Some(x.to_json()),
             ServoExtensionCommand::SetPrefs(ref x) => Some(x.to_json()),
             ServoExtensionCommand::ResetPrefs(ref x) => Some(x.to_json()),
+            ServoExtensionCommand::SetRequestInterception(ref x) => Some(x.to_json()),
+            ServoExtensionCommand::GetSelection => None,
         }
     }
 }
@@ -774,6 +803,8 @@ impl Handler {
             Err(WebDriverJSError::Timeout) => Err(WebDriverError::new(ErrorStatus::Timeout, "")),
             Err(WebDriverJSError::UnknownType) => Err(WebDriverError::new(
                 ErrorStatus::UnsupportedOperation, "Unsupported return type")),
+            Err(WebDriverJSError::JSError) => Err(WebDriverError::new(
+                ErrorStatus::JavascriptError, "Unable to serialize the return value")),
             Err(WebDriverJSError::BrowsingContextNotFound) => Err(WebDriverError::new(
                 ErrorStatus::JavascriptError, "Pipeline id not found in browsing context"))
         }
@@ -806,7 +837,49 @@ impl Handler {
         Ok(WebDriverResponse::Void)
     }
 
-    fn handle_take_screenshot(&self) -> WebDriverResult<WebDriverResponse> {
+    // https://w3c.github.io/webdriver/webdriver-spec.html#element-click
+    fn handle_element_click(&mut self, element: &WebElement) -> WebDriverResult<WebDriverResponse> {
+        // Steps 4-7
+        let (x, y) = match self.element_in_view_center_point(element)? {
+            Some(point) => point,
+            None => return Err(WebDriverError::new(ErrorStatus::ElementNotInteractable,
+                                                   "Element is not in view")),
+        };
+
+        // Steps 8-11
+        self.dispatch_click(x, y)?;
+        Ok(WebDriverResponse::Void)
+    }
+
+    // https://w3c.github.io/webdriver/webdriver-spec.html#element-clear
+    fn handle_element_clear(&self, element: &WebElement) -> WebDriverResult<WebDriverResponse> {
+        let (sender, receiver) = ipc::channel().unwrap();
+        let cmd = WebDriverScriptCommand::ElementClear(element.id.clone(), sender);
+        self.browsing_context_script_command(cmd)?;
+        match receiver.recv().unwrap() {
+            Ok(()) => Ok(WebDriverResponse::Void),
+            Err(WebDriverElementError::NotFound) => Err(WebDriverError::new(
+                ErrorStatus::StaleElementReference, "Unable to find element in document")),
+            Err(WebDriverElementError::InvalidState) => Err(WebDriverError::new(
+                ErrorStatus::InvalidElementState, "Element is not editable")),
+        }
+    }
+
+    // https://w3c.github.io/webdriver/webdriver-spec.html#perform-actions
+    fn handle_perform_actions(&mut self, parameters: &ActionsParameters) -> WebDriverResult<WebDriverResponse> {
+        self.dispatch_actions(&parameters.actions)?;
+        Ok(WebDriverResponse::Void)
+    }
+
+    // https://w3c.github.io/webdriver/webdriver-spec.html#release-actions
+    fn handle_release_actions(&mut self) -> WebDriverResult<WebDriverResponse> {
+        self.release_actions()?;
+        Ok(WebDriverResponse::Void)
+    }
+
+    /// Takes a screenshot of the viewport, cropped to the given rectangle in CSS pixels
+    /// relative to the viewport, and returns it as a base64 encoded PNG.
+    fn take_screenshot(&self, rect: Option<Rect<f64>>) -> WebDriverResult<String> {
         let mut img = None;
         let top_level_id = self.session()?.top_level_browsing_context_id;
 
@@ -835,11 +908,60 @@ impl Handler {
         // The compositor always sends RGB pixels.
         assert_eq!(img.format, PixelFormat::RGB8, "Unexpected screenshot pixel format");
         let rgb = RgbImage::from_raw(img.width, img.height, img.bytes.to_vec()).unwrap();
+        let mut image = DynamicImage::ImageRgb8(rgb);
+
+        if let Some(rect) = rect {
+            let (sender, receiver) = ipc::channel().unwrap();
+            let cmd_msg = WebDriverCommandMsg::GetWindowSize(top_level_id, sender);
+            self.constellation_chan.send(ConstellationMsg::WebDriverCommand(cmd_msg)).unwrap();
+            let dppx = receiver.recv().unwrap().device_pixel_ratio.get() as f64;
+
+            let bounds = Rect::new(Point2D::zero(), Size2D::new(img.width as f64, img.height as f64));
+            let rect = rect.scale(dppx, dppx).round_out();
+            image = match rect.intersection(&bounds) {
+                Some(ref rect) if rect.size.width > 0. && rect.size.height > 0. => {
+                    image.crop(rect.origin.x as u32,
+                               rect.origin.y as u32,
+                               rect.size.width as u32,
+                               rect.size.height as u32)
+                },
+                _ => return Err(WebDriverError::new(ErrorStatus::UnableToCaptureScreen,
+                                                    "The element has no visible area")),
+            };
+        }
 
         let mut png_data = Vec::new();
-        DynamicImage::ImageRgb8(rgb).write_to(&mut png_data, ImageFormat::PNG).unwrap();
+        image.write_to(&mut png_data, ImageFormat::PNG).unwrap();
+
+        Ok(base64::encode(&png_data))
+    }
 
-        let encoded = base64::encode(&png_data);
+    fn handle_take_screenshot(&self) -> WebDriverResult<WebDriverResponse> {
+        let encoded = self.take_screenshot(None)?;
+        Ok(WebDriverResponse::Generic(ValueResponse::new(encoded.to_json())))
+    }
+
+    // https://w3c.github.io/webdriver/webdriver-spec.html#take-element-screenshot
+    fn handle_take_element_screenshot(&self, element: &WebElement) -> WebDriverResult<WebDriverResponse> {
+        // Step 4
+        self.element_in_view_center_point(element)?;
+
+        // Step 5. The element rect is relative to the document, so make it relative
+        // to the top-level viewport, which is what the screenshot captures.
+        let (sender, receiver) = ipc::channel().unwrap();
+        let cmd = WebDriverScriptCommand::GetElementRect(element.id.clone(), sender);
+        self.browsing_context_script_command(cmd)?;
+        let rect = receiver.recv().unwrap().or_else(|_| Err(WebDriverError::new(
+            ErrorStatus::StaleElementReference, "Unable to find element in document")))?;
+
+        let (sender, receiver) = ipc::channel().unwrap();
+        self.browsing_context_script_command(WebDriverScriptCommand::GetScrollOffset(sender))?;
+        let (scroll_x, scroll_y) = receiver.recv().unwrap();
+        let (frame_x, frame_y) = self.top_level_frame_offset()?;
+        let rect = rect.translate(&Vector2D::new(frame_x as f64 - scroll_x, frame_y as f64 - scroll_y));
+
+        // Steps 6-7
+        let encoded = self.take_screenshot(Some(rect))?;
         Ok(WebDriverResponse::Generic(ValueResponse::new(encoded.to_json())))
     }
 
@@ -874,6 +996,32 @@ impl Handler {
         };
         Ok(WebDriverResponse::Generic(ValueResponse::new(prefs.to_json())))
     }
+
+    fn handle_set_request_interception(&self,
+                                       parameters: &RequestInterceptionParameters)
+                                       -> WebDriverResult<WebDriverResponse> {
+        let msg = ConstellationMsg::SetRequestInterception(parameters.interception.clone());
+        self.constellation_chan.send(msg).unwrap();
+        Ok(WebDriverResponse::Void)
+    }
+
+    fn handle_get_selection(&self) -> WebDriverResult<WebDriverResponse> {
+        let (sender, receiver) = ipc::channel().unwrap();
+
+        self.browsing_context_script_command(WebDriverScriptCommand::GetSelection(sender))?;
+
+        let value = receiver.recv().unwrap().map(|selection| {
+            let mut data = BTreeMap::new();
+            data.insert("text".to_owned(), selection.text.to_json());
+            data.insert("type".to_owned(), selection.selection_type.to_json());
+            data.insert("anchorElement".to_owned(), selection.anchor_element.map(WebElement::new).to_json());
+            data.insert("anchorOffset".to_owned(), selection.anchor_offset.to_json());
+            data.insert("focusElement".to_owned(), selection.focus_element.map(WebElement::new).to_json());
+            data.insert("focusOffset".to_owned(), selection.focus_offset.to_json());
+            Json::Object(data)
+        });
+        Ok(WebDriverResponse::Generic(ValueResponse::new(value.to_json())))
+    }
 }
 
 impl WebDriverHandler<ServoExtensionRoute> for Handler {
@@ -923,13 +1071,20 @@ impl WebDriverHandler<ServoExtensionRoute> for Handler {
             WebDriverCommand::ExecuteAsyncScript(ref x) => self.handle_execute_async_script(x),
             WebDriverCommand::ElementSendKeys(ref element, ref keys) =>
                 self.handle_element_send_keys(element, keys),
+            WebDriverCommand::ElementClick(ref element) => self.handle_element_click(element),
+            WebDriverCommand::ElementClear(ref element) => self.handle_element_clear(element),
+            WebDriverCommand::PerformActions(ref x) => self.handle_perform_actions(x),
+            WebDriverCommand::ReleaseActions => self.handle_release_actions(),
             WebDriverCommand::SetTimeouts(ref x) => self.handle_set_timeouts(x),
             WebDriverCommand::TakeScreenshot => self.handle_take_screenshot(),
+            WebDriverCommand::TakeElementScreenshot(ref element) => self.handle_take_element_screenshot(element),
             WebDriverCommand::Extension(ref extension) => {
                 match *extension {
                     ServoExtensionCommand::GetPrefs(ref x) => self.handle_get_prefs(x),
                     ServoExtensionCommand::SetPrefs(ref x) => self.handle_set_prefs(x),
                     ServoExtensionCommand::ResetPrefs(ref x) => self.handle_reset_prefs(x),
+                    ServoExtensionCommand::SetRequestInterception(ref x) => self.handle_set_request_interception(x),
+                    ServoExtensionCommand::GetSelection => self.handle_get_selection(),
                 }
             }
             _ => Err(WebDriverError::new(ErrorStatus::UnsupportedOperation,
@@ -6,6 +6,7 @@ use serde_json;
 use servo::{self, gl, webrender_api, BrowserId, Servo};
 use servo::compositing::windowing::{AnimationState, EmbedderCoordinates, MouseWindowEvent, WindowEvent, WindowMethods};
 use servo::embedder_traits::EmbedderMsg;
+use servo::embedder_traits::request_interception::InterceptionAction;
 use servo::embedder_traits::resources::{self, Resource};
 use servo::euclid::{Length, TypedPoint2D, TypedScale, TypedSize2D, TypedVector2D};
 use servo::ipc_channel::ipc;
@@ -323,6 +324,9 @@ impl ServoGlue {
                     info!("Alert: {}", message);
                     let _ = sender.send(());
                 },
+                EmbedderMsg::InterceptRequest(_, sender) => {
+                    let _ = sender.send(InterceptionAction::Continue);
+                },
                 EmbedderMsg::CloseBrowser |
                 EmbedderMsg::Status(..) |
                 EmbedderMsg::SelectFiles(..) |
@@ -7,6 +7,7 @@ use glutin_app::keyutils::{CMD_OR_CONTROL, CMD_OR_ALT};
 use glutin_app::window::{Window, LINE_HEIGHT};
 use servo::compositing::windowing::{WebRenderDebugOption, WindowEvent};
 use servo::embedder_traits::{EmbedderMsg, FilterPattern};
+use servo::embedder_traits::request_interception::InterceptionAction;
 use servo::msg::constellation_msg::{Key, TopLevelBrowsingContextId as BrowserId};
 use servo::msg::constellation_msg::{KeyModifiers, KeyState, TraversalDirection};
 use servo::net_traits::pub_domains::is_reg_domain;
@@ -342,6 +343,9 @@ impl Browser {
                 EmbedderMsg::HideIME => {
                     debug!("HideIME received");
                 }
+                EmbedderMsg::InterceptRequest(_request, sender) => {
+                    let _ = sender.send(InterceptionAction::Continue);
+                }
             }
         }
     }
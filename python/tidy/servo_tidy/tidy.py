@@ -87,6 +87,7 @@ WEBIDL_STANDARDS = [
     "//encoding.spec.whatwg.org",
     "//fetch.spec.whatwg.org",
     "//html.spec.whatwg.org",
+    "//streams.spec.whatwg.org",
     "//url.spec.whatwg.org",
     "//xhr.spec.whatwg.org",
     "//w3c.github.io",
@@ -26,6 +26,7 @@ mod animated_properties;
 mod attr;
 mod custom_properties;
 mod logical_geometry;
+mod media_queries;
 mod parsing;
 mod properties;
 mod rule_tree;
@@ -0,0 +1,120 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use cssparser::{Parser, ParserInput};
+use euclid::{TypedScale, TypedSize2D};
+use servo_url::ServoUrl;
+use style::context::QuirksMode;
+use style::media_queries::{Device, MediaList, MediaType};
+use style::parser::ParserContext;
+use style::servo::media_queries::{Hover, MediaPreferences, Pointer, PrefersColorScheme, PrefersReducedMotion};
+use style::stylesheets::{CssRuleType, Origin};
+use style_traits::{ParsingMode, ToCss};
+
+fn parse_media_list(css: &str) -> MediaList {
+    let url = ServoUrl::parse("http://localhost").unwrap();
+    let context = ParserContext::new(Origin::Author, &url, Some(CssRuleType::Media),
+                                     ParsingMode::DEFAULT,
+                                     QuirksMode::NoQuirks, None);
+    let mut input = ParserInput::new(css);
+    MediaList::parse(&context, &mut Parser::new(&mut input))
+}
+
+fn create_device(width: f32, height: f32, device_pixel_ratio: f32) -> Device {
+    Device::new(MediaType::screen(), TypedSize2D::new(width, height), TypedScale::new(device_pixel_ratio))
+}
+
+fn media_query_matches(css: &str, width: f32, height: f32, device_pixel_ratio: f32) -> bool {
+    parse_media_list(css).evaluate(&create_device(width, height, device_pixel_ratio), QuirksMode::NoQuirks)
+}
+
+fn media_query_matches_preferences(css: &str, media_preferences: MediaPreferences) -> bool {
+    let mut device = create_device(500., 300., 1.);
+    device.set_media_preferences(media_preferences);
+    parse_media_list(css).evaluate(&device, QuirksMode::NoQuirks)
+}
+
+#[test]
+fn test_media_feature_serialization() {
+    let cases = [
+        ("(min-width: 100px)", "(min-width: 100px)"),
+        ("(height)", "(height)"),
+        ("(width >= 400px)", "(width >= 400px)"),
+        ("(400px <= width)", "(width >= 400px)"),
+        ("(400px <= width < 700px)", "(400px <= width < 700px)"),
+        ("(aspect-ratio: 16/9)", "(aspect-ratio: 16/9)"),
+        ("(min-resolution: 2dppx)", "(min-resolution: 2dppx)"),
+        ("(orientation: landscape)", "(orientation: landscape)"),
+        ("(prefers-color-scheme: dark)", "(prefers-color-scheme: dark)"),
+        ("(hover)", "(hover)"),
+    ];
+    for &(css, expected) in cases.iter() {
+        assert_eq!(parse_media_list(css).to_css_string(), expected);
+    }
+}
+
+#[test]
+fn test_invalid_media_features() {
+    let cases = [
+        "(min-orientation: portrait)",
+        "(min-width >= 400px)",
+        "(400px <= width >= 700px)",
+        "(400px = width < 700px)",
+        "(hover: sometimes)",
+        "(400px <= height <= 700px <= width)",
+    ];
+    for css in cases.iter() {
+        assert_eq!(parse_media_list(css).to_css_string(), "not all", "{}", css);
+    }
+}
+
+#[test]
+fn test_media_feature_ranges() {
+    assert!(media_query_matches("(width: 500px)", 500., 300., 1.));
+    assert!(media_query_matches("(min-height: 300px)", 500., 300., 1.));
+    assert!(!media_query_matches("(max-height: 299px)", 500., 300., 1.));
+    assert!(media_query_matches("(400px <= width <= 700px)", 500., 300., 1.));
+    assert!(!media_query_matches("(400px <= width <= 700px)", 800., 300., 1.));
+    assert!(media_query_matches("(700px > width > 400px)", 500., 300., 1.));
+    assert!(!media_query_matches("(width < 500px)", 500., 300., 1.));
+    assert!(media_query_matches("(500px <= width)", 500., 300., 1.));
+    assert!(!media_query_matches("(width)", 0., 300., 1.));
+}
+
+#[test]
+fn test_media_feature_device() {
+    assert!(media_query_matches("(orientation: landscape)", 500., 300., 1.));
+    assert!(media_query_matches("(orientation: portrait)", 300., 300., 1.));
+    assert!(media_query_matches("(aspect-ratio: 5/3)", 500., 300., 1.));
+    assert!(media_query_matches("(min-aspect-ratio: 16/9)", 1920., 1080., 1.));
+    assert!(!media_query_matches("(min-aspect-ratio: 16/9)", 1024., 768., 1.));
+    assert!(media_query_matches("(min-resolution: 2dppx)", 500., 300., 2.));
+    assert!(media_query_matches("(resolution: 192dpi)", 500., 300., 2.));
+    assert!(!media_query_matches("(min-resolution: 2dppx)", 500., 300., 1.));
+}
+
+#[test]
+fn test_media_feature_preferences() {
+    let preferences = MediaPreferences {
+        prefers_color_scheme: PrefersColorScheme::Dark,
+        prefers_reduced_motion: PrefersReducedMotion::Reduce,
+        hover: Hover::None,
+        pointer: Pointer::Coarse,
+    };
+    assert!(media_query_matches_preferences("(prefers-color-scheme: dark)", preferences));
+    assert!(media_query_matches_preferences("(prefers-reduced-motion)", preferences));
+    assert!(!media_query_matches_preferences("(hover)", preferences));
+    assert!(media_query_matches_preferences("(pointer: coarse)", preferences));
+
+    let preferences = MediaPreferences {
+        prefers_color_scheme: PrefersColorScheme::Light,
+        prefers_reduced_motion: PrefersReducedMotion::NoPreference,
+        hover: Hover::Hover,
+        pointer: Pointer::Fine,
+    };
+    assert!(!media_query_matches_preferences("(prefers-color-scheme: dark)", preferences));
+    assert!(!media_query_matches_preferences("(prefers-reduced-motion)", preferences));
+    assert!(media_query_matches_preferences("(hover: hover)", preferences));
+    assert!(media_query_matches_preferences("(pointer: fine)", preferences));
+}
@@ -63,6 +63,7 @@ fn test_revalidation_selectors() {
         // Not revalidation selectors.
         "div",
         "div:not(.foo)",
+        "div:is(.foo, span)",
         "div span",
         "div > span",
 
@@ -105,6 +106,10 @@ fn test_revalidation_selectors() {
         // Sibling combinators.
         "span + div",
         "span ~ div",
+        "div:is(span + *)",
+
+        // Relative selectors.
+        "div:has(span)",
 
         // Selectors in the ancestor chain (needed for cousin sharing).
         "p:first-child span",
@@ -144,6 +149,10 @@ fn test_revalidation_selectors() {
         // Sibling combinators.
         "span + div",
         "span ~ div",
+        "div:is(span + *)",
+
+        // Relative selectors.
+        "div:has(span)",
 
         // Selectors in the ancestor chain (needed for cousin sharing).
         "p:first-child span",
@@ -7,7 +7,12 @@ importScripts("interfaces.js");
 
 // IMPORTANT: Do not change the list below without review from a DOM peer!
 test_interfaces([
+  "AbortController",
+  "AbortSignal",
   "Blob",
+  "BroadcastChannel",
+  "CanvasGradient",
+  "CanvasPattern",
   "CloseEvent",
   "DOMMatrix",
   "DOMMatrixReadOnly",
@@ -19,6 +24,7 @@ test_interfaces([
   "CustomEvent",
   "DedicatedWorkerGlobalScope",
   "DOMException",
+  "DOMStringList",
   "ErrorEvent",
   "Event",
   "EventSource",
@@ -30,8 +36,12 @@ test_interfaces([
   "FormData",
   "Headers",
   "History",
+  "ImageBitmap",
   "ImageData",
+  "MessageChannel",
   "MessageEvent",
+  "MessagePort",
+  "Path2D",
   "Performance",
   "PerformanceEntry",
   "PerformanceMark",
@@ -46,6 +56,18 @@ test_interfaces([
   "TextEncoder",
   "URL",
   "URLSearchParams",
+  "WebGLActiveInfo",
+  "WebGLBuffer",
+  "WebGLContextEvent",
+  "WebGLFramebuffer",
+  "WebGLObject",
+  "WebGLProgram",
+  "WebGLRenderbuffer",
+  "WebGLRenderingContext",
+  "WebGLShader",
+  "WebGLShaderPrecisionFormat",
+  "WebGLTexture",
+  "WebGLUniformLocation",
   "WebSocket",
   "Worker",
   "WorkerGlobalScope",